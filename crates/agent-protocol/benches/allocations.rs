@@ -0,0 +1,192 @@
+//! Heap allocations per request on the agent hot path, with and without
+//! pooling.
+//!
+//! Criterion measures time; this counts allocations through a counting
+//! global allocator and prints allocations and bytes per operation:
+//! - `build_event`: a `RequestHeadersEvent` built fresh vs taken from the
+//!   object pool and refilled
+//! - `encode_*`: an event serialized into a new `Vec` vs into a reused
+//!   buffer
+//!
+//! Run with `cargo bench -p zentinel-agent-protocol --bench allocations`
+//! (add `--features binary-uds` for the MessagePack rows).
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use zentinel_agent_protocol::buffer_pool::{Pooled, Recycle};
+use zentinel_agent_protocol::v2::UdsEncoding;
+use zentinel_agent_protocol::{RequestHeadersEvent, RequestMetadata};
+
+// ============================================================================
+// Counting allocator
+// ============================================================================
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// ============================================================================
+// Fixtures
+// ============================================================================
+
+fn sample_headers(count: usize) -> HashMap<String, Vec<String>> {
+    (0..count)
+        .map(|i| (format!("x-bench-{}", i), vec![format!("value-{}", i)]))
+        .collect()
+}
+
+fn sample_metadata() -> RequestMetadata {
+    let mut metadata = RequestMetadata::empty();
+    metadata.correlation_id = "bench-correlation-id".to_string();
+    metadata.request_id = "bench-request-id".to_string();
+    metadata.client_ip = "203.0.113.7".to_string();
+    metadata.client_port = 54321;
+    metadata.server_name = Some("api.example.com".to_string());
+    metadata.protocol = "HTTP/1.1".to_string();
+    metadata.route_id = Some("api".to_string());
+    metadata.upstream_id = Some("backend".to_string());
+    metadata.timestamp = "2026-01-01T00:00:00Z".to_string();
+    metadata
+}
+
+const ITERATIONS: usize = 10_000;
+const HEADER_COUNTS: [usize; 3] = [5, 20, 50];
+
+/// Allocations and bytes per call of `measured`; `setup` runs outside the
+/// counted region and its output is handed to `measured`
+fn count<I, S, M>(mut setup: S, mut measured: M) -> (f64, f64)
+where
+    S: FnMut() -> I,
+    M: FnMut(I),
+{
+    // Warm up pools and lazily initialised state
+    for _ in 0..100 {
+        measured(setup());
+    }
+
+    let mut allocations = 0;
+    let mut bytes = 0;
+    for _ in 0..ITERATIONS {
+        let input = setup();
+        let (a, b) = (
+            ALLOCATIONS.load(Ordering::Relaxed),
+            ALLOCATED_BYTES.load(Ordering::Relaxed),
+        );
+        measured(input);
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - a;
+        bytes += ALLOCATED_BYTES.load(Ordering::Relaxed) - b;
+    }
+    (
+        allocations as f64 / ITERATIONS as f64,
+        bytes as f64 / ITERATIONS as f64,
+    )
+}
+
+fn report(name: &str, headers: usize, fresh: (f64, f64), pooled: (f64, f64)) {
+    println!(
+        "{:<16} {:>7} {:>12.1} {:>12.1} {:>12.0} {:>12.0}",
+        name, headers, fresh.0, pooled.0, fresh.1, pooled.1
+    );
+}
+
+// ============================================================================
+// Measurements
+// ============================================================================
+
+fn build_event(headers: usize) {
+    let metadata = sample_metadata();
+    let source = sample_headers(headers);
+
+    let fresh = count(
+        || source.clone(),
+        |headers| {
+            let event = RequestHeadersEvent {
+                metadata: metadata.clone(),
+                method: "POST".to_string(),
+                uri: "/api/v1/orders?page=2".to_string(),
+                headers,
+            };
+            black_box(&event);
+        },
+    );
+
+    let pooled = count(
+        || source.clone(),
+        |mut headers| {
+            let mut event = Pooled::<RequestHeadersEvent>::acquire();
+            event.metadata.fill_from(&metadata);
+            event.method.push_str("POST");
+            event.uri.push_str("/api/v1/orders?page=2");
+            event.headers.extend(headers.drain());
+            black_box(&*event);
+        },
+    );
+
+    report("build_event", headers, fresh, pooled);
+}
+
+fn encode(name: &str, encoding: UdsEncoding, headers: usize) {
+    let event = RequestHeadersEvent {
+        metadata: sample_metadata(),
+        method: "POST".to_string(),
+        uri: "/api/v1/orders?page=2".to_string(),
+        headers: sample_headers(headers),
+    };
+
+    let fresh = count(
+        || (),
+        |()| {
+            black_box(encoding.serialize(&event).unwrap());
+        },
+    );
+
+    let mut buf = Vec::new();
+    let pooled = count(
+        || (),
+        |()| {
+            buf.clear();
+            encoding.serialize_into(&event, &mut buf).unwrap();
+            black_box(&buf);
+        },
+    );
+
+    report(name, headers, fresh, pooled);
+}
+
+fn main() {
+    println!(
+        "{:<16} {:>7} {:>12} {:>12} {:>12} {:>12}",
+        "operation", "headers", "allocs/fresh", "allocs/pool", "bytes/fresh", "bytes/pool"
+    );
+    for headers in HEADER_COUNTS {
+        build_event(headers);
+        encode("encode_json", UdsEncoding::Json, headers);
+        #[cfg(feature = "binary-uds")]
+        encode("encode_msgpack", UdsEncoding::MessagePack, headers);
+    }
+}
@@ -738,6 +738,61 @@ fn bench_body_chunk_deserialization(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark the proxy-side body chunk dispatch path.
+///
+/// `base64_event` is the old AgentManager path: encode the chunk to base64,
+/// round-trip the event through `serde_json::Value` for typed dispatch, then
+/// decode it again before handing bytes to gRPC. `binary_event` is the
+/// current path: one copy into `Bytes`, one copy into the protobuf message.
+fn bench_body_chunk_dispatch(c: &mut Criterion) {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use bytes::Bytes;
+    use zentinel_agent_protocol::{BinaryRequestBodyChunkEvent, RequestBodyChunkEvent};
+
+    let mut group = c.benchmark_group("body_chunk_dispatch");
+
+    for size in [1024, 16384, 65536, 1024 * 1024] {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        let data: Vec<u8> = (0..size).map(|i| (i % 256) as u8).collect();
+
+        group.bench_with_input(BenchmarkId::new("base64_event", size), &size, |b, _| {
+            b.iter(|| {
+                let event = RequestBodyChunkEvent {
+                    correlation_id: "bench-123".to_string(),
+                    data: STANDARD.encode(black_box(&data)),
+                    is_last: true,
+                    total_size: Some(size),
+                    chunk_index: 0,
+                    bytes_received: size,
+                    part: None,
+                };
+                let value = serde_json::to_value(&event).unwrap();
+                let typed: RequestBodyChunkEvent = serde_json::from_value(value).unwrap();
+                let binary = BinaryRequestBodyChunkEvent::from(&typed);
+                black_box(binary.data.to_vec())
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("binary_event", size), &size, |b, _| {
+            b.iter(|| {
+                let event = BinaryRequestBodyChunkEvent {
+                    correlation_id: "bench-123".to_string(),
+                    data: Bytes::copy_from_slice(black_box(&data)),
+                    is_last: true,
+                    total_size: Some(size),
+                    chunk_index: 0,
+                    bytes_received: size,
+                    part: None,
+                };
+                black_box(event.data.to_vec())
+            })
+        });
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // P3: Protocol Metrics Benchmarks
 // ============================================================================
@@ -940,6 +995,7 @@ criterion_group!(
     p3_benchmarks,
     bench_body_chunk_serialization,
     bench_body_chunk_deserialization,
+    bench_body_chunk_dispatch,
     bench_protocol_metrics,
     bench_connection_affinity,
 );
@@ -0,0 +1,25 @@
+//! Agent responses in every encoding the proxy accepts.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zentinel_agent_protocol::binary::BinaryAgentResponse;
+use zentinel_agent_protocol::v2::UdsEncoding;
+use zentinel_agent_protocol::AgentResponse;
+
+fuzz_target!(|data: &[u8]| {
+    for encoding in [UdsEncoding::Json, UdsEncoding::MessagePack] {
+        // Whatever decodes must encode and decode again
+        if let Ok(response) = encoding.deserialize::<AgentResponse>(data) {
+            let bytes = encoding.serialize(&response).unwrap();
+            encoding.deserialize::<AgentResponse>(&bytes).unwrap();
+        }
+    }
+
+    if let Ok(response) = BinaryAgentResponse::decode(data.to_vec().into()) {
+        let again = BinaryAgentResponse::decode(response.encode()).unwrap();
+        assert_eq!(again.decision, response.decision);
+        assert_eq!(again.request_headers, response.request_headers);
+        assert_eq!(again.response_headers, response.response_headers);
+    }
+});
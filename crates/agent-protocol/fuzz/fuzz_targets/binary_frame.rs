@@ -0,0 +1,29 @@
+//! Binary framing and the binary event payloads.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zentinel_agent_protocol::binary::{
+    BinaryBodyChunk, BinaryFrame, BinaryRequestHeaders, MessageType, MAX_BINARY_MESSAGE_SIZE,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let mut reader = data;
+        while let Ok(frame) = BinaryFrame::decode(&mut reader).await {
+            assert!(frame.payload.len() < MAX_BINARY_MESSAGE_SIZE);
+            match frame.msg_type {
+                MessageType::RequestHeaders => {
+                    let _ = BinaryRequestHeaders::decode(frame.payload);
+                }
+                MessageType::RequestBodyChunk | MessageType::ResponseBodyChunk => {
+                    let _ = BinaryBodyChunk::decode(frame.payload);
+                }
+                _ => {}
+            }
+        }
+    });
+});
@@ -0,0 +1,25 @@
+//! Handshake parsing on both sides of the UDS and gRPC transports.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zentinel_agent_protocol::v2::{
+    AgentCapabilities, HandshakeRequest, HandshakeResponse, UdsHandshakeRequest,
+    UdsHandshakeResponse,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // Handshakes are always JSON
+    let _ = serde_json::from_slice::<HandshakeRequest>(data);
+    let _ = serde_json::from_slice::<HandshakeResponse>(data);
+    let _ = serde_json::from_slice::<UdsHandshakeRequest>(data);
+
+    // The proxy converts the agent's capabilities and limits after parsing
+    if let Ok(response) = serde_json::from_slice::<UdsHandshakeResponse>(data) {
+        let capabilities = AgentCapabilities::from(response.capabilities);
+        assert!(
+            capabilities.limits.max_message_size
+                <= zentinel_agent_protocol::MAX_NEGOTIATED_MESSAGE_SIZE
+        );
+    }
+});
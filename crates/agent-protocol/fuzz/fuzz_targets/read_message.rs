@@ -0,0 +1,43 @@
+//! UDS framing: split arbitrary bytes into messages and decode each payload
+//! the way the proxy and the agent server would.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zentinel_agent_protocol::v2::uds::read_message_with_limit;
+use zentinel_agent_protocol::v2::{MessageType, UdsEncoding};
+use zentinel_agent_protocol::{
+    AgentResponse, RequestBodyChunkEvent, RequestHeadersEvent, ResponseHeadersEvent,
+};
+
+/// Small enough that oversized length prefixes are hit often
+const LIMIT: usize = 64 * 1024;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let mut reader = data;
+        while let Ok((msg_type, payload)) = read_message_with_limit(&mut reader, LIMIT).await {
+            assert!(payload.len() <= LIMIT);
+            for encoding in [UdsEncoding::Json, UdsEncoding::MessagePack] {
+                match msg_type {
+                    MessageType::AgentResponse => {
+                        let _ = encoding.deserialize::<AgentResponse>(&payload);
+                    }
+                    MessageType::RequestHeaders => {
+                        let _ = encoding.deserialize::<RequestHeadersEvent>(&payload);
+                    }
+                    MessageType::RequestBodyChunk => {
+                        let _ = encoding.deserialize::<RequestBodyChunkEvent>(&payload);
+                    }
+                    MessageType::ResponseHeaders => {
+                        let _ = encoding.deserialize::<ResponseHeadersEvent>(&payload);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    });
+});
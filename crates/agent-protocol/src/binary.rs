@@ -15,6 +15,10 @@
 //! - **Type**: 1-byte message type discriminator
 //! - **Payload**: Variable-length payload (format depends on type)
 //!
+//! Strings and counts inside payloads carry `u16` prefixes. Longer strings are
+//! cut at a character boundary and longer lists are cut at `u16::MAX` entries,
+//! so an oversized value can never spill into the fields after it.
+//!
 //! # Performance Benefits
 //!
 //! - No JSON parsing overhead (~10x faster for small messages)
@@ -31,6 +35,12 @@ use crate::{AgentProtocolError, Decision, HeaderOp};
 /// Maximum binary message size (10 MB)
 pub const MAX_BINARY_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
 
+/// Largest string length or element count a `u16` prefix can carry
+const MAX_PREFIXED_LEN: usize = u16::MAX as usize;
+
+/// Initial payload buffer size when decoding a frame; larger payloads grow it
+const DECODE_BUFFER_INITIAL_CAPACITY: usize = 64 * 1024;
+
 /// Binary message types
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -146,18 +156,34 @@ impl BinaryFrame {
 
         // Read type byte
         let mut type_buf = [0u8; 1];
-        reader.read_exact(&mut type_buf).await?;
-        let msg_type = MessageType::try_from(type_buf[0])?;
+        reader.read_exact(&mut type_buf).await.map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                AgentProtocolError::InvalidMessage("Frame truncated".to_string())
+            } else {
+                AgentProtocolError::Io(e)
+            }
+        })?;
 
-        // Read payload
+        // Read payload, growing the buffer as data arrives rather than
+        // trusting the length prefix for the allocation
         let payload_len = total_len - 1;
-        let mut payload = BytesMut::with_capacity(payload_len);
-        payload.resize(payload_len, 0);
-        reader.read_exact(&mut payload).await?;
+        let mut payload = Vec::with_capacity(payload_len.min(DECODE_BUFFER_INITIAL_CAPACITY));
+        reader
+            .take(payload_len as u64)
+            .read_to_end(&mut payload)
+            .await?;
+        if payload.len() < payload_len {
+            return Err(AgentProtocolError::InvalidMessage(
+                "Frame truncated".to_string(),
+            ));
+        }
+
+        // Checked after the payload, so the reader stays at a frame boundary
+        let msg_type = MessageType::try_from(type_buf[0])?;
 
         Ok(Self {
             msg_type,
-            payload: payload.freeze(),
+            payload: Bytes::from(payload),
         })
     }
 
@@ -205,15 +231,16 @@ impl BinaryRequestHeaders {
         put_string(&mut buf, &self.uri);
 
         // Headers count
-        let header_count: usize = self.headers.values().map(|v| v.len()).sum();
-        buf.put_u16(header_count as u16);
+        let header_count = put_count(&mut buf, self.headers.values().map(|v| v.len()).sum());
 
         // Headers (flattened: each value gets its own entry)
-        for (name, values) in &self.headers {
-            for value in values {
-                put_string(&mut buf, name);
-                put_string(&mut buf, value);
-            }
+        let entries = self
+            .headers
+            .iter()
+            .flat_map(|(name, values)| values.iter().map(move |value| (name, value)));
+        for (name, value) in entries.take(header_count) {
+            put_string(&mut buf, name);
+            put_string(&mut buf, value);
         }
 
         // Client IP
@@ -365,10 +392,9 @@ impl BinaryAgentResponse {
                 buf.put_u16(*status);
                 put_optional_string(&mut buf, body.as_deref());
                 // Block headers
-                let h_count = headers.as_ref().map(|h| h.len()).unwrap_or(0);
-                buf.put_u16(h_count as u16);
+                let h_count = put_count(&mut buf, headers.as_ref().map(|h| h.len()).unwrap_or(0));
                 if let Some(headers) = headers {
-                    for (k, v) in headers {
+                    for (k, v) in headers.iter().take(h_count) {
                         put_string(&mut buf, k);
                         put_string(&mut buf, v);
                     }
@@ -385,8 +411,8 @@ impl BinaryAgentResponse {
             } => {
                 buf.put_u8(3);
                 put_string(&mut buf, challenge_type);
-                buf.put_u16(params.len() as u16);
-                for (k, v) in params {
+                let p_count = put_count(&mut buf, params.len());
+                for (k, v) in params.iter().take(p_count) {
                     put_string(&mut buf, k);
                     put_string(&mut buf, v);
                 }
@@ -394,14 +420,14 @@ impl BinaryAgentResponse {
         }
 
         // Request header ops
-        buf.put_u16(self.request_headers.len() as u16);
-        for op in &self.request_headers {
+        let req_h_count = put_count(&mut buf, self.request_headers.len());
+        for op in self.request_headers.iter().take(req_h_count) {
             encode_header_op(&mut buf, op);
         }
 
         // Response header ops
-        buf.put_u16(self.response_headers.len() as u16);
-        for op in &self.response_headers {
+        let resp_h_count = put_count(&mut buf, self.response_headers.len());
+        for op in self.response_headers.iter().take(resp_h_count) {
             encode_header_op(&mut buf, op);
         }
 
@@ -499,7 +525,7 @@ impl BinaryAgentResponse {
             ));
         }
         let req_h_count = data.get_u16() as usize;
-        let mut request_headers = Vec::with_capacity(req_h_count);
+        let mut request_headers = Vec::with_capacity(req_h_count.min(data.remaining()));
         for _ in 0..req_h_count {
             request_headers.push(decode_header_op(&mut data)?);
         }
@@ -511,7 +537,7 @@ impl BinaryAgentResponse {
             ));
         }
         let resp_h_count = data.get_u16() as usize;
-        let mut response_headers = Vec::with_capacity(resp_h_count);
+        let mut response_headers = Vec::with_capacity(resp_h_count.min(data.remaining()));
         for _ in 0..resp_h_count {
             response_headers.push(decode_header_op(&mut data)?);
         }
@@ -539,9 +565,20 @@ impl BinaryAgentResponse {
 // =============================================================================
 
 fn put_string(buf: &mut BytesMut, s: &str) {
-    let bytes = s.as_bytes();
-    buf.put_u16(bytes.len() as u16);
-    buf.put_slice(bytes);
+    // Cut at a character boundary rather than let the length wrap
+    let mut len = s.len().min(MAX_PREFIXED_LEN);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    buf.put_u16(len as u16);
+    buf.put_slice(&s.as_bytes()[..len]);
+}
+
+/// Write an element count, returning how many elements to write
+fn put_count(buf: &mut BytesMut, count: usize) -> usize {
+    let count = count.min(MAX_PREFIXED_LEN);
+    buf.put_u16(count as u16);
+    count
 }
 
 fn get_string(data: &mut Bytes) -> Result<String, AgentProtocolError> {
@@ -670,6 +707,35 @@ mod tests {
         assert_eq!(&encoded[5..], b"hello");
     }
 
+    #[tokio::test]
+    async fn test_binary_frame_decode_truncated() {
+        let mut truncated: &[u8] = &[0, 0, 0, 100, MessageType::Ping as u8, 1, 2, 3];
+        assert!(matches!(
+            BinaryFrame::decode(&mut truncated).await,
+            Err(AgentProtocolError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_oversized_string_does_not_spill() {
+        // Multi-byte characters straddle the u16 limit
+        let uri = "é".repeat(40_000);
+        let headers = BinaryRequestHeaders {
+            correlation_id: "req-1".to_string(),
+            method: "GET".to_string(),
+            uri: uri.clone(),
+            headers: HashMap::new(),
+            client_ip: "10.0.0.1".to_string(),
+            client_port: 443,
+        };
+
+        let decoded = BinaryRequestHeaders::decode(headers.encode()).unwrap();
+        assert!(uri.starts_with(&decoded.uri));
+        assert_eq!(decoded.uri.len(), u16::MAX as usize - 1);
+        assert_eq!(decoded.client_ip, "10.0.0.1");
+        assert_eq!(decoded.client_port, 443);
+    }
+
     #[test]
     fn test_binary_request_headers_roundtrip() {
         let headers = BinaryRequestHeaders {
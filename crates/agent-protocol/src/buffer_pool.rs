@@ -9,10 +9,22 @@
 //! - Small messages (< 64KB): Reused from pool, zero allocation
 //! - Large messages (>= 64KB): Fresh allocation (rare case)
 //! - Thread-local: No contention between threads
+//!
+//! Two more pools cover the per-request objects of the v2 client:
+//!
+//! - [`SharedBufferPool`]: serialization buffers that are filled on one task
+//!   and released on another (the UDS writer task), so it is shared rather
+//!   than thread-local
+//! - [`Pooled`]: recycled [`RequestHeadersEvent`]s and [`RequestMetadata`]s
+//!   whose strings and header map keep their capacity between requests
 
 use bytes::BytesMut;
+use parking_lot::Mutex;
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{RequestHeadersEvent, RequestMetadata};
 
 /// Default buffer size (64 KB).
 pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
@@ -250,6 +262,282 @@ pub fn acquire_default() -> PooledBuffer {
     PooledBuffer::default_size()
 }
 
+// ============================================================================
+// Shared serialization buffers
+// ============================================================================
+
+/// Capacity given to a freshly allocated serialization buffer.
+pub const DEFAULT_ENCODE_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Pool of `Vec<u8>` serialization buffers shared between threads.
+///
+/// The v2 UDS client encodes an event on the calling task and hands the
+/// buffer to its writer task, which returns it here once the frame is
+/// written. Buffers above [`MAX_POOLED_BUFFER_SIZE`] are not kept.
+pub struct SharedBufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: usize,
+    allocated: AtomicUsize,
+    reused: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl SharedBufferPool {
+    /// Create a pool that keeps at most `max_buffers` idle buffers.
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+            max_buffers,
+            allocated: AtomicUsize::new(0),
+            reused: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take an empty buffer, reusing an idle one when available.
+    pub fn get(&self) -> Vec<u8> {
+        if let Some(buf) = self.buffers.lock().pop() {
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            return buf;
+        }
+        self.allocated.fetch_add(1, Ordering::Relaxed);
+        Vec::with_capacity(DEFAULT_ENCODE_BUFFER_SIZE)
+    }
+
+    /// Return a buffer to the pool.
+    pub fn put(&self, mut buf: Vec<u8>) {
+        if buf.capacity() > MAX_POOLED_BUFFER_SIZE {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        buf.clear();
+        let mut buffers = self.buffers.lock();
+        if buffers.len() >= self.max_buffers {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        buffers.push(buf);
+    }
+
+    /// Statistics for this pool.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            pooled: self.buffers.lock().len(),
+            allocated: self.allocated.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for SharedBufferPool {
+    fn default() -> Self {
+        Self::new(MAX_POOL_SIZE * 4)
+    }
+}
+
+impl std::fmt::Debug for SharedBufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBufferPool")
+            .field("max_buffers", &self.max_buffers)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+// ============================================================================
+// Recycled protocol objects
+// ============================================================================
+
+/// Maximum number of idle objects kept per type and thread.
+pub const MAX_POOLED_OBJECTS: usize = 64;
+
+/// Header maps with more entries than this are not recycled.
+const MAX_RECYCLED_HEADERS: usize = 256;
+
+/// A protocol object that can be reset and reused.
+///
+/// `recycle` empties the object while keeping the capacity of its strings
+/// and collections, so refilling it does not allocate for values that fit.
+pub trait Recycle: Sized + 'static {
+    /// An empty object.
+    fn empty() -> Self;
+
+    /// Reset to the state of [`Recycle::empty`], keeping capacity.
+    fn recycle(&mut self);
+
+    /// Whether the object is worth keeping (not oversized).
+    fn reusable(&self) -> bool {
+        true
+    }
+
+    /// Run `f` with this type's thread-local pool.
+    fn with_pool<R>(f: impl FnOnce(&mut Vec<Self>) -> R) -> R;
+}
+
+thread_local! {
+    static REQUEST_HEADERS_POOL: RefCell<Vec<RequestHeadersEvent>> = const { RefCell::new(Vec::new()) };
+    static REQUEST_METADATA_POOL: RefCell<Vec<RequestMetadata>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Recycle for RequestMetadata {
+    fn empty() -> Self {
+        Self {
+            correlation_id: String::new(),
+            request_id: String::new(),
+            client_ip: String::new(),
+            client_port: 0,
+            server_name: None,
+            protocol: String::new(),
+            tls_version: None,
+            tls_cipher: None,
+            route_id: None,
+            upstream_id: None,
+            timestamp: String::new(),
+            traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
+        }
+    }
+
+    fn recycle(&mut self) {
+        self.correlation_id.clear();
+        self.request_id.clear();
+        self.client_ip.clear();
+        self.client_port = 0;
+        self.server_name = None;
+        self.protocol.clear();
+        self.tls_version = None;
+        self.tls_cipher = None;
+        self.route_id = None;
+        self.upstream_id = None;
+        self.timestamp.clear();
+        self.traceparent = None;
+        self.client_cert = None;
+        self.tags.clear();
+        self.bot_signals = None;
+        self.dry_run = false;
+    }
+
+    fn with_pool<R>(f: impl FnOnce(&mut Vec<Self>) -> R) -> R {
+        REQUEST_METADATA_POOL.with(|pool| f(&mut pool.borrow_mut()))
+    }
+}
+
+impl Recycle for RequestHeadersEvent {
+    fn empty() -> Self {
+        Self {
+            metadata: RequestMetadata::empty(),
+            method: String::new(),
+            uri: String::new(),
+            headers: HashMap::new(),
+        }
+    }
+
+    fn recycle(&mut self) {
+        self.metadata.recycle();
+        self.method.clear();
+        self.uri.clear();
+        self.headers.clear();
+    }
+
+    fn reusable(&self) -> bool {
+        self.headers.capacity() <= MAX_RECYCLED_HEADERS
+    }
+
+    fn with_pool<R>(f: impl FnOnce(&mut Vec<Self>) -> R) -> R {
+        REQUEST_HEADERS_POOL.with(|pool| f(&mut pool.borrow_mut()))
+    }
+}
+
+impl RequestMetadata {
+    /// Overwrite with a copy of `source`, reusing this value's string
+    /// buffers instead of allocating new ones.
+    pub fn fill_from(&mut self, source: &RequestMetadata) {
+        self.correlation_id.clone_from(&source.correlation_id);
+        self.request_id.clone_from(&source.request_id);
+        self.client_ip.clone_from(&source.client_ip);
+        self.client_port = source.client_port;
+        self.server_name.clone_from(&source.server_name);
+        self.protocol.clone_from(&source.protocol);
+        self.tls_version.clone_from(&source.tls_version);
+        self.tls_cipher.clone_from(&source.tls_cipher);
+        self.route_id.clone_from(&source.route_id);
+        self.upstream_id.clone_from(&source.upstream_id);
+        self.timestamp.clone_from(&source.timestamp);
+        self.traceparent.clone_from(&source.traceparent);
+        self.client_cert.clone_from(&source.client_cert);
+        self.tags.clone_from(&source.tags);
+        self.bot_signals.clone_from(&source.bot_signals);
+        self.dry_run = source.dry_run;
+    }
+}
+
+/// A recycled object that goes back to its thread's pool on drop.
+///
+/// The object may be dropped on a different thread than it was taken on
+/// (e.g. after an `.await`); it then joins that thread's pool.
+pub struct Pooled<T: Recycle> {
+    /// Invariant: `Some` until `into_inner` or `Drop`.
+    value: Option<T>,
+}
+
+impl<T: Recycle> Pooled<T> {
+    /// Take an empty object from the current thread's pool.
+    pub fn acquire() -> Self {
+        let value = T::with_pool(|pool| pool.pop()).unwrap_or_else(T::empty);
+        Self { value: Some(value) }
+    }
+
+    /// Take the object out; it will not return to the pool.
+    pub fn into_inner(mut self) -> T {
+        self.value.take().expect("pooled value already taken")
+    }
+}
+
+impl<T: Recycle> Drop for Pooled<T> {
+    fn drop(&mut self) {
+        if let Some(mut value) = self.value.take() {
+            if !value.reusable() {
+                return;
+            }
+            value.recycle();
+            T::with_pool(|pool| {
+                if pool.len() < MAX_POOLED_OBJECTS {
+                    pool.push(value);
+                }
+            });
+        }
+    }
+}
+
+impl<T: Recycle> std::ops::Deref for Pooled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("pooled value already taken")
+    }
+}
+
+impl<T: Recycle> std::ops::DerefMut for Pooled<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("pooled value already taken")
+    }
+}
+
+impl<T: Recycle + std::fmt::Debug> std::fmt::Debug for Pooled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Number of idle objects of type `T` in the current thread's pool.
+pub fn pooled_objects<T: Recycle>() -> usize {
+    T::with_pool(|pool| pool.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,4 +652,83 @@ mod tests {
         assert_eq!(stats.pooled, MAX_POOL_SIZE);
         assert!(stats.dropped >= 5);
     }
+
+    #[test]
+    fn test_shared_pool_reuses_buffers() {
+        let pool = SharedBufferPool::new(2);
+
+        let mut buf = pool.get();
+        buf.extend_from_slice(b"encoded event");
+        let capacity = buf.capacity();
+        pool.put(buf);
+
+        let buf = pool.get();
+        assert!(buf.is_empty());
+        assert_eq!(buf.capacity(), capacity);
+
+        let stats = pool.stats();
+        assert_eq!(stats.allocated, 1);
+        assert_eq!(stats.reused, 1);
+    }
+
+    #[test]
+    fn test_shared_pool_drops_oversized_and_excess_buffers() {
+        let pool = SharedBufferPool::new(1);
+
+        pool.put(Vec::with_capacity(MAX_POOLED_BUFFER_SIZE + 1));
+        pool.put(Vec::with_capacity(16));
+        pool.put(Vec::with_capacity(16));
+
+        let stats = pool.stats();
+        assert_eq!(stats.pooled, 1);
+        assert_eq!(stats.dropped, 2);
+    }
+
+    #[test]
+    fn test_pooled_event_is_recycled_with_capacity() {
+        REQUEST_HEADERS_POOL.with(|pool| pool.borrow_mut().clear());
+
+        let uri_capacity = {
+            let mut event = Pooled::<RequestHeadersEvent>::acquire();
+            event.metadata.correlation_id.push_str("req-1");
+            event.uri.push_str("/api/v1/orders?page=2");
+            event
+                .headers
+                .insert("host".to_string(), vec!["example.com".to_string()]);
+            event.uri.capacity()
+        };
+        assert_eq!(pooled_objects::<RequestHeadersEvent>(), 1);
+
+        let event = Pooled::<RequestHeadersEvent>::acquire();
+        assert_eq!(pooled_objects::<RequestHeadersEvent>(), 0);
+        assert!(event.metadata.correlation_id.is_empty());
+        assert!(event.uri.is_empty());
+        assert!(event.headers.is_empty());
+        assert_eq!(event.uri.capacity(), uri_capacity);
+    }
+
+    #[test]
+    fn test_into_inner_leaves_pool() {
+        REQUEST_METADATA_POOL.with(|pool| pool.borrow_mut().clear());
+
+        let metadata = Pooled::<RequestMetadata>::acquire().into_inner();
+        drop(metadata);
+        assert_eq!(pooled_objects::<RequestMetadata>(), 0);
+    }
+
+    #[test]
+    fn test_fill_from_copies_metadata() {
+        let mut source = RequestMetadata::empty();
+        source.correlation_id = "req-1".to_string();
+        source.client_ip = "203.0.113.7".to_string();
+        source.client_port = 443;
+        source.route_id = Some("api".to_string());
+
+        let mut metadata = Pooled::<RequestMetadata>::acquire();
+        metadata.fill_from(&source);
+        assert_eq!(metadata.correlation_id, "req-1");
+        assert_eq!(metadata.client_ip, "203.0.113.7");
+        assert_eq!(metadata.client_port, 443);
+        assert_eq!(metadata.route_id.as_deref(), Some("api"));
+    }
 }
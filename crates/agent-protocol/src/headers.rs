@@ -9,10 +9,17 @@
 //! - Header lookup: O(1) average (borrowed from source HashMap)
 //! - Conversion to owned: Only allocates when actually needed
 //! - SmallVec for values: Inline storage for single-value headers (most common)
+//!
+//! HTTP header names are case-insensitive: [`HeaderName`] stores them
+//! lowercased (interned for the names in [`names`]) and the lookups here
+//! match names regardless of case.
 
-use std::borrow::Cow;
+use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::SmallVec;
 
 /// Header values using SmallVec for inline storage.
@@ -21,11 +28,22 @@ use smallvec::SmallVec;
 /// avoids heap allocation for the Vec in the common case.
 pub type HeaderValues = SmallVec<[String; 1]>;
 
-/// Optimized header map using SmallVec for values.
+/// Look up a header, matching the name case-insensitively.
 ///
-/// This reduces allocations for typical requests where most headers
-/// have only one value.
-pub type OptimizedHeaderMap = HashMap<String, HeaderValues>;
+/// Tries an exact match first, so maps with lowercase names (as the proxy
+/// sends them) never pay for the fallback scan.
+#[inline]
+pub fn get_ignore_case<'a>(
+    headers: &'a HashMap<String, Vec<String>>,
+    name: &str,
+) -> Option<&'a Vec<String>> {
+    headers.get(name).or_else(|| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, values)| values)
+    })
+}
 
 /// Zero-copy header reference.
 ///
@@ -42,25 +60,22 @@ impl<'a> HeadersRef<'a> {
         Self { inner: headers }
     }
 
-    /// Get a header value by name.
+    /// Get a header value by name (case-insensitive).
     #[inline]
     pub fn get(&self, name: &str) -> Option<&Vec<String>> {
-        self.inner.get(name)
+        get_ignore_case(&self.inner, name)
     }
 
-    /// Get the first value for a header.
+    /// Get the first value for a header (case-insensitive).
     #[inline]
     pub fn get_first(&self, name: &str) -> Option<&str> {
-        self.inner
-            .get(name)
-            .and_then(|v| v.first())
-            .map(|s| s.as_str())
+        self.get(name).and_then(|v| v.first()).map(|s| s.as_str())
     }
 
-    /// Check if a header exists.
+    /// Check if a header exists (case-insensitive).
     #[inline]
     pub fn contains(&self, name: &str) -> bool {
-        self.inner.contains_key(name)
+        self.get(name).is_some()
     }
 
     /// Get the number of unique header names.
@@ -127,25 +142,22 @@ impl<'a> HeadersCow<'a> {
         }
     }
 
-    /// Get a header value by name.
+    /// Get a header value by name (case-insensitive).
     #[inline]
     pub fn get(&self, name: &str) -> Option<&Vec<String>> {
-        self.inner.get(name)
+        get_ignore_case(&self.inner, name)
     }
 
-    /// Get the first value for a header.
+    /// Get the first value for a header (case-insensitive).
     #[inline]
     pub fn get_first(&self, name: &str) -> Option<&str> {
-        self.inner
-            .get(name)
-            .and_then(|v| v.first())
-            .map(|s| s.as_str())
+        self.get(name).and_then(|v| v.first()).map(|s| s.as_str())
     }
 
-    /// Check if a header exists.
+    /// Check if a header exists (case-insensitive).
     #[inline]
     pub fn contains(&self, name: &str) -> bool {
-        self.inner.contains_key(name)
+        self.get(name).is_some()
     }
 
     /// Set a header value (triggers clone if borrowed).
@@ -290,6 +302,345 @@ pub mod names {
     pub const X_SPAN_ID: &str = "x-span-id";
 }
 
+/// Lowercase `name`, borrowing it when it already is.
+#[inline]
+fn lowercase(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// The static name for a well-known header; `name` must be lowercase.
+#[inline]
+fn known_header_name(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "host" => names::HOST,
+        "content-type" => names::CONTENT_TYPE,
+        "content-length" => names::CONTENT_LENGTH,
+        "user-agent" => names::USER_AGENT,
+        "accept" => names::ACCEPT,
+        "accept-encoding" => names::ACCEPT_ENCODING,
+        "accept-language" => names::ACCEPT_LANGUAGE,
+        "authorization" => names::AUTHORIZATION,
+        "cookie" => names::COOKIE,
+        "set-cookie" => names::SET_COOKIE,
+        "cache-control" => names::CACHE_CONTROL,
+        "connection" => names::CONNECTION,
+        "date" => names::DATE,
+        "etag" => names::ETAG,
+        "if-match" => names::IF_MATCH,
+        "if-none-match" => names::IF_NONE_MATCH,
+        "if-modified-since" => names::IF_MODIFIED_SINCE,
+        "last-modified" => names::LAST_MODIFIED,
+        "location" => names::LOCATION,
+        "origin" => names::ORIGIN,
+        "referer" => names::REFERER,
+        "server" => names::SERVER,
+        "transfer-encoding" => names::TRANSFER_ENCODING,
+        "vary" => names::VARY,
+        "x-forwarded-for" => names::X_FORWARDED_FOR,
+        "x-forwarded-proto" => names::X_FORWARDED_PROTO,
+        "x-forwarded-host" => names::X_FORWARDED_HOST,
+        "x-real-ip" => names::X_REAL_IP,
+        "x-request-id" => names::X_REQUEST_ID,
+        "x-correlation-id" => names::X_CORRELATION_ID,
+        "x-trace-id" => names::X_TRACE_ID,
+        "x-span-id" => names::X_SPAN_ID,
+        _ => return None,
+    })
+}
+
+/// Case-insensitive, interned header name.
+///
+/// Names are stored lowercased, so equality and hashing ignore case. The
+/// names in [`names`] are static references; others are owned.
+///
+/// # Example
+///
+/// ```
+/// use zentinel_agent_protocol::headers::HeaderName;
+///
+/// let name = HeaderName::new("Content-Type");
+/// assert!(name.is_interned());
+/// assert_eq!(name, HeaderName::new("content-type"));
+/// assert_eq!(name.as_str(), "content-type");
+/// ```
+#[derive(Clone)]
+pub struct HeaderName(HeaderNameRepr);
+
+#[derive(Clone)]
+enum HeaderNameRepr {
+    Known(&'static str),
+    Custom(Box<str>),
+}
+
+impl HeaderName {
+    /// Create a header name, lowercasing and interning it.
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        let lower = lowercase(name);
+        match known_header_name(&lower) {
+            Some(known) => Self(HeaderNameRepr::Known(known)),
+            None => Self(HeaderNameRepr::Custom(lower.into_owned().into_boxed_str())),
+        }
+    }
+
+    /// Create a header name from an owned string, reusing its allocation
+    /// for names that are not interned.
+    #[inline]
+    pub fn from_string(mut name: String) -> Self {
+        name.make_ascii_lowercase();
+        match known_header_name(&name) {
+            Some(known) => Self(HeaderNameRepr::Known(known)),
+            None => Self(HeaderNameRepr::Custom(name.into_boxed_str())),
+        }
+    }
+
+    /// The lowercase name.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            HeaderNameRepr::Known(name) => name,
+            HeaderNameRepr::Custom(name) => name,
+        }
+    }
+
+    /// Whether the name is a static reference from [`names`].
+    #[inline]
+    pub fn is_interned(&self) -> bool {
+        matches!(self.0, HeaderNameRepr::Known(_))
+    }
+
+    /// Convert into an owned `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        match self.0 {
+            HeaderNameRepr::Known(name) => name.to_string(),
+            HeaderNameRepr::Custom(name) => name.into_string(),
+        }
+    }
+}
+
+impl PartialEq for HeaderName {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for HeaderName {}
+
+impl PartialEq<str> for HeaderName {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<&str> for HeaderName {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl Hash for HeaderName {
+    // Same hash as the lowercase `str`, which `Borrow<str>` relies on
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Borrow<str> for HeaderName {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for HeaderName {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for HeaderName {
+    #[inline]
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for HeaderName {
+    #[inline]
+    fn from(name: String) -> Self {
+        Self::from_string(name)
+    }
+}
+
+impl From<HeaderName> for String {
+    #[inline]
+    fn from(name: HeaderName) -> Self {
+        name.into_string()
+    }
+}
+
+impl Serialize for HeaderName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from_string)
+    }
+}
+
+/// Header map with case-insensitive [`HeaderName`] keys and SmallVec values.
+///
+/// Most headers have a single value, which `HeaderValues` stores inline;
+/// well-known names are interned. Names differing only in case are one
+/// entry. Serializes as a map of names to value lists, like the
+/// `HashMap<String, Vec<String>>` in the protocol events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OptimizedHeaderMap {
+    inner: HashMap<HeaderName, HeaderValues>,
+}
+
+impl OptimizedHeaderMap {
+    /// Create an empty map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty map with room for `capacity` names.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Get the values of a header (case-insensitive).
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&HeaderValues> {
+        self.inner.get(lowercase(name).as_ref())
+    }
+
+    /// Get the first value of a header (case-insensitive).
+    #[inline]
+    pub fn get_first(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|v| v.first()).map(|s| s.as_str())
+    }
+
+    /// Check if a header exists (case-insensitive).
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Replace the values of a header, returning the previous ones.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        name: impl Into<HeaderName>,
+        values: HeaderValues,
+    ) -> Option<HeaderValues> {
+        self.inner.insert(name.into(), values)
+    }
+
+    /// Add a value to a header.
+    #[inline]
+    pub fn append(&mut self, name: impl Into<HeaderName>, value: impl Into<String>) {
+        self.inner
+            .entry(name.into())
+            .or_default()
+            .push(value.into());
+    }
+
+    /// Remove a header (case-insensitive).
+    #[inline]
+    pub fn remove(&mut self, name: &str) -> Option<HeaderValues> {
+        self.inner.remove(lowercase(name).as_ref())
+    }
+
+    /// Number of distinct header names.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether the map is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Remove all headers, keeping the allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Iterate over header names and values.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &HeaderValues)> {
+        self.inner.iter()
+    }
+}
+
+impl FromIterator<(HeaderName, HeaderValues)> for OptimizedHeaderMap {
+    /// Values of names that differ only in case are merged.
+    fn from_iter<I: IntoIterator<Item = (HeaderName, HeaderValues)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(iter.size_hint().0);
+        for (name, values) in iter {
+            map.inner.entry(name).or_default().extend(values);
+        }
+        map
+    }
+}
+
+impl IntoIterator for OptimizedHeaderMap {
+    type Item = (HeaderName, HeaderValues);
+    type IntoIter = std::collections::hash_map::IntoIter<HeaderName, HeaderValues>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl From<HashMap<String, Vec<String>>> for OptimizedHeaderMap {
+    fn from(headers: HashMap<String, Vec<String>>) -> Self {
+        to_optimized(headers)
+    }
+}
+
+impl From<OptimizedHeaderMap> for HashMap<String, Vec<String>> {
+    fn from(headers: OptimizedHeaderMap) -> Self {
+        from_optimized(headers)
+    }
+}
+
 /// Header name type using Cow for zero-allocation on common headers.
 ///
 /// When the header name matches a well-known header, this borrows a static
@@ -346,42 +697,10 @@ pub type CowHeaderMap = HashMap<CowHeaderName, HeaderValues>;
 #[inline]
 pub fn intern_header_name(name: &str) -> CowHeaderName {
     // Case-insensitive matching for HTTP headers
-    let lower = name.to_ascii_lowercase();
-
-    match lower.as_str() {
-        "host" => Cow::Borrowed(names::HOST),
-        "content-type" => Cow::Borrowed(names::CONTENT_TYPE),
-        "content-length" => Cow::Borrowed(names::CONTENT_LENGTH),
-        "user-agent" => Cow::Borrowed(names::USER_AGENT),
-        "accept" => Cow::Borrowed(names::ACCEPT),
-        "accept-encoding" => Cow::Borrowed(names::ACCEPT_ENCODING),
-        "accept-language" => Cow::Borrowed(names::ACCEPT_LANGUAGE),
-        "authorization" => Cow::Borrowed(names::AUTHORIZATION),
-        "cookie" => Cow::Borrowed(names::COOKIE),
-        "set-cookie" => Cow::Borrowed(names::SET_COOKIE),
-        "cache-control" => Cow::Borrowed(names::CACHE_CONTROL),
-        "connection" => Cow::Borrowed(names::CONNECTION),
-        "date" => Cow::Borrowed(names::DATE),
-        "etag" => Cow::Borrowed(names::ETAG),
-        "if-match" => Cow::Borrowed(names::IF_MATCH),
-        "if-none-match" => Cow::Borrowed(names::IF_NONE_MATCH),
-        "if-modified-since" => Cow::Borrowed(names::IF_MODIFIED_SINCE),
-        "last-modified" => Cow::Borrowed(names::LAST_MODIFIED),
-        "location" => Cow::Borrowed(names::LOCATION),
-        "origin" => Cow::Borrowed(names::ORIGIN),
-        "referer" => Cow::Borrowed(names::REFERER),
-        "server" => Cow::Borrowed(names::SERVER),
-        "transfer-encoding" => Cow::Borrowed(names::TRANSFER_ENCODING),
-        "vary" => Cow::Borrowed(names::VARY),
-        "x-forwarded-for" => Cow::Borrowed(names::X_FORWARDED_FOR),
-        "x-forwarded-proto" => Cow::Borrowed(names::X_FORWARDED_PROTO),
-        "x-forwarded-host" => Cow::Borrowed(names::X_FORWARDED_HOST),
-        "x-real-ip" => Cow::Borrowed(names::X_REAL_IP),
-        "x-request-id" => Cow::Borrowed(names::X_REQUEST_ID),
-        "x-correlation-id" => Cow::Borrowed(names::X_CORRELATION_ID),
-        "x-trace-id" => Cow::Borrowed(names::X_TRACE_ID),
-        "x-span-id" => Cow::Borrowed(names::X_SPAN_ID),
-        _ => Cow::Owned(lower), // Unknown header - use the lowercased string
+    let lower = lowercase(name);
+    match known_header_name(&lower) {
+        Some(known) => Cow::Borrowed(known),
+        None => Cow::Owned(lower.into_owned()), // Unknown header - use the lowercased string
     }
 }
 
@@ -419,23 +738,30 @@ pub fn iter_flat_cow(headers: &CowHeaderMap) -> impl Iterator<Item = (&str, &str
 /// Convert standard headers to optimized format.
 ///
 /// This is useful when receiving headers from external sources (JSON, gRPC)
-/// and converting them for internal processing.
+/// and converting them for internal processing. Names are lowercased and
+/// interned; names differing only in case are merged.
 #[inline]
 pub fn to_optimized(headers: HashMap<String, Vec<String>>) -> OptimizedHeaderMap {
     headers
         .into_iter()
-        .map(|(name, values)| (name, HeaderValues::from_vec(values)))
+        .map(|(name, values)| {
+            (
+                HeaderName::from_string(name),
+                HeaderValues::from_vec(values),
+            )
+        })
         .collect()
 }
 
 /// Convert optimized headers back to standard format.
 ///
 /// This is useful when serializing headers for external transmission.
+/// Names come back lowercased.
 #[inline]
 pub fn from_optimized(headers: OptimizedHeaderMap) -> HashMap<String, Vec<String>> {
     headers
         .into_iter()
-        .map(|(name, values)| (name, values.into_vec()))
+        .map(|(name, values)| (name.into_string(), values.into_vec()))
         .collect()
 }
 
@@ -559,7 +885,7 @@ mod tests {
 
     #[test]
     fn test_optimized_header_map() {
-        let mut optimized: OptimizedHeaderMap = HashMap::new();
+        let mut optimized = OptimizedHeaderMap::new();
 
         // Single value - stored inline (no Vec allocation)
         optimized.insert(
@@ -765,4 +1091,73 @@ mod tests {
         assert!(pairs.contains(&("accept", "application/json")));
         assert!(pairs.contains(&("x-custom", "value")));
     }
+
+    #[test]
+    fn test_header_name_is_case_insensitive() {
+        let known = HeaderName::new("Content-Type");
+        assert!(known.is_interned());
+        assert_eq!(known.as_str(), "content-type");
+        assert_eq!(known, HeaderName::from_string("CONTENT-TYPE".to_string()));
+        assert_eq!(known, "content-TYPE");
+
+        let custom = HeaderName::new("X-Custom-Header");
+        assert!(!custom.is_interned());
+        assert_eq!(custom.as_str(), "x-custom-header");
+        assert_eq!(custom.into_string(), "x-custom-header");
+    }
+
+    #[test]
+    fn test_optimized_header_map_case_insensitive_lookup() {
+        let mut headers = OptimizedHeaderMap::new();
+        headers.insert(
+            "Content-Type",
+            HeaderValues::from_iter(["text/html".into()]),
+        );
+        headers.append("X-Custom", "a");
+        headers.append("x-custom", "b");
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get_first("CONTENT-TYPE"), Some("text/html"));
+        assert_eq!(headers.get("X-CUSTOM").map(|v| v.len()), Some(2));
+        assert!(headers.remove("X-Custom").is_some());
+        assert!(!headers.contains("x-custom"));
+    }
+
+    #[test]
+    fn test_to_optimized_merges_names_differing_in_case() {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), vec!["text/html".to_string()]);
+        headers.insert("accept".to_string(), vec!["application/json".to_string()]);
+
+        let optimized = to_optimized(headers);
+        assert_eq!(optimized.len(), 1);
+        assert_eq!(optimized.get("accept").map(|v| v.len()), Some(2));
+        assert!(optimized.iter().all(|(name, _)| name.is_interned()));
+    }
+
+    #[test]
+    fn test_optimized_header_map_serializes_like_std_map() {
+        let headers = sample_headers();
+        let json = serde_json::to_value(to_optimized(headers.clone())).unwrap();
+        assert_eq!(json, serde_json::to_value(&headers).unwrap());
+
+        let parsed: OptimizedHeaderMap =
+            serde_json::from_str(r#"{"Content-Type":["application/json"]}"#).unwrap();
+        assert_eq!(parsed.get_first("content-type"), Some("application/json"));
+    }
+
+    #[test]
+    fn test_lookups_ignore_case() {
+        let headers = sample_headers();
+        assert_eq!(
+            HeadersRef::new(&headers).get_first("Content-Type"),
+            Some("application/json")
+        );
+        assert!(HeadersCow::borrowed(&headers).contains("X-CUSTOM"));
+        assert_eq!(
+            get_ignore_case(&headers, "ACCEPT").map(|v| v.len()),
+            Some(2)
+        );
+        assert!(get_ignore_case(&headers, "missing").is_none());
+    }
 }
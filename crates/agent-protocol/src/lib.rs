@@ -24,7 +24,7 @@
 //! ## Unix Domain Sockets (Default)
 //! Messages are length-prefixed with negotiated encoding (JSON or MessagePack):
 //! - 4-byte big-endian length prefix
-//! - Encoded payload (max 10MB)
+//! - Encoded payload (16MB by default, negotiated per agent during the handshake)
 //!
 //! ## gRPC
 //! Binary protocol using Protocol Buffers over HTTP/2:
@@ -56,16 +56,18 @@ pub use errors::AgentProtocolError;
 // Re-export protocol types
 pub use protocol::{
     AgentResponse, AuditMetadata, BinaryRequestBodyChunkEvent, BinaryResponseBodyChunkEvent,
-    BodyMutation, Decision, DetectionSeverity, EventType, GuardrailDetection,
-    GuardrailInspectEvent, GuardrailInspectionType, GuardrailResponse, HeaderOp,
-    RequestBodyChunkEvent, RequestCompleteEvent, RequestHeadersEvent, RequestMetadata,
-    ResponseBodyChunkEvent, ResponseHeadersEvent, TextSpan, WebSocketDecision, WebSocketFrameEvent,
-    WebSocketOpcode, MAX_MESSAGE_SIZE,
+    BodyMutation, BotSignals, ClientCertificate, Decision, DetectionSeverity, EventType,
+    GuardrailDetection, GuardrailInspectEvent, GuardrailInspectionType, GuardrailResponse,
+    HeaderOp, LatencyBreakdown, MultipartPart, RequestBodyChunkEvent, RequestCompleteEvent,
+    RequestHeadersEvent, RequestMetadata, RequestTag, ResponseBodyChunkEvent, ResponseHeadersEvent,
+    TagCardinality, TextSpan, WebSocketDecision, WebSocketFrameEvent, WebSocketOpcode,
+    MAX_MESSAGE_SIZE, MAX_NEGOTIATED_MESSAGE_SIZE,
 };
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_body_mutation_types() {
@@ -110,4 +112,37 @@ mod tests {
         let response = AgentResponse::default_allow().set_needs_more(true);
         assert!(response.needs_more);
     }
+
+    #[test]
+    fn test_request_complete_latency_breakdown() {
+        // Events from older proxies carry no breakdown
+        let event: RequestCompleteEvent = serde_json::from_value(serde_json::json!({
+            "correlation_id": "req-1",
+            "status": 200,
+            "duration_ms": 42,
+            "request_body_size": 0,
+            "response_body_size": 128,
+            "upstream_attempts": 1,
+            "error": null
+        }))
+        .unwrap();
+        assert_eq!(event.latency, LatencyBreakdown::default());
+
+        let latency = LatencyBreakdown {
+            connect_ms: Some(3),
+            agent_total_ms: 5,
+            agents_ms: HashMap::from([("waf".to_string(), 5)]),
+            upstream_ttfb_ms: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&latency).unwrap(),
+            serde_json::json!({
+                "connect_ms": 3,
+                "agent_total_ms": 5,
+                "agents_ms": {"waf": 5},
+                "upstream_ttfb_ms": 20
+            })
+        );
+    }
 }
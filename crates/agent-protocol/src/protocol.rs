@@ -10,8 +10,18 @@ use std::collections::HashMap;
 /// Agent protocol version
 pub const PROTOCOL_VERSION: u32 = 2;
 
-/// Maximum message size for gRPC transport (10MB)
-pub const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+/// Default maximum message size for agent transports (16MB)
+///
+/// Agents and the proxy may negotiate a different limit during the handshake
+/// (see [`AgentLimits::max_message_size`](crate::v2::AgentLimits)); this is
+/// the value used when neither side specifies one.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Upper bound for a negotiated maximum message size (64MB)
+///
+/// Larger configured values are clamped to this ceiling so a misconfigured
+/// agent cannot make the proxy allocate unbounded read buffers.
+pub const MAX_NEGOTIATED_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
 
 /// Agent event type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -225,6 +235,62 @@ pub struct RequestMetadata {
     /// Agents can use this to create child spans that link to the proxy's span.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub traceparent: Option<String>,
+    /// Client certificate from a mutual-TLS handshake
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<ClientCertificate>,
+    /// Tags set on this request so far by agents that already ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<RequestTag>,
+    /// Client fingerprints, on listeners with `bot-signals` enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot_signals: Option<BotSignals>,
+    /// Synthetic request from the proxy's `explain` admin handler
+    ///
+    /// Nothing is sent upstream and the decision is only reported. Agents
+    /// should decide as usual but skip side effects such as counting the
+    /// request against limits or updating stored state.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// Client certificate presented on a mutual-TLS connection
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientCertificate {
+    /// Subject distinguished name (e.g. `CN=client.example.com, O=Example`)
+    pub subject: Option<String>,
+    /// Subject alternative names (DNS names, IP addresses, URIs, emails)
+    #[serde(default)]
+    pub sans: Vec<String>,
+    /// SHA-256 fingerprint of the DER-encoded certificate (lowercase hex)
+    pub fingerprint_sha256: String,
+    /// Serial number (hex)
+    pub serial_number: Option<String>,
+    /// Whether the certificate chain was verified against the listener's CA
+    pub verified: bool,
+}
+
+/// Client fingerprints for bot detection.
+///
+/// TLS fingerprints are taken from the connection's ClientHello, the HTTP/2
+/// fingerprint from its connection preface; each is `None` when the
+/// connection didn't carry it (plain HTTP, HTTP/1 over TLS).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotSignals {
+    /// JA3 string: `version,ciphers,extensions,groups,point-formats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ja3: Option<String>,
+    /// MD5 of the JA3 string (lowercase hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ja3_hash: Option<String>,
+    /// JA4 fingerprint (e.g. `t13d1516h2_8daaf6152771_e5627efa2ab1`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ja4: Option<String>,
+    /// HTTP/2 fingerprint: `settings|window-update|priority|pseudo-headers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http2: Option<String>,
+    /// Hash of the request's header names in the order sent (12 hex digits)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_order_hash: Option<String>,
 }
 
 /// Request headers event
@@ -240,6 +306,23 @@ pub struct RequestHeadersEvent {
     pub headers: HashMap<String, Vec<String>>,
 }
 
+impl RequestHeadersEvent {
+    /// First value of a header, matching the name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name)?.first().map(String::as_str)
+    }
+
+    /// All values of a header, matching the name case-insensitively
+    pub fn header_values(&self, name: &str) -> Option<&[String]> {
+        crate::headers::get_ignore_case(&self.headers, name).map(Vec::as_slice)
+    }
+
+    /// The headers as a case-insensitive [`OptimizedHeaderMap`](crate::headers::OptimizedHeaderMap)
+    pub fn optimized_headers(&self) -> crate::headers::OptimizedHeaderMap {
+        crate::headers::to_optimized(self.headers.clone())
+    }
+}
+
 /// Request body chunk event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestBodyChunkEvent {
@@ -259,6 +342,28 @@ pub struct RequestBodyChunkEvent {
     /// Bytes received so far (cumulative)
     #[serde(default)]
     pub bytes_received: usize,
+    /// multipart/form-data part the chunk belongs to
+    ///
+    /// Set when the proxy parses the body into parts; `is_last` then marks
+    /// the last chunk of the part and `bytes_received` counts the part's
+    /// bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<MultipartPart>,
+}
+
+/// A part of a multipart/form-data request body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultipartPart {
+    /// Position of the part in the body (0-based)
+    pub index: u32,
+    /// Form field name from `Content-Disposition`
+    pub name: String,
+    /// Filename from `Content-Disposition`, for file uploads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// The part's `Content-Type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
 }
 
 /// Response headers event
@@ -272,6 +377,23 @@ pub struct ResponseHeadersEvent {
     pub headers: HashMap<String, Vec<String>>,
 }
 
+impl ResponseHeadersEvent {
+    /// First value of a header, matching the name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name)?.first().map(String::as_str)
+    }
+
+    /// All values of a header, matching the name case-insensitively
+    pub fn header_values(&self, name: &str) -> Option<&[String]> {
+        crate::headers::get_ignore_case(&self.headers, name).map(Vec::as_slice)
+    }
+
+    /// The headers as a case-insensitive [`OptimizedHeaderMap`](crate::headers::OptimizedHeaderMap)
+    pub fn optimized_headers(&self) -> crate::headers::OptimizedHeaderMap {
+        crate::headers::to_optimized(self.headers.clone())
+    }
+}
+
 /// Response body chunk event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseBodyChunkEvent {
@@ -318,6 +440,8 @@ pub struct BinaryRequestBodyChunkEvent {
     pub chunk_index: u32,
     /// Bytes received so far (cumulative)
     pub bytes_received: usize,
+    /// multipart/form-data part the chunk belongs to
+    pub part: Option<MultipartPart>,
 }
 
 /// Binary response body chunk event.
@@ -361,6 +485,7 @@ impl BinaryRequestBodyChunkEvent {
             is_last,
             total_size: None,
             chunk_index,
+            part: None,
         }
     }
 
@@ -375,6 +500,12 @@ impl BinaryRequestBodyChunkEvent {
         self.bytes_received = bytes;
         self
     }
+
+    /// Set the multipart part the chunk belongs to.
+    pub fn with_part(mut self, part: MultipartPart) -> Self {
+        self.part = Some(part);
+        self
+    }
 }
 
 impl BinaryResponseBodyChunkEvent {
@@ -424,6 +555,7 @@ impl From<BinaryRequestBodyChunkEvent> for RequestBodyChunkEvent {
             total_size: event.total_size,
             chunk_index: event.chunk_index,
             bytes_received: event.bytes_received,
+            part: event.part,
         }
     }
 }
@@ -445,6 +577,7 @@ impl From<&RequestBodyChunkEvent> for BinaryRequestBodyChunkEvent {
             total_size: event.total_size,
             chunk_index: event.chunk_index,
             bytes_received: event.bytes_received,
+            part: event.part.clone(),
         }
     }
 }
@@ -502,6 +635,42 @@ pub struct RequestCompleteEvent {
     pub upstream_attempts: u32,
     /// Error if any
     pub error: Option<String>,
+    /// Where the request's time went
+    #[serde(default)]
+    pub latency: LatencyBreakdown,
+}
+
+/// Per-phase breakdown of a request's duration, in milliseconds.
+///
+/// Phases the request did not go through are `None`: a reused upstream
+/// connection has no DNS, connect or TLS time and a cached response no
+/// upstream time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyBreakdown {
+    /// Resolving the upstream address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<u64>,
+    /// Establishing the TCP connection to the upstream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// TLS handshake with the upstream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    /// Time spent waiting for agents; agents called in parallel count once
+    pub agent_total_ms: u64,
+    /// Time spent in each agent, by agent ID
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agents_ms: HashMap<String, u64>,
+    /// From the upstream connection being ready to the response headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_ttfb_ms: Option<u64>,
+    /// From the response headers to the end of the upstream body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_body_ms: Option<u64>,
+    /// From the end of the upstream body to the response being written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downstream_write_ms: Option<u64>,
 }
 
 // ============================================================================
@@ -625,6 +794,9 @@ pub struct AgentResponse {
     /// Routing metadata modifications
     #[serde(default)]
     pub routing_metadata: HashMap<String, String>,
+    /// Tags to attach to the request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<RequestTag>,
     /// Audit metadata
     #[serde(default)]
     pub audit: AuditMetadata,
@@ -672,6 +844,7 @@ impl AgentResponse {
             request_headers: vec![],
             response_headers: vec![],
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             audit: AuditMetadata::default(),
             needs_more: false,
             request_body_mutation: None,
@@ -692,6 +865,7 @@ impl AgentResponse {
             request_headers: vec![],
             response_headers: vec![],
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             audit: AuditMetadata::default(),
             needs_more: false,
             request_body_mutation: None,
@@ -708,6 +882,7 @@ impl AgentResponse {
             request_headers: vec![],
             response_headers: vec![],
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             audit: AuditMetadata::default(),
             needs_more: false,
             request_body_mutation: None,
@@ -724,6 +899,7 @@ impl AgentResponse {
             request_headers: vec![],
             response_headers: vec![],
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             audit: AuditMetadata::default(),
             needs_more: true,
             request_body_mutation: None,
@@ -797,6 +973,59 @@ impl AgentResponse {
         self.audit = audit;
         self
     }
+
+    /// Attach a tag to the request
+    pub fn add_tag(mut self, tag: RequestTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+}
+
+/// Expected number of distinct values of a request tag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagCardinality {
+    /// A small, bounded set of values (e.g. a client tier); may become a
+    /// metric label
+    Low,
+    /// Unbounded values (e.g. a user ID); only logged
+    #[default]
+    High,
+}
+
+/// Typed tag an agent attaches to a request.
+///
+/// Tags appear as structured fields in the access and audit logs and can be
+/// read by filters and by agents that run later in the request. A tag set
+/// again under the same name replaces the earlier value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestTag {
+    /// Tag name
+    pub name: String,
+    /// Tag value
+    pub value: String,
+    /// Cardinality hint
+    #[serde(default)]
+    pub cardinality: TagCardinality,
+}
+
+impl RequestTag {
+    /// Create a high-cardinality tag
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            cardinality: TagCardinality::High,
+        }
+    }
+
+    /// Create a low-cardinality tag
+    pub fn low_cardinality(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            cardinality: TagCardinality::Low,
+            ..Self::new(name, value)
+        }
+    }
 }
 
 /// Audit metadata from agent
@@ -857,6 +1086,14 @@ pub struct GuardrailInspectEvent {
     /// Additional metadata for context
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+    /// Embedding of `content` computed by the proxy, when the route has an
+    /// embedding endpoint configured (for similarity checks against vector
+    /// blocklists)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+    /// Model that produced `embedding`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
 }
 
 /// Guardrail inspection response from agent
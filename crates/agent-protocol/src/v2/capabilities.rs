@@ -2,6 +2,7 @@
 
 use crate::EventType;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Agent capabilities declared during handshake.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +18,9 @@ pub struct AgentCapabilities {
     pub limits: AgentLimits,
     #[serde(default)]
     pub health: HealthConfig,
+    /// Requests to replay after every (re)connect, before real traffic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<WarmupRequest>,
 }
 
 impl AgentCapabilities {
@@ -34,6 +38,7 @@ impl AgentCapabilities {
             features: AgentFeatures::default(),
             limits: AgentLimits::default(),
             health: HealthConfig::default(),
+            warmup: Vec::new(),
         }
     }
 
@@ -57,6 +62,43 @@ impl AgentCapabilities {
         self.limits = limits;
         self
     }
+
+    pub fn with_warmup(mut self, request: WarmupRequest) -> Self {
+        self.warmup.push(request);
+        self
+    }
+}
+
+/// Synthetic request an agent asks to receive after connecting.
+///
+/// Proxies with warm-up enabled send each one as a `RequestHeaders` event
+/// while the agent's traffic ramps up, so it can compile rules and fill its
+/// caches before real requests arrive. Decisions for warm-up requests are
+/// discarded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmupRequest {
+    pub method: String,
+    pub uri: String,
+    #[serde(default)]
+    pub headers: HashMap<String, Vec<String>>,
+}
+
+impl WarmupRequest {
+    pub fn new(method: impl Into<String>, uri: impl Into<String>) -> Self {
+        Self {
+            method: method.into(),
+            uri: uri.into(),
+            headers: HashMap::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers
+            .entry(name.into())
+            .or_default()
+            .push(value.into());
+        self
+    }
 }
 
 /// Features this agent supports.
@@ -80,6 +122,10 @@ pub struct AgentFeatures {
     pub flow_control: bool,
     #[serde(default)]
     pub health_reporting: bool,
+    /// Agent reassembles body chunks that the proxy split into several
+    /// messages to stay under the negotiated `max_message_size`.
+    #[serde(default)]
+    pub chunk_reassembly: bool,
 }
 
 impl AgentFeatures {
@@ -97,6 +143,7 @@ impl AgentFeatures {
             cancellation: true,
             flow_control: true,
             health_reporting: true,
+            chunk_reassembly: true,
         }
     }
 }
@@ -109,6 +156,22 @@ pub struct AgentLimits {
     pub preferred_chunk_size: usize,
     pub max_memory: Option<usize>,
     pub max_processing_time_ms: Option<u64>,
+    /// Largest single protocol message this agent accepts, in bytes.
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+}
+
+fn default_max_message_size() -> usize {
+    crate::MAX_MESSAGE_SIZE
+}
+
+impl AgentLimits {
+    /// Set the maximum message size, clamped to
+    /// [`MAX_NEGOTIATED_MESSAGE_SIZE`](crate::MAX_NEGOTIATED_MESSAGE_SIZE).
+    pub fn with_max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size.min(crate::MAX_NEGOTIATED_MESSAGE_SIZE);
+        self
+    }
 }
 
 impl Default for AgentLimits {
@@ -119,6 +182,7 @@ impl Default for AgentLimits {
             preferred_chunk_size: 64 * 1024,
             max_memory: None,
             max_processing_time_ms: Some(5000),
+            max_message_size: crate::MAX_MESSAGE_SIZE,
         }
     }
 }
@@ -153,7 +217,7 @@ pub struct HandshakeRequest {
 impl HandshakeRequest {
     pub fn new(proxy_id: impl Into<String>, proxy_version: impl Into<String>) -> Self {
         Self {
-            supported_versions: vec![super::PROTOCOL_VERSION_2, 1],
+            supported_versions: super::supported_versions(),
             proxy_id: proxy_id.into(),
             proxy_version: proxy_version.into(),
             config: serde_json::Value::Null,
@@ -209,6 +273,21 @@ mod tests {
         assert!(caps.features.streaming_body);
     }
 
+    #[test]
+    fn test_warmup_requests() {
+        let caps = AgentCapabilities::new("waf", "WAF", "1.0.0").with_warmup(
+            WarmupRequest::new("POST", "/login").with_header("content-type", "text/plain"),
+        );
+        let json = serde_json::to_string(&caps).unwrap();
+        let parsed: AgentCapabilities = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.warmup, caps.warmup);
+        assert_eq!(parsed.warmup[0].headers["content-type"], vec!["text/plain"]);
+
+        // Agents without warm-up requests omit the field
+        let caps = AgentCapabilities::new("waf", "WAF", "1.0.0");
+        assert!(!serde_json::to_string(&caps).unwrap().contains("warmup"));
+    }
+
     #[test]
     fn test_handshake() {
         let request = HandshakeRequest::new("proxy-1", "0.2.5");
@@ -227,4 +306,18 @@ mod tests {
         let full = AgentFeatures::full();
         assert!(full.streaming_body);
     }
+
+    #[test]
+    fn test_limits_max_message_size() {
+        let limits = AgentLimits::default();
+        assert_eq!(limits.max_message_size, crate::MAX_MESSAGE_SIZE);
+
+        let limits = AgentLimits::default().with_max_message_size(usize::MAX);
+        assert_eq!(limits.max_message_size, crate::MAX_NEGOTIATED_MESSAGE_SIZE);
+
+        // Older agents omit the field entirely
+        let json = r#"{"max_body_size":1024,"max_concurrency":1,"preferred_chunk_size":512,"max_memory":null,"max_processing_time_ms":null}"#;
+        let parsed: AgentLimits = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.max_message_size, crate::MAX_MESSAGE_SIZE);
+    }
 }
@@ -3,6 +3,7 @@
 //! The v2 client supports bidirectional streaming with connection multiplexing,
 //! allowing multiple concurrent requests over a single connection.
 
+use prost::Message as _;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -14,8 +15,15 @@ use tracing::{debug, info, trace, warn};
 use crate::grpc_v2::{self, agent_service_v2_client::AgentServiceV2Client, ProxyToAgent};
 use crate::headers::iter_flat;
 use crate::v2::pool::CHANNEL_BUFFER_SIZE;
-use crate::v2::{AgentCapabilities, PROTOCOL_VERSION_2};
-use crate::{AgentProtocolError, AgentResponse, Decision, EventType, HeaderOp};
+use crate::v2::protocol_metrics::{DurationRecorder, ProtocolMetrics};
+use crate::v2::{
+    accept_agent_version, adapt_capabilities, supported_versions, AgentCapabilities,
+    StateDeleteRequest, StateGetRequest, StateHandler, StateRequest, StateResponse,
+    StateSetRequest, PROTOCOL_VERSION_2,
+};
+use crate::{
+    AgentProtocolError, AgentResponse, Decision, EventType, HeaderOp, RequestTag, TagCardinality,
+};
 
 /// Cancellation reason for in-flight requests.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -111,6 +119,12 @@ pub struct AgentClientV2 {
     metrics_callback: Option<MetricsCallback>,
     /// Callback for config update requests
     config_update_callback: Option<ConfigUpdateCallback>,
+    /// Handler for session state requests
+    state_handler: Option<Arc<dyn StateHandler>>,
+    /// Maximum encoded gRPC message size in either direction
+    max_message_size: usize,
+    /// Pool metrics that event conversion time is recorded into
+    protocol_metrics: Option<Arc<ProtocolMetrics>>,
 }
 
 impl AgentClientV2 {
@@ -150,9 +164,25 @@ impl AgentClientV2 {
             in_flight: AtomicU64::new(0),
             metrics_callback: None,
             config_update_callback: None,
+            state_handler: None,
+            max_message_size: crate::MAX_MESSAGE_SIZE,
+            protocol_metrics: None,
         })
     }
 
+    /// Set the maximum gRPC message size for this agent.
+    ///
+    /// Clamped to [`MAX_NEGOTIATED_MESSAGE_SIZE`](crate::MAX_NEGOTIATED_MESSAGE_SIZE).
+    /// Must be called before [`connect`](Self::connect).
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.max_message_size = size.min(crate::MAX_NEGOTIATED_MESSAGE_SIZE);
+    }
+
+    /// Get the maximum gRPC message size for this agent.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
     /// Set the metrics callback for receiving agent metrics reports.
     ///
     /// This callback is invoked whenever the agent sends a metrics report
@@ -169,9 +199,33 @@ impl AgentClientV2 {
         self.config_update_callback = Some(callback);
     }
 
+    /// Set the handler for session state requests from the agent.
+    ///
+    /// Without a handler, state requests are answered with an error.
+    pub fn set_state_handler(&mut self, handler: Arc<dyn StateHandler>) {
+        self.state_handler = Some(handler);
+    }
+
+    /// Record the time spent converting events to protobuf into `metrics`.
+    ///
+    /// Protobuf encoding itself happens in the gRPC stream and is not
+    /// included.
+    pub fn set_protocol_metrics(&mut self, metrics: Arc<ProtocolMetrics>) {
+        self.protocol_metrics = Some(metrics);
+    }
+
+    /// Serialization timer, if the client reports into pool metrics.
+    fn serialization_timer(&self, event_type: EventType) -> Option<DurationRecorder<'_>> {
+        self.protocol_metrics
+            .as_deref()
+            .map(|metrics| metrics.time_serialization(event_type))
+    }
+
     /// Connect and perform handshake.
     pub async fn connect(&self) -> Result<(), AgentProtocolError> {
-        let mut client = AgentServiceV2Client::new(self.channel.clone());
+        let mut client = AgentServiceV2Client::new(self.channel.clone())
+            .max_decoding_message_size(self.max_message_size)
+            .max_encoding_message_size(self.max_message_size);
 
         // Create bidirectional stream
         let (tx, rx) = mpsc::channel::<ProxyToAgent>(CHANNEL_BUFFER_SIZE);
@@ -188,7 +242,7 @@ impl AgentClientV2 {
         let handshake = ProxyToAgent {
             message: Some(grpc_v2::proxy_to_agent::Message::Handshake(
                 grpc_v2::HandshakeRequest {
-                    supported_versions: vec![PROTOCOL_VERSION_2, 1],
+                    supported_versions: supported_versions(),
                     proxy_id: "zentinel-proxy".to_string(),
                     proxy_version: env!("CARGO_PKG_VERSION").to_string(),
                     config_json: "{}".to_string(),
@@ -218,17 +272,19 @@ impl AgentClientV2 {
                 )));
             }
 
+            let protocol_version = accept_agent_version(&self.agent_id, resp.protocol_version)?;
             self.protocol_version
-                .store(resp.protocol_version as u64, Ordering::SeqCst);
+                .store(protocol_version as u64, Ordering::SeqCst);
 
             if let Some(caps) = resp.capabilities {
-                let capabilities = convert_capabilities_from_grpc(caps);
+                let capabilities =
+                    adapt_capabilities(protocol_version, convert_capabilities_from_grpc(caps));
                 *self.capabilities.write().await = Some(capabilities);
             }
 
             info!(
                 agent_id = %self.agent_id,
-                protocol_version = resp.protocol_version,
+                protocol_version = protocol_version,
                 "v2 handshake successful"
             );
         } else {
@@ -237,6 +293,10 @@ impl AgentClientV2 {
             ));
         }
 
+        // State responses are sent by the inbound task; a weak sender lets
+        // the stream end on close
+        let state_tx = tx.downgrade();
+
         // Store outbound sender
         *self.outbound_tx.lock().await = Some(tx);
         *self.connected.write().await = true;
@@ -253,6 +313,7 @@ impl AgentClientV2 {
         let health_state_clone = Arc::clone(&health_state);
         let metrics_callback = self.metrics_callback.clone();
         let config_update_callback = self.config_update_callback.clone();
+        let state_handler = self.state_handler.clone();
 
         tokio::spawn(async move {
             while let Ok(Some(msg)) = inbound.message().await {
@@ -327,6 +388,41 @@ impl AgentClientV2 {
                             // For now, the callback handles the request and logs/processes it
                         }
                     }
+                    Some(grpc_v2::agent_to_proxy::Message::StateGet(get)) => {
+                        spawn_state_request(
+                            state_handler.clone(),
+                            agent_id.clone(),
+                            StateRequest::Get(StateGetRequest {
+                                request_id: get.request_id,
+                                key: get.key,
+                            }),
+                            state_tx.clone(),
+                        );
+                    }
+                    Some(grpc_v2::agent_to_proxy::Message::StateSet(set)) => {
+                        spawn_state_request(
+                            state_handler.clone(),
+                            agent_id.clone(),
+                            StateRequest::Set(StateSetRequest {
+                                request_id: set.request_id,
+                                key: set.key,
+                                value: set.value,
+                                ttl_secs: set.ttl_secs,
+                            }),
+                            state_tx.clone(),
+                        );
+                    }
+                    Some(grpc_v2::agent_to_proxy::Message::StateDelete(delete)) => {
+                        spawn_state_request(
+                            state_handler.clone(),
+                            agent_id.clone(),
+                            StateRequest::Delete(StateDeleteRequest {
+                                request_id: delete.request_id,
+                                key: delete.key,
+                            }),
+                            state_tx.clone(),
+                        );
+                    }
                     Some(grpc_v2::agent_to_proxy::Message::Log(log_msg)) => {
                         // Handle log messages from agent
                         match log_msg.level {
@@ -359,10 +455,13 @@ impl AgentClientV2 {
         correlation_id: &str,
         event: &crate::RequestHeadersEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        let msg = ProxyToAgent {
-            message: Some(grpc_v2::proxy_to_agent::Message::RequestHeaders(
-                convert_request_headers_to_grpc(event),
-            )),
+        let msg = {
+            let _timer = self.serialization_timer(EventType::RequestHeaders);
+            ProxyToAgent {
+                message: Some(grpc_v2::proxy_to_agent::Message::RequestHeaders(
+                    convert_request_headers_to_grpc(event),
+                )),
+            }
         };
 
         self.send_and_wait(correlation_id, msg).await
@@ -377,13 +476,38 @@ impl AgentClientV2 {
         correlation_id: &str,
         event: &crate::RequestBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        let msg = ProxyToAgent {
-            message: Some(grpc_v2::proxy_to_agent::Message::RequestBodyChunk(
-                convert_body_chunk_to_grpc(event),
-            )),
+        let chunk = {
+            let _timer = self.serialization_timer(EventType::RequestBodyChunk);
+            convert_body_chunk_to_grpc(event)
         };
 
-        self.send_and_wait(correlation_id, msg).await
+        self.send_body_chunk(
+            correlation_id,
+            chunk,
+            grpc_v2::proxy_to_agent::Message::RequestBodyChunk,
+        )
+        .await
+    }
+
+    /// Send a binary request body chunk event and wait for response.
+    ///
+    /// Protobuf carries bytes natively, so this skips the base64 round-trip
+    /// of [`send_request_body_chunk`](Self::send_request_body_chunk).
+    pub async fn send_request_body_chunk_binary(
+        &self,
+        event: &crate::BinaryRequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let chunk = {
+            let _timer = self.serialization_timer(EventType::RequestBodyChunk);
+            convert_binary_body_chunk_to_grpc(event)
+        };
+
+        self.send_body_chunk(
+            &event.correlation_id,
+            chunk,
+            grpc_v2::proxy_to_agent::Message::RequestBodyChunk,
+        )
+        .await
     }
 
     /// Send a response headers event and wait for response.
@@ -395,10 +519,13 @@ impl AgentClientV2 {
         correlation_id: &str,
         event: &crate::ResponseHeadersEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        let msg = ProxyToAgent {
-            message: Some(grpc_v2::proxy_to_agent::Message::ResponseHeaders(
-                convert_response_headers_to_grpc(event),
-            )),
+        let msg = {
+            let _timer = self.serialization_timer(EventType::ResponseHeaders);
+            ProxyToAgent {
+                message: Some(grpc_v2::proxy_to_agent::Message::ResponseHeaders(
+                    convert_response_headers_to_grpc(event),
+                )),
+            }
         };
 
         self.send_and_wait(correlation_id, msg).await
@@ -413,13 +540,35 @@ impl AgentClientV2 {
         correlation_id: &str,
         event: &crate::ResponseBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        let msg = ProxyToAgent {
-            message: Some(grpc_v2::proxy_to_agent::Message::ResponseBodyChunk(
-                convert_response_body_chunk_to_grpc(event),
-            )),
+        let chunk = {
+            let _timer = self.serialization_timer(EventType::ResponseBodyChunk);
+            convert_response_body_chunk_to_grpc(event)
         };
 
-        self.send_and_wait(correlation_id, msg).await
+        self.send_body_chunk(
+            correlation_id,
+            chunk,
+            grpc_v2::proxy_to_agent::Message::ResponseBodyChunk,
+        )
+        .await
+    }
+
+    /// Send a binary response body chunk event and wait for response.
+    pub async fn send_response_body_chunk_binary(
+        &self,
+        event: &crate::BinaryResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let chunk = {
+            let _timer = self.serialization_timer(EventType::ResponseBodyChunk);
+            convert_binary_response_body_chunk_to_grpc(event)
+        };
+
+        self.send_body_chunk(
+            &event.correlation_id,
+            chunk,
+            grpc_v2::proxy_to_agent::Message::ResponseBodyChunk,
+        )
+        .await
     }
 
     /// Send any event type and wait for response.
@@ -459,6 +608,85 @@ impl AgentClientV2 {
         self.send_and_wait(&correlation_id, msg).await
     }
 
+    /// Send a body chunk and wait for response, splitting it into fragment
+    /// messages if it exceeds the message size limit.
+    ///
+    /// Leading fragments are sent without waiting; the agent buffers them and
+    /// answers once the final part arrives. Splitting requires the agent to
+    /// advertise `chunk_reassembly`.
+    async fn send_body_chunk(
+        &self,
+        correlation_id: &str,
+        mut chunk: grpc_v2::BodyChunkEvent,
+        wrap: fn(grpc_v2::BodyChunkEvent) -> grpc_v2::proxy_to_agent::Message,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let (max, chunk_reassembly) = match self.capabilities.read().await.as_ref() {
+            Some(caps) => (
+                self.max_message_size.min(caps.limits.max_message_size),
+                caps.features.chunk_reassembly,
+            ),
+            None => (self.max_message_size, false),
+        };
+        // Size of the ProxyToAgent message: oneof tag, length and the event
+        let len = chunk.encoded_len();
+        let size = 1 + prost::length_delimiter_len(len) + len;
+        if size <= max {
+            let msg = ProxyToAgent {
+                message: Some(wrap(chunk)),
+            };
+            return self.send_and_wait(correlation_id, msg).await;
+        }
+        if !chunk_reassembly {
+            return Err(AgentProtocolError::MessageTooLarge { size, max });
+        }
+
+        let data = std::mem::take(&mut chunk.data);
+        // Pieces are a multiple of 3 bytes so agents can reassemble base64
+        // encodings of each fragment by concatenation.
+        let piece_len = max.saturating_sub(chunk.encoded_len() + FRAGMENT_HEADROOM) / 3 * 3;
+        if piece_len == 0 {
+            return Err(AgentProtocolError::MessageTooLarge { size, max });
+        }
+        let mut pieces = data.chunks(piece_len);
+        let last = pieces.next_back().unwrap_or_default().to_vec();
+
+        {
+            let outbound = self.outbound_tx.lock().await;
+            let Some(sender) = outbound.as_ref() else {
+                return Err(AgentProtocolError::ConnectionFailed(
+                    "Not connected".to_string(),
+                ));
+            };
+            for piece in pieces {
+                let fragment = grpc_v2::BodyChunkEvent {
+                    data: piece.to_vec(),
+                    fragment: true,
+                    ..chunk.clone()
+                };
+                sender
+                    .send(ProxyToAgent {
+                        message: Some(wrap(fragment)),
+                    })
+                    .await
+                    .map_err(|e| {
+                        AgentProtocolError::ConnectionFailed(format!("Send failed: {}", e))
+                    })?;
+            }
+        }
+        trace!(
+            correlation_id = %correlation_id,
+            chunk_index = chunk.chunk_index,
+            size = size,
+            "Split body chunk into fragments"
+        );
+
+        chunk.data = last;
+        let msg = ProxyToAgent {
+            message: Some(wrap(chunk)),
+        };
+        self.send_and_wait(correlation_id, msg).await
+    }
+
     /// Send a message and wait for response.
     async fn send_and_wait(
         &self,
@@ -563,6 +791,11 @@ impl AgentClientV2 {
         // Remove from pending (will cause the waiter to receive an error)
         self.pending.lock().await.remove(correlation_id);
 
+        // v1 agents have no cancel message
+        if self.protocol_version() < PROTOCOL_VERSION_2 {
+            return Ok(());
+        }
+
         // Send cancel message to agent
         let msg = ProxyToAgent {
             message: Some(grpc_v2::proxy_to_agent::Message::Cancel(
@@ -775,7 +1008,7 @@ pub enum DrainReason {
 // =============================================================================
 
 fn convert_capabilities_from_grpc(caps: grpc_v2::AgentCapabilities) -> AgentCapabilities {
-    use crate::v2::{AgentFeatures, AgentLimits, HealthConfig};
+    use crate::v2::{AgentFeatures, AgentLimits, HealthConfig, WarmupRequest};
 
     let features = caps
         .features
@@ -789,6 +1022,7 @@ fn convert_capabilities_from_grpc(caps: grpc_v2::AgentCapabilities) -> AgentCapa
             cancellation: f.cancellation,
             flow_control: f.flow_control,
             health_reporting: f.health_reporting,
+            chunk_reassembly: f.chunk_reassembly,
         })
         .unwrap_or_default();
 
@@ -800,6 +1034,10 @@ fn convert_capabilities_from_grpc(caps: grpc_v2::AgentCapabilities) -> AgentCapa
             preferred_chunk_size: l.preferred_chunk_size as usize,
             max_memory: l.max_memory.map(|m| m as usize),
             max_processing_time_ms: l.max_processing_time_ms,
+            max_message_size: l
+                .max_message_size
+                .map(|m| (m as usize).min(crate::MAX_NEGOTIATED_MESSAGE_SIZE))
+                .unwrap_or(crate::MAX_MESSAGE_SIZE),
         })
         .unwrap_or_default();
 
@@ -825,6 +1063,18 @@ fn convert_capabilities_from_grpc(caps: grpc_v2::AgentCapabilities) -> AgentCapa
         features,
         limits,
         health,
+        warmup: caps
+            .warmup
+            .into_iter()
+            .map(|w| WarmupRequest {
+                method: w.method,
+                uri: w.uri,
+                headers: w.headers.into_iter().fold(HashMap::new(), |mut map, h| {
+                    map.entry(h.name).or_insert_with(Vec::new).push(h.value);
+                    map
+                }),
+            })
+            .collect(),
     }
 }
 
@@ -857,6 +1107,39 @@ fn convert_request_headers_to_grpc(
         upstream_id: event.metadata.upstream_id.clone(),
         timestamp_ms: now_ms(),
         traceparent: event.metadata.traceparent.clone(),
+        client_cert: event
+            .metadata
+            .client_cert
+            .as_ref()
+            .map(|c| grpc_v2::ClientCertificate {
+                subject: c.subject.clone(),
+                sans: c.sans.clone(),
+                fingerprint_sha256: c.fingerprint_sha256.clone(),
+                serial_number: c.serial_number.clone(),
+                verified: c.verified,
+            }),
+        tags: event
+            .metadata
+            .tags
+            .iter()
+            .map(|t| grpc_v2::RequestTag {
+                name: t.name.clone(),
+                value: t.value.clone(),
+                low_cardinality: t.cardinality == TagCardinality::Low,
+            })
+            .collect(),
+        bot_signals: event
+            .metadata
+            .bot_signals
+            .as_ref()
+            .map(|s| grpc_v2::BotSignals {
+                ja3: s.ja3.clone(),
+                ja3_hash: s.ja3_hash.clone(),
+                ja4: s.ja4.clone(),
+                http2: s.http2.clone(),
+                header_order_hash: s.header_order_hash.clone(),
+            }),
+        dry_run: event.metadata.dry_run,
     });
 
     // Use iter_flat helper for cleaner iteration over flattened headers
@@ -876,6 +1159,10 @@ fn convert_request_headers_to_grpc(
     }
 }
 
+/// Room left in each fragment for the data field's tag and length prefix and
+/// the ProxyToAgent wrapper.
+const FRAGMENT_HEADROOM: usize = 64;
+
 fn convert_body_chunk_to_grpc(event: &crate::RequestBodyChunkEvent) -> grpc_v2::BodyChunkEvent {
     // Convert through binary type to centralize the base64 decode logic
     let binary: crate::BinaryRequestBodyChunkEvent = event.into();
@@ -897,6 +1184,13 @@ fn convert_binary_body_chunk_to_grpc(
         bytes_transferred: event.bytes_received as u64,
         proxy_buffer_available: 0, // Will be set by flow control
         timestamp_ms: now_ms(),
+        part: event.part.as_ref().map(|part| grpc_v2::BodyPart {
+            index: part.index,
+            name: part.name.clone(),
+            filename: part.filename.clone(),
+            content_type: part.content_type.clone(),
+        }),
+        fragment: false,
     }
 }
 
@@ -941,6 +1235,8 @@ fn convert_binary_response_body_chunk_to_grpc(
         bytes_transferred: event.bytes_sent as u64,
         proxy_buffer_available: 0,
         timestamp_ms: now_ms(),
+        part: None,
+        fragment: false,
     }
 }
 
@@ -979,6 +1275,20 @@ fn convert_response_from_grpc(resp: grpc_v2::AgentResponse) -> AgentResponse {
         .filter_map(convert_header_op_from_grpc)
         .collect();
 
+    let tags: Vec<RequestTag> = resp
+        .tags
+        .into_iter()
+        .map(|t| RequestTag {
+            name: t.name,
+            value: t.value,
+            cardinality: if t.low_cardinality {
+                TagCardinality::Low
+            } else {
+                TagCardinality::High
+            },
+        })
+        .collect();
+
     let audit = resp
         .audit
         .map(|a| crate::AuditMetadata {
@@ -1000,6 +1310,7 @@ fn convert_response_from_grpc(resp: grpc_v2::AgentResponse) -> AgentResponse {
         request_headers,
         response_headers,
         routing_metadata: HashMap::new(),
+        tags,
         audit,
         needs_more: resp.needs_more,
         request_body_mutation: None,
@@ -1081,6 +1392,35 @@ fn convert_metrics_from_grpc(
     }
 }
 
+/// Answer a session state request from the agent without blocking the
+/// inbound stream.
+fn spawn_state_request(
+    handler: Option<Arc<dyn StateHandler>>,
+    agent_id: String,
+    request: StateRequest,
+    tx: mpsc::WeakSender<ProxyToAgent>,
+) {
+    tokio::spawn(async move {
+        let response = match handler {
+            Some(handler) => handler.handle(&agent_id, request).await,
+            None => StateResponse::error(request.request_id(), "session store not configured"),
+        };
+        let msg = ProxyToAgent {
+            message: Some(grpc_v2::proxy_to_agent::Message::StateResponse(
+                grpc_v2::StateResponse {
+                    request_id: response.request_id,
+                    success: response.success,
+                    value: response.value,
+                    error: response.error,
+                },
+            )),
+        };
+        if let Some(tx) = tx.upgrade() {
+            let _ = tx.send(msg).await;
+        }
+    });
+}
+
 fn convert_config_update_from_grpc(
     update: grpc_v2::ConfigUpdateRequest,
 ) -> crate::v2::ConfigUpdateRequest {
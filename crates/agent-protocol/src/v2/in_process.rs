@@ -0,0 +1,365 @@
+//! In-process agent transport for Protocol v2.
+//!
+//! Applications embedding the proxy can run an [`AgentHandlerV2`] in the same
+//! process instead of behind a socket. Events are handed to the handler as
+//! direct method calls: nothing is serialized and no connection can fail, so
+//! the pool never has to reconnect.
+//!
+//! Handler calls run on a runtime of their own rather than on the caller's
+//! task. Slow or blocking policy code does not stall the proxy's request
+//! threads, a call that times out is abandoned, and a panicking handler fails
+//! the call instead of the request.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use zentinel_agent_protocol::v2::AgentPool;
+//!
+//! let pool = AgentPool::new();
+//! pool.add_in_process_agent("waf", Arc::new(MyWafHandler)).await?;
+//! ```
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::v2::server::AgentHandlerV2;
+use crate::v2::{
+    accept_agent_version, adapt_capabilities, AgentCapabilities, CancelReason, HandshakeRequest,
+};
+use crate::{
+    AgentProtocolError, AgentResponse, BinaryRequestBodyChunkEvent, BinaryResponseBodyChunkEvent,
+    RequestBodyChunkEvent, RequestHeadersEvent, ResponseBodyChunkEvent, ResponseHeadersEvent,
+};
+
+/// Endpoint prefix identifying in-process agents in the pool
+pub const IN_PROCESS_ENDPOINT_PREFIX: &str = "in-process:";
+
+/// Runtime shared by the handlers of all in-process agents
+static HANDLER_RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .thread_name("zentinel-agent")
+        .enable_all()
+        .build()
+        .expect("Failed to build in-process agent runtime")
+});
+
+/// Client calling an agent handler in the same process.
+pub struct InProcessClient {
+    agent_id: String,
+    handler: Arc<dyn AgentHandlerV2>,
+    capabilities: AgentCapabilities,
+    timeout: Duration,
+    connected: AtomicBool,
+}
+
+/// Handler call that is aborted when nobody waits for it anymore
+struct HandlerTask(JoinHandle<AgentResponse>);
+
+impl Drop for HandlerTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl InProcessClient {
+    /// Perform the handshake with `handler`; later calls fail after `timeout`.
+    pub async fn connect(
+        agent_id: impl Into<String>,
+        handler: Arc<dyn AgentHandlerV2>,
+        timeout: Duration,
+    ) -> Result<Self, AgentProtocolError> {
+        let agent_id = agent_id.into();
+        let response = handler
+            .on_handshake(HandshakeRequest::new("zentinel", env!("CARGO_PKG_VERSION")))
+            .await;
+        if !response.success {
+            return Err(AgentProtocolError::ConnectionFailed(format!(
+                "In-process agent {} rejected handshake: {}",
+                agent_id,
+                response.error.unwrap_or_default()
+            )));
+        }
+
+        let protocol_version = accept_agent_version(&agent_id, response.protocol_version)?;
+        debug!(
+            agent_id = %agent_id,
+            protocol_version = protocol_version,
+            "In-process agent connected"
+        );
+
+        Ok(Self {
+            capabilities: adapt_capabilities(protocol_version, response.capabilities),
+            agent_id,
+            handler,
+            timeout,
+            connected: AtomicBool::new(true),
+        })
+    }
+
+    /// Get negotiated capabilities.
+    pub async fn capabilities(&self) -> Option<AgentCapabilities> {
+        Some(self.capabilities.clone())
+    }
+
+    /// Check if connected (until [`close`](Self::close)).
+    pub async fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+
+    /// In-process agents have no flow control; they always accept requests.
+    pub async fn can_accept_requests(&self) -> bool {
+        self.is_connected().await
+    }
+
+    /// Send a request headers event.
+    pub async fn send_request_headers(
+        &self,
+        _correlation_id: &str,
+        event: &RequestHeadersEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let (handler, event) = (Arc::clone(&self.handler), event.clone());
+        self.call(async move { handler.on_request_headers(event).await })
+            .await
+    }
+
+    /// Send a request body chunk event.
+    pub async fn send_request_body_chunk(
+        &self,
+        _correlation_id: &str,
+        event: &RequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let (handler, event) = (Arc::clone(&self.handler), event.clone());
+        self.call(async move { handler.on_request_body_chunk(event).await })
+            .await
+    }
+
+    /// Send a response headers event.
+    pub async fn send_response_headers(
+        &self,
+        _correlation_id: &str,
+        event: &ResponseHeadersEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let (handler, event) = (Arc::clone(&self.handler), event.clone());
+        self.call(async move { handler.on_response_headers(event).await })
+            .await
+    }
+
+    /// Send a response body chunk event.
+    pub async fn send_response_body_chunk(
+        &self,
+        _correlation_id: &str,
+        event: &ResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let (handler, event) = (Arc::clone(&self.handler), event.clone());
+        self.call(async move { handler.on_response_body_chunk(event).await })
+            .await
+    }
+
+    /// Send a binary request body chunk event.
+    ///
+    /// Handlers receive body chunks in their base64 form, as over any other
+    /// transport.
+    pub async fn send_request_body_chunk_binary(
+        &self,
+        event: &BinaryRequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let event: RequestBodyChunkEvent = event.clone().into();
+        let handler = Arc::clone(&self.handler);
+        self.call(async move { handler.on_request_body_chunk(event).await })
+            .await
+    }
+
+    /// Send a binary response body chunk event.
+    pub async fn send_response_body_chunk_binary(
+        &self,
+        event: &BinaryResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let event: ResponseBodyChunkEvent = event.clone().into();
+        let handler = Arc::clone(&self.handler);
+        self.call(async move { handler.on_response_body_chunk(event).await })
+            .await
+    }
+
+    /// Calls are awaited by the caller, so there is nothing to cancel.
+    pub async fn cancel_request(
+        &self,
+        _correlation_id: &str,
+        _reason: CancelReason,
+    ) -> Result<(), AgentProtocolError> {
+        Ok(())
+    }
+
+    /// Calls are awaited by the caller, so there is nothing to cancel.
+    pub async fn cancel_all(&self, _reason: CancelReason) -> Result<usize, AgentProtocolError> {
+        Ok(0)
+    }
+
+    /// Close the client and notify the handler.
+    pub async fn close(&self) -> Result<(), AgentProtocolError> {
+        if self.connected.swap(false, Ordering::AcqRel) {
+            self.handler.on_stream_closed().await;
+        }
+        Ok(())
+    }
+
+    /// Get agent ID.
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Run a handler call on the handler runtime, within the timeout.
+    async fn call(
+        &self,
+        call: impl Future<Output = AgentResponse> + Send + 'static,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        self.ensure_connected()?;
+        let mut task = HandlerTask(HANDLER_RUNTIME.spawn(call));
+        match tokio::time::timeout(self.timeout, &mut task.0).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(e)) => {
+                warn!(agent_id = %self.agent_id, error = %e, "In-process agent handler failed");
+                Err(AgentProtocolError::ConnectionFailed(format!(
+                    "In-process agent {} handler failed: {}",
+                    self.agent_id, e
+                )))
+            }
+            Err(_) => Err(AgentProtocolError::Timeout(self.timeout)),
+        }
+    }
+
+    fn ensure_connected(&self) -> Result<(), AgentProtocolError> {
+        if self.connected.load(Ordering::Acquire) {
+            Ok(())
+        } else {
+            Err(AgentProtocolError::ConnectionClosed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::HandshakeResponse;
+    use crate::{Decision, EventType, RequestMetadata};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+
+    struct BlockAdmin;
+
+    #[async_trait]
+    impl AgentHandlerV2 for BlockAdmin {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::new("block-admin", "Block Admin", "1.0.0")
+                .with_event(EventType::RequestHeaders)
+        }
+
+        async fn on_request_headers(&self, event: RequestHeadersEvent) -> AgentResponse {
+            if event.uri == "/slow" {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            if event.uri == "/panic" {
+                panic!("handler bug");
+            }
+            if event.uri.starts_with("/admin") {
+                AgentResponse::block(403, None)
+            } else {
+                AgentResponse::default_allow()
+            }
+        }
+    }
+
+    struct Rejecting;
+
+    #[async_trait]
+    impl AgentHandlerV2 for Rejecting {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::new("rejecting", "Rejecting", "1.0.0")
+        }
+
+        async fn on_handshake(&self, _request: HandshakeRequest) -> HandshakeResponse {
+            HandshakeResponse::failure("not today")
+        }
+    }
+
+    fn headers_event(uri: &str) -> RequestHeadersEvent {
+        RequestHeadersEvent {
+            metadata: RequestMetadata {
+                correlation_id: "c1".to_string(),
+                request_id: "r1".to_string(),
+                client_ip: "127.0.0.1".to_string(),
+                client_port: 0,
+                server_name: None,
+                protocol: "HTTP/1.1".to_string(),
+                tls_version: None,
+                tls_cipher: None,
+                route_id: None,
+                upstream_id: None,
+                timestamp: "2026-01-01T00:00:00Z".to_string(),
+                traceparent: None,
+                client_cert: None,
+                tags: Vec::new(),
+                bot_signals: None,
+                dry_run: false,
+            },
+            method: "GET".to_string(),
+            uri: uri.to_string(),
+            headers: HashMap::new(),
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(200);
+
+    #[tokio::test]
+    async fn test_in_process_client_calls_handler() {
+        let client = InProcessClient::connect("block-admin", Arc::new(BlockAdmin), TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(client.capabilities().await.unwrap().agent_id, "block-admin");
+
+        let blocked = client
+            .send_request_headers("c1", &headers_event("/admin/users"))
+            .await
+            .unwrap();
+        assert!(matches!(blocked.decision, Decision::Block { .. }));
+        let allowed = client
+            .send_request_headers("c1", &headers_event("/public"))
+            .await
+            .unwrap();
+        assert!(matches!(allowed.decision, Decision::Allow));
+
+        // Handler misbehaviour fails the call, not the caller
+        assert!(matches!(
+            client
+                .send_request_headers("c1", &headers_event("/slow"))
+                .await,
+            Err(AgentProtocolError::Timeout(_))
+        ));
+        assert!(matches!(
+            client
+                .send_request_headers("c1", &headers_event("/panic"))
+                .await,
+            Err(AgentProtocolError::ConnectionFailed(_))
+        ));
+
+        client.close().await.unwrap();
+        assert!(!client.is_connected().await);
+        assert!(matches!(
+            client
+                .send_request_headers("c1", &headers_event("/public"))
+                .await,
+            Err(AgentProtocolError::ConnectionClosed)
+        ));
+
+        assert!(
+            InProcessClient::connect("rejecting", Arc::new(Rejecting), TIMEOUT)
+                .await
+                .is_err()
+        );
+    }
+}
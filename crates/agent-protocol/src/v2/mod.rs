@@ -5,27 +5,37 @@
 //! - Health reporting
 //! - Flow control
 //! - Metrics export
+//! - Session state shared with the proxy
 //! - Bidirectional streaming
 //! - v2 server and client implementations
+//! - Version negotiation with a v1 fallback
 
 mod capabilities;
 pub mod client;
 mod control;
 mod health;
+pub mod in_process;
 mod metrics;
 pub mod observability;
 pub mod pool;
 pub mod protocol_metrics;
+#[cfg(feature = "quantile-sketch")]
+pub mod quantile_sketch;
 pub mod reverse;
 pub mod server;
+#[cfg(feature = "mmap-buffers")]
+pub mod shm;
+mod state;
 mod streaming;
 pub mod uds;
 pub mod uds_server;
+mod version;
 
 pub use capabilities::*;
 pub use client::{AgentClientV2, CancelReason, ConfigUpdateCallback, FlowState, MetricsCallback};
 pub use control::*;
 pub use health::*;
+pub use in_process::InProcessClient;
 pub use metrics::*;
 pub use observability::{
     AgentConnection, ConfigPusher, ConfigPusherConfig, ConfigUpdateHandler, MetricsCollector,
@@ -33,8 +43,11 @@ pub use observability::{
 };
 pub use pool::{AgentPool, AgentPoolConfig, AgentPoolStats, LoadBalanceStrategy, V2Transport};
 pub use protocol_metrics::{
-    HistogramMetric, HistogramSnapshot, ProtocolMetrics, ProtocolMetricsSnapshot,
+    DurationRecorder, EventTypeMetrics, EventTypeMetricsSnapshot, HistogramMetric,
+    HistogramSnapshot, ProtocolMetrics, ProtocolMetricsSnapshot, TRACKED_EVENT_TYPES,
 };
+#[cfg(feature = "quantile-sketch")]
+pub use quantile_sketch::{QuantileSketch, SketchSnapshot};
 pub use reverse::{
     RegistrationRequest, RegistrationResponse, ReverseConnectionClient, ReverseConnectionConfig,
     ReverseConnectionListener,
@@ -42,19 +55,24 @@ pub use reverse::{
 pub use server::{
     AgentHandlerV2, DrainReason, GrpcAgentHandlerV2, GrpcAgentServerV2, ShutdownReason,
 };
+#[cfg(feature = "mmap-buffers")]
+pub use shm::{ShmConfig, ShmRing, ShmSegment};
+pub use state::*;
 pub use streaming::*;
 pub use uds::{
-    AgentClientV2Uds, MessageType, UdsCapabilities, UdsEncoding, UdsFeatures, UdsHandshakeRequest,
-    UdsHandshakeResponse, UdsLimits, MAX_UDS_MESSAGE_SIZE,
+    AgentClientV2Uds, ChunkReassembler, MessageType, UdsCapabilities, UdsEncoding, UdsFeatures,
+    UdsHandshakeRequest, UdsHandshakeResponse, UdsLimits, UdsShmBodyChunk, UdsShmDescriptor,
+    UdsShmOffer, MAX_UDS_MESSAGE_SIZE,
 };
 pub use uds_server::UdsAgentServerV2;
+pub use version::*;
 
 /// Protocol version 2
 pub const PROTOCOL_VERSION_2: u32 = 2;
 
 /// Check if a version is supported by v2.
 pub fn supports_version(version: u32) -> bool {
-    version <= PROTOCOL_VERSION_2
+    (MIN_PROTOCOL_VERSION..=MAX_PROTOCOL_VERSION).contains(&version)
 }
 
 #[cfg(test)]
@@ -65,6 +83,7 @@ mod tests {
     fn test_version_support() {
         assert!(supports_version(1));
         assert!(supports_version(2));
+        assert!(!supports_version(0));
         assert!(!supports_version(3));
     }
 }
@@ -13,6 +13,7 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 use tracing::warn;
+use zentinel_common::observability::{metric_labels, MetricsSource, MetricsWriter};
 
 /// Metrics collector that receives and aggregates metrics from agents.
 #[derive(Debug)]
@@ -168,7 +169,7 @@ impl MetricsCollector {
         for counter in &report.counters {
             let mut labels = counter.labels.clone();
             if self.config.include_agent_id_label {
-                labels.insert("agent_id".to_string(), report.agent_id.clone());
+                labels.insert(metric_labels::AGENT_ID.to_string(), report.agent_id.clone());
             }
 
             let key = MetricKey::new(&report.agent_id, &counter.name, &labels);
@@ -198,7 +199,7 @@ impl MetricsCollector {
         for gauge in &report.gauges {
             let mut labels = gauge.labels.clone();
             if self.config.include_agent_id_label {
-                labels.insert("agent_id".to_string(), report.agent_id.clone());
+                labels.insert(metric_labels::AGENT_ID.to_string(), report.agent_id.clone());
             }
 
             let key = MetricKey::new(&report.agent_id, &gauge.name, &labels);
@@ -228,7 +229,7 @@ impl MetricsCollector {
         for histogram in &report.histograms {
             let mut labels = histogram.labels.clone();
             if self.config.include_agent_id_label {
-                labels.insert("agent_id".to_string(), report.agent_id.clone());
+                labels.insert(metric_labels::AGENT_ID.to_string(), report.agent_id.clone());
             }
 
             let key = MetricKey::new(&report.agent_id, &histogram.name, &labels);
@@ -291,97 +292,9 @@ impl MetricsCollector {
 
     /// Export metrics in Prometheus text format.
     pub fn export_prometheus(&self) -> String {
-        let mut output = String::new();
-
-        // Export counters
-        let counters = self.counters.read();
-        let mut counter_names: Vec<_> = counters.values().map(|c| &c.name).collect();
-        counter_names.sort();
-        counter_names.dedup();
-
-        for name in counter_names {
-            let metrics: Vec<_> = counters.values().filter(|c| &c.name == name).collect();
-            if let Some(first) = metrics.first() {
-                if let Some(help) = &first.help {
-                    output.push_str(&format!("# HELP {} {}\n", name, help));
-                }
-                output.push_str(&format!("# TYPE {} counter\n", name));
-            }
-            for metric in metrics {
-                output.push_str(&format_metric_line(
-                    name,
-                    &metric.labels,
-                    metric.value as f64,
-                ));
-            }
-        }
-
-        // Export gauges
-        let gauges = self.gauges.read();
-        let mut gauge_names: Vec<_> = gauges.values().map(|g| &g.name).collect();
-        gauge_names.sort();
-        gauge_names.dedup();
-
-        for name in gauge_names {
-            let metrics: Vec<_> = gauges.values().filter(|g| &g.name == name).collect();
-            if let Some(first) = metrics.first() {
-                if let Some(help) = &first.help {
-                    output.push_str(&format!("# HELP {} {}\n", name, help));
-                }
-                output.push_str(&format!("# TYPE {} gauge\n", name));
-            }
-            for metric in metrics {
-                output.push_str(&format_metric_line(name, &metric.labels, metric.value));
-            }
-        }
-
-        // Export histograms
-        let histograms = self.histograms.read();
-        let mut histogram_names: Vec<_> = histograms.values().map(|h| &h.name).collect();
-        histogram_names.sort();
-        histogram_names.dedup();
-
-        for name in histogram_names {
-            let metrics: Vec<_> = histograms.values().filter(|h| &h.name == name).collect();
-            if let Some(first) = metrics.first() {
-                if let Some(help) = &first.help {
-                    output.push_str(&format!("# HELP {} {}\n", name, help));
-                }
-                output.push_str(&format!("# TYPE {} histogram\n", name));
-            }
-            for metric in metrics {
-                // Buckets
-                for bucket in &metric.buckets {
-                    let mut labels = metric.labels.clone();
-                    labels.insert(
-                        "le".to_string(),
-                        if bucket.le.is_infinite() {
-                            "+Inf".to_string()
-                        } else {
-                            bucket.le.to_string()
-                        },
-                    );
-                    output.push_str(&format_metric_line(
-                        &format!("{}_bucket", name),
-                        &labels,
-                        bucket.count as f64,
-                    ));
-                }
-                // Sum and count
-                output.push_str(&format_metric_line(
-                    &format!("{}_sum", name),
-                    &metric.labels,
-                    metric.sum,
-                ));
-                output.push_str(&format_metric_line(
-                    &format!("{}_count", name),
-                    &metric.labels,
-                    metric.count as f64,
-                ));
-            }
-        }
-
-        output
+        let mut writer = MetricsWriter::new();
+        self.collect(&mut writer);
+        writer.render()
     }
 
     /// Get a snapshot of all metrics.
@@ -401,6 +314,44 @@ impl Default for MetricsCollector {
     }
 }
 
+impl MetricsSource for MetricsCollector {
+    fn collect(&self, writer: &mut MetricsWriter) {
+        for counter in self.counters.read().values() {
+            writer.counter(
+                &counter.name,
+                counter.help.as_deref().unwrap_or_default(),
+                &sorted_labels(&counter.labels),
+                counter.value as f64,
+            );
+        }
+        for gauge in self.gauges.read().values() {
+            writer.gauge(
+                &gauge.name,
+                gauge.help.as_deref().unwrap_or_default(),
+                &sorted_labels(&gauge.labels),
+                gauge.value,
+            );
+        }
+        for histogram in self.histograms.read().values() {
+            // The +Inf bucket is derived from the count
+            let buckets: Vec<(f64, u64)> = histogram
+                .buckets
+                .iter()
+                .filter(|bucket| bucket.le.is_finite())
+                .map(|bucket| (bucket.le, bucket.count))
+                .collect();
+            writer.histogram(
+                &histogram.name,
+                histogram.help.as_deref().unwrap_or_default(),
+                &sorted_labels(&histogram.labels),
+                &buckets,
+                histogram.sum,
+                histogram.count,
+            );
+        }
+    }
+}
+
 /// Snapshot of all metrics at a point in time.
 #[derive(Debug)]
 pub struct MetricsSnapshot {
@@ -602,105 +553,59 @@ impl UnifiedMetricsAggregator {
 
     /// Export all metrics in Prometheus text format.
     pub fn export_prometheus(&self) -> String {
-        let mut output = String::new();
+        let mut writer = MetricsWriter::new();
+        self.collect_proxy(&mut writer);
+        let mut output = writer.render();
+
+        output.push_str("\n# Agent metrics\n");
+        output.push_str(&self.agent_collector.export_prometheus());
+        output
+    }
 
-        // Add service info metric
-        output.push_str(
-            "# HELP zentinel_info Zentinel proxy information\n# TYPE zentinel_info gauge\n",
+    /// Write the service info and proxy metrics, without agent metrics.
+    fn collect_proxy(&self, writer: &mut MetricsWriter) {
+        writer.gauge(
+            "zentinel_info",
+            "Zentinel proxy information",
+            &[
+                ("service", self.service_name.as_str()),
+                ("instance", self.instance_id.as_str()),
+            ],
+            1.0,
         );
-        output.push_str(&format!(
-            "zentinel_info{{service=\"{}\",instance=\"{}\"}} 1\n",
-            escape_label_value(&self.service_name),
-            escape_label_value(&self.instance_id)
-        ));
-
-        // Export proxy counters
-        let counters = self.proxy_counters.read();
-        let mut counter_names: Vec<_> = counters.values().map(|c| &c.name).collect();
-        counter_names.sort();
-        counter_names.dedup();
-
-        for name in counter_names {
-            let metrics: Vec<_> = counters.values().filter(|c| &c.name == name).collect();
-            if let Some(first) = metrics.first() {
-                output.push_str(&format!("# HELP {} {}\n", name, first.help));
-                output.push_str(&format!("# TYPE {} counter\n", name));
-            }
-            for metric in metrics {
-                output.push_str(&format_metric_line(
-                    name,
-                    &metric.labels,
-                    metric.value as f64,
-                ));
-            }
-        }
 
-        // Export proxy gauges
-        let gauges = self.proxy_gauges.read();
-        let mut gauge_names: Vec<_> = gauges.values().map(|g| &g.name).collect();
-        gauge_names.sort();
-        gauge_names.dedup();
-
-        for name in gauge_names {
-            let metrics: Vec<_> = gauges.values().filter(|g| &g.name == name).collect();
-            if let Some(first) = metrics.first() {
-                output.push_str(&format!("# HELP {} {}\n", name, first.help));
-                output.push_str(&format!("# TYPE {} gauge\n", name));
-            }
-            for metric in metrics {
-                output.push_str(&format_metric_line(name, &metric.labels, metric.value));
-            }
+        for counter in self.proxy_counters.read().values() {
+            writer.counter(
+                &counter.name,
+                &counter.help,
+                &sorted_labels(&counter.labels),
+                counter.value as f64,
+            );
         }
-
-        // Export proxy histograms
-        let histograms = self.proxy_histograms.read();
-        let mut histogram_names: Vec<_> = histograms.values().map(|h| &h.name).collect();
-        histogram_names.sort();
-        histogram_names.dedup();
-
-        for name in histogram_names {
-            let metrics: Vec<_> = histograms.values().filter(|h| &h.name == name).collect();
-            if let Some(first) = metrics.first() {
-                output.push_str(&format!("# HELP {} {}\n", name, first.help));
-                output.push_str(&format!("# TYPE {} histogram\n", name));
-            }
-            for metric in metrics {
-                // Buckets
-                for (le, count) in &metric.buckets {
-                    let mut labels = metric.labels.clone();
-                    labels.insert(
-                        "le".to_string(),
-                        if le.is_infinite() {
-                            "+Inf".to_string()
-                        } else {
-                            le.to_string()
-                        },
-                    );
-                    output.push_str(&format_metric_line(
-                        &format!("{}_bucket", name),
-                        &labels,
-                        *count as f64,
-                    ));
-                }
-                // Sum and count
-                output.push_str(&format_metric_line(
-                    &format!("{}_sum", name),
-                    &metric.labels,
-                    metric.sum,
-                ));
-                output.push_str(&format_metric_line(
-                    &format!("{}_count", name),
-                    &metric.labels,
-                    metric.count as f64,
-                ));
-            }
+        for gauge in self.proxy_gauges.read().values() {
+            writer.gauge(
+                &gauge.name,
+                &gauge.help,
+                &sorted_labels(&gauge.labels),
+                gauge.value,
+            );
+        }
+        for histogram in self.proxy_histograms.read().values() {
+            let buckets: Vec<(f64, u64)> = histogram
+                .buckets
+                .iter()
+                .filter(|(le, _)| le.is_finite())
+                .copied()
+                .collect();
+            writer.histogram(
+                &histogram.name,
+                &histogram.help,
+                &sorted_labels(&histogram.labels),
+                &buckets,
+                histogram.sum,
+                histogram.count,
+            );
         }
-
-        // Export agent metrics
-        output.push_str("\n# Agent metrics\n");
-        output.push_str(&self.agent_collector.export_prometheus());
-
-        output
     }
 
     /// Get total metric series count.
@@ -729,44 +634,21 @@ impl Default for UnifiedMetricsAggregator {
     }
 }
 
-/// Format a single metric line in Prometheus format.
-fn format_metric_line(name: &str, labels: &HashMap<String, String>, value: f64) -> String {
-    if labels.is_empty() {
-        format!("{} {}\n", name, format_value(value))
-    } else {
-        let mut pairs: Vec<_> = labels.iter().collect();
-        pairs.sort_by_key(|(k, _)| *k);
-        let labels_str = pairs
-            .into_iter()
-            .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
-            .collect::<Vec<_>>()
-            .join(",");
-        format!("{}{{{}}} {}\n", name, labels_str, format_value(value))
-    }
-}
-
-/// Format a value for Prometheus output.
-fn format_value(v: f64) -> String {
-    if v.is_infinite() {
-        if v.is_sign_positive() {
-            "+Inf".to_string()
-        } else {
-            "-Inf".to_string()
-        }
-    } else if v.is_nan() {
-        "NaN".to_string()
-    } else if v.fract() == 0.0 {
-        format!("{}", v as i64)
-    } else {
-        format!("{}", v)
+impl MetricsSource for UnifiedMetricsAggregator {
+    fn collect(&self, writer: &mut MetricsWriter) {
+        self.collect_proxy(writer);
+        self.agent_collector.collect(writer);
     }
 }
 
-/// Escape a label value for Prometheus format.
-fn escape_label_value(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
+/// Labels sorted by name, for stable output.
+fn sorted_labels(labels: &HashMap<String, String>) -> Vec<(&str, &str)> {
+    let mut pairs: Vec<(&str, &str)> = labels
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    pairs.sort_unstable();
+    pairs
 }
 
 /// Handler for config updates from agents.
@@ -1243,6 +1125,7 @@ fn now_ms() -> u64 {
 mod tests {
     use super::*;
     use crate::v2::metrics::{standard, CounterMetric, GaugeMetric, HistogramMetric};
+    use zentinel_common::observability::escape_label_value;
 
     #[test]
     fn test_metrics_collector_basic() {
@@ -1407,6 +1290,21 @@ mod tests {
         assert_eq!(escape_label_value("with\nnewline"), "with\\nnewline");
     }
 
+    #[test]
+    fn test_export_escapes_label_values() {
+        let collector = MetricsCollector::new();
+        let mut report = MetricsReport::new("agent-1", 10_000);
+        let mut counter = CounterMetric::new("escaped_total", 1);
+        counter
+            .labels
+            .insert("path".to_string(), "with\"quotes\\and\nnewline".to_string());
+        report.counters.push(counter);
+        collector.record(&report);
+
+        let output = collector.export_prometheus();
+        assert!(output.contains(r#"path="with\"quotes\\and\nnewline""#));
+    }
+
     #[test]
     fn test_config_pusher_basic() {
         let pusher = ConfigPusher::new();
@@ -1617,6 +1515,17 @@ mod tests {
         assert!(output.contains("Agent metrics"));
     }
 
+    #[test]
+    fn test_unified_aggregator_labels_agent_metrics() {
+        let aggregator = UnifiedMetricsAggregator::new("test", "1");
+        let mut report = MetricsReport::new("waf-agent", 5_000);
+        report.counters.push(CounterMetric::new("waf_blocked", 50));
+        aggregator.record_agent_metrics(&report);
+
+        let output = aggregator.export_prometheus();
+        assert!(output.contains("waf_blocked{agent_id=\"waf-agent\"} 50"));
+    }
+
     #[test]
     fn test_unified_aggregator_service_info() {
         let aggregator = UnifiedMetricsAggregator::new("my-service", "node-42");
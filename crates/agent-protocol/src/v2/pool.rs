@@ -19,14 +19,17 @@ use tracing::{debug, info, trace, warn};
 
 use crate::v2::client::{AgentClientV2, CancelReason, ConfigUpdateCallback, MetricsCallback};
 use crate::v2::control::ConfigUpdateType;
+use crate::v2::in_process::{InProcessClient, IN_PROCESS_ENDPOINT_PREFIX};
 use crate::v2::observability::{ConfigPusher, ConfigUpdateHandler, MetricsCollector};
 use crate::v2::protocol_metrics::ProtocolMetrics;
 use crate::v2::reverse::ReverseConnectionClient;
+use crate::v2::server::AgentHandlerV2;
 use crate::v2::uds::AgentClientV2Uds;
-use crate::v2::AgentCapabilities;
+use crate::v2::{AgentCapabilities, StateHandler};
 use crate::{
-    AgentProtocolError, AgentResponse, GuardrailInspectEvent, RequestBodyChunkEvent,
-    RequestHeadersEvent, ResponseBodyChunkEvent, ResponseHeadersEvent,
+    AgentProtocolError, AgentResponse, BinaryRequestBodyChunkEvent, BinaryResponseBodyChunkEvent,
+    EventType, GuardrailInspectEvent, RequestBodyChunkEvent, RequestHeadersEvent,
+    ResponseBodyChunkEvent, ResponseHeadersEvent,
 };
 
 /// Channel buffer size for all transports.
@@ -171,6 +174,29 @@ pub struct AgentPoolConfig {
     ///
     /// Default: 100_000
     pub max_correlation_affinities: usize,
+    /// Maximum encoded message size offered to agents at handshake.
+    ///
+    /// UDS connections negotiate the smaller of this and the agent's limit;
+    /// body chunks exceeding it are split when the agent supports reassembly.
+    ///
+    /// Default: [`MAX_MESSAGE_SIZE`](crate::MAX_MESSAGE_SIZE) (16MB)
+    pub max_message_size: usize,
+    /// Shared-memory body transport offered to UDS agents.
+    ///
+    /// Each UDS connection gets its own segment; agents that do not map it
+    /// keep receiving body chunks inline.
+    ///
+    /// Default: None (disabled)
+    #[cfg(feature = "mmap-buffers")]
+    pub shared_memory: Option<super::shm::ShmConfig>,
+    /// Bucket boundaries of the request duration histogram in microseconds.
+    ///
+    /// Default: None ([`DEFAULT_BUCKETS_MICROS`](super::protocol_metrics::DEFAULT_BUCKETS_MICROS))
+    pub request_duration_buckets_us: Option<Vec<u64>>,
+    /// Bucket boundaries of the serialization time histogram in microseconds.
+    ///
+    /// Default: None ([`DEFAULT_BUCKETS_MICROS`](super::protocol_metrics::DEFAULT_BUCKETS_MICROS))
+    pub serialization_buckets_us: Option<Vec<u64>>,
 }
 
 impl Default for AgentPoolConfig {
@@ -191,6 +217,28 @@ impl Default for AgentPoolConfig {
             sticky_session_timeout: Some(Duration::from_secs(5 * 60)), // 5 minutes
             correlation_affinity_ttl: Duration::from_secs(5 * 60),
             max_correlation_affinities: 100_000,
+            max_message_size: crate::MAX_MESSAGE_SIZE,
+            #[cfg(feature = "mmap-buffers")]
+            shared_memory: None,
+            request_duration_buckets_us: None,
+            serialization_buckets_us: None,
+        }
+    }
+}
+
+/// Body chunk event borrowed for dispatch through the pool.
+enum BodyChunk<'a> {
+    Request(&'a RequestBodyChunkEvent),
+    RequestBinary(&'a BinaryRequestBodyChunkEvent),
+    Response(&'a ResponseBodyChunkEvent),
+    ResponseBinary(&'a BinaryResponseBodyChunkEvent),
+}
+
+impl BodyChunk<'_> {
+    fn event_type(&self) -> EventType {
+        match self {
+            BodyChunk::Request(_) | BodyChunk::RequestBinary(_) => EventType::RequestBodyChunk,
+            BodyChunk::Response(_) | BodyChunk::ResponseBinary(_) => EventType::ResponseBodyChunk,
         }
     }
 }
@@ -252,7 +300,7 @@ impl StickySession {
 
 /// Transport layer for v2 agent connections.
 ///
-/// Supports gRPC, Unix Domain Socket, and reverse connections.
+/// Supports gRPC, Unix Domain Socket, reverse and in-process connections.
 pub enum V2Transport {
     /// gRPC over HTTP/2
     Grpc(AgentClientV2),
@@ -260,6 +308,8 @@ pub enum V2Transport {
     Uds(AgentClientV2Uds),
     /// Reverse connection (agent connected to proxy)
     Reverse(ReverseConnectionClient),
+    /// Agent handler running in the proxy's process
+    InProcess(InProcessClient),
 }
 
 impl V2Transport {
@@ -269,6 +319,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.is_connected().await,
             V2Transport::Uds(client) => client.is_connected().await,
             V2Transport::Reverse(client) => client.is_connected().await,
+            V2Transport::InProcess(client) => client.is_connected().await,
         }
     }
 
@@ -280,6 +331,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.can_accept_requests().await,
             V2Transport::Uds(client) => client.can_accept_requests().await,
             V2Transport::Reverse(client) => client.can_accept_requests().await,
+            V2Transport::InProcess(client) => client.can_accept_requests().await,
         }
     }
 
@@ -289,6 +341,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.capabilities().await,
             V2Transport::Uds(client) => client.capabilities().await,
             V2Transport::Reverse(client) => client.capabilities().await,
+            V2Transport::InProcess(client) => client.capabilities().await,
         }
     }
 
@@ -304,6 +357,9 @@ impl V2Transport {
             V2Transport::Reverse(client) => {
                 client.send_request_headers(correlation_id, event).await
             }
+            V2Transport::InProcess(client) => {
+                client.send_request_headers(correlation_id, event).await
+            }
         }
     }
 
@@ -321,6 +377,9 @@ impl V2Transport {
             V2Transport::Reverse(client) => {
                 client.send_request_body_chunk(correlation_id, event).await
             }
+            V2Transport::InProcess(client) => {
+                client.send_request_body_chunk(correlation_id, event).await
+            }
         }
     }
 
@@ -336,6 +395,9 @@ impl V2Transport {
             V2Transport::Reverse(client) => {
                 client.send_response_headers(correlation_id, event).await
             }
+            V2Transport::InProcess(client) => {
+                client.send_response_headers(correlation_id, event).await
+            }
         }
     }
 
@@ -355,6 +417,38 @@ impl V2Transport {
             V2Transport::Reverse(client) => {
                 client.send_response_body_chunk(correlation_id, event).await
             }
+            V2Transport::InProcess(client) => {
+                client.send_response_body_chunk(correlation_id, event).await
+            }
+        }
+    }
+
+    /// Send a binary request body chunk event.
+    ///
+    /// gRPC and MessagePack UDS carry the bytes as-is; JSON UDS and reverse
+    /// connections fall back to base64.
+    pub async fn send_request_body_chunk_binary(
+        &self,
+        event: &BinaryRequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        match self {
+            V2Transport::Grpc(client) => client.send_request_body_chunk_binary(event).await,
+            V2Transport::Uds(client) => client.send_request_body_chunk_binary(event).await,
+            V2Transport::Reverse(client) => client.send_request_body_chunk_binary(event).await,
+            V2Transport::InProcess(client) => client.send_request_body_chunk_binary(event).await,
+        }
+    }
+
+    /// Send a binary response body chunk event.
+    pub async fn send_response_body_chunk_binary(
+        &self,
+        event: &BinaryResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        match self {
+            V2Transport::Grpc(client) => client.send_response_body_chunk_binary(event).await,
+            V2Transport::Uds(client) => client.send_response_body_chunk_binary(event).await,
+            V2Transport::Reverse(client) => client.send_response_body_chunk_binary(event).await,
+            V2Transport::InProcess(client) => client.send_response_body_chunk_binary(event).await,
         }
     }
 
@@ -372,6 +466,9 @@ impl V2Transport {
             V2Transport::Reverse(_client) => Err(AgentProtocolError::InvalidMessage(
                 "GuardrailInspect events are not yet supported via reverse connections".to_string(),
             )),
+            V2Transport::InProcess(_client) => Err(AgentProtocolError::InvalidMessage(
+                "GuardrailInspect events are not supported by in-process agents".to_string(),
+            )),
         }
     }
 
@@ -385,6 +482,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.cancel_request(correlation_id, reason).await,
             V2Transport::Uds(client) => client.cancel_request(correlation_id, reason).await,
             V2Transport::Reverse(client) => client.cancel_request(correlation_id, reason).await,
+            V2Transport::InProcess(client) => client.cancel_request(correlation_id, reason).await,
         }
     }
 
@@ -394,6 +492,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.cancel_all(reason).await,
             V2Transport::Uds(client) => client.cancel_all(reason).await,
             V2Transport::Reverse(client) => client.cancel_all(reason).await,
+            V2Transport::InProcess(client) => client.cancel_all(reason).await,
         }
     }
 
@@ -403,6 +502,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.close().await,
             V2Transport::Uds(client) => client.close().await,
             V2Transport::Reverse(client) => client.close().await,
+            V2Transport::InProcess(client) => client.close().await,
         }
     }
 
@@ -412,6 +512,7 @@ impl V2Transport {
             V2Transport::Grpc(client) => client.agent_id(),
             V2Transport::Uds(client) => client.agent_id(),
             V2Transport::Reverse(client) => client.agent_id(),
+            V2Transport::InProcess(client) => client.agent_id(),
         }
     }
 }
@@ -527,6 +628,9 @@ struct AgentEntry {
     last_reconnect_attempt_ms: AtomicU64,
     /// Cached aggregate health - true if any connection is healthy
     healthy: AtomicBool,
+    /// Bumped whenever the agent connects with no healthy connection left,
+    /// i.e. on add and after a restart
+    connect_generation: AtomicU64,
 }
 
 impl AgentEntry {
@@ -540,6 +644,7 @@ impl AgentEntry {
             reconnect_attempts: AtomicUsize::new(0),
             last_reconnect_attempt_ms: AtomicU64::new(0),
             healthy: AtomicBool::new(true),
+            connect_generation: AtomicU64::new(1),
         }
     }
 
@@ -639,6 +744,10 @@ pub struct AgentPool {
     /// Sticky sessions: session_id → session info for long-lived streams.
     /// Used for WebSocket, SSE, and long-polling connections.
     sticky_sessions: DashMap<String, StickySession>,
+    /// Handler for session state requests, handed to new connections
+    state_handler: parking_lot::RwLock<Option<Arc<dyn StateHandler>>>,
+    /// Handlers of in-process agents, by agent ID
+    in_process_handlers: DashMap<String, Arc<dyn AgentHandlerV2>>,
 }
 
 impl AgentPool {
@@ -672,6 +781,11 @@ impl AgentPool {
             handler_clone.handle(request)
         });
 
+        let protocol_metrics = Arc::new(ProtocolMetrics::with_buckets(
+            config.serialization_buckets_us.clone(),
+            config.request_duration_buckets_us.clone(),
+        ));
+
         Self {
             config,
             agents: DashMap::new(),
@@ -682,13 +796,23 @@ impl AgentPool {
             config_pusher,
             config_update_handler,
             config_update_callback,
-            protocol_metrics: Arc::new(ProtocolMetrics::new()),
+            protocol_metrics,
             correlation_affinity: DashMap::new(),
             affinity_at_capacity: AtomicBool::new(false),
             sticky_sessions: DashMap::new(),
+            state_handler: parking_lot::RwLock::new(None),
+            in_process_handlers: DashMap::new(),
         }
     }
 
+    /// Serve session state requests from agents with `handler`.
+    ///
+    /// Applies to connections established afterwards, so call it before
+    /// adding agents.
+    pub fn set_state_handler(&self, handler: Arc<dyn StateHandler>) {
+        *self.state_handler.write() = Some(handler);
+    }
+
     /// Get the protocol metrics for accessing proxy-side instrumentation.
     pub fn protocol_metrics(&self) -> &ProtocolMetrics {
         &self.protocol_metrics
@@ -921,9 +1045,9 @@ impl AgentPool {
         correlation_id: &str,
         event: &RequestHeadersEvent,
     ) -> Result<(AgentResponse, bool), AgentProtocolError> {
-        let start = Instant::now();
         self.total_requests.fetch_add(1, Ordering::Relaxed);
-        self.protocol_metrics.inc_requests();
+        self.protocol_metrics
+            .inc_requests(EventType::RequestHeaders);
         self.protocol_metrics.inc_in_flight();
 
         // Try sticky session first
@@ -933,15 +1057,20 @@ impl AgentPool {
             } else {
                 (self.select_connection(agent_id)?, false)
             };
+        let timer = self
+            .protocol_metrics
+            .time_request(EventType::RequestHeaders);
 
         // Check flow control
         match self.check_flow_control(&conn, agent_id).await {
             Ok(true) => {}
             Ok(false) => {
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Ok((AgentResponse::default_allow(), used_sticky));
             }
             Err(e) => {
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Err(e);
             }
@@ -968,21 +1097,24 @@ impl AgentPool {
         conn.in_flight.fetch_sub(1, Ordering::Relaxed);
         conn.request_count.fetch_add(1, Ordering::Relaxed);
         self.protocol_metrics.dec_in_flight();
-        self.protocol_metrics
-            .record_request_duration(start.elapsed());
+        timer.record();
 
         match &result {
             Ok(_) => {
                 conn.consecutive_errors.store(0, Ordering::Relaxed);
-                self.protocol_metrics.inc_responses();
+                self.protocol_metrics
+                    .inc_responses(EventType::RequestHeaders);
             }
             Err(e) => {
                 conn.error_count.fetch_add(1, Ordering::Relaxed);
                 let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
                 self.total_errors.fetch_add(1, Ordering::Relaxed);
+                self.protocol_metrics.inc_errors(EventType::RequestHeaders);
 
                 match e {
-                    AgentProtocolError::Timeout(_) => self.protocol_metrics.inc_timeouts(),
+                    AgentProtocolError::Timeout(_) => self
+                        .protocol_metrics
+                        .inc_timeouts(EventType::RequestHeaders),
                     AgentProtocolError::ConnectionFailed(_)
                     | AgentProtocolError::ConnectionClosed => {
                         self.protocol_metrics.inc_connection_errors();
@@ -1138,6 +1270,21 @@ impl AgentPool {
         Ok(())
     }
 
+    /// Add an agent whose handler runs in this process.
+    ///
+    /// Events are passed to `handler` directly, without a socket or
+    /// serialization. The agent is otherwise pooled like any other.
+    pub async fn add_in_process_agent(
+        &self,
+        agent_id: impl Into<String>,
+        handler: Arc<dyn AgentHandlerV2>,
+    ) -> Result<(), AgentProtocolError> {
+        let agent_id = agent_id.into();
+        self.in_process_handlers.insert(agent_id.clone(), handler);
+        let endpoint = format!("{}{}", IN_PROCESS_ENDPOINT_PREFIX, agent_id);
+        self.add_agent(agent_id, endpoint).await
+    }
+
     /// Remove an agent from the pool.
     ///
     /// This gracefully closes all connections to the agent.
@@ -1156,6 +1303,7 @@ impl AgentPool {
         for conn in connections.iter() {
             let _ = conn.client.close().await;
         }
+        self.in_process_handlers.remove(agent_id);
 
         info!(agent_id = %agent_id, "Agent removed from pool");
         Ok(())
@@ -1169,7 +1317,7 @@ impl AgentPool {
     pub async fn add_reverse_connection(
         &self,
         agent_id: &str,
-        client: ReverseConnectionClient,
+        mut client: ReverseConnectionClient,
         capabilities: AgentCapabilities,
     ) -> Result<(), AgentProtocolError> {
         info!(
@@ -1178,6 +1326,7 @@ impl AgentPool {
             "Adding reverse connection to pool"
         );
 
+        client.set_protocol_metrics(Arc::clone(&self.protocol_metrics));
         let transport = V2Transport::Reverse(client);
         let conn = Arc::new(PooledConnection::new(
             transport,
@@ -1302,22 +1451,27 @@ impl AgentPool {
         correlation_id: &str,
         event: &RequestHeadersEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        let start = Instant::now();
         self.total_requests.fetch_add(1, Ordering::Relaxed);
-        self.protocol_metrics.inc_requests();
+        self.protocol_metrics
+            .inc_requests(EventType::RequestHeaders);
         self.protocol_metrics.inc_in_flight();
 
         let conn = self.select_connection(agent_id)?;
+        let timer = self
+            .protocol_metrics
+            .time_request(EventType::RequestHeaders);
 
         // Check flow control before sending (respects flow_control_mode config)
         match self.check_flow_control(&conn, agent_id).await {
             Ok(true) => {} // Proceed normally
             Ok(false) => {
                 // FailOpen mode: skip agent, return allow response
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Ok(AgentResponse::default_allow());
             }
             Err(e) => {
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Err(e);
             }
@@ -1344,22 +1498,25 @@ impl AgentPool {
         conn.in_flight.fetch_sub(1, Ordering::Relaxed);
         conn.request_count.fetch_add(1, Ordering::Relaxed);
         self.protocol_metrics.dec_in_flight();
-        self.protocol_metrics
-            .record_request_duration(start.elapsed());
+        timer.record();
 
         match &result {
             Ok(_) => {
                 conn.consecutive_errors.store(0, Ordering::Relaxed);
-                self.protocol_metrics.inc_responses();
+                self.protocol_metrics
+                    .inc_responses(EventType::RequestHeaders);
             }
             Err(e) => {
                 conn.error_count.fetch_add(1, Ordering::Relaxed);
                 let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
                 self.total_errors.fetch_add(1, Ordering::Relaxed);
+                self.protocol_metrics.inc_errors(EventType::RequestHeaders);
 
                 // Record error type
                 match e {
-                    AgentProtocolError::Timeout(_) => self.protocol_metrics.inc_timeouts(),
+                    AgentProtocolError::Timeout(_) => self
+                        .protocol_metrics
+                        .inc_timeouts(EventType::RequestHeaders),
                     AgentProtocolError::ConnectionFailed(_)
                     | AgentProtocolError::ConnectionClosed => {
                         self.protocol_metrics.inc_connection_errors();
@@ -1370,10 +1527,11 @@ impl AgentPool {
                     _ => {}
                 }
 
-                // Mark unhealthy immediately on repeated failures (fast feedback)
-                if consecutive >= 3 {
+                // Mark unhealthy immediately on repeated failures (fast
+                // feedback) or once the connection closed
+                if consecutive >= 3 || matches!(e, AgentProtocolError::ConnectionClosed) {
                     conn.healthy_cached.store(false, Ordering::Release);
-                    trace!(agent_id = %agent_id, error = %e, "Connection marked unhealthy after consecutive errors");
+                    trace!(agent_id = %agent_id, error = %e, "Connection marked unhealthy");
                 }
             }
         }
@@ -1392,25 +1550,105 @@ impl AgentPool {
         event: &RequestBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
         self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let conn = self.affinity_connection(agent_id, correlation_id)?;
+        self.send_body_chunk(&conn, agent_id, correlation_id, BodyChunk::Request(event))
+            .await
+    }
+
+    /// Send a binary request body chunk to an agent.
+    ///
+    /// Same routing as [`send_request_body_chunk`](Self::send_request_body_chunk),
+    /// but the raw bytes are passed to the transport, which only base64-encodes
+    /// them when the negotiated encoding requires it.
+    pub async fn send_request_body_chunk_binary(
+        &self,
+        agent_id: &str,
+        event: &BinaryRequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let correlation_id = &event.correlation_id;
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let conn = self.affinity_connection(agent_id, correlation_id)?;
+        self.send_body_chunk(
+            &conn,
+            agent_id,
+            correlation_id,
+            BodyChunk::RequestBinary(event),
+        )
+        .await
+    }
 
-        // Try to use affinity (same connection as headers), fall back to selection
-        let conn = if let Some(entry) = self.correlation_affinity.get(correlation_id) {
+    /// Connection for a follow-up event of a request (body chunks, response
+    /// headers): the one that received the request headers.
+    ///
+    /// Without an affinity (e.g. an agent that only sees the response) the
+    /// selected connection is pinned for the rest of the request. If the
+    /// pinned connection died mid-request, the request fails over to a
+    /// healthy connection and is pinned there; the agent behind it has not
+    /// seen the earlier events, so this is counted in
+    /// `affinity_failovers_total`.
+    fn affinity_connection(
+        &self,
+        agent_id: &str,
+        correlation_id: &str,
+    ) -> Result<Arc<PooledConnection>, AgentProtocolError> {
+        let pinned = self.correlation_affinity.get(correlation_id).map(|entry| {
             entry.touch();
             Arc::clone(&entry.connection)
-        } else {
-            // No affinity found, use normal selection (shouldn't happen in normal flow)
-            trace!(correlation_id = %correlation_id, "No affinity found for body chunk, using selection");
-            self.select_connection(agent_id)?
-        };
+        });
+
+        match pinned {
+            Some(conn) if conn.is_healthy_cached() => Ok(conn),
+            Some(_) => {
+                let conn = match self.select_connection(agent_id) {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        self.clear_correlation_affinity(correlation_id);
+                        return Err(e);
+                    }
+                };
+                warn!(
+                    agent_id = %agent_id,
+                    correlation_id = %correlation_id,
+                    "Pinned agent connection failed mid-request, failing over"
+                );
+                self.protocol_metrics.inc_affinity_failovers();
+                self.store_correlation_affinity(correlation_id, &conn);
+                Ok(conn)
+            }
+            None => {
+                trace!(correlation_id = %correlation_id, "No affinity found, using selection");
+                let conn = self.select_connection(agent_id)?;
+                self.store_correlation_affinity(correlation_id, &conn);
+                Ok(conn)
+            }
+        }
+    }
+
+    /// Send a body chunk on a connection, applying flow control and
+    /// per-connection accounting.
+    async fn send_body_chunk(
+        &self,
+        conn: &PooledConnection,
+        agent_id: &str,
+        correlation_id: &str,
+        chunk: BodyChunk<'_>,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let event_type = chunk.event_type();
+        self.protocol_metrics.inc_requests(event_type);
+        let timer = self.protocol_metrics.time_request(event_type);
 
         // Check flow control before sending body chunks (critical for backpressure)
-        match self.check_flow_control(&conn, agent_id).await {
+        match self.check_flow_control(conn, agent_id).await {
             Ok(true) => {} // Proceed normally
             Ok(false) => {
                 // FailOpen mode: skip agent, return allow response
+                timer.cancel();
                 return Ok(AgentResponse::default_allow());
             }
-            Err(e) => return Err(e),
+            Err(e) => {
+                timer.cancel();
+                return Err(e);
+            }
         }
 
         let _permit = conn.concurrency_limiter.acquire().await.map_err(|_| {
@@ -1420,23 +1658,45 @@ impl AgentPool {
         conn.in_flight.fetch_add(1, Ordering::Relaxed);
         conn.touch();
 
-        let result = conn
-            .client
-            .send_request_body_chunk(correlation_id, event)
-            .await;
+        let result = match chunk {
+            BodyChunk::Request(event) => {
+                conn.client
+                    .send_request_body_chunk(correlation_id, event)
+                    .await
+            }
+            BodyChunk::RequestBinary(event) => {
+                conn.client.send_request_body_chunk_binary(event).await
+            }
+            BodyChunk::Response(event) => {
+                conn.client
+                    .send_response_body_chunk(correlation_id, event)
+                    .await
+            }
+            BodyChunk::ResponseBinary(event) => {
+                conn.client.send_response_body_chunk_binary(event).await
+            }
+        };
 
         conn.in_flight.fetch_sub(1, Ordering::Relaxed);
         conn.request_count.fetch_add(1, Ordering::Relaxed);
+        timer.record();
 
         match &result {
             Ok(_) => {
                 conn.consecutive_errors.store(0, Ordering::Relaxed);
+                self.protocol_metrics.inc_responses(event_type);
             }
-            Err(_) => {
+            Err(e) => {
                 conn.error_count.fetch_add(1, Ordering::Relaxed);
                 let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
                 self.total_errors.fetch_add(1, Ordering::Relaxed);
-                if consecutive >= 3 {
+                self.protocol_metrics.inc_errors(event_type);
+                if matches!(e, AgentProtocolError::Timeout(_)) {
+                    self.protocol_metrics.inc_timeouts(event_type);
+                }
+                // A closed connection is dead for good: fail pinned
+                // requests over on their next event
+                if consecutive >= 3 || matches!(e, AgentProtocolError::ConnectionClosed) {
                     conn.healthy_cached.store(false, Ordering::Release);
                 }
             }
@@ -1456,8 +1716,13 @@ impl AgentPool {
         event: &ResponseHeadersEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
         self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.protocol_metrics
+            .inc_requests(EventType::ResponseHeaders);
 
-        let conn = self.select_connection(agent_id)?;
+        let conn = self.affinity_connection(agent_id, correlation_id)?;
+        let timer = self
+            .protocol_metrics
+            .time_request(EventType::ResponseHeaders);
 
         let _permit = conn.concurrency_limiter.acquire().await.map_err(|_| {
             AgentProtocolError::ConnectionFailed("Concurrency limit reached".to_string())
@@ -1473,16 +1738,26 @@ impl AgentPool {
 
         conn.in_flight.fetch_sub(1, Ordering::Relaxed);
         conn.request_count.fetch_add(1, Ordering::Relaxed);
+        timer.record();
 
         match &result {
             Ok(_) => {
                 conn.consecutive_errors.store(0, Ordering::Relaxed);
+                self.protocol_metrics
+                    .inc_responses(EventType::ResponseHeaders);
             }
-            Err(_) => {
+            Err(e) => {
                 conn.error_count.fetch_add(1, Ordering::Relaxed);
                 let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
                 self.total_errors.fetch_add(1, Ordering::Relaxed);
-                if consecutive >= 3 {
+                self.protocol_metrics.inc_errors(EventType::ResponseHeaders);
+                if matches!(e, AgentProtocolError::Timeout(_)) {
+                    self.protocol_metrics
+                        .inc_timeouts(EventType::ResponseHeaders);
+                }
+                // A closed connection is dead for good: fail pinned
+                // requests over on their next event
+                if consecutive >= 3 || matches!(e, AgentProtocolError::ConnectionClosed) {
                     conn.healthy_cached.store(false, Ordering::Release);
                 }
             }
@@ -1502,49 +1777,28 @@ impl AgentPool {
         event: &ResponseBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
         self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let conn = self.affinity_connection(agent_id, correlation_id)?;
+        self.send_body_chunk(&conn, agent_id, correlation_id, BodyChunk::Response(event))
+            .await
+    }
 
-        let conn = self.select_connection(agent_id)?;
-
-        // Check flow control before sending body chunks (critical for backpressure)
-        match self.check_flow_control(&conn, agent_id).await {
-            Ok(true) => {} // Proceed normally
-            Ok(false) => {
-                // FailOpen mode: skip agent, return allow response
-                return Ok(AgentResponse::default_allow());
-            }
-            Err(e) => return Err(e),
-        }
-
-        let _permit = conn.concurrency_limiter.acquire().await.map_err(|_| {
-            AgentProtocolError::ConnectionFailed("Concurrency limit reached".to_string())
-        })?;
-
-        conn.in_flight.fetch_add(1, Ordering::Relaxed);
-        conn.touch();
-
-        let result = conn
-            .client
-            .send_response_body_chunk(correlation_id, event)
-            .await;
-
-        conn.in_flight.fetch_sub(1, Ordering::Relaxed);
-        conn.request_count.fetch_add(1, Ordering::Relaxed);
-
-        match &result {
-            Ok(_) => {
-                conn.consecutive_errors.store(0, Ordering::Relaxed);
-            }
-            Err(_) => {
-                conn.error_count.fetch_add(1, Ordering::Relaxed);
-                let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
-                self.total_errors.fetch_add(1, Ordering::Relaxed);
-                if consecutive >= 3 {
-                    conn.healthy_cached.store(false, Ordering::Release);
-                }
-            }
-        }
-
-        result
+    /// Send a binary response body chunk to an agent.
+    ///
+    /// Binary counterpart of [`send_response_body_chunk`](Self::send_response_body_chunk).
+    pub async fn send_response_body_chunk_binary(
+        &self,
+        agent_id: &str,
+        event: &BinaryResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let conn = self.affinity_connection(agent_id, &event.correlation_id)?;
+        self.send_body_chunk(
+            &conn,
+            agent_id,
+            &event.correlation_id,
+            BodyChunk::ResponseBinary(event),
+        )
+        .await
     }
 
     /// Send a guardrail inspect event to an agent.
@@ -1559,18 +1813,24 @@ impl AgentPool {
         event: &GuardrailInspectEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
         self.total_requests.fetch_add(1, Ordering::Relaxed);
-        self.protocol_metrics.inc_requests();
+        self.protocol_metrics
+            .inc_requests(EventType::GuardrailInspect);
         self.protocol_metrics.inc_in_flight();
 
         let conn = self.select_connection(agent_id)?;
+        let timer = self
+            .protocol_metrics
+            .time_request(EventType::GuardrailInspect);
 
         match self.check_flow_control(&conn, agent_id).await {
             Ok(true) => {}
             Ok(false) => {
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Ok(AgentResponse::default_allow());
             }
             Err(e) => {
+                timer.cancel();
                 self.protocol_metrics.dec_in_flight();
                 return Err(e);
             }
@@ -1593,19 +1853,25 @@ impl AgentPool {
         conn.in_flight.fetch_sub(1, Ordering::Relaxed);
         conn.request_count.fetch_add(1, Ordering::Relaxed);
         self.protocol_metrics.dec_in_flight();
+        timer.record();
 
         match &result {
             Ok(_) => {
                 conn.consecutive_errors.store(0, Ordering::Relaxed);
-                self.protocol_metrics.inc_responses();
+                self.protocol_metrics
+                    .inc_responses(EventType::GuardrailInspect);
             }
             Err(e) => {
                 conn.error_count.fetch_add(1, Ordering::Relaxed);
                 let consecutive = conn.consecutive_errors.fetch_add(1, Ordering::Relaxed) + 1;
                 self.total_errors.fetch_add(1, Ordering::Relaxed);
+                self.protocol_metrics
+                    .inc_errors(EventType::GuardrailInspect);
 
                 match e {
-                    AgentProtocolError::Timeout(_) => self.protocol_metrics.inc_timeouts(),
+                    AgentProtocolError::Timeout(_) => self
+                        .protocol_metrics
+                        .inc_timeouts(EventType::GuardrailInspect),
                     AgentProtocolError::ConnectionFailed(_)
                     | AgentProtocolError::ConnectionClosed => {
                         self.protocol_metrics.inc_connection_errors();
@@ -1697,6 +1963,17 @@ impl AgentPool {
             .find(|s| s.agent_id == agent_id)
     }
 
+    /// Connection generation of an agent.
+    ///
+    /// Starts at 1 when the agent is added and grows each time the agent
+    /// reconnects after losing all its healthy connections, so callers can
+    /// tell that it came back from a restart.
+    pub fn agent_connect_generation(&self, agent_id: &str) -> Option<u64> {
+        self.agents
+            .get(agent_id)
+            .map(|entry| entry.connect_generation.load(Ordering::Acquire))
+    }
+
     /// Get the capabilities of an agent.
     pub async fn agent_capabilities(&self, agent_id: &str) -> Option<AgentCapabilities> {
         // Clone the Arc out of the DashMap Ref to avoid lifetime issues
@@ -1850,7 +2127,21 @@ impl AgentPool {
         endpoint: &str,
     ) -> Result<PooledConnection, AgentProtocolError> {
         // Detect transport type from endpoint
-        let transport = if is_uds_endpoint(endpoint) {
+        let transport = if let Some(id) = endpoint.strip_prefix(IN_PROCESS_ENDPOINT_PREFIX) {
+            let handler = self
+                .in_process_handlers
+                .get(id)
+                .map(|h| Arc::clone(h.value()))
+                .ok_or_else(|| {
+                    AgentProtocolError::ConnectionFailed(format!(
+                        "No in-process handler registered for agent {}",
+                        id
+                    ))
+                })?;
+            V2Transport::InProcess(
+                InProcessClient::connect(agent_id, handler, self.config.request_timeout).await?,
+            )
+        } else if is_uds_endpoint(endpoint) {
             // Unix Domain Socket transport
             let socket_path = endpoint.strip_prefix("unix:").unwrap_or(endpoint);
 
@@ -1860,6 +2151,15 @@ impl AgentPool {
             // Set callbacks before connecting
             client.set_metrics_callback(Arc::clone(&self.metrics_callback));
             client.set_config_update_callback(Arc::clone(&self.config_update_callback));
+            if let Some(handler) = self.state_handler.read().clone() {
+                client.set_state_handler(handler);
+            }
+            client.set_max_message_size(self.config.max_message_size);
+            client.set_protocol_metrics(Arc::clone(&self.protocol_metrics));
+            #[cfg(feature = "mmap-buffers")]
+            if let Some(shm) = &self.config.shared_memory {
+                client.set_shared_memory(shm.clone());
+            }
 
             client.connect().await?;
             V2Transport::Uds(client)
@@ -1871,6 +2171,11 @@ impl AgentPool {
             // Set callbacks before connecting
             client.set_metrics_callback(Arc::clone(&self.metrics_callback));
             client.set_config_update_callback(Arc::clone(&self.config_update_callback));
+            if let Some(handler) = self.state_handler.read().clone() {
+                client.set_state_handler(handler);
+            }
+            client.set_max_message_size(self.config.max_message_size);
+            client.set_protocol_metrics(Arc::clone(&self.protocol_metrics));
 
             client.connect().await?;
             V2Transport::Grpc(client)
@@ -1989,6 +2294,14 @@ impl AgentPool {
         match self.create_connection(agent_id, &entry.endpoint).await {
             Ok(conn) => {
                 let mut connections = entry.connections.write().await;
+                // With no healthy connection left the agent was down (e.g.
+                // restarted): it may have new capabilities and a cold start
+                if !connections.iter().any(|c| c.is_healthy_cached()) {
+                    if let Some(caps) = conn.client.capabilities().await {
+                        *entry.capabilities.write().await = Some(caps);
+                    }
+                    entry.connect_generation.fetch_add(1, Ordering::AcqRel);
+                }
                 connections.push(Arc::new(conn));
                 entry.reconnect_attempts.store(0, Ordering::Relaxed);
                 info!(agent_id = %agent_id, "Reconnected successfully");
@@ -2081,6 +2394,7 @@ mod tests {
     fn test_is_agent_healthy_not_found() {
         let pool = AgentPool::new();
         assert!(!pool.is_agent_healthy("nonexistent"));
+        assert!(pool.agent_connect_generation("nonexistent").is_none());
     }
 
     #[tokio::test]
@@ -2164,6 +2478,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn affinity_fails_over_when_pinned_connection_dies() {
+        let pool = AgentPool::new();
+        let pinned = test_conn().await;
+        let spare = test_conn().await;
+        let entry = Arc::new(AgentEntry::new(
+            "agent".to_string(),
+            "/tmp/zentinel-test-nonexistent.sock".to_string(),
+        ));
+        *entry.connections.write().await = vec![Arc::clone(&pinned), Arc::clone(&spare)];
+        pool.agents.insert("agent".to_string(), entry);
+
+        pool.store_correlation_affinity("corr-1", &pinned);
+        let conn = pool.affinity_connection("agent", "corr-1").unwrap();
+        assert!(Arc::ptr_eq(&conn, &pinned));
+
+        // The pinned connection dies mid-request
+        pinned.healthy_cached.store(false, Ordering::Release);
+        let conn = pool.affinity_connection("agent", "corr-1").unwrap();
+        assert!(Arc::ptr_eq(&conn, &spare));
+        // Later events stay on the new connection
+        let conn = pool.affinity_connection("agent", "corr-1").unwrap();
+        assert!(Arc::ptr_eq(&conn, &spare));
+        assert_eq!(
+            pool.protocol_metrics()
+                .affinity_failovers_total
+                .load(Ordering::Relaxed),
+            1
+        );
+
+        // Nowhere to fail over to: the affinity is dropped
+        spare.healthy_cached.store(false, Ordering::Release);
+        assert!(pool.affinity_connection("agent", "corr-1").is_err());
+        assert_eq!(pool.correlation_affinity_count(), 0);
+    }
+
     #[tokio::test]
     async fn affinity_cleared_on_request_end() {
         let pool = AgentPool::new();
@@ -2182,6 +2532,59 @@ mod tests {
         );
     }
 
+    struct SlowAgent;
+
+    #[async_trait::async_trait]
+    impl AgentHandlerV2 for SlowAgent {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::new("slow", "Slow", "1.0.0").with_event(EventType::RequestHeaders)
+        }
+
+        async fn on_request_headers(&self, event: RequestHeadersEvent) -> AgentResponse {
+            if event.uri == "/slow" {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            AgentResponse::default_allow()
+        }
+    }
+
+    #[tokio::test]
+    async fn request_duration_recorded_when_caller_gives_up() {
+        use crate::buffer_pool::Recycle;
+
+        let pool = AgentPool::with_config(AgentPoolConfig {
+            connections_per_agent: 1,
+            ..Default::default()
+        });
+        pool.add_in_process_agent("slow", Arc::new(SlowAgent))
+            .await
+            .unwrap();
+
+        let mut event = RequestHeadersEvent::empty();
+        event.uri = "/fast".to_string();
+        pool.send_request_headers("slow", "c1", &event)
+            .await
+            .unwrap();
+
+        // The caller's own timeout drops the request future mid-await
+        event.uri = "/slow".to_string();
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(50),
+            pool.send_request_headers("slow", "c2", &event),
+        )
+        .await;
+        assert!(abandoned.is_err());
+
+        let snap = pool.protocol_metrics().snapshot();
+        assert_eq!(snap.request_duration.count, 2);
+        assert!(snap.request_duration.sum >= 50_000);
+
+        let headers = snap.event(EventType::RequestHeaders).unwrap();
+        assert_eq!(headers.requests_total, 2);
+        assert_eq!(headers.responses_total, 1);
+        assert_eq!(headers.request_duration.count, 2);
+    }
+
     #[tokio::test]
     async fn expired_affinities_are_reclaimed() {
         let config = AgentPoolConfig {
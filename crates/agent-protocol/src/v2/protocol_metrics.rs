@@ -7,9 +7,73 @@
 //! - Request/response counters
 //!
 //! These metrics are for proxy-side instrumentation, not agent-reported metrics.
+//!
+//! Request, response, timeout and error counters and both histograms are
+//! also kept per event type (see [`TRACKED_EVENT_TYPES`]) and exported with
+//! an `event_type` label, so headers and body chunks can be told apart.
+//!
+//! Histogram buckets can be set per pool. With the `quantile-sketch` feature
+//! each histogram also feeds a [`QuantileSketch`], which makes
+//! [`HistogramSnapshot::percentile_micros`] accurate to a few percent and
+//! adds `*_quantile_seconds` summaries to the exported metrics.
 
+#[cfg(feature = "quantile-sketch")]
+use super::quantile_sketch::{QuantileSketch, SketchSnapshot};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
+use zentinel_common::observability::{metric_labels, MetricsWriter};
+
+use crate::EventType;
+
+/// Prefix of the metric families written by [`ProtocolMetrics::write_metrics`]
+pub const METRIC_PREFIX: &str = "zentinel_agent_pool";
+
+/// Default histogram buckets in microseconds: 10μs to 1s
+pub const DEFAULT_BUCKETS_MICROS: &[u64] = &[
+    10, 50, 100, 500, 1_000, 5_000, 10_000, 50_000, 100_000, 500_000, 1_000_000,
+];
+
+/// Quantiles exported as summaries when the `quantile-sketch` feature is on
+pub const SUMMARY_QUANTILES: &[f64] = &[0.5, 0.9, 0.99, 0.999];
+
+/// Label of the per-event-type series
+pub const EVENT_TYPE_LABEL: &str = "event_type";
+
+/// Event types sent through the pool, each with its own counters and
+/// histograms
+pub const TRACKED_EVENT_TYPES: [EventType; 5] = [
+    EventType::RequestHeaders,
+    EventType::RequestBodyChunk,
+    EventType::ResponseHeaders,
+    EventType::ResponseBodyChunk,
+    EventType::GuardrailInspect,
+];
+
+/// Position of an event type in [`TRACKED_EVENT_TYPES`]
+fn event_index(event_type: EventType) -> Option<usize> {
+    match event_type {
+        EventType::RequestHeaders => Some(0),
+        EventType::RequestBodyChunk => Some(1),
+        EventType::ResponseHeaders => Some(2),
+        EventType::ResponseBodyChunk => Some(3),
+        EventType::GuardrailInspect => Some(4),
+        EventType::Configure | EventType::RequestComplete | EventType::WebSocketFrame => None,
+    }
+}
+
+/// `event_type` label value, matching the serialized event type name
+pub fn event_type_label(event_type: EventType) -> &'static str {
+    match event_type {
+        EventType::Configure => "configure",
+        EventType::RequestHeaders => "request_headers",
+        EventType::RequestBodyChunk => "request_body_chunk",
+        EventType::ResponseHeaders => "response_headers",
+        EventType::ResponseBodyChunk => "response_body_chunk",
+        EventType::RequestComplete => "request_complete",
+        EventType::WebSocketFrame => "web_socket_frame",
+        EventType::GuardrailInspect => "guardrail_inspect",
+    }
+}
 
 /// Protocol-level metrics for the agent pool.
 #[derive(Debug, Default)]
@@ -36,6 +100,8 @@ pub struct ProtocolMetrics {
     pub affinity_evictions_total: AtomicU64,
     /// Affinities dropped because the affinity map was at capacity
     pub affinity_rejections_total: AtomicU64,
+    /// Requests moved off their pinned connection after it failed
+    pub affinity_failovers_total: AtomicU64,
 
     // Gauges
     /// Current in-flight requests
@@ -52,8 +118,12 @@ pub struct ProtocolMetrics {
     // Histograms (using simple bucketed approach)
     /// Serialization time histogram
     pub serialization_time: HistogramMetric,
-    /// Request duration histogram (end-to-end)
+    /// Request duration histogram (end-to-end, one observation per event
+    /// sent to an agent)
     pub request_duration: HistogramMetric,
+
+    /// Per-event-type metrics, indexed like [`TRACKED_EVENT_TYPES`]
+    events: [EventTypeMetrics; TRACKED_EVENT_TYPES.len()],
 }
 
 impl ProtocolMetrics {
@@ -62,22 +132,69 @@ impl ProtocolMetrics {
         Self::default()
     }
 
+    /// Create protocol metrics with custom histogram buckets (in
+    /// microseconds); `None` keeps [`DEFAULT_BUCKETS_MICROS`].
+    pub fn with_buckets(
+        serialization: Option<Vec<u64>>,
+        request_duration: Option<Vec<u64>>,
+    ) -> Self {
+        let histogram = |buckets: &Option<Vec<u64>>| {
+            buckets
+                .clone()
+                .map(HistogramMetric::with_buckets)
+                .unwrap_or_default()
+        };
+        Self {
+            serialization_time: histogram(&serialization),
+            request_duration: histogram(&request_duration),
+            events: std::array::from_fn(|_| EventTypeMetrics {
+                serialization_time: histogram(&serialization),
+                request_duration: histogram(&request_duration),
+                ..EventTypeMetrics::default()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Metrics of one event type; `None` for types the pool does not send.
+    #[inline]
+    pub fn event(&self, event_type: EventType) -> Option<&EventTypeMetrics> {
+        event_index(event_type).map(|i| &self.events[i])
+    }
+
     /// Increment requests total.
     #[inline]
-    pub fn inc_requests(&self) {
+    pub fn inc_requests(&self, event_type: EventType) {
         self.requests_total.fetch_add(1, Ordering::Relaxed);
+        if let Some(event) = self.event(event_type) {
+            event.requests_total.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Increment responses total.
     #[inline]
-    pub fn inc_responses(&self) {
+    pub fn inc_responses(&self, event_type: EventType) {
         self.responses_total.fetch_add(1, Ordering::Relaxed);
+        if let Some(event) = self.event(event_type) {
+            event.responses_total.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Increment timeouts.
     #[inline]
-    pub fn inc_timeouts(&self) {
+    pub fn inc_timeouts(&self, event_type: EventType) {
         self.timeouts_total.fetch_add(1, Ordering::Relaxed);
+        if let Some(event) = self.event(event_type) {
+            event.timeouts_total.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Increment failed requests of an event type (any error).
+    #[inline]
+    pub fn inc_errors(&self, event_type: EventType) {
+        if let Some(event) = self.event(event_type) {
+            event.errors_total.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Increment connection errors.
@@ -128,6 +245,13 @@ impl ProtocolMetrics {
             .fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Increment affinity failovers.
+    #[inline]
+    pub fn inc_affinity_failovers(&self) {
+        self.affinity_failovers_total
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Set correlation affinities gauge.
     #[inline]
     pub fn set_correlation_affinities(&self, count: u64) {
@@ -173,14 +297,38 @@ impl ProtocolMetrics {
 
     /// Record serialization time.
     #[inline]
-    pub fn record_serialization_time(&self, duration: Duration) {
+    pub fn record_serialization_time(&self, event_type: EventType, duration: Duration) {
         self.serialization_time.record(duration);
+        if let Some(event) = self.event(event_type) {
+            event.serialization_time.record(duration);
+        }
     }
 
     /// Record request duration.
     #[inline]
-    pub fn record_request_duration(&self, duration: Duration) {
+    pub fn record_request_duration(&self, event_type: EventType, duration: Duration) {
         self.request_duration.record(duration);
+        if let Some(event) = self.event(event_type) {
+            event.request_duration.record(duration);
+        }
+    }
+
+    /// Start timing serialization; recorded when the guard is dropped.
+    #[inline]
+    pub fn time_serialization(&self, event_type: EventType) -> DurationRecorder<'_> {
+        DurationRecorder::paired(
+            &self.serialization_time,
+            self.event(event_type).map(|e| &e.serialization_time),
+        )
+    }
+
+    /// Start timing a request; recorded when the guard is dropped.
+    #[inline]
+    pub fn time_request(&self, event_type: EventType) -> DurationRecorder<'_> {
+        DurationRecorder::paired(
+            &self.request_duration,
+            self.event(event_type).map(|e| &e.request_duration),
+        )
     }
 
     /// Get a snapshot of all metrics.
@@ -198,6 +346,7 @@ impl ProtocolMetrics {
                 .load(Ordering::Relaxed),
             affinity_evictions_total: self.affinity_evictions_total.load(Ordering::Relaxed),
             affinity_rejections_total: self.affinity_rejections_total.load(Ordering::Relaxed),
+            affinity_failovers_total: self.affinity_failovers_total.load(Ordering::Relaxed),
             in_flight_requests: self.in_flight_requests.load(Ordering::Relaxed),
             correlation_affinities: self.correlation_affinities.load(Ordering::Relaxed),
             buffer_utilization_percent: self.buffer_utilization_percent.load(Ordering::Relaxed),
@@ -205,119 +354,324 @@ impl ProtocolMetrics {
             paused_connections: self.paused_connections.load(Ordering::Relaxed),
             serialization_time: self.serialization_time.snapshot(),
             request_duration: self.request_duration.snapshot(),
+            events: TRACKED_EVENT_TYPES
+                .iter()
+                .zip(&self.events)
+                .map(|(&event_type, event)| event.snapshot(event_type))
+                .collect(),
         }
     }
 
-    /// Export metrics in Prometheus text format.
+    /// Export pool-wide totals in Prometheus text format.
+    ///
+    /// Families are named `{prefix}_*` and carry no labels. The proxy serves
+    /// pool metrics through [`write_metrics`](Self::write_metrics) instead.
     pub fn to_prometheus(&self, prefix: &str) -> String {
         let snap = self.snapshot();
-        let mut output = String::with_capacity(2048);
-
-        // Counters
-        output.push_str(&format!(
-            "# HELP {prefix}_requests_total Total requests sent to agents\n\
-             # TYPE {prefix}_requests_total counter\n\
-             {prefix}_requests_total {}\n\n",
-            snap.requests_total
-        ));
+        let mut writer = MetricsWriter::new();
+
+        let counters = [
+            (
+                "requests_total",
+                "Total requests sent to agents",
+                snap.requests_total,
+            ),
+            (
+                "responses_total",
+                "Total responses received from agents",
+                snap.responses_total,
+            ),
+            (
+                "timeouts_total",
+                "Total request timeouts",
+                snap.timeouts_total,
+            ),
+            (
+                "connection_errors_total",
+                "Total connection errors",
+                snap.connection_errors_total,
+            ),
+            (
+                "flow_control_pauses_total",
+                "Flow control pause events",
+                snap.flow_control_pauses_total,
+            ),
+            (
+                "flow_control_rejections_total",
+                "Requests rejected due to flow control",
+                snap.flow_control_rejections_total,
+            ),
+            (
+                "affinity_evictions_total",
+                "Correlation affinities reclaimed by TTL sweep",
+                snap.affinity_evictions_total,
+            ),
+            (
+                "affinity_rejections_total",
+                "Affinities dropped because the map was at capacity",
+                snap.affinity_rejections_total,
+            ),
+        ];
+        for (name, help, value) in counters {
+            writer.counter(&format!("{prefix}_{name}"), help, &[], value as f64);
+        }
 
-        output.push_str(&format!(
-            "# HELP {prefix}_responses_total Total responses received from agents\n\
-             # TYPE {prefix}_responses_total counter\n\
-             {prefix}_responses_total {}\n\n",
-            snap.responses_total
-        ));
+        let gauges = [
+            (
+                "in_flight_requests",
+                "Current in-flight requests",
+                snap.in_flight_requests,
+            ),
+            (
+                "correlation_affinities",
+                "Current correlation affinity entries",
+                snap.correlation_affinities,
+            ),
+            (
+                "buffer_utilization_percent",
+                "Buffer utilization percentage",
+                snap.buffer_utilization_percent,
+            ),
+            (
+                "healthy_connections",
+                "Number of healthy agent connections",
+                snap.healthy_connections,
+            ),
+            (
+                "paused_connections",
+                "Number of flow-control paused connections",
+                snap.paused_connections,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            writer.gauge(&format!("{prefix}_{name}"), help, &[], value as f64);
+        }
 
-        output.push_str(&format!(
-            "# HELP {prefix}_timeouts_total Total request timeouts\n\
-             # TYPE {prefix}_timeouts_total counter\n\
-             {prefix}_timeouts_total {}\n\n",
-            snap.timeouts_total
-        ));
+        snap.serialization_time.write_metrics(
+            &mut writer,
+            &format!("{prefix}_serialization_seconds"),
+            "Serialization time in seconds",
+            &[],
+        );
+        snap.request_duration.write_metrics(
+            &mut writer,
+            &format!("{prefix}_request_duration_seconds"),
+            "Request duration in seconds",
+            &[],
+        );
 
-        output.push_str(&format!(
-            "# HELP {prefix}_connection_errors_total Total connection errors\n\
-             # TYPE {prefix}_connection_errors_total counter\n\
-             {prefix}_connection_errors_total {}\n\n",
-            snap.connection_errors_total
-        ));
+        writer.render()
+    }
 
-        output.push_str(&format!(
-            "# HELP {prefix}_flow_control_pauses_total Flow control pause events\n\
-             # TYPE {prefix}_flow_control_pauses_total counter\n\
-             {prefix}_flow_control_pauses_total {}\n\n",
-            snap.flow_control_pauses_total
-        ));
+    /// Write metrics for the pool of `agent_id` to the unified registry.
+    ///
+    /// Families are named `zentinel_agent_pool_*` and labelled with
+    /// `agent_id`, so several pools share one family per metric. Request,
+    /// response, timeout and error counters and the histograms have one
+    /// series per `event_type`; sum over it for the pool total.
+    pub fn write_metrics(&self, writer: &mut MetricsWriter, agent_id: &str) {
+        let snap = self.snapshot();
+        let labels = [(metric_labels::AGENT_ID, agent_id)];
 
-        output.push_str(&format!(
-            "# HELP {prefix}_flow_control_rejections_total Requests rejected due to flow control\n\
-             # TYPE {prefix}_flow_control_rejections_total counter\n\
-             {prefix}_flow_control_rejections_total {}\n\n",
-            snap.flow_control_rejections_total
-        ));
+        for event in &snap.events {
+            event.write_metrics(writer, agent_id);
+        }
 
-        output.push_str(&format!(
-            "# HELP {prefix}_affinity_evictions_total Correlation affinities reclaimed by TTL sweep\n\
-             # TYPE {prefix}_affinity_evictions_total counter\n\
-             {prefix}_affinity_evictions_total {}\n\n",
-            snap.affinity_evictions_total
-        ));
+        let counters = [
+            (
+                "connection_errors_total",
+                "Total connection errors",
+                snap.connection_errors_total,
+            ),
+            (
+                "flow_control_pauses_total",
+                "Flow control pause events",
+                snap.flow_control_pauses_total,
+            ),
+            (
+                "flow_control_rejections_total",
+                "Requests rejected due to flow control",
+                snap.flow_control_rejections_total,
+            ),
+            (
+                "affinity_evictions_total",
+                "Correlation affinities reclaimed by TTL sweep",
+                snap.affinity_evictions_total,
+            ),
+            (
+                "affinity_rejections_total",
+                "Affinities dropped because the map was at capacity",
+                snap.affinity_rejections_total,
+            ),
+            (
+                "affinity_failovers_total",
+                "Requests moved off their pinned connection after it failed",
+                snap.affinity_failovers_total,
+            ),
+        ];
+        for (name, help, value) in counters {
+            writer.counter(
+                &format!("{METRIC_PREFIX}_{name}"),
+                help,
+                &labels,
+                value as f64,
+            );
+        }
 
-        output.push_str(&format!(
-            "# HELP {prefix}_affinity_rejections_total Affinities dropped because the map was at capacity\n\
-             # TYPE {prefix}_affinity_rejections_total counter\n\
-             {prefix}_affinity_rejections_total {}\n\n",
-            snap.affinity_rejections_total
-        ));
+        let gauges = [
+            (
+                "in_flight_requests",
+                "Current in-flight requests",
+                snap.in_flight_requests,
+            ),
+            (
+                "correlation_affinities",
+                "Current correlation affinity entries",
+                snap.correlation_affinities,
+            ),
+            (
+                "buffer_utilization_percent",
+                "Buffer utilization percentage",
+                snap.buffer_utilization_percent,
+            ),
+            (
+                "healthy_connections",
+                "Number of healthy agent connections",
+                snap.healthy_connections,
+            ),
+            (
+                "paused_connections",
+                "Number of flow-control paused connections",
+                snap.paused_connections,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            writer.gauge(
+                &format!("{METRIC_PREFIX}_{name}"),
+                help,
+                &labels,
+                value as f64,
+            );
+        }
+    }
+}
 
-        // Gauges
-        output.push_str(&format!(
-            "# HELP {prefix}_in_flight_requests Current in-flight requests\n\
-             # TYPE {prefix}_in_flight_requests gauge\n\
-             {prefix}_in_flight_requests {}\n\n",
-            snap.in_flight_requests
-        ));
+/// Counters and histograms of one event type.
+///
+/// Updated through [`ProtocolMetrics`], which also maintains the pool-wide
+/// totals.
+#[derive(Debug, Default)]
+pub struct EventTypeMetrics {
+    /// Events sent
+    pub requests_total: AtomicU64,
+    /// Responses received
+    pub responses_total: AtomicU64,
+    /// Events that timed out
+    pub timeouts_total: AtomicU64,
+    /// Events that failed (timeouts included)
+    pub errors_total: AtomicU64,
+    /// Serialization time histogram
+    pub serialization_time: HistogramMetric,
+    /// Request duration histogram
+    pub request_duration: HistogramMetric,
+}
 
-        output.push_str(&format!(
-            "# HELP {prefix}_correlation_affinities Current correlation affinity entries\n\
-             # TYPE {prefix}_correlation_affinities gauge\n\
-             {prefix}_correlation_affinities {}\n\n",
-            snap.correlation_affinities
-        ));
+impl EventTypeMetrics {
+    fn snapshot(&self, event_type: EventType) -> EventTypeMetricsSnapshot {
+        EventTypeMetricsSnapshot {
+            event_type,
+            requests_total: self.requests_total.load(Ordering::Relaxed),
+            responses_total: self.responses_total.load(Ordering::Relaxed),
+            timeouts_total: self.timeouts_total.load(Ordering::Relaxed),
+            errors_total: self.errors_total.load(Ordering::Relaxed),
+            serialization_time: self.serialization_time.snapshot(),
+            request_duration: self.request_duration.snapshot(),
+        }
+    }
+}
 
-        output.push_str(&format!(
-            "# HELP {prefix}_buffer_utilization_percent Buffer utilization percentage\n\
-             # TYPE {prefix}_buffer_utilization_percent gauge\n\
-             {prefix}_buffer_utilization_percent {}\n\n",
-            snap.buffer_utilization_percent
-        ));
+/// Snapshot of one event type's metrics.
+#[derive(Debug, Clone)]
+pub struct EventTypeMetricsSnapshot {
+    pub event_type: EventType,
+    pub requests_total: u64,
+    pub responses_total: u64,
+    pub timeouts_total: u64,
+    pub errors_total: u64,
+    pub serialization_time: HistogramSnapshot,
+    pub request_duration: HistogramSnapshot,
+}
 
-        output.push_str(&format!(
-            "# HELP {prefix}_healthy_connections Number of healthy agent connections\n\
-             # TYPE {prefix}_healthy_connections gauge\n\
-             {prefix}_healthy_connections {}\n\n",
-            snap.healthy_connections
-        ));
+impl EventTypeMetricsSnapshot {
+    /// Write the event type's series, labelled with `agent_id` and
+    /// `event_type`.
+    fn write_metrics(&self, writer: &mut MetricsWriter, agent_id: &str) {
+        let labels = [
+            (metric_labels::AGENT_ID, agent_id),
+            (EVENT_TYPE_LABEL, event_type_label(self.event_type)),
+        ];
 
-        output.push_str(&format!(
-            "# HELP {prefix}_paused_connections Number of flow-control paused connections\n\
-             # TYPE {prefix}_paused_connections gauge\n\
-             {prefix}_paused_connections {}\n\n",
-            snap.paused_connections
-        ));
+        let counters = [
+            (
+                "requests_total",
+                "Total requests sent to agents",
+                self.requests_total,
+            ),
+            (
+                "responses_total",
+                "Total responses received from agents",
+                self.responses_total,
+            ),
+            (
+                "timeouts_total",
+                "Total request timeouts",
+                self.timeouts_total,
+            ),
+            (
+                "errors_total",
+                "Total failed requests, timeouts included",
+                self.errors_total,
+            ),
+        ];
+        for (name, help, value) in counters {
+            writer.counter(
+                &format!("{METRIC_PREFIX}_{name}"),
+                help,
+                &labels,
+                value as f64,
+            );
+        }
 
-        // Histograms
-        output.push_str(&snap.serialization_time.to_prometheus(
-            &format!("{prefix}_serialization_seconds"),
+        self.serialization_time.write_metrics(
+            writer,
+            &format!("{METRIC_PREFIX}_serialization_seconds"),
             "Serialization time in seconds",
-        ));
-
-        output.push_str(&snap.request_duration.to_prometheus(
-            &format!("{prefix}_request_duration_seconds"),
+            &labels,
+        );
+        self.request_duration.write_metrics(
+            writer,
+            &format!("{METRIC_PREFIX}_request_duration_seconds"),
             "Request duration in seconds",
-        ));
-
-        output
+            &labels,
+        );
+
+        #[cfg(feature = "quantile-sketch")]
+        {
+            self.serialization_time.write_summary(
+                writer,
+                &format!("{METRIC_PREFIX}_serialization_quantile_seconds"),
+                "Serialization time quantiles in seconds",
+                &labels,
+                SUMMARY_QUANTILES,
+            );
+            self.request_duration.write_summary(
+                writer,
+                &format!("{METRIC_PREFIX}_request_duration_quantile_seconds"),
+                "Request duration quantiles in seconds",
+                &labels,
+                SUMMARY_QUANTILES,
+            );
+        }
     }
 }
 
@@ -332,36 +686,46 @@ pub struct HistogramMetric {
     sum: AtomicU64,
     /// Total count
     count: AtomicU64,
+    /// Fine-grained copy of the observations for quantiles
+    #[cfg(feature = "quantile-sketch")]
+    sketch: QuantileSketch,
 }
 
 impl Default for HistogramMetric {
     fn default() -> Self {
-        // Default buckets: 10μs, 50μs, 100μs, 500μs, 1ms, 5ms, 10ms, 50ms, 100ms, 500ms, 1s
-        let buckets = vec![
-            10, 50, 100, 500, 1_000, 5_000, 10_000, 50_000, 100_000, 500_000, 1_000_000,
-        ];
-        let counts = (0..=buckets.len()).map(|_| AtomicU64::new(0)).collect();
-        Self {
-            buckets,
-            counts,
-            sum: AtomicU64::new(0),
-            count: AtomicU64::new(0),
-        }
+        Self::with_buckets(DEFAULT_BUCKETS_MICROS.to_vec())
     }
 }
 
 impl HistogramMetric {
     /// Create a new histogram with custom bucket boundaries (in microseconds).
-    pub fn with_buckets(buckets: Vec<u64>) -> Self {
+    ///
+    /// Boundaries are sorted and deduplicated.
+    pub fn with_buckets(mut buckets: Vec<u64>) -> Self {
+        buckets.sort_unstable();
+        buckets.dedup();
         let counts = (0..=buckets.len()).map(|_| AtomicU64::new(0)).collect();
         Self {
             buckets,
             counts,
             sum: AtomicU64::new(0),
             count: AtomicU64::new(0),
+            #[cfg(feature = "quantile-sketch")]
+            sketch: QuantileSketch::new(),
         }
     }
 
+    /// Bucket boundaries in microseconds
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Start timing; the elapsed time is recorded when the guard is dropped.
+    #[inline]
+    pub fn start_timer(&self) -> DurationRecorder<'_> {
+        DurationRecorder::new(self)
+    }
+
     /// Record an observation.
     #[inline]
     pub fn record(&self, duration: Duration) {
@@ -378,6 +742,9 @@ impl HistogramMetric {
             .position(|&b| micros <= b)
             .unwrap_or(self.buckets.len());
         self.counts[bucket_idx].fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "quantile-sketch")]
+        self.sketch.record(micros);
     }
 
     /// Get a snapshot of the histogram.
@@ -391,6 +758,8 @@ impl HistogramMetric {
                 .collect(),
             sum: self.sum.load(Ordering::Relaxed),
             count: self.count.load(Ordering::Relaxed),
+            #[cfg(feature = "quantile-sketch")]
+            sketch: self.sketch.snapshot(),
         }
     }
 }
@@ -406,34 +775,69 @@ pub struct HistogramSnapshot {
     pub sum: u64,
     /// Total count
     pub count: u64,
+    /// Quantile sketch of the same observations
+    #[cfg(feature = "quantile-sketch")]
+    pub sketch: SketchSnapshot,
 }
 
 impl HistogramSnapshot {
-    /// Export to Prometheus format.
+    /// Export to Prometheus format, in seconds.
     pub fn to_prometheus(&self, name: &str, help: &str) -> String {
-        let mut output = String::with_capacity(512);
-
-        output.push_str(&format!("# HELP {name} {help}\n"));
-        output.push_str(&format!("# TYPE {name} histogram\n"));
+        let mut writer = MetricsWriter::new();
+        self.write_metrics(&mut writer, name, help, &[]);
+        writer.render()
+    }
 
-        // Cumulative bucket counts
+    /// Write the histogram to the unified registry, in seconds.
+    pub fn write_metrics(
+        &self,
+        writer: &mut MetricsWriter,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+    ) {
         let mut cumulative = 0u64;
-        for (i, &boundary) in self.buckets.iter().enumerate() {
-            cumulative += self.counts[i];
-            let le = boundary as f64 / 1_000_000.0; // Convert to seconds
-            output.push_str(&format!("{name}_bucket{{le=\"{le:.6}\"}} {cumulative}\n"));
-        }
-
-        // +Inf bucket
-        cumulative += self.counts.last().copied().unwrap_or(0);
-        output.push_str(&format!("{name}_bucket{{le=\"+Inf\"}} {cumulative}\n"));
-
-        // Sum and count
-        let sum_seconds = self.sum as f64 / 1_000_000.0;
-        output.push_str(&format!("{name}_sum {sum_seconds:.6}\n"));
-        output.push_str(&format!("{name}_count {}\n\n", self.count));
+        let buckets: Vec<(f64, u64)> = self
+            .buckets
+            .iter()
+            .zip(&self.counts)
+            .map(|(&boundary, &count)| {
+                cumulative += count;
+                (boundary as f64 / 1_000_000.0, cumulative)
+            })
+            .collect();
+        writer.histogram(
+            name,
+            help,
+            labels,
+            &buckets,
+            self.sum as f64 / 1_000_000.0,
+            self.count,
+        );
+    }
 
-        output
+    /// Write quantiles (0.0-1.0) of the histogram to the unified registry as
+    /// a summary, in seconds.
+    pub fn write_summary(
+        &self,
+        writer: &mut MetricsWriter,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        quantiles: &[f64],
+    ) {
+        let values: Vec<(f64, f64)> = quantiles
+            .iter()
+            .map(|&q| (q, self.percentile_micros(q * 100.0) as f64 / 1_000_000.0))
+            .collect();
+        writer.summary(
+            name,
+            help,
+            labels,
+            &values,
+            self.sum as f64 / 1_000_000.0,
+            self.count,
+        );
     }
 
     /// Get the mean value in microseconds.
@@ -445,25 +849,34 @@ impl HistogramSnapshot {
         }
     }
 
-    /// Get the approximate percentile value in microseconds.
+    /// Get the approximate percentile (0-100) value in microseconds.
+    ///
+    /// With the `quantile-sketch` feature this reads the sketch; otherwise
+    /// it interpolates linearly within the bucket the percentile falls in.
     pub fn percentile_micros(&self, p: f64) -> u64 {
+        #[cfg(feature = "quantile-sketch")]
+        if self.sketch.count > 0 {
+            return self.sketch.quantile(p / 100.0);
+        }
+
         if self.count == 0 {
             return 0;
         }
 
-        let target = (self.count as f64 * p / 100.0).ceil() as u64;
+        let target = ((self.count as f64 * p / 100.0).ceil() as u64).max(1);
         let mut cumulative = 0u64;
 
         for (i, &count) in self.counts.iter().enumerate() {
-            cumulative += count;
-            if cumulative >= target {
-                return if i < self.buckets.len() {
-                    self.buckets[i]
-                } else {
+            if cumulative + count >= target {
+                let Some(&upper) = self.buckets.get(i) else {
                     // +Inf bucket, return last finite bucket
-                    self.buckets.last().copied().unwrap_or(0)
+                    return self.buckets.last().copied().unwrap_or(0);
                 };
+                let lower = if i == 0 { 0 } else { self.buckets[i - 1] };
+                let fraction = (target - cumulative) as f64 / count as f64;
+                return lower + ((upper - lower) as f64 * fraction).round() as u64;
             }
+            cumulative += count;
         }
 
         self.buckets.last().copied().unwrap_or(0)
@@ -484,6 +897,7 @@ pub struct ProtocolMetricsSnapshot {
     pub flow_control_rejections_total: u64,
     pub affinity_evictions_total: u64,
     pub affinity_rejections_total: u64,
+    pub affinity_failovers_total: u64,
 
     // Gauges
     pub in_flight_requests: u64,
@@ -495,32 +909,82 @@ pub struct ProtocolMetricsSnapshot {
     // Histograms
     pub serialization_time: HistogramSnapshot,
     pub request_duration: HistogramSnapshot,
+
+    /// Per-event-type metrics, in [`TRACKED_EVENT_TYPES`] order
+    pub events: Vec<EventTypeMetricsSnapshot>,
 }
 
-/// Helper to measure and record duration.
+impl ProtocolMetricsSnapshot {
+    /// Metrics of one event type.
+    pub fn event(&self, event_type: EventType) -> Option<&EventTypeMetricsSnapshot> {
+        self.events.iter().find(|e| e.event_type == event_type)
+    }
+}
+
+/// Scope guard that records the time since its creation into a histogram
+/// when dropped.
+///
+/// Every exit of the timed scope is covered: early returns, `?` errors and
+/// futures dropped mid-await (e.g. by a caller's timeout). Paths that
+/// should not count call [`cancel`](Self::cancel).
+#[must_use = "the duration is recorded when the recorder is dropped"]
 pub struct DurationRecorder<'a> {
     histogram: &'a HistogramMetric,
+    /// Per-event-type histogram recorded alongside
+    event_histogram: Option<&'a HistogramMetric>,
     start: Instant,
+    armed: bool,
 }
 
 impl<'a> DurationRecorder<'a> {
     /// Start recording duration.
     pub fn new(histogram: &'a HistogramMetric) -> Self {
+        Self::paired(histogram, None)
+    }
+
+    fn paired(
+        histogram: &'a HistogramMetric,
+        event_histogram: Option<&'a HistogramMetric>,
+    ) -> Self {
         Self {
             histogram,
+            event_histogram,
             start: Instant::now(),
+            armed: true,
+        }
+    }
+
+    fn record_elapsed(&mut self) -> Duration {
+        let elapsed = self.start.elapsed();
+        self.histogram.record(elapsed);
+        if let Some(event_histogram) = self.event_histogram {
+            event_histogram.record(elapsed);
         }
+        self.armed = false;
+        elapsed
     }
 
-    /// Record the elapsed duration.
-    pub fn record(self) {
-        self.histogram.record(self.start.elapsed());
+    /// Time elapsed so far.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Record the elapsed duration now instead of at the end of the scope.
+    pub fn record(mut self) -> Duration {
+        self.record_elapsed()
+    }
+
+    /// Drop without recording.
+    pub fn cancel(mut self) {
+        self.armed = false;
     }
 }
 
 impl Drop for DurationRecorder<'_> {
     fn drop(&mut self) {
-        // Don't double-record, this is just a safety net
+        if self.armed {
+            self.record_elapsed();
+        }
     }
 }
 
@@ -532,15 +996,54 @@ mod tests {
     fn test_counter_increments() {
         let metrics = ProtocolMetrics::new();
 
-        metrics.inc_requests();
-        metrics.inc_requests();
-        metrics.inc_responses();
+        metrics.inc_requests(EventType::RequestHeaders);
+        metrics.inc_requests(EventType::RequestBodyChunk);
+        metrics.inc_responses(EventType::RequestHeaders);
 
         let snap = metrics.snapshot();
         assert_eq!(snap.requests_total, 2);
         assert_eq!(snap.responses_total, 1);
     }
 
+    #[test]
+    fn test_per_event_type_metrics() {
+        let metrics = ProtocolMetrics::new();
+
+        metrics.inc_requests(EventType::RequestHeaders);
+        metrics.inc_requests(EventType::ResponseBodyChunk);
+        metrics.inc_timeouts(EventType::ResponseBodyChunk);
+        metrics.inc_errors(EventType::ResponseBodyChunk);
+        metrics.time_request(EventType::ResponseBodyChunk).record();
+        // Not sent through the pool: only the aggregate moves
+        metrics.inc_requests(EventType::Configure);
+
+        let snap = metrics.snapshot();
+        assert_eq!(snap.requests_total, 3);
+        assert_eq!(snap.timeouts_total, 1);
+        assert_eq!(snap.request_duration.count, 1);
+        assert_eq!(snap.events.len(), TRACKED_EVENT_TYPES.len());
+        assert!(metrics.event(EventType::Configure).is_none());
+
+        let body = snap.event(EventType::ResponseBodyChunk).unwrap();
+        assert_eq!(body.requests_total, 1);
+        assert_eq!(body.timeouts_total, 1);
+        assert_eq!(body.errors_total, 1);
+        assert_eq!(body.request_duration.count, 1);
+        let headers = snap.event(EventType::RequestHeaders).unwrap();
+        assert_eq!(headers.requests_total, 1);
+        assert_eq!(headers.request_duration.count, 0);
+
+        let mut writer = MetricsWriter::new();
+        metrics.write_metrics(&mut writer, "waf");
+        let output = writer.render();
+        assert!(output.contains(
+            "zentinel_agent_pool_errors_total{agent_id=\"waf\",event_type=\"response_body_chunk\"} 1\n"
+        ));
+        assert!(output.contains(
+            "zentinel_agent_pool_requests_total{agent_id=\"waf\",event_type=\"guardrail_inspect\"} 0\n"
+        ));
+    }
+
     #[test]
     fn test_gauge_updates() {
         let metrics = ProtocolMetrics::new();
@@ -557,13 +1060,15 @@ mod tests {
     fn test_histogram_recording() {
         let metrics = ProtocolMetrics::new();
 
-        metrics.record_serialization_time(Duration::from_micros(50));
-        metrics.record_serialization_time(Duration::from_micros(150));
-        metrics.record_serialization_time(Duration::from_millis(5));
+        metrics.record_serialization_time(EventType::RequestHeaders, Duration::from_micros(50));
+        metrics.record_serialization_time(EventType::RequestHeaders, Duration::from_micros(150));
+        metrics.record_serialization_time(EventType::ResponseHeaders, Duration::from_millis(5));
 
         let snap = metrics.snapshot();
         assert_eq!(snap.serialization_time.count, 3);
         assert_eq!(snap.serialization_time.sum, 50 + 150 + 5000);
+        let headers = snap.event(EventType::RequestHeaders).unwrap();
+        assert_eq!(headers.serialization_time.count, 2);
     }
 
     #[test]
@@ -583,6 +1088,83 @@ mod tests {
         assert!(p50 <= 100, "p50 was {}", p50);
     }
 
+    #[test]
+    fn test_duration_recorder_records_once() {
+        let hist = HistogramMetric::default();
+
+        // Recorded at the end of the scope
+        {
+            let _timer = hist.start_timer();
+        }
+        // Recorded explicitly, not again on drop
+        hist.start_timer().record();
+        // Cancelled
+        hist.start_timer().cancel();
+
+        assert_eq!(hist.snapshot().count, 2);
+    }
+
+    #[test]
+    fn test_percentile_within_bucket() {
+        let hist = HistogramMetric::with_buckets(vec![10_000, 1_000, 100_000]);
+        assert_eq!(hist.buckets(), &[1_000, 10_000, 100_000]);
+
+        // 1000 observations spread evenly over 1ms..=10ms
+        for i in 1..=1_000u64 {
+            hist.record(Duration::from_micros(1_000 + i * 9));
+        }
+        let snap = hist.snapshot();
+
+        // Bucket upper bounds would report 10ms for every percentile
+        let p50 = snap.percentile_micros(50.0);
+        assert!((5_000..=6_000).contains(&p50), "p50 was {}", p50);
+        let p99 = snap.percentile_micros(99.0);
+        assert!((9_500..=10_000).contains(&p99), "p99 was {}", p99);
+    }
+
+    #[test]
+    fn test_custom_buckets_export() {
+        let metrics = ProtocolMetrics::with_buckets(None, Some(vec![250, 2_500]));
+        assert_eq!(metrics.serialization_time.buckets(), DEFAULT_BUCKETS_MICROS);
+        metrics.record_request_duration(EventType::RequestHeaders, Duration::from_micros(200));
+
+        let mut writer = MetricsWriter::new();
+        metrics.write_metrics(&mut writer, "waf");
+        let output = writer.render();
+
+        assert!(output.contains(
+            "zentinel_agent_pool_request_duration_seconds_bucket{agent_id=\"waf\",event_type=\"request_headers\",le=\"0.00025\"} 1\n"
+        ));
+        assert!(!output.contains("request_duration_seconds_bucket{agent_id=\"waf\",event_type=\"request_headers\",le=\"0.001\"}"));
+    }
+
+    #[cfg(feature = "quantile-sketch")]
+    #[test]
+    fn test_sketch_quantile_summary() {
+        let metrics = ProtocolMetrics::new();
+        for i in 1..=1_000u64 {
+            metrics.record_request_duration(
+                EventType::GuardrailInspect,
+                Duration::from_micros(10_000 + i * 40),
+            );
+        }
+
+        // Fixed buckets put all of these in (10ms, 50ms]
+        let p99 = metrics.snapshot().request_duration.percentile_micros(99.0);
+        assert!((49_000..=50_400).contains(&p99), "p99 was {}", p99);
+        let p50 = metrics.snapshot().request_duration.percentile_micros(50.0);
+        assert!((29_400..=30_600).contains(&p50), "p50 was {}", p50);
+
+        let mut writer = MetricsWriter::new();
+        metrics.write_metrics(&mut writer, "waf");
+        let output = writer.render();
+        assert!(output
+            .contains("# TYPE zentinel_agent_pool_request_duration_quantile_seconds summary\n"));
+        assert!(output.contains(
+            "zentinel_agent_pool_request_duration_quantile_seconds{agent_id=\"waf\",event_type=\"guardrail_inspect\",quantile=\"0.99\"}"
+        ));
+    }
+
     #[test]
     fn test_flow_control_metrics() {
         let metrics = ProtocolMetrics::new();
@@ -600,9 +1182,9 @@ mod tests {
     fn test_prometheus_export() {
         let metrics = ProtocolMetrics::new();
 
-        metrics.inc_requests();
+        metrics.inc_requests(EventType::RequestHeaders);
         metrics.set_healthy_connections(3);
-        metrics.record_serialization_time(Duration::from_micros(100));
+        metrics.record_serialization_time(EventType::RequestHeaders, Duration::from_micros(100));
 
         let output = metrics.to_prometheus("agent_protocol");
 
@@ -610,4 +1192,25 @@ mod tests {
         assert!(output.contains("agent_protocol_healthy_connections 3"));
         assert!(output.contains("agent_protocol_serialization_seconds"));
     }
+
+    #[test]
+    fn test_write_metrics() {
+        let metrics = ProtocolMetrics::new();
+
+        metrics.inc_requests(EventType::RequestHeaders);
+        metrics.set_healthy_connections(3);
+        metrics.record_serialization_time(EventType::RequestHeaders, Duration::from_micros(100));
+
+        let mut writer = MetricsWriter::new();
+        metrics.write_metrics(&mut writer, "waf");
+        let output = writer.render();
+
+        assert!(output.contains(
+            "zentinel_agent_pool_requests_total{agent_id=\"waf\",event_type=\"request_headers\"} 1\n"
+        ));
+        assert!(output.contains("zentinel_agent_pool_healthy_connections{agent_id=\"waf\"} 3\n"));
+        assert!(output.contains(
+            "zentinel_agent_pool_serialization_seconds_bucket{agent_id=\"waf\",event_type=\"request_headers\",le=\"0.0001\"} 1\n"
+        ));
+    }
 }
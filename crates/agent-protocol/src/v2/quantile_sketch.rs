@@ -0,0 +1,182 @@
+//! Lock-free quantile sketch for protocol latency metrics.
+//!
+//! Fixed histogram buckets only bound a quantile to the bucket it falls in,
+//! so a p99 between 10ms and 50ms reads as 50ms. The sketch keeps a
+//! log-linear histogram in the style of HdrHistogram instead:
+//! - values below 64 are counted exactly
+//! - above that, every power of two is split into 32 linear sub-buckets, so
+//!   a reported quantile is within ~1.6% of the recorded value
+//!
+//! Values are microseconds and saturate at 2^40 µs (~12.7 days). The sketch
+//! is about 9 KiB of counters and recording is a single atomic increment.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Values below this are counted in their own bucket
+const EXACT_VALUES: u64 = 64;
+/// log2 of the sub-buckets per power of two
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+/// Highest power of two tracked; larger values are clamped into it
+const MAX_EXPONENT: u32 = 39;
+/// First power of two past the exact range (2^6 = 64)
+const MIN_EXPONENT: u32 = EXACT_VALUES.trailing_zeros();
+const BUCKETS: usize =
+    EXACT_VALUES as usize + (MAX_EXPONENT - MIN_EXPONENT + 1) as usize * SUB_BUCKETS;
+
+/// Concurrent log-linear histogram of microsecond values
+#[derive(Debug)]
+pub struct QuantileSketch {
+    counts: Box<[AtomicU64]>,
+    max: AtomicU64,
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self {
+            counts: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl QuantileSketch {
+    /// Create an empty sketch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a value in microseconds
+    #[inline]
+    pub fn record(&self, micros: u64) {
+        self.counts[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+        self.max.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Copy the non-empty buckets
+    pub fn snapshot(&self) -> SketchSnapshot {
+        let buckets: Vec<(u32, u64)> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, count)| {
+                let count = count.load(Ordering::Relaxed);
+                (count > 0).then_some((index as u32, count))
+            })
+            .collect();
+        SketchSnapshot {
+            count: buckets.iter().map(|&(_, count)| count).sum(),
+            max: self.max.load(Ordering::Relaxed),
+            buckets,
+        }
+    }
+}
+
+/// Point-in-time copy of a [`QuantileSketch`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SketchSnapshot {
+    /// `(bucket index, count)` of the non-empty buckets, in ascending order
+    buckets: Vec<(u32, u64)>,
+    /// Total count
+    pub count: u64,
+    /// Largest recorded value in microseconds
+    pub max: u64,
+}
+
+impl SketchSnapshot {
+    /// Value at quantile `q` (0.0-1.0) in microseconds; 0 when empty
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((self.count as f64 * q.clamp(0.0, 1.0)).ceil() as u64).max(1);
+        if rank >= self.count {
+            return self.max;
+        }
+        let mut cumulative = 0u64;
+        for &(index, count) in &self.buckets {
+            cumulative += count;
+            if cumulative >= rank {
+                return bucket_value(index as usize).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+/// Bucket a value falls in
+#[inline]
+fn bucket_index(micros: u64) -> usize {
+    if micros < EXACT_VALUES {
+        return micros as usize;
+    }
+    let exponent = (63 - micros.leading_zeros()).min(MAX_EXPONENT);
+    let micros = micros.min((1u64 << (MAX_EXPONENT + 1)) - 1);
+    let sub_bucket = (micros >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    EXACT_VALUES as usize + (exponent - MIN_EXPONENT) as usize * SUB_BUCKETS + sub_bucket
+}
+
+/// Midpoint of a bucket, the value reported for everything in it
+fn bucket_value(index: usize) -> u64 {
+    if index < EXACT_VALUES as usize {
+        return index as u64;
+    }
+    let offset = index - EXACT_VALUES as usize;
+    let exponent = MIN_EXPONENT + (offset / SUB_BUCKETS) as u32;
+    let sub_bucket = (offset % SUB_BUCKETS) as u64;
+    let width = 1u64 << (exponent - SUB_BUCKET_BITS);
+    (SUB_BUCKETS as u64 + sub_bucket) * width + width / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_index_is_monotonic() {
+        let mut last = 0;
+        for micros in (0..100_000).chain([1 << 39, (1 << 40) - 1, 1 << 40, u64::MAX]) {
+            let index = bucket_index(micros);
+            assert!(index >= last, "{} went backwards", micros);
+            assert!(index < BUCKETS);
+            last = index;
+        }
+        assert_eq!(bucket_index(u64::MAX), BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_quantiles_are_accurate() {
+        let sketch = QuantileSketch::new();
+        for micros in 1..=100_000 {
+            sketch.record(micros);
+        }
+        let snap = sketch.snapshot();
+        assert_eq!(snap.count, 100_000);
+        assert_eq!(snap.max, 100_000);
+
+        for (q, expected) in [(0.5, 50_000.0), (0.9, 90_000.0), (0.99, 99_000.0)] {
+            let value = snap.quantile(q) as f64;
+            assert!(
+                (value - expected).abs() / expected < 0.02,
+                "q{} was {}, expected {}",
+                q,
+                value,
+                expected
+            );
+        }
+        assert_eq!(snap.quantile(1.0), 100_000);
+    }
+
+    #[test]
+    fn test_small_values_are_exact() {
+        let sketch = QuantileSketch::new();
+        for micros in [3, 3, 7, 42] {
+            sketch.record(micros);
+        }
+        let snap = sketch.snapshot();
+        assert_eq!(snap.quantile(0.5), 3);
+        assert_eq!(snap.quantile(0.75), 7);
+        assert_eq!(snap.quantile(1.0), 42);
+        assert_eq!(SketchSnapshot::default().quantile(0.99), 0);
+    }
+}
@@ -39,8 +39,9 @@ use tracing::{debug, error, info, warn};
 
 use crate::v2::client::FlowState;
 use crate::v2::pool::CHANNEL_BUFFER_SIZE;
+use crate::v2::protocol_metrics::{DurationRecorder, ProtocolMetrics};
 use crate::v2::uds::{read_message, write_message, MessageType, UdsCapabilities};
-use crate::v2::{AgentCapabilities, AgentPool, PROTOCOL_VERSION_2};
+use crate::v2::{accept_agent_version, adapt_capabilities, AgentCapabilities, AgentPool};
 use crate::{AgentProtocolError, AgentResponse};
 
 /// Configuration for the reverse connection listener.
@@ -76,7 +77,8 @@ impl Default for ReverseConnectionConfig {
 /// Registration request sent by agent when connecting to proxy.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RegistrationRequest {
-    /// Protocol version the agent supports
+    /// Protocol version the agent speaks (missing for v1 agents)
+    #[serde(default)]
     pub protocol_version: u32,
     /// Agent's unique identifier
     pub agent_id: String,
@@ -218,18 +220,21 @@ impl ReverseConnectionListener {
         let agent_id = registration.agent_id.clone();
 
         // Validate registration
-        if let Err(e) = self.validate_registration(&registration) {
-            let response = RegistrationResponse {
-                success: false,
-                error: Some(e.to_string()),
-                proxy_id: "zentinel-proxy".to_string(),
-                proxy_version: env!("CARGO_PKG_VERSION").to_string(),
-                connection_id: String::new(),
-            };
-            self.send_registration_response(&mut writer, &response)
-                .await?;
-            return Err(e);
-        }
+        let protocol_version = match self.validate_registration(&registration) {
+            Ok(version) => version,
+            Err(e) => {
+                let response = RegistrationResponse {
+                    success: false,
+                    error: Some(e.to_string()),
+                    proxy_id: "zentinel-proxy".to_string(),
+                    proxy_version: env!("CARGO_PKG_VERSION").to_string(),
+                    connection_id: String::new(),
+                };
+                self.send_registration_response(&mut writer, &response)
+                    .await?;
+                return Err(e);
+            }
+        };
 
         // Generate connection ID
         let connection_id = format!(
@@ -259,7 +264,7 @@ impl ReverseConnectionListener {
         );
 
         // Convert capabilities
-        let capabilities: AgentCapabilities = registration.capabilities.into();
+        let capabilities = adapt_capabilities(protocol_version, registration.capabilities.into());
 
         // Create the reverse connection client wrapper
         let client = ReverseConnectionClient::new(
@@ -309,18 +314,14 @@ impl ReverseConnectionListener {
         write_message(writer, MessageType::HandshakeResponse, &payload).await
     }
 
-    /// Validate a registration request.
+    /// Validate a registration request, returning the protocol version to use.
     fn validate_registration(
         &self,
         registration: &RegistrationRequest,
-    ) -> Result<(), AgentProtocolError> {
+    ) -> Result<u32, AgentProtocolError> {
         // Check protocol version
-        if registration.protocol_version != PROTOCOL_VERSION_2 {
-            return Err(AgentProtocolError::VersionMismatch {
-                expected: PROTOCOL_VERSION_2,
-                actual: registration.protocol_version,
-            });
-        }
+        let protocol_version =
+            accept_agent_version(&registration.agent_id, registration.protocol_version)?;
 
         // Check agent ID is not empty
         if registration.agent_id.is_empty() {
@@ -346,7 +347,7 @@ impl ReverseConnectionListener {
             ));
         }
 
-        Ok(())
+        Ok(protocol_version)
     }
 }
 
@@ -375,6 +376,8 @@ pub struct ReverseConnectionClient {
     in_flight: std::sync::atomic::AtomicU64,
     /// Flow control state - tracks if agent has requested pause
     flow_state: Arc<RwLock<FlowState>>,
+    /// Pool metrics that serialization time is recorded into
+    protocol_metrics: Option<Arc<ProtocolMetrics>>,
 }
 
 impl ReverseConnectionClient {
@@ -464,9 +467,23 @@ impl ReverseConnectionClient {
             timeout,
             in_flight: std::sync::atomic::AtomicU64::new(0),
             flow_state: Arc::new(RwLock::new(FlowState::Normal)),
+            protocol_metrics: None,
         }
     }
 
+    /// Record event serialization time into `metrics`.
+    pub fn set_protocol_metrics(&mut self, metrics: Arc<ProtocolMetrics>) {
+        self.protocol_metrics = Some(metrics);
+    }
+
+    /// Serialization timer, if the client reports into pool metrics.
+    fn serialization_timer(&self, msg_type: MessageType) -> Option<DurationRecorder<'_>> {
+        let event_type = msg_type.event_type()?;
+        self.protocol_metrics
+            .as_deref()
+            .map(|metrics| metrics.time_serialization(event_type))
+    }
+
     /// Get the agent ID.
     pub fn agent_id(&self) -> &str {
         &self.agent_id
@@ -542,6 +559,30 @@ impl ReverseConnectionClient {
             .await
     }
 
+    /// Send a binary request body chunk event.
+    ///
+    /// Reverse connections use JSON, so the bytes are base64-encoded here.
+    pub async fn send_request_body_chunk_binary(
+        &self,
+        event: &crate::BinaryRequestBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let encoded: crate::RequestBodyChunkEvent = event.clone().into();
+        self.send_request_body_chunk(&event.correlation_id, &encoded)
+            .await
+    }
+
+    /// Send a binary response body chunk event.
+    ///
+    /// Reverse connections use JSON, so the bytes are base64-encoded here.
+    pub async fn send_response_body_chunk_binary(
+        &self,
+        event: &crate::BinaryResponseBodyChunkEvent,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let encoded: crate::ResponseBodyChunkEvent = event.clone().into();
+        self.send_response_body_chunk(&event.correlation_id, &encoded)
+            .await
+    }
+
     /// Send an event and wait for response.
     async fn send_event<T: serde::Serialize>(
         &self,
@@ -551,6 +592,7 @@ impl ReverseConnectionClient {
     ) -> Result<AgentResponse, AgentProtocolError> {
         // Serialize event with correlation ID (before registering the pending
         // entry, so serialization failures cannot leak it)
+        let timer = self.serialization_timer(msg_type);
         let mut payload = serde_json::to_value(event)
             .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?;
 
@@ -563,6 +605,7 @@ impl ReverseConnectionClient {
 
         let payload_bytes = serde_json::to_vec(&payload)
             .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?;
+        drop(timer);
 
         let (tx, rx) = oneshot::channel();
         self.pending
@@ -694,6 +737,7 @@ mod tests {
                 supported_events: vec![1, 2],
                 features: Default::default(),
                 limits: Default::default(),
+                warmup: Vec::new(),
             },
             auth_token: None,
             metadata: None,
@@ -706,6 +750,39 @@ mod tests {
         assert_eq!(parsed.protocol_version, 2);
     }
 
+    #[tokio::test]
+    async fn test_validate_registration_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = ReverseConnectionListener::bind_uds(
+            dir.path().join("reverse.sock"),
+            ReverseConnectionConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        // v1 agents send neither a version nor features or limits
+        let legacy: RegistrationRequest = serde_json::from_value(serde_json::json!({
+            "agent_id": "old-waf",
+            "capabilities": {
+                "agent_id": "old-waf",
+                "name": "Old WAF",
+                "version": "0.9.0",
+                "supported_events": [1],
+            },
+            "auth_token": null,
+            "metadata": null,
+        }))
+        .unwrap();
+        assert_eq!(listener.validate_registration(&legacy).unwrap(), 1);
+
+        let mut future = legacy;
+        future.protocol_version = 3;
+        assert!(matches!(
+            listener.validate_registration(&future),
+            Err(AgentProtocolError::VersionMismatch { actual: 3, .. })
+        ));
+    }
+
     #[test]
     fn test_registration_response_serialization() {
         let response = RegistrationResponse {
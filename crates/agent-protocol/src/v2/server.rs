@@ -17,10 +17,14 @@ use crate::grpc_v2::{
     AgentToProxy, ProxyToAgent,
 };
 use crate::v2::pool::CHANNEL_BUFFER_SIZE;
-use crate::v2::{AgentCapabilities, HandshakeRequest, HandshakeResponse, HealthStatus};
+use crate::v2::{
+    negotiate_version, supported_versions, AgentCapabilities, ChunkReassembler, HandshakeRequest,
+    HandshakeResponse, HealthStatus,
+};
 use crate::{
-    AgentResponse, Decision, EventType, HeaderOp, RequestBodyChunkEvent, RequestCompleteEvent,
-    RequestHeadersEvent, RequestMetadata, ResponseBodyChunkEvent, ResponseHeadersEvent,
+    AgentResponse, BotSignals, ClientCertificate, Decision, EventType, HeaderOp, LatencyBreakdown,
+    MultipartPart, RequestBodyChunkEvent, RequestCompleteEvent, RequestHeadersEvent,
+    RequestMetadata, RequestTag, ResponseBodyChunkEvent, ResponseHeadersEvent, TagCardinality,
     WebSocketFrameEvent,
 };
 
@@ -87,9 +91,24 @@ pub trait AgentHandlerV2: Send + Sync {
     fn capabilities(&self) -> AgentCapabilities;
 
     /// Handle handshake request.
-    async fn on_handshake(&self, _request: HandshakeRequest) -> HandshakeResponse {
-        // Default: accept handshake with our capabilities
-        HandshakeResponse::success(self.capabilities())
+    async fn on_handshake(&self, request: HandshakeRequest) -> HandshakeResponse {
+        // Default: accept handshake with our capabilities, at the newest
+        // version both sides speak
+        let mut response = HandshakeResponse::success(self.capabilities());
+        if request.supported_versions.is_empty() {
+            return response;
+        }
+        match negotiate_version(&request.supported_versions) {
+            Some(version) => {
+                response.protocol_version = response.protocol_version.min(version);
+                response
+            }
+            None => HandshakeResponse::failure(format!(
+                "No common protocol version: proxy supports {:?}, agent supports {:?}",
+                request.supported_versions,
+                supported_versions()
+            )),
+        }
     }
 
     /// Handle a request headers event.
@@ -224,10 +243,13 @@ impl GrpcAgentServerV2 {
     /// Get the tonic service for this agent.
     pub fn into_service(self) -> AgentServiceV2Server<GrpcAgentHandlerV2> {
         trace!(agent_id = %self.id, "Converting to tonic v2 service");
+        let max_message_size = self.handler.capabilities().limits.max_message_size;
         AgentServiceV2Server::new(GrpcAgentHandlerV2 {
             id: self.id,
             handler: self.handler,
         })
+        .max_decoding_message_size(max_message_size)
+        .max_encoding_message_size(max_message_size)
     }
 
     /// Start the gRPC server on the given address.
@@ -276,6 +298,11 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
 
         debug!(agent_id = %agent_id, "Starting v2 process stream");
 
+        // Leading fragments of split body chunks, buffered until the final part
+        let limits = handler.capabilities().limits;
+        let mut reassembler =
+            ChunkReassembler::new(limits.max_body_size.max(limits.max_message_size));
+
         tokio::spawn(async move {
             let mut handshake_done = false;
 
@@ -292,7 +319,9 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
                     Some(grpc_v2::proxy_to_agent::Message::Handshake(req)) => {
                         trace!(agent_id = %agent_id, "Processing handshake");
                         let handshake_req = convert_handshake_request(req);
-                        let resp = handler.on_handshake(handshake_req).await;
+                        let mut resp = handler.on_handshake(handshake_req).await;
+                        // Fragment reassembly is handled here, independent of the handler
+                        resp.capabilities.features.chunk_reassembly = true;
                         handshake_done = resp.success;
                         Some(AgentToProxy {
                             message: Some(grpc_v2::agent_to_proxy::Message::Handshake(
@@ -320,11 +349,31 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
                         if !handshake_done {
                             continue;
                         }
-                        let event = convert_body_chunk_to_request(e);
+                        if e.fragment {
+                            buffer_fragment(&mut reassembler, &e);
+                            continue;
+                        }
+                        let mut event = convert_body_chunk_to_request(e);
                         let correlation_id = event.correlation_id.clone();
-                        let start = Instant::now();
-                        let resp = handler.on_request_body_chunk(event).await;
-                        let processing_time_ms = start.elapsed().as_millis() as u64;
+                        let data = std::mem::take(&mut event.data);
+                        let (resp, processing_time_ms) =
+                            match reassembler.complete(&correlation_id, event.chunk_index, data) {
+                                Ok(data) => {
+                                    event.data = data;
+                                    let start = Instant::now();
+                                    let resp = handler.on_request_body_chunk(event).await;
+                                    (resp, start.elapsed().as_millis() as u64)
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        agent_id = %agent_id,
+                                        correlation_id = %correlation_id,
+                                        error = %e,
+                                        "Dropping oversized RequestBodyChunk"
+                                    );
+                                    (payload_too_large(), 0)
+                                }
+                            };
                         Some(create_agent_response(
                             correlation_id,
                             resp,
@@ -350,11 +399,31 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
                         if !handshake_done {
                             continue;
                         }
-                        let event = convert_body_chunk_to_response(e);
+                        if e.fragment {
+                            buffer_fragment(&mut reassembler, &e);
+                            continue;
+                        }
+                        let mut event = convert_body_chunk_to_response(e);
                         let correlation_id = event.correlation_id.clone();
-                        let start = Instant::now();
-                        let resp = handler.on_response_body_chunk(event).await;
-                        let processing_time_ms = start.elapsed().as_millis() as u64;
+                        let data = std::mem::take(&mut event.data);
+                        let (resp, processing_time_ms) =
+                            match reassembler.complete(&correlation_id, event.chunk_index, data) {
+                                Ok(data) => {
+                                    event.data = data;
+                                    let start = Instant::now();
+                                    let resp = handler.on_response_body_chunk(event).await;
+                                    (resp, start.elapsed().as_millis() as u64)
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        agent_id = %agent_id,
+                                        correlation_id = %correlation_id,
+                                        error = %e,
+                                        "Dropping oversized ResponseBodyChunk"
+                                    );
+                                    (payload_too_large(), 0)
+                                }
+                            };
                         Some(create_agent_response(
                             correlation_id,
                             resp,
@@ -402,6 +471,7 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
                         })
                     }
                     Some(grpc_v2::proxy_to_agent::Message::Cancel(cancel)) => {
+                        reassembler.discard(&cancel.correlation_id);
                         debug!(
                             agent_id = %agent_id,
                             correlation_id = %cancel.correlation_id,
@@ -417,6 +487,10 @@ impl AgentServiceV2 for GrpcAgentHandlerV2 {
                         // Guardrail inspection - allow by default
                         None
                     }
+                    Some(grpc_v2::proxy_to_agent::Message::StateResponse(_)) => {
+                        // Only answers state requests, which handlers don't send
+                        None
+                    }
                     None => {
                         warn!(agent_id = %agent_id, "Empty message received");
                         None
@@ -680,6 +754,7 @@ fn convert_capabilities_to_grpc(caps: &AgentCapabilities) -> grpc_v2::AgentCapab
             cancellation: caps.features.cancellation,
             flow_control: caps.features.flow_control,
             health_reporting: caps.features.health_reporting,
+            chunk_reassembly: caps.features.chunk_reassembly,
         }),
         limits: Some(grpc_v2::AgentLimits {
             max_body_size: caps.limits.max_body_size as u64,
@@ -687,12 +762,27 @@ fn convert_capabilities_to_grpc(caps: &AgentCapabilities) -> grpc_v2::AgentCapab
             preferred_chunk_size: caps.limits.preferred_chunk_size as u64,
             max_memory: caps.limits.max_memory.map(|m| m as u64),
             max_processing_time_ms: caps.limits.max_processing_time_ms,
+            max_message_size: Some(caps.limits.max_message_size as u64),
         }),
         health_config: Some(grpc_v2::HealthConfig {
             report_interval_ms: caps.health.report_interval_ms,
             include_load_metrics: caps.health.include_load_metrics,
             include_resource_metrics: caps.health.include_resource_metrics,
         }),
+        warmup: caps
+            .warmup
+            .iter()
+            .map(|w| grpc_v2::WarmupRequest {
+                method: w.method.clone(),
+                uri: w.uri.clone(),
+                headers: crate::headers::iter_flat(&w.headers)
+                    .map(|(name, value)| grpc_v2::Header {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect(),
     }
 }
 
@@ -724,6 +814,34 @@ fn convert_request_headers_from_grpc(e: grpc_v2::RequestHeadersEvent) -> Request
             upstream_id: m.upstream_id,
             timestamp: format!("{}", m.timestamp_ms),
             traceparent: m.traceparent,
+            client_cert: m.client_cert.map(|c| ClientCertificate {
+                subject: c.subject,
+                sans: c.sans,
+                fingerprint_sha256: c.fingerprint_sha256,
+                serial_number: c.serial_number,
+                verified: c.verified,
+            }),
+            tags: m
+                .tags
+                .into_iter()
+                .map(|t| RequestTag {
+                    name: t.name,
+                    value: t.value,
+                    cardinality: if t.low_cardinality {
+                        TagCardinality::Low
+                    } else {
+                        TagCardinality::High
+                    },
+                })
+                .collect(),
+            bot_signals: m.bot_signals.map(|s| BotSignals {
+                ja3: s.ja3,
+                ja3_hash: s.ja3_hash,
+                ja4: s.ja4,
+                http2: s.http2,
+                header_order_hash: s.header_order_hash,
+            }),
+            dry_run: m.dry_run,
         },
         None => RequestMetadata {
             correlation_id: String::new(),
@@ -738,6 +856,10 @@ fn convert_request_headers_from_grpc(e: grpc_v2::RequestHeadersEvent) -> Request
             upstream_id: None,
             timestamp: String::new(),
             traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
         },
     };
 
@@ -757,6 +879,19 @@ fn convert_request_headers_from_grpc(e: grpc_v2::RequestHeadersEvent) -> Request
     }
 }
 
+/// Buffer a leading fragment of a split body chunk. Fragments get no response;
+/// the final part is answered once reassembled.
+fn buffer_fragment(reassembler: &mut ChunkReassembler, e: &grpc_v2::BodyChunkEvent) {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    // Leading fragments are a multiple of 3 bytes, so their encodings concatenate
+    reassembler.push_fragment(&e.correlation_id, e.chunk_index, &STANDARD.encode(&e.data));
+}
+
+/// Response for a body chunk whose fragments exceeded the reassembly buffer.
+fn payload_too_large() -> AgentResponse {
+    AgentResponse::block(413, Some("Payload Too Large".to_string()))
+}
+
 fn convert_body_chunk_to_request(e: grpc_v2::BodyChunkEvent) -> RequestBodyChunkEvent {
     use base64::{engine::general_purpose::STANDARD, Engine as _};
     RequestBodyChunkEvent {
@@ -766,6 +901,12 @@ fn convert_body_chunk_to_request(e: grpc_v2::BodyChunkEvent) -> RequestBodyChunk
         total_size: e.total_size.map(|s| s as usize),
         chunk_index: e.chunk_index,
         bytes_received: e.bytes_transferred as usize,
+        part: e.part.map(|part| MultipartPart {
+            index: part.index,
+            name: part.name,
+            filename: part.filename,
+            content_type: part.content_type,
+        }),
     }
 }
 
@@ -806,6 +947,19 @@ fn convert_request_complete_from_grpc(e: grpc_v2::RequestCompleteEvent) -> Reque
         response_body_size: e.bytes_sent as usize,
         upstream_attempts: 1,
         error: e.error,
+        latency: e
+            .latency
+            .map(|l| LatencyBreakdown {
+                dns_ms: l.dns_ms,
+                connect_ms: l.connect_ms,
+                tls_ms: l.tls_ms,
+                agent_total_ms: l.agent_total_ms,
+                agents_ms: l.agents_ms.into_iter().collect(),
+                upstream_ttfb_ms: l.upstream_ttfb_ms,
+                upstream_body_ms: l.upstream_body_ms,
+                downstream_write_ms: l.downstream_write_ms,
+            })
+            .unwrap_or_default(),
     }
 }
 
@@ -876,6 +1030,16 @@ fn create_agent_response(
         .map(convert_header_op_to_grpc)
         .collect();
 
+    let tags: Vec<grpc_v2::RequestTag> = resp
+        .tags
+        .into_iter()
+        .map(|t| grpc_v2::RequestTag {
+            low_cardinality: t.cardinality == TagCardinality::Low,
+            name: t.name,
+            value: t.value,
+        })
+        .collect();
+
     let audit = Some(grpc_v2::AuditMetadata {
         tags: resp.audit.tags,
         rule_ids: resp.audit.rule_ids,
@@ -899,6 +1063,7 @@ fn create_agent_response(
                 audit,
                 processing_time_ms: Some(processing_time_ms),
                 needs_more: resp.needs_more,
+                tags,
             },
         )),
     }
@@ -948,4 +1113,76 @@ mod tests {
         let server = GrpcAgentServerV2::new("test", Box::new(TestHandlerV2));
         assert_eq!(server.id, "test");
     }
+
+    #[tokio::test]
+    async fn test_default_handshake_negotiates_version() {
+        let mut request = HandshakeRequest::new("zentinel", "1.0.0");
+        let response = TestHandlerV2.on_handshake(request.clone()).await;
+        assert!(response.success);
+        assert_eq!(response.protocol_version, 2);
+
+        request.supported_versions = vec![1];
+        let response = TestHandlerV2.on_handshake(request.clone()).await;
+        assert_eq!(response.protocol_version, 1);
+
+        request.supported_versions = vec![3];
+        assert!(!TestHandlerV2.on_handshake(request).await.success);
+    }
+
+    struct BodyLengthHandler;
+
+    #[async_trait]
+    impl AgentHandlerV2 for BodyLengthHandler {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::new("test-grpc-split", "Body Length Agent", "1.0.0")
+                .with_event(EventType::RequestBodyChunk)
+        }
+
+        async fn on_request_body_chunk(&self, event: RequestBodyChunkEvent) -> AgentResponse {
+            use base64::{engine::general_purpose::STANDARD, Engine as _};
+            let len = STANDARD.decode(&event.data).map(|d| d.len()).unwrap_or(0);
+            AgentResponse::default_allow().add_request_header(HeaderOp::Set {
+                name: "x-body-len".to_string(),
+                value: len.to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_chunk_is_split_and_reassembled() {
+        use crate::v2::client::AgentClientV2;
+        use std::time::Duration;
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = GrpcAgentServerV2::new("test-split", Box::new(BodyLengthHandler));
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run(addr).await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut client = AgentClientV2::new(
+            "test-agent",
+            format!("http://{}", addr),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        client.set_max_message_size(1024);
+        client.connect().await.unwrap();
+
+        // 10KB body needs many 1KB messages
+        let body: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let event = crate::BinaryRequestBodyChunkEvent::new("split-cid", body, 0, true);
+        let response = client.send_request_body_chunk_binary(&event).await.unwrap();
+        assert!(response.request_headers.iter().any(|h| matches!(
+            h,
+            HeaderOp::Set { name, value } if name == "x-body-len" && value == "10000"
+        )));
+
+        client.close().await.unwrap();
+        server_handle.abort();
+    }
 }
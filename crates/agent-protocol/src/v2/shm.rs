@@ -0,0 +1,745 @@
+//! Shared-memory body transport for co-located agents.
+//!
+//! Large body chunks sent over UDS are copied into the socket by the proxy and
+//! out of it by the agent. With shared memory the proxy instead writes the
+//! chunk into a slot of a memory-mapped segment and sends only a small
+//! descriptor (`0x1A`/`0x1B` messages); the agent reads the bytes in place.
+//!
+//! # Segment Layout
+//!
+//! ```text
+//! ┌──────────────┬───────────────────────────┬─────────┬──────────────────────┐
+//! │ Header (64B) │ Slot headers (16B × N)    │ padding │ Slot data (size × N) │
+//! └──────────────┴───────────────────────────┴─────────┴──────────────────────┘
+//! ```
+//!
+//! Each slot header holds a reference count, a generation and the payload
+//! length. The proxy claims a free slot (count 0 → 1), bumps its generation,
+//! copies the chunk and keeps its reference until the agent has answered.
+//! The agent takes its own reference while reading, so a slot is only reused
+//! once both sides have let go. Descriptors carry the generation, which lets
+//! the agent reject a descriptor for a slot that has since been recycled.
+//!
+//! # Lifecycle and Crash Cleanup
+//!
+//! Every UDS connection gets its own segment, created by the proxy and
+//! offered in the handshake. The proxy unlinks the file when the connection
+//! is dropped, so references leaked by a crashed agent disappear with the
+//! segment. Segment files are named after the owning proxy's uid, PID and
+//! process start time. When a new segment is created, files of the same uid
+//! whose process is gone are removed; a reused PID does not match the start
+//! time, and segments of other users are never touched.
+//!
+//! # Feature Flag
+//!
+//! Requires the `mmap-buffers` feature.
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use memmap2::MmapMut;
+use tracing::{debug, warn};
+
+use crate::v2::uds::{UdsShmDescriptor, UdsShmOffer};
+use crate::AgentProtocolError;
+
+/// Magic bytes at the start of every segment.
+const SHM_MAGIC: u64 = u64::from_be_bytes(*b"ZNTSHM01");
+
+/// Segment layout version.
+const SHM_VERSION: u32 = 1;
+
+/// Size of the segment header.
+const HEADER_SIZE: usize = 64;
+
+/// Size of one slot header (refcount, generation, length).
+const SLOT_HEADER_SIZE: usize = 16;
+
+/// Slot data starts on a page boundary.
+const DATA_ALIGN: usize = 4096;
+
+/// File name prefix for segments.
+const SEGMENT_PREFIX: &str = "zentinel-shm-";
+
+/// Configuration for the shared-memory body transport.
+#[derive(Debug, Clone)]
+pub struct ShmConfig {
+    /// Directory for segment files.
+    ///
+    /// Default: `/dev/shm` when present, otherwise the system temp directory
+    pub dir: PathBuf,
+    /// Number of slots per segment (upper bound on chunks in flight).
+    ///
+    /// Default: 64
+    pub slot_count: u32,
+    /// Size of each slot in bytes. Larger chunks are sent inline.
+    ///
+    /// Default: 1MB
+    pub slot_size: usize,
+    /// Chunks smaller than this are sent inline; the descriptor round-trip
+    /// does not pay off for small payloads.
+    ///
+    /// Default: 64KB
+    pub min_chunk_size: usize,
+    /// Permission bits for segment files. Widen this if the agent runs as a
+    /// different user than the proxy.
+    ///
+    /// Default: 0o600
+    pub mode: u32,
+}
+
+impl Default for ShmConfig {
+    fn default() -> Self {
+        let shm = Path::new("/dev/shm");
+        Self {
+            dir: if shm.is_dir() {
+                shm.to_path_buf()
+            } else {
+                std::env::temp_dir()
+            },
+            slot_count: 64,
+            slot_size: 1024 * 1024,
+            min_chunk_size: 64 * 1024,
+            mode: 0o600,
+        }
+    }
+}
+
+/// Byte offset of the first slot's data.
+fn data_offset(slot_count: u32) -> usize {
+    let headers = HEADER_SIZE + SLOT_HEADER_SIZE * slot_count as usize;
+    headers.div_ceil(DATA_ALIGN) * DATA_ALIGN
+}
+
+/// Total segment size.
+fn segment_len(slot_count: u32, slot_size: usize) -> usize {
+    data_offset(slot_count) + slot_count as usize * slot_size
+}
+
+/// A mapped segment with typed access to its header and slots.
+struct Mapping {
+    map: MmapMut,
+    slot_count: u32,
+    slot_size: usize,
+}
+
+// SAFETY: all shared mutation of the mapping goes through atomics in the slot
+// headers; slot data is only written by the holder of an exclusive claim
+// (refcount 0 → 1) and only read while a reference is held.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn base(&self) -> *mut u8 {
+        self.map.as_ptr() as *mut u8
+    }
+
+    fn header_u64(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.map[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn header_u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.map[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn refcount(&self, slot: u32) -> &AtomicU32 {
+        // SAFETY: slot < slot_count is checked by callers; the offset is
+        // 16-byte aligned within a page-aligned mapping.
+        unsafe { &*(self.base().add(self.slot_header(slot)) as *const AtomicU32) }
+    }
+
+    fn generation(&self, slot: u32) -> &AtomicU32 {
+        // SAFETY: as above
+        unsafe { &*(self.base().add(self.slot_header(slot) + 4) as *const AtomicU32) }
+    }
+
+    fn len(&self, slot: u32) -> &AtomicU64 {
+        // SAFETY: as above
+        unsafe { &*(self.base().add(self.slot_header(slot) + 8) as *const AtomicU64) }
+    }
+
+    fn slot_header(&self, slot: u32) -> usize {
+        HEADER_SIZE + SLOT_HEADER_SIZE * slot as usize
+    }
+
+    fn slot_data(&self, slot: u32) -> *mut u8 {
+        // SAFETY: slot < slot_count, so the offset is within the mapping
+        unsafe {
+            self.base()
+                .add(data_offset(self.slot_count) + slot as usize * self.slot_size)
+        }
+    }
+}
+
+/// Proxy-side shared-memory segment.
+///
+/// Created per UDS connection and offered to the agent during the handshake.
+/// The segment file is removed when the ring is dropped.
+pub struct ShmRing {
+    path: PathBuf,
+    mapping: Mapping,
+    min_chunk_size: usize,
+    next: AtomicU32,
+}
+
+impl ShmRing {
+    /// Create a new segment in `config.dir`.
+    ///
+    /// Segments left behind by proxies that are no longer running are
+    /// removed first.
+    pub fn create(config: &ShmConfig) -> io::Result<Self> {
+        if config.slot_count == 0 || config.slot_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shared memory slot_count and slot_size must be non-zero",
+            ));
+        }
+
+        sweep_stale_segments(&config.dir);
+
+        static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+        let owner = SegmentOwner::current();
+        let path = config.dir.join(format!(
+            "{}{}-{}",
+            SEGMENT_PREFIX,
+            owner,
+            SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(config.mode)
+            .open(&path)?;
+
+        let result = Self::init(file, &path, config);
+        if result.is_err() {
+            let _ = fs::remove_file(&path);
+        }
+        result
+    }
+
+    fn init(file: File, path: &Path, config: &ShmConfig) -> io::Result<Self> {
+        file.set_len(segment_len(config.slot_count, config.slot_size) as u64)?;
+        // SAFETY: the file was just created by us with create_new; only
+        // agents we hand the path to map it, and they follow the slot protocol.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+
+        // The file is zero-filled, so all slots start free at generation 0
+        map[0..8].copy_from_slice(&SHM_MAGIC.to_le_bytes());
+        map[8..12].copy_from_slice(&SHM_VERSION.to_le_bytes());
+        map[12..16].copy_from_slice(&config.slot_count.to_le_bytes());
+        map[16..24].copy_from_slice(&(config.slot_size as u64).to_le_bytes());
+        map[24..28].copy_from_slice(&std::process::id().to_le_bytes());
+
+        debug!(
+            path = %path.display(),
+            slot_count = config.slot_count,
+            slot_size = config.slot_size,
+            "Created shared memory segment"
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            mapping: Mapping {
+                map,
+                slot_count: config.slot_count,
+                slot_size: config.slot_size,
+            },
+            min_chunk_size: config.min_chunk_size,
+            next: AtomicU32::new(0),
+        })
+    }
+
+    /// Path of the segment file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Handshake offer describing this segment.
+    pub fn offer(&self) -> UdsShmOffer {
+        UdsShmOffer {
+            path: self.path.to_string_lossy().into_owned(),
+            slot_count: self.mapping.slot_count,
+            slot_size: self.mapping.slot_size as u64,
+        }
+    }
+
+    /// Whether a chunk of `len` bytes should go through shared memory.
+    pub fn accepts(&self, len: usize) -> bool {
+        len >= self.min_chunk_size && len <= self.mapping.slot_size
+    }
+
+    /// Copy `data` into a free slot.
+    ///
+    /// Returns `None` when the chunk does not fit in a slot or every slot is
+    /// still referenced; callers fall back to sending the chunk inline.
+    pub fn write(&self, data: &[u8]) -> Option<ShmSlot<'_>> {
+        if data.len() > self.mapping.slot_size {
+            return None;
+        }
+
+        let slot_count = self.mapping.slot_count;
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for i in 0..slot_count {
+            let slot = start.wrapping_add(i) % slot_count;
+            if self
+                .mapping
+                .refcount(slot)
+                .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            // Bump the generation before touching the data so stale
+            // descriptors for this slot are rejected from here on
+            let generation = self
+                .mapping
+                .generation(slot)
+                .fetch_add(1, Ordering::AcqRel)
+                .wrapping_add(1);
+            // SAFETY: we hold the only claim on this slot and data fits
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    data.as_ptr(),
+                    self.mapping.slot_data(slot),
+                    data.len(),
+                );
+            }
+            self.mapping
+                .len(slot)
+                .store(data.len() as u64, Ordering::Release);
+
+            return Some(ShmSlot {
+                ring: self,
+                descriptor: UdsShmDescriptor {
+                    slot,
+                    generation,
+                    len: data.len() as u64,
+                },
+            });
+        }
+
+        None
+    }
+
+    /// Number of slots currently referenced by either side.
+    pub fn slots_in_use(&self) -> usize {
+        (0..self.mapping.slot_count)
+            .filter(|&slot| self.mapping.refcount(slot).load(Ordering::Relaxed) > 0)
+            .count()
+    }
+}
+
+impl Drop for ShmRing {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!(path = %self.path.display(), error = %e, "Failed to remove shared memory segment");
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ShmRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShmRing")
+            .field("path", &self.path)
+            .field("slot_count", &self.mapping.slot_count)
+            .field("slot_size", &self.mapping.slot_size)
+            .finish()
+    }
+}
+
+/// Proxy-side reference to a written slot, released on drop.
+pub struct ShmSlot<'a> {
+    ring: &'a ShmRing,
+    descriptor: UdsShmDescriptor,
+}
+
+impl ShmSlot<'_> {
+    /// Descriptor to send to the agent.
+    pub fn descriptor(&self) -> &UdsShmDescriptor {
+        &self.descriptor
+    }
+}
+
+impl Drop for ShmSlot<'_> {
+    fn drop(&mut self) {
+        self.ring
+            .mapping
+            .refcount(self.descriptor.slot)
+            .fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Agent-side view of a segment offered by the proxy.
+pub struct ShmSegment {
+    mapping: Mapping,
+}
+
+impl ShmSegment {
+    /// Map the segment described by a handshake offer.
+    pub fn open(offer: &UdsShmOffer) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&offer.path)?;
+
+        let slot_size = usize::try_from(offer.slot_size)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "slot size too large"))?;
+        let expected = segment_len(offer.slot_count, slot_size);
+        if file.metadata()?.len() != expected as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shared memory segment size does not match offer",
+            ));
+        }
+
+        // SAFETY: the proxy created this segment and follows the slot protocol
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let mapping = Mapping {
+            map,
+            slot_count: offer.slot_count,
+            slot_size,
+        };
+
+        if mapping.header_u64(0) != SHM_MAGIC
+            || mapping.header_u32(8) != SHM_VERSION
+            || mapping.header_u32(12) != offer.slot_count
+            || mapping.header_u64(16) != offer.slot_size
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shared memory segment header does not match offer",
+            ));
+        }
+
+        Ok(Self { mapping })
+    }
+
+    /// Borrow the chunk a descriptor points at.
+    ///
+    /// Holds a reference on the slot until the returned chunk is dropped.
+    pub fn read(&self, descriptor: &UdsShmDescriptor) -> Result<ShmChunk<'_>, AgentProtocolError> {
+        let slot = descriptor.slot;
+        if slot >= self.mapping.slot_count || descriptor.len > self.mapping.slot_size as u64 {
+            return Err(AgentProtocolError::InvalidMessage(format!(
+                "Shared memory descriptor out of range: slot {} len {}",
+                slot, descriptor.len
+            )));
+        }
+
+        // Only join a slot the proxy still holds; a free slot means the
+        // descriptor is stale
+        let refcount = self.mapping.refcount(slot);
+        let mut current = refcount.load(Ordering::Acquire);
+        loop {
+            if current == 0 {
+                return Err(stale_descriptor(descriptor));
+            }
+            match refcount.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+
+        let chunk = ShmChunk {
+            segment: self,
+            slot,
+            len: descriptor.len as usize,
+        };
+        if self.mapping.generation(slot).load(Ordering::Acquire) != descriptor.generation
+            || self.mapping.len(slot).load(Ordering::Acquire) != descriptor.len
+        {
+            // Dropping the chunk releases our reference
+            return Err(stale_descriptor(descriptor));
+        }
+        Ok(chunk)
+    }
+}
+
+fn stale_descriptor(descriptor: &UdsShmDescriptor) -> AgentProtocolError {
+    AgentProtocolError::InvalidMessage(format!(
+        "Stale shared memory descriptor: slot {} generation {}",
+        descriptor.slot, descriptor.generation
+    ))
+}
+
+impl std::fmt::Debug for ShmSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShmSegment")
+            .field("slot_count", &self.mapping.slot_count)
+            .field("slot_size", &self.mapping.slot_size)
+            .finish()
+    }
+}
+
+/// Agent-side reference to a chunk in shared memory, released on drop.
+pub struct ShmChunk<'a> {
+    segment: &'a ShmSegment,
+    slot: u32,
+    len: usize,
+}
+
+impl std::ops::Deref for ShmChunk<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: we hold a reference on the slot, so the proxy will not
+        // reclaim or rewrite it, and len was validated against slot_size
+        unsafe { std::slice::from_raw_parts(self.segment.mapping.slot_data(self.slot), self.len) }
+    }
+}
+
+impl Drop for ShmChunk<'_> {
+    fn drop(&mut self) {
+        self.segment
+            .mapping
+            .refcount(self.slot)
+            .fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Process that created a segment, encoded in its file name as
+/// `{uid}-{pid}-{start_time}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SegmentOwner {
+    uid: u32,
+    pid: u32,
+    /// Process start time in clock ticks since boot (`/proc/<pid>/stat`),
+    /// which tells a reused PID apart from the original process
+    start_time: u64,
+}
+
+impl SegmentOwner {
+    /// The current process. Fields that cannot be read are 0.
+    fn current() -> Self {
+        let pid = std::process::id();
+        Self {
+            uid: fs::metadata("/proc/self").map_or(0, |m| m.uid()),
+            pid,
+            start_time: process_start_time(pid).unwrap_or(0),
+        }
+    }
+
+    /// Parse the owner from a segment file name.
+    fn from_file_name(name: &str) -> Option<Self> {
+        let mut parts = name.strip_prefix(SEGMENT_PREFIX)?.split('-');
+        Some(Self {
+            uid: parts.next()?.parse().ok()?,
+            pid: parts.next()?.parse().ok()?,
+            start_time: parts.next()?.parse().ok()?,
+        })
+    }
+
+    /// Whether the owning process is still running.
+    fn is_running(&self) -> bool {
+        process_start_time(self.pid) == Some(self.start_time)
+    }
+}
+
+impl std::fmt::Display for SegmentOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}-{}", self.uid, self.pid, self.start_time)
+    }
+}
+
+/// Start time of a running process, or `None` if it does not exist.
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces; fields after it are plain.
+    // `starttime` is field 22, the 20th after the closing parenthesis.
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(19)?
+        .parse()
+        .ok()
+}
+
+/// Remove segments of this user whose owning proxy is no longer running.
+///
+/// Liveness is checked through `/proc`; on systems without it nothing is
+/// removed. Segments of other users, or owned by a different uid than their
+/// name claims, are left alone.
+fn sweep_stale_segments(dir: &Path) {
+    if !Path::new("/proc").is_dir() {
+        return;
+    }
+    let own_uid = SegmentOwner::current().uid;
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Some(owner) = entry
+            .file_name()
+            .to_str()
+            .and_then(SegmentOwner::from_file_name)
+        else {
+            continue;
+        };
+        if owner.uid != own_uid || !entry.metadata().is_ok_and(|m| m.uid() == own_uid) {
+            continue;
+        }
+
+        if !owner.is_running() {
+            debug!(
+                path = %entry.path().display(),
+                pid = owner.pid,
+                "Removing stale shared memory segment"
+            );
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(dir: &Path) -> ShmConfig {
+        ShmConfig {
+            dir: dir.to_path_buf(),
+            slot_count: 2,
+            slot_size: 4096,
+            min_chunk_size: 16,
+            mode: 0o600,
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_across_mappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+        let segment = ShmSegment::open(&ring.offer()).unwrap();
+
+        let slot = ring.write(b"hello shared memory").unwrap();
+        let chunk = segment.read(slot.descriptor()).unwrap();
+        assert_eq!(&*chunk, b"hello shared memory");
+        assert_eq!(ring.slots_in_use(), 1);
+
+        drop(chunk);
+        drop(slot);
+        assert_eq!(ring.slots_in_use(), 0);
+    }
+
+    #[test]
+    fn test_referenced_slots_are_not_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+        let segment = ShmSegment::open(&ring.offer()).unwrap();
+
+        let first = ring.write(b"first").unwrap();
+        let descriptor = first.descriptor().clone();
+        let held = segment.read(&descriptor).unwrap();
+        drop(first);
+
+        // The agent still holds slot 0, so the next two writes use slot 1
+        // and then find no free slot
+        let second = ring.write(b"second").unwrap();
+        assert_ne!(second.descriptor().slot, descriptor.slot);
+        assert!(ring.write(b"third").is_none());
+        assert_eq!(&*held, b"first");
+    }
+
+    #[test]
+    fn test_stale_descriptor_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+        let segment = ShmSegment::open(&ring.offer()).unwrap();
+
+        let slot = ring.write(b"old").unwrap();
+        let stale = slot.descriptor().clone();
+        drop(slot);
+        assert!(segment.read(&stale).is_err());
+
+        // Recycle the slot under a new generation
+        let _a = ring.write(b"new").unwrap();
+        let _b = ring.write(b"new").unwrap();
+        assert!(segment.read(&stale).is_err());
+        assert_eq!(ring.slots_in_use(), 2);
+    }
+
+    #[test]
+    fn test_oversized_chunk_not_accepted() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+
+        assert!(!ring.accepts(8));
+        assert!(ring.accepts(1024));
+        assert!(!ring.accepts(8192));
+        assert!(ring.write(&[0u8; 8192]).is_none());
+    }
+
+    #[test]
+    fn test_segment_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+        let path = ring.path().to_path_buf();
+        assert!(path.exists());
+
+        drop(ring);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_stale_segments_swept() {
+        let dir = tempfile::tempdir().unwrap();
+        let me = SegmentOwner::current();
+        let segment = |owner: SegmentOwner| {
+            let path = dir.path().join(format!("{}{}-0", SEGMENT_PREFIX, owner));
+            fs::write(&path, b"").unwrap();
+            path
+        };
+
+        // PIDs are capped well below u32::MAX, so this one is never running
+        let dead = segment(SegmentOwner {
+            pid: u32::MAX,
+            ..me
+        });
+        // Our PID, but started at a different time: a reused PID
+        let reused = segment(SegmentOwner {
+            start_time: me.start_time + 1,
+            ..me
+        });
+        let other_user = segment(SegmentOwner {
+            uid: me.uid.wrapping_add(1),
+            pid: u32::MAX,
+            ..me
+        });
+        let live = segment(me);
+        let unrelated = dir.path().join("other-file");
+        fs::write(&unrelated, b"").unwrap();
+
+        let _ring = ShmRing::create(&test_config(dir.path())).unwrap();
+        if Path::new("/proc").is_dir() {
+            assert!(!dead.exists());
+            assert!(!reused.exists());
+        }
+        assert!(other_user.exists());
+        assert!(live.exists());
+        assert!(unrelated.exists());
+    }
+
+    #[test]
+    fn test_open_rejects_mismatched_offer() {
+        let dir = tempfile::tempdir().unwrap();
+        let ring = ShmRing::create(&test_config(dir.path())).unwrap();
+
+        let mut offer = ring.offer();
+        offer.slot_count = 3;
+        assert!(ShmSegment::open(&offer).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Session state messages for Protocol v2.
+//!
+//! Agents that need per-client state (auth sessions, rate counters) can keep
+//! it in the proxy's session store instead of running their own Redis. An
+//! agent sends `StateGet`, `StateSet` or `StateDelete`; the proxy answers
+//! each with a [`StateResponse`] carrying the same `request_id`.
+//!
+//! Keys are namespaced per agent by the proxy, and writes are subject to the
+//! agent's size quota.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Read a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateGetRequest {
+    pub request_id: String,
+    pub key: String,
+}
+
+/// Write a key, replacing any previous value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSetRequest {
+    pub request_id: String,
+    pub key: String,
+    pub value: String,
+    /// Time to live; the store's default TTL when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Remove a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDeleteRequest {
+    pub request_id: String,
+    pub key: String,
+}
+
+/// A state operation requested by an agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateRequest {
+    Get(StateGetRequest),
+    Set(StateSetRequest),
+    Delete(StateDeleteRequest),
+}
+
+impl StateRequest {
+    /// ID the response has to carry
+    pub fn request_id(&self) -> &str {
+        match self {
+            StateRequest::Get(r) => &r.request_id,
+            StateRequest::Set(r) => &r.request_id,
+            StateRequest::Delete(r) => &r.request_id,
+        }
+    }
+
+    /// Key the operation applies to
+    pub fn key(&self) -> &str {
+        match self {
+            StateRequest::Get(r) => &r.key,
+            StateRequest::Set(r) => &r.key,
+            StateRequest::Delete(r) => &r.key,
+        }
+    }
+}
+
+/// Result of a state operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateResponse {
+    pub request_id: String,
+    pub success: bool,
+    /// Value read by a `StateGet`; `None` if the key does not exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StateResponse {
+    pub fn ok(request_id: impl Into<String>, value: Option<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            success: true,
+            value,
+            error: None,
+        }
+    }
+
+    pub fn error(request_id: impl Into<String>, error: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            success: false,
+            value: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Proxy-side handler for agent state requests.
+#[async_trait]
+pub trait StateHandler: Send + Sync {
+    /// Execute `request` on behalf of `agent_id`.
+    async fn handle(&self, agent_id: &str, request: StateRequest) -> StateResponse;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_messages_serialization() {
+        let set = StateSetRequest {
+            request_id: "r1".to_string(),
+            key: "session:abc".to_string(),
+            value: "{\"user\":\"alice\"}".to_string(),
+            ttl_secs: None,
+        };
+        let json = serde_json::to_string(&set).unwrap();
+        assert!(!json.contains("ttl_secs"));
+        let parsed: StateSetRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, set);
+
+        let request = StateRequest::Set(set);
+        assert_eq!(request.request_id(), "r1");
+        assert_eq!(request.key(), "session:abc");
+
+        let response = StateResponse::error("r2", "quota exceeded");
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("value"));
+        assert!(StateResponse::ok("r3", None).error.is_none());
+    }
+}
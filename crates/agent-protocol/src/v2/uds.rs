@@ -26,17 +26,41 @@
 //! - 0x15: WebSocket Frame Event
 //! - 0x16: Guardrail Inspect Event
 //! - 0x17: Configure Event
+//! - 0x18: Request Body Chunk Fragment
+//! - 0x19: Response Body Chunk Fragment
+//! - 0x1A: Request Body Chunk (shared memory)
+//! - 0x1B: Response Body Chunk (shared memory)
 //! - 0x20: Agent Response
 //! - 0x30: Health Status
 //! - 0x31: Metrics Report
 //! - 0x32: Config Update Request
 //! - 0x33: Flow Control Signal
+//! - 0x34: State Get (agent -> proxy)
+//! - 0x35: State Set (agent -> proxy)
+//! - 0x36: State Delete (agent -> proxy)
+//! - 0x37: State Response (proxy -> agent)
 //! - 0x40: Cancel Request
 //! - 0x41: Ping
 //! - 0x42: Pong
+//!
+//! # Message Size
+//!
+//! Both sides advertise a maximum message size during the handshake and the
+//! smaller of the two applies for the rest of the connection. When a body
+//! chunk does not fit, the client sends the leading parts as fragment
+//! messages (0x18/0x19) and the remainder as a regular body chunk message;
+//! the agent concatenates them before invoking its handler. Fragments are
+//! only used when the agent advertises the `chunk_reassembly` feature.
+//!
+//! # Shared Memory
+//!
+//! With the `mmap-buffers` feature the proxy can offer a shared-memory
+//! segment in the handshake (see [`shm`](super::shm)). If the agent maps it
+//! and advertises the `shared_memory` feature, large binary body chunks are
+//! written into the segment and only a descriptor is sent (0x1A/0x1B).
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -45,14 +69,22 @@ use tokio::net::UnixStream;
 use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
 use tracing::{debug, error, info, trace, warn};
 
+use crate::buffer_pool::{PoolStats, SharedBufferPool};
 use crate::v2::pool::CHANNEL_BUFFER_SIZE;
-use crate::v2::{AgentCapabilities, AgentFeatures, AgentLimits, HealthConfig, PROTOCOL_VERSION_2};
+use crate::v2::protocol_metrics::{DurationRecorder, ProtocolMetrics};
+use crate::v2::{
+    accept_agent_version, adapt_capabilities, supported_versions, AgentCapabilities, AgentFeatures,
+    AgentLimits, HealthConfig, StateHandler, StateRequest, StateResponse, WarmupRequest,
+    PROTOCOL_VERSION_2,
+};
 use crate::{AgentProtocolError, AgentResponse, EventType};
 
 use super::client::{ConfigUpdateCallback, FlowState, MetricsCallback};
 
-/// Maximum message size for UDS transport (16 MB).
-pub const MAX_UDS_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+/// Default maximum message size for UDS transport (16 MB).
+///
+/// Used for the handshake and whenever the peer does not negotiate a limit.
+pub const MAX_UDS_MESSAGE_SIZE: usize = crate::MAX_MESSAGE_SIZE;
 
 /// Payload encoding for UDS transport.
 ///
@@ -78,8 +110,10 @@ impl UdsEncoding {
         match self {
             UdsEncoding::Json => serde_json::to_vec(value)
                 .map_err(|e| AgentProtocolError::Serialization(e.to_string())),
+            // Structs are encoded as maps: positional arrays misalign as soon
+            // as a `skip_serializing_if` field is left out
             #[cfg(feature = "binary-uds")]
-            UdsEncoding::MessagePack => rmp_serde::to_vec(value)
+            UdsEncoding::MessagePack => rmp_serde::to_vec_named(value)
                 .map_err(|e| AgentProtocolError::Serialization(e.to_string())),
             #[cfg(not(feature = "binary-uds"))]
             UdsEncoding::MessagePack => {
@@ -90,6 +124,27 @@ impl UdsEncoding {
         }
     }
 
+    /// Serialize a value using this encoding, appending to `buf`.
+    ///
+    /// Lets callers reuse serialization buffers across messages.
+    #[inline]
+    pub fn serialize_into<T: serde::Serialize>(
+        &self,
+        value: &T,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), AgentProtocolError> {
+        match self {
+            UdsEncoding::Json => serde_json::to_writer(&mut *buf, value)
+                .map_err(|e| AgentProtocolError::Serialization(e.to_string())),
+            #[cfg(feature = "binary-uds")]
+            UdsEncoding::MessagePack => rmp_serde::encode::write_named(buf, value)
+                .map_err(|e| AgentProtocolError::Serialization(e.to_string())),
+            #[cfg(not(feature = "binary-uds"))]
+            UdsEncoding::MessagePack => serde_json::to_writer(&mut *buf, value)
+                .map_err(|e| AgentProtocolError::Serialization(e.to_string())),
+        }
+    }
+
     /// Deserialize a value using this encoding.
     ///
     /// Returns the deserialized value, or an error if deserialization fails.
@@ -131,6 +186,10 @@ pub enum MessageType {
     WebSocketFrame = 0x15,
     GuardrailInspect = 0x16,
     Configure = 0x17,
+    RequestBodyChunkFragment = 0x18,
+    ResponseBodyChunkFragment = 0x19,
+    RequestBodyChunkShm = 0x1A,
+    ResponseBodyChunkShm = 0x1B,
 
     // Response (agent -> proxy)
     AgentResponse = 0x20,
@@ -140,6 +199,10 @@ pub enum MessageType {
     MetricsReport = 0x31,
     ConfigUpdateRequest = 0x32,
     FlowControl = 0x33,
+    StateGet = 0x34,
+    StateSet = 0x35,
+    StateDelete = 0x36,
+    StateResponse = 0x37,
 
     // Management
     Cancel = 0x40,
@@ -162,11 +225,19 @@ impl TryFrom<u8> for MessageType {
             0x15 => Ok(MessageType::WebSocketFrame),
             0x16 => Ok(MessageType::GuardrailInspect),
             0x17 => Ok(MessageType::Configure),
+            0x18 => Ok(MessageType::RequestBodyChunkFragment),
+            0x19 => Ok(MessageType::ResponseBodyChunkFragment),
+            0x1A => Ok(MessageType::RequestBodyChunkShm),
+            0x1B => Ok(MessageType::ResponseBodyChunkShm),
             0x20 => Ok(MessageType::AgentResponse),
             0x30 => Ok(MessageType::HealthStatus),
             0x31 => Ok(MessageType::MetricsReport),
             0x32 => Ok(MessageType::ConfigUpdateRequest),
             0x33 => Ok(MessageType::FlowControl),
+            0x34 => Ok(MessageType::StateGet),
+            0x35 => Ok(MessageType::StateSet),
+            0x36 => Ok(MessageType::StateDelete),
+            0x37 => Ok(MessageType::StateResponse),
             0x40 => Ok(MessageType::Cancel),
             0x41 => Ok(MessageType::Ping),
             0x42 => Ok(MessageType::Pong),
@@ -178,6 +249,55 @@ impl TryFrom<u8> for MessageType {
     }
 }
 
+impl MessageType {
+    /// Event type carried by this message; fragments and shared-memory
+    /// chunks count as the body chunk they belong to.
+    pub fn event_type(self) -> Option<EventType> {
+        match self {
+            MessageType::RequestHeaders => Some(EventType::RequestHeaders),
+            MessageType::RequestBodyChunk
+            | MessageType::RequestBodyChunkFragment
+            | MessageType::RequestBodyChunkShm => Some(EventType::RequestBodyChunk),
+            MessageType::ResponseHeaders => Some(EventType::ResponseHeaders),
+            MessageType::ResponseBodyChunk
+            | MessageType::ResponseBodyChunkFragment
+            | MessageType::ResponseBodyChunkShm => Some(EventType::ResponseBodyChunk),
+            MessageType::RequestComplete => Some(EventType::RequestComplete),
+            MessageType::WebSocketFrame => Some(EventType::WebSocketFrame),
+            MessageType::GuardrailInspect => Some(EventType::GuardrailInspect),
+            MessageType::Configure => Some(EventType::Configure),
+            _ => None,
+        }
+    }
+}
+
+/// Answer a session state request from an agent without blocking the
+/// reader task.
+fn spawn_state_request(
+    handler: Option<Arc<dyn StateHandler>>,
+    agent_id: String,
+    request: StateRequest,
+    encoding: UdsEncoding,
+    tx: mpsc::WeakSender<(MessageType, Vec<u8>)>,
+) {
+    tokio::spawn(async move {
+        let response = match handler {
+            Some(handler) => handler.handle(&agent_id, request).await,
+            None => StateResponse::error(request.request_id(), "session store not configured"),
+        };
+        let payload = match encoding.serialize(&response) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!(agent_id = %agent_id, error = %e, "Failed to encode state response");
+                return;
+            }
+        };
+        if let Some(tx) = tx.upgrade() {
+            let _ = tx.send((MessageType::StateResponse, payload)).await;
+        }
+    });
+}
+
 /// Handshake request sent from proxy to agent over UDS.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UdsHandshakeRequest {
@@ -189,11 +309,58 @@ pub struct UdsHandshakeRequest {
     /// If empty or missing, only JSON is supported.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub supported_encodings: Vec<UdsEncoding>,
+    /// Largest message the proxy is willing to exchange.
+    /// If missing, [`MAX_UDS_MESSAGE_SIZE`] applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_size: Option<u64>,
+    /// Shared-memory segment offered for body chunks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm: Option<UdsShmOffer>,
+}
+
+/// Shared-memory segment offered by the proxy during the handshake.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UdsShmOffer {
+    /// Path of the segment file
+    pub path: String,
+    /// Number of slots in the segment
+    pub slot_count: u32,
+    /// Size of each slot in bytes
+    pub slot_size: u64,
+}
+
+/// Location of a body chunk within the shared-memory segment.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UdsShmDescriptor {
+    /// Slot index
+    pub slot: u32,
+    /// Slot generation at the time of writing
+    pub generation: u32,
+    /// Payload length in bytes
+    pub len: u64,
+}
+
+/// Body chunk whose data lives in shared memory (0x1A/0x1B).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UdsShmBodyChunk {
+    pub correlation_id: String,
+    pub shm: UdsShmDescriptor,
+    pub is_last: bool,
+    pub total_size: Option<usize>,
+    #[serde(default)]
+    pub chunk_index: u32,
+    #[serde(default)]
+    pub bytes_received: usize,
+    #[serde(default)]
+    pub bytes_sent: usize,
 }
 
 /// Handshake response from agent to proxy over UDS.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UdsHandshakeResponse {
+    /// Version the agent picked from `supported_versions`.
+    /// If missing (v1 agents), 0.
+    #[serde(default)]
     pub protocol_version: u32,
     pub capabilities: UdsCapabilities,
     pub success: bool,
@@ -211,12 +378,20 @@ pub struct UdsCapabilities {
     pub name: String,
     pub version: String,
     pub supported_events: Vec<i32>,
+    #[serde(default)]
     pub features: UdsFeatures,
+    #[serde(default)]
     pub limits: UdsLimits,
+    /// Requests to replay after every (re)connect
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<WarmupRequest>,
 }
 
 /// Agent features.
+///
+/// Features missing from the handshake (older agents) are off.
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct UdsFeatures {
     pub streaming_body: bool,
     pub websocket: bool,
@@ -227,14 +402,25 @@ pub struct UdsFeatures {
     pub cancellation: bool,
     pub flow_control: bool,
     pub health_reporting: bool,
+    #[serde(default)]
+    pub chunk_reassembly: bool,
+    /// Agent mapped the shared-memory segment offered in the handshake.
+    #[serde(default)]
+    pub shared_memory: bool,
 }
 
 /// Agent limits.
+///
+/// Limits missing from the handshake are 0 (not specified).
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct UdsLimits {
     pub max_body_size: u64,
     pub max_concurrency: u32,
     pub preferred_chunk_size: u64,
+    /// Negotiated maximum message size (0 = not specified).
+    #[serde(default)]
+    pub max_message_size: u64,
 }
 
 impl From<UdsCapabilities> for AgentCapabilities {
@@ -259,6 +445,7 @@ impl From<UdsCapabilities> for AgentCapabilities {
                 cancellation: caps.features.cancellation,
                 flow_control: caps.features.flow_control,
                 health_reporting: caps.features.health_reporting,
+                chunk_reassembly: caps.features.chunk_reassembly,
             },
             limits: AgentLimits {
                 max_body_size: caps.limits.max_body_size as usize,
@@ -266,8 +453,13 @@ impl From<UdsCapabilities> for AgentCapabilities {
                 preferred_chunk_size: caps.limits.preferred_chunk_size as usize,
                 max_memory: None,
                 max_processing_time_ms: None,
+                max_message_size: match caps.limits.max_message_size {
+                    0 => MAX_UDS_MESSAGE_SIZE,
+                    size => (size as usize).min(crate::MAX_NEGOTIATED_MESSAGE_SIZE),
+                },
             },
             health: HealthConfig::default(),
+            warmup: caps.warmup,
         }
     }
 }
@@ -294,12 +486,16 @@ impl From<AgentCapabilities> for UdsCapabilities {
                 cancellation: caps.features.cancellation,
                 flow_control: caps.features.flow_control,
                 health_reporting: caps.features.health_reporting,
+                chunk_reassembly: caps.features.chunk_reassembly,
+                shared_memory: false,
             },
             limits: UdsLimits {
                 max_body_size: caps.limits.max_body_size as u64,
                 max_concurrency: caps.limits.max_concurrency,
                 preferred_chunk_size: caps.limits.preferred_chunk_size as u64,
+                max_message_size: caps.limits.max_message_size as u64,
             },
+            warmup: caps.warmup,
         }
     }
 }
@@ -336,11 +532,23 @@ pub struct AgentClientV2Uds {
     protocol_version: AtomicU64,
     /// Negotiated payload encoding
     encoding: RwLock<UdsEncoding>,
+    /// Maximum message size (configured before connect, negotiated after)
+    max_message_size: AtomicUsize,
+    /// Whether the agent reassembles fragmented body chunks
+    chunk_reassembly: AtomicBool,
+    /// Shared-memory configuration offered during the handshake
+    #[cfg(feature = "mmap-buffers")]
+    shm_config: Option<super::shm::ShmConfig>,
+    /// Shared-memory segment accepted by the agent
+    #[cfg(feature = "mmap-buffers")]
+    shm_ring: RwLock<Option<Arc<super::shm::ShmRing>>>,
     /// Pending requests by correlation ID
     pending: Arc<Mutex<HashMap<String, oneshot::Sender<AgentResponse>>>>,
     /// Sender for outbound messages
     #[allow(clippy::type_complexity)]
     outbound_tx: Mutex<Option<mpsc::Sender<(MessageType, Vec<u8>)>>>,
+    /// Serialization buffers, returned by the writer task after each write
+    encode_buffers: Arc<SharedBufferPool>,
     /// Sequence counter for pings
     ping_sequence: AtomicU64,
     /// Connection state
@@ -355,6 +563,10 @@ pub struct AgentClientV2Uds {
     metrics_callback: Option<MetricsCallback>,
     /// Callback for config update requests
     config_update_callback: Option<ConfigUpdateCallback>,
+    /// Handler for session state requests
+    state_handler: Option<Arc<dyn StateHandler>>,
+    /// Pool metrics that serialization time is recorded into
+    protocol_metrics: Option<Arc<ProtocolMetrics>>,
 }
 
 impl AgentClientV2Uds {
@@ -381,8 +593,15 @@ impl AgentClientV2Uds {
             capabilities: RwLock::new(None),
             protocol_version: AtomicU64::new(0),
             encoding: RwLock::new(UdsEncoding::Json),
+            max_message_size: AtomicUsize::new(MAX_UDS_MESSAGE_SIZE),
+            chunk_reassembly: AtomicBool::new(false),
+            #[cfg(feature = "mmap-buffers")]
+            shm_config: None,
+            #[cfg(feature = "mmap-buffers")]
+            shm_ring: RwLock::new(None),
             pending: Arc::new(Mutex::new(HashMap::new())),
             outbound_tx: Mutex::new(None),
+            encode_buffers: Arc::new(SharedBufferPool::default()),
             ping_sequence: AtomicU64::new(0),
             connected: RwLock::new(false),
             flow_state: RwLock::new(FlowState::Normal),
@@ -390,6 +609,8 @@ impl AgentClientV2Uds {
             in_flight: AtomicU64::new(0),
             metrics_callback: None,
             config_update_callback: None,
+            state_handler: None,
+            protocol_metrics: None,
         })
     }
 
@@ -412,16 +633,90 @@ impl AgentClientV2Uds {
         *self.encoding.read().await
     }
 
+    /// Get the maximum message size.
+    ///
+    /// Before [`connect`](Self::connect) this is the configured limit; after
+    /// the handshake it is the limit negotiated with the agent.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size.load(Ordering::Relaxed)
+    }
+
+    /// Set the maximum message size offered to the agent during the handshake.
+    ///
+    /// Clamped to [`MAX_NEGOTIATED_MESSAGE_SIZE`](crate::MAX_NEGOTIATED_MESSAGE_SIZE).
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.max_message_size.store(
+            size.min(crate::MAX_NEGOTIATED_MESSAGE_SIZE),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Offer a shared-memory segment for large body chunks on connect.
+    ///
+    /// Only used if the agent maps the segment; otherwise chunks are sent
+    /// inline as usual.
+    #[cfg(feature = "mmap-buffers")]
+    pub fn set_shared_memory(&mut self, config: super::shm::ShmConfig) {
+        self.shm_config = Some(config);
+    }
+
+    /// Whether body chunks can be sent through shared memory.
+    #[cfg(feature = "mmap-buffers")]
+    pub async fn shared_memory_active(&self) -> bool {
+        self.shm_ring.read().await.is_some()
+    }
+
     /// Set the metrics callback.
     pub fn set_metrics_callback(&mut self, callback: MetricsCallback) {
         self.metrics_callback = Some(callback);
     }
 
+    /// Create the shared-memory segment to offer, if configured.
+    ///
+    /// Failure is not fatal; the connection proceeds without shared memory.
+    #[cfg(feature = "mmap-buffers")]
+    fn create_shm_ring(&self) -> Option<super::shm::ShmRing> {
+        let config = self.shm_config.as_ref()?;
+        match super::shm::ShmRing::create(config) {
+            Ok(ring) => Some(ring),
+            Err(e) => {
+                warn!(
+                    agent_id = %self.agent_id,
+                    dir = %config.dir.display(),
+                    error = %e,
+                    "Failed to create shared memory segment, sending bodies inline"
+                );
+                None
+            }
+        }
+    }
+
     /// Set the config update callback.
     pub fn set_config_update_callback(&mut self, callback: ConfigUpdateCallback) {
         self.config_update_callback = Some(callback);
     }
 
+    /// Set the handler for session state requests.
+    ///
+    /// Without a handler, state requests from the agent are answered with
+    /// an error.
+    pub fn set_state_handler(&mut self, handler: Arc<dyn StateHandler>) {
+        self.state_handler = Some(handler);
+    }
+
+    /// Record event serialization time into `metrics`.
+    pub fn set_protocol_metrics(&mut self, metrics: Arc<ProtocolMetrics>) {
+        self.protocol_metrics = Some(metrics);
+    }
+
+    /// Serialization timer, if the client reports into pool metrics.
+    fn serialization_timer(&self, msg_type: MessageType) -> Option<DurationRecorder<'_>> {
+        let event_type = msg_type.event_type()?;
+        self.protocol_metrics
+            .as_deref()
+            .map(|metrics| metrics.time_serialization(event_type))
+    }
+
     /// Connect and perform handshake.
     pub async fn connect(&self) -> Result<(), AgentProtocolError> {
         info!(
@@ -445,13 +740,24 @@ impl AgentClientV2Uds {
         let mut reader = BufReader::new(read_half);
         let mut writer = BufWriter::new(write_half);
 
+        // A fresh segment per connection: references held by a previous,
+        // possibly crashed, agent process go away with the old segment
+        #[cfg(feature = "mmap-buffers")]
+        let shm_ring = self.create_shm_ring();
+        #[cfg(feature = "mmap-buffers")]
+        let shm = shm_ring.as_ref().map(|ring| ring.offer());
+        #[cfg(not(feature = "mmap-buffers"))]
+        let shm = None;
+
         // Send handshake request with supported encodings
         let handshake_req = UdsHandshakeRequest {
-            supported_versions: vec![PROTOCOL_VERSION_2],
+            supported_versions: supported_versions(),
             proxy_id: "zentinel-proxy".to_string(),
             proxy_version: env!("CARGO_PKG_VERSION").to_string(),
             config: None,
             supported_encodings: Self::supported_encodings(),
+            max_message_size: Some(self.max_message_size() as u64),
+            shm,
         };
 
         // Handshake always uses JSON (before encoding is negotiated)
@@ -481,11 +787,26 @@ impl AgentClientV2Uds {
             ));
         }
 
-        // Store capabilities and negotiated encoding
-        let capabilities: AgentCapabilities = response.capabilities.into();
+        let protocol_version = accept_agent_version(&self.agent_id, response.protocol_version)?;
+
+        #[cfg(feature = "mmap-buffers")]
+        {
+            let accepted = response.capabilities.features.shared_memory;
+            *self.shm_ring.write().await = shm_ring.filter(|_| accepted).map(Arc::new);
+        }
+
+        // Store capabilities, negotiated encoding and message size limit
+        let capabilities = adapt_capabilities(protocol_version, response.capabilities.into());
+        let max_message_size = self
+            .max_message_size()
+            .min(capabilities.limits.max_message_size);
+        self.max_message_size
+            .store(max_message_size, Ordering::Relaxed);
+        self.chunk_reassembly
+            .store(capabilities.features.chunk_reassembly, Ordering::Relaxed);
         *self.capabilities.write().await = Some(capabilities);
         self.protocol_version
-            .store(response.protocol_version as u64, Ordering::SeqCst);
+            .store(protocol_version as u64, Ordering::SeqCst);
 
         // Store the negotiated encoding for subsequent messages
         let negotiated_encoding = response.encoding;
@@ -493,21 +814,30 @@ impl AgentClientV2Uds {
 
         info!(
             agent_id = %self.agent_id,
-            protocol_version = response.protocol_version,
+            protocol_version = protocol_version,
             encoding = ?negotiated_encoding,
+            max_message_size = max_message_size,
             "UDS v2 handshake successful"
         );
 
         // Create message channel
         let (tx, mut rx) = mpsc::channel::<(MessageType, Vec<u8>)>(CHANNEL_BUFFER_SIZE);
+        // State responses are written by the reader task; a weak sender
+        // lets the writer shut down on close
+        let state_tx = tx.downgrade();
         *self.outbound_tx.lock().await = Some(tx);
         *self.connected.write().await = true;
 
         // Spawn writer task
         let agent_id_clone = self.agent_id.clone();
+        let encode_buffers = Arc::clone(&self.encode_buffers);
         tokio::spawn(async move {
             while let Some((msg_type, payload)) = rx.recv().await {
-                if let Err(e) = write_message(&mut writer, msg_type, &payload).await {
+                let result =
+                    write_message_with_limit(&mut writer, msg_type, &payload, max_message_size)
+                        .await;
+                encode_buffers.put(payload);
+                if let Err(e) = result {
                     error!(
                         agent_id = %agent_id_clone,
                         error = %e,
@@ -528,12 +858,13 @@ impl AgentClientV2Uds {
         let health_state_clone = Arc::clone(&health_state);
         let metrics_callback = self.metrics_callback.clone();
         let config_update_callback = self.config_update_callback.clone();
+        let state_handler = self.state_handler.clone();
         // Encoding is fixed after handshake, so we can copy it
         let reader_encoding = negotiated_encoding;
 
         tokio::spawn(async move {
             loop {
-                match read_message(&mut reader).await {
+                match read_message_with_limit(&mut reader, max_message_size).await {
                     Ok((msg_type, payload)) => {
                         match msg_type {
                             MessageType::AgentResponse => {
@@ -608,6 +939,37 @@ impl AgentClientV2Uds {
                                     }
                                 }
                             }
+                            MessageType::StateGet
+                            | MessageType::StateSet
+                            | MessageType::StateDelete => {
+                                let request = match msg_type {
+                                    MessageType::StateGet => {
+                                        reader_encoding.deserialize(&payload).map(StateRequest::Get)
+                                    }
+                                    MessageType::StateSet => {
+                                        reader_encoding.deserialize(&payload).map(StateRequest::Set)
+                                    }
+                                    _ => reader_encoding
+                                        .deserialize(&payload)
+                                        .map(StateRequest::Delete),
+                                };
+                                match request {
+                                    Ok(request) => spawn_state_request(
+                                        state_handler.clone(),
+                                        agent_id.clone(),
+                                        request,
+                                        reader_encoding,
+                                        state_tx.clone(),
+                                    ),
+                                    Err(e) => {
+                                        warn!(
+                                            agent_id = %agent_id,
+                                            error = %e,
+                                            "Failed to parse state request"
+                                        );
+                                    }
+                                }
+                            }
                             MessageType::Pong => {
                                 trace!(agent_id = %agent_id, "Received pong");
                             }
@@ -664,8 +1026,13 @@ impl AgentClientV2Uds {
         correlation_id: &str,
         event: &crate::RequestBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        self.send_event(MessageType::RequestBodyChunk, correlation_id, event)
-            .await
+        self.send_body_chunk_event(
+            MessageType::RequestBodyChunk,
+            MessageType::RequestBodyChunkFragment,
+            correlation_id,
+            event,
+        )
+        .await
     }
 
     /// Send a response headers event.
@@ -684,8 +1051,13 @@ impl AgentClientV2Uds {
         correlation_id: &str,
         event: &crate::ResponseBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        self.send_event(MessageType::ResponseBodyChunk, correlation_id, event)
-            .await
+        self.send_body_chunk_event(
+            MessageType::ResponseBodyChunk,
+            MessageType::ResponseBodyChunkFragment,
+            correlation_id,
+            event,
+        )
+        .await
     }
 
     /// Send a request complete event.
@@ -746,6 +1118,12 @@ impl AgentClientV2Uds {
         event: &crate::BinaryRequestBodyChunkEvent,
     ) -> Result<AgentResponse, AgentProtocolError> {
         let correlation_id = &event.correlation_id;
+        if event.part.is_some() {
+            // The binary and shared-memory chunk formats have no part field
+            return self
+                .send_request_body_chunk(correlation_id, &event.clone().into())
+                .await;
+        }
         self.send_binary_body_chunk(
             MessageType::RequestBodyChunk,
             correlation_id,
@@ -782,6 +1160,10 @@ impl AgentClientV2Uds {
     }
 
     /// Internal helper to send binary body chunks with encoding-aware serialization.
+    ///
+    /// Chunks that exceed the negotiated message size are split on raw byte
+    /// boundaries (multiples of 3 for JSON so the base64 pieces concatenate
+    /// cleanly) and sent as fragments followed by a final regular message.
     #[allow(clippy::too_many_arguments)]
     async fn send_binary_body_chunk(
         &self,
@@ -794,91 +1176,188 @@ impl AgentClientV2Uds {
         bytes_received: Option<usize>,
         bytes_sent: Option<usize>,
     ) -> Result<AgentResponse, AgentProtocolError> {
-        // Create response channel
-        let (tx, rx) = oneshot::channel();
-        self.pending
-            .lock()
-            .await
-            .insert(correlation_id.to_string(), tx);
+        #[cfg(feature = "mmap-buffers")]
+        {
+            let ring = self.shm_ring.read().await.clone();
+            let slot = ring
+                .as_ref()
+                .filter(|ring| ring.accepts(data.len()))
+                .and_then(|ring| ring.write(data));
+            if let Some(slot) = slot {
+                let chunk = UdsShmBodyChunk {
+                    correlation_id: correlation_id.to_string(),
+                    shm: slot.descriptor().clone(),
+                    is_last,
+                    total_size,
+                    chunk_index,
+                    bytes_received: bytes_received.unwrap_or_default(),
+                    bytes_sent: bytes_sent.unwrap_or_default(),
+                };
+                let encoding = *self.encoding.read().await;
+                let payload = {
+                    let _timer = self.serialization_timer(msg_type);
+                    encoding.serialize(&chunk)?
+                };
+                // The slot stays referenced until the agent has answered
+                return self
+                    .send_and_wait(shm_type_for(msg_type), correlation_id, payload)
+                    .await;
+            }
+        }
 
         // Get the current encoding
         let encoding = *self.encoding.read().await;
 
         // Serialize body chunk using encoding-optimized format
-        let payload_bytes = match encoding {
-            UdsEncoding::Json => {
-                // JSON path: must use base64 encoding for binary data
-                use base64::{engine::general_purpose::STANDARD, Engine as _};
-                let json = serde_json::json!({
-                    "correlation_id": correlation_id,
-                    "data": STANDARD.encode(data),
-                    "is_last": is_last,
-                    "total_size": total_size,
-                    "chunk_index": chunk_index,
-                    "bytes_received": bytes_received,
-                    "bytes_sent": bytes_sent,
-                });
-                serde_json::to_vec(&json)
-                    .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?
-            }
-            UdsEncoding::MessagePack => {
-                // MessagePack path: raw bytes via serde_bytes for zero-copy serialization
-                #[derive(serde::Serialize)]
-                struct BinaryBodyChunk<'a> {
-                    correlation_id: &'a str,
-                    #[serde(with = "serde_bytes")]
-                    data: &'a [u8],
-                    is_last: bool,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    total_size: Option<usize>,
-                    chunk_index: u32,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    bytes_received: Option<usize>,
-                    #[serde(skip_serializing_if = "Option::is_none")]
-                    bytes_sent: Option<usize>,
+        let encode = |data: &[u8]| -> Result<Vec<u8>, AgentProtocolError> {
+            let _timer = self.serialization_timer(msg_type);
+            match encoding {
+                UdsEncoding::Json => {
+                    // JSON path: must use base64 encoding for binary data
+                    use base64::{engine::general_purpose::STANDARD, Engine as _};
+                    let json = serde_json::json!({
+                        "correlation_id": correlation_id,
+                        "data": STANDARD.encode(data),
+                        "is_last": is_last,
+                        "total_size": total_size,
+                        "chunk_index": chunk_index,
+                        "bytes_received": bytes_received,
+                        "bytes_sent": bytes_sent,
+                    });
+                    serde_json::to_vec(&json)
+                        .map_err(|e| AgentProtocolError::Serialization(e.to_string()))
+                }
+                UdsEncoding::MessagePack => {
+                    // MessagePack path: raw bytes via serde_bytes for zero-copy serialization
+                    #[derive(serde::Serialize)]
+                    struct BinaryBodyChunk<'a> {
+                        correlation_id: &'a str,
+                        #[serde(with = "serde_bytes")]
+                        data: &'a [u8],
+                        is_last: bool,
+                        #[serde(skip_serializing_if = "Option::is_none")]
+                        total_size: Option<usize>,
+                        chunk_index: u32,
+                        #[serde(skip_serializing_if = "Option::is_none")]
+                        bytes_received: Option<usize>,
+                        #[serde(skip_serializing_if = "Option::is_none")]
+                        bytes_sent: Option<usize>,
+                    }
+                    let chunk = BinaryBodyChunk {
+                        correlation_id,
+                        data,
+                        is_last,
+                        total_size,
+                        chunk_index,
+                        bytes_received,
+                        bytes_sent,
+                    };
+                    encoding.serialize(&chunk)
                 }
-                let chunk = BinaryBodyChunk {
-                    correlation_id,
-                    data: data.as_ref(),
-                    is_last,
-                    total_size,
-                    chunk_index,
-                    bytes_received,
-                    bytes_sent,
-                };
-                encoding.serialize(&chunk)?
             }
         };
 
-        // Send message
-        {
-            let outbound = self.outbound_tx.lock().await;
-            if let Some(tx) = outbound.as_ref() {
-                tx.send((msg_type, payload_bytes))
-                    .await
-                    .map_err(|_| AgentProtocolError::ConnectionClosed)?;
-            } else {
-                return Err(AgentProtocolError::ConnectionClosed);
-            }
+        let payload_bytes = encode(data)?;
+        let max_message_size = self.max_message_size();
+        if payload_bytes.len() <= max_message_size {
+            return self
+                .send_and_wait(msg_type, correlation_id, payload_bytes)
+                .await;
         }
 
-        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        let budget = self.fragment_budget(payload_bytes.len(), encode(&[])?.len())?;
+        let piece_len = match encoding {
+            UdsEncoding::Json => budget / 4 * 3,
+            UdsEncoding::MessagePack => budget,
+        };
+        if piece_len == 0 {
+            return Err(AgentProtocolError::MessageTooLarge {
+                size: payload_bytes.len(),
+                max: max_message_size,
+            });
+        }
 
-        // Wait for response with timeout
-        let response = tokio::time::timeout(self.timeout, rx)
+        let pieces: Vec<&[u8]> = data.chunks(piece_len).collect();
+        let fragment_type = fragment_type_for(msg_type);
+        let (last, leading) = pieces.split_last().expect("oversized chunk is non-empty");
+        for piece in leading {
+            self.enqueue(fragment_type, encode(piece)?).await?;
+        }
+        self.send_and_wait(msg_type, correlation_id, encode(last)?)
             .await
-            .map_err(|_| {
-                self.pending
-                    .try_lock()
-                    .ok()
-                    .map(|mut p| p.remove(correlation_id));
-                AgentProtocolError::Timeout(self.timeout)
-            })?
-            .map_err(|_| AgentProtocolError::ConnectionClosed)?;
+    }
 
-        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    /// Send a base64 body chunk event, splitting it when it exceeds the
+    /// negotiated message size.
+    async fn send_body_chunk_event<T: SplittableBodyChunk>(
+        &self,
+        msg_type: MessageType,
+        fragment_type: MessageType,
+        correlation_id: &str,
+        event: &T,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let encoding = *self.encoding.read().await;
+        let payload_bytes = self.encode_event(msg_type, encoding, correlation_id, event)?;
+        let max_message_size = self.max_message_size();
+        if payload_bytes.len() <= max_message_size {
+            return self
+                .send_and_wait(msg_type, correlation_id, payload_bytes)
+                .await;
+        }
 
-        Ok(response)
+        let empty = encode_event(
+            encoding,
+            correlation_id,
+            &event.with_data(String::new()),
+            Vec::new(),
+        )?;
+        // Split on 4-character boundaries so every piece is valid base64 and
+        // the pieces concatenate back into the original string.
+        let piece_len = self.fragment_budget(payload_bytes.len(), empty.len())? / 4 * 4;
+        if piece_len == 0 {
+            return Err(AgentProtocolError::MessageTooLarge {
+                size: payload_bytes.len(),
+                max: max_message_size,
+            });
+        }
+
+        let data = event.data();
+        let mut offset = 0;
+        while data.len() - offset > piece_len {
+            let piece = event.with_data(data[offset..offset + piece_len].to_string());
+            self.enqueue(
+                fragment_type,
+                self.encode_event(msg_type, encoding, correlation_id, &piece)?,
+            )
+            .await?;
+            offset += piece_len;
+        }
+
+        trace!(
+            agent_id = %self.agent_id,
+            correlation_id = %correlation_id,
+            fragments = offset / piece_len + 1,
+            "Split oversized body chunk"
+        );
+
+        let last = event.with_data(data[offset..].to_string());
+        self.send_and_wait(
+            msg_type,
+            correlation_id,
+            self.encode_event(msg_type, encoding, correlation_id, &last)?,
+        )
+        .await
+    }
+
+    /// Number of payload bytes available per fragment, given the encoded size
+    /// of the full message and of the same message with empty data.
+    fn fragment_budget(&self, size: usize, overhead: usize) -> Result<usize, AgentProtocolError> {
+        let max = self.max_message_size();
+        if !self.chunk_reassembly.load(Ordering::Relaxed) {
+            return Err(AgentProtocolError::MessageTooLarge { size, max });
+        }
+        // Leave room for varint/length headers that grow with the data.
+        Ok(max.saturating_sub(overhead + FRAGMENT_HEADROOM))
     }
 
     /// Send an event and wait for response.
@@ -887,6 +1366,58 @@ impl AgentClientV2Uds {
         msg_type: MessageType,
         correlation_id: &str,
         event: &T,
+    ) -> Result<AgentResponse, AgentProtocolError> {
+        let encoding = *self.encoding.read().await;
+        let payload_bytes = self.encode_event(msg_type, encoding, correlation_id, event)?;
+        self.send_and_wait(msg_type, correlation_id, payload_bytes)
+            .await
+    }
+
+    /// Encode an event into a buffer from the client's pool.
+    fn encode_event<T: serde::Serialize>(
+        &self,
+        msg_type: MessageType,
+        encoding: UdsEncoding,
+        correlation_id: &str,
+        event: &T,
+    ) -> Result<Vec<u8>, AgentProtocolError> {
+        let buf = self.encode_buffers.get();
+        let _timer = self.serialization_timer(msg_type);
+        encode_event(encoding, correlation_id, event, buf)
+    }
+
+    /// Queue an already-encoded message on the writer task.
+    async fn enqueue(
+        &self,
+        msg_type: MessageType,
+        payload: Vec<u8>,
+    ) -> Result<(), AgentProtocolError> {
+        // Reject oversized payloads here: a failed write in the writer task
+        // would tear down the connection for every multiplexed request.
+        let max = self.max_message_size();
+        if payload.len() > max {
+            return Err(AgentProtocolError::MessageTooLarge {
+                size: payload.len(),
+                max,
+            });
+        }
+
+        let outbound = self.outbound_tx.lock().await;
+        if let Some(tx) = outbound.as_ref() {
+            tx.send((msg_type, payload))
+                .await
+                .map_err(|_| AgentProtocolError::ConnectionClosed)
+        } else {
+            Err(AgentProtocolError::ConnectionClosed)
+        }
+    }
+
+    /// Send an encoded event and wait for the correlated response.
+    async fn send_and_wait(
+        &self,
+        msg_type: MessageType,
+        correlation_id: &str,
+        payload: Vec<u8>,
     ) -> Result<AgentResponse, AgentProtocolError> {
         // Create response channel
         let (tx, rx) = oneshot::channel();
@@ -895,50 +1426,10 @@ impl AgentClientV2Uds {
             .await
             .insert(correlation_id.to_string(), tx);
 
-        // Get the current encoding
-        let encoding = *self.encoding.read().await;
-
-        // Serialize event using negotiated encoding
-        let payload_bytes = match encoding {
-            UdsEncoding::Json => {
-                // JSON path: use Value mutation for backwards compatibility
-                let mut payload = serde_json::to_value(event)
-                    .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?;
-                if let Some(obj) = payload.as_object_mut() {
-                    obj.insert(
-                        "correlation_id".to_string(),
-                        serde_json::Value::String(correlation_id.to_string()),
-                    );
-                }
-                serde_json::to_vec(&payload)
-                    .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?
-            }
-            UdsEncoding::MessagePack => {
-                // MessagePack path: use wrapper struct for efficient serialization
-                #[derive(serde::Serialize)]
-                struct EventWithCorrelation<'a, T: serde::Serialize> {
-                    correlation_id: &'a str,
-                    #[serde(flatten)]
-                    event: &'a T,
-                }
-                let wrapped = EventWithCorrelation {
-                    correlation_id,
-                    event,
-                };
-                encoding.serialize(&wrapped)?
-            }
-        };
-
         // Send message
-        {
-            let outbound = self.outbound_tx.lock().await;
-            if let Some(tx) = outbound.as_ref() {
-                tx.send((msg_type, payload_bytes))
-                    .await
-                    .map_err(|_| AgentProtocolError::ConnectionClosed)?;
-            } else {
-                return Err(AgentProtocolError::ConnectionClosed);
-            }
+        if let Err(e) = self.enqueue(msg_type, payload).await {
+            self.pending.lock().await.remove(correlation_id);
+            return Err(e);
         }
 
         self.in_flight.fetch_add(1, Ordering::Relaxed);
@@ -975,8 +1466,11 @@ impl AgentClientV2Uds {
         let payload = serde_json::to_vec(&cancel)
             .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?;
 
+        // v1 agents have no cancel message
+        let cancellable =
+            self.protocol_version.load(Ordering::SeqCst) >= u64::from(PROTOCOL_VERSION_2);
         let outbound = self.outbound_tx.lock().await;
-        if let Some(tx) = outbound.as_ref() {
+        if let Some(tx) = outbound.as_ref().filter(|_| cancellable) {
             tx.send((MessageType::Cancel, payload))
                 .await
                 .map_err(|_| AgentProtocolError::ConnectionClosed)?;
@@ -1028,6 +1522,10 @@ impl AgentClientV2Uds {
     pub async fn close(&self) -> Result<(), AgentProtocolError> {
         *self.connected.write().await = false;
         *self.outbound_tx.lock().await = None;
+        #[cfg(feature = "mmap-buffers")]
+        {
+            *self.shm_ring.write().await = None;
+        }
         Ok(())
     }
 
@@ -1036,6 +1534,11 @@ impl AgentClientV2Uds {
         self.in_flight.load(Ordering::Relaxed)
     }
 
+    /// Statistics of the serialization buffer pool.
+    pub fn encode_buffer_stats(&self) -> PoolStats {
+        self.encode_buffers.stats()
+    }
+
     /// Get agent ID.
     pub fn agent_id(&self) -> &str {
         &self.agent_id
@@ -1057,16 +1560,225 @@ impl AgentClientV2Uds {
     }
 }
 
+/// Bytes reserved in each fragment for encoding headers that grow with the data.
+const FRAGMENT_HEADROOM: usize = 64;
+
+/// Body chunk events whose base64 `data` can be split across messages.
+trait SplittableBodyChunk: serde::Serialize {
+    fn data(&self) -> &str;
+    fn with_data(&self, data: String) -> Self;
+}
+
+impl SplittableBodyChunk for crate::RequestBodyChunkEvent {
+    fn data(&self) -> &str {
+        &self.data
+    }
+
+    fn with_data(&self, data: String) -> Self {
+        Self {
+            data,
+            correlation_id: self.correlation_id.clone(),
+            part: self.part.clone(),
+            ..*self
+        }
+    }
+}
+
+impl SplittableBodyChunk for crate::ResponseBodyChunkEvent {
+    fn data(&self) -> &str {
+        &self.data
+    }
+
+    fn with_data(&self, data: String) -> Self {
+        Self {
+            data,
+            correlation_id: self.correlation_id.clone(),
+            ..*self
+        }
+    }
+}
+
+/// Map a body chunk message type to its fragment message type.
+fn fragment_type_for(msg_type: MessageType) -> MessageType {
+    match msg_type {
+        MessageType::ResponseBodyChunk => MessageType::ResponseBodyChunkFragment,
+        _ => MessageType::RequestBodyChunkFragment,
+    }
+}
+
+/// Shared-memory descriptor message type for a body chunk message type.
+#[cfg(feature = "mmap-buffers")]
+fn shm_type_for(msg_type: MessageType) -> MessageType {
+    match msg_type {
+        MessageType::ResponseBodyChunk => MessageType::ResponseBodyChunkShm,
+        _ => MessageType::RequestBodyChunkShm,
+    }
+}
+
+/// Serialize an event with its correlation ID using the negotiated encoding,
+/// appending to `buf`.
+fn encode_event<T: serde::Serialize>(
+    encoding: UdsEncoding,
+    correlation_id: &str,
+    event: &T,
+    mut buf: Vec<u8>,
+) -> Result<Vec<u8>, AgentProtocolError> {
+    match encoding {
+        UdsEncoding::Json => {
+            // JSON path: use Value mutation for backwards compatibility
+            let mut payload = serde_json::to_value(event)
+                .map_err(|e| AgentProtocolError::Serialization(e.to_string()))?;
+            if let Some(obj) = payload.as_object_mut() {
+                obj.insert(
+                    "correlation_id".to_string(),
+                    serde_json::Value::String(correlation_id.to_string()),
+                );
+            }
+            encoding.serialize_into(&payload, &mut buf)?;
+            Ok(buf)
+        }
+        UdsEncoding::MessagePack => {
+            // MessagePack path: use wrapper struct for efficient serialization
+            #[derive(serde::Serialize)]
+            struct EventWithCorrelation<'a, T: serde::Serialize> {
+                correlation_id: &'a str,
+                #[serde(flatten)]
+                event: &'a T,
+            }
+            let wrapped = EventWithCorrelation {
+                correlation_id,
+                event,
+            };
+            encoding.serialize_into(&wrapped, &mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+/// Reassembles body chunks that were split into fragment messages.
+///
+/// Used by agent-side servers: fragments (0x18/0x19) are buffered per
+/// correlation ID and chunk index, and the final regular body chunk message
+/// completes the sequence.
+#[derive(Debug)]
+pub struct ChunkReassembler {
+    partial: HashMap<(String, u32), PartialChunk>,
+    buffered: usize,
+    max_buffered: usize,
+}
+
+#[derive(Debug)]
+enum PartialChunk {
+    Buffering(String),
+    /// The buffer limit was exceeded; the sequence is dropped.
+    Overflowed,
+}
+
+impl ChunkReassembler {
+    /// Create a reassembler that buffers at most `max_buffered` bytes across
+    /// all in-progress chunks.
+    pub fn new(max_buffered: usize) -> Self {
+        Self {
+            partial: HashMap::new(),
+            buffered: 0,
+            max_buffered,
+        }
+    }
+
+    /// Buffer a leading fragment of a body chunk.
+    pub fn push_fragment(&mut self, correlation_id: &str, chunk_index: u32, data: &str) {
+        let key = (correlation_id.to_string(), chunk_index);
+        let entry = self
+            .partial
+            .entry(key)
+            .or_insert_with(|| PartialChunk::Buffering(String::new()));
+        if let PartialChunk::Buffering(buf) = entry {
+            if self.buffered + data.len() > self.max_buffered {
+                self.buffered -= buf.len();
+                *entry = PartialChunk::Overflowed;
+            } else {
+                buf.push_str(data);
+                self.buffered += data.len();
+            }
+        }
+    }
+
+    /// Complete a body chunk with its final part.
+    ///
+    /// Returns the full chunk data (just `data` if the chunk was not split),
+    /// or `MessageTooLarge` if its fragments exceeded the buffer limit.
+    pub fn complete(
+        &mut self,
+        correlation_id: &str,
+        chunk_index: u32,
+        data: String,
+    ) -> Result<String, AgentProtocolError> {
+        if self.partial.is_empty() {
+            return Ok(data);
+        }
+        match self
+            .partial
+            .remove(&(correlation_id.to_string(), chunk_index))
+        {
+            None => Ok(data),
+            Some(PartialChunk::Buffering(mut buf)) => {
+                self.buffered -= buf.len();
+                buf.push_str(&data);
+                Ok(buf)
+            }
+            Some(PartialChunk::Overflowed) => Err(AgentProtocolError::MessageTooLarge {
+                size: self.max_buffered + 1,
+                max: self.max_buffered,
+            }),
+        }
+    }
+
+    /// Drop all buffered fragments for a request (e.g. on cancellation).
+    pub fn discard(&mut self, correlation_id: &str) {
+        let mut freed = 0;
+        self.partial.retain(|(cid, _), chunk| {
+            if cid != correlation_id {
+                return true;
+            }
+            if let PartialChunk::Buffering(buf) = chunk {
+                freed += buf.len();
+            }
+            false
+        });
+        self.buffered -= freed;
+    }
+
+    /// Number of body chunks currently being reassembled.
+    pub fn in_progress(&self) -> usize {
+        self.partial.len()
+    }
+}
+
+/// Initial buffer size for reading a message; larger payloads grow it
+const READ_BUFFER_INITIAL_CAPACITY: usize = 64 * 1024;
+
 /// Write a message to the stream.
 pub async fn write_message<W: AsyncWriteExt + Unpin>(
     writer: &mut W,
     msg_type: MessageType,
     payload: &[u8],
 ) -> Result<(), AgentProtocolError> {
-    if payload.len() > MAX_UDS_MESSAGE_SIZE {
+    write_message_with_limit(writer, msg_type, payload, MAX_UDS_MESSAGE_SIZE).await
+}
+
+/// Write a message to the stream, enforcing a negotiated size limit.
+pub async fn write_message_with_limit<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    msg_type: MessageType,
+    payload: &[u8],
+    max_message_size: usize,
+) -> Result<(), AgentProtocolError> {
+    // The length prefix has to fit the payload and the type byte
+    let max = max_message_size.min(u32::MAX as usize - 1);
+    if payload.len() > max {
         return Err(AgentProtocolError::MessageTooLarge {
             size: payload.len(),
-            max: MAX_UDS_MESSAGE_SIZE,
+            max,
         });
     }
 
@@ -1087,6 +1799,14 @@ pub async fn write_message<W: AsyncWriteExt + Unpin>(
 /// Read a message from the stream.
 pub async fn read_message<R: AsyncReadExt + Unpin>(
     reader: &mut R,
+) -> Result<(MessageType, Vec<u8>), AgentProtocolError> {
+    read_message_with_limit(reader, MAX_UDS_MESSAGE_SIZE).await
+}
+
+/// Read a message from the stream, enforcing a negotiated size limit.
+pub async fn read_message_with_limit<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    max_message_size: usize,
 ) -> Result<(MessageType, Vec<u8>), AgentProtocolError> {
     // Read length (4 bytes, big-endian)
     let mut len_bytes = [0u8; 4];
@@ -1106,28 +1826,50 @@ pub async fn read_message<R: AsyncReadExt + Unpin>(
         ));
     }
 
-    if total_len > MAX_UDS_MESSAGE_SIZE {
+    // The length prefix includes the type byte
+    let payload_len = total_len - 1;
+    if payload_len > max_message_size {
         return Err(AgentProtocolError::MessageTooLarge {
-            size: total_len,
-            max: MAX_UDS_MESSAGE_SIZE,
+            size: payload_len,
+            max: max_message_size,
         });
     }
 
     // Read message type (1 byte)
     let mut type_byte = [0u8; 1];
-    reader.read_exact(&mut type_byte).await?;
-    let msg_type = MessageType::try_from(type_byte[0])?;
-
-    // Read payload
-    let payload_len = total_len - 1;
-    let mut payload = vec![0u8; payload_len];
-    if payload_len > 0 {
-        reader.read_exact(&mut payload).await?;
+    reader.read_exact(&mut type_byte).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            truncated(total_len, 0)
+        } else {
+            e.into()
+        }
+    })?;
+
+    // Read payload. The buffer grows as data arrives, so a length prefix
+    // alone cannot make us allocate the maximum message size.
+    let mut payload = Vec::with_capacity(payload_len.min(READ_BUFFER_INITIAL_CAPACITY));
+    (&mut *reader)
+        .take(payload_len as u64)
+        .read_to_end(&mut payload)
+        .await?;
+    if payload.len() < payload_len {
+        return Err(truncated(total_len, payload.len() + 1));
     }
 
+    // Checked last: the whole frame has been consumed, so an unknown type
+    // leaves the stream at the next message
+    let msg_type = MessageType::try_from(type_byte[0])?;
+
     Ok((msg_type, payload))
 }
 
+fn truncated(expected: usize, got: usize) -> AgentProtocolError {
+    AgentProtocolError::InvalidMessage(format!(
+        "Truncated message: expected {} bytes, got {}",
+        expected, got
+    ))
+}
+
 fn now_ms() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -1145,6 +1887,8 @@ mod tests {
             MessageType::HandshakeRequest,
             MessageType::HandshakeResponse,
             MessageType::RequestHeaders,
+            MessageType::RequestBodyChunkShm,
+            MessageType::ResponseBodyChunkShm,
             MessageType::AgentResponse,
             MessageType::HealthStatus,
             MessageType::Ping,
@@ -1172,6 +1916,8 @@ mod tests {
             proxy_version: "1.0.0".to_string(),
             config: None,
             supported_encodings: vec![],
+            max_message_size: None,
+            shm: None,
         };
 
         let json = serde_json::to_string(&req).unwrap();
@@ -1181,6 +1927,33 @@ mod tests {
         assert_eq!(parsed.proxy_id, "test-proxy");
     }
 
+    #[test]
+    fn test_v1_handshake_response_defaults() {
+        // v1 agents answer without a version, features or limits
+        let response: UdsHandshakeResponse = serde_json::from_value(serde_json::json!({
+            "capabilities": {
+                "agent_id": "old-waf",
+                "name": "Old WAF",
+                "version": "0.9.0",
+                "supported_events": [1, 2],
+            },
+            "success": true,
+            "error": null,
+        }))
+        .unwrap();
+        assert_eq!(response.encoding, UdsEncoding::Json);
+
+        let version = accept_agent_version("old-waf", response.protocol_version).unwrap();
+        let caps = adapt_capabilities(version, response.capabilities.into());
+        assert_eq!(caps.protocol_version, 1);
+        assert_eq!(caps.supported_events.len(), 2);
+        assert!(!caps.features.cancellation);
+        assert_eq!(
+            caps.limits.max_body_size,
+            AgentLimits::default().max_body_size
+        );
+    }
+
     #[tokio::test]
     async fn test_write_read_message() {
         use tokio::io::duplex;
@@ -1277,6 +2050,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fragment_message_types() {
+        assert_eq!(
+            MessageType::try_from(0x18).unwrap(),
+            MessageType::RequestBodyChunkFragment
+        );
+        assert_eq!(
+            fragment_type_for(MessageType::ResponseBodyChunk),
+            MessageType::ResponseBodyChunkFragment
+        );
+    }
+
+    #[test]
+    fn test_chunk_reassembler() {
+        let mut reassembler = ChunkReassembler::new(1024);
+
+        // Unsplit chunks pass straight through
+        assert_eq!(reassembler.complete("a", 0, "xyz".into()).unwrap(), "xyz");
+
+        reassembler.push_fragment("a", 1, "AAAA");
+        reassembler.push_fragment("a", 1, "BBBB");
+        reassembler.push_fragment("b", 1, "CCCC");
+        assert_eq!(reassembler.in_progress(), 2);
+        assert_eq!(
+            reassembler.complete("a", 1, "CC==".into()).unwrap(),
+            "AAAABBBBCC=="
+        );
+
+        reassembler.discard("b");
+        assert_eq!(reassembler.in_progress(), 0);
+        assert_eq!(reassembler.buffered, 0);
+    }
+
+    #[test]
+    fn test_chunk_reassembler_overflow() {
+        let mut reassembler = ChunkReassembler::new(8);
+        reassembler.push_fragment("a", 0, "AAAA");
+        reassembler.push_fragment("a", 0, "BBBBBBBB");
+        reassembler.push_fragment("a", 0, "CC");
+        assert!(matches!(
+            reassembler.complete("a", 0, "DD".into()),
+            Err(AgentProtocolError::MessageTooLarge { .. })
+        ));
+        assert_eq!(reassembler.buffered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_message_enforces_limit() {
+        use tokio::io::duplex;
+
+        let (mut client, mut server) = duplex(4096);
+        write_message(&mut client, MessageType::Ping, &[0u8; 100])
+            .await
+            .unwrap();
+        let result = read_message_with_limit(&mut server, 64).await;
+        assert!(matches!(
+            result,
+            Err(AgentProtocolError::MessageTooLarge { size: 100, max: 64 })
+        ));
+
+        let result =
+            write_message_with_limit(&mut client, MessageType::Ping, &[0u8; 100], 64).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_message_rejects_malformed_frames() {
+        // Length prefix promising more than the stream holds
+        let mut truncated: &[u8] = &[0, 0, 0, 10, MessageType::Ping as u8, 1, 2];
+        assert!(matches!(
+            read_message_with_limit(&mut truncated, usize::MAX).await,
+            Err(AgentProtocolError::InvalidMessage(_))
+        ));
+
+        // Unknown type: rejected, but the frame is consumed
+        let mut stream: &[u8] = &[0, 0, 0, 2, 0xEE, 0, 0, 0, 0, 1, MessageType::Pong as u8];
+        assert!(read_message(&mut stream).await.is_err());
+        let (msg_type, payload) = read_message(&mut stream).await.unwrap();
+        assert_eq!(msg_type, MessageType::Pong);
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "binary-uds")]
+    fn test_uds_encoding_msgpack_skipped_fields() {
+        // Fields omitted by `skip_serializing_if` must not shift the others
+        let mut response = AgentResponse::block(403, Some("denied".to_string()));
+        response.needs_more = true;
+        let encoding = UdsEncoding::MessagePack;
+        let parsed: AgentResponse = encoding
+            .deserialize(&encoding.serialize(&response).unwrap())
+            .unwrap();
+        assert_eq!(parsed.decision, response.decision);
+        assert!(parsed.needs_more);
+    }
+
     #[test]
     fn test_uds_encoding_default() {
         assert_eq!(UdsEncoding::default(), UdsEncoding::Json);
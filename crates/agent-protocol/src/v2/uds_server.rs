@@ -14,10 +14,13 @@ use tracing::{debug, error, info, trace, warn};
 
 use crate::v2::server::AgentHandlerV2;
 use crate::v2::uds::{
-    read_message, write_message, MessageType, UdsCapabilities, UdsEncoding, UdsHandshakeRequest,
-    UdsHandshakeResponse,
+    read_message, read_message_with_limit, write_message, write_message_with_limit,
+    ChunkReassembler, MessageType, UdsCapabilities, UdsEncoding, UdsHandshakeRequest,
+    UdsHandshakeResponse, MAX_UDS_MESSAGE_SIZE,
 };
 use crate::v2::HandshakeRequest;
+#[cfg(feature = "mmap-buffers")]
+use crate::v2::{shm::ShmSegment, uds::UdsShmBodyChunk};
 use crate::{
     AgentProtocolError, AgentResponse, RequestBodyChunkEvent, RequestCompleteEvent,
     RequestHeadersEvent, ResponseBodyChunkEvent, ResponseHeadersEvent, WebSocketFrameEvent,
@@ -189,10 +192,46 @@ async fn handle_connection(
     // Negotiate encoding: pick the first proxy-preferred encoding we support
     let negotiated_encoding = negotiate_encoding(&uds_req.supported_encodings);
 
+    // Negotiate message size: the smaller of both limits. Proxies that do not
+    // advertise a limit use the transport default.
+    let mut capabilities = handshake_resp.capabilities;
+    let max_message_size = uds_req
+        .max_message_size
+        .map_or(MAX_UDS_MESSAGE_SIZE, |size| size as usize)
+        .min(capabilities.limits.max_message_size)
+        .min(crate::MAX_NEGOTIATED_MESSAGE_SIZE);
+    capabilities.limits.max_message_size = max_message_size;
+    // Fragment reassembly is handled here, independent of the handler
+    capabilities.features.chunk_reassembly = true;
+    let max_buffered = capabilities.limits.max_body_size.max(max_message_size);
+    #[cfg_attr(not(feature = "mmap-buffers"), allow(unused_mut))]
+    let mut uds_capabilities = UdsCapabilities::from(capabilities);
+
+    // Map the shared-memory segment if the proxy offered one
+    #[cfg(feature = "mmap-buffers")]
+    let shm = match uds_req.shm.as_ref().filter(|_| success) {
+        Some(offer) => match ShmSegment::open(offer) {
+            Ok(segment) => {
+                uds_capabilities.features.shared_memory = true;
+                Some(segment)
+            }
+            Err(e) => {
+                warn!(
+                    agent_id = %agent_id,
+                    path = %offer.path,
+                    error = %e,
+                    "Failed to map shared memory segment, receiving bodies inline"
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
     // Build UDS-level response
     let uds_resp = UdsHandshakeResponse {
         protocol_version: handshake_resp.protocol_version,
-        capabilities: UdsCapabilities::from(handshake_resp.capabilities),
+        capabilities: uds_capabilities,
         success,
         error: handshake_resp.error,
         encoding: negotiated_encoding,
@@ -210,23 +249,34 @@ async fn handle_connection(
     info!(
         agent_id = %agent_id,
         encoding = ?negotiated_encoding,
+        max_message_size = max_message_size,
+        shared_memory = uds_resp.capabilities.features.shared_memory,
         "UDS v2 handshake completed"
     );
 
     // ── Event loop (uses negotiated encoding) ────────────────────────────
 
+    let mut reassembler = ChunkReassembler::new(max_buffered);
+
     loop {
-        let (msg_type, payload) = read_message(&mut reader).await?;
+        let (msg_type, payload) = read_message_with_limit(&mut reader, max_message_size).await?;
 
         match msg_type {
             MessageType::Ping => {
                 trace!(agent_id = %agent_id, "Received ping, sending pong");
                 // Echo the payload back as pong
-                write_message(&mut writer, MessageType::Pong, &payload).await?;
+                write_message_with_limit(
+                    &mut writer,
+                    MessageType::Pong,
+                    &payload,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::Cancel => {
                 // Extract correlation_id for logging
                 let cid = extract_correlation_id(&negotiated_encoding, &payload);
+                reassembler.discard(&cid);
                 debug!(
                     agent_id = %agent_id,
                     correlation_id = %cid,
@@ -236,36 +286,140 @@ async fn handle_connection(
             MessageType::RequestHeaders => {
                 let response =
                     handle_request_headers(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
+            }
+            MessageType::RequestBodyChunkFragment => {
+                buffer_fragment::<RequestBodyChunkEvent>(
+                    &mut reassembler,
+                    &negotiated_encoding,
+                    &payload,
+                    |e| (&e.correlation_id, e.chunk_index, &e.data),
+                );
+            }
+            MessageType::ResponseBodyChunkFragment => {
+                buffer_fragment::<ResponseBodyChunkEvent>(
+                    &mut reassembler,
+                    &negotiated_encoding,
+                    &payload,
+                    |e| (&e.correlation_id, e.chunk_index, &e.data),
+                );
             }
             MessageType::RequestBodyChunk => {
-                let response =
-                    handle_request_body_chunk(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                let response = handle_request_body_chunk(
+                    &handler,
+                    &negotiated_encoding,
+                    &payload,
+                    &mut reassembler,
+                )
+                .await;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
+            }
+            #[cfg(feature = "mmap-buffers")]
+            MessageType::RequestBodyChunkShm => {
+                let response = handle_shm_body_chunk(
+                    &handler,
+                    &negotiated_encoding,
+                    &payload,
+                    shm.as_ref(),
+                    true,
+                )
+                .await;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
+            }
+            #[cfg(feature = "mmap-buffers")]
+            MessageType::ResponseBodyChunkShm => {
+                let response = handle_shm_body_chunk(
+                    &handler,
+                    &negotiated_encoding,
+                    &payload,
+                    shm.as_ref(),
+                    false,
+                )
+                .await;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::ResponseHeaders => {
                 let response =
                     handle_response_headers(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::ResponseBodyChunk => {
-                let response =
-                    handle_response_body_chunk(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                let response = handle_response_body_chunk(
+                    &handler,
+                    &negotiated_encoding,
+                    &payload,
+                    &mut reassembler,
+                )
+                .await;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::RequestComplete => {
                 let response =
                     handle_request_complete(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::WebSocketFrame => {
                 let response =
                     handle_websocket_frame(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             MessageType::Configure => {
                 let response = handle_configure(&handler, &negotiated_encoding, &payload).await;
-                write_response(&mut writer, &negotiated_encoding, response).await?;
+                write_response(
+                    &mut writer,
+                    &negotiated_encoding,
+                    response,
+                    max_message_size,
+                )
+                .await?;
             }
             _ => {
                 warn!(
@@ -319,8 +473,9 @@ async fn handle_request_body_chunk(
     handler: &Arc<dyn AgentHandlerV2>,
     encoding: &UdsEncoding,
     payload: &[u8],
+    reassembler: &mut ChunkReassembler,
 ) -> (String, AgentResponse, u64) {
-    let event: RequestBodyChunkEvent = match encoding.deserialize(payload) {
+    let mut event: RequestBodyChunkEvent = match encoding.deserialize(payload) {
         Ok(e) => e,
         Err(e) => {
             warn!(error = %e, "Failed to deserialize RequestBodyChunk");
@@ -329,6 +484,13 @@ async fn handle_request_body_chunk(
         }
     };
     let cid = event.correlation_id.clone();
+    match reassembler.complete(&cid, event.chunk_index, std::mem::take(&mut event.data)) {
+        Ok(data) => event.data = data,
+        Err(e) => {
+            warn!(correlation_id = %cid, error = %e, "Dropping oversized RequestBodyChunk");
+            return (cid, payload_too_large(), 0);
+        }
+    }
     let start = Instant::now();
     let resp = handler.on_request_body_chunk(event).await;
     (cid, resp, start.elapsed().as_millis() as u64)
@@ -357,8 +519,9 @@ async fn handle_response_body_chunk(
     handler: &Arc<dyn AgentHandlerV2>,
     encoding: &UdsEncoding,
     payload: &[u8],
+    reassembler: &mut ChunkReassembler,
 ) -> (String, AgentResponse, u64) {
-    let event: ResponseBodyChunkEvent = match encoding.deserialize(payload) {
+    let mut event: ResponseBodyChunkEvent = match encoding.deserialize(payload) {
         Ok(e) => e,
         Err(e) => {
             warn!(error = %e, "Failed to deserialize ResponseBodyChunk");
@@ -367,11 +530,81 @@ async fn handle_response_body_chunk(
         }
     };
     let cid = event.correlation_id.clone();
+    match reassembler.complete(&cid, event.chunk_index, std::mem::take(&mut event.data)) {
+        Ok(data) => event.data = data,
+        Err(e) => {
+            warn!(correlation_id = %cid, error = %e, "Dropping oversized ResponseBodyChunk");
+            return (cid, payload_too_large(), 0);
+        }
+    }
     let start = Instant::now();
     let resp = handler.on_response_body_chunk(event).await;
     (cid, resp, start.elapsed().as_millis() as u64)
 }
 
+/// Handle a body chunk whose data lives in shared memory.
+///
+/// The slot is read (and released) before the handler runs; the proxy keeps
+/// its own reference until this chunk has been answered.
+#[cfg(feature = "mmap-buffers")]
+async fn handle_shm_body_chunk(
+    handler: &Arc<dyn AgentHandlerV2>,
+    encoding: &UdsEncoding,
+    payload: &[u8],
+    shm: Option<&ShmSegment>,
+    is_request: bool,
+) -> (String, AgentResponse, u64) {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let chunk: UdsShmBodyChunk = match encoding.deserialize(payload) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!(error = %e, "Failed to deserialize shared memory body chunk");
+            let cid = extract_correlation_id(encoding, payload);
+            return (cid, AgentResponse::default_allow(), 0);
+        }
+    };
+    let cid = chunk.correlation_id;
+    let data = match shm.map(|segment| segment.read(&chunk.shm)) {
+        Some(Ok(data)) => STANDARD.encode(&*data),
+        Some(Err(e)) => {
+            warn!(correlation_id = %cid, error = %e, "Failed to read shared memory body chunk");
+            return (cid, AgentResponse::default_allow(), 0);
+        }
+        None => {
+            warn!(correlation_id = %cid, "Shared memory body chunk without a mapped segment");
+            return (cid, AgentResponse::default_allow(), 0);
+        }
+    };
+
+    let start = Instant::now();
+    let resp = if is_request {
+        handler
+            .on_request_body_chunk(RequestBodyChunkEvent {
+                correlation_id: cid.clone(),
+                data,
+                is_last: chunk.is_last,
+                total_size: chunk.total_size,
+                chunk_index: chunk.chunk_index,
+                bytes_received: chunk.bytes_received,
+                part: None,
+            })
+            .await
+    } else {
+        handler
+            .on_response_body_chunk(ResponseBodyChunkEvent {
+                correlation_id: cid.clone(),
+                data,
+                is_last: chunk.is_last,
+                total_size: chunk.total_size,
+                chunk_index: chunk.chunk_index,
+                bytes_sent: chunk.bytes_sent,
+            })
+            .await
+    };
+    (cid, resp, start.elapsed().as_millis() as u64)
+}
+
 async fn handle_request_complete(
     handler: &Arc<dyn AgentHandlerV2>,
     encoding: &UdsEncoding,
@@ -448,6 +681,28 @@ async fn handle_configure(
     (cid, resp, start.elapsed().as_millis() as u64)
 }
 
+/// Buffer a leading fragment of a split body chunk. Fragments get no response;
+/// the final regular body chunk message is answered once reassembled.
+fn buffer_fragment<E: serde::de::DeserializeOwned>(
+    reassembler: &mut ChunkReassembler,
+    encoding: &UdsEncoding,
+    payload: &[u8],
+    parts: impl Fn(&E) -> (&String, u32, &String),
+) {
+    match encoding.deserialize::<E>(payload) {
+        Ok(event) => {
+            let (cid, chunk_index, data) = parts(&event);
+            reassembler.push_fragment(cid, chunk_index, data);
+        }
+        Err(e) => warn!(error = %e, "Failed to deserialize body chunk fragment"),
+    }
+}
+
+/// Response for a body chunk whose fragments exceeded the reassembly buffer.
+fn payload_too_large() -> AgentResponse {
+    AgentResponse::block(413, Some("Payload Too Large".to_string()))
+}
+
 // ─── Response serialization ──────────────────────────────────────────────────
 
 /// Serialize and write an agent response, injecting the correlation ID into
@@ -456,6 +711,7 @@ async fn write_response<W: tokio::io::AsyncWriteExt + Unpin>(
     writer: &mut W,
     encoding: &UdsEncoding,
     (correlation_id, mut response, _processing_time_ms): (String, AgentResponse, u64),
+    max_message_size: usize,
 ) -> Result<(), AgentProtocolError> {
     // Inject correlation_id so the client can route the response
     response.audit.custom.insert(
@@ -464,7 +720,13 @@ async fn write_response<W: tokio::io::AsyncWriteExt + Unpin>(
     );
 
     let resp_bytes = encoding.serialize(&response)?;
-    write_message(writer, MessageType::AgentResponse, &resp_bytes).await
+    write_message_with_limit(
+        writer,
+        MessageType::AgentResponse,
+        &resp_bytes,
+        max_message_size,
+    )
+    .await
 }
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
@@ -581,6 +843,10 @@ mod tests {
                 upstream_id: None,
                 timestamp: "0".to_string(),
                 traceparent: None,
+                client_cert: None,
+                tags: Vec::new(),
+                bot_signals: None,
+                dry_run: false,
             },
             method: "GET".to_string(),
             uri: "/test".to_string(),
@@ -605,4 +871,168 @@ mod tests {
         server_handle.abort();
         let _ = std::fs::remove_file(&socket_path_clone);
     }
+    struct BodyLengthHandler;
+
+    #[async_trait]
+    impl AgentHandlerV2 for BodyLengthHandler {
+        fn capabilities(&self) -> AgentCapabilities {
+            AgentCapabilities::new("test-uds-split", "Body Length Agent", "1.0.0")
+                .with_event(crate::EventType::RequestBodyChunk)
+        }
+
+        async fn on_request_body_chunk(&self, event: RequestBodyChunkEvent) -> AgentResponse {
+            use base64::{engine::general_purpose::STANDARD, Engine as _};
+            let len = STANDARD.decode(&event.data).map(|d| d.len()).unwrap_or(0);
+            AgentResponse::default_allow().add_request_header(crate::HeaderOp::Set {
+                name: "x-body-len".to_string(),
+                value: len.to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_chunk_is_split_and_reassembled() {
+        use crate::v2::uds::AgentClientV2Uds;
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use std::time::Duration;
+
+        let socket_path = format!("/tmp/test-uds-v2-split-{}.sock", std::process::id());
+        let server = UdsAgentServerV2::new("test-split", &socket_path, Box::new(BodyLengthHandler));
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut client = AgentClientV2Uds::new("test-agent", &socket_path, Duration::from_secs(5))
+            .await
+            .unwrap();
+        client.set_max_message_size(1024);
+        client.connect().await.unwrap();
+        assert_eq!(client.max_message_size(), 1024);
+
+        // ~13KB of base64 needs many 1KB messages
+        let body: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let event = RequestBodyChunkEvent {
+            correlation_id: "split-cid".to_string(),
+            data: STANDARD.encode(&body),
+            is_last: true,
+            total_size: Some(body.len()),
+            chunk_index: 0,
+            bytes_received: body.len(),
+            part: None,
+        };
+
+        let response = client
+            .send_request_body_chunk("split-cid", &event)
+            .await
+            .unwrap();
+        assert!(response.request_headers.iter().any(|h| matches!(
+            h,
+            crate::HeaderOp::Set { name, value } if name == "x-body-len" && value == "10000"
+        )));
+
+        // Binary path splits on raw bytes
+        let binary = crate::BinaryRequestBodyChunkEvent::new("split-bin", body.clone(), 1, true);
+        let response = client
+            .send_request_body_chunk_binary(&binary)
+            .await
+            .unwrap();
+        assert!(response.request_headers.iter().any(|h| matches!(
+            h,
+            crate::HeaderOp::Set { name, value } if name == "x-body-len" && value == "10000"
+        )));
+
+        client.close().await.unwrap();
+        server_handle.abort();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[tokio::test]
+    async fn test_pool_binary_body_chunk_over_uds() {
+        use crate::v2::pool::{AgentPool, AgentPoolConfig};
+        use std::time::Duration;
+
+        let socket_path = format!("/tmp/test-uds-v2-pool-bin-{}.sock", std::process::id());
+        let server =
+            UdsAgentServerV2::new("test-pool-bin", &socket_path, Box::new(BodyLengthHandler));
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let pool = AgentPool::with_config(AgentPoolConfig {
+            connections_per_agent: 1,
+            ..Default::default()
+        });
+        pool.add_agent("body-len", &socket_path).await.unwrap();
+
+        let request = crate::BinaryRequestBodyChunkEvent::new("pool-req", vec![7u8; 4096], 0, true);
+        let response = pool
+            .send_request_body_chunk_binary("body-len", &request)
+            .await
+            .unwrap();
+        assert!(response.request_headers.iter().any(|h| matches!(
+            h,
+            crate::HeaderOp::Set { name, value } if name == "x-body-len" && value == "4096"
+        )));
+
+        let response_chunk =
+            crate::BinaryResponseBodyChunkEvent::new("pool-resp", vec![7u8; 512], 0, true);
+        let response = pool
+            .send_response_body_chunk_binary("body-len", &response_chunk)
+            .await
+            .unwrap();
+        assert!(matches!(response.decision, crate::Decision::Allow));
+
+        pool.shutdown().await.unwrap();
+        server_handle.abort();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[cfg(feature = "mmap-buffers")]
+    #[tokio::test]
+    async fn test_body_chunk_over_shared_memory() {
+        use crate::v2::shm::ShmConfig;
+        use crate::v2::uds::AgentClientV2Uds;
+        use std::time::Duration;
+
+        let socket_path = format!("/tmp/test-uds-v2-shm-{}.sock", std::process::id());
+        let server = UdsAgentServerV2::new("test-shm", &socket_path, Box::new(BodyLengthHandler));
+        let server_handle = tokio::spawn(async move {
+            let _ = server.run().await;
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let shm_dir = tempfile::tempdir().unwrap();
+        let mut client = AgentClientV2Uds::new("test-agent", &socket_path, Duration::from_secs(5))
+            .await
+            .unwrap();
+        client.set_shared_memory(ShmConfig {
+            dir: shm_dir.path().to_path_buf(),
+            slot_count: 4,
+            slot_size: 64 * 1024,
+            min_chunk_size: 1024,
+            ..Default::default()
+        });
+        client.connect().await.unwrap();
+        assert!(client.shared_memory_active().await);
+
+        // Large chunk goes through shared memory, small one inline
+        for (cid, len) in [("shm-large", 50_000usize), ("shm-small", 100)] {
+            let body: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let event = crate::BinaryRequestBodyChunkEvent::new(cid, body, 0, true);
+            let response = client.send_request_body_chunk_binary(&event).await.unwrap();
+            let expected = len.to_string();
+            assert!(response.request_headers.iter().any(|h| matches!(
+                h,
+                crate::HeaderOp::Set { name, value } if name == "x-body-len" && *value == expected
+            )));
+        }
+
+        // Closing the connection removes the segment
+        client.close().await.unwrap();
+        assert_eq!(std::fs::read_dir(shm_dir.path()).unwrap().count(), 0);
+        server_handle.abort();
+        let _ = std::fs::remove_file(&socket_path);
+    }
 }
@@ -0,0 +1,152 @@
+//! Protocol version negotiation.
+//!
+//! The proxy advertises every version it speaks, newest first, and the agent
+//! answers with the one it picked. Agents still on protocol v1 keep working
+//! through a small adapter: fields their handshake lacks are mapped to
+//! defaults and v2-only features are switched off, so the proxy never sends
+//! them messages they do not understand. Every v1 connection logs a
+//! deprecation warning.
+
+use tracing::warn;
+
+use super::{AgentCapabilities, AgentLimits, HealthConfig, PROTOCOL_VERSION_2};
+use crate::AgentProtocolError;
+
+/// Protocol version 1 (deprecated)
+pub const PROTOCOL_VERSION_1: u32 = 1;
+
+/// Oldest protocol version the proxy still speaks
+pub const MIN_PROTOCOL_VERSION: u32 = PROTOCOL_VERSION_1;
+
+/// Newest protocol version the proxy speaks
+pub const MAX_PROTOCOL_VERSION: u32 = PROTOCOL_VERSION_2;
+
+/// Versions advertised in handshakes, newest first.
+pub fn supported_versions() -> Vec<u32> {
+    (MIN_PROTOCOL_VERSION..=MAX_PROTOCOL_VERSION)
+        .rev()
+        .collect()
+}
+
+/// Newest version in `offered` that is also supported here.
+pub fn negotiate_version(offered: &[u32]) -> Option<u32> {
+    offered
+        .iter()
+        .copied()
+        .filter(|v| super::supports_version(*v))
+        .max()
+}
+
+/// Check the version an agent answered the handshake with.
+///
+/// Agents that leave the version out (0) predate negotiation and speak v1.
+/// Versions outside the supported range fail with
+/// [`AgentProtocolError::VersionMismatch`]; v1 logs a deprecation warning.
+pub fn accept_agent_version(agent_id: &str, version: u32) -> Result<u32, AgentProtocolError> {
+    let version = if version == 0 {
+        PROTOCOL_VERSION_1
+    } else {
+        version
+    };
+
+    if !super::supports_version(version) {
+        return Err(AgentProtocolError::VersionMismatch {
+            expected: MAX_PROTOCOL_VERSION,
+            actual: version,
+        });
+    }
+
+    if version < PROTOCOL_VERSION_2 {
+        warn!(
+            agent_id = %agent_id,
+            protocol_version = version,
+            latest_version = MAX_PROTOCOL_VERSION,
+            "Agent uses deprecated protocol version, upgrade it to protocol v2"
+        );
+    }
+
+    Ok(version)
+}
+
+/// Capabilities of an agent speaking `version`, as the proxy should treat them.
+///
+/// v2 capabilities are returned unchanged. v1 has no cancellation, flow
+/// control, health reports, config push, metrics export or chunk
+/// reassembly, and limits a v1 agent left at zero take their defaults.
+pub fn adapt_capabilities(version: u32, mut capabilities: AgentCapabilities) -> AgentCapabilities {
+    if version >= PROTOCOL_VERSION_2 {
+        return capabilities;
+    }
+    capabilities.protocol_version = PROTOCOL_VERSION_1;
+
+    let features = &mut capabilities.features;
+    features.config_push = false;
+    features.metrics_export = false;
+    features.cancellation = false;
+    features.flow_control = false;
+    features.health_reporting = false;
+    features.chunk_reassembly = false;
+
+    let defaults = AgentLimits::default();
+    let limits = &mut capabilities.limits;
+    if limits.max_body_size == 0 {
+        limits.max_body_size = defaults.max_body_size;
+    }
+    if limits.max_concurrency == 0 {
+        limits.max_concurrency = defaults.max_concurrency;
+    }
+    if limits.preferred_chunk_size == 0 {
+        limits.preferred_chunk_size = defaults.preferred_chunk_size;
+    }
+    capabilities.health = HealthConfig::default();
+
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::AgentFeatures;
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(supported_versions(), vec![2, 1]);
+        assert_eq!(negotiate_version(&[1, 2]), Some(2));
+        assert_eq!(negotiate_version(&[3, 1]), Some(1));
+        assert_eq!(negotiate_version(&[3]), None);
+        assert_eq!(negotiate_version(&[]), None);
+    }
+
+    #[test]
+    fn test_accept_agent_version() {
+        let mut caps = AgentCapabilities::new("old-waf", "Old WAF", "0.9.0");
+        caps.features = AgentFeatures::full();
+        caps.limits.max_body_size = 0;
+
+        assert_eq!(accept_agent_version("waf", 2).unwrap(), 2);
+        let current = adapt_capabilities(2, caps.clone());
+        assert!(current.features.cancellation);
+        assert_eq!(current.limits.max_body_size, 0);
+
+        // A missing version means v1
+        let version = accept_agent_version("old-waf", 0).unwrap();
+        assert_eq!(version, PROTOCOL_VERSION_1);
+        let legacy = adapt_capabilities(version, caps);
+        assert_eq!(legacy.protocol_version, PROTOCOL_VERSION_1);
+        assert!(!legacy.features.cancellation);
+        assert!(!legacy.features.flow_control);
+        assert!(legacy.features.streaming_body);
+        assert_eq!(
+            legacy.limits.max_body_size,
+            AgentLimits::default().max_body_size
+        );
+
+        assert!(matches!(
+            accept_agent_version("future", 3),
+            Err(AgentProtocolError::VersionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,715 @@
+//! Property tests for the agent wire protocol.
+//!
+//! - every protocol type survives a round trip through each UDS payload
+//!   encoding (JSON, and MessagePack with the `binary-uds` feature)
+//! - the binary framing types round-trip
+//! - decoders return errors, never panic, on arbitrary input
+
+use proptest::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use zentinel_agent_protocol::binary::{
+    BinaryAgentResponse, BinaryBodyChunk, BinaryFrame, BinaryRequestHeaders,
+};
+use zentinel_agent_protocol::v2::uds::{read_message_with_limit, write_message};
+use zentinel_agent_protocol::v2::{
+    AgentCapabilities, AgentFeatures, AgentLimits, HandshakeRequest, HandshakeResponse,
+    HealthConfig, MessageType, UdsCapabilities, UdsEncoding, UdsFeatures, UdsHandshakeRequest,
+    UdsHandshakeResponse, UdsLimits, WarmupRequest,
+};
+use zentinel_agent_protocol::{
+    AgentResponse, AuditMetadata, BodyMutation, BotSignals, ClientCertificate, Decision,
+    DetectionSeverity, EventType, GuardrailDetection, GuardrailInspectEvent,
+    GuardrailInspectionType, GuardrailResponse, HeaderOp, LatencyBreakdown, RequestBodyChunkEvent,
+    RequestCompleteEvent, RequestHeadersEvent, RequestMetadata, RequestTag, ResponseBodyChunkEvent,
+    ResponseHeadersEvent, TagCardinality, TextSpan, WebSocketDecision, WebSocketFrameEvent,
+};
+
+const ENCODINGS: [UdsEncoding; 2] = [UdsEncoding::Json, UdsEncoding::MessagePack];
+
+/// Encode and decode `value` with every encoding and compare the results.
+///
+/// Values are compared through their JSON form, which also covers the types
+/// without `PartialEq`.
+fn assert_roundtrip<T: Serialize + DeserializeOwned>(value: &T) -> Result<(), TestCaseError> {
+    let expected = serde_json::to_value(value).unwrap();
+    for encoding in ENCODINGS {
+        let bytes = encoding
+            .serialize(value)
+            .map_err(|e| TestCaseError::fail(format!("{encoding:?} encode: {e}")))?;
+        let decoded: T = encoding
+            .deserialize(&bytes)
+            .map_err(|e| TestCaseError::fail(format!("{encoding:?} decode: {e}")))?;
+        prop_assert_eq!(&serde_json::to_value(&decoded).unwrap(), &expected);
+    }
+    Ok(())
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+// =============================================================================
+// Strategies
+// =============================================================================
+
+fn text() -> impl Strategy<Value = String> {
+    "\\PC{0,24}"
+}
+
+fn opt_text() -> impl Strategy<Value = Option<String>> {
+    proptest::option::of(text())
+}
+
+fn string_map() -> impl Strategy<Value = HashMap<String, String>> {
+    prop::collection::hash_map(text(), text(), 0..4)
+}
+
+fn headers() -> impl Strategy<Value = HashMap<String, Vec<String>>> {
+    prop::collection::hash_map(text(), prop::collection::vec(text(), 1..3), 0..4)
+}
+
+/// Confidence scores with a short decimal form, so JSON keeps them exact
+fn confidence() -> impl Strategy<Value = f64> {
+    (0u32..=1000).prop_map(|n| f64::from(n) / 1000.0)
+}
+
+fn event_type() -> impl Strategy<Value = EventType> {
+    prop::sample::select(vec![
+        EventType::Configure,
+        EventType::RequestHeaders,
+        EventType::RequestBodyChunk,
+        EventType::ResponseHeaders,
+        EventType::ResponseBodyChunk,
+        EventType::RequestComplete,
+        EventType::WebSocketFrame,
+        EventType::GuardrailInspect,
+    ])
+}
+
+fn tag() -> impl Strategy<Value = RequestTag> {
+    (text(), text(), any::<bool>()).prop_map(|(name, value, low)| RequestTag {
+        name,
+        value,
+        cardinality: if low {
+            TagCardinality::Low
+        } else {
+            TagCardinality::High
+        },
+    })
+}
+
+fn client_cert() -> impl Strategy<Value = ClientCertificate> {
+    (
+        opt_text(),
+        prop::collection::vec(text(), 0..3),
+        text(),
+        opt_text(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(subject, sans, fingerprint_sha256, serial_number, verified)| ClientCertificate {
+                subject,
+                sans,
+                fingerprint_sha256,
+                serial_number,
+                verified,
+            },
+        )
+}
+
+fn bot_signals() -> impl Strategy<Value = BotSignals> {
+    (opt_text(), opt_text(), opt_text(), opt_text(), opt_text()).prop_map(
+        |(ja3, ja3_hash, ja4, http2, header_order_hash)| BotSignals {
+            ja3,
+            ja3_hash,
+            ja4,
+            http2,
+            header_order_hash,
+        },
+    )
+}
+
+prop_compose! {
+    fn metadata()(
+        (correlation_id, request_id, client_ip, client_port) in (text(), text(), text(), any::<u16>()),
+        (server_name, protocol, tls_version, tls_cipher) in (opt_text(), text(), opt_text(), opt_text()),
+        (route_id, upstream_id, timestamp, traceparent) in (opt_text(), opt_text(), text(), opt_text()),
+        client_cert in proptest::option::of(client_cert()),
+        tags in prop::collection::vec(tag(), 0..3),
+        bot_signals in proptest::option::of(bot_signals()),
+        dry_run in any::<bool>(),
+    ) -> RequestMetadata {
+        RequestMetadata {
+            correlation_id,
+            request_id,
+            client_ip,
+            client_port,
+            server_name,
+            protocol,
+            tls_version,
+            tls_cipher,
+            route_id,
+            upstream_id,
+            timestamp,
+            traceparent,
+            client_cert,
+            tags,
+            bot_signals,
+            dry_run,
+        }
+    }
+}
+
+fn decision() -> impl Strategy<Value = Decision> {
+    prop_oneof![
+        Just(Decision::Allow),
+        (any::<u16>(), opt_text(), proptest::option::of(string_map())).prop_map(
+            |(status, body, headers)| Decision::Block {
+                status,
+                body,
+                headers,
+            }
+        ),
+        (text(), any::<u16>()).prop_map(|(url, status)| Decision::Redirect { url, status }),
+        (text(), string_map()).prop_map(|(challenge_type, params)| Decision::Challenge {
+            challenge_type,
+            params,
+        }),
+    ]
+}
+
+fn header_op() -> impl Strategy<Value = HeaderOp> {
+    prop_oneof![
+        (text(), text()).prop_map(|(name, value)| HeaderOp::Set { name, value }),
+        (text(), text()).prop_map(|(name, value)| HeaderOp::Add { name, value }),
+        text().prop_map(|name| HeaderOp::Remove { name }),
+    ]
+}
+
+fn body_mutation() -> impl Strategy<Value = BodyMutation> {
+    (opt_text(), any::<u32>()).prop_map(|(data, chunk_index)| BodyMutation { data, chunk_index })
+}
+
+fn audit() -> impl Strategy<Value = AuditMetadata> {
+    let custom_value = prop_oneof![
+        text().prop_map(serde_json::Value::from),
+        any::<i64>().prop_map(serde_json::Value::from),
+        any::<bool>().prop_map(serde_json::Value::from),
+    ];
+    (
+        prop::collection::vec(text(), 0..3),
+        prop::collection::vec(text(), 0..3),
+        proptest::option::of((0u32..=100).prop_map(|n| n as f32 / 100.0)),
+        prop::collection::vec(text(), 0..3),
+        prop::collection::hash_map(text(), custom_value, 0..3),
+    )
+        .prop_map(
+            |(tags, rule_ids, confidence, reason_codes, custom)| AuditMetadata {
+                tags,
+                rule_ids,
+                confidence,
+                reason_codes,
+                custom,
+            },
+        )
+}
+
+fn websocket_decision() -> impl Strategy<Value = WebSocketDecision> {
+    prop_oneof![
+        Just(WebSocketDecision::Allow),
+        Just(WebSocketDecision::Drop),
+        (any::<u16>(), text()).prop_map(|(code, reason)| WebSocketDecision::Close { code, reason }),
+    ]
+}
+
+prop_compose! {
+    fn agent_response()(
+        (version, decision, needs_more) in (any::<u32>(), decision(), any::<bool>()),
+        request_headers in prop::collection::vec(header_op(), 0..3),
+        response_headers in prop::collection::vec(header_op(), 0..3),
+        routing_metadata in string_map(),
+        tags in prop::collection::vec(tag(), 0..3),
+        audit in audit(),
+        request_body_mutation in proptest::option::of(body_mutation()),
+        response_body_mutation in proptest::option::of(body_mutation()),
+        websocket_decision in proptest::option::of(websocket_decision()),
+    ) -> AgentResponse {
+        AgentResponse {
+            version,
+            decision,
+            request_headers,
+            response_headers,
+            routing_metadata,
+            tags,
+            audit,
+            needs_more,
+            request_body_mutation,
+            response_body_mutation,
+            websocket_decision,
+        }
+    }
+}
+
+fn latency() -> impl Strategy<Value = LatencyBreakdown> {
+    let ms = || proptest::option::of(any::<u64>());
+    (
+        (ms(), ms(), ms()),
+        any::<u64>(),
+        prop::collection::hash_map(text(), any::<u64>(), 0..3),
+        (ms(), ms(), ms()),
+    )
+        .prop_map(
+            |(
+                (dns_ms, connect_ms, tls_ms),
+                agent_total_ms,
+                agents_ms,
+                (upstream_ttfb_ms, upstream_body_ms, downstream_write_ms),
+            )| LatencyBreakdown {
+                dns_ms,
+                connect_ms,
+                tls_ms,
+                agent_total_ms,
+                agents_ms,
+                upstream_ttfb_ms,
+                upstream_body_ms,
+                downstream_write_ms,
+            },
+        )
+}
+
+fn capabilities() -> impl Strategy<Value = AgentCapabilities> {
+    let warmup = (text(), text(), headers()).prop_map(|(method, uri, headers)| WarmupRequest {
+        method,
+        uri,
+        headers,
+    });
+    (
+        (any::<u32>(), text(), text(), text()),
+        prop::collection::vec(event_type(), 0..4),
+        (any::<bool>(), any::<bool>(), any::<u32>()),
+        (
+            any::<usize>(),
+            any::<u32>(),
+            proptest::option::of(any::<u64>()),
+        ),
+        prop::collection::vec(warmup, 0..2),
+    )
+        .prop_map(
+            |(
+                (protocol_version, agent_id, name, version),
+                supported_events,
+                (streaming_body, cancellation, concurrent_requests),
+                (max_body_size, max_concurrency, max_processing_time_ms),
+                warmup,
+            )| AgentCapabilities {
+                protocol_version,
+                agent_id,
+                name,
+                version,
+                supported_events,
+                features: AgentFeatures {
+                    streaming_body,
+                    cancellation,
+                    concurrent_requests,
+                    ..AgentFeatures::default()
+                },
+                limits: AgentLimits {
+                    max_body_size,
+                    max_concurrency,
+                    max_processing_time_ms,
+                    ..AgentLimits::default()
+                },
+                health: HealthConfig::default(),
+                warmup,
+            },
+        )
+}
+
+fn uds_capabilities() -> impl Strategy<Value = UdsCapabilities> {
+    (
+        (text(), text(), text()),
+        prop::collection::vec(any::<i32>(), 0..4),
+        (any::<bool>(), any::<bool>(), any::<u32>()),
+        (any::<u64>(), any::<u32>(), any::<u64>(), any::<u64>()),
+    )
+        .prop_map(
+            |(
+                (agent_id, name, version),
+                supported_events,
+                (streaming_body, shared_memory, concurrent_requests),
+                (max_body_size, max_concurrency, preferred_chunk_size, max_message_size),
+            )| UdsCapabilities {
+                agent_id,
+                name,
+                version,
+                supported_events,
+                features: UdsFeatures {
+                    streaming_body,
+                    shared_memory,
+                    concurrent_requests,
+                    ..UdsFeatures::default()
+                },
+                limits: UdsLimits {
+                    max_body_size,
+                    max_concurrency,
+                    preferred_chunk_size,
+                    max_message_size,
+                },
+                warmup: Vec::new(),
+            },
+        )
+}
+
+/// Block headers as the binary format carries them: an empty map is `None`
+fn binary_decision() -> impl Strategy<Value = Decision> {
+    decision().prop_map(|decision| match decision {
+        Decision::Block {
+            status,
+            body,
+            headers,
+        } => Decision::Block {
+            status,
+            body,
+            headers: headers.filter(|h| !h.is_empty()),
+        },
+        other => other,
+    })
+}
+
+// =============================================================================
+// Payload Encoding Round Trips
+// =============================================================================
+
+proptest! {
+    #[test]
+    fn request_headers_event_roundtrip(
+        metadata in metadata(),
+        method in text(),
+        uri in text(),
+        headers in headers(),
+    ) {
+        assert_roundtrip(&RequestHeadersEvent { metadata, method, uri, headers })?;
+    }
+
+    #[test]
+    fn body_chunk_events_roundtrip(
+        correlation_id in text(),
+        data in text(),
+        is_last in any::<bool>(),
+        total_size in proptest::option::of(any::<usize>()),
+        chunk_index in any::<u32>(),
+        bytes in any::<usize>(),
+    ) {
+        assert_roundtrip(&RequestBodyChunkEvent {
+            correlation_id: correlation_id.clone(),
+            data: data.clone(),
+            is_last,
+            total_size,
+            chunk_index,
+            bytes_received: bytes,
+            part: None,
+        })?;
+        assert_roundtrip(&ResponseBodyChunkEvent {
+            correlation_id,
+            data,
+            is_last,
+            total_size,
+            chunk_index,
+            bytes_sent: bytes,
+        })?;
+    }
+
+    #[test]
+    fn response_headers_event_roundtrip(
+        correlation_id in text(),
+        status in any::<u16>(),
+        headers in headers(),
+    ) {
+        assert_roundtrip(&ResponseHeadersEvent { correlation_id, status, headers })?;
+    }
+
+    #[test]
+    fn request_complete_event_roundtrip(
+        (correlation_id, status, duration_ms) in (text(), any::<u16>(), any::<u64>()),
+        (request_body_size, response_body_size) in (any::<usize>(), any::<usize>()),
+        upstream_attempts in any::<u32>(),
+        error in opt_text(),
+        latency in latency(),
+    ) {
+        assert_roundtrip(&RequestCompleteEvent {
+            correlation_id,
+            status,
+            duration_ms,
+            request_body_size,
+            response_body_size,
+            upstream_attempts,
+            error,
+            latency,
+        })?;
+    }
+
+    #[test]
+    fn websocket_frame_event_roundtrip(
+        (correlation_id, opcode, data) in (text(), text(), text()),
+        (client_to_server, frame_index, fin) in (any::<bool>(), any::<u64>(), any::<bool>()),
+        route_id in opt_text(),
+        client_ip in text(),
+    ) {
+        assert_roundtrip(&WebSocketFrameEvent {
+            correlation_id,
+            opcode,
+            data,
+            client_to_server,
+            frame_index,
+            fin,
+            route_id,
+            client_ip,
+        })?;
+    }
+
+    #[test]
+    fn guardrail_types_roundtrip(
+        (correlation_id, content, model, route_id) in (text(), text(), opt_text(), opt_text()),
+        pii in any::<bool>(),
+        categories in prop::collection::vec(text(), 0..3),
+        metadata in string_map(),
+        detections in prop::collection::vec(
+            (text(), text(), proptest::option::of(confidence()), proptest::option::of((any::<usize>(), any::<usize>()))),
+            0..3,
+        ),
+        redacted_content in opt_text(),
+        embedding in proptest::option::of(prop::collection::vec(-1.0f32..1.0, 0..8)),
+        embedding_model in opt_text(),
+    ) {
+        assert_roundtrip(&GuardrailInspectEvent {
+            correlation_id,
+            inspection_type: if pii {
+                GuardrailInspectionType::PiiDetection
+            } else {
+                GuardrailInspectionType::PromptInjection
+            },
+            content,
+            model,
+            categories,
+            route_id,
+            metadata,
+            embedding,
+            embedding_model,
+        })?;
+
+        let detections: Vec<_> = detections
+            .into_iter()
+            .map(|(category, description, confidence, span)| GuardrailDetection {
+                category,
+                description,
+                severity: DetectionSeverity::High,
+                confidence,
+                span: span.map(|(start, end)| TextSpan { start, end }),
+            })
+            .collect();
+        assert_roundtrip(&GuardrailResponse {
+            detected: !detections.is_empty(),
+            confidence: 0.5,
+            detections,
+            redacted_content,
+        })?;
+    }
+
+    #[test]
+    fn agent_response_roundtrip(response in agent_response()) {
+        assert_roundtrip(&response)?;
+    }
+
+    #[test]
+    fn handshake_roundtrip(
+        supported_versions in prop::collection::vec(any::<u32>(), 0..3),
+        (proxy_id, proxy_version) in (text(), text()),
+        capabilities in capabilities(),
+        error in opt_text(),
+    ) {
+        assert_roundtrip(&HandshakeRequest {
+            supported_versions,
+            proxy_id,
+            proxy_version,
+            config: serde_json::json!({ "mode": "block" }),
+        })?;
+        assert_roundtrip(&HandshakeResponse {
+            protocol_version: capabilities.protocol_version,
+            capabilities,
+            success: error.is_none(),
+            error,
+        })?;
+    }
+
+    #[test]
+    fn uds_handshake_roundtrip(
+        supported_versions in prop::collection::vec(any::<u32>(), 0..3),
+        (proxy_id, proxy_version) in (text(), text()),
+        max_message_size in proptest::option::of(any::<u64>()),
+        capabilities in uds_capabilities(),
+        error in opt_text(),
+        msgpack in any::<bool>(),
+    ) {
+        assert_roundtrip(&UdsHandshakeRequest {
+            supported_versions,
+            proxy_id,
+            proxy_version,
+            config: None,
+            supported_encodings: ENCODINGS.to_vec(),
+            max_message_size,
+            shm: None,
+        })?;
+        assert_roundtrip(&UdsHandshakeResponse {
+            protocol_version: 2,
+            capabilities,
+            success: error.is_none(),
+            error,
+            encoding: if msgpack { UdsEncoding::MessagePack } else { UdsEncoding::Json },
+        })?;
+    }
+}
+
+// =============================================================================
+// Binary Format Round Trips
+// =============================================================================
+
+proptest! {
+    #[test]
+    fn binary_request_headers_roundtrip(
+        (correlation_id, method, uri, client_ip) in (text(), text(), text(), text()),
+        headers in headers(),
+        client_port in any::<u16>(),
+    ) {
+        let original = BinaryRequestHeaders {
+            correlation_id,
+            method,
+            uri,
+            headers,
+            client_ip,
+            client_port,
+        };
+        let decoded = BinaryRequestHeaders::decode(original.encode()).unwrap();
+        prop_assert_eq!(decoded.correlation_id, original.correlation_id);
+        prop_assert_eq!(decoded.method, original.method);
+        prop_assert_eq!(decoded.uri, original.uri);
+        prop_assert_eq!(decoded.client_ip, original.client_ip);
+        prop_assert_eq!(decoded.client_port, original.client_port);
+        prop_assert_eq!(decoded.headers.len(), original.headers.len());
+        for (name, values) in &original.headers {
+            let mut expected = values.clone();
+            let mut actual = decoded.headers[name].clone();
+            expected.sort();
+            actual.sort();
+            prop_assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn binary_body_chunk_roundtrip(
+        correlation_id in text(),
+        chunk_index in any::<u32>(),
+        is_last in any::<bool>(),
+        data in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let original = BinaryBodyChunk {
+            correlation_id,
+            chunk_index,
+            is_last,
+            data: data.into(),
+        };
+        let decoded = BinaryBodyChunk::decode(original.encode()).unwrap();
+        prop_assert_eq!(decoded.correlation_id, original.correlation_id);
+        prop_assert_eq!(decoded.chunk_index, original.chunk_index);
+        prop_assert_eq!(decoded.is_last, original.is_last);
+        prop_assert_eq!(decoded.data, original.data);
+    }
+
+    #[test]
+    fn binary_agent_response_roundtrip(
+        correlation_id in text(),
+        decision in binary_decision(),
+        request_headers in prop::collection::vec(header_op(), 0..3),
+        response_headers in prop::collection::vec(header_op(), 0..3),
+        needs_more in any::<bool>(),
+    ) {
+        let original = BinaryAgentResponse {
+            correlation_id,
+            decision,
+            request_headers,
+            response_headers,
+            needs_more,
+        };
+        let decoded = BinaryAgentResponse::decode(original.encode()).unwrap();
+        prop_assert_eq!(decoded.correlation_id, original.correlation_id);
+        prop_assert_eq!(decoded.decision, original.decision);
+        prop_assert_eq!(decoded.request_headers, original.request_headers);
+        prop_assert_eq!(decoded.response_headers, original.response_headers);
+        prop_assert_eq!(decoded.needs_more, original.needs_more);
+    }
+
+    #[test]
+    fn uds_frame_roundtrip(
+        payloads in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..256), 1..4),
+    ) {
+        block_on(async {
+            let mut stream = Vec::new();
+            for payload in &payloads {
+                write_message(&mut stream, MessageType::AgentResponse, payload)
+                    .await
+                    .unwrap();
+            }
+            let mut reader = stream.as_slice();
+            for payload in &payloads {
+                let (msg_type, decoded) = read_message_with_limit(&mut reader, 256).await.unwrap();
+                prop_assert_eq!(msg_type, MessageType::AgentResponse);
+                prop_assert_eq!(&decoded, payload);
+            }
+            Ok(())
+        })?;
+    }
+}
+
+// =============================================================================
+// Decoders Reject Arbitrary Input
+// =============================================================================
+
+proptest! {
+    #[test]
+    fn uds_reader_never_panics(
+        bytes in prop::collection::vec(any::<u8>(), 0..512),
+        limit in 0usize..1024,
+    ) {
+        block_on(async {
+            let mut reader = bytes.as_slice();
+            // Every message consumes at least its 5 header bytes
+            for _ in 0..=bytes.len() / 5 {
+                if read_message_with_limit(&mut reader, limit).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn binary_decoders_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = BinaryRequestHeaders::decode(bytes.clone().into());
+        let _ = BinaryBodyChunk::decode(bytes.clone().into());
+        let _ = BinaryAgentResponse::decode(bytes.clone().into());
+        let _ = block_on(BinaryFrame::decode(&mut bytes.as_slice()));
+    }
+
+    #[test]
+    fn payload_decoders_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        for encoding in ENCODINGS {
+            let _ = encoding.deserialize::<AgentResponse>(&bytes);
+            let _ = encoding.deserialize::<UdsHandshakeRequest>(&bytes);
+            let _ = encoding.deserialize::<UdsHandshakeResponse>(&bytes);
+            let _ = encoding.deserialize::<RequestHeadersEvent>(&bytes);
+        }
+    }
+}
@@ -43,12 +43,16 @@ fn test_capabilities(agent_id: &str) -> UdsCapabilities {
             cancellation: false,
             flow_control: false,
             health_reporting: false,
+            chunk_reassembly: false,
+            shared_memory: false,
         },
         limits: UdsLimits {
             max_body_size: 1024 * 1024,
             max_concurrency: 10,
             preferred_chunk_size: 64 * 1024,
+            max_message_size: 0,
         },
+        warmup: Vec::new(),
     }
 }
 
@@ -230,6 +234,31 @@ async fn rejects_wrong_protocol_version() {
     assert!(response.error.is_some());
 }
 
+#[tokio::test]
+async fn accepts_v1_agent() {
+    let socket_path = temp_socket_path("v1-agent");
+    let config = ReverseConnectionConfig::default();
+    let pool = AgentPool::with_config(AgentPoolConfig::default());
+
+    let listener = ReverseConnectionListener::bind_uds(&socket_path, config)
+        .await
+        .unwrap();
+
+    let socket_path_clone = socket_path.clone();
+    let agent_handle = tokio::spawn(async move {
+        let mut stream = UnixStream::connect(&socket_path_clone).await.unwrap();
+        let mut request = test_registration_request("v1-agent");
+        request.protocol_version = 1;
+        perform_handshake(&mut stream, &request).await
+    });
+
+    let result = listener.accept_one(&pool).await;
+    assert_eq!(result.unwrap(), "v1-agent");
+
+    let response = agent_handle.await.unwrap();
+    assert!(response.success);
+}
+
 #[tokio::test]
 async fn rejects_empty_agent_id() {
     let socket_path = temp_socket_path("empty-id");
@@ -379,6 +379,7 @@ fn test_full_capability_handshake() {
             cancellation: true,
             flow_control: true,
             health_reporting: true,
+            chunk_reassembly: false,
         },
         limits: AgentLimits {
             max_body_size: 10 * 1024 * 1024, // 10MB
@@ -386,12 +387,14 @@ fn test_full_capability_handshake() {
             preferred_chunk_size: 64 * 1024,     // 64KB
             max_memory: Some(512 * 1024 * 1024), // 512MB
             max_processing_time_ms: Some(5000),
+            max_message_size: 16 * 1024 * 1024, // 16MB
         },
         health: HealthConfig {
             report_interval_ms: 5000,
             include_load_metrics: true,
             include_resource_metrics: true,
         },
+        warmup: Vec::new(),
     };
 
     // Verify protocol version
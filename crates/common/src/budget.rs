@@ -15,7 +15,9 @@
 //! # Cost Attribution
 //!
 //! Cost attribution tracks the monetary cost of inference requests based
-//! on model-specific pricing for input and output tokens.
+//! on model-specific pricing for input and output tokens. An optional
+//! monthly cost budget blocks requests, or downgrades them to a cheaper
+//! model, once a route, tenant or API key has spent its limit.
 
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +30,7 @@ use serde::{Deserialize, Serialize};
 /// Budgets track cumulative token usage over a configurable period,
 /// with optional alerts and enforcement.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TokenBudgetConfig {
     /// Budget period (when the budget resets)
     #[serde(default)]
@@ -92,6 +95,7 @@ impl Default for TokenBudgetConfig {
 
 /// Budget period defining when the budget resets.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum BudgetPeriod {
     /// Resets every hour
@@ -128,6 +132,7 @@ impl BudgetPeriod {
 ///
 /// Allows per-model pricing with separate input/output token rates.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CostAttributionConfig {
     /// Whether cost attribution is enabled
     #[serde(default)]
@@ -148,6 +153,10 @@ pub struct CostAttributionConfig {
     /// Currency for cost values (default: USD)
     #[serde(default = "default_currency")]
     pub currency: String,
+
+    /// Monthly spend limit (optional)
+    #[serde(default)]
+    pub budget: Option<CostBudgetConfig>,
 }
 
 fn default_input_cost() -> f64 {
@@ -170,16 +179,81 @@ impl Default for CostAttributionConfig {
             default_input_cost: default_input_cost(),
             default_output_cost: default_output_cost(),
             currency: default_currency(),
+            budget: None,
+        }
+    }
+}
+
+/// Hard monthly spend limit for an inference route.
+///
+/// Spend is counted per calendar month (UTC) in the attribution currency,
+/// once for the whole route or separately per tenant or API key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CostBudgetConfig {
+    /// Spend allowed per month
+    pub monthly_limit: f64,
+
+    /// Whose spend the limit applies to
+    #[serde(default)]
+    pub scope: CostBudgetScope,
+
+    /// What happens to requests once the limit is spent
+    #[serde(default)]
+    pub action: CostBudgetAction,
+
+    /// Fractions of the limit at which alerts fire (e.g., [0.80, 0.90])
+    #[serde(default = "default_alert_thresholds")]
+    pub alert_thresholds: Vec<f64>,
+}
+
+/// Whose spend a cost budget limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CostBudgetScope {
+    /// All traffic of the route shares one budget
+    #[default]
+    Route,
+    /// Each tenant has its own budget
+    Tenant,
+    /// Each API key has its own budget
+    ApiKey,
+}
+
+impl CostBudgetScope {
+    /// Returns the string label for this scope (for metrics and logging).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Route => "route",
+            Self::Tenant => "tenant",
+            Self::ApiKey => "api_key",
         }
     }
 }
 
+/// What happens to requests once a cost budget is spent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CostBudgetAction {
+    /// Reject requests with 429 until the month ends
+    #[default]
+    Block,
+    /// Rewrite the request's `model` to a cheaper one
+    Downgrade {
+        /// Model requests are sent to instead
+        model: String,
+    },
+}
+
 /// Per-model pricing configuration.
 ///
 /// The `model_pattern` supports glob-style matching:
 /// - `gpt-4*` matches `gpt-4`, `gpt-4-turbo`, `gpt-4o`, etc.
 /// - `claude-3-*` matches all Claude 3 variants
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ModelPricing {
     /// Model name or pattern (glob-style matching with `*`)
     pub model_pattern: String,
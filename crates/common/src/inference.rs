@@ -18,6 +18,7 @@ use serde::{Deserialize, Serialize};
 /// All fields are optional - only enabled checks are performed.
 /// The base inference health check (models endpoint) always runs first.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InferenceReadinessConfig {
     /// Send minimal inference request to verify model can respond
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -41,6 +42,7 @@ pub struct InferenceReadinessConfig {
 /// Sends a minimal completion request to verify the model can actually
 /// process requests, not just that the server is running.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InferenceProbeConfig {
     /// Endpoint for completion request
     #[serde(default = "default_probe_endpoint")]
@@ -71,6 +73,7 @@ pub struct InferenceProbeConfig {
 /// Queries provider-specific status endpoints to verify model readiness.
 /// Useful for providers that expose detailed model state information.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ModelStatusConfig {
     /// Endpoint pattern with `{model}` placeholder
     #[serde(default = "default_status_endpoint")]
@@ -97,6 +100,7 @@ pub struct ModelStatusConfig {
 /// Monitors queue depth to detect overloaded backends before they
 /// start timing out or returning errors.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct QueueDepthConfig {
     /// Header containing queue depth (e.g., "x-queue-depth")
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -127,6 +131,7 @@ pub struct QueueDepthConfig {
 /// idle periods. This is a passive check that observes actual requests
 /// rather than sending probes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WarmthDetectionConfig {
     /// Number of requests to sample for baseline latency
     #[serde(default = "default_warmth_sample_size")]
@@ -158,6 +163,7 @@ impl Eq for WarmthDetectionConfig {}
 
 /// Action to take when a cold model is detected
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ColdModelAction {
     /// Log the cold start but continue serving (observability only)
@@ -33,8 +33,8 @@ pub mod types;
 // Re-export commonly used items at the crate root (runtime only)
 #[cfg(feature = "runtime")]
 pub use observability::{
-    init_tracing, AuditLogEntry, ComponentHealth, ComponentHealthTracker, HealthStatus,
-    RequestMetrics,
+    init_tracing, metric_labels, AuditLogEntry, ComponentHealth, ComponentHealthTracker,
+    HealthStatus, MetricKind, MetricsRegistry, MetricsSource, MetricsWriter, RequestMetrics,
 };
 
 // Backwards compatibility alias (deprecated, use ComponentHealthTracker)
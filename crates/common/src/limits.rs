@@ -14,6 +14,7 @@ use crate::errors::{LimitType, ZentinelError, ZentinelResult};
 
 /// System-wide limits configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Limits {
     // Header limits
     pub max_header_size_bytes: usize,
@@ -5,12 +5,13 @@
 
 use anyhow::{Context, Result};
 use prometheus::{
-    register_counter_vec, register_gauge, register_histogram_vec, register_int_counter_vec,
-    register_int_gauge, register_int_gauge_vec, CounterVec, Gauge, HistogramVec, IntCounterVec,
-    IntGauge, IntGaugeVec,
+    register_counter_vec, register_gauge, register_gauge_vec, register_histogram_vec,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, CounterVec, Gauge,
+    GaugeVec, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
 };
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 /// Initialize the tracing/logging subsystem
@@ -76,6 +77,8 @@ pub struct RequestMetrics {
     upstream_failures: IntCounterVec,
     /// Circuit breaker state (0 = closed, 1 = open)
     circuit_breaker_state: IntGaugeVec,
+    /// Active health check result per target (1 = healthy, 0 = unhealthy)
+    upstream_target_healthy: IntGaugeVec,
     /// Agent call latency
     agent_latency: HistogramVec,
     /// Agent call timeouts
@@ -92,6 +95,9 @@ pub struct RequestMetrics {
     connection_pool_size: IntGaugeVec,
     connection_pool_idle: IntGaugeVec,
     connection_pool_acquired: IntCounterVec,
+    connection_pool_in_use: IntGaugeVec,
+    connection_pool_capacity: IntGaugeVec,
+    connection_pool_utilization: GaugeVec,
     /// System metrics
     memory_usage: IntGauge,
     cpu_usage: Gauge,
@@ -112,6 +118,16 @@ pub struct RequestMetrics {
     pii_detected_total: IntCounterVec,
 }
 
+/// Sum of a counter vector over all label values
+fn sum_counters(counters: &impl prometheus::core::Collector) -> f64 {
+    counters
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .map(|metric| metric.get_counter().value())
+        .sum()
+}
+
 /// Return a static string for common HTTP status codes to avoid
 /// per-request `u16::to_string()` allocation in metrics labels.
 fn status_str(status: u16) -> &'static str {
@@ -205,6 +221,13 @@ impl RequestMetrics {
         )
         .context("Failed to register circuit_breaker_state metric")?;
 
+        let upstream_target_healthy = register_int_gauge_vec!(
+            "zentinel_upstream_target_healthy",
+            "Active health check result per target (1=healthy, 0=unhealthy)",
+            &["upstream", "target"]
+        )
+        .context("Failed to register upstream_target_healthy metric")?;
+
         let agent_latency = register_histogram_vec!(
             "zentinel_agent_latency_seconds",
             "Agent call latency in seconds",
@@ -272,6 +295,27 @@ impl RequestMetrics {
         )
         .context("Failed to register connection_pool_acquired metric")?;
 
+        let connection_pool_in_use = register_int_gauge_vec!(
+            "zentinel_connection_pool_in_use",
+            "Connections currently in use by in-flight requests",
+            &["upstream"]
+        )
+        .context("Failed to register connection_pool_in_use metric")?;
+
+        let connection_pool_capacity = register_int_gauge_vec!(
+            "zentinel_connection_pool_capacity",
+            "Configured connection budget across all targets",
+            &["upstream"]
+        )
+        .context("Failed to register connection_pool_capacity metric")?;
+
+        let connection_pool_utilization = register_gauge_vec!(
+            "zentinel_connection_pool_utilization_ratio",
+            "Connections in use as a fraction of the pool capacity",
+            &["upstream"]
+        )
+        .context("Failed to register connection_pool_utilization metric")?;
+
         let memory_usage = register_int_gauge!(
             "zentinel_memory_usage_bytes",
             "Current memory usage in bytes"
@@ -388,6 +432,7 @@ impl RequestMetrics {
             upstream_attempts,
             upstream_failures,
             circuit_breaker_state,
+            upstream_target_healthy,
             agent_latency,
             agent_timeouts,
             blocked_requests,
@@ -397,6 +442,9 @@ impl RequestMetrics {
             connection_pool_size,
             connection_pool_idle,
             connection_pool_acquired,
+            connection_pool_in_use,
+            connection_pool_capacity,
+            connection_pool_utilization,
             memory_usage,
             cpu_usage,
             open_connections,
@@ -456,6 +504,14 @@ impl RequestMetrics {
             .set(state);
     }
 
+    /// Record an active health check result for an upstream target
+    pub fn set_upstream_target_health(&self, upstream: &str, target: &str, healthy: bool) {
+        let state = if healthy { 1 } else { 0 };
+        self.upstream_target_healthy
+            .with_label_values(&[upstream, target])
+            .set(state);
+    }
+
     /// Record agent call latency
     pub fn record_agent_latency(&self, agent: &str, event: &str, duration: Duration) {
         self.agent_latency
@@ -473,6 +529,37 @@ impl RequestMetrics {
         self.blocked_requests.with_label_values(&[reason]).inc();
     }
 
+    /// Requests completed so far, over all routes and statuses
+    pub fn requests_total(&self) -> u64 {
+        sum_counters(&self.request_count) as u64
+    }
+
+    /// Requests blocked so far, over all reasons
+    pub fn blocked_requests_total(&self) -> u64 {
+        sum_counters(&self.blocked_requests) as u64
+    }
+
+    /// Requests completed with a 5xx status so far, over all routes
+    pub fn server_errors_total(&self) -> u64 {
+        self.request_count
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .filter(|metric| {
+                metric
+                    .get_label()
+                    .iter()
+                    .any(|label| label.name() == "status" && label.value().starts_with('5'))
+            })
+            .map(|metric| metric.get_counter().value())
+            .sum::<f64>() as u64
+    }
+
+    /// Requests currently in flight
+    pub fn active_requests(&self) -> i64 {
+        self.active_requests.get()
+    }
+
     /// Record PII detection in inference response
     pub fn record_pii_detected(&self, route: &str, category: &str) {
         self.pii_detected_total
@@ -518,6 +605,24 @@ impl RequestMetrics {
             .inc();
     }
 
+    /// Update connection pool utilization (in-use connections vs. capacity)
+    pub fn update_connection_pool_utilization(&self, upstream: &str, in_use: u64, capacity: usize) {
+        self.connection_pool_in_use
+            .with_label_values(&[upstream])
+            .set(in_use as i64);
+        self.connection_pool_capacity
+            .with_label_values(&[upstream])
+            .set(capacity as i64);
+        let ratio = if capacity > 0 {
+            in_use as f64 / capacity as f64
+        } else {
+            0.0
+        };
+        self.connection_pool_utilization
+            .with_label_values(&[upstream])
+            .set(ratio);
+    }
+
     /// Update system metrics
     pub fn update_system_metrics(&self) {
         use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
@@ -842,6 +947,354 @@ impl ComponentHealthTracker {
     pub fn get_report(&self) -> Vec<ComponentHealth> {
         self.components.read().clone()
     }
+
+    /// Drop components for which `keep` returns false (e.g. after a reload
+    /// removed them)
+    pub fn retain(&self, keep: impl Fn(&ComponentHealth) -> bool) {
+        self.components.write().retain(|c| keep(c));
+    }
+}
+
+// =============================================================================
+// Unified metrics registry
+// =============================================================================
+
+/// Label names shared by every subsystem, so series from the proxy, agent
+/// pools, caches and rate limiters can be joined on them.
+pub mod metric_labels {
+    /// Agent ID
+    pub const AGENT_ID: &str = "agent_id";
+    /// Route ID
+    pub const ROUTE_ID: &str = "route_id";
+    /// Tenant ID
+    pub const TENANT: &str = "tenant";
+}
+
+/// Type of a metric family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MetricFamily {
+    help: String,
+    kind: MetricKind,
+    lines: Vec<String>,
+}
+
+/// Samples gathered from [`MetricsSource`]s during one scrape.
+///
+/// Samples are grouped by family, so a family fed by several sources (one
+/// per agent pool, say) gets a single `HELP` and `TYPE` line.
+#[derive(Debug, Default)]
+pub struct MetricsWriter {
+    families: std::collections::BTreeMap<String, MetricFamily>,
+}
+
+impl MetricsWriter {
+    /// Create an empty writer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a counter sample. An empty `help` omits the `HELP` line.
+    pub fn counter(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        if let Some(lines) = self.family(name, help, MetricKind::Counter) {
+            lines.push(sample_line(name, labels, None, value));
+        }
+    }
+
+    /// Add a gauge sample
+    pub fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        if let Some(lines) = self.family(name, help, MetricKind::Gauge) {
+            lines.push(sample_line(name, labels, None, value));
+        }
+    }
+
+    /// Add a histogram.
+    ///
+    /// `buckets` holds `(upper bound, cumulative count)` pairs without the
+    /// `+Inf` bucket, which is taken from `count`.
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        buckets: &[(f64, u64)],
+        sum: f64,
+        count: u64,
+    ) {
+        let Some(lines) = self.family(name, help, MetricKind::Histogram) else {
+            return;
+        };
+        let bucket_name = format!("{}_bucket", name);
+        for &(le, cumulative) in buckets {
+            let le = format_metric_value(le);
+            lines.push(sample_line(
+                &bucket_name,
+                labels,
+                Some(("le", &le)),
+                cumulative as f64,
+            ));
+        }
+        lines.push(sample_line(
+            &bucket_name,
+            labels,
+            Some(("le", "+Inf")),
+            count as f64,
+        ));
+        lines.push(sample_line(&format!("{}_sum", name), labels, None, sum));
+        lines.push(sample_line(
+            &format!("{}_count", name),
+            labels,
+            None,
+            count as f64,
+        ));
+    }
+
+    /// Add a summary.
+    ///
+    /// `quantiles` holds `(quantile, value)` pairs, e.g. `(0.99, 0.012)`.
+    pub fn summary(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        quantiles: &[(f64, f64)],
+        sum: f64,
+        count: u64,
+    ) {
+        let Some(lines) = self.family(name, help, MetricKind::Summary) else {
+            return;
+        };
+        for &(quantile, value) in quantiles {
+            let quantile = format_metric_value(quantile);
+            lines.push(sample_line(
+                name,
+                labels,
+                Some(("quantile", &quantile)),
+                value,
+            ));
+        }
+        lines.push(sample_line(&format!("{}_sum", name), labels, None, sum));
+        lines.push(sample_line(
+            &format!("{}_count", name),
+            labels,
+            None,
+            count as f64,
+        ));
+    }
+
+    /// Whether a family has been written
+    pub fn contains(&self, name: &str) -> bool {
+        self.families.contains_key(name)
+    }
+
+    /// Render the families in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        self.render_into(&mut output, |_| false);
+        output
+    }
+
+    fn render_into(&self, output: &mut String, skip: impl Fn(&str) -> bool) {
+        for (name, family) in &self.families {
+            if skip(name) {
+                warn!(
+                    metric = %name,
+                    "Metric family is already registered with the prometheus registry, skipping"
+                );
+                continue;
+            }
+            if !family.help.is_empty() {
+                output.push_str(&format!("# HELP {} {}\n", name, family.help));
+            }
+            output.push_str(&format!("# TYPE {} {}\n", name, family.kind.as_str()));
+            for line in &family.lines {
+                output.push_str(line);
+            }
+        }
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: MetricKind) -> Option<&mut Vec<String>> {
+        let family = self
+            .families
+            .entry(name.to_string())
+            .or_insert_with(|| MetricFamily {
+                help: help.replace('\\', "\\\\").replace('\n', "\\n"),
+                kind,
+                lines: Vec::new(),
+            });
+        if family.kind != kind {
+            warn!(
+                metric = %name,
+                registered = family.kind.as_str(),
+                written = kind.as_str(),
+                "Metric written with conflicting types, dropping sample"
+            );
+            return None;
+        }
+        Some(&mut family.lines)
+    }
+}
+
+/// Format one sample; `extra` is the `le` or `quantile` label of histogram
+/// and summary samples
+fn sample_line(
+    name: &str,
+    labels: &[(&str, &str)],
+    extra: Option<(&str, &str)>,
+    value: f64,
+) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+        .collect();
+    if let Some((key, value)) = extra {
+        pairs.push(format!("{}=\"{}\"", key, value));
+    }
+    if pairs.is_empty() {
+        format!("{} {}\n", name, format_metric_value(value))
+    } else {
+        format!(
+            "{}{{{}}} {}\n",
+            name,
+            pairs.join(","),
+            format_metric_value(value)
+        )
+    }
+}
+
+/// Escape a label value for the Prometheus text format.
+pub fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_metric_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// A subsystem whose metrics are read on every scrape.
+///
+/// Sources suit metrics kept outside the `prometheus` registry, such as
+/// atomics in agent pools or cache statistics.
+pub trait MetricsSource: Send + Sync {
+    /// Write the current samples
+    fn collect(&self, writer: &mut MetricsWriter);
+}
+
+/// The registry behind the metrics endpoint.
+///
+/// A scrape renders everything registered with the default `prometheus`
+/// registry (the `register_*!` macros), followed by the families written by
+/// the registered [`MetricsSource`]s. A source family whose name is already
+/// taken by a registered collector is skipped.
+#[derive(Default)]
+pub struct MetricsRegistry {
+    sources: parking_lot::RwLock<std::collections::BTreeMap<String, Arc<dyn MetricsSource>>>,
+}
+
+static GLOBAL_METRICS_REGISTRY: LazyLock<MetricsRegistry> = LazyLock::new(MetricsRegistry::new);
+
+impl MetricsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry served by the metrics endpoint
+    pub fn global() -> &'static MetricsRegistry {
+        &GLOBAL_METRICS_REGISTRY
+    }
+
+    /// Register a source under `id`, replacing any source with the same ID
+    pub fn register(&self, id: impl Into<String>, source: Arc<dyn MetricsSource>) {
+        self.sources.write().insert(id.into(), source);
+    }
+
+    /// Remove a source. Returns whether it was registered.
+    pub fn unregister(&self, id: &str) -> bool {
+        self.sources.write().remove(id).is_some()
+    }
+
+    /// Remove the source registered under `id` only if it is `source`.
+    ///
+    /// Lets an owner that has been replaced under the same ID, as on a config
+    /// reload, shut down without removing its successor.
+    pub fn unregister_source(&self, id: &str, source: &Arc<dyn MetricsSource>) -> bool {
+        let mut sources = self.sources.write();
+        if sources
+            .get(id)
+            .is_some_and(|registered| Arc::ptr_eq(registered, source))
+        {
+            sources.remove(id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// IDs of the registered sources
+    pub fn source_ids(&self) -> Vec<String> {
+        self.sources.read().keys().cloned().collect()
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> Result<Vec<u8>, prometheus::Error> {
+        self.render_with(&[])
+    }
+
+    /// Render all metrics plus those of `extra` sources that are only
+    /// known to the caller
+    pub fn render_with(&self, extra: &[&dyn MetricsSource]) -> Result<Vec<u8>, prometheus::Error> {
+        use prometheus::{Encoder, TextEncoder};
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+
+        let mut writer = MetricsWriter::new();
+        for source in self.sources.read().values() {
+            source.collect(&mut writer);
+        }
+        for source in extra {
+            source.collect(&mut writer);
+        }
+
+        let registered: std::collections::HashSet<String> = String::from_utf8_lossy(&buffer)
+            .lines()
+            .filter_map(|line| line.strip_prefix("# TYPE "))
+            .filter_map(|rest| rest.split_whitespace().next())
+            .map(str::to_string)
+            .collect();
+        let mut output = String::new();
+        writer.render_into(&mut output, |name| registered.contains(name));
+        buffer.extend_from_slice(output.as_bytes());
+        Ok(buffer)
+    }
 }
 
 #[cfg(test)]
@@ -907,5 +1360,87 @@ mod tests {
             Some("Connection refused".to_string()),
         );
         assert_eq!(checker.get_status(), HealthStatus::Unhealthy);
+
+        // Removed components no longer count
+        checker.retain(|c| c.name != "upstream2");
+        assert_eq!(checker.get_report().len(), 2);
+        assert_eq!(checker.get_status(), HealthStatus::Degraded);
+    }
+
+    struct PoolSource(&'static str, u64);
+
+    impl MetricsSource for PoolSource {
+        fn collect(&self, writer: &mut MetricsWriter) {
+            let labels = [(metric_labels::AGENT_ID, self.0)];
+            writer.counter(
+                "test_registry_pool_requests_total",
+                "Requests sent",
+                &labels,
+                self.1 as f64,
+            );
+            writer.histogram(
+                "test_registry_pool_duration_seconds",
+                "Duration",
+                &labels,
+                &[(0.1, 1), (1.0, 2)],
+                1.5,
+                3,
+            );
+        }
+    }
+
+    #[test]
+    fn test_registry_merges_source_families() {
+        let registry = MetricsRegistry::new();
+        registry.register("pool:waf", Arc::new(PoolSource("waf", 3)));
+        registry.register("pool:auth", Arc::new(PoolSource("auth", 5)));
+
+        let output = String::from_utf8(registry.render().unwrap()).unwrap();
+        assert_eq!(
+            output
+                .matches("# TYPE test_registry_pool_requests_total counter")
+                .count(),
+            1
+        );
+        assert!(output.contains("test_registry_pool_requests_total{agent_id=\"waf\"} 3\n"));
+        assert!(output.contains("test_registry_pool_requests_total{agent_id=\"auth\"} 5\n"));
+        assert!(output.contains(
+            "test_registry_pool_duration_seconds_bucket{agent_id=\"waf\",le=\"+Inf\"} 3\n"
+        ));
+
+        assert!(registry.unregister("pool:auth"));
+        let stale: Arc<dyn MetricsSource> = Arc::new(PoolSource("waf", 0));
+        assert!(!registry.unregister_source("pool:waf", &stale));
+        let output = String::from_utf8(registry.render().unwrap()).unwrap();
+        assert!(!output.contains("agent_id=\"auth\""));
+        assert!(output.contains("agent_id=\"waf\""));
+    }
+
+    #[test]
+    fn test_writer_rejects_conflicting_types() {
+        let mut writer = MetricsWriter::new();
+        writer.gauge("test_conflict", "Help", &[], 1.0);
+        writer.counter("test_conflict", "Help", &[("route_id", "a\"b")], 2.0);
+        let output = writer.render();
+        assert!(output.contains("# TYPE test_conflict gauge"));
+        assert!(!output.contains("route_id"));
+    }
+
+    #[test]
+    fn test_writer_renders_summaries() {
+        let mut writer = MetricsWriter::new();
+        writer.summary(
+            "test_summary_seconds",
+            "Latency",
+            &[(metric_labels::AGENT_ID, "waf")],
+            &[(0.5, 0.002), (0.99, 0.015)],
+            1.25,
+            200,
+        );
+        let output = writer.render();
+        assert!(output.contains("# TYPE test_summary_seconds summary\n"));
+        assert!(output.contains("test_summary_seconds{agent_id=\"waf\",quantile=\"0.99\"} 0.015\n"));
+        assert!(output.contains("test_summary_seconds_sum{agent_id=\"waf\"} 1.25\n"));
+        assert!(output.contains("test_summary_seconds_count{agent_id=\"waf\"} 200\n"));
     }
 }
@@ -11,6 +11,7 @@ use std::str::FromStr;
 
 /// HTTP method wrapper with validation
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum HttpMethod {
     GET,
     POST,
@@ -63,6 +64,7 @@ impl fmt::Display for HttpMethod {
 
 /// TLS version
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum TlsVersion {
     #[serde(rename = "TLS1.2")]
     Tls12,
@@ -93,14 +95,25 @@ impl fmt::Display for TlsVersion {
 ///   Guaranteed unique, widely compatible.
 ///   Example: `550e8400-e29b-41d4-a716-446655440000`
 ///
+/// - **UUIDv7**: 36-character UUID with a millisecond timestamp prefix, so
+///   IDs sort by creation time.
+///   Example: `01890a5d-ac96-774b-bcce-b302099a8057`
+///
+/// - **ULID**: 26-character Crockford Base32, millisecond time prefix.
+///   Example: `01ARZ3NDEKTSV4RRFFQ69G5FAV`
+///
+/// - **Hex**: 32 random lowercase hex characters (128 bits).
+///   Example: `4bf92f3577b34da6a3ce929d0e0e4736`
+///
 /// # Configuration
 ///
 /// ```kdl
 /// server {
-///     trace-id-format "tinyflake"  // or "uuid"
+///     trace-id-format "tinyflake"  // or "uuid", "uuidv7", "ulid", "hex"
 /// }
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum TraceIdFormat {
     /// TinyFlake format: 11-char Base58, time-prefixed (default)
@@ -109,6 +122,15 @@ pub enum TraceIdFormat {
 
     /// UUID v4 format: 36-char with dashes
     Uuid,
+
+    /// UUID v7 format: 36-char with dashes, time-ordered
+    UuidV7,
+
+    /// ULID format: 26-char Crockford Base32, time-ordered
+    Ulid,
+
+    /// Random hex: 32 lowercase hex chars
+    Hex,
 }
 
 impl TraceIdFormat {
@@ -116,6 +138,9 @@ impl TraceIdFormat {
     pub fn from_str_loose(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "uuid" | "uuid4" | "uuidv4" => TraceIdFormat::Uuid,
+            "uuid7" | "uuidv7" => TraceIdFormat::UuidV7,
+            "ulid" => TraceIdFormat::Ulid,
+            "hex" => TraceIdFormat::Hex,
             _ => TraceIdFormat::TinyFlake, // Default to TinyFlake
         }
     }
@@ -126,12 +151,16 @@ impl fmt::Display for TraceIdFormat {
         match self {
             TraceIdFormat::TinyFlake => write!(f, "tinyflake"),
             TraceIdFormat::Uuid => write!(f, "uuid"),
+            TraceIdFormat::UuidV7 => write!(f, "uuidv7"),
+            TraceIdFormat::Ulid => write!(f, "ulid"),
+            TraceIdFormat::Hex => write!(f, "hex"),
         }
     }
 }
 
 /// Load balancing algorithm
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum LoadBalancingAlgorithm {
     RoundRobin,
@@ -189,6 +218,7 @@ pub enum LoadBalancingAlgorithm {
 
 /// Health check type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum HealthCheckType {
     Http {
@@ -196,6 +226,12 @@ pub enum HealthCheckType {
         expected_status: u16,
         #[serde(skip_serializing_if = "Option::is_none")]
         host: Option<String>,
+        /// Probe method (GET or HEAD)
+        #[serde(default)]
+        method: HealthCheckMethod,
+        /// Substring the response body must contain (GET only)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_body: Option<String>,
     },
     Tcp,
     Grpc {
@@ -218,8 +254,29 @@ pub enum HealthCheckType {
     },
 }
 
+/// HTTP method used by active HTTP health probes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthCheckMethod {
+    #[default]
+    Get,
+    Head,
+}
+
+impl HealthCheckMethod {
+    /// Method name as sent on the request line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthCheckMethod::Get => "GET",
+            HealthCheckMethod::Head => "HEAD",
+        }
+    }
+}
+
 /// Retry policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RetryPolicy {
     pub max_attempts: u32,
 }
@@ -232,6 +289,7 @@ impl Default for RetryPolicy {
 
 /// Circuit breaker configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CircuitBreakerConfig {
     pub failure_threshold: u32,
     pub success_threshold: u32,
@@ -252,6 +310,7 @@ impl Default for CircuitBreakerConfig {
 
 /// Circuit breaker state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum CircuitBreakerState {
     Closed,
@@ -282,6 +341,7 @@ pub enum CircuitBreakerState {
 /// assert_eq!(Priority::default(), Priority::NORMAL);
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct Priority(pub i32);
 
@@ -324,6 +384,7 @@ impl From<i32> for Priority {
 
 /// Time window for rate limiting and metrics
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TimeWindow {
     pub seconds: u64,
 }
@@ -397,6 +458,21 @@ impl<'de> Deserialize<'de> for ByteSize {
     }
 }
 
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for ByteSize {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ByteSize".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Size in bytes, optionally with a unit (B, KB, MB, GB), e.g. \"10MB\"",
+            "pattern": "^\\s*[0-9.]+\\s*([bB]|[kKmMgG][bB]?)?\\s*$",
+        })
+    }
+}
+
 impl FromStr for ByteSize {
     type Err = String;
 
@@ -437,6 +513,7 @@ impl FromStr for ByteSize {
 
 /// IP address wrapper with additional metadata
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ClientIp {
     pub address: std::net::IpAddr,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -486,6 +563,11 @@ mod tests {
     #[test]
     fn test_trace_id_format() {
         assert_eq!(TraceIdFormat::from_str_loose("uuid"), TraceIdFormat::Uuid);
+        assert_eq!(
+            TraceIdFormat::from_str_loose("UUIDv7"),
+            TraceIdFormat::UuidV7
+        );
+        assert_eq!(TraceIdFormat::from_str_loose("ulid"), TraceIdFormat::Ulid);
         assert_eq!(
             TraceIdFormat::from_str_loose("tinyflake"),
             TraceIdFormat::TinyFlake
@@ -302,6 +302,7 @@ fn summarize_matches(matches: &[zentinel_config::MatchCondition]) -> String {
                 Some(v) => format!("?{name}={v}"),
                 None => format!("?{name}"),
             },
+            MatchCondition::Expr(expr) => format!("expr:{expr}"),
         })
         .collect();
 
@@ -313,6 +314,7 @@ fn summarize_transport(transport: &AgentTransport) -> String {
         AgentTransport::UnixSocket { path } => format!("uds://{}", path.display()),
         AgentTransport::Grpc { address, .. } => format!("grpc://{address}"),
         AgentTransport::Http { url, .. } => url.clone(),
+        AgentTransport::InProcess => "in-process".to_string(),
     }
 }
 
@@ -101,11 +101,14 @@ fn has_extra_restrictions(higher: &RouteConfig, lower: &RouteConfig) -> bool {
     let lower_has_method = lower.matches.iter().any(|m| matches!(m, MatchCondition::Method(_)));
     let higher_has_header = higher.matches.iter().any(|m| matches!(m, MatchCondition::Header { .. }));
     let lower_has_header = lower.matches.iter().any(|m| matches!(m, MatchCondition::Header { .. }));
+    // Expressions can't be compared, so any expression counts as a restriction
+    let higher_has_expr = higher.matches.iter().any(|m| matches!(m, MatchCondition::Expr(_)));
 
     // If higher route has a condition type that lower doesn't, it's more restrictive
     (higher_has_host && !lower_has_host)
         || (higher_has_method && !lower_has_method)
         || (higher_has_header && !lower_has_header)
+        || higher_has_expr
 }
 
 /// Extract path-type conditions from match conditions.
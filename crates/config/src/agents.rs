@@ -4,11 +4,13 @@
 //! (WAF, auth, rate limiting, custom logic).
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use validator::Validate;
 
 use zentinel_common::types::CircuitBreakerConfig;
 
+use crate::filters::RedisBackendConfig;
 use crate::routes::FailureMode;
 
 // ============================================================================
@@ -22,6 +24,7 @@ use crate::routes::FailureMode;
 /// - `Stream`: Send chunks as they arrive (lower latency, lower memory)
 /// - `Hybrid`: Buffer small bodies, stream large ones
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
 pub enum BodyStreamingMode {
@@ -61,7 +64,20 @@ fn default_hybrid_threshold() -> usize {
 // ============================================================================
 
 /// Pool configuration for agent connections
+///
+/// KDL format (inside an `agent` block):
+/// ```kdl
+/// pool {
+///     connections-per-agent 8
+///     load-balance-strategy "least_connections"
+///     request-duration-buckets-us 250 1000 2500 10000 50000
+///     shared-memory {
+///         slot-count 32
+///     }
+/// }
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AgentPoolConfig {
     /// Number of connections to maintain per agent (default: 4)
     #[serde(default = "default_connections_per_agent")]
@@ -94,6 +110,21 @@ pub struct AgentPoolConfig {
     /// Health check interval in milliseconds (default: 10000)
     #[serde(default = "default_health_check_interval_ms")]
     pub health_check_interval_ms: u64,
+
+    /// Bucket boundaries of the request duration histogram in microseconds
+    /// (default: 10µs to 1s)
+    #[serde(default)]
+    pub request_duration_buckets_us: Option<Vec<u64>>,
+
+    /// Bucket boundaries of the serialization time histogram in microseconds
+    /// (default: 10µs to 1s)
+    #[serde(default)]
+    pub serialization_buckets_us: Option<Vec<u64>>,
+
+    /// Pass large body chunks to a Unix socket agent through shared memory
+    /// (requires the proxy's `agent-shared-memory` feature)
+    #[serde(default)]
+    pub shared_memory: Option<AgentSharedMemoryConfig>,
 }
 
 impl Default for AgentPoolConfig {
@@ -107,10 +138,81 @@ impl Default for AgentPoolConfig {
             drain_timeout_ms: default_drain_timeout_ms(),
             max_concurrent_per_connection: default_max_concurrent_per_connection(),
             health_check_interval_ms: default_health_check_interval_ms(),
+            request_duration_buckets_us: None,
+            serialization_buckets_us: None,
+            shared_memory: None,
         }
     }
 }
 
+/// Shared-memory body transport of a Unix socket agent
+///
+/// Each connection gets a memory-mapped segment; body chunks of at least
+/// `min-chunk-size` bytes are written into a slot and the socket carries
+/// only a descriptor. Agents that do not map the segment get every chunk
+/// inline.
+///
+/// KDL format (inside a `pool` block):
+/// ```kdl
+/// shared-memory {
+///     dir "/dev/shm"
+///     slot-count 64
+///     slot-size 1048576
+///     min-chunk-size 65536
+///     mode 0o600
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AgentSharedMemoryConfig {
+    /// Directory for segment files (default: `/dev/shm`, or the temp
+    /// directory where it does not exist)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Slots per segment, the chunks in flight per connection (default: 64)
+    #[serde(default = "default_shm_slot_count")]
+    pub slot_count: u32,
+
+    /// Size of a slot in bytes; larger chunks are sent inline (default: 1MB)
+    #[serde(default = "default_shm_slot_size")]
+    pub slot_size: usize,
+
+    /// Chunks smaller than this are sent inline (default: 64KB)
+    #[serde(default = "default_shm_min_chunk_size")]
+    pub min_chunk_size: usize,
+
+    /// Permission bits of segment files; widen when the agent runs as
+    /// another user (default: 0o600)
+    #[serde(default = "default_shm_mode")]
+    pub mode: u32,
+}
+
+impl Default for AgentSharedMemoryConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            slot_count: default_shm_slot_count(),
+            slot_size: default_shm_slot_size(),
+            min_chunk_size: default_shm_min_chunk_size(),
+            mode: default_shm_mode(),
+        }
+    }
+}
+
+fn default_shm_slot_count() -> u32 {
+    64
+}
+fn default_shm_slot_size() -> usize {
+    1024 * 1024
+}
+fn default_shm_min_chunk_size() -> usize {
+    64 * 1024
+}
+fn default_shm_mode() -> u32 {
+    0o600
+}
+
 fn default_connections_per_agent() -> usize {
     4
 }
@@ -135,6 +237,7 @@ fn default_health_check_interval_ms() -> u64 {
 
 /// Load balancing strategy for v2 agent pool
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum LoadBalanceStrategy {
     /// Round-robin across all healthy connections
@@ -150,6 +253,7 @@ pub enum LoadBalanceStrategy {
 
 /// Agent configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AgentConfig {
     /// Unique agent identifier
     pub id: String,
@@ -172,6 +276,10 @@ pub struct AgentConfig {
     #[serde(default = "default_agent_timeout")]
     pub timeout_ms: u64,
 
+    /// Per-event overrides of `timeout_ms`
+    #[serde(default, skip_serializing_if = "AgentEventTimeouts::is_empty")]
+    pub event_timeouts: AgentEventTimeouts,
+
     /// Failure mode when agent is unavailable
     #[serde(default)]
     pub failure_mode: FailureMode,
@@ -225,22 +333,371 @@ pub struct AgentConfig {
     /// Default: 100 concurrent calls per agent
     #[serde(default = "default_max_concurrent_calls")]
     pub max_concurrent_calls: usize,
+
+    /// Maximum encoded message size for this agent (bytes)
+    ///
+    /// Offered to the agent at handshake; the smaller of the two limits wins.
+    /// Body chunks larger than the negotiated limit are split into multiple
+    /// messages when the agent supports reassembly.
+    /// Default: 16MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_size: Option<usize>,
+
+    /// Supervised agent process
+    ///
+    /// When set, the agent binary is spawned and restarted by Zentinel
+    /// instead of being managed externally (systemd, containers).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<AgentProcessConfig>,
+
+    /// Traffic ramp after the agent (re)connects
+    ///
+    /// When unset, a freshly started agent gets its full share of traffic
+    /// at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_start: Option<AgentSlowStartConfig>,
+
+    /// Limits on the agent's data in the session store
+    #[serde(default)]
+    pub state_quota: AgentStateQuota,
+}
+
+impl AgentConfig {
+    /// Timeout for calls carrying `event`, in milliseconds
+    pub fn timeout_ms_for(&self, event: AgentEvent) -> u64 {
+        let timeouts = &self.event_timeouts;
+        let timeout = match event {
+            AgentEvent::RequestHeaders | AgentEvent::ResponseHeaders => timeouts.headers_ms,
+            AgentEvent::RequestBody | AgentEvent::ResponseBody => timeouts.body_ms,
+            AgentEvent::WebSocketFrame => timeouts.websocket_ms,
+            AgentEvent::Guardrail => timeouts.guardrail_ms,
+            AgentEvent::Log => None,
+        };
+        timeout.unwrap_or(self.timeout_ms)
+    }
+
+    /// Longest timeout of any call to this agent, in milliseconds
+    pub fn max_timeout_ms(&self) -> u64 {
+        self.event_timeouts
+            .iter()
+            .map(|(_, ms)| ms)
+            .fold(self.timeout_ms, u64::max)
+    }
 }
 
 fn default_chunk_timeout() -> u64 {
     5000 // 5 seconds
 }
 
+/// Per-event timeout overrides for an agent
+///
+/// An agent can take far longer for a body chunk than for headers; unset
+/// events use the agent's `timeout-ms`.
+///
+/// KDL format (inside an `agent` block):
+/// ```kdl
+/// agent "waf" type="waf" {
+///     timeout-ms 50
+///     event-timeouts {
+///         headers 5
+///         body 100
+///         websocket 20
+///         guardrail 500
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AgentEventTimeouts {
+    /// Request and response headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers_ms: Option<u64>,
+
+    /// Request and response body chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_ms: Option<u64>,
+
+    /// WebSocket frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket_ms: Option<u64>,
+
+    /// Guardrail inspections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrail_ms: Option<u64>,
+}
+
+impl AgentEventTimeouts {
+    /// Whether no event overrides the agent's timeout
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// The overrides that are set, with their KDL names
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> {
+        [
+            ("headers", self.headers_ms),
+            ("body", self.body_ms),
+            ("websocket", self.websocket_ms),
+            ("guardrail", self.guardrail_ms),
+        ]
+        .into_iter()
+        .filter_map(|(name, ms)| ms.map(|ms| (name, ms)))
+    }
+}
+
 fn default_max_concurrent_calls() -> usize {
     100 // Per-agent concurrency limit
 }
 
+// ============================================================================
+// Agent Slow Start
+// ============================================================================
+
+/// Slow start for an agent that just (re)connected
+///
+/// The agent's concurrency limit ramps linearly from 1 to
+/// `max-concurrent-calls` over the window, so a restarted agent can load
+/// its rules before taking full traffic.
+///
+/// KDL format (inside an `agent` block):
+/// ```kdl
+/// agent "waf" type="waf" {
+///     slow-start {
+///         window-ms 30000
+///         warmup #true
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AgentSlowStartConfig {
+    /// Time to reach full concurrency
+    #[serde(default = "default_slow_start_window_ms")]
+    pub window_ms: u64,
+
+    /// Send the warm-up requests the agent declares in its capabilities
+    #[serde(default)]
+    pub warmup: bool,
+}
+
+impl Default for AgentSlowStartConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: default_slow_start_window_ms(),
+            warmup: false,
+        }
+    }
+}
+
+fn default_slow_start_window_ms() -> u64 {
+    30000
+}
+
+// ============================================================================
+// Agent Session State
+// ============================================================================
+
+/// Key-value store the proxy keeps on behalf of agents
+///
+/// Agents read and write it with `StateGet`/`StateSet`/`StateDelete`
+/// messages instead of each running their own Redis. Keys are namespaced per
+/// agent.
+///
+/// KDL format (top level):
+/// ```kdl
+/// session-store {
+///     backend "redis"              // or "memory" (default)
+///     default-ttl-secs 3600
+///     max-entries 100000           // memory backend only
+///     redis-url "redis://127.0.0.1:6379"
+///     redis-prefix "zentinel:state:"
+///     redis-timeout-ms 50
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SessionStoreConfig {
+    /// Where the state is kept
+    #[serde(default)]
+    pub backend: SessionStoreBackend,
+
+    /// TTL for writes that don't specify one
+    #[serde(default = "default_session_ttl_secs")]
+    pub default_ttl_secs: u64,
+
+    /// Maximum number of entries held by the memory backend
+    #[serde(default = "default_session_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for SessionStoreConfig {
+    fn default() -> Self {
+        Self {
+            backend: SessionStoreBackend::default(),
+            default_ttl_secs: default_session_ttl_secs(),
+            max_entries: default_session_max_entries(),
+        }
+    }
+}
+
+/// Storage backend of the session store
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionStoreBackend {
+    /// In-process map, lost on restart and not shared between instances
+    #[default]
+    Memory,
+    /// Redis, shared between proxy instances
+    Redis(RedisBackendConfig),
+}
+
+/// Per-agent limits on session state
+///
+/// KDL format (inside an `agent` block):
+/// ```kdl
+/// state-quota {
+///     max-bytes 1048576
+///     max-value-bytes 65536
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AgentStateQuota {
+    /// Total size of the agent's keys and values
+    #[serde(default = "default_state_max_bytes")]
+    pub max_bytes: u64,
+
+    /// Size of a single value
+    #[serde(default = "default_state_max_value_bytes")]
+    pub max_value_bytes: u64,
+}
+
+impl Default for AgentStateQuota {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_state_max_bytes(),
+            max_value_bytes: default_state_max_value_bytes(),
+        }
+    }
+}
+
+fn default_session_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_session_max_entries() -> usize {
+    100_000
+}
+
+fn default_state_max_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_state_max_value_bytes() -> u64 {
+    64 * 1024
+}
+
+// ============================================================================
+// Agent Process
+// ============================================================================
+
+/// Directory for agent sockets when none is configured
+pub const DEFAULT_AGENT_SOCKET_DIR: &str = "/var/run/zentinel";
+
+/// Default Unix socket path for an agent
+pub fn default_agent_socket_path(agent_id: &str) -> PathBuf {
+    Path::new(DEFAULT_AGENT_SOCKET_DIR).join(format!("{}.sock", agent_id))
+}
+
+/// Configuration for an agent run as a child process
+///
+/// KDL format (inside an `agent` block, compatible with `zentinel-stack`):
+/// ```kdl
+/// agent "waf" type="waf" {
+///     command "zentinel-waf-agent" "--socket" "{socket}"
+///     restart-policy "on-failure"
+///     restart-delay-ms 1000
+///     max-restart-delay-ms 30000
+///     max-restarts 0
+///     env {
+///         RUST_LOG "info"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AgentProcessConfig {
+    /// Program and arguments
+    ///
+    /// `{socket}` in any argument is replaced with the agent's socket path,
+    /// which is also exported as `ZENTINEL_AGENT_SOCKET`.
+    pub command: Vec<String>,
+
+    /// Additional environment variables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// When to restart the process after it exits
+    #[serde(default)]
+    pub restart_policy: AgentRestartPolicy,
+
+    /// Delay before the first restart, doubled after each consecutive crash
+    #[serde(default = "default_restart_delay_ms")]
+    pub restart_delay_ms: u64,
+
+    /// Upper bound for the restart delay
+    #[serde(default = "default_max_restart_delay_ms")]
+    pub max_restart_delay_ms: u64,
+
+    /// Consecutive restarts before giving up (0 = unlimited)
+    #[serde(default)]
+    pub max_restarts: u32,
+}
+
+impl AgentProcessConfig {
+    /// Create a process configuration with default restart settings
+    pub fn new(command: Vec<String>) -> Self {
+        Self {
+            command,
+            env: BTreeMap::new(),
+            restart_policy: AgentRestartPolicy::default(),
+            restart_delay_ms: default_restart_delay_ms(),
+            max_restart_delay_ms: default_max_restart_delay_ms(),
+            max_restarts: 0,
+        }
+    }
+}
+
+/// Restart policy for supervised agent processes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum AgentRestartPolicy {
+    /// Restart whenever the process exits
+    Always,
+    /// Restart only after a non-zero exit or a signal
+    #[default]
+    OnFailure,
+    /// Never restart
+    Never,
+}
+
+fn default_restart_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_restart_delay_ms() -> u64 {
+    30000
+}
+
 // ============================================================================
 // Agent Type
 // ============================================================================
 
 /// Agent type
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AgentType {
     Waf,
@@ -255,6 +712,7 @@ pub enum AgentType {
 
 /// Agent transport configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AgentTransport {
     /// Unix domain socket
@@ -271,10 +729,15 @@ pub enum AgentTransport {
         url: String,
         tls: Option<AgentTlsConfig>,
     },
+
+    /// Handler running inside the proxy process, registered by the
+    /// application embedding it
+    InProcess,
 }
 
 /// Agent TLS configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AgentTlsConfig {
     /// Skip certificate verification
     #[serde(default)]
@@ -296,6 +759,7 @@ pub struct AgentTlsConfig {
 
 /// Agent events
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AgentEvent {
     RequestHeaders,
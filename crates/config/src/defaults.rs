@@ -7,7 +7,7 @@
 //! - Listens on port 8080 (HTTP) for the main service
 //! - Listens on port 9090 for admin/health endpoints
 //! - Returns JSON status at the root path
-//! - Provides /health and /metrics endpoints
+//! - Provides /health, /livez, /readyz, /healthz and /metrics endpoints
 
 /// Embedded default configuration in KDL format
 pub const DEFAULT_CONFIG_KDL: &str = r#"
@@ -60,13 +60,42 @@ routes {
         priority "high"
         matches {
             path "/health"
-            path "/healthz"
-            path "/ready"
         }
         service-type "builtin"
         builtin-handler "health"
     }
 
+    // Liveness probe on admin port
+    route "livez" {
+        priority "high"
+        matches {
+            path "/livez"
+        }
+        service-type "builtin"
+        builtin-handler "livez"
+    }
+
+    // Readiness probe on admin port
+    route "readyz" {
+        priority "high"
+        matches {
+            path "/readyz"
+            path "/ready"
+        }
+        service-type "builtin"
+        builtin-handler "readyz"
+    }
+
+    // Detailed subsystem health on admin port
+    route "healthz" {
+        priority "high"
+        matches {
+            path "/healthz"
+        }
+        service-type "builtin"
+        builtin-handler "healthz"
+    }
+
     // Metrics endpoint on admin port
     route "metrics" {
         priority "high"
@@ -99,6 +128,149 @@ routes {
         builtin-handler "upstreams"
     }
 
+    // Supervised agent process status endpoint on admin port
+    route "agents" {
+        priority "high"
+        matches {
+            path "/admin/agents"
+            path "/agents"
+        }
+        service-type "builtin"
+        builtin-handler "agents"
+    }
+
+    // Tenant status and drain/reload endpoint on admin port
+    route "tenants" {
+        priority "high"
+        matches {
+            path "/admin/tenants"
+            path "/tenants"
+        }
+        service-type "builtin"
+        builtin-handler "tenants"
+    }
+
+    // Maintenance mode status and toggle endpoint on admin port
+    route "maintenance" {
+        priority "high"
+        matches {
+            path "/admin/maintenance"
+            path "/maintenance"
+        }
+        service-type "builtin"
+        builtin-handler "maintenance"
+    }
+
+    // API key status and revocation endpoint on admin port
+    route "api-keys" {
+        priority "high"
+        matches {
+            path "/admin/api-keys"
+            path "/api-keys"
+        }
+        service-type "builtin"
+        builtin-handler "api-keys"
+    }
+
+    // Chaos fault status and arming endpoint on admin port
+    route "chaos" {
+        priority "high"
+        matches {
+            path "/admin/chaos"
+            path "/chaos"
+        }
+        service-type "builtin"
+        builtin-handler "chaos"
+    }
+
+    // Traffic capture status, start and stop endpoint on admin port
+    route "capture" {
+        priority "high"
+        matches {
+            path "/admin/capture"
+            path "/capture"
+        }
+        service-type "builtin"
+        builtin-handler "capture"
+    }
+
+    // Agent decision chain lookup endpoint on admin port
+    route "audit" {
+        priority "high"
+        matches {
+            path "/admin/audit"
+            path "/audit"
+        }
+        service-type "builtin"
+        builtin-handler "audit"
+    }
+
+    // Dry-run request explanation endpoint on admin port
+    route "explain" {
+        priority "high"
+        matches {
+            path "/admin/explain"
+            path "/explain"
+        }
+        service-type "builtin"
+        builtin-handler "explain"
+    }
+
+    // Consumer quota usage and reset endpoint on admin port
+    route "quotas" {
+        priority "high"
+        matches {
+            path "/admin/quotas"
+            path "/quotas"
+        }
+        service-type "builtin"
+        builtin-handler "quotas"
+    }
+
+    // Alert rule states endpoint on admin port
+    route "alerts" {
+        priority "high"
+        matches {
+            path "/admin/alerts"
+            path "/alerts"
+        }
+        service-type "builtin"
+        builtin-handler "alerts"
+    }
+
+    // Live request tap (Server-Sent Events) on admin port
+    route "tap" {
+        priority "high"
+        matches {
+            path "/admin/tap"
+            path "/tap"
+        }
+        service-type "builtin"
+        builtin-handler "tap"
+    }
+
+    // In-flight requests endpoint on admin port
+    route "requests" {
+        priority "high"
+        matches {
+            path "/admin/requests"
+            path "/requests"
+        }
+        service-type "builtin"
+        builtin-handler "requests"
+    }
+
+    // Open connections endpoint on admin port
+    route "connections" {
+        priority "high"
+        matches {
+            path "/admin/connections"
+            path "/connections"
+        }
+        service-type "builtin"
+        builtin-handler "connections"
+    }
+
     // Cache statistics endpoint on admin port
     route "cache-stats" {
         priority "high"
@@ -171,6 +343,17 @@ pub fn create_default_config() -> Config {
             trace_id_format: Default::default(),
             auto_reload: false,
             route_cache_size: 1000,
+            client_ip: Default::default(),
+            outbound_proxy: Default::default(),
+            maintenance: Default::default(),
+            concurrency: None,
+            request_id: Default::default(),
+            problem_details: None,
+            readiness: Default::default(),
+            chaos: Default::default(),
+            capture: None,
+            rollout: None,
+            debug_headers: None,
         },
         listeners: vec![
             ListenerConfig {
@@ -184,6 +367,13 @@ pub fn create_default_config() -> Config {
                 keepalive_timeout_secs: 75,
                 max_concurrent_streams: 100,
                 keepalive_max_requests: None,
+                proxy_protocol: false,
+                bot_signals: false,
+                quic: Default::default(),
+                slow_client: Default::default(),
+                hardening: Default::default(),
+                forward_proxy: None,
+                stream: None,
             },
             ListenerConfig {
                 id: "admin".to_string(),
@@ -196,6 +386,13 @@ pub fn create_default_config() -> Config {
                 keepalive_timeout_secs: 30,
                 max_concurrent_streams: 100,
                 keepalive_max_requests: None,
+                proxy_protocol: false,
+                bot_signals: false,
+                quic: Default::default(),
+                slow_client: Default::default(),
+                hardening: Default::default(),
+                forward_proxy: None,
+                stream: None,
             },
         ],
         routes: vec![
@@ -222,16 +419,75 @@ pub fn create_default_config() -> Config {
             RouteConfig {
                 id: "health".to_string(),
                 priority: Priority::HIGH,
+                matches: vec![MatchCondition::Path("/health".to_string())],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Health),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "livez".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![MatchCondition::Path("/livez".to_string())],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Livez),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "readyz".to_string(),
+                priority: Priority::HIGH,
                 matches: vec![
-                    MatchCondition::Path("/health".to_string()),
-                    MatchCondition::Path("/healthz".to_string()),
+                    MatchCondition::Path("/readyz".to_string()),
                     MatchCondition::Path("/ready".to_string()),
                 ],
                 upstream: None,
                 service_type: ServiceType::Builtin,
                 policies: RoutePolicies::default(),
                 filters: vec![],
-                builtin_handler: Some(BuiltinHandler::Health),
+                builtin_handler: Some(BuiltinHandler::Readyz),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "healthz".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![MatchCondition::Path("/healthz".to_string())],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Healthz),
                 waf_enabled: false,
                 retry_policy: None,
                 static_files: None,
@@ -309,6 +565,305 @@ pub fn create_default_config() -> Config {
                 shadow: None,
                 fallback: None,
             },
+            RouteConfig {
+                id: "agents".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/agents".to_string()),
+                    MatchCondition::Path("/agents".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Agents),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "tenants".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/tenants".to_string()),
+                    MatchCondition::Path("/tenants".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Tenants),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "maintenance".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/maintenance".to_string()),
+                    MatchCondition::Path("/maintenance".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Maintenance),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "api-keys".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/api-keys".to_string()),
+                    MatchCondition::Path("/api-keys".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::ApiKeys),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "chaos".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/chaos".to_string()),
+                    MatchCondition::Path("/chaos".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Chaos),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "capture".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/capture".to_string()),
+                    MatchCondition::Path("/capture".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Capture),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "audit".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/audit".to_string()),
+                    MatchCondition::Path("/audit".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Audit),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "explain".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/explain".to_string()),
+                    MatchCondition::Path("/explain".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Explain),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "quotas".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/quotas".to_string()),
+                    MatchCondition::Path("/quotas".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Quotas),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "alerts".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/alerts".to_string()),
+                    MatchCondition::Path("/alerts".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Alerts),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "tap".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/tap".to_string()),
+                    MatchCondition::Path("/tap".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Tap),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "requests".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/requests".to_string()),
+                    MatchCondition::Path("/requests".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Requests),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
+            RouteConfig {
+                id: "connections".to_string(),
+                priority: Priority::HIGH,
+                matches: vec![
+                    MatchCondition::Path("/admin/connections".to_string()),
+                    MatchCondition::Path("/connections".to_string()),
+                ],
+                upstream: None,
+                service_type: ServiceType::Builtin,
+                policies: RoutePolicies::default(),
+                filters: vec![],
+                builtin_handler: Some(BuiltinHandler::Connections),
+                waf_enabled: false,
+                retry_policy: None,
+                static_files: None,
+                api_schema: None,
+                inference: None,
+                error_pages: None,
+                websocket: false,
+                websocket_inspection: false,
+                shadow: None,
+                fallback: None,
+            },
             RouteConfig {
                 id: "cache-stats".to_string(),
                 priority: Priority::HIGH,
@@ -361,10 +916,14 @@ pub fn create_default_config() -> Config {
         agents: vec![],
         waf: None,
         namespaces: vec![],
+        tenants: vec![],
         limits: Limits::default(),
         observability: ObservabilityConfig::default(),
         rate_limits: GlobalRateLimitConfig::default(),
         cache: None,
+        session_store: None,
+        dns: None,
+        notifications: None,
         default_upstream: None,
     }
 }
@@ -388,11 +947,26 @@ mod tests {
     fn test_create_default_config() {
         let config = create_default_config();
         assert_eq!(config.listeners.len(), 2);
-        assert_eq!(config.routes.len(), 7);
+        assert_eq!(config.routes.len(), 23);
         assert!(config.routes.iter().any(|r| r.id == "status"));
         assert!(config.routes.iter().any(|r| r.id == "health"));
+        assert!(config.routes.iter().any(|r| r.id == "livez"));
+        assert!(config.routes.iter().any(|r| r.id == "readyz"));
+        assert!(config.routes.iter().any(|r| r.id == "healthz"));
         assert!(config.routes.iter().any(|r| r.id == "config"));
         assert!(config.routes.iter().any(|r| r.id == "upstreams"));
+        assert!(config.routes.iter().any(|r| r.id == "agents"));
+        assert!(config.routes.iter().any(|r| r.id == "tenants"));
+        assert!(config.routes.iter().any(|r| r.id == "maintenance"));
+        assert!(config.routes.iter().any(|r| r.id == "api-keys"));
+        assert!(config.routes.iter().any(|r| r.id == "chaos"));
+        assert!(config.routes.iter().any(|r| r.id == "audit"));
+        assert!(config.routes.iter().any(|r| r.id == "explain"));
+        assert!(config.routes.iter().any(|r| r.id == "quotas"));
+        assert!(config.routes.iter().any(|r| r.id == "alerts"));
+        assert!(config.routes.iter().any(|r| r.id == "tap"));
+        assert!(config.routes.iter().any(|r| r.id == "requests"));
+        assert!(config.routes.iter().any(|r| r.id == "connections"));
         assert!(config.routes.iter().any(|r| r.id == "cache-stats"));
         assert!(config.routes.iter().any(|r| r.id == "cache-purge"));
     }
@@ -0,0 +1,1219 @@
+//! Policy expressions for route matching and filter conditions.
+//!
+//! A small, CEL-like expression language used by `expr` route matches and
+//! the `enable-if` condition of filters:
+//!
+//! ```text
+//! request.method in ["GET", "HEAD"] && request.path.startsWith("/api/")
+//! request.headers["x-beta"] == "1" || client.ip.inCidr("10.0.0.0/8")
+//! client.country in ["DE", "FR"] && metadata["tier"] != "free"
+//! ```
+//!
+//! # Variables
+//!
+//! | Variable | Type | Value |
+//! |----------|------|-------|
+//! | `request.method` | string | HTTP method |
+//! | `request.path` | string | Path without the query string |
+//! | `request.host` | string | Host header |
+//! | `request.headers` | map | Request headers (names are case-insensitive) |
+//! | `request.query` | map | Query parameters |
+//! | `client.ip` | ip | Client address (after trusted proxy resolution) |
+//! | `client.country` | string | ISO country code from a geo filter lookup |
+//! | `metadata` | map | Routing metadata set by agents |
+//! | `tags` | map | Request tags set by agents |
+//!
+//! Looking up a missing map key yields `null`.
+//!
+//! # Operators and functions
+//!
+//! `!`, `&&`, `||`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `in` (list membership
+//! or map key presence), `[]` indexing, and the methods `startsWith`,
+//! `endsWith`, `contains`, `matches` (regex), `inCidr` and `size`
+//! (also as `size(x)`).
+//!
+//! Expressions are parsed and type-checked when the configuration is loaded;
+//! regexes and CIDRs must be literals and are compiled once. Evaluation never
+//! fails: an expression that does not produce `true` (for example because a
+//! header is missing) is treated as false.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::server::IpCidr;
+
+/// Maximum length of an expression source in bytes.
+const MAX_SOURCE_LEN: usize = 4096;
+
+/// Maximum nesting depth of an expression.
+const MAX_DEPTH: usize = 32;
+
+/// Compiled size limit for `matches()` patterns.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Request data an expression is evaluated against.
+pub trait ExprContext {
+    /// HTTP method
+    fn method(&self) -> &str;
+
+    /// Request path without the query string
+    fn path(&self) -> &str;
+
+    /// Host header value
+    fn host(&self) -> &str;
+
+    /// Request header by lowercase name
+    fn header(&self, name: &str) -> Option<&str>;
+
+    /// Query parameter by name
+    fn query_param(&self, name: &str) -> Option<&str>;
+
+    /// Client IP address
+    fn client_ip(&self) -> Option<IpAddr> {
+        None
+    }
+
+    /// Client country code from a geo lookup
+    fn country(&self) -> Option<&str> {
+        None
+    }
+
+    /// Routing metadata set by agents
+    fn metadata(&self, _key: &str) -> Option<&str> {
+        None
+    }
+
+    /// Request tag set by agents
+    fn tag(&self, _name: &str) -> Option<&str> {
+        None
+    }
+}
+
+/// Expression compilation errors
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ExprError {
+    #[error("expression is longer than {MAX_SOURCE_LEN} bytes")]
+    TooLong,
+
+    #[error("expression is nested deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+
+    #[error("syntax error at offset {offset}: {message}")]
+    Syntax { offset: usize, message: String },
+
+    #[error("unknown variable '{0}'")]
+    UnknownVariable(String),
+
+    #[error("unknown function '{0}'")]
+    UnknownFunction(String),
+
+    #[error("type error: {0}")]
+    Type(String),
+
+    #[error("invalid regex '{pattern}': {error}")]
+    InvalidRegex { pattern: String, error: String },
+
+    #[error("invalid CIDR: {0}")]
+    InvalidCidr(String),
+}
+
+/// A compiled policy expression.
+///
+/// Serializes as its source string; deserializing compiles it.
+#[derive(Clone)]
+pub struct Expression {
+    source: String,
+    root: Arc<Node>,
+    uses: Uses,
+}
+
+impl Expression {
+    /// Parse, type-check and compile an expression.
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        if source.len() > MAX_SOURCE_LEN {
+            return Err(ExprError::TooLong);
+        }
+
+        let tokens = lex(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+            uses: Uses::default(),
+            end: source.len(),
+        };
+        let root = parser.parse_expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(ExprError::Syntax {
+                offset: token.offset,
+                message: format!("unexpected {}", token.kind),
+            });
+        }
+
+        let ty = check(&root)?;
+        if !matches!(ty, Type::Bool) {
+            return Err(ExprError::Type(format!(
+                "expression must evaluate to a bool, found {ty}"
+            )));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            root: Arc::new(root),
+            uses: parser.uses,
+        })
+    }
+
+    /// The expression source
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the expression; anything other than `true` is false.
+    pub fn evaluate(&self, ctx: &dyn ExprContext) -> bool {
+        matches!(eval(&self.root, ctx), Value::Bool(true))
+    }
+
+    /// Whether the expression reads `request.headers`
+    pub fn needs_headers(&self) -> bool {
+        self.uses.headers
+    }
+
+    /// Whether the expression reads `request.query`
+    pub fn needs_query_params(&self) -> bool {
+        self.uses.query
+    }
+
+    /// Whether the expression reads `client.ip`
+    pub fn needs_client_ip(&self) -> bool {
+        self.uses.client_ip
+    }
+
+    /// Whether the expression reads `client.country`
+    pub fn needs_country(&self) -> bool {
+        self.uses.country
+    }
+
+    /// Whether the expression reads agent `metadata` or `tags`
+    pub fn needs_metadata(&self) -> bool {
+        self.uses.metadata
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Expression").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Expression {
+    fn schema_name() -> Cow<'static, str> {
+        "Expression".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Policy expression, e.g. `request.path.startsWith(\"/api/\")`",
+        })
+    }
+}
+
+// ============================================================================
+// Syntax Tree
+// ============================================================================
+
+/// Request attributes read by an expression
+#[derive(Debug, Clone, Copy, Default)]
+struct Uses {
+    headers: bool,
+    query: bool,
+    client_ip: bool,
+    country: bool,
+    metadata: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Var {
+    Method,
+    Path,
+    Host,
+    Headers,
+    Query,
+    ClientIp,
+    Country,
+    Metadata,
+    Tags,
+}
+
+impl Var {
+    fn resolve(path: &str) -> Option<Self> {
+        Some(match path {
+            "request.method" => Self::Method,
+            "request.path" => Self::Path,
+            "request.host" => Self::Host,
+            "request.headers" => Self::Headers,
+            "request.query" => Self::Query,
+            "client.ip" => Self::ClientIp,
+            "client.country" => Self::Country,
+            "metadata" => Self::Metadata,
+            "tags" => Self::Tags,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StrOp {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug)]
+enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Node>),
+    Var(Var),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Cmp(CmpOp, Box<Node>, Box<Node>),
+    In(Box<Node>, Box<Node>),
+    Index(Box<Node>, Box<Node>),
+    StrOp(StrOp, Box<Node>, Box<Node>),
+    Matches(Box<Node>, Regex),
+    InCidr(Box<Node>, IpCidr),
+    Size(Box<Node>),
+}
+
+// ============================================================================
+// Lexer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(name) => write!(f, "'{name}'"),
+            Self::Str(_) => f.write_str("string"),
+            Self::Int(_) => f.write_str("integer"),
+            Self::LParen => f.write_str("'('"),
+            Self::RParen => f.write_str("')'"),
+            Self::LBracket => f.write_str("'['"),
+            Self::RBracket => f.write_str("']'"),
+            Self::Comma => f.write_str("','"),
+            Self::Dot => f.write_str("'.'"),
+            Self::Not => f.write_str("'!'"),
+            Self::And => f.write_str("'&&'"),
+            Self::Or => f.write_str("'||'"),
+            Self::Eq => f.write_str("'=='"),
+            Self::Ne => f.write_str("'!='"),
+            Self::Lt => f.write_str("'<'"),
+            Self::Le => f.write_str("'<='"),
+            Self::Gt => f.write_str("'>'"),
+            Self::Ge => f.write_str("'>='"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Tok,
+    offset: usize,
+}
+
+fn syntax(offset: usize, message: impl Into<String>) -> ExprError {
+    ExprError::Syntax {
+        offset,
+        message: message.into(),
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            '[' => Tok::LBracket,
+            ']' => Tok::RBracket,
+            ',' => Tok::Comma,
+            '.' => Tok::Dot,
+            '!' | '=' | '<' | '>' | '&' | '|' => {
+                let next = chars.peek().map(|(_, c)| *c);
+                let (kind, pair) = match (c, next) {
+                    ('!', Some('=')) => (Tok::Ne, true),
+                    ('!', _) => (Tok::Not, false),
+                    ('=', Some('=')) => (Tok::Eq, true),
+                    ('<', Some('=')) => (Tok::Le, true),
+                    ('<', _) => (Tok::Lt, false),
+                    ('>', Some('=')) => (Tok::Ge, true),
+                    ('>', _) => (Tok::Gt, false),
+                    ('&', Some('&')) => (Tok::And, true),
+                    ('|', Some('|')) => (Tok::Or, true),
+                    _ => return Err(syntax(offset, format!("unexpected '{c}'"))),
+                };
+                if pair {
+                    chars.next();
+                }
+                kind
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == quote => break,
+                        Some((escape, '\\')) => match chars.next() {
+                            Some((_, 'n')) => value.push('\n'),
+                            Some((_, 't')) => value.push('\t'),
+                            Some((_, c @ ('\\' | '"' | '\''))) => value.push(c),
+                            _ => return Err(syntax(escape, "invalid escape sequence")),
+                        },
+                        Some((_, c)) => value.push(c),
+                        None => return Err(syntax(offset, "unterminated string")),
+                    }
+                }
+                Tok::Str(value)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = offset + c.len_utf8();
+                while let Some((i, c)) = chars.peek().copied() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let value = source[offset..end]
+                    .parse()
+                    .map_err(|_| syntax(offset, "integer out of range"))?;
+                Tok::Int(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = offset + c.len_utf8();
+                while let Some((i, c)) = chars.peek().copied() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                Tok::Ident(source[offset..end].to_string())
+            }
+            c => return Err(syntax(offset, format!("unexpected '{c}'"))),
+        };
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+    uses: Uses,
+    /// Source length, reported as the offset of "end of expression" errors
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self, ahead: usize) -> Option<&Tok> {
+        self.tokens.get(self.pos + ahead).map(|t| &t.kind)
+    }
+
+    fn offset(&self) -> usize {
+        self.peek().map(|t| t.offset).unwrap_or(self.end)
+    }
+
+    fn eat(&mut self, kind: &Tok) -> bool {
+        if self.peek_kind(0) == Some(kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: Tok) -> Result<(), ExprError> {
+        if self.eat(&kind) {
+            return Ok(());
+        }
+        let found = match self.peek() {
+            Some(token) => token.kind.to_string(),
+            None => "end of expression".to_string(),
+        };
+        Err(syntax(
+            self.offset(),
+            format!("expected {kind}, found {found}"),
+        ))
+    }
+
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::TooDeep);
+        }
+        Ok(())
+    }
+
+    fn parse_expr(&mut self) -> Result<Node, ExprError> {
+        self.enter()?;
+        let node = self.parse_or();
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.parse_and()?;
+        while self.eat(&Tok::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.parse_comparison()?;
+        while self.eat(&Tok::And) {
+            node = Node::And(Box::new(node), Box::new(self.parse_comparison()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Node, ExprError> {
+        let lhs = self.parse_unary()?;
+
+        let op = match self.peek_kind(0) {
+            Some(Tok::Eq) => CmpOp::Eq,
+            Some(Tok::Ne) => CmpOp::Ne,
+            Some(Tok::Lt) => CmpOp::Lt,
+            Some(Tok::Le) => CmpOp::Le,
+            Some(Tok::Gt) => CmpOp::Gt,
+            Some(Tok::Ge) => CmpOp::Ge,
+            Some(Tok::Ident(name)) if name == "in" => {
+                self.pos += 1;
+                let rhs = self.parse_unary()?;
+                return Ok(Node::In(Box::new(lhs), Box::new(rhs)));
+            }
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.parse_unary()?;
+        Ok(Node::Cmp(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ExprError> {
+        if self.eat(&Tok::Not) {
+            self.enter()?;
+            let operand = self.parse_unary()?;
+            self.depth -= 1;
+            return Ok(Node::Not(Box::new(operand)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.parse_primary()?;
+
+        loop {
+            if self.eat(&Tok::LBracket) {
+                let key = self.parse_expr()?;
+                self.expect(Tok::RBracket)?;
+                node = Node::Index(Box::new(node), Box::new(key));
+            } else if self.peek_kind(0) == Some(&Tok::Dot) {
+                let offset = self.offset();
+                self.pos += 1;
+                let name = match self.peek_kind(0) {
+                    Some(Tok::Ident(name)) => name.clone(),
+                    _ => return Err(syntax(self.offset(), "expected a method name")),
+                };
+                self.pos += 1;
+                if self.peek_kind(0) != Some(&Tok::LParen) {
+                    return Err(syntax(
+                        offset,
+                        format!("'.{name}' is not a method call; fields exist only on variables"),
+                    ));
+                }
+                let args = self.parse_args()?;
+                node = method_call(node, &name, args)?;
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Node>, ExprError> {
+        self.expect(Tok::LParen)?;
+        let mut args = Vec::new();
+        if !self.eat(&Tok::RParen) {
+            loop {
+                args.push(self.parse_expr()?);
+                if self.eat(&Tok::RParen) {
+                    break;
+                }
+                self.expect(Tok::Comma)?;
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ExprError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(syntax(self.end, "unexpected end of expression"));
+        };
+        self.pos += 1;
+
+        match token.kind {
+            Tok::Int(value) => Ok(Node::Int(value)),
+            Tok::Str(value) => Ok(Node::Str(value)),
+            Tok::LParen => {
+                let node = self.parse_expr()?;
+                self.expect(Tok::RParen)?;
+                Ok(node)
+            }
+            Tok::LBracket => {
+                let mut items = Vec::new();
+                if !self.eat(&Tok::RBracket) {
+                    loop {
+                        items.push(self.parse_expr()?);
+                        if self.eat(&Tok::RBracket) {
+                            break;
+                        }
+                        self.expect(Tok::Comma)?;
+                    }
+                }
+                Ok(Node::List(items))
+            }
+            Tok::Ident(name) => match name.as_str() {
+                "true" => Ok(Node::Bool(true)),
+                "false" => Ok(Node::Bool(false)),
+                "null" => Ok(Node::Null),
+                _ if self.peek_kind(0) == Some(&Tok::LParen) => {
+                    let args = self.parse_args()?;
+                    match (name.as_str(), args.len()) {
+                        ("size", 1) => Ok(Node::Size(Box::new(args.into_iter().next().unwrap()))),
+                        ("size", n) => {
+                            Err(ExprError::Type(format!("size() takes 1 argument, got {n}")))
+                        }
+                        _ => Err(ExprError::UnknownFunction(name)),
+                    }
+                }
+                _ => self.variable(name),
+            },
+            other => Err(syntax(token.offset, format!("unexpected {other}"))),
+        }
+    }
+
+    /// Resolve a dotted variable name, leaving a trailing method call to
+    /// `parse_postfix`.
+    fn variable(&mut self, first: String) -> Result<Node, ExprError> {
+        let mut path = first;
+        while self.peek_kind(0) == Some(&Tok::Dot) && self.peek_kind(2) != Some(&Tok::LParen) {
+            match self.peek_kind(1) {
+                Some(Tok::Ident(name)) => {
+                    path.push('.');
+                    path.push_str(name);
+                    self.pos += 2;
+                }
+                _ => break,
+            }
+        }
+
+        let var = Var::resolve(&path).ok_or(ExprError::UnknownVariable(path))?;
+        match var {
+            Var::Headers => self.uses.headers = true,
+            Var::Query => self.uses.query = true,
+            Var::ClientIp => self.uses.client_ip = true,
+            Var::Country => self.uses.country = true,
+            Var::Metadata | Var::Tags => self.uses.metadata = true,
+            Var::Method | Var::Path | Var::Host => {}
+        }
+        Ok(Node::Var(var))
+    }
+}
+
+/// Compile a method call on `receiver`.
+fn method_call(receiver: Node, name: &str, args: Vec<Node>) -> Result<Node, ExprError> {
+    let arity = match name {
+        "size" => 0,
+        "startsWith" | "endsWith" | "contains" | "matches" | "inCidr" => 1,
+        _ => return Err(ExprError::UnknownFunction(name.to_string())),
+    };
+    if args.len() != arity {
+        return Err(ExprError::Type(format!(
+            "{name}() takes {arity} argument(s), got {}",
+            args.len()
+        )));
+    }
+    let mut args = args.into_iter();
+    let receiver = Box::new(receiver);
+
+    Ok(match name {
+        "size" => Node::Size(receiver),
+        "startsWith" => Node::StrOp(StrOp::StartsWith, receiver, Box::new(args.next().unwrap())),
+        "endsWith" => Node::StrOp(StrOp::EndsWith, receiver, Box::new(args.next().unwrap())),
+        "contains" => Node::StrOp(StrOp::Contains, receiver, Box::new(args.next().unwrap())),
+        "matches" => {
+            let Some(Node::Str(pattern)) = args.next() else {
+                return Err(ExprError::Type(
+                    "matches() requires a string literal pattern".to_string(),
+                ));
+            };
+            let regex = RegexBuilder::new(&pattern)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .build()
+                .map_err(|e| ExprError::InvalidRegex {
+                    pattern: pattern.clone(),
+                    error: e.to_string(),
+                })?;
+            Node::Matches(receiver, regex)
+        }
+        "inCidr" => {
+            let Some(Node::Str(cidr)) = args.next() else {
+                return Err(ExprError::Type(
+                    "inCidr() requires a string literal CIDR".to_string(),
+                ));
+            };
+            Node::InCidr(receiver, cidr.parse().map_err(ExprError::InvalidCidr)?)
+        }
+        _ => unreachable!("arity checked above"),
+    })
+}
+
+// ============================================================================
+// Type Checking
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Null,
+    Bool,
+    Int,
+    Str,
+    Ip,
+    List,
+    Map,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Int => "int",
+            Self::Str => "string",
+            Self::Ip => "ip",
+            Self::List => "list",
+            Self::Map => "map",
+        })
+    }
+}
+
+fn expect_type(node: &Node, expected: Type, what: &str) -> Result<(), ExprError> {
+    let ty = check(node)?;
+    if ty == expected || ty == Type::Null {
+        Ok(())
+    } else {
+        Err(ExprError::Type(format!(
+            "{what} expects {expected}, found {ty}"
+        )))
+    }
+}
+
+fn check(node: &Node) -> Result<Type, ExprError> {
+    Ok(match node {
+        Node::Null => Type::Null,
+        Node::Bool(_) => Type::Bool,
+        Node::Int(_) => Type::Int,
+        Node::Str(_) => Type::Str,
+        Node::List(items) => {
+            for item in items {
+                check(item)?;
+            }
+            Type::List
+        }
+        Node::Var(var) => match var {
+            Var::Method | Var::Path | Var::Host | Var::Country => Type::Str,
+            Var::Headers | Var::Query | Var::Metadata | Var::Tags => Type::Map,
+            Var::ClientIp => Type::Ip,
+        },
+        Node::Not(operand) => {
+            expect_type(operand, Type::Bool, "'!'")?;
+            Type::Bool
+        }
+        Node::And(lhs, rhs) | Node::Or(lhs, rhs) => {
+            expect_type(lhs, Type::Bool, "'&&' / '||'")?;
+            expect_type(rhs, Type::Bool, "'&&' / '||'")?;
+            Type::Bool
+        }
+        Node::Cmp(op, lhs, rhs) => {
+            let (lt, rt) = (check(lhs)?, check(rhs)?);
+            let comparable = match op {
+                CmpOp::Eq | CmpOp::Ne => {
+                    lt == rt
+                        || lt == Type::Null
+                        || rt == Type::Null
+                        || matches!((lt, rt), (Type::Ip, Type::Str) | (Type::Str, Type::Ip))
+                }
+                _ => lt == rt && matches!(lt, Type::Int | Type::Str),
+            };
+            if !comparable || lt == Type::Map || rt == Type::Map {
+                return Err(ExprError::Type(format!("cannot compare {lt} with {rt}")));
+            }
+            Type::Bool
+        }
+        Node::In(lhs, rhs) => {
+            let lt = check(lhs)?;
+            match check(rhs)? {
+                Type::List => {}
+                Type::Map if matches!(lt, Type::Str | Type::Null) => {}
+                Type::Map => {
+                    return Err(ExprError::Type(format!("map keys are strings, found {lt}")))
+                }
+                other => {
+                    return Err(ExprError::Type(format!(
+                        "'in' expects a list or map, found {other}"
+                    )))
+                }
+            }
+            Type::Bool
+        }
+        Node::Index(map, key) => {
+            if check(map)? != Type::Map {
+                return Err(ExprError::Type("only maps can be indexed".to_string()));
+            }
+            expect_type(key, Type::Str, "map index")?;
+            Type::Str
+        }
+        Node::StrOp(op, receiver, arg) => {
+            let name = match op {
+                StrOp::StartsWith => "startsWith()",
+                StrOp::EndsWith => "endsWith()",
+                StrOp::Contains => "contains()",
+            };
+            expect_type(receiver, Type::Str, name)?;
+            expect_type(arg, Type::Str, name)?;
+            Type::Bool
+        }
+        Node::Matches(receiver, _) => {
+            expect_type(receiver, Type::Str, "matches()")?;
+            Type::Bool
+        }
+        Node::InCidr(receiver, _) => {
+            expect_type(receiver, Type::Ip, "inCidr()")?;
+            Type::Bool
+        }
+        Node::Size(operand) => {
+            let ty = check(operand)?;
+            if !matches!(ty, Type::Str | Type::List | Type::Null) {
+                return Err(ExprError::Type(format!(
+                    "size() expects a string or list, found {ty}"
+                )));
+            }
+            Type::Int
+        }
+    })
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+#[derive(Debug, Clone)]
+enum Value<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(Cow<'a, str>),
+    Ip(IpAddr),
+    List(Vec<Value<'a>>),
+    Map(Var),
+}
+
+fn lookup<'a>(ctx: &'a dyn ExprContext, map: Var, key: &str) -> Option<&'a str> {
+    match map {
+        Var::Headers => ctx.header(&key.to_ascii_lowercase()),
+        Var::Query => ctx.query_param(key),
+        Var::Metadata => ctx.metadata(key),
+        Var::Tags => ctx.tag(key),
+        _ => None,
+    }
+}
+
+fn equals(lhs: &Value<'_>, rhs: &Value<'_>) -> bool {
+    match (lhs, rhs) {
+        (Value::Null, Value::Null) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Str(a), Value::Str(b)) => a == b,
+        (Value::Ip(a), Value::Ip(b)) => a.to_canonical() == b.to_canonical(),
+        (Value::Ip(ip), Value::Str(s)) | (Value::Str(s), Value::Ip(ip)) => s
+            .parse::<IpAddr>()
+            .is_ok_and(|parsed| parsed.to_canonical() == ip.to_canonical()),
+        _ => false,
+    }
+}
+
+fn eval<'a>(node: &'a Node, ctx: &'a dyn ExprContext) -> Value<'a> {
+    match node {
+        Node::Null => Value::Null,
+        Node::Bool(b) => Value::Bool(*b),
+        Node::Int(i) => Value::Int(*i),
+        Node::Str(s) => Value::Str(Cow::Borrowed(s)),
+        Node::List(items) => Value::List(items.iter().map(|item| eval(item, ctx)).collect()),
+        Node::Var(var) => match var {
+            Var::Method => Value::Str(Cow::Borrowed(ctx.method())),
+            Var::Path => Value::Str(Cow::Borrowed(ctx.path())),
+            Var::Host => Value::Str(Cow::Borrowed(ctx.host())),
+            Var::ClientIp => ctx.client_ip().map_or(Value::Null, Value::Ip),
+            Var::Country => ctx
+                .country()
+                .map_or(Value::Null, |c| Value::Str(Cow::Borrowed(c))),
+            Var::Headers | Var::Query | Var::Metadata | Var::Tags => Value::Map(*var),
+        },
+        Node::Not(operand) => match eval(operand, ctx) {
+            Value::Bool(b) => Value::Bool(!b),
+            _ => Value::Null,
+        },
+        // Like CEL, `false && x` and `true || x` hold even if `x` is not a bool
+        Node::And(lhs, rhs) => {
+            let lhs = eval(lhs, ctx);
+            if matches!(lhs, Value::Bool(false)) {
+                return lhs;
+            }
+            match (lhs, eval(rhs, ctx)) {
+                (_, Value::Bool(false)) => Value::Bool(false),
+                (Value::Bool(true), Value::Bool(true)) => Value::Bool(true),
+                _ => Value::Null,
+            }
+        }
+        Node::Or(lhs, rhs) => {
+            let lhs = eval(lhs, ctx);
+            if matches!(lhs, Value::Bool(true)) {
+                return lhs;
+            }
+            match (lhs, eval(rhs, ctx)) {
+                (_, Value::Bool(true)) => Value::Bool(true),
+                (Value::Bool(false), Value::Bool(false)) => Value::Bool(false),
+                _ => Value::Null,
+            }
+        }
+        Node::Cmp(op, lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs, ctx), eval(rhs, ctx));
+            let ordering = match (&lhs, &rhs) {
+                (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+                (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            match op {
+                CmpOp::Eq => Value::Bool(equals(&lhs, &rhs)),
+                CmpOp::Ne => Value::Bool(!equals(&lhs, &rhs)),
+                CmpOp::Lt => ordering.map_or(Value::Null, |o| Value::Bool(o.is_lt())),
+                CmpOp::Le => ordering.map_or(Value::Null, |o| Value::Bool(o.is_le())),
+                CmpOp::Gt => ordering.map_or(Value::Null, |o| Value::Bool(o.is_gt())),
+                CmpOp::Ge => ordering.map_or(Value::Null, |o| Value::Bool(o.is_ge())),
+            }
+        }
+        Node::In(needle, haystack) => {
+            let needle = eval(needle, ctx);
+            match (eval(haystack, ctx), &needle) {
+                (Value::List(items), _) => {
+                    Value::Bool(items.iter().any(|item| equals(item, &needle)))
+                }
+                (Value::Map(map), Value::Str(key)) => Value::Bool(lookup(ctx, map, key).is_some()),
+                _ => Value::Null,
+            }
+        }
+        Node::Index(map, key) => match (eval(map, ctx), eval(key, ctx)) {
+            (Value::Map(map), Value::Str(key)) => {
+                lookup(ctx, map, &key).map_or(Value::Null, |value| Value::Str(Cow::Borrowed(value)))
+            }
+            _ => Value::Null,
+        },
+        Node::StrOp(op, receiver, arg) => match (eval(receiver, ctx), eval(arg, ctx)) {
+            (Value::Str(s), Value::Str(arg)) => Value::Bool(match op {
+                StrOp::StartsWith => s.starts_with(arg.as_ref()),
+                StrOp::EndsWith => s.ends_with(arg.as_ref()),
+                StrOp::Contains => s.contains(arg.as_ref()),
+            }),
+            _ => Value::Null,
+        },
+        Node::Matches(receiver, regex) => match eval(receiver, ctx) {
+            Value::Str(s) => Value::Bool(regex.is_match(&s)),
+            _ => Value::Null,
+        },
+        Node::InCidr(receiver, cidr) => match eval(receiver, ctx) {
+            Value::Ip(ip) => Value::Bool(cidr.contains(ip)),
+            _ => Value::Null,
+        },
+        Node::Size(operand) => match eval(operand, ctx) {
+            Value::Str(s) => Value::Int(s.chars().count() as i64),
+            Value::List(items) => Value::Int(items.len() as i64),
+            _ => Value::Null,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct TestRequest {
+        method: &'static str,
+        path: &'static str,
+        host: &'static str,
+        headers: HashMap<String, String>,
+        query: HashMap<String, String>,
+        client_ip: Option<IpAddr>,
+        country: Option<String>,
+        metadata: HashMap<String, String>,
+        tags: HashMap<String, String>,
+    }
+
+    impl ExprContext for TestRequest {
+        fn method(&self) -> &str {
+            self.method
+        }
+        fn path(&self) -> &str {
+            self.path
+        }
+        fn host(&self) -> &str {
+            self.host
+        }
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers.get(name).map(String::as_str)
+        }
+        fn query_param(&self, name: &str) -> Option<&str> {
+            self.query.get(name).map(String::as_str)
+        }
+        fn client_ip(&self) -> Option<IpAddr> {
+            self.client_ip
+        }
+        fn country(&self) -> Option<&str> {
+            self.country.as_deref()
+        }
+        fn metadata(&self, key: &str) -> Option<&str> {
+            self.metadata.get(key).map(String::as_str)
+        }
+        fn tag(&self, name: &str) -> Option<&str> {
+            self.tags.get(name).map(String::as_str)
+        }
+    }
+
+    fn request() -> TestRequest {
+        TestRequest {
+            method: "GET",
+            path: "/api/v1/users",
+            host: "example.com",
+            headers: HashMap::from([("x-beta".to_string(), "1".to_string())]),
+            query: HashMap::from([("page".to_string(), "2".to_string())]),
+            client_ip: Some("10.1.2.3".parse().unwrap()),
+            country: Some("DE".to_string()),
+            metadata: HashMap::from([("tier".to_string(), "gold".to_string())]),
+            tags: HashMap::from([("bot_class".to_string(), "verified".to_string())]),
+        }
+    }
+
+    fn eval_str(source: &str) -> bool {
+        Expression::parse(source)
+            .unwrap_or_else(|e| panic!("{source}: {e}"))
+            .evaluate(&request())
+    }
+
+    #[test]
+    fn test_request_attributes() {
+        assert!(eval_str(r#"request.method == "GET""#));
+        assert!(eval_str(r#"request.method in ["GET", "HEAD"]"#));
+        assert!(eval_str(r#"request.path.startsWith("/api/")"#));
+        assert!(eval_str(
+            r#"request.host.endsWith(".com") && !(request.host == "other")"#
+        ));
+        assert!(eval_str(r#"request.headers["X-Beta"] == "1""#));
+        assert!(eval_str(r#""x-beta" in request.headers"#));
+        assert!(eval_str(r#"request.query["page"] == '2'"#));
+        assert!(eval_str(r#"request.path.matches("^/api/v[0-9]+/")"#));
+        assert!(eval_str(
+            "size(request.path) > 5 && request.path.size() < 100"
+        ));
+    }
+
+    #[test]
+    fn test_client_and_metadata() {
+        assert!(eval_str(r#"client.ip.inCidr("10.0.0.0/8")"#));
+        assert!(eval_str(r#"client.ip == "10.1.2.3""#));
+        assert!(eval_str(r#"client.country in ["DE", "FR"]"#));
+        assert!(eval_str(r#"metadata["tier"] == "gold""#));
+        assert!(eval_str(
+            r#"tags["bot_class"] == "verified" && !("user_id" in tags)"#
+        ));
+        assert!(!eval_str(r#"client.ip.inCidr("192.168.0.0/16")"#));
+    }
+
+    #[test]
+    fn test_missing_values_are_false() {
+        assert!(!eval_str(r#"request.headers["x-missing"] == "1""#));
+        assert!(!eval_str(r#"request.headers["x-missing"].startsWith("a")"#));
+        assert!(eval_str(r#"request.headers["x-missing"] == null"#));
+        assert!(eval_str(r#"request.headers["x-missing"] != "1""#));
+        // Short-circuit absorbs a null operand
+        assert!(eval_str(
+            r#"request.headers["x-missing"].contains("a") || true"#
+        ));
+        assert!(!eval_str(
+            r#"false && request.headers["x-missing"].contains("a")"#
+        ));
+
+        let expr = Expression::parse(r#"client.country == "DE""#).unwrap();
+        assert!(!expr.evaluate(&TestRequest::default()));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let err = |source: &str| Expression::parse(source).unwrap_err();
+
+        assert_eq!(
+            err("request.verb == \"GET\""),
+            ExprError::UnknownVariable("request.verb".to_string())
+        );
+        assert!(matches!(err("request.method"), ExprError::Type(_)));
+        assert!(matches!(err("request.method == 1"), ExprError::Type(_)));
+        assert!(matches!(
+            err("client.ip.startsWith(\"10.\")"),
+            ExprError::Type(_)
+        ));
+        assert!(matches!(
+            err("request.path.matches(request.host)"),
+            ExprError::Type(_)
+        ));
+        assert!(matches!(
+            err("request.path.matches(\"(\")"),
+            ExprError::InvalidRegex { .. }
+        ));
+        assert!(matches!(
+            err("client.ip.inCidr(\"10.0.0.0/99\")"),
+            ExprError::InvalidCidr(_)
+        ));
+        assert!(matches!(
+            err("request.method == "),
+            ExprError::Syntax { .. }
+        ));
+        assert!(matches!(
+            err("request.method = \"GET\""),
+            ExprError::Syntax { .. }
+        ));
+        assert!(matches!(err("exec(\"rm\")"), ExprError::UnknownFunction(_)));
+        assert_eq!(err(&"!".repeat(64)), ExprError::TooDeep);
+        assert_eq!(err(&"x".repeat(MAX_SOURCE_LEN + 1)), ExprError::TooLong);
+    }
+
+    #[test]
+    fn test_uses_and_serde() {
+        let expr =
+            Expression::parse(r#"request.headers["a"] == "b" && metadata["c"] == "d""#).unwrap();
+        assert!(expr.needs_headers());
+        assert!(expr.needs_metadata());
+        assert!(!expr.needs_query_params());
+        assert!(!expr.needs_client_ip());
+        assert!(!expr.needs_country());
+        assert!(Expression::parse(r#"tags["tier"] == "gold""#)
+            .unwrap()
+            .needs_metadata());
+
+        let json = serde_json::to_string(&expr).unwrap();
+        let parsed: Expression = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, expr);
+        assert!(serde_json::from_str::<Expression>("\"request.nope\"").is_err());
+    }
+}
@@ -31,9 +31,11 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::FailureMode;
+use crate::expr::Expression;
+use crate::json_path::{JsonPath, JsonPathSegment};
+use crate::{AgentEvent, FailureMode};
 
 // =============================================================================
 // Filter Instance Configuration
@@ -44,6 +46,7 @@ use crate::FailureMode;
 /// Filter instances are defined in the top-level `filters` block and
 /// referenced by ID in route configurations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct FilterConfig {
     /// Unique identifier for this filter instance
     pub id: String,
@@ -51,6 +54,10 @@ pub struct FilterConfig {
     /// The filter type and its configuration
     #[serde(flatten)]
     pub filter: Filter,
+
+    /// Apply the filter only when this expression is true (`enable-if`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_if: Option<Expression>,
 }
 
 impl FilterConfig {
@@ -59,6 +66,7 @@ impl FilterConfig {
         Self {
             id: id.into(),
             filter,
+            enable_if: None,
         }
     }
 
@@ -84,6 +92,7 @@ impl FilterConfig {
 
 /// Filter execution phase
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum FilterPhase {
     /// Execute during request processing (before upstream)
@@ -97,6 +106,7 @@ pub enum FilterPhase {
 
 /// A filter type with its configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Filter {
     /// Rate limiting filter (built-in)
@@ -128,6 +138,42 @@ pub enum Filter {
 
     /// URL rewrite filter (modifies request path/host before forwarding)
     UrlRewrite(UrlRewriteFilter),
+
+    /// Embedded WebAssembly filter (runs in-process)
+    Wasm(WasmFilter),
+
+    /// JSON body field manipulation (built-in)
+    JsonTransform(JsonTransformFilter),
+
+    /// Request validation against an OpenAPI or JSON Schema document (built-in)
+    SchemaValidate(SchemaValidateFilter),
+
+    /// API key authentication against a local key file (built-in)
+    ApiKey(ApiKeyFilter),
+
+    /// Response size, content-type and header policy (built-in)
+    ResponsePolicy(ResponsePolicyFilter),
+
+    /// WAF-style rule bundle with anomaly scoring (built-in)
+    ManagedRules(ManagedRulesFilter),
+
+    /// Client IP allow/deny lists (built-in)
+    IpAccess(IpAccessFilter),
+
+    /// Per-client anomaly scoring with temporary throttles or challenges (built-in)
+    AdaptiveProtection(AdaptiveProtectionFilter),
+
+    /// Daily and monthly request/byte quotas per API consumer (built-in)
+    Quota(QuotaFilter),
+
+    /// Server-side system prompt and allowed prompt templates for inference requests (built-in)
+    PromptTemplate(PromptTemplateFilter),
+
+    /// multipart/form-data parsing with per-part agent events and size limits (built-in)
+    Multipart(MultipartFilter),
+
+    /// Antivirus scanning of bodies on an ICAP server (built-in)
+    Icap(IcapFilter),
 }
 
 impl Filter {
@@ -151,6 +197,30 @@ impl Filter {
             Filter::Agent(a) => a.phase.unwrap_or(FilterPhase::Request),
             Filter::Redirect(_) => FilterPhase::Request,
             Filter::UrlRewrite(_) => FilterPhase::Request,
+            Filter::Wasm(w) => {
+                let request =
+                    w.handles(AgentEvent::RequestHeaders) || w.handles(AgentEvent::RequestBody);
+                match (request, w.handles(AgentEvent::ResponseHeaders)) {
+                    (true, true) => FilterPhase::Both,
+                    (false, true) => FilterPhase::Response,
+                    _ => FilterPhase::Request,
+                }
+            }
+            Filter::JsonTransform(j) => j.phase,
+            Filter::SchemaValidate(_) => FilterPhase::Request,
+            Filter::ApiKey(_) => FilterPhase::Request,
+            Filter::ResponsePolicy(_) => FilterPhase::Response,
+            Filter::ManagedRules(_) => FilterPhase::Request,
+            Filter::IpAccess(_) => FilterPhase::Request,
+            Filter::AdaptiveProtection(_) => FilterPhase::Both,
+            Filter::Quota(_) => FilterPhase::Both,
+            Filter::PromptTemplate(_) => FilterPhase::Request,
+            Filter::Multipart(_) => FilterPhase::Request,
+            Filter::Icap(i) => match (i.reqmod.is_some(), i.respmod.is_some()) {
+                (true, true) => FilterPhase::Both,
+                (false, true) => FilterPhase::Response,
+                _ => FilterPhase::Request,
+            },
         }
     }
 
@@ -167,6 +237,18 @@ impl Filter {
             Filter::Agent(_) => "agent",
             Filter::Redirect(_) => "redirect",
             Filter::UrlRewrite(_) => "url-rewrite",
+            Filter::Wasm(_) => "wasm",
+            Filter::JsonTransform(_) => "json-transform",
+            Filter::SchemaValidate(_) => "schema-validate",
+            Filter::ApiKey(_) => "api-key",
+            Filter::ResponsePolicy(_) => "response-policy",
+            Filter::ManagedRules(_) => "managed-rules",
+            Filter::IpAccess(_) => "ip-access",
+            Filter::AdaptiveProtection(_) => "adaptive-protection",
+            Filter::Quota(_) => "quota",
+            Filter::PromptTemplate(_) => "prompt-template",
+            Filter::Multipart(_) => "multipart",
+            Filter::Icap(_) => "icap",
         }
     }
 
@@ -209,6 +291,260 @@ impl Filter {
                     a.agent, available_agents
                 ));
             }
+            Filter::Wasm(w) => {
+                if w.module.is_empty() {
+                    return Err("wasm filter requires 'module'".into());
+                }
+                if w.max_memory_mb == 0 || w.max_fuel == 0 || w.instances == 0 {
+                    return Err(
+                        "wasm filter: max-memory-mb, max-fuel and instances must be > 0".into(),
+                    );
+                }
+                if w.events.is_empty() {
+                    return Err("wasm filter requires at least one event".into());
+                }
+                if let Some(event) = w
+                    .events
+                    .iter()
+                    .find(|e| !WasmFilter::SUPPORTED_EVENTS.contains(e))
+                {
+                    return Err(format!(
+                        "wasm filter: unsupported event {:?} (supported: {:?})",
+                        event,
+                        WasmFilter::SUPPORTED_EVENTS
+                    ));
+                }
+                if let Some(ref config) = w.config {
+                    if let Err(e) = serde_json::from_str::<serde_json::Value>(config) {
+                        return Err(format!("wasm filter: 'config' is not valid JSON: {}", e));
+                    }
+                }
+            }
+            Filter::JsonTransform(j) => j.validate()?,
+            Filter::SchemaValidate(s) => {
+                if s.schema.is_empty() {
+                    return Err("schema-validate filter requires 'schema'".into());
+                }
+                if s.validate_body && s.max_body_bytes == 0 {
+                    return Err("schema-validate filter: max-body-bytes must be > 0".into());
+                }
+                if !s.validate_body && !s.validate_parameters {
+                    return Err(
+                        "schema-validate filter: validate-body and validate-parameters are both disabled"
+                            .into(),
+                    );
+                }
+                if s.serve_spec.as_ref().is_some_and(|p| !p.starts_with('/')) {
+                    return Err("schema-validate filter: serve-spec must start with '/'".into());
+                }
+            }
+            Filter::ApiKey(a) => {
+                if a.key_file.is_empty() {
+                    return Err("api-key filter requires 'key-file'".into());
+                }
+                if a.header.is_none() && a.query_param.is_none() {
+                    return Err(
+                        "api-key filter requires a 'header' or 'query-param' to read the key from"
+                            .into(),
+                    );
+                }
+            }
+            Filter::ResponsePolicy(r) => {
+                if r.max_body_bytes == Some(0) {
+                    return Err("response-policy filter: max-body-bytes must be > 0".into());
+                }
+                if r.max_body_bytes.is_none()
+                    && r.allowed_content_types.is_empty()
+                    && r.strip_headers.is_empty()
+                {
+                    return Err("response-policy filter requires at least one of \
+                                'max-body-bytes', 'allowed-content-types' or 'strip-headers'"
+                        .into());
+                }
+                if let Some(ct) = r
+                    .allowed_content_types
+                    .iter()
+                    .find(|ct| ct.is_empty() || !ct.contains('/'))
+                {
+                    return Err(format!(
+                        "response-policy filter: invalid content type '{}' (expected 'type/subtype' or 'type/*')",
+                        ct
+                    ));
+                }
+            }
+            Filter::ManagedRules(m) => {
+                if m.rules.is_empty() {
+                    return Err("managed-rules filter requires 'rules'".into());
+                }
+                if m.anomaly_threshold == 0 {
+                    return Err("managed-rules filter: anomaly-threshold must be > 0".into());
+                }
+                if m.inspect_body && m.max_body_bytes == 0 {
+                    return Err("managed-rules filter: max-body-bytes must be > 0".into());
+                }
+            }
+            Filter::IpAccess(i) => {
+                if i.cidrs.is_empty() && i.sources.is_empty() {
+                    return Err("ip-access filter requires 'cidrs' or 'sources'".into());
+                }
+                if let Some(cidr) = i.cidrs.iter().find(|c| !is_valid_cidr(c)) {
+                    return Err(format!(
+                        "ip-access filter: invalid CIDR '{}' (expected e.g. '10.0.0.0/8' or '2001:db8::1')",
+                        cidr
+                    ));
+                }
+                if i.sources.iter().any(|s| s.is_empty()) {
+                    return Err("ip-access filter: empty source".into());
+                }
+                if !i.sources.is_empty() && i.refresh_secs == 0 {
+                    return Err("ip-access filter: refresh-secs must be > 0".into());
+                }
+                if !(400..=599).contains(&i.status_code) {
+                    return Err(format!(
+                        "ip-access filter: status-code must be 400-599, got {}",
+                        i.status_code
+                    ));
+                }
+            }
+            Filter::AdaptiveProtection(a) => {
+                if a.window_secs == 0 {
+                    return Err("adaptive-protection filter: window-secs must be > 0".into());
+                }
+                if a.penalty_secs == 0 {
+                    return Err("adaptive-protection filter: penalty-secs must be > 0".into());
+                }
+                if a.max_clients == 0 {
+                    return Err("adaptive-protection filter: max-clients must be > 0".into());
+                }
+                if a.rate_threshold == 0 {
+                    return Err("adaptive-protection filter: rate-threshold must be > 0".into());
+                }
+                if a.error_ratio_threshold <= 0.0 || a.error_ratio_threshold > 1.0 {
+                    return Err(
+                        "adaptive-protection filter: error-ratio-threshold must be in (0, 1]"
+                            .into(),
+                    );
+                }
+                if a.path_entropy_threshold <= 0.0 {
+                    return Err(
+                        "adaptive-protection filter: path-entropy-threshold must be > 0".into(),
+                    );
+                }
+                let weights = [a.rate_weight, a.error_weight, a.entropy_weight];
+                if weights.iter().any(|w| *w < 0.0) {
+                    return Err("adaptive-protection filter: weights must be >= 0".into());
+                }
+                if a.score_threshold <= 0.0 {
+                    return Err("adaptive-protection filter: score-threshold must be > 0".into());
+                }
+                if a.action == AdaptiveAction::Challenge && a.challenge_type.is_empty() {
+                    return Err(
+                        "adaptive-protection filter: challenge-type is required for action 'challenge'"
+                            .into(),
+                    );
+                }
+                if !(400..=599).contains(&a.status_code) {
+                    return Err(format!(
+                        "adaptive-protection filter: status-code must be 400-599, got {}",
+                        a.status_code
+                    ));
+                }
+            }
+            Filter::Quota(q) => {
+                let limits = q.limits();
+                if limits.iter().all(Option::is_none) {
+                    return Err("quota filter requires at least one of requests-per-day, \
+                                requests-per-month, bytes-per-day or bytes-per-month"
+                        .into());
+                }
+                if limits.contains(&Some(0)) {
+                    return Err("quota filter: limits must be > 0".into());
+                }
+                if let Some(webhook) = &q.webhook {
+                    if !webhook.starts_with("http://") && !webhook.starts_with("https://") {
+                        return Err(format!(
+                            "quota filter: webhook must be an http(s) URL, got '{}'",
+                            webhook
+                        ));
+                    }
+                }
+                if !(400..=599).contains(&q.status_code) {
+                    return Err(format!(
+                        "quota filter: status-code must be 400-599, got {}",
+                        q.status_code
+                    ));
+                }
+            }
+            Filter::PromptTemplate(p) => {
+                if p.system_prompt.is_none()
+                    && p.client_system_prompt == ClientSystemPrompt::Keep
+                    && p.templates.is_empty()
+                {
+                    return Err("prompt-template filter requires system-prompt, \
+                                client-system-prompt \"strip\" or \"reject\", or at least one template"
+                        .into());
+                }
+                if p.system_prompt.as_deref().is_some_and(str::is_empty) {
+                    return Err("prompt-template filter: system-prompt is empty".into());
+                }
+                let mut ids = HashSet::new();
+                for template in &p.templates {
+                    if !ids.insert(template.id.as_str()) {
+                        return Err(format!(
+                            "prompt-template filter: duplicate template '{}'",
+                            template.id
+                        ));
+                    }
+                    if let Err(e) = template.literals() {
+                        return Err(format!(
+                            "prompt-template filter: template '{}': {}",
+                            template.id, e
+                        ));
+                    }
+                }
+                if p.max_body_bytes == 0 {
+                    return Err("prompt-template filter: max-body-bytes must be > 0".into());
+                }
+                if !(400..=599).contains(&p.status_code) {
+                    return Err(format!(
+                        "prompt-template filter: status-code must be 400-599, got {}",
+                        p.status_code
+                    ));
+                }
+            }
+            Filter::Multipart(m) => {
+                if m.max_part_bytes == 0 || m.max_total_bytes == 0 || m.max_parts == 0 {
+                    return Err("multipart filter: limits must be > 0".into());
+                }
+                if m.max_part_bytes > m.max_total_bytes {
+                    return Err(format!(
+                        "multipart filter: max-part-bytes ({}) exceeds max-total-bytes ({})",
+                        m.max_part_bytes, m.max_total_bytes
+                    ));
+                }
+            }
+            Filter::Icap(i) => {
+                if i.reqmod.is_none() && i.respmod.is_none() {
+                    return Err("icap filter requires reqmod or respmod".into());
+                }
+                for service in [&i.reqmod, &i.respmod].into_iter().flatten() {
+                    if let Err(e) = parse_icap_url(service) {
+                        return Err(format!("icap filter: {}", e));
+                    }
+                }
+                if i.preview_bytes == Some(0) {
+                    return Err("icap filter: preview-bytes must be > 0".into());
+                }
+                if i.max_body_bytes == 0 || i.timeout_ms == 0 {
+                    return Err("icap filter: max-body-bytes and timeout-ms must be > 0".into());
+                }
+                if !(400..=599).contains(&i.status_code) {
+                    return Err(format!(
+                        "icap filter: status-code must be 400-599, got {}",
+                        i.status_code
+                    ));
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -221,6 +557,7 @@ impl Filter {
 
 /// Rate limiting configuration using token bucket algorithm
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RateLimitFilter {
     /// Maximum requests per second
     #[serde(rename = "max-rps")]
@@ -277,6 +614,7 @@ pub(crate) fn default_max_keys() -> usize {
 
 /// Global rate limit configuration applied server-wide
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GlobalRateLimitConfig {
     /// Default requests per second for routes without explicit rate limiting
     #[serde(default, rename = "default-rps")]
@@ -297,6 +635,7 @@ pub struct GlobalRateLimitConfig {
 
 /// Global rate limit that applies to all requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GlobalLimitConfig {
     /// Maximum requests per second globally
     #[serde(rename = "max-rps")]
@@ -313,6 +652,7 @@ pub struct GlobalLimitConfig {
 
 /// Backend storage for rate limit state
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum RateLimitBackend {
     /// Local in-memory storage (single-instance only)
@@ -326,6 +666,7 @@ pub enum RateLimitBackend {
 
 /// Redis backend configuration for distributed rate limiting
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RedisBackendConfig {
     /// Redis connection URL (e.g., "redis://127.0.0.1:6379")
     pub url: String,
@@ -373,6 +714,7 @@ fn default_redis_timeout_ms() -> u64 {
 
 /// Memcached backend configuration for distributed rate limiting
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MemcachedBackendConfig {
     /// Memcached server URL (e.g., "memcache://127.0.0.1:11211")
     pub url: String,
@@ -437,6 +779,7 @@ fn default_limit_status() -> u16 {
 
 /// Key for rate limit bucketing
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum RateLimitKey {
     /// Rate limit by client IP address
@@ -454,6 +797,7 @@ pub enum RateLimitKey {
 
 /// Action to take when rate limit is exceeded
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum RateLimitAction {
     /// Reject the request with 429 status
@@ -471,6 +815,7 @@ pub enum RateLimitAction {
 
 /// Header manipulation filter
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HeadersFilter {
     /// Phase to apply header modifications
     #[serde(default)]
@@ -499,6 +844,7 @@ pub struct HeadersFilter {
 
 /// Response compression filter
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CompressFilter {
     /// Compression algorithms in preference order
     #[serde(default = "default_algorithms")]
@@ -512,9 +858,26 @@ pub struct CompressFilter {
     #[serde(default = "default_content_types", rename = "content-types")]
     pub content_types: Vec<String>,
 
-    /// Compression level (1-9, algorithm-specific)
+    /// Compression level, clamped to the algorithm's range (gzip and
+    /// deflate 1-9, brotli 0-11, zstd 1-22)
     #[serde(default = "default_compression_level")]
     pub level: u8,
+
+    /// Levels for specific content types, keyed by MIME type prefix
+    #[serde(default)]
+    pub levels: HashMap<String, u8>,
+}
+
+impl CompressFilter {
+    /// Level for a response content type: the longest matching `levels`
+    /// prefix, or `level`
+    pub fn level_for(&self, content_type: &str) -> u8 {
+        self.levels
+            .iter()
+            .filter(|(prefix, _)| content_type.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
 }
 
 impl Default for CompressFilter {
@@ -524,6 +887,7 @@ impl Default for CompressFilter {
             min_size: default_min_size(),
             content_types: default_content_types(),
             level: default_compression_level(),
+            levels: HashMap::new(),
         }
     }
 }
@@ -555,6 +919,7 @@ fn default_compression_level() -> u8 {
 
 /// Compression algorithm
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum CompressionAlgorithm {
     Gzip,
@@ -569,6 +934,7 @@ pub enum CompressionAlgorithm {
 
 /// CORS (Cross-Origin Resource Sharing) filter
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CorsFilter {
     /// Allowed origins (use "*" for any)
     #[serde(default, rename = "allowed-origins")]
@@ -630,6 +996,7 @@ fn default_cors_max_age() -> u64 {
 
 /// Timeout override filter
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TimeoutFilter {
     /// Request timeout override (seconds)
     #[serde(rename = "request-timeout-secs")]
@@ -650,6 +1017,7 @@ pub struct TimeoutFilter {
 
 /// Request/response logging filter
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LogFilter {
     /// Log request details
     #[serde(default = "default_true", rename = "log-request")]
@@ -707,6 +1075,7 @@ fn default_log_level() -> String {
 
 /// GeoIP database type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum GeoDatabaseType {
     /// MaxMind GeoLite2/GeoIP2 database (.mmdb format)
@@ -717,6 +1086,7 @@ pub enum GeoDatabaseType {
 
 /// Action to take based on geo filter result
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum GeoFilterAction {
     /// Block requests from matching countries (blocklist mode)
@@ -730,6 +1100,7 @@ pub enum GeoFilterAction {
 
 /// Behavior when geo lookup fails
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum GeoFailureMode {
     /// Allow request on lookup failure (fail-open)
@@ -741,6 +1112,7 @@ pub enum GeoFailureMode {
 
 /// GeoIP filtering configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GeoFilter {
     /// Path to GeoIP database file (.mmdb or .bin)
     #[serde(rename = "database-path")]
@@ -809,6 +1181,7 @@ fn default_geo_cache_ttl() -> u64 {
 
 /// External agent filter - references an agent defined in the agents section
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AgentFilter {
     /// Agent ID (must match an agent in the agents configuration)
     pub agent: String,
@@ -832,6 +1205,11 @@ pub struct AgentFilter {
     /// Maximum request body bytes to send to agent
     #[serde(rename = "max-body-bytes")]
     pub max_body_bytes: Option<usize>,
+
+    /// Decision priority for the route's `priority-override` merge strategy
+    /// (higher wins)
+    #[serde(default)]
+    pub priority: i32,
 }
 
 impl AgentFilter {
@@ -844,6 +1222,7 @@ impl AgentFilter {
             failure_mode: None,
             inspect_body: false,
             max_body_bytes: None,
+            priority: 0,
         }
     }
 
@@ -1091,6 +1470,311 @@ mod tests {
         assert_eq!(config.filter_type(), "geo");
         assert_eq!(config.phase(), FilterPhase::Request);
     }
+
+    #[test]
+    fn test_wasm_filter_phase() {
+        let mut wasm = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+        assert_eq!(Filter::Wasm(wasm.clone()).phase(), FilterPhase::Request);
+
+        wasm.events = vec![AgentEvent::ResponseHeaders];
+        assert_eq!(Filter::Wasm(wasm.clone()).phase(), FilterPhase::Response);
+
+        wasm.events.push(AgentEvent::RequestBody);
+        assert_eq!(Filter::Wasm(wasm).phase(), FilterPhase::Both);
+    }
+
+    #[test]
+    fn test_wasm_filter_validation() {
+        let valid = Filter::Wasm(WasmFilter::new("/etc/zentinel/filters/tag.wasm"));
+        assert!(valid.validate(&[]).is_ok());
+
+        let mut wasm = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+        wasm.events = vec![AgentEvent::ResponseBody];
+        let result = Filter::Wasm(wasm).validate(&[]);
+        assert!(result.unwrap_err().contains("unsupported event"));
+
+        let mut wasm = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+        wasm.max_fuel = 0;
+        assert!(Filter::Wasm(wasm).validate(&[]).is_err());
+
+        let mut wasm = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+        wasm.config = Some("{not json".to_string());
+        let result = Filter::Wasm(wasm).validate(&[]);
+        assert!(result.unwrap_err().contains("valid JSON"));
+    }
+
+    #[test]
+    fn test_json_transform_filter_validation() {
+        let path = |p: &str| JsonPath::parse(p).unwrap();
+
+        let empty = Filter::JsonTransform(JsonTransformFilter::default());
+        assert!(empty.validate(&[]).unwrap_err().contains("at least one"));
+
+        let mut json = JsonTransformFilter::default();
+        json.redact.push(path("$..password"));
+        json.set.push(JsonSetField {
+            path: path("$.meta.source"),
+            value: serde_json::json!("zentinel"),
+        });
+        assert!(Filter::JsonTransform(json.clone()).validate(&[]).is_ok());
+
+        json.inject.push(JsonInjectField {
+            path: path("$..request_id"),
+            value: JsonComputedValue::CorrelationId,
+        });
+        let result = Filter::JsonTransform(json).validate(&[]);
+        assert!(result.unwrap_err().contains("cannot use '..'"));
+
+        let mut json = JsonTransformFilter::default();
+        json.rename.push(JsonRename {
+            path: path("$.items[0]"),
+            to: "first".to_string(),
+        });
+        let result = Filter::JsonTransform(json).validate(&[]);
+        assert!(result.unwrap_err().contains("must end in a field name"));
+    }
+
+    #[test]
+    fn test_schema_validate_filter_validation() {
+        let valid = Filter::SchemaValidate(SchemaValidateFilter::new("/etc/zentinel/api.yaml"));
+        assert_eq!(valid.phase(), FilterPhase::Request);
+        assert!(valid.validate(&[]).is_ok());
+
+        let result = Filter::SchemaValidate(SchemaValidateFilter::new("")).validate(&[]);
+        assert!(result.unwrap_err().contains("requires 'schema'"));
+
+        let mut schema = SchemaValidateFilter::new("/etc/zentinel/api.yaml");
+        schema.validate_body = false;
+        schema.validate_parameters = false;
+        let result = Filter::SchemaValidate(schema).validate(&[]);
+        assert!(result.unwrap_err().contains("both disabled"));
+
+        let mut schema = SchemaValidateFilter::new("/etc/zentinel/api.yaml");
+        schema.serve_spec = Some("openapi.json".to_string());
+        let result = Filter::SchemaValidate(schema).validate(&[]);
+        assert!(result.unwrap_err().contains("serve-spec"));
+    }
+
+    #[test]
+    fn test_managed_rules_filter_validation() {
+        let valid = Filter::ManagedRules(ManagedRulesFilter::new("/etc/zentinel/rules.conf"));
+        assert_eq!(valid.phase(), FilterPhase::Request);
+        assert_eq!(valid.type_name(), "managed-rules");
+        assert!(valid.validate(&[]).is_ok());
+
+        let result = Filter::ManagedRules(ManagedRulesFilter::new("")).validate(&[]);
+        assert!(result.unwrap_err().contains("requires 'rules'"));
+
+        let mut rules = ManagedRulesFilter::new("/etc/zentinel/rules.conf");
+        rules.anomaly_threshold = 0;
+        let result = Filter::ManagedRules(rules).validate(&[]);
+        assert!(result.unwrap_err().contains("anomaly-threshold"));
+    }
+
+    #[test]
+    fn test_ip_access_filter_validation() {
+        let mut filter = IpAccessFilter {
+            cidrs: vec!["203.0.113.0/24".into(), "2001:db8::1".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            Filter::IpAccess(filter.clone()).phase(),
+            FilterPhase::Request
+        );
+        assert!(Filter::IpAccess(filter.clone()).validate(&[]).is_ok());
+
+        filter.cidrs.push("10.0.0.0/33".into());
+        let result = Filter::IpAccess(filter).validate(&[]);
+        assert!(result.unwrap_err().contains("invalid CIDR '10.0.0.0/33'"));
+
+        let result = Filter::IpAccess(IpAccessFilter::default()).validate(&[]);
+        assert!(result.unwrap_err().contains("'cidrs' or 'sources'"));
+
+        assert!(IpAccessFilter::is_url_source(
+            "https://lists.example.com/drop.txt"
+        ));
+        assert!(!IpAccessFilter::is_url_source("/etc/zentinel/denylist.txt"));
+    }
+
+    #[test]
+    fn test_adaptive_protection_filter_validation() {
+        let filter = AdaptiveProtectionFilter::default();
+        assert_eq!(
+            Filter::AdaptiveProtection(filter.clone()).phase(),
+            FilterPhase::Both
+        );
+        assert!(Filter::AdaptiveProtection(filter.clone())
+            .validate(&[])
+            .is_ok());
+
+        let result = Filter::AdaptiveProtection(AdaptiveProtectionFilter {
+            error_ratio_threshold: 1.5,
+            ..filter.clone()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("error-ratio-threshold"));
+
+        let result = Filter::AdaptiveProtection(AdaptiveProtectionFilter {
+            action: AdaptiveAction::Challenge,
+            challenge_type: String::new(),
+            ..filter
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("challenge-type"));
+    }
+
+    #[test]
+    fn test_quota_filter_validation() {
+        let filter = QuotaFilter {
+            requests_per_day: Some(10_000),
+            ..Default::default()
+        };
+        assert!(Filter::Quota(filter.clone()).validate(&[]).is_ok());
+
+        let result = Filter::Quota(QuotaFilter::default()).validate(&[]);
+        assert!(result.unwrap_err().contains("at least one"));
+
+        let result = Filter::Quota(QuotaFilter {
+            bytes_per_month: Some(0),
+            ..filter.clone()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("must be > 0"));
+
+        let result = Filter::Quota(QuotaFilter {
+            webhook: Some("hooks.example.com".into()),
+            ..filter
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("webhook"));
+    }
+
+    #[test]
+    fn test_prompt_template_filter_validation() {
+        let template = |id: &str, template: &str| PromptTemplate {
+            id: id.to_string(),
+            template: template.to_string(),
+        };
+        let filter = PromptTemplateFilter {
+            templates: vec![template("summarize", "Summarize: {{ text }}")],
+            ..Default::default()
+        };
+        assert!(Filter::PromptTemplate(filter.clone()).validate(&[]).is_ok());
+        assert_eq!(
+            filter.templates[0].literals().unwrap(),
+            vec!["Summarize: ", ""]
+        );
+        assert!(!filter.rewrites_body());
+
+        let result = Filter::PromptTemplate(PromptTemplateFilter::default()).validate(&[]);
+        assert!(result.unwrap_err().contains("requires"));
+
+        let result = Filter::PromptTemplate(PromptTemplateFilter {
+            templates: vec![template("broken", "Summarize: {{text")],
+            ..Default::default()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("unterminated"));
+
+        let result = Filter::PromptTemplate(PromptTemplateFilter {
+            templates: vec![template("a", "x"), template("a", "y")],
+            ..Default::default()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("duplicate"));
+    }
+
+    #[test]
+    fn test_multipart_filter_validation() {
+        let filter = MultipartFilter::default();
+        assert!(Filter::Multipart(filter.clone()).validate(&[]).is_ok());
+
+        let result = Filter::Multipart(MultipartFilter {
+            max_parts: 0,
+            ..filter.clone()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("must be > 0"));
+
+        let result = Filter::Multipart(MultipartFilter {
+            max_part_bytes: filter.max_total_bytes + 1,
+            ..filter
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("exceeds max-total-bytes"));
+    }
+
+    #[test]
+    fn test_icap_filter_validation() {
+        let filter = IcapFilter {
+            reqmod: Some("icap://clamav.internal/avscan".to_string()),
+            ..Default::default()
+        };
+        let icap = Filter::Icap(filter.clone());
+        assert!(icap.validate(&[]).is_ok());
+        assert_eq!(icap.phase(), FilterPhase::Request);
+        assert_eq!(
+            parse_icap_url("icap://clamav.internal/avscan").unwrap(),
+            ("clamav.internal".to_string(), 1344, "/avscan".to_string())
+        );
+        assert_eq!(
+            parse_icap_url("icap://10.0.0.5:11344").unwrap(),
+            ("10.0.0.5".to_string(), 11344, "/".to_string())
+        );
+
+        let result = Filter::Icap(IcapFilter::default()).validate(&[]);
+        assert!(result.unwrap_err().contains("requires"));
+
+        let result = Filter::Icap(IcapFilter {
+            respmod: Some("http://clamav.internal/avscan".to_string()),
+            ..filter.clone()
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("icap://"));
+
+        let result = Filter::Icap(IcapFilter {
+            preview_bytes: Some(0),
+            ..filter
+        })
+        .validate(&[]);
+        assert!(result.unwrap_err().contains("preview-bytes"));
+    }
+
+    #[test]
+    fn test_api_key_filter_validation() {
+        let valid = Filter::ApiKey(ApiKeyFilter::new("/etc/zentinel/api-keys.json"));
+        assert_eq!(valid.phase(), FilterPhase::Request);
+        assert!(valid.validate(&[]).is_ok());
+
+        let mut api_key = ApiKeyFilter::new("/etc/zentinel/api-keys.json");
+        api_key.header = None;
+        let result = Filter::ApiKey(api_key).validate(&[]);
+        assert!(result.unwrap_err().contains("'header' or 'query-param'"));
+    }
+
+    #[test]
+    fn test_response_policy_filter_validation() {
+        let policy = ResponsePolicyFilter {
+            max_body_bytes: Some(1024),
+            allowed_content_types: vec!["application/json".into(), "text/*".into()],
+            ..Default::default()
+        };
+        let valid = Filter::ResponsePolicy(policy);
+        assert_eq!(valid.phase(), FilterPhase::Response);
+        assert!(valid.validate(&[]).is_ok());
+
+        let empty = Filter::ResponsePolicy(ResponsePolicyFilter::default());
+        assert!(empty.validate(&[]).unwrap_err().contains("at least one"));
+
+        let invalid = Filter::ResponsePolicy(ResponsePolicyFilter {
+            allowed_content_types: vec!["json".into()],
+            ..Default::default()
+        });
+        assert!(invalid
+            .validate(&[])
+            .unwrap_err()
+            .contains("invalid content type 'json'"));
+    }
 }
 
 // =============================================================================
@@ -1101,6 +1785,7 @@ mod tests {
 ///
 /// Used to implement Gateway API's `RequestRedirect` filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RedirectFilter {
     /// Hostname to use in the `Location` header.
     /// When empty, the hostname from the request is preserved.
@@ -1138,6 +1823,7 @@ fn default_redirect_status() -> u16 {
 ///
 /// Used to implement Gateway API's `URLRewrite` filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UrlRewriteFilter {
     /// Hostname to set on the request's `Host` header before forwarding.
     #[serde(default)]
@@ -1154,6 +1840,7 @@ pub struct UrlRewriteFilter {
 
 /// Defines how to modify a request path for redirects or rewrites.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum PathModifier {
     /// Replace the full request path with the given value.
@@ -1167,3 +1854,1150 @@ pub enum PathModifier {
         value: String,
     },
 }
+
+// =============================================================================
+// WASM Filter
+// =============================================================================
+
+/// Runs a WebAssembly component in-process for small request/response
+/// transformations that do not justify an external agent.
+///
+/// The component implements the same `zentinel:agent` interface as WASM
+/// agents and sees the same events. Every call is bounded by `max-fuel`
+/// and the instance's linear memory by `max-memory-mb`. Requires the
+/// proxy's `wasm-filters` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WasmFilter {
+    /// Path to the compiled WASM component
+    pub module: String,
+
+    /// Configuration passed to the component's `configure` (JSON)
+    #[serde(default)]
+    pub config: Option<String>,
+
+    /// Events delivered to the component
+    #[serde(default = "default_wasm_events")]
+    pub events: Vec<AgentEvent>,
+
+    /// Maximum linear memory per instance (MB)
+    #[serde(default = "default_wasm_max_memory_mb", rename = "max-memory-mb")]
+    pub max_memory_mb: usize,
+
+    /// Maximum fuel (instructions) per call
+    #[serde(default = "default_wasm_max_fuel", rename = "max-fuel")]
+    pub max_fuel: u64,
+
+    /// Number of instances, bounding concurrent calls
+    #[serde(default = "default_wasm_instances")]
+    pub instances: usize,
+
+    /// Behavior when the component traps or runs out of fuel
+    #[serde(default, rename = "failure-mode")]
+    pub failure_mode: FailureMode,
+}
+
+impl WasmFilter {
+    /// Events a WASM filter can handle.
+    ///
+    /// The component interface has no body mutation, and response bodies
+    /// are streamed after the headers are sent, so response body chunks
+    /// could neither be changed nor blocked.
+    pub const SUPPORTED_EVENTS: &'static [AgentEvent] = &[
+        AgentEvent::RequestHeaders,
+        AgentEvent::RequestBody,
+        AgentEvent::ResponseHeaders,
+    ];
+
+    /// Create a WASM filter for a component with default limits
+    pub fn new(module: impl Into<String>) -> Self {
+        Self {
+            module: module.into(),
+            config: None,
+            events: default_wasm_events(),
+            max_memory_mb: default_wasm_max_memory_mb(),
+            max_fuel: default_wasm_max_fuel(),
+            instances: default_wasm_instances(),
+            failure_mode: FailureMode::Closed,
+        }
+    }
+
+    /// Check if the component receives this event
+    pub fn handles(&self, event: AgentEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+fn default_wasm_events() -> Vec<AgentEvent> {
+    vec![AgentEvent::RequestHeaders]
+}
+
+fn default_wasm_max_memory_mb() -> usize {
+    16
+}
+
+fn default_wasm_max_fuel() -> u64 {
+    1_000_000
+}
+
+fn default_wasm_instances() -> usize {
+    4
+}
+
+// =============================================================================
+// JSON Transform Filter
+// =============================================================================
+
+/// Manipulates fields of JSON request or response bodies without an agent.
+///
+/// Operations run in a fixed order: rename, remove, redact, set, inject.
+/// Bodies are buffered up to `max-body-bytes`; larger bodies, bodies that
+/// are not valid JSON and non-JSON content types pass through unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonTransformFilter {
+    /// Phase whose body is transformed
+    #[serde(default)]
+    pub phase: FilterPhase,
+
+    /// Fields to rename (the last path segment is renamed in place)
+    #[serde(default)]
+    pub rename: Vec<JsonRename>,
+
+    /// Fields or elements to remove
+    #[serde(default)]
+    pub remove: Vec<JsonPath>,
+
+    /// Values to replace with `redaction`
+    #[serde(default)]
+    pub redact: Vec<JsonPath>,
+
+    /// Replacement for redacted values
+    #[serde(default = "default_json_redaction")]
+    pub redaction: String,
+
+    /// Fields to set to a fixed value (missing parents are created)
+    #[serde(default)]
+    pub set: Vec<JsonSetField>,
+
+    /// Fields to set to a value computed per request
+    #[serde(default)]
+    pub inject: Vec<JsonInjectField>,
+
+    /// Largest body that is buffered and transformed
+    #[serde(
+        default = "default_json_transform_max_body_bytes",
+        rename = "max-body-bytes"
+    )]
+    pub max_body_bytes: usize,
+}
+
+/// Rename of a JSON field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonRename {
+    /// Field to rename
+    pub path: JsonPath,
+    /// New field name
+    pub to: String,
+}
+
+/// Fixed value for a JSON field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonSetField {
+    /// Field to set
+    pub path: JsonPath,
+    /// Value to set
+    pub value: serde_json::Value,
+}
+
+/// Computed value for a JSON field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonInjectField {
+    /// Field to set
+    pub path: JsonPath,
+    /// Value to compute
+    pub value: JsonComputedValue,
+}
+
+/// Per-request values the JSON transform filter can inject
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonComputedValue {
+    /// Current time (RFC 3339)
+    Timestamp,
+    /// Current time in milliseconds since the Unix epoch
+    TimestampMs,
+    /// Request correlation ID
+    CorrelationId,
+    /// Matched route ID
+    RouteId,
+    /// Client IP address
+    ClientIp,
+}
+
+impl JsonComputedValue {
+    /// Parse the KDL name of a computed value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "timestamp" => Some(Self::Timestamp),
+            "timestamp-ms" => Some(Self::TimestampMs),
+            "correlation-id" => Some(Self::CorrelationId),
+            "route-id" => Some(Self::RouteId),
+            "client-ip" => Some(Self::ClientIp),
+            _ => None,
+        }
+    }
+}
+
+impl Default for JsonTransformFilter {
+    fn default() -> Self {
+        Self {
+            phase: FilterPhase::Request,
+            rename: Vec::new(),
+            remove: Vec::new(),
+            redact: Vec::new(),
+            redaction: default_json_redaction(),
+            set: Vec::new(),
+            inject: Vec::new(),
+            max_body_bytes: default_json_transform_max_body_bytes(),
+        }
+    }
+}
+
+impl JsonTransformFilter {
+    /// Check if the filter has no operations
+    pub fn is_empty(&self) -> bool {
+        self.rename.is_empty()
+            && self.remove.is_empty()
+            && self.redact.is_empty()
+            && self.set.is_empty()
+            && self.inject.is_empty()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.is_empty() {
+            return Err("json-transform filter requires at least one operation".into());
+        }
+        if self.max_body_bytes == 0 {
+            return Err("json-transform filter: max-body-bytes must be > 0".into());
+        }
+        for rename in &self.rename {
+            if rename.to.is_empty() {
+                return Err(format!(
+                    "json-transform filter: rename of '{}' needs a new name",
+                    rename.path
+                ));
+            }
+            if !matches!(
+                rename.path.last(),
+                JsonPathSegment::Field(_) | JsonPathSegment::Descendant(_)
+            ) {
+                return Err(format!(
+                    "json-transform filter: rename path '{}' must end in a field name",
+                    rename.path
+                ));
+            }
+        }
+        let written = self
+            .set
+            .iter()
+            .map(|s| &s.path)
+            .chain(self.inject.iter().map(|i| &i.path));
+        for path in written {
+            let descendant = path
+                .segments()
+                .iter()
+                .any(|s| matches!(s, JsonPathSegment::Descendant(_)));
+            if descendant || !matches!(path.last(), JsonPathSegment::Field(_)) {
+                return Err(format!(
+                    "json-transform filter: set/inject path '{}' must end in a field name and cannot use '..'",
+                    path
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_json_redaction() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_json_transform_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+// =============================================================================
+// Schema Validate Filter
+// =============================================================================
+
+/// Validates requests against an OpenAPI 3.1 or JSON Schema document.
+///
+/// The document is loaded when the configuration is applied. For OpenAPI
+/// documents the operation is matched by method and path, and its path,
+/// query and header parameters and JSON request body are checked. A plain
+/// JSON Schema document validates the JSON body of every request. Requests
+/// that fail are answered with 400 and a list of violations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SchemaValidateFilter {
+    /// Path to the OpenAPI (JSON or YAML) or JSON Schema document
+    pub schema: String,
+
+    /// Validate path, query and header parameters
+    #[serde(default = "default_true", rename = "validate-parameters")]
+    pub validate_parameters: bool,
+
+    /// Validate JSON request bodies
+    #[serde(default = "default_true", rename = "validate-body")]
+    pub validate_body: bool,
+
+    /// Reject requests that match no operation of an OpenAPI document
+    #[serde(default, rename = "reject-unknown-operations")]
+    pub reject_unknown_operations: bool,
+
+    /// Largest request body buffered for validation; larger bodies are
+    /// rejected
+    #[serde(
+        default = "default_schema_validate_max_body_bytes",
+        rename = "max-body-bytes"
+    )]
+    pub max_body_bytes: usize,
+
+    /// Request path at which the document is served as JSON, e.g.
+    /// `/openapi.json`
+    #[serde(default, rename = "serve-spec")]
+    pub serve_spec: Option<String>,
+}
+
+impl SchemaValidateFilter {
+    /// Create a filter for a schema document with default settings
+    pub fn new(schema: impl Into<String>) -> Self {
+        Self {
+            schema: schema.into(),
+            validate_parameters: true,
+            validate_body: true,
+            reject_unknown_operations: false,
+            max_body_bytes: default_schema_validate_max_body_bytes(),
+            serve_spec: None,
+        }
+    }
+}
+
+fn default_schema_validate_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+// =============================================================================
+// API Key Filter
+// =============================================================================
+
+/// Authenticates requests with API keys from a local key file.
+///
+/// The key file stores SHA-256 hashes of the keys with per-key metadata
+/// (owner, allowed routes, rate tier) and is reloaded when it changes. The
+/// key's identity is attached to the request as tags.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ApiKeyFilter {
+    /// Path to the key file (JSON or YAML)
+    #[serde(rename = "key-file")]
+    pub key_file: String,
+
+    /// Header carrying the key
+    #[serde(default = "default_api_key_header")]
+    pub header: Option<String>,
+
+    /// Query parameter carrying the key, read when the header is absent
+    #[serde(default, rename = "query-param")]
+    pub query_param: Option<String>,
+
+    /// Remove the key from the request before it is forwarded
+    #[serde(default = "default_true", rename = "strip-key")]
+    pub strip_key: bool,
+}
+
+impl ApiKeyFilter {
+    /// Create a filter reading keys from the `X-API-Key` header
+    pub fn new(key_file: impl Into<String>) -> Self {
+        Self {
+            key_file: key_file.into(),
+            header: default_api_key_header(),
+            query_param: None,
+            strip_key: true,
+        }
+    }
+}
+
+fn default_api_key_header() -> Option<String> {
+    Some("X-API-Key".to_string())
+}
+
+// =============================================================================
+// Managed Rules Filter
+// =============================================================================
+
+/// Evaluates a WAF-style rule bundle in-proxy.
+///
+/// Rules are read from a file in a subset of the ModSecurity `SecRule`
+/// syntax used by the OWASP Core Rule Set. Each matching rule adds its
+/// severity's score to the request's anomaly score; requests reaching
+/// `anomaly-threshold` are blocked with 403 (or only logged in `detect`
+/// mode). A fallback for deployments without a WAF agent, or a cheap first
+/// pass in front of one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ManagedRulesFilter {
+    /// Path to the rule file
+    pub rules: String,
+
+    /// Block or only log requests over the threshold
+    #[serde(default)]
+    pub mode: ManagedRulesMode,
+
+    /// Anomaly score at which a request is blocked
+    #[serde(
+        default = "default_managed_rules_anomaly_threshold",
+        rename = "anomaly-threshold"
+    )]
+    pub anomaly_threshold: u32,
+
+    /// Run body (phase 2) rules against the request body
+    #[serde(default = "default_true", rename = "inspect-body")]
+    pub inspect_body: bool,
+
+    /// Bytes of the request body held back and inspected; the rest is
+    /// passed through uninspected
+    #[serde(
+        default = "default_managed_rules_max_body_bytes",
+        rename = "max-body-bytes"
+    )]
+    pub max_body_bytes: usize,
+
+    /// Rule IDs to skip
+    #[serde(default, rename = "disabled-rules")]
+    pub disabled_rules: Vec<u64>,
+}
+
+impl ManagedRulesFilter {
+    /// Create a blocking filter for a rule file with default settings
+    pub fn new(rules: impl Into<String>) -> Self {
+        Self {
+            rules: rules.into(),
+            mode: ManagedRulesMode::default(),
+            anomaly_threshold: default_managed_rules_anomaly_threshold(),
+            inspect_body: true,
+            max_body_bytes: default_managed_rules_max_body_bytes(),
+            disabled_rules: Vec::new(),
+        }
+    }
+}
+
+/// What a managed-rules filter does with requests over the threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ManagedRulesMode {
+    /// Answer with 403
+    #[default]
+    Block,
+    /// Log and count, but forward the request
+    Detect,
+}
+
+fn default_managed_rules_anomaly_threshold() -> u32 {
+    5
+}
+
+fn default_managed_rules_max_body_bytes() -> usize {
+    128 * 1024
+}
+
+// =============================================================================
+// IP Access Filter
+// =============================================================================
+
+/// Allows or denies requests by client IP.
+///
+/// Addresses come from inline CIDRs and from list sources: local files or
+/// HTTP(S) URLs with one IP or CIDR per line, refreshed every
+/// `refresh-secs`. With `action "deny"` listed clients are blocked; with
+/// `action "allow"` only listed clients get through.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IpAccessFilter {
+    /// What happens to listed clients
+    #[serde(default)]
+    pub action: IpAccessAction,
+
+    /// Inline IPs and CIDRs
+    #[serde(default)]
+    pub cidrs: Vec<String>,
+
+    /// List files and HTTP(S) URLs
+    #[serde(default)]
+    pub sources: Vec<String>,
+
+    /// Interval between source refreshes
+    #[serde(default = "default_ip_access_refresh_secs", rename = "refresh-secs")]
+    pub refresh_secs: u64,
+
+    /// HTTP status code for blocked requests
+    #[serde(default = "default_ip_access_status", rename = "status-code")]
+    pub status_code: u16,
+
+    /// Custom response message for blocked requests
+    #[serde(default, rename = "block-message")]
+    pub block_message: Option<String>,
+}
+
+impl Default for IpAccessFilter {
+    fn default() -> Self {
+        Self {
+            action: IpAccessAction::default(),
+            cidrs: Vec::new(),
+            sources: Vec::new(),
+            refresh_secs: default_ip_access_refresh_secs(),
+            status_code: default_ip_access_status(),
+            block_message: None,
+        }
+    }
+}
+
+impl IpAccessFilter {
+    /// Whether a source is fetched over HTTP(S) rather than read from disk
+    pub fn is_url_source(source: &str) -> bool {
+        source.starts_with("http://") || source.starts_with("https://")
+    }
+}
+
+/// What an ip-access filter does with listed clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum IpAccessAction {
+    /// Block listed clients
+    #[default]
+    Deny,
+    /// Block everyone else
+    Allow,
+}
+
+fn default_ip_access_refresh_secs() -> u64 {
+    300
+}
+
+fn default_ip_access_status() -> u16 {
+    403
+}
+
+/// Whether a string is an IP address or an `address/prefix` CIDR
+fn is_valid_cidr(cidr: &str) -> bool {
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (cidr, None),
+    };
+    let Ok(addr) = addr.parse::<std::net::IpAddr>() else {
+        return false;
+    };
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    prefix.is_none_or(|p| p.parse::<u8>().is_ok_and(|p| p <= max))
+}
+
+// =============================================================================
+// Adaptive Protection Filter
+// =============================================================================
+
+/// Scores clients by behaviour and restrains outliers.
+///
+/// For each client IP the filter tracks, over a sliding `window-secs`
+/// window, the request count, the share of responses with status 400 or
+/// above, and the Shannon entropy of the request paths (scanners spread
+/// requests over many distinct paths). Each signal over its threshold adds
+/// its weight to the client's anomaly score. Once the score reaches
+/// `score-threshold` the client is throttled or challenged for
+/// `penalty-secs`; with `action "log"` the decision is only logged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AdaptiveProtectionFilter {
+    /// What happens to clients over the score threshold
+    #[serde(default)]
+    pub action: AdaptiveAction,
+
+    /// Length of the sliding window
+    #[serde(default = "default_adaptive_window_secs", rename = "window-secs")]
+    pub window_secs: u64,
+
+    /// Requests in the window before a client is scored
+    #[serde(default = "default_adaptive_min_requests", rename = "min-requests")]
+    pub min_requests: u32,
+
+    /// Requests per window considered a high rate
+    #[serde(default = "default_adaptive_rate_threshold", rename = "rate-threshold")]
+    pub rate_threshold: u32,
+
+    /// Share of error responses (status >= 400) considered anomalous
+    #[serde(
+        default = "default_adaptive_error_ratio_threshold",
+        rename = "error-ratio-threshold"
+    )]
+    pub error_ratio_threshold: f64,
+
+    /// Path entropy (bits) considered anomalous
+    #[serde(
+        default = "default_adaptive_path_entropy_threshold",
+        rename = "path-entropy-threshold"
+    )]
+    pub path_entropy_threshold: f64,
+
+    /// Score added by a high request rate
+    #[serde(default = "default_adaptive_rate_weight", rename = "rate-weight")]
+    pub rate_weight: f64,
+
+    /// Score added by a high error ratio
+    #[serde(default = "default_adaptive_signal_weight", rename = "error-weight")]
+    pub error_weight: f64,
+
+    /// Score added by a high path entropy
+    #[serde(default = "default_adaptive_signal_weight", rename = "entropy-weight")]
+    pub entropy_weight: f64,
+
+    /// Score at which a client is penalized
+    #[serde(
+        default = "default_adaptive_score_threshold",
+        rename = "score-threshold"
+    )]
+    pub score_threshold: f64,
+
+    /// How long a penalty lasts
+    #[serde(default = "default_adaptive_penalty_secs", rename = "penalty-secs")]
+    pub penalty_secs: u64,
+
+    /// Requests per second a throttled client may still make
+    #[serde(default = "default_adaptive_throttle_rps", rename = "throttle-rps")]
+    pub throttle_rps: u32,
+
+    /// Challenge served with `action "challenge"` (`cookie` or `js-pow`)
+    #[serde(default = "default_adaptive_challenge_type", rename = "challenge-type")]
+    pub challenge_type: String,
+
+    /// HTTP status code for throttled requests
+    #[serde(default = "default_adaptive_status", rename = "status-code")]
+    pub status_code: u16,
+
+    /// Most clients tracked at once; new clients are not tracked beyond it
+    #[serde(default = "default_adaptive_max_clients", rename = "max-clients")]
+    pub max_clients: usize,
+}
+
+impl Default for AdaptiveProtectionFilter {
+    fn default() -> Self {
+        Self {
+            action: AdaptiveAction::default(),
+            window_secs: default_adaptive_window_secs(),
+            min_requests: default_adaptive_min_requests(),
+            rate_threshold: default_adaptive_rate_threshold(),
+            error_ratio_threshold: default_adaptive_error_ratio_threshold(),
+            path_entropy_threshold: default_adaptive_path_entropy_threshold(),
+            rate_weight: default_adaptive_rate_weight(),
+            error_weight: default_adaptive_signal_weight(),
+            entropy_weight: default_adaptive_signal_weight(),
+            score_threshold: default_adaptive_score_threshold(),
+            penalty_secs: default_adaptive_penalty_secs(),
+            throttle_rps: default_adaptive_throttle_rps(),
+            challenge_type: default_adaptive_challenge_type(),
+            status_code: default_adaptive_status(),
+            max_clients: default_adaptive_max_clients(),
+        }
+    }
+}
+
+/// What an adaptive-protection filter does with outliers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AdaptiveAction {
+    /// Limit the client to `throttle-rps`
+    #[default]
+    Throttle,
+    /// Require the client to solve a challenge
+    Challenge,
+    /// Only log and count the decision
+    Log,
+}
+
+fn default_adaptive_window_secs() -> u64 {
+    60
+}
+
+fn default_adaptive_min_requests() -> u32 {
+    20
+}
+
+fn default_adaptive_rate_threshold() -> u32 {
+    600
+}
+
+fn default_adaptive_error_ratio_threshold() -> f64 {
+    0.5
+}
+
+fn default_adaptive_path_entropy_threshold() -> f64 {
+    5.0
+}
+
+fn default_adaptive_rate_weight() -> f64 {
+    2.0
+}
+
+fn default_adaptive_signal_weight() -> f64 {
+    1.0
+}
+
+fn default_adaptive_score_threshold() -> f64 {
+    2.0
+}
+
+fn default_adaptive_penalty_secs() -> u64 {
+    300
+}
+
+fn default_adaptive_throttle_rps() -> u32 {
+    1
+}
+
+fn default_adaptive_challenge_type() -> String {
+    "js-pow".to_string()
+}
+
+fn default_adaptive_status() -> u16 {
+    429
+}
+
+fn default_adaptive_max_clients() -> usize {
+    100_000
+}
+
+// =============================================================================
+// Quota Filter
+// =============================================================================
+
+/// Request and byte quotas per API consumer over calendar days and months.
+///
+/// Consumers are identified by the API key accepted by an `api-key` filter
+/// or by the `sub` claim of a bearer JWT. Counters live in the
+/// `session-store` (in memory, or in Redis to share them between instances
+/// and keep them across restarts). Days and months are UTC.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct QuotaFilter {
+    /// How consumers are identified
+    #[serde(default)]
+    pub key: QuotaKey,
+
+    /// Requests per UTC day
+    #[serde(default, rename = "requests-per-day")]
+    pub requests_per_day: Option<u64>,
+
+    /// Requests per UTC calendar month
+    #[serde(default, rename = "requests-per-month")]
+    pub requests_per_month: Option<u64>,
+
+    /// Request and response body bytes per UTC day
+    #[serde(default, rename = "bytes-per-day")]
+    pub bytes_per_day: Option<u64>,
+
+    /// Request and response body bytes per UTC calendar month
+    #[serde(default, rename = "bytes-per-month")]
+    pub bytes_per_month: Option<u64>,
+
+    /// Add `X-Quota-Limit`, `X-Quota-Remaining` and `X-Quota-Reset` to responses
+    #[serde(default = "default_true")]
+    pub headers: bool,
+
+    /// URL notified with a POST when a consumer exhausts a quota
+    #[serde(default)]
+    pub webhook: Option<String>,
+
+    /// HTTP status code for requests over quota
+    #[serde(default = "default_quota_status", rename = "status-code")]
+    pub status_code: u16,
+}
+
+impl Default for QuotaFilter {
+    fn default() -> Self {
+        Self {
+            key: QuotaKey::default(),
+            requests_per_day: None,
+            requests_per_month: None,
+            bytes_per_day: None,
+            bytes_per_month: None,
+            headers: true,
+            webhook: None,
+            status_code: default_quota_status(),
+        }
+    }
+}
+
+impl QuotaFilter {
+    /// Configured limits: requests per day and month, then bytes per day and month
+    pub fn limits(&self) -> [Option<u64>; 4] {
+        [
+            self.requests_per_day,
+            self.requests_per_month,
+            self.bytes_per_day,
+            self.bytes_per_month,
+        ]
+    }
+}
+
+/// How a quota filter identifies consumers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum QuotaKey {
+    /// ID of the key accepted by the route's api-key filter
+    #[default]
+    #[serde(rename = "api-key")]
+    ApiKey,
+    /// `sub` claim of the `Authorization: Bearer` JWT
+    #[serde(rename = "jwt-sub")]
+    JwtSubject,
+}
+
+fn default_quota_status() -> u16 {
+    429
+}
+
+// =============================================================================
+// Prompt Template Filter
+// =============================================================================
+
+/// Enforces a server-side system prompt and a set of allowed prompt
+/// templates on inference requests.
+///
+/// The JSON request body (OpenAI-style `messages` or Anthropic-style
+/// `system` plus `messages`) is buffered. The configured system prompt is
+/// placed before any other; client system prompts are kept, stripped or
+/// rejected. With templates configured, every user message must match one
+/// of them, where `{{name}}` placeholders match any text. Violations are
+/// logged with the point where the message diverges from the closest
+/// template, and either block the request or are only logged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PromptTemplateFilter {
+    /// System prompt placed first in every request
+    #[serde(default, rename = "system-prompt")]
+    pub system_prompt: Option<String>,
+
+    /// What happens to system prompts sent by the client
+    #[serde(default, rename = "client-system-prompt")]
+    pub client_system_prompt: ClientSystemPrompt,
+
+    /// Templates user messages must match; empty allows any message
+    #[serde(default)]
+    pub templates: Vec<PromptTemplate>,
+
+    /// What happens to requests that violate the policy
+    #[serde(default, rename = "on-violation")]
+    pub on_violation: PromptViolationAction,
+
+    /// HTTP status code for blocked requests
+    #[serde(default = "default_prompt_template_status", rename = "status-code")]
+    pub status_code: u16,
+
+    /// Largest request body buffered for enforcement
+    #[serde(
+        default = "default_prompt_template_max_body_bytes",
+        rename = "max-body-bytes"
+    )]
+    pub max_body_bytes: usize,
+}
+
+impl Default for PromptTemplateFilter {
+    fn default() -> Self {
+        Self {
+            system_prompt: None,
+            client_system_prompt: ClientSystemPrompt::default(),
+            templates: Vec::new(),
+            on_violation: PromptViolationAction::default(),
+            status_code: default_prompt_template_status(),
+            max_body_bytes: default_prompt_template_max_body_bytes(),
+        }
+    }
+}
+
+impl PromptTemplateFilter {
+    /// Whether the filter may change the request body
+    pub fn rewrites_body(&self) -> bool {
+        self.system_prompt.is_some() || self.client_system_prompt == ClientSystemPrompt::Strip
+    }
+}
+
+/// Allowed shape of a user message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PromptTemplate {
+    /// Template name, reported in violations
+    pub id: String,
+    /// Text with `{{name}}` placeholders
+    pub template: String,
+}
+
+impl PromptTemplate {
+    /// Literal text around the placeholders: the prefix, the text between
+    /// consecutive placeholders, and the suffix (one more than placeholders)
+    pub fn literals(&self) -> Result<Vec<&str>, String> {
+        let mut literals = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                return Err(format!("unterminated placeholder at '{}'", &rest[start..]));
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            if !name
+                .trim()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(format!("invalid placeholder name '{}'", name));
+            }
+            literals.push(&rest[..start]);
+            rest = &rest[start + 2 + len + 2..];
+        }
+        literals.push(rest);
+        Ok(literals)
+    }
+}
+
+/// Handling of system prompts sent by the client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ClientSystemPrompt {
+    /// Keep them after the configured system prompt
+    #[default]
+    Keep,
+    /// Remove them
+    Strip,
+    /// Treat them as a violation
+    Reject,
+}
+
+/// Handling of requests that violate a prompt-template filter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum PromptViolationAction {
+    /// Reject the request
+    #[default]
+    Block,
+    /// Log the violation and forward the request
+    Log,
+}
+
+fn default_prompt_template_status() -> u16 {
+    400
+}
+
+fn default_prompt_template_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+// =============================================================================
+// Multipart Filter
+// =============================================================================
+
+/// Parses multipart/form-data request bodies so agents can inspect uploads
+/// part by part.
+///
+/// Each part's field name, filename and content type are sent to the
+/// route's agents with the part's body, streamed as request body chunk
+/// events. Agents can block the request, drop a part or replace its data;
+/// the body is re-encoded before it goes upstream. Parts and bodies over
+/// the limits are rejected with 413.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MultipartFilter {
+    /// Largest body of a single part
+    #[serde(
+        default = "default_multipart_max_part_bytes",
+        rename = "max-part-bytes"
+    )]
+    pub max_part_bytes: usize,
+
+    /// Largest multipart body
+    #[serde(
+        default = "default_multipart_max_total_bytes",
+        rename = "max-total-bytes"
+    )]
+    pub max_total_bytes: usize,
+
+    /// Largest number of parts
+    #[serde(default = "default_multipart_max_parts", rename = "max-parts")]
+    pub max_parts: usize,
+}
+
+impl Default for MultipartFilter {
+    fn default() -> Self {
+        Self {
+            max_part_bytes: default_multipart_max_part_bytes(),
+            max_total_bytes: default_multipart_max_total_bytes(),
+            max_parts: default_multipart_max_parts(),
+        }
+    }
+}
+
+fn default_multipart_max_part_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_multipart_max_total_bytes() -> usize {
+    50 * 1024 * 1024
+}
+
+fn default_multipart_max_parts() -> usize {
+    100
+}
+
+// =============================================================================
+// ICAP Filter
+// =============================================================================
+
+/// Scans request and response bodies on an ICAP server (RFC 3507), e.g.
+/// c-icap with ClamAV.
+///
+/// Bodies are held back and streamed to the server's REQMOD or RESPMOD
+/// service; they are released once the server answers 204 (no
+/// modification), which it may do after the preview. Any other answer
+/// blocks the request, or cuts off the response. Unreachable servers,
+/// timeouts and bodies larger than `max-body-bytes` are handled per
+/// `failure-mode`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IcapFilter {
+    /// REQMOD service URL (`icap://host[:port]/service`) for request bodies
+    #[serde(default)]
+    pub reqmod: Option<String>,
+
+    /// RESPMOD service URL for response bodies
+    #[serde(default)]
+    pub respmod: Option<String>,
+
+    /// Preview size; by default the size advertised in the service's
+    /// OPTIONS response
+    #[serde(default, rename = "preview-bytes")]
+    pub preview_bytes: Option<usize>,
+
+    /// Largest body held back for scanning
+    #[serde(default = "default_icap_max_body_bytes", rename = "max-body-bytes")]
+    pub max_body_bytes: usize,
+
+    /// Time limit for a scan, including the connection
+    #[serde(default = "default_icap_timeout_ms", rename = "timeout-ms")]
+    pub timeout_ms: u64,
+
+    /// Whether bodies pass when they can't be scanned
+    #[serde(default, rename = "failure-mode")]
+    pub failure_mode: FailureMode,
+
+    /// HTTP status code for blocked requests
+    #[serde(default = "default_icap_status", rename = "status-code")]
+    pub status_code: u16,
+}
+
+impl Default for IcapFilter {
+    fn default() -> Self {
+        Self {
+            reqmod: None,
+            respmod: None,
+            preview_bytes: None,
+            max_body_bytes: default_icap_max_body_bytes(),
+            timeout_ms: default_icap_timeout_ms(),
+            failure_mode: FailureMode::Closed,
+            status_code: default_icap_status(),
+        }
+    }
+}
+
+/// Host, port (default 1344) and service path of an `icap://` URL
+pub fn parse_icap_url(url: &str) -> Result<(String, u16, String), String> {
+    let Some(rest) = url.strip_prefix("icap://") else {
+        return Err(format!(
+            "service URL must start with icap://, got '{}'",
+            url
+        ));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(at) => (&rest[..at], &rest[at..]),
+        None => (rest, "/"),
+    };
+    // The port follows the last colon, after the brackets of an IPv6 host
+    let host_end = authority.rfind(']').map_or(0, |at| at + 1);
+    let (host, port) = match authority[host_end..].rfind(':') {
+        Some(at) => {
+            let port = authority[host_end + at + 1..]
+                .parse::<u16>()
+                .map_err(|_| format!("invalid port in service URL '{}'", url))?;
+            (&authority[..host_end + at], port)
+        }
+        None => (authority, 1344),
+    };
+    if host.is_empty() {
+        return Err(format!("service URL '{}' has no host", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+fn default_icap_max_body_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_icap_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_icap_status() -> u16 {
+    403
+}
+
+// =============================================================================
+// Response Policy Filter
+// =============================================================================
+
+/// Protects clients from what an upstream sends back.
+///
+/// Responses larger than `max-body-bytes` or with a content type outside
+/// the allowlist are refused with 502; listed headers are removed. With
+/// `upstreams` set, the policy only applies to responses from those
+/// upstreams, e.g. third-party APIs behind a route with fallbacks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ResponsePolicyFilter {
+    /// Largest response body passed to the client
+    #[serde(default, rename = "max-body-bytes")]
+    pub max_body_bytes: Option<u64>,
+
+    /// Allowed response content types (`type/subtype` or `type/*`)
+    #[serde(default, rename = "allowed-content-types")]
+    pub allowed_content_types: Vec<String>,
+
+    /// Response headers removed before the response reaches the client
+    #[serde(default, rename = "strip-headers")]
+    pub strip_headers: Vec<String>,
+
+    /// Upstreams the policy applies to (empty: all)
+    #[serde(default)]
+    pub upstreams: Vec<String>,
+}
+
+impl ResponsePolicyFilter {
+    /// Whether the policy covers responses from an upstream
+    pub fn applies_to(&self, upstream: Option<&str>) -> bool {
+        self.upstreams.is_empty()
+            || upstream.is_some_and(|u| self.upstreams.iter().any(|id| id == u))
+    }
+}
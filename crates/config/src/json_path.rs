@@ -0,0 +1,394 @@
+//! JSONPath subset used by the `json-transform` filter.
+//!
+//! Supported syntax:
+//!
+//! | Syntax | Meaning |
+//! |--------|---------|
+//! | `$.name`, `$['name']` | Object field |
+//! | `$.items[0]` | Array element |
+//! | `$.items[*]`, `$.user.*` | Every element or field value |
+//! | `$..password` | Field at any depth |
+//!
+//! Paths are parsed when the configuration is loaded. Operations work on
+//! the *parent* of each match together with the last path segment, which
+//! is how fields are removed, renamed or created.
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Maximum length of a path in bytes.
+const MAX_SOURCE_LEN: usize = 1024;
+
+/// JSONPath parse errors
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum JsonPathError {
+    #[error("path must start with '$'")]
+    MissingRoot,
+
+    #[error("path is longer than {MAX_SOURCE_LEN} bytes")]
+    TooLong,
+
+    #[error("path must select at least one field or element")]
+    Empty,
+
+    #[error("syntax error at offset {offset}: {message}")]
+    Syntax { offset: usize, message: String },
+}
+
+/// One step of a JSONPath
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathSegment {
+    /// Object field by name
+    Field(String),
+    /// Array element by index
+    Index(usize),
+    /// Every array element or object field value
+    Wildcard,
+    /// Object field by name at any depth below the current value
+    Descendant(String),
+}
+
+/// A parsed JSONPath.
+///
+/// Serializes as its source string; deserializing parses it.
+#[derive(Clone)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<JsonPathSegment>,
+}
+
+impl JsonPath {
+    /// Parse a path such as `$.user.cards[*].number`.
+    pub fn parse(source: &str) -> Result<Self, JsonPathError> {
+        if source.len() > MAX_SOURCE_LEN {
+            return Err(JsonPathError::TooLong);
+        }
+        let rest = source.strip_prefix('$').ok_or(JsonPathError::MissingRoot)?;
+
+        let bytes = rest.as_bytes();
+        let mut segments = Vec::new();
+        let mut pos = 0;
+        let syntax = |pos: usize, message: &str| JsonPathError::Syntax {
+            offset: pos + 1,
+            message: message.to_string(),
+        };
+
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'.' if bytes.get(pos + 1) == Some(&b'.') => {
+                    let name = read_name(rest, pos + 2);
+                    if name.is_empty() || name == "*" {
+                        return Err(syntax(pos, "expected a field name after '..'"));
+                    }
+                    pos += 2 + name.len();
+                    segments.push(JsonPathSegment::Descendant(name.to_string()));
+                }
+                b'.' => {
+                    let name = read_name(rest, pos + 1);
+                    if name.is_empty() {
+                        return Err(syntax(pos, "expected a field name after '.'"));
+                    }
+                    pos += 1 + name.len();
+                    segments.push(if name == "*" {
+                        JsonPathSegment::Wildcard
+                    } else {
+                        JsonPathSegment::Field(name.to_string())
+                    });
+                }
+                b'[' => {
+                    let end = rest[pos..]
+                        .find(']')
+                        .map(|i| pos + i)
+                        .ok_or_else(|| syntax(pos, "unclosed '['"))?;
+                    let inner = rest[pos + 1..end].trim();
+                    let segment = if inner == "*" {
+                        JsonPathSegment::Wildcard
+                    } else if let Some(name) = quoted(inner) {
+                        JsonPathSegment::Field(name.to_string())
+                    } else {
+                        inner
+                            .parse()
+                            .map(JsonPathSegment::Index)
+                            .map_err(|_| syntax(pos, "expected an index, '*' or a quoted name"))?
+                    };
+                    segments.push(segment);
+                    pos = end + 1;
+                }
+                _ => return Err(syntax(pos, "expected '.' or '['")),
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(JsonPathError::Empty);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    /// Path segments in order
+    pub fn segments(&self) -> &[JsonPathSegment] {
+        &self.segments
+    }
+
+    /// Last segment of the path
+    pub fn last(&self) -> &JsonPathSegment {
+        self.segments
+            .last()
+            .expect("parsed paths have at least one segment")
+    }
+
+    /// Whether the path selects at most one location (no wildcards or
+    /// descendant steps)
+    pub fn is_definite(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|s| matches!(s, JsonPathSegment::Field(_) | JsonPathSegment::Index(_)))
+    }
+
+    /// Call `f` with the parent of every location the path selects and the
+    /// segment that selects it within that parent.
+    ///
+    /// With `create_missing`, missing intermediate fields are created as
+    /// empty objects so the last segment can be inserted. A trailing
+    /// descendant step is passed to `f` as a [`JsonPathSegment::Field`] for
+    /// every object at any depth.
+    pub fn for_each_parent_mut<F>(&self, root: &mut Value, create_missing: bool, mut f: F)
+    where
+        F: FnMut(&mut Value, &JsonPathSegment),
+    {
+        visit_parents(root, &self.segments, create_missing, &mut f);
+    }
+}
+
+fn visit_parents<F>(value: &mut Value, segments: &[JsonPathSegment], create: bool, f: &mut F)
+where
+    F: FnMut(&mut Value, &JsonPathSegment),
+{
+    let (first, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    if rest.is_empty() {
+        match first {
+            JsonPathSegment::Descendant(name) => {
+                let field = JsonPathSegment::Field(name.clone());
+                for_each_object(value, &mut |object| f(object, &field));
+            }
+            segment => f(value, segment),
+        }
+        return;
+    }
+
+    match first {
+        JsonPathSegment::Field(name) => {
+            if let Value::Object(map) = value {
+                if create && !map.contains_key(name) {
+                    map.insert(name.clone(), Value::Object(Default::default()));
+                }
+                if let Some(child) = map.get_mut(name) {
+                    visit_parents(child, rest, create, f);
+                }
+            }
+        }
+        JsonPathSegment::Index(index) => {
+            if let Some(child) = value.get_mut(*index) {
+                visit_parents(child, rest, create, f);
+            }
+        }
+        JsonPathSegment::Wildcard => match value {
+            Value::Object(map) => {
+                for child in map.values_mut() {
+                    visit_parents(child, rest, create, f);
+                }
+            }
+            Value::Array(items) => {
+                for child in items {
+                    visit_parents(child, rest, create, f);
+                }
+            }
+            _ => {}
+        },
+        JsonPathSegment::Descendant(name) => {
+            if let Some(child) = value.get_mut(name.as_str()) {
+                visit_parents(child, rest, create, f);
+            }
+            match value {
+                Value::Object(map) => {
+                    for child in map.values_mut() {
+                        visit_parents(child, segments, create, f);
+                    }
+                }
+                Value::Array(items) => {
+                    for child in items {
+                        visit_parents(child, segments, create, f);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Call `f` for `value` and every value below it that is an object
+fn for_each_object(value: &mut Value, f: &mut dyn FnMut(&mut Value)) {
+    if value.is_object() {
+        f(value);
+    }
+    match value {
+        Value::Object(map) => map.values_mut().for_each(|child| for_each_object(child, f)),
+        Value::Array(items) => items.iter_mut().for_each(|child| for_each_object(child, f)),
+        _ => {}
+    }
+}
+
+/// Field name starting at `start`, up to the next '.' or '['
+fn read_name(path: &str, start: usize) -> &str {
+    let rest = &path[start.min(path.len())..];
+    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+    &rest[..end]
+}
+
+fn quoted(s: &str) -> Option<&str> {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .or_else(|| s.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+}
+
+impl fmt::Debug for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("JsonPath").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl PartialEq for JsonPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for JsonPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for JsonPath {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "JsonPath".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "JSONPath selecting fields or elements, e.g. `$.user.password`",
+            "pattern": "^\\$",
+            "maxLength": MAX_SOURCE_LEN,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn segments(path: &str) -> Vec<JsonPathSegment> {
+        JsonPath::parse(path).unwrap().segments().to_vec()
+    }
+
+    #[test]
+    fn test_parse() {
+        use JsonPathSegment::*;
+
+        assert_eq!(
+            segments("$.user.cards[*].number"),
+            vec![
+                Field("user".into()),
+                Field("cards".into()),
+                Wildcard,
+                Field("number".into())
+            ]
+        );
+        assert_eq!(
+            segments("$['odd.name'][2]"),
+            vec![Field("odd.name".into()), Index(2)]
+        );
+        assert_eq!(segments("$..password"), vec![Descendant("password".into())]);
+        assert_eq!(segments("$.meta.*"), vec![Field("meta".into()), Wildcard]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            JsonPath::parse("user").unwrap_err(),
+            JsonPathError::MissingRoot
+        );
+        assert_eq!(JsonPath::parse("$").unwrap_err(), JsonPathError::Empty);
+        assert!(JsonPath::parse("$.").is_err());
+        assert!(JsonPath::parse("$.items[x]").is_err());
+        assert!(JsonPath::parse("$.items[0").is_err());
+        assert!(JsonPath::parse("$..*").is_err());
+    }
+
+    #[test]
+    fn test_for_each_parent_mut() {
+        let mut value = json!({
+            "user": {"password": "a", "profile": {"password": "b"}},
+            "items": [{"card": "1"}, {"card": "2"}]
+        });
+
+        let path = JsonPath::parse("$..password").unwrap();
+        path.for_each_parent_mut(&mut value, false, |parent, last| {
+            if let (Value::Object(map), JsonPathSegment::Field(name)) = (parent, last) {
+                if let Some(v) = map.get_mut(name) {
+                    *v = json!("***");
+                }
+            }
+        });
+        assert_eq!(value["user"]["password"], "***");
+        assert_eq!(value["user"]["profile"]["password"], "***");
+
+        let mut parents = 0;
+        JsonPath::parse("$.items[*].card")
+            .unwrap()
+            .for_each_parent_mut(&mut value, false, |_, _| parents += 1);
+        assert_eq!(parents, 2);
+
+        JsonPath::parse("$.meta.source")
+            .unwrap()
+            .for_each_parent_mut(&mut value, true, |parent, last| {
+                if let (Value::Object(map), JsonPathSegment::Field(name)) = (parent, last) {
+                    map.insert(name.clone(), json!("zentinel"));
+                }
+            });
+        assert_eq!(value["meta"]["source"], "zentinel");
+    }
+}
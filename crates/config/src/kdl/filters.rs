@@ -4,11 +4,16 @@ use anyhow::Result;
 use std::collections::HashMap;
 use tracing::trace;
 
+use crate::expr::Expression;
 use crate::filters::*;
+use crate::json_path::JsonPath;
 use crate::routes::FailureMode;
-use crate::FilterConfig;
+use crate::{AgentEvent, FilterConfig};
 
-use super::helpers::{get_bool_entry, get_first_arg_string, get_int_entry, get_string_entry};
+use super::helpers::{
+    get_bool_entry, get_first_arg_string, get_float_entry, get_int_entry, get_string_entry,
+};
+use super::kdl_value_to_json;
 
 /// Parse top-level filter definitions block
 pub fn parse_filter_definitions(node: &kdl::KdlNode) -> Result<HashMap<String, FilterConfig>> {
@@ -27,7 +32,19 @@ pub fn parse_filter_definitions(node: &kdl::KdlNode) -> Result<HashMap<String, F
                 trace!(filter_id = %id, "Parsing filter definition");
 
                 let filter = parse_single_filter_definition(child)?;
-                filters.insert(id.clone(), FilterConfig::new(id, filter));
+                let mut filter_config = FilterConfig::new(id.clone(), filter);
+                if let Some(source) = get_string_entry(child, "enable-if") {
+                    let expr = Expression::parse(&source).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Filter '{}': invalid enable-if expression '{}': {}",
+                            id,
+                            source,
+                            e
+                        )
+                    })?;
+                    filter_config.enable_if = Some(expr);
+                }
+                filters.insert(id, filter_config);
             }
         }
     }
@@ -43,7 +60,7 @@ pub fn parse_filter_definitions(node: &kdl::KdlNode) -> Result<HashMap<String, F
 pub fn parse_single_filter_definition(node: &kdl::KdlNode) -> Result<Filter> {
     let filter_type = get_string_entry(node, "type").ok_or_else(|| {
         anyhow::anyhow!(
-            "Filter definition requires a 'type' field. Valid types: rate-limit, agent, headers, compress, cors, timeout, log, geo"
+            "Filter definition requires a 'type' field. Valid types: rate-limit, agent, headers, compress, cors, timeout, log, geo, redirect, url-rewrite, wasm, json-transform, schema-validate, api-key, response-policy, managed-rules, ip-access, adaptive-protection, quota, prompt-template, multipart, icap"
         )
     })?;
 
@@ -60,8 +77,20 @@ pub fn parse_single_filter_definition(node: &kdl::KdlNode) -> Result<Filter> {
         "geo" => parse_geo_filter(node),
         "redirect" => parse_redirect_filter(node),
         "url-rewrite" => parse_url_rewrite_filter(node),
+        "wasm" => parse_wasm_filter(node),
+        "json-transform" => parse_json_transform_filter(node),
+        "schema-validate" => parse_schema_validate_filter(node),
+        "api-key" => parse_api_key_filter(node),
+        "response-policy" => parse_response_policy_filter(node),
+        "managed-rules" => parse_managed_rules_filter(node),
+        "ip-access" => parse_ip_access_filter(node),
+        "adaptive-protection" => parse_adaptive_protection_filter(node),
+        "quota" => parse_quota_filter(node),
+        "prompt-template" => parse_prompt_template_filter(node),
+        "multipart" => parse_multipart_filter(node),
+        "icap" => parse_icap_filter(node),
         other => Err(anyhow::anyhow!(
-            "Unknown filter type: '{}'. Valid types: rate-limit, agent, headers, compress, cors, timeout, log, geo, redirect, url-rewrite",
+            "Unknown filter type: '{}'. Valid types: rate-limit, agent, headers, compress, cors, timeout, log, geo, redirect, url-rewrite, wasm, json-transform, schema-validate, api-key, response-policy, managed-rules, ip-access, adaptive-protection, quota, prompt-template, multipart, icap",
             other
         )),
     }
@@ -205,6 +234,9 @@ fn parse_agent_filter(node: &kdl::KdlNode) -> Result<Filter> {
         failure_mode,
         inspect_body: get_bool_entry(node, "inspect-body").unwrap_or(false),
         max_body_bytes: get_int_entry(node, "max-body-bytes").map(|v| v as usize),
+        priority: get_int_entry(node, "priority")
+            .map(|v| v as i32)
+            .unwrap_or(0),
     }))
 }
 
@@ -290,16 +322,50 @@ fn parse_compress_filter(node: &kdl::KdlNode) -> Result<Filter> {
         .map(|v| v as usize)
         .unwrap_or(1024);
 
-    Ok(Filter::Compress(CompressFilter {
-        algorithms,
-        min_size,
-        content_types: vec![
+    let mut content_types: Vec<String> = node
+        .children()
+        .and_then(|c| c.get("content-types"))
+        .map(|n| {
+            n.entries()
+                .iter()
+                .filter_map(|e| e.value().as_string().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    if content_types.is_empty() {
+        content_types = vec![
             "text/html".into(),
             "text/css".into(),
             "application/json".into(),
             "application/javascript".into(),
-        ],
+        ];
+    }
+
+    // levels { "application/json" 4; "text/" 9; }
+    let mut levels = HashMap::new();
+    if let Some(levels_node) = node.children().and_then(|c| c.get("levels")) {
+        for entry_node in levels_node.children().into_iter().flat_map(|c| c.nodes()) {
+            let content_type = entry_node.name().value().to_string();
+            let level = entry_node
+                .entries()
+                .first()
+                .and_then(|e| e.value().as_integer())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Compression level for '{}' must be an integer",
+                        content_type
+                    )
+                })?;
+            levels.insert(content_type, level.clamp(0, 22) as u8);
+        }
+    }
+
+    Ok(Filter::Compress(CompressFilter {
+        algorithms,
+        min_size,
+        content_types,
         level: get_int_entry(node, "level").map(|v| v as u8).unwrap_or(6),
+        levels,
     }))
 }
 
@@ -423,6 +489,494 @@ fn parse_url_rewrite_filter(node: &kdl::KdlNode) -> Result<Filter> {
     Ok(Filter::UrlRewrite(UrlRewriteFilter { hostname, path }))
 }
 
+fn parse_wasm_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let module = get_string_entry(node, "module").ok_or_else(|| {
+        anyhow::anyhow!("WASM filter requires a 'module' path to a compiled WASM component")
+    })?;
+
+    let mut wasm = WasmFilter::new(module);
+    wasm.config = get_string_entry(node, "config");
+
+    if let Some(events_node) = node.children().and_then(|c| c.get("events")) {
+        wasm.events.clear();
+        for entry in events_node.entries() {
+            if let Some(event_str) = entry.value().as_string() {
+                let event = match event_str {
+                    "request_headers" | "request-headers" => AgentEvent::RequestHeaders,
+                    "request_body" | "request-body" => AgentEvent::RequestBody,
+                    "response_headers" | "response-headers" => AgentEvent::ResponseHeaders,
+                    "response_body" | "response-body" => AgentEvent::ResponseBody,
+                    other => return Err(anyhow::anyhow!("Unknown WASM filter event: '{}'", other)),
+                };
+                wasm.events.push(event);
+            }
+        }
+    }
+
+    if let Some(v) = get_int_entry(node, "max-memory-mb") {
+        wasm.max_memory_mb = v as usize;
+    }
+    if let Some(v) = get_int_entry(node, "max-fuel") {
+        wasm.max_fuel = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "instances") {
+        wasm.instances = v as usize;
+    }
+    if let Some(mode) = get_string_entry(node, "failure-mode") {
+        wasm.failure_mode = match mode.as_str() {
+            "open" => FailureMode::Open,
+            "closed" => FailureMode::Closed,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown failure mode: '{}'. Use 'open' or 'closed'",
+                    other
+                ))
+            }
+        };
+    }
+
+    Ok(Filter::Wasm(wasm))
+}
+
+fn parse_json_transform_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let mut transform = JsonTransformFilter::default();
+
+    let json_path = |source: &str| {
+        JsonPath::parse(source)
+            .map_err(|e| anyhow::anyhow!("Invalid JSON path '{}': {}", source, e))
+    };
+    // Path entries of a list node such as `remove "$.a" "$.b"`
+    let path_list = |name: &str| -> Result<Vec<JsonPath>> {
+        node.children()
+            .and_then(|c| c.get(name))
+            .map(|n| n.entries())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|e| e.value().as_string())
+            .map(json_path)
+            .collect()
+    };
+    // `path value` children of a block node such as `set { "$.a" 1 }`
+    let path_block = |name: &str| -> Vec<(String, kdl::KdlValue)> {
+        node.children()
+            .and_then(|c| c.get(name))
+            .and_then(|n| n.children())
+            .map(|c| c.nodes())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|n| {
+                let value = n.entries().first()?.value().clone();
+                Some((n.name().value().to_string(), value))
+            })
+            .collect()
+    };
+
+    for (path, value) in path_block("rename") {
+        let to = value
+            .as_string()
+            .ok_or_else(|| anyhow::anyhow!("rename of '{}' requires a new field name", path))?;
+        transform.rename.push(JsonRename {
+            path: json_path(&path)?,
+            to: to.to_string(),
+        });
+    }
+    transform.remove = path_list("remove")?;
+    transform.redact = path_list("redact")?;
+    if let Some(redaction) = get_string_entry(node, "redaction") {
+        transform.redaction = redaction;
+    }
+    for (path, value) in path_block("set") {
+        let value = kdl_value_to_json(&value)
+            .ok_or_else(|| anyhow::anyhow!("set of '{}' has an unsupported value", path))?;
+        transform.set.push(JsonSetField {
+            path: json_path(&path)?,
+            value,
+        });
+    }
+    for (path, value) in path_block("inject") {
+        let name = value.as_string().unwrap_or_default();
+        let value = JsonComputedValue::from_name(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown computed value '{}' for '{}'. Valid values: timestamp, timestamp-ms, correlation-id, route-id, client-ip",
+                name,
+                path
+            )
+        })?;
+        transform.inject.push(JsonInjectField {
+            path: json_path(&path)?,
+            value,
+        });
+    }
+
+    if let Some(phase) = get_string_entry(node, "phase") {
+        transform.phase = match phase.as_str() {
+            "request" => FilterPhase::Request,
+            "response" => FilterPhase::Response,
+            "both" => FilterPhase::Both,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown phase: '{}'. Use 'request', 'response' or 'both'",
+                    other
+                ))
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "max-body-bytes") {
+        transform.max_body_bytes = v as usize;
+    }
+
+    Ok(Filter::JsonTransform(transform))
+}
+
+fn parse_schema_validate_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let schema = get_string_entry(node, "schema").ok_or_else(|| {
+        anyhow::anyhow!(
+            "Schema validate filter requires a 'schema' path to an OpenAPI or JSON Schema document"
+        )
+    })?;
+
+    let mut filter = SchemaValidateFilter::new(schema);
+    if let Some(v) = get_bool_entry(node, "validate-parameters") {
+        filter.validate_parameters = v;
+    }
+    if let Some(v) = get_bool_entry(node, "validate-body") {
+        filter.validate_body = v;
+    }
+    if let Some(v) = get_bool_entry(node, "reject-unknown-operations") {
+        filter.reject_unknown_operations = v;
+    }
+    if let Some(v) = get_int_entry(node, "max-body-bytes") {
+        filter.max_body_bytes = v as usize;
+    }
+    filter.serve_spec = get_string_entry(node, "serve-spec");
+
+    Ok(Filter::SchemaValidate(filter))
+}
+
+fn parse_api_key_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let key_file = get_string_entry(node, "key-file").ok_or_else(|| {
+        anyhow::anyhow!("API key filter requires a 'key-file' path to the key store")
+    })?;
+
+    let mut filter = ApiKeyFilter::new(key_file);
+    if let Some(header) = get_string_entry(node, "header") {
+        filter.header = Some(header).filter(|h| !h.is_empty());
+    }
+    filter.query_param = get_string_entry(node, "query-param");
+    if let Some(v) = get_bool_entry(node, "strip-key") {
+        filter.strip_key = v;
+    }
+
+    Ok(Filter::ApiKey(filter))
+}
+
+fn parse_response_policy_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let string_list = |name: &str| -> Vec<String> {
+        node.children()
+            .and_then(|c| c.get(name))
+            .map(|n| {
+                n.entries()
+                    .iter()
+                    .filter_map(|e| e.value().as_string().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Ok(Filter::ResponsePolicy(ResponsePolicyFilter {
+        max_body_bytes: get_int_entry(node, "max-body-bytes").map(|v| v.max(0) as u64),
+        allowed_content_types: string_list("allowed-content-types")
+            .into_iter()
+            .map(|ct| ct.to_ascii_lowercase())
+            .collect(),
+        strip_headers: string_list("strip-headers"),
+        upstreams: string_list("upstreams"),
+    }))
+}
+
+fn parse_managed_rules_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let rules = get_string_entry(node, "rules").ok_or_else(|| {
+        anyhow::anyhow!("Managed rules filter requires a 'rules' path to a rule file")
+    })?;
+
+    let mut filter = ManagedRulesFilter::new(rules);
+    if let Some(mode) = get_string_entry(node, "mode") {
+        filter.mode = match mode.as_str() {
+            "block" => ManagedRulesMode::Block,
+            "detect" => ManagedRulesMode::Detect,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid managed rules mode '{}'. Valid options: block, detect",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "anomaly-threshold") {
+        filter.anomaly_threshold = v.max(0) as u32;
+    }
+    if let Some(v) = get_bool_entry(node, "inspect-body") {
+        filter.inspect_body = v;
+    }
+    if let Some(v) = get_int_entry(node, "max-body-bytes") {
+        filter.max_body_bytes = v.max(0) as usize;
+    }
+    if let Some(disabled) = node.children().and_then(|c| c.get("disabled-rules")) {
+        filter.disabled_rules = disabled
+            .entries()
+            .iter()
+            .filter_map(|e| e.value().as_integer())
+            .map(|id| id as u64)
+            .collect();
+    }
+
+    Ok(Filter::ManagedRules(filter))
+}
+
+fn parse_ip_access_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let string_list = |name: &str| -> Vec<String> {
+        node.children()
+            .and_then(|c| c.get(name))
+            .map(|n| {
+                n.entries()
+                    .iter()
+                    .filter_map(|e| e.value().as_string().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut filter = IpAccessFilter {
+        cidrs: string_list("cidrs"),
+        sources: string_list("sources"),
+        block_message: get_string_entry(node, "block-message"),
+        ..Default::default()
+    };
+    if let Some(action) = get_string_entry(node, "action") {
+        filter.action = match action.as_str() {
+            "deny" => IpAccessAction::Deny,
+            "allow" => IpAccessAction::Allow,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid ip-access action '{}'. Valid options: deny, allow",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "refresh-secs") {
+        filter.refresh_secs = v.max(0) as u64;
+    }
+    if let Some(v) = get_int_entry(node, "status-code") {
+        filter.status_code = v.clamp(0, u16::MAX as i128) as u16;
+    }
+
+    Ok(Filter::IpAccess(filter))
+}
+
+fn parse_adaptive_protection_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let mut filter = AdaptiveProtectionFilter::default();
+
+    if let Some(action) = get_string_entry(node, "action") {
+        filter.action = match action.as_str() {
+            "throttle" => AdaptiveAction::Throttle,
+            "challenge" => AdaptiveAction::Challenge,
+            "log" => AdaptiveAction::Log,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid adaptive-protection action '{}'. Valid options: throttle, challenge, log",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "window-secs") {
+        filter.window_secs = v.max(0) as u64;
+    }
+    if let Some(v) = get_int_entry(node, "min-requests") {
+        filter.min_requests = v.clamp(0, u32::MAX as i128) as u32;
+    }
+    if let Some(v) = get_int_entry(node, "rate-threshold") {
+        filter.rate_threshold = v.clamp(0, u32::MAX as i128) as u32;
+    }
+    if let Some(v) = get_float_entry(node, "error-ratio-threshold") {
+        filter.error_ratio_threshold = v;
+    }
+    if let Some(v) = get_float_entry(node, "path-entropy-threshold") {
+        filter.path_entropy_threshold = v;
+    }
+    if let Some(v) = get_float_entry(node, "rate-weight") {
+        filter.rate_weight = v;
+    }
+    if let Some(v) = get_float_entry(node, "error-weight") {
+        filter.error_weight = v;
+    }
+    if let Some(v) = get_float_entry(node, "entropy-weight") {
+        filter.entropy_weight = v;
+    }
+    if let Some(v) = get_float_entry(node, "score-threshold") {
+        filter.score_threshold = v;
+    }
+    if let Some(v) = get_int_entry(node, "penalty-secs") {
+        filter.penalty_secs = v.max(0) as u64;
+    }
+    if let Some(v) = get_int_entry(node, "throttle-rps") {
+        filter.throttle_rps = v.clamp(0, u32::MAX as i128) as u32;
+    }
+    if let Some(v) = get_string_entry(node, "challenge-type") {
+        filter.challenge_type = v;
+    }
+    if let Some(v) = get_int_entry(node, "status-code") {
+        filter.status_code = v.clamp(0, u16::MAX as i128) as u16;
+    }
+    if let Some(v) = get_int_entry(node, "max-clients") {
+        filter.max_clients = v.max(0) as usize;
+    }
+
+    Ok(Filter::AdaptiveProtection(filter))
+}
+
+fn parse_quota_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let limit = |name: &str| get_int_entry(node, name).map(|v| v.max(0) as u64);
+
+    let mut filter = QuotaFilter {
+        requests_per_day: limit("requests-per-day"),
+        requests_per_month: limit("requests-per-month"),
+        bytes_per_day: limit("bytes-per-day"),
+        bytes_per_month: limit("bytes-per-month"),
+        webhook: get_string_entry(node, "webhook"),
+        ..Default::default()
+    };
+    if let Some(key) = get_string_entry(node, "key") {
+        filter.key = match key.as_str() {
+            "api-key" => QuotaKey::ApiKey,
+            "jwt-sub" => QuotaKey::JwtSubject,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid quota key '{}'. Valid options: api-key, jwt-sub",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(headers) = get_bool_entry(node, "headers") {
+        filter.headers = headers;
+    }
+    if let Some(v) = get_int_entry(node, "status-code") {
+        filter.status_code = v.clamp(0, u16::MAX as i128) as u16;
+    }
+
+    Ok(Filter::Quota(filter))
+}
+
+fn parse_prompt_template_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let mut filter = PromptTemplateFilter {
+        system_prompt: get_string_entry(node, "system-prompt"),
+        ..Default::default()
+    };
+    if let Some(mode) = get_string_entry(node, "client-system-prompt") {
+        filter.client_system_prompt = match mode.as_str() {
+            "keep" => ClientSystemPrompt::Keep,
+            "strip" => ClientSystemPrompt::Strip,
+            "reject" => ClientSystemPrompt::Reject,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid client-system-prompt '{}'. Valid options: keep, strip, reject",
+                    other
+                ));
+            }
+        };
+    }
+    // `template "id" "text with {{placeholders}}"` children
+    for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+        if child.name().value() != "template" {
+            continue;
+        }
+        let mut args = child.entries().iter().filter(|e| e.name().is_none());
+        let (Some(id), Some(template)) = (
+            args.next().and_then(|e| e.value().as_string()),
+            args.next().and_then(|e| e.value().as_string()),
+        ) else {
+            return Err(anyhow::anyhow!(
+                "prompt-template 'template' requires an ID and a template, e.g., template \"summarize\" \"Summarize: {{{{text}}}}\""
+            ));
+        };
+        filter.templates.push(PromptTemplate {
+            id: id.to_string(),
+            template: template.to_string(),
+        });
+    }
+    if let Some(action) = get_string_entry(node, "on-violation") {
+        filter.on_violation = match action.as_str() {
+            "block" => PromptViolationAction::Block,
+            "log" => PromptViolationAction::Log,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid on-violation '{}'. Valid options: block, log",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "status-code") {
+        filter.status_code = v.clamp(0, u16::MAX as i128) as u16;
+    }
+    if let Some(v) = get_int_entry(node, "max-body-bytes") {
+        filter.max_body_bytes = v.max(0) as usize;
+    }
+
+    Ok(Filter::PromptTemplate(filter))
+}
+
+fn parse_multipart_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let mut filter = MultipartFilter::default();
+    if let Some(v) = get_int_entry(node, "max-part-bytes") {
+        filter.max_part_bytes = v.max(0) as usize;
+    }
+    if let Some(v) = get_int_entry(node, "max-total-bytes") {
+        filter.max_total_bytes = v.max(0) as usize;
+    }
+    if let Some(v) = get_int_entry(node, "max-parts") {
+        filter.max_parts = v.max(0) as usize;
+    }
+
+    Ok(Filter::Multipart(filter))
+}
+
+fn parse_icap_filter(node: &kdl::KdlNode) -> Result<Filter> {
+    let mut filter = IcapFilter {
+        reqmod: get_string_entry(node, "reqmod"),
+        respmod: get_string_entry(node, "respmod"),
+        ..Default::default()
+    };
+    if let Some(v) = get_int_entry(node, "preview-bytes") {
+        filter.preview_bytes = Some(v.max(0) as usize);
+    }
+    if let Some(v) = get_int_entry(node, "max-body-bytes") {
+        filter.max_body_bytes = v.max(0) as usize;
+    }
+    if let Some(v) = get_int_entry(node, "timeout-ms") {
+        filter.timeout_ms = v.max(0) as u64;
+    }
+    if let Some(mode) = get_string_entry(node, "failure-mode") {
+        filter.failure_mode = match mode.as_str() {
+            "open" => FailureMode::Open,
+            "closed" => FailureMode::Closed,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown failure mode: '{}'. Use 'open' or 'closed'",
+                    other
+                ))
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "status-code") {
+        filter.status_code = v.clamp(0, u16::MAX as i128) as u16;
+    }
+
+    Ok(Filter::Icap(filter))
+}
+
 fn parse_path_modifier(node: &kdl::KdlNode) -> Option<PathModifier> {
     if let Some(full) = get_string_entry(node, "replace-full-path") {
         Some(PathModifier::ReplaceFullPath { value: full })
@@ -475,4 +1029,398 @@ mod tests {
             other => panic!("expected rate-limit filter, got {other:?}"),
         }
     }
+
+    #[test]
+    fn compress_filter_parses_levels() {
+        let filter = parse_filter(
+            r#"filter "gz" {
+    type "compress"
+    algorithms "zstd,br,gzip"
+    level 5
+    content-types "text/" "application/json"
+    levels {
+        "application/json" 3
+        "text/html" 11
+    }
+}"#,
+        );
+        match filter {
+            Filter::Compress(compress) => {
+                assert_eq!(
+                    compress.algorithms,
+                    vec![
+                        CompressionAlgorithm::Zstd,
+                        CompressionAlgorithm::Brotli,
+                        CompressionAlgorithm::Gzip
+                    ]
+                );
+                assert_eq!(compress.content_types, vec!["text/", "application/json"]);
+                assert_eq!(compress.level_for("text/html; charset=utf-8"), 11);
+                assert_eq!(compress.level_for("application/json"), 3);
+                assert_eq!(compress.level_for("text/css"), 5);
+            }
+            other => panic!("expected compress filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn filter_parses_enable_if() {
+        let doc: kdl::KdlDocument = r#"filters {
+    filter "beta-headers" {
+        type "headers"
+        enable-if "request.headers[\"x-beta\"] == \"1\""
+    }
+}"#
+        .parse()
+        .expect("kdl parses");
+        let filters = parse_filter_definitions(doc.nodes().first().unwrap()).unwrap();
+        let condition = filters["beta-headers"].enable_if.as_ref().unwrap();
+        assert_eq!(condition.as_str(), r#"request.headers["x-beta"] == "1""#);
+        assert!(condition.needs_headers());
+    }
+
+    #[test]
+    fn filter_rejects_invalid_enable_if() {
+        let doc: kdl::KdlDocument = r#"filters {
+    filter "beta-headers" {
+        type "headers"
+        enable-if "request.nope == 1"
+    }
+}"#
+        .parse()
+        .expect("kdl parses");
+        let err = parse_filter_definitions(doc.nodes().first().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("invalid enable-if expression"));
+    }
+
+    #[test]
+    fn wasm_filter_parses_events_and_limits() {
+        let filter = parse_filter(
+            r#"filter "tag" {
+    type "wasm"
+    module "/etc/zentinel/filters/tag.wasm"
+    config "{\"header\": \"x-tag\"}"
+    events "request-headers" "response-headers"
+    max-memory-mb 8
+    max-fuel 500000
+    failure-mode "open"
+}"#,
+        );
+        match filter {
+            Filter::Wasm(wasm) => {
+                assert_eq!(wasm.module, "/etc/zentinel/filters/tag.wasm");
+                assert_eq!(wasm.config.as_deref(), Some(r#"{"header": "x-tag"}"#));
+                assert_eq!(
+                    wasm.events,
+                    vec![AgentEvent::RequestHeaders, AgentEvent::ResponseHeaders]
+                );
+                assert_eq!(wasm.max_memory_mb, 8);
+                assert_eq!(wasm.max_fuel, 500_000);
+                assert_eq!(wasm.instances, 4);
+                assert_eq!(wasm.failure_mode, FailureMode::Open);
+            }
+            other => panic!("expected wasm filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn json_transform_filter_parses_operations() {
+        let filter = parse_filter(
+            r#"filter "scrub" {
+    type "json-transform"
+    phase "response"
+    max-body-bytes 65536
+    rename {
+        "$.user_id" "userId"
+    }
+    remove "$.internal" "$.debug"
+    redact "$..password"
+    set {
+        "$.meta.source" "zentinel"
+        "$.meta.version" 2
+    }
+    inject {
+        "$.meta.request_id" "correlation-id"
+    }
+}"#,
+        );
+        match filter {
+            Filter::JsonTransform(json) => {
+                assert_eq!(json.phase, FilterPhase::Response);
+                assert_eq!(json.max_body_bytes, 65536);
+                assert_eq!(json.rename[0].path.to_string(), "$.user_id");
+                assert_eq!(json.rename[0].to, "userId");
+                assert_eq!(json.remove.len(), 2);
+                assert_eq!(json.redact[0].to_string(), "$..password");
+                assert_eq!(json.redaction, "[REDACTED]");
+                assert_eq!(json.set[1].value, serde_json::json!(2));
+                assert_eq!(json.inject[0].value, JsonComputedValue::CorrelationId);
+            }
+            other => panic!("expected json-transform filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn json_transform_filter_rejects_bad_paths() {
+        let doc: kdl::KdlDocument = r#"filter "bad" {
+    type "json-transform"
+    remove "internal"
+}"#
+        .parse()
+        .unwrap();
+        let err = parse_single_filter_definition(doc.nodes().first().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Invalid JSON path"));
+    }
+
+    #[test]
+    fn schema_validate_filter_parses_options() {
+        let filter = parse_filter(
+            r#"filter "api-schema" {
+    type "schema-validate"
+    schema "/etc/zentinel/openapi.yaml"
+    validate-parameters #false
+    reject-unknown-operations #true
+    max-body-bytes 65536
+    serve-spec "/openapi.json"
+}"#,
+        );
+        match filter {
+            Filter::SchemaValidate(schema) => {
+                assert_eq!(schema.schema, "/etc/zentinel/openapi.yaml");
+                assert!(!schema.validate_parameters);
+                assert!(schema.validate_body);
+                assert!(schema.reject_unknown_operations);
+                assert_eq!(schema.max_body_bytes, 65536);
+                assert_eq!(schema.serve_spec.as_deref(), Some("/openapi.json"));
+            }
+            other => panic!("expected schema-validate filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn api_key_filter_parses_key_sources() {
+        let filter = parse_filter(
+            r#"filter "keys" {
+    type "api-key"
+    key-file "/etc/zentinel/api-keys.json"
+    header ""
+    query-param "api_key"
+    strip-key #false
+}"#,
+        );
+        match filter {
+            Filter::ApiKey(api_key) => {
+                assert_eq!(api_key.key_file, "/etc/zentinel/api-keys.json");
+                assert_eq!(api_key.header, None);
+                assert_eq!(api_key.query_param.as_deref(), Some("api_key"));
+                assert!(!api_key.strip_key);
+            }
+            other => panic!("expected api-key filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn response_policy_filter_parses_lists() {
+        let filter = parse_filter(
+            r#"filter "partner" {
+    type "response-policy"
+    max-body-bytes 1048576
+    allowed-content-types "application/json" "Text/*"
+    strip-headers "Set-Cookie" "Server"
+    upstreams "partner-api"
+}"#,
+        );
+        match filter {
+            Filter::ResponsePolicy(policy) => {
+                assert_eq!(policy.max_body_bytes, Some(1048576));
+                assert_eq!(policy.allowed_content_types, ["application/json", "text/*"]);
+                assert_eq!(policy.strip_headers, ["Set-Cookie", "Server"]);
+                assert!(policy.applies_to(Some("partner-api")));
+                assert!(!policy.applies_to(Some("internal")));
+            }
+            other => panic!("expected response-policy filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn managed_rules_filter_parses_options() {
+        let filter = parse_filter(
+            r#"filter "crs-lite" {
+    type "managed-rules"
+    rules "/etc/zentinel/rules/crs-subset.conf"
+    mode "detect"
+    anomaly-threshold 10
+    inspect-body #false
+    disabled-rules 942100 920350
+}"#,
+        );
+        match filter {
+            Filter::ManagedRules(rules) => {
+                assert_eq!(rules.rules, "/etc/zentinel/rules/crs-subset.conf");
+                assert_eq!(rules.mode, ManagedRulesMode::Detect);
+                assert_eq!(rules.anomaly_threshold, 10);
+                assert!(!rules.inspect_body);
+                assert_eq!(rules.max_body_bytes, 128 * 1024);
+                assert_eq!(rules.disabled_rules, [942100, 920350]);
+            }
+            other => panic!("expected managed-rules filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn ip_access_filter_parses_lists() {
+        let filter = parse_filter(
+            r#"filter "office-only" {
+    type "ip-access"
+    action "allow"
+    cidrs "10.0.0.0/8" "2001:db8::/32"
+    sources "/etc/zentinel/office.txt" "https://lists.example.com/vpn.txt"
+    refresh-secs 60
+}"#,
+        );
+        match filter {
+            Filter::IpAccess(access) => {
+                assert_eq!(access.action, IpAccessAction::Allow);
+                assert_eq!(access.cidrs, ["10.0.0.0/8", "2001:db8::/32"]);
+                assert_eq!(access.sources.len(), 2);
+                assert_eq!(access.refresh_secs, 60);
+                assert_eq!(access.status_code, 403);
+            }
+            other => panic!("expected ip-access filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn adaptive_protection_filter_parses_thresholds() {
+        let filter = parse_filter(
+            r#"filter "adaptive" {
+    type "adaptive-protection"
+    action "challenge"
+    challenge-type "cookie"
+    window-secs 30
+    rate-threshold 200
+    error-ratio-threshold 0.4
+    path-entropy-threshold 4
+    score-threshold 1.5
+}"#,
+        );
+        match filter {
+            Filter::AdaptiveProtection(adaptive) => {
+                assert_eq!(adaptive.action, AdaptiveAction::Challenge);
+                assert_eq!(adaptive.challenge_type, "cookie");
+                assert_eq!(adaptive.window_secs, 30);
+                assert_eq!(adaptive.rate_threshold, 200);
+                assert_eq!(adaptive.error_ratio_threshold, 0.4);
+                assert_eq!(adaptive.path_entropy_threshold, 4.0);
+                assert_eq!(adaptive.score_threshold, 1.5);
+                assert_eq!(adaptive.penalty_secs, 300);
+            }
+            other => panic!("expected adaptive-protection filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn quota_filter_parses_limits() {
+        let filter = parse_filter(
+            r#"filter "partner-quota" {
+    type "quota"
+    key "jwt-sub"
+    requests-per-day 10000
+    bytes-per-month 10737418240
+    webhook "https://hooks.example.com/quota"
+}"#,
+        );
+        match filter {
+            Filter::Quota(quota) => {
+                assert_eq!(quota.key, QuotaKey::JwtSubject);
+                assert_eq!(quota.requests_per_day, Some(10_000));
+                assert_eq!(quota.requests_per_month, None);
+                assert_eq!(quota.bytes_per_month, Some(10_737_418_240));
+                assert!(quota.headers);
+                assert_eq!(
+                    quota.webhook.as_deref(),
+                    Some("https://hooks.example.com/quota")
+                );
+            }
+            other => panic!("expected quota filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn prompt_template_filter_parses_templates() {
+        let filter = parse_filter(
+            r#"filter "support-prompt" {
+    type "prompt-template"
+    system-prompt "You are the Acme support assistant."
+    client-system-prompt "strip"
+    template "summarize" "Summarize this ticket: {{ticket}}"
+    template "translate" "Translate to {{language}}: {{text}}"
+    on-violation "log"
+}"#,
+        );
+        match filter {
+            Filter::PromptTemplate(prompt) => {
+                assert_eq!(
+                    prompt.system_prompt.as_deref(),
+                    Some("You are the Acme support assistant.")
+                );
+                assert_eq!(prompt.client_system_prompt, ClientSystemPrompt::Strip);
+                assert_eq!(prompt.templates.len(), 2);
+                assert_eq!(prompt.templates[1].id, "translate");
+                assert_eq!(
+                    prompt.templates[1].template,
+                    "Translate to {{language}}: {{text}}"
+                );
+                assert_eq!(prompt.on_violation, PromptViolationAction::Log);
+                assert_eq!(prompt.status_code, 400);
+            }
+            other => panic!("expected prompt-template filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn multipart_filter_parses_limits() {
+        let filter = parse_filter(
+            r#"filter "uploads" {
+    type "multipart"
+    max-part-bytes 5242880
+    max-parts 10
+}"#,
+        );
+        match filter {
+            Filter::Multipart(multipart) => {
+                assert_eq!(multipart.max_part_bytes, 5_242_880);
+                assert_eq!(multipart.max_total_bytes, 50 * 1024 * 1024);
+                assert_eq!(multipart.max_parts, 10);
+            }
+            other => panic!("expected multipart filter, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn icap_filter_parses_services() {
+        let filter = parse_filter(
+            r#"filter "antivirus" {
+    type "icap"
+    reqmod "icap://clamav.internal:1344/avscan"
+    respmod "icap://clamav.internal:1344/avscan"
+    preview-bytes 4096
+    failure-mode "open"
+}"#,
+        );
+        match filter {
+            Filter::Icap(icap) => {
+                assert_eq!(
+                    icap.reqmod.as_deref(),
+                    Some("icap://clamav.internal:1344/avscan")
+                );
+                assert!(icap.respmod.is_some());
+                assert_eq!(icap.preview_bytes, Some(4096));
+                assert_eq!(icap.failure_mode, FailureMode::Open);
+                assert_eq!(icap.timeout_ms, 5000);
+                assert_eq!(icap.status_code, 403);
+            }
+            other => panic!("expected icap filter, got {other:?}"),
+        }
+    }
 }
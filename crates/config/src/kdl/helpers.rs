@@ -26,6 +26,17 @@ pub fn offset_to_line_col(content: &str, offset: usize) -> (usize, usize) {
     (line, col)
 }
 
+/// Line and column (1-indexed) where a parsed node or entry starts
+///
+/// Spans may include the leading whitespace, which is skipped.
+pub fn span_line_col(content: &str, span: miette::SourceSpan) -> (usize, usize) {
+    let offset = span.offset();
+    let leading = content
+        .get(offset..)
+        .map_or(0, |rest| rest.len() - rest.trim_start().len());
+    offset_to_line_col(content, offset + leading)
+}
+
 /// Helper to get a string entry from a KDL node
 pub fn get_string_entry(node: &kdl::KdlNode, name: &str) -> Option<String> {
     node.children()
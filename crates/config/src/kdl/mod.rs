@@ -10,6 +10,7 @@
 //! - `upstreams`: Upstream target parsing
 //! - `filters`: Filter definition parsing
 //! - `namespace`: Namespace and service parsing
+//! - `tenants`: Tenant parsing
 
 mod circuitbreaker_helper;
 mod filters;
@@ -18,6 +19,7 @@ mod namespace;
 mod retrypolicy_helper;
 mod routes;
 mod server;
+mod tenants;
 mod upstreams;
 
 use tracing::{debug, trace, warn};
@@ -25,11 +27,18 @@ use tracing::{debug, trace, warn};
 // Re-export commonly used items
 pub use helpers::{
     get_bool_entry, get_first_arg_string, get_int_entry, get_string_entry, offset_to_line_col,
+    span_line_col,
 };
 
 pub use filters::parse_filter_definitions;
-pub use routes::parse_routes;
-pub use server::{parse_listeners, parse_server_config};
+pub use routes::{parse_concurrency_limit_config, parse_problem_details_config, parse_routes};
+pub use server::{
+    parse_capture_config, parse_chaos_config, parse_client_ip_config, parse_debug_headers_config,
+    parse_hardening_config, parse_listeners, parse_maintenance_config, parse_normalization_config,
+    parse_outbound_proxy_config, parse_readiness_config, parse_request_id_config,
+    parse_rollout_config, parse_server_config, parse_slow_client_config,
+};
+pub use tenants::parse_tenant;
 pub use upstreams::{parse_upstream, parse_upstreams};
 
 use anyhow::Result;
@@ -60,10 +69,14 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
     let mut agents = Vec::new();
     let mut waf = None;
     let mut namespaces = Vec::new();
+    let mut tenants: Vec<crate::TenantConfig> = Vec::new();
     let mut limits = None;
     let mut observability = None;
     let mut rate_limits = None;
     let mut cache = None;
+    let mut session_store = None;
+    let mut dns = None;
+    let mut notifications = None;
 
     for node in doc.nodes() {
         let node_name = node.name().value();
@@ -182,6 +195,17 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
                 trace!(namespace = %ns.id, "Parsed namespace");
                 namespaces.push(ns);
             }
+            "tenant" => {
+                let tenant = parse_tenant(node)?;
+                if tenants.iter().any(|t| t.id == tenant.id) {
+                    return Err(anyhow::anyhow!(
+                        "Duplicate tenant ID '{}' found. Each tenant ID must be unique across all config files.",
+                        tenant.id
+                    ));
+                }
+                trace!(tenant = %tenant.id, "Parsed tenant");
+                tenants.push(tenant);
+            }
             "limits" => {
                 limits = Some(parse_limits_config(node)?);
                 trace!("Parsed limits configuration");
@@ -198,6 +222,18 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
                 cache = Some(parse_cache_config(node)?);
                 trace!("Parsed cache configuration");
             }
+            "session-store" => {
+                session_store = Some(parse_session_store_config(node)?);
+                trace!("Parsed session-store configuration");
+            }
+            "dns" => {
+                dns = Some(parse_dns_config(node)?);
+                trace!("Parsed dns configuration");
+            }
+            "notifications" => {
+                notifications = Some(parse_notifications_config(node)?);
+                trace!("Parsed notifications configuration");
+            }
             "include" => {
                 return Err(anyhow::anyhow!(
                     "The 'include' directive is not supported when parsing raw KDL strings.\n\
@@ -208,7 +244,8 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
                 return Err(anyhow::anyhow!(
                     "Unknown top-level configuration block: '{}'\n\
                      Valid blocks are: schema-version, system, listeners, routes, upstreams, \
-                     filters, agents, waf, namespace, limits, observability, rate-limits, cache",
+                     filters, agents, waf, namespace, tenant, limits, observability, rate-limits, cache, \
+                     session-store, dns, notifications",
                     other
                 ));
             }
@@ -247,6 +284,7 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
         filters = filters.len(),
         agents = agents.len(),
         namespaces = namespaces.len(),
+        tenants = tenants.len(),
         has_waf = waf.is_some(),
         "KDL document parsed successfully"
     );
@@ -261,10 +299,14 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
         agents,
         waf,
         namespaces,
+        tenants,
         limits: limits.unwrap_or_default(),
         observability: observability.unwrap_or_default(),
         rate_limits: rate_limits.unwrap_or_default(),
         cache,
+        session_store,
+        dns,
+        notifications,
         default_upstream: None,
     })
 }
@@ -273,7 +315,12 @@ pub fn parse_kdl_document(doc: kdl::KdlDocument) -> Result<Config> {
 // Agent Parsing
 // ============================================================================
 
-use crate::agents::{AgentEvent, AgentTlsConfig, AgentTransport, AgentType, BodyStreamingMode};
+use crate::agents::{
+    default_agent_socket_path, AgentEvent, AgentEventTimeouts, AgentPoolConfig, AgentProcessConfig,
+    AgentRestartPolicy, AgentSharedMemoryConfig, AgentSlowStartConfig, AgentStateQuota,
+    AgentTlsConfig, AgentTransport, AgentType, BodyStreamingMode, LoadBalanceStrategy,
+    SessionStoreBackend, SessionStoreConfig,
+};
 use crate::routes::FailureMode;
 use std::path::PathBuf;
 
@@ -381,7 +428,7 @@ fn kdl_to_json(node: &kdl::KdlNode) -> Result<serde_json::Value> {
 }
 
 /// Convert a single KDL value to JSON
-fn kdl_value_to_json(value: &kdl::KdlValue) -> Option<serde_json::Value> {
+pub(super) fn kdl_value_to_json(value: &kdl::KdlValue) -> Option<serde_json::Value> {
     if let Some(s) = value.as_string() {
         Some(serde_json::Value::String(s.to_string()))
     } else if let Some(n) = value.as_integer() {
@@ -435,6 +482,7 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
     let mut chunk_timeout_ms = 5000u64;
     let mut config: Option<serde_json::Value> = None;
     let mut max_concurrent_calls = 100usize; // Per-agent concurrency limit
+    let mut max_message_size = None;
     for child in children.nodes() {
         match child.name().value() {
             "unix-socket" => {
@@ -463,6 +511,9 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
                 let tls = parse_agent_tls(child)?;
                 transport = Some(AgentTransport::Http { url, tls });
             }
+            "in-process" => {
+                transport = Some(AgentTransport::InProcess);
+            }
             "timeout-ms" => {
                 if let Some(entry) = child.entries().first() {
                     if let Some(v) = entry.value().as_integer() {
@@ -549,6 +600,13 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
                     }
                 }
             }
+            "max-message-size" => {
+                if let Some(entry) = child.entries().first() {
+                    if let Some(v) = entry.value().as_integer() {
+                        max_message_size = Some(v as usize);
+                    }
+                }
+            }
             "protocol-version" => {
                 warn!(
                     agent_id = %id,
@@ -559,12 +617,31 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
         }
     }
 
-    let transport = transport.ok_or_else(|| {
-        anyhow::anyhow!(
-            "Agent '{}' requires a transport (unix-socket, grpc, or http)",
-            id
-        )
-    })?;
+    let process = parse_agent_process(node, &id)?;
+    let event_timeouts = parse_agent_event_timeouts(node, &id)?;
+    let slow_start = parse_agent_slow_start(node);
+    let state_quota = parse_agent_state_quota(node);
+    let pool = parse_agent_pool(node, &id)?;
+
+    // Supervised agents listen on a default socket unless one is configured
+    let transport = match (transport, &process) {
+        (Some(AgentTransport::InProcess), Some(_)) => {
+            return Err(anyhow::anyhow!(
+                "Agent '{}' runs in-process and cannot have a 'process' block",
+                id
+            ))
+        }
+        (Some(transport), _) => transport,
+        (None, Some(_)) => AgentTransport::UnixSocket {
+            path: default_agent_socket_path(&id),
+        },
+        (None, None) => {
+            return Err(anyhow::anyhow!(
+                "Agent '{}' requires a transport (unix-socket, grpc, http, or in-process)",
+                id
+            ))
+        }
+    };
 
     // Default events if none specified
     if events.is_empty() {
@@ -576,8 +653,9 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
         agent_type,
         transport,
         events,
-        pool: None,
+        pool,
         timeout_ms,
+        event_timeouts,
         failure_mode,
         circuit_breaker,
         max_request_body_bytes,
@@ -587,9 +665,244 @@ fn parse_single_agent(node: &kdl::KdlNode) -> Result<AgentConfig> {
         chunk_timeout_ms,
         config,
         max_concurrent_calls,
+        max_message_size,
+        process,
+        slow_start,
+        state_quota,
     })
 }
 
+/// Parse the supervised process settings of an agent
+///
+/// Returns `None` when the agent has no `command`, i.e. it is managed
+/// externally.
+pub(crate) fn parse_agent_process(
+    node: &kdl::KdlNode,
+    agent_id: &str,
+) -> Result<Option<AgentProcessConfig>> {
+    let Some(children) = node.children() else {
+        return Ok(None);
+    };
+    let Some(command_node) = children.get("command") else {
+        return Ok(None);
+    };
+
+    let command: Vec<String> = command_node
+        .entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+        .collect();
+    if command.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Agent '{}': command requires at least a program",
+            agent_id
+        ));
+    }
+
+    let mut process = AgentProcessConfig::new(command);
+
+    if let Some(policy) = get_string_entry(node, "restart-policy") {
+        process.restart_policy = match policy.as_str() {
+            "always" => AgentRestartPolicy::Always,
+            "on-failure" | "on_failure" => AgentRestartPolicy::OnFailure,
+            "never" => AgentRestartPolicy::Never,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Agent '{}': unknown restart policy '{}'. Valid policies: always, on-failure, never",
+                    agent_id,
+                    other
+                ))
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "restart-delay-ms") {
+        process.restart_delay_ms = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "max-restart-delay-ms") {
+        process.max_restart_delay_ms = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "max-restarts") {
+        process.max_restarts = v as u32;
+    }
+
+    if let Some(env) = children.get("env").and_then(|n| n.children()) {
+        for var in env.nodes() {
+            if let Some(value) = get_first_arg_string(var) {
+                process.env.insert(var.name().value().to_string(), value);
+            }
+        }
+    }
+
+    Ok(Some(process))
+}
+
+/// Parse the per-event timeout overrides of an agent (`event-timeouts` block)
+pub(crate) fn parse_agent_event_timeouts(
+    node: &kdl::KdlNode,
+    agent_id: &str,
+) -> Result<AgentEventTimeouts> {
+    let mut timeouts = AgentEventTimeouts::default();
+    let Some(block) = node.children().and_then(|c| c.get("event-timeouts")) else {
+        return Ok(timeouts);
+    };
+
+    for (name, slot) in [
+        ("headers", &mut timeouts.headers_ms),
+        ("body", &mut timeouts.body_ms),
+        ("websocket", &mut timeouts.websocket_ms),
+        ("guardrail", &mut timeouts.guardrail_ms),
+    ] {
+        match get_int_entry(block, name) {
+            Some(v) if v > 0 => *slot = Some(v as u64),
+            Some(v) => {
+                return Err(anyhow::anyhow!(
+                "Agent '{}': event-timeouts {} must be a positive number of milliseconds, got {}",
+                agent_id,
+                name,
+                v
+            ))
+            }
+            None => {}
+        }
+    }
+
+    Ok(timeouts)
+}
+
+/// Parse the slow-start settings of an agent (`slow-start` block)
+pub(crate) fn parse_agent_slow_start(node: &kdl::KdlNode) -> Option<AgentSlowStartConfig> {
+    let block = node.children()?.get("slow-start")?;
+    let defaults = AgentSlowStartConfig::default();
+    Some(AgentSlowStartConfig {
+        window_ms: get_int_entry(block, "window-ms")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.window_ms),
+        warmup: get_bool_entry(block, "warmup").unwrap_or(defaults.warmup),
+    })
+}
+
+/// Parse the connection pool settings of an agent (`pool` block)
+pub(crate) fn parse_agent_pool(
+    node: &kdl::KdlNode,
+    agent_id: &str,
+) -> Result<Option<AgentPoolConfig>> {
+    let Some(block) = node.children().and_then(|c| c.get("pool")) else {
+        return Ok(None);
+    };
+    let defaults = AgentPoolConfig::default();
+
+    let load_balance_strategy = match get_string_entry(block, "load-balance-strategy").as_deref()
+    {
+        None => defaults.load_balance_strategy,
+        Some("round_robin" | "round-robin") => LoadBalanceStrategy::RoundRobin,
+        Some("least_connections" | "least-connections") => LoadBalanceStrategy::LeastConnections,
+        Some("health_based" | "health-based") => LoadBalanceStrategy::HealthBased,
+        Some("random") => LoadBalanceStrategy::Random,
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Agent '{}': unknown pool load-balance-strategy '{}'. Valid strategies: round_robin, least_connections, health_based, random",
+                agent_id,
+                other
+            ))
+        }
+    };
+
+    Ok(Some(AgentPoolConfig {
+        connections_per_agent: get_int_entry(block, "connections-per-agent")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.connections_per_agent),
+        load_balance_strategy,
+        connect_timeout_ms: get_int_entry(block, "connect-timeout-ms")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.connect_timeout_ms),
+        reconnect_interval_ms: get_int_entry(block, "reconnect-interval-ms")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.reconnect_interval_ms),
+        max_reconnect_attempts: get_int_entry(block, "max-reconnect-attempts")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.max_reconnect_attempts),
+        drain_timeout_ms: get_int_entry(block, "drain-timeout-ms")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.drain_timeout_ms),
+        max_concurrent_per_connection: get_int_entry(block, "max-concurrent-per-connection")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.max_concurrent_per_connection),
+        health_check_interval_ms: get_int_entry(block, "health-check-interval-ms")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.health_check_interval_ms),
+        request_duration_buckets_us: parse_bucket_list(
+            block,
+            "request-duration-buckets-us",
+            agent_id,
+        )?,
+        serialization_buckets_us: parse_bucket_list(block, "serialization-buckets-us", agent_id)?,
+        shared_memory: parse_agent_shared_memory(block),
+    }))
+}
+
+/// Parse the shared-memory body transport of an agent pool (`shared-memory` block)
+fn parse_agent_shared_memory(pool: &kdl::KdlNode) -> Option<AgentSharedMemoryConfig> {
+    let block = pool.children()?.get("shared-memory")?;
+    let defaults = AgentSharedMemoryConfig::default();
+    Some(AgentSharedMemoryConfig {
+        dir: get_string_entry(block, "dir").map(PathBuf::from),
+        slot_count: get_int_entry(block, "slot-count")
+            .map(|v| v as u32)
+            .unwrap_or(defaults.slot_count),
+        slot_size: get_int_entry(block, "slot-size")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.slot_size),
+        min_chunk_size: get_int_entry(block, "min-chunk-size")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.min_chunk_size),
+        mode: get_int_entry(block, "mode")
+            .map(|v| v as u32)
+            .unwrap_or(defaults.mode),
+    })
+}
+
+/// Read the arguments of a histogram bucket node (`name 100 500 1000`)
+fn parse_bucket_list(block: &kdl::KdlNode, name: &str, agent_id: &str) -> Result<Option<Vec<u64>>> {
+    let Some(node) = block.children().and_then(|c| c.get(name)) else {
+        return Ok(None);
+    };
+    node.entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .map(|e| {
+            e.value()
+                .as_integer()
+                .and_then(|v| u64::try_from(v).ok())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Agent '{}': pool {} must be a list of non-negative integers, got {}",
+                        agent_id,
+                        name,
+                        e.value()
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Parse the session state limits of an agent (`state-quota` block)
+pub(crate) fn parse_agent_state_quota(node: &kdl::KdlNode) -> AgentStateQuota {
+    let defaults = AgentStateQuota::default();
+    let Some(block) = node.children().and_then(|c| c.get("state-quota")) else {
+        return defaults;
+    };
+    AgentStateQuota {
+        max_bytes: get_int_entry(block, "max-bytes")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.max_bytes),
+        max_value_bytes: get_int_entry(block, "max-value-bytes")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.max_value_bytes),
+    }
+}
+
 /// Parse body streaming mode from string
 fn parse_body_streaming_mode(mode: &str, agent_id: &str) -> Result<BodyStreamingMode> {
     match mode {
@@ -1065,6 +1378,291 @@ pub fn parse_cache_config(node: &kdl::KdlNode) -> Result<CacheStorageConfig> {
     Ok(config)
 }
 
+// ============================================================================
+// Session Store Parsing
+// ============================================================================
+
+use crate::filters::RedisBackendConfig;
+
+/// Parse session-store configuration block
+///
+/// KDL format:
+/// ```kdl
+/// session-store {
+///     backend "memory"          // "memory" or "redis"
+///     default-ttl-secs 3600
+///     max-entries 100000        // Memory backend only
+///     redis-url "redis://127.0.0.1:6379"
+///     redis-prefix "zentinel:state:"
+///     redis-timeout-ms 50
+/// }
+/// ```
+pub fn parse_session_store_config(node: &kdl::KdlNode) -> Result<SessionStoreConfig> {
+    let mut config = SessionStoreConfig::default();
+
+    let backend = get_string_entry(node, "backend").unwrap_or_else(|| "memory".to_string());
+    config.backend = match backend.as_str() {
+        "memory" => SessionStoreBackend::Memory,
+        "redis" => SessionStoreBackend::Redis(RedisBackendConfig {
+            url: get_string_entry(node, "redis-url")
+                .unwrap_or_else(|| "redis://127.0.0.1:6379".to_string()),
+            key_prefix: get_string_entry(node, "redis-prefix")
+                .unwrap_or_else(|| "zentinel:state:".to_string()),
+            timeout_ms: get_int_entry(node, "redis-timeout-ms")
+                .map(|v| v as u64)
+                .unwrap_or(50),
+            ..Default::default()
+        }),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Invalid session-store backend '{}'. Valid options: memory, redis",
+                other
+            ));
+        }
+    };
+
+    if let Some(v) = get_int_entry(node, "default-ttl-secs") {
+        config.default_ttl_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "max-entries") {
+        config.max_entries = v as usize;
+    }
+
+    Ok(config)
+}
+
+// ============================================================================
+// DNS Parsing
+// ============================================================================
+
+use crate::upstreams::{DnsConfig, IpFamilyPreference};
+
+/// Parse dns configuration block
+///
+/// KDL format:
+/// ```kdl
+/// dns {
+///     nameservers "10.0.0.2" "10.0.0.3"
+///     timeout-ms 2000
+///     attempts 2
+///     min-ttl-secs 1
+///     max-ttl-secs 300
+///     negative-ttl-secs 5
+///     ip-family "prefer-ipv6"   // ipv4-only, ipv6-only, prefer-ipv4, prefer-ipv6
+///     happy-eyeballs true
+///     happy-eyeballs-delay-ms 250
+/// }
+/// ```
+pub fn parse_dns_config(node: &kdl::KdlNode) -> Result<DnsConfig> {
+    let mut config = DnsConfig::default();
+
+    if let Some(children) = node.children() {
+        if let Some(servers) = children.get("nameservers") {
+            for entry in servers.entries() {
+                let Some(server) = entry.value().as_string() else {
+                    continue;
+                };
+                let ip = server.parse().map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid DNS nameserver '{}': expected an IP address",
+                        server
+                    )
+                })?;
+                config.nameservers.push(ip);
+            }
+        }
+    }
+
+    if let Some(v) = get_int_entry(node, "timeout-ms") {
+        config.timeout_ms = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "attempts") {
+        config.attempts = v as usize;
+    }
+    if let Some(v) = get_int_entry(node, "min-ttl-secs") {
+        config.min_ttl_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "max-ttl-secs") {
+        config.max_ttl_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "negative-ttl-secs") {
+        config.negative_ttl_secs = v as u64;
+    }
+    if let Some(family) = get_string_entry(node, "ip-family") {
+        config.ip_family = match family.as_str() {
+            "ipv4-only" => IpFamilyPreference::Ipv4Only,
+            "ipv6-only" => IpFamilyPreference::Ipv6Only,
+            "prefer-ipv4" => IpFamilyPreference::PreferIpv4,
+            "prefer-ipv6" => IpFamilyPreference::PreferIpv6,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid dns ip-family '{}'. Valid options: ipv4-only, ipv6-only, \
+                     prefer-ipv4, prefer-ipv6",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_bool_entry(node, "happy-eyeballs") {
+        config.happy_eyeballs = v;
+    }
+    if let Some(v) = get_int_entry(node, "happy-eyeballs-delay-ms") {
+        config.happy_eyeballs_delay_ms = v as u64;
+    }
+
+    if config.min_ttl_secs > config.max_ttl_secs {
+        return Err(anyhow::anyhow!(
+            "dns min-ttl-secs ({}) must not exceed max-ttl-secs ({})",
+            config.min_ttl_secs,
+            config.max_ttl_secs
+        ));
+    }
+
+    Ok(config)
+}
+
+// ============================================================================
+// Notifications Parsing
+// ============================================================================
+
+use crate::notifications::{
+    AlertRuleConfig, NotificationEvent, NotificationSeverity, NotificationsConfig, WebhookConfig,
+};
+
+/// Parse notifications configuration block
+///
+/// KDL format:
+/// ```kdl
+/// notifications {
+///     cooldown-secs 300
+///     certificate-expiry-days 14
+///     guardrail-min-severity "high"   // low, medium, high, critical
+///     block-rate {
+///         threshold 0.25
+///         min-requests 100
+///         window-secs 60
+///     }
+///     webhook "security" {
+///         url "https://hooks.example.com/zentinel"
+///         secret "${env:WEBHOOK_SECRET}"
+///         events "circuit-breaker-open" "agent-unhealthy"   // default: all
+///         timeout-ms 5000
+///         max-retries 3
+///         retry-backoff-ms 1000
+///     }
+/// }
+/// ```
+pub fn parse_notifications_config(node: &kdl::KdlNode) -> Result<NotificationsConfig> {
+    let mut config = NotificationsConfig::default();
+
+    if let Some(v) = get_int_entry(node, "cooldown-secs") {
+        config.cooldown_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "certificate-expiry-days") {
+        config.certificate_expiry_days = v as u32;
+    }
+    if let Some(severity) = get_string_entry(node, "guardrail-min-severity") {
+        config.guardrail_min_severity =
+            NotificationSeverity::from_name(&severity).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid notifications guardrail-min-severity '{}'. Valid options: low, \
+                     medium, high, critical",
+                    severity
+                )
+            })?;
+    }
+
+    let Some(children) = node.children() else {
+        return Ok(config);
+    };
+    if let Some(block_rate) = children.get("block-rate") {
+        if let Some(v) = helpers::get_float_entry(block_rate, "threshold") {
+            config.block_rate.threshold = v;
+        }
+        if let Some(v) = get_int_entry(block_rate, "min-requests") {
+            config.block_rate.min_requests = v as u64;
+        }
+        if let Some(v) = get_int_entry(block_rate, "window-secs") {
+            config.block_rate.window_secs = v as u64;
+        }
+    }
+
+    for alert_node in children.nodes() {
+        if alert_node.name().value() != "alert" {
+            continue;
+        }
+        let name = get_first_arg_string(alert_node)
+            .ok_or_else(|| anyhow::anyhow!("Alert rule requires a name argument"))?;
+        let condition = get_string_entry(alert_node, "condition")
+            .ok_or_else(|| anyhow::anyhow!("Alert rule '{}' requires a 'condition'", name))?;
+        let mut rule = AlertRuleConfig::from_condition(&name, &condition)
+            .map_err(|e| anyhow::anyhow!("Alert rule '{}': {}", name, e))?;
+        if let Some(v) = get_int_entry(alert_node, "for-secs") {
+            rule.for_secs = v as u64;
+        }
+        if let Some(v) = get_int_entry(alert_node, "window-secs") {
+            rule.window_secs = v as u64;
+        }
+        if let Some(severity) = get_string_entry(alert_node, "severity") {
+            rule.severity = NotificationSeverity::from_name(&severity).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid severity '{}' in alert rule '{}'. Valid options: low, medium, \
+                     high, critical",
+                    severity,
+                    name
+                )
+            })?;
+        }
+        config.alerts.push(rule);
+    }
+
+    for webhook_node in children.nodes() {
+        if webhook_node.name().value() != "webhook" {
+            continue;
+        }
+        let id = get_first_arg_string(webhook_node)
+            .ok_or_else(|| anyhow::anyhow!("Notification webhook requires an ID argument"))?;
+        let url = get_string_entry(webhook_node, "url")
+            .ok_or_else(|| anyhow::anyhow!("Notification webhook '{}' requires a 'url'", id))?;
+
+        let mut events = Vec::new();
+        if let Some(events_node) = webhook_node.children().and_then(|c| c.get("events")) {
+            for entry in events_node.entries() {
+                let Some(name) = entry.value().as_string() else {
+                    continue;
+                };
+                let event = NotificationEvent::from_name(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown event '{}' in notification webhook '{}'. Valid events: \
+                         circuit-breaker-open, agent-unhealthy, block-rate-spike, \
+                         certificate-expiry, guardrail-detection, alert-firing, alert-resolved, \
+                         config-rolled-back",
+                        name,
+                        id
+                    )
+                })?;
+                events.push(event);
+            }
+        }
+
+        let mut webhook = WebhookConfig::new(id, url);
+        webhook.secret = get_string_entry(webhook_node, "secret");
+        webhook.events = events;
+        if let Some(v) = get_int_entry(webhook_node, "timeout-ms") {
+            webhook.timeout_ms = v as u64;
+        }
+        if let Some(v) = get_int_entry(webhook_node, "max-retries") {
+            webhook.max_retries = v as u32;
+        }
+        if let Some(v) = get_int_entry(webhook_node, "retry-backoff-ms") {
+            webhook.retry_backoff_ms = v as u64;
+        }
+        config.webhooks.push(webhook);
+    }
+
+    Ok(config)
+}
+
 // ============================================================================
 // Rate Limits Parsing
 // ============================================================================
@@ -1181,6 +1779,12 @@ fn parse_rate_limit_key(key: &str) -> Result<RateLimitKey> {
 ///             log-blocked true
 ///             log-agent-decisions true
 ///             log-waf-events true
+///             store {
+///                 backend "sqlite"
+///                 path "/var/lib/zentinel/audit.db"
+///                 max-records 1000000
+///                 retention-secs 604800
+///             }
 ///         }
 ///     }
 /// }
@@ -1239,6 +1843,9 @@ fn parse_logging_config(node: &kdl::KdlNode) -> Result<crate::observability::Log
                 "audit-log" => {
                     config.audit_log = Some(parse_audit_log_config(child)?);
                 }
+                "journal" => {
+                    config.journal = Some(parse_log_journal_config(child)?);
+                }
                 _ => {
                     trace!(name = %name, "Unknown logging config block, ignoring");
                 }
@@ -1320,6 +1927,99 @@ fn parse_audit_log_config(node: &kdl::KdlNode) -> Result<crate::observability::A
     if let Some(log_waf) = get_bool_entry(node, "log-waf-events") {
         config.log_waf_events = log_waf;
     }
+    if let Some(store) = node.children().and_then(|c| c.get("store")) {
+        config.store = Some(parse_audit_store_config(store)?);
+    }
+
+    Ok(config)
+}
+
+/// Parse the audit store block inside `audit-log`
+fn parse_audit_store_config(node: &kdl::KdlNode) -> Result<crate::observability::AuditStoreConfig> {
+    use crate::observability::{AuditStoreBackend, AuditStoreConfig};
+
+    let mut config = AuditStoreConfig::default();
+
+    let backend = get_string_entry(node, "backend").unwrap_or_else(|| "memory".to_string());
+    config.backend = match backend.as_str() {
+        "memory" => AuditStoreBackend::Memory,
+        "sqlite" => AuditStoreBackend::Sqlite {
+            path: get_string_entry(node, "path")
+                .map(std::path::PathBuf::from)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("The sqlite audit store requires a 'path' to the database file")
+                })?,
+        },
+        other => {
+            return Err(anyhow::anyhow!(
+                "Invalid audit store backend '{}'. Valid options: memory, sqlite",
+                other
+            ));
+        }
+    };
+
+    if let Some(v) = get_int_entry(node, "max-records") {
+        if v <= 0 {
+            return Err(anyhow::anyhow!(
+                "Audit store 'max-records' must be greater than 0, got {}",
+                v
+            ));
+        }
+        config.max_records = v as usize;
+    }
+    if let Some(v) = get_int_entry(node, "retention-secs") {
+        if v <= 0 {
+            return Err(anyhow::anyhow!(
+                "Audit store 'retention-secs' must be greater than 0, got {}",
+                v
+            ));
+        }
+        config.retention_secs = v as u64;
+    }
+
+    Ok(config)
+}
+
+/// Parse the log journal block inside `logging`
+fn parse_log_journal_config(node: &kdl::KdlNode) -> Result<crate::observability::LogJournalConfig> {
+    use crate::observability::{JournalFsync, LogJournalConfig};
+
+    let mut config = LogJournalConfig::default();
+
+    if let Some(path) = get_string_entry(node, "path") {
+        config.path = std::path::PathBuf::from(path);
+    }
+    if let Some(fsync) = get_string_entry(node, "fsync") {
+        config.fsync = match fsync.as_str() {
+            "always" => JournalFsync::Always,
+            "interval" => JournalFsync::Interval,
+            "never" => JournalFsync::Never,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Invalid journal fsync policy '{}'. Valid options: always, interval, never",
+                    other
+                ));
+            }
+        };
+    }
+    if let Some(v) = get_int_entry(node, "fsync-interval-ms") {
+        if v <= 0 {
+            return Err(anyhow::anyhow!(
+                "Journal 'fsync-interval-ms' must be greater than 0, got {}",
+                v
+            ));
+        }
+        config.fsync_interval_ms = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "max-bytes") {
+        if v <= 0 {
+            return Err(anyhow::anyhow!(
+                "Journal 'max-bytes' must be greater than 0, got {}",
+                v
+            ));
+        }
+        config.max_bytes = v as u64;
+    }
 
     Ok(config)
 }
@@ -1342,6 +2042,13 @@ fn parse_metrics_config(node: &kdl::KdlNode) -> Result<crate::observability::Met
     if let Some(high_cardinality) = get_bool_entry(node, "high-cardinality") {
         config.high_cardinality = high_cardinality;
     }
+    if let Some(tag_labels) = node.children().and_then(|c| c.get("tag-labels")) {
+        config.tag_labels = tag_labels
+            .entries()
+            .iter()
+            .filter_map(|e| e.value().as_string().map(str::to_string))
+            .collect();
+    }
 
     Ok(config)
 }
@@ -1862,6 +2569,159 @@ mod tests {
         }
     }
 
+    // =========================================================================
+    // Session Store Configuration Tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_session_store_config() {
+        let doc: kdl::KdlDocument = "session-store {}".parse().unwrap();
+        let config = parse_session_store_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(config, SessionStoreConfig::default());
+
+        let kdl = r#"
+            session-store {
+                backend "redis"
+                default-ttl-secs 600
+                redis-url "redis://cache:6379"
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_session_store_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(config.default_ttl_secs, 600);
+        match config.backend {
+            SessionStoreBackend::Redis(redis) => {
+                assert_eq!(redis.url, "redis://cache:6379");
+                assert_eq!(redis.key_prefix, "zentinel:state:");
+            }
+            other => panic!("Expected Redis backend, got {:?}", other),
+        }
+
+        let doc: kdl::KdlDocument = r#"session-store { backend "etcd" }"#.parse().unwrap();
+        assert!(parse_session_store_config(doc.nodes().first().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_dns_config() {
+        let doc: kdl::KdlDocument = "dns {}".parse().unwrap();
+        let config = parse_dns_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(config, DnsConfig::default());
+
+        let kdl = r#"
+            dns {
+                nameservers "10.0.0.2" "fd00::53"
+                min-ttl-secs 5
+                max-ttl-secs 60
+                negative-ttl-secs 0
+                ip-family "prefer-ipv4"
+                happy-eyeballs false
+                happy-eyeballs-delay-ms 100
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_dns_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(
+            config.nameservers,
+            vec![
+                "10.0.0.2".parse::<std::net::IpAddr>().unwrap(),
+                "fd00::53".parse().unwrap()
+            ]
+        );
+        assert_eq!((config.min_ttl_secs, config.max_ttl_secs), (5, 60));
+        assert_eq!(config.negative_ttl_secs, 0);
+        assert_eq!(config.ip_family, IpFamilyPreference::PreferIpv4);
+        assert!(!config.happy_eyeballs);
+        assert_eq!(config.happy_eyeballs_delay_ms, 100);
+
+        for invalid in [
+            r#"dns { nameservers "dns.example.com" }"#,
+            r#"dns { ip-family "ipv5" }"#,
+            "dns { min-ttl-secs 600 }",
+        ] {
+            let doc: kdl::KdlDocument = invalid.parse().unwrap();
+            assert!(parse_dns_config(doc.nodes().first().unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_notifications_config() {
+        let kdl = r#"
+            notifications {
+                cooldown-secs 60
+                guardrail-min-severity "critical"
+                block-rate {
+                    threshold 0.5
+                    window-secs 30
+                }
+                webhook "security" {
+                    url "https://hooks.example.com/zentinel"
+                    secret "s3cret"
+                    events "circuit-breaker-open" "certificate-expiry"
+                    max-retries 5
+                }
+                webhook "audit" {
+                    url "http://audit.internal/events"
+                }
+                alert "agent-timeouts" {
+                    condition "agent_timeout_rate > 5%"
+                    for-secs 300
+                    severity "critical"
+                }
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_notifications_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(config.cooldown_secs, 60);
+        assert_eq!(config.certificate_expiry_days, 14);
+        assert_eq!(
+            config.guardrail_min_severity,
+            NotificationSeverity::Critical
+        );
+        assert_eq!(config.block_rate.threshold, 0.5);
+        assert_eq!(config.block_rate.min_requests, 100);
+        assert_eq!(config.block_rate.window_secs, 30);
+
+        assert_eq!(config.webhooks.len(), 2);
+        let security = &config.webhooks[0];
+        assert_eq!(security.id, "security");
+        assert_eq!(security.secret.as_deref(), Some("s3cret"));
+        assert_eq!(security.max_retries, 5);
+        assert!(security.wants(NotificationEvent::CertificateExpiry));
+        assert!(!security.wants(NotificationEvent::BlockRateSpike));
+        assert!(config.webhooks[1].wants(NotificationEvent::BlockRateSpike));
+
+        assert_eq!(config.alerts.len(), 1);
+        let alert = &config.alerts[0];
+        assert_eq!(alert.name, "agent-timeouts");
+        assert_eq!(
+            alert.metric,
+            crate::notifications::AlertMetric::AgentTimeoutRate
+        );
+        assert!((alert.threshold - 0.05).abs() < 1e-9);
+        assert_eq!(alert.for_secs, 300);
+        assert_eq!(alert.window_secs, 300);
+        assert_eq!(alert.severity, NotificationSeverity::Critical);
+
+        for invalid in [
+            r#"notifications { webhook "a" { url "http://x" events "nope" } }"#,
+            r#"notifications { webhook "a" }"#,
+            r#"notifications { guardrail-min-severity "severe" }"#,
+            r#"notifications { alert "a" { condition "p99_latency > 1" } }"#,
+            r#"notifications { alert "a" }"#,
+        ] {
+            let doc: kdl::KdlDocument = invalid.parse().unwrap();
+            assert!(parse_notifications_config(doc.nodes().first().unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_metrics_tag_labels() {
+        let kdl = r#"metrics { tag-labels "tier" "bot_class" }"#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_metrics_config(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(config.tag_labels, vec!["tier", "bot_class"]);
+    }
+
     // =========================================================================
     // Cache Configuration Tests
     // =========================================================================
@@ -2082,6 +2942,7 @@ mod tests {
                     unix-socket path="/tmp/waf.sock"
                     events "request_headers" "request_body"
                     max-concurrent-calls 50
+                    max-message-size 33554432
                 }
                 agent "auth" type="auth" {
                     unix-socket path="/tmp/auth.sock"
@@ -2106,10 +2967,12 @@ mod tests {
         // Verify custom max-concurrent-calls
         let waf_agent = config.agents.iter().find(|a| a.id == "waf").unwrap();
         assert_eq!(waf_agent.max_concurrent_calls, 50);
+        assert_eq!(waf_agent.max_message_size, Some(32 * 1024 * 1024));
 
         // Verify default max-concurrent-calls (100)
         let auth_agent = config.agents.iter().find(|a| a.id == "auth").unwrap();
         assert_eq!(auth_agent.max_concurrent_calls, 100);
+        assert_eq!(auth_agent.max_message_size, None);
     }
 
     #[test]
@@ -2723,6 +3586,272 @@ mod tests {
         assert!(mapping.provider.is_none()); // No provider override
     }
 
+    #[test]
+    fn test_parse_inference_key_pool() {
+        let kdl = r#"
+            listeners {
+                listener "http" {
+                    address "0.0.0.0:8080"
+                    protocol "http"
+                }
+            }
+
+            upstreams {
+                upstream "openai-us" {
+                    target "api.openai.com:443"
+                }
+                upstream "anthropic" {
+                    target "api.anthropic.com:443"
+                }
+            }
+
+            routes {
+                route "chat" {
+                    matches {
+                        path-prefix "/v1/chat/completions"
+                    }
+                    service-type "inference"
+                    upstream "openai-us"
+
+                    inference {
+                        provider "openai"
+
+                        key-pool {
+                            cooldown-secs 30
+                            failover-status 429 503
+                            member "openai-a" upstream="openai-us" key="sk-a" weight=3 requests-per-minute=500
+                            member "claude" upstream="anthropic" key="sk-ant" provider="anthropic" tokens-per-minute=40000
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let config = Config::from_kdl(kdl).expect("Failed to parse key pool KDL");
+        let pool = config.routes[0]
+            .inference
+            .as_ref()
+            .and_then(|i| i.key_pool.as_ref())
+            .expect("Key pool not found");
+
+        assert_eq!(pool.cooldown_secs, 30);
+        assert_eq!(pool.failover_status, vec![429, 503]);
+        assert_eq!(pool.max_attempts, 3);
+        assert_eq!(pool.members.len(), 2);
+
+        let openai = &pool.members[0];
+        assert_eq!(openai.id, "openai-a");
+        assert_eq!(openai.upstream, "openai-us");
+        assert_eq!(openai.key, "sk-a");
+        assert_eq!(openai.provider, None);
+        assert_eq!(openai.weight, 3);
+        assert_eq!(openai.requests_per_minute, Some(500));
+
+        let claude = &pool.members[1];
+        assert_eq!(claude.provider, Some(crate::InferenceProvider::Anthropic));
+        assert_eq!(claude.weight, 1);
+        assert_eq!(claude.tokens_per_minute, Some(40000));
+    }
+
+    #[test]
+    fn test_parse_cost_budget() {
+        let kdl = r#"
+            listeners {
+                listener "http" {
+                    address "0.0.0.0:8080"
+                    protocol "http"
+                }
+            }
+
+            upstreams {
+                upstream "openai" {
+                    target "api.openai.com:443"
+                }
+            }
+
+            routes {
+                route "chat" {
+                    matches {
+                        path-prefix "/v1/chat/completions"
+                    }
+                    service-type "inference"
+                    upstream "openai"
+
+                    inference {
+                        cost-attribution {
+                            pricing {
+                                model "gpt-4o" {
+                                    input-cost-per-million 2.5
+                                    output-cost-per-million 10.0
+                                }
+                            }
+                            budget {
+                                monthly-limit 250
+                                scope "api-key"
+                                action "downgrade"
+                                downgrade-model "gpt-4o-mini"
+                                alert-thresholds 0.5 0.9
+                            }
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let config = Config::from_kdl(kdl).expect("Failed to parse cost budget KDL");
+        let budget = config.routes[0]
+            .inference
+            .as_ref()
+            .and_then(|i| i.cost_attribution.as_ref())
+            .and_then(|c| c.budget.as_ref())
+            .expect("Cost budget not found");
+
+        assert_eq!(budget.monthly_limit, 250.0);
+        assert_eq!(budget.scope, crate::CostBudgetScope::ApiKey);
+        assert_eq!(
+            budget.action,
+            crate::CostBudgetAction::Downgrade {
+                model: "gpt-4o-mini".to_string()
+            }
+        );
+        assert_eq!(budget.alert_thresholds, vec![0.5, 0.9]);
+    }
+
+    #[test]
+    fn test_parse_guardrail_embedding() {
+        let kdl = r#"
+            listeners {
+                listener "http" {
+                    address "0.0.0.0:8080"
+                    protocol "http"
+                }
+            }
+
+            upstreams {
+                upstream "openai" {
+                    target "api.openai.com:443"
+                }
+            }
+
+            routes {
+                route "chat" {
+                    matches {
+                        path-prefix "/v1/chat/completions"
+                    }
+                    service-type "inference"
+                    upstream "openai"
+
+                    inference {
+                        guardrails {
+                            prompt-injection {
+                                enabled #true
+                                agent "jailbreak-agent"
+                            }
+                            embedding {
+                                endpoint "http://embeddings.internal/v1/embeddings"
+                                model "text-embedding-3-small"
+                                timeout-ms 150
+                            }
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let config = Config::from_kdl(kdl).expect("Failed to parse guardrail embedding KDL");
+        let embedding = config.routes[0]
+            .inference
+            .as_ref()
+            .and_then(|i| i.guardrails.as_ref())
+            .and_then(|g| g.embedding.as_ref())
+            .expect("Guardrail embedding not found");
+
+        assert_eq!(
+            embedding.endpoint,
+            "http://embeddings.internal/v1/embeddings"
+        );
+        assert_eq!(embedding.model, "text-embedding-3-small");
+        assert_eq!(embedding.api_key, None);
+        assert_eq!(embedding.timeout_ms, 150);
+    }
+
+    #[test]
+    fn test_parse_audit_store_config() {
+        use crate::observability::AuditStoreBackend;
+
+        let kdl = r#"
+        observability {
+            logging {
+                audit-log {
+                    file "/tmp/audit.log"
+                    store {
+                        backend "sqlite"
+                        path "/var/lib/zentinel/audit.db"
+                        max-records 5000
+                    }
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_observability_config(doc.get("observability").unwrap()).unwrap();
+        let store = config.logging.audit_log.unwrap().store.unwrap();
+        assert_eq!(
+            store.backend,
+            AuditStoreBackend::Sqlite {
+                path: "/var/lib/zentinel/audit.db".into()
+            }
+        );
+        assert_eq!(store.max_records, 5000);
+        assert_eq!(store.retention_secs, 7 * 24 * 60 * 60);
+
+        let kdl = r#"
+        audit-log {
+            store {
+                backend "sqlite"
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let err = parse_audit_log_config(doc.get("audit-log").unwrap()).unwrap_err();
+        assert!(err.to_string().contains("requires a 'path'"));
+    }
+
+    #[test]
+    fn test_parse_log_journal_config() {
+        use crate::observability::JournalFsync;
+
+        let kdl = r#"
+        observability {
+            logging {
+                journal {
+                    path "/tmp/logs.journal"
+                    fsync "always"
+                    max-bytes 1048576
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let config = parse_observability_config(doc.get("observability").unwrap()).unwrap();
+        let journal = config.logging.journal.unwrap();
+        assert_eq!(journal.path, std::path::PathBuf::from("/tmp/logs.journal"));
+        assert_eq!(journal.fsync, JournalFsync::Always);
+        assert_eq!(journal.fsync_interval_ms, 1000);
+        assert_eq!(journal.max_bytes, 1048576);
+
+        let kdl = r#"
+        journal {
+            fsync "sometimes"
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let err = parse_log_journal_config(doc.get("journal").unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Invalid journal fsync policy"));
+    }
+
     #[test]
     fn test_parse_waf_config_basic() {
         let kdl = r#"
@@ -2951,10 +4080,80 @@ mod tests {
 
         assert_eq!(
             format!("{}", err_msg),
-            "Agent 'waf-agent' requires a transport (unix-socket, grpc, or http)"
+            "Agent 'waf-agent' requires a transport (unix-socket, grpc, http, or in-process)"
         );
     }
 
+    #[test]
+    fn test_parse_single_agent_in_process() {
+        let kdl = r#"
+        agent "denylist" {
+            in-process
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let agent = parse_single_agent(doc.get("agent").unwrap()).unwrap();
+        assert!(matches!(agent.transport, AgentTransport::InProcess));
+
+        let kdl = r#"
+        agent "denylist" {
+            in-process
+            command "zentinel-denylist-agent"
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_single_agent(doc.get("agent").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_single_agent_process() {
+        let kdl = r#"
+        agent "waf" type="waf" {
+            command "zentinel-waf-agent" "--socket" "{socket}"
+            restart-policy "always"
+            restart-delay-ms 500
+            max-restarts 3
+            env {
+                RUST_LOG "debug"
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let agent = parse_single_agent(doc.get("agent").unwrap()).unwrap();
+
+        // Supervised agents default to a Unix socket
+        assert!(matches!(
+            agent.transport,
+            AgentTransport::UnixSocket { ref path } if path == &PathBuf::from("/var/run/zentinel/waf.sock")
+        ));
+
+        let process = agent.process.unwrap();
+        assert_eq!(
+            process.command,
+            ["zentinel-waf-agent", "--socket", "{socket}"]
+        );
+        assert_eq!(process.restart_policy, AgentRestartPolicy::Always);
+        assert_eq!(process.restart_delay_ms, 500);
+        assert_eq!(process.max_restart_delay_ms, 30000);
+        assert_eq!(process.max_restarts, 3);
+        assert_eq!(
+            process.env.get("RUST_LOG").map(String::as_str),
+            Some("debug")
+        );
+    }
+
+    #[test]
+    fn test_parse_single_agent_process_invalid_policy() {
+        let kdl = r#"
+        agent "waf" {
+            command "zentinel-waf-agent"
+            restart-policy "sometimes"
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_single_agent(doc.get("agent").unwrap()).is_err());
+    }
+
     ///Check if we have a CBconfig when one is specified
     #[test]
     fn test_parse_single_agent_config_circuit_breaker() {
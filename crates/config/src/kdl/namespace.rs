@@ -198,7 +198,7 @@ pub fn parse_service(node: &kdl::KdlNode) -> Result<ServiceConfig> {
 /// }
 /// ```
 fn parse_single_listener(node: &kdl::KdlNode) -> Result<ListenerConfig> {
-    use super::helpers::{get_int_entry, get_string_entry};
+    use super::helpers::{get_bool_entry, get_int_entry, get_string_entry};
     use crate::server::ListenerProtocol;
 
     // Get ID from first argument or from 'id' property in node
@@ -254,6 +254,31 @@ fn parse_single_listener(node: &kdl::KdlNode) -> Result<ListenerConfig> {
             .map(|v| v as u32)
             .unwrap_or(100),
         keepalive_max_requests: get_int_entry(node, "keepalive-max-requests").map(|v| v as u32),
+        proxy_protocol: get_bool_entry(node, "proxy-protocol").unwrap_or(false),
+        bot_signals: get_bool_entry(node, "bot-signals").unwrap_or(false),
+        quic: node
+            .children()
+            .and_then(|children| children.get("quic"))
+            .map(super::server::parse_quic_config)
+            .transpose()
+            .context("Failed to parse QUIC config")?
+            .unwrap_or_default(),
+        slow_client: node
+            .children()
+            .and_then(|children| children.get("slow-client"))
+            .map(super::server::parse_slow_client_config)
+            .transpose()
+            .context("Failed to parse slow-client config")?
+            .unwrap_or_default(),
+        hardening: node
+            .children()
+            .and_then(|children| children.get("hardening"))
+            .map(super::server::parse_hardening_config)
+            .transpose()
+            .context("Failed to parse hardening config")?
+            .unwrap_or_default(),
+        forward_proxy: None,
+        stream: None,
     })
 }
 
@@ -313,7 +338,7 @@ fn parse_exports(node: &kdl::KdlNode) -> Result<ExportConfig> {
 /// ```kdl
 /// upstreams "shared-auth" "shared-cache" "another"
 /// ```
-fn parse_string_list(node: &kdl::KdlNode) -> Vec<String> {
+pub(super) fn parse_string_list(node: &kdl::KdlNode) -> Vec<String> {
     node.entries()
         .iter()
         .filter_map(|entry| {
@@ -6,9 +6,12 @@ use std::path::PathBuf;
 use tracing::{trace, warn};
 
 use zentinel_common::budget::{
-    BudgetPeriod, CostAttributionConfig, ModelPricing, TokenBudgetConfig,
+    BudgetPeriod, CostAttributionConfig, CostBudgetAction, CostBudgetConfig, CostBudgetScope,
+    ModelPricing, TokenBudgetConfig,
 };
 
+use crate::expr::Expression;
+use crate::server::SlowClientConfig;
 use crate::{kdl::retrypolicy_helper::parse_retry_policy, routes::*};
 
 use super::helpers::{
@@ -22,6 +25,7 @@ const RECOGNIZED_ROUTE_CHILDREN: &[&str] = &[
     "priority",
     "upstream",
     "static-files",
+    "error-pages",
     "api-schema",
     "inference",
     "filters",
@@ -93,6 +97,22 @@ pub fn parse_routes(node: &kdl::KdlNode) -> Result<Vec<RouteConfig>> {
                         "upstreams" => Some(BuiltinHandler::Upstreams),
                         "cache-purge" | "cache_purge" => Some(BuiltinHandler::CachePurge),
                         "cache-stats" | "cache_stats" => Some(BuiltinHandler::CacheStats),
+                        "agents" => Some(BuiltinHandler::Agents),
+                        "tenants" => Some(BuiltinHandler::Tenants),
+                        "maintenance" => Some(BuiltinHandler::Maintenance),
+                        "api-keys" | "api_keys" => Some(BuiltinHandler::ApiKeys),
+                        "chaos" => Some(BuiltinHandler::Chaos),
+                        "capture" => Some(BuiltinHandler::Capture),
+                        "audit" => Some(BuiltinHandler::Audit),
+                        "explain" => Some(BuiltinHandler::Explain),
+                        "quotas" => Some(BuiltinHandler::Quotas),
+                        "alerts" => Some(BuiltinHandler::Alerts),
+                        "tap" => Some(BuiltinHandler::Tap),
+                        "requests" => Some(BuiltinHandler::Requests),
+                        "connections" => Some(BuiltinHandler::Connections),
+                        "livez" => Some(BuiltinHandler::Livez),
+                        "readyz" => Some(BuiltinHandler::Readyz),
+                        "healthz" => Some(BuiltinHandler::Healthz),
                         _ => None,
                     });
 
@@ -148,6 +168,16 @@ pub fn parse_routes(node: &kdl::KdlNode) -> Result<Vec<RouteConfig>> {
                     request_headers,
                     response_headers,
                     cache: cache_config,
+                    agent_budget_ms: parse_agent_budget(child)?,
+                    decision_merge: parse_decision_merge(child)?,
+                    block_page: parse_block_page(child)?,
+                    challenge: parse_challenge(child)?,
+                    concurrency: parse_route_concurrency(child)?,
+                    slow_client: parse_route_slow_client(child)?,
+                    streaming_passthrough: parse_streaming_passthrough(child),
+                    body_framing: parse_body_framing(child)?,
+                    server_timing: parse_server_timing(child),
+                    priority_class: parse_priority_class(child)?,
                     ..RoutePolicies::default()
                 };
 
@@ -165,7 +195,7 @@ pub fn parse_routes(node: &kdl::KdlNode) -> Result<Vec<RouteConfig>> {
                     static_files,
                     api_schema,
                     inference,
-                    error_pages: None,
+                    error_pages: parse_error_pages_opt(child)?,
                     websocket: get_bool_entry(child, "websocket").unwrap_or(false),
                     websocket_inspection: get_bool_entry(child, "websocket-inspection")
                         .unwrap_or(false),
@@ -241,6 +271,19 @@ fn parse_match_conditions(node: &kdl::KdlNode) -> Result<Vec<MatchCondition>> {
                                 });
                             }
                         }
+                        "expr" => {
+                            if let Some(source) = get_first_arg_string(match_node) {
+                                let expr = Expression::parse(&source).map_err(|e| {
+                                    anyhow::anyhow!(
+                                        "Route '{}': invalid match expression '{}': {}",
+                                        get_first_arg_string(node).unwrap_or_default(),
+                                        source,
+                                        e
+                                    )
+                                })?;
+                                matches.push(MatchCondition::Expr(expr));
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -365,6 +408,309 @@ fn parse_route_header_policies(
     Ok((request_headers, response_headers))
 }
 
+/// Parse `agent-budget-ms` from the route's policies block.
+fn parse_agent_budget(node: &kdl::KdlNode) -> Result<Option<u64>> {
+    let Some(policies_node) = node.children().and_then(|c| c.get("policies")) else {
+        return Ok(None);
+    };
+    match get_int_entry(policies_node, "agent-budget-ms") {
+        None => Ok(None),
+        Some(ms) if ms > 0 => Ok(Some(ms as u64)),
+        Some(ms) => Err(anyhow::anyhow!(
+            "agent-budget-ms must be a positive number of milliseconds, got {}",
+            ms
+        )),
+    }
+}
+
+/// Parse `decision-merge` (and `decision-score-threshold`) from the route's
+/// policies block.
+fn parse_decision_merge(node: &kdl::KdlNode) -> Result<DecisionMergeStrategy> {
+    let Some(policies_node) = node.children().and_then(|c| c.get("policies")) else {
+        return Ok(DecisionMergeStrategy::default());
+    };
+    let strategy = match get_string_entry(policies_node, "decision-merge").as_deref() {
+        Some("first-block-wins") | Some("first_block_wins") | None => {
+            DecisionMergeStrategy::FirstBlockWins
+        }
+        Some("all-must-allow") | Some("all_must_allow") => DecisionMergeStrategy::AllMustAllow,
+        Some("score-based") | Some("score_based") => {
+            let threshold = get_float_entry(policies_node, "decision-score-threshold")
+                .map(|t| t as f32)
+                .unwrap_or(DEFAULT_DECISION_SCORE_THRESHOLD);
+            if threshold <= 0.0 {
+                return Err(anyhow::anyhow!(
+                    "decision-score-threshold must be positive, got {}",
+                    threshold
+                ));
+            }
+            DecisionMergeStrategy::ScoreBased { threshold }
+        }
+        Some("priority-override") | Some("priority_override") => {
+            DecisionMergeStrategy::PriorityOverride
+        }
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Unknown decision merge strategy '{}'. Valid strategies: first-block-wins, all-must-allow, score-based, priority-override",
+                other
+            ));
+        }
+    };
+    Ok(strategy)
+}
+
+/// Parse the `block-page` block from the route's policies block.
+fn parse_block_page(node: &kdl::KdlNode) -> Result<Option<BlockPageConfig>> {
+    let Some(block_page_node) = node
+        .children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| p.children())
+        .and_then(|c| c.get("block-page"))
+    else {
+        return Ok(None);
+    };
+    let default_format = match get_string_entry(block_page_node, "default-format").as_deref() {
+        Some("html") | None => BlockPageFormat::Html,
+        Some("json") => BlockPageFormat::Json,
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Unknown block page format '{}'. Valid formats: html, json",
+                other
+            ));
+        }
+    };
+    Ok(Some(BlockPageConfig {
+        html_template: get_string_entry(block_page_node, "html-template").map(PathBuf::from),
+        json_template: get_string_entry(block_page_node, "json-template").map(PathBuf::from),
+        default_format,
+    }))
+}
+
+/// Parse the `challenge` block from the route's policies block.
+fn parse_challenge(node: &kdl::KdlNode) -> Result<ChallengeConfig> {
+    let Some(challenge_node) = node
+        .children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| p.children())
+        .and_then(|c| c.get("challenge"))
+    else {
+        return Ok(ChallengeConfig::default());
+    };
+    let defaults = ChallengeConfig::default();
+
+    let pow_difficulty = match get_int_entry(challenge_node, "pow-difficulty") {
+        None => defaults.pow_difficulty,
+        Some(bits) if (1..=MAX_POW_DIFFICULTY as i128).contains(&bits) => bits as u8,
+        Some(bits) => {
+            return Err(anyhow::anyhow!(
+                "pow-difficulty must be between 1 and {} bits, got {}",
+                MAX_POW_DIFFICULTY,
+                bits
+            ));
+        }
+    };
+
+    Ok(ChallengeConfig {
+        cookie_name: get_string_entry(challenge_node, "cookie-name")
+            .unwrap_or(defaults.cookie_name),
+        ttl_secs: get_int_entry(challenge_node, "ttl-secs")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.ttl_secs),
+        pow_difficulty,
+    })
+}
+
+/// Parse the `concurrency` block from the route's policies block.
+fn parse_route_concurrency(node: &kdl::KdlNode) -> Result<Option<ConcurrencyLimitConfig>> {
+    node.children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| p.children())
+        .and_then(|c| c.get("concurrency"))
+        .map(parse_concurrency_limit_config)
+        .transpose()
+}
+
+/// Parse the `slow-client` block from the route's policies block.
+fn parse_route_slow_client(node: &kdl::KdlNode) -> Result<Option<SlowClientConfig>> {
+    node.children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| p.children())
+        .and_then(|c| c.get("slow-client"))
+        .map(super::server::parse_slow_client_config)
+        .transpose()
+}
+
+/// Parse `streaming-passthrough` from the route's policies block.
+fn parse_streaming_passthrough(node: &kdl::KdlNode) -> bool {
+    node.children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| get_bool_entry(p, "streaming-passthrough"))
+        .unwrap_or(false)
+}
+
+/// Parse the `server-timing` block from the route's policies block.
+fn parse_server_timing(node: &kdl::KdlNode) -> Option<ServerTimingConfig> {
+    let server_timing = node
+        .children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| p.children())
+        .and_then(|c| c.get("server-timing"))?;
+    Some(ServerTimingConfig {
+        per_agent: get_bool_entry(server_timing, "per-agent").unwrap_or(false),
+        privacy: get_bool_entry(server_timing, "privacy").unwrap_or(false),
+    })
+}
+
+/// Parse `priority-class` from the route's policies block.
+fn parse_priority_class(node: &kdl::KdlNode) -> Result<PriorityClass> {
+    let class = node
+        .children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| get_string_entry(p, "priority-class"));
+    let Some(class) = class else {
+        return Ok(PriorityClass::default());
+    };
+    PriorityClass::ALL
+        .into_iter()
+        .find(|c| c.as_str() == class)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown priority class '{}'. Valid classes: critical, high, normal, low",
+                class
+            )
+        })
+}
+
+/// Parse `body-framing` from the route's policies block.
+fn parse_body_framing(node: &kdl::KdlNode) -> Result<Option<BodyFraming>> {
+    let framing = node
+        .children()
+        .and_then(|c| c.get("policies"))
+        .and_then(|p| get_string_entry(p, "body-framing"));
+    match framing.as_deref() {
+        None => Ok(None),
+        Some("ndjson") => Ok(Some(BodyFraming::Ndjson)),
+        Some("sse") => Ok(Some(BodyFraming::Sse)),
+        Some("length-prefixed") => Ok(Some(BodyFraming::LengthPrefixed)),
+        Some(other) => Err(anyhow::anyhow!(
+            "Unknown body framing '{}'. Valid framings: ndjson, sse, length-prefixed",
+            other
+        )),
+    }
+}
+
+/// Parse a `concurrency` block (per route or in the server block).
+///
+/// Example KDL:
+/// ```kdl
+/// concurrency {
+///     max-in-flight 500
+///     retry-after-secs 1
+///     shed-body "{\"error\":\"overloaded\"}"
+///     shed-content-type "application/json"
+///     adaptive {
+///         min-limit 20
+///         initial-limit 100
+///         tolerance 1.5
+///         smoothing 0.2
+///         window-ms 1000
+///     }
+/// }
+/// ```
+pub fn parse_concurrency_limit_config(node: &kdl::KdlNode) -> Result<ConcurrencyLimitConfig> {
+    let max_in_flight = match get_int_entry(node, "max-in-flight") {
+        Some(max) if (1..=u32::MAX as i128).contains(&max) => max as u32,
+        Some(max) => {
+            return Err(anyhow::anyhow!(
+                "concurrency max-in-flight must be a positive number, got {}",
+                max
+            ));
+        }
+        None => return Err(anyhow::anyhow!("concurrency block requires max-in-flight")),
+    };
+
+    let retry_after_secs = match get_int_entry(node, "retry-after-secs") {
+        Some(secs) if secs >= 0 => Some(secs as u64),
+        Some(secs) => {
+            return Err(anyhow::anyhow!(
+                "concurrency retry-after-secs must not be negative, got {}",
+                secs
+            ));
+        }
+        None => None,
+    };
+
+    let adaptive = node
+        .children()
+        .and_then(|c| c.get("adaptive"))
+        .map(|adaptive_node| parse_adaptive_concurrency(adaptive_node, max_in_flight))
+        .transpose()?;
+
+    Ok(ConcurrencyLimitConfig {
+        max_in_flight,
+        adaptive,
+        retry_after_secs,
+        shed_body: get_string_entry(node, "shed-body"),
+        shed_content_type: get_string_entry(node, "shed-content-type")
+            .unwrap_or_else(default_shed_content_type),
+    })
+}
+
+fn parse_adaptive_concurrency(
+    node: &kdl::KdlNode,
+    max_in_flight: u32,
+) -> Result<AdaptiveConcurrencyConfig> {
+    let defaults = AdaptiveConcurrencyConfig::default();
+    let limit = |name: &str, default: u32| -> Result<u32> {
+        match get_int_entry(node, name) {
+            None => Ok(default.min(max_in_flight)),
+            Some(v) if (1..=max_in_flight as i128).contains(&v) => Ok(v as u32),
+            Some(v) => Err(anyhow::anyhow!(
+                "concurrency adaptive {} must be between 1 and max-in-flight ({}), got {}",
+                name,
+                max_in_flight,
+                v
+            )),
+        }
+    };
+
+    let min_limit = limit("min-limit", defaults.min_limit)?;
+    let initial_limit = limit("initial-limit", defaults.initial_limit)?.max(min_limit);
+
+    let tolerance = get_float_entry(node, "tolerance").unwrap_or(defaults.tolerance);
+    if tolerance < 1.0 {
+        return Err(anyhow::anyhow!(
+            "concurrency adaptive tolerance must be at least 1.0, got {}",
+            tolerance
+        ));
+    }
+    let smoothing = get_float_entry(node, "smoothing").unwrap_or(defaults.smoothing);
+    if !(smoothing > 0.0 && smoothing <= 1.0) {
+        return Err(anyhow::anyhow!(
+            "concurrency adaptive smoothing must be in (0.0, 1.0], got {}",
+            smoothing
+        ));
+    }
+    let window_ms = match get_int_entry(node, "window-ms") {
+        None => defaults.window_ms,
+        Some(ms) if ms > 0 => ms as u64,
+        Some(ms) => {
+            return Err(anyhow::anyhow!(
+                "concurrency adaptive window-ms must be positive, got {}",
+                ms
+            ));
+        }
+    };
+
+    Ok(AdaptiveConcurrencyConfig {
+        min_limit,
+        initial_limit,
+        tolerance,
+        smoothing,
+        window_ms,
+    })
+}
+
 /// Parse a header modifications block (rename, set, add, remove).
 fn parse_header_modifications(node: &kdl::KdlNode) -> Result<HeaderModifications> {
     let mut rename = HashMap::new();
@@ -440,6 +786,159 @@ pub fn parse_static_file_config(node: &kdl::KdlNode) -> Result<StaticFileConfig>
     })
 }
 
+/// Parse the optional `error-pages` block of a route.
+///
+/// Example KDL:
+/// ```kdl
+/// error-pages {
+///     default-format "html"
+///     template-dir "/etc/zentinel/errors"
+///     page 503 {
+///         template "503.html"
+///         message "Down for maintenance"
+///         headers { Retry-After "300"; }
+///     }
+/// }
+/// ```
+fn parse_error_pages_opt(node: &kdl::KdlNode) -> Result<Option<ErrorPageConfig>> {
+    let Some(error_pages_node) = node.children().and_then(|c| c.get("error-pages")) else {
+        return Ok(None);
+    };
+
+    let default_format = get_string_entry(error_pages_node, "default-format")
+        .map(|f| parse_error_format(&f))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut pages = HashMap::new();
+    if let Some(children) = error_pages_node.children() {
+        for page_node in children.nodes() {
+            if page_node.name().value() != "page" {
+                continue;
+            }
+            let status = page_node
+                .entries()
+                .first()
+                .and_then(|e| e.value().as_integer())
+                .filter(|s| (400..=599).contains(s))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Error page requires a 4xx or 5xx status code, e.g., page 503 {{ ... }}"
+                    )
+                })? as u16;
+
+            let mut headers = HashMap::new();
+            if let Some(header_nodes) = page_node
+                .children()
+                .and_then(|c| c.get("headers"))
+                .and_then(|h| h.children())
+            {
+                for header_node in header_nodes.nodes() {
+                    if let Some(value) = get_first_arg_string(header_node) {
+                        headers.insert(header_node.name().value().to_string(), value);
+                    }
+                }
+            }
+
+            let page = ErrorPage {
+                format: get_string_entry(page_node, "format")
+                    .map(|f| parse_error_format(&f))
+                    .transpose()?
+                    .unwrap_or(default_format),
+                template: get_string_entry(page_node, "template").map(PathBuf::from),
+                message: get_string_entry(page_node, "message"),
+                headers,
+            };
+            if pages.insert(status, page).is_some() {
+                return Err(anyhow::anyhow!(
+                    "Duplicate error page for status {}",
+                    status
+                ));
+            }
+        }
+    }
+
+    let problem = error_pages_node
+        .children()
+        .and_then(|c| c.get("problem"))
+        .map(parse_problem_details_config)
+        .transpose()?;
+
+    Ok(Some(ErrorPageConfig {
+        pages,
+        default_format,
+        include_stack_trace: false,
+        template_dir: get_string_entry(error_pages_node, "template-dir").map(PathBuf::from),
+        problem,
+    }))
+}
+
+/// Parse a problem details block (`server { problem-details }` or
+/// `error-pages { problem }`).
+///
+/// Example KDL:
+/// ```kdl
+/// problem-details {
+///     type-base "https://errors.example.com/"
+///     map 503 {
+///         type "https://errors.example.com/overloaded"
+///         title "Service overloaded"
+///         detail "Please retry in a few seconds."
+///     }
+/// }
+/// ```
+pub fn parse_problem_details_config(node: &kdl::KdlNode) -> Result<ProblemDetailsConfig> {
+    let mut mappings = HashMap::new();
+    if let Some(children) = node.children() {
+        for map_node in children.nodes() {
+            if map_node.name().value() != "map" {
+                continue;
+            }
+            let status = map_node
+                .entries()
+                .first()
+                .and_then(|e| e.value().as_integer())
+                .filter(|s| (400..=599).contains(s))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Problem mapping requires a 4xx or 5xx status code, e.g., map 503 {{ ... }}"
+                    )
+                })? as u16;
+
+            let mapping = ProblemMapping {
+                type_uri: get_string_entry(map_node, "type"),
+                title: get_string_entry(map_node, "title"),
+                detail: get_string_entry(map_node, "detail"),
+            };
+            if mappings.insert(status, mapping).is_some() {
+                return Err(anyhow::anyhow!(
+                    "Duplicate problem mapping for status {}",
+                    status
+                ));
+            }
+        }
+    }
+
+    Ok(ProblemDetailsConfig {
+        type_base: get_string_entry(node, "type-base"),
+        mappings,
+    })
+}
+
+fn parse_error_format(format: &str) -> Result<ErrorFormat> {
+    match format {
+        "html" => Ok(ErrorFormat::Html),
+        "json" => Ok(ErrorFormat::Json),
+        "text" => Ok(ErrorFormat::Text),
+        "xml" => Ok(ErrorFormat::Xml),
+        "problem" => Ok(ErrorFormat::Problem),
+        other => Err(anyhow::anyhow!(
+            "Unknown error page format '{}'. Valid formats: html, json, text, xml, problem",
+            other
+        )),
+    }
+}
+
 /// Parse optional cache configuration from a route
 fn parse_cache_config_opt(node: &kdl::KdlNode) -> Result<Option<RouteCacheConfig>> {
     if let Some(route_children) = node.children() {
@@ -465,6 +964,11 @@ fn parse_cache_config_opt(node: &kdl::KdlNode) -> Result<Option<RouteCacheConfig
 ///     cacheable-status-codes 200 203 204 206 300 301 308 404 410
 ///     vary-headers "Accept" "Accept-Encoding"
 ///     ignore-query-params "utm_source" "utm_medium"
+///     coalesce {
+///         enabled #true
+///         max-waiters 100
+///         timeout-secs 5
+///     }
 /// }
 /// ```
 fn parse_cache_config(node: &kdl::KdlNode) -> Result<RouteCacheConfig> {
@@ -566,10 +1070,16 @@ fn parse_cache_config(node: &kdl::KdlNode) -> Result<RouteCacheConfig> {
         Vec::new()
     };
 
+    let coalesce = match node.children().and_then(|c| c.get("coalesce")) {
+        Some(coalesce_node) => parse_cache_coalesce_config(coalesce_node)?,
+        None => CacheCoalesceConfig::default(),
+    };
+
     trace!(
         enabled = enabled,
         default_ttl = default_ttl_secs,
         max_size = max_size_bytes,
+        coalesce = coalesce.enabled,
         "Parsed cache configuration"
     );
 
@@ -586,6 +1096,28 @@ fn parse_cache_config(node: &kdl::KdlNode) -> Result<RouteCacheConfig> {
         ignore_query_params,
         exclude_extensions,
         exclude_paths,
+        coalesce,
+    })
+}
+
+/// Parse the `coalesce` block of a cache configuration
+fn parse_cache_coalesce_config(node: &kdl::KdlNode) -> Result<CacheCoalesceConfig> {
+    let defaults = CacheCoalesceConfig::default();
+    let max_waiters = match get_int_entry(node, "max-waiters") {
+        Some(n) if n < 0 => anyhow::bail!("cache coalesce max-waiters must not be negative"),
+        Some(n) => n as usize,
+        None => defaults.max_waiters,
+    };
+    let timeout_secs = match get_int_entry(node, "timeout-secs") {
+        Some(n) if n <= 0 => anyhow::bail!("cache coalesce timeout-secs must be positive"),
+        Some(n) => Some(n as u64),
+        None => None,
+    };
+
+    Ok(CacheCoalesceConfig {
+        enabled: get_bool_entry(node, "enabled").unwrap_or(defaults.enabled),
+        max_waiters,
+        timeout_secs,
     })
 }
 
@@ -1233,6 +1765,12 @@ fn parse_inference_config(node: &kdl::KdlNode) -> Result<InferenceConfig> {
     // Parse guardrails block if present
     let guardrails = parse_guardrails_config_opt(node)?;
 
+    // Parse key-pool block if present
+    let key_pool = match node.children().and_then(|c| c.get("key-pool")) {
+        Some(pool_node) => Some(parse_inference_key_pool(pool_node)?),
+        None => None,
+    };
+
     Ok(InferenceConfig {
         provider,
         model_header,
@@ -1242,6 +1780,107 @@ fn parse_inference_config(node: &kdl::KdlNode) -> Result<InferenceConfig> {
         routing,
         model_routing,
         guardrails,
+        key_pool,
+    })
+}
+
+/// Parse an inference key pool block.
+///
+/// Example KDL:
+/// ```kdl
+/// key-pool {
+///     cooldown-secs 60
+///     failover-status 429 500 502 503 504
+///     max-attempts 3
+///     member "openai-a" upstream="openai-us" key="${env:OPENAI_KEY_A}" weight=2
+///     member "claude" upstream="anthropic" key="${env:ANTHROPIC_KEY}" provider="anthropic"
+/// }
+/// ```
+fn parse_inference_key_pool(node: &kdl::KdlNode) -> Result<InferenceKeyPoolConfig> {
+    let cooldown_secs = get_int_entry(node, "cooldown-secs").unwrap_or(60) as u64;
+    let max_attempts = get_int_entry(node, "max-attempts").unwrap_or(3) as u32;
+
+    let failover_status = node
+        .children()
+        .and_then(|c| c.get("failover-status"))
+        .map(|n| {
+            n.entries()
+                .iter()
+                .filter_map(|e| e.value().as_integer().map(|v| v as u16))
+                .collect()
+        })
+        .unwrap_or_else(|| vec![429, 500, 502, 503, 504]);
+
+    let mut members = Vec::new();
+    if let Some(children) = node.children() {
+        for member_node in children.nodes() {
+            if member_node.name().value() != "member" {
+                continue;
+            }
+            let id = get_first_arg_string(member_node)
+                .ok_or_else(|| anyhow::anyhow!("Key pool member requires an ID argument"))?;
+            let string_prop = |name: &str| {
+                member_node
+                    .entries()
+                    .iter()
+                    .find(|e| e.name().map(|n| n.value()) == Some(name))
+                    .and_then(|e| e.value().as_string())
+                    .map(|s| s.to_string())
+            };
+            let int_prop = |name: &str| {
+                member_node
+                    .entries()
+                    .iter()
+                    .find(|e| e.name().map(|n| n.value()) == Some(name))
+                    .and_then(|e| e.value().as_integer())
+            };
+
+            let upstream = string_prop("upstream").ok_or_else(|| {
+                anyhow::anyhow!("Key pool member '{}' requires 'upstream' attribute", id)
+            })?;
+            let key = string_prop("key").ok_or_else(|| {
+                anyhow::anyhow!("Key pool member '{}' requires 'key' attribute", id)
+            })?;
+            let provider = match string_prop("provider").as_deref() {
+                Some("openai") | Some("open-ai") | Some("open_ai") => {
+                    Some(InferenceProvider::OpenAi)
+                }
+                Some("anthropic") => Some(InferenceProvider::Anthropic),
+                Some("generic") => Some(InferenceProvider::Generic),
+                None => None,
+                Some(other) => {
+                    return Err(anyhow::anyhow!(
+                        "Unknown provider '{}' for key pool member '{}'. Valid providers: openai, anthropic, generic",
+                        other,
+                        id
+                    ));
+                }
+            };
+
+            members.push(InferenceKeyPoolMember {
+                id,
+                upstream,
+                key,
+                provider,
+                weight: int_prop("weight").map(|v| v as u32).unwrap_or(1),
+                requests_per_minute: int_prop("requests-per-minute").map(|v| v as u64),
+                tokens_per_minute: int_prop("tokens-per-minute").map(|v| v as u64),
+            });
+        }
+    }
+
+    trace!(
+        member_count = members.len(),
+        cooldown_secs = cooldown_secs,
+        max_attempts = max_attempts,
+        "Parsed inference key pool"
+    );
+
+    Ok(InferenceKeyPoolConfig {
+        members,
+        cooldown_secs,
+        failover_status,
+        max_attempts,
     })
 }
 
@@ -1420,12 +2059,19 @@ fn parse_cost_attribution(node: &kdl::KdlNode) -> Result<CostAttributionConfig>
         Vec::new()
     };
 
+    // Parse budget sub-block
+    let budget = match node.children().and_then(|c| c.get("budget")) {
+        Some(budget_node) => Some(parse_cost_budget(budget_node)?),
+        None => None,
+    };
+
     trace!(
         enabled = enabled,
         default_input_cost = default_input_cost,
         default_output_cost = default_output_cost,
         currency = %currency,
         pricing_rules = pricing.len(),
+        has_budget = budget.is_some(),
         "Parsed cost attribution configuration"
     );
 
@@ -1435,6 +2081,74 @@ fn parse_cost_attribution(node: &kdl::KdlNode) -> Result<CostAttributionConfig>
         default_input_cost,
         default_output_cost,
         currency,
+        budget,
+    })
+}
+
+/// Parse a monthly cost budget
+///
+/// KDL format:
+/// ```kdl
+/// budget {
+///     monthly-limit 500.0
+///     scope "tenant"
+///     action "downgrade"
+///     downgrade-model "gpt-4o-mini"
+///     alert-thresholds 0.80 0.95
+/// }
+/// ```
+fn parse_cost_budget(node: &kdl::KdlNode) -> Result<CostBudgetConfig> {
+    let monthly_limit = get_float_entry(node, "monthly-limit")
+        .ok_or_else(|| anyhow::anyhow!("Cost budget requires 'monthly-limit'"))?;
+
+    let scope = match get_string_entry(node, "scope").as_deref() {
+        Some("route") | None => CostBudgetScope::Route,
+        Some("tenant") => CostBudgetScope::Tenant,
+        Some("api-key") | Some("api_key") => CostBudgetScope::ApiKey,
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Unknown cost budget scope '{}'. Valid scopes: route, tenant, api-key",
+                other
+            ));
+        }
+    };
+
+    let action = match get_string_entry(node, "action").as_deref() {
+        Some("block") | None => CostBudgetAction::Block,
+        Some("downgrade") => {
+            let model = get_string_entry(node, "downgrade-model").ok_or_else(|| {
+                anyhow::anyhow!("Cost budget action 'downgrade' requires 'downgrade-model'")
+            })?;
+            CostBudgetAction::Downgrade { model }
+        }
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Unknown cost budget action '{}'. Valid actions: block, downgrade",
+                other
+            ));
+        }
+    };
+
+    let alert_thresholds = node
+        .children()
+        .and_then(|c| c.get("alert-thresholds"))
+        .map(|n| {
+            n.entries()
+                .iter()
+                .filter_map(|e| {
+                    e.value()
+                        .as_float()
+                        .or_else(|| e.value().as_integer().map(|i| i as f64))
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| vec![0.80, 0.90, 0.95]);
+
+    Ok(CostBudgetConfig {
+        monthly_limit,
+        scope,
+        action,
+        alert_thresholds,
     })
 }
 
@@ -1535,15 +2249,47 @@ fn parse_guardrails_config(node: &kdl::KdlNode) -> Result<GuardrailsConfig> {
         None
     };
 
+    // Parse embedding sub-block
+    let embedding = match node.children().and_then(|c| c.get("embedding")) {
+        Some(embedding_node) => Some(parse_guardrail_embedding_config(embedding_node)?),
+        None => None,
+    };
+
     trace!(
         has_prompt_injection = prompt_injection.is_some(),
         has_pii_detection = pii_detection.is_some(),
+        has_embedding = embedding.is_some(),
         "Parsed guardrails configuration"
     );
 
     Ok(GuardrailsConfig {
         prompt_injection,
         pii_detection,
+        embedding,
+    })
+}
+
+/// Parse guardrail embedding endpoint configuration.
+fn parse_guardrail_embedding_config(node: &kdl::KdlNode) -> Result<GuardrailEmbeddingConfig> {
+    let endpoint = get_string_entry(node, "endpoint")
+        .ok_or_else(|| anyhow::anyhow!("Guardrail embedding config requires 'endpoint' field"))?;
+    let model = get_string_entry(node, "model")
+        .ok_or_else(|| anyhow::anyhow!("Guardrail embedding config requires 'model' field"))?;
+    let api_key = get_string_entry(node, "api-key");
+    let timeout_ms = get_int_entry(node, "timeout-ms").unwrap_or(200) as u64;
+
+    trace!(
+        endpoint = %endpoint,
+        model = %model,
+        timeout_ms = timeout_ms,
+        "Parsed guardrail embedding configuration"
+    );
+
+    Ok(GuardrailEmbeddingConfig {
+        endpoint,
+        model,
+        api_key,
+        timeout_ms,
     })
 }
 
@@ -1770,6 +2516,489 @@ mod tests {
         assert!(Priority(25) > Priority::LOW);
     }
 
+    #[test]
+    fn test_parse_error_pages() {
+        let kdl = r#"
+        routes {
+            route "web" {
+                upstream "backend"
+                error-pages {
+                    template-dir "/etc/zentinel/errors"
+                    page 503 {
+                        template "503.html"
+                        message "Down for maintenance"
+                        headers { Retry-After "300"; }
+                    }
+                    page 504 {
+                        format "json"
+                    }
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        let error_pages = routes[0].error_pages.as_ref().unwrap();
+
+        let page = &error_pages.pages[&503];
+        assert_eq!(page.format, ErrorFormat::Html);
+        assert_eq!(page.template, Some(PathBuf::from("503.html")));
+        assert_eq!(page.message.as_deref(), Some("Down for maintenance"));
+        assert_eq!(page.headers["Retry-After"], "300");
+        assert_eq!(error_pages.pages[&504].format, ErrorFormat::Json);
+
+        let kdl = r#"
+        routes {
+            route "r" {
+                error-pages {
+                    page 200 { }
+                }
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_problem_error_pages() {
+        let kdl = r#"
+        routes {
+            route "api" {
+                upstream "backend"
+                error-pages {
+                    default-format "problem"
+                    problem {
+                        type-base "https://errors.example.com/"
+                        map 429 {
+                            title "Slow down"
+                            detail "Too many requests from this client."
+                        }
+                    }
+                }
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        let error_pages = routes[0].error_pages.as_ref().unwrap();
+
+        assert_eq!(error_pages.default_format, ErrorFormat::Problem);
+        let problem = error_pages.problem.as_ref().unwrap();
+        assert_eq!(
+            problem.type_base.as_deref(),
+            Some("https://errors.example.com/")
+        );
+        assert_eq!(problem.mappings[&429].title.as_deref(), Some("Slow down"));
+        assert!(problem.mappings[&429].type_uri.is_none());
+    }
+
+    #[test]
+    fn test_parse_agent_budget() {
+        let kdl = r#"
+        routes {
+            route "budgeted" {
+                upstream "backend"
+                policies {
+                    agent-budget-ms 250
+                }
+            }
+            route "unbudgeted" {
+                upstream "backend"
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+
+        assert_eq!(routes[0].policies.agent_budget_ms, Some(250));
+        assert_eq!(routes[1].policies.agent_budget_ms, None);
+    }
+
+    #[test]
+    fn test_parse_agent_budget_rejects_zero() {
+        let kdl = r#"
+        routes {
+            route "r" {
+                policies {
+                    agent-budget-ms 0
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_decision_merge() {
+        let kdl = r#"
+        routes {
+            route "default" {
+                upstream "backend"
+            }
+            route "scored" {
+                upstream "backend"
+                policies {
+                    decision-merge "score-based"
+                    decision-score-threshold 1.5
+                }
+            }
+            route "priority" {
+                upstream "backend"
+                policies {
+                    decision-merge "priority-override"
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+
+        assert_eq!(
+            routes[0].policies.decision_merge,
+            DecisionMergeStrategy::FirstBlockWins
+        );
+        assert_eq!(
+            routes[1].policies.decision_merge,
+            DecisionMergeStrategy::ScoreBased { threshold: 1.5 }
+        );
+        assert_eq!(
+            routes[2].policies.decision_merge,
+            DecisionMergeStrategy::PriorityOverride
+        );
+    }
+
+    #[test]
+    fn test_parse_body_framing() {
+        let kdl = r#"
+        routes {
+            route "events" {
+                upstream "backend"
+                policies {
+                    body-framing "ndjson"
+                }
+            }
+            route "plain" {
+                upstream "backend"
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        assert_eq!(routes[0].policies.body_framing, Some(BodyFraming::Ndjson));
+        assert_eq!(routes[1].policies.body_framing, None);
+
+        let kdl = r#"
+        routes {
+            route "r" {
+                policies {
+                    body-framing "csv"
+                }
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_server_timing() {
+        let kdl = r#"
+        routes {
+            route "app" {
+                upstream "backend"
+                policies {
+                    server-timing {
+                        per-agent #true
+                    }
+                }
+            }
+            route "public" {
+                upstream "backend"
+                policies {
+                    server-timing {
+                        privacy #true
+                    }
+                }
+            }
+            route "plain" {
+                upstream "backend"
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        assert_eq!(
+            routes[0].policies.server_timing,
+            Some(ServerTimingConfig {
+                per_agent: true,
+                privacy: false,
+            })
+        );
+        assert_eq!(
+            routes[1].policies.server_timing,
+            Some(ServerTimingConfig {
+                per_agent: false,
+                privacy: true,
+            })
+        );
+        assert_eq!(routes[2].policies.server_timing, None);
+    }
+
+    #[test]
+    fn test_parse_priority_class() {
+        let kdl = r#"
+        routes {
+            route "payments" {
+                upstream "backend"
+                policies {
+                    priority-class "critical"
+                }
+            }
+            route "default" {
+                upstream "backend"
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        assert_eq!(routes[0].policies.priority_class, PriorityClass::Critical);
+        assert_eq!(routes[1].policies.priority_class, PriorityClass::Normal);
+
+        let invalid = r#"
+        routes {
+            route "r" {
+                policies {
+                    priority-class "urgent"
+                }
+            }
+        }
+        "#;
+        let doc: kdl::KdlDocument = invalid.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_decision_merge_rejects_unknown_strategy() {
+        let kdl = r#"
+        routes {
+            route "r" {
+                policies {
+                    decision-merge "majority"
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_block_page() {
+        let kdl = r#"
+        routes {
+            route "api" {
+                upstream "backend"
+                policies {
+                    block-page {
+                        json-template "/etc/zentinel/blocked.json"
+                        default-format "json"
+                    }
+                }
+            }
+            route "plain" {
+                upstream "backend"
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+
+        let block_page = routes[0].policies.block_page.as_ref().unwrap();
+        assert!(block_page.html_template.is_none());
+        assert_eq!(
+            block_page.json_template.as_deref(),
+            Some(std::path::Path::new("/etc/zentinel/blocked.json"))
+        );
+        assert_eq!(block_page.default_format, BlockPageFormat::Json);
+        assert!(routes[1].policies.block_page.is_none());
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let kdl = r#"
+        routes {
+            route "login" {
+                upstream "backend"
+                policies {
+                    challenge {
+                        cookie-name "clearance"
+                        ttl-secs 600
+                        pow-difficulty 20
+                    }
+                }
+            }
+            route "default" {
+                upstream "backend"
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+
+        let challenge = &routes[0].policies.challenge;
+        assert_eq!(challenge.cookie_name, "clearance");
+        assert_eq!(challenge.ttl_secs, 600);
+        assert_eq!(challenge.pow_difficulty, 20);
+        assert_eq!(routes[1].policies.challenge.pow_difficulty, 16);
+    }
+
+    #[test]
+    fn test_parse_challenge_rejects_excessive_difficulty() {
+        let kdl = r#"
+        routes {
+            route "r" {
+                policies {
+                    challenge {
+                        pow-difficulty 64
+                    }
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_route_concurrency() {
+        let kdl = r#"
+        routes {
+            route "api" {
+                policies {
+                    concurrency {
+                        max-in-flight 200
+                        retry-after-secs 2
+                        shed-content-type "application/json"
+                        adaptive {
+                            min-limit 20
+                            tolerance 2
+                        }
+                    }
+                }
+            }
+            route "fixed" {
+                policies {
+                    concurrency {
+                        max-in-flight 5
+                        adaptive
+                    }
+                }
+            }
+            route "unlimited" {
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+
+        let api = routes[0].policies.concurrency.as_ref().unwrap();
+        assert_eq!(api.max_in_flight, 200);
+        assert_eq!(api.retry_after_secs, Some(2));
+        assert_eq!(api.shed_content_type, "application/json");
+        let adaptive = api.adaptive.as_ref().unwrap();
+        assert_eq!(adaptive.min_limit, 20);
+        assert_eq!(adaptive.initial_limit, 50);
+        assert_eq!(adaptive.tolerance, 2.0);
+
+        // Defaults are capped at max-in-flight
+        let adaptive = routes[1].policies.concurrency.as_ref().unwrap();
+        let adaptive = adaptive.adaptive.as_ref().unwrap();
+        assert_eq!((adaptive.min_limit, adaptive.initial_limit), (5, 5));
+
+        assert!(routes[2].policies.concurrency.is_none());
+    }
+
+    #[test]
+    fn test_parse_route_concurrency_rejects_invalid_limits() {
+        for block in [
+            "concurrency { retry-after-secs 1; }",
+            "concurrency { max-in-flight 0; }",
+            "concurrency { max-in-flight 10; adaptive { min-limit 20; }; }",
+            "concurrency { max-in-flight 10; adaptive { tolerance 0.5; }; }",
+        ] {
+            let kdl = format!("routes {{ route \"r\" {{ policies {{ {} }} }} }}", block);
+            let doc: kdl::KdlDocument = kdl.parse().unwrap();
+            assert!(
+                parse_routes(doc.get("routes").unwrap()).is_err(),
+                "{} should be rejected",
+                block
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_route_cache_coalesce() {
+        let kdl = r#"
+        routes {
+            route "assets" {
+                cache {
+                    enabled #true
+                    coalesce {
+                        max-waiters 20
+                        timeout-secs 3
+                    }
+                }
+            }
+            route "api" {
+                cache {
+                    enabled #true
+                    coalesce {
+                        enabled #false
+                    }
+                }
+            }
+            route "plain" {
+                cache {
+                    enabled #true
+                }
+            }
+        }
+        "#;
+
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let routes = parse_routes(doc.get("routes").unwrap()).unwrap();
+        let coalesce = |i: usize| routes[i].policies.cache.as_ref().unwrap().coalesce.clone();
+
+        let assets = coalesce(0);
+        assert!(assets.enabled);
+        assert_eq!(assets.max_waiters, 20);
+        assert_eq!(assets.timeout_secs, Some(3));
+
+        assert!(!coalesce(1).enabled);
+
+        let plain = coalesce(2);
+        assert!(plain.enabled);
+        assert_eq!(plain.max_waiters, 100);
+        assert_eq!(plain.timeout_secs, None);
+
+        let kdl = r#"routes { route "r" { cache { coalesce { timeout-secs 0; }; }; }; }"#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        assert!(parse_routes(doc.get("routes").unwrap()).is_err());
+    }
+
     /// retry-policy stanza present, all values normally set, use those values
     /// Retain this test here to ensure block parser works
     #[test]
@@ -1,6 +1,6 @@
 //! Server and listener KDL parsing.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::PathBuf;
 use tracing::{debug, trace};
 
@@ -10,11 +10,19 @@ use crate::server::{
     default_acme_storage, default_graceful_shutdown_timeout, default_keepalive_timeout,
     default_max_concurrent_streams, default_max_connections, default_renewal_days,
     default_request_timeout, default_worker_threads, AcmeChallengeType, AcmeConfig, AcmeKeyType,
-    DnsProviderConfig, DnsProviderType, ExternalAccountBinding, ListenerConfig, ListenerProtocol,
-    PropagationCheckConfig, ServerConfig, SniCertificate, TlsConfig,
+    CaptureConfig, ChaosConfig, ChaosFault, ChaosFaultKind, ChaosTarget, ClientIpConfig,
+    ClientIpHeader, DebugHeadersConfig, DnsProviderConfig, DnsProviderType, ExternalAccountBinding,
+    ForwardProxyConfig, ForwardProxyUser, HardeningStatusCodes, IpCidr, ListenerConfig,
+    ListenerProtocol, MaintenanceConfig, NormalizationMode, ObsFoldPolicy, OutboundProxyConfig,
+    PropagationCheckConfig, QuicConfig, ReadinessConfig, RedisStreamConfig, RequestHardeningConfig,
+    RequestIdConfig, RequestNormalizationConfig, RolloutConfig, ServerConfig, SlowClientConfig,
+    SniCertificate, StreamProxyConfig, StreamRoute, TlsConfig,
 };
 
-use super::helpers::{get_bool_entry, get_first_arg_string, get_int_entry, get_string_entry};
+use super::helpers::{
+    get_bool_entry, get_first_arg_string, get_float_entry, get_int_entry, get_string_entry,
+};
+use super::routes::{parse_concurrency_limit_config, parse_problem_details_config};
 
 /// Parse server configuration block
 pub fn parse_server_config(node: &kdl::KdlNode) -> Result<ServerConfig> {
@@ -24,6 +32,82 @@ pub fn parse_server_config(node: &kdl::KdlNode) -> Result<ServerConfig> {
         .map(|s| TraceIdFormat::from_str_loose(&s))
         .unwrap_or_default();
 
+    let client_ip = match node
+        .children()
+        .and_then(|children| children.get("client-ip"))
+    {
+        Some(client_ip_node) => parse_client_ip_config(client_ip_node)?,
+        None => ClientIpConfig::default(),
+    };
+
+    let outbound_proxy = match node
+        .children()
+        .and_then(|children| children.get("outbound-proxy"))
+    {
+        Some(proxy_node) => parse_outbound_proxy_config(proxy_node)?,
+        None => OutboundProxyConfig::default(),
+    };
+
+    let maintenance = match node
+        .children()
+        .and_then(|children| children.get("maintenance"))
+    {
+        Some(maintenance_node) => parse_maintenance_config(maintenance_node)?,
+        None => MaintenanceConfig::default(),
+    };
+
+    let concurrency = node
+        .children()
+        .and_then(|children| children.get("concurrency"))
+        .map(parse_concurrency_limit_config)
+        .transpose()?;
+
+    let problem_details = node
+        .children()
+        .and_then(|children| children.get("problem-details"))
+        .map(parse_problem_details_config)
+        .transpose()?;
+
+    let request_id = match node
+        .children()
+        .and_then(|children| children.get("request-id"))
+    {
+        Some(request_id_node) => parse_request_id_config(request_id_node)?,
+        None => RequestIdConfig::default(),
+    };
+
+    let readiness = node
+        .children()
+        .and_then(|children| children.get("readiness"))
+        .map(parse_readiness_config)
+        .transpose()?
+        .unwrap_or_default();
+
+    let chaos = node
+        .children()
+        .and_then(|children| children.get("chaos"))
+        .map(parse_chaos_config)
+        .transpose()?
+        .unwrap_or_default();
+
+    let capture = node
+        .children()
+        .and_then(|children| children.get("capture"))
+        .map(parse_capture_config)
+        .transpose()?;
+
+    let rollout = node
+        .children()
+        .and_then(|children| children.get("rollout"))
+        .map(parse_rollout_config)
+        .transpose()?;
+
+    let debug_headers = node
+        .children()
+        .and_then(|children| children.get("debug-headers"))
+        .map(parse_debug_headers_config)
+        .transpose()?;
+
     let config = ServerConfig {
         worker_threads: get_int_entry(node, "worker-threads")
             .map(|v| v as usize)
@@ -44,6 +128,17 @@ pub fn parse_server_config(node: &kdl::KdlNode) -> Result<ServerConfig> {
         route_cache_size: get_int_entry(node, "route-cache-size")
             .map(|v| v as usize)
             .unwrap_or_else(crate::server::default_route_cache_size),
+        client_ip,
+        outbound_proxy,
+        maintenance,
+        concurrency,
+        request_id,
+        problem_details,
+        readiness,
+        chaos,
+        capture,
+        rollout,
+        debug_headers,
     };
 
     trace!(
@@ -51,12 +146,903 @@ pub fn parse_server_config(node: &kdl::KdlNode) -> Result<ServerConfig> {
         max_connections = config.max_connections,
         daemon = config.daemon,
         auto_reload = config.auto_reload,
+        trusted_proxies = config.client_ip.trusted_proxies.len(),
         "Parsed server configuration"
     );
 
     Ok(config)
 }
 
+/// Parse client IP resolution block
+///
+/// Example KDL:
+/// ```kdl
+/// client-ip {
+///     trusted-proxies "10.0.0.0/8" "2001:db8::/32"
+///     headers "x-forwarded-for" "forwarded" "x-real-ip"
+/// }
+/// ```
+pub fn parse_client_ip_config(node: &kdl::KdlNode) -> Result<ClientIpConfig> {
+    let mut config = ClientIpConfig::default();
+    let Some(children) = node.children() else {
+        return Ok(config);
+    };
+
+    let args = |name: &str| -> Vec<String> {
+        children
+            .nodes()
+            .iter()
+            .filter(|n| n.name().value() == name)
+            .flat_map(|n| {
+                n.entries()
+                    .iter()
+                    .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+            })
+            .collect()
+    };
+
+    config.trusted_proxies = args("trusted-proxies")
+        .iter()
+        .map(|cidr| {
+            cidr.parse::<IpCidr>()
+                .map_err(|e| anyhow::anyhow!("client-ip trusted-proxies: {}", e))
+        })
+        .collect::<Result<_>>()?;
+
+    let headers = args("headers");
+    if !headers.is_empty() {
+        config.headers = headers
+            .iter()
+            .map(|name| {
+                ClientIpHeader::from_str_loose(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Invalid client-ip header '{}'. Valid headers: x-forwarded-for, forwarded, x-real-ip",
+                        name
+                    )
+                })
+            })
+            .collect::<Result<_>>()?;
+    }
+
+    Ok(config)
+}
+
+/// Parse outbound proxy block
+///
+/// Example KDL:
+/// ```kdl
+/// outbound-proxy {
+///     https "http://proxy.corp:3128"
+///     all "socks5h://proxy.corp:1080"
+///     no-proxy "localhost" ".corp.example" "10.0.0.0/8"
+/// }
+/// ```
+pub fn parse_outbound_proxy_config(node: &kdl::KdlNode) -> Result<OutboundProxyConfig> {
+    let url = |name: &str| -> Result<Option<String>> {
+        let Some(value) = get_string_entry(node, name) else {
+            return Ok(None);
+        };
+        let scheme = value.split_once("://").map(|(scheme, _)| scheme);
+        match scheme {
+            Some(scheme) if OutboundProxyConfig::SCHEMES.contains(&scheme) => Ok(Some(value)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid outbound-proxy {} URL '{}'. Expected one of: {}",
+                name,
+                value,
+                OutboundProxyConfig::SCHEMES
+                    .iter()
+                    .map(|s| format!("{}://", s))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    };
+
+    let no_proxy = node
+        .children()
+        .map(|children| {
+            children
+                .nodes()
+                .iter()
+                .filter(|n| n.name().value() == "no-proxy")
+                .flat_map(|n| {
+                    n.entries()
+                        .iter()
+                        .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(OutboundProxyConfig {
+        http: url("http")?,
+        https: url("https")?,
+        all: url("all")?,
+        no_proxy,
+    })
+}
+
+/// Parse maintenance mode block
+///
+/// Example KDL:
+/// ```kdl
+/// maintenance {
+///     routes "checkout"
+///     retry-after-secs 600
+///     page "/etc/zentinel/maintenance.html"
+///     allow-ips "10.0.0.0/8"
+///     bypass-header "X-Maintenance-Bypass"
+///     bypass-secret-env "ZENTINEL_MAINTENANCE_SECRET"
+///     state-file "/var/lib/zentinel/maintenance.json"
+/// }
+/// ```
+pub fn parse_request_id_config(node: &kdl::KdlNode) -> Result<RequestIdConfig> {
+    let header_name = |key: &str| -> Result<Option<String>> {
+        match get_string_entry(node, key) {
+            Some(name) if is_header_name(&name) => Ok(Some(name)),
+            Some(name) => Err(anyhow::anyhow!(
+                "request-id {}: '{}' is not a valid header name",
+                key,
+                name
+            )),
+            None => Ok(None),
+        }
+    };
+
+    let max_length = match get_int_entry(node, "max-length") {
+        Some(len) if (1..=1024).contains(&len) => len as usize,
+        Some(len) => {
+            return Err(anyhow::anyhow!(
+                "request-id max-length must be between 1 and 1024, got {}",
+                len
+            ))
+        }
+        None => crate::server::default_request_id_max_length(),
+    };
+
+    Ok(RequestIdConfig {
+        header: header_name("header")?,
+        trust_incoming: get_bool_entry(node, "trust-incoming").unwrap_or(true),
+        max_length,
+        propagate_header: header_name("propagate-header")?
+            .unwrap_or_else(crate::server::default_request_id_header),
+        echo: get_bool_entry(node, "echo").unwrap_or(true),
+        response_header: header_name("response-header")?
+            .unwrap_or_else(crate::server::default_request_id_header),
+    })
+}
+
+/// Whether `name` is a valid HTTP header field name (RFC 9110 token)
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Parse readiness criteria block
+///
+/// Example KDL:
+/// ```kdl
+/// readiness {
+///     min-healthy-upstreams 1
+///     required-upstreams "payments"
+///     required-agents "waf"
+/// }
+/// ```
+pub fn parse_readiness_config(node: &kdl::KdlNode) -> Result<ReadinessConfig> {
+    let count = |key: &str| -> Result<usize> {
+        match get_int_entry(node, key) {
+            Some(n) if n >= 0 => Ok(n as usize),
+            Some(n) => Err(anyhow::anyhow!(
+                "readiness {} must not be negative, got {}",
+                key,
+                n
+            )),
+            None => Ok(0),
+        }
+    };
+    let ids = |key: &str| -> Vec<String> {
+        node.children()
+            .and_then(|children| children.get(key))
+            .map(|n| {
+                n.entries()
+                    .iter()
+                    .filter_map(|e| e.value().as_string().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Ok(ReadinessConfig {
+        require_listeners: get_bool_entry(node, "require-listeners").unwrap_or(true),
+        min_healthy_upstreams: count("min-healthy-upstreams")?,
+        min_healthy_agents: count("min-healthy-agents")?,
+        required_upstreams: ids("required-upstreams"),
+        required_agents: ids("required-agents"),
+    })
+}
+
+/// Parse chaos fault injection block
+///
+/// Example KDL:
+/// ```kdl
+/// chaos {
+///     enabled #true
+///     fault "slow-api" {
+///         route "api"
+///         kind "latency"
+///         latency-ms 500
+///         probability 0.2
+///         duration-secs 600
+///     }
+///     fault "waf-timeouts" {
+///         agent "waf"
+///         kind "agent-timeout"
+///     }
+/// }
+/// ```
+pub fn parse_chaos_config(node: &kdl::KdlNode) -> Result<ChaosConfig> {
+    let faults = node
+        .children()
+        .map(|children| {
+            children
+                .nodes()
+                .iter()
+                .filter(|n| n.name().value() == "fault")
+                .map(parse_chaos_fault)
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(ChaosConfig {
+        enabled: get_bool_entry(node, "enabled").unwrap_or(false),
+        faults,
+    })
+}
+
+fn parse_chaos_fault(node: &kdl::KdlNode) -> Result<ChaosFault> {
+    let id = get_first_arg_string(node)
+        .ok_or_else(|| anyhow::anyhow!("chaos fault requires an ID: fault \"name\" { ... }"))?;
+
+    let target = match (
+        get_string_entry(node, "route"),
+        get_string_entry(node, "agent"),
+    ) {
+        (Some(route), None) => ChaosTarget::Route(route),
+        (None, Some(agent)) => ChaosTarget::Agent(agent),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "chaos fault '{}' requires exactly one of 'route' or 'agent'",
+                id
+            ))
+        }
+    };
+
+    let kind = match get_string_entry(node, "kind").as_deref() {
+        Some("latency") => match get_int_entry(node, "latency-ms") {
+            Some(ms) if ms > 0 => ChaosFaultKind::Latency { ms: ms as u64 },
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "chaos fault '{}' of kind 'latency' requires a positive latency-ms",
+                    id
+                ))
+            }
+        },
+        Some("error") => ChaosFaultKind::Error {
+            status: get_int_entry(node, "status")
+                .map(|s| s as u16)
+                .unwrap_or(503),
+        },
+        Some("reset") => ChaosFaultKind::Reset,
+        Some("agent-timeout") => ChaosFaultKind::AgentTimeout,
+        other => {
+            return Err(anyhow::anyhow!(
+                "chaos fault '{}' has invalid kind {:?}. \
+                 Valid kinds: latency, error, reset, agent-timeout",
+                id,
+                other
+            ))
+        }
+    };
+
+    let duration_secs = match get_int_entry(node, "duration-secs") {
+        Some(secs) if secs > 0 => secs as u64,
+        Some(secs) => {
+            return Err(anyhow::anyhow!(
+                "chaos fault '{}' duration-secs must be positive, got {}",
+                id,
+                secs
+            ))
+        }
+        None => crate::server::default_chaos_duration(),
+    };
+
+    Ok(ChaosFault {
+        id,
+        target,
+        kind,
+        probability: get_float_entry(node, "probability")
+            .unwrap_or_else(crate::server::default_chaos_probability),
+        duration_secs,
+    })
+}
+
+/// Parse traffic capture block
+///
+/// Example KDL:
+/// ```kdl
+/// capture {
+///     directory "/var/lib/zentinel/captures"
+///     max-body-bytes 16384
+///     redact-headers "x-session-id"
+///     redact-query-params "signature"
+///     redact-json-fields "card_number" "cvv"
+/// }
+/// ```
+pub fn parse_capture_config(node: &kdl::KdlNode) -> Result<CaptureConfig> {
+    let directory = get_string_entry(node, "directory")
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("capture requires a 'directory'"))?;
+
+    let max_body_bytes = match get_int_entry(node, "max-body-bytes") {
+        Some(bytes) if bytes >= 0 => bytes as usize,
+        Some(bytes) => {
+            return Err(anyhow::anyhow!(
+                "capture max-body-bytes must not be negative, got {}",
+                bytes
+            ))
+        }
+        None => crate::server::default_capture_max_body_bytes(),
+    };
+
+    let args = |name: &str| -> Vec<String> {
+        node.children()
+            .and_then(|children| children.get(name))
+            .map(|n| {
+                n.entries()
+                    .iter()
+                    .filter_map(|e| e.value().as_string().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Ok(CaptureConfig {
+        directory,
+        max_body_bytes,
+        redact_headers: args("redact-headers"),
+        redact_query_params: args("redact-query-params"),
+        redact_json_fields: args("redact-json-fields"),
+    })
+}
+
+/// Parse staged rollout block
+///
+/// ```kdl
+/// rollout {
+///     percent 10
+///     bake-window-secs 300
+///     min-requests 100
+///     max-error-rate-increase 1.0
+///     max-latency-increase-percent 50
+///     hash-header "x-user-id"
+/// }
+/// ```
+pub fn parse_rollout_config(node: &kdl::KdlNode) -> Result<RolloutConfig> {
+    let defaults = RolloutConfig::default();
+    let non_negative = |name: &str| -> Result<Option<u64>> {
+        match get_int_entry(node, name) {
+            Some(value) if value >= 0 => Ok(Some(value as u64)),
+            Some(value) => Err(anyhow::anyhow!(
+                "rollout {} must not be negative, got {}",
+                name,
+                value
+            )),
+            None => Ok(None),
+        }
+    };
+
+    Ok(RolloutConfig {
+        percent: get_float_entry(node, "percent").unwrap_or(defaults.percent),
+        bake_window_secs: non_negative("bake-window-secs")?.unwrap_or(defaults.bake_window_secs),
+        min_requests: non_negative("min-requests")?.unwrap_or(defaults.min_requests),
+        max_error_rate_increase: get_float_entry(node, "max-error-rate-increase")
+            .unwrap_or(defaults.max_error_rate_increase),
+        max_latency_increase_percent: get_float_entry(node, "max-latency-increase-percent")
+            .unwrap_or(defaults.max_latency_increase_percent),
+        hash_header: get_string_entry(node, "hash-header"),
+    })
+}
+
+/// Parse agent decision debug headers block
+///
+/// ```kdl
+/// debug-headers {
+///     secret "${env:ZENTINEL_DEBUG_SECRET}"
+///     token-header "X-Zentinel-Debug-Token"
+///     max-ttl-secs 3600
+/// }
+/// ```
+pub fn parse_debug_headers_config(node: &kdl::KdlNode) -> Result<DebugHeadersConfig> {
+    let secret = get_string_entry(node, "secret")
+        .ok_or_else(|| anyhow::anyhow!("debug-headers requires a 'secret'"))?;
+    let max_ttl_secs = match get_int_entry(node, "max-ttl-secs") {
+        None => crate::server::default_debug_token_max_ttl(),
+        Some(v) if v > 0 => v as u64,
+        Some(v) => {
+            return Err(anyhow::anyhow!(
+                "debug-headers max-ttl-secs must be a positive number, got {}",
+                v
+            ))
+        }
+    };
+
+    Ok(DebugHeadersConfig {
+        secret,
+        token_header: get_string_entry(node, "token-header")
+            .unwrap_or_else(crate::server::default_debug_token_header),
+        max_ttl_secs,
+    })
+}
+
+/// Parse maintenance mode block
+pub fn parse_maintenance_config(node: &kdl::KdlNode) -> Result<MaintenanceConfig> {
+    let args = |name: &str| -> Vec<String> {
+        node.children()
+            .map(|children| {
+                children
+                    .nodes()
+                    .iter()
+                    .filter(|n| n.name().value() == name)
+                    .flat_map(|n| {
+                        n.entries()
+                            .iter()
+                            .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let allow_ips = args("allow-ips")
+        .iter()
+        .map(|cidr| {
+            cidr.parse::<IpCidr>()
+                .map_err(|e| anyhow::anyhow!("maintenance allow-ips: {}", e))
+        })
+        .collect::<Result<_>>()?;
+
+    let retry_after_secs = match get_int_entry(node, "retry-after-secs") {
+        Some(secs) if secs >= 0 => secs as u64,
+        Some(secs) => {
+            return Err(anyhow::anyhow!(
+                "maintenance retry-after-secs must not be negative, got {}",
+                secs
+            ))
+        }
+        None => crate::server::default_maintenance_retry_after(),
+    };
+
+    Ok(MaintenanceConfig {
+        enabled: get_bool_entry(node, "enabled").unwrap_or(false),
+        routes: args("routes"),
+        retry_after_secs,
+        page: get_string_entry(node, "page").map(PathBuf::from),
+        allow_ips,
+        bypass_header: get_string_entry(node, "bypass-header"),
+        bypass_secret_env: get_string_entry(node, "bypass-secret-env"),
+        state_file: get_string_entry(node, "state-file").map(PathBuf::from),
+    })
+}
+
+/// Parse QUIC transport parameters for an HTTP/3 listener
+///
+/// Example KDL:
+/// ```kdl
+/// quic {
+///     max-idle-timeout-secs 30
+///     keep-alive-interval-secs 10
+///     initial-max-data 10485760
+///     initial-max-stream-data 1048576
+///     max-concurrent-uni-streams 100
+///     initial-mtu 1200
+///     alt-svc-max-age-secs 86400
+/// }
+/// ```
+pub fn parse_quic_config(node: &kdl::KdlNode) -> Result<QuicConfig> {
+    let defaults = QuicConfig::default();
+    let config = QuicConfig {
+        max_idle_timeout_secs: get_int_entry(node, "max-idle-timeout-secs")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.max_idle_timeout_secs),
+        keep_alive_interval_secs: get_int_entry(node, "keep-alive-interval-secs").map(|v| v as u64),
+        initial_max_data: get_int_entry(node, "initial-max-data")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.initial_max_data),
+        initial_max_stream_data: get_int_entry(node, "initial-max-stream-data")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.initial_max_stream_data),
+        max_concurrent_uni_streams: get_int_entry(node, "max-concurrent-uni-streams")
+            .map(|v| v as u32)
+            .unwrap_or(defaults.max_concurrent_uni_streams),
+        initial_mtu: get_int_entry(node, "initial-mtu")
+            .map(|v| v as u16)
+            .unwrap_or(defaults.initial_mtu),
+        alt_svc_max_age_secs: get_int_entry(node, "alt-svc-max-age-secs")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.alt_svc_max_age_secs),
+    };
+
+    // HTTP/3 opens three unidirectional streams per side (control, QPACK
+    // encoder and decoder); fewer stalls the connection
+    if config.max_concurrent_uni_streams < 3 {
+        return Err(anyhow::anyhow!(
+            "quic max-concurrent-uni-streams must be at least 3, got {}",
+            config.max_concurrent_uni_streams
+        ));
+    }
+    if config.initial_mtu < 1200 {
+        return Err(anyhow::anyhow!(
+            "quic initial-mtu must be at least 1200 (the QUIC minimum), got {}",
+            config.initial_mtu
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Parse the egress rules of a `forward-proxy` listener
+///
+/// `allow`, `deny` and `agents` take any number of values and may repeat.
+///
+/// Example KDL:
+/// ```kdl
+/// forward-proxy {
+///     socks5 true
+///     allow "*.github.com:443" "api.stripe.com:443" "10.0.0.0/8:*"
+///     deny "169.254.169.254:*"
+///     user "ci" password="${env:EGRESS_PASSWORD}"
+///     agents "egress-audit"
+///     connect-timeout-secs 10
+///     idle-timeout-secs 300
+/// }
+/// ```
+pub fn parse_forward_proxy_config(
+    node: &kdl::KdlNode,
+    listener_id: &str,
+) -> Result<ForwardProxyConfig> {
+    let mut config = ForwardProxyConfig {
+        socks5: get_bool_entry(node, "socks5").unwrap_or(false),
+        ..Default::default()
+    };
+    if let Some(v) = get_int_entry(node, "connect-timeout-secs") {
+        config.connect_timeout_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "idle-timeout-secs") {
+        config.idle_timeout_secs = v as u64;
+    }
+
+    let Some(children) = node.children() else {
+        return Ok(config);
+    };
+    for child in children.nodes() {
+        let values = || {
+            child
+                .entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string())
+        };
+        match child.name().value() {
+            name @ ("allow" | "deny") => {
+                for value in values() {
+                    let rule = value.parse().map_err(|e| {
+                        anyhow::anyhow!("Listener '{}' forward-proxy {}: {}", listener_id, name, e)
+                    })?;
+                    if name == "allow" {
+                        config.allow.push(rule);
+                    } else {
+                        config.deny.push(rule);
+                    }
+                }
+            }
+            "agents" => config.agents.extend(values().map(str::to_string)),
+            "user" => {
+                let username = get_first_arg_string(child).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Listener '{}' forward-proxy user requires a name, e.g., user \"ci\" password=\"...\"",
+                        listener_id
+                    )
+                })?;
+                let password = child
+                    .entries()
+                    .iter()
+                    .find(|e| e.name().map(|n| n.value()) == Some("password"))
+                    .and_then(|e| e.value().as_string())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Listener '{}' forward-proxy user '{}' requires a password",
+                            listener_id,
+                            username
+                        )
+                    })?
+                    .to_string();
+                config.users.push(ForwardProxyUser { username, password });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(config)
+}
+
+/// Parse the stream routing of a `tcp` listener
+///
+/// Example KDL:
+/// ```kdl
+/// stream {
+///     upstream "postgres"
+///     route "tenant-a" {
+///         sni "a.db.example.com" "*.tenant-a.example.com"
+///         upstream "pg-tenant-a"
+///     }
+///     agents "db-audit"
+///     inspect-bytes 4096
+///     sample-rate 0.1
+///     connect-timeout-secs 10
+///     idle-timeout-secs 600
+///     redis {
+///         deny-commands "FLUSHALL" "FLUSHDB" "CONFIG"
+///     }
+/// }
+/// ```
+pub fn parse_stream_proxy_config(
+    node: &kdl::KdlNode,
+    listener_id: &str,
+) -> Result<StreamProxyConfig> {
+    let mut config = StreamProxyConfig {
+        upstream: get_string_entry(node, "upstream"),
+        ..Default::default()
+    };
+    if let Some(v) = get_int_entry(node, "inspect-bytes") {
+        config.inspect_bytes = v as usize;
+    }
+    if let Some(v) = get_float_entry(node, "sample-rate") {
+        if !(0.0..=1.0).contains(&v) {
+            return Err(anyhow::anyhow!(
+                "Listener '{}' stream sample-rate must be between 0.0 and 1.0, got {}",
+                listener_id,
+                v
+            ));
+        }
+        config.sample_rate = v;
+    }
+    if let Some(v) = get_int_entry(node, "connect-timeout-secs") {
+        config.connect_timeout_secs = v as u64;
+    }
+    if let Some(v) = get_int_entry(node, "idle-timeout-secs") {
+        config.idle_timeout_secs = v as u64;
+    }
+
+    let Some(children) = node.children() else {
+        return Ok(config);
+    };
+    for child in children.nodes() {
+        let values = |node: &kdl::KdlNode| -> Vec<String> {
+            node.entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string())
+                .map(str::to_string)
+                .collect()
+        };
+        match child.name().value() {
+            "agents" => config.agents.extend(values(child)),
+            "route" => {
+                let id = get_first_arg_string(child).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Listener '{}' stream route requires an ID, e.g., route \"tenant-a\" {{ ... }}",
+                        listener_id
+                    )
+                })?;
+                let upstream = get_string_entry(child, "upstream").ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Listener '{}' stream route '{}' requires an 'upstream'",
+                        listener_id,
+                        id
+                    )
+                })?;
+                let sni: Vec<String> = child
+                    .children()
+                    .map(|c| {
+                        c.nodes()
+                            .iter()
+                            .filter(|n| n.name().value() == "sni")
+                            .flat_map(values)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if sni.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Listener '{}' stream route '{}' requires at least one 'sni' name",
+                        listener_id,
+                        id
+                    ));
+                }
+                config.routes.push(StreamRoute { id, sni, upstream });
+            }
+            "redis" => {
+                let mut redis = RedisStreamConfig::default();
+                for list in child.children().map(|c| c.nodes()).unwrap_or_default() {
+                    match list.name().value() {
+                        "allow-commands" => redis.allow_commands.extend(values(list)),
+                        "deny-commands" => redis.deny_commands.extend(values(list)),
+                        _ => {}
+                    }
+                }
+                config.redis = Some(redis);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(config)
+}
+
+/// Parse a slow-client protection block (listener or route policies)
+///
+/// Example KDL:
+/// ```kdl
+/// slow-client {
+///     header-timeout-secs 10
+///     min-body-rate 1024
+///     min-send-rate 1024
+///     rate-grace-secs 5
+///     max-request-duration-secs 300
+/// }
+/// ```
+pub fn parse_slow_client_config(node: &kdl::KdlNode) -> Result<SlowClientConfig> {
+    let positive = |name: &str| -> Result<Option<u64>> {
+        match get_int_entry(node, name) {
+            None => Ok(None),
+            Some(v) if v > 0 => Ok(Some(v as u64)),
+            Some(v) => Err(anyhow::anyhow!(
+                "slow-client {} must be a positive number, got {}",
+                name,
+                v
+            )),
+        }
+    };
+
+    Ok(SlowClientConfig {
+        header_timeout_secs: positive("header-timeout-secs")?,
+        min_body_rate: positive("min-body-rate")?,
+        min_send_rate: positive("min-send-rate")?,
+        rate_grace_secs: positive("rate-grace-secs")?,
+        max_request_duration_secs: positive("max-request-duration-secs")?,
+    })
+}
+
+/// Parse a listener's request hardening block
+///
+/// Example KDL:
+/// ```kdl
+/// hardening {
+///     max-header-count 64
+///     max-header-bytes 16384
+///     max-uri-length 4096
+///     allowed-methods "GET" "HEAD" "POST"
+///     reject-ambiguous-framing #true
+///     obs-fold "normalize"
+///     reject-control-chars #true
+///     status {
+///         uri-length 400
+///     }
+/// }
+/// ```
+pub fn parse_hardening_config(node: &kdl::KdlNode) -> Result<RequestHardeningConfig> {
+    let positive = |name: &str| -> Result<Option<usize>> {
+        match get_int_entry(node, name) {
+            None => Ok(None),
+            Some(v) if v > 0 => Ok(Some(v as usize)),
+            Some(v) => Err(anyhow::anyhow!(
+                "hardening {} must be a positive number, got {}",
+                name,
+                v
+            )),
+        }
+    };
+
+    let allowed_methods = node
+        .children()
+        .and_then(|children| children.get("allowed-methods"))
+        .map(|n| {
+            n.entries()
+                .iter()
+                .filter_map(|e| e.value().as_string().map(str::to_uppercase))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let obs_fold = match get_string_entry(node, "obs-fold").as_deref() {
+        None | Some("allow") => ObsFoldPolicy::Allow,
+        Some("normalize") => ObsFoldPolicy::Normalize,
+        Some("reject") => ObsFoldPolicy::Reject,
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Invalid hardening obs-fold '{}'. Valid values: allow, normalize, reject",
+                other
+            ))
+        }
+    };
+
+    let normalization = match node
+        .children()
+        .and_then(|children| children.get("normalization"))
+    {
+        Some(normalization_node) => Some(parse_normalization_config(normalization_node)?),
+        None => None,
+    };
+
+    let mut status = HardeningStatusCodes::default();
+    if let Some(status_node) = node.children().and_then(|children| children.get("status")) {
+        let code = |name: &str, default: u16| -> Result<u16> {
+            match get_int_entry(status_node, name) {
+                None => Ok(default),
+                Some(v) if (400..=599).contains(&v) => Ok(v as u16),
+                Some(v) => Err(anyhow::anyhow!(
+                    "hardening status {} must be between 400 and 599, got {}",
+                    name,
+                    v
+                )),
+            }
+        };
+        status = HardeningStatusCodes {
+            header_count: code("header-count", status.header_count)?,
+            header_bytes: code("header-bytes", status.header_bytes)?,
+            uri_length: code("uri-length", status.uri_length)?,
+            method: code("method", status.method)?,
+            framing: code("framing", status.framing)?,
+            obs_fold: code("obs-fold", status.obs_fold)?,
+            control_chars: code("control-chars", status.control_chars)?,
+            normalization: code("normalization", status.normalization)?,
+        };
+    }
+
+    Ok(RequestHardeningConfig {
+        max_header_count: positive("max-header-count")?,
+        max_header_bytes: positive("max-header-bytes")?,
+        max_uri_length: positive("max-uri-length")?,
+        allowed_methods,
+        reject_ambiguous_framing: get_bool_entry(node, "reject-ambiguous-framing").unwrap_or(false),
+        obs_fold,
+        reject_control_chars: get_bool_entry(node, "reject-control-chars").unwrap_or(false),
+        normalization,
+        status,
+    })
+}
+
+/// Parse the `normalization` block of a listener's `hardening` block
+pub fn parse_normalization_config(node: &kdl::KdlNode) -> Result<RequestNormalizationConfig> {
+    let mode = match get_string_entry(node, "mode").as_deref() {
+        None | Some("strict") => NormalizationMode::Strict,
+        Some("log-only") => NormalizationMode::LogOnly,
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "Invalid normalization mode '{}'. Valid values: strict, log-only",
+                other
+            ))
+        }
+    };
+
+    Ok(RequestNormalizationConfig {
+        mode,
+        merge_slashes: get_bool_entry(node, "merge-slashes").unwrap_or(false),
+    })
+}
+
 /// Parse listeners configuration block
 pub fn parse_listeners(node: &kdl::KdlNode) -> Result<Vec<ListenerConfig>> {
     trace!("Parsing listeners configuration block");
@@ -87,9 +1073,11 @@ pub fn parse_listeners(node: &kdl::KdlNode) -> Result<Vec<ListenerConfig>> {
                     "https" => ListenerProtocol::Https,
                     "h2" => ListenerProtocol::Http2,
                     "h3" => ListenerProtocol::Http3,
+                    "forward-proxy" => ListenerProtocol::ForwardProxy,
+                    "tcp" => ListenerProtocol::Tcp,
                     other => {
                         return Err(anyhow::anyhow!(
-                            "Invalid protocol '{}' for listener '{}'. Valid protocols: http, https, h2, h3",
+                            "Invalid protocol '{}' for listener '{}'. Valid protocols: http, https, h2, h3, forward-proxy, tcp",
                             other,
                             id
                         ));
@@ -108,6 +1096,40 @@ pub fn parse_listeners(node: &kdl::KdlNode) -> Result<Vec<ListenerConfig>> {
                     None
                 };
 
+                let quic = child
+                    .children()
+                    .and_then(|children| children.get("quic"))
+                    .map(parse_quic_config)
+                    .transpose()?
+                    .unwrap_or_default();
+
+                let slow_client = child
+                    .children()
+                    .and_then(|children| children.get("slow-client"))
+                    .map(parse_slow_client_config)
+                    .transpose()?
+                    .unwrap_or_default();
+
+                let hardening = child
+                    .children()
+                    .and_then(|children| children.get("hardening"))
+                    .map(parse_hardening_config)
+                    .transpose()
+                    .with_context(|| format!("Invalid hardening for listener '{}'", id))?
+                    .unwrap_or_default();
+
+                let forward_proxy = child
+                    .children()
+                    .and_then(|children| children.get("forward-proxy"))
+                    .map(|node| parse_forward_proxy_config(node, &id))
+                    .transpose()?;
+
+                let stream = child
+                    .children()
+                    .and_then(|children| children.get("stream"))
+                    .map(|node| parse_stream_proxy_config(node, &id))
+                    .transpose()?;
+
                 trace!(
                     listener_id = %id,
                     address = %address,
@@ -134,6 +1156,13 @@ pub fn parse_listeners(node: &kdl::KdlNode) -> Result<Vec<ListenerConfig>> {
                         .unwrap_or_else(default_max_concurrent_streams),
                     keepalive_max_requests: get_int_entry(child, "keepalive-max-requests")
                         .map(|v| v as u32),
+                    proxy_protocol: get_bool_entry(child, "proxy-protocol").unwrap_or(false),
+                    bot_signals: get_bool_entry(child, "bot-signals").unwrap_or(false),
+                    quic,
+                    slow_client,
+                    hardening,
+                    forward_proxy,
+                    stream,
                 });
             }
         }
@@ -157,6 +1186,7 @@ pub fn parse_listeners(node: &kdl::KdlNode) -> Result<Vec<ListenerConfig>> {
 ///     ca-file "/etc/certs/ca.crt"  // Optional, for mTLS
 ///     min-version "1.2"
 ///     client-auth true
+///     client-cert-header "X-Client-Cert"  // Optional, forwards the client cert upstream
 ///
 ///     // SNI certificates
 ///     sni {
@@ -214,6 +1244,7 @@ pub fn parse_tls_config(node: &kdl::KdlNode, listener_id: &str) -> Result<TlsCon
 
     // Client authentication (mTLS)
     let client_auth = get_bool_entry(node, "client-auth").unwrap_or(false);
+    let client_cert_header = get_string_entry(node, "client-cert-header");
 
     // OCSP and session options
     let ocsp_stapling = get_bool_entry(node, "ocsp-stapling").unwrap_or(true);
@@ -262,6 +1293,7 @@ pub fn parse_tls_config(node: &kdl::KdlNode, listener_id: &str) -> Result<TlsCon
         max_version,
         cipher_suites,
         client_auth,
+        client_cert_header,
         ocsp_stapling,
         session_resumption,
         acme,
@@ -601,6 +1633,14 @@ fn parse_propagation_config(node: &kdl::KdlNode) -> PropagationCheckConfig {
 ///     cert-file "/etc/certs/example.crt"
 ///     key-file "/etc/certs/example.key"
 /// }
+///
+/// // Tenant certificate with its own route set
+/// sni {
+///     hostnames "*.tenant-a.example.com"
+///     cert-file "/etc/certs/tenant-a.crt"
+///     key-file "/etc/certs/tenant-a.key"
+///     namespace "tenant-a"
+/// }
 /// ```
 fn parse_sni_certificate(node: &kdl::KdlNode, listener_id: &str) -> Result<SniCertificate> {
     let children = node.children();
@@ -661,6 +1701,20 @@ fn parse_sni_certificate(node: &kdl::KdlNode, listener_id: &str) -> Result<SniCe
 
     let cert_file = get_string_entry(node, "cert-file").map(PathBuf::from);
     let key_file = get_string_entry(node, "key-file").map(PathBuf::from);
+    let namespace = get_string_entry(node, "namespace");
+
+    // Routing needs hostnames known at config time
+    if namespace.is_some()
+        && hostnames.is_empty()
+        && priority_hostnames.is_empty()
+        && acme.is_none()
+    {
+        return Err(anyhow::anyhow!(
+            "SNI certificate for listener '{}' with a 'namespace' requires 'hostnames', \
+             'priority-hostnames' or an 'acme' block to route on",
+            listener_id
+        ));
+    }
 
     // Validate mutual exclusion and completeness
     match (&acme, &cert_file, &key_file) {
@@ -716,6 +1770,7 @@ fn parse_sni_certificate(node: &kdl::KdlNode, listener_id: &str) -> Result<SniCe
         cert_file,
         key_file,
         acme,
+        namespace,
     })
 }
 
@@ -763,4 +1818,754 @@ mod tests {
         assert_eq!(admin.namespace, Some("ops".to_string()));
         assert_eq!(admin.address, "127.0.0.1:9000");
     }
+
+    #[test]
+    fn parses_listener_proxy_protocol() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "lb" {
+                    address "0.0.0.0:8080"
+                    proxy-protocol #true
+                }
+                listener "direct" {
+                    address "0.0.0.0:8081"
+                }
+            }
+            "#,
+        );
+
+        assert!(
+            listeners
+                .iter()
+                .find(|l| l.id == "lb")
+                .unwrap()
+                .proxy_protocol
+        );
+        assert!(
+            !listeners
+                .iter()
+                .find(|l| l.id == "direct")
+                .unwrap()
+                .proxy_protocol
+        );
+    }
+
+    #[test]
+    fn parses_listener_bot_signals() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "web" {
+                    address "0.0.0.0:8443"
+                    bot-signals #true
+                }
+                listener "api" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            "#,
+        );
+
+        assert!(listeners[0].bot_signals);
+        assert!(!listeners[1].bot_signals);
+    }
+
+    #[test]
+    fn parses_tls_client_cert_header() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "mtls" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                    tls {
+                        cert-file "/etc/zentinel/server.crt"
+                        key-file "/etc/zentinel/server.key"
+                        ca-file "/etc/zentinel/clients-ca.crt"
+                        client-auth #true
+                        client-cert-header "X-Client-Cert"
+                    }
+                }
+            }
+            "#,
+        );
+
+        let tls = listeners[0].tls.as_ref().unwrap();
+        assert!(tls.client_auth);
+        assert_eq!(tls.client_cert_header.as_deref(), Some("X-Client-Cert"));
+    }
+
+    #[test]
+    fn parses_sni_certificate_namespace() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "edge" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                    tls {
+                        cert-file "/etc/zentinel/default.crt"
+                        key-file "/etc/zentinel/default.key"
+                        sni {
+                            hostnames "*.tenant-a.example.com"
+                            cert-file "/etc/zentinel/tenant-a.crt"
+                            key-file "/etc/zentinel/tenant-a.key"
+                            namespace "tenant-a"
+                        }
+                    }
+                }
+            }
+            "#,
+        );
+
+        let sni = &listeners[0].tls.as_ref().unwrap().additional_certs[0];
+        assert_eq!(sni.namespace.as_deref(), Some("tenant-a"));
+        assert_eq!(sni.hostnames, vec!["*.tenant-a.example.com"]);
+    }
+
+    #[test]
+    fn rejects_sni_namespace_without_hostnames() {
+        let doc: kdl::KdlDocument = r#"
+            listeners {
+                listener "edge" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                    tls {
+                        cert-file "/etc/zentinel/default.crt"
+                        key-file "/etc/zentinel/default.key"
+                        sni {
+                            cert-file "/etc/zentinel/tenant-a.crt"
+                            key-file "/etc/zentinel/tenant-a.key"
+                            namespace "tenant-a"
+                        }
+                    }
+                }
+            }
+            "#
+        .parse()
+        .unwrap();
+        assert!(parse_listeners(doc.nodes().first().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parses_http3_listener_quic_config() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "quic" {
+                    address "0.0.0.0:443"
+                    protocol "h3"
+                    max-concurrent-streams 200
+                    tls {
+                        cert-file "/etc/zentinel/server.crt"
+                        key-file "/etc/zentinel/server.key"
+                    }
+                    quic {
+                        max-idle-timeout-secs 60
+                        keep-alive-interval-secs 15
+                        initial-max-stream-data 2097152
+                        alt-svc-max-age-secs 3600
+                    }
+                }
+                listener "web" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                }
+            }
+            "#,
+        );
+
+        let quic = &listeners[0].quic;
+        assert_eq!(listeners[0].protocol, ListenerProtocol::Http3);
+        assert_eq!(listeners[0].max_concurrent_streams, 200);
+        assert_eq!(quic.max_idle_timeout_secs, 60);
+        assert_eq!(quic.keep_alive_interval_secs, Some(15));
+        assert_eq!(quic.initial_max_stream_data, 2 * 1024 * 1024);
+        assert_eq!(
+            quic.initial_max_data,
+            QuicConfig::default().initial_max_data
+        );
+        assert_eq!(quic.alt_svc_max_age_secs, 3600);
+        assert_eq!(listeners[1].quic, QuicConfig::default());
+    }
+
+    #[test]
+    fn parses_listener_slow_client_config() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                    slow-client {
+                        header-timeout-secs 10
+                        min-body-rate 1024
+                        max-request-duration-secs 300
+                    }
+                }
+                listener "internal" {
+                    address "127.0.0.1:8081"
+                }
+            }
+            "#,
+        );
+
+        let slow_client = &listeners[0].slow_client;
+        assert_eq!(slow_client.header_timeout_secs, Some(10));
+        assert_eq!(slow_client.min_body_rate, Some(1024));
+        assert_eq!(slow_client.min_send_rate, None);
+        assert_eq!(slow_client.max_request_duration_secs, Some(300));
+        assert!(!listeners[1].slow_client.is_enabled());
+
+        let route = SlowClientConfig {
+            min_body_rate: Some(64),
+            min_send_rate: Some(512),
+            ..Default::default()
+        };
+        let merged = slow_client.overridden_by(&route);
+        assert_eq!(merged.header_timeout_secs, Some(10));
+        assert_eq!(merged.min_body_rate, Some(64));
+        assert_eq!(merged.min_send_rate, Some(512));
+
+        let doc: kdl::KdlDocument = "slow-client { min-body-rate 0; }".parse().unwrap();
+        assert!(parse_slow_client_config(doc.nodes().first().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parses_listener_hardening_config() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                    hardening {
+                        max-header-count 64
+                        max-uri-length 4096
+                        allowed-methods "get" "HEAD" "POST"
+                        reject-ambiguous-framing #true
+                        obs-fold "normalize"
+                        normalization {
+                            mode "log-only"
+                            merge-slashes #true
+                        }
+                        status {
+                            uri-length 400
+                        }
+                    }
+                }
+                listener "internal" {
+                    address "127.0.0.1:8081"
+                }
+            }
+            "#,
+        );
+
+        let hardening = &listeners[0].hardening;
+        assert_eq!(hardening.max_header_count, Some(64));
+        assert_eq!(hardening.max_header_bytes, None);
+        assert_eq!(hardening.max_uri_length, Some(4096));
+        assert_eq!(hardening.allowed_methods, ["GET", "HEAD", "POST"]);
+        assert!(hardening.reject_ambiguous_framing);
+        assert_eq!(hardening.obs_fold, ObsFoldPolicy::Normalize);
+        assert!(!hardening.reject_control_chars);
+        assert_eq!(hardening.status.uri_length, 400);
+        assert_eq!(hardening.status.method, 405);
+        assert_eq!(
+            hardening.normalization,
+            Some(RequestNormalizationConfig {
+                mode: NormalizationMode::LogOnly,
+                merge_slashes: true,
+            })
+        );
+        assert_eq!(listeners[1].hardening, RequestHardeningConfig::default());
+
+        for invalid in [
+            "hardening { obs-fold \"strip\"; }",
+            "hardening { max-uri-length 0; }",
+            "hardening { status { framing 200; }; }",
+            "hardening { normalization { mode \"audit\"; }; }",
+        ] {
+            let doc: kdl::KdlDocument = invalid.parse().unwrap();
+            assert!(
+                parse_hardening_config(doc.nodes().first().unwrap()).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    fn parse_server(input: &str) -> Result<ServerConfig> {
+        let doc: kdl::KdlDocument = input.parse().unwrap();
+        parse_server_config(doc.nodes().first().unwrap())
+    }
+
+    #[test]
+    fn parses_client_ip_config() {
+        let server = parse_server(
+            r#"
+            server {
+                client-ip {
+                    trusted-proxies "10.0.0.0/8" "2001:db8::/32" "192.0.2.1"
+                    headers "forwarded" "X-Real-IP"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let client_ip = server.client_ip;
+        assert_eq!(client_ip.trusted_proxies.len(), 3);
+        assert_eq!(
+            client_ip.headers,
+            vec![ClientIpHeader::Forwarded, ClientIpHeader::XRealIp]
+        );
+        assert!(client_ip.trusted_proxies[0].contains("10.1.2.3".parse().unwrap()));
+        assert!(client_ip.trusted_proxies[0].contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!client_ip.trusted_proxies[0].contains("11.0.0.1".parse().unwrap()));
+        assert!(client_ip.trusted_proxies[1].contains("2001:db8:1::1".parse().unwrap()));
+        assert!(client_ip.trusted_proxies[2].contains("192.0.2.1".parse().unwrap()));
+        assert!(!client_ip.trusted_proxies[2].contains("192.0.2.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn client_ip_defaults_to_socket_peer() {
+        let server = parse_server("server {}").unwrap();
+        assert!(server.client_ip.trusted_proxies.is_empty());
+        assert_eq!(
+            server.client_ip.headers,
+            vec![ClientIpHeader::XForwardedFor]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_client_ip_config() {
+        assert!(
+            parse_server(r#"server { client-ip { trusted-proxies "10.0.0.0/33"; }; }"#).is_err()
+        );
+        assert!(parse_server(r#"server { client-ip { trusted-proxies "not-an-ip"; }; }"#).is_err());
+        assert!(parse_server(r#"server { client-ip { headers "x-client-ip"; }; }"#).is_err());
+    }
+
+    #[test]
+    fn parses_outbound_proxy() {
+        let server = parse_server(
+            r#"
+            server {
+                outbound-proxy {
+                    https "http://proxy.corp:3128"
+                    all "socks5h://proxy.corp:1080"
+                    no-proxy "localhost" ".corp.example"
+                    no-proxy "10.0.0.0/8"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let proxy = &server.outbound_proxy;
+        assert!(proxy.is_configured());
+        assert_eq!(proxy.http, None);
+        assert_eq!(proxy.https.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(proxy.all.as_deref(), Some("socks5h://proxy.corp:1080"));
+        assert_eq!(
+            proxy.no_proxy,
+            vec!["localhost", ".corp.example", "10.0.0.0/8"]
+        );
+
+        assert!(!parse_server("server {}")
+            .unwrap()
+            .outbound_proxy
+            .is_configured());
+    }
+
+    #[test]
+    fn parses_maintenance() {
+        let server = parse_server(
+            r#"
+            server {
+                maintenance {
+                    routes "checkout" "search"
+                    retry-after-secs 600
+                    allow-ips "10.0.0.0/8"
+                    bypass-header "X-Maintenance-Bypass"
+                    bypass-secret-env "MAINTENANCE_SECRET"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let maintenance = &server.maintenance;
+        assert!(!maintenance.enabled);
+        assert_eq!(maintenance.routes, vec!["checkout", "search"]);
+        assert_eq!(maintenance.retry_after_secs, 600);
+        assert!(maintenance.allow_ips[0].contains("10.1.2.3".parse().unwrap()));
+        assert_eq!(
+            maintenance.bypass_header.as_deref(),
+            Some("X-Maintenance-Bypass")
+        );
+        assert_eq!(maintenance.state_file, None);
+
+        assert_eq!(
+            parse_server("server {}").unwrap().maintenance,
+            MaintenanceConfig::default()
+        );
+        assert!(parse_server(r#"server { maintenance { allow-ips "nope"; }; }"#).is_err());
+    }
+
+    #[test]
+    fn parses_global_concurrency() {
+        let server = parse_server(
+            r#"
+            server {
+                concurrency {
+                    max-in-flight 10000
+                    shed-body "overloaded"
+                    adaptive {
+                        window-ms 500
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let concurrency = server.concurrency.unwrap();
+        assert_eq!(concurrency.max_in_flight, 10000);
+        assert_eq!(concurrency.shed_body.as_deref(), Some("overloaded"));
+        assert_eq!(concurrency.adaptive.unwrap().window_ms, 500);
+
+        assert!(parse_server("server {}").unwrap().concurrency.is_none());
+    }
+
+    #[test]
+    fn parses_request_id_policy() {
+        let server = parse_server(
+            r#"
+            server {
+                trace-id-format "ulid"
+                request-id {
+                    header "X-Request-Id"
+                    trust-incoming #false
+                    propagate-header "X-Request-Id"
+                    echo #false
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(server.trace_id_format, TraceIdFormat::Ulid);
+        let request_id = server.request_id;
+        assert_eq!(request_id.header.as_deref(), Some("X-Request-Id"));
+        assert!(!request_id.trust_incoming);
+        assert_eq!(request_id.propagate_header, "X-Request-Id");
+        assert!(!request_id.echo);
+        assert_eq!(request_id.response_header, "X-Correlation-Id");
+
+        assert_eq!(
+            parse_server("server {}").unwrap().request_id,
+            RequestIdConfig::default()
+        );
+        assert!(parse_server(r#"server { request-id { header "bad header"; }; }"#).is_err());
+    }
+
+    #[test]
+    fn parses_readiness_criteria() {
+        let server = parse_server(
+            r#"
+            server {
+                readiness {
+                    require-listeners #false
+                    min-healthy-upstreams 2
+                    required-upstreams "payments" "orders"
+                    required-agents "waf"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let readiness = server.readiness;
+        assert!(!readiness.require_listeners);
+        assert_eq!(readiness.min_healthy_upstreams, 2);
+        assert_eq!(readiness.min_healthy_agents, 0);
+        assert_eq!(readiness.required_upstreams, ["payments", "orders"]);
+        assert_eq!(readiness.required_agents, ["waf"]);
+
+        assert_eq!(
+            parse_server("server {}").unwrap().readiness,
+            ReadinessConfig::default()
+        );
+        assert!(parse_server("server { readiness { min-healthy-agents -1; }; }").is_err());
+    }
+
+    #[test]
+    fn parses_capture_settings() {
+        let server = parse_server(
+            r#"
+            server {
+                capture {
+                    directory "/var/lib/zentinel/captures"
+                    max-body-bytes 1024
+                    redact-json-fields "card_number" "cvv"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let capture = server.capture.unwrap();
+        assert_eq!(
+            capture.directory,
+            PathBuf::from("/var/lib/zentinel/captures")
+        );
+        assert_eq!(capture.max_body_bytes, 1024);
+        assert_eq!(capture.redact_json_fields, ["card_number", "cvv"]);
+        assert!(capture.redact_headers.is_empty());
+
+        assert!(parse_server("server {}").unwrap().capture.is_none());
+        assert!(parse_server("server { capture { max-body-bytes 10; }; }").is_err());
+    }
+
+    #[test]
+    fn parses_rollout_settings() {
+        let server = parse_server(
+            r#"
+            server {
+                rollout {
+                    percent 5
+                    bake-window-secs 600
+                    max-error-rate-increase 0.5
+                    hash-header "x-user-id"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let rollout = server.rollout.unwrap();
+        assert_eq!(rollout.percent, 5.0);
+        assert_eq!(rollout.bake_window_secs, 600);
+        assert_eq!(rollout.max_error_rate_increase, 0.5);
+        assert_eq!(rollout.hash_header.as_deref(), Some("x-user-id"));
+        assert_eq!(rollout.min_requests, 100);
+        assert_eq!(rollout.max_latency_increase_percent, 50.0);
+
+        assert!(parse_server("server {}").unwrap().rollout.is_none());
+        assert!(parse_server("server { rollout { min-requests -1; }; }").is_err());
+    }
+
+    #[test]
+    fn parses_debug_headers_settings() {
+        let server = parse_server(
+            r#"
+            server {
+                debug-headers {
+                    secret "s3cr3t"
+                    max-ttl-secs 600
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let debug_headers = server.debug_headers.unwrap();
+        assert_eq!(debug_headers.secret, "s3cr3t");
+        assert_eq!(debug_headers.token_header, "X-Zentinel-Debug-Token");
+        assert_eq!(debug_headers.max_ttl_secs, 600);
+
+        assert!(parse_server("server {}").unwrap().debug_headers.is_none());
+        assert!(parse_server("server { debug-headers { max-ttl-secs 60; }; }").is_err());
+        assert!(
+            parse_server(r#"server { debug-headers { secret "x"; max-ttl-secs 0; }; }"#).is_err()
+        );
+    }
+
+    #[test]
+    fn parses_chaos_faults() {
+        let server = parse_server(
+            r#"
+            server {
+                chaos {
+                    enabled #true
+                    fault "slow-api" {
+                        route "api"
+                        kind "latency"
+                        latency-ms 250
+                        probability 0.5
+                    }
+                    fault "waf-down" {
+                        agent "waf"
+                        kind "error"
+                        duration-secs 60
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let chaos = server.chaos;
+        assert!(chaos.enabled);
+        assert_eq!(chaos.faults.len(), 2);
+        assert_eq!(
+            chaos.faults[0].target,
+            ChaosTarget::Route("api".to_string())
+        );
+        assert_eq!(chaos.faults[0].kind, ChaosFaultKind::Latency { ms: 250 });
+        assert_eq!(chaos.faults[0].probability, 0.5);
+        assert_eq!(chaos.faults[0].duration_secs, 300);
+        assert_eq!(chaos.faults[1].kind, ChaosFaultKind::Error { status: 503 });
+        assert_eq!(chaos.faults[1].duration_secs, 60);
+
+        assert_eq!(
+            parse_server("server {}").unwrap().chaos,
+            ChaosConfig::default()
+        );
+        assert!(parse_server(
+            r#"server { chaos { fault "x" { route "a"; agent "b"; kind "reset"; }; }; }"#
+        )
+        .is_err());
+        assert!(
+            parse_server(r#"server { chaos { fault "x" { route "a"; kind "latency"; }; }; }"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_forward_proxy_listener() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "egress" {
+                    address "0.0.0.0:3128"
+                    protocol "forward-proxy"
+                    forward-proxy {
+                        socks5 #true
+                        allow "*.github.com:443" "api.stripe.com:443"
+                        allow "10.0.0.0/8:8000-8999"
+                        deny "10.0.0.1:*"
+                        user "ci" password="secret"
+                        agents "egress-audit"
+                        idle-timeout-secs 60
+                    }
+                }
+            }
+            "#,
+        );
+        assert_eq!(listeners[0].protocol, ListenerProtocol::ForwardProxy);
+        let fp = listeners[0].forward_proxy.as_ref().unwrap();
+        assert!(fp.socks5);
+        assert_eq!(fp.allow.len(), 3);
+        assert_eq!(fp.users[0].username, "ci");
+        assert_eq!(fp.users[0].password, "secret");
+        assert_eq!(fp.agents, vec!["egress-audit"]);
+        assert_eq!(fp.connect_timeout_secs, 10);
+        assert_eq!(fp.idle_timeout_secs, 60);
+
+        let [github, stripe, internal] = &fp.allow[..] else {
+            unreachable!()
+        };
+        assert!(github.matches("api.github.com", None, 443));
+        assert!(github.matches("API.GitHub.com.", None, 443));
+        assert!(!github.matches("github.com", None, 443));
+        assert!(!github.matches("api.github.com", None, 80));
+        assert!(stripe.matches("api.stripe.com", None, 443));
+        assert!(!stripe.matches("evil-api.stripe.com", None, 443));
+        let ip = "10.1.2.3".parse().ok();
+        assert!(internal.matches("build.internal", ip, 8080));
+        assert!(internal.matches("10.1.2.3", None, 8999));
+        assert!(!internal.matches("build.internal", None, 8080));
+        assert!(!internal.matches("10.1.2.3", None, 9000));
+        assert!(fp.deny[0].matches("10.0.0.1", None, 22));
+        assert!(internal.matches_addresses() && !github.matches_addresses());
+
+        for invalid in [
+            "github.com",
+            "github.com:http",
+            "*github.com:443",
+            "10.0.0.0/33:*",
+        ] {
+            let input = format!(
+                r#"listeners {{ listener "e" {{ address "0.0.0.0:3128"; protocol "forward-proxy"; forward-proxy {{ allow "{}"; }}; }}; }}"#,
+                invalid
+            );
+            let doc: kdl::KdlDocument = input.parse().unwrap();
+            assert!(
+                parse_listeners(doc.nodes().first().unwrap()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn parses_stream_listener() {
+        let listeners = parse(
+            r#"
+            listeners {
+                listener "db" {
+                    address "0.0.0.0:5432"
+                    protocol "tcp"
+                    stream {
+                        upstream "postgres"
+                        route "tenant-a" {
+                            sni "a.db.example.com" "*.tenant-a.example.com"
+                            upstream "pg-a"
+                        }
+                        agents "db-audit"
+                        inspect-bytes 4096
+                        sample-rate 0.25
+                        redis {
+                            deny-commands "FLUSHALL" "config"
+                        }
+                    }
+                }
+            }
+            "#,
+        );
+        assert_eq!(listeners[0].protocol, ListenerProtocol::Tcp);
+        let stream = listeners[0].stream.as_ref().unwrap();
+        assert_eq!(stream.upstream.as_deref(), Some("postgres"));
+        assert_eq!(stream.agents, vec!["db-audit"]);
+        assert_eq!(stream.inspect_bytes, 4096);
+        assert_eq!(stream.sample_rate, 0.25);
+        assert_eq!(stream.idle_timeout_secs, 600);
+        let redis = stream.redis.as_ref().unwrap();
+        assert!(!redis.permits("flushall") && !redis.permits("CONFIG"));
+        assert!(redis.permits("GET"));
+
+        assert_eq!(
+            stream.route_for("A.DB.example.com.").unwrap().upstream,
+            "pg-a"
+        );
+        assert_eq!(
+            stream.route_for("x.tenant-a.example.com").unwrap().id,
+            "tenant-a"
+        );
+        assert!(stream.route_for("tenant-a.example.com").is_none());
+        assert!(stream.route_for("b.db.example.com").is_none());
+
+        for invalid in [
+            r#"route "r" { upstream "pg"; }"#,
+            r#"route "r" { sni "a.example.com"; }"#,
+            "sample-rate 1.5",
+        ] {
+            let input = format!(
+                r#"listeners {{ listener "db" {{ address "0.0.0.0:5432"; protocol "tcp"; stream {{ {} }}; }}; }}"#,
+                invalid
+            );
+            let doc: kdl::KdlDocument = input.parse().unwrap();
+            assert!(
+                parse_listeners(doc.nodes().first().unwrap()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_outbound_proxy_scheme() {
+        assert!(parse_server(r#"server { outbound-proxy { all "ftp://proxy:21"; }; }"#).is_err());
+        assert!(parse_server(r#"server { outbound-proxy { https "proxy:3128"; }; }"#).is_err());
+    }
 }
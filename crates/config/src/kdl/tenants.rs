@@ -0,0 +1,132 @@
+//! KDL parsing for tenant configuration blocks.
+
+use anyhow::{Context, Result};
+use tracing::trace;
+
+use crate::tenants::{TenantConfig, TenantQuotas};
+
+use super::helpers::{extract_u32_with_limits, extract_u64_with_limits, get_first_arg_string};
+use super::namespace::parse_string_list;
+
+/// Parse a tenant block from KDL.
+///
+/// # Example KDL
+///
+/// ```kdl
+/// tenant "acme" {
+///     namespaces "acme-api"
+///     listeners "acme-https"
+///     routes "acme-legacy"
+///     agents "acme-waf"
+///     quotas {
+///         requests-per-second 500
+///         max-concurrent-requests 200
+///     }
+/// }
+/// ```
+pub fn parse_tenant(node: &kdl::KdlNode) -> Result<TenantConfig> {
+    let id = get_first_arg_string(node)
+        .ok_or_else(|| anyhow::anyhow!("Tenant requires an ID as first argument"))?;
+
+    trace!(tenant_id = %id, "Parsing tenant");
+
+    let mut tenant = TenantConfig::new(id.clone());
+
+    let Some(children) = node.children() else {
+        return Ok(tenant);
+    };
+
+    for child in children.nodes() {
+        match child.name().value() {
+            "namespaces" => tenant.namespaces = parse_string_list(child),
+            "listeners" => tenant.listeners = parse_string_list(child),
+            "routes" => tenant.routes = parse_string_list(child),
+            "agents" => tenant.agents = parse_string_list(child),
+            "quotas" => {
+                tenant.quotas = parse_tenant_quotas(child)
+                    .with_context(|| format!("Invalid quotas in tenant '{}'", id))?;
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown block '{}' in tenant '{}'\n\
+                     Valid blocks are: namespaces, listeners, routes, agents, quotas",
+                    other,
+                    id
+                ));
+            }
+        }
+    }
+
+    Ok(tenant)
+}
+
+fn parse_tenant_quotas(node: &kdl::KdlNode) -> Result<TenantQuotas> {
+    let mut quotas = TenantQuotas::default();
+
+    let Some(children) = node.children() else {
+        return Ok(quotas);
+    };
+
+    for child in children.nodes() {
+        match child.name().value() {
+            "requests-per-second" => {
+                quotas.requests_per_second = Some(extract_u32_with_limits(child)?);
+            }
+            "max-concurrent-requests" => {
+                quotas.max_concurrent_requests = Some(extract_u64_with_limits(child)?);
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown quota '{}'\n\
+                     Valid quotas are: requests-per-second, max-concurrent-requests",
+                    other
+                ));
+            }
+        }
+    }
+
+    Ok(quotas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_kdl_node(kdl: &str) -> kdl::KdlNode {
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        doc.nodes().first().unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse_tenant() {
+        let kdl = r#"
+            tenant "acme" {
+                namespaces "acme-api" "acme-web"
+                routes "acme-legacy"
+                quotas {
+                    requests-per-second 500
+                    max-concurrent-requests 200
+                }
+            }
+        "#;
+        let tenant = parse_tenant(&parse_kdl_node(kdl)).unwrap();
+        assert_eq!(tenant.id, "acme");
+        assert_eq!(tenant.namespaces, vec!["acme-api", "acme-web"]);
+        assert_eq!(tenant.routes, vec!["acme-legacy"]);
+        assert!(tenant.listeners.is_empty());
+        assert_eq!(tenant.quotas.requests_per_second, Some(500));
+        assert_eq!(tenant.quotas.max_concurrent_requests, Some(200));
+    }
+
+    #[test]
+    fn test_parse_tenant_rejects_zero_quota() {
+        let kdl = r#"tenant "acme" { quotas { requests-per-second 0 } }"#;
+        assert!(parse_tenant(&parse_kdl_node(kdl)).is_err());
+    }
+
+    #[test]
+    fn test_tenant_requires_id() {
+        let result = parse_tenant(&parse_kdl_node(r#"tenant {}"#));
+        assert!(result.unwrap_err().to_string().contains("requires an ID"));
+    }
+}
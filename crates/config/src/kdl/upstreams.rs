@@ -1,15 +1,17 @@
 //! Upstream KDL parsing.
 
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use tracing::trace;
 
-use zentinel_common::types::{HealthCheckType, LoadBalancingAlgorithm};
+use zentinel_common::types::{HealthCheckMethod, HealthCheckType, LoadBalancingAlgorithm};
 
 use crate::{kdl::circuitbreaker_helper::parse_circuit_breaker_faildefault, upstreams::*};
 
-use super::helpers::{get_first_arg_string, get_int_entry, parse_upstream_targets};
+use super::helpers::{
+    get_bool_entry, get_first_arg_string, get_int_entry, get_string_entry, parse_upstream_targets,
+};
 
 //Parse a single upstream block
 pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
@@ -26,9 +28,17 @@ pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
         // `parse_upstream_targets`).
         let targets = parse_upstream_targets(child);
 
-        if targets.is_empty() {
+        // Parse service discovery (dynamic membership)
+        let discovery = child
+            .children()
+            .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "discovery"))
+            .map(|n| parse_discovery(&id, n))
+            .transpose()?;
+
+        if targets.is_empty() && discovery.is_none() {
             return Err(anyhow::anyhow!(
-                "Upstream '{}' requires at least one target, e.g., target \"127.0.0.1:8081\"",
+                "Upstream '{}' requires at least one target, e.g., target \"127.0.0.1:8081\", \
+                 or a discovery block",
                 id
             ));
         }
@@ -62,6 +72,28 @@ pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
             );
         }
 
+        // Parse hash key settings (if load-balancing is a hashing algorithm with children)
+        let consistent_hash = if matches!(
+            load_balancing,
+            LoadBalancingAlgorithm::ConsistentHash | LoadBalancingAlgorithm::Maglev
+        ) {
+            load_balancing_node
+                .and_then(|n| n.children())
+                .map(parse_consistent_hash_config)
+                .transpose()?
+        } else {
+            None
+        };
+
+        if let Some(ref hash) = consistent_hash {
+            trace!(
+                upstream_id = %id,
+                key = ?hash.key,
+                bounded_load = hash.bounded_load,
+                "Parsed consistent hash configuration"
+            );
+        }
+
         // Parse health check configuration
         let health_check = child
             .children()
@@ -120,7 +152,8 @@ pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
         let tls = child
             .children()
             .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "tls"))
-            .map(parse_upstream_tls);
+            .map(parse_upstream_tls)
+            .transpose()?;
 
         if tls.is_some() {
             trace!(
@@ -129,6 +162,20 @@ pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
             );
         }
 
+        // Parse credentials attached to upstream requests
+        let auth = child
+            .children()
+            .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "auth"))
+            .map(|n| parse_upstream_auth(&id, n))
+            .transpose()?;
+
+        // Parse in-flight limit and request queue
+        let queue = child
+            .children()
+            .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "queue"))
+            .map(|n| parse_upstream_queue(&id, n))
+            .transpose()?;
+
         let circuit_breaker = child
             .children()
             .and_then(|c| {
@@ -154,13 +201,17 @@ pub fn parse_upstream(child: &kdl::KdlNode) -> Result<UpstreamConfig> {
         Ok(UpstreamConfig {
             id,
             targets,
+            discovery,
             load_balancing,
             sticky_session,
+            consistent_hash,
             health_check,
             circuit_breaker,
             connection_pool,
             timeouts,
             tls,
+            auth,
+            queue,
             http_version,
         })
     } else {
@@ -194,13 +245,14 @@ pub fn parse_upstreams(node: &kdl::KdlNode) -> Result<HashMap<String, UpstreamCo
 }
 
 /// Parse load balancing algorithm from string
+///
+/// Hyphens and underscores are interchangeable (`least-connections` ==
+/// `least_connections`).
 fn parse_load_balancing(s: &str) -> LoadBalancingAlgorithm {
-    match s.to_lowercase().as_str() {
+    match s.to_lowercase().replace('-', "_").as_str() {
         "round_robin" | "roundrobin" => LoadBalancingAlgorithm::RoundRobin,
         "least_connections" | "leastconnections" => LoadBalancingAlgorithm::LeastConnections,
-        "weighted" | "weighted_round_robin" | "weighted-round-robin" => {
-            LoadBalancingAlgorithm::Weighted
-        }
+        "weighted" | "weighted_round_robin" => LoadBalancingAlgorithm::Weighted,
         "ip_hash" | "iphash" => LoadBalancingAlgorithm::IpHash,
         "random" => LoadBalancingAlgorithm::Random,
         "consistent_hash" | "consistenthash" => LoadBalancingAlgorithm::ConsistentHash,
@@ -279,6 +331,382 @@ fn parse_sticky_session_config(children: &kdl::KdlDocument) -> StickySessionConf
     }
 }
 
+/// Parse service discovery configuration
+///
+/// Example KDL:
+/// ```kdl
+/// discovery "dns-srv" {
+///     service "_http._tcp.api.internal"
+///     refresh-interval 30
+/// }
+/// ```
+fn parse_discovery(upstream_id: &str, node: &kdl::KdlNode) -> Result<ServiceDiscoveryConfig> {
+    let kind = get_first_arg_string(node).ok_or_else(|| {
+        anyhow!(
+            "Upstream '{}' discovery requires a type: \"dns\", \"dns-srv\", \"consul\", \"kubernetes\" or \"file\"",
+            upstream_id
+        )
+    })?;
+
+    let require = |name: &str| {
+        get_string_entry(node, name).ok_or_else(|| {
+            anyhow!(
+                "Upstream '{}' {} discovery requires '{}'",
+                upstream_id,
+                kind,
+                name
+            )
+        })
+    };
+    let refresh_interval_secs = get_int_entry(node, "refresh-interval")
+        .map(|v| v.max(1) as u64)
+        .unwrap_or(30);
+
+    let config = match kind.to_lowercase().replace('_', "-").as_str() {
+        "dns" => ServiceDiscoveryConfig::Dns {
+            hostname: require("hostname")?,
+            port: get_int_entry(node, "port")
+                .and_then(|p| u16::try_from(p).ok())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Upstream '{}' dns discovery requires a valid 'port'",
+                        upstream_id
+                    )
+                })?,
+            refresh_interval_secs,
+        },
+        "dns-srv" | "srv" => ServiceDiscoveryConfig::DnsSrv {
+            service: require("service")?,
+            refresh_interval_secs,
+        },
+        "consul" => ServiceDiscoveryConfig::Consul {
+            address: require("address")?,
+            service: require("service")?,
+            datacenter: get_string_entry(node, "datacenter"),
+            only_passing: get_bool_entry(node, "only-passing").unwrap_or(true),
+            tag: get_string_entry(node, "tag"),
+            refresh_interval_secs,
+        },
+        "kubernetes" | "k8s" => ServiceDiscoveryConfig::Kubernetes {
+            namespace: get_string_entry(node, "namespace").unwrap_or_else(|| "default".to_string()),
+            service: require("service")?,
+            port_name: get_string_entry(node, "port-name"),
+            kubeconfig: get_string_entry(node, "kubeconfig"),
+            refresh_interval_secs,
+        },
+        "file" => ServiceDiscoveryConfig::File {
+            path: require("path")?,
+            watch_interval_secs: get_int_entry(node, "watch-interval")
+                .map(|v| v.max(1) as u64)
+                .unwrap_or(30),
+        },
+        other => {
+            return Err(anyhow!(
+                "Upstream '{}' has unknown discovery type '{}'",
+                upstream_id,
+                other
+            ))
+        }
+    };
+
+    trace!(upstream_id = %upstream_id, discovery = ?config, "Parsed service discovery");
+    Ok(config)
+}
+
+/// Parse upstream authentication
+///
+/// Example KDL:
+/// ```kdl
+/// auth "oauth2" {
+///     token-url "https://auth.example.com/oauth/token"
+///     client-id "zentinel"
+///     client-secret "${env:BILLING_CLIENT_SECRET}"
+///     scope "billing:read" "billing:write"
+/// }
+/// ```
+fn parse_upstream_auth(upstream_id: &str, node: &kdl::KdlNode) -> Result<UpstreamAuthConfig> {
+    let kind = get_first_arg_string(node).ok_or_else(|| {
+        anyhow!(
+            "Upstream '{}' auth requires a type: \"bearer\", \"basic\" or \"oauth2\"",
+            upstream_id
+        )
+    })?;
+
+    let require = |name: &str| {
+        get_string_entry(node, name).ok_or_else(|| {
+            anyhow!(
+                "Upstream '{}' {} auth requires '{}'",
+                upstream_id,
+                kind,
+                name
+            )
+        })
+    };
+
+    let config = match kind.to_lowercase().as_str() {
+        "bearer" => UpstreamAuthConfig::Bearer {
+            token: require("token")?,
+        },
+        "basic" => UpstreamAuthConfig::Basic {
+            username: require("username")?,
+            password: require("password")?,
+        },
+        "oauth2" => {
+            let client_auth = match get_string_entry(node, "client-auth").as_deref() {
+                None | Some("basic") => OAuth2ClientAuth::Basic,
+                Some("body") => OAuth2ClientAuth::Body,
+                Some(other) => {
+                    return Err(anyhow!(
+                        "Upstream '{}' oauth2 auth has unknown client-auth '{}'. \
+                         Valid values: basic, body",
+                        upstream_id,
+                        other
+                    ))
+                }
+            };
+            let scopes = node
+                .children()
+                .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "scope"))
+                .map(|n| {
+                    n.entries()
+                        .iter()
+                        .filter(|e| e.name().is_none())
+                        .filter_map(|e| e.value().as_string().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            UpstreamAuthConfig::OAuth2 {
+                token_url: require("token-url")?,
+                client_id: require("client-id")?,
+                client_secret: require("client-secret")?,
+                scopes,
+                audience: get_string_entry(node, "audience"),
+                client_auth,
+                refresh_before_secs: get_int_entry(node, "refresh-before-secs")
+                    .map(|v| v.max(0) as u64)
+                    .unwrap_or(60),
+                timeout_secs: get_int_entry(node, "timeout-secs")
+                    .map(|v| v.max(1) as u64)
+                    .unwrap_or(10),
+            }
+        }
+        other => {
+            return Err(anyhow!(
+                "Upstream '{}' has unknown auth type '{}'",
+                upstream_id,
+                other
+            ))
+        }
+    };
+
+    trace!(upstream_id = %upstream_id, auth = %kind, "Parsed upstream auth");
+    Ok(config)
+}
+
+/// Parse the in-flight limit and request queue of an upstream
+///
+/// Example KDL:
+/// ```kdl
+/// queue {
+///     max-in-flight 200
+///     max-depth 500
+///     timeout-ms 2000
+///     retry-after-secs 1
+///     fair {
+///         client-key "api-key"
+///         default-weight 1
+///         tier "gold" weight=4
+///     }
+/// }
+/// ```
+fn parse_upstream_queue(upstream_id: &str, node: &kdl::KdlNode) -> Result<UpstreamQueueConfig> {
+    let max_in_flight = match get_int_entry(node, "max-in-flight") {
+        Some(max) if (1..=u32::MAX as i128).contains(&max) => max as u32,
+        Some(max) => {
+            return Err(anyhow!(
+                "Upstream '{}' queue max-in-flight must be a positive number, got {}",
+                upstream_id,
+                max
+            ))
+        }
+        None => {
+            return Err(anyhow!(
+                "Upstream '{}' queue requires max-in-flight",
+                upstream_id
+            ))
+        }
+    };
+
+    let max_depth = match get_int_entry(node, "max-depth") {
+        Some(depth) if (0..=u32::MAX as i128).contains(&depth) => depth as u32,
+        Some(depth) => {
+            return Err(anyhow!(
+                "Upstream '{}' queue max-depth must not be negative, got {}",
+                upstream_id,
+                depth
+            ))
+        }
+        None => default_queue_max_depth(),
+    };
+
+    let timeout_ms = match get_int_entry(node, "timeout-ms") {
+        Some(ms) if ms > 0 => ms as u64,
+        Some(ms) => {
+            return Err(anyhow!(
+                "Upstream '{}' queue timeout-ms must be positive, got {}",
+                upstream_id,
+                ms
+            ))
+        }
+        None => default_queue_timeout_ms(),
+    };
+
+    let fair = node
+        .children()
+        .and_then(|c| c.get("fair"))
+        .map(|n| parse_fair_queue(upstream_id, n))
+        .transpose()?;
+
+    trace!(
+        upstream_id = %upstream_id,
+        max_in_flight = max_in_flight,
+        max_depth = max_depth,
+        fair = fair.is_some(),
+        "Parsed upstream queue"
+    );
+    Ok(UpstreamQueueConfig {
+        max_in_flight,
+        max_depth,
+        timeout_ms,
+        retry_after_secs: get_int_entry(node, "retry-after-secs").map(|secs| secs.max(0) as u64),
+        fair,
+    })
+}
+
+/// Parse the `fair` block of an upstream queue
+fn parse_fair_queue(upstream_id: &str, node: &kdl::KdlNode) -> Result<FairQueueConfig> {
+    let client_key = match get_string_entry(node, "client-key").as_deref() {
+        None | Some("api-key") => FairQueueKey::ApiKey,
+        Some("client-ip") => FairQueueKey::ClientIp,
+        Some(other) => {
+            return Err(anyhow!(
+                "Upstream '{}' fair queue has unknown client-key '{}'. \
+                 Valid values: api-key, client-ip",
+                upstream_id,
+                other
+            ))
+        }
+    };
+
+    let weight = |value: Option<i128>, what: &str| -> Result<Option<u32>> {
+        match value {
+            None => Ok(None),
+            Some(w) if (1..=u32::MAX as i128).contains(&w) => Ok(Some(w as u32)),
+            Some(w) => Err(anyhow!(
+                "Upstream '{}' fair queue {} must be a positive number, got {}",
+                upstream_id,
+                what,
+                w
+            )),
+        }
+    };
+
+    let default_weight =
+        weight(get_int_entry(node, "default-weight"), "default-weight")?.unwrap_or(1);
+
+    let mut tiers = BTreeMap::new();
+    for tier_node in node.children().map(|c| c.nodes()).unwrap_or_default() {
+        if tier_node.name().value() != "tier" {
+            continue;
+        }
+        let tier = get_first_arg_string(tier_node).ok_or_else(|| {
+            anyhow!(
+                "Upstream '{}' fair queue tier requires a name, e.g. tier \"gold\" weight=4",
+                upstream_id
+            )
+        })?;
+        let value = tier_node
+            .entries()
+            .iter()
+            .find(|e| e.name().map(|n| n.value()) == Some("weight"))
+            .and_then(|e| e.value().as_integer());
+        let Some(tier_weight) = weight(value, "tier weight")? else {
+            return Err(anyhow!(
+                "Upstream '{}' fair queue tier '{}' requires weight",
+                upstream_id,
+                tier
+            ));
+        };
+        tiers.insert(tier, tier_weight);
+    }
+
+    Ok(FairQueueConfig {
+        client_key,
+        default_weight,
+        tiers,
+    })
+}
+
+/// Parse consistent hash configuration
+///
+/// Example KDL:
+/// ```kdl
+/// load-balancing "consistent_hash" {
+///     hash-key "header" "X-User-Id"
+///     virtual-nodes 150
+///     bounded-load #true
+///     max-load-factor 1.25
+/// }
+/// ```
+fn parse_consistent_hash_config(children: &kdl::KdlDocument) -> Result<ConsistentHashConfig> {
+    let nodes = children.nodes();
+    let mut config = ConsistentHashConfig::default();
+
+    if let Some(node) = nodes.iter().find(|n| n.name().value() == "hash-key") {
+        let args: Vec<&str> = node
+            .entries()
+            .iter()
+            .filter(|e| e.name().is_none())
+            .filter_map(|e| e.value().as_string())
+            .collect();
+        config.key = match args.as_slice() {
+            ["client-ip"] | ["client_ip"] | ["ip"] => HashKeySource::ClientIp,
+            ["header", name] => HashKeySource::Header(name.to_lowercase()),
+            ["cookie", name] => HashKeySource::Cookie(name.to_string()),
+            _ => {
+                return Err(anyhow!(
+                    "Invalid hash-key: expected \"client-ip\", \"header\" \"<name>\" or \"cookie\" \"<name>\""
+                ))
+            }
+        };
+    }
+
+    if let Some(virtual_nodes) = find_int_entry(nodes, "virtual-nodes") {
+        if virtual_nodes < 1 {
+            return Err(anyhow!("virtual-nodes must be at least 1"));
+        }
+        config.virtual_nodes = virtual_nodes as usize;
+    }
+
+    if let Some(bounded_load) = nodes
+        .iter()
+        .find(|n| n.name().value() == "bounded-load")
+        .and_then(|n| n.entries().first())
+        .and_then(|e| e.value().as_bool())
+    {
+        config.bounded_load = bounded_load;
+    }
+
+    if let Some(factor) = find_float_entry(nodes, "max-load-factor") {
+        if factor < 1.0 {
+            return Err(anyhow!("max-load-factor must be at least 1.0"));
+        }
+        config.max_load_factor = factor;
+    }
+
+    Ok(config)
+}
+
 /// Parse duration string like "1h", "30m", "1d" to seconds
 fn parse_duration_string(s: &str) -> Option<u64> {
     let s = s.trim();
@@ -354,6 +782,11 @@ fn parse_http_version(node: &kdl::KdlNode) -> HttpVersionConfig {
 ///     max-idle 20
 ///     idle-timeout 60
 ///     max-lifetime 3600
+///     tcp-keepalive {
+///         idle 60
+///         interval 10
+///         count 3
+///     }
 /// }
 /// ```
 fn parse_connection_pool(node: &kdl::KdlNode) -> ConnectionPoolConfig {
@@ -371,11 +804,46 @@ fn parse_connection_pool(node: &kdl::KdlNode) -> ConnectionPoolConfig {
 
     let max_lifetime_secs = get_int_entry(node, "max-lifetime").map(|v| v as u64);
 
+    let tcp_keepalive = node
+        .children()
+        .and_then(|c| c.get("tcp-keepalive"))
+        .map(parse_tcp_keepalive)
+        .unwrap_or_default();
+
     ConnectionPoolConfig {
         max_connections,
         max_idle,
         idle_timeout_secs,
         max_lifetime_secs,
+        tcp_keepalive,
+    }
+}
+
+/// Parse TCP keepalive configuration
+///
+/// `tcp-keepalive #false` disables keepalive probes; otherwise the block
+/// overrides the probe timings.
+fn parse_tcp_keepalive(node: &kdl::KdlNode) -> TcpKeepaliveConfig {
+    let defaults = TcpKeepaliveConfig::default();
+
+    let enabled = node
+        .entries()
+        .first()
+        .and_then(|e| e.value().as_bool())
+        .or_else(|| get_bool_entry(node, "enabled"))
+        .unwrap_or(defaults.enabled);
+
+    TcpKeepaliveConfig {
+        enabled,
+        idle_secs: get_int_entry(node, "idle")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.idle_secs),
+        interval_secs: get_int_entry(node, "interval")
+            .map(|v| v as u64)
+            .unwrap_or(defaults.interval_secs),
+        count: get_int_entry(node, "count")
+            .map(|v| v as usize)
+            .unwrap_or(defaults.count),
     }
 }
 
@@ -421,9 +889,11 @@ fn parse_upstream_timeouts(node: &kdl::KdlNode) -> UpstreamTimeouts {
 ///     client-cert "/path/to/client.crt"
 ///     client-key "/path/to/client.key"
 ///     ca-cert "/path/to/ca.crt"
+///     // or from the environment: client-cert-env, client-key-env, ca-cert-env
+///     alpn "h2" "http/1.1"
 /// }
 /// ```
-fn parse_upstream_tls(node: &kdl::KdlNode) -> UpstreamTlsConfig {
+fn parse_upstream_tls(node: &kdl::KdlNode) -> Result<UpstreamTlsConfig> {
     let sni = find_string_entry_from_node(node, "sni");
 
     let insecure_skip_verify =
@@ -435,13 +905,58 @@ fn parse_upstream_tls(node: &kdl::KdlNode) -> UpstreamTlsConfig {
 
     let ca_cert = find_string_entry_from_node(node, "ca-cert").map(PathBuf::from);
 
-    UpstreamTlsConfig {
+    let alpn = find_string_args_from_node(node, "alpn")
+        .iter()
+        .map(|id| {
+            AlpnProtocol::from_id(id).ok_or_else(|| {
+                anyhow!(
+                    "Unknown ALPN protocol '{}' in upstream tls block. Valid values: h2, http/1.1",
+                    id
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // `spiffe-id` may be repeated, each with one or more IDs
+    let spiffe_ids = node
+        .children()
+        .map(|c| {
+            c.nodes()
+                .iter()
+                .filter(|n| n.name().value() == "spiffe-id")
+                .flat_map(|n| n.entries().iter())
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(UpstreamTlsConfig {
         sni,
         insecure_skip_verify,
         client_cert,
         client_key,
         ca_cert,
-    }
+        client_cert_env: find_string_entry_from_node(node, "client-cert-env"),
+        client_key_env: find_string_entry_from_node(node, "client-key-env"),
+        ca_cert_env: find_string_entry_from_node(node, "ca-cert-env"),
+        alpn,
+        spiffe_ids,
+    })
+}
+
+/// Find all string arguments of a child node by name
+fn find_string_args_from_node(node: &kdl::KdlNode, name: &str) -> Vec<String> {
+    node.children()
+        .and_then(|c| c.nodes().iter().find(|n| n.name().value() == name))
+        .map(|n| {
+            n.entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 /// Find a string entry in a node's children by name
@@ -493,10 +1008,32 @@ fn parse_health_check(node: &kdl::KdlNode) -> Result<HealthCheck> {
                         .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "host"))
                         .and_then(get_first_arg_string);
 
+                    let method = match type_node
+                        .children()
+                        .and_then(|c| c.nodes().iter().find(|n| n.name().value() == "method"))
+                        .and_then(get_first_arg_string)
+                        .map(|m| m.to_uppercase())
+                        .as_deref()
+                    {
+                        Some("HEAD") => HealthCheckMethod::Head,
+                        _ => HealthCheckMethod::Get,
+                    };
+
+                    let expected_body = type_node
+                        .children()
+                        .and_then(|c| {
+                            c.nodes()
+                                .iter()
+                                .find(|n| n.name().value() == "expected-body")
+                        })
+                        .and_then(get_first_arg_string);
+
                     HealthCheckType::Http {
                         path,
                         expected_status,
                         host,
+                        method,
+                        expected_body,
                     }
                 }
                 "grpc" => {
@@ -801,6 +1338,404 @@ mod tests {
         assert!(err.to_string().contains("at least one target"));
     }
 
+    #[test]
+    fn test_parse_connection_pool_tcp_keepalive() {
+        let kdl = r#"
+        upstreams {
+            upstream "tuned" {
+                target "10.0.0.1:8080"
+                connection-pool {
+                    max-idle 50
+                    idle-timeout 90
+                    tcp-keepalive {
+                        idle 30
+                        count 5
+                    }
+                }
+            }
+            upstream "no-keepalive" {
+                target "10.0.0.2:8080"
+                connection-pool {
+                    tcp-keepalive #false
+                }
+            }
+        }
+        "#;
+
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        let pool = &upstreams.get("tuned").unwrap().connection_pool;
+        assert_eq!(pool.max_idle, 50);
+        assert_eq!(pool.idle_timeout_secs, 90);
+        assert!(pool.tcp_keepalive.enabled);
+        assert_eq!(pool.tcp_keepalive.idle_secs, 30);
+        assert_eq!(pool.tcp_keepalive.interval_secs, 10); // default
+        assert_eq!(pool.tcp_keepalive.count, 5);
+
+        let pool = &upstreams.get("no-keepalive").unwrap().connection_pool;
+        assert!(!pool.tcp_keepalive.enabled);
+        assert_eq!(pool.max_idle, 20); // default
+    }
+
+    #[test]
+    fn test_parse_discovery() {
+        let kdl = r#"
+            upstreams {
+                upstream "srv" {
+                    discovery "dns-srv" {
+                        service "_http._tcp.api.internal"
+                        refresh-interval 15
+                    }
+                }
+                upstream "consul" {
+                    target "10.0.0.1:8080"
+                    discovery "consul" {
+                        address "http://127.0.0.1:8500"
+                        service "api"
+                        tag "v2"
+                        only-passing #false
+                    }
+                }
+                upstream "k8s" {
+                    discovery "kubernetes" {
+                        service "api"
+                        port-name "http"
+                    }
+                }
+            }
+        "#;
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        assert!(upstreams["srv"].targets.is_empty());
+        assert_eq!(
+            upstreams["srv"].discovery,
+            Some(ServiceDiscoveryConfig::DnsSrv {
+                service: "_http._tcp.api.internal".to_string(),
+                refresh_interval_secs: 15,
+            })
+        );
+        assert_eq!(
+            upstreams["consul"].discovery,
+            Some(ServiceDiscoveryConfig::Consul {
+                address: "http://127.0.0.1:8500".to_string(),
+                service: "api".to_string(),
+                datacenter: None,
+                only_passing: false,
+                tag: Some("v2".to_string()),
+                refresh_interval_secs: 30,
+            })
+        );
+        assert_eq!(
+            upstreams["k8s"].discovery,
+            Some(ServiceDiscoveryConfig::Kubernetes {
+                namespace: "default".to_string(),
+                service: "api".to_string(),
+                port_name: Some("http".to_string()),
+                kubeconfig: None,
+                refresh_interval_secs: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_discovery_missing_field() {
+        let kdl = r#"
+            upstreams {
+                upstream "dns" {
+                    discovery "dns" {
+                        hostname "api.internal"
+                    }
+                }
+            }
+        "#;
+        let err = parse_kdl_upstreams(kdl).unwrap_err().to_string();
+        assert!(err.contains("port"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_upstream_auth() {
+        let kdl = r#"
+            upstreams {
+                upstream "static" {
+                    target "10.0.0.1:8080"
+                    auth "bearer" {
+                        token "s3cret"
+                    }
+                }
+                upstream "billing" {
+                    target "10.0.0.2:8080"
+                    auth "oauth2" {
+                        token-url "https://auth.example.com/oauth/token"
+                        client-id "zentinel"
+                        client-secret "shh"
+                        scope "billing:read" "billing:write"
+                        client-auth "body"
+                    }
+                }
+            }
+        "#;
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        assert_eq!(
+            upstreams["static"].auth,
+            Some(UpstreamAuthConfig::Bearer {
+                token: "s3cret".to_string(),
+            })
+        );
+        assert_eq!(
+            upstreams["billing"].auth,
+            Some(UpstreamAuthConfig::OAuth2 {
+                token_url: "https://auth.example.com/oauth/token".to_string(),
+                client_id: "zentinel".to_string(),
+                client_secret: "shh".to_string(),
+                scopes: vec!["billing:read".to_string(), "billing:write".to_string()],
+                audience: None,
+                client_auth: OAuth2ClientAuth::Body,
+                refresh_before_secs: 60,
+                timeout_secs: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_upstream_auth_missing_field() {
+        let kdl = r#"
+            upstreams {
+                upstream "basic" {
+                    target "10.0.0.1:8080"
+                    auth "basic" {
+                        username "zentinel"
+                    }
+                }
+            }
+        "#;
+        let err = parse_kdl_upstreams(kdl).unwrap_err().to_string();
+        assert!(err.contains("password"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_upstream_queue() {
+        let kdl = r#"
+            upstreams {
+                upstream "payments" {
+                    target "10.0.0.1:8080"
+                    queue {
+                        max-in-flight 200
+                        timeout-ms 2000
+                        retry-after-secs 1
+                    }
+                }
+                upstream "plain" {
+                    target "10.0.0.2:8080"
+                }
+            }
+        "#;
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        assert_eq!(
+            upstreams["payments"].queue,
+            Some(UpstreamQueueConfig {
+                max_in_flight: 200,
+                max_depth: 100,
+                timeout_ms: 2000,
+                retry_after_secs: Some(1),
+                fair: None,
+            })
+        );
+        assert_eq!(upstreams["plain"].queue, None);
+
+        let missing = r#"
+            upstreams {
+                upstream "payments" {
+                    target "10.0.0.1:8080"
+                    queue {
+                        max-depth 10
+                    }
+                }
+            }
+        "#;
+        let err = parse_kdl_upstreams(missing).unwrap_err().to_string();
+        assert!(err.contains("max-in-flight"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_fair_queue() {
+        let kdl = r#"
+            upstreams {
+                upstream "shared" {
+                    target "10.0.0.1:8080"
+                    queue {
+                        max-in-flight 100
+                        fair {
+                            client-key "client-ip"
+                            default-weight 2
+                            tier "gold" weight=8
+                            tier "free" weight=1
+                        }
+                    }
+                }
+                upstream "defaults" {
+                    target "10.0.0.2:8080"
+                    queue {
+                        max-in-flight 100
+                        fair
+                    }
+                }
+            }
+        "#;
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        let fair = upstreams["shared"].queue.as_ref().unwrap().fair.clone();
+        assert_eq!(
+            fair,
+            Some(FairQueueConfig {
+                client_key: FairQueueKey::ClientIp,
+                default_weight: 2,
+                tiers: BTreeMap::from([("free".to_string(), 1), ("gold".to_string(), 8)]),
+            })
+        );
+        let fair = upstreams["defaults"].queue.as_ref().unwrap().fair.clone();
+        assert_eq!(
+            fair,
+            Some(FairQueueConfig {
+                client_key: FairQueueKey::ApiKey,
+                default_weight: 1,
+                tiers: BTreeMap::new(),
+            })
+        );
+
+        let zero_weight = r#"
+            upstreams {
+                upstream "shared" {
+                    target "10.0.0.1:8080"
+                    queue {
+                        max-in-flight 100
+                        fair {
+                            tier "free" weight=0
+                        }
+                    }
+                }
+            }
+        "#;
+        let err = parse_kdl_upstreams(zero_weight).unwrap_err().to_string();
+        assert!(err.contains("tier weight"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_consistent_hash_config() {
+        let kdl = r#"
+            upstreams {
+                upstream "sessions" {
+                    target "10.0.0.1:8080"
+                    target "10.0.0.2:8080"
+                    load-balancing "consistent_hash" {
+                        hash-key "header" "X-User-Id"
+                        virtual-nodes 64
+                        max-load-factor 1.5
+                    }
+                }
+                upstream "by-cookie" {
+                    target "10.0.0.1:8080"
+                    load-balancing "consistent_hash" {
+                        hash-key "cookie" "session"
+                        bounded-load #false
+                    }
+                }
+                upstream "plain" {
+                    target "10.0.0.1:8080"
+                    load-balancing "consistent_hash"
+                }
+            }
+        "#;
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        let hash = upstreams["sessions"].consistent_hash.as_ref().unwrap();
+        assert_eq!(hash.key, HashKeySource::Header("x-user-id".to_string()));
+        assert_eq!(hash.virtual_nodes, 64);
+        assert!(hash.bounded_load);
+        assert_eq!(hash.max_load_factor, 1.5);
+
+        let hash = upstreams["by-cookie"].consistent_hash.as_ref().unwrap();
+        assert_eq!(hash.key, HashKeySource::Cookie("session".to_string()));
+        assert!(!hash.bounded_load);
+
+        assert!(upstreams["plain"].consistent_hash.is_none());
+    }
+
+    #[test]
+    fn test_parse_consistent_hash_invalid_key() {
+        let kdl = r#"
+            upstreams {
+                upstream "bad" {
+                    target "10.0.0.1:8080"
+                    load-balancing "consistent_hash" {
+                        hash-key "header"
+                    }
+                }
+            }
+        "#;
+        assert!(parse_kdl_upstreams(kdl).is_err());
+    }
+
+    #[test]
+    fn test_parse_http_health_check_probe() {
+        let kdl = r#"
+        upstreams {
+            upstream "api" {
+                target "10.0.0.1:8080"
+                health-check {
+                    type "http" {
+                        path "/ready"
+                        method "head"
+                        expected-status 204
+                    }
+                    interval-secs 3
+                    unhealthy-threshold 2
+                }
+            }
+            upstream "web" {
+                target "10.0.0.2:8080"
+                health-check {
+                    type "http" {
+                        expected-body "OK"
+                    }
+                }
+            }
+        }
+        "#;
+
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+
+        let hc = upstreams.get("api").unwrap().health_check.as_ref().unwrap();
+        assert_eq!(hc.interval_secs, 3);
+        assert_eq!(hc.unhealthy_threshold, 2);
+        assert_eq!(
+            hc.check_type,
+            HealthCheckType::Http {
+                path: "/ready".to_string(),
+                expected_status: 204,
+                host: None,
+                method: HealthCheckMethod::Head,
+                expected_body: None,
+            }
+        );
+
+        let hc = upstreams.get("web").unwrap().health_check.as_ref().unwrap();
+        match &hc.check_type {
+            HealthCheckType::Http {
+                path,
+                method,
+                expected_body,
+                ..
+            } => {
+                assert_eq!(path, "/health");
+                assert_eq!(*method, HealthCheckMethod::Get);
+                assert_eq!(expected_body.as_deref(), Some("OK"));
+            }
+            other => panic!("expected HTTP health check, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_parse_upstream_tls_full() {
         let kdl = r#"
@@ -831,6 +1766,52 @@ mod tests {
         assert_eq!(tls.ca_cert, Some(PathBuf::from("/path/to/ca.crt")));
     }
 
+    #[test]
+    fn test_parse_upstream_tls_env_alpn_and_spiffe() {
+        let kdl = r#"
+        upstreams {
+            upstream "mesh-backend" {
+                target "10.0.0.1:8443"
+                tls {
+                    client-cert-env "BACKEND_CLIENT_CERT"
+                    client-key-env "BACKEND_CLIENT_KEY"
+                    ca-cert-env "MESH_CA"
+                    alpn "h2" "http/1.1"
+                    spiffe-id "spiffe://example.org/ns/prod/sa/api"
+                    spiffe-id "spiffe://partner.example"
+                }
+            }
+        }
+        "#;
+
+        let upstreams = parse_kdl_upstreams(kdl).unwrap();
+        let tls = upstreams["mesh-backend"].tls.as_ref().unwrap();
+
+        assert_eq!(tls.client_cert_env.as_deref(), Some("BACKEND_CLIENT_CERT"));
+        assert_eq!(tls.client_key_env.as_deref(), Some("BACKEND_CLIENT_KEY"));
+        assert_eq!(tls.ca_cert_env.as_deref(), Some("MESH_CA"));
+        assert!(tls.has_client_cert() && tls.has_client_key());
+        assert_eq!(tls.alpn, vec![AlpnProtocol::H2, AlpnProtocol::Http11]);
+        assert_eq!(
+            tls.spiffe_ids,
+            vec![
+                "spiffe://example.org/ns/prod/sa/api".to_string(),
+                "spiffe://partner.example".to_string()
+            ]
+        );
+
+        let invalid = r#"
+        upstreams {
+            upstream "bad" {
+                target "10.0.0.1:8443"
+                tls { alpn "h3" }
+            }
+        }
+        "#;
+        let err = parse_kdl_upstreams(invalid).unwrap_err().to_string();
+        assert!(err.contains("Unknown ALPN protocol 'h3'"), "{err}");
+    }
+
     #[test]
     fn test_parse_upstream_tls_minimal() {
         let kdl = r#"
@@ -12,6 +12,9 @@
 //! - [`waf`]: WAF (Web Application Firewall) configuration
 //! - [`observability`]: Metrics, logging, and tracing configuration
 //! - [`filters`]: Filter types for request/response processing
+//! - [`tenants`]: Tenant ownership and quotas
+//! - [`notifications`]: Webhook notifications for security events
+//! - `schema`: JSON Schema of the configuration (`schema` feature)
 //! - [`validation`]: Configuration validation functions
 //! - `kdl`: KDL format parsing
 //! - `defaults`: Default embedded configuration
@@ -40,16 +43,23 @@ use zentinel_common::{
 
 pub mod agents;
 mod defaults;
+pub mod expr;
 pub mod filters;
 pub mod flatten;
+pub mod json_path;
 mod kdl;
 #[cfg(feature = "runtime")]
 pub mod multi_file;
 pub mod namespace;
+pub mod notifications;
 pub mod observability;
 pub mod resolution;
 pub mod routes;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod secrets;
 pub mod server;
+pub mod tenants;
 pub mod upstreams;
 #[cfg(feature = "validation")]
 pub mod validate;
@@ -62,15 +72,21 @@ pub mod waf;
 
 // Agents
 pub use agents::{
-    AgentConfig, AgentEvent, AgentPoolConfig, AgentTlsConfig, AgentTransport, AgentType,
-    BodyStreamingMode, LoadBalanceStrategy,
+    default_agent_socket_path, AgentConfig, AgentEvent, AgentEventTimeouts, AgentPoolConfig,
+    AgentProcessConfig, AgentRestartPolicy, AgentSharedMemoryConfig, AgentSlowStartConfig,
+    AgentStateQuota, AgentTlsConfig, AgentTransport, AgentType, BodyStreamingMode,
+    LoadBalanceStrategy, SessionStoreBackend, SessionStoreConfig, DEFAULT_AGENT_SOCKET_DIR,
 };
 
 // Defaults
 pub use defaults::{create_default_config, DEFAULT_CONFIG_KDL};
 
+// Expressions
+pub use expr::{ExprContext, ExprError, Expression};
+
 // Filters
 pub use filters::*;
+pub use json_path::{JsonPath, JsonPathError, JsonPathSegment};
 // Explicit re-exports for gateway controller
 pub use filters::{Filter, FilterConfig, PathModifier, RedirectFilter, UrlRewriteFilter};
 
@@ -87,43 +103,73 @@ pub use flatten::FlattenedConfig;
 // Resolution
 pub use resolution::ResourceResolver;
 
+// Notifications
+pub use notifications::{
+    AlertMetric, AlertOperator, AlertRuleConfig, BlockRateAlertConfig, NotificationEvent,
+    NotificationSeverity, NotificationsConfig, WebhookConfig,
+};
+
 // Observability
 pub use observability::{
-    AccessLogConfig, AccessLogFields, AuditLogConfig, ErrorLogConfig, LoggingConfig, MetricsConfig,
+    AccessLogConfig, AccessLogFields, AuditLogConfig, AuditStoreBackend, AuditStoreConfig,
+    ErrorLogConfig, JournalFsync, LogJournalConfig, LoggingConfig, MetricsConfig,
     ObservabilityConfig, TracingBackend, TracingConfig,
 };
 
 // Routes
 pub use routes::{
-    ApiSchemaConfig, BuiltinHandler, CacheBackend, CacheStorageConfig, ErrorFormat, ErrorPage,
-    ErrorPageConfig, FailureMode, FallbackConfig, FallbackTriggers, FallbackUpstream,
-    GuardrailAction, GuardrailFailureMode, GuardrailsConfig, HeaderModifications, InferenceConfig,
-    InferenceProvider, InferenceRouting, InferenceRoutingStrategy, MatchCondition,
-    ModelRoutingConfig, ModelUpstreamMapping, PiiAction, PiiDetectionConfig, PromptInjectionConfig,
-    RateLimitPolicy, RouteCacheConfig, RouteConfig, RoutePolicies, ServiceType, StaticFileConfig,
-    TokenEstimation, TokenRateLimit,
+    AdaptiveConcurrencyConfig, ApiSchemaConfig, BlockPageConfig, BlockPageFormat, BodyFraming,
+    BuiltinHandler,
+    CacheBackend, CacheCoalesceConfig, CacheStorageConfig, ChallengeConfig, ConcurrencyLimitConfig,
+    DecisionMergeStrategy, ErrorFormat, ErrorPage, ErrorPageConfig, FailureMode, FallbackConfig,
+    FallbackTriggers, FallbackUpstream, GuardrailAction, GuardrailEmbeddingConfig,
+    GuardrailFailureMode, GuardrailsConfig, HeaderModifications, InferenceConfig,
+    InferenceKeyPoolConfig, InferenceKeyPoolMember, InferenceProvider, InferenceRouting,
+    InferenceRoutingStrategy, MatchCondition, ModelRoutingConfig, ModelUpstreamMapping, PiiAction,
+    PiiDetectionConfig, PriorityClass, ProblemDetailsConfig, ProblemMapping, PromptInjectionConfig,
+    RateLimitPolicy, RouteCacheConfig, RouteConfig, RoutePolicies, ServerTimingConfig, ServiceType,
+    StaticFileConfig, TokenEstimation, TokenRateLimit,
 };
 
 // Server
-pub use server::{ListenerConfig, ListenerProtocol, ServerConfig, SniCertificate, TlsConfig};
+pub use server::{
+    CaptureConfig, ChaosConfig, ChaosFault, ChaosFaultKind, ChaosTarget, ClientIpConfig,
+    ClientIpHeader, DebugHeadersConfig, DestinationRule, ForwardProxyConfig, ForwardProxyUser,
+    HardeningStatusCodes, IpCidr, ListenerConfig, ListenerProtocol, MaintenanceConfig,
+    NormalizationMode, ObsFoldPolicy, OutboundProxyConfig, QuicConfig, ReadinessConfig,
+    RedisStreamConfig, RequestHardeningConfig, RequestIdConfig, RequestNormalizationConfig,
+    RolloutConfig, ServerConfig, SlowClientConfig, SniCertificate, StreamProxyConfig, StreamRoute,
+    TlsConfig,
+};
+
+// Tenants
+pub use tenants::{TenantConfig, TenantQuotas};
 
 // Re-export TraceIdFormat from common for convenience
 pub use zentinel_common::TraceIdFormat;
 
 // Re-export budget types from common for convenience
 pub use zentinel_common::budget::{
-    BudgetPeriod, CostAttributionConfig, ModelPricing, TokenBudgetConfig,
+    BudgetPeriod, CostAttributionConfig, CostBudgetAction, CostBudgetConfig, CostBudgetScope,
+    ModelPricing, TokenBudgetConfig,
 };
 
 // Upstreams
 pub use upstreams::{
-    ConnectionPoolConfig, HealthCheck, HttpVersionConfig, UpstreamConfig, UpstreamPeer,
-    UpstreamTarget, UpstreamTimeouts, UpstreamTlsConfig,
+    is_valid_spiffe_id, AlpnProtocol, ConnectionPoolConfig, ConsistentHashConfig, DnsConfig,
+    FairQueueConfig, FairQueueKey, HashKeySource, HealthCheck, HttpVersionConfig,
+    IpFamilyPreference, OAuth2ClientAuth, ServiceDiscoveryConfig, TcpKeepaliveConfig,
+    UpstreamAuthConfig, UpstreamConfig, UpstreamPeer, UpstreamQueueConfig, UpstreamTarget,
+    UpstreamTimeouts, UpstreamTlsConfig,
 };
 
 // Validation
 pub use validation::ValidationContext;
 
+// JSON Schema
+#[cfg(feature = "schema")]
+pub use schema::config_schema;
+
 // WAF
 pub use waf::{
     BodyInspectionPolicy, ExclusionScope, RuleExclusion, WafConfig, WafEngine, WafMode, WafRuleset,
@@ -144,6 +190,7 @@ pub const MIN_SCHEMA_VERSION: &str = "1.0";
 
 /// Main configuration structure for Zentinel proxy
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[validate(schema(function = "validation::validate_config_semantics"))]
 pub struct Config {
     /// Configuration schema version for compatibility checking
@@ -186,6 +233,13 @@ pub struct Config {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub namespaces: Vec<NamespaceConfig>,
 
+    /// Tenant definitions for multi-tenant deployments.
+    ///
+    /// Tenants group listeners, routes, agents and namespaces under a name
+    /// with shared quotas. Resources not owned by any tenant are shared.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tenants: Vec<TenantConfig>,
+
     /// Global limits configuration
     #[serde(default)]
     pub limits: Limits,
@@ -202,6 +256,24 @@ pub struct Config {
     #[serde(default)]
     pub cache: Option<CacheStorageConfig>,
 
+    /// Key-value store the proxy keeps for agents
+    ///
+    /// When unset, agent state requests are refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_store: Option<SessionStoreConfig>,
+
+    /// Resolution of upstream hostnames
+    ///
+    /// When unset, the system resolver is used with the default TTL limits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsConfig>,
+
+    /// Webhook notifications for security and availability events
+    ///
+    /// When unset, no notifications are sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationsConfig>,
+
     /// Default upstream for Phase 0 testing
     #[serde(skip)]
     pub default_upstream: Option<UpstreamPeer>,
@@ -350,10 +422,7 @@ impl Config {
         );
 
         let config = match extension {
-            "kdl" => {
-                let expanded = Self::expand_kdl_includes(&content, path)?;
-                Self::from_kdl(&expanded)
-            }
+            "kdl" => Self::load_kdl_file(&content, path).and_then(kdl::parse_kdl_document),
             "json" => Self::from_json(&content),
             "toml" => Self::from_toml(&content),
             _ => Err(anyhow::anyhow!("Unsupported config format: {}", extension)),
@@ -371,64 +440,57 @@ impl Config {
         Ok(config)
     }
 
-    /// Expand `include` directives in KDL content.
+    /// Parse a KDL config file, expanding `include` directives.
     ///
-    /// This pre-processes the KDL string, resolving any `include` directives
-    /// by reading the referenced files (supporting glob patterns) and
-    /// recursively expanding their includes. Returns a single KDL string
-    /// with all includes inlined.
+    /// `include "pattern"` inserts the top-level nodes of every file matching
+    /// the glob, relative to the including file and in sorted order, so
+    /// `listeners`, `routes`, `upstreams`, `filters` and `agents` blocks from
+    /// several files are merged. Each file is parsed and its references
+    /// resolved on its own, so errors name the file and line they come from.
     #[cfg(feature = "runtime")]
-    fn expand_kdl_includes(content: &str, source_path: &Path) -> Result<String> {
-        // Quick check: if there are no include directives, return as-is
-        let doc: ::kdl::KdlDocument = content
-            .parse()
-            .map_err(|e| anyhow::anyhow!("KDL parse error during include expansion: {}", e))?;
-
-        let has_includes = doc.nodes().iter().any(|n| n.name().value() == "include");
-        if !has_includes {
-            return Ok(content.to_string());
-        }
-
-        let mut visited = HashSet::new();
-        Self::expand_includes_recursive(content, source_path, &mut visited)
+    fn load_kdl_file(content: &str, path: &Path) -> Result<::kdl::KdlDocument> {
+        let mut doc = ::kdl::KdlDocument::new();
+        let mut includes = IncludeState::default();
+        Self::expand_includes_recursive(content, path, &mut includes, doc.nodes_mut())?;
+        Ok(doc)
     }
 
     /// Fallback when the `runtime` feature is not enabled.
     #[cfg(not(feature = "runtime"))]
-    fn expand_kdl_includes(content: &str, source_path: &Path) -> Result<String> {
-        // Check if the content has include directives
-        let doc: ::kdl::KdlDocument = content
-            .parse()
-            .map_err(|e| anyhow::anyhow!("KDL parse error during include expansion: {}", e))?;
+    fn load_kdl_file(content: &str, path: &Path) -> Result<::kdl::KdlDocument> {
+        let mut doc = parse_kdl_source(content, Some(path))?;
 
         let has_includes = doc.nodes().iter().any(|n| n.name().value() == "include");
         if has_includes {
             return Err(anyhow::anyhow!(
                 "The 'include' directive in '{}' requires the 'runtime' feature.\n\
                  Build with: cargo build --features runtime",
-                source_path.display()
+                path.display()
             ));
         }
 
-        Ok(content.to_string())
+        secrets::resolve_kdl_document(&mut doc, content)
+            .with_context(|| format!("Failed to resolve config secrets in '{}'", path.display()))?;
+        Ok(doc)
     }
 
     /// Recursively expand include directives in a KDL file.
     ///
-    /// Parses the KDL content, replaces `include` nodes with the contents of
-    /// the referenced files, and returns the merged KDL string. Uses a visited
-    /// set of canonical paths to detect and prevent circular includes.
+    /// Appends the top-level nodes of `content` to `nodes`, replacing
+    /// `include` nodes with the nodes of the referenced files. Circular
+    /// includes and IDs defined more than once are rejected.
     #[cfg(feature = "runtime")]
     fn expand_includes_recursive(
         content: &str,
         source_path: &Path,
-        visited: &mut HashSet<PathBuf>,
-    ) -> Result<String> {
+        includes: &mut IncludeState,
+        nodes: &mut Vec<::kdl::KdlNode>,
+    ) -> Result<()> {
         let canonical = source_path
             .canonicalize()
             .with_context(|| format!("Failed to resolve config path: {}", source_path.display()))?;
 
-        if !visited.insert(canonical.clone()) {
+        if !includes.visited.insert(canonical.clone()) {
             return Err(anyhow::anyhow!(
                 "Circular include detected: '{}' has already been included",
                 source_path.display()
@@ -442,12 +504,14 @@ impl Config {
             )
         })?;
 
-        let doc: ::kdl::KdlDocument = content.parse().map_err(|e| {
-            anyhow::anyhow!("KDL parse error in '{}': {}", source_path.display(), e)
+        let mut doc = parse_kdl_source(content, Some(source_path))?;
+        secrets::resolve_kdl_document(&mut doc, content).with_context(|| {
+            format!(
+                "Failed to resolve config secrets in '{}'",
+                source_path.display()
+            )
         })?;
 
-        let mut output = String::new();
-
         for node in doc.nodes() {
             if node.name().value() == "include" {
                 let pattern = node
@@ -513,10 +577,7 @@ impl Config {
                         )
                     })?;
 
-                    let expanded =
-                        Self::expand_includes_recursive(&included_content, &path, visited)?;
-                    output.push_str(&expanded);
-                    output.push('\n');
+                    Self::expand_includes_recursive(&included_content, &path, includes, nodes)?;
                 }
 
                 if !matched_any {
@@ -527,13 +588,12 @@ impl Config {
                     );
                 }
             } else {
-                // Serialize the node back to KDL
-                output.push_str(&node.to_string());
-                output.push('\n');
+                includes.check_ids(node, content, source_path)?;
+                nodes.push(node.clone());
             }
         }
 
-        Ok(output)
+        Ok(())
     }
 
     /// Load the default embedded configuration.
@@ -556,86 +616,30 @@ impl Config {
     /// Parse configuration from KDL format
     pub fn from_kdl(content: &str) -> Result<Self> {
         trace!(content_length = content.len(), "Parsing KDL configuration");
-        let doc: ::kdl::KdlDocument = content.parse().map_err(|e: ::kdl::KdlError| {
-            use miette::Diagnostic;
-
-            let mut error_msg = String::new();
-            error_msg.push_str("KDL configuration parse error:\n\n");
-
-            let mut found_details = false;
-            if let Some(related) = e.related() {
-                for diagnostic in related {
-                    let diag_str = format!("{}", diagnostic);
-                    error_msg.push_str(&format!("  {}\n", diag_str));
-                    found_details = true;
-
-                    if let Some(labels) = diagnostic.labels() {
-                        for label in labels {
-                            let offset = label.offset();
-                            let (line, col) = kdl::offset_to_line_col(content, offset);
-                            error_msg
-                                .push_str(&format!("\n  --> at line {}, column {}\n", line, col));
-
-                            let lines: Vec<&str> = content.lines().collect();
-
-                            if line > 1 {
-                                if let Some(lc) = lines.get(line.saturating_sub(2)) {
-                                    error_msg.push_str(&format!("{:>4} | {}\n", line - 1, lc));
-                                }
-                            }
-
-                            if let Some(line_content) = lines.get(line.saturating_sub(1)) {
-                                error_msg.push_str(&format!("{:>4} | {}\n", line, line_content));
-                                error_msg.push_str(&format!(
-                                    "     | {}^",
-                                    " ".repeat(col.saturating_sub(1))
-                                ));
-                                if let Some(label_msg) = label.label() {
-                                    error_msg.push_str(&format!(" {}", label_msg));
-                                }
-                                error_msg.push('\n');
-                            }
-
-                            if let Some(lc) = lines.get(line) {
-                                error_msg.push_str(&format!("{:>4} | {}\n", line + 1, lc));
-                            }
-                        }
-                    }
-
-                    if let Some(help) = diagnostic.help() {
-                        error_msg.push_str(&format!("\n  Help: {}\n", help));
-                    }
-                }
-            }
-
-            if !found_details {
-                error_msg.push_str(&format!("  {}\n", e));
-                error_msg.push_str("\n  Note: Check your KDL syntax. Common issues:\n");
-                error_msg.push_str("    - Unclosed strings (missing closing quote)\n");
-                error_msg.push_str("    - Unclosed blocks (missing closing brace)\n");
-                error_msg.push_str("    - Invalid node names or values\n");
-            }
-
-            if let Some(help) = e.help() {
-                error_msg.push_str(&format!("\n  Help: {}\n", help));
-            }
-
-            anyhow::anyhow!("{}", error_msg)
-        })?;
-
+        let mut doc = parse_kdl_source(content, None)?;
+        secrets::resolve_kdl_document(&mut doc, content)
+            .context("Failed to resolve config secrets")?;
         kdl::parse_kdl_document(doc)
     }
 
     /// Parse configuration from JSON format
     pub fn from_json(content: &str) -> Result<Self> {
         trace!(content_length = content.len(), "Parsing JSON configuration");
-        serde_json::from_str(content).context("Failed to parse JSON configuration")
+        let mut value: serde_json::Value =
+            serde_json::from_str(content).context("Failed to parse JSON configuration")?;
+        secrets::resolve_json(&mut value).context("Failed to resolve config secrets")?;
+        serde_json::from_value(value).context("Failed to parse JSON configuration")
     }
 
     /// Parse configuration from TOML format
     pub fn from_toml(content: &str) -> Result<Self> {
         trace!(content_length = content.len(), "Parsing TOML configuration");
-        toml::from_str(content).context("Failed to parse TOML configuration")
+        let mut value: toml::Value =
+            toml::from_str(content).context("Failed to parse TOML configuration")?;
+        secrets::resolve_toml(&mut value).context("Failed to resolve config secrets")?;
+        value
+            .try_into()
+            .context("Failed to parse TOML configuration")
     }
 
     /// Check schema version compatibility
@@ -747,7 +751,7 @@ impl Config {
 
     fn validate_upstreams(&self) -> ZentinelResult<()> {
         for (id, upstream) in &self.upstreams {
-            if upstream.targets.is_empty() {
+            if upstream.targets.is_empty() && upstream.discovery.is_none() {
                 return Err(ZentinelError::Config {
                     message: format!("Upstream '{}' has no targets", id),
                     source: None,
@@ -798,11 +802,15 @@ impl Config {
                 }],
                 load_balancing: LoadBalancingAlgorithm::RoundRobin,
                 sticky_session: None,
+                consistent_hash: None,
+                discovery: None,
                 health_check: None,
                 circuit_breaker: None,
                 connection_pool: ConnectionPoolConfig::default(),
                 timeouts: UpstreamTimeouts::default(),
                 tls: None,
+                auth: None,
+                queue: None,
                 http_version: HttpVersionConfig::default(),
             },
         );
@@ -821,6 +829,17 @@ impl Config {
                 trace_id_format: Default::default(),
                 auto_reload: false,
                 route_cache_size: 1000,
+                client_ip: Default::default(),
+                outbound_proxy: Default::default(),
+                maintenance: Default::default(),
+                concurrency: None,
+                request_id: Default::default(),
+                problem_details: None,
+                readiness: Default::default(),
+                chaos: Default::default(),
+                capture: None,
+                rollout: None,
+                debug_headers: None,
             },
             listeners: vec![ListenerConfig {
                 id: "http".to_string(),
@@ -833,6 +852,13 @@ impl Config {
                 keepalive_timeout_secs: 75,
                 max_concurrent_streams: 100,
                 keepalive_max_requests: None,
+                proxy_protocol: false,
+                bot_signals: false,
+                quic: Default::default(),
+                slow_client: Default::default(),
+                hardening: Default::default(),
+                forward_proxy: None,
+                stream: None,
             }],
             routes: vec![RouteConfig {
                 id: "default".to_string(),
@@ -859,10 +885,14 @@ impl Config {
             agents: vec![],
             waf: None,
             namespaces: vec![],
+            tenants: vec![],
             limits: Limits::for_testing(),
             observability: ObservabilityConfig::default(),
             rate_limits: GlobalRateLimitConfig::default(),
             cache: None,
+            session_store: None,
+            dns: None,
+            notifications: None,
             default_upstream: Some(UpstreamPeer {
                 address: "127.0.0.1:8081".to_string(),
                 tls: false,
@@ -916,6 +946,145 @@ impl Config {
     }
 }
 
+// ============================================================================
+// KDL Loading Helpers
+// ============================================================================
+
+/// Parse KDL text, formatting parse errors with the offending lines
+///
+/// `path` is named in the error when the text was read from a file.
+fn parse_kdl_source(content: &str, path: Option<&Path>) -> Result<::kdl::KdlDocument> {
+    content.parse().map_err(|e: ::kdl::KdlError| {
+        use miette::Diagnostic;
+
+        let mut error_msg = String::new();
+        match path {
+            Some(path) => error_msg.push_str(&format!(
+                "KDL configuration parse error in '{}':\n\n",
+                path.display()
+            )),
+            None => error_msg.push_str("KDL configuration parse error:\n\n"),
+        }
+
+        let mut found_details = false;
+        if let Some(related) = e.related() {
+            for diagnostic in related {
+                let diag_str = format!("{}", diagnostic);
+                error_msg.push_str(&format!("  {}\n", diag_str));
+                found_details = true;
+
+                if let Some(labels) = diagnostic.labels() {
+                    for label in labels {
+                        let offset = label.offset();
+                        let (line, col) = kdl::offset_to_line_col(content, offset);
+                        error_msg.push_str(&format!("\n  --> at line {}, column {}\n", line, col));
+
+                        let lines: Vec<&str> = content.lines().collect();
+
+                        if line > 1 {
+                            if let Some(lc) = lines.get(line.saturating_sub(2)) {
+                                error_msg.push_str(&format!("{:>4} | {}\n", line - 1, lc));
+                            }
+                        }
+
+                        if let Some(line_content) = lines.get(line.saturating_sub(1)) {
+                            error_msg.push_str(&format!("{:>4} | {}\n", line, line_content));
+                            error_msg.push_str(&format!(
+                                "     | {}^",
+                                " ".repeat(col.saturating_sub(1))
+                            ));
+                            if let Some(label_msg) = label.label() {
+                                error_msg.push_str(&format!(" {}", label_msg));
+                            }
+                            error_msg.push('\n');
+                        }
+
+                        if let Some(lc) = lines.get(line) {
+                            error_msg.push_str(&format!("{:>4} | {}\n", line + 1, lc));
+                        }
+                    }
+                }
+
+                if let Some(help) = diagnostic.help() {
+                    error_msg.push_str(&format!("\n  Help: {}\n", help));
+                }
+            }
+        }
+
+        if !found_details {
+            error_msg.push_str(&format!("  {}\n", e));
+            error_msg.push_str("\n  Note: Check your KDL syntax. Common issues:\n");
+            error_msg.push_str("    - Unclosed strings (missing closing quote)\n");
+            error_msg.push_str("    - Unclosed blocks (missing closing brace)\n");
+            error_msg.push_str("    - Invalid node names or values\n");
+        }
+
+        if let Some(help) = e.help() {
+            error_msg.push_str(&format!("\n  Help: {}\n", help));
+        }
+
+        anyhow::anyhow!("{}", error_msg)
+    })
+}
+
+/// State shared while expanding the includes of a config file
+#[cfg(feature = "runtime")]
+#[derive(Default)]
+struct IncludeState {
+    /// Canonical paths of the files included so far
+    visited: HashSet<PathBuf>,
+    /// File and line each listener, route, upstream, filter, agent and
+    /// tenant ID was defined at
+    ids: HashMap<(&'static str, String), (PathBuf, usize)>,
+}
+
+#[cfg(feature = "runtime")]
+impl IncludeState {
+    /// Record the IDs defined by a top-level node, rejecting duplicates
+    fn check_ids(&mut self, node: &::kdl::KdlNode, content: &str, path: &Path) -> Result<()> {
+        let kind = match node.name().value() {
+            "tenant" => "tenant",
+            "listeners" => "listener",
+            "routes" => "route",
+            "upstreams" => "upstream",
+            "filters" => "filter",
+            "agents" => "agent",
+            _ => return Ok(()),
+        };
+        let defined: Vec<&::kdl::KdlNode> = if kind == "tenant" {
+            vec![node]
+        } else {
+            node.children()
+                .map(|children| {
+                    children
+                        .nodes()
+                        .iter()
+                        .filter(|child| child.name().value() == kind)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        for item in defined {
+            // A missing ID is reported when the block is parsed
+            let Some(id) = kdl::get_first_arg_string(item) else {
+                continue;
+            };
+            let (line, _) = kdl::span_line_col(content, item.span());
+            if let Some((first_path, first_line)) = self.ids.get(&(kind, id.clone())) {
+                return Err(anyhow::anyhow!(
+                    "Duplicate {kind} ID '{id}' in '{}' at line {line}, already defined in '{}' \
+                     at line {first_line}. Each {kind} ID must be unique across all config files.",
+                    path.display(),
+                    first_path.display(),
+                ));
+            }
+            self.ids.insert((kind, id), (path.to_path_buf(), line));
+        }
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1297,6 +1466,88 @@ include "vhosts/*.kdl"
         );
     }
 
+    #[test]
+    fn test_include_interpolation_and_error_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let teams_dir = dir.path().join("teams");
+        std::fs::create_dir(&teams_dir).unwrap();
+        let main_config = dir.path().join("zentinel.kdl");
+        std::fs::write(
+            &main_config,
+            r#"
+schema-version "1.0"
+system {
+    worker-threads 4
+}
+listeners {
+    listener "http" {
+        address "0.0.0.0:${ZENTINEL_TEST_UNSET_PORT:-8080}"
+        protocol "http"
+    }
+}
+include "teams/*.kdl"
+"#,
+        )
+        .unwrap();
+
+        let team_file = teams_dir.join("payments.kdl");
+        std::fs::write(
+            &team_file,
+            r#"
+upstreams {
+    upstream "payments" {
+        target "${ZENTINEL_TEST_UNSET_HOST:-127.0.0.1}:3000"
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&main_config).unwrap();
+        assert_eq!(config.listeners[0].address, "0.0.0.0:8080");
+        assert_eq!(
+            config.upstreams["payments"].targets[0].address,
+            "127.0.0.1:3000"
+        );
+
+        // A required variable names the file and line it is used at
+        std::fs::write(
+            teams_dir.join("search.kdl"),
+            r#"
+upstreams {
+    upstream "search" {
+        target "${ZENTINEL_TEST_UNSET_HOST:?set the search backend}"
+    }
+}
+"#,
+        )
+        .unwrap();
+        let err = format!("{:#}", Config::from_file(&main_config).unwrap_err());
+        assert!(err.contains("search.kdl"), "{}", err);
+        assert!(err.contains("line 4, column"), "{}", err);
+        assert!(err.contains("set the search backend"), "{}", err);
+
+        // A duplicate ID names both definitions
+        std::fs::write(
+            teams_dir.join("search.kdl"),
+            r#"
+upstreams {
+    upstream "payments" {
+        target "127.0.0.1:4000"
+    }
+}
+"#,
+        )
+        .unwrap();
+        let err = Config::from_file(&main_config).unwrap_err().to_string();
+        assert!(err.contains("Duplicate upstream ID 'payments'"), "{}", err);
+        assert!(
+            err.contains("payments.kdl' at line 3") && err.contains("search.kdl' at line 3"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_multi_file_duplicate_upstream_rejected() {
         let dir = tempfile::tempdir().unwrap();
@@ -11,7 +11,7 @@ use tracing::debug;
 
 use crate::{
     AgentConfig, Config, GlobalRateLimitConfig, Limits, ListenerConfig, NamespaceConfig,
-    ObservabilityConfig, RouteConfig, ServerConfig, UpstreamConfig, WafConfig,
+    ObservabilityConfig, RouteConfig, ServerConfig, TenantConfig, UpstreamConfig, WafConfig,
 };
 
 use super::parsers::{
@@ -35,6 +35,7 @@ pub(super) struct PartialConfig {
     pub limits: Option<Limits>,
     pub observability: Option<ObservabilityConfig>,
     pub namespaces: Vec<NamespaceConfig>,
+    pub tenants: Vec<TenantConfig>,
     /// Include directives found in this file (relative paths to include)
     pub includes: Vec<PathBuf>,
 }
@@ -86,6 +87,9 @@ impl PartialConfig {
                 "namespace" => {
                     config.namespaces.push(parse_namespace(node)?);
                 }
+                "tenant" => {
+                    config.tenants.push(crate::kdl::parse_tenant(node)?);
+                }
                 "metadata" => {
                     // Skip metadata for now - not part of the main config structure
                 }
@@ -117,6 +121,7 @@ pub(super) struct ConfigBuilder {
     limits: Option<Limits>,
     observability: Option<ObservabilityConfig>,
     namespaces: Vec<NamespaceConfig>,
+    tenants: Vec<TenantConfig>,
 
     // Tracking for duplicates
     listener_ids: HashSet<String>,
@@ -125,6 +130,7 @@ pub(super) struct ConfigBuilder {
     filter_ids: HashSet<String>,
     agent_ids: HashSet<String>,
     namespace_ids: HashSet<String>,
+    tenant_ids: HashSet<String>,
 }
 
 impl ConfigBuilder {
@@ -140,11 +146,13 @@ impl ConfigBuilder {
             limits: None,
             observability: None,
             namespaces: Vec::new(),
+            tenants: Vec::new(),
             listener_ids: HashSet::new(),
             route_ids: HashSet::new(),
             filter_ids: HashSet::new(),
             agent_ids: HashSet::new(),
             namespace_ids: HashSet::new(),
+            tenant_ids: HashSet::new(),
         }
     }
 
@@ -210,6 +218,18 @@ impl ConfigBuilder {
             self.namespaces.push(namespace);
         }
 
+        // Merge tenants
+        for tenant in partial.tenants {
+            if !self.tenant_ids.insert(tenant.id.clone()) {
+                return Err(anyhow!(
+                    "Duplicate tenant '{}' in {:?}",
+                    tenant.id,
+                    partial.source_file
+                ));
+            }
+            self.tenants.push(tenant);
+        }
+
         // Merge singleton configs (last wins with warnings)
         if partial.server.is_some() {
             if self.server.is_some() {
@@ -259,10 +279,14 @@ impl ConfigBuilder {
             agents: self.agents,
             waf: self.waf,
             namespaces: self.namespaces,
+            tenants: self.tenants,
             limits: self.limits.unwrap_or_default(),
             observability: self.observability.unwrap_or_default(),
             rate_limits: GlobalRateLimitConfig::default(),
             cache: None,
+            session_store: None,
+            dns: None,
+            notifications: None,
             default_upstream: None,
         })
     }
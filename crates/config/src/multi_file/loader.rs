@@ -251,9 +251,11 @@ impl MultiFileLoader {
         let content =
             fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
 
-        let doc: KdlDocument = content
+        let mut doc: KdlDocument = content
             .parse()
             .with_context(|| format!("Failed to parse KDL file: {:?}", path))?;
+        crate::secrets::resolve_kdl_document(&mut doc, &content)
+            .with_context(|| format!("Failed to resolve secrets in {:?}", path))?;
 
         PartialConfig::from_kdl(doc, path)
     }
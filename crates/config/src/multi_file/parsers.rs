@@ -9,7 +9,14 @@ use std::path::PathBuf;
 
 use zentinel_common::TraceIdFormat;
 
-use crate::kdl::parse_circuit_breaker_faildefault;
+use crate::kdl::{
+    parse_agent_event_timeouts, parse_agent_pool, parse_agent_process, parse_agent_slow_start,
+    parse_agent_state_quota, parse_capture_config, parse_chaos_config,
+    parse_circuit_breaker_faildefault, parse_client_ip_config, parse_concurrency_limit_config,
+    parse_debug_headers_config, parse_hardening_config, parse_maintenance_config,
+    parse_outbound_proxy_config, parse_problem_details_config, parse_readiness_config,
+    parse_request_id_config, parse_rollout_config, parse_slow_client_config,
+};
 use crate::namespace::ExportConfig;
 use crate::{
     AgentConfig, Limits, ListenerConfig, NamespaceConfig, ObservabilityConfig, RouteConfig,
@@ -117,6 +124,67 @@ pub(super) fn parse_server(node: &KdlNode) -> Result<ServerConfig> {
         route_cache_size: get_int_entry(node, "route-cache-size")
             .map(|v| v as usize)
             .unwrap_or_else(crate::server::default_route_cache_size),
+        client_ip: node
+            .children()
+            .and_then(|children| children.get("client-ip"))
+            .map(parse_client_ip_config)
+            .transpose()?
+            .unwrap_or_default(),
+        outbound_proxy: node
+            .children()
+            .and_then(|children| children.get("outbound-proxy"))
+            .map(parse_outbound_proxy_config)
+            .transpose()?
+            .unwrap_or_default(),
+        maintenance: node
+            .children()
+            .and_then(|children| children.get("maintenance"))
+            .map(parse_maintenance_config)
+            .transpose()?
+            .unwrap_or_default(),
+        concurrency: node
+            .children()
+            .and_then(|children| children.get("concurrency"))
+            .map(parse_concurrency_limit_config)
+            .transpose()?,
+        request_id: node
+            .children()
+            .and_then(|children| children.get("request-id"))
+            .map(parse_request_id_config)
+            .transpose()?
+            .unwrap_or_default(),
+        problem_details: node
+            .children()
+            .and_then(|children| children.get("problem-details"))
+            .map(parse_problem_details_config)
+            .transpose()?,
+        readiness: node
+            .children()
+            .and_then(|children| children.get("readiness"))
+            .map(parse_readiness_config)
+            .transpose()?
+            .unwrap_or_default(),
+        chaos: node
+            .children()
+            .and_then(|children| children.get("chaos"))
+            .map(parse_chaos_config)
+            .transpose()?
+            .unwrap_or_default(),
+        capture: node
+            .children()
+            .and_then(|children| children.get("capture"))
+            .map(parse_capture_config)
+            .transpose()?,
+        rollout: node
+            .children()
+            .and_then(|children| children.get("rollout"))
+            .map(parse_rollout_config)
+            .transpose()?,
+        debug_headers: node
+            .children()
+            .and_then(|children| children.get("debug-headers"))
+            .map(parse_debug_headers_config)
+            .transpose()?,
     })
 }
 
@@ -143,6 +211,23 @@ pub(super) fn parse_listener(node: &KdlNode) -> Result<ListenerConfig> {
             .map(|v| v as u32)
             .unwrap_or(100),
         keepalive_max_requests: get_int_entry(node, "keepalive-max-requests").map(|v| v as u32),
+        proxy_protocol: get_bool_entry(node, "proxy-protocol").unwrap_or(false),
+        bot_signals: get_bool_entry(node, "bot-signals").unwrap_or(false),
+        quic: Default::default(),
+        slow_client: node
+            .children()
+            .and_then(|children| children.get("slow-client"))
+            .map(parse_slow_client_config)
+            .transpose()?
+            .unwrap_or_default(),
+        hardening: node
+            .children()
+            .and_then(|children| children.get("hardening"))
+            .map(parse_hardening_config)
+            .transpose()?
+            .unwrap_or_default(),
+        forward_proxy: None,
+        stream: None,
     })
 }
 
@@ -204,10 +289,9 @@ pub(super) fn parse_agent(node: &KdlNode) -> Result<AgentConfig> {
 
     // Parse transport - default to unix socket
     let socket_path = get_string_entry(node, "socket-path")
-        .unwrap_or_else(|| format!("/var/run/zentinel/{}.sock", id));
-    let transport = crate::AgentTransport::UnixSocket {
-        path: PathBuf::from(socket_path),
-    };
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::default_agent_socket_path(&id));
+    let transport = crate::AgentTransport::UnixSocket { path: socket_path };
 
     let circuit_breaker = node
         .children()
@@ -219,15 +303,22 @@ pub(super) fn parse_agent(node: &KdlNode) -> Result<AgentConfig> {
         .map(parse_circuit_breaker_faildefault)
         .transpose()?;
 
+    let process = parse_agent_process(node, &id)?;
+    let event_timeouts = parse_agent_event_timeouts(node, &id)?;
+    let slow_start = parse_agent_slow_start(node);
+    let state_quota = parse_agent_state_quota(node);
+    let pool = parse_agent_pool(node, &id)?;
+
     Ok(AgentConfig {
         id,
         agent_type,
         transport,
         events: vec![crate::AgentEvent::RequestHeaders],
-        pool: None,
+        pool,
         timeout_ms: get_int_entry(node, "timeout-ms")
             .map(|v| v as u64)
             .unwrap_or(100),
+        event_timeouts,
         failure_mode: match get_string_entry(node, "failure-mode").as_deref() {
             Some("closed") => crate::FailureMode::Closed,
             _ => crate::FailureMode::Open,
@@ -242,6 +333,10 @@ pub(super) fn parse_agent(node: &KdlNode) -> Result<AgentConfig> {
         max_concurrent_calls: get_int_entry(node, "max-concurrent-calls")
             .map(|v| v as usize)
             .unwrap_or(100),
+        max_message_size: get_int_entry(node, "max-message-size").map(|v| v as usize),
+        process,
+        slow_start,
+        state_quota,
     })
 }
 
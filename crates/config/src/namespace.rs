@@ -68,6 +68,7 @@ use crate::{AgentConfig, FilterConfig, ListenerConfig, RouteConfig, UpstreamConf
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NamespaceConfig {
     /// Unique namespace identifier.
     ///
@@ -195,6 +196,7 @@ impl NamespaceConfig {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ServiceConfig {
     /// Unique service identifier within the namespace.
     ///
@@ -282,6 +284,7 @@ impl ServiceConfig {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExportConfig {
     /// Upstream IDs to export globally.
     ///
@@ -333,11 +336,15 @@ mod tests {
             }],
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: None,
             circuit_breaker: None,
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         }
     }
@@ -0,0 +1,508 @@
+//! Webhook notifications for security and availability events.
+//!
+//! The top-level `notifications` block posts signed JSON payloads to
+//! webhook endpoints when notable events occur.
+//!
+//! ```kdl
+//! notifications {
+//!     cooldown-secs 300
+//!     certificate-expiry-days 14
+//!     guardrail-min-severity "high"
+//!     block-rate {
+//!         threshold 0.25
+//!         min-requests 100
+//!         window-secs 60
+//!     }
+//!     webhook "security" {
+//!         url "https://hooks.example.com/zentinel"
+//!         secret "${env:WEBHOOK_SECRET}"
+//!         events "circuit-breaker-open" "agent-unhealthy" "block-rate-spike"
+//!         max-retries 3
+//!     }
+//!     alert "agent-timeouts" {
+//!         condition "agent_timeout_rate > 5%"
+//!         for-secs 300
+//!         severity "critical"
+//!     }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Notifications
+// ============================================================================
+
+/// Webhook notifications for notable events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct NotificationsConfig {
+    /// Endpoints notified of events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Minimum time between two notifications of the same event for the
+    /// same subject (e.g. the same upstream target)
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+
+    /// When a spike of blocked requests is reported
+    #[serde(default)]
+    pub block_rate: BlockRateAlertConfig,
+
+    /// Certificates expiring within this many days are reported
+    #[serde(default = "default_certificate_expiry_days")]
+    pub certificate_expiry_days: u32,
+
+    /// Lowest guardrail detection severity that is reported
+    #[serde(default = "default_guardrail_min_severity")]
+    pub guardrail_min_severity: NotificationSeverity,
+
+    /// Alert rules evaluated over built-in metrics
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRuleConfig>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            cooldown_secs: default_cooldown_secs(),
+            block_rate: BlockRateAlertConfig::default(),
+            certificate_expiry_days: default_certificate_expiry_days(),
+            guardrail_min_severity: default_guardrail_min_severity(),
+            alerts: Vec::new(),
+        }
+    }
+}
+
+/// A webhook endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct WebhookConfig {
+    /// Webhook identifier (used in logs and metrics)
+    pub id: String,
+
+    /// URL receiving a POST per notification
+    pub url: String,
+
+    /// Key of the HMAC-SHA256 signature sent in `X-Zentinel-Signature`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Events sent to this webhook (all when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<NotificationEvent>,
+
+    /// Timeout of a single delivery attempt
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Retries after a failed delivery (network error, 429 or 5xx)
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for each further retry
+    #[serde(default = "default_webhook_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+impl WebhookConfig {
+    /// Webhook with default delivery settings, receiving all events
+    pub fn new(id: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            url: url.into(),
+            secret: None,
+            events: Vec::new(),
+            timeout_ms: default_webhook_timeout_ms(),
+            max_retries: default_webhook_max_retries(),
+            retry_backoff_ms: default_webhook_retry_backoff_ms(),
+        }
+    }
+
+    /// Whether this webhook receives `event`
+    pub fn wants(&self, event: NotificationEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Events that can be notified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationEvent {
+    /// A circuit breaker (upstream target or agent) opened
+    CircuitBreakerOpen,
+    /// An agent became unhealthy
+    AgentUnhealthy,
+    /// The share of blocked requests rose above the threshold
+    BlockRateSpike,
+    /// A certificate is close to expiry
+    CertificateExpiry,
+    /// A guardrail detection at or above the minimum severity
+    GuardrailDetection,
+    /// An alert rule's condition held for its `for-secs`
+    AlertFiring,
+    /// A firing alert rule's condition no longer holds
+    AlertResolved,
+    /// A staged configuration rollout regressed and was rolled back
+    ConfigRolledBack,
+}
+
+impl NotificationEvent {
+    /// All events
+    pub const ALL: [NotificationEvent; 8] = [
+        NotificationEvent::CircuitBreakerOpen,
+        NotificationEvent::AgentUnhealthy,
+        NotificationEvent::BlockRateSpike,
+        NotificationEvent::CertificateExpiry,
+        NotificationEvent::GuardrailDetection,
+        NotificationEvent::AlertFiring,
+        NotificationEvent::AlertResolved,
+        NotificationEvent::ConfigRolledBack,
+    ];
+
+    /// Name used in configuration and payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::CircuitBreakerOpen => "circuit-breaker-open",
+            NotificationEvent::AgentUnhealthy => "agent-unhealthy",
+            NotificationEvent::BlockRateSpike => "block-rate-spike",
+            NotificationEvent::CertificateExpiry => "certificate-expiry",
+            NotificationEvent::GuardrailDetection => "guardrail-detection",
+            NotificationEvent::AlertFiring => "alert-firing",
+            NotificationEvent::AlertResolved => "alert-resolved",
+            NotificationEvent::ConfigRolledBack => "config-rolled-back",
+        }
+    }
+
+    /// Event with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+}
+
+/// Severity of a notification, ordered from lowest to highest
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSeverity {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl NotificationSeverity {
+    /// Name used in configuration and payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationSeverity::Low => "low",
+            NotificationSeverity::Medium => "medium",
+            NotificationSeverity::High => "high",
+            NotificationSeverity::Critical => "critical",
+        }
+    }
+
+    /// Severity with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(NotificationSeverity::Low),
+            "medium" => Some(NotificationSeverity::Medium),
+            "high" => Some(NotificationSeverity::High),
+            "critical" => Some(NotificationSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Spike detection on the share of blocked requests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockRateAlertConfig {
+    /// Share of requests blocked within a window that is reported (0-1)
+    #[serde(default = "default_block_rate_threshold")]
+    pub threshold: f64,
+
+    /// Windows with fewer requests are not evaluated
+    #[serde(default = "default_block_rate_min_requests")]
+    pub min_requests: u64,
+
+    /// Length of the evaluation window
+    #[serde(default = "default_block_rate_window_secs")]
+    pub window_secs: u64,
+}
+
+impl Default for BlockRateAlertConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_block_rate_threshold(),
+            min_requests: default_block_rate_min_requests(),
+            window_secs: default_block_rate_window_secs(),
+        }
+    }
+}
+
+// ============================================================================
+// Alert Rules
+// ============================================================================
+
+/// An alert rule: `<metric> <operator> <threshold>`, held for `for_secs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct AlertRuleConfig {
+    /// Rule name (subject of its notifications)
+    pub name: String,
+
+    /// Metric the rule watches
+    pub metric: AlertMetric,
+
+    /// Comparison of the metric against the threshold
+    pub operator: AlertOperator,
+
+    /// Threshold; rates are fractions (0.05 for 5%)
+    pub threshold: f64,
+
+    /// How long the condition must hold before the alert fires
+    #[serde(default)]
+    pub for_secs: u64,
+
+    /// Window over which rates are computed
+    #[serde(default = "default_alert_window_secs")]
+    pub window_secs: u64,
+
+    /// Severity of the alert's notifications
+    #[serde(default = "default_alert_severity")]
+    pub severity: NotificationSeverity,
+}
+
+impl AlertRuleConfig {
+    /// Rule from a condition such as `agent_timeout_rate > 5%`
+    ///
+    /// A `%` suffix divides the threshold by 100.
+    pub fn from_condition(name: impl Into<String>, condition: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = condition.split_whitespace().collect();
+        let [metric, operator, threshold] = parts[..] else {
+            return Err(format!(
+                "condition '{}' must have the form '<metric> <operator> <threshold>'",
+                condition
+            ));
+        };
+        let metric = AlertMetric::from_name(metric).ok_or_else(|| {
+            format!(
+                "unknown metric '{}'. Valid metrics: {}",
+                metric,
+                AlertMetric::ALL.map(|m| m.as_str()).join(", ")
+            )
+        })?;
+        let operator = AlertOperator::from_symbol(operator).ok_or_else(|| {
+            format!(
+                "unknown operator '{}'. Valid operators: >, >=, <, <=",
+                operator
+            )
+        })?;
+        let (number, scale) = match threshold.strip_suffix('%') {
+            Some(number) => (number, 0.01),
+            None => (threshold, 1.0),
+        };
+        let threshold = number
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("invalid threshold '{}'", threshold))?
+            * scale;
+
+        Ok(Self {
+            name: name.into(),
+            metric,
+            operator,
+            threshold,
+            for_secs: 0,
+            window_secs: default_alert_window_secs(),
+            severity: default_alert_severity(),
+        })
+    }
+}
+
+/// Built-in metrics alert rules can watch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Requests per second over the window
+    RequestRate,
+    /// Share of responses with a 5xx status over the window
+    ErrorRate,
+    /// Share of requests blocked over the window
+    BlockRate,
+    /// Share of agent calls that timed out over the window
+    AgentTimeoutRate,
+    /// Share of agent calls that failed or timed out over the window
+    AgentFailureRate,
+    /// Requests in flight
+    ActiveRequests,
+    /// Agents with an open circuit breaker or no healthy connections
+    UnhealthyAgents,
+}
+
+impl AlertMetric {
+    /// All metrics
+    pub const ALL: [AlertMetric; 7] = [
+        AlertMetric::RequestRate,
+        AlertMetric::ErrorRate,
+        AlertMetric::BlockRate,
+        AlertMetric::AgentTimeoutRate,
+        AlertMetric::AgentFailureRate,
+        AlertMetric::ActiveRequests,
+        AlertMetric::UnhealthyAgents,
+    ];
+
+    /// Name used in conditions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertMetric::RequestRate => "request_rate",
+            AlertMetric::ErrorRate => "error_rate",
+            AlertMetric::BlockRate => "block_rate",
+            AlertMetric::AgentTimeoutRate => "agent_timeout_rate",
+            AlertMetric::AgentFailureRate => "agent_failure_rate",
+            AlertMetric::ActiveRequests => "active_requests",
+            AlertMetric::UnhealthyAgents => "unhealthy_agents",
+        }
+    }
+
+    /// Metric with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.as_str() == name)
+    }
+
+    /// Whether the metric is a share between 0 and 1
+    pub fn is_ratio(&self) -> bool {
+        matches!(
+            self,
+            AlertMetric::ErrorRate
+                | AlertMetric::BlockRate
+                | AlertMetric::AgentTimeoutRate
+                | AlertMetric::AgentFailureRate
+        )
+    }
+}
+
+/// Comparison of an alert metric against its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AlertOperator {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl AlertOperator {
+    /// Operator for a symbol (`>`, `>=`, `<`, `<=`)
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            ">" => Some(AlertOperator::Gt),
+            ">=" => Some(AlertOperator::Ge),
+            "<" => Some(AlertOperator::Lt),
+            "<=" => Some(AlertOperator::Le),
+            _ => None,
+        }
+    }
+
+    /// Symbol used in conditions
+    pub fn as_symbol(&self) -> &'static str {
+        match self {
+            AlertOperator::Gt => ">",
+            AlertOperator::Ge => ">=",
+            AlertOperator::Lt => "<",
+            AlertOperator::Le => "<=",
+        }
+    }
+
+    /// Whether `value` satisfies the comparison with `threshold`
+    pub fn matches(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertOperator::Gt => value > threshold,
+            AlertOperator::Ge => value >= threshold,
+            AlertOperator::Lt => value < threshold,
+            AlertOperator::Le => value <= threshold,
+        }
+    }
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_certificate_expiry_days() -> u32 {
+    14
+}
+
+fn default_guardrail_min_severity() -> NotificationSeverity {
+    NotificationSeverity::High
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_webhook_retry_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_block_rate_threshold() -> f64 {
+    0.25
+}
+
+fn default_block_rate_min_requests() -> u64 {
+    100
+}
+
+fn default_block_rate_window_secs() -> u64 {
+    60
+}
+
+fn default_alert_window_secs() -> u64 {
+    300
+}
+
+fn default_alert_severity() -> NotificationSeverity {
+    NotificationSeverity::High
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_rule_from_condition() {
+        let rule = AlertRuleConfig::from_condition("timeouts", "agent_timeout_rate > 5%").unwrap();
+        assert_eq!(rule.metric, AlertMetric::AgentTimeoutRate);
+        assert_eq!(rule.operator, AlertOperator::Gt);
+        assert!((rule.threshold - 0.05).abs() < 1e-9);
+
+        let rule = AlertRuleConfig::from_condition("quiet", "request_rate <= 1.5").unwrap();
+        assert_eq!(rule.operator, AlertOperator::Le);
+        assert_eq!(rule.threshold, 1.5);
+        assert!(rule.operator.matches(1.5, rule.threshold));
+        assert!(!rule.operator.matches(2.0, rule.threshold));
+
+        assert!(AlertRuleConfig::from_condition("x", "latency > 5").is_err());
+        assert!(AlertRuleConfig::from_condition("x", "error_rate = 5%").is_err());
+        assert!(AlertRuleConfig::from_condition("x", "error_rate > lots").is_err());
+        assert!(AlertRuleConfig::from_condition("x", "error_rate >").is_err());
+    }
+}
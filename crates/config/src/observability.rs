@@ -12,6 +12,7 @@ use std::path::PathBuf;
 
 /// Observability configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ObservabilityConfig {
     /// Metrics configuration
     #[serde(default)]
@@ -32,6 +33,7 @@ pub struct ObservabilityConfig {
 
 /// Metrics configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MetricsConfig {
     /// Enable metrics collection
     #[serde(default = "default_true")]
@@ -48,6 +50,10 @@ pub struct MetricsConfig {
     /// Include high-cardinality metrics
     #[serde(default)]
     pub high_cardinality: bool,
+
+    /// Names of low-cardinality request tags exported as metric labels
+    #[serde(default)]
+    pub tag_labels: Vec<String>,
 }
 
 impl Default for MetricsConfig {
@@ -57,6 +63,7 @@ impl Default for MetricsConfig {
             address: default_metrics_address(),
             path: default_metrics_path(),
             high_cardinality: false,
+            tag_labels: Vec::new(),
         }
     }
 }
@@ -67,6 +74,7 @@ impl Default for MetricsConfig {
 
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LoggingConfig {
     /// Log level
     #[serde(default = "default_log_level")]
@@ -94,6 +102,10 @@ pub struct LoggingConfig {
     /// Audit log configuration (security events)
     #[serde(default)]
     pub audit_log: Option<AuditLogConfig>,
+
+    /// Write-ahead journal for access and audit records
+    #[serde(default)]
+    pub journal: Option<LogJournalConfig>,
 }
 
 impl Default for LoggingConfig {
@@ -106,12 +118,14 @@ impl Default for LoggingConfig {
             access_log: None,
             error_log: Some(ErrorLogConfig::default()),
             audit_log: None,
+            journal: None,
         }
     }
 }
 
 /// Access log field selection
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AccessLogFields {
     #[serde(default = "default_true")]
     pub timestamp: bool,
@@ -143,6 +157,8 @@ pub struct AccessLogFields {
     pub referer: bool,
     #[serde(default = "default_true")]
     pub client_ip: bool,
+    #[serde(default = "default_true")]
+    pub request_tags: bool,
 }
 
 impl Default for AccessLogFields {
@@ -163,12 +179,14 @@ impl Default for AccessLogFields {
             user_agent: true,
             referer: true,
             client_ip: true,
+            request_tags: true,
         }
     }
 }
 
 /// Access log configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AccessLogConfig {
     /// Enable access logging
     #[serde(default = "default_true")]
@@ -220,6 +238,7 @@ impl Default for AccessLogConfig {
 
 /// Error log configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ErrorLogConfig {
     /// Enable error logging
     #[serde(default = "default_true")]
@@ -251,6 +270,7 @@ impl Default for ErrorLogConfig {
 
 /// Audit log configuration (security events)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AuditLogConfig {
     /// Enable audit logging
     #[serde(default = "default_true")]
@@ -275,6 +295,10 @@ pub struct AuditLogConfig {
     /// Log WAF events
     #[serde(default = "default_true")]
     pub log_waf_events: bool,
+
+    /// Queryable store of agent decisions, served by the `audit` handler
+    #[serde(default)]
+    pub store: Option<AuditStoreConfig>,
 }
 
 impl Default for AuditLogConfig {
@@ -286,16 +310,131 @@ impl Default for AuditLogConfig {
             log_blocked: true,
             log_agent_decisions: true,
             log_waf_events: true,
+            store: None,
+        }
+    }
+}
+
+/// Audit store configuration
+///
+/// Keeps every agent response (decision and audit metadata) by correlation
+/// ID so the decision chain of a request can be looked up later.
+///
+/// KDL format (inside `audit-log`):
+/// ```kdl
+/// store {
+///     backend "sqlite"                  // memory (default) or sqlite
+///     path "/var/lib/zentinel/audit.db" // sqlite only
+///     max-records 1000000
+///     retention-secs 604800
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AuditStoreConfig {
+    /// Where the records are kept
+    #[serde(default)]
+    pub backend: AuditStoreBackend,
+
+    /// Records kept at most; the oldest are dropped first
+    #[serde(default = "default_audit_store_max_records")]
+    pub max_records: usize,
+
+    /// Age after which records are dropped
+    #[serde(default = "default_audit_store_retention_secs")]
+    pub retention_secs: u64,
+}
+
+impl Default for AuditStoreConfig {
+    fn default() -> Self {
+        Self {
+            backend: AuditStoreBackend::default(),
+            max_records: default_audit_store_max_records(),
+            retention_secs: default_audit_store_retention_secs(),
         }
     }
 }
 
+/// Storage backend of the audit store
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditStoreBackend {
+    /// In-process ring, lost on restart
+    #[default]
+    Memory,
+    /// SQLite database file (requires the `audit-store-sqlite` feature)
+    Sqlite { path: PathBuf },
+}
+
+/// Log journal configuration
+///
+/// Access and audit records are appended to the journal before they enter
+/// the buffered log writers. The journal is truncated once the writers are
+/// flushed and synced; whatever is left in it at startup is replayed into
+/// the log files, so a crash loses no completed request.
+///
+/// KDL format (inside `logging`):
+/// ```kdl
+/// journal {
+///     path "/var/lib/zentinel/logs.journal"
+///     fsync "interval"        // always, interval (default) or never
+///     fsync-interval-ms 1000
+///     max-bytes 67108864
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogJournalConfig {
+    /// Journal file path
+    #[serde(default = "default_log_journal_path")]
+    pub path: PathBuf,
+
+    /// When appended records are synced to disk
+    #[serde(default)]
+    pub fsync: JournalFsync,
+
+    /// Sync interval for `JournalFsync::Interval`
+    #[serde(default = "default_log_journal_fsync_interval_ms")]
+    pub fsync_interval_ms: u64,
+
+    /// Journal size that forces a flush of the log writers
+    #[serde(default = "default_log_journal_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl Default for LogJournalConfig {
+    fn default() -> Self {
+        Self {
+            path: default_log_journal_path(),
+            fsync: JournalFsync::default(),
+            fsync_interval_ms: default_log_journal_fsync_interval_ms(),
+            max_bytes: default_log_journal_max_bytes(),
+        }
+    }
+}
+
+/// Sync policy of the log journal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum JournalFsync {
+    /// Sync after every record; survives power loss
+    Always,
+    /// Sync at most once per interval; survives process crashes
+    #[default]
+    Interval,
+    /// Leave syncing to the OS; survives process crashes
+    Never,
+}
+
 // ============================================================================
 // Tracing Configuration
 // ============================================================================
 
 /// Tracing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TracingConfig {
     /// Tracing backend
     pub backend: TracingBackend,
@@ -311,6 +450,7 @@ pub struct TracingConfig {
 
 /// Tracing backend
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TracingBackend {
     Jaeger { endpoint: String },
@@ -366,6 +506,26 @@ fn default_audit_log_file() -> PathBuf {
     PathBuf::from("/var/log/zentinel/audit.log")
 }
 
+fn default_audit_store_max_records() -> usize {
+    100_000
+}
+
+fn default_audit_store_retention_secs() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_log_journal_path() -> PathBuf {
+    PathBuf::from("/var/lib/zentinel/logs.journal")
+}
+
+fn default_log_journal_fsync_interval_ms() -> u64 {
+    1000
+}
+
+fn default_log_journal_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
 fn default_sampling_rate() -> f64 {
     0.01
 }
@@ -408,11 +408,15 @@ mod tests {
             }],
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: None,
             circuit_breaker: None,
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         }
     }
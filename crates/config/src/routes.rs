@@ -11,7 +11,9 @@ use validator::Validate;
 use zentinel_common::budget::{CostAttributionConfig, TokenBudgetConfig};
 use zentinel_common::types::{ByteSize, Priority, RetryPolicy};
 
+use crate::expr::Expression;
 use crate::filters::RateLimitKey;
+use crate::server::SlowClientConfig;
 
 // ============================================================================
 // Route Configuration
@@ -19,6 +21,7 @@ use crate::filters::RateLimitKey;
 
 /// Route configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RouteConfig {
     /// Unique route identifier
     pub id: String,
@@ -105,6 +108,7 @@ pub struct RouteConfig {
 
 /// Match condition for route selection
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum MatchCondition {
     /// Match by path prefix
@@ -127,6 +131,9 @@ pub enum MatchCondition {
 
     /// Match by query parameter
     QueryParam { name: String, value: Option<String> },
+
+    /// Match by policy expression (see [`crate::expr`])
+    Expr(Expression),
 }
 
 // ============================================================================
@@ -135,6 +142,7 @@ pub enum MatchCondition {
 
 /// Service type for route handling
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
 pub enum ServiceType {
@@ -153,6 +161,7 @@ pub enum ServiceType {
 
 /// Built-in handler types for ServiceType::Builtin routes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum BuiltinHandler {
     /// JSON status page with version and uptime
@@ -171,6 +180,38 @@ pub enum BuiltinHandler {
     CachePurge,
     /// Cache statistics endpoint (admin only)
     CacheStats,
+    /// Supervised agent process status endpoint (admin only)
+    Agents,
+    /// Tenant status, drain and reload endpoint (admin only)
+    Tenants,
+    /// Maintenance mode status and toggles (admin only)
+    Maintenance,
+    /// API key status and revocation (admin only)
+    ApiKeys,
+    /// Chaos fault status, arming and disarming (admin only)
+    Chaos,
+    /// Traffic capture status, start and stop (admin only)
+    Capture,
+    /// Decision chain lookup by correlation ID (admin only)
+    Audit,
+    /// Dry run of a synthetic request through routing, filters and agents (admin only)
+    Explain,
+    /// Consumer quota usage and reset (admin only)
+    Quotas,
+    /// Alert rule states (admin only)
+    Alerts,
+    /// Live stream of request summaries as Server-Sent Events (admin only)
+    Tap,
+    /// In-flight requests and their cancellation by correlation ID (admin only)
+    Requests,
+    /// Open client connections with requests in flight (admin only)
+    Connections,
+    /// Liveness probe (200 while the process serves requests)
+    Livez,
+    /// Readiness probe checked against `system.readiness`
+    Readyz,
+    /// Detailed health of every subsystem
+    Healthz,
 }
 
 // ============================================================================
@@ -179,6 +220,7 @@ pub enum BuiltinHandler {
 
 /// Route-specific policies
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RoutePolicies {
     /// Request header modifications
     #[serde(default)]
@@ -194,7 +236,8 @@ pub struct RoutePolicies {
     /// Body size limit override
     pub max_body_size: Option<ByteSize>,
 
-    /// Rate limit override
+    /// Rate limit override (legacy, prefer a `rate-limit` filter)
+    #[cfg_attr(feature = "schema", schemars(extend("deprecated" = true)))]
     pub rate_limit: Option<RateLimitPolicy>,
 
     /// Failure mode (fail-open or fail-closed)
@@ -212,14 +255,131 @@ pub struct RoutePolicies {
     /// HTTP caching configuration
     #[serde(default)]
     pub cache: Option<RouteCacheConfig>,
+
+    /// Total time budget for all agent calls of a request (milliseconds)
+    ///
+    /// Once spent, remaining agents are skipped (fail-open) or the request
+    /// is blocked (fail-closed) according to each agent's failure mode.
+    #[serde(default)]
+    pub agent_budget_ms: Option<u64>,
+
+    /// How decisions from multiple agents are combined
+    #[serde(default)]
+    pub decision_merge: DecisionMergeStrategy,
+
+    /// Structured block page for agent block decisions
+    ///
+    /// When unset, agent blocks are answered with the agent's body as plain text.
+    #[serde(default)]
+    pub block_page: Option<BlockPageConfig>,
+
+    /// Settings for challenges served on behalf of agent challenge decisions
+    #[serde(default)]
+    pub challenge: ChallengeConfig,
+
+    /// In-flight request limit and load shedding for this route
+    #[serde(default)]
+    pub concurrency: Option<ConcurrencyLimitConfig>,
+
+    /// Slow-client settings overriding those of the listener
+    #[serde(default)]
+    pub slow_client: Option<SlowClientConfig>,
+
+    /// Never buffer response bodies (SSE and other streaming APIs)
+    ///
+    /// Response body agent events and guardrail buffering are disabled and
+    /// every upstream chunk is forwarded as soon as it arrives. Filters that
+    /// need the whole response body are rejected at validation.
+    #[serde(default)]
+    pub streaming_passthrough: bool,
+
+    /// Record format of request bodies streamed to agents
+    ///
+    /// Body chunk events of agents in `stream` body mode are cut on record
+    /// boundaries, so every event carries whole records.
+    #[serde(default)]
+    pub body_framing: Option<BodyFraming>,
+
+    /// `Server-Timing` response header with the request's phase durations
+    #[serde(default)]
+    pub server_timing: Option<ServerTimingConfig>,
+
+    /// Priority of the route's requests in upstream request queues
+    #[serde(default)]
+    pub priority_class: PriorityClass,
+}
+
+/// `Server-Timing` response header settings of a route
+///
+/// The header reports route matching, agents, upstream and total time, so
+/// browser developer tools show where a response's latency comes from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ServerTimingConfig {
+    /// Add one entry per agent, named after the agent ID
+    #[serde(default)]
+    pub per_agent: bool,
+
+    /// Report durations rounded up to coarse buckets, without per-agent
+    /// entries, so clients cannot observe fine-grained timings
+    #[serde(default)]
+    pub privacy: bool,
+}
+
+/// Record format of a streamed request body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum BodyFraming {
+    /// Newline-delimited records (NDJSON, JSON Lines)
+    Ndjson,
+    /// Server-sent events, each ended by a blank line
+    Sse,
+    /// Records preceded by their length as a 4-byte big-endian integer
+    LengthPrefixed,
+}
+
+impl BodyFraming {
+    /// Configuration name of the framing
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::Sse => "sse",
+            Self::LengthPrefixed => "length-prefixed",
+        }
+    }
 }
 
+/// Strategy for combining the decisions of a route's agents
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionMergeStrategy {
+    /// The first non-allow decision is final (default)
+    #[default]
+    FirstBlockWins,
+    /// Every agent must explicitly allow; agent failures count as a block
+    /// even in fail-open mode
+    AllMustAllow,
+    /// Non-allow decisions are votes weighted by their audit confidence
+    /// (1.0 when unset); the request is blocked once the sum reaches
+    /// `threshold`
+    ScoreBased { threshold: f32 },
+    /// The decision of the highest-priority agent filter is final, so a
+    /// higher-priority allow overrules a lower-priority block
+    PriorityOverride,
+}
+
+/// Default summed confidence at which `score-based` merging blocks
+pub const DEFAULT_DECISION_SCORE_THRESHOLD: f32 = 1.0;
+
 // ============================================================================
 // Cache Configuration
 // ============================================================================
 
 /// Route-level HTTP caching configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RouteCacheConfig {
     /// Enable caching for this route
     #[serde(default)]
@@ -268,6 +428,10 @@ pub struct RouteCacheConfig {
     /// Path patterns to exclude from caching (glob: *, **, ?)
     #[serde(default)]
     pub exclude_paths: Vec<String>,
+
+    /// Coalescing of concurrent misses for the same cache key
+    #[serde(default)]
+    pub coalesce: CacheCoalesceConfig,
 }
 
 impl Default for RouteCacheConfig {
@@ -285,10 +449,52 @@ impl Default for RouteCacheConfig {
             ignore_query_params: Vec::new(),
             exclude_extensions: Vec::new(),
             exclude_paths: Vec::new(),
+            coalesce: CacheCoalesceConfig::default(),
         }
     }
 }
 
+/// Request coalescing for cache misses
+///
+/// Concurrent requests for the same cache key wait for the first one to
+/// fetch the response from upstream and are then served from the cache,
+/// instead of all going upstream at once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CacheCoalesceConfig {
+    /// Coalesce concurrent misses (default: true)
+    #[serde(default = "default_coalesce_enabled")]
+    pub enabled: bool,
+
+    /// Requests that may wait on one fetch; further requests for the same
+    /// key go upstream themselves (default: 100)
+    #[serde(default = "default_coalesce_max_waiters")]
+    pub max_waiters: usize,
+
+    /// How long waiters wait for the fetch before going upstream themselves
+    /// (default: the global cache `lock-timeout-secs`)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for CacheCoalesceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_coalesce_enabled(),
+            max_waiters: default_coalesce_max_waiters(),
+            timeout_secs: None,
+        }
+    }
+}
+
+fn default_coalesce_enabled() -> bool {
+    true
+}
+
+fn default_coalesce_max_waiters() -> usize {
+    100
+}
+
 fn default_cache_ttl() -> u64 {
     3600 // 1 hour
 }
@@ -323,6 +529,7 @@ fn default_cacheable_status_codes() -> Vec<u16> {
 /// This is separate from per-route cache policies which control
 /// what gets cached and for how long.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CacheStorageConfig {
     /// Enable HTTP caching globally (default: true when cache block is present)
     #[serde(default = "default_cache_enabled")]
@@ -389,6 +596,7 @@ impl Default for CacheStorageConfig {
 
 /// Cache storage backend type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum CacheBackend {
     /// In-memory cache (fast, but lost on restart)
@@ -418,6 +626,7 @@ fn default_disk_shards() -> u32 {
 
 /// Header modification rules
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HeaderModifications {
     /// Headers to rename (old_name -> new_name, applied before set/add/remove)
     #[serde(default)]
@@ -438,6 +647,7 @@ pub struct HeaderModifications {
 
 /// Rate limit policy (legacy - prefer using rate-limit filter)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RateLimitPolicy {
     /// Requests per second
     pub requests_per_second: u32,
@@ -451,6 +661,7 @@ pub struct RateLimitPolicy {
 
 /// Failure mode for degraded operation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum FailureMode {
     Open, // Allow traffic through on failure
@@ -468,6 +679,7 @@ pub(crate) fn default_failure_mode() -> FailureMode {
 
 /// Static file serving configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct StaticFileConfig {
     /// Root directory for static files
     pub root: PathBuf,
@@ -514,6 +726,7 @@ fn default_true() -> bool {
 
 /// API schema validation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ApiSchemaConfig {
     /// OpenAPI/Swagger schema file path (mutually exclusive with schema_content)
     pub schema_file: Option<PathBuf>,
@@ -547,6 +760,7 @@ pub struct ApiSchemaConfig {
 
 /// Error page configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ErrorPageConfig {
     /// Custom error pages by status code
     #[serde(default)]
@@ -562,10 +776,16 @@ pub struct ErrorPageConfig {
 
     /// Custom error template directory
     pub template_dir: Option<PathBuf>,
+
+    /// Problem details overrides for this route, applied over the server's
+    /// `problem-details` settings
+    #[serde(default)]
+    pub problem: Option<ProblemDetailsConfig>,
 }
 
 /// Individual error page configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ErrorPage {
     /// Error page format
     pub format: ErrorFormat,
@@ -583,6 +803,7 @@ pub struct ErrorPage {
 
 /// Error response format
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
 pub enum ErrorFormat {
@@ -595,6 +816,296 @@ pub enum ErrorFormat {
     Text,
     /// XML error response
     Xml,
+    /// RFC 9457 problem details (`application/problem+json`)
+    Problem,
+}
+
+/// RFC 9457 problem details for errors generated by the proxy.
+///
+/// Set on the server, every proxy-generated error (agent blocks excepted)
+/// is answered with `application/problem+json`, or an HTML rendering when
+/// the client prefers HTML. Routes add their own mappings in
+/// `error-pages { problem { ... } }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProblemDetailsConfig {
+    /// URI prefix for the `type` member; the status code is appended.
+    /// Unmapped errors use `about:blank` when unset.
+    #[serde(default)]
+    pub type_base: Option<String>,
+
+    /// Members to use for specific status codes
+    #[serde(default)]
+    pub mappings: HashMap<u16, ProblemMapping>,
+}
+
+impl ProblemDetailsConfig {
+    /// These settings with the type base and mappings of `other` taking
+    /// precedence
+    pub fn overridden_by(&self, other: &ProblemDetailsConfig) -> ProblemDetailsConfig {
+        let mut mappings = self.mappings.clone();
+        mappings.extend(other.mappings.clone());
+        ProblemDetailsConfig {
+            type_base: other.type_base.clone().or_else(|| self.type_base.clone()),
+            mappings,
+        }
+    }
+}
+
+/// Problem details members for one status code
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProblemMapping {
+    /// `type` URI identifying the problem
+    #[serde(default, rename = "type")]
+    pub type_uri: Option<String>,
+
+    /// Short summary; defaults to the status reason phrase
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Explanation sent instead of the proxy's own detail
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+// ============================================================================
+// Block Page Configuration
+// ============================================================================
+
+/// Block page configuration for agent block decisions
+///
+/// Templates may use `{{status}}`, `{{title}}`, `{{message}}`,
+/// `{{correlation_id}}`, `{{agent}}`, `{{rule_ids}}` and `{{timestamp}}`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BlockPageConfig {
+    /// HTML template file (built-in branded page when unset)
+    pub html_template: Option<PathBuf>,
+
+    /// JSON template file (built-in JSON document when unset)
+    pub json_template: Option<PathBuf>,
+
+    /// Format used when the `Accept` header expresses no preference
+    #[serde(default)]
+    pub default_format: BlockPageFormat,
+}
+
+/// Block page response format
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BlockPageFormat {
+    /// HTML page
+    #[default]
+    Html,
+    /// JSON document
+    Json,
+}
+
+// ============================================================================
+// Challenge Configuration
+// ============================================================================
+
+/// Challenge configuration for agent challenge decisions
+///
+/// Agents choose the challenge type; these settings control how the proxy
+/// serves and verifies it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChallengeConfig {
+    /// Name of the clearance cookie set once a challenge is solved
+    #[serde(default = "default_challenge_cookie_name")]
+    pub cookie_name: String,
+
+    /// How long a solved challenge stays valid (seconds)
+    #[serde(default = "default_challenge_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Leading zero bits required for JavaScript proof-of-work solutions
+    ///
+    /// Agents may override this per challenge with a `difficulty` parameter.
+    #[serde(default = "default_pow_difficulty")]
+    pub pow_difficulty: u8,
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: default_challenge_cookie_name(),
+            ttl_secs: default_challenge_ttl_secs(),
+            pow_difficulty: default_pow_difficulty(),
+        }
+    }
+}
+
+fn default_challenge_cookie_name() -> String {
+    "zentinel_clearance".to_string()
+}
+
+fn default_challenge_ttl_secs() -> u64 {
+    3600 // 1 hour
+}
+
+fn default_pow_difficulty() -> u8 {
+    16
+}
+
+/// Maximum proof-of-work difficulty (leading zero bits)
+pub const MAX_POW_DIFFICULTY: u8 = 32;
+
+// ============================================================================
+// Concurrency Limit Configuration
+// ============================================================================
+
+/// In-flight request limit with optional adaptive load shedding
+///
+/// Used per route (`policies { concurrency { ... } }`) and for all proxied
+/// traffic (`server { concurrency { ... } }`). Requests over the limit are
+/// shed with a 503 before agents run or an upstream is selected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConcurrencyLimitConfig {
+    /// Hard cap on requests in flight
+    pub max_in_flight: u32,
+
+    /// Latency-driven limit below `max_in_flight` (fixed limit when unset)
+    #[serde(default)]
+    pub adaptive: Option<AdaptiveConcurrencyConfig>,
+
+    /// `Retry-After` value sent with shed responses, in seconds
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+
+    /// Body of shed responses (a short plain-text message when unset)
+    #[serde(default)]
+    pub shed_body: Option<String>,
+
+    /// Content type of shed responses
+    #[serde(default = "default_shed_content_type")]
+    pub shed_content_type: String,
+}
+
+impl ConcurrencyLimitConfig {
+    /// Fixed limit of `max_in_flight` requests
+    pub fn fixed(max_in_flight: u32) -> Self {
+        Self {
+            max_in_flight,
+            adaptive: None,
+            retry_after_secs: None,
+            shed_body: None,
+            shed_content_type: default_shed_content_type(),
+        }
+    }
+}
+
+/// Priority of a route's requests in upstream request queues
+///
+/// Requests waiting for an upstream with a `queue` are admitted highest class
+/// first. When the queue is full, a request displaces the newest waiting
+/// request of a lower class, or is shed if there is none.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum PriorityClass {
+    /// Shed first (batch jobs, prefetching)
+    Low,
+    /// Default class
+    #[default]
+    Normal,
+    /// Admitted before normal traffic
+    High,
+    /// Admitted first and shed last (health checks, payments)
+    Critical,
+}
+
+impl PriorityClass {
+    /// All classes, highest first
+    pub const ALL: [PriorityClass; 4] = [
+        PriorityClass::Critical,
+        PriorityClass::High,
+        PriorityClass::Normal,
+        PriorityClass::Low,
+    ];
+
+    /// Name used in configuration and metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriorityClass::Low => "low",
+            PriorityClass::Normal => "normal",
+            PriorityClass::High => "high",
+            PriorityClass::Critical => "critical",
+        }
+    }
+}
+
+/// Gradient-based adaptive concurrency limit
+///
+/// Every `window_ms` the average latency of the window is compared with a
+/// long-term baseline. While latency stays within `tolerance` times the
+/// baseline the limit grows; when it rises above, the limit shrinks in
+/// proportion, shedding load before queues build up at the upstream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AdaptiveConcurrencyConfig {
+    /// Lowest limit the algorithm may choose
+    #[serde(default = "default_adaptive_min_limit")]
+    pub min_limit: u32,
+
+    /// Limit used until the first latency window completes
+    #[serde(default = "default_adaptive_initial_limit")]
+    pub initial_limit: u32,
+
+    /// Accepted ratio of window latency to baseline latency before the
+    /// limit shrinks
+    #[serde(default = "default_adaptive_tolerance")]
+    pub tolerance: f64,
+
+    /// Weight of each new limit estimate (0.0-1.0)
+    #[serde(default = "default_adaptive_smoothing")]
+    pub smoothing: f64,
+
+    /// Length of a latency sampling window (milliseconds)
+    #[serde(default = "default_adaptive_window_ms")]
+    pub window_ms: u64,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            min_limit: default_adaptive_min_limit(),
+            initial_limit: default_adaptive_initial_limit(),
+            tolerance: default_adaptive_tolerance(),
+            smoothing: default_adaptive_smoothing(),
+            window_ms: default_adaptive_window_ms(),
+        }
+    }
+}
+
+pub(crate) fn default_shed_content_type() -> String {
+    "text/plain; charset=utf-8".to_string()
+}
+
+fn default_adaptive_min_limit() -> u32 {
+    10
+}
+
+fn default_adaptive_initial_limit() -> u32 {
+    50
+}
+
+fn default_adaptive_tolerance() -> f64 {
+    1.5
+}
+
+fn default_adaptive_smoothing() -> f64 {
+    0.2
+}
+
+fn default_adaptive_window_ms() -> u64 {
+    1000
 }
 
 // ============================================================================
@@ -606,6 +1117,7 @@ pub enum ErrorFormat {
 /// Enables fire-and-forget request duplication to a shadow upstream
 /// for safe canary deployments and testing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ShadowConfig {
     /// Shadow target upstream ID
     pub upstream: String,
@@ -654,6 +1166,7 @@ fn default_shadow_max_body_bytes() -> usize {
 /// Provides token-based rate limiting, model-aware load balancing,
 /// and multi-provider support for inference traffic.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InferenceConfig {
     /// Inference provider (determines token extraction strategy)
     #[serde(default)]
@@ -679,10 +1192,14 @@ pub struct InferenceConfig {
 
     /// Semantic guardrails configuration (prompt injection, PII detection)
     pub guardrails: Option<GuardrailsConfig>,
+
+    /// Pool of provider credentials the route spreads traffic across
+    pub key_pool: Option<InferenceKeyPoolConfig>,
 }
 
 /// Inference provider type (determines token counting strategy)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum InferenceProvider {
     /// Generic provider (uses x-tokens-used header or estimation)
@@ -707,6 +1224,7 @@ impl InferenceProvider {
 
 /// Token-based rate limiting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TokenRateLimit {
     /// Maximum tokens per minute
     pub tokens_per_minute: u64,
@@ -729,6 +1247,7 @@ fn default_burst_tokens() -> u64 {
 
 /// Token estimation method for request sizing
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TokenEstimation {
     /// Character count / 4 (fast, rough estimate)
@@ -742,6 +1261,7 @@ pub enum TokenEstimation {
 
 /// Inference-aware routing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InferenceRouting {
     /// Load balancing strategy for inference traffic
     #[serde(default)]
@@ -753,6 +1273,7 @@ pub struct InferenceRouting {
 
 /// Inference-specific load balancing strategies
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum InferenceRoutingStrategy {
     /// Route to upstream with least tokens queued (default)
@@ -783,6 +1304,7 @@ pub enum InferenceRoutingStrategy {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ModelRoutingConfig {
     /// Ordered list of model-to-upstream mappings (first match wins).
     /// Supports exact matches and glob patterns with `*` wildcard.
@@ -799,6 +1321,7 @@ pub struct ModelRoutingConfig {
 /// Maps a model name (or pattern) to a specific upstream pool.
 /// Optionally overrides the inference provider for cross-provider routing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ModelUpstreamMapping {
     /// Model name pattern. Can be:
     /// - Exact match: `"gpt-4"`, `"claude-3-opus"`
@@ -814,6 +1337,93 @@ pub struct ModelUpstreamMapping {
     pub provider: Option<InferenceProvider>,
 }
 
+// ============================================================================
+// Inference Key Pool Configuration
+// ============================================================================
+
+/// Pool of provider endpoints and API keys for an inference route.
+///
+/// Each request is sent through one member, chosen by weight among the
+/// members that are not cooling down and still have per-minute budget left.
+/// A response with a failover status puts the member into cooldown and the
+/// request is retried on another member. The pool selects the upstream and
+/// attaches the member's key; it overrides the route's upstream and model
+/// routing.
+///
+/// # Example KDL Configuration
+/// ```kdl
+/// key-pool {
+///     cooldown-secs 60
+///     failover-status 429 500 502 503 504
+///     max-attempts 3
+///     member "openai-a" upstream="openai-us" key="${env:OPENAI_KEY_A}" weight=2
+///     member "openai-b" upstream="openai-eu" key="${env:OPENAI_KEY_B}" requests-per-minute=500
+///     member "claude" upstream="anthropic" key="${env:ANTHROPIC_KEY}" provider="anthropic"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InferenceKeyPoolConfig {
+    /// Pool members (at least one)
+    pub members: Vec<InferenceKeyPoolMember>,
+
+    /// Cooldown after a failover status, when the response has no Retry-After
+    #[serde(default = "default_key_pool_cooldown_secs")]
+    pub cooldown_secs: u64,
+
+    /// Upstream statuses that trigger cooldown and failover
+    #[serde(default = "default_key_pool_failover_status")]
+    pub failover_status: Vec<u16>,
+
+    /// Maximum members tried for one request
+    #[serde(default = "default_key_pool_max_attempts")]
+    pub max_attempts: u32,
+}
+
+/// A single provider endpoint and API key in a key pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InferenceKeyPoolMember {
+    /// Member identifier (used in logs and metrics)
+    pub id: String,
+
+    /// Upstream the member's requests are sent to
+    pub upstream: String,
+
+    /// API key attached to requests sent through this member
+    pub key: String,
+
+    /// Provider, which selects the credential header
+    /// (defaults to the inference block's provider)
+    pub provider: Option<InferenceProvider>,
+
+    /// Relative share of traffic
+    #[serde(default = "default_key_pool_weight")]
+    pub weight: u32,
+
+    /// Requests per minute this key may send
+    pub requests_per_minute: Option<u64>,
+
+    /// Tokens per minute this key may consume
+    pub tokens_per_minute: Option<u64>,
+}
+
+fn default_key_pool_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_key_pool_failover_status() -> Vec<u16> {
+    vec![429, 500, 502, 503, 504]
+}
+
+fn default_key_pool_max_attempts() -> u32 {
+    3
+}
+
+fn default_key_pool_weight() -> u32 {
+    1
+}
+
 // ============================================================================
 // Fallback Routing Configuration
 // ============================================================================
@@ -824,6 +1434,7 @@ pub struct ModelUpstreamMapping {
 /// when the primary upstream is unhealthy, exhausted, or returns errors.
 /// Supports cross-provider failback with model mapping.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct FallbackConfig {
     /// Ordered list of fallback upstreams (tried in order)
     #[serde(default)]
@@ -840,6 +1451,7 @@ pub struct FallbackConfig {
 
 /// A single fallback upstream with optional model mapping
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct FallbackUpstream {
     /// Upstream pool ID to fallback to
     pub upstream: String,
@@ -860,6 +1472,7 @@ pub struct FallbackUpstream {
 
 /// Triggers that activate fallback routing
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct FallbackTriggers {
     /// Trigger on health check failure of primary upstream
     #[serde(default = "default_true")]
@@ -908,12 +1521,42 @@ fn default_max_fallback_attempts() -> u32 {
 /// - Prompt injection detection on requests
 /// - PII detection on responses
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GuardrailsConfig {
     /// Prompt injection detection configuration
     pub prompt_injection: Option<PromptInjectionConfig>,
 
     /// PII detection configuration
     pub pii_detection: Option<PiiDetectionConfig>,
+
+    /// Embedding service whose vectors are attached to prompt injection
+    /// events
+    #[serde(default)]
+    pub embedding: Option<GuardrailEmbeddingConfig>,
+}
+
+/// Embedding endpoint for guardrail inspection.
+///
+/// The proxy embeds the inspected content once and sends the vector with the
+/// guardrail event, so agents can run similarity checks against vector
+/// blocklists without calling the embedding service themselves. The
+/// endpoint speaks the OpenAI embeddings API (`POST {"model", "input"}`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GuardrailEmbeddingConfig {
+    /// Embeddings API URL (e.g., `https://api.openai.com/v1/embeddings`)
+    pub endpoint: String,
+
+    /// Embedding model name
+    pub model: String,
+
+    /// Bearer token for the endpoint
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Request timeout in milliseconds (default: 200)
+    #[serde(default = "default_guardrail_embedding_timeout_ms")]
+    pub timeout_ms: u64,
 }
 
 /// Prompt injection detection configuration.
@@ -921,6 +1564,7 @@ pub struct GuardrailsConfig {
 /// Detects and optionally blocks requests containing prompt injection attempts.
 /// Uses an external agent for content analysis.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PromptInjectionConfig {
     /// Enable prompt injection detection
     #[serde(default)]
@@ -954,6 +1598,7 @@ pub struct PromptInjectionConfig {
 /// Detects sensitive data (SSN, credit cards, emails, etc.) in responses.
 /// Uses an external agent for content analysis.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PiiDetectionConfig {
     /// Enable PII detection
     #[serde(default)]
@@ -981,6 +1626,7 @@ pub struct PiiDetectionConfig {
 
 /// Action to take when a guardrail detects an issue
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum GuardrailAction {
     /// Block the request and return an error
@@ -994,6 +1640,7 @@ pub enum GuardrailAction {
 
 /// Action to take when PII is detected in responses
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum PiiAction {
     /// Log the detection only (default)
@@ -1007,6 +1654,7 @@ pub enum PiiAction {
 
 /// Failure mode for guardrail agents (when agent times out or errors)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum GuardrailFailureMode {
     /// Allow request to proceed on agent failure (fail-open, default)
@@ -1024,6 +1672,10 @@ fn default_prompt_injection_timeout_ms() -> u64 {
     500
 }
 
+fn default_guardrail_embedding_timeout_ms() -> u64 {
+    200
+}
+
 fn default_pii_detection_timeout_ms() -> u64 {
     1000
 }
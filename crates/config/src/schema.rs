@@ -0,0 +1,93 @@
+//! JSON Schema of the configuration.
+//!
+//! The schema is generated from the configuration types with `schemars`, so
+//! it describes the JSON and TOML forms accepted by [`Config::from_json`]
+//! and [`Config::from_toml`], including every filter and agent type. Editors
+//! and CI validators use it to reject configuration errors before deploy.
+//!
+//! The schema is versioned with [`CURRENT_SCHEMA_VERSION`]: the `$id` ends
+//! in the version and `x-zentinel-schema-version` carries it. Fields that
+//! are kept only for compatibility are annotated with `"deprecated": true`.
+
+use crate::{Config, CURRENT_SCHEMA_VERSION, MIN_SCHEMA_VERSION};
+
+/// Base of the schema `$id`, followed by `/<schema version>.json`
+pub const SCHEMA_ID_BASE: &str = "https://zentinelproxy.io/schemas/config";
+
+/// JSON Schema (draft 2020-12) of the configuration
+pub fn config_schema() -> serde_json::Value {
+    let mut schema = schemars::generate::SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<Config>();
+    schema.insert(
+        "$id".to_string(),
+        format!("{}/{}.json", SCHEMA_ID_BASE, CURRENT_SCHEMA_VERSION).into(),
+    );
+    schema.insert("title".to_string(), "Zentinel configuration".into());
+    schema.insert(
+        "x-zentinel-schema-version".to_string(),
+        CURRENT_SCHEMA_VERSION.into(),
+    );
+    schema.insert(
+        "x-zentinel-min-schema-version".to_string(),
+        MIN_SCHEMA_VERSION.into(),
+    );
+    schema.to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_is_versioned() {
+        let schema = config_schema();
+        assert_eq!(
+            schema["$id"],
+            format!("{}/{}.json", SCHEMA_ID_BASE, CURRENT_SCHEMA_VERSION)
+        );
+        assert_eq!(schema["x-zentinel-schema-version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(
+            schema["properties"]["schema_version"]["default"],
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_schema_covers_filters_and_agents() {
+        let schema = config_schema();
+        let defs = schema["$defs"].as_object().unwrap();
+        for name in [
+            "FilterConfig",
+            "RateLimitFilter",
+            "IcapFilter",
+            "AgentConfig",
+            "Expression",
+        ] {
+            assert!(defs.contains_key(name), "missing definition {}", name);
+        }
+        assert_eq!(
+            defs["RoutePolicies"]["properties"]["rate_limit"]["deprecated"],
+            true
+        );
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_default_config_matches_schema() {
+        let validator = jsonschema::validator_for(&config_schema()).unwrap();
+        let config = serde_json::to_value(Config::default_embedded().unwrap()).unwrap();
+        let errors: Vec<String> = validator
+            .iter_errors(&config)
+            .map(|e| e.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let invalid = serde_json::json!({ "server": {}, "listeners": [], "routes": "none" });
+        assert!(!validator.is_valid(&invalid));
+    }
+}
@@ -0,0 +1,503 @@
+//! Secret references in configuration values.
+//!
+//! Any string value may embed a reference instead of the secret itself:
+//!
+//! ```kdl
+//! redis-url "redis://:${env:REDIS_PASSWORD}@redis:6379"
+//! token "${file:/run/secrets/api-token}"
+//! ```
+//!
+//! `${env:VAR}` reads an environment variable and `${file:/path}` a file
+//! (trailing newlines removed). With the `secret-providers` feature, other
+//! schemes such as `${vault:kv/zentinel#redis}` are resolved by a registered
+//! [`SecretProvider`]. References are resolved when a configuration is loaded
+//! or reloaded; an unresolvable reference fails the load.
+//!
+//! Resolved values are remembered so they can be kept out of logs and admin
+//! output with [`redact`] and [`redact_json`].
+//!
+//! Environment variables can also be interpolated shell-style, for values
+//! that differ per deployment but are not secret:
+//!
+//! ```kdl
+//! address "0.0.0.0:${HTTP_PORT:-8080}"
+//! target "${BACKEND_HOST:?set BACKEND_HOST to the backend address}:9000"
+//! ```
+//!
+//! `${VAR:-default}` uses `default` when `VAR` is unset or empty and
+//! `${VAR:?message}` fails the load with `message`. Interpolated values are
+//! not redacted; use `${env:VAR}` for secrets.
+//!
+//! `$${` escapes a literal `${`. A `${...}` without a lowercase scheme or an
+//! interpolation operator, such as the `${HOME}` expansion in agent
+//! environments, is left untouched.
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// Replacement for redacted secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Values shorter than this are not redacted; they would match everywhere
+const MIN_REDACT_LEN: usize = 4;
+
+/// Every secret value resolved so far, including from earlier reloads
+static RESOLVED: Lazy<RwLock<BTreeSet<String>>> = Lazy::new(|| RwLock::new(BTreeSet::new()));
+
+/// Error resolving a secret reference
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SecretError {
+    #[error("environment variable '{0}' is not set (referenced as ${{env:{0}}})")]
+    EnvNotSet(String),
+
+    #[error("failed to read secret file '{path}': {message}")]
+    File { path: String, message: String },
+
+    #[error("no secret provider registered for '{0}:' references")]
+    UnknownProvider(String),
+
+    #[error("secret provider '{scheme}' failed to resolve '{reference}': {message}")]
+    Provider {
+        scheme: String,
+        reference: String,
+        message: String,
+    },
+
+    #[error("environment variable '{name}' is not set: {message}")]
+    EnvRequired { name: String, message: String },
+
+    #[error("unterminated secret reference: missing '}}' after '${{{0}'")]
+    Unterminated(String),
+
+    /// An error with the location of the value that caused it
+    #[error("{location}: {error}")]
+    At {
+        location: String,
+        error: Box<SecretError>,
+    },
+}
+
+impl SecretError {
+    fn at(self, location: impl Into<String>) -> Self {
+        SecretError::At {
+            location: location.into(),
+            error: Box::new(self),
+        }
+    }
+}
+
+/// External secret store (Vault, a cloud KMS, ...)
+///
+/// Providers resolve `${<scheme>:<reference>}`; the reference format is up
+/// to the provider. Resolution happens while loading the configuration, so
+/// it is synchronous.
+#[cfg(feature = "secret-providers")]
+pub trait SecretProvider: Send + Sync {
+    /// Scheme this provider handles, e.g. `vault`
+    fn scheme(&self) -> &str;
+
+    /// Fetch the secret named by `reference`
+    fn resolve(&self, reference: &str) -> Result<String, String>;
+}
+
+#[cfg(feature = "secret-providers")]
+static PROVIDERS: Lazy<RwLock<Vec<std::sync::Arc<dyn SecretProvider>>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Register a provider, replacing any earlier one for the same scheme
+#[cfg(feature = "secret-providers")]
+pub fn register_provider(provider: std::sync::Arc<dyn SecretProvider>) {
+    let mut providers = PROVIDERS.write();
+    providers.retain(|p| p.scheme() != provider.scheme());
+    providers.push(provider);
+}
+
+/// Resolve the references in `value`; `None` if it contains none.
+pub fn resolve_str(value: &str) -> Result<Option<String>, SecretError> {
+    if !value.contains("${") {
+        return Ok(None);
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut changed = false;
+
+    while let Some(start) = rest.find("${") {
+        // `$${` is an escaped literal `${`
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            changed = true;
+            continue;
+        }
+
+        out.push_str(&rest[..start]);
+        let body = &rest[start + 2..];
+        if let Some((value, len)) = interpolate(body)? {
+            out.push_str(&value);
+            rest = &body[len..];
+            changed = true;
+            continue;
+        }
+        let Some((scheme, reference)) = body.split_once(':').filter(|(s, _)| is_scheme(s)) else {
+            // Not a secret reference, e.g. `${HOME}`
+            out.push_str("${");
+            rest = body;
+            continue;
+        };
+        let end = reference
+            .find('}')
+            .ok_or_else(|| SecretError::Unterminated(scheme.to_string()))?;
+        let reference = &reference[..end];
+
+        let secret = resolve_reference(scheme, reference)?;
+        remember(&secret);
+        out.push_str(&secret);
+        rest = &body[scheme.len() + 1 + end + 1..];
+        changed = true;
+    }
+    out.push_str(rest);
+
+    Ok(changed.then_some(out))
+}
+
+/// Resolve `NAME:-default}` or `NAME:?message}` at the start of `body`,
+/// returning the value and the length consumed
+fn interpolate(body: &str) -> Result<Option<(String, usize)>, SecretError> {
+    let name_len = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(body.len());
+    let name = &body[..name_len];
+    let operator = body[name_len..].get(..2);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    if !matches!(operator, Some(":-" | ":?")) {
+        return Ok(None);
+    }
+
+    let arg_start = name_len + 2;
+    let end = body[arg_start..]
+        .find('}')
+        .ok_or_else(|| SecretError::Unterminated(name.to_string()))?;
+    let arg = &body[arg_start..arg_start + end];
+
+    let value = match std::env::var(name) {
+        Ok(value) if !value.is_empty() => value,
+        _ if operator == Some(":-") => arg.to_string(),
+        _ => {
+            return Err(SecretError::EnvRequired {
+                name: name.into(),
+                message: if arg.is_empty() {
+                    "a value is required".into()
+                } else {
+                    arg.into()
+                },
+            })
+        }
+    };
+    Ok(Some((value, arg_start + end + 1)))
+}
+
+fn is_scheme(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+}
+
+fn resolve_reference(scheme: &str, reference: &str) -> Result<String, SecretError> {
+    match scheme {
+        "env" => std::env::var(reference).map_err(|_| SecretError::EnvNotSet(reference.into())),
+        "file" => std::fs::read_to_string(reference)
+            .map(|s| s.trim_end_matches(['\n', '\r']).to_string())
+            .map_err(|e| SecretError::File {
+                path: reference.into(),
+                message: e.to_string(),
+            }),
+        _ => resolve_with_provider(scheme, reference),
+    }
+}
+
+#[cfg(feature = "secret-providers")]
+fn resolve_with_provider(scheme: &str, reference: &str) -> Result<String, SecretError> {
+    let provider = PROVIDERS
+        .read()
+        .iter()
+        .find(|p| p.scheme() == scheme)
+        .cloned()
+        .ok_or_else(|| SecretError::UnknownProvider(scheme.into()))?;
+    provider
+        .resolve(reference)
+        .map_err(|message| SecretError::Provider {
+            scheme: scheme.into(),
+            reference: reference.into(),
+            message,
+        })
+}
+
+#[cfg(not(feature = "secret-providers"))]
+fn resolve_with_provider(scheme: &str, _reference: &str) -> Result<String, SecretError> {
+    Err(SecretError::UnknownProvider(scheme.into()))
+}
+
+fn remember(secret: &str) {
+    if secret.len() >= MIN_REDACT_LEN {
+        RESOLVED.write().insert(secret.to_string());
+    }
+}
+
+/// Resolve references in every string value of a KDL document.
+///
+/// `source` is the text the document was parsed from; errors carry the line
+/// and column of the offending value in it.
+pub(crate) fn resolve_kdl_document(
+    doc: &mut ::kdl::KdlDocument,
+    source: &str,
+) -> Result<(), SecretError> {
+    for node in doc.nodes_mut() {
+        for entry in node.entries_mut() {
+            let resolved = match entry.value().as_string() {
+                Some(value) => resolve_str(value).map_err(|e| {
+                    let (line, col) = crate::kdl::span_line_col(source, entry.span());
+                    e.at(format!("line {}, column {}", line, col))
+                })?,
+                None => None,
+            };
+            if let Some(resolved) = resolved {
+                entry.set_value(::kdl::KdlValue::String(resolved));
+            }
+        }
+        if let Some(children) = node.children_mut().as_mut() {
+            resolve_kdl_document(children, source)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolve references in every string of a JSON value.
+///
+/// Errors carry the path of the offending value, e.g. `routes[0].upstream`.
+pub(crate) fn resolve_json(value: &mut serde_json::Value) -> Result<(), SecretError> {
+    resolve_json_at(value, "")
+}
+
+fn resolve_json_at(value: &mut serde_json::Value, path: &str) -> Result<(), SecretError> {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Some(resolved) = resolve_str(s).map_err(|e| e.at(path))? {
+                *s = resolved;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                resolve_json_at(item, &format!("{}[{}]", path, i))?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                resolve_json_at(item, &child_path(path, key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Resolve references in every string of a TOML value.
+///
+/// Errors carry the path of the offending value, e.g. `routes[0].upstream`.
+pub(crate) fn resolve_toml(value: &mut toml::Value) -> Result<(), SecretError> {
+    resolve_toml_at(value, "")
+}
+
+fn resolve_toml_at(value: &mut toml::Value, path: &str) -> Result<(), SecretError> {
+    match value {
+        toml::Value::String(s) => {
+            if let Some(resolved) = resolve_str(s).map_err(|e| e.at(path))? {
+                *s = resolved;
+            }
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                resolve_toml_at(item, &format!("{}[{}]", path, i))?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                resolve_toml_at(item, &child_path(path, key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Replace resolved secret values in `text` with [`REDACTED`].
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let secrets = RESOLVED.read();
+    if !secrets.iter().any(|s| text.contains(s.as_str())) {
+        return Cow::Borrowed(text);
+    }
+    // Longest first, so a secret containing another is replaced whole
+    let mut ordered: Vec<&String> = secrets.iter().collect();
+    ordered.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    let mut redacted = text.to_string();
+    for secret in ordered {
+        redacted = redacted.replace(secret.as_str(), REDACTED);
+    }
+    Cow::Owned(redacted)
+}
+
+/// Redact resolved secret values in every string of a JSON value.
+pub fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Cow::Owned(redacted) = redact(s) {
+                *s = redacted;
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        serde_json::Value::Object(map) => map.values_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_env_and_file_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "file-secret-1234\n").unwrap();
+        let path_var = std::env::var("PATH").unwrap();
+
+        let value = format!(
+            "a=${{env:PATH}} b=${{file:{}}} c=${{HOME}} d=$${{env:PATH}}",
+            path.display()
+        );
+        let resolved = resolve_str(&value).unwrap().unwrap();
+        assert_eq!(
+            resolved,
+            format!("a={path_var} b=file-secret-1234 c=${{HOME}} d=${{env:PATH}}")
+        );
+
+        assert_eq!(resolve_str("plain").unwrap(), None);
+        assert_eq!(
+            resolve_str("${env:ZENTINEL_TEST_UNSET_SECRET}").unwrap_err(),
+            SecretError::EnvNotSet("ZENTINEL_TEST_UNSET_SECRET".into())
+        );
+        assert!(matches!(
+            resolve_str("${env:PATH").unwrap_err(),
+            SecretError::Unterminated(_)
+        ));
+        assert!(matches!(
+            resolve_str("${vault:kv/app}").unwrap_err(),
+            SecretError::UnknownProvider(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_kdl_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key");
+        std::fs::write(&path, "kdl-secret-value").unwrap();
+
+        let source = format!(
+            "outer {{\n    inner \"${{file:{}}}\" plain=\"${{HOME}}\"\n}}",
+            path.display()
+        );
+        let mut doc: ::kdl::KdlDocument = source.parse().unwrap();
+        resolve_kdl_document(&mut doc, &source).unwrap();
+
+        let inner = &doc.nodes()[0].children().unwrap().nodes()[0];
+        assert_eq!(
+            inner.entries()[0].value().as_string(),
+            Some("kdl-secret-value")
+        );
+        assert_eq!(inner.entries()[1].value().as_string(), Some("${HOME}"));
+    }
+
+    #[test]
+    fn test_interpolate_env_with_default() {
+        let path_var = std::env::var("PATH").unwrap();
+        assert_eq!(
+            resolve_str(
+                "${PATH:-none}|${ZENTINEL_TEST_UNSET_VAR:-8080}|${ZENTINEL_TEST_UNSET_VAR:-}"
+            )
+            .unwrap()
+            .unwrap(),
+            format!("{path_var}|8080|")
+        );
+        assert_eq!(resolve_str("${PATH:?required}").unwrap().unwrap(), path_var);
+        assert_eq!(
+            resolve_str("$${PORT:-80} ${1X:-y}").unwrap().unwrap(),
+            "${PORT:-80} ${1X:-y}"
+        );
+
+        assert_eq!(
+            resolve_str("${ZENTINEL_TEST_UNSET_VAR:?set the backend host}").unwrap_err(),
+            SecretError::EnvRequired {
+                name: "ZENTINEL_TEST_UNSET_VAR".into(),
+                message: "set the backend host".into(),
+            }
+        );
+        assert!(matches!(
+            resolve_str("${PORT:-80").unwrap_err(),
+            SecretError::Unterminated(_)
+        ));
+    }
+
+    #[test]
+    fn test_errors_carry_location() {
+        let source = "routes {\n    route \"api\" {\n        upstream \"${ZENTINEL_TEST_UNSET_VAR:?}\"\n    }\n}";
+        let mut doc: ::kdl::KdlDocument = source.parse().unwrap();
+        let err = resolve_kdl_document(&mut doc, source).unwrap_err();
+        assert!(err.to_string().starts_with("line 3, column "), "{}", err);
+
+        let mut json =
+            serde_json::json!({ "routes": [{ "upstream": "${env:ZENTINEL_TEST_UNSET_VAR}" }] });
+        let err = resolve_json(&mut json).unwrap_err();
+        assert!(
+            err.to_string().starts_with("routes[0].upstream: "),
+            "{}",
+            err
+        );
+
+        let mut toml: toml::Value =
+            toml::from_str("[upstreams.api]\ntarget = \"${ZENTINEL_TEST_UNSET_VAR:?}\"").unwrap();
+        let err = resolve_toml(&mut toml).unwrap_err();
+        assert!(
+            err.to_string().starts_with("upstreams.api.target: "),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_resolved_secrets_are_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("password");
+        std::fs::write(&path, "hunter2-redact-me").unwrap();
+
+        let url = resolve_str(&format!("redis://:${{file:{}}}@redis:6379", path.display()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(redact(&url), "redis://:[REDACTED]@redis:6379");
+        assert!(matches!(redact("nothing secret"), Cow::Borrowed(_)));
+
+        let mut json = serde_json::json!({ "backend": { "url": url }, "port": 6379 });
+        redact_json(&mut json);
+        assert_eq!(json["backend"]["url"], "redis://:[REDACTED]@redis:6379");
+    }
+}
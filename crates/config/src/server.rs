@@ -4,17 +4,23 @@
 //! and its listeners (ports/addresses it binds to).
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::str::FromStr;
 use validator::Validate;
 
 use zentinel_common::types::{TlsVersion, TraceIdFormat};
 
+use crate::routes::{ConcurrencyLimitConfig, ProblemDetailsConfig};
+
 // ============================================================================
 // Server Configuration
 // ============================================================================
 
 /// Server-wide configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ServerConfig {
     /// Number of worker threads (0 = number of CPU cores)
     #[serde(default = "default_worker_threads")]
@@ -48,6 +54,9 @@ pub struct ServerConfig {
     ///
     /// - `tinyflake` (default): 11-char Base58, operator-friendly
     /// - `uuid`: 36-char UUID v4, guaranteed unique
+    /// - `uuidv7`: 36-char UUID v7, time-ordered
+    /// - `ulid`: 26-char ULID, time-ordered
+    /// - `hex`: 32 random hex chars
     #[serde(default)]
     pub trace_id_format: TraceIdFormat,
 
@@ -64,6 +73,50 @@ pub struct ServerConfig {
     /// `zentinel_route_cache_evictions_total`). Default: 1000.
     #[serde(default = "default_route_cache_size")]
     pub route_cache_size: usize,
+
+    /// Client IP resolution behind load balancers and CDNs
+    #[serde(default)]
+    pub client_ip: ClientIpConfig,
+
+    /// Proxy for HTTP requests the proxy itself makes (ACME, bundle downloads)
+    #[serde(default)]
+    pub outbound_proxy: OutboundProxyConfig,
+
+    /// Maintenance mode page, bypass rules and persisted state
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+
+    /// In-flight limit and load shedding across all proxied routes
+    #[serde(default)]
+    pub concurrency: Option<ConcurrencyLimitConfig>,
+
+    /// Where request IDs come from and which headers carry them
+    #[serde(default)]
+    pub request_id: RequestIdConfig,
+
+    /// Render proxy-generated errors as RFC 9457 problem details
+    #[serde(default)]
+    pub problem_details: Option<ProblemDetailsConfig>,
+
+    /// What the `readyz` probe requires before reporting ready
+    #[serde(default)]
+    pub readiness: ReadinessConfig,
+
+    /// Fault injection for resilience testing, armed through the admin API
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+
+    /// Traffic capture for replay, started through the admin API
+    #[serde(default)]
+    pub capture: Option<CaptureConfig>,
+
+    /// Staged rollout of reloaded configurations, with automatic rollback
+    #[serde(default)]
+    pub rollout: Option<RolloutConfig>,
+
+    /// Agent decision headers on responses to callers with a debug token
+    #[serde(default)]
+    pub debug_headers: Option<DebugHeadersConfig>,
 }
 
 // ============================================================================
@@ -72,6 +125,7 @@ pub struct ServerConfig {
 
 /// Listener configuration (port binding)
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ListenerConfig {
     /// Unique identifier for this listener
     pub id: String,
@@ -115,10 +169,47 @@ pub struct ListenerConfig {
     /// Equivalent to nginx's keepalive_requests. None = unlimited.
     #[serde(default)]
     pub keepalive_max_requests: Option<u32>,
+
+    /// Expect a PROXY protocol (v1 or v2) header on every connection.
+    ///
+    /// The source address from the header replaces the socket peer for client
+    /// IP resolution. Only enable this behind a load balancer that always
+    /// sends the header; connections without one are rejected.
+    #[serde(default)]
+    pub proxy_protocol: bool,
+
+    /// Collect bot-detection signals (TLS and HTTP/2 fingerprints, header
+    /// order) for agents.
+    ///
+    /// Connections are read by an acceptor in front of the proxy, as with
+    /// `proxy_protocol`, to see the raw ClientHello.
+    #[serde(default)]
+    pub bot_signals: bool,
+
+    /// QUIC transport parameters (`h3` listeners only)
+    #[serde(default)]
+    pub quic: QuicConfig,
+
+    /// Slow-client (slowloris) protection for requests on this listener
+    #[serde(default)]
+    pub slow_client: SlowClientConfig,
+
+    /// Request parser hardening for requests on this listener
+    #[serde(default)]
+    pub hardening: RequestHardeningConfig,
+
+    /// Egress rules (`forward-proxy` listeners only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward_proxy: Option<ForwardProxyConfig>,
+
+    /// Stream routing (`tcp` listeners only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamProxyConfig>,
 }
 
 /// Listener protocol
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ListenerProtocol {
     Http,
@@ -127,6 +218,1232 @@ pub enum ListenerProtocol {
     Http2,
     #[serde(rename = "h3")]
     Http3,
+    /// Egress proxy for HTTP CONNECT (and optionally SOCKS5) tunnels
+    #[serde(rename = "forward-proxy")]
+    ForwardProxy,
+    /// Layer 4 proxy forwarding raw TCP (or passed-through TLS) streams
+    Tcp,
+}
+
+/// QUIC transport parameters for HTTP/3 listeners.
+///
+/// The number of concurrent request streams per connection comes from the
+/// listener's `max-concurrent-streams`, as for HTTP/2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct QuicConfig {
+    /// Close connections idle for this long
+    #[serde(default = "default_quic_idle_timeout")]
+    pub max_idle_timeout_secs: u64,
+
+    /// Send keep-alive packets at this interval (None = disabled)
+    #[serde(default)]
+    pub keep_alive_interval_secs: Option<u64>,
+
+    /// Connection-level flow control window in bytes
+    #[serde(default = "default_quic_initial_max_data")]
+    pub initial_max_data: u64,
+
+    /// Per-stream flow control window in bytes
+    #[serde(default = "default_quic_initial_max_stream_data")]
+    pub initial_max_stream_data: u64,
+
+    /// Maximum concurrent unidirectional streams (HTTP/3 control and QPACK)
+    #[serde(default = "default_quic_max_concurrent_uni_streams")]
+    pub max_concurrent_uni_streams: u32,
+
+    /// Initial UDP payload size before path MTU discovery
+    #[serde(default = "default_quic_initial_mtu")]
+    pub initial_mtu: u16,
+
+    /// `ma` value of the `Alt-Svc` header advertising this listener on
+    /// HTTPS listeners (0 = don't advertise)
+    #[serde(default = "default_alt_svc_max_age")]
+    pub alt_svc_max_age_secs: u64,
+}
+
+impl Default for QuicConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_timeout_secs: default_quic_idle_timeout(),
+            keep_alive_interval_secs: None,
+            initial_max_data: default_quic_initial_max_data(),
+            initial_max_stream_data: default_quic_initial_max_stream_data(),
+            max_concurrent_uni_streams: default_quic_max_concurrent_uni_streams(),
+            initial_mtu: default_quic_initial_mtu(),
+            alt_svc_max_age_secs: default_alt_svc_max_age(),
+        }
+    }
+}
+
+// ============================================================================
+// Slow-Client Protection
+// ============================================================================
+
+/// Timeouts and minimum transfer rates against slow clients (slowloris).
+///
+/// Set per listener; a route's `policies { slow-client }` block overrides
+/// individual settings. Offending connections are closed. Every limit is
+/// off unless set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SlowClientConfig {
+    /// Longest pause allowed while reading the headers of follow-up requests
+    /// on a keep-alive connection; also caps the keep-alive idle time.
+    #[serde(default)]
+    pub header_timeout_secs: Option<u64>,
+
+    /// Minimum average request body upload rate (bytes per second)
+    #[serde(default)]
+    pub min_body_rate: Option<u64>,
+
+    /// Minimum response send rate (bytes per second)
+    #[serde(default)]
+    pub min_send_rate: Option<u64>,
+
+    /// Time before `min_body_rate` is enforced, so short bursts of latency
+    /// do not count (default: 5 seconds)
+    #[serde(default)]
+    pub rate_grace_secs: Option<u64>,
+
+    /// Longest a request may take from its headers to the last response
+    /// byte
+    #[serde(default)]
+    pub max_request_duration_secs: Option<u64>,
+}
+
+impl SlowClientConfig {
+    /// Default for `rate_grace_secs`
+    pub const DEFAULT_RATE_GRACE_SECS: u64 = 5;
+
+    /// These settings with every setting of `other` that is set taking
+    /// precedence
+    pub fn overridden_by(&self, other: &SlowClientConfig) -> SlowClientConfig {
+        SlowClientConfig {
+            header_timeout_secs: other.header_timeout_secs.or(self.header_timeout_secs),
+            min_body_rate: other.min_body_rate.or(self.min_body_rate),
+            min_send_rate: other.min_send_rate.or(self.min_send_rate),
+            rate_grace_secs: other.rate_grace_secs.or(self.rate_grace_secs),
+            max_request_duration_secs: other
+                .max_request_duration_secs
+                .or(self.max_request_duration_secs),
+        }
+    }
+
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.header_timeout_secs.is_some()
+            || self.min_body_rate.is_some()
+            || self.min_send_rate.is_some()
+            || self.max_request_duration_secs.is_some()
+    }
+}
+
+// ============================================================================
+// Request Hardening
+// ============================================================================
+
+/// Checks on the parsed request head, set per listener.
+///
+/// Each check answers with its own status code and closes the connection.
+/// Every check is off unless set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RequestHardeningConfig {
+    /// Maximum number of request headers
+    #[serde(default)]
+    pub max_header_count: Option<usize>,
+
+    /// Maximum total size of header names and values, in bytes
+    #[serde(default)]
+    pub max_header_bytes: Option<usize>,
+
+    /// Maximum length of the request target (path and query), in bytes
+    #[serde(default)]
+    pub max_uri_length: Option<usize>,
+
+    /// Methods accepted on the listener; empty accepts every method
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+
+    /// Reject requests with both `Content-Length` and `Transfer-Encoding`,
+    /// or with conflicting `Content-Length` values
+    #[serde(default)]
+    pub reject_ambiguous_framing: bool,
+
+    /// Handling of header values continued over several lines (obs-fold)
+    #[serde(default)]
+    pub obs_fold: ObsFoldPolicy,
+
+    /// Reject requests with NUL or other control characters in header
+    /// names or values
+    #[serde(default)]
+    pub reject_control_chars: bool,
+
+    /// Canonicalize request framing and path before route matching
+    #[serde(default)]
+    pub normalization: Option<RequestNormalizationConfig>,
+
+    /// Status codes of rejected requests, by check
+    #[serde(default)]
+    pub status: HardeningStatusCodes,
+}
+
+/// Canonical form of the request, applied before route matching and agents.
+///
+/// `Content-Length` and `Transfer-Encoding` are reduced to one unambiguous
+/// header, and the path has unreserved characters decoded, percent-encodings
+/// upper-cased and dot segments removed, so routes, agents and the upstream
+/// all see the same request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RequestNormalizationConfig {
+    /// Whether requests are rewritten and rejected, or only logged
+    #[serde(default)]
+    pub mode: NormalizationMode,
+
+    /// Collapse repeated slashes in the path
+    #[serde(default)]
+    pub merge_slashes: bool,
+}
+
+/// Enforcement of request normalization
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalizationMode {
+    /// Rewrite requests to their canonical form; reject requests without one
+    #[default]
+    Strict,
+    /// Log and count what would change, forwarding requests as received
+    LogOnly,
+}
+
+impl NormalizationMode {
+    /// Name used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NormalizationMode::Strict => "strict",
+            NormalizationMode::LogOnly => "log-only",
+        }
+    }
+}
+
+/// Handling of obs-fold (a header line starting with whitespace, continuing
+/// the previous value)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ObsFoldPolicy {
+    /// Forward values as parsed
+    #[default]
+    Allow,
+    /// Replace each fold with a single space, as RFC 9112 allows
+    Normalize,
+    /// Reject the request
+    Reject,
+}
+
+impl ObsFoldPolicy {
+    /// Name used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObsFoldPolicy::Allow => "allow",
+            ObsFoldPolicy::Normalize => "normalize",
+            ObsFoldPolicy::Reject => "reject",
+        }
+    }
+}
+
+/// Status codes answered by each request hardening check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HardeningStatusCodes {
+    #[serde(default = "default_status_header_fields_too_large")]
+    pub header_count: u16,
+    #[serde(default = "default_status_header_fields_too_large")]
+    pub header_bytes: u16,
+    #[serde(default = "default_status_uri_too_long")]
+    pub uri_length: u16,
+    #[serde(default = "default_status_method_not_allowed")]
+    pub method: u16,
+    #[serde(default = "default_status_bad_request")]
+    pub framing: u16,
+    #[serde(default = "default_status_bad_request")]
+    pub obs_fold: u16,
+    #[serde(default = "default_status_bad_request")]
+    pub control_chars: u16,
+    #[serde(default = "default_status_bad_request")]
+    pub normalization: u16,
+}
+
+impl Default for HardeningStatusCodes {
+    fn default() -> Self {
+        Self {
+            header_count: default_status_header_fields_too_large(),
+            header_bytes: default_status_header_fields_too_large(),
+            uri_length: default_status_uri_too_long(),
+            method: default_status_method_not_allowed(),
+            framing: default_status_bad_request(),
+            obs_fold: default_status_bad_request(),
+            control_chars: default_status_bad_request(),
+            normalization: default_status_bad_request(),
+        }
+    }
+}
+
+impl HardeningStatusCodes {
+    /// Every status code with the name of its check
+    pub fn all(&self) -> [(&'static str, u16); 8] {
+        [
+            ("header-count", self.header_count),
+            ("header-bytes", self.header_bytes),
+            ("uri-length", self.uri_length),
+            ("method", self.method),
+            ("framing", self.framing),
+            ("obs-fold", self.obs_fold),
+            ("control-chars", self.control_chars),
+            ("normalization", self.normalization),
+        ]
+    }
+}
+
+fn default_status_header_fields_too_large() -> u16 {
+    431
+}
+
+fn default_status_uri_too_long() -> u16 {
+    414
+}
+
+fn default_status_method_not_allowed() -> u16 {
+    405
+}
+
+fn default_status_bad_request() -> u16 {
+    400
+}
+
+// ============================================================================
+// Forward Proxy
+// ============================================================================
+
+/// Egress rules of a `forward-proxy` listener.
+///
+/// Clients open tunnels with HTTP `CONNECT host:port` (or SOCKS5 when
+/// enabled). A tunnel is opened only when the destination matches an `allow`
+/// rule and no `deny` rule, is authenticated when `users` are configured, and
+/// is allowed by the listed agents. Nothing is reachable without `allow`
+/// rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ForwardProxyConfig {
+    /// Also accept SOCKS5 clients on the listener
+    #[serde(default)]
+    pub socks5: bool,
+
+    /// Destinations clients may reach
+    #[serde(default)]
+    pub allow: Vec<DestinationRule>,
+
+    /// Destinations refused even when allowed, matched against the hostname
+    /// and the address it resolves to
+    #[serde(default)]
+    pub deny: Vec<DestinationRule>,
+
+    /// Accepted credentials (`Proxy-Authorization: Basic` or SOCKS5
+    /// username/password); no authentication when empty
+    #[serde(default)]
+    pub users: Vec<ForwardProxyUser>,
+
+    /// Agents asked about each tunnel, as a `CONNECT` request headers event
+    #[serde(default)]
+    pub agents: Vec<String>,
+
+    /// Timeout for connecting to the destination
+    #[serde(default = "default_forward_proxy_connect_timeout")]
+    pub connect_timeout_secs: u64,
+
+    /// Close tunnels without traffic in either direction for this long
+    #[serde(default = "default_forward_proxy_idle_timeout")]
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for ForwardProxyConfig {
+    fn default() -> Self {
+        Self {
+            socks5: false,
+            allow: Vec::new(),
+            deny: Vec::new(),
+            users: Vec::new(),
+            agents: Vec::new(),
+            connect_timeout_secs: default_forward_proxy_connect_timeout(),
+            idle_timeout_secs: default_forward_proxy_idle_timeout(),
+        }
+    }
+}
+
+/// Credentials accepted by a forward-proxy listener
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ForwardProxyUser {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for ForwardProxyUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForwardProxyUser")
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Forward-proxy destination rule, written `HOST:PORT`.
+///
+/// HOST is a hostname (`api.example.com`), a wildcard matching subdomains
+/// (`*.example.com`), `*`, or an IP network (`10.0.0.0/8`,
+/// `[2001:db8::]/32`). PORT is a number, a range (`8000-8999`) or `*`.
+/// Networks match the address a hostname resolves to as well as IP literals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "String", into = "String")]
+pub struct DestinationRule {
+    host: DestinationHost,
+    ports: (u16, u16),
+    raw: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DestinationHost {
+    Any,
+    Exact(String),
+    /// Subdomains of the name, stored with its leading dot
+    Suffix(String),
+    Network(IpCidr),
+}
+
+impl DestinationRule {
+    /// Check whether a destination matches, given its hostname (or IP
+    /// literal) and, once resolved, its address.
+    pub fn matches(&self, host: &str, ip: Option<IpAddr>, port: u16) -> bool {
+        if port < self.ports.0 || port > self.ports.1 {
+            return false;
+        }
+        let host = host.trim_end_matches('.');
+        match &self.host {
+            DestinationHost::Any => true,
+            DestinationHost::Exact(name) => host.eq_ignore_ascii_case(name),
+            DestinationHost::Suffix(suffix) => {
+                host.len() > suffix.len() && host.to_ascii_lowercase().ends_with(suffix.as_str())
+            }
+            DestinationHost::Network(cidr) => host
+                .parse::<IpAddr>()
+                .ok()
+                .or(ip)
+                .is_some_and(|ip| cidr.contains(ip)),
+        }
+    }
+
+    /// Whether the rule matches on addresses, so a hostname it does not
+    /// match by name may still match once resolved.
+    pub fn matches_addresses(&self) -> bool {
+        matches!(self.host, DestinationHost::Network(_))
+    }
+}
+
+impl FromStr for DestinationRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("destination '{}' must be HOST:PORT", s))?;
+
+        let ports = match port {
+            "*" => (0, u16::MAX),
+            port => {
+                let (low, high) = port.split_once('-').unwrap_or((port, port));
+                match (low.parse::<u16>(), high.parse::<u16>()) {
+                    (Ok(low), Ok(high)) if low <= high => (low, high),
+                    _ => return Err(format!("invalid port '{}' in destination '{}'", port, s)),
+                }
+            }
+        };
+
+        let host = host.trim();
+        let unbracketed = host.replace(['[', ']'], "");
+        let host = if host == "*" {
+            DestinationHost::Any
+        } else if unbracketed.contains('/') || unbracketed.parse::<IpAddr>().is_ok() {
+            DestinationHost::Network(unbracketed.parse()?)
+        } else if let Some(domain) = host.strip_prefix("*.") {
+            DestinationHost::Suffix(format!(".{}", domain.to_ascii_lowercase()))
+        } else if !host.is_empty() && !host.contains('*') {
+            DestinationHost::Exact(host.trim_end_matches('.').to_ascii_lowercase())
+        } else {
+            return Err(format!("invalid host '{}' in destination '{}'", host, s));
+        };
+
+        Ok(Self {
+            host,
+            ports,
+            raw: s.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for DestinationRule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<DestinationRule> for String {
+    fn from(rule: DestinationRule) -> Self {
+        rule.raw
+    }
+}
+
+impl fmt::Display for DestinationRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+// ============================================================================
+// Stream Proxy
+// ============================================================================
+
+/// Stream routing of a `tcp` listener.
+///
+/// Connections are forwarded byte for byte to an upstream pool. TLS is not
+/// terminated: when `routes` are configured, the server name of a TLS
+/// ClientHello picks the route, and connections without a matching name go
+/// to `upstream`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StreamProxyConfig {
+    /// Upstream for connections no route matches
+    #[serde(default)]
+    pub upstream: Option<String>,
+
+    /// SNI routes, first match wins
+    #[serde(default)]
+    pub routes: Vec<StreamRoute>,
+
+    /// Agents notified when connections open and close; they may refuse
+    /// a connection when it opens
+    #[serde(default)]
+    pub agents: Vec<String>,
+
+    /// Bytes of each direction sent to agents as body chunks (0 = none)
+    #[serde(default)]
+    pub inspect_bytes: usize,
+
+    /// Fraction of connections whose payload is inspected
+    #[serde(default = "default_stream_sample_rate")]
+    pub sample_rate: f64,
+
+    /// Timeout for connecting to the upstream
+    #[serde(default = "default_stream_connect_timeout")]
+    pub connect_timeout_secs: u64,
+
+    /// Close connections without traffic in either direction for this long
+    #[serde(default = "default_stream_idle_timeout")]
+    pub idle_timeout_secs: u64,
+
+    /// Parse connections to the default upstream as Redis (RESP) traffic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis: Option<RedisStreamConfig>,
+}
+
+impl Default for StreamProxyConfig {
+    fn default() -> Self {
+        Self {
+            upstream: None,
+            routes: Vec::new(),
+            agents: Vec::new(),
+            inspect_bytes: 0,
+            sample_rate: default_stream_sample_rate(),
+            connect_timeout_secs: default_stream_connect_timeout(),
+            idle_timeout_secs: default_stream_idle_timeout(),
+            redis: None,
+        }
+    }
+}
+
+impl StreamProxyConfig {
+    /// Route for a ClientHello server name, if any matches
+    pub fn route_for(&self, server_name: &str) -> Option<&StreamRoute> {
+        self.routes.iter().find(|route| route.matches(server_name))
+    }
+}
+
+/// Redis awareness of a `tcp` listener's default upstream.
+///
+/// Commands are parsed from the client's RESP frames and checked one by
+/// one: against `deny-commands`, then `allow-commands` when set, then the
+/// listener's agents. A refused command is answered with an error and never
+/// reaches Redis; the connection stays open. Only plaintext connections can
+/// be parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RedisStreamConfig {
+    /// Only these commands are forwarded (all when empty)
+    #[serde(default)]
+    pub allow_commands: Vec<String>,
+
+    /// Commands never forwarded
+    #[serde(default)]
+    pub deny_commands: Vec<String>,
+}
+
+impl RedisStreamConfig {
+    /// Whether the lists let `command` (any case) through
+    pub fn permits(&self, command: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|c| c.eq_ignore_ascii_case(command));
+        !listed(&self.deny_commands)
+            && (self.allow_commands.is_empty() || listed(&self.allow_commands))
+    }
+}
+
+/// Connections for some TLS server names, forwarded to one upstream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StreamRoute {
+    pub id: String,
+
+    /// Server names, exact or `*.domain` for subdomains
+    pub sni: Vec<String>,
+
+    pub upstream: String,
+}
+
+impl StreamRoute {
+    /// Check whether a server name matches one of the route's names
+    pub fn matches(&self, server_name: &str) -> bool {
+        let name = server_name.trim_end_matches('.');
+        self.sni
+            .iter()
+            .any(|pattern| match pattern.strip_prefix('*') {
+                Some(suffix) => {
+                    name.len() > suffix.len()
+                        && name
+                            .to_ascii_lowercase()
+                            .ends_with(&suffix.to_ascii_lowercase())
+                }
+                None => name.eq_ignore_ascii_case(pattern),
+            })
+    }
+}
+
+// ============================================================================
+// Client IP Resolution
+// ============================================================================
+
+/// How the client IP is determined for metadata, rate limiting and logs.
+///
+/// Forwarding headers are only honoured when the connecting peer is a trusted
+/// proxy. Header chains are walked right to left, skipping trusted proxies;
+/// the first untrusted address is the client. With no trusted proxies (the
+/// default) the socket peer (or PROXY protocol source) is always used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClientIpConfig {
+    /// Networks whose forwarding headers are trusted
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpCidr>,
+
+    /// Headers to consult, in order; the first one present is used
+    #[serde(default = "default_client_ip_headers")]
+    pub headers: Vec<ClientIpHeader>,
+}
+
+impl Default for ClientIpConfig {
+    fn default() -> Self {
+        Self {
+            trusted_proxies: Vec::new(),
+            headers: default_client_ip_headers(),
+        }
+    }
+}
+
+/// Outbound proxy for control-plane HTTP requests
+///
+/// Covers requests Zentinel makes on its own behalf: agent bundle and lock
+/// file downloads, the ACME client and DNS provider APIs, OCSP fetches and
+/// Kubernetes discovery. Proxied client traffic is never affected. When no
+/// proxy URL is set here, the standard `HTTP_PROXY`, `HTTPS_PROXY`,
+/// `ALL_PROXY` and `NO_PROXY` environment variables are honoured instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OutboundProxyConfig {
+    /// Proxy URL for `http://` requests
+    #[serde(default)]
+    pub http: Option<String>,
+
+    /// Proxy URL for `https://` requests
+    #[serde(default)]
+    pub https: Option<String>,
+
+    /// Proxy URL for all requests, e.g. `socks5h://proxy.corp:1080`
+    #[serde(default)]
+    pub all: Option<String>,
+
+    /// Hosts, domain suffixes (`.corp.example`) and CIDRs that bypass the proxy
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl OutboundProxyConfig {
+    /// Proxy URL schemes accepted for outbound requests
+    pub const SCHEMES: &'static [&'static str] = &["http", "https", "socks5", "socks5h"];
+
+    /// Whether any proxy URL is configured
+    pub fn is_configured(&self) -> bool {
+        self.http.is_some() || self.https.is_some() || self.all.is_some()
+    }
+}
+
+/// Maintenance mode configuration.
+///
+/// Routes, or every proxied route at once, are put into maintenance at
+/// runtime through the `maintenance` admin endpoint, without a config
+/// reload. Their requests are answered with 503 and `Retry-After` unless the
+/// client address is in `allow_ips` or the request carries `bypass_header`
+/// with the secret read from `bypass_secret_env`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MaintenanceConfig {
+    /// Put every proxied route into maintenance at startup
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Routes in maintenance at startup
+    #[serde(default)]
+    pub routes: Vec<String>,
+
+    /// `Retry-After` value sent with the 503, in seconds
+    #[serde(default = "default_maintenance_retry_after")]
+    pub retry_after_secs: u64,
+
+    /// HTML page served with the 503 (built-in page when unset)
+    #[serde(default)]
+    pub page: Option<PathBuf>,
+
+    /// Client networks that reach routes in maintenance
+    #[serde(default)]
+    pub allow_ips: Vec<IpCidr>,
+
+    /// Request header that bypasses maintenance when it carries the secret
+    #[serde(default)]
+    pub bypass_header: Option<String>,
+
+    /// Environment variable holding the bypass secret
+    #[serde(default)]
+    pub bypass_secret_env: Option<String>,
+
+    /// File the maintenance flags are written to on every change and
+    /// restored from on startup. Flags are kept in memory only when unset.
+    #[serde(default)]
+    pub state_file: Option<PathBuf>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            routes: Vec::new(),
+            retry_after_secs: default_maintenance_retry_after(),
+            page: None,
+            allow_ips: Vec::new(),
+            bypass_header: None,
+            bypass_secret_env: None,
+            state_file: None,
+        }
+    }
+}
+
+pub(crate) fn default_maintenance_retry_after() -> u64 {
+    300
+}
+
+/// Fault injection for resilience testing.
+///
+/// Faults are inert until armed through the `chaos` admin endpoint, which
+/// refuses to arm any while `enabled` is off. An armed fault applies to a
+/// `probability` share of the requests or agent calls it targets and
+/// disarms itself after `duration_secs`. Armed faults are kept in memory
+/// only; a restart disarms them all.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChaosConfig {
+    /// Allow faults to be armed
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Faults that can be armed
+    #[serde(default)]
+    pub faults: Vec<ChaosFault>,
+}
+
+/// A fault that can be armed through the `chaos` admin endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChaosFault {
+    /// Unique fault identifier, used to arm and disarm it
+    pub id: String,
+
+    /// Route or agent the fault applies to
+    pub target: ChaosTarget,
+
+    /// What happens to an affected request or agent call
+    pub kind: ChaosFaultKind,
+
+    /// Share of requests or agent calls affected while armed (0.0-1.0]
+    #[serde(default = "default_chaos_probability")]
+    pub probability: f64,
+
+    /// Seconds the fault stays armed
+    #[serde(default = "default_chaos_duration")]
+    pub duration_secs: u64,
+}
+
+/// What a chaos fault applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ChaosTarget {
+    /// Requests matched to a route
+    Route(String),
+    /// Calls to an agent
+    Agent(String),
+}
+
+/// Failure injected by a chaos fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ChaosFaultKind {
+    /// Delay the request or agent call
+    Latency { ms: u64 },
+    /// Answer the request with `status`; agent calls fail with an error
+    Error { status: u16 },
+    /// Close the client connection without a response (routes only)
+    Reset,
+    /// Hold the agent call until its timeout fires (agents only)
+    AgentTimeout,
+}
+
+impl ChaosFaultKind {
+    /// Name used in KDL, metrics and the admin endpoint
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Latency { .. } => "latency",
+            Self::Error { .. } => "error",
+            Self::Reset => "reset",
+            Self::AgentTimeout => "agent-timeout",
+        }
+    }
+}
+
+/// Traffic capture for load tests and agent regression tests.
+///
+/// Captures are started through the `capture` admin endpoint for one route
+/// and a sample of its requests, and written as JSON lines to a file in
+/// `directory`. Header values, query parameters and JSON body fields that
+/// carry secrets are redacted before anything is written; the built-in
+/// lists below are always applied, the configured ones are added to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CaptureConfig {
+    /// Directory capture files are written to
+    pub directory: PathBuf,
+
+    /// Request body bytes kept per request; longer bodies are truncated
+    #[serde(default = "default_capture_max_body_bytes")]
+    pub max_body_bytes: usize,
+
+    /// Headers whose values are redacted, in addition to `authorization`,
+    /// `proxy-authorization`, `cookie` and `x-api-key`
+    #[serde(default)]
+    pub redact_headers: Vec<String>,
+
+    /// Query parameters whose values are redacted, in addition to
+    /// `access_token`, `api_key`, `apikey`, `token`, `password` and `secret`
+    #[serde(default)]
+    pub redact_query_params: Vec<String>,
+
+    /// JSON body fields (at any depth) whose values are redacted, in
+    /// addition to `password`, `secret`, `token`, `access_token`,
+    /// `refresh_token`, `client_secret` and `api_key`
+    #[serde(default)]
+    pub redact_json_fields: Vec<String>,
+}
+
+impl CaptureConfig {
+    /// Headers always redacted
+    pub const REDACTED_HEADERS: &'static [&'static str] = &[
+        "authorization",
+        "proxy-authorization",
+        "cookie",
+        "x-api-key",
+    ];
+
+    /// Query parameters always redacted
+    pub const REDACTED_QUERY_PARAMS: &'static [&'static str] = &[
+        "access_token",
+        "api_key",
+        "apikey",
+        "token",
+        "password",
+        "secret",
+    ];
+
+    /// JSON body fields always redacted
+    pub const REDACTED_JSON_FIELDS: &'static [&'static str] = &[
+        "password",
+        "secret",
+        "token",
+        "access_token",
+        "refresh_token",
+        "client_secret",
+        "api_key",
+    ];
+}
+
+pub(crate) fn default_capture_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+/// Staged rollout of reloaded configurations.
+///
+/// When set, a reloaded route table first serves `percent` of the traffic,
+/// picked by a hash of `hash_header` (or of the client IP), while the rest
+/// keeps the previous route table. Error rate and mean latency of both
+/// cohorts are compared during the bake window: a regression beyond the
+/// thresholds rolls the configuration back and sends a
+/// `config-rolled-back` notification, otherwise the new version is promoted
+/// to all traffic when the window ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RolloutConfig {
+    /// Percentage of traffic the new version serves during the bake window
+    #[serde(default = "default_rollout_percent")]
+    pub percent: f64,
+
+    /// How long both versions are compared before the new one is promoted
+    #[serde(default = "default_rollout_bake_window")]
+    pub bake_window_secs: u64,
+
+    /// Requests each cohort must see before the versions are compared
+    #[serde(default = "default_rollout_min_requests")]
+    pub min_requests: u64,
+
+    /// Allowed increase of the 5xx rate, in percentage points
+    #[serde(default = "default_rollout_max_error_rate_increase")]
+    pub max_error_rate_increase: f64,
+
+    /// Allowed increase of the mean latency, in percent
+    #[serde(default = "default_rollout_max_latency_increase")]
+    pub max_latency_increase_percent: f64,
+
+    /// Header whose value assigns requests to a cohort; the client IP is
+    /// used when unset or absent from the request
+    #[serde(default)]
+    pub hash_header: Option<String>,
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        Self {
+            percent: default_rollout_percent(),
+            bake_window_secs: default_rollout_bake_window(),
+            min_requests: default_rollout_min_requests(),
+            max_error_rate_increase: default_rollout_max_error_rate_increase(),
+            max_latency_increase_percent: default_rollout_max_latency_increase(),
+            hash_header: None,
+        }
+    }
+}
+
+pub(crate) fn default_rollout_percent() -> f64 {
+    10.0
+}
+
+pub(crate) fn default_rollout_bake_window() -> u64 {
+    300
+}
+
+pub(crate) fn default_rollout_min_requests() -> u64 {
+    100
+}
+
+pub(crate) fn default_rollout_max_error_rate_increase() -> f64 {
+    1.0
+}
+
+pub(crate) fn default_rollout_max_latency_increase() -> f64 {
+    50.0
+}
+
+/// Agent decision headers for trusted callers.
+///
+/// A request carrying a valid debug token in `token_header` is answered with
+/// `X-Zentinel-Decision`, `X-Zentinel-Rule-Ids` and `X-Zentinel-Agent-Times`,
+/// assembled from the audit metadata of the agents that saw it. A token is
+/// `<subject>.<expires>.<signature>`: `expires` is a Unix timestamp and the
+/// signature the hex HMAC-SHA256 of `<subject>.<expires>` under `secret`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DebugHeadersConfig {
+    /// Key debug tokens are signed with, normally a secret reference such as
+    /// `${env:ZENTINEL_DEBUG_SECRET}`
+    pub secret: String,
+
+    /// Request header carrying the debug token; removed before agents and
+    /// the upstream see the request
+    #[serde(default = "default_debug_token_header")]
+    pub token_header: String,
+
+    /// Longest accepted remaining token lifetime, in seconds
+    #[serde(default = "default_debug_token_max_ttl")]
+    pub max_ttl_secs: u64,
+}
+
+pub(crate) fn default_debug_token_header() -> String {
+    "X-Zentinel-Debug-Token".to_string()
+}
+
+pub(crate) fn default_debug_token_max_ttl() -> u64 {
+    3600
+}
+
+pub(crate) fn default_chaos_probability() -> f64 {
+    1.0
+}
+
+pub(crate) fn default_chaos_duration() -> u64 {
+    300
+}
+
+/// Readiness criteria for the `readyz` probe.
+///
+/// A loaded configuration is always required. An upstream counts as
+/// healthy while at least one of its targets is not marked unhealthy; an
+/// agent while its connection pool is healthy and its circuit breaker closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReadinessConfig {
+    /// Require every listener to accept connections
+    #[serde(default = "default_true")]
+    pub require_listeners: bool,
+
+    /// Healthy upstreams needed to be ready
+    #[serde(default)]
+    pub min_healthy_upstreams: usize,
+
+    /// Healthy agents needed to be ready
+    #[serde(default)]
+    pub min_healthy_agents: usize,
+
+    /// Upstreams that must be healthy to be ready
+    #[serde(default)]
+    pub required_upstreams: Vec<String>,
+
+    /// Agents that must be healthy to be ready
+    #[serde(default)]
+    pub required_agents: Vec<String>,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            require_listeners: true,
+            min_healthy_upstreams: 0,
+            min_healthy_agents: 0,
+            required_upstreams: Vec::new(),
+            required_agents: Vec::new(),
+        }
+    }
+}
+
+/// Request ID policy.
+///
+/// Every request gets an ID (the correlation ID) that appears in logs,
+/// agent metadata and error pages. It is taken from an incoming header when
+/// trusted and valid, otherwise generated in the `trace-id-format` format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RequestIdConfig {
+    /// Incoming header carrying the ID. When unset, `X-Trace-Id`,
+    /// `X-Correlation-Id` and `X-Request-Id` are checked in that order.
+    #[serde(default)]
+    pub header: Option<String>,
+
+    /// Use the incoming ID instead of generating one
+    #[serde(default = "default_true")]
+    pub trust_incoming: bool,
+
+    /// Longest incoming ID accepted; longer IDs are replaced
+    #[serde(default = "default_request_id_max_length")]
+    pub max_length: usize,
+
+    /// Header carrying the ID to upstreams
+    #[serde(default = "default_request_id_header")]
+    pub propagate_header: String,
+
+    /// Return the ID to the client
+    #[serde(default = "default_true")]
+    pub echo: bool,
+
+    /// Response header carrying the ID back to the client
+    #[serde(default = "default_request_id_header")]
+    pub response_header: String,
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self {
+            header: None,
+            trust_incoming: true,
+            max_length: default_request_id_max_length(),
+            propagate_header: default_request_id_header(),
+            echo: true,
+            response_header: default_request_id_header(),
+        }
+    }
+}
+
+pub(crate) fn default_request_id_max_length() -> usize {
+    128
+}
+
+pub(crate) fn default_request_id_header() -> String {
+    "X-Correlation-Id".to_string()
+}
+
+/// Forwarding header carrying the client address chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ClientIpHeader {
+    /// `X-Forwarded-For: client, proxy1, proxy2`
+    XForwardedFor,
+    /// RFC 7239 `Forwarded: for=client, for=proxy1`
+    Forwarded,
+    /// `X-Real-IP: client` (single address)
+    XRealIp,
+}
+
+impl ClientIpHeader {
+    /// Parse a header name (case-insensitive).
+    pub fn from_str_loose(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "x-forwarded-for" => Some(Self::XForwardedFor),
+            "forwarded" => Some(Self::Forwarded),
+            "x-real-ip" => Some(Self::XRealIp),
+            _ => None,
+        }
+    }
+
+    /// Lowercase HTTP header name.
+    pub fn header_name(&self) -> &'static str {
+        match self {
+            Self::XForwardedFor => "x-forwarded-for",
+            Self::Forwarded => "forwarded",
+            Self::XRealIp => "x-real-ip",
+        }
+    }
+}
+
+/// IP network in CIDR notation (`10.0.0.0/8`, `2001:db8::/32`).
+///
+/// A bare address is a single-host network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "String", into = "String")]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Check whether an address falls within this network.
+    ///
+    /// IPv4-mapped IPv6 addresses match the corresponding IPv4 network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid IP address in CIDR '{}'", s))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| {
+                    format!("invalid prefix length in CIDR '{}' (max {})", s, max_len)
+                })?,
+            None => max_len,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl TryFrom<String> for IpCidr {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<IpCidr> for String {
+    fn from(cidr: IpCidr) -> Self {
+        cidr.to_string()
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
 }
 
 // ============================================================================
@@ -135,6 +1452,7 @@ pub enum ListenerProtocol {
 
 /// TLS configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TlsConfig {
     /// Default certificate file path (used when no SNI match)
     /// Optional when ACME is configured
@@ -167,6 +1485,11 @@ pub struct TlsConfig {
     #[serde(default)]
     pub client_auth: bool,
 
+    /// Request header that forwards the client certificate to upstreams as
+    /// URL-encoded PEM (mTLS only; not forwarded when unset)
+    #[serde(default)]
+    pub client_cert_header: Option<String>,
+
     /// OCSP stapling
     #[serde(default = "default_ocsp_stapling")]
     pub ocsp_stapling: bool,
@@ -214,6 +1537,7 @@ pub struct TlsConfig {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AcmeConfig {
     /// Contact email for account registration and recovery
     #[validate(email)]
@@ -263,6 +1587,7 @@ pub struct AcmeConfig {
 
 /// ACME key type
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum AcmeKeyType {
     /// ECDSA P-256 (default)
@@ -293,6 +1618,7 @@ impl AcmeKeyType {
 
 /// External Account Binding (EAB) credentials for ACME
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExternalAccountBinding {
     /// Key ID (KID) provided by the ACME CA
     pub kid: String,
@@ -302,6 +1628,7 @@ pub struct ExternalAccountBinding {
 
 /// ACME challenge type
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum AcmeChallengeType {
     /// HTTP-01 challenge (default)
@@ -329,6 +1656,7 @@ impl AcmeChallengeType {
 
 /// DNS provider configuration for DNS-01 challenges
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DnsProviderConfig {
     /// DNS provider type
     pub provider: DnsProviderType,
@@ -351,6 +1679,7 @@ pub struct DnsProviderConfig {
 
 /// DNS provider type
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum DnsProviderType {
     /// Hetzner DNS API
@@ -370,6 +1699,7 @@ pub enum DnsProviderType {
 
 /// Configuration for DNS propagation checking
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PropagationCheckConfig {
     /// Initial delay before first check (seconds)
     #[serde(default = "default_propagation_initial_delay")]
@@ -402,6 +1732,7 @@ impl Default for PropagationCheckConfig {
 
 /// SNI certificate configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SniCertificate {
     /// Hostname patterns to match (e.g., "example.com", "*.example.com").
     /// When set, only these hostnames are registered and SAN auto-extraction is skipped.
@@ -420,12 +1751,26 @@ pub struct SniCertificate {
 
     /// ACME configuration for this certificate
     pub acme: Option<AcmeConfig>,
+
+    /// Route set for requests to this certificate's hostnames, by namespace name.
+    ///
+    /// Requests whose server name matches one of the declared hostnames
+    /// (`hostnames`, `priority-hostnames` or ACME domains; wildcards allowed)
+    /// are matched only against the named namespace's routes, taking
+    /// precedence over the listener's `namespace`. Lets one listener serve
+    /// several tenants with separate route tables.
+    #[serde(default)]
+    pub namespace: Option<String>,
 }
 
 // ============================================================================
 // Default Value Functions
 // ============================================================================
 
+fn default_true() -> bool {
+    true
+}
+
 pub(crate) fn default_worker_threads() -> usize {
     0
 }
@@ -438,6 +1783,10 @@ pub(crate) fn default_route_cache_size() -> usize {
     1000
 }
 
+fn default_client_ip_headers() -> Vec<ClientIpHeader> {
+    vec![ClientIpHeader::XForwardedFor]
+}
+
 pub(crate) fn default_graceful_shutdown_timeout() -> u64 {
     30
 }
@@ -454,6 +1803,50 @@ pub(crate) fn default_max_concurrent_streams() -> u32 {
     100
 }
 
+pub(crate) fn default_quic_idle_timeout() -> u64 {
+    30
+}
+
+pub(crate) fn default_quic_initial_max_data() -> u64 {
+    10 * 1024 * 1024
+}
+
+pub(crate) fn default_quic_initial_max_stream_data() -> u64 {
+    1024 * 1024
+}
+
+pub(crate) fn default_quic_max_concurrent_uni_streams() -> u32 {
+    100
+}
+
+pub(crate) fn default_quic_initial_mtu() -> u16 {
+    1200
+}
+
+pub(crate) fn default_alt_svc_max_age() -> u64 {
+    86400
+}
+
+fn default_forward_proxy_connect_timeout() -> u64 {
+    10
+}
+
+fn default_forward_proxy_idle_timeout() -> u64 {
+    300
+}
+
+fn default_stream_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_stream_connect_timeout() -> u64 {
+    10
+}
+
+fn default_stream_idle_timeout() -> u64 {
+    600
+}
+
 fn default_min_tls_version() -> TlsVersion {
     TlsVersion::Tls12
 }
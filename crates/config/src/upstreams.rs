@@ -4,7 +4,8 @@
 //! including load balancing, health checks, and connection pooling.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use validator::Validate;
 
@@ -19,6 +20,7 @@ use zentinel_common::{
 
 /// Cookie SameSite policy for sticky session cookies
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum SameSitePolicy {
     /// Lax - Cookies sent with top-level navigations and GET from third-party sites
@@ -45,6 +47,7 @@ impl std::fmt::Display for SameSitePolicy {
 /// When enabled, the load balancer will set an affinity cookie on responses
 /// and use it to route subsequent requests to the same backend.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct StickySessionConfig {
     /// Cookie name for session affinity (e.g., "SERVERID")
     pub cookie_name: String,
@@ -81,20 +84,238 @@ fn default_sticky_fallback() -> LoadBalancingAlgorithm {
     LoadBalancingAlgorithm::RoundRobin
 }
 
+// ============================================================================
+// Consistent Hash Configuration
+// ============================================================================
+
+/// Request attribute used as the consistent-hash key
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", tag = "type", content = "name")]
+pub enum HashKeySource {
+    /// Client IP address
+    #[default]
+    ClientIp,
+    /// Value of the named request header
+    Header(String),
+    /// Value of the named cookie
+    Cookie(String),
+}
+
+/// Configuration for consistent-hash and Maglev load balancing
+///
+/// Requests with the same key are routed to the same target while it stays
+/// healthy. Bounded loads cap each target at `max_load_factor` times the
+/// average in-flight load, spilling hot keys to the next target on the ring.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConsistentHashConfig {
+    /// Where the hash key is taken from
+    #[serde(default)]
+    pub key: HashKeySource,
+
+    /// Virtual nodes per target on the hash ring
+    #[serde(default = "default_virtual_nodes")]
+    pub virtual_nodes: usize,
+
+    /// Whether to enforce bounded loads
+    #[serde(default = "default_true")]
+    pub bounded_load: bool,
+
+    /// Load cap relative to the average (1.25 = 25% above average)
+    #[serde(default = "default_max_load_factor")]
+    pub max_load_factor: f64,
+}
+
+impl Default for ConsistentHashConfig {
+    fn default() -> Self {
+        Self {
+            key: HashKeySource::default(),
+            virtual_nodes: default_virtual_nodes(),
+            bounded_load: true,
+            max_load_factor: default_max_load_factor(),
+        }
+    }
+}
+
+fn default_virtual_nodes() -> usize {
+    150
+}
+
+fn default_max_load_factor() -> f64 {
+    1.25
+}
+
+// ============================================================================
+// Service Discovery Configuration
+// ============================================================================
+
+/// Source of upstream membership for dynamic environments
+///
+/// Discovered targets replace the static `targets` list once the first
+/// refresh succeeds; static targets (if any) serve until then.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServiceDiscoveryConfig {
+    /// Periodic DNS A/AAAA resolution
+    Dns {
+        /// Hostname to resolve
+        hostname: String,
+        /// Port for every resolved address
+        port: u16,
+        /// Seconds between resolutions
+        #[serde(default = "default_discovery_refresh_secs")]
+        refresh_interval_secs: u64,
+    },
+    /// Periodic DNS SRV resolution (ports and weights come from the records)
+    DnsSrv {
+        /// SRV name, e.g. `_http._tcp.api.internal`
+        service: String,
+        /// Seconds between resolutions
+        #[serde(default = "default_discovery_refresh_secs")]
+        refresh_interval_secs: u64,
+    },
+    /// Consul service catalog
+    Consul {
+        /// Consul HTTP API address
+        address: String,
+        /// Service name
+        service: String,
+        /// Datacenter (default: the agent's)
+        #[serde(default)]
+        datacenter: Option<String>,
+        /// Only return instances passing their Consul health checks
+        #[serde(default = "default_true")]
+        only_passing: bool,
+        /// Only return instances with this tag
+        #[serde(default)]
+        tag: Option<String>,
+        /// Seconds between catalog queries
+        #[serde(default = "default_discovery_refresh_secs")]
+        refresh_interval_secs: u64,
+    },
+    /// Kubernetes EndpointSlices for a Service (requires the `kubernetes` feature)
+    Kubernetes {
+        /// Namespace of the Service
+        namespace: String,
+        /// Service name
+        service: String,
+        /// Named port to use (default: the first port)
+        #[serde(default)]
+        port_name: Option<String>,
+        /// Kubeconfig path (default: in-cluster credentials)
+        #[serde(default)]
+        kubeconfig: Option<String>,
+        /// Seconds between API queries
+        #[serde(default = "default_discovery_refresh_secs")]
+        refresh_interval_secs: u64,
+    },
+    /// Backend list file, re-read when modified
+    File {
+        /// Path to the file (one `host:port [weight=N]` per line)
+        path: String,
+        /// Seconds between modification checks
+        #[serde(default = "default_discovery_refresh_secs")]
+        watch_interval_secs: u64,
+    },
+}
+
+fn default_discovery_refresh_secs() -> u64 {
+    30
+}
+
+// ============================================================================
+// Upstream Authentication
+// ============================================================================
+
+/// Credentials attached to every request sent to an upstream
+///
+/// Secrets are normally given as secret references (`${env:VAR}`,
+/// `${file:/path}`), resolved when the configuration is loaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UpstreamAuthConfig {
+    /// Static bearer token (`Authorization: Bearer <token>`)
+    Bearer {
+        /// Token sent to the upstream
+        token: String,
+    },
+    /// HTTP basic authentication
+    Basic {
+        /// User name
+        username: String,
+        /// Password
+        password: String,
+    },
+    /// OAuth2 client credentials grant; tokens are cached and refreshed
+    /// shortly before they expire
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        /// Token endpoint of the authorization server
+        token_url: String,
+        /// Client ID
+        client_id: String,
+        /// Client secret
+        client_secret: String,
+        /// Requested scopes
+        #[serde(default)]
+        scopes: Vec<String>,
+        /// Audience parameter, for authorization servers that require one
+        #[serde(default)]
+        audience: Option<String>,
+        /// How the client authenticates to the token endpoint
+        #[serde(default)]
+        client_auth: OAuth2ClientAuth,
+        /// Seconds before expiry at which a token is refreshed
+        #[serde(default = "default_oauth2_refresh_before_secs")]
+        refresh_before_secs: u64,
+        /// Timeout for token requests in seconds
+        #[serde(default = "default_oauth2_timeout_secs")]
+        timeout_secs: u64,
+    },
+}
+
+/// Client authentication method for OAuth2 token requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OAuth2ClientAuth {
+    /// HTTP basic authentication (`client_secret_basic`)
+    #[default]
+    Basic,
+    /// Credentials in the form body (`client_secret_post`)
+    Body,
+}
+
+fn default_oauth2_refresh_before_secs() -> u64 {
+    60
+}
+
+fn default_oauth2_timeout_secs() -> u64 {
+    10
+}
+
 // ============================================================================
 // Upstream Configuration
 // ============================================================================
 
 /// Upstream configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[validate(schema(function = "validate_upstream_targets"))]
 pub struct UpstreamConfig {
     /// Unique upstream identifier
     pub id: String,
 
-    /// Upstream targets
-    #[validate(length(min = 1, message = "At least one target is required"))]
+    /// Upstream targets (may be empty when `discovery` is set)
     pub targets: Vec<UpstreamTarget>,
 
+    /// Service discovery source for dynamic membership
+    #[serde(default)]
+    pub discovery: Option<ServiceDiscoveryConfig>,
+
     /// Load balancing algorithm
     #[serde(default = "default_lb_algorithm")]
     pub load_balancing: LoadBalancingAlgorithm,
@@ -102,6 +323,10 @@ pub struct UpstreamConfig {
     /// Sticky session configuration (for cookie-based session affinity)
     pub sticky_session: Option<StickySessionConfig>,
 
+    /// Hash key and bounded-load settings (for consistent_hash and maglev)
+    #[serde(default)]
+    pub consistent_hash: Option<ConsistentHashConfig>,
+
     /// Health check configuration
     pub health_check: Option<HealthCheck>,
 
@@ -119,6 +344,14 @@ pub struct UpstreamConfig {
     /// TLS configuration for upstream connections
     pub tls: Option<UpstreamTlsConfig>,
 
+    /// Credentials attached to requests sent to this upstream
+    #[serde(default)]
+    pub auth: Option<UpstreamAuthConfig>,
+
+    /// In-flight limit with a priority queue for requests over it
+    #[serde(default)]
+    pub queue: Option<UpstreamQueueConfig>,
+
     /// HTTP version configuration
     #[serde(default)]
     pub http_version: HttpVersionConfig,
@@ -126,6 +359,7 @@ pub struct UpstreamConfig {
 
 /// HTTP version configuration for upstream connections
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HttpVersionConfig {
     /// Minimum HTTP version (1 or 2)
     #[serde(default = "default_min_http_version")]
@@ -169,6 +403,7 @@ fn default_max_h2_streams() -> usize {
 
 /// Individual upstream target
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UpstreamTarget {
     /// Target address (host:port)
     pub address: String,
@@ -191,6 +426,7 @@ pub struct UpstreamTarget {
 
 /// Health check configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HealthCheck {
     /// Health check type
     #[serde(rename = "type")]
@@ -219,6 +455,7 @@ pub struct HealthCheck {
 
 /// Connection pool configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ConnectionPoolConfig {
     /// Maximum connections per target
     #[serde(default = "default_max_connections_per_target")]
@@ -234,6 +471,10 @@ pub struct ConnectionPoolConfig {
 
     /// Connection lifetime
     pub max_lifetime_secs: Option<u64>,
+
+    /// TCP keepalive probes on upstream connections
+    #[serde(default)]
+    pub tcp_keepalive: TcpKeepaliveConfig,
 }
 
 impl Default for ConnectionPoolConfig {
@@ -243,16 +484,118 @@ impl Default for ConnectionPoolConfig {
             max_idle: default_max_idle_connections(),
             idle_timeout_secs: default_idle_timeout(),
             max_lifetime_secs: None,
+            tcp_keepalive: TcpKeepaliveConfig::default(),
+        }
+    }
+}
+
+/// TCP keepalive configuration for upstream connections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TcpKeepaliveConfig {
+    /// Enable TCP keepalive probes
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Idle time before the first probe
+    #[serde(default = "default_tcp_keepalive_idle")]
+    pub idle_secs: u64,
+
+    /// Interval between probes
+    #[serde(default = "default_tcp_keepalive_interval")]
+    pub interval_secs: u64,
+
+    /// Unanswered probes before the connection is dropped
+    #[serde(default = "default_tcp_keepalive_count")]
+    pub count: usize,
+}
+
+impl Default for TcpKeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            idle_secs: default_tcp_keepalive_idle(),
+            interval_secs: default_tcp_keepalive_interval(),
+            count: default_tcp_keepalive_count(),
         }
     }
 }
 
+// ============================================================================
+// Upstream Request Queue
+// ============================================================================
+
+/// In-flight limit of an upstream, with a bounded queue for requests over it
+///
+/// Queued requests are admitted by the `priority-class` of their route,
+/// highest first, and in arrival order within a class. Requests that find
+/// the queue full, wait longer than `timeout-ms` or are displaced by a
+/// higher class are shed with a 503.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UpstreamQueueConfig {
+    /// Requests sent to the upstream at the same time
+    pub max_in_flight: u32,
+
+    /// Requests waiting for a slot, across all classes (0 sheds at once)
+    #[serde(default = "default_queue_max_depth")]
+    pub max_depth: u32,
+
+    /// Longest time a request waits for a slot
+    #[serde(default = "default_queue_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// `Retry-After` value sent with shed responses, in seconds
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+
+    /// Fair queuing between the clients sharing the upstream
+    #[serde(default)]
+    pub fair: Option<FairQueueConfig>,
+}
+
+/// Fair queuing between the clients of an upstream
+///
+/// When a slot frees up, the waiting request whose client has the fewest
+/// requests in flight relative to its weight goes first, so a busy client
+/// cannot hold every slot while others wait. Priority classes still come
+/// first: fairness orders requests within a class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FairQueueConfig {
+    /// How clients are told apart
+    #[serde(default)]
+    pub client_key: FairQueueKey,
+
+    /// Weight of clients whose tier is not listed in `tiers`
+    #[serde(default = "default_fair_queue_weight")]
+    pub default_weight: u32,
+
+    /// Weight per API key rate tier; a client with weight 4 gets four times
+    /// the slots of a client with weight 1
+    #[serde(default)]
+    pub tiers: BTreeMap<String, u32>,
+}
+
+/// Identity fair queuing schedules by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum FairQueueKey {
+    /// ID of the request's API key, or its client IP when it has none
+    #[default]
+    ApiKey,
+    /// Client IP address
+    ClientIp,
+}
+
 // ============================================================================
 // Upstream Timeouts
 // ============================================================================
 
 /// Upstream timeouts
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UpstreamTimeouts {
     /// Connection timeout
     #[serde(default = "default_connect_timeout")]
@@ -282,12 +625,139 @@ impl Default for UpstreamTimeouts {
     }
 }
 
+// ============================================================================
+// DNS Resolution
+// ============================================================================
+
+/// Resolution of upstream hostnames
+///
+/// Applies to every upstream target given as `host:port`. Answers are cached
+/// for their record TTL, clamped to `min-ttl-secs..=max-ttl-secs`; failed
+/// lookups are cached for `negative-ttl-secs` so a missing name is not
+/// queried on every request.
+///
+/// KDL format (top level):
+/// ```kdl
+/// dns {
+///     nameservers "10.0.0.2" "10.0.0.3"   // default: system resolver
+///     timeout-ms 2000
+///     attempts 2
+///     min-ttl-secs 1
+///     max-ttl-secs 300
+///     negative-ttl-secs 5                  // 0 disables negative caching
+///     ip-family "prefer-ipv6"              // ipv4-only, ipv6-only, prefer-ipv4
+///     happy-eyeballs true
+///     happy-eyeballs-delay-ms 250
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DnsConfig {
+    /// Nameservers to query instead of the system resolver configuration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nameservers: Vec<IpAddr>,
+
+    /// Timeout of a single query
+    #[serde(default = "default_dns_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Queries sent per lookup before giving up
+    #[serde(default = "default_dns_attempts")]
+    pub attempts: usize,
+
+    /// Shortest time an answer is cached, whatever its TTL
+    #[serde(default = "default_dns_min_ttl")]
+    pub min_ttl_secs: u64,
+
+    /// Longest time an answer is cached, whatever its TTL
+    #[serde(default = "default_dns_max_ttl")]
+    pub max_ttl_secs: u64,
+
+    /// How long a failed lookup is cached (0 disables negative caching)
+    #[serde(default = "default_dns_negative_ttl")]
+    pub negative_ttl_secs: u64,
+
+    /// Address families to use and which to try first
+    #[serde(default)]
+    pub ip_family: IpFamilyPreference,
+
+    /// Race connections to both address families (RFC 8305) and keep the
+    /// address that connected first
+    #[serde(default = "default_true")]
+    pub happy_eyeballs: bool,
+
+    /// Head start of each connection attempt over the next one
+    #[serde(default = "default_happy_eyeballs_delay_ms")]
+    pub happy_eyeballs_delay_ms: u64,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        Self {
+            nameservers: Vec::new(),
+            timeout_ms: default_dns_timeout_ms(),
+            attempts: default_dns_attempts(),
+            min_ttl_secs: default_dns_min_ttl(),
+            max_ttl_secs: default_dns_max_ttl(),
+            negative_ttl_secs: default_dns_negative_ttl(),
+            ip_family: IpFamilyPreference::default(),
+            happy_eyeballs: default_true(),
+            happy_eyeballs_delay_ms: default_happy_eyeballs_delay_ms(),
+        }
+    }
+}
+
+/// Address families used for upstream connections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum IpFamilyPreference {
+    /// Only IPv4 (A records)
+    Ipv4Only,
+    /// Only IPv6 (AAAA records)
+    Ipv6Only,
+    /// Both, IPv4 first
+    PreferIpv4,
+    /// Both, IPv6 first
+    #[default]
+    PreferIpv6,
+}
+
+fn default_dns_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_dns_attempts() -> usize {
+    2
+}
+
+fn default_dns_min_ttl() -> u64 {
+    1
+}
+
+fn default_dns_max_ttl() -> u64 {
+    300
+}
+
+fn default_dns_negative_ttl() -> u64 {
+    5
+}
+
+fn default_happy_eyeballs_delay_ms() -> u64 {
+    250
+}
+
 // ============================================================================
 // Upstream TLS Configuration
 // ============================================================================
 
 /// Upstream TLS configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Certificates, keys and CA bundles are PEM, read from a file or from an
+/// environment variable (`*_env`). Files are re-read when they change on
+/// disk, so rotated secrets apply to new connections without a reload.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UpstreamTlsConfig {
     /// SNI hostname
     pub sni: Option<String>,
@@ -304,6 +774,95 @@ pub struct UpstreamTlsConfig {
 
     /// CA certificates
     pub ca_cert: Option<PathBuf>,
+
+    /// Environment variable holding the client certificate
+    #[serde(default)]
+    pub client_cert_env: Option<String>,
+
+    /// Environment variable holding the client key
+    #[serde(default)]
+    pub client_key_env: Option<String>,
+
+    /// Environment variable holding the CA certificates
+    #[serde(default)]
+    pub ca_cert_env: Option<String>,
+
+    /// ALPN protocols to offer (h2 is preferred when both are listed);
+    /// derived from the upstream's HTTP version range when empty
+    #[serde(default)]
+    pub alpn: Vec<AlpnProtocol>,
+
+    /// SPIFFE IDs accepted in the upstream certificate's URI SANs
+    ///
+    /// An entry without a path (`spiffe://example.org`) accepts any ID in
+    /// that trust domain. Only honored by clients built with
+    /// `zentinel_proxy::tls::build_upstream_tls_config`; validation rejects
+    /// it for proxied upstreams, whose certificates Pingora verifies by
+    /// hostname.
+    #[serde(default)]
+    pub spiffe_ids: Vec<String>,
+}
+
+impl UpstreamTlsConfig {
+    /// Whether a client certificate is configured, from a file or the environment
+    pub fn has_client_cert(&self) -> bool {
+        self.client_cert.is_some() || self.client_cert_env.is_some()
+    }
+
+    /// Whether a client key is configured, from a file or the environment
+    pub fn has_client_key(&self) -> bool {
+        self.client_key.is_some() || self.client_key_env.is_some()
+    }
+}
+
+/// ALPN protocol offered to upstreams
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AlpnProtocol {
+    /// HTTP/2
+    #[serde(rename = "h2")]
+    H2,
+    /// HTTP/1.1
+    #[serde(rename = "http/1.1")]
+    Http11,
+}
+
+impl AlpnProtocol {
+    /// Parse an ALPN protocol ID
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "h2" => Some(Self::H2),
+            "http/1.1" => Some(Self::Http11),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `id` is a well-formed SPIFFE ID (`spiffe://<trust-domain>[/<path>]`)
+pub fn is_valid_spiffe_id(id: &str) -> bool {
+    let Some(rest) = id.strip_prefix("spiffe://") else {
+        return false;
+    };
+    let (trust_domain, path) = match rest.split_once('/') {
+        Some((domain, path)) => (domain, Some(path)),
+        None => (rest, None),
+    };
+    let domain_ok = !trust_domain.is_empty()
+        && trust_domain
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'));
+    let path_ok = path.is_none_or(|p| {
+        !p.is_empty()
+            && p.split('/').all(|segment| {
+                !segment.is_empty()
+                    && segment != "."
+                    && segment != ".."
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            })
+    });
+    domain_ok && path_ok
 }
 
 // ============================================================================
@@ -325,6 +884,15 @@ pub struct UpstreamPeer {
 // Default Value Functions
 // ============================================================================
 
+/// Require at least one target unless membership comes from service discovery
+fn validate_upstream_targets(config: &UpstreamConfig) -> Result<(), validator::ValidationError> {
+    if config.targets.is_empty() && config.discovery.is_none() {
+        return Err(validator::ValidationError::new("targets")
+            .with_message("At least one target or a discovery source is required".into()));
+    }
+    Ok(())
+}
+
 fn default_lb_algorithm() -> LoadBalancingAlgorithm {
     LoadBalancingAlgorithm::RoundRobin
 }
@@ -353,6 +921,18 @@ fn default_max_connections_per_target() -> usize {
     100
 }
 
+pub(crate) fn default_queue_max_depth() -> u32 {
+    100
+}
+
+pub(crate) fn default_queue_timeout_ms() -> u64 {
+    5000
+}
+
+pub(crate) fn default_fair_queue_weight() -> u32 {
+    1
+}
+
 fn default_max_idle_connections() -> usize {
     20
 }
@@ -361,6 +941,22 @@ fn default_idle_timeout() -> u64 {
     60
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_tcp_keepalive_idle() -> u64 {
+    60
+}
+
+fn default_tcp_keepalive_interval() -> u64 {
+    10
+}
+
+fn default_tcp_keepalive_count() -> usize {
+    3
+}
+
 pub(crate) fn default_connect_timeout() -> u64 {
     10
 }
@@ -61,6 +61,8 @@ pub async fn validate_agents(config: &Config) -> ValidationResult {
                 AgentTransport::Http { url, .. } => {
                     validate_http_url(&mut result, agent_id, url);
                 }
+                // Registered at startup by the embedding application
+                AgentTransport::InProcess => {}
             }
         }
     }
@@ -105,6 +107,7 @@ pub async fn validate_agents(config: &Config) -> ValidationResult {
                     )));
                 }
             }
+            AgentTransport::InProcess => {}
         }
     }
 
@@ -237,6 +240,7 @@ mod tests {
             events: vec![AgentEvent::RequestHeaders],
             pool: None,
             timeout_ms: 1000,
+            event_timeouts: Default::default(),
             failure_mode: crate::FailureMode::default(),
             circuit_breaker: None,
             max_request_body_bytes: None,
@@ -246,6 +250,10 @@ mod tests {
             chunk_timeout_ms: 5000,
             config: None,
             max_concurrent_calls: 100,
+            max_message_size: None,
+            process: None,
+            slow_start: None,
+            state_quota: Default::default(),
         }
     }
 
@@ -3,7 +3,7 @@
 //! Validates TLS certificates including existence, expiry, and validity.
 
 use super::{ErrorCategory, ValidationError, ValidationResult, ValidationWarning};
-use crate::Config;
+use crate::{Config, ListenerConfig};
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
@@ -12,115 +12,126 @@ pub async fn validate_certificates(config: &Config) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     for listener in &config.listeners {
-        if let Some(ref tls) = listener.tls {
-            // If ACME is configured, validate ACME config instead of manual certs
-            if let Some(ref acme_config) = tls.acme {
-                // Validate ACME configuration
-                if acme_config.domains.is_empty() {
-                    result.add_error(ValidationError::new(
-                        ErrorCategory::Certificate,
-                        format!(
-                            "ACME configuration for listener '{}' requires at least one domain",
-                            listener.id
-                        ),
-                    ));
-                }
+        result.merge(validate_listener_certificates(listener));
+    }
 
-                // Check storage directory is writable
-                let storage_path = &acme_config.storage;
-                if storage_path.exists() && !is_dir_writable(storage_path) {
-                    result.add_error(ValidationError::new(
-                        ErrorCategory::Certificate,
-                        format!("ACME storage directory not writable: {:?}", storage_path),
-                    ));
-                }
+    result
+}
 
-                // Check if existing ACME certificates need renewal
-                let primary_domain = acme_config.domains.first();
-                if let Some(domain) = primary_domain {
-                    let cert_path = storage_path.join("domains").join(domain).join("cert.pem");
-                    if cert_path.exists() {
-                        match load_and_validate_cert(&cert_path) {
-                            Ok(Some(expiry_warning)) => {
-                                result.add_warning(expiry_warning);
-                            }
-                            Ok(None) => {
-                                // ACME certificate is valid
-                            }
-                            Err(e) => {
-                                // ACME cert invalid, will be renewed
-                                result.add_warning(ValidationWarning::new(format!(
-                                    "ACME certificate validation failed (will be renewed): {}",
-                                    e.message
-                                )));
-                            }
-                        }
-                    }
-                }
+/// Validate the TLS material of one listener
+pub fn validate_listener_certificates(listener: &ListenerConfig) -> ValidationResult {
+    let mut result = ValidationResult::new();
 
-                continue;
-            }
+    let Some(ref tls) = listener.tls else {
+        return result;
+    };
 
-            // Manual certificate validation
-            let cert_file = match &tls.cert_file {
-                Some(path) => path,
-                None => {
-                    result.add_error(ValidationError::new(
-                        ErrorCategory::Certificate,
-                        format!(
-                            "TLS configuration for listener '{}' requires cert-file or acme block",
-                            listener.id
-                        ),
-                    ));
-                    continue;
-                }
-            };
-
-            let key_file = match &tls.key_file {
-                Some(path) => path,
-                None => {
-                    result.add_error(ValidationError::new(
-                        ErrorCategory::Certificate,
-                        format!(
-                            "TLS configuration for listener '{}' requires key-file or acme block",
-                            listener.id
-                        ),
-                    ));
-                    continue;
-                }
-            };
-
-            // Check certificate file exists
-            if !Path::new(cert_file).exists() {
-                result.add_error(ValidationError::new(
-                    ErrorCategory::Certificate,
-                    format!("Certificate not found: {:?}", cert_file),
-                ));
-                continue;
-            }
+    // If ACME is configured, validate ACME config instead of manual certs
+    if let Some(ref acme_config) = tls.acme {
+        // Validate ACME configuration
+        if acme_config.domains.is_empty() {
+            result.add_error(ValidationError::new(
+                ErrorCategory::Certificate,
+                format!(
+                    "ACME configuration for listener '{}' requires at least one domain",
+                    listener.id
+                ),
+            ));
+        }
 
-            // Check key file exists
-            if !Path::new(key_file).exists() {
-                result.add_error(ValidationError::new(
-                    ErrorCategory::Certificate,
-                    format!("Private key not found: {:?}", key_file),
-                ));
-                continue;
-            }
+        // Check storage directory is writable
+        let storage_path = &acme_config.storage;
+        if storage_path.exists() && !is_dir_writable(storage_path) {
+            result.add_error(ValidationError::new(
+                ErrorCategory::Certificate,
+                format!("ACME storage directory not writable: {:?}", storage_path),
+            ));
+        }
 
-            // Try to load and validate the certificate
-            match load_and_validate_cert(cert_file) {
-                Ok(Some(expiry_warning)) => {
-                    result.add_warning(expiry_warning);
-                }
-                Ok(None) => {
-                    // Certificate is valid
-                }
-                Err(e) => {
-                    result.add_error(e);
+        // Check if existing ACME certificates need renewal
+        let primary_domain = acme_config.domains.first();
+        if let Some(domain) = primary_domain {
+            let cert_path = storage_path.join("domains").join(domain).join("cert.pem");
+            if cert_path.exists() {
+                match load_and_validate_cert(&cert_path) {
+                    Ok(Some(expiry_warning)) => {
+                        result.add_warning(expiry_warning);
+                    }
+                    Ok(None) => {
+                        // ACME certificate is valid
+                    }
+                    Err(e) => {
+                        // ACME cert invalid, will be renewed
+                        result.add_warning(ValidationWarning::new(format!(
+                            "ACME certificate validation failed (will be renewed): {}",
+                            e.message
+                        )));
+                    }
                 }
             }
         }
+
+        return result;
+    }
+
+    // Manual certificate validation
+    let cert_file = match &tls.cert_file {
+        Some(path) => path,
+        None => {
+            result.add_error(ValidationError::new(
+                ErrorCategory::Certificate,
+                format!(
+                    "TLS configuration for listener '{}' requires cert-file or acme block",
+                    listener.id
+                ),
+            ));
+            return result;
+        }
+    };
+
+    let key_file = match &tls.key_file {
+        Some(path) => path,
+        None => {
+            result.add_error(ValidationError::new(
+                ErrorCategory::Certificate,
+                format!(
+                    "TLS configuration for listener '{}' requires key-file or acme block",
+                    listener.id
+                ),
+            ));
+            return result;
+        }
+    };
+
+    // Check certificate file exists
+    if !Path::new(cert_file).exists() {
+        result.add_error(ValidationError::new(
+            ErrorCategory::Certificate,
+            format!("Certificate not found: {:?}", cert_file),
+        ));
+        return result;
+    }
+
+    // Check key file exists
+    if !Path::new(key_file).exists() {
+        result.add_error(ValidationError::new(
+            ErrorCategory::Certificate,
+            format!("Private key not found: {:?}", key_file),
+        ));
+        return result;
+    }
+
+    // Try to load and validate the certificate
+    match load_and_validate_cert(cert_file) {
+        Ok(Some(expiry_warning)) => {
+            result.add_warning(expiry_warning);
+        }
+        Ok(None) => {
+            // Certificate is valid
+        }
+        Err(e) => {
+            result.add_error(e);
+        }
     }
 
     result
@@ -212,6 +223,7 @@ mod tests {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: false,
             acme: None,
@@ -230,6 +242,13 @@ mod tests {
             keepalive_timeout_secs: 75,
             max_concurrent_streams: 100,
             keepalive_max_requests: None,
+            proxy_protocol: false,
+            bot_signals: false,
+            quic: Default::default(),
+            slow_client: Default::default(),
+            hardening: Default::default(),
+            forward_proxy: None,
+            stream: None,
         }
     }
 
@@ -4,7 +4,8 @@
 
 use super::{ValidationResult, ValidationWarning};
 use crate::filters::{Filter, HeadersFilter};
-use crate::Config;
+use crate::{Config, HealthCheck};
+use zentinel_common::types::{HealthCheckMethod, HealthCheckType};
 
 /// Lint configuration for best practices
 pub fn lint_config(config: &Config) -> ValidationResult {
@@ -59,6 +60,23 @@ pub fn lint_config(config: &Config) -> ValidationResult {
                 name
             )));
         }
+
+        // HEAD responses carry no body, so a body match can never succeed
+        if let Some(HealthCheck {
+            check_type:
+                HealthCheckType::Http {
+                    method: HealthCheckMethod::Head,
+                    expected_body: Some(_),
+                    ..
+                },
+            ..
+        }) = &upstream.health_check
+        {
+            result.add_warning(ValidationWarning::new(format!(
+                "Upstream '{}' health check uses HEAD with expected-body (body is never checked)",
+                name
+            )));
+        }
     }
 
     // Check listeners for security best practices
@@ -208,11 +226,15 @@ mod tests {
             }],
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: None,
             circuit_breaker: None,
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         }
     }
@@ -229,6 +251,13 @@ mod tests {
             keepalive_timeout_secs: 75,
             max_concurrent_streams: 100,
             keepalive_max_requests: None,
+            proxy_protocol: false,
+            bot_signals: false,
+            quic: Default::default(),
+            slow_client: Default::default(),
+            hardening: Default::default(),
+            forward_proxy: None,
+            stream: None,
         }
     }
 
@@ -246,6 +275,7 @@ mod tests {
                 max_version: None,
                 cipher_suites: vec![],
                 client_auth: false,
+                client_cert_header: None,
                 ocsp_stapling: true,
                 session_resumption: true,
                 acme: None,
@@ -256,6 +286,13 @@ mod tests {
             keepalive_timeout_secs: 75,
             max_concurrent_streams: 100,
             keepalive_max_requests: None,
+            proxy_protocol: false,
+            bot_signals: false,
+            quic: Default::default(),
+            slow_client: Default::default(),
+            hardening: Default::default(),
+            forward_proxy: None,
+            stream: None,
         }
     }
 
@@ -287,6 +324,33 @@ mod tests {
             .any(|w| w.message.contains("no health check")));
     }
 
+    #[test]
+    fn test_lint_head_health_check_with_expected_body() {
+        let mut config = Config::default_for_testing();
+        let mut upstream = test_upstream_config();
+        upstream.health_check = Some(HealthCheck {
+            check_type: HealthCheckType::Http {
+                path: "/health".to_string(),
+                expected_status: 200,
+                host: None,
+                method: HealthCheckMethod::Head,
+                expected_body: Some("OK".to_string()),
+            },
+            interval_secs: 10,
+            timeout_secs: 5,
+            healthy_threshold: 2,
+            unhealthy_threshold: 3,
+        });
+        config.upstreams.insert("test".to_string(), upstream);
+
+        let result = lint_config(&config);
+
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.message.contains("HEAD with expected-body")));
+    }
+
     #[test]
     fn test_lint_http_on_port_80() {
         let mut config = Config::default_for_testing();
@@ -64,11 +64,15 @@ mod tests {
                 }],
                 load_balancing: zentinel_common::types::LoadBalancingAlgorithm::RoundRobin,
                 sticky_session: None,
+                consistent_hash: None,
+                discovery: None,
                 health_check: None,
                 circuit_breaker: None,
                 connection_pool: ConnectionPoolConfig::default(),
                 timeouts: UpstreamTimeouts::default(),
                 tls: None,
+                auth: None,
+                queue: None,
                 http_version: HttpVersionConfig::default(),
             },
         );
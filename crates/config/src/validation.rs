@@ -13,7 +13,10 @@ use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use tracing::{debug, trace, warn};
 
-use crate::{Config, Filter, NamespaceConfig, ServiceConfig, ServiceType, WafMode};
+use crate::{
+    ChaosFaultKind, ChaosTarget, Config, Filter, MatchCondition, NamespaceConfig, ServiceConfig,
+    ServiceType, WafMode,
+};
 use zentinel_common::ids::Scope;
 use zentinel_common::types::{Priority, TlsVersion};
 
@@ -384,6 +387,24 @@ pub fn validate_config_semantics(config: &Config) -> Result<(), validator::Valid
         );
     }
 
+    // Validate maintenance mode settings
+    validate_maintenance(config, &route_ids, &mut errors);
+
+    // Validate readiness criteria
+    validate_readiness(config, &upstream_ids, &agent_ids, &mut errors);
+
+    // Validate chaos faults
+    validate_chaos(config, &route_ids, &agent_ids, &mut errors, &mut warnings);
+
+    // Validate staged rollout settings
+    validate_rollout(config, &mut errors);
+
+    // Validate debug header settings
+    validate_debug_headers(config, &mut errors);
+
+    // Validate notification webhooks
+    validate_notifications(config, &mut errors);
+
     // Validate routes
     trace!("Validating routes");
     validate_routes(config, &route_ids, &upstream_ids, &filter_ids, &mut errors);
@@ -417,6 +438,10 @@ pub fn validate_config_semantics(config: &Config) -> Result<(), validator::Valid
     let ctx = ValidationContext::from_config(config);
     validate_namespaces(config, &ctx, &mut errors);
 
+    // Validate tenants
+    trace!("Validating tenants");
+    validate_tenants(config, &route_ids, &agent_ids, &mut errors);
+
     // Warn about orphaned upstreams
     warn_orphaned_upstreams(config, &upstream_ids);
 
@@ -442,6 +467,281 @@ pub fn validate_config_semantics(config: &Config) -> Result<(), validator::Valid
     build_validation_result(errors)
 }
 
+fn validate_maintenance(config: &Config, route_ids: &HashSet<&str>, errors: &mut Vec<String>) {
+    let maintenance = &config.server.maintenance;
+
+    for route in &maintenance.routes {
+        let namespaced = config
+            .namespaces
+            .iter()
+            .any(|ns| ns.routes.iter().any(|r| &r.id == route));
+        if !route_ids.contains(route.as_str()) && !namespaced {
+            errors.push(format!(
+                "server.maintenance references unknown route '{}'.\n\
+                 Available routes: {}",
+                route,
+                format_available(route_ids)
+            ));
+        }
+    }
+
+    match (&maintenance.bypass_header, &maintenance.bypass_secret_env) {
+        (Some(header), _)
+            if header.is_empty()
+                || !header
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)) =>
+        {
+            errors.push(format!(
+                "server.maintenance bypass-header '{}' is not a valid header name",
+                header
+            ));
+        }
+        (Some(_), None) => errors.push(
+            "server.maintenance bypass-header requires bypass-secret-env (the environment \
+             variable holding the bypass secret)"
+                .to_string(),
+        ),
+        (None, Some(_)) => errors.push(
+            "server.maintenance bypass-secret-env has no effect without bypass-header".to_string(),
+        ),
+        _ => {}
+    }
+}
+
+fn validate_readiness(
+    config: &Config,
+    upstream_ids: &HashSet<&str>,
+    agent_ids: &HashSet<&str>,
+    errors: &mut Vec<String>,
+) {
+    let readiness = &config.server.readiness;
+
+    for upstream in &readiness.required_upstreams {
+        if !upstream_ids.contains(upstream.as_str()) {
+            errors.push(format!(
+                "server.readiness requires unknown upstream '{}'.\n\
+                 Available upstreams: {}",
+                upstream,
+                format_available(upstream_ids)
+            ));
+        }
+    }
+    for agent in &readiness.required_agents {
+        if !agent_ids.contains(agent.as_str()) {
+            errors.push(format!(
+                "server.readiness requires unknown agent '{}'.\n\
+                 Available agents: {}",
+                agent,
+                format_available(agent_ids)
+            ));
+        }
+    }
+
+    // Such a proxy could never become ready
+    if readiness.min_healthy_upstreams > upstream_ids.len() {
+        errors.push(format!(
+            "server.readiness min-healthy-upstreams is {} but only {} upstreams are configured",
+            readiness.min_healthy_upstreams,
+            upstream_ids.len()
+        ));
+    }
+    if readiness.min_healthy_agents > agent_ids.len() {
+        errors.push(format!(
+            "server.readiness min-healthy-agents is {} but only {} agents are configured",
+            readiness.min_healthy_agents,
+            agent_ids.len()
+        ));
+    }
+}
+
+fn validate_notifications(config: &Config, errors: &mut Vec<String>) {
+    let Some(notifications) = config.notifications.as_ref() else {
+        return;
+    };
+
+    let mut seen = HashSet::new();
+    for webhook in &notifications.webhooks {
+        if !seen.insert(webhook.id.as_str()) {
+            errors.push(format!(
+                "Duplicate notification webhook ID '{}'",
+                webhook.id
+            ));
+        }
+        if !(webhook.url.starts_with("http://") || webhook.url.starts_with("https://")) {
+            errors.push(format!(
+                "Notification webhook '{}' url '{}' must start with http:// or https://",
+                webhook.id, webhook.url
+            ));
+        }
+        if webhook.timeout_ms == 0 {
+            errors.push(format!(
+                "Notification webhook '{}' timeout-ms must be greater than 0",
+                webhook.id
+            ));
+        }
+    }
+
+    let block_rate = &notifications.block_rate;
+    if !(block_rate.threshold > 0.0 && block_rate.threshold <= 1.0) {
+        errors.push(format!(
+            "notifications block-rate threshold must be in (0, 1], got {}",
+            block_rate.threshold
+        ));
+    }
+    if block_rate.window_secs == 0 {
+        errors.push("notifications block-rate window-secs must be greater than 0".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for alert in &notifications.alerts {
+        if !seen.insert(alert.name.as_str()) {
+            errors.push(format!("Duplicate alert rule name '{}'", alert.name));
+        }
+        if alert.window_secs == 0 {
+            errors.push(format!(
+                "Alert rule '{}' window-secs must be greater than 0",
+                alert.name
+            ));
+        }
+        if alert.metric.is_ratio() && !(0.0..=1.0).contains(&alert.threshold) {
+            errors.push(format!(
+                "Alert rule '{}' threshold for {} must be between 0% and 100%",
+                alert.name,
+                alert.metric.as_str()
+            ));
+        }
+    }
+}
+
+fn validate_debug_headers(config: &Config, errors: &mut Vec<String>) {
+    let Some(debug_headers) = &config.server.debug_headers else {
+        return;
+    };
+    if debug_headers.secret.len() < 16 {
+        errors.push("server.debug-headers secret must be at least 16 bytes long".to_string());
+    }
+    if !is_http_token(&debug_headers.token_header) {
+        errors.push(format!(
+            "server.debug-headers token-header '{}' is not a valid header name",
+            debug_headers.token_header
+        ));
+    }
+}
+
+fn validate_rollout(config: &Config, errors: &mut Vec<String>) {
+    let Some(rollout) = &config.server.rollout else {
+        return;
+    };
+    if !(rollout.percent > 0.0 && rollout.percent < 100.0) {
+        errors.push(format!(
+            "server.rollout percent must be in (0, 100), got {}",
+            rollout.percent
+        ));
+    }
+    if rollout.bake_window_secs == 0 {
+        errors.push("server.rollout bake-window-secs must be greater than 0".to_string());
+    }
+    if rollout.max_error_rate_increase < 0.0 {
+        errors.push(format!(
+            "server.rollout max-error-rate-increase must not be negative, got {}",
+            rollout.max_error_rate_increase
+        ));
+    }
+    if rollout.max_latency_increase_percent < 0.0 {
+        errors.push(format!(
+            "server.rollout max-latency-increase-percent must not be negative, got {}",
+            rollout.max_latency_increase_percent
+        ));
+    }
+}
+
+fn validate_chaos(
+    config: &Config,
+    route_ids: &HashSet<&str>,
+    agent_ids: &HashSet<&str>,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    let chaos = &config.server.chaos;
+    if chaos.enabled {
+        warnings.push(
+            "server.chaos is enabled: faults can be armed through the chaos admin endpoint. \
+             Do not enable it in production."
+                .to_string(),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    for fault in &chaos.faults {
+        if !seen.insert(fault.id.as_str()) {
+            errors.push(format!("server.chaos has duplicate fault '{}'", fault.id));
+        }
+
+        match &fault.target {
+            ChaosTarget::Route(route) => {
+                let namespaced = config
+                    .namespaces
+                    .iter()
+                    .any(|ns| ns.routes.iter().any(|r| &r.id == route));
+                if !route_ids.contains(route.as_str()) && !namespaced {
+                    errors.push(format!(
+                        "server.chaos fault '{}' references unknown route '{}'.\n\
+                         Available routes: {}",
+                        fault.id,
+                        route,
+                        format_available(route_ids)
+                    ));
+                }
+                if fault.kind == ChaosFaultKind::AgentTimeout {
+                    errors.push(format!(
+                        "server.chaos fault '{}': kind 'agent-timeout' only applies to agents",
+                        fault.id
+                    ));
+                }
+            }
+            ChaosTarget::Agent(agent) => {
+                if !agent_ids.contains(agent.as_str()) {
+                    errors.push(format!(
+                        "server.chaos fault '{}' references unknown agent '{}'.\n\
+                         Available agents: {}",
+                        fault.id,
+                        agent,
+                        format_available(agent_ids)
+                    ));
+                }
+                if fault.kind == ChaosFaultKind::Reset {
+                    errors.push(format!(
+                        "server.chaos fault '{}': kind 'reset' only applies to routes",
+                        fault.id
+                    ));
+                }
+            }
+        }
+
+        if let ChaosFaultKind::Error { status } = fault.kind {
+            if !(400..=599).contains(&status) {
+                errors.push(format!(
+                    "server.chaos fault '{}' status must be between 400 and 599, got {}",
+                    fault.id, status
+                ));
+            }
+        }
+        if !(fault.probability > 0.0 && fault.probability <= 1.0) {
+            errors.push(format!(
+                "server.chaos fault '{}' probability must be in (0.0, 1.0], got {}",
+                fault.id, fault.probability
+            ));
+        }
+        if fault.duration_secs == 0 {
+            errors.push(format!(
+                "server.chaos fault '{}' duration-secs must be greater than 0",
+                fault.id
+            ));
+        }
+    }
+}
+
 fn validate_routes(
     config: &Config,
     _route_ids: &HashSet<&str>,
@@ -529,6 +829,222 @@ fn validate_routes(
         }
     }
 
+    // Validate inference cost budgets
+    for route in &config.routes {
+        let Some(cost) = route
+            .inference
+            .as_ref()
+            .and_then(|i| i.cost_attribution.as_ref())
+        else {
+            continue;
+        };
+        let Some(budget) = cost.budget.as_ref() else {
+            continue;
+        };
+        if !cost.enabled {
+            warn!(
+                route_id = %route.id,
+                "Cost budget is ignored because cost attribution is disabled"
+            );
+        }
+        if budget.monthly_limit.is_nan() || budget.monthly_limit <= 0.0 {
+            errors.push(format!(
+                "Route '{}' cost budget monthly-limit must be greater than 0.",
+                route.id
+            ));
+        }
+        if let Some(threshold) = budget
+            .alert_thresholds
+            .iter()
+            .find(|t| t.is_nan() || **t <= 0.0 || **t > 1.0)
+        {
+            errors.push(format!(
+                "Route '{}' cost budget alert threshold {} must be between 0 and 1.",
+                route.id, threshold
+            ));
+        }
+        if let crate::CostBudgetAction::Downgrade { ref model } = budget.action {
+            if model.is_empty() {
+                errors.push(format!(
+                    "Route '{}' cost budget downgrade-model is empty.",
+                    route.id
+                ));
+            }
+        }
+    }
+
+    // Validate guardrail embedding endpoints
+    for route in &config.routes {
+        let Some(guardrails) = route.inference.as_ref().and_then(|i| i.guardrails.as_ref()) else {
+            continue;
+        };
+        let Some(embedding) = guardrails.embedding.as_ref() else {
+            continue;
+        };
+        match url::Url::parse(&embedding.endpoint) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => errors.push(format!(
+                "Route '{}' guardrail embedding has invalid endpoint '{}'.\n\
+                 Expected an http:// or https:// URL",
+                route.id, embedding.endpoint
+            )),
+        }
+        if embedding.model.is_empty() {
+            errors.push(format!(
+                "Route '{}' guardrail embedding model is empty.",
+                route.id
+            ));
+        }
+        if !guardrails
+            .prompt_injection
+            .as_ref()
+            .is_some_and(|p| p.enabled)
+        {
+            warn!(
+                route_id = %route.id,
+                "Guardrail embedding is unused because prompt injection detection is disabled"
+            );
+        }
+    }
+
+    // Validate inference key pools
+    for route in &config.routes {
+        let Some(inference) = route.inference.as_ref() else {
+            continue;
+        };
+        let Some(pool) = inference.key_pool.as_ref() else {
+            continue;
+        };
+        if pool.members.is_empty() {
+            errors.push(format!(
+                "Route '{}' key-pool has no members.\n\
+                 Add at least one 'member' or remove the key-pool block.",
+                route.id
+            ));
+        }
+        if pool.max_attempts == 0 {
+            errors.push(format!(
+                "Route '{}' key-pool max-attempts must be at least 1.",
+                route.id
+            ));
+        }
+        if let Some(status) = pool
+            .failover_status
+            .iter()
+            .find(|s| !(400..=599).contains(*s))
+        {
+            errors.push(format!(
+                "Route '{}' key-pool failover-status {} is not an error status (400-599).",
+                route.id, status
+            ));
+        }
+        let mut member_ids = HashSet::new();
+        for member in &pool.members {
+            if !member_ids.insert(member.id.as_str()) {
+                errors.push(format!(
+                    "Route '{}' key-pool defines member '{}' more than once.",
+                    route.id, member.id
+                ));
+            }
+            if !upstream_ids.contains(member.upstream.as_str()) {
+                errors.push(format!(
+                    "Route '{}' key-pool member '{}' references upstream '{}' which doesn't exist.\n\
+                     Available upstreams: {}",
+                    route.id,
+                    member.id,
+                    member.upstream,
+                    format_available(upstream_ids)
+                ));
+            }
+            if member.weight == 0 {
+                errors.push(format!(
+                    "Route '{}' key-pool member '{}' has weight 0; use a weight of at least 1.",
+                    route.id, member.id
+                ));
+            }
+            if member.key.is_empty() {
+                errors.push(format!(
+                    "Route '{}' key-pool member '{}' has an empty key.",
+                    route.id, member.id
+                ));
+            }
+            if member.tokens_per_minute.is_some() && inference.rate_limit.is_none() {
+                warn!(
+                    route_id = %route.id,
+                    member = %member.id,
+                    "Key pool tokens-per-minute needs the route's inference rate-limit to count tokens"
+                );
+            }
+        }
+    }
+
+    // Validate match expressions only read what is known at routing time
+    for route in &config.routes {
+        for condition in &route.matches {
+            if let MatchCondition::Expr(expr) = condition {
+                if expr.needs_country() || expr.needs_metadata() {
+                    errors.push(format!(
+                        "Route '{}' match expression '{}' reads client.country, metadata or tags.\n\
+                         These are only known after routing (geo filters and agents run on the matched route).",
+                        route.id, expr
+                    ));
+                }
+            }
+        }
+    }
+
+    // Validate streaming-passthrough routes never need a whole response body
+    for route in config
+        .routes
+        .iter()
+        .filter(|r| r.policies.streaming_passthrough)
+    {
+        if route.policies.buffer_responses {
+            errors.push(format!(
+                "Route '{}' enables both streaming-passthrough and response buffering.",
+                route.id
+            ));
+        }
+        for filter_id in &route.filters {
+            let Some(filter_config) = config.filters.get(filter_id) else {
+                continue;
+            };
+            match filter_config.filter {
+                crate::Filter::JsonTransform(ref json)
+                    if json.phase != crate::FilterPhase::Request =>
+                {
+                    errors.push(format!(
+                        "Route '{}' enables streaming-passthrough but filter '{}' (json-transform) \
+                         buffers response bodies.\n\
+                         Remove the filter from the route or set its phase to \"request\".",
+                        route.id, filter_id
+                    ));
+                }
+                crate::Filter::Icap(ref icap) if icap.respmod.is_some() => {
+                    errors.push(format!(
+                        "Route '{}' enables streaming-passthrough but filter '{}' (icap) \
+                         scans response bodies.\n\
+                         Remove the filter from the route or drop its respmod service.",
+                        route.id, filter_id
+                    ));
+                }
+                _ => {}
+            }
+        }
+        let pii_detection = route
+            .inference
+            .as_ref()
+            .and_then(|i| i.guardrails.as_ref())
+            .and_then(|g| g.pii_detection.as_ref())
+            .is_some_and(|p| p.enabled);
+        if pii_detection {
+            warn!(
+                route_id = %route.id,
+                "Route enables streaming-passthrough; PII detection on responses is skipped"
+            );
+        }
+    }
+
     // Validate routes have at least one match condition
     for route in &config.routes {
         if route.matches.is_empty() && route.priority != Priority::LOW {
@@ -576,39 +1092,162 @@ fn validate_listeners(config: &Config, route_ids: &HashSet<&str>, errors: &mut V
 
     for listener in &config.listeners {
         trace!(listener_id = %listener.id, "Validating listener");
-        if let Some(ref default_route) = listener.default_route {
-            if !route_ids.contains(default_route.as_str()) {
-                warn!(
-                    listener_id = %listener.id,
-                    default_route = %default_route,
-                    "Listener references non-existent default route"
-                );
+        if listener.protocol == crate::ListenerProtocol::Http3 {
+            match listener.tls {
+                None => errors.push(format!(
+                    "Listener '{}' uses protocol 'h3' but has no 'tls' block.\n\
+                     HTTP/3 runs over QUIC, which always uses TLS 1.3.",
+                    listener.id
+                )),
+                Some(ref tls) if tls.max_version == Some(TlsVersion::Tls12) => {
+                    errors.push(format!(
+                        "Listener '{}' uses protocol 'h3' but limits TLS to 1.2.\n\
+                         HTTP/3 runs over QUIC, which requires TLS 1.3.",
+                        listener.id
+                    ))
+                }
+                Some(_) => {}
+            }
+            if listener.proxy_protocol {
                 errors.push(format!(
-                    "Listener '{}' references default-route '{}' which doesn't exist.\n\
-                     Available routes: {}",
-                    listener.id,
-                    default_route,
-                    format_available(route_ids)
+                    "Listener '{}' uses protocol 'h3' with 'proxy-protocol'.\n\
+                     PROXY protocol is only supported on TCP listeners.",
+                    listener.id
                 ));
             }
         }
 
-        if let Some(ref namespace) = listener.namespace {
-            if !config.namespaces.iter().any(|ns| &ns.id == namespace) {
+        if listener.bot_signals
+            && matches!(
+                listener.protocol,
+                crate::ListenerProtocol::Http3
+                    | crate::ListenerProtocol::ForwardProxy
+                    | crate::ListenerProtocol::Tcp
+            )
+        {
+            warn!(
+                listener_id = %listener.id,
+                protocol = ?listener.protocol,
+                "bot-signals is only collected on HTTP/1 and HTTP/2 listeners; ignored"
+            );
+        }
+
+        if listener.protocol == crate::ListenerProtocol::ForwardProxy {
+            if listener.tls.is_some() || listener.proxy_protocol {
+                errors.push(format!(
+                    "Listener '{}' uses protocol 'forward-proxy' with 'tls' or 'proxy-protocol'.\n\
+                     Forward-proxy listeners accept plain CONNECT and SOCKS5 connections only.",
+                    listener.id
+                ));
+            }
+            let forward_proxy = listener.forward_proxy.clone().unwrap_or_default();
+            if forward_proxy.allow.is_empty() {
                 warn!(
                     listener_id = %listener.id,
-                    namespace = %namespace,
-                    "Listener references non-existent namespace route set"
+                    "Forward-proxy listener has no allow rules and refuses every destination"
                 );
-                let available: Vec<&str> =
-                    config.namespaces.iter().map(|ns| ns.id.as_str()).collect();
-                errors.push(format!(
-                    "Listener '{}' references namespace '{}' which doesn't exist.\n\
-                     Define a `namespace \"{}\" {{ routes {{ ... }} }}` block, \
-                     or remove the reference to serve the global routes.\n\
-                     Available namespaces: {}",
-                    listener.id,
-                    namespace,
+            }
+            for agent_id in &forward_proxy.agents {
+                if !config.agents.iter().any(|a| &a.id == agent_id) {
+                    errors.push(format!(
+                        "Listener '{}' forward-proxy references agent '{}' which doesn't exist.",
+                        listener.id, agent_id
+                    ));
+                }
+            }
+        } else if listener.forward_proxy.is_some() {
+            warn!(
+                listener_id = %listener.id,
+                "Listener has a forward-proxy block but protocol is not 'forward-proxy'; ignored"
+            );
+        }
+
+        if listener.protocol == crate::ListenerProtocol::Tcp {
+            if listener.tls.is_some() || listener.proxy_protocol {
+                errors.push(format!(
+                    "Listener '{}' uses protocol 'tcp' with 'tls' or 'proxy-protocol'.\n\
+                     TCP listeners pass TLS through to the upstream and accept no PROXY header.",
+                    listener.id
+                ));
+            }
+            match listener.stream {
+                Some(ref stream) if stream.upstream.is_some() || !stream.routes.is_empty() => {
+                    let upstreams = stream
+                        .upstream
+                        .iter()
+                        .map(|u| (None, u))
+                        .chain(stream.routes.iter().map(|r| (Some(&r.id), &r.upstream)));
+                    for (route_id, upstream) in upstreams {
+                        if !config.upstreams.contains_key(upstream) {
+                            errors.push(format!(
+                                "Listener '{}' stream{} references upstream '{}' which doesn't exist.",
+                                listener.id,
+                                route_id.map(|id| format!(" route '{}'", id)).unwrap_or_default(),
+                                upstream
+                            ));
+                        }
+                    }
+                    for agent_id in &stream.agents {
+                        if !config.agents.iter().any(|a| &a.id == agent_id) {
+                            errors.push(format!(
+                                "Listener '{}' stream references agent '{}' which doesn't exist.",
+                                listener.id, agent_id
+                            ));
+                        }
+                    }
+                    if stream.redis.is_some() && stream.upstream.is_none() {
+                        warn!(
+                            listener_id = %listener.id,
+                            "Stream redis settings apply to the default upstream, which is not set; ignored"
+                        );
+                    }
+                }
+                _ => errors.push(format!(
+                    "Listener '{}' uses protocol 'tcp' but has no stream upstream.\n\
+                     Add stream {{ upstream \"<id>\" }} or SNI routes.",
+                    listener.id
+                )),
+            }
+        } else if listener.stream.is_some() {
+            warn!(
+                listener_id = %listener.id,
+                "Listener has a stream block but protocol is not 'tcp'; ignored"
+            );
+        }
+
+        if let Some(ref default_route) = listener.default_route {
+            if !route_ids.contains(default_route.as_str()) {
+                warn!(
+                    listener_id = %listener.id,
+                    default_route = %default_route,
+                    "Listener references non-existent default route"
+                );
+                errors.push(format!(
+                    "Listener '{}' references default-route '{}' which doesn't exist.\n\
+                     Available routes: {}",
+                    listener.id,
+                    default_route,
+                    format_available(route_ids)
+                ));
+            }
+        }
+
+        if let Some(ref namespace) = listener.namespace {
+            if !config.namespaces.iter().any(|ns| &ns.id == namespace) {
+                warn!(
+                    listener_id = %listener.id,
+                    namespace = %namespace,
+                    "Listener references non-existent namespace route set"
+                );
+                let available: Vec<&str> =
+                    config.namespaces.iter().map(|ns| ns.id.as_str()).collect();
+                errors.push(format!(
+                    "Listener '{}' references namespace '{}' which doesn't exist.\n\
+                     Define a `namespace \"{}\" {{ routes {{ ... }} }}` block, \
+                     or remove the reference to serve the global routes.\n\
+                     Available namespaces: {}",
+                    listener.id,
+                    namespace,
                     namespace,
                     if available.is_empty() {
                         "(none)".to_string()
@@ -618,9 +1257,73 @@ fn validate_listeners(config: &Config, route_ids: &HashSet<&str>, errors: &mut V
                 ));
             }
         }
+
+        let sni_namespaces = listener
+            .tls
+            .iter()
+            .flat_map(|tls| tls.additional_certs.iter())
+            .filter_map(|sni| sni.namespace.as_ref());
+        for namespace in sni_namespaces {
+            if !config.namespaces.iter().any(|ns| &ns.id == namespace) {
+                warn!(
+                    listener_id = %listener.id,
+                    namespace = %namespace,
+                    "SNI certificate references non-existent namespace route set"
+                );
+                errors.push(format!(
+                    "Listener '{}' has an SNI certificate referencing namespace '{}' which doesn't exist.\n\
+                     Define a `namespace \"{}\" {{ routes {{ ... }} }}` block, \
+                     or remove the reference to serve the listener's routes.",
+                    listener.id, namespace, namespace
+                ));
+            }
+        }
+
+        validate_hardening(listener, errors);
+    }
+}
+
+fn validate_hardening(listener: &crate::ListenerConfig, errors: &mut Vec<String>) {
+    let hardening = &listener.hardening;
+    for (check, status) in hardening.status.all() {
+        if !(400..=599).contains(&status) {
+            errors.push(format!(
+                "Listener '{}' hardening status {} must be between 400 and 599, got {}",
+                listener.id, check, status
+            ));
+        }
+    }
+    let limits = [
+        ("max-header-count", hardening.max_header_count),
+        ("max-header-bytes", hardening.max_header_bytes),
+        ("max-uri-length", hardening.max_uri_length),
+    ];
+    for (name, limit) in limits {
+        if limit == Some(0) {
+            errors.push(format!(
+                "Listener '{}' hardening {} must be greater than 0",
+                listener.id, name
+            ));
+        }
+    }
+    for method in &hardening.allowed_methods {
+        if !is_http_token(method) {
+            errors.push(format!(
+                "Listener '{}' hardening allows invalid method '{}'",
+                listener.id, method
+            ));
+        }
     }
 }
 
+/// Whether a method or header name is a valid HTTP token
+fn is_http_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
 /// Validate ACME domains across all configurations (global uniqueness)
 fn validate_acme_domains(config: &Config, errors: &mut Vec<String>) {
     let mut domain_source: HashMap<String, String> = HashMap::new();
@@ -672,6 +1375,27 @@ fn validate_filters(config: &Config, agent_ids: &HashSet<&str>, errors: &mut Vec
 
     for (filter_id, filter_config) in &config.filters {
         trace!(filter_id = %filter_id, "Validating filter");
+
+        // A condition must be decidable before its filter runs
+        if let Some(ref condition) = filter_config.enable_if {
+            let unsupported = match &filter_config.filter {
+                Filter::RateLimit(_) => Some("on rate-limit filters (limits are merged per route)"),
+                Filter::Geo(_) if condition.needs_country() || condition.needs_metadata() => {
+                    Some("reading client.country, metadata or tags on geo filters")
+                }
+                Filter::Agent(_) if condition.needs_metadata() => {
+                    Some("reading metadata or tags on agent filters")
+                }
+                _ => None,
+            };
+            if let Some(what) = unsupported {
+                errors.push(format!(
+                    "Filter '{}' has enable-if condition '{}', which is not supported {}.",
+                    filter_id, condition, what
+                ));
+            }
+        }
+
         if let Filter::Agent(agent_filter) = &filter_config.filter {
             if !agent_ids.contains(agent_filter.agent.as_str()) {
                 warn!(
@@ -688,6 +1412,85 @@ fn validate_filters(config: &Config, agent_ids: &HashSet<&str>, errors: &mut Vec
                 ));
             }
         }
+
+        if let Filter::Wasm(wasm) = &filter_config.filter {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            } else if !std::path::Path::new(&wasm.module).is_file() {
+                errors.push(format!(
+                    "Filter '{}' WASM module '{}' does not exist.",
+                    filter_id, wasm.module
+                ));
+            }
+        }
+
+        if matches!(
+            filter_config.filter,
+            Filter::AdaptiveProtection(_)
+                | Filter::Quota(_)
+                | Filter::PromptTemplate(_)
+                | Filter::Multipart(_)
+                | Filter::Icap(_)
+        ) {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            }
+        }
+
+        if matches!(filter_config.filter, Filter::JsonTransform(_)) {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            }
+        }
+
+        if let Filter::SchemaValidate(schema) = &filter_config.filter {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            } else if !std::path::Path::new(&schema.schema).is_file() {
+                errors.push(format!(
+                    "Filter '{}' schema document '{}' does not exist.",
+                    filter_id, schema.schema
+                ));
+            }
+        }
+
+        if let Filter::ManagedRules(rules) = &filter_config.filter {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            } else if !std::path::Path::new(&rules.rules).is_file() {
+                errors.push(format!(
+                    "Filter '{}' rule file '{}' does not exist.",
+                    filter_id, rules.rules
+                ));
+            }
+        }
+
+        if let Filter::IpAccess(access) = &filter_config.filter {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            }
+            for source in &access.sources {
+                if !crate::IpAccessFilter::is_url_source(source)
+                    && !std::path::Path::new(source).is_file()
+                {
+                    errors.push(format!(
+                        "Filter '{}' IP list '{}' does not exist.",
+                        filter_id, source
+                    ));
+                }
+            }
+        }
+
+        if let Filter::ApiKey(api_key) = &filter_config.filter {
+            if let Err(e) = filter_config.validate(&[]) {
+                errors.push(format!("Filter '{}': {}", filter_id, e));
+            } else if !std::path::Path::new(&api_key.key_file).is_file() {
+                errors.push(format!(
+                    "Filter '{}' API key file '{}' does not exist.",
+                    filter_id, api_key.key_file
+                ));
+            }
+        }
     }
 }
 
@@ -697,6 +1500,18 @@ fn validate_upstreams(config: &Config, errors: &mut Vec<String>) {
         "Validating upstream configurations"
     );
 
+    if let Some(ref dns) = config.dns {
+        if dns.min_ttl_secs > dns.max_ttl_secs {
+            errors.push(format!(
+                "DNS min-ttl-secs ({}) must not exceed max-ttl-secs ({})",
+                dns.min_ttl_secs, dns.max_ttl_secs
+            ));
+        }
+        if dns.attempts == 0 {
+            errors.push("DNS attempts must be at least 1".to_string());
+        }
+    }
+
     for (upstream_id, upstream) in &config.upstreams {
         trace!(
             upstream_id = %upstream_id,
@@ -704,15 +1519,50 @@ fn validate_upstreams(config: &Config, errors: &mut Vec<String>) {
             "Validating upstream"
         );
 
-        if upstream.targets.is_empty() {
+        if upstream.targets.is_empty() && upstream.discovery.is_none() {
             warn!(upstream_id = %upstream_id, "Upstream has no targets");
             errors.push(format!(
                 "Upstream '{}' has no targets defined.\n\
-                 Each upstream must have at least one target.",
+                 Each upstream must have at least one target or a discovery source.",
                 upstream_id
             ));
         }
 
+        if let Some(ref tls) = upstream.tls {
+            validate_upstream_tls(upstream_id, tls, errors);
+        }
+
+        if let Some(ref auth) = upstream.auth {
+            validate_upstream_auth(upstream_id, auth, errors);
+        }
+
+        if let Some(ref queue) = upstream.queue {
+            if queue.max_in_flight == 0 {
+                errors.push(format!(
+                    "Upstream '{}' queue max-in-flight must be at least 1",
+                    upstream_id
+                ));
+            }
+            if queue.timeout_ms == 0 {
+                errors.push(format!(
+                    "Upstream '{}' queue timeout-ms must be positive",
+                    upstream_id
+                ));
+            }
+            if let Some(ref fair) = queue.fair {
+                let zero_weights = std::iter::once(("default-weight", fair.default_weight))
+                    .chain(fair.tiers.iter().map(|(tier, w)| (tier.as_str(), *w)))
+                    .filter(|(_, w)| *w == 0)
+                    .map(|(name, _)| name);
+                for name in zero_weights {
+                    errors.push(format!(
+                        "Upstream '{}' fair queue weight of '{}' must be at least 1",
+                        upstream_id, name
+                    ));
+                }
+            }
+        }
+
         for (i, target) in upstream.targets.iter().enumerate() {
             if target.address.parse::<SocketAddr>().is_err() {
                 let parts: Vec<&str> = target.address.rsplitn(2, ':').collect();
@@ -736,6 +1586,128 @@ fn validate_upstreams(config: &Config, errors: &mut Vec<String>) {
     }
 }
 
+fn validate_upstream_tls(
+    upstream_id: &str,
+    tls: &crate::UpstreamTlsConfig,
+    errors: &mut Vec<String>,
+) {
+    let sources = [
+        (
+            "client-cert",
+            tls.client_cert.is_some(),
+            tls.client_cert_env.is_some(),
+        ),
+        (
+            "client-key",
+            tls.client_key.is_some(),
+            tls.client_key_env.is_some(),
+        ),
+        ("ca-cert", tls.ca_cert.is_some(), tls.ca_cert_env.is_some()),
+    ];
+    for (name, file, env) in sources {
+        if file && env {
+            errors.push(format!(
+                "Upstream '{}' tls sets both {} and {}-env. Use one source.",
+                upstream_id, name, name
+            ));
+        }
+    }
+
+    if tls.has_client_cert() != tls.has_client_key() {
+        errors.push(format!(
+            "Upstream '{}' tls needs both a client certificate and a client key for mTLS.",
+            upstream_id
+        ));
+    }
+
+    for id in &tls.spiffe_ids {
+        if !crate::is_valid_spiffe_id(id) {
+            errors.push(format!(
+                "Upstream '{}' tls has invalid SPIFFE ID '{}'.\n\
+                 Expected format: spiffe://<trust-domain>[/<path>]",
+                upstream_id, id
+            ));
+        }
+    }
+
+    // Pingora verifies upstream certificates internally and accepts no custom
+    // verifier, so proxied connections could only check the hostname
+    if !tls.spiffe_ids.is_empty() {
+        errors.push(format!(
+            "Upstream '{}' tls sets spiffe-id, which proxied connections cannot enforce yet.\n\
+             Upstream certificates are verified by hostname; remove spiffe-id and give \
+             the SVID a DNS SAN matching sni.",
+            upstream_id
+        ));
+    }
+
+    let unique: HashSet<_> = tls.alpn.iter().collect();
+    if unique.len() != tls.alpn.len() {
+        errors.push(format!(
+            "Upstream '{}' tls lists an ALPN protocol more than once.",
+            upstream_id
+        ));
+    }
+}
+
+fn validate_upstream_auth(
+    upstream_id: &str,
+    auth: &crate::UpstreamAuthConfig,
+    errors: &mut Vec<String>,
+) {
+    use crate::UpstreamAuthConfig;
+
+    let required: Vec<(&str, &str)> = match auth {
+        UpstreamAuthConfig::Bearer { token } => vec![("token", token.as_str())],
+        UpstreamAuthConfig::Basic { username, password } => {
+            if username.contains(':') {
+                errors.push(format!(
+                    "Upstream '{}' basic auth username must not contain ':'.",
+                    upstream_id
+                ));
+            }
+            vec![
+                ("username", username.as_str()),
+                ("password", password.as_str()),
+            ]
+        }
+        UpstreamAuthConfig::OAuth2 {
+            token_url,
+            client_id,
+            client_secret,
+            ..
+        } => {
+            match url::Url::parse(token_url) {
+                Ok(url) if url.scheme() == "https" => {}
+                Ok(url) if url.scheme() == "http" => {
+                    warn!(
+                        upstream_id = %upstream_id,
+                        "OAuth2 token-url uses plain HTTP; client credentials are sent unencrypted"
+                    );
+                }
+                _ => errors.push(format!(
+                    "Upstream '{}' oauth2 auth has invalid token-url '{}'.\n\
+                     Expected an http:// or https:// URL",
+                    upstream_id, token_url
+                )),
+            }
+            vec![
+                ("client-id", client_id.as_str()),
+                ("client-secret", client_secret.as_str()),
+            ]
+        }
+    };
+
+    for (name, value) in required {
+        if value.is_empty() {
+            errors.push(format!(
+                "Upstream '{}' auth has an empty {}.",
+                upstream_id, name
+            ));
+        }
+    }
+}
+
 fn validate_agents(config: &Config, errors: &mut Vec<String>) {
     trace!(agent_count = config.agents.len(), "Validating agents");
 
@@ -756,6 +1728,53 @@ fn validate_agents(config: &Config, errors: &mut Vec<String>) {
             ));
         }
 
+        if agent.slow_start.as_ref().is_some_and(|s| s.window_ms == 0) {
+            errors.push(format!(
+                "Agent '{}' has slow-start window-ms 0. Use a positive window, or remove the slow-start block.",
+                agent.id
+            ));
+        }
+
+        if let Some(pool) = &agent.pool {
+            for (name, buckets) in [
+                (
+                    "request-duration-buckets-us",
+                    &pool.request_duration_buckets_us,
+                ),
+                ("serialization-buckets-us", &pool.serialization_buckets_us),
+            ] {
+                let Some(buckets) = buckets else { continue };
+                if buckets.is_empty() || buckets[0] == 0 || buckets.windows(2).any(|w| w[0] >= w[1])
+                {
+                    errors.push(format!(
+                        "Agent '{}' has invalid pool {}. Bucket boundaries must be positive and strictly ascending.",
+                        agent.id, name
+                    ));
+                }
+            }
+
+            if let Some(shm) = &pool.shared_memory {
+                if !matches!(agent.transport, crate::AgentTransport::UnixSocket { .. }) {
+                    errors.push(format!(
+                        "Agent '{}' enables pool shared-memory, which only works with the unix-socket transport.",
+                        agent.id
+                    ));
+                }
+                if shm.slot_count == 0 || shm.slot_size == 0 {
+                    errors.push(format!(
+                        "Agent '{}' has pool shared-memory slot-count or slot-size 0. Both must be greater than 0.",
+                        agent.id
+                    ));
+                }
+                if shm.min_chunk_size > shm.slot_size {
+                    errors.push(format!(
+                        "Agent '{}' has pool shared-memory min-chunk-size larger than slot-size, so no chunk fits a slot.",
+                        agent.id
+                    ));
+                }
+            }
+        }
+
         if agent.max_request_body_bytes == Some(0) {
             warn!(agent_id = %agent.id, "Agent has zero request body limit");
             errors.push(format!(
@@ -772,6 +1791,32 @@ fn validate_agents(config: &Config, errors: &mut Vec<String>) {
             ));
         }
     }
+
+    // A per-event timeout longer than a route's agent budget can never be
+    // waited out on that route
+    for route in &config.routes {
+        let Some(budget_ms) = route.policies.agent_budget_ms else {
+            continue;
+        };
+        for filter_id in &route.filters {
+            let Some(Filter::Agent(agent_filter)) =
+                config.filters.get(filter_id).map(|f| &f.filter)
+            else {
+                continue;
+            };
+            let Some(agent) = config.agents.iter().find(|a| a.id == agent_filter.agent) else {
+                continue;
+            };
+            for (name, timeout_ms) in agent.event_timeouts.iter() {
+                if timeout_ms > budget_ms {
+                    errors.push(format!(
+                        "Agent '{}' event-timeouts {} ({}ms) exceeds the agent budget of route '{}' ({}ms).",
+                        agent.id, name, timeout_ms, route.id, budget_ms
+                    ));
+                }
+            }
+        }
+    }
 }
 
 fn validate_duplicates(config: &Config, errors: &mut Vec<String>) {
@@ -838,6 +1883,13 @@ fn warn_orphaned_upstreams(config: &Config, upstream_ids: &HashSet<&str>) {
         .routes
         .iter()
         .filter_map(|r| r.upstream.as_ref())
+        .chain(
+            config
+                .routes
+                .iter()
+                .filter_map(|r| r.inference.as_ref()?.key_pool.as_ref())
+                .flat_map(|pool| pool.members.iter().map(|m| &m.upstream)),
+        )
         .map(|s| s.as_str())
         .collect();
 
@@ -1043,74 +2095,186 @@ fn format_available_owned(ids: &HashSet<String>) -> String {
 }
 
 // ============================================================================
-// Implementation Status Validation
+// Tenant Validation
 // ============================================================================
 
-/// Validate that configured features are actually wired into the runtime.
-///
-/// Features that are parsed successfully but silently ignored at runtime are
-/// dangerous — especially security features. This function produces:
+/// Validate tenant definitions.
 ///
-/// - **Hard errors** for security-critical features (WAF mode, TLS hardening)
-///   that would give a false sense of protection if ignored.
-/// - **Warnings** for convenience features (filters, policies, listener settings)
-///   that won't cause security issues but may surprise operators.
-fn validate_implementation_status(
+/// Every referenced resource must exist and belong to at most one tenant,
+/// quotas must be coherent, and a tenant's routes may not run agents owned
+/// by another tenant.
+fn validate_tenants(
     config: &Config,
+    route_ids: &HashSet<&str>,
+    agent_ids: &HashSet<&str>,
     errors: &mut Vec<String>,
-    warnings: &mut Vec<String>,
 ) {
-    // ========================================================================
-    // Hard errors: Security-critical features that MUST NOT be silently ignored
-    // ========================================================================
+    trace!(
+        tenant_count = config.tenants.len(),
+        "Validating tenant configurations"
+    );
 
-    // WAF mode: Detection/Prevention configured but WAF engine not consumed
-    if let Some(ref waf) = config.waf {
-        if waf.mode != WafMode::Off {
-            let mode_str = match waf.mode {
-                WafMode::Detection => "detection",
-                WafMode::Prevention => "prevention",
-                WafMode::Off => unreachable!(),
-            };
+    let namespace_ids: HashSet<_> = config.namespaces.iter().map(|n| n.id.as_str()).collect();
+    let listener_ids: HashSet<_> = config.listeners.iter().map(|l| l.id.as_str()).collect();
+
+    let mut seen_ids = HashSet::new();
+    let mut owners: HashMap<(&str, &str), &str> = HashMap::new();
+    let mut route_owners: HashMap<&str, &str> = HashMap::new();
+    let mut agent_owners: HashMap<&str, &str> = HashMap::new();
+
+    for tenant in &config.tenants {
+        if tenant.id.is_empty() || tenant.id.contains(':') {
             errors.push(format!(
-                "WAF mode is set to '{}' but the WAF engine is not yet wired into the proxy runtime. \
-                 Requests are NOT being inspected. \
-                 Set waf mode to 'off' or remove the waf block until WAF support is implemented.",
-                mode_str
+                "Tenant ID '{}' is invalid. Tenant IDs must be non-empty and must not contain ':'.",
+                tenant.id
+            ));
+        }
+        if !seen_ids.insert(tenant.id.as_str()) {
+            errors.push(format!(
+                "Duplicate tenant ID '{}'. Each tenant must have a unique identifier.",
+                tenant.id
             ));
         }
-    }
 
-    // TLS hardening: settings are validated and ready in build_server_config(),
-    // but Pingora's TlsSettings doesn't yet accept a custom ServerConfig.
-    // Warn (not error) so operators know these settings are staged but not active.
-    for listener in &config.listeners {
-        if let Some(ref tls) = listener.tls {
-            if !tls.cipher_suites.is_empty() {
-                warnings.push(format!(
-                    "Listener '{}' has TLS cipher_suites configured ({:?}) but Pingora's TLS integration \
-                     does not yet support custom cipher suites. The default rustls cipher suite selection is used.",
-                    listener.id, tls.cipher_suites
-                ));
+        let references = [
+            ("namespace", &tenant.namespaces, &namespace_ids),
+            ("listener", &tenant.listeners, &listener_ids),
+            ("route", &tenant.routes, route_ids),
+            ("agent", &tenant.agents, agent_ids),
+        ];
+        for (kind, ids, known) in references {
+            for id in ids {
+                if !known.contains(id.as_str()) {
+                    errors.push(format!(
+                        "Tenant '{}' references {} '{}' which doesn't exist.\n\
+                         Available {}s: {}",
+                        tenant.id,
+                        kind,
+                        id,
+                        kind,
+                        format_available(known)
+                    ));
+                }
+                if let Some(other) = owners.insert((kind, id.as_str()), tenant.id.as_str()) {
+                    if other != tenant.id {
+                        errors.push(format!(
+                            "Tenants '{}' and '{}' both claim {} '{}'. \
+                             A resource can belong to only one tenant.",
+                            other, tenant.id, kind, id
+                        ));
+                    }
+                }
             }
+        }
 
-            if tls.min_version != TlsVersion::Tls12 {
-                warnings.push(format!(
-                    "Listener '{}' has TLS min_version set to '{}' but Pingora's TLS integration \
-                     does not yet apply this setting. Default TLS 1.2+ is used.",
-                    listener.id, tls.min_version
-                ));
+        // Requests are attributed by route ID, so a route ID may not be
+        // served for two tenants (e.g. the same ID in two namespaces)
+        for route_id in tenant.route_ids(config) {
+            if let Some(other) = route_owners.insert(route_id, tenant.id.as_str()) {
+                if other != tenant.id {
+                    errors.push(format!(
+                        "Route ID '{}' is used by tenants '{}' and '{}'. \
+                         Route IDs must be unique across tenants.",
+                        route_id, other, tenant.id
+                    ));
+                }
             }
+        }
 
-            if let Some(ref max_ver) = tls.max_version {
-                warnings.push(format!(
-                    "Listener '{}' has TLS max_version set to '{}' but Pingora's TLS integration \
-                     does not yet apply this setting.",
-                    listener.id, max_ver
-                ));
+        for agent_id in &tenant.agents {
+            agent_owners.insert(agent_id.as_str(), tenant.id.as_str());
+        }
+
+        let quotas = &tenant.quotas;
+        if quotas.requests_per_second == Some(0) {
+            errors.push(format!(
+                "Tenant '{}' requests-per-second must be greater than 0.",
+                tenant.id
+            ));
+        }
+        if quotas.max_concurrent_requests == Some(0) {
+            errors.push(format!(
+                "Tenant '{}' max-concurrent-requests must be greater than 0.",
+                tenant.id
+            ));
+        }
+    }
+
+    // A tenant's routes may only use its own agents or shared (unowned) ones
+    for tenant in &config.tenants {
+        let routes = config
+            .routes
+            .iter()
+            .filter(|r| tenant.routes.contains(&r.id))
+            .map(|r| (r, None));
+        let ns_routes = config
+            .namespaces
+            .iter()
+            .filter(|ns| tenant.owns_namespace(&ns.id))
+            .flat_map(|ns| ns.routes.iter().map(move |r| (r, Some(ns))));
+
+        for (route, ns) in routes.chain(ns_routes) {
+            for filter_id in &route.filters {
+                let filter = ns
+                    .and_then(|ns| ns.filters.get(filter_id))
+                    .or_else(|| config.filters.get(filter_id));
+                let Some(Filter::Agent(agent_filter)) = filter.map(|f| &f.filter) else {
+                    continue;
+                };
+                if ns.is_some_and(|ns| ns.agents.iter().any(|a| a.id == agent_filter.agent)) {
+                    continue;
+                }
+                if let Some(owner) = agent_owners.get(agent_filter.agent.as_str()) {
+                    if *owner != tenant.id {
+                        errors.push(format!(
+                            "Route '{}' of tenant '{}' uses agent '{}' owned by tenant '{}'.",
+                            route.id, tenant.id, agent_filter.agent, owner
+                        ));
+                    }
+                }
             }
         }
     }
+}
+
+// ============================================================================
+// Implementation Status Validation
+// ============================================================================
+
+/// Validate that configured features are actually wired into the runtime.
+///
+/// Features that are parsed successfully but silently ignored at runtime are
+/// dangerous — especially security features. This function produces:
+///
+/// - **Hard errors** for security-critical features (WAF mode, TLS hardening)
+///   that would give a false sense of protection if ignored.
+/// - **Warnings** for convenience features (filters, policies, listener settings)
+///   that won't cause security issues but may surprise operators.
+fn validate_implementation_status(
+    config: &Config,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    // ========================================================================
+    // Hard errors: Security-critical features that MUST NOT be silently ignored
+    // ========================================================================
+
+    // WAF mode: Detection/Prevention configured but WAF engine not consumed
+    if let Some(ref waf) = config.waf {
+        if waf.mode != WafMode::Off {
+            let mode_str = match waf.mode {
+                WafMode::Detection => "detection",
+                WafMode::Prevention => "prevention",
+                WafMode::Off => unreachable!(),
+            };
+            errors.push(format!(
+                "WAF mode is set to '{}' but the WAF engine is not yet wired into the proxy runtime. \
+                 Requests are NOT being inspected. \
+                 Set waf mode to 'off' or remove the waf block until WAF support is implemented.",
+                mode_str
+            ));
+        }
+    }
 
     // ========================================================================
     // Warnings: Convenience features that are configured but not yet wired
@@ -1181,28 +2345,440 @@ fn build_validation_result(errors: Vec<String>) -> Result<(), validator::Validat
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filters::AgentFilter;
     use crate::namespace::{ExportConfig, NamespaceConfig, ServiceConfig};
     use crate::{
-        ConnectionPoolConfig, HttpVersionConfig, MatchCondition, RouteConfig, RoutePolicies,
-        UpstreamConfig, UpstreamTarget, UpstreamTimeouts,
+        AgentEvent, ConnectionPoolConfig, FilterConfig, HttpVersionConfig, MatchCondition,
+        RouteConfig, RoutePolicies, UpstreamConfig, UpstreamTarget, UpstreamTimeouts,
     };
     use zentinel_common::types::LoadBalancingAlgorithm;
 
     #[test]
-    fn listener_referencing_unknown_namespace_fails_validation() {
+    fn listener_referencing_unknown_namespace_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "admin" {
+                    address "127.0.0.1:9000"
+                    namespace "ghost"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        assert!(
+            config.validate().is_err(),
+            "listener referencing an undefined namespace must fail validation"
+        );
+    }
+
+    #[test]
+    fn listener_with_defined_namespace_passes_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+                listener "admin" {
+                    address "127.0.0.1:9000"
+                    namespace "ops"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+            namespace "ops" {
+                routes {
+                    route "metrics" {
+                        matches { path "/metrics" }
+                        service-type "builtin"
+                        builtin-handler "metrics"
+                    }
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        assert!(
+            config.validate().is_ok(),
+            "listener referencing a defined namespace must pass validation"
+        );
+    }
+
+    #[test]
+    fn sni_certificate_referencing_unknown_namespace_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "edge" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                    tls {
+                        cert-file "/tmp/default.crt"
+                        key-file "/tmp/default.key"
+                        sni {
+                            hostnames "*.tenant-a.example.com"
+                            cert-file "/tmp/tenant-a.crt"
+                            key-file "/tmp/tenant-a.key"
+                            namespace "ghost"
+                        }
+                    }
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        assert!(
+            config.validate().is_err(),
+            "SNI certificate referencing an undefined namespace must fail validation"
+        );
+    }
+
+    #[test]
+    fn http3_listener_without_tls_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "quic" {
+                    address "0.0.0.0:8443"
+                    protocol "h3"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        assert!(
+            config.validate().is_err(),
+            "HTTP/3 listener without TLS must fail validation"
+        );
+    }
+
+    fn test_upstream(id: &str) -> UpstreamConfig {
+        UpstreamConfig {
+            id: id.to_string(),
+            targets: vec![UpstreamTarget {
+                address: "127.0.0.1:8080".to_string(),
+                weight: 1,
+                max_requests: None,
+                metadata: HashMap::new(),
+            }],
+            load_balancing: LoadBalancingAlgorithm::RoundRobin,
+            sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
+            health_check: None,
+            circuit_breaker: None,
+            connection_pool: ConnectionPoolConfig::default(),
+            timeouts: UpstreamTimeouts::default(),
+            tls: None,
+            auth: None,
+            queue: None,
+            http_version: HttpVersionConfig::default(),
+        }
+    }
+
+    fn test_route(id: &str, upstream: Option<&str>) -> RouteConfig {
+        RouteConfig {
+            id: id.to_string(),
+            priority: Priority::NORMAL,
+            matches: vec![MatchCondition::PathPrefix("/".to_string())],
+            upstream: upstream.map(String::from),
+            service_type: ServiceType::Web,
+            policies: RoutePolicies::default(),
+            filters: vec![],
+            builtin_handler: None,
+            waf_enabled: false,
+            retry_policy: None,
+            static_files: None,
+            api_schema: None,
+            error_pages: None,
+            websocket: false,
+            websocket_inspection: false,
+            inference: None,
+            shadow: None,
+            fallback: None,
+        }
+    }
+
+    fn config_with_agent_block(agent_block: &str) -> crate::Config {
+        let kdl = format!(
+            r#"
+            schema-version "1.0"
+            system {{ worker-threads 0 }}
+            listeners {{
+                listener "public" {{
+                    address "0.0.0.0:8080"
+                }}
+            }}
+            routes {{
+                route "api" {{
+                    matches {{ path-prefix "/" }}
+                    upstream "backend"
+                }}
+            }}
+            upstreams {{
+                upstream "backend" {{
+                    target "127.0.0.1:3000"
+                }}
+            }}
+            agents {{
+                {agent_block}
+            }}
+        "#
+        );
+        crate::Config::from_kdl(&kdl).expect("config parses")
+    }
+
+    fn validation_errors(config: &crate::Config) -> String {
+        match config.validate() {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn route_expr_reading_metadata_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            routes {
+                route "api" {
+                    matches {
+                        path-prefix "/"
+                        expr "metadata[\"tier\"] == \"gold\""
+                    }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("only known after routing"),
+            "route expression reading metadata must fail validation, got: {errors}"
+        );
+    }
+
+    #[test]
+    fn enable_if_on_rate_limit_filter_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                    filters "limit"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+            filters {
+                filter "limit" {
+                    type "rate-limit"
+                    max-rps 10
+                    enable-if "request.method == \"POST\""
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("not supported on rate-limit filters"),
+            "enable-if on a rate-limit filter must fail validation, got: {errors}"
+        );
+    }
+
+    #[test]
+    fn upstream_tls_with_conflicting_secret_sources_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:8443"
+                    tls {
+                        client-cert "/certs/client.pem"
+                        client-cert-env "CLIENT_CERT"
+                        spiffe-id "spiffe://Example.org/api"
+                    }
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("sets both client-cert and client-cert-env"),
+            "{errors}"
+        );
+        assert!(
+            errors.contains("needs both a client certificate and a client key"),
+            "{errors}"
+        );
+        assert!(errors.contains("invalid SPIFFE ID"), "{errors}");
+        assert!(errors.contains("cannot enforce yet"), "{errors}");
+    }
+
+    #[test]
+    fn upstream_auth_problems_are_reported() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:8443"
+                    auth "oauth2" {
+                        token-url "auth.example.com/token"
+                        client-id "zentinel"
+                        client-secret ""
+                    }
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(errors.contains("invalid token-url"), "{errors}");
+        assert!(errors.contains("empty client-secret"), "{errors}");
+    }
+
+    #[test]
+    fn inference_key_pool_problems_are_reported() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
+                }
+            }
+            routes {
+                route "chat" {
+                    matches { path-prefix "/v1" }
+                    service-type "inference"
+                    upstream "openai"
+                    inference {
+                        key-pool {
+                            failover-status 200 429
+                            member "a" upstream="openai" key="sk-a" weight=0
+                            member "a" upstream="missing" key="sk-b"
+                        }
+                    }
+                }
+            }
+            upstreams {
+                upstream "openai" {
+                    target "127.0.0.1:8443"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(errors.contains("failover-status 200"), "{errors}");
+        assert!(errors.contains("has weight 0"), "{errors}");
+        assert!(errors.contains("member 'a' more than once"), "{errors}");
+        assert!(errors.contains("upstream 'missing'"), "{errors}");
+    }
+
+    #[test]
+    fn streaming_passthrough_rejects_response_json_transform() {
         let kdl = r#"
             schema-version "1.0"
             system { worker-threads 0 }
             listeners {
-                listener "admin" {
-                    address "127.0.0.1:9000"
-                    namespace "ghost"
+                listener "public" {
+                    address "0.0.0.0:8080"
                 }
             }
             routes {
-                route "api" {
-                    matches { path-prefix "/" }
+                route "events" {
+                    matches { path-prefix "/events" }
                     upstream "backend"
+                    filters "strip"
+                    policies {
+                        streaming-passthrough #true
+                    }
                 }
             }
             upstreams {
@@ -1210,16 +2786,25 @@ mod tests {
                     target "127.0.0.1:3000"
                 }
             }
+            filters {
+                filter "strip" {
+                    type "json-transform"
+                    phase "response"
+                    remove "$.internal"
+                }
+            }
         "#;
         let config = crate::Config::from_kdl(kdl).expect("config parses");
+        assert!(config.routes[0].policies.streaming_passthrough);
+        let errors = validation_errors(&config);
         assert!(
-            config.validate().is_err(),
-            "listener referencing an undefined namespace must fail validation"
+            errors.contains("buffers response bodies"),
+            "a response json-transform on a passthrough route must fail validation, got: {errors}"
         );
     }
 
     #[test]
-    fn listener_with_defined_namespace_passes_validation() {
+    fn streaming_passthrough_rejects_icap_response_scan() {
         let kdl = r#"
             schema-version "1.0"
             system { worker-threads 0 }
@@ -1227,15 +2812,52 @@ mod tests {
                 listener "public" {
                     address "0.0.0.0:8080"
                 }
-                listener "admin" {
-                    address "127.0.0.1:9000"
-                    namespace "ops"
+            }
+            routes {
+                route "downloads" {
+                    matches { path-prefix "/downloads" }
+                    upstream "backend"
+                    filters "scan"
+                    policies {
+                        streaming-passthrough #true
+                    }
+                }
+            }
+            upstreams {
+                upstream "backend" {
+                    target "127.0.0.1:3000"
+                }
+            }
+            filters {
+                filter "scan" {
+                    type "icap"
+                    respmod "icap://clamav.internal:1344/avscan"
+                }
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("filter 'scan' (icap) scans response bodies"),
+            "an icap response scan on a passthrough route must fail validation, got: {errors}"
+        );
+    }
+
+    #[test]
+    fn wasm_filter_with_missing_module_fails_validation() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
+                    address "0.0.0.0:8080"
                 }
             }
             routes {
                 route "api" {
                     matches { path-prefix "/" }
                     upstream "backend"
+                    filters "tag"
                 }
             }
             upstreams {
@@ -1243,100 +2865,149 @@ mod tests {
                     target "127.0.0.1:3000"
                 }
             }
-            namespace "ops" {
-                routes {
-                    route "metrics" {
-                        matches { path "/metrics" }
-                        service-type "builtin"
-                        builtin-handler "metrics"
-                    }
+            filters {
+                filter "tag" {
+                    type "wasm"
+                    module "/nonexistent/tag.wasm"
                 }
             }
         "#;
         let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
         assert!(
-            config.validate().is_ok(),
-            "listener referencing a defined namespace must pass validation"
+            errors.contains("WASM module '/nonexistent/tag.wasm' does not exist"),
+            "a missing WASM module must fail validation, got: {errors}"
         );
     }
 
-    fn test_upstream(id: &str) -> UpstreamConfig {
-        UpstreamConfig {
-            id: id.to_string(),
-            targets: vec![UpstreamTarget {
-                address: "127.0.0.1:8080".to_string(),
-                weight: 1,
-                max_requests: None,
-                metadata: HashMap::new(),
-            }],
-            load_balancing: LoadBalancingAlgorithm::RoundRobin,
-            sticky_session: None,
-            health_check: None,
-            circuit_breaker: None,
-            connection_pool: ConnectionPoolConfig::default(),
-            timeouts: UpstreamTimeouts::default(),
-            tls: None,
-            http_version: HttpVersionConfig::default(),
-        }
+    #[test]
+    fn maintenance_with_unknown_route_or_missing_secret_fails_validation() {
+        let mut config = crate::Config::default_for_testing();
+        config.server.maintenance.routes = vec!["missing".to_string()];
+        config.server.maintenance.bypass_header = Some("X-Maintenance-Bypass".to_string());
+
+        let errors = validation_errors(&config);
+        assert!(errors.contains("unknown route 'missing'"), "{errors}");
+        assert!(errors.contains("requires bypass-secret-env"), "{errors}");
     }
 
-    fn test_route(id: &str, upstream: Option<&str>) -> RouteConfig {
-        RouteConfig {
+    #[test]
+    fn readiness_with_unknown_ids_or_unreachable_minimum_fails_validation() {
+        let mut config = crate::Config::default_for_testing();
+        config.server.readiness.required_upstreams = vec!["ghost".to_string()];
+        config.server.readiness.required_agents = vec!["waf".to_string()];
+        config.server.readiness.min_healthy_agents = 1;
+
+        let errors = validation_errors(&config);
+        assert!(errors.contains("unknown upstream 'ghost'"), "{errors}");
+        assert!(errors.contains("unknown agent 'waf'"), "{errors}");
+        assert!(errors.contains("min-healthy-agents is 1"), "{errors}");
+    }
+
+    #[test]
+    fn chaos_faults_with_unknown_targets_or_mismatched_kinds_fail_validation() {
+        let mut config = crate::Config::default_for_testing();
+        let route = config.routes[0].id.clone();
+        let fault = |id: &str, target: ChaosTarget, kind: ChaosFaultKind| crate::ChaosFault {
             id: id.to_string(),
-            priority: Priority::NORMAL,
-            matches: vec![MatchCondition::PathPrefix("/".to_string())],
-            upstream: upstream.map(String::from),
-            service_type: ServiceType::Web,
-            policies: RoutePolicies::default(),
-            filters: vec![],
-            builtin_handler: None,
-            waf_enabled: false,
-            retry_policy: None,
-            static_files: None,
-            api_schema: None,
-            error_pages: None,
-            websocket: false,
-            websocket_inspection: false,
-            inference: None,
-            shadow: None,
-            fallback: None,
-        }
+            target,
+            kind,
+            probability: 1.0,
+            duration_secs: 60,
+        };
+        config.server.chaos.faults = vec![
+            fault(
+                "ok",
+                ChaosTarget::Route(route.clone()),
+                ChaosFaultKind::Latency { ms: 100 },
+            ),
+            fault(
+                "ghost",
+                ChaosTarget::Agent("ghost".to_string()),
+                ChaosFaultKind::Reset,
+            ),
+            fault(
+                "teapot",
+                ChaosTarget::Route(route),
+                ChaosFaultKind::Error { status: 200 },
+            ),
+        ];
+        config.server.chaos.faults[2].probability = 1.5;
+
+        let errors = validation_errors(&config);
+        assert!(errors.contains("unknown agent 'ghost'"), "{errors}");
+        assert!(
+            errors.contains("'reset' only applies to routes"),
+            "{errors}"
+        );
+        assert!(errors.contains("between 400 and 599, got 200"), "{errors}");
+        assert!(errors.contains("probability must be in"), "{errors}");
+        assert!(!errors.contains("fault 'ok'"), "{errors}");
     }
 
-    fn config_with_agent_block(agent_block: &str) -> crate::Config {
-        let kdl = format!(
-            r#"
+    #[test]
+    fn rollout_thresholds_out_of_range_fail_validation() {
+        let mut config = crate::Config::default_for_testing();
+        config.server.rollout = Some(crate::RolloutConfig::default());
+        assert!(!validation_errors(&config).contains("server.rollout"));
+
+        config.server.rollout = Some(crate::RolloutConfig {
+            percent: 100.0,
+            bake_window_secs: 0,
+            max_latency_increase_percent: -5.0,
+            ..Default::default()
+        });
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("percent must be in (0, 100), got 100"),
+            "{errors}"
+        );
+        assert!(
+            errors.contains("bake-window-secs must be greater than 0"),
+            "{errors}"
+        );
+        assert!(
+            errors.contains("max-latency-increase-percent must not be negative"),
+            "{errors}"
+        );
+        assert!(!errors.contains("max-error-rate-increase"), "{errors}");
+    }
+
+    #[test]
+    fn tenants_claiming_the_same_route_fail_validation() {
+        let kdl = r#"
             schema-version "1.0"
-            system {{ worker-threads 0 }}
-            listeners {{
-                listener "public" {{
+            system { worker-threads 0 }
+            listeners {
+                listener "public" {
                     address "0.0.0.0:8080"
-                }}
-            }}
-            routes {{
-                route "api" {{
-                    matches {{ path-prefix "/" }}
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/" }
                     upstream "backend"
-                }}
-            }}
-            upstreams {{
-                upstream "backend" {{
+                }
+            }
+            upstreams {
+                upstream "backend" {
                     target "127.0.0.1:3000"
-                }}
-            }}
-            agents {{
-                {agent_block}
-            }}
-        "#
+                }
+            }
+            tenant "acme" {
+                routes "api"
+            }
+            tenant "globex" {
+                routes "api" "ghost"
+            }
+        "#;
+        let config = crate::Config::from_kdl(kdl).expect("config parses");
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("Tenants 'acme' and 'globex' both claim route 'api'"),
+            "a route owned by two tenants must fail validation, got: {errors}"
         );
-        crate::Config::from_kdl(&kdl).expect("config parses")
-    }
-
-    fn validation_errors(config: &crate::Config) -> String {
-        match config.validate() {
-            Ok(()) => String::new(),
-            Err(e) => e.to_string(),
-        }
+        assert!(errors.contains("Tenant 'globex' references route 'ghost'"));
     }
 
     #[test]
@@ -1410,6 +3081,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn agent_slow_start_zero_window_fails_validation() {
+        let config = config_with_agent_block(
+            r#"
+            agent "waf" type="waf" {
+                unix-socket path="/tmp/waf.sock"
+                slow-start {
+                    window-ms 0
+                    warmup #true
+                }
+            }
+            "#,
+        );
+        let slow_start = config.agents[0].slow_start.as_ref().unwrap();
+        assert!(slow_start.warmup);
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("Agent 'waf' has slow-start window-ms 0"),
+            "expected zero window error, got: {errors}"
+        );
+    }
+
+    #[test]
+    fn agent_pool_buckets_are_parsed_and_validated() {
+        let config = config_with_agent_block(
+            r#"
+            agent "waf" type="waf" {
+                unix-socket path="/tmp/waf.sock"
+                pool {
+                    connections-per-agent 2
+                    load-balance-strategy "least_connections"
+                    request-duration-buckets-us 250 1000 1000 5000
+                    serialization-buckets-us 10 100
+                }
+            }
+            "#,
+        );
+        let pool = config.agents[0].pool.as_ref().unwrap();
+        assert_eq!(pool.connections_per_agent, 2);
+        assert_eq!(
+            pool.load_balance_strategy,
+            crate::LoadBalanceStrategy::LeastConnections
+        );
+        assert_eq!(pool.serialization_buckets_us, Some(vec![10, 100]));
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("Agent 'waf' has invalid pool request-duration-buckets-us"),
+            "expected bucket order error, got: {errors}"
+        );
+        assert!(!errors.contains("serialization-buckets-us"));
+    }
+
+    #[test]
+    fn agent_pool_shared_memory_is_parsed_and_validated() {
+        let config = config_with_agent_block(
+            r#"
+            agent "dlp" type="custom" {
+                unix-socket path="/tmp/dlp.sock"
+                pool {
+                    shared-memory {
+                        dir "/run/zentinel/shm"
+                        slot-count 8
+                        mode 0o660
+                    }
+                }
+            }
+            agent "waf" type="waf" {
+                grpc address="http://localhost:50051"
+                pool {
+                    shared-memory {
+                        slot-size 1024
+                    }
+                }
+            }
+            "#,
+        );
+        let shm = config.agents[0]
+            .pool
+            .as_ref()
+            .and_then(|p| p.shared_memory.as_ref())
+            .unwrap();
+        assert_eq!(
+            shm.dir.as_deref(),
+            Some(std::path::Path::new("/run/zentinel/shm"))
+        );
+        assert_eq!(shm.slot_count, 8);
+        assert_eq!(shm.slot_size, 1024 * 1024);
+        assert_eq!(shm.mode, 0o660);
+
+        let errors = validation_errors(&config);
+        assert!(!errors.contains("Agent 'dlp' enables"), "{errors}");
+        assert!(!errors.contains("Agent 'dlp' has"), "{errors}");
+        assert!(
+            errors.contains("Agent 'waf' enables pool shared-memory"),
+            "expected transport error, got: {errors}"
+        );
+        assert!(
+            errors.contains(
+                "Agent 'waf' has pool shared-memory min-chunk-size larger than slot-size"
+            ),
+            "expected chunk size error, got: {errors}"
+        );
+    }
+
     #[test]
     fn agent_zero_body_limits_fail_validation() {
         let config = config_with_agent_block(
@@ -1432,6 +3207,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn agent_event_timeout_over_route_budget_fails_validation() {
+        let mut config = config_with_agent_block(
+            r#"
+            agent "waf" type="waf" {
+                unix-socket path="/tmp/waf.sock"
+                timeout-ms 20
+                event-timeouts {
+                    headers 5
+                    body 100
+                }
+            }
+            "#,
+        );
+        assert_eq!(
+            config.agents[0].timeout_ms_for(AgentEvent::RequestHeaders),
+            5
+        );
+        assert_eq!(
+            config.agents[0].timeout_ms_for(AgentEvent::ResponseBody),
+            100
+        );
+        assert_eq!(config.agents[0].timeout_ms_for(AgentEvent::Guardrail), 20);
+        assert!(validation_errors(&config).is_empty());
+
+        config.filters.insert(
+            "waf".to_string(),
+            FilterConfig::new("waf", Filter::Agent(AgentFilter::new("waf"))),
+        );
+        config.routes[0].filters.push("waf".to_string());
+        config.routes[0].policies.agent_budget_ms = Some(50);
+        let errors = validation_errors(&config);
+        assert!(
+            errors.contains("Agent 'waf' event-timeouts body (100ms) exceeds the agent budget of route 'api' (50ms)"),
+            "expected budget error, got: {errors}"
+        );
+        assert!(!errors.contains("event-timeouts headers"));
+    }
+
     #[test]
     fn valid_agent_passes_validation() {
         let config = config_with_agent_block(
@@ -1686,6 +3500,7 @@ mod tests {
             max_version: None,
             cipher_suites: vec!["TLS_AES_256_GCM_SHA384".to_string()],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: true,
             session_resumption: true,
             acme: None,
@@ -1718,6 +3533,7 @@ mod tests {
             max_version: Some(TlsVersion::Tls12),
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: true,
             session_resumption: true,
             acme: None,
@@ -1911,7 +3727,8 @@ mod tests {
         };
         use crate::observability::{LoggingConfig, MetricsConfig, ObservabilityConfig};
         use crate::routes::{
-            CacheBackend, CacheStorageConfig, FailureMode, HeaderModifications, RouteCacheConfig,
+            CacheBackend, CacheCoalesceConfig, CacheStorageConfig, FailureMode,
+            HeaderModifications, RouteCacheConfig,
         };
         use crate::server::{ListenerConfig, ListenerProtocol, ServerConfig, TlsConfig};
         use crate::waf::{BodyInspectionPolicy, WafConfig, WafEngine, WafRuleset};
@@ -1930,6 +3747,17 @@ mod tests {
             trace_id_format: Default::default(),
             auto_reload: false,
             route_cache_size: 1000,
+            client_ip: Default::default(),
+            outbound_proxy: Default::default(),
+            maintenance: Default::default(),
+            concurrency: None,
+            request_id: Default::default(),
+            problem_details: None,
+            readiness: Default::default(),
+            chaos: Default::default(),
+            capture: None,
+            rollout: None,
+            debug_headers: None,
         };
 
         // --- ListenerConfig ---
@@ -1944,6 +3772,13 @@ mod tests {
             keepalive_timeout_secs: 75,
             max_concurrent_streams: 100,
             keepalive_max_requests: None,
+            proxy_protocol: false,
+            bot_signals: false,
+            quic: Default::default(),
+            slow_client: Default::default(),
+            hardening: Default::default(),
+            forward_proxy: None,
+            stream: None,
         };
 
         // --- TlsConfig ---
@@ -1956,6 +3791,7 @@ mod tests {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: true,
             session_resumption: true,
             acme: None,
@@ -1988,6 +3824,16 @@ mod tests {
                 buffer_requests: false,
                 buffer_responses: false,
                 cache: None,
+                agent_budget_ms: None,
+                decision_merge: Default::default(),
+                block_page: None,
+                challenge: Default::default(),
+                concurrency: None,
+                slow_client: None,
+                streaming_passthrough: false,
+                body_framing: None,
+                server_timing: None,
+                priority_class: Default::default(),
             },
             filters: vec![],
             builtin_handler: None,
@@ -2014,11 +3860,15 @@ mod tests {
             }],
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: None,
             circuit_breaker: None,
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         };
 
@@ -2036,6 +3886,7 @@ mod tests {
             min_size: 1024,
             content_types: vec!["text/html".to_string()],
             level: 6,
+            levels: HashMap::new(),
         };
 
         let _cors = CorsFilter {
@@ -2091,6 +3942,7 @@ mod tests {
                 address: "0.0.0.0:9090".to_string(),
                 path: "/metrics".to_string(),
                 high_cardinality: false,
+                tag_labels: Vec::new(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
@@ -2100,6 +3952,7 @@ mod tests {
                 access_log: None,
                 error_log: None,
                 audit_log: None,
+                journal: None,
             },
             tracing: None,
         };
@@ -2118,6 +3971,11 @@ mod tests {
             ignore_query_params: vec![],
             exclude_extensions: vec![],
             exclude_paths: vec![],
+            coalesce: CacheCoalesceConfig {
+                enabled: true,
+                max_waiters: 100,
+                timeout_secs: None,
+            },
         };
 
         // --- CacheStorageConfig ---
@@ -2160,16 +4018,17 @@ mod tests {
 
         // Enable all features that currently produce warnings:
 
-        // TLS cipher_suites (unwired — Pingora doesn't expose custom cipher config)
+        // TLS hardening (wired — applied by the HTTPS acceptor, no warnings)
         config.listeners[0].tls = Some(crate::TlsConfig {
             cert_file: Some("/tmp/cert.pem".into()),
             key_file: Some("/tmp/key.pem".into()),
             additional_certs: vec![],
             ca_file: None,
-            min_version: TlsVersion::Tls13, // non-default → produces warning
-            max_version: Some(TlsVersion::Tls13), // set → produces warning
-            cipher_suites: vec!["TLS_AES_256_GCM_SHA384".to_string()], // set → produces warning
+            min_version: TlsVersion::Tls13,
+            max_version: Some(TlsVersion::Tls13),
+            cipher_suites: vec!["TLS_AES_256_GCM_SHA384".to_string()],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: true,
             session_resumption: true,
             acme: None,
@@ -2195,14 +4054,8 @@ mod tests {
         // Exact expected warnings — update when features are wired or new warnings added.
         //
         // Each entry is a substring that must appear in exactly one warning.
-        let expected_warning_fragments = vec![
-            "cipher_suites",
-            "min_version",
-            "max_version",
-            "max_concurrent_streams",
-            "logging.file",
-            "Logging level",
-        ];
+        let expected_warning_fragments =
+            vec!["max_concurrent_streams", "logging.file", "Logging level"];
 
         assert_eq!(
             warnings.len(),
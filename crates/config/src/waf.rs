@@ -12,6 +12,7 @@ use std::path::PathBuf;
 
 /// WAF configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WafConfig {
     /// WAF engine type
     pub engine: WafEngine,
@@ -38,6 +39,7 @@ pub struct WafConfig {
 
 /// WAF engine type
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum WafEngine {
     ModSecurity,
@@ -51,6 +53,7 @@ pub enum WafEngine {
 
 /// WAF ruleset configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WafRuleset {
     /// CRS version
     pub crs_version: String,
@@ -73,6 +76,7 @@ pub struct WafRuleset {
 
 /// WAF rule exclusion
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RuleExclusion {
     /// Rule IDs to exclude
     pub rule_ids: Vec<String>,
@@ -83,6 +87,7 @@ pub struct RuleExclusion {
 
 /// Exclusion scope
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ExclusionScope {
     Global,
@@ -96,6 +101,7 @@ pub enum ExclusionScope {
 
 /// WAF mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum WafMode {
     Off,
@@ -109,6 +115,7 @@ pub enum WafMode {
 
 /// Body inspection policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BodyInspectionPolicy {
     /// Enable request body inspection
     #[serde(default = "default_inspect_request_body")]
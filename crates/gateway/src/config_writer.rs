@@ -147,6 +147,7 @@ impl ConfigWriter {
                             path,
                             expected_status,
                             host,
+                            ..
                         } => {
                             out.push_str("            type \"http\"\n");
                             out.push_str(&format!("            path \"{path}\"\n"));
@@ -268,6 +269,10 @@ impl ConfigWriter {
                                 ));
                             }
                         }
+                        zentinel_config::MatchCondition::Expr(expr) => {
+                            let escaped = expr.as_str().replace('\\', "\\\\").replace('"', "\\\"");
+                            out.push_str(&format!("            expr \"{escaped}\"\n"));
+                        }
                     }
                 }
                 out.push_str("        }\n");
@@ -549,11 +554,15 @@ mod tests {
                 ],
                 load_balancing: LoadBalancingAlgorithm::RoundRobin,
                 sticky_session: None,
+                consistent_hash: None,
+                discovery: None,
                 health_check: None,
                 circuit_breaker: None,
                 connection_pool: ConnectionPoolConfig::default(),
                 timeouts: UpstreamTimeouts::default(),
                 tls: None,
+                auth: None,
+                queue: None,
                 http_version: HttpVersionConfig::default(),
             },
         );
@@ -572,6 +581,17 @@ mod tests {
                 trace_id_format: Default::default(),
                 auto_reload: true,
                 route_cache_size: 1000,
+                client_ip: Default::default(),
+                outbound_proxy: Default::default(),
+                maintenance: Default::default(),
+                concurrency: None,
+                request_id: Default::default(),
+                problem_details: None,
+                readiness: Default::default(),
+                chaos: Default::default(),
+                capture: None,
+                rollout: None,
+                debug_headers: None,
             },
             listeners: vec![ListenerConfig {
                 id: "http".to_string(),
@@ -584,6 +604,13 @@ mod tests {
                 keepalive_timeout_secs: 75,
                 max_concurrent_streams: 100,
                 keepalive_max_requests: None,
+                proxy_protocol: false,
+                bot_signals: false,
+                quic: Default::default(),
+                slow_client: Default::default(),
+                hardening: Default::default(),
+                forward_proxy: None,
+                stream: None,
             }],
             routes: vec![RouteConfig {
                 id: "test-route".to_string(),
@@ -635,10 +662,14 @@ mod tests {
             agents: vec![],
             waf: None,
             namespaces: vec![],
+            tenants: vec![],
             limits: Default::default(),
             observability: Default::default(),
             rate_limits: Default::default(),
             cache: None,
+            session_store: None,
+            dns: None,
+            notifications: None,
             default_upstream: None,
         };
 
@@ -23,6 +23,7 @@ use tracing::{error, info, warn};
 use zentinel_config::Config;
 
 use crate::config_writer::ConfigWriter;
+use crate::embedded::ConfigSink;
 use crate::error::GatewayError;
 use crate::reconcilers::{
     GatewayClassReconciler, GatewayReconciler, GrpcRouteReconciler, HttpRouteReconciler,
@@ -45,6 +46,7 @@ pub struct GatewayController {
     reference_grants: Arc<ReferenceGrantIndex>,
     cert_manager: Arc<SecretCertificateManager>,
     config_output_path: Option<PathBuf>,
+    config_sink: Option<Arc<dyn ConfigSink>>,
 }
 
 impl GatewayController {
@@ -69,6 +71,7 @@ impl GatewayController {
             reference_grants,
             cert_manager,
             config_output_path: None,
+            config_sink: None,
         })
     }
 
@@ -86,6 +89,7 @@ impl GatewayController {
             reference_grants,
             cert_manager,
             config_output_path: None,
+            config_sink: None,
         }
     }
 
@@ -99,6 +103,15 @@ impl GatewayController {
         self
     }
 
+    /// Set a sink that receives the translated config after each rebuild.
+    ///
+    /// Used in embedded mode, where an `EmbeddedSink` applies the config
+    /// to a proxy running in the same process.
+    pub fn with_config_sink(mut self, sink: Arc<dyn ConfigSink>) -> Self {
+        self.config_sink = Some(sink);
+        self
+    }
+
     /// Get a handle to the shared config (for the data plane to read).
     pub fn config_handle(&self) -> Arc<ArcSwap<Config>> {
         Arc::clone(&self.config)
@@ -121,6 +134,11 @@ impl GatewayController {
             info!(path = %path.display(), "Config output enabled for proxy sidecar");
         }
 
+        if let Some(ref sink) = self.config_sink {
+            translator = translator.with_config_sink(Arc::clone(sink));
+            info!("Config push to embedded proxy enabled");
+        }
+
         let translator = Arc::new(translator);
 
         info!("Starting Gateway API controller");
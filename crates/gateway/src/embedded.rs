@@ -29,15 +29,26 @@
 //! validation, hook, and atomic swap pipeline.
 //!
 //! ```rust,ignore
-//! use zentinel_gateway::embedded::ConfigBridge;
+//! use zentinel_gateway::embedded::EmbeddedSink;
 //!
-//! // Create the bridge with a reference to the proxy's config manager
-//! let bridge = ConfigBridge::new(config_manager.clone());
+//! let sink = EmbeddedSink::new(move |config| {
+//!     let config_manager = config_manager.clone();
+//!     async move {
+//!         config_manager
+//!             .apply_config(config, ReloadTrigger::GatewayApi)
+//!             .await
+//!             .map_err(|e| e.to_string())
+//!     }
+//! });
 //!
-//! // The translator calls the bridge after each rebuild
-//! bridge.push(new_config).await?;
+//! // The translator pushes to the sink after each rebuild
+//! let controller = GatewayController::new().await?.with_config_sink(Arc::new(sink));
+//! controller.run().await?;
 //! ```
 //!
+//! The `zentinel` binary does this when built with the `gateway-api`
+//! feature and started with `--gateway-controller`.
+//!
 //! # Limitations
 //!
 //! - Pingora listeners must be configured before `run_forever()`. Gateway
@@ -87,17 +98,8 @@ impl ConfigSink for FileSink {
 /// share the same process. The `ConfigManager` reference is obtained
 /// from the proxy's `ZentinelProxy` instance.
 ///
-/// # Example
-///
-/// ```rust,ignore
-/// // In the proxy's main(), after creating ZentinelProxy:
-/// let config_manager = proxy.config_manager.clone();
-/// let sink = EmbeddedSink::new(config_manager);
-///
-/// // Pass to the gateway controller
-/// let controller = GatewayController::new().await?;
-/// // controller would use sink.push() after each rebuild
-/// ```
+/// Attach it with `GatewayController::with_config_sink()`; the translator
+/// pushes to it after each rebuild.
 pub struct EmbeddedSink {
     /// The apply function, stored as a boxed async closure.
     /// This avoids a direct dependency on `zentinel-proxy` crate.
@@ -15,7 +15,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
-use zentinel_common::types::{HealthCheckType, LoadBalancingAlgorithm, Priority};
+use zentinel_common::types::{
+    HealthCheckMethod, HealthCheckType, LoadBalancingAlgorithm, Priority,
+};
 use zentinel_config::{
     ConnectionPoolConfig, HealthCheck, HttpVersionConfig, MatchCondition, RouteConfig,
     RoutePolicies, ServiceType, UpstreamConfig, UpstreamTarget, UpstreamTimeouts,
@@ -163,11 +165,15 @@ pub fn translate_ingresses(
                         }],
                         load_balancing: LoadBalancingAlgorithm::RoundRobin,
                         sticky_session: None,
+                        consistent_hash: None,
+                        discovery: None,
                         health_check: Some(HealthCheck {
                             check_type: HealthCheckType::Http {
                                 path: "/".to_string(),
                                 expected_status: 200,
                                 host: None,
+                                method: HealthCheckMethod::Get,
+                                expected_body: None,
                             },
                             interval_secs: 10,
                             timeout_secs: 5,
@@ -178,6 +184,8 @@ pub fn translate_ingresses(
                         connection_pool: ConnectionPoolConfig::default(),
                         timeouts: UpstreamTimeouts::default(),
                         tls: None,
+                        auth: None,
+                        queue: None,
                         http_version: HttpVersionConfig::default(),
                     };
 
@@ -232,11 +240,15 @@ pub fn translate_ingresses(
                         }],
                         load_balancing: LoadBalancingAlgorithm::RoundRobin,
                         sticky_session: None,
+                        consistent_hash: None,
+                        discovery: None,
                         health_check: None,
                         circuit_breaker: None,
                         connection_pool: ConnectionPoolConfig::default(),
                         timeouts: UpstreamTimeouts::default(),
                         tls: None,
+                        auth: None,
+                        queue: None,
                         http_version: HttpVersionConfig::default(),
                     },
                 );
@@ -20,7 +20,9 @@ use kube::api::ListParams;
 use kube::{Api, Client, ResourceExt};
 use tracing::{debug, error, info, warn};
 
-use zentinel_common::types::{HealthCheckType, LoadBalancingAlgorithm, Priority, TlsVersion};
+use zentinel_common::types::{
+    HealthCheckMethod, HealthCheckType, LoadBalancingAlgorithm, Priority, TlsVersion,
+};
 use zentinel_config::{
     Config, ConnectionPoolConfig, Filter, FilterConfig, HeaderModifications, HealthCheck,
     HttpVersionConfig, ListenerConfig, ListenerProtocol, MatchCondition, PathModifier,
@@ -29,6 +31,7 @@ use zentinel_config::{
 };
 
 use crate::config_writer::ConfigWriter;
+use crate::embedded::ConfigSink;
 use crate::error::GatewayError;
 use crate::reconcilers::gateway_class::CONTROLLER_NAME;
 use crate::reconcilers::ingress::translate_ingresses;
@@ -45,6 +48,7 @@ pub struct ConfigTranslator {
     reference_grants: Arc<ReferenceGrantIndex>,
     cert_manager: Arc<SecretCertificateManager>,
     config_writer: Option<ConfigWriter>,
+    config_sink: Option<Arc<dyn ConfigSink>>,
 }
 
 impl ConfigTranslator {
@@ -58,6 +62,7 @@ impl ConfigTranslator {
             reference_grants,
             cert_manager,
             config_writer: None,
+            config_sink: None,
         }
     }
 
@@ -71,6 +76,15 @@ impl ConfigTranslator {
         self
     }
 
+    /// Push each translated config to a sink (e.g. an embedded proxy).
+    ///
+    /// The sink is called after the ArcSwap store and the optional file
+    /// write. Push failures are logged; the next rebuild retries.
+    pub fn with_config_sink(mut self, sink: Arc<dyn ConfigSink>) -> Self {
+        self.config_sink = Some(sink);
+        self
+    }
+
     /// Get the current config (for reading).
     pub fn current_config(&self) -> Arc<Config> {
         self.config.load_full()
@@ -296,6 +310,17 @@ impl ConfigTranslator {
                 trace_id_format: Default::default(),
                 auto_reload: true,
                 route_cache_size: 1000,
+                client_ip: Default::default(),
+                outbound_proxy: Default::default(),
+                maintenance: Default::default(),
+                concurrency: None,
+                request_id: Default::default(),
+                problem_details: None,
+                readiness: Default::default(),
+                chaos: Default::default(),
+                capture: None,
+                rollout: None,
+                debug_headers: None,
             },
             listeners,
             routes,
@@ -304,10 +329,14 @@ impl ConfigTranslator {
             agents: vec![],
             waf: None,
             namespaces: vec![],
+            tenants: vec![],
             limits: Default::default(),
             observability: Default::default(),
             rate_limits: Default::default(),
             cache: None,
+            session_store: None,
+            dns: None,
+            notifications: None,
             default_upstream: None,
         };
 
@@ -327,6 +356,13 @@ impl ConfigTranslator {
             }
         }
 
+        // Push config to the embedded proxy
+        if let Some(ref sink) = self.config_sink {
+            if let Err(e) = sink.push(&new_config).await {
+                error!(error = %e, "Failed to push config to sink");
+            }
+        }
+
         Ok(())
     }
 
@@ -381,6 +417,13 @@ impl ConfigTranslator {
                 keepalive_timeout_secs: 75,
                 max_concurrent_streams: 100,
                 keepalive_max_requests: None,
+                proxy_protocol: false,
+                bot_signals: false,
+                quic: Default::default(),
+                slow_client: Default::default(),
+                hardening: Default::default(),
+                forward_proxy: None,
+                stream: None,
             });
         }
 
@@ -447,6 +490,7 @@ impl ConfigTranslator {
                         cert_file: Some(cert.cert_path),
                         key_file: Some(cert.key_path),
                         acme: None,
+                        namespace: None,
                     });
                 }
                 Err(e) => {
@@ -468,6 +512,7 @@ impl ConfigTranslator {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: true,
             session_resumption: true,
             acme: None,
@@ -855,11 +900,15 @@ impl ConfigTranslator {
             targets,
             load_balancing,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: Some(HealthCheck {
                 check_type: HealthCheckType::Http {
                     path: "/".to_string(),
                     expected_status: 200,
                     host: None,
+                    method: HealthCheckMethod::Get,
+                    expected_body: None,
                 },
                 interval_secs: 10,
                 timeout_secs: 5,
@@ -870,6 +919,8 @@ impl ConfigTranslator {
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         };
 
@@ -1270,6 +1321,8 @@ impl ConfigTranslator {
             targets,
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: Some(HealthCheck {
                 check_type: HealthCheckType::Grpc {
                     service: String::new(), // gRPC health check on default service
@@ -1283,6 +1336,8 @@ impl ConfigTranslator {
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig {
                 min_version: 2, // gRPC requires HTTP/2
                 max_version: 2,
@@ -1425,6 +1480,8 @@ impl ConfigTranslator {
             targets,
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: Some(HealthCheck {
                 check_type: HealthCheckType::Tcp,
                 interval_secs: 10,
@@ -1436,6 +1493,8 @@ impl ConfigTranslator {
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: UpstreamTimeouts::default(),
             tls: None, // Passthrough — no TLS termination at proxy
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         };
 
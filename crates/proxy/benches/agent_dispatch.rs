@@ -0,0 +1,178 @@
+//! Benchmarks of `AgentManager` dispatch.
+//!
+//! Agents are served in process by no-op handlers, so the numbers cover the
+//! manager's own work per request: agent lookup, semaphores, circuit
+//! breakers, parallel fan-out, decision merging and per-request cleanup,
+//! without any transport. Transport costs are measured by the
+//! `zentinel-agent-protocol` benchmarks.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::Arc;
+
+use zentinel_agent_protocol::v2::server::AgentHandlerV2;
+use zentinel_agent_protocol::v2::AgentCapabilities;
+use zentinel_agent_protocol::{AgentResponse, EventType, RequestHeadersEvent, RequestMetadata};
+use zentinel_common::CorrelationId;
+use zentinel_config::{Config, FailureMode};
+use zentinel_proxy::{AgentCallContext, AgentManager};
+
+/// Allows every request without looking at it
+struct NoopAgent;
+
+#[async_trait::async_trait]
+impl AgentHandlerV2 for NoopAgent {
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("bench-noop", "No-op Agent", "1.0.0")
+            .with_event(EventType::RequestHeaders)
+    }
+
+    async fn on_request_headers(&self, _event: RequestHeadersEvent) -> AgentResponse {
+        AgentResponse::default_allow()
+    }
+}
+
+/// Config with `count` request-header agents; their addresses are never
+/// dialed because every agent is served in process
+fn config_with_agents(count: usize) -> Config {
+    let agents: String = (0..count)
+        .map(|i| {
+            format!(
+                r#"
+            agent "agent-{i}" type="custom" {{
+                grpc address="http://127.0.0.1:1"
+                events "request_headers"
+                timeout-ms 1000
+                failure-mode "closed"
+            }}"#
+            )
+        })
+        .collect();
+
+    Config::from_kdl(&format!(
+        r#"
+        listeners {{
+            listener "http" {{
+                address "127.0.0.1:8080"
+                protocol "http"
+            }}
+        }}
+        upstreams {{
+            upstream "backend" {{
+                target "127.0.0.1:3000"
+            }}
+        }}
+        routes {{
+            route "api" {{
+                matches {{
+                    path-prefix "/"
+                }}
+                upstream "backend"
+            }}
+        }}
+        agents {{{agents}
+        }}
+        "#
+    ))
+    .expect("benchmark config should parse")
+}
+
+fn metadata() -> RequestMetadata {
+    RequestMetadata {
+        correlation_id: "bench".to_string(),
+        request_id: "bench".to_string(),
+        client_ip: "203.0.113.7".to_string(),
+        client_port: 54321,
+        server_name: None,
+        protocol: "HTTP/1.1".to_string(),
+        tls_version: None,
+        tls_cipher: None,
+        route_id: Some("api".to_string()),
+        upstream_id: None,
+        timestamp: "2026-01-01T00:00:00Z".to_string(),
+        traceparent: None,
+        client_cert: None,
+        tags: Vec::new(),
+        bot_signals: None,
+        dry_run: false,
+    }
+}
+
+fn request_headers() -> HashMap<String, Vec<String>> {
+    [
+        (":method", "GET"),
+        (":path", "/api/v1/orders"),
+        ("host", "api.example.com"),
+        ("user-agent", "bench/1.0"),
+        ("accept", "application/json"),
+        ("x-request-id", "7d0c9c2e-5d1b-4c1f-9a3e-2b8f0f6f1d42"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), vec![value.to_string()]))
+    .collect()
+}
+
+/// `process_request_headers` plus `end_request` for 1 to 8 agents on a route
+fn bench_parallel_dispatch(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("agent_dispatch");
+    group.throughput(Throughput::Elements(1));
+
+    for count in [1, 2, 4, 8] {
+        let config = config_with_agents(count);
+        let handlers: HashMap<String, Arc<dyn AgentHandlerV2>> = config
+            .agents
+            .iter()
+            .map(|agent| {
+                (
+                    agent.id.clone(),
+                    Arc::new(NoopAgent) as Arc<dyn AgentHandlerV2>,
+                )
+            })
+            .collect();
+        let manager = runtime.block_on(async {
+            let manager = AgentManager::with_in_process_agents(config.agents.clone(), handlers)
+                .await
+                .unwrap();
+            manager.initialize().await.unwrap();
+            manager
+        });
+        let route_agents: Vec<(String, FailureMode)> = config
+            .agents
+            .iter()
+            .map(|agent| (agent.id.clone(), FailureMode::Closed))
+            .collect();
+        let ctx = AgentCallContext::new(CorrelationId::from_string("bench"), metadata());
+        let headers = request_headers();
+
+        group.bench_with_input(
+            BenchmarkId::new("request_headers", count),
+            &count,
+            |b, _| {
+                b.iter(|| {
+                    runtime.block_on(async {
+                        let decision = manager
+                            .process_request_headers(&ctx, headers.clone(), &route_agents)
+                            .await
+                            .unwrap();
+                        manager.end_request("bench").await;
+                        black_box(decision)
+                    })
+                })
+            },
+        );
+
+        runtime.block_on(manager.shutdown());
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parallel_dispatch);
+criterion_main!(benches);
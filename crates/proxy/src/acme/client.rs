@@ -6,15 +6,19 @@
 //! - Challenge handling (HTTP-01 and DNS-01)
 //! - Certificate finalization
 
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
+use http_body_util::{BodyExt, Full};
 use instant_acme::{
-    Account, AuthorizationStatus, ChallengeType, Identifier, LetsEncrypt, NewAccount, NewOrder,
-    Order, OrderStatus, RetryPolicy,
+    Account, AccountBuilder, AuthorizationStatus, BodyWrapper, BytesResponse, ChallengeType,
+    HttpClient, Identifier, LetsEncrypt, NewAccount, NewOrder, Order, OrderStatus, RetryPolicy,
 };
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, trace, warn};
@@ -35,6 +39,74 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
 /// Timeout for challenge validation
 const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// Create an ACME account builder
+///
+/// instant-acme's built-in HTTP client does not support proxies, so when an
+/// outbound proxy is in effect (configured or from the environment) requests
+/// go through a reqwest client instead.
+fn account_builder() -> Result<AccountBuilder, AcmeError> {
+    if !crate::outbound::proxy_enabled() {
+        return Account::builder().map_err(|e| AcmeError::AccountCreation(e.to_string()));
+    }
+
+    let client = crate::outbound::client_builder()
+        .timeout(DEFAULT_TIMEOUT)
+        .build()
+        .map_err(|e| AcmeError::AccountCreation(format!("Failed to create HTTP client: {}", e)))?;
+    debug!("Using outbound proxy for ACME requests");
+    Ok(Account::builder_with_http(Box::new(ProxiedHttpClient(
+        client,
+    ))))
+}
+
+/// instant-acme HTTP client backed by reqwest, honouring the outbound proxy
+struct ProxiedHttpClient(reqwest::Client);
+
+impl HttpClient for ProxiedHttpClient {
+    fn request(
+        &self,
+        req: http::Request<BodyWrapper<Bytes>>,
+    ) -> Pin<Box<dyn Future<Output = Result<BytesResponse, instant_acme::Error>> + Send>> {
+        let client = self.0.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let body = body
+                .collect()
+                .await
+                .map_err(|e| instant_acme::Error::Other(e.into()))?
+                .to_bytes();
+
+            let response = client
+                .request(parts.method, parts.uri.to_string())
+                .headers(parts.headers)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| instant_acme::Error::Other(Box::new(e)))?;
+
+            let mut builder = http::Response::builder()
+                .status(response.status())
+                .version(response.version());
+            if let Some(headers) = builder.headers_mut() {
+                *headers = response.headers().clone();
+            }
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| instant_acme::Error::Other(Box::new(e)))?;
+            let (parts, body) = builder
+                .body(Full::new(bytes))
+                .map_err(|e| instant_acme::Error::Other(Box::new(e)))?
+                .into_parts();
+
+            Ok(BytesResponse {
+                parts,
+                body: Box::new(body),
+            })
+        })
+    }
+}
+
 /// ACME client for automatic certificate management
 ///
 /// Wraps the `instant-acme` library and provides Zentinel-specific functionality
@@ -104,8 +176,7 @@ impl AcmeClient {
             })?;
 
             // Reconstruct account from stored credentials
-            let account = Account::builder()
-                .map_err(|e| AcmeError::AccountCreation(e.to_string()))?
+            let account = account_builder()?
                 .from_credentials(credentials)
                 .await
                 .map_err(|e| AcmeError::AccountCreation(e.to_string()))?;
@@ -135,8 +206,7 @@ impl AcmeClient {
             None
         };
 
-        let (account, credentials) = Account::builder()
-            .map_err(|e| AcmeError::AccountCreation(e.to_string()))?
+        let (account, credentials) = account_builder()?
             .create(
                 &NewAccount {
                     contact: &[&format!("mailto:{}", self.config.email)],
@@ -39,9 +39,12 @@ impl CloudflareProvider {
     /// * `token` - Cloudflare API Token (Bearer auth)
     /// * `timeout` - Request timeout
     pub fn new(token: &str, timeout: Duration) -> DnsResult<Self> {
-        let client = Client::builder().timeout(timeout).build().map_err(|e| {
-            DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
-        })?;
+        let client = crate::outbound::client_builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
+            })?;
 
         Ok(Self {
             client,
@@ -38,9 +38,12 @@ impl HetznerProvider {
     /// * `token` - Hetzner DNS API token
     /// * `timeout` - Request timeout
     pub fn new(token: &str, timeout: Duration) -> DnsResult<Self> {
-        let client = Client::builder().timeout(timeout).build().map_err(|e| {
-            DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
-        })?;
+        let client = crate::outbound::client_builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
+            })?;
 
         Ok(Self {
             client,
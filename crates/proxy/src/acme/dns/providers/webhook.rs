@@ -74,9 +74,12 @@ impl WebhookProvider {
         credentials: Option<Credentials>,
         timeout: Duration,
     ) -> DnsResult<Self> {
-        let client = Client::builder().timeout(timeout).build().map_err(|e| {
-            DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
-        })?;
+        let client = crate::outbound::client_builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                DnsProviderError::Configuration(format!("Failed to create HTTP client: {}", e))
+            })?;
 
         // Remove trailing slash from base URL
         let base_url = base_url.trim_end_matches('/').to_string();
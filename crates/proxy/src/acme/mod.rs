@@ -9,7 +9,7 @@
 //! - DNS-01 challenge support for wildcard certificates
 //! - Modular DNS provider system (Hetzner, webhook)
 //! - Persistent storage for certificates and account credentials
-//! - Background renewal scheduler
+//! - Background renewal scheduler with OCSP stapling and hot-reload
 //!
 //! # Architecture
 //!
@@ -19,7 +19,8 @@
 //! - [`CertificateStorage`] - Persistent storage for certificates and account keys
 //! - [`ChallengeManager`] - Manages pending HTTP-01 challenges for serving
 //! - [`dns`] - DNS-01 challenge support with pluggable providers
-//! - [`RenewalScheduler`] - Background task for checking and renewing certificates
+//! - [`RenewalScheduler`] - Background task for checking and renewing certificates,
+//!   refreshing OCSP staples, and recording renewal metrics
 //!
 //! # Example (HTTP-01)
 //!
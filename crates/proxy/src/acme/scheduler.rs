@@ -1,12 +1,14 @@
 //! Background certificate renewal scheduler
 //!
-//! Periodically checks certificates and triggers renewal when needed.
-//! Supports both HTTP-01 and DNS-01 challenge types.
+//! Checks certificates daily and triggers renewal when they are within
+//! `renew-before-days` of expiry. Supports both HTTP-01 and DNS-01 challenge
+//! types. Each check also refreshes stapled OCSP responses, and renewed
+//! certificates are hot-swapped into the listener's SNI resolver.
 
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::time::{interval, Instant};
+use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use zentinel_config::server::AcmeChallengeType;
@@ -16,13 +18,17 @@ use super::client::AcmeClient;
 use super::dns::Dns01ChallengeManager;
 use super::error::AcmeError;
 use crate::tls::HotReloadableSniResolver;
+use crate::tls_metrics::get_tls_metrics;
 
-/// Default check interval (12 hours)
-const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 3600);
+/// Default check interval (daily)
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 3600);
 
 /// Minimum check interval (1 hour)
 const MIN_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
 
+/// Delay before retrying after a failed renewal
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+
 /// Background certificate renewal scheduler
 ///
 /// Runs as a background task and periodically checks if any certificates
@@ -69,6 +75,14 @@ impl RenewalScheduler {
         &self.client
     }
 
+    /// Set the SNI resolver to hot-reload after renewal
+    ///
+    /// The resolver's OCSP responses are also refreshed on every check.
+    pub fn with_sni_resolver(mut self, resolver: Arc<HotReloadableSniResolver>) -> Self {
+        self.sni_resolver = Some(resolver);
+        self
+    }
+
     /// Set the DNS-01 challenge manager
     ///
     /// Required when using DNS-01 challenge type.
@@ -94,7 +108,9 @@ impl RenewalScheduler {
     /// Run the renewal scheduler loop
     ///
     /// This runs indefinitely, checking certificates at the configured
-    /// interval and renewing as needed.
+    /// interval and renewing as needed. After a failed renewal the next
+    /// check is scheduled sooner, so a transient CA or DNS outage does not
+    /// wait a full interval.
     pub async fn run(self) {
         info!(
             check_interval_hours = self.check_interval.as_secs() / 3600,
@@ -102,23 +118,68 @@ impl RenewalScheduler {
         );
 
         // Initial check after a short delay
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        let mut next_check = Duration::from_secs(10);
+
+        loop {
+            tokio::time::sleep(next_check).await;
 
-        if let Err(e) = self.check_renewals().await {
-            error!(error = %e, "Initial certificate renewal check failed");
+            debug!("Running scheduled certificate renewal check");
+
+            next_check = match self.check_renewals().await {
+                Ok(()) => self.check_interval,
+                Err(e) => {
+                    error!(
+                        error = %e,
+                        retry_in_secs = RETRY_INTERVAL.as_secs(),
+                        "Certificate renewal check failed"
+                    );
+                    RETRY_INTERVAL.min(self.check_interval)
+                }
+            };
+
+            self.refresh_ocsp().await;
         }
+    }
 
-        // Periodic checks
-        let mut interval = interval(self.check_interval);
+    /// Refresh stapled OCSP responses for the listener
+    async fn refresh_ocsp(&self) {
+        let Some(ref resolver) = self.sni_resolver else {
+            return;
+        };
 
-        loop {
-            interval.tick().await;
+        let success = match resolver.refresh_ocsp().await {
+            Ok(warnings) if warnings.is_empty() => true,
+            Ok(warnings) => {
+                warn!(
+                    listener_id = %resolver.listener_id(),
+                    warnings = ?warnings,
+                    "Some OCSP responses could not be refreshed"
+                );
+                false
+            }
+            Err(e) => {
+                error!(
+                    listener_id = %resolver.listener_id(),
+                    error = %e,
+                    "Failed to reload TLS certificates with refreshed OCSP responses"
+                );
+                false
+            }
+        };
 
-            debug!("Running scheduled certificate renewal check");
+        if let Some(metrics) = get_tls_metrics() {
+            metrics.record_ocsp_refresh(resolver.listener_id(), success);
+        }
+    }
 
-            if let Err(e) = self.check_renewals().await {
-                error!(error = %e, "Certificate renewal check failed");
+    /// Record the stored certificate's expiry for alerting
+    fn record_expiry(&self, domain: &str) {
+        if let Ok(Some(cert)) = self.client.storage().load_certificate(domain) {
+            let expires = cert.meta.expires.timestamp();
+            if let Some(metrics) = get_tls_metrics() {
+                metrics.set_cert_expiry(domain, expires);
             }
+            crate::notifications::certificate_loaded(&format!("acme/{}", domain), expires);
         }
     }
 
@@ -138,9 +199,15 @@ impl RenewalScheduler {
             Ok(true) => {
                 info!(domain = %domain, "Certificate needs renewal");
 
-                match self.renew_certificate().await {
+                let result = self.renew_certificate().await;
+                if let Some(metrics) = get_tls_metrics() {
+                    metrics.record_cert_renewal(domain, result.is_ok());
+                }
+
+                match result {
                     Ok(()) => {
                         info!(domain = %domain, "Certificate renewed successfully");
+                        self.record_expiry(domain);
 
                         // Trigger TLS hot-reload
                         if let Some(ref resolver) = self.sni_resolver {
@@ -167,6 +234,7 @@ impl RenewalScheduler {
             }
             Ok(false) => {
                 debug!(domain = %domain, "Certificate is still valid");
+                self.record_expiry(domain);
             }
             Err(e) => {
                 warn!(
@@ -0,0 +1,706 @@
+//! Anomaly-based adaptive protection
+//!
+//! The `adaptive-protection` filter keeps a short behavioural profile per
+//! client IP and restrains clients that look unlike ordinary traffic,
+//! without fixed per-route limits.
+//!
+//! # Signals
+//!
+//! Each client's requests are counted in `BUCKETS` buckets spanning the
+//! filter's sliding window. Three signals are derived from them:
+//!
+//! - **rate**: requests in the window
+//! - **error ratio**: share of responses with status 400 or above
+//! - **path entropy**: Shannon entropy (bits) of the request paths; a
+//!   scanner probing many distinct paths scores high, a client reloading
+//!   the same few pages scores low
+//!
+//! Every signal over its threshold adds its weight to the anomaly score.
+//! Clients are only scored once they made `min-requests` requests in the
+//! window.
+//!
+//! # Decisions
+//!
+//! A client whose score reaches `score-threshold` is penalized for
+//! `penalty-secs`: throttled to `throttle-rps`, or required to solve a
+//! challenge, or (with `action "log"`) only logged. Each decision carries
+//! reason codes (`high_rate`, `error_ratio`, `path_entropy`) for the audit
+//! log and metrics.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use tracing::debug;
+
+use zentinel_config::{AdaptiveAction, AdaptiveProtectionFilter};
+
+/// Penalty decisions per filter, action and reason code
+static ADAPTIVE_DECISIONS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_adaptive_protection_decisions_total",
+        "Clients penalized by adaptive-protection filters",
+        &["filter", "action", "reason"]
+    )
+    .ok()
+});
+
+/// Requests throttled or challenged per filter and action
+static ADAPTIVE_ENFORCED: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_adaptive_protection_enforced_total",
+        "Requests restrained by adaptive-protection penalties",
+        &["filter", "action"]
+    )
+    .ok()
+});
+
+/// Buckets per sliding window
+const BUCKETS: u32 = 6;
+
+/// Distinct paths tracked per bucket; further paths count as unique
+const MAX_PATHS_PER_BUCKET: usize = 256;
+
+/// Why a client was penalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    HighRate,
+    ErrorRatio,
+    PathEntropy,
+}
+
+impl Reason {
+    /// Reason code for logs and metrics
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Reason::HighRate => "high_rate",
+            Reason::ErrorRatio => "error_ratio",
+            Reason::PathEntropy => "path_entropy",
+        }
+    }
+}
+
+/// Behaviour of a client over the window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Signals {
+    /// Requests in the window
+    pub requests: u32,
+    /// Share of responses with status >= 400
+    pub error_ratio: f64,
+    /// Shannon entropy of the request paths in bits
+    pub path_entropy: f64,
+}
+
+impl Signals {
+    /// Anomaly score and the signals that contributed to it
+    pub fn score(&self, config: &AdaptiveProtectionFilter) -> (f64, Vec<Reason>) {
+        let mut score = 0.0;
+        let mut reasons = Vec::new();
+        if self.requests >= config.rate_threshold {
+            score += config.rate_weight;
+            reasons.push(Reason::HighRate);
+        }
+        if self.error_ratio >= config.error_ratio_threshold {
+            score += config.error_weight;
+            reasons.push(Reason::ErrorRatio);
+        }
+        if self.path_entropy >= config.path_entropy_threshold {
+            score += config.entropy_weight;
+            reasons.push(Reason::PathEntropy);
+        }
+        (score, reasons)
+    }
+}
+
+/// A client newly penalized by a filter
+#[derive(Debug, Clone)]
+pub struct AdaptiveDecision {
+    pub action: AdaptiveAction,
+    pub score: f64,
+    pub reasons: Vec<Reason>,
+    pub signals: Signals,
+    pub penalty: Duration,
+}
+
+impl AdaptiveDecision {
+    /// Comma-separated reason codes
+    pub fn reason_codes(&self) -> String {
+        self.reasons
+            .iter()
+            .map(|r| r.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// What to do with the current request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdaptiveVerdict {
+    /// Forward the request
+    Allow,
+    /// Reject the request; the client may retry after the given seconds
+    Throttle {
+        status_code: u16,
+        retry_after_secs: u64,
+    },
+    /// Serve a challenge unless the client already solved it
+    Challenge { challenge_type: String },
+}
+
+/// Result of observing a request
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub verdict: AdaptiveVerdict,
+    /// Set when this request got the client penalized
+    pub decision: Option<AdaptiveDecision>,
+}
+
+impl Observation {
+    fn allow() -> Self {
+        Self {
+            verdict: AdaptiveVerdict::Allow,
+            decision: None,
+        }
+    }
+}
+
+// =============================================================================
+// Client State
+// =============================================================================
+
+#[derive(Debug)]
+struct Bucket {
+    started: Instant,
+    requests: u32,
+    responses: u32,
+    errors: u32,
+    /// Path hash → requests
+    paths: HashMap<u64, u32>,
+    /// Requests whose path did not fit into `paths`
+    overflow: u32,
+}
+
+impl Bucket {
+    fn new(started: Instant) -> Self {
+        Self {
+            started,
+            requests: 0,
+            responses: 0,
+            errors: 0,
+            paths: HashMap::new(),
+            overflow: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Penalty {
+    until: Instant,
+    /// Start of the current throttle second and requests allowed in it
+    second: Instant,
+    allowed: u32,
+}
+
+#[derive(Debug)]
+struct ClientState {
+    buckets: VecDeque<Bucket>,
+    penalty: Option<Penalty>,
+    last_seen: Instant,
+}
+
+impl ClientState {
+    fn new(now: Instant) -> Self {
+        Self {
+            buckets: VecDeque::new(),
+            penalty: None,
+            last_seen: now,
+        }
+    }
+
+    /// Current bucket, after dropping buckets that left the window
+    fn current(&mut self, now: Instant, window: Duration) -> &mut Bucket {
+        let bucket_len = window / BUCKETS;
+        while self
+            .buckets
+            .front()
+            .is_some_and(|b| now.duration_since(b.started) >= window)
+        {
+            self.buckets.pop_front();
+        }
+        if self
+            .buckets
+            .back()
+            .is_none_or(|b| now.duration_since(b.started) >= bucket_len)
+        {
+            self.buckets.push_back(Bucket::new(now));
+        }
+        self.buckets.back_mut().expect("bucket was just ensured")
+    }
+
+    fn record_request(&mut self, now: Instant, window: Duration, path: &str) {
+        self.last_seen = now;
+        let bucket = self.current(now, window);
+        bucket.requests += 1;
+
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some(count) = bucket.paths.get_mut(&hash) {
+            *count += 1;
+        } else if bucket.paths.len() < MAX_PATHS_PER_BUCKET {
+            bucket.paths.insert(hash, 1);
+        } else {
+            bucket.overflow += 1;
+        }
+    }
+
+    fn record_response(&mut self, now: Instant, window: Duration, status: u16) {
+        let bucket = self.current(now, window);
+        bucket.responses += 1;
+        if status >= 400 {
+            bucket.errors += 1;
+        }
+    }
+
+    fn signals(&self) -> Signals {
+        let mut requests = 0;
+        let mut responses = 0;
+        let mut errors = 0;
+        let mut overflow = 0;
+        let mut paths: HashMap<u64, u32> = HashMap::new();
+        for bucket in &self.buckets {
+            requests += bucket.requests;
+            responses += bucket.responses;
+            errors += bucket.errors;
+            overflow += bucket.overflow;
+            for (hash, count) in &bucket.paths {
+                *paths.entry(*hash).or_default() += count;
+            }
+        }
+
+        let error_ratio = if responses == 0 {
+            0.0
+        } else {
+            errors as f64 / responses as f64
+        };
+
+        // Overflowed requests are counted as distinct paths
+        let total = (paths.values().sum::<u32>() + overflow) as f64;
+        let term = |count: u32| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        };
+        let path_entropy = if total == 0.0 {
+            0.0
+        } else {
+            paths.values().map(|c| term(*c)).sum::<f64>() + overflow as f64 * term(1)
+        };
+
+        Signals {
+            requests,
+            error_ratio,
+            path_entropy,
+        }
+    }
+}
+
+// =============================================================================
+// Filters
+// =============================================================================
+
+/// Client profiles of one adaptive-protection filter
+#[derive(Debug)]
+pub struct AdaptiveFilter {
+    filter_id: String,
+    config: AdaptiveProtectionFilter,
+    clients: DashMap<String, ClientState>,
+}
+
+impl AdaptiveFilter {
+    fn new(filter_id: &str, config: AdaptiveProtectionFilter) -> Self {
+        Self {
+            filter_id: filter_id.to_string(),
+            config,
+            clients: DashMap::new(),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs)
+    }
+
+    /// Record a request and decide what to do with it
+    pub fn observe(&self, client_ip: &str, path: &str, now: Instant) -> Observation {
+        if self.clients.len() >= self.config.max_clients && !self.clients.contains_key(client_ip) {
+            return Observation::allow();
+        }
+        let mut state = self
+            .clients
+            .entry(client_ip.to_string())
+            .or_insert_with(|| ClientState::new(now));
+        state.record_request(now, self.window(), path);
+
+        if state.penalty.as_ref().is_some_and(|p| p.until <= now) {
+            state.penalty = None;
+        }
+        if let Some(penalty) = state.penalty.as_mut() {
+            return Observation {
+                verdict: self.enforce(penalty, now),
+                decision: None,
+            };
+        }
+
+        let signals = state.signals();
+        if signals.requests < self.config.min_requests {
+            return Observation::allow();
+        }
+        let (score, reasons) = signals.score(&self.config);
+        if score < self.config.score_threshold {
+            return Observation::allow();
+        }
+
+        let penalty_len = Duration::from_secs(self.config.penalty_secs);
+        let mut penalty = Penalty {
+            until: now + penalty_len,
+            second: now,
+            allowed: 0,
+        };
+        let verdict = self.enforce(&mut penalty, now);
+        state.penalty = Some(penalty);
+
+        let decision = AdaptiveDecision {
+            action: self.config.action,
+            score,
+            reasons,
+            signals,
+            penalty: penalty_len,
+        };
+        if let Some(counter) = ADAPTIVE_DECISIONS.as_ref() {
+            for reason in &decision.reasons {
+                counter
+                    .with_label_values(&[
+                        self.filter_id.as_str(),
+                        action_label(decision.action),
+                        reason.as_str(),
+                    ])
+                    .inc();
+            }
+        }
+        debug!(
+            filter_id = %self.filter_id,
+            client_ip = %client_ip,
+            score,
+            reasons = %decision.reason_codes(),
+            "Client penalized by adaptive protection"
+        );
+
+        Observation {
+            verdict,
+            decision: Some(decision),
+        }
+    }
+
+    fn enforce(&self, penalty: &mut Penalty, now: Instant) -> AdaptiveVerdict {
+        let verdict = match self.config.action {
+            AdaptiveAction::Log => return AdaptiveVerdict::Allow,
+            AdaptiveAction::Challenge => AdaptiveVerdict::Challenge {
+                challenge_type: self.config.challenge_type.clone(),
+            },
+            AdaptiveAction::Throttle => {
+                if now.duration_since(penalty.second) >= Duration::from_secs(1) {
+                    penalty.second = now;
+                    penalty.allowed = 0;
+                }
+                if penalty.allowed < self.config.throttle_rps {
+                    penalty.allowed += 1;
+                    return AdaptiveVerdict::Allow;
+                }
+                AdaptiveVerdict::Throttle {
+                    status_code: self.config.status_code,
+                    retry_after_secs: 1,
+                }
+            }
+        };
+        if let Some(counter) = ADAPTIVE_ENFORCED.as_ref() {
+            counter
+                .with_label_values(&[self.filter_id.as_str(), action_label(self.config.action)])
+                .inc();
+        }
+        verdict
+    }
+
+    /// Record the status of a forwarded request
+    pub fn record_response(&self, client_ip: &str, status: u16, now: Instant) {
+        if let Some(mut state) = self.clients.get_mut(client_ip) {
+            state.record_response(now, self.window(), status);
+        }
+    }
+
+    /// Signals of a client, if tracked
+    pub fn signals(&self, client_ip: &str) -> Option<Signals> {
+        self.clients.get(client_ip).map(|state| state.signals())
+    }
+
+    /// Clients currently tracked
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Forget clients idle for a whole window and not under a penalty
+    fn cleanup(&self, now: Instant) {
+        let window = self.window();
+        self.clients.retain(|_, state| {
+            now.duration_since(state.last_seen) < window
+                || state.penalty.as_ref().is_some_and(|p| p.until > now)
+        });
+    }
+}
+
+fn action_label(action: AdaptiveAction) -> &'static str {
+    match action {
+        AdaptiveAction::Throttle => "throttle",
+        AdaptiveAction::Challenge => "challenge",
+        AdaptiveAction::Log => "log",
+    }
+}
+
+// =============================================================================
+// Adaptive Protection Manager
+// =============================================================================
+
+/// Client profiles of all adaptive-protection filters
+pub struct AdaptiveProtectionManager {
+    /// Filter ID → filter
+    filters: DashMap<String, Arc<AdaptiveFilter>>,
+}
+
+impl AdaptiveProtectionManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            filters: DashMap::new(),
+        }
+    }
+
+    /// Add a filter, replacing any existing one and its client profiles
+    pub fn register_filter(&self, filter_id: &str, config: &AdaptiveProtectionFilter) {
+        self.filters.insert(
+            filter_id.to_string(),
+            Arc::new(AdaptiveFilter::new(filter_id, config.clone())),
+        );
+    }
+
+    /// Filter by ID
+    pub fn get(&self, filter_id: &str) -> Option<Arc<AdaptiveFilter>> {
+        self.filters.get(filter_id).map(|f| Arc::clone(&f))
+    }
+
+    /// Record a request against a filter
+    pub fn observe(&self, filter_id: &str, client_ip: &str, path: &str) -> Option<Observation> {
+        self.get(filter_id)
+            .map(|filter| filter.observe(client_ip, path, Instant::now()))
+    }
+
+    /// Record the status of a forwarded request against a filter
+    pub fn record_response(&self, filter_id: &str, client_ip: &str, status: u16) {
+        if let Some(filter) = self.get(filter_id) {
+            filter.record_response(client_ip, status, Instant::now());
+        }
+    }
+
+    /// Apply a new configuration
+    ///
+    /// Filters with unchanged settings keep their client profiles and
+    /// penalties; changed filters start over.
+    pub fn reload(&self, filters: &HashMap<String, AdaptiveProtectionFilter>) {
+        self.filters.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            let unchanged = self
+                .get(filter_id)
+                .is_some_and(|existing| existing.config == *config);
+            if !unchanged {
+                self.register_filter(filter_id, config);
+            }
+        }
+    }
+
+    /// Forget idle clients
+    pub fn cleanup(&self) {
+        let now = Instant::now();
+        for filter in self.filters.iter() {
+            filter.cleanup(now);
+        }
+    }
+}
+
+impl Default for AdaptiveProtectionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AdaptiveProtectionFilter {
+        AdaptiveProtectionFilter {
+            min_requests: 10,
+            rate_threshold: 50,
+            throttle_rps: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_path_entropy() {
+        let start = Instant::now();
+        let window = Duration::from_secs(60);
+
+        let mut repeated = ClientState::new(start);
+        for _ in 0..32 {
+            repeated.record_request(start, window, "/");
+        }
+        assert_eq!(repeated.signals().path_entropy, 0.0);
+
+        let mut scanner = ClientState::new(start);
+        for i in 0..32 {
+            scanner.record_request(start, window, &format!("/probe/{i}"));
+        }
+        assert!((scanner.signals().path_entropy - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let start = Instant::now();
+        let window = Duration::from_secs(60);
+        let mut state = ClientState::new(start);
+
+        for _ in 0..10 {
+            state.record_request(start, window, "/");
+        }
+        state.record_request(start + Duration::from_secs(30), window, "/");
+        assert_eq!(state.signals().requests, 11);
+
+        state.record_request(start + Duration::from_secs(61), window, "/");
+        assert_eq!(state.signals().requests, 2);
+    }
+
+    #[test]
+    fn test_high_rate_is_throttled() {
+        let filter = AdaptiveFilter::new("adaptive", config());
+        let now = Instant::now();
+
+        for _ in 0..49 {
+            let observation = filter.observe("203.0.113.1", "/", now);
+            assert_eq!(observation.verdict, AdaptiveVerdict::Allow);
+        }
+
+        let observation = filter.observe("203.0.113.1", "/", now);
+        let decision = observation.decision.expect("client penalized");
+        assert_eq!(decision.reasons, vec![Reason::HighRate]);
+        assert_eq!(decision.reason_codes(), "high_rate");
+        assert!(matches!(
+            observation.verdict,
+            AdaptiveVerdict::Throttle {
+                status_code: 429,
+                ..
+            }
+        ));
+
+        // Penalty holds without a new decision; other clients are unaffected
+        let observation = filter.observe("203.0.113.1", "/", now);
+        assert!(observation.decision.is_none());
+        assert_ne!(observation.verdict, AdaptiveVerdict::Allow);
+        assert_eq!(
+            filter.observe("203.0.113.2", "/", now).verdict,
+            AdaptiveVerdict::Allow
+        );
+
+        // Penalty expires
+        let later = now + Duration::from_secs(301);
+        assert!(filter.observe("203.0.113.1", "/", later).decision.is_none());
+    }
+
+    #[test]
+    fn test_scanner_is_challenged() {
+        let filter = AdaptiveFilter::new(
+            "adaptive",
+            AdaptiveProtectionFilter {
+                action: AdaptiveAction::Challenge,
+                ..config()
+            },
+        );
+        let now = Instant::now();
+
+        let mut decision = None;
+        for i in 0..40 {
+            let observation = filter.observe("198.51.100.9", &format!("/wp-{i}.php"), now);
+            filter.record_response("198.51.100.9", 404, now);
+            if observation.decision.is_some() {
+                assert_eq!(
+                    observation.verdict,
+                    AdaptiveVerdict::Challenge {
+                        challenge_type: "js-pow".into()
+                    }
+                );
+                decision = observation.decision;
+                break;
+            }
+        }
+
+        // One signal alone scores 1, so error ratio and entropy both fired
+        let decision = decision.expect("scanner penalized");
+        assert_eq!(
+            decision.reasons,
+            vec![Reason::ErrorRatio, Reason::PathEntropy]
+        );
+        assert!(decision.signals.path_entropy >= 5.0);
+    }
+
+    #[test]
+    fn test_log_action_never_restrains() {
+        let filter = AdaptiveFilter::new(
+            "adaptive",
+            AdaptiveProtectionFilter {
+                action: AdaptiveAction::Log,
+                ..config()
+            },
+        );
+        let now = Instant::now();
+        let decisions = (0..100)
+            .filter(|_| {
+                let observation = filter.observe("203.0.113.1", "/", now);
+                assert_eq!(observation.verdict, AdaptiveVerdict::Allow);
+                observation.decision.is_some()
+            })
+            .count();
+        assert_eq!(decisions, 1);
+    }
+
+    #[test]
+    fn test_reload_keeps_unchanged_filters() {
+        let manager = AdaptiveProtectionManager::new();
+        manager.register_filter("adaptive", &config());
+        manager.observe("adaptive", "203.0.113.1", "/");
+
+        let mut filters = HashMap::from([("adaptive".to_string(), config())]);
+        manager.reload(&filters);
+        assert_eq!(manager.get("adaptive").unwrap().client_count(), 1);
+
+        filters.get_mut("adaptive").unwrap().rate_threshold = 100;
+        manager.reload(&filters);
+        assert_eq!(manager.get("adaptive").unwrap().client_count(), 0);
+
+        manager.reload(&HashMap::new());
+        assert!(manager.get("adaptive").is_none());
+    }
+}
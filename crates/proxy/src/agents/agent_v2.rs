@@ -3,31 +3,84 @@
 //! This module provides v2 agent support using the bidirectional streaming
 //! protocol with capabilities, health reporting, and metrics export.
 
+use std::future::Future;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use tokio::sync::OwnedSemaphorePermit;
 use tracing::{debug, error, info, trace, warn};
 use zentinel_agent_protocol::v2::{
-    AgentCapabilities, AgentPool, AgentPoolConfig as ProtocolPoolConfig, AgentPoolStats,
-    CancelReason, ConfigPusher, ConfigUpdateType, LoadBalanceStrategy as ProtocolLBStrategy,
-    MetricsCollector,
+    AgentCapabilities, AgentHandlerV2, AgentPool, AgentPoolConfig as ProtocolPoolConfig,
+    AgentPoolStats, CancelReason, ConfigPusher, ConfigUpdateType,
+    LoadBalanceStrategy as ProtocolLBStrategy, MetricsCollector, ProtocolMetrics, StateHandler,
 };
 use zentinel_agent_protocol::{
-    AgentResponse, EventType, GuardrailInspectEvent, RequestBodyChunkEvent, RequestHeadersEvent,
+    AgentResponse, BinaryRequestBodyChunkEvent, BinaryResponseBodyChunkEvent, EventType,
+    GuardrailInspectEvent, RequestBodyChunkEvent, RequestHeadersEvent, RequestMetadata,
     ResponseBodyChunkEvent, ResponseHeadersEvent,
 };
 use zentinel_common::{
     errors::{ZentinelError, ZentinelResult},
-    CircuitBreaker,
+    CircuitBreaker, MetricsRegistry, MetricsSource, MetricsWriter,
 };
 use zentinel_config::{AgentConfig, AgentEvent, FailureMode, LoadBalanceStrategy};
 
 use super::metrics::AgentMetrics;
+use super::slow_start::SlowStart;
 
 /// Zentinel value indicating no timestamp recorded
 const NO_TIMESTAMP: u64 = 0;
 
+/// An event the agent manager can dispatch to a v2 agent.
+///
+/// Each event type calls the matching typed method directly, so body chunks
+/// travel as raw bytes and are never serialized through JSON on the way to
+/// a binary transport.
+pub(crate) trait AgentCall: Sync {
+    /// Send this event to `agent` and wait for its response.
+    fn call(&self, agent: &AgentV2) -> impl Future<Output = ZentinelResult<AgentResponse>> + Send;
+}
+
+impl AgentCall for RequestHeadersEvent {
+    fn call(&self, agent: &AgentV2) -> impl Future<Output = ZentinelResult<AgentResponse>> + Send {
+        agent.call_request_headers(self)
+    }
+}
+
+impl AgentCall for ResponseHeadersEvent {
+    fn call(&self, agent: &AgentV2) -> impl Future<Output = ZentinelResult<AgentResponse>> + Send {
+        agent.call_response_headers(self)
+    }
+}
+
+impl AgentCall for BinaryRequestBodyChunkEvent {
+    fn call(&self, agent: &AgentV2) -> impl Future<Output = ZentinelResult<AgentResponse>> + Send {
+        agent.call_request_body_chunk_binary(self)
+    }
+}
+
+impl AgentCall for BinaryResponseBodyChunkEvent {
+    fn call(&self, agent: &AgentV2) -> impl Future<Output = ZentinelResult<AgentResponse>> + Send {
+        agent.call_response_body_chunk_binary(self)
+    }
+}
+
+/// Pool metrics of one agent, labelled with its ID
+struct AgentPoolMetrics {
+    agent_id: String,
+    protocol: Arc<ProtocolMetrics>,
+    reported: Arc<MetricsCollector>,
+}
+
+impl MetricsSource for AgentPoolMetrics {
+    fn collect(&self, writer: &mut MetricsWriter) {
+        self.protocol.write_metrics(writer, &self.agent_id);
+        // Agent-reported series already carry the agent_id label
+        self.reported.collect(writer);
+    }
+}
+
 /// Protocol v2 agent with connection pooling and bidirectional streaming.
 pub struct AgentV2 {
     /// Agent configuration
@@ -47,6 +100,13 @@ pub struct AgentV2 {
     /// Background pool maintenance task (health checks, reconnection,
     /// affinity/session cleanup). Spawned by `initialize`, aborted by `shutdown`.
     maintenance_handle: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Pool metrics, registered with the global metrics registry while the
+    /// agent is running
+    metrics_source: Arc<dyn MetricsSource>,
+    /// Concurrency ramp after (re)connects, when `slow-start` is configured
+    slow_start: Option<SlowStart>,
+    /// Handler running in this process, replacing the configured transport
+    in_process: Option<Arc<dyn AgentHandlerV2>>,
 }
 
 impl AgentV2 {
@@ -61,24 +121,54 @@ impl AgentV2 {
         );
 
         // Convert config pool settings to protocol pool config
-        let pool_config = config
+        let mut pool_config: ProtocolPoolConfig = config
             .pool
             .as_ref()
             .map(|p| ProtocolPoolConfig {
                 connections_per_agent: p.connections_per_agent,
                 load_balance_strategy: convert_lb_strategy(p.load_balance_strategy),
                 connect_timeout: Duration::from_millis(p.connect_timeout_ms),
-                request_timeout: Duration::from_millis(config.timeout_ms),
+                // The manager enforces per-event timeouts; the transport
+                // must not cut the longest of them short
+                request_timeout: Duration::from_millis(config.max_timeout_ms()),
                 reconnect_interval: Duration::from_millis(p.reconnect_interval_ms),
                 max_reconnect_attempts: p.max_reconnect_attempts,
                 drain_timeout: Duration::from_millis(p.drain_timeout_ms),
                 max_concurrent_per_connection: p.max_concurrent_per_connection,
                 health_check_interval: Duration::from_millis(p.health_check_interval_ms),
+                request_duration_buckets_us: p.request_duration_buckets_us.clone(),
+                serialization_buckets_us: p.serialization_buckets_us.clone(),
                 ..Default::default()
             })
             .unwrap_or_default();
+        if let Some(max_message_size) = config.max_message_size {
+            pool_config.max_message_size = max_message_size;
+        }
+        let shared_memory = config.pool.as_ref().and_then(|p| p.shared_memory.as_ref());
+        #[cfg(feature = "agent-shared-memory")]
+        {
+            pool_config.shared_memory = shared_memory.map(convert_shared_memory);
+        }
+        #[cfg(not(feature = "agent-shared-memory"))]
+        if shared_memory.is_some() {
+            warn!(
+                agent_id = %config.id,
+                "Agent pool shared-memory needs the agent-shared-memory feature, sending bodies over the socket"
+            );
+        }
 
         let pool = Arc::new(AgentPool::with_config(pool_config));
+        let metrics_source: Arc<dyn MetricsSource> = Arc::new(AgentPoolMetrics {
+            agent_id: config.id.clone(),
+            protocol: pool.protocol_metrics_arc(),
+            reported: pool.metrics_collector_arc(),
+        });
+        let slow_start = config.slow_start.as_ref().map(|s| {
+            SlowStart::new(
+                Duration::from_millis(s.window_ms),
+                config.max_concurrent_calls,
+            )
+        });
 
         Self {
             config,
@@ -89,14 +179,34 @@ impl AgentV2 {
             last_success_ns: AtomicU64::new(NO_TIMESTAMP),
             consecutive_failures: AtomicU32::new(0),
             maintenance_handle: std::sync::Mutex::new(None),
+            metrics_source,
+            slow_start,
+            in_process: None,
         }
     }
 
+    /// Serve this agent from `handler` in this process instead of connecting
+    /// to its configured transport.
+    pub fn with_in_process_handler(mut self, handler: Arc<dyn AgentHandlerV2>) -> Self {
+        self.in_process = Some(handler);
+        self
+    }
+
     /// Get the agent ID.
     pub fn id(&self) -> &str {
         &self.config.id
     }
 
+    /// Get the agent's configuration.
+    pub fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    /// Get the handler serving this agent in process, if any.
+    pub fn in_process_handler(&self) -> Option<Arc<dyn AgentHandlerV2>> {
+        self.in_process.clone()
+    }
+
     /// Get the agent's circuit breaker.
     pub fn circuit_breaker(&self) -> &CircuitBreaker {
         &self.circuit_breaker
@@ -112,6 +222,17 @@ impl AgentV2 {
         self.config.timeout_ms
     }
 
+    /// Get the agent's timeout for an event type in milliseconds.
+    ///
+    /// Per-event overrides from the agent's `event-timeouts` block take precedence
+    /// over `timeout-ms`.
+    pub fn timeout_ms_for(&self, event_type: EventType) -> u64 {
+        match event_kind(event_type) {
+            Some(event) => self.config.timeout_ms_for(event),
+            None => self.config.timeout_ms,
+        }
+    }
+
     /// Maximum request body size (bytes) this agent will inspect.
     pub fn max_request_body_bytes(&self) -> usize {
         self.config
@@ -133,21 +254,92 @@ impl AgentV2 {
 
     /// Check if agent handles a specific event type.
     pub fn handles_event(&self, event_type: EventType) -> bool {
-        self.config.events.iter().any(|e| match (e, event_type) {
-            (AgentEvent::RequestHeaders, EventType::RequestHeaders) => true,
-            (AgentEvent::RequestBody, EventType::RequestBodyChunk) => true,
-            (AgentEvent::ResponseHeaders, EventType::ResponseHeaders) => true,
-            (AgentEvent::ResponseBody, EventType::ResponseBodyChunk) => true,
-            (AgentEvent::Log, EventType::RequestComplete) => true,
-            (AgentEvent::WebSocketFrame, EventType::WebSocketFrame) => true,
-            (AgentEvent::Guardrail, EventType::GuardrailInspect) => true,
-            _ => false,
-        })
+        event_kind(event_type).is_some_and(|event| self.config.events.contains(&event))
+    }
+
+    /// Wait for the agent's slow-start ramp to admit another call.
+    ///
+    /// Returns `None` when slow start is off or the ramp has finished;
+    /// otherwise the permit has to be held for the duration of the call. A
+    /// new connection generation (first use, or the agent restarted) begins
+    /// a ramp and, with `warmup` set, replays the agent's warm-up requests.
+    pub async fn slow_start_permit(&self) -> Option<OwnedSemaphorePermit> {
+        let slow_start = self.slow_start.as_ref()?;
+        if let Some(generation) = self.pool.agent_connect_generation(&self.config.id) {
+            let warmup = self.config.slow_start.as_ref().is_some_and(|s| s.warmup);
+            if slow_start.observe_generation(generation) {
+                debug!(
+                    agent_id = %self.config.id,
+                    generation = generation,
+                    "Agent (re)connected, starting slow-start ramp"
+                );
+                if warmup {
+                    self.spawn_warmup(generation);
+                }
+            }
+        }
+        slow_start.acquire().await
+    }
+
+    /// Send the warm-up requests the agent advertised in its capabilities.
+    ///
+    /// Decisions are discarded; the requests only serve to prime the agent.
+    fn spawn_warmup(&self, generation: u64) {
+        let pool = Arc::clone(&self.pool);
+        let agent_id = self.config.id.clone();
+
+        tokio::spawn(async move {
+            let Some(capabilities) = pool.agent_capabilities(&agent_id).await else {
+                return;
+            };
+            for (i, request) in capabilities.warmup.into_iter().enumerate() {
+                let correlation_id = format!("warmup-{}-{}-{}", agent_id, generation, i);
+                let event = RequestHeadersEvent {
+                    metadata: RequestMetadata {
+                        correlation_id: correlation_id.clone(),
+                        request_id: correlation_id.clone(),
+                        client_ip: "127.0.0.1".to_string(),
+                        client_port: 0,
+                        server_name: None,
+                        protocol: "HTTP/1.1".to_string(),
+                        tls_version: None,
+                        tls_cipher: None,
+                        route_id: None,
+                        upstream_id: None,
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                        traceparent: None,
+                        client_cert: None,
+                        tags: Vec::new(),
+                        bot_signals: None,
+                        dry_run: false,
+                    },
+                    method: request.method,
+                    uri: request.uri,
+                    headers: request.headers,
+                };
+
+                if let Err(e) = pool
+                    .send_request_headers(&agent_id, &correlation_id, &event)
+                    .await
+                {
+                    warn!(
+                        agent_id = %agent_id,
+                        error = %e,
+                        "Agent warm-up request failed"
+                    );
+                }
+                pool.clear_correlation_affinity(&correlation_id);
+            }
+            debug!(agent_id = %agent_id, "Agent warm-up finished");
+        });
     }
 
     /// Initialize agent connection(s).
     pub async fn initialize(&self) -> ZentinelResult<()> {
-        let endpoint = self.get_endpoint()?;
+        let endpoint = match self.in_process {
+            Some(_) => "in-process".to_string(),
+            None => self.get_endpoint()?,
+        };
 
         debug!(
             agent_id = %self.config.id,
@@ -158,23 +350,28 @@ impl AgentV2 {
         let start = Instant::now();
 
         // Add agent to pool - pool will establish connections
-        self.pool
-            .add_agent(&self.config.id, &endpoint)
-            .await
-            .map_err(|e| {
-                error!(
-                    agent_id = %self.config.id,
-                    endpoint = %endpoint,
-                    error = %e,
-                    "Failed to add agent to v2 pool"
-                );
-                ZentinelError::Agent {
-                    agent: self.config.id.clone(),
-                    message: format!("Failed to initialize v2 agent: {}", e),
-                    event: "initialize".to_string(),
-                    source: None,
-                }
-            })?;
+        let added = match &self.in_process {
+            Some(handler) => {
+                self.pool
+                    .add_in_process_agent(&self.config.id, Arc::clone(handler))
+                    .await
+            }
+            None => self.pool.add_agent(&self.config.id, &endpoint).await,
+        };
+        added.map_err(|e| {
+            error!(
+                agent_id = %self.config.id,
+                endpoint = %endpoint,
+                error = %e,
+                "Failed to add agent to v2 pool"
+            );
+            ZentinelError::Agent {
+                agent: self.config.id.clone(),
+                message: format!("Failed to initialize v2 agent: {}", e),
+                event: "initialize".to_string(),
+                source: None,
+            }
+        })?;
 
         info!(
             agent_id = %self.config.id,
@@ -199,6 +396,9 @@ impl AgentV2 {
             }
         }
 
+        MetricsRegistry::global()
+            .register(self.metrics_registry_id(), Arc::clone(&self.metrics_source));
+
         // Send configuration if present
         if let Some(config_value) = &self.config.config {
             self.send_configure(config_value.clone()).await?;
@@ -207,6 +407,13 @@ impl AgentV2 {
         Ok(())
     }
 
+    /// Serve this agent's session state requests from `handler`.
+    ///
+    /// Must be called before [`initialize`](Self::initialize).
+    pub fn set_state_handler(&self, handler: Arc<dyn StateHandler>) {
+        self.pool.set_state_handler(handler);
+    }
+
     /// Clear the connection affinity for a completed request.
     pub fn clear_correlation_affinity(&self, correlation_id: &str) {
         self.pool.clear_correlation_affinity(correlation_id);
@@ -230,6 +437,12 @@ impl AgentV2 {
                     source: None,
                 })
             }
+            AgentTransport::InProcess => Err(ZentinelError::Agent {
+                agent: self.config.id.clone(),
+                message: "No in-process handler registered".to_string(),
+                event: "initialize".to_string(),
+                source: None,
+            }),
         }
     }
 
@@ -329,6 +542,44 @@ impl AgentV2 {
             })
     }
 
+    /// Call agent with a binary request body chunk event.
+    ///
+    /// The raw bytes go straight to the transport; base64 is only applied
+    /// for connections that negotiated JSON encoding.
+    pub async fn call_request_body_chunk_binary(
+        &self,
+        event: &BinaryRequestBodyChunkEvent,
+    ) -> ZentinelResult<AgentResponse> {
+        let correlation_id = &event.correlation_id;
+
+        trace!(
+            agent_id = %self.config.id,
+            correlation_id = %correlation_id,
+            chunk_index = event.chunk_index,
+            chunk_size = event.data.len(),
+            is_last = event.is_last,
+            "Sending binary request body chunk to v2 agent"
+        );
+
+        self.pool
+            .send_request_body_chunk_binary(&self.config.id, event)
+            .await
+            .map_err(|e| {
+                error!(
+                    agent_id = %self.config.id,
+                    correlation_id = %correlation_id,
+                    error = %e,
+                    "V2 agent request body chunk call failed"
+                );
+                ZentinelError::Agent {
+                    agent: self.config.id.clone(),
+                    message: e.to_string(),
+                    event: "request_body_chunk".to_string(),
+                    source: None,
+                }
+            })
+    }
+
     /// Call agent with response headers event.
     ///
     /// Called when upstream response headers are received, allowing the agent
@@ -402,6 +653,41 @@ impl AgentV2 {
             })
     }
 
+    /// Call agent with a binary response body chunk event.
+    pub async fn call_response_body_chunk_binary(
+        &self,
+        event: &BinaryResponseBodyChunkEvent,
+    ) -> ZentinelResult<AgentResponse> {
+        let correlation_id = &event.correlation_id;
+
+        trace!(
+            agent_id = %self.config.id,
+            correlation_id = %correlation_id,
+            chunk_index = event.chunk_index,
+            chunk_size = event.data.len(),
+            is_last = event.is_last,
+            "Sending binary response body chunk to v2 agent"
+        );
+
+        self.pool
+            .send_response_body_chunk_binary(&self.config.id, event)
+            .await
+            .map_err(|e| {
+                error!(
+                    agent_id = %self.config.id,
+                    correlation_id = %correlation_id,
+                    error = %e,
+                    "V2 agent response body chunk call failed"
+                );
+                ZentinelError::Agent {
+                    agent: self.config.id.clone(),
+                    message: e.to_string(),
+                    event: "response_body_chunk".to_string(),
+                    source: None,
+                }
+            })
+    }
+
     /// Call agent with guardrail inspect event.
     pub async fn call_guardrail_inspect(
         &self,
@@ -601,7 +887,9 @@ impl AgentV2 {
             "Recorded v2 agent call failure"
         );
 
-        self.circuit_breaker.record_failure();
+        if self.circuit_breaker.record_failure() {
+            crate::notifications::circuit_breaker_opened("agent", &self.config.id);
+        }
     }
 
     /// Record timeout.
@@ -617,7 +905,9 @@ impl AgentV2 {
             "Recorded v2 agent call timeout"
         );
 
-        self.circuit_breaker.record_failure();
+        if self.circuit_breaker.record_failure() {
+            crate::notifications::circuit_breaker_opened("agent", &self.config.id);
+        }
     }
 
     /// Get pool statistics.
@@ -640,12 +930,9 @@ impl AgentV2 {
         self.pool.metrics_collector_arc()
     }
 
-    /// Export agent metrics in Prometheus format.
-    ///
-    /// Returns a string containing all metrics collected from agents
-    /// in Prometheus exposition format.
-    pub fn export_prometheus(&self) -> String {
-        self.pool.export_prometheus()
+    /// ID of the pool's source in the global metrics registry.
+    fn metrics_registry_id(&self) -> String {
+        format!("agent-pool:{}", self.config.id)
     }
 
     /// Get the pool's config pusher.
@@ -710,6 +997,9 @@ impl AgentV2 {
             handle.abort();
         }
 
+        MetricsRegistry::global()
+            .unregister_source(&self.metrics_registry_id(), &self.metrics_source);
+
         // Remove from pool - this gracefully closes connections
         if let Err(e) = self.pool.remove_agent(&self.config.id).await {
             warn!(
@@ -737,6 +1027,20 @@ impl AgentV2 {
     }
 }
 
+/// Config event an agent subscribes to for a protocol event type.
+fn event_kind(event_type: EventType) -> Option<AgentEvent> {
+    match event_type {
+        EventType::RequestHeaders => Some(AgentEvent::RequestHeaders),
+        EventType::RequestBodyChunk => Some(AgentEvent::RequestBody),
+        EventType::ResponseHeaders => Some(AgentEvent::ResponseHeaders),
+        EventType::ResponseBodyChunk => Some(AgentEvent::ResponseBody),
+        EventType::RequestComplete => Some(AgentEvent::Log),
+        EventType::WebSocketFrame => Some(AgentEvent::WebSocketFrame),
+        EventType::GuardrailInspect => Some(AgentEvent::Guardrail),
+        EventType::Configure => None,
+    }
+}
+
 /// Convert config load balance strategy to protocol load balance strategy.
 fn convert_lb_strategy(strategy: LoadBalanceStrategy) -> ProtocolLBStrategy {
     match strategy {
@@ -747,6 +1051,21 @@ fn convert_lb_strategy(strategy: LoadBalanceStrategy) -> ProtocolLBStrategy {
     }
 }
 
+/// Convert the config's shared-memory settings to the protocol's
+#[cfg(feature = "agent-shared-memory")]
+fn convert_shared_memory(
+    config: &zentinel_config::AgentSharedMemoryConfig,
+) -> zentinel_agent_protocol::v2::ShmConfig {
+    let defaults = zentinel_agent_protocol::v2::ShmConfig::default();
+    zentinel_agent_protocol::v2::ShmConfig {
+        dir: config.dir.clone().unwrap_or(defaults.dir),
+        slot_count: config.slot_count,
+        slot_size: config.slot_size,
+        min_chunk_size: config.min_chunk_size,
+        mode: config.mode,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -770,4 +1089,32 @@ mod tests {
             ProtocolLBStrategy::Random
         );
     }
+
+    #[cfg(feature = "agent-shared-memory")]
+    #[test]
+    fn test_convert_shared_memory() {
+        let config = zentinel_config::AgentSharedMemoryConfig {
+            slot_count: 8,
+            mode: 0o660,
+            ..Default::default()
+        };
+        let shm = convert_shared_memory(&config);
+        assert_eq!(shm.slot_count, 8);
+        assert_eq!(shm.slot_size, config.slot_size);
+        assert_eq!(shm.min_chunk_size, config.min_chunk_size);
+        assert_eq!(shm.mode, 0o660);
+        assert_eq!(
+            shm.dir,
+            zentinel_agent_protocol::v2::ShmConfig::default().dir
+        );
+
+        let config = zentinel_config::AgentSharedMemoryConfig {
+            dir: Some("/run/zentinel/shm".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            convert_shared_memory(&config).dir,
+            std::path::PathBuf::from("/run/zentinel/shm")
+        );
+    }
 }
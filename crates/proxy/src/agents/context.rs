@@ -1,8 +1,15 @@
 //! Agent call context.
 
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
 use zentinel_agent_protocol::RequestMetadata;
 use zentinel_common::CorrelationId;
 
+use super::DecisionMergePolicy;
+use crate::audit_store::AuditRecord;
+
 /// Agent call context.
 ///
 /// Contains all information needed for an agent to process a request,
@@ -20,6 +27,12 @@ pub struct AgentCallContext {
     pub request_body: Option<Vec<u8>>,
     /// Response body buffer (if body inspection enabled)
     pub response_body: Option<Vec<u8>>,
+    /// Total agent time budget for the request (route `agent-budget-ms`)
+    pub agent_budget: Option<Duration>,
+    /// How agent decisions are combined (route `decision-merge`)
+    pub decision_merge: Arc<DecisionMergePolicy>,
+    /// Collects every agent response, in call order (explain dry runs)
+    pub trace: Option<Arc<Mutex<Vec<AuditRecord>>>>,
 }
 
 impl AgentCallContext {
@@ -32,6 +45,9 @@ impl AgentCallContext {
             upstream_id: None,
             request_body: None,
             response_body: None,
+            agent_budget: None,
+            decision_merge: Arc::default(),
+            trace: None,
         }
     }
 
@@ -46,4 +62,10 @@ impl AgentCallContext {
         self.upstream_id = Some(upstream_id.into());
         self
     }
+
+    /// Set the total agent time budget for the request.
+    pub fn with_agent_budget(mut self, budget: Duration) -> Self {
+        self.agent_budget = Some(budget);
+        self
+    }
 }
@@ -2,7 +2,10 @@
 
 use std::collections::HashMap;
 
-use zentinel_agent_protocol::{AgentResponse, AuditMetadata, BodyMutation, Decision, HeaderOp};
+use zentinel_agent_protocol::{
+    AgentResponse, AuditMetadata, BodyMutation, Decision, HeaderOp, RequestTag,
+};
+use zentinel_config::DecisionMergeStrategy;
 
 /// Agent decision combining all agent responses.
 #[derive(Debug, Clone)]
@@ -23,6 +26,8 @@ pub struct AgentDecision {
     pub audit: Vec<AuditMetadata>,
     /// Routing metadata updates
     pub routing_metadata: HashMap<String, String>,
+    /// Request tags, in the order the agents set them
+    pub tags: Vec<RequestTag>,
     /// Whether agent needs more data to make final decision (streaming mode)
     pub needs_more: bool,
     /// Mutation for request body chunk (streaming mode)
@@ -61,6 +66,7 @@ impl AgentDecision {
             response_headers: Vec::new(),
             audit: Vec::new(),
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             needs_more: false,
             request_body_mutation: None,
             response_body_mutation: None,
@@ -80,6 +86,7 @@ impl AgentDecision {
             response_headers: Vec::new(),
             audit: Vec::new(),
             routing_metadata: HashMap::new(),
+            tags: Vec::new(),
             needs_more: false,
             request_body_mutation: None,
             response_body_mutation: None,
@@ -107,7 +114,7 @@ impl AgentDecision {
     /// Merge another decision into this one.
     ///
     /// If other decision is not allow, use it as the action.
-    /// Header modifications, audit metadata, and routing metadata are merged.
+    /// Header modifications, audit metadata, routing metadata and tags are merged.
     pub fn merge(&mut self, other: AgentDecision) {
         // If other decision is not allow, use it (and keep its attribution)
         if !other.is_allow() {
@@ -124,6 +131,7 @@ impl AgentDecision {
 
         // Merge routing metadata
         self.routing_metadata.extend(other.routing_metadata);
+        self.tags.extend(other.tags);
 
         // Streaming: if any agent needs more, we need more
         if other.needs_more {
@@ -140,6 +148,155 @@ impl AgentDecision {
     }
 }
 
+/// Route-level policy for combining the decisions of multiple agents.
+#[derive(Debug, Clone, Default)]
+pub struct DecisionMergePolicy {
+    /// Merge strategy from the route's `decision-merge` policy
+    pub strategy: DecisionMergeStrategy,
+    /// Agent filter priorities (`priority-override`); unlisted agents are 0
+    pub priorities: HashMap<String, i32>,
+}
+
+impl DecisionMergePolicy {
+    /// Priority of an agent's decisions under `priority-override`.
+    fn priority(&self, agent_id: Option<&str>) -> i32 {
+        agent_id
+            .and_then(|id| self.priorities.get(id))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// The action taken from a single agent's decision, with its attribution.
+#[derive(Debug)]
+struct Verdict {
+    action: AgentAction,
+    decided_by: Option<String>,
+}
+
+/// Combines agent decisions one at a time according to a merge policy.
+///
+/// Header modifications, audit metadata, routing metadata, tags and body
+/// mutations are always merged from every decision; the strategy only selects
+/// which action (and attribution) the combined decision carries.
+pub struct DecisionMerger<'a> {
+    policy: &'a DecisionMergePolicy,
+    combined: AgentDecision,
+    /// Selected non-allow action, if any
+    verdict: Option<Verdict>,
+    /// Confidence of the selected verdict (`score-based`)
+    verdict_confidence: f32,
+    /// Summed confidence of non-allow decisions (`score-based`)
+    score: f32,
+    /// Highest priority that produced a decision (`priority-override`)
+    top_priority: Option<i32>,
+}
+
+impl<'a> DecisionMerger<'a> {
+    /// Start merging with an allow decision.
+    pub fn new(policy: &'a DecisionMergePolicy) -> Self {
+        Self {
+            policy,
+            combined: AgentDecision::default_allow(),
+            verdict: None,
+            verdict_confidence: 0.0,
+            score: 0.0,
+            top_priority: None,
+        }
+    }
+
+    /// Add an agent's decision.
+    ///
+    /// Returns `true` once the outcome is final and remaining agents can be
+    /// skipped.
+    pub fn add(&mut self, mut decision: AgentDecision) -> bool {
+        let verdict = Verdict {
+            action: std::mem::replace(&mut decision.action, AgentAction::Allow),
+            decided_by: decision.decided_by.take(),
+        };
+        let allows = matches!(verdict.action, AgentAction::Allow);
+        let confidence = decision
+            .audit
+            .iter()
+            .filter_map(|audit| audit.confidence)
+            .reduce(f32::max)
+            .unwrap_or(1.0);
+        self.combined.merge(decision);
+
+        match self.policy.strategy {
+            DecisionMergeStrategy::FirstBlockWins | DecisionMergeStrategy::AllMustAllow => {
+                if !allows {
+                    self.verdict = Some(verdict);
+                }
+                !allows
+            }
+            DecisionMergeStrategy::ScoreBased { threshold } => {
+                if !allows {
+                    self.score += confidence;
+                    if self.verdict.is_none() || confidence > self.verdict_confidence {
+                        self.verdict_confidence = confidence;
+                        self.verdict = Some(verdict);
+                    }
+                }
+                self.score >= threshold
+            }
+            DecisionMergeStrategy::PriorityOverride => {
+                let priority = self.policy.priority(verdict.decided_by.as_deref());
+                match self.top_priority {
+                    Some(top) if priority < top => {}
+                    Some(top) if priority == top => {
+                        if self.verdict.is_none() && !allows {
+                            self.verdict = Some(verdict);
+                        }
+                    }
+                    _ => {
+                        self.top_priority = Some(priority);
+                        self.verdict = (!allows).then_some(verdict);
+                    }
+                }
+                // A later, higher-priority agent may still overrule
+                false
+            }
+        }
+    }
+
+    /// Record an agent that failed without producing a decision and was
+    /// skipped under fail-open.
+    ///
+    /// Only `all-must-allow` treats this as a block. Returns `true` once the
+    /// outcome is final.
+    pub fn add_failure(&mut self, agent_id: &str) -> bool {
+        if self.policy.strategy != DecisionMergeStrategy::AllMustAllow {
+            return false;
+        }
+        if self.verdict.is_none() {
+            self.verdict = Some(Verdict {
+                action: AgentAction::Block {
+                    status: 503,
+                    body: Some("Service unavailable".to_string()),
+                    headers: None,
+                },
+                decided_by: Some(agent_id.to_string()),
+            });
+        }
+        true
+    }
+
+    /// Produce the combined decision.
+    pub fn finish(self) -> AgentDecision {
+        let mut combined = self.combined;
+        let verdict = match self.policy.strategy {
+            DecisionMergeStrategy::ScoreBased { threshold } if self.score < threshold => None,
+            _ => self.verdict,
+        };
+        if let Some(verdict) = verdict {
+            combined.action = verdict.action;
+            combined.decided_by = verdict.decided_by;
+        }
+        combined
+    }
+}
+
 impl From<AgentResponse> for AgentDecision {
     fn from(response: AgentResponse) -> Self {
         let action = match response.decision {
@@ -170,6 +327,7 @@ impl From<AgentResponse> for AgentDecision {
             response_headers: response.response_headers,
             audit: vec![response.audit],
             routing_metadata: response.routing_metadata,
+            tags: response.tags,
             needs_more: response.needs_more,
             request_body_mutation: response.request_body_mutation,
             response_body_mutation: response.response_body_mutation,
@@ -216,6 +374,135 @@ mod tests {
         assert_eq!(combined.decided_by.as_deref(), Some("waf"));
     }
 
+    fn scored_block(agent_id: &str, confidence: Option<f32>) -> AgentDecision {
+        let mut decision = AgentDecision::block(403, "Forbidden").with_decided_by(agent_id);
+        decision.audit.push(AuditMetadata {
+            confidence,
+            ..Default::default()
+        });
+        decision
+    }
+
+    fn policy(strategy: DecisionMergeStrategy) -> DecisionMergePolicy {
+        DecisionMergePolicy {
+            strategy,
+            priorities: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn first_block_wins_stops_at_first_block() {
+        let policy = policy(DecisionMergeStrategy::FirstBlockWins);
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(!merger.add(AgentDecision::default_allow().with_decided_by("auth")));
+        assert!(merger.add(AgentDecision::block(403, "Forbidden").with_decided_by("waf")));
+
+        let decision = merger.finish();
+        assert!(!decision.is_allow());
+        assert_eq!(decision.decided_by.as_deref(), Some("waf"));
+    }
+
+    #[test]
+    fn all_must_allow_blocks_on_skipped_agent() {
+        let policy = policy(DecisionMergeStrategy::AllMustAllow);
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(!merger.add(AgentDecision::default_allow().with_decided_by("auth")));
+        assert!(merger.add_failure("waf"));
+
+        let decision = merger.finish();
+        assert!(matches!(
+            decision.action,
+            AgentAction::Block { status: 503, .. }
+        ));
+        assert_eq!(decision.decided_by.as_deref(), Some("waf"));
+    }
+
+    #[test]
+    fn failures_are_ignored_outside_all_must_allow() {
+        let policy = policy(DecisionMergeStrategy::FirstBlockWins);
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(!merger.add_failure("waf"));
+        assert!(merger.finish().is_allow());
+    }
+
+    #[test]
+    fn score_based_blocks_once_threshold_reached() {
+        let policy = policy(DecisionMergeStrategy::ScoreBased { threshold: 1.0 });
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(!merger.add(scored_block("bot", Some(0.4))));
+        assert!(merger.add(scored_block("waf", Some(0.7))));
+
+        let decision = merger.finish();
+        assert!(!decision.is_allow());
+        // The most confident vote supplies the block
+        assert_eq!(decision.decided_by.as_deref(), Some("waf"));
+    }
+
+    #[test]
+    fn score_based_allows_below_threshold() {
+        let policy = policy(DecisionMergeStrategy::ScoreBased { threshold: 1.0 });
+        let mut merger = DecisionMerger::new(&policy);
+
+        merger.add(scored_block("bot", Some(0.4)));
+        merger.add(AgentDecision::default_allow().with_decided_by("waf"));
+
+        let decision = merger.finish();
+        assert!(decision.is_allow());
+        assert!(decision.decided_by.is_none());
+        // Audit from sub-threshold votes is kept
+        assert_eq!(decision.audit.len(), 1);
+    }
+
+    #[test]
+    fn score_based_counts_unscored_blocks_as_full_confidence() {
+        let policy = policy(DecisionMergeStrategy::ScoreBased { threshold: 1.0 });
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(merger.add(AgentDecision::block(403, "Forbidden").with_decided_by("waf")));
+        assert!(!merger.finish().is_allow());
+    }
+
+    #[test]
+    fn priority_override_lets_higher_priority_allow_overrule_block() {
+        let mut policy = policy(DecisionMergeStrategy::PriorityOverride);
+        policy.priorities.insert("allowlist".to_string(), 10);
+        let mut merger = DecisionMerger::new(&policy);
+
+        assert!(!merger.add(AgentDecision::block(403, "Forbidden").with_decided_by("waf")));
+        assert!(!merger.add(AgentDecision::default_allow().with_decided_by("allowlist")));
+
+        assert!(merger.finish().is_allow());
+    }
+
+    #[test]
+    fn priority_override_ignores_lower_priority_block() {
+        let mut policy = policy(DecisionMergeStrategy::PriorityOverride);
+        policy.priorities.insert("allowlist".to_string(), 10);
+        let mut merger = DecisionMerger::new(&policy);
+
+        merger.add(AgentDecision::default_allow().with_decided_by("allowlist"));
+        merger.add(AgentDecision::block(403, "Forbidden").with_decided_by("waf"));
+
+        assert!(merger.finish().is_allow());
+    }
+
+    #[test]
+    fn priority_override_equal_priorities_behave_like_first_block_wins() {
+        let policy = policy(DecisionMergeStrategy::PriorityOverride);
+        let mut merger = DecisionMerger::new(&policy);
+
+        merger.add(AgentDecision::default_allow().with_decided_by("auth"));
+        merger.add(AgentDecision::block(403, "Forbidden").with_decided_by("waf"));
+        merger.add(AgentDecision::block(429, "Slow down").with_decided_by("ratelimit"));
+
+        let decision = merger.finish();
+        assert_eq!(decision.decided_by.as_deref(), Some("waf"));
+    }
+
     #[test]
     fn from_response_sets_decided_by() {
         let response = AgentResponse::block(403, None);
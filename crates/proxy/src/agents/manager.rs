@@ -1,43 +1,77 @@
 //! Agent manager for coordinating external processing agents.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
-use base64::{engine::general_purpose::STANDARD, Engine as _};
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use dashmap::DashMap;
 use futures::future::join_all;
 use pingora_timeout::timeout;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, trace, warn};
 use zentinel_agent_protocol::{
-    v2::MetricsCollector, AgentResponse, EventType, GuardrailInspectEvent, RequestBodyChunkEvent,
-    RequestHeadersEvent, ResponseBodyChunkEvent, ResponseHeadersEvent, WebSocketFrameEvent,
+    buffer_pool::Pooled,
+    v2::{AgentHandlerV2, MetricsCollector, StateHandler},
+    AgentResponse, BinaryRequestBodyChunkEvent, BinaryResponseBodyChunkEvent, EventType,
+    GuardrailInspectEvent, MultipartPart, RequestHeadersEvent, ResponseHeadersEvent,
+    WebSocketFrameEvent,
 };
 use zentinel_common::{
     errors::{ZentinelError, ZentinelResult},
     types::CircuitBreakerConfig,
     CircuitBreaker,
 };
-use zentinel_config::{AgentConfig, FailureMode};
+use zentinel_config::{AgentConfig, AgentTransport, FailureMode};
 
-use super::agent_v2::AgentV2;
+use super::agent_v2::{AgentCall, AgentV2};
 use super::context::AgentCallContext;
-use super::decision::AgentDecision;
+use super::decision::{AgentDecision, DecisionMerger};
 use super::metrics::AgentMetrics;
+use super::state_store::AgentStateStore;
+use crate::audit_store::{AuditRecord, AuditStore};
+use crate::chaos;
+
+/// Agent time spent on one request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentTimings {
+    /// Wall time spent waiting for agents; agents called in parallel count once
+    pub total: Duration,
+    /// Time spent in each agent, by agent ID
+    pub by_agent: HashMap<String, Duration>,
+}
+
+/// Configured agents with their per-agent semaphores.
+///
+/// Never mutated: a change to the agent set builds a new snapshot and swaps
+/// it in, so requests read agents without taking a lock. Circuit breakers
+/// are owned by each [`AgentV2`].
+struct AgentSnapshot {
+    /// Agents by ID
+    agents: HashMap<String, Arc<AgentV2>>,
+    /// Per-agent semaphores for queue isolation (prevents noisy neighbor problem)
+    semaphores: HashMap<String, Arc<Semaphore>>,
+}
 
 /// Agent manager handling all external agents.
 ///
 /// All agents use the v2 protocol with bidirectional streaming, capabilities,
 /// health reporting, metrics export, and flow control.
 pub struct AgentManager {
-    /// Configured agents
-    agents: Arc<RwLock<HashMap<String, Arc<AgentV2>>>>,
-    /// Circuit breakers per agent
-    circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    /// Configured agents, read lock-free on every request
+    snapshot: ArcSwap<AgentSnapshot>,
     /// Global agent metrics
     metrics: Arc<AgentMetrics>,
-    /// Per-agent semaphores for queue isolation (prevents noisy neighbor problem)
-    agent_semaphores: Arc<RwLock<HashMap<String, Arc<Semaphore>>>>,
+    /// Agent time spent per correlation ID (only tracked for routes with a budget)
+    agent_time_spent: DashMap<String, Duration>,
+    /// Agent time per correlation ID, for the request's latency breakdown
+    agent_timings: DashMap<String, AgentTimings>,
+    /// Store every agent response is recorded in, when configured
+    audit_store: OnceLock<Arc<AuditStore>>,
+    /// Session state handler, also given to agents added on reload
+    state_handler: OnceLock<Arc<dyn StateHandler>>,
 }
 
 impl AgentManager {
@@ -47,45 +81,39 @@ impl AgentManager {
     /// agent from affecting other agents (noisy neighbor problem). The concurrency
     /// limit is configured per-agent via `max_concurrent_calls` in the agent config.
     pub async fn new(agents: Vec<AgentConfig>) -> ZentinelResult<Self> {
+        Self::with_in_process_agents(agents, HashMap::new()).await
+    }
+
+    /// Create new agent manager, serving some agents from handlers in this
+    /// process.
+    ///
+    /// `in_process` maps agent IDs to handlers; those agents skip their
+    /// configured transport. Every handler has to belong to a configured agent,
+    /// and every agent with the `in-process` transport needs a handler.
+    pub async fn with_in_process_agents(
+        agents: Vec<AgentConfig>,
+        mut in_process: HashMap<String, Arc<dyn AgentHandlerV2>>,
+    ) -> ZentinelResult<Self> {
         info!(agent_count = agents.len(), "Creating agent manager");
 
         let mut agent_map = HashMap::new();
-        let breakers = HashMap::new();
         let mut semaphores = HashMap::new();
 
         for config in agents {
-            debug!(
-                agent_id = %config.id,
-                transport = ?config.transport,
-                timeout_ms = config.timeout_ms,
-                failure_mode = ?config.failure_mode,
-                max_concurrent_calls = config.max_concurrent_calls,
-                "Configuring agent"
-            );
-
-            // Create per-agent semaphore for queue isolation
-            let semaphore = Arc::new(Semaphore::new(config.max_concurrent_calls));
-
-            let circuit_breaker = Arc::new(CircuitBreaker::new(
-                config.circuit_breaker.unwrap_or_default(),
-            ));
-
-            trace!(
-                agent_id = %config.id,
-                max_concurrent_calls = config.max_concurrent_calls,
-                pool_config = ?config.pool,
-                "Creating agent instance with internal pool"
-            );
-
-            let agent = Arc::new(AgentV2::new(config.clone(), circuit_breaker));
-
-            agent_map.insert(config.id.clone(), agent);
-            semaphores.insert(config.id.clone(), semaphore);
+            let handler = in_process.remove(&config.id);
+            let (agent, semaphore) = build_agent(config, handler)?;
+            semaphores.insert(agent.id().to_string(), semaphore);
+            agent_map.insert(agent.id().to_string(), agent);
+        }
 
-            debug!(
-                agent_id = %config.id,
-                "Agent configured successfully"
-            );
+        if let Some(agent_id) = in_process.keys().next() {
+            return Err(ZentinelError::Agent {
+                agent: agent_id.clone(),
+                message: "In-process handler registered for an agent that is not configured"
+                    .to_string(),
+                event: "initialize".to_string(),
+                source: None,
+            });
         }
 
         info!(
@@ -94,23 +122,129 @@ impl AgentManager {
         );
 
         Ok(Self {
-            agents: Arc::new(RwLock::new(agent_map)),
-            circuit_breakers: Arc::new(RwLock::new(breakers)),
+            snapshot: ArcSwap::from_pointee(AgentSnapshot {
+                agents: agent_map,
+                semaphores,
+            }),
             metrics: Arc::new(AgentMetrics::default()),
-            agent_semaphores: Arc::new(RwLock::new(semaphores)),
+            agent_time_spent: DashMap::new(),
+            agent_timings: DashMap::new(),
+            audit_store: OnceLock::new(),
+            state_handler: OnceLock::new(),
         })
     }
 
+    /// Apply a new agent set.
+    ///
+    /// Agents whose config is unchanged are kept with their connections,
+    /// circuit breakers and semaphores. New and changed agents are connected
+    /// and swapped in with a new snapshot, then the agents they replace are
+    /// shut down. A changed in-process agent keeps its handler; agents that
+    /// cannot be built are left out and logged.
+    pub async fn reload(&self, agents: Vec<AgentConfig>) {
+        let current = self.snapshot.load_full();
+        let mut agent_map = HashMap::with_capacity(agents.len());
+        let mut semaphores = HashMap::with_capacity(agents.len());
+        let mut started = Vec::new();
+
+        for config in agents {
+            let existing = current.agents.get(&config.id);
+            if let Some(agent) = existing.filter(|agent| same_agent(agent.config(), &config)) {
+                if let Some(semaphore) = current.semaphores.get(&config.id) {
+                    semaphores.insert(config.id.clone(), Arc::clone(semaphore));
+                }
+                agent_map.insert(config.id.clone(), Arc::clone(agent));
+                continue;
+            }
+
+            let agent_id = config.id.clone();
+            let handler = existing.and_then(|agent| agent.in_process_handler());
+            match build_agent(config, handler) {
+                Ok((agent, semaphore)) => {
+                    if let Some(handler) = self.state_handler.get() {
+                        agent.set_state_handler(Arc::clone(handler));
+                    }
+                    started.push(Arc::clone(&agent));
+                    semaphores.insert(agent_id.clone(), semaphore);
+                    agent_map.insert(agent_id, agent);
+                }
+                Err(e) => {
+                    error!(
+                        agent_id = %agent_id,
+                        error = %e,
+                        "Failed to configure agent on reload, leaving it out"
+                    );
+                }
+            }
+        }
+
+        for agent in &started {
+            if let Err(e) = agent.initialize().await {
+                error!(
+                    agent_id = %agent.id(),
+                    error = %e,
+                    "Failed to initialize agent on reload"
+                );
+            }
+        }
+
+        let snapshot = Arc::new(AgentSnapshot {
+            agents: agent_map,
+            semaphores,
+        });
+        self.snapshot.store(Arc::clone(&snapshot));
+
+        for (id, agent) in current.agents.iter() {
+            if !snapshot
+                .agents
+                .get(id)
+                .is_some_and(|kept| Arc::ptr_eq(kept, agent))
+            {
+                debug!(agent_id = %id, "Shutting down replaced agent");
+                agent.shutdown().await;
+            }
+        }
+
+        info!(
+            configured_agents = snapshot.agents.len(),
+            started = started.len(),
+            "Agent set reloaded"
+        );
+    }
+
+    /// Serve agent session state requests from `store`.
+    ///
+    /// Must be called before [`initialize`](Self::initialize) so the
+    /// agents' connections pick it up.
+    pub async fn set_state_store(&self, store: Arc<AgentStateStore>) {
+        let handler: Arc<dyn StateHandler> = store;
+        if self.state_handler.set(Arc::clone(&handler)).is_err() {
+            warn!("Agent state store already set, keeping the first one");
+            return;
+        }
+        let snapshot = self.snapshot.load();
+        for agent in snapshot.agents.values() {
+            agent.set_state_handler(Arc::clone(&handler));
+        }
+    }
+
+    /// Record every agent response in `store`.
+    pub fn set_audit_store(&self, store: Arc<AuditStore>) {
+        if self.audit_store.set(store).is_err() {
+            warn!("Agent audit store already set, keeping the first one");
+        }
+    }
+
     /// Check if any of the given route agents handle a specific event type.
     pub async fn any_agent_handles_event(
         &self,
         route_agents: &[String],
         event_type: EventType,
     ) -> bool {
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load();
         route_agents
             .iter()
-            .filter_map(|id| agents.get(id))
+            .filter_map(|id| snapshot.agents.get(id))
             .any(|agent| agent.handles_event(event_type))
     }
 
@@ -126,38 +260,50 @@ impl AgentManager {
         mut headers: HashMap<String, Vec<String>>,
         route_agents: &[(String, FailureMode)],
     ) -> ZentinelResult<AgentDecision> {
-        let method = headers
-            .remove(":method")
-            .and_then(|mut v| {
-                if v.is_empty() {
-                    None
-                } else {
-                    Some(v.swap_remove(0))
-                }
-            })
-            .unwrap_or_else(|| "GET".to_string());
-        let uri = headers
-            .remove(":path")
-            .and_then(|mut v| {
-                if v.is_empty() {
-                    None
-                } else {
-                    Some(v.swap_remove(0))
-                }
-            })
-            .unwrap_or_else(|| "/".to_string());
-        let event = RequestHeadersEvent {
-            metadata: ctx.metadata.clone(),
-            method,
-            uri,
-            headers,
+        let pseudo = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.first())
+                .map(String::as_str)
         };
 
+        // Recycled event: its strings and header map keep their capacity
+        // from earlier requests on this thread
+        let mut event = Pooled::<RequestHeadersEvent>::acquire();
+        event.metadata.fill_from(&ctx.metadata);
+        event.method.push_str(pseudo(":method").unwrap_or("GET"));
+        event.uri.push_str(pseudo(":path").unwrap_or("/"));
+        event.headers.extend(
+            headers
+                .drain()
+                .filter(|(name, _)| name != ":method" && name != ":path"),
+        );
+
         // Use parallel processing for better latency with multiple agents
-        self.process_event_parallel(EventType::RequestHeaders, &event, route_agents, ctx)
+        self.process_event_parallel(EventType::RequestHeaders, &*event, route_agents, ctx)
             .await
     }
 
+    /// Process request headers through the agents after `agent_id` in
+    /// `route_agents`.
+    ///
+    /// Used once the client passed a challenge decided by `agent_id`: the
+    /// merge stopped at the challenger, so the agents after it have not
+    /// contributed to the decision yet.
+    pub async fn process_request_headers_after(
+        &self,
+        ctx: &AgentCallContext,
+        headers: HashMap<String, Vec<String>>,
+        route_agents: &[(String, FailureMode)],
+        agent_id: &str,
+    ) -> ZentinelResult<AgentDecision> {
+        let remaining = route_agents
+            .iter()
+            .position(|(id, _)| id == agent_id)
+            .map_or(&[][..], |i| &route_agents[i + 1..]);
+        self.process_request_headers(ctx, headers, remaining).await
+    }
+
     /// Process request body chunk through agents.
     pub async fn process_request_body(
         &self,
@@ -175,13 +321,14 @@ impl AgentManager {
             BodyLimitsResult::Proceed(agents) => agents,
         };
 
-        let event = RequestBodyChunkEvent {
+        let event = BinaryRequestBodyChunkEvent {
             correlation_id: ctx.correlation_id.to_string(),
-            data: STANDARD.encode(data),
+            data: Bytes::copy_from_slice(data),
             is_last,
             total_size: ctx.request_body.as_ref().map(|b| b.len()),
             chunk_index: 0, // Buffer mode sends entire body as single chunk
             bytes_received: data.len(),
+            part: None,
         };
 
         self.process_event(EventType::RequestBodyChunk, &event, &inspecting_agents, ctx)
@@ -225,15 +372,66 @@ impl AgentManager {
             BodyLimitsResult::Proceed(agents) => agents,
         };
 
-        let event = RequestBodyChunkEvent {
+        let event = BinaryRequestBodyChunkEvent {
             correlation_id: ctx.correlation_id.to_string(),
-            data: STANDARD.encode(data),
+            data: Bytes::copy_from_slice(data),
             is_last,
             total_size,
             chunk_index,
             bytes_received,
+            part: None,
+        };
+
+        self.process_event(EventType::RequestBodyChunk, &event, &inspecting_agents, ctx)
+            .await
+    }
+
+    /// Process a chunk of one part of a multipart/form-data body.
+    ///
+    /// `is_last` marks the last chunk of the part and `bytes_received`
+    /// counts the part's bytes, which body inspection limits apply to.
+    pub async fn process_request_body_part(
+        &self,
+        ctx: &AgentCallContext,
+        part: &MultipartPart,
+        data: &[u8],
+        is_last: bool,
+        chunk_index: u32,
+        bytes_received: usize,
+        route_agents: &[String],
+    ) -> ZentinelResult<AgentDecision> {
+        trace!(
+            correlation_id = %ctx.correlation_id,
+            part_index = part.index,
+            part_name = %part.name,
+            chunk_index = chunk_index,
+            chunk_size = data.len(),
+            is_last = is_last,
+            "Processing multipart body chunk"
+        );
+
+        let inspecting_agents = match self
+            .apply_body_limits(
+                ctx,
+                route_agents,
+                bytes_received,
+                EventType::RequestBodyChunk,
+            )
+            .await
+        {
+            BodyLimitsResult::Block(decision) => return Ok(*decision),
+            BodyLimitsResult::Proceed(agents) => agents,
         };
 
+        let event = BinaryRequestBodyChunkEvent::new(
+            ctx.correlation_id.to_string(),
+            Bytes::copy_from_slice(data),
+            chunk_index,
+            is_last,
+        )
+        .with_bytes_received(bytes_received)
+        .with_part(part.clone());
+
         self.process_event(EventType::RequestBodyChunk, &event, &inspecting_agents, ctx)
             .await
     }
@@ -267,9 +465,9 @@ impl AgentManager {
             BodyLimitsResult::Proceed(agents) => agents,
         };
 
-        let event = ResponseBodyChunkEvent {
+        let event = BinaryResponseBodyChunkEvent {
             correlation_id: ctx.correlation_id.to_string(),
-            data: STANDARD.encode(data),
+            data: Bytes::copy_from_slice(data),
             is_last,
             total_size,
             chunk_index,
@@ -322,8 +520,9 @@ impl AgentManager {
         );
 
         // Get relevant agents for this route that handle WebSocket frames
-        let agents = self.agents.read().await;
-        let relevant_agents: Vec<_> = agents
+        let snapshot = self.snapshot.load_full();
+        let relevant_agents: Vec<_> = snapshot
+            .agents
             .values()
             .filter(|agent| agent.handles_event(EventType::WebSocketFrame))
             .collect();
@@ -370,11 +569,16 @@ impl AgentManager {
 
             // Call agent with timeout
             let start = Instant::now();
-            let timeout_duration = Duration::from_millis(agent.timeout_ms());
+            let timeout_duration =
+                Duration::from_millis(agent.timeout_ms_for(EventType::WebSocketFrame));
 
             match timeout(
                 timeout_duration,
-                agent.call_event(EventType::WebSocketFrame, &event),
+                chaos::agent_call(
+                    agent.id(),
+                    EventType::WebSocketFrame,
+                    agent.call_event(EventType::WebSocketFrame, &event),
+                ),
             )
             .await
             {
@@ -428,7 +632,7 @@ impl AgentManager {
                     warn!(
                         agent_id = %agent.id(),
                         correlation_id = %event.correlation_id,
-                        timeout_ms = agent.timeout_ms(),
+                        timeout_ms = agent.timeout_ms_for(EventType::WebSocketFrame),
                         failure_mode = ?agent.failure_mode(),
                         "WebSocket frame agent call timed out"
                     );
@@ -448,7 +652,7 @@ impl AgentManager {
     }
 
     /// Process an event through relevant agents.
-    async fn process_event<T: serde::Serialize>(
+    async fn process_event<T: AgentCall>(
         &self,
         event_type: EventType,
         event: &T,
@@ -463,10 +667,10 @@ impl AgentManager {
         );
 
         // Get relevant agents for this route and event type
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load_full();
         let relevant_agents: Vec<_> = route_agents
             .iter()
-            .filter_map(|id| agents.get(id))
+            .filter_map(|id| snapshot.agents.get(id))
             .filter(|agent| agent.handles_event(event_type))
             .collect();
 
@@ -488,7 +692,7 @@ impl AgentManager {
         );
 
         // Process through each agent sequentially
-        let mut combined_decision = AgentDecision::default_allow();
+        let mut merger = DecisionMerger::new(&ctx.decision_merge);
 
         for (agent_index, agent) in relevant_agents.iter().enumerate() {
             trace!(
@@ -499,10 +703,18 @@ impl AgentManager {
                 "Processing event through agent"
             );
 
+            // Enforce the request's total agent time budget
+            let remaining_budget = self.remaining_budget(ctx);
+            if remaining_budget == Some(Duration::ZERO) {
+                match self.budget_exhausted(ctx, agent, agent.failure_mode()) {
+                    Some(decision) => return Ok(decision),
+                    None if merger.add_failure(agent.id()) => break,
+                    None => continue,
+                }
+            }
+
             // Acquire per-agent semaphore permit (queue isolation)
-            let semaphores = self.agent_semaphores.read().await;
-            let agent_semaphore = semaphores.get(agent.id()).cloned();
-            drop(semaphores); // Release lock before awaiting
+            let agent_semaphore = snapshot.semaphores.get(agent.id()).cloned();
 
             let _permit = match agent_semaphore {
                 Some(semaphore) => {
@@ -534,6 +746,8 @@ impl AgentManager {
                     None
                 }
             };
+            // Ramp up after the agent (re)connected
+            let _ramp_permit = agent.slow_start_permit().await;
 
             // Check circuit breaker
             if !agent.circuit_breaker().is_closed() {
@@ -554,21 +768,33 @@ impl AgentManager {
                     return Ok(AgentDecision::block(503, "Service unavailable")
                         .with_decided_by(agent.id()));
                 }
+                if merger.add_failure(agent.id()) {
+                    break;
+                }
                 continue;
             }
 
             // Call agent with timeout (using pingora-timeout for efficiency)
             let start = Instant::now();
-            let timeout_duration = Duration::from_millis(agent.timeout_ms());
+            let agent_timeout = Duration::from_millis(agent.timeout_ms_for(event_type));
+            let timeout_duration = budget_timeout(agent_timeout, remaining_budget);
 
             trace!(
                 correlation_id = %ctx.correlation_id,
                 agent_id = %agent.id(),
-                timeout_ms = agent.timeout_ms(),
+                timeout_ms = agent.timeout_ms_for(event_type),
                 "Calling agent"
             );
 
-            match timeout(timeout_duration, agent.call_event(event_type, event)).await {
+            let result = timeout(
+                timeout_duration,
+                chaos::agent_call(agent.id(), event_type, event.call(agent)),
+            )
+            .await;
+            self.charge_budget(ctx, start.elapsed());
+            self.record_agent_time(ctx.correlation_id.as_str(), agent.id(), start.elapsed());
+
+            match result {
                 Ok(Ok(response)) => {
                     let duration = start.elapsed();
                     agent.record_success(duration);
@@ -580,17 +806,17 @@ impl AgentManager {
                         decision = ?response,
                         "Agent call succeeded"
                     );
+                    self.record_audit(ctx, event_type, agent.id(), &response, duration);
 
-                    // Merge response into combined decision (attributed to this agent)
-                    combined_decision.merge(AgentDecision::from_response(response, agent.id()));
+                    // Attribute the response to this agent
+                    let decision = AgentDecision::from_response(response, agent.id());
 
-                    // If decision is to block/redirect/challenge, stop processing
-                    if !combined_decision.is_allow() {
+                    // Stop once the route's merge strategy has a final outcome
+                    if merger.add(decision) {
                         debug!(
                             correlation_id = %ctx.correlation_id,
                             agent_id = %agent.id(),
-                            decision = ?combined_decision,
-                            "Agent returned blocking decision, stopping agent chain"
+                            "Agent decision is final, stopping agent chain"
                         );
                         break;
                     }
@@ -609,13 +835,25 @@ impl AgentManager {
                     if agent.failure_mode() == FailureMode::Closed {
                         return Err(e);
                     }
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
+                }
+                Err(_) if timeout_duration < agent_timeout => {
+                    if let Some(decision) = self.budget_exhausted(ctx, agent, agent.failure_mode())
+                    {
+                        return Ok(decision);
+                    }
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
                 }
                 Err(_) => {
                     agent.record_timeout();
                     warn!(
                         agent_id = %agent.id(),
                         correlation_id = %ctx.correlation_id,
-                        timeout_ms = agent.timeout_ms(),
+                        timeout_ms = agent.timeout_ms_for(event_type),
                         failure_mode = ?agent.failure_mode(),
                         "Agent call timed out"
                     );
@@ -629,10 +867,15 @@ impl AgentManager {
                         return Ok(AgentDecision::block(504, "Gateway timeout")
                             .with_decided_by(agent.id()));
                     }
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
                 }
             }
         }
 
+        let combined_decision = merger.finish();
+
         trace!(
             correlation_id = %ctx.correlation_id,
             decision = ?combined_decision,
@@ -647,7 +890,7 @@ impl AgentManager {
     ///
     /// This is the preferred method for processing events as it respects the
     /// failure mode configured on each filter, not just the agent's default.
-    async fn process_event_with_failure_modes<T: serde::Serialize>(
+    async fn process_event_with_failure_modes<T: AgentCall>(
         &self,
         event_type: EventType,
         event: &T,
@@ -662,10 +905,12 @@ impl AgentManager {
         );
 
         // Get relevant agents for this route and event type, preserving failure modes
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load_full();
         let relevant_agents: Vec<_> = route_agents
             .iter()
-            .filter_map(|(id, failure_mode)| agents.get(id).map(|agent| (agent, *failure_mode)))
+            .filter_map(|(id, failure_mode)| {
+                snapshot.agents.get(id).map(|agent| (agent, *failure_mode))
+            })
             .filter(|(agent, _)| agent.handles_event(event_type))
             .collect();
 
@@ -687,7 +932,7 @@ impl AgentManager {
         );
 
         // Process through each agent sequentially
-        let mut combined_decision = AgentDecision::default_allow();
+        let mut merger = DecisionMerger::new(&ctx.decision_merge);
 
         for (agent_index, (agent, filter_failure_mode)) in relevant_agents.iter().enumerate() {
             trace!(
@@ -699,10 +944,18 @@ impl AgentManager {
                 "Processing event through agent with filter failure mode"
             );
 
+            // Enforce the request's total agent time budget
+            let remaining_budget = self.remaining_budget(ctx);
+            if remaining_budget == Some(Duration::ZERO) {
+                match self.budget_exhausted(ctx, agent, *filter_failure_mode) {
+                    Some(decision) => return Ok(decision),
+                    None if merger.add_failure(agent.id()) => break,
+                    None => continue,
+                }
+            }
+
             // Acquire per-agent semaphore permit (queue isolation)
-            let semaphores = self.agent_semaphores.read().await;
-            let agent_semaphore = semaphores.get(agent.id()).cloned();
-            drop(semaphores); // Release lock before awaiting
+            let agent_semaphore = snapshot.semaphores.get(agent.id()).cloned();
 
             let _permit = if let Some(semaphore) = agent_semaphore {
                 trace!(
@@ -731,6 +984,8 @@ impl AgentManager {
                 );
                 None
             };
+            // Ramp up after the agent (re)connected
+            let _ramp_permit = agent.slow_start_permit().await;
 
             // Check circuit breaker
             if !agent.circuit_breaker().is_closed() {
@@ -752,21 +1007,33 @@ impl AgentManager {
                         .with_decided_by(agent.id()));
                 }
                 // Fail-open: continue to next agent
+                if merger.add_failure(agent.id()) {
+                    break;
+                }
                 continue;
             }
 
             // Call agent with timeout
             let start = Instant::now();
-            let timeout_duration = Duration::from_millis(agent.timeout_ms());
+            let agent_timeout = Duration::from_millis(agent.timeout_ms_for(event_type));
+            let timeout_duration = budget_timeout(agent_timeout, remaining_budget);
 
             trace!(
                 correlation_id = %ctx.correlation_id,
                 agent_id = %agent.id(),
-                timeout_ms = agent.timeout_ms(),
+                timeout_ms = agent.timeout_ms_for(event_type),
                 "Calling agent"
             );
 
-            match timeout(timeout_duration, agent.call_event(event_type, event)).await {
+            let result = timeout(
+                timeout_duration,
+                chaos::agent_call(agent.id(), event_type, event.call(agent)),
+            )
+            .await;
+            self.charge_budget(ctx, start.elapsed());
+            self.record_agent_time(ctx.correlation_id.as_str(), agent.id(), start.elapsed());
+
+            match result {
                 Ok(Ok(response)) => {
                     let duration = start.elapsed();
                     agent.record_success(duration);
@@ -778,17 +1045,17 @@ impl AgentManager {
                         decision = ?response,
                         "Agent call succeeded"
                     );
+                    self.record_audit(ctx, event_type, agent.id(), &response, duration);
 
-                    // Merge response into combined decision (attributed to this agent)
-                    combined_decision.merge(AgentDecision::from_response(response, agent.id()));
+                    // Attribute the response to this agent
+                    let decision = AgentDecision::from_response(response, agent.id());
 
-                    // If decision is to block/redirect/challenge, stop processing
-                    if !combined_decision.is_allow() {
+                    // Stop once the route's merge strategy has a final outcome
+                    if merger.add(decision) {
                         debug!(
                             correlation_id = %ctx.correlation_id,
                             agent_id = %agent.id(),
-                            decision = ?combined_decision,
-                            "Agent returned blocking decision, stopping agent chain"
+                            "Agent decision is final, stopping agent chain"
                         );
                         break;
                     }
@@ -820,13 +1087,25 @@ impl AgentManager {
                         agent_id = %agent.id(),
                         "Continuing despite agent failure (filter fail-open mode)"
                     );
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
+                }
+                Err(_) if timeout_duration < agent_timeout => {
+                    if let Some(decision) = self.budget_exhausted(ctx, agent, *filter_failure_mode)
+                    {
+                        return Ok(decision);
+                    }
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
                 }
                 Err(_) => {
                     agent.record_timeout();
                     warn!(
                         agent_id = %agent.id(),
                         correlation_id = %ctx.correlation_id,
-                        timeout_ms = agent.timeout_ms(),
+                        timeout_ms = agent.timeout_ms_for(event_type),
                         filter_failure_mode = ?filter_failure_mode,
                         "Agent call timed out"
                     );
@@ -847,10 +1126,15 @@ impl AgentManager {
                         agent_id = %agent.id(),
                         "Continuing despite timeout (filter fail-open mode)"
                     );
+                    if merger.add_failure(agent.id()) {
+                        break;
+                    }
                 }
             }
         }
 
+        let combined_decision = merger.finish();
+
         trace!(
             correlation_id = %ctx.correlation_id,
             decision = ?combined_decision,
@@ -875,7 +1159,7 @@ impl AgentManager {
     /// - Parallel: O(L) (assuming sufficient concurrency)
     ///
     /// This is the preferred method for most use cases.
-    async fn process_event_parallel<T: serde::Serialize + Sync>(
+    async fn process_event_parallel<T: AgentCall>(
         &self,
         event_type: EventType,
         event: &T,
@@ -890,25 +1174,19 @@ impl AgentManager {
         );
 
         // Get relevant agents for this route and event type
-        let agents = self.agents.read().await;
-        let semaphores = self.agent_semaphores.read().await;
-
-        // Collect agent info upfront to minimize lock duration
+        let snapshot = self.snapshot.load();
         let agent_info: Vec<_> = route_agents
             .iter()
             .filter_map(|(id, failure_mode)| {
-                let agent = agents.get(id)?;
+                let agent = snapshot.agents.get(id)?;
                 if !agent.handles_event(event_type) {
                     return None;
                 }
-                let semaphore = semaphores.get(id).cloned();
+                let semaphore = snapshot.semaphores.get(id).cloned();
                 Some((Arc::clone(agent), *failure_mode, semaphore))
             })
             .collect();
-
-        // Release locks early
-        drop(agents);
-        drop(semaphores);
+        drop(snapshot);
 
         if agent_info.is_empty() {
             trace!(
@@ -919,6 +1197,17 @@ impl AgentManager {
             return Ok(AgentDecision::default_allow());
         }
 
+        // Enforce the request's total agent time budget
+        let remaining_budget = self.remaining_budget(ctx);
+        if remaining_budget == Some(Duration::ZERO) {
+            let mut blocking_decision = None;
+            for (agent, filter_failure_mode, _) in &agent_info {
+                let decision = self.budget_exhausted(ctx, agent, *filter_failure_mode);
+                blocking_decision = blocking_decision.or(decision);
+            }
+            return Ok(blocking_decision.unwrap_or_else(AgentDecision::default_allow));
+        }
+
         debug!(
             correlation_id = %ctx.correlation_id,
             event_type = ?event_type,
@@ -957,6 +1246,8 @@ impl AgentManager {
                     } else {
                         None
                     };
+                    // Ramp up after the agent (re)connected
+                    let _ramp_permit = agent.slow_start_permit().await;
 
                     // Check circuit breaker
                     if !agent.circuit_breaker().is_closed() {
@@ -975,9 +1266,16 @@ impl AgentManager {
 
                     // Call agent with timeout
                     let start = Instant::now();
-                    let timeout_duration = Duration::from_millis(agent.timeout_ms());
-
-                    match timeout(timeout_duration, agent.call_event(event_type, event)).await {
+                    let agent_timeout = Duration::from_millis(agent.timeout_ms_for(event_type));
+                    let timeout_duration = budget_timeout(agent_timeout, remaining_budget);
+
+                    let result = timeout(
+                        timeout_duration,
+                        chaos::agent_call(agent.id(), event_type, event.call(&agent)),
+                    )
+                    .await;
+                    self.record_agent_time(correlation_id.as_str(), agent.id(), start.elapsed());
+                    match result {
                         Ok(Ok(response)) => {
                             let duration = start.elapsed();
                             agent.record_success(duration);
@@ -987,6 +1285,7 @@ impl AgentManager {
                                 duration_ms = duration.as_millis(),
                                 "Parallel agent call succeeded"
                             );
+                            self.record_audit(ctx, event_type, agent.id(), &response, duration);
                             Ok((agent.id().to_string(), response))
                         }
                         Ok(Err(e)) => {
@@ -1005,12 +1304,20 @@ impl AgentManager {
                                 format!("Agent error: {}", e),
                             ))
                         }
+                        Err(_) if timeout_duration < agent_timeout => {
+                            self.budget_exhausted(ctx, &agent, filter_failure_mode);
+                            Err((
+                                agent.id().to_string(),
+                                filter_failure_mode,
+                                BUDGET_EXHAUSTED.to_string(),
+                            ))
+                        }
                         Err(_) => {
                             agent.record_timeout();
                             warn!(
                                 agent_id = %agent.id(),
                                 correlation_id = %correlation_id,
-                                timeout_ms = agent.timeout_ms(),
+                                timeout_ms = agent.timeout_ms_for(event_type),
                                 filter_failure_mode = ?filter_failure_mode,
                                 "Parallel agent call timed out"
                             );
@@ -1026,10 +1333,12 @@ impl AgentManager {
             .collect();
 
         // Execute all agent calls in parallel
+        let start = Instant::now();
         let results = join_all(futures).await;
+        self.charge_budget(ctx, start.elapsed());
 
         // Process results and merge decisions
-        let mut combined_decision = AgentDecision::default_allow();
+        let mut merger = DecisionMerger::new(&ctx.decision_merge);
         let mut blocking_error: Option<AgentDecision> = None;
 
         for result in results {
@@ -1037,19 +1346,17 @@ impl AgentManager {
                 Ok((agent_id, response)) => {
                     let decision = AgentDecision::from_response(response, &agent_id);
 
-                    // Check for blocking decision
-                    if !decision.is_allow() {
+                    // Return as soon as the route's merge strategy has a final outcome
+                    if merger.add(decision) {
+                        let decision = merger.finish();
                         debug!(
                             correlation_id = %ctx.correlation_id,
                             agent_id = %agent_id,
                             decision = ?decision,
-                            "Agent returned blocking decision"
+                            "Agent decision is final"
                         );
-                        // Return first blocking decision immediately
                         return Ok(decision);
                     }
-
-                    combined_decision.merge(decision);
                 }
                 Err((agent_id, failure_mode, reason)) => {
                     // Handle failure based on filter's failure mode
@@ -1062,11 +1369,12 @@ impl AgentManager {
                         );
                         // Store blocking error but continue processing other results
                         // in case another agent returned a more specific block
-                        let status = if reason.contains("Timeout") { 504 } else { 503 };
-                        let message = if reason.contains("Timeout") {
-                            "Gateway timeout"
+                        let (status, message) = if reason == BUDGET_EXHAUSTED {
+                            (504, BUDGET_EXHAUSTED)
+                        } else if reason.contains("Timeout") {
+                            (504, "Gateway timeout")
                         } else {
-                            "Service unavailable"
+                            (503, "Service unavailable")
                         };
                         blocking_error =
                             Some(AgentDecision::block(status, message).with_decided_by(&agent_id));
@@ -1078,11 +1386,18 @@ impl AgentManager {
                             reason = %reason,
                             "Agent failure in fail-open mode, continuing"
                         );
+                        merger.add_failure(&agent_id);
                     }
                 }
             }
         }
 
+        // An explicit block takes precedence over a fail-closed error
+        let combined_decision = merger.finish();
+        if !combined_decision.is_allow() {
+            return Ok(combined_decision);
+        }
+
         // If we have a fail-closed error and no explicit block, return the error
         if let Some(error_decision) = blocking_error {
             return Ok(error_decision);
@@ -1107,21 +1422,22 @@ impl AgentManager {
         agent_name: &str,
         event: GuardrailInspectEvent,
     ) -> ZentinelResult<AgentResponse> {
-        let agents = self.agents.read().await;
-        let agent = agents.get(agent_name).ok_or_else(|| ZentinelError::Agent {
-            agent: agent_name.to_string(),
-            message: format!("Agent '{}' not found", agent_name),
-            event: "guardrail_inspect".to_string(),
-            source: None,
-        })?;
+        let snapshot = self.snapshot.load();
+        let agent = snapshot
+            .agents
+            .get(agent_name)
+            .ok_or_else(|| ZentinelError::Agent {
+                agent: agent_name.to_string(),
+                message: format!("Agent '{}' not found", agent_name),
+                event: "guardrail_inspect".to_string(),
+                source: None,
+            })?;
 
         let agent = Arc::clone(agent);
-        drop(agents); // Release lock before calling
+        let semaphore = snapshot.semaphores.get(agent_name).cloned();
+        drop(snapshot);
 
         // Acquire per-agent semaphore permit
-        let semaphores = self.agent_semaphores.read().await;
-        let semaphore = semaphores.get(agent_name).cloned();
-        drop(semaphores);
 
         let _permit = if let Some(sem) = semaphore {
             Some(
@@ -1137,6 +1453,8 @@ impl AgentManager {
         } else {
             None
         };
+        // Ramp up after the agent (re)connected
+        let _ramp_permit = agent.slow_start_permit().await;
 
         // Check circuit breaker
         if !agent.circuit_breaker().is_closed() {
@@ -1149,7 +1467,8 @@ impl AgentManager {
         }
 
         let start = Instant::now();
-        let timeout_duration = Duration::from_millis(agent.timeout_ms());
+        let timeout_duration =
+            Duration::from_millis(agent.timeout_ms_for(EventType::GuardrailInspect));
 
         match timeout(timeout_duration, agent.call_guardrail_inspect(&event)).await {
             Ok(Ok(response)) => {
@@ -1177,14 +1496,17 @@ impl AgentManager {
 
     /// Initialize agent connections.
     pub async fn initialize(&self) -> ZentinelResult<()> {
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load_full();
 
-        info!(agent_count = agents.len(), "Initializing agent connections");
+        info!(
+            agent_count = snapshot.agents.len(),
+            "Initializing agent connections"
+        );
 
         let mut initialized_count = 0;
         let mut failed_count = 0;
 
-        for (id, agent) in agents.iter() {
+        for (id, agent) in snapshot.agents.iter() {
             debug!(agent_id = %id, "Initializing agent connection");
             if let Err(e) = agent.initialize().await {
                 error!(
@@ -1203,7 +1525,7 @@ impl AgentManager {
         info!(
             initialized = initialized_count,
             failed = failed_count,
-            total = agents.len(),
+            total = snapshot.agents.len(),
             "Agent initialization complete"
         );
 
@@ -1212,11 +1534,14 @@ impl AgentManager {
 
     /// Shutdown all agents.
     pub async fn shutdown(&self) {
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load_full();
 
-        info!(agent_count = agents.len(), "Shutting down agent manager");
+        info!(
+            agent_count = snapshot.agents.len(),
+            "Shutting down agent manager"
+        );
 
-        for (id, agent) in agents.iter() {
+        for (id, agent) in snapshot.agents.iter() {
             debug!(agent_id = %id, "Shutting down agent");
             agent.shutdown().await;
             trace!(agent_id = %id, "Agent shutdown complete");
@@ -1227,14 +1552,30 @@ impl AgentManager {
 
     /// Release per-request agent state after a request completes.
     ///
-    /// Clears the correlation affinity (headers → body chunk connection
-    /// pinning) on every agent pool. Affinities that are never released here
-    /// are reclaimed by the pool maintenance TTL sweep.
-    pub async fn end_request(&self, correlation_id: &str) {
-        let agents = self.agents.read().await;
-        for agent in agents.values() {
+    /// Clears the correlation affinity (pinning of all the request's events
+    /// to one agent connection) on every agent pool and the request's agent time budget, and
+    /// returns the time the request spent in agents. Affinities that are
+    /// never released here are reclaimed by the pool maintenance TTL sweep.
+    pub async fn end_request(&self, correlation_id: &str) -> AgentTimings {
+        self.agent_time_spent.remove(correlation_id);
+        let timings = self
+            .agent_timings
+            .remove(correlation_id)
+            .map(|(_, timings)| timings)
+            .unwrap_or_default();
+        let snapshot = self.snapshot.load();
+        for agent in snapshot.agents.values() {
             agent.clear_correlation_affinity(correlation_id);
         }
+        timings
+    }
+
+    /// Time a request has spent in agents so far, while it is in flight.
+    pub fn request_timings(&self, correlation_id: &str) -> AgentTimings {
+        self.agent_timings
+            .get(correlation_id)
+            .map(|timings| timings.clone())
+            .unwrap_or_default()
     }
 
     /// Get agent metrics.
@@ -1247,17 +1588,53 @@ impl AgentManager {
     /// This is useful for pre-filtering agents before making calls,
     /// e.g., to check if any agents handle WebSocket frames.
     pub fn get_agents_for_event(&self, event_type: EventType) -> Vec<String> {
-        // Use try_read to avoid blocking - return empty if lock is held
-        // This is acceptable since this is only used for informational purposes
-        if let Ok(agents) = self.agents.try_read() {
-            agents
-                .values()
-                .filter(|agent| agent.handles_event(event_type))
-                .map(|agent| agent.id().to_string())
-                .collect()
-        } else {
-            Vec::new()
+        self.snapshot
+            .load()
+            .agents
+            .values()
+            .filter(|agent| agent.handles_event(event_type))
+            .map(|agent| agent.id().to_string())
+            .collect()
+    }
+
+    /// Health of every agent, with the reason for unhealthy ones.
+    ///
+    /// An agent is healthy while its connection pool is and its circuit
+    /// breaker is not open.
+    pub async fn agent_health(&self) -> Vec<(String, Option<String>)> {
+        let snapshot = self.snapshot.load_full();
+        let mut health = Vec::with_capacity(snapshot.agents.len());
+        for (id, agent) in snapshot.agents.iter() {
+            let problem = if agent.circuit_breaker().state()
+                == zentinel_common::types::CircuitBreakerState::Open
+            {
+                Some("circuit breaker open".to_string())
+            } else if !agent.is_healthy().await {
+                Some("no healthy connections".to_string())
+            } else {
+                None
+            };
+            health.push((id.clone(), problem));
         }
+        health
+    }
+
+    /// Calls, failed calls and timed out calls summed over all agents.
+    ///
+    /// Counters restart when an agent is recreated on reload.
+    pub fn call_totals(&self) -> (u64, u64, u64) {
+        let snapshot = self.snapshot.load();
+        snapshot
+            .agents
+            .values()
+            .fold((0, 0, 0), |(calls, failed, timeouts), agent| {
+                let metrics = agent.metrics();
+                (
+                    calls + metrics.calls_total.load(Ordering::Relaxed),
+                    failed + metrics.calls_failed.load(Ordering::Relaxed),
+                    timeouts + metrics.calls_timeout.load(Ordering::Relaxed),
+                )
+            })
     }
 
     /// Get pool metrics collectors from all agents.
@@ -1266,41 +1643,116 @@ impl AgentManager {
     /// These can be registered with the MetricsManager to include agent pool
     /// metrics in the /metrics endpoint output.
     pub async fn get_v2_pool_metrics(&self) -> Vec<(String, Arc<MetricsCollector>)> {
-        let agents = self.agents.read().await;
-        agents
+        let snapshot = self.snapshot.load();
+        snapshot
+            .agents
             .iter()
             .map(|(id, agent)| (id.clone(), agent.pool_metrics_collector_arc()))
             .collect()
     }
 
-    /// Export prometheus metrics from all agent pools.
-    ///
-    /// Returns the combined prometheus-formatted metrics from all agent pools.
-    pub async fn export_v2_pool_metrics(&self) -> String {
-        let agents = self.agents.read().await;
-        let mut output = String::new();
-
-        for (id, agent) in agents.iter() {
-            let pool_metrics = agent.export_prometheus();
-            if !pool_metrics.is_empty() {
-                output.push_str(&format!("\n# Agent pool metrics: {}\n", id));
-                output.push_str(&pool_metrics);
-            }
-        }
-
-        output
-    }
-
     /// Get an agent's metrics collector by ID.
     ///
     /// Returns None if the agent doesn't exist.
     pub async fn get_v2_metrics_collector(&self, agent_id: &str) -> Option<Arc<MetricsCollector>> {
-        let agents = self.agents.read().await;
-        agents
+        let snapshot = self.snapshot.load();
+        snapshot
+            .agents
             .get(agent_id)
             .map(|agent| agent.pool_metrics_collector_arc())
     }
 
+    /// Agent time left for this request, or `None` if the route has no budget.
+    fn remaining_budget(&self, ctx: &AgentCallContext) -> Option<Duration> {
+        let budget = ctx.agent_budget?;
+        let spent = self
+            .agent_time_spent
+            .get(ctx.correlation_id.as_str())
+            .map(|spent| *spent)
+            .unwrap_or_default();
+        Some(budget.saturating_sub(spent))
+    }
+
+    /// Add agent processing time to the request's total and, for routes
+    /// with a budget, to its budget accounting.
+    fn charge_budget(&self, ctx: &AgentCallContext, elapsed: Duration) {
+        self.agent_timings
+            .entry(ctx.correlation_id.to_string())
+            .or_default()
+            .total += elapsed;
+        if ctx.agent_budget.is_some() {
+            *self
+                .agent_time_spent
+                .entry(ctx.correlation_id.to_string())
+                .or_default() += elapsed;
+        }
+    }
+
+    /// Keep an agent's response in the audit store, if one is set, and in
+    /// the context's trace. Dry-run responses stay out of the store.
+    fn record_audit(
+        &self,
+        ctx: &AgentCallContext,
+        event_type: EventType,
+        agent_id: &str,
+        response: &AgentResponse,
+        duration: Duration,
+    ) {
+        let store = self.audit_store.get().filter(|_| !ctx.metadata.dry_run);
+        if store.is_none() && ctx.trace.is_none() {
+            return;
+        }
+
+        let record = AuditRecord::new(
+            ctx.correlation_id.as_str(),
+            ctx.route_id.as_deref(),
+            agent_id,
+            event_type,
+            response,
+            duration,
+        );
+        if let Some(trace) = &ctx.trace {
+            trace.lock().push(record.clone());
+        }
+        if let Some(store) = store {
+            store.record(record);
+        }
+    }
+
+    /// Record the time one agent call took.
+    fn record_agent_time(&self, correlation_id: &str, agent_id: &str, elapsed: Duration) {
+        *self
+            .agent_timings
+            .entry(correlation_id.to_string())
+            .or_default()
+            .by_agent
+            .entry(agent_id.to_string())
+            .or_default() += elapsed;
+    }
+
+    /// Handle an agent that cannot run (or finish) because the request's agent
+    /// time budget is spent.
+    ///
+    /// Fail-closed returns a 504 Block decision; fail-open skips the agent.
+    fn budget_exhausted(
+        &self,
+        ctx: &AgentCallContext,
+        agent: &AgentV2,
+        failure_mode: FailureMode,
+    ) -> Option<AgentDecision> {
+        agent.metrics().record_budget_exhausted();
+        warn!(
+            correlation_id = %ctx.correlation_id,
+            agent_id = %agent.id(),
+            budget_ms = ctx.agent_budget.map(|b| b.as_millis() as u64),
+            failure_mode = ?failure_mode,
+            "Agent time budget exhausted"
+        );
+
+        (failure_mode == FailureMode::Closed)
+            .then(|| AgentDecision::block(504, BUDGET_EXHAUSTED).with_decided_by(agent.id()))
+    }
+
     /// Enforce per-agent body inspection limits for a body of `body_size` bytes.
     ///
     /// Agents whose limit is exceeded are handled according to their failure
@@ -1314,10 +1766,10 @@ impl AgentManager {
         body_size: usize,
         event_type: EventType,
     ) -> BodyLimitsResult {
-        let agents = self.agents.read().await;
+        let snapshot = self.snapshot.load();
         let limits: Vec<(String, FailureMode, usize)> = route_agents
             .iter()
-            .filter_map(|id| agents.get(id))
+            .filter_map(|id| snapshot.agents.get(id))
             .filter(|agent| agent.handles_event(event_type))
             .map(|agent| {
                 let limit = if event_type == EventType::ResponseBodyChunk {
@@ -1340,7 +1792,7 @@ impl AgentManager {
                 event_type = ?event_type,
                 "Body exceeds agent inspection limit, skipping agent (fail-open)"
             );
-            if let Some(agent) = agents.get(agent_id) {
+            if let Some(agent) = snapshot.agents.get(agent_id) {
                 agent.metrics().record_body_size_skip();
             }
         }
@@ -1364,6 +1816,79 @@ impl AgentManager {
     }
 }
 
+/// Block message (and parallel failure reason) for an exhausted agent time budget.
+const BUDGET_EXHAUSTED: &str = "Agent time budget exhausted";
+
+/// Build an agent and its semaphore, served by `handler` when given
+fn build_agent(
+    config: AgentConfig,
+    handler: Option<Arc<dyn AgentHandlerV2>>,
+) -> ZentinelResult<(Arc<AgentV2>, Arc<Semaphore>)> {
+    debug!(
+        agent_id = %config.id,
+        transport = ?config.transport,
+        timeout_ms = config.timeout_ms,
+        failure_mode = ?config.failure_mode,
+        max_concurrent_calls = config.max_concurrent_calls,
+        "Configuring agent"
+    );
+
+    // Create per-agent semaphore for queue isolation
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent_calls));
+
+    let circuit_breaker = Arc::new(CircuitBreaker::new(
+        config.circuit_breaker.unwrap_or_default(),
+    ));
+
+    trace!(
+        agent_id = %config.id,
+        max_concurrent_calls = config.max_concurrent_calls,
+        pool_config = ?config.pool,
+        "Creating agent instance with internal pool"
+    );
+
+    let agent_id = config.id.clone();
+    let in_process_transport = matches!(config.transport, AgentTransport::InProcess);
+    let mut agent = AgentV2::new(config, circuit_breaker);
+    match handler {
+        Some(handler) => {
+            debug!(agent_id = %agent_id, "Serving agent in process");
+            agent = agent.with_in_process_handler(handler);
+        }
+        None if in_process_transport => {
+            return Err(ZentinelError::Agent {
+                agent: agent_id,
+                message: "Agent uses the in-process transport but no handler is registered"
+                    .to_string(),
+                event: "initialize".to_string(),
+                source: None,
+            });
+        }
+        None => {}
+    }
+
+    debug!(
+        agent_id = %agent_id,
+        "Agent configured successfully"
+    );
+
+    Ok((Arc::new(agent), semaphore))
+}
+
+/// Whether an agent's config is unchanged, so the running agent can be kept
+fn same_agent(previous: &AgentConfig, current: &AgentConfig) -> bool {
+    matches!(
+        (serde_json::to_value(previous), serde_json::to_value(current)),
+        (Ok(a), Ok(b)) if a == b
+    )
+}
+
+/// Effective timeout for an agent call: the agent's own timeout, capped by
+/// what is left of the request's agent time budget.
+fn budget_timeout(agent_timeout: Duration, remaining_budget: Option<Duration>) -> Duration {
+    remaining_budget.map_or(agent_timeout, |remaining| remaining.min(agent_timeout))
+}
+
 /// Result of enforcing body inspection limits.
 enum BodyLimitsResult {
     /// Agents (within their limits) that may inspect the body.
@@ -1446,6 +1971,169 @@ mod tests {
         assert!(outcome.blocked_by.is_none());
     }
 
+    fn budget_ctx(budget: Option<Duration>) -> AgentCallContext {
+        let metadata = zentinel_agent_protocol::RequestMetadata {
+            correlation_id: "budget-req".to_string(),
+            request_id: "budget-req".to_string(),
+            client_ip: "127.0.0.1".to_string(),
+            client_port: 0,
+            server_name: None,
+            protocol: "HTTP/1.1".to_string(),
+            tls_version: None,
+            tls_cipher: None,
+            route_id: None,
+            upstream_id: None,
+            timestamp: String::new(),
+            traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
+        };
+        let mut ctx = AgentCallContext::new(
+            zentinel_common::CorrelationId::from_string("budget-req"),
+            metadata,
+        );
+        ctx.agent_budget = budget;
+        ctx
+    }
+
+    #[test]
+    fn budget_caps_agent_timeout() {
+        let agent_timeout = Duration::from_millis(200);
+        assert_eq!(budget_timeout(agent_timeout, None), agent_timeout);
+        assert_eq!(
+            budget_timeout(agent_timeout, Some(Duration::from_millis(50))),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            budget_timeout(agent_timeout, Some(Duration::from_secs(1))),
+            agent_timeout
+        );
+    }
+
+    #[tokio::test]
+    async fn budget_is_charged_per_request_and_released() {
+        let manager = AgentManager::new(vec![]).await.unwrap();
+        let ctx = budget_ctx(Some(Duration::from_millis(100)));
+
+        assert_eq!(
+            manager.remaining_budget(&ctx),
+            Some(Duration::from_millis(100))
+        );
+        manager.charge_budget(&ctx, Duration::from_millis(60));
+        assert_eq!(
+            manager.remaining_budget(&ctx),
+            Some(Duration::from_millis(40))
+        );
+        manager.charge_budget(&ctx, Duration::from_millis(60));
+        assert_eq!(manager.remaining_budget(&ctx), Some(Duration::ZERO));
+
+        manager.end_request("budget-req").await;
+        assert_eq!(
+            manager.remaining_budget(&ctx),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[tokio::test]
+    async fn routes_without_budget_are_not_tracked() {
+        let manager = AgentManager::new(vec![]).await.unwrap();
+        let ctx = budget_ctx(None);
+
+        manager.charge_budget(&ctx, Duration::from_millis(500));
+        assert_eq!(manager.remaining_budget(&ctx), None);
+        assert!(manager.agent_time_spent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn end_request_returns_agent_timings() {
+        let manager = AgentManager::new(vec![]).await.unwrap();
+        let ctx = budget_ctx(None);
+
+        // Two agents called in parallel: the total counts the wall time once
+        manager.record_agent_time("budget-req", "waf", Duration::from_millis(30));
+        manager.record_agent_time("budget-req", "auth", Duration::from_millis(20));
+        manager.charge_budget(&ctx, Duration::from_millis(30));
+        manager.record_agent_time("budget-req", "waf", Duration::from_millis(5));
+        manager.charge_budget(&ctx, Duration::from_millis(5));
+
+        let timings = manager.end_request("budget-req").await;
+        assert_eq!(timings.total, Duration::from_millis(35));
+        assert_eq!(timings.by_agent["waf"], Duration::from_millis(35));
+        assert_eq!(timings.by_agent["auth"], Duration::from_millis(20));
+        assert!(manager.agent_timings.is_empty());
+    }
+
+    fn unix_agent(id: &str, socket_dir: &std::path::Path) -> AgentConfig {
+        use zentinel_config::{AgentEvent, AgentType};
+
+        AgentConfig {
+            id: id.to_string(),
+            agent_type: AgentType::Custom("test".to_string()),
+            transport: AgentTransport::UnixSocket {
+                path: socket_dir.join(format!("{id}.sock")),
+            },
+            events: vec![AgentEvent::RequestHeaders],
+            pool: None,
+            timeout_ms: 1000,
+            event_timeouts: Default::default(),
+            failure_mode: Default::default(),
+            circuit_breaker: None,
+            max_request_body_bytes: None,
+            max_response_body_bytes: None,
+            request_body_mode: Default::default(),
+            response_body_mode: Default::default(),
+            chunk_timeout_ms: 5000,
+            config: None,
+            max_concurrent_calls: 10,
+            max_message_size: None,
+            process: None,
+            slow_start: None,
+            state_quota: Default::default(),
+        }
+    }
+
+    fn agents_for_request_headers(manager: &AgentManager) -> Vec<String> {
+        let mut ids = manager.get_agents_for_event(EventType::RequestHeaders);
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn reload_swaps_in_the_new_agent_set() {
+        // No agent listens on these sockets: agents are configured even
+        // when they cannot be reached yet
+        let socket_dir = tempfile::TempDir::new().unwrap();
+        let manager = AgentManager::new(vec![]).await.unwrap();
+        assert!(agents_for_request_headers(&manager).is_empty());
+
+        manager
+            .reload(vec![unix_agent("waf", socket_dir.path())])
+            .await;
+        assert_eq!(agents_for_request_headers(&manager), vec!["waf"]);
+        let waf = Arc::clone(&manager.snapshot.load().agents["waf"]);
+
+        manager
+            .reload(vec![
+                unix_agent("waf", socket_dir.path()),
+                unix_agent("dlp", socket_dir.path()),
+            ])
+            .await;
+        assert_eq!(agents_for_request_headers(&manager), vec!["dlp", "waf"]);
+        // The unchanged agent is kept, not rebuilt
+        assert!(Arc::ptr_eq(&waf, &manager.snapshot.load().agents["waf"]));
+
+        let mut changed = unix_agent("waf", socket_dir.path());
+        changed.max_concurrent_calls = 20;
+        manager.reload(vec![changed]).await;
+        assert_eq!(agents_for_request_headers(&manager), vec!["waf"]);
+        assert!(!Arc::ptr_eq(&waf, &manager.snapshot.load().agents["waf"]));
+
+        manager.reload(vec![]).await;
+        assert!(agents_for_request_headers(&manager).is_empty());
+    }
+
     #[test]
     fn no_agents_yields_empty_outcome() {
         let outcome = evaluate_body_limits(&[], 1_000_000);
@@ -26,6 +26,9 @@ pub struct AgentMetrics {
     pub decisions_challenge: AtomicU64,
     /// Bodies that exceeded an agent's inspection limit and skipped it (fail-open)
     pub body_size_skips: AtomicU64,
+    /// Agents skipped, blocked, or cut short because the request's agent
+    /// time budget ran out
+    pub budget_exhausted: AtomicU64,
 }
 
 impl AgentMetrics {
@@ -59,6 +62,11 @@ impl AgentMetrics {
         self.body_size_skips.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record an agent call lost to an exhausted request agent time budget.
+    pub fn record_budget_exhausted(&self) {
+        self.budget_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Get average call duration in microseconds.
     pub fn average_duration_us(&self) -> f64 {
         let total = self.duration_total_us.load(Ordering::Relaxed) as f64;
@@ -11,6 +11,8 @@
 //! - [`AgentV2`]: Agent with bidirectional streaming and connection pooling
 //! - [`AgentDecision`]: Combined result from processing through agents
 //! - [`AgentCallContext`]: Request context passed to agents
+//! - [`AgentSupervisor`]: Runs agents configured with a `command` as child processes
+//! - [`AgentStateStore`]: Session state agents keep in the proxy (`session-store`)
 //!
 //! # Queue Isolation
 //!
@@ -18,6 +20,12 @@
 //! from affecting other agents (noisy neighbor problem). Configure concurrency
 //! limits per-agent via `max_concurrent_calls` in the agent configuration.
 //!
+//! # Slow Start
+//!
+//! Agents with a `slow-start` block ramp their concurrency limit up from a
+//! single call after every (re)connect, optionally priming the agent with the
+//! warm-up requests it advertises in its capabilities. See [`SlowStart`].
+//!
 //! # Example
 //!
 //! ```ignore
@@ -38,6 +46,9 @@ mod context;
 mod decision;
 mod manager;
 mod metrics;
+mod slow_start;
+mod state_store;
+mod supervisor;
 
 /// Default maximum body size (in bytes) sent to an agent for inspection.
 ///
@@ -49,9 +60,12 @@ pub const DEFAULT_AGENT_MAX_BODY_BYTES: usize = 1024 * 1024;
 
 pub use agent_v2::AgentV2;
 pub use context::AgentCallContext;
-pub use decision::{AgentAction, AgentDecision};
-pub use manager::AgentManager;
+pub use decision::{AgentAction, AgentDecision, DecisionMergePolicy, DecisionMerger};
+pub use manager::{AgentManager, AgentTimings};
 pub use metrics::AgentMetrics;
+pub use slow_start::SlowStart;
+pub use state_store::AgentStateStore;
+pub use supervisor::{AgentProcessState, AgentProcessStatus, AgentSupervisor};
 
 #[cfg(test)]
 mod tests {
@@ -113,6 +127,7 @@ mod tests {
             events: vec![AgentEvent::RequestHeaders],
             pool: None,
             timeout_ms: 1000,
+            event_timeouts: Default::default(),
             failure_mode: Default::default(),
             circuit_breaker: None,
             max_request_body_bytes: None,
@@ -122,6 +137,10 @@ mod tests {
             chunk_timeout_ms: 5000,
             config: None,
             max_concurrent_calls: 50, // Custom limit
+            max_message_size: None,
+            process: None,
+            slow_start: None,
+            state_quota: Default::default(),
         };
 
         assert_eq!(config.max_concurrent_calls, 50);
@@ -136,6 +155,7 @@ mod tests {
             events: vec![AgentEvent::RequestHeaders],
             pool: None,
             timeout_ms: 1000,
+            event_timeouts: Default::default(),
             failure_mode: Default::default(),
             circuit_breaker: None,
             max_request_body_bytes: None,
@@ -145,6 +165,10 @@ mod tests {
             chunk_timeout_ms: 5000,
             config: None,
             max_concurrent_calls: 100, // Default value
+            max_message_size: None,
+            process: None,
+            slow_start: None,
+            state_quota: Default::default(),
         };
 
         assert_eq!(default_config.max_concurrent_calls, 100);
@@ -174,8 +198,12 @@ mod tests {
                 drain_timeout_ms: 60000,
                 max_concurrent_per_connection: 200,
                 health_check_interval_ms: 5000,
+                request_duration_buckets_us: Some(vec![500, 5_000, 50_000]),
+                serialization_buckets_us: None,
+                shared_memory: None,
             }),
             timeout_ms: 2000,
+            event_timeouts: Default::default(),
             failure_mode: Default::default(),
             circuit_breaker: None,
             max_request_body_bytes: Some(1024 * 1024),
@@ -185,6 +213,10 @@ mod tests {
             chunk_timeout_ms: 5000,
             config: None,
             max_concurrent_calls: 100,
+            max_message_size: None,
+            process: None,
+            slow_start: None,
+            state_quota: Default::default(),
         };
 
         assert!(config.pool.is_some());
@@ -0,0 +1,167 @@
+//! Slow start for agents that just (re)connected.
+//!
+//! A restarted agent often needs a while to load rules and warm its caches;
+//! sending it full traffic at once makes it time out. [`SlowStart`] ramps the
+//! agent's concurrency limit linearly from 1 to `max-concurrent-calls` over
+//! the configured window, on top of the agent's regular semaphore.
+//!
+//! A ramp begins whenever the agent pool reports a new connection
+//! generation, i.e. on the first call after startup and after every
+//! restart. Permits are topped up lazily as calls arrive; once the window
+//! has passed the ramp semaphore is closed, releasing every waiter.
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrency ramp of one agent
+#[derive(Debug)]
+pub struct SlowStart {
+    window: Duration,
+    max: usize,
+    /// Last connection generation seen, 0 before the first call
+    generation: AtomicU64,
+    /// Ramp in progress, `None` at full concurrency
+    ramp: Mutex<Option<Ramp>>,
+}
+
+#[derive(Debug)]
+struct Ramp {
+    started: Instant,
+    semaphore: Arc<Semaphore>,
+    /// Permits handed to the semaphore so far
+    granted: usize,
+}
+
+impl SlowStart {
+    /// Create a ramp to `max` concurrent calls over `window`
+    pub fn new(window: Duration, max: usize) -> Self {
+        Self {
+            window,
+            max: max.max(1),
+            generation: AtomicU64::new(0),
+            ramp: Mutex::new(None),
+        }
+    }
+
+    /// Note the agent's current connection generation.
+    ///
+    /// Returns true when it changed, in which case a new ramp has begun.
+    pub fn observe_generation(&self, generation: u64) -> bool {
+        if self.generation.swap(generation, Ordering::AcqRel) == generation {
+            return false;
+        }
+        self.begin_at(Instant::now());
+        true
+    }
+
+    /// Wait until the ramp admits another call.
+    ///
+    /// Returns `None` without waiting when no ramp is in progress; the
+    /// permit otherwise has to be held for the duration of the call.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.semaphore_at(Instant::now())?;
+        // A closed semaphore means the ramp finished while waiting
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Current concurrency limit
+    pub fn limit(&self) -> usize {
+        self.limit_at(Instant::now())
+    }
+
+    fn begin_at(&self, now: Instant) {
+        let mut ramp = self.ramp.lock();
+        if let Some(previous) = ramp.take() {
+            previous.semaphore.close();
+        }
+        *ramp = Some(Ramp {
+            started: now,
+            semaphore: Arc::new(Semaphore::new(1)),
+            granted: 1,
+        });
+    }
+
+    fn limit_at(&self, now: Instant) -> usize {
+        match *self.ramp.lock() {
+            Some(ref ramp) => self.ramp_limit(ramp, now),
+            None => self.max,
+        }
+    }
+
+    fn ramp_limit(&self, ramp: &Ramp, now: Instant) -> usize {
+        let elapsed = now.saturating_duration_since(ramp.started);
+        if self.window.is_zero() || elapsed >= self.window {
+            return self.max;
+        }
+        let progress = elapsed.as_secs_f64() / self.window.as_secs_f64();
+        1 + ((self.max - 1) as f64 * progress) as usize
+    }
+
+    /// Top up the ramp semaphore to the current limit and return it, or end
+    /// the ramp once the window has passed.
+    fn semaphore_at(&self, now: Instant) -> Option<Arc<Semaphore>> {
+        let mut guard = self.ramp.lock();
+        let ramp = guard.as_ref()?;
+        let limit = self.ramp_limit(ramp, now);
+
+        if limit >= self.max {
+            ramp.semaphore.close();
+            *guard = None;
+            return None;
+        }
+
+        let ramp = guard.as_mut()?;
+        if limit > ramp.granted {
+            ramp.semaphore.add_permits(limit - ramp.granted);
+            ramp.granted = limit;
+        }
+        Some(Arc::clone(&ramp.semaphore))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramp_limit() {
+        let slow_start = SlowStart::new(Duration::from_secs(10), 101);
+        assert_eq!(slow_start.limit(), 101);
+
+        let start = Instant::now();
+        slow_start.begin_at(start);
+        assert_eq!(slow_start.limit_at(start), 1);
+        assert_eq!(slow_start.limit_at(start + Duration::from_secs(5)), 51);
+        assert_eq!(slow_start.limit_at(start + Duration::from_secs(10)), 101);
+
+        let semaphore = slow_start
+            .semaphore_at(start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(semaphore.available_permits(), 11);
+
+        // The ramp ends after the window and releases its waiters
+        assert!(slow_start
+            .semaphore_at(start + Duration::from_secs(11))
+            .is_none());
+        assert!(semaphore.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_new_generation_restarts_ramp() {
+        let slow_start = SlowStart::new(Duration::from_secs(60), 10);
+        assert!(slow_start.acquire().await.is_none());
+
+        assert!(slow_start.observe_generation(1));
+        assert!(!slow_start.observe_generation(1));
+        let permit = slow_start.acquire().await;
+        assert!(permit.is_some());
+        assert_eq!(slow_start.limit(), 1);
+
+        // The agent restarted: back to a single call
+        assert!(slow_start.observe_generation(2));
+        assert_eq!(slow_start.limit(), 1);
+    }
+}
@@ -0,0 +1,408 @@
+//! Session state store shared with agents.
+//!
+//! Agents keep per-client state (auth sessions, rate counters) here through
+//! `StateGet`/`StateSet`/`StateDelete` protocol messages instead of each
+//! running their own Redis. Keys are namespaced per agent, so agents cannot
+//! see each other's state.
+//!
+//! Values live in memory with a TTL, or in Redis when built with the
+//! `session-store-redis` feature. Every write is checked against the
+//! agent's `state-quota`; the bytes an agent uses are tracked by this proxy
+//! instance, so with a shared Redis each instance enforces the quota on its
+//! own writes.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+#[cfg(feature = "session-store-redis")]
+use redis::aio::ConnectionManager;
+
+use zentinel_agent_protocol::v2::{StateHandler, StateRequest, StateResponse};
+use zentinel_config::{AgentConfig, AgentStateQuota, SessionStoreBackend, SessionStoreConfig};
+
+/// Key-value store answering agent state requests
+pub struct AgentStateStore {
+    backend: Backend,
+    default_ttl: Duration,
+    quotas: HashMap<String, AgentStateQuota>,
+    /// Bytes used per agent
+    usage: Mutex<HashMap<String, AgentUsage>>,
+}
+
+/// Sizes of the keys an agent wrote, with their expiry
+#[derive(Default)]
+struct AgentUsage {
+    entries: HashMap<String, (u64, Instant)>,
+    bytes: u64,
+}
+
+impl AgentUsage {
+    fn size_of(&self, key: &str, now: Instant) -> u64 {
+        match self.entries.get(key) {
+            Some(&(size, expires_at)) if expires_at > now => size,
+            _ => 0,
+        }
+    }
+
+    fn insert(&mut self, key: &str, size: u64, expires_at: Instant) {
+        if let Some((old, _)) = self.entries.insert(key.to_string(), (size, expires_at)) {
+            self.bytes -= old;
+        }
+        self.bytes += size;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((old, _)) = self.entries.remove(key) {
+            self.bytes -= old;
+        }
+    }
+
+    fn purge_expired(&mut self, now: Instant) {
+        let bytes = &mut self.bytes;
+        self.entries.retain(|_, &mut (size, expires_at)| {
+            if expires_at <= now {
+                *bytes -= size;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+enum Backend {
+    Memory(MemoryBackend),
+    #[cfg(feature = "session-store-redis")]
+    Redis(RedisBackend),
+}
+
+impl AgentStateStore {
+    /// Create the store configured in `session-store`.
+    ///
+    /// Falls back to the memory backend when Redis is unavailable.
+    pub async fn new(config: &SessionStoreConfig, agents: &[AgentConfig]) -> Self {
+        let memory = || Backend::Memory(MemoryBackend::new(config.max_entries));
+        let backend = match &config.backend {
+            SessionStoreBackend::Memory => memory(),
+            #[cfg(feature = "session-store-redis")]
+            SessionStoreBackend::Redis(redis) => match RedisBackend::new(redis).await {
+                Ok(backend) => Backend::Redis(backend),
+                Err(e) => {
+                    warn!(
+                        url = %zentinel_config::secrets::redact(&redis.url),
+                        error = %e,
+                        "Failed to connect session store to Redis, keeping state in memory"
+                    );
+                    memory()
+                }
+            },
+            #[cfg(not(feature = "session-store-redis"))]
+            SessionStoreBackend::Redis(_) => {
+                warn!(
+                    "Redis session store requested but the 'session-store-redis' feature is \
+                     disabled. Keeping state in memory."
+                );
+                memory()
+            }
+        };
+
+        Self {
+            backend,
+            default_ttl: Duration::from_secs(config.default_ttl_secs),
+            quotas: agents
+                .iter()
+                .map(|a| (a.id.clone(), a.state_quota.clone()))
+                .collect(),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get(&self, agent_id: &str, key: &str) -> Result<Option<String>, String> {
+        let key = namespaced(agent_id, key);
+        match &self.backend {
+            Backend::Memory(memory) => Ok(memory.get(&key)),
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.get(&key).await,
+        }
+    }
+
+    async fn set(
+        &self,
+        agent_id: &str,
+        key: &str,
+        value: String,
+        ttl_secs: Option<u64>,
+    ) -> Result<(), String> {
+        let quota = self.quotas.get(agent_id).cloned().unwrap_or_default();
+        if value.len() as u64 > quota.max_value_bytes {
+            return Err(format!(
+                "value of {} bytes exceeds max-value-bytes {}",
+                value.len(),
+                quota.max_value_bytes
+            ));
+        }
+
+        let ttl = ttl_secs
+            .map(Duration::from_secs)
+            .unwrap_or(self.default_ttl);
+        let size = (key.len() + value.len()) as u64;
+        let now = Instant::now();
+        {
+            let mut usage = self.usage.lock();
+            let usage = usage.entry(agent_id.to_string()).or_default();
+            if usage.bytes - usage.size_of(key, now) + size > quota.max_bytes {
+                usage.purge_expired(now);
+                if usage.bytes - usage.size_of(key, now) + size > quota.max_bytes {
+                    return Err(format!("state quota of {} bytes exceeded", quota.max_bytes));
+                }
+            }
+        }
+
+        let namespaced = namespaced(agent_id, key);
+        match &self.backend {
+            Backend::Memory(memory) => memory.set(namespaced, value, ttl)?,
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.set(&namespaced, &value, ttl).await?,
+        }
+
+        self.usage
+            .lock()
+            .entry(agent_id.to_string())
+            .or_default()
+            .insert(key, size, now + ttl);
+        Ok(())
+    }
+
+    async fn delete(&self, agent_id: &str, key: &str) -> Result<(), String> {
+        let namespaced = namespaced(agent_id, key);
+        match &self.backend {
+            Backend::Memory(memory) => memory.delete(&namespaced),
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.delete(&namespaced).await?,
+        }
+        if let Some(usage) = self.usage.lock().get_mut(agent_id) {
+            usage.remove(key);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StateHandler for AgentStateStore {
+    async fn handle(&self, agent_id: &str, request: StateRequest) -> StateResponse {
+        let request_id = request.request_id().to_string();
+        let result = match request {
+            StateRequest::Get(get) => self.get(agent_id, &get.key).await,
+            StateRequest::Set(set) => self
+                .set(agent_id, &set.key, set.value, set.ttl_secs)
+                .await
+                .map(|()| None),
+            StateRequest::Delete(delete) => self.delete(agent_id, &delete.key).await.map(|()| None),
+        };
+
+        match result {
+            Ok(value) => StateResponse::ok(request_id, value),
+            Err(error) => {
+                debug!(agent_id = %agent_id, error = %error, "Agent state request failed");
+                StateResponse::error(request_id, error)
+            }
+        }
+    }
+}
+
+/// Store key of an agent's key
+fn namespaced(agent_id: &str, key: &str) -> String {
+    format!("{}:{}", agent_id, key)
+}
+
+/// In-process backend with per-entry TTL
+struct MemoryBackend {
+    entries: DashMap<String, (String, Instant)>,
+    max_entries: usize,
+}
+
+impl MemoryBackend {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            max_entries,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        let now = Instant::now();
+        let entry = self.entries.get(key)?;
+        if entry.1 > now {
+            return Some(entry.0.clone());
+        }
+        drop(entry);
+        self.entries
+            .remove_if(key, |_, (_, expires_at)| *expires_at <= now);
+        None
+    }
+
+    fn set(&self, key: String, value: String, ttl: Duration) -> Result<(), String> {
+        let now = Instant::now();
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            self.entries.retain(|_, (_, expires_at)| *expires_at > now);
+            if self.entries.len() >= self.max_entries {
+                return Err("session store is full".to_string());
+            }
+        }
+        self.entries.insert(key, (value, now + ttl));
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) {
+        self.entries.remove(key);
+    }
+}
+
+/// Redis backend, shared between proxy instances
+#[cfg(feature = "session-store-redis")]
+struct RedisBackend {
+    connection: ConnectionManager,
+    key_prefix: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "session-store-redis")]
+impl RedisBackend {
+    async fn new(config: &zentinel_config::RedisBackendConfig) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(config.url.as_str())?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            key_prefix: config.key_prefix.clone(),
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+
+    async fn run<T: redis::FromRedisValue>(&self, cmd: redis::Cmd) -> Result<T, String> {
+        let mut conn = self.connection.clone();
+        match tokio::time::timeout(self.timeout, cmd.query_async(&mut conn)).await {
+            Ok(result) => result.map_err(|e| format!("session store error: {}", e)),
+            Err(_) => Err("session store timed out".to_string()),
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.run(
+            redis::cmd("GET")
+                .arg(format!("{}{}", self.key_prefix, key))
+                .clone(),
+        )
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), String> {
+        self.run(
+            redis::cmd("SET")
+                .arg(format!("{}{}", self.key_prefix, key))
+                .arg(value)
+                .arg("EX")
+                .arg(ttl.as_secs().max(1))
+                .clone(),
+        )
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.run(
+            redis::cmd("DEL")
+                .arg(format!("{}{}", self.key_prefix, key))
+                .clone(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_agent_protocol::v2::{StateDeleteRequest, StateGetRequest, StateSetRequest};
+
+    async fn store(quota: AgentStateQuota) -> AgentStateStore {
+        let mut store = AgentStateStore::new(&SessionStoreConfig::default(), &[]).await;
+        store.quotas.insert("auth".to_string(), quota);
+        store
+    }
+
+    fn set(key: &str, value: &str) -> StateRequest {
+        StateRequest::Set(StateSetRequest {
+            request_id: format!("set-{}", key),
+            key: key.to_string(),
+            value: value.to_string(),
+            ttl_secs: None,
+        })
+    }
+
+    fn get(key: &str) -> StateRequest {
+        StateRequest::Get(StateGetRequest {
+            request_id: format!("get-{}", key),
+            key: key.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip_is_namespaced() {
+        let store = store(AgentStateQuota::default()).await;
+
+        let response = store.handle("auth", set("session", "alice")).await;
+        assert!(response.success);
+        assert_eq!(response.request_id, "set-session");
+
+        let response = store.handle("auth", get("session")).await;
+        assert_eq!(response.value.as_deref(), Some("alice"));
+        // Another agent does not see it
+        assert!(store.handle("waf", get("session")).await.value.is_none());
+
+        let delete = StateRequest::Delete(StateDeleteRequest {
+            request_id: "del".to_string(),
+            key: "session".to_string(),
+        });
+        assert!(store.handle("auth", delete).await.success);
+        assert!(store.handle("auth", get("session")).await.value.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_state_quota() {
+        let store = store(AgentStateQuota {
+            max_bytes: 20,
+            max_value_bytes: 8,
+        })
+        .await;
+
+        let response = store.handle("auth", set("k", "too long value")).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("max-value-bytes"));
+
+        assert!(store.handle("auth", set("a", "12345678")).await.success);
+        assert!(store.handle("auth", set("b", "12345678")).await.success);
+        // 27 bytes would exceed the quota
+        assert!(!store.handle("auth", set("c", "12345678")).await.success);
+        // Overwriting an existing key only counts the difference
+        assert!(store.handle("auth", set("b", "1234")).await.success);
+    }
+
+    #[test]
+    fn test_memory_backend_expiry_and_capacity() {
+        let memory = MemoryBackend::new(1);
+        memory
+            .set("a".to_string(), "1".to_string(), Duration::ZERO)
+            .unwrap();
+        assert!(memory.get("a").is_none());
+
+        // The expired entry makes room
+        memory
+            .set("b".to_string(), "2".to_string(), Duration::from_secs(60))
+            .unwrap();
+        assert!(memory
+            .set("c".to_string(), "3".to_string(), Duration::from_secs(60))
+            .is_err());
+    }
+}
@@ -0,0 +1,647 @@
+//! Supervisor for agent processes.
+//!
+//! Agents configured with a `command` are spawned as child processes instead
+//! of being managed externally (systemd units, containers). The supervisor
+//! hands each agent its socket path, forwards the agent's stdout and stderr
+//! into Zentinel's log, restarts it with exponential backoff when it exits,
+//! and keeps a status snapshot for the `agents` builtin handler.
+
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use zentinel_config::{AgentConfig, AgentProcessConfig, AgentRestartPolicy, AgentTransport};
+
+/// Placeholder in agent arguments replaced with the agent's socket path
+pub const SOCKET_PLACEHOLDER: &str = "{socket}";
+
+/// Environment variable carrying the agent's socket path
+pub const SOCKET_ENV: &str = "ZENTINEL_AGENT_SOCKET";
+
+/// Environment variable carrying the agent ID
+pub const AGENT_ID_ENV: &str = "ZENTINEL_AGENT_ID";
+
+/// A process that ran at least this long resets the restart backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// Time a stopping agent gets after SIGTERM before it is killed
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// Lifecycle state of a supervised agent process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentProcessState {
+    /// Being spawned
+    Starting,
+    /// Running
+    Running,
+    /// Waiting to be restarted after an exit
+    Backoff,
+    /// Exited and not restarted (restart policy)
+    Exited,
+    /// Gave up after repeated failures
+    Failed,
+    /// Stopped by the supervisor
+    Stopped,
+}
+
+impl AgentProcessState {
+    /// Whether the supervisor will no longer start the process
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Exited | Self::Failed | Self::Stopped)
+    }
+}
+
+/// Status of a supervised agent process
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentProcessStatus {
+    /// Agent ID
+    pub id: String,
+    /// Current state
+    pub state: AgentProcessState,
+    /// Process ID while running
+    pub pid: Option<u32>,
+    /// Socket path handed to the agent
+    pub socket: Option<PathBuf>,
+    /// Number of restarts since the supervisor started
+    pub restarts: u32,
+    /// Seconds since the current process was started
+    pub uptime_secs: Option<u64>,
+    /// How the previous process ended
+    pub last_exit: Option<String>,
+    /// Last spawn or supervision error
+    pub last_error: Option<String>,
+}
+
+/// Shared state of one supervised agent
+struct ProcessRecord {
+    status: AgentProcessStatus,
+    started_at: Option<Instant>,
+}
+
+impl ProcessRecord {
+    fn new(id: &str, socket: Option<PathBuf>) -> Self {
+        Self {
+            status: AgentProcessStatus {
+                id: id.to_string(),
+                state: AgentProcessState::Starting,
+                pid: None,
+                socket,
+                restarts: 0,
+                uptime_secs: None,
+                last_exit: None,
+                last_error: None,
+            },
+            started_at: None,
+        }
+    }
+
+    fn set_running(&mut self, pid: Option<u32>) {
+        self.status.state = AgentProcessState::Running;
+        self.status.pid = pid;
+        self.started_at = Some(Instant::now());
+    }
+
+    fn set_state(&mut self, state: AgentProcessState) {
+        self.status.state = state;
+        if state != AgentProcessState::Running {
+            self.status.pid = None;
+            self.started_at = None;
+        }
+    }
+
+    fn snapshot(&self) -> AgentProcessStatus {
+        let mut status = self.status.clone();
+        status.uptime_secs = self.started_at.map(|t| t.elapsed().as_secs());
+        status
+    }
+}
+
+/// Spawns and supervises agent processes
+pub struct AgentSupervisor {
+    records: Vec<Arc<Mutex<ProcessRecord>>>,
+    shutdown: watch::Sender<bool>,
+    tasks: tokio::sync::Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl AgentSupervisor {
+    /// Spawn every agent that has a process configuration
+    ///
+    /// Returns `None` when no agent is supervised. Must be called from within
+    /// a Tokio runtime; the agents are stopped when the supervisor is dropped.
+    pub fn start(agents: &[AgentConfig]) -> Option<Arc<Self>> {
+        let supervised: Vec<_> = agents
+            .iter()
+            .filter_map(|agent| agent.process.as_ref().map(|process| (agent, process)))
+            .collect();
+        if supervised.is_empty() {
+            return None;
+        }
+
+        let (shutdown, _) = watch::channel(false);
+        let mut records = Vec::with_capacity(supervised.len());
+        let mut tasks = Vec::with_capacity(supervised.len());
+
+        for (agent, process) in supervised {
+            let socket = match &agent.transport {
+                AgentTransport::UnixSocket { path } => Some(path.clone()),
+                _ => None,
+            };
+            if let Some(ref path) = socket {
+                prepare_socket(&agent.id, path);
+            }
+
+            let record = Arc::new(Mutex::new(ProcessRecord::new(&agent.id, socket.clone())));
+            let supervised = SupervisedAgent {
+                id: agent.id.clone(),
+                process: process.clone(),
+                socket,
+                record: Arc::clone(&record),
+            };
+            tasks.push(tokio::spawn(supervised.run(shutdown.subscribe())));
+            records.push(record);
+        }
+
+        info!(agent_count = records.len(), "Agent supervisor started");
+
+        Some(Arc::new(Self {
+            records,
+            shutdown,
+            tasks: tokio::sync::Mutex::new(tasks),
+        }))
+    }
+
+    /// Current status of all supervised agents
+    pub fn status(&self) -> Vec<AgentProcessStatus> {
+        self.records.iter().map(|r| r.lock().snapshot()).collect()
+    }
+
+    /// Wait until every supervised agent has created its socket
+    ///
+    /// Returns `false` if some sockets did not appear within `timeout`. Those
+    /// agents keep starting in the background and are connected once the
+    /// agent pool reconnects.
+    pub async fn wait_ready(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let pending: Vec<String> = self
+                .records
+                .iter()
+                .filter_map(|record| {
+                    let record = record.lock();
+                    let status = &record.status;
+                    match status.socket {
+                        Some(ref path) if !status.state.is_terminal() && !path.exists() => {
+                            Some(status.id.clone())
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+
+            if pending.is_empty() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    agents = ?pending,
+                    timeout_ms = timeout.as_millis() as u64,
+                    "Supervised agents not ready, continuing"
+                );
+                return false;
+            }
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Wait until no supervised agent will be started again
+    pub async fn wait(&self) {
+        let mut tasks = self.tasks.lock().await;
+        // Handles are only removed once finished, so a cancelled wait loses nothing
+        while let Some(task) = tasks.last_mut() {
+            if let Err(e) = task.await {
+                error!(error = %e, "Agent supervision task panicked");
+            }
+            tasks.pop();
+        }
+    }
+
+    /// Stop all agents and wait for them to exit
+    pub async fn shutdown(&self) {
+        info!(
+            agent_count = self.records.len(),
+            "Stopping supervised agents"
+        );
+        self.shutdown.send_replace(true);
+        self.wait().await;
+    }
+}
+
+/// Supervision loop state of one agent
+struct SupervisedAgent {
+    id: String,
+    process: AgentProcessConfig,
+    socket: Option<PathBuf>,
+    record: Arc<Mutex<ProcessRecord>>,
+}
+
+impl SupervisedAgent {
+    async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        let initial_delay = Duration::from_millis(self.process.restart_delay_ms);
+        let max_delay = Duration::from_millis(self.process.max_restart_delay_ms).max(initial_delay);
+        let mut delay = initial_delay;
+        let mut attempts = 0u32;
+
+        loop {
+            let started = Instant::now();
+            self.record.lock().set_state(AgentProcessState::Starting);
+
+            let exit = match self.spawn() {
+                Ok(mut child) => {
+                    let pid = child.id();
+                    info!(agent = %self.id, pid = ?pid, "Agent process started");
+                    self.record.lock().set_running(pid);
+
+                    tokio::select! {
+                        result = child.wait() => result
+                            .map_err(|e| format!("failed to wait for process: {}", e)),
+                        _ = shutdown.changed() => {
+                            self.stop(&mut child).await;
+                            self.record.lock().set_state(AgentProcessState::Stopped);
+                            return;
+                        }
+                    }
+                }
+                Err(e) => Err(format!(
+                    "failed to spawn '{}': {}",
+                    self.process
+                        .command
+                        .first()
+                        .map(String::as_str)
+                        .unwrap_or(""),
+                    e
+                )),
+            };
+
+            let success = match exit {
+                Ok(status) => {
+                    let description = describe_exit(&status);
+                    if status.success() {
+                        info!(agent = %self.id, status = %description, "Agent process exited");
+                    } else {
+                        warn!(agent = %self.id, status = %description, "Agent process exited");
+                    }
+                    self.record.lock().status.last_exit = Some(description);
+                    status.success()
+                }
+                Err(e) => {
+                    error!(agent = %self.id, error = %e, "Agent process failed");
+                    self.record.lock().status.last_error = Some(e);
+                    false
+                }
+            };
+
+            let restart = match self.process.restart_policy {
+                AgentRestartPolicy::Always => true,
+                AgentRestartPolicy::OnFailure => !success,
+                AgentRestartPolicy::Never => false,
+            };
+            if !restart {
+                let state = if success {
+                    AgentProcessState::Exited
+                } else {
+                    AgentProcessState::Failed
+                };
+                self.record.lock().set_state(state);
+                return;
+            }
+
+            // A process that ran for a while starts over with the initial delay
+            if started.elapsed() >= STABLE_RUN {
+                attempts = 0;
+                delay = initial_delay;
+            }
+
+            if self.process.max_restarts > 0 && attempts >= self.process.max_restarts {
+                error!(
+                    agent = %self.id,
+                    max_restarts = self.process.max_restarts,
+                    "Agent process keeps exiting, giving up"
+                );
+                self.record.lock().set_state(AgentProcessState::Failed);
+                return;
+            }
+
+            attempts += 1;
+            self.record.lock().set_state(AgentProcessState::Backoff);
+            warn!(
+                agent = %self.id,
+                attempt = attempts,
+                delay_ms = delay.as_millis() as u64,
+                "Restarting agent process"
+            );
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.changed() => {
+                    self.record.lock().set_state(AgentProcessState::Stopped);
+                    return;
+                }
+            }
+
+            delay = (delay * 2).min(max_delay);
+            self.record.lock().status.restarts += 1;
+            if let Some(ref path) = self.socket {
+                prepare_socket(&self.id, path);
+            }
+        }
+    }
+
+    /// Spawn the agent process with its output forwarded to the log
+    fn spawn(&self) -> std::io::Result<Child> {
+        let (program, args) = self.process.command.split_first().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty command")
+        })?;
+        let socket = self.socket.as_ref().map(|path| path.display().to_string());
+
+        let mut cmd = Command::new(program);
+        cmd.args(args.iter().map(|arg| match socket {
+            Some(ref socket) => arg.replace(SOCKET_PLACEHOLDER, socket),
+            None => arg.clone(),
+        }))
+        .env(AGENT_ID_ENV, &self.id)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+        if let Some(ref socket) = socket {
+            cmd.env(SOCKET_ENV, socket);
+        }
+        cmd.envs(&self.process.env);
+
+        // Agents must not outlive the proxy when it exits without stopping them
+        #[cfg(target_os = "linux")]
+        {
+            // SAFETY: prctl is async-signal-safe and touches no parent memory
+            unsafe {
+                cmd.pre_exec(|| {
+                    libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+                    Ok(())
+                });
+            }
+        }
+
+        debug!(agent = %self.id, program = %program, args = ?args, "Spawning agent process");
+
+        let mut child = cmd.spawn()?;
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_output(self.id.clone(), "stdout", stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_output(self.id.clone(), "stderr", stderr));
+        }
+
+        Ok(child)
+    }
+
+    /// Ask the agent to exit, killing it after [`STOP_GRACE`]
+    async fn stop(&self, child: &mut Child) {
+        info!(agent = %self.id, "Stopping agent process");
+
+        #[cfg(unix)]
+        {
+            if let Some(pid) = child.id() {
+                // SAFETY: sending a signal to our own child process
+                unsafe {
+                    libc::kill(pid as libc::pid_t, libc::SIGTERM);
+                }
+            }
+        }
+
+        if tokio::time::timeout(STOP_GRACE, child.wait())
+            .await
+            .is_err()
+        {
+            warn!(agent = %self.id, "Agent process did not stop gracefully, killing");
+            if let Err(e) = child.kill().await {
+                error!(agent = %self.id, error = %e, "Failed to kill agent process");
+            }
+        }
+    }
+}
+
+/// Create the socket directory and remove a socket left by a previous run
+///
+/// Agents fail to bind while a stale socket file exists.
+fn prepare_socket(agent_id: &str, path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            warn!(
+                agent = %agent_id,
+                dir = %parent.display(),
+                error = %e,
+                "Failed to create agent socket directory"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let is_socket = std::fs::symlink_metadata(path)
+            .map(|m| m.file_type().is_socket())
+            .unwrap_or(false);
+        if is_socket {
+            debug!(agent = %agent_id, path = %path.display(), "Removing stale agent socket");
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Log each line an agent writes to stdout or stderr
+async fn forward_output(agent: String, stream: &'static str, output: impl AsyncRead + Unpin) {
+    let mut reader = BufReader::new(output);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end();
+                if !text.is_empty() {
+                    info!(agent = %agent, stream, "{}", text);
+                }
+            }
+            Err(e) => {
+                debug!(agent = %agent, stream, error = %e, "Agent output closed");
+                break;
+            }
+        }
+    }
+}
+
+/// Human-readable exit status
+fn describe_exit(status: &ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => status.to_string(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use zentinel_config::{AgentEvent, AgentType};
+
+    fn agent(id: &str, socket: &Path, process: AgentProcessConfig) -> AgentConfig {
+        AgentConfig {
+            id: id.to_string(),
+            agent_type: AgentType::Custom("test".to_string()),
+            transport: AgentTransport::UnixSocket {
+                path: socket.to_path_buf(),
+            },
+            events: vec![AgentEvent::RequestHeaders],
+            pool: None,
+            timeout_ms: 1000,
+            event_timeouts: Default::default(),
+            failure_mode: Default::default(),
+            circuit_breaker: None,
+            max_request_body_bytes: None,
+            max_response_body_bytes: None,
+            request_body_mode: Default::default(),
+            response_body_mode: Default::default(),
+            chunk_timeout_ms: 5000,
+            config: None,
+            max_concurrent_calls: 100,
+            max_message_size: None,
+            process: Some(process),
+            slow_start: None,
+            state_quota: Default::default(),
+        }
+    }
+
+    fn shell(script: &str) -> AgentProcessConfig {
+        AgentProcessConfig::new(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            script.to_string(),
+            "sh".to_string(),
+            SOCKET_PLACEHOLDER.to_string(),
+        ])
+    }
+
+    async fn wait_finished(supervisor: &AgentSupervisor) {
+        tokio::time::timeout(Duration::from_secs(10), supervisor.wait())
+            .await
+            .expect("supervised agents finished");
+    }
+
+    #[test]
+    fn test_no_supervised_agents() {
+        assert!(AgentSupervisor::start(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_socket_passed_to_agent() {
+        let temp = tempfile::tempdir().unwrap();
+        let socket = temp.path().join("run/echo.sock");
+        let out = temp.path().join("out");
+
+        let mut process = shell(&format!(
+            "echo \"$1 $ZENTINEL_AGENT_SOCKET $ZENTINEL_AGENT_ID $EXTRA\" > {}",
+            out.display()
+        ));
+        process.restart_policy = AgentRestartPolicy::Never;
+        process.env.insert("EXTRA".to_string(), "extra".to_string());
+
+        let supervisor = AgentSupervisor::start(&[agent("echo", &socket, process)]).unwrap();
+        wait_finished(&supervisor).await;
+
+        let socket = socket.display().to_string();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap().trim(),
+            format!("{} {} echo extra", socket, socket)
+        );
+
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, AgentProcessState::Exited);
+        assert_eq!(status.last_exit.as_deref(), Some("exit code 0"));
+        assert_eq!(status.restarts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restart_with_backoff_until_max_restarts() {
+        let temp = tempfile::tempdir().unwrap();
+        let runs = temp.path().join("runs");
+
+        let mut process = shell(&format!("echo run >> {}; exit 3", runs.display()));
+        process.restart_delay_ms = 10;
+        process.max_restarts = 2;
+
+        let supervisor =
+            AgentSupervisor::start(&[agent("crash", &temp.path().join("crash.sock"), process)])
+                .unwrap();
+        wait_finished(&supervisor).await;
+
+        assert_eq!(std::fs::read_to_string(&runs).unwrap().lines().count(), 3);
+
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, AgentProcessState::Failed);
+        assert_eq!(status.restarts, 2);
+        assert_eq!(status.last_exit.as_deref(), Some("exit code 3"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_ready_and_shutdown() {
+        let temp = tempfile::tempdir().unwrap();
+        let socket = temp.path().join("ready.sock");
+
+        // Stands in for an agent binding its socket
+        let process = shell("touch \"$1\"; exec sleep 30");
+        let supervisor = AgentSupervisor::start(&[agent("ready", &socket, process)]).unwrap();
+
+        assert!(supervisor.wait_ready(Duration::from_secs(10)).await);
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, AgentProcessState::Running);
+        assert!(status.pid.is_some());
+
+        tokio::time::timeout(Duration::from_secs(10), supervisor.shutdown())
+            .await
+            .expect("agent stopped");
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, AgentProcessState::Stopped);
+        assert!(status.pid.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_failure_is_reported() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut process = AgentProcessConfig::new(vec!["/nonexistent/zentinel-agent".to_string()]);
+        process.restart_policy = AgentRestartPolicy::Never;
+
+        let supervisor =
+            AgentSupervisor::start(&[agent("missing", &temp.path().join("m.sock"), process)])
+                .unwrap();
+        wait_finished(&supervisor).await;
+
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, AgentProcessState::Failed);
+        assert!(status
+            .last_error
+            .as_deref()
+            .is_some_and(|e| e.contains("/nonexistent/zentinel-agent")));
+    }
+}
@@ -0,0 +1,450 @@
+//! Alert rules over built-in metrics
+//!
+//! Rules are configured as `alert` blocks in the top-level `notifications`
+//! block, e.g. `condition "agent_timeout_rate > 5%"` with `for-secs 300`.
+//! They are evaluated by the notification monitor every 10 seconds:
+//!
+//! - A rule whose condition holds becomes `pending`; once it has held for
+//!   `for-secs` it is `firing` and an `alert-firing` notification is sent.
+//! - A firing rule whose condition no longer holds sends `alert-resolved`
+//!   and becomes `inactive` again.
+//!
+//! Rates (`request_rate`, `error_rate`, `block_rate`, `agent_timeout_rate`,
+//! `agent_failure_rate`) are computed over the rule's `window-secs` from
+//! sampled counters; a ratio without any events in the window has no value
+//! and never matches. `active_requests` and `unhealthy_agents` are read as is.
+//!
+//! The state of every rule is shown by the `alerts` builtin handler.
+
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use zentinel_common::observability::RequestMetrics;
+use zentinel_config::{
+    AlertMetric, AlertRuleConfig, NotificationEvent, NotificationSeverity, NotificationsConfig,
+};
+
+use crate::agents::AgentManager;
+use crate::notifications::Notification;
+
+/// Rules and metric history, replaced rule by rule on reload
+static ENGINE: LazyLock<Mutex<AlertEngine>> = LazyLock::new(|| Mutex::new(AlertEngine::new(&[])));
+
+/// Set the alert rules
+///
+/// Called at startup and on every config reload. Rules whose definition did
+/// not change keep their state.
+pub fn configure(config: Option<&NotificationsConfig>) {
+    let rules = config.map_or(&[][..], |config| config.alerts.as_slice());
+    let mut engine = ENGINE.lock();
+    if engine.rules.iter().map(|r| &r.rule).eq(rules.iter()) {
+        return;
+    }
+    if !rules.is_empty() {
+        info!(rules = rules.len(), "Alert rules configured");
+    }
+    engine.reconfigure(rules);
+}
+
+/// Whether any alert rule is configured
+pub fn has_rules() -> bool {
+    !ENGINE.lock().rules.is_empty()
+}
+
+/// Evaluate every rule against a new sample and return the notifications
+/// for rules that started firing or resolved
+pub fn evaluate(sample: MetricsSample) -> Vec<Notification> {
+    ENGINE.lock().evaluate(Instant::now(), sample)
+}
+
+/// State of every rule
+pub fn status() -> Vec<AlertStatus> {
+    ENGINE.lock().status()
+}
+
+/// Counters and gauges the rules are evaluated on
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MetricsSample {
+    pub requests: u64,
+    pub server_errors: u64,
+    pub blocked: u64,
+    pub agent_calls: u64,
+    pub agent_failures: u64,
+    pub agent_timeouts: u64,
+    pub active_requests: i64,
+    pub unhealthy_agents: u64,
+}
+
+impl MetricsSample {
+    /// Sample the proxy's request metrics and agents
+    pub async fn collect(metrics: &RequestMetrics, agent_manager: &AgentManager) -> Self {
+        let (agent_calls, agent_failures, agent_timeouts) = agent_manager.call_totals();
+        let unhealthy_agents = agent_manager
+            .agent_health()
+            .await
+            .iter()
+            .filter(|(_, problem)| problem.is_some())
+            .count() as u64;
+        Self {
+            requests: metrics.requests_total(),
+            server_errors: metrics.server_errors_total(),
+            blocked: metrics.blocked_requests_total(),
+            agent_calls,
+            agent_failures,
+            agent_timeouts,
+            active_requests: metrics.active_requests(),
+            unhealthy_agents,
+        }
+    }
+}
+
+/// State of an alert rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Inactive,
+    Pending,
+    Firing,
+}
+
+/// Admin view of an alert rule
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertStatus {
+    pub name: String,
+    pub condition: String,
+    pub state: AlertState,
+    /// Metric value at the last evaluation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    pub severity: NotificationSeverity,
+    pub for_secs: u64,
+    pub window_secs: u64,
+    /// When the rule became pending or firing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+struct RuleState {
+    rule: AlertRuleConfig,
+    state: AlertState,
+    since: Option<(Instant, chrono::DateTime<chrono::Utc>)>,
+    value: Option<f64>,
+}
+
+impl RuleState {
+    fn new(rule: AlertRuleConfig) -> Self {
+        Self {
+            rule,
+            state: AlertState::Inactive,
+            since: None,
+            value: None,
+        }
+    }
+
+    fn enter(&mut self, state: AlertState, now: Instant) {
+        self.state = state;
+        self.since = (state != AlertState::Inactive).then(|| (now, chrono::Utc::now()));
+    }
+}
+
+/// Evaluates alert rules against a history of metric samples
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+    samples: VecDeque<(Instant, MetricsSample)>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: &[AlertRuleConfig]) -> Self {
+        Self {
+            rules: rules.iter().cloned().map(RuleState::new).collect(),
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn reconfigure(&mut self, rules: &[AlertRuleConfig]) {
+        let mut previous = std::mem::take(&mut self.rules);
+        self.rules = rules
+            .iter()
+            .map(
+                |rule| match previous.iter().position(|state| &state.rule == rule) {
+                    Some(index) => previous.swap_remove(index),
+                    None => RuleState::new(rule.clone()),
+                },
+            )
+            .collect();
+        if self.rules.is_empty() {
+            self.samples.clear();
+        }
+    }
+
+    /// Record `sample` taken at `now` and update every rule
+    pub fn evaluate(&mut self, now: Instant, sample: MetricsSample) -> Vec<Notification> {
+        self.samples.push_back((now, sample));
+        let history = self
+            .rules
+            .iter()
+            .map(|state| Duration::from_secs(state.rule.window_secs))
+            .max()
+            .unwrap_or_default();
+        // Keep the newest sample at least `history` old as the window start
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= history {
+            self.samples.pop_front();
+        }
+
+        let mut notifications = Vec::new();
+        for index in 0..self.rules.len() {
+            let value = self.value(&self.rules[index].rule, now, &sample);
+            let state = &mut self.rules[index];
+            state.value = value;
+            let rule = &state.rule;
+            let matches = value.is_some_and(|v| rule.operator.matches(v, rule.threshold));
+            let held = state
+                .since
+                .is_some_and(|(since, _)| now.duration_since(since).as_secs() >= rule.for_secs);
+
+            match (state.state, matches) {
+                (AlertState::Inactive, true) if rule.for_secs == 0 => {
+                    state.enter(AlertState::Firing, now);
+                    notifications.push(alert_notification(state, NotificationEvent::AlertFiring));
+                }
+                (AlertState::Inactive, true) => state.enter(AlertState::Pending, now),
+                (AlertState::Pending, true) if held => {
+                    state.enter(AlertState::Firing, now);
+                    notifications.push(alert_notification(state, NotificationEvent::AlertFiring));
+                }
+                (AlertState::Pending, false) => state.enter(AlertState::Inactive, now),
+                (AlertState::Firing, false) => {
+                    state.enter(AlertState::Inactive, now);
+                    notifications.push(alert_notification(state, NotificationEvent::AlertResolved));
+                }
+                _ => {}
+            }
+        }
+        notifications
+    }
+
+    /// Value of a rule's metric, `None` without data
+    fn value(&self, rule: &AlertRuleConfig, now: Instant, current: &MetricsSample) -> Option<f64> {
+        let window_start = now.checked_sub(Duration::from_secs(rule.window_secs));
+        let (start, previous) = self
+            .samples
+            .iter()
+            .rev()
+            .find(|(at, _)| window_start.is_some_and(|start| *at <= start))
+            .or_else(|| self.samples.front())?;
+        let elapsed = now.duration_since(*start).as_secs_f64();
+        let delta = |f: fn(&MetricsSample) -> u64| f(current).saturating_sub(f(previous)) as f64;
+        let ratio = |numerator: f64, denominator: f64| {
+            (elapsed > 0.0 && denominator > 0.0).then(|| numerator / denominator)
+        };
+
+        match rule.metric {
+            AlertMetric::RequestRate => (elapsed > 0.0).then(|| delta(|s| s.requests) / elapsed),
+            AlertMetric::ErrorRate => ratio(delta(|s| s.server_errors), delta(|s| s.requests)),
+            AlertMetric::BlockRate => ratio(delta(|s| s.blocked), delta(|s| s.requests)),
+            AlertMetric::AgentTimeoutRate => {
+                ratio(delta(|s| s.agent_timeouts), delta(|s| s.agent_calls))
+            }
+            AlertMetric::AgentFailureRate => ratio(
+                delta(|s| s.agent_failures) + delta(|s| s.agent_timeouts),
+                delta(|s| s.agent_calls),
+            ),
+            AlertMetric::ActiveRequests => Some(current.active_requests as f64),
+            AlertMetric::UnhealthyAgents => Some(current.unhealthy_agents as f64),
+        }
+    }
+
+    /// State of every rule
+    pub fn status(&self) -> Vec<AlertStatus> {
+        self.rules
+            .iter()
+            .map(|state| AlertStatus {
+                name: state.rule.name.clone(),
+                condition: condition(&state.rule),
+                state: state.state,
+                value: state.value,
+                severity: state.rule.severity,
+                for_secs: state.rule.for_secs,
+                window_secs: state.rule.window_secs,
+                since: state.since.map(|(_, at)| at.to_rfc3339()),
+            })
+            .collect()
+    }
+}
+
+/// Rule condition as written in the config, e.g. `error_rate > 5%`
+fn condition(rule: &AlertRuleConfig) -> String {
+    format!(
+        "{} {} {}",
+        rule.metric.as_str(),
+        rule.operator.as_symbol(),
+        format_value(rule.metric, rule.threshold)
+    )
+}
+
+fn format_value(metric: AlertMetric, value: f64) -> String {
+    if metric.is_ratio() {
+        format!("{}%", (value * 10_000.0).round() / 100.0)
+    } else {
+        format!("{}", (value * 100.0).round() / 100.0)
+    }
+}
+
+fn alert_notification(state: &RuleState, event: NotificationEvent) -> Notification {
+    let rule = &state.rule;
+    let value = state.value.map(|v| format_value(rule.metric, v));
+    let message = match event {
+        NotificationEvent::AlertFiring => format!(
+            "Alert {} firing: {} is {} ({})",
+            rule.name,
+            rule.metric.as_str(),
+            value.as_deref().unwrap_or("unknown"),
+            condition(rule)
+        ),
+        _ => format!("Alert {} resolved ({})", rule.name, condition(rule)),
+    };
+    if event == NotificationEvent::AlertFiring {
+        warn!(alert = %rule.name, value = ?state.value, "Alert firing");
+    } else {
+        info!(alert = %rule.name, value = ?state.value, "Alert resolved");
+    }
+    Notification::new(
+        event,
+        rule.severity,
+        format!("alert:{}", rule.name),
+        message,
+        json!({
+            "alert": rule.name,
+            "condition": condition(rule),
+            "metric": rule.metric.as_str(),
+            "value": state.value,
+            "threshold": rule.threshold,
+            "for_secs": rule.for_secs,
+            "window_secs": rule.window_secs,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(condition: &str, for_secs: u64, window_secs: u64) -> AlertRuleConfig {
+        let mut rule = AlertRuleConfig::from_condition("test", condition).unwrap();
+        rule.for_secs = for_secs;
+        rule.window_secs = window_secs;
+        rule
+    }
+
+    fn sample(agent_calls: u64, agent_timeouts: u64) -> MetricsSample {
+        MetricsSample {
+            agent_calls,
+            agent_timeouts,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pending_firing_resolved() {
+        let mut engine = AlertEngine::new(&[rule("agent_timeout_rate > 5%", 20, 10)]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // First sample has no window yet
+        assert!(engine.evaluate(at(0), sample(0, 0)).is_empty());
+        assert_eq!(engine.status()[0].state, AlertState::Inactive);
+
+        // 10% of calls timed out: pending until held for 20s
+        assert!(engine.evaluate(at(10), sample(100, 10)).is_empty());
+        assert_eq!(engine.status()[0].state, AlertState::Pending);
+        assert!((engine.status()[0].value.unwrap() - 0.1).abs() < 1e-9);
+        assert!(engine.evaluate(at(20), sample(200, 20)).is_empty());
+
+        let fired = engine.evaluate(at(30), sample(300, 30));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].event, NotificationEvent::AlertFiring);
+        assert_eq!(fired[0].subject, "alert:test");
+        assert_eq!(engine.status()[0].state, AlertState::Firing);
+        assert!(engine.evaluate(at(40), sample(400, 40)).is_empty());
+
+        // Timeouts stop: the 10s window no longer matches
+        let resolved = engine.evaluate(at(50), sample(500, 40));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].event, NotificationEvent::AlertResolved);
+        assert_eq!(engine.status()[0].state, AlertState::Inactive);
+    }
+
+    #[test]
+    fn test_pending_resets_and_ratio_without_data() {
+        let mut engine = AlertEngine::new(&[rule("agent_timeout_rate > 5%", 30, 10)]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        engine.evaluate(at(0), sample(0, 0));
+        engine.evaluate(at(10), sample(100, 50));
+        assert_eq!(engine.status()[0].state, AlertState::Pending);
+
+        // No agent calls in the window: no value, back to inactive
+        engine.evaluate(at(20), sample(100, 50));
+        assert_eq!(engine.status()[0].value, None);
+        assert_eq!(engine.status()[0].state, AlertState::Inactive);
+    }
+
+    #[test]
+    fn test_window_and_gauges() {
+        let mut engine = AlertEngine::new(&[
+            rule("request_rate >= 5", 0, 30),
+            rule("active_requests > 100", 0, 30),
+        ]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let requests = |requests, active_requests| MetricsSample {
+            requests,
+            active_requests,
+            ..Default::default()
+        };
+
+        for (secs, total) in [(0, 0), (10, 0), (20, 0), (30, 150)] {
+            engine.evaluate(at(secs), requests(total, 10));
+        }
+        // 150 requests over the 30s window
+        let status = engine.status();
+        assert_eq!(status[0].value, Some(5.0));
+        assert_eq!(status[0].state, AlertState::Firing);
+        assert_eq!(status[1].state, AlertState::Inactive);
+
+        // The window moves on: 150 requests over the last 30s, then none
+        engine.evaluate(at(40), requests(150, 200));
+        assert_eq!(engine.status()[0].value, Some(5.0));
+        let notifications = engine.evaluate(at(70), requests(150, 200));
+        assert_eq!(engine.status()[0].value, Some(0.0));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].event, NotificationEvent::AlertResolved);
+        assert_eq!(engine.status()[1].state, AlertState::Firing);
+        assert!(engine.samples.len() <= 4);
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_unchanged_rules() {
+        let timeouts = rule("agent_timeout_rate > 5%", 0, 10);
+        let mut engine = AlertEngine::new(std::slice::from_ref(&timeouts));
+        let start = Instant::now();
+        engine.evaluate(start, sample(0, 0));
+        engine.evaluate(start + Duration::from_secs(10), sample(10, 10));
+        assert_eq!(engine.status()[0].state, AlertState::Firing);
+
+        let mut errors = rule("error_rate > 1%", 0, 10);
+        errors.name = "errors".to_string();
+        engine.reconfigure(&[errors, timeouts]);
+        let status = engine.status();
+        assert_eq!(status[0].state, AlertState::Inactive);
+        assert_eq!(status[1].state, AlertState::Firing);
+        assert_eq!(status[1].condition, "agent_timeout_rate > 5%");
+    }
+}
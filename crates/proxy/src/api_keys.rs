@@ -0,0 +1,638 @@
+//! API key authentication for the `api-key` filter.
+//!
+//! Each filter reads its keys from a local key file (JSON, or YAML by
+//! extension). Keys are stored as SHA-256 hashes, never in plain text:
+//!
+//! ```json
+//! {
+//!   "keys": [
+//!     {
+//!       "id": "acme-prod",
+//!       "hash": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+//!       "owner": "acme",
+//!       "routes": ["orders-api"],
+//!       "rate-tier": "gold"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! A key without `routes` is valid on every route using the filter. Valid
+//! keys set the `api_key_id`, `api_key_owner` and `api_key_tier` request
+//! tags, so `enable-if` conditions and agents can act on the caller's tier.
+//!
+//! Key files are watched and reloaded when they change; a file that fails to
+//! parse keeps the previous keys. Keys are revoked either in the key file
+//! (`"revoked": true`) or at runtime through the `api-keys` admin handler.
+//! Runtime revocations apply to every filter, survive file and config
+//! reloads, and last until restart.
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use parking_lot::{Mutex, RwLock};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use dashmap::DashMap;
+use zentinel_agent_protocol::RequestTag;
+use zentinel_config::ApiKeyFilter;
+
+/// Authentication attempts per filter and outcome
+static API_KEY_REQUESTS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_api_key_requests_total",
+        "Requests checked by api-key filters, by outcome",
+        &["filter", "outcome"]
+    )
+    .ok()
+});
+
+/// Errors from loading a key file
+#[derive(Debug, Error)]
+pub enum ApiKeyError {
+    /// The key file could not be read
+    #[error("failed to read API key file '{path}': {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The key file is not valid JSON or YAML, or has invalid entries
+    #[error("invalid API key file '{path}': {message}")]
+    Parse { path: String, message: String },
+
+    /// The file watcher could not be started
+    #[error("failed to watch API key files: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+/// Key file contents
+#[derive(Debug, Deserialize)]
+struct KeyFile {
+    #[serde(default)]
+    keys: Vec<KeyFileEntry>,
+}
+
+/// A key as stored in the key file
+#[derive(Debug, Deserialize)]
+struct KeyFileEntry {
+    id: String,
+    /// Hex SHA-256 of the key, optionally prefixed with `sha256:`
+    hash: String,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    routes: Vec<String>,
+    #[serde(default, rename = "rate-tier", alias = "rate_tier")]
+    rate_tier: Option<String>,
+    #[serde(default)]
+    revoked: bool,
+}
+
+/// Metadata of a stored key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiKey {
+    /// Key ID, unique within its file
+    pub id: String,
+    /// Owner of the key
+    pub owner: Option<String>,
+    /// Routes the key may be used on (empty: all routes)
+    pub routes: Vec<String>,
+    /// Rate tier of the key
+    pub rate_tier: Option<String>,
+    /// Revoked in the key file
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    /// Request tags identifying the key
+    pub fn tags(&self) -> Vec<RequestTag> {
+        let mut tags = vec![RequestTag::new("api_key_id", &self.id)];
+        if let Some(ref owner) = self.owner {
+            tags.push(RequestTag::new("api_key_owner", owner));
+        }
+        if let Some(ref tier) = self.rate_tier {
+            tags.push(RequestTag::low_cardinality("api_key_tier", tier));
+        }
+        tags
+    }
+}
+
+/// Keys of one key file, indexed by hash
+#[derive(Debug)]
+pub struct ApiKeyStore {
+    /// Canonical path of the key file
+    path: PathBuf,
+    by_hash: HashMap<[u8; 32], Arc<ApiKey>>,
+}
+
+impl ApiKeyStore {
+    /// Read and index a key file
+    pub fn load(path: &Path) -> Result<Self, ApiKeyError> {
+        let io_error = |source| ApiKeyError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let content = std::fs::read_to_string(path).map_err(io_error)?;
+        let path = std::fs::canonicalize(path).map_err(io_error)?;
+        let parse_error = |message: String| ApiKeyError::Parse {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let file: KeyFile = if path.extension().is_some_and(|e| e == "yaml" || e == "yml") {
+            serde_yaml::from_str(&content).map_err(|e| parse_error(e.to_string()))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| parse_error(e.to_string()))?
+        };
+
+        let mut by_hash = HashMap::with_capacity(file.keys.len());
+        let mut ids = HashSet::new();
+        for entry in file.keys {
+            if !ids.insert(entry.id.clone()) {
+                return Err(parse_error(format!("duplicate key ID '{}'", entry.id)));
+            }
+            let hash = parse_hash(&entry.hash).ok_or_else(|| {
+                parse_error(format!(
+                    "key '{}' has an invalid hash (expected 64 hex digits of SHA-256)",
+                    entry.id
+                ))
+            })?;
+            let key = ApiKey {
+                id: entry.id,
+                owner: entry.owner,
+                routes: entry.routes,
+                rate_tier: entry.rate_tier,
+                revoked: entry.revoked,
+            };
+            if by_hash.insert(hash, Arc::new(key)).is_some() {
+                return Err(parse_error("two keys have the same hash".to_string()));
+            }
+        }
+
+        Ok(Self { path, by_hash })
+    }
+
+    /// Key matching a presented secret
+    pub fn lookup(&self, secret: &str) -> Option<&Arc<ApiKey>> {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Sha256::digest(secret.as_bytes()));
+        self.by_hash.get(&hash)
+    }
+
+    /// All stored keys
+    pub fn keys(&self) -> impl Iterator<Item = &Arc<ApiKey>> {
+        self.by_hash.values()
+    }
+
+    /// Canonical path of the key file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Decode a `sha256:`-prefixed or bare hex digest
+fn parse_hash(hash: &str) -> Option<[u8; 32]> {
+    let hex_digest = hash.strip_prefix("sha256:").unwrap_or(hash);
+    let mut digest = [0u8; 32];
+    hex::decode_to_slice(hex_digest, &mut digest).ok()?;
+    Some(digest)
+}
+
+/// Outcome of checking a request's key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyOutcome {
+    /// The key is valid for the route
+    Valid(Arc<ApiKey>),
+    /// The request carries no key
+    Missing,
+    /// The key is not in the store
+    Invalid,
+    /// The key was revoked in the key file or through the admin handler
+    Revoked(Arc<ApiKey>),
+    /// The key is not allowed on the route
+    Forbidden(Arc<ApiKey>),
+}
+
+impl ApiKeyOutcome {
+    /// Label used in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Valid(_) => "valid",
+            Self::Missing => "missing",
+            Self::Invalid => "invalid",
+            Self::Revoked(_) => "revoked",
+            Self::Forbidden(_) => "forbidden",
+        }
+    }
+
+    /// Response status for a rejected request
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::Valid(_) => 200,
+            Self::Forbidden(_) => 403,
+            _ => 401,
+        }
+    }
+}
+
+/// Key status for the admin handler (hashes are never exposed)
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyStatus {
+    /// Filter whose key file holds the key
+    pub filter: String,
+    #[serde(flatten)]
+    pub key: ApiKey,
+    /// Revoked through the admin handler
+    pub revoked_at_runtime: bool,
+}
+
+// =============================================================================
+// ApiKeyManager
+// =============================================================================
+
+/// Key stores of all `api-key` filters and runtime revocations
+pub struct ApiKeyManager {
+    /// Filter ID → key store
+    stores: DashMap<String, Arc<ApiKeyStore>>,
+    /// Key IDs revoked through the admin handler
+    revoked: RwLock<BTreeSet<String>>,
+    /// Watches the directories of the key files
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    /// Directories currently watched
+    watched: Mutex<HashSet<PathBuf>>,
+}
+
+impl ApiKeyManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            stores: DashMap::new(),
+            revoked: RwLock::new(BTreeSet::new()),
+            watcher: Mutex::new(None),
+            watched: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Load a filter's key file
+    pub fn register_filter(
+        &self,
+        filter_id: &str,
+        config: &ApiKeyFilter,
+    ) -> Result<(), ApiKeyError> {
+        let store = ApiKeyStore::load(Path::new(&config.key_file))?;
+        debug!(
+            filter_id = %filter_id,
+            keys = store.by_hash.len(),
+            "Registered api-key filter"
+        );
+        self.stores.insert(filter_id.to_string(), Arc::new(store));
+        Ok(())
+    }
+
+    /// Get all filter IDs
+    pub fn filter_ids(&self) -> Vec<String> {
+        self.stores.iter().map(|r| r.key().clone()).collect()
+    }
+
+    /// Reload key files from a new configuration
+    ///
+    /// A key file that fails to load keeps its previous keys, if any.
+    pub fn reload(&self, filters: &HashMap<String, ApiKeyFilter>) {
+        self.stores.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            if let Err(e) = self.register_filter(filter_id, config) {
+                warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload API key file"
+                );
+            }
+        }
+        self.sync_watches();
+    }
+
+    /// Check the key presented to a filter on a route
+    ///
+    /// A filter whose key file is not loaded rejects every key.
+    pub fn authenticate(
+        &self,
+        filter_id: &str,
+        secret: Option<&str>,
+        route_id: Option<&str>,
+    ) -> ApiKeyOutcome {
+        let outcome = match secret.filter(|s| !s.is_empty()) {
+            None => ApiKeyOutcome::Missing,
+            Some(secret) => match self
+                .stores
+                .get(filter_id)
+                .and_then(|s| s.lookup(secret).cloned())
+            {
+                None => ApiKeyOutcome::Invalid,
+                Some(key) if key.revoked || self.revoked.read().contains(&key.id) => {
+                    ApiKeyOutcome::Revoked(key)
+                }
+                Some(key)
+                    if !key.routes.is_empty()
+                        && !route_id.is_some_and(|r| key.routes.iter().any(|k| k == r)) =>
+                {
+                    ApiKeyOutcome::Forbidden(key)
+                }
+                Some(key) => ApiKeyOutcome::Valid(key),
+            },
+        };
+
+        if let Some(metric) = API_KEY_REQUESTS.as_ref() {
+            metric
+                .with_label_values(&[filter_id, outcome.as_str()])
+                .inc();
+        }
+        outcome
+    }
+
+    /// Revoke a key ID at runtime
+    ///
+    /// Returns `false` if no loaded key file has the ID.
+    pub fn revoke(&self, key_id: &str) -> bool {
+        if !self.has_key(key_id) {
+            return false;
+        }
+        self.revoked.write().insert(key_id.to_string());
+        info!(key_id = %key_id, "API key revoked");
+        true
+    }
+
+    /// Lift a runtime revocation
+    ///
+    /// Returns `false` if the key was not revoked at runtime. Keys revoked
+    /// in their key file stay revoked.
+    pub fn restore(&self, key_id: &str) -> bool {
+        let restored = self.revoked.write().remove(key_id);
+        if restored {
+            info!(key_id = %key_id, "API key revocation lifted");
+        }
+        restored
+    }
+
+    fn has_key(&self, key_id: &str) -> bool {
+        self.stores
+            .iter()
+            .any(|store| store.keys().any(|key| key.id == key_id))
+    }
+
+    /// Status of every stored key, sorted by filter and key ID
+    pub fn status(&self) -> Vec<ApiKeyStatus> {
+        let revoked = self.revoked.read();
+        let mut status: Vec<ApiKeyStatus> = self
+            .stores
+            .iter()
+            .flat_map(|store| {
+                let filter = store.key().clone();
+                store
+                    .keys()
+                    .map(|key| ApiKeyStatus {
+                        filter: filter.clone(),
+                        key: key.as_ref().clone(),
+                        revoked_at_runtime: revoked.contains(&key.id),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        status.sort_by(|a, b| (&a.filter, &a.key.id).cmp(&(&b.filter, &b.key.id)));
+        status
+    }
+
+    // =========================================================================
+    // Key file watching
+    // =========================================================================
+
+    /// Start watching the key files; changed paths are sent to the returned
+    /// channel for [`handle_change`](Self::handle_change)
+    ///
+    /// The directories are watched rather than the files, so files replaced
+    /// by a rename are picked up.
+    pub fn start_watching(&self) -> Result<mpsc::Receiver<PathBuf>, ApiKeyError> {
+        let (tx, rx) = mpsc::channel::<PathBuf>(16);
+        let watcher = notify::recommended_watcher(move |event: Result<Event, notify::Error>| {
+            if let Ok(event) = event {
+                if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    for path in event.paths {
+                        // Events are debounced by the receiver, so a full
+                        // channel can drop them
+                        let _ = tx.try_send(path);
+                    }
+                }
+            }
+        })?;
+        *self.watcher.lock() = Some(watcher);
+        self.watched.lock().clear();
+        self.sync_watches();
+        Ok(rx)
+    }
+
+    /// Watch the directories of the current key files
+    fn sync_watches(&self) {
+        let mut watcher = self.watcher.lock();
+        let Some(watcher) = watcher.as_mut() else {
+            return;
+        };
+        let dirs: HashSet<PathBuf> = self
+            .stores
+            .iter()
+            .filter_map(|store| store.path().parent().map(Path::to_path_buf))
+            .collect();
+
+        let mut watched = self.watched.lock();
+        for dir in watched.difference(&dirs) {
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&watched) {
+            match watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => info!(path = %dir.display(), "Watching API key files for changes"),
+                Err(e) => warn!(
+                    path = %dir.display(),
+                    error = %e,
+                    "Failed to watch API key directory"
+                ),
+            }
+        }
+        *watched = dirs;
+    }
+
+    /// Reload the stores backed by a changed file
+    pub fn handle_change(&self, path: &Path) {
+        let changed: Vec<(String, PathBuf)> = self
+            .stores
+            .iter()
+            .filter(|store| store.path() == path)
+            .map(|store| (store.key().clone(), store.path().to_path_buf()))
+            .collect();
+
+        for (filter_id, path) in changed {
+            match ApiKeyStore::load(&path) {
+                Ok(store) => {
+                    info!(
+                        filter_id = %filter_id,
+                        keys = store.by_hash.len(),
+                        "API key file reloaded"
+                    );
+                    self.stores.insert(filter_id, Arc::new(store));
+                }
+                Err(e) => warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload API key file, keeping previous keys"
+                ),
+            }
+        }
+    }
+}
+
+impl Default for ApiKeyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn hash(secret: &str) -> String {
+        format!("sha256:{}", hex::encode(Sha256::digest(secret.as_bytes())))
+    }
+
+    fn key_file(dir: &tempfile::TempDir, keys: serde_json::Value) -> PathBuf {
+        let path = dir.path().join("keys.json");
+        let mut file = std::fs::File::create(&path).unwrap();
+        write!(file, "{}", serde_json::json!({ "keys": keys })).unwrap();
+        path
+    }
+
+    fn manager_with(path: &Path) -> ApiKeyManager {
+        let manager = ApiKeyManager::new();
+        manager
+            .register_filter("keys", &ApiKeyFilter::new(path.display().to_string()))
+            .unwrap();
+        manager
+    }
+
+    #[test]
+    fn authenticates_keys_by_hash_and_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = key_file(
+            &dir,
+            serde_json::json!([
+                { "id": "acme", "hash": hash("s3cret"), "owner": "acme", "rate-tier": "gold" },
+                { "id": "orders-only", "hash": hash("orders"), "routes": ["orders"] },
+                { "id": "old", "hash": hash("old"), "revoked": true }
+            ]),
+        );
+        let manager = manager_with(&path);
+
+        let ApiKeyOutcome::Valid(key) = manager.authenticate("keys", Some("s3cret"), Some("api"))
+        else {
+            panic!("expected a valid key");
+        };
+        let tags = key.tags();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[2].name, "api_key_tier");
+        assert_eq!(tags[2].value, "gold");
+
+        assert_eq!(
+            manager.authenticate("keys", None, Some("api")),
+            ApiKeyOutcome::Missing
+        );
+        assert_eq!(
+            manager.authenticate("keys", Some("wrong"), Some("api")),
+            ApiKeyOutcome::Invalid
+        );
+        assert!(matches!(
+            manager.authenticate("keys", Some("old"), Some("api")),
+            ApiKeyOutcome::Revoked(_)
+        ));
+        let outcome = manager.authenticate("keys", Some("orders"), Some("api"));
+        assert!(matches!(outcome, ApiKeyOutcome::Forbidden(_)));
+        assert_eq!(outcome.status(), 403);
+        assert!(matches!(
+            manager.authenticate("keys", Some("orders"), Some("orders")),
+            ApiKeyOutcome::Valid(_)
+        ));
+    }
+
+    #[test]
+    fn runtime_revocation_survives_file_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = key_file(
+            &dir,
+            serde_json::json!([{ "id": "acme", "hash": hash("s3cret") }]),
+        );
+        let manager = manager_with(&path);
+
+        assert!(!manager.revoke("unknown"));
+        assert!(manager.revoke("acme"));
+        assert!(matches!(
+            manager.authenticate("keys", Some("s3cret"), None),
+            ApiKeyOutcome::Revoked(_)
+        ));
+        assert!(manager.status()[0].revoked_at_runtime);
+
+        // A rotated key file is picked up; the revocation still applies
+        key_file(
+            &dir,
+            serde_json::json!([
+                { "id": "acme", "hash": hash("s3cret") },
+                { "id": "globex", "hash": hash("rotated") }
+            ]),
+        );
+        manager.handle_change(&std::fs::canonicalize(&path).unwrap());
+        assert!(matches!(
+            manager.authenticate("keys", Some("rotated"), None),
+            ApiKeyOutcome::Valid(_)
+        ));
+        assert!(matches!(
+            manager.authenticate("keys", Some("s3cret"), None),
+            ApiKeyOutcome::Revoked(_)
+        ));
+
+        assert!(manager.restore("acme"));
+        assert!(matches!(
+            manager.authenticate("keys", Some("s3cret"), None),
+            ApiKeyOutcome::Valid(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_key_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = key_file(
+            &dir,
+            serde_json::json!([{ "id": "a", "hash": "plain-text" }]),
+        );
+        let err = ApiKeyStore::load(&path).unwrap_err();
+        assert!(err.to_string().contains("invalid hash"));
+
+        let path = key_file(
+            &dir,
+            serde_json::json!([
+                { "id": "a", "hash": hash("x") },
+                { "id": "a", "hash": hash("y") }
+            ]),
+        );
+        let err = ApiKeyStore::load(&path).unwrap_err();
+        assert!(err.to_string().contains("duplicate key ID"));
+    }
+}
@@ -0,0 +1,477 @@
+//! Queryable store of agent decisions for incident investigation.
+//!
+//! The audit log is written for blocks and other security events; this
+//! store keeps every agent response (decision and audit metadata) by
+//! correlation ID, so the whole decision chain of a request can be pulled
+//! up later through the `audit` admin handler
+//! (`GET /audit?correlation_id=...`).
+//!
+//! Records live in memory, or in a SQLite file when built with the
+//! `audit-store-sqlite` feature. SQLite writes are batched on a dedicated
+//! thread; when it falls behind, records are dropped rather than slowing
+//! down requests. Both backends drop records older than `retention-secs`
+//! and the oldest records beyond `max-records`.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::warn;
+
+use zentinel_agent_protocol::{AgentResponse, AuditMetadata, Decision, EventType};
+use zentinel_config::{AuditStoreBackend, AuditStoreConfig};
+
+/// Records handed to the store, by outcome
+static AUDIT_STORE_RECORDS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_audit_store_records_total",
+        "Agent responses handed to the audit store",
+        &["outcome"]
+    )
+    .ok()
+});
+
+fn count_record(outcome: &str) {
+    if let Some(counter) = AUDIT_STORE_RECORDS.as_ref() {
+        counter.with_label_values(&[outcome]).inc();
+    }
+}
+
+/// One agent response, as kept by the store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub correlation_id: String,
+    /// When the agent answered
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route_id: Option<String>,
+    pub agent_id: String,
+    pub event_type: EventType,
+    pub decision: Decision,
+    pub audit: AuditMetadata,
+    /// Time the agent took to answer
+    pub duration_ms: u64,
+}
+
+impl AuditRecord {
+    /// Record of an agent's response, timestamped now
+    pub fn new(
+        correlation_id: &str,
+        route_id: Option<&str>,
+        agent_id: &str,
+        event_type: EventType,
+        response: &AgentResponse,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            correlation_id: correlation_id.to_string(),
+            timestamp: Utc::now(),
+            route_id: route_id.map(str::to_string),
+            agent_id: agent_id.to_string(),
+            event_type,
+            decision: response.decision.clone(),
+            audit: response.audit.clone(),
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Store of agent responses, looked up by correlation ID
+pub struct AuditStore {
+    backend: Backend,
+    max_records: usize,
+    retention: Duration,
+}
+
+enum Backend {
+    Memory(MemoryBackend),
+    #[cfg(feature = "audit-store-sqlite")]
+    Sqlite(sqlite::SqliteBackend),
+}
+
+impl AuditStore {
+    /// Create the store configured in `audit-log { store { ... } }`.
+    ///
+    /// Falls back to the memory backend when the SQLite file cannot be
+    /// opened.
+    pub fn new(config: &AuditStoreConfig) -> Self {
+        let retention = Duration::from_secs(config.retention_secs);
+        let memory = || Backend::Memory(MemoryBackend::default());
+        let backend = match &config.backend {
+            AuditStoreBackend::Memory => memory(),
+            #[cfg(feature = "audit-store-sqlite")]
+            AuditStoreBackend::Sqlite { path } => {
+                match sqlite::SqliteBackend::open(path, config.max_records, retention) {
+                    Ok(backend) => Backend::Sqlite(backend),
+                    Err(e) => {
+                        warn!(
+                            path = %path.display(),
+                            error = %e,
+                            "Failed to open audit store database, keeping records in memory"
+                        );
+                        memory()
+                    }
+                }
+            }
+            #[cfg(not(feature = "audit-store-sqlite"))]
+            AuditStoreBackend::Sqlite { .. } => {
+                warn!(
+                    "SQLite audit store requested but the 'audit-store-sqlite' feature is \
+                     disabled. Keeping records in memory."
+                );
+                memory()
+            }
+        };
+
+        Self {
+            backend,
+            max_records: config.max_records,
+            retention,
+        }
+    }
+
+    /// Name of the backend in use
+    pub fn backend_name(&self) -> &'static str {
+        match &self.backend {
+            Backend::Memory(_) => "memory",
+            #[cfg(feature = "audit-store-sqlite")]
+            Backend::Sqlite(_) => "sqlite",
+        }
+    }
+
+    /// Keep a record
+    pub fn record(&self, record: AuditRecord) {
+        match &self.backend {
+            Backend::Memory(memory) => {
+                memory.insert(record, self.max_records, self.retention);
+                count_record("stored");
+            }
+            #[cfg(feature = "audit-store-sqlite")]
+            Backend::Sqlite(sqlite) => {
+                count_record(if sqlite.insert(record) {
+                    "stored"
+                } else {
+                    "dropped"
+                });
+            }
+        }
+    }
+
+    /// Records of a request, oldest first
+    pub fn query(&self, correlation_id: &str) -> Result<Vec<AuditRecord>, String> {
+        match &self.backend {
+            Backend::Memory(memory) => Ok(memory.query(correlation_id, self.retention)),
+            #[cfg(feature = "audit-store-sqlite")]
+            Backend::Sqlite(sqlite) => sqlite.query(correlation_id),
+        }
+    }
+}
+
+/// Cutoff before which records are expired
+fn expiry_cutoff(retention: Duration) -> DateTime<Utc> {
+    let retention = chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+    Utc::now()
+        .checked_sub_signed(retention)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Records in arrival order
+#[derive(Default)]
+struct MemoryBackend {
+    records: Mutex<VecDeque<AuditRecord>>,
+}
+
+impl MemoryBackend {
+    fn insert(&self, record: AuditRecord, max_records: usize, retention: Duration) {
+        let cutoff = expiry_cutoff(retention);
+        let mut records = self.records.lock();
+        while records
+            .front()
+            .is_some_and(|oldest| oldest.timestamp < cutoff)
+        {
+            records.pop_front();
+        }
+        records.push_back(record);
+        while records.len() > max_records {
+            records.pop_front();
+        }
+    }
+
+    fn query(&self, correlation_id: &str, retention: Duration) -> Vec<AuditRecord> {
+        let cutoff = expiry_cutoff(retention);
+        self.records
+            .lock()
+            .iter()
+            .filter(|r| r.correlation_id == correlation_id && r.timestamp >= cutoff)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(feature = "audit-store-sqlite")]
+mod sqlite {
+    use rusqlite::{params, Connection};
+    use std::path::Path;
+    use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+    use std::time::{Duration, Instant};
+    use tracing::warn;
+
+    use super::{expiry_cutoff, AuditRecord};
+    use parking_lot::Mutex;
+
+    /// Records waiting for the writer thread before new ones are dropped
+    const QUEUE_SIZE: usize = 16 * 1024;
+    /// Records written per transaction at most
+    const WRITE_BATCH: usize = 512;
+    /// How often expired and excess records are deleted
+    const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+    const SCHEMA: &str = "
+        PRAGMA journal_mode = WAL;
+        PRAGMA synchronous = NORMAL;
+        CREATE TABLE IF NOT EXISTS agent_decisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            correlation_id TEXT NOT NULL,
+            recorded_at INTEGER NOT NULL,
+            record TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS agent_decisions_correlation_id
+            ON agent_decisions (correlation_id);
+        CREATE INDEX IF NOT EXISTS agent_decisions_recorded_at
+            ON agent_decisions (recorded_at);
+    ";
+
+    /// SQLite file written by a dedicated thread
+    pub(super) struct SqliteBackend {
+        queue: SyncSender<AuditRecord>,
+        reader: Mutex<Connection>,
+    }
+
+    impl SqliteBackend {
+        pub(super) fn open(
+            path: &Path,
+            max_records: usize,
+            retention: Duration,
+        ) -> Result<Self, String> {
+            let writer = connect(path).map_err(|e| e.to_string())?;
+            let reader = connect(path).map_err(|e| e.to_string())?;
+            let (queue, rx) = sync_channel(QUEUE_SIZE);
+            std::thread::Builder::new()
+                .name("audit-store".to_string())
+                .spawn(move || run_writer(writer, rx, max_records, retention))
+                .map_err(|e| e.to_string())?;
+            Ok(Self {
+                queue,
+                reader: Mutex::new(reader),
+            })
+        }
+
+        /// Queue a record; `false` when the writer is behind and it was dropped
+        pub(super) fn insert(&self, record: AuditRecord) -> bool {
+            self.queue.try_send(record).is_ok()
+        }
+
+        pub(super) fn query(&self, correlation_id: &str) -> Result<Vec<AuditRecord>, String> {
+            let reader = self.reader.lock();
+            let mut stmt = reader
+                .prepare_cached(
+                    "SELECT record FROM agent_decisions WHERE correlation_id = ?1 ORDER BY id",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([correlation_id], |row| row.get::<_, String>(0))
+                .map_err(|e| e.to_string())?;
+
+            let mut records = Vec::new();
+            for row in rows {
+                let json = row.map_err(|e| e.to_string())?;
+                match serde_json::from_str(&json) {
+                    Ok(record) => records.push(record),
+                    Err(e) => warn!(error = %e, "Skipping unreadable audit store record"),
+                }
+            }
+            Ok(records)
+        }
+    }
+
+    fn connect(path: &Path) -> rusqlite::Result<Connection> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(conn)
+    }
+
+    /// Write queued records until the store is dropped
+    fn run_writer(
+        mut conn: Connection,
+        rx: Receiver<AuditRecord>,
+        max_records: usize,
+        retention: Duration,
+    ) {
+        let mut last_prune = Instant::now();
+        while let Ok(first) = rx.recv() {
+            let batch: Vec<_> = std::iter::once(first)
+                .chain(rx.try_iter().take(WRITE_BATCH - 1))
+                .collect();
+            if let Err(e) = insert_batch(&mut conn, &batch) {
+                warn!(error = %e, records = batch.len(), "Failed to write audit store records");
+            }
+            if last_prune.elapsed() >= PRUNE_INTERVAL {
+                if let Err(e) = prune(&conn, max_records, retention) {
+                    warn!(error = %e, "Failed to prune audit store");
+                }
+                last_prune = Instant::now();
+            }
+        }
+    }
+
+    fn insert_batch(conn: &mut Connection, batch: &[AuditRecord]) -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO agent_decisions (correlation_id, recorded_at, record) \
+                 VALUES (?1, ?2, ?3)",
+            )?;
+            for record in batch {
+                let json = match serde_json::to_string(record) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to serialize audit store record");
+                        continue;
+                    }
+                };
+                stmt.execute(params![
+                    record.correlation_id,
+                    record.timestamp.timestamp_millis(),
+                    json
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    fn prune(conn: &Connection, max_records: usize, retention: Duration) -> rusqlite::Result<()> {
+        conn.execute(
+            "DELETE FROM agent_decisions WHERE recorded_at < ?1",
+            [expiry_cutoff(retention).timestamp_millis()],
+        )?;
+        conn.execute(
+            "DELETE FROM agent_decisions \
+             WHERE id <= (SELECT MAX(id) FROM agent_decisions) - ?1",
+            [max_records as i64],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(decision: Decision, rule: &str) -> AgentResponse {
+        let mut response = AgentResponse::default_allow();
+        response.decision = decision;
+        response.audit.rule_ids = vec![rule.to_string()];
+        response
+    }
+
+    fn record(correlation_id: &str, agent_id: &str, decision: Decision) -> AuditRecord {
+        AuditRecord::new(
+            correlation_id,
+            Some("api"),
+            agent_id,
+            EventType::RequestHeaders,
+            &response(decision, "942100"),
+            Duration::from_millis(3),
+        )
+    }
+
+    #[test]
+    fn returns_the_decision_chain_of_a_request() {
+        let store = AuditStore::new(&AuditStoreConfig::default());
+        assert_eq!(store.backend_name(), "memory");
+
+        store.record(record("req-1", "auth", Decision::Allow));
+        store.record(record("req-2", "auth", Decision::Allow));
+        store.record(record(
+            "req-1",
+            "waf",
+            Decision::Block {
+                status: 403,
+                body: None,
+                headers: None,
+            },
+        ));
+
+        let chain = store.query("req-1").unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].agent_id, "auth");
+        assert_eq!(chain[1].agent_id, "waf");
+        assert!(matches!(
+            chain[1].decision,
+            Decision::Block { status: 403, .. }
+        ));
+        assert_eq!(chain[1].audit.rule_ids, vec!["942100".to_string()]);
+        assert_eq!(chain[1].route_id.as_deref(), Some("api"));
+        assert!(store.query("req-3").unwrap().is_empty());
+    }
+
+    #[test]
+    fn drops_the_oldest_records_past_the_limit() {
+        let store = AuditStore::new(&AuditStoreConfig {
+            max_records: 2,
+            ..Default::default()
+        });
+        store.record(record("req-1", "waf", Decision::Allow));
+        store.record(record("req-2", "waf", Decision::Allow));
+        store.record(record("req-3", "waf", Decision::Allow));
+
+        assert!(store.query("req-1").unwrap().is_empty());
+        assert_eq!(store.query("req-3").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn expires_records_after_the_retention() {
+        let store = AuditStore::new(&AuditStoreConfig::default());
+        let mut old = record("req-1", "waf", Decision::Allow);
+        old.timestamp -= chrono::Duration::days(8);
+        store.record(old);
+        store.record(record("req-2", "waf", Decision::Allow));
+
+        assert!(store.query("req-1").unwrap().is_empty());
+        assert_eq!(store.query("req-2").unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "audit-store-sqlite")]
+    #[test]
+    fn persists_records_in_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.db");
+        let config = AuditStoreConfig {
+            backend: AuditStoreBackend::Sqlite { path: path.clone() },
+            ..Default::default()
+        };
+
+        let store = AuditStore::new(&config);
+        assert_eq!(store.backend_name(), "sqlite");
+        store.record(record("req-1", "auth", Decision::Allow));
+        store.record(record("req-1", "waf", Decision::Allow));
+
+        // Records are written by a background thread
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while store.query("req-1").unwrap().len() < 2 {
+            assert!(std::time::Instant::now() < deadline, "records not written");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // A new store sees the records of the previous one
+        drop(store);
+        let reopened = AuditStore::new(&config);
+        let chain = reopened.query("req-1").unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1].agent_id, "waf");
+    }
+}
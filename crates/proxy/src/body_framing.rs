@@ -0,0 +1,161 @@
+//! Record framing of request bodies streamed to agents
+//!
+//! Agents in `stream` body mode get one event per body chunk, and chunks
+//! follow the network, not the records of NDJSON, SSE or length-prefixed
+//! APIs. With `policies { body-framing "..." }` the proxy holds back the
+//! incomplete record at the end of each chunk and forwards it with the next
+//! one, so every chunk event (and every chunk sent upstream) ends on a record
+//! boundary.
+//!
+//! A record that grows past [`MAX_PENDING_BYTES`] without ending is released
+//! as is, so a body that doesn't follow its framing can't be buffered
+//! without bound.
+
+use bytes::{Bytes, BytesMut};
+use tracing::debug;
+
+use zentinel_config::BodyFraming;
+
+/// Longest incomplete record held back
+pub const MAX_PENDING_BYTES: usize = 1024 * 1024;
+
+/// Length of a length-prefixed record's header
+const LENGTH_PREFIX_BYTES: usize = 4;
+
+/// Cuts a streamed body on record boundaries
+#[derive(Debug)]
+pub struct RecordFramer {
+    framing: BodyFraming,
+    pending: BytesMut,
+}
+
+impl RecordFramer {
+    /// Framer for bodies in `framing`
+    pub fn new(framing: BodyFraming) -> Self {
+        Self {
+            framing,
+            pending: BytesMut::new(),
+        }
+    }
+
+    /// Add a chunk and return the complete records received so far, `None`
+    /// while the first pending record is incomplete. At end of stream the
+    /// rest of the body is returned whether it ends a record or not.
+    pub fn push(&mut self, chunk: Option<Bytes>, end_of_stream: bool) -> Option<Bytes> {
+        if let Some(chunk) = chunk {
+            self.pending.extend_from_slice(&chunk);
+        }
+        if self.pending.is_empty() {
+            return None;
+        }
+        if end_of_stream {
+            return Some(std::mem::take(&mut self.pending).freeze());
+        }
+
+        match self.boundary() {
+            0 if self.pending.len() > MAX_PENDING_BYTES => {
+                debug!(
+                    framing = self.framing.as_str(),
+                    pending_bytes = self.pending.len(),
+                    "Record exceeds framing limit, releasing it unframed"
+                );
+                Some(std::mem::take(&mut self.pending).freeze())
+            }
+            0 => None,
+            end => Some(self.pending.split_to(end).freeze()),
+        }
+    }
+
+    /// Length of the complete records at the start of the pending bytes
+    fn boundary(&self) -> usize {
+        let buf = &self.pending[..];
+        match self.framing {
+            BodyFraming::Ndjson => buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1),
+            BodyFraming::Sse => (1..buf.len())
+                .rev()
+                .find(|&i| ends_sse_event(&buf[..=i]))
+                .map_or(0, |i| i + 1),
+            BodyFraming::LengthPrefixed => {
+                let mut end = 0;
+                let mut rest = buf;
+                while rest.len() >= LENGTH_PREFIX_BYTES {
+                    let mut prefix = [0u8; LENGTH_PREFIX_BYTES];
+                    prefix.copy_from_slice(&rest[..LENGTH_PREFIX_BYTES]);
+                    let record = LENGTH_PREFIX_BYTES + u32::from_be_bytes(prefix) as usize;
+                    if rest.len() < record {
+                        break;
+                    }
+                    end += record;
+                    rest = &rest[record..];
+                }
+                end
+            }
+        }
+    }
+}
+
+/// Whether `buf` ends with the blank line closing an SSE event ("\n\n",
+/// "\r\n\r\n" or "\n\r\n")
+fn ends_sse_event(buf: &[u8]) -> bool {
+    buf.ends_with(b"\n\n") || buf.ends_with(b"\n\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(framer: &mut RecordFramer, chunk: &'static [u8], end: bool) -> Option<Bytes> {
+        framer.push(Some(Bytes::from_static(chunk)), end)
+    }
+
+    #[test]
+    fn test_ndjson_records() {
+        let mut framer = RecordFramer::new(BodyFraming::Ndjson);
+        assert_eq!(push(&mut framer, br#"{"a":1}"#, false), None);
+        assert_eq!(
+            push(&mut framer, b"\n{\"b\":2}\n{\"c\"", false).as_deref(),
+            Some(&b"{\"a\":1}\n{\"b\":2}\n"[..])
+        );
+        assert_eq!(
+            push(&mut framer, b":3}", true).as_deref(),
+            Some(&b"{\"c\":3}"[..])
+        );
+        assert_eq!(framer.push(None, true), None);
+    }
+
+    #[test]
+    fn test_sse_events() {
+        let mut framer = RecordFramer::new(BodyFraming::Sse);
+        assert_eq!(push(&mut framer, b"data: one\n", false), None);
+        assert_eq!(
+            push(&mut framer, b"\ndata: two\r\n\r\ndata: th", false).as_deref(),
+            Some(&b"data: one\n\ndata: two\r\n\r\n"[..])
+        );
+        assert_eq!(
+            push(&mut framer, b"ree\n\n", false).as_deref(),
+            Some(&b"data: three\n\n"[..])
+        );
+    }
+
+    #[test]
+    fn test_length_prefixed_records() {
+        let mut framer = RecordFramer::new(BodyFraming::LengthPrefixed);
+        assert_eq!(push(&mut framer, b"\0\0\0\x03ab", false), None);
+        assert_eq!(
+            push(&mut framer, b"c\0\0\0\x02x", false).as_deref(),
+            Some(&b"\0\0\0\x03abc"[..])
+        );
+        assert_eq!(
+            push(&mut framer, b"y\0\0\0\0", false).as_deref(),
+            Some(&b"\0\0\0\x02xy\0\0\0\0"[..])
+        );
+    }
+
+    #[test]
+    fn test_oversized_record_is_released() {
+        let mut framer = RecordFramer::new(BodyFraming::Ndjson);
+        let record = Bytes::from(vec![b'x'; MAX_PENDING_BYTES + 1]);
+        let released = framer.push(Some(record), false).unwrap();
+        assert_eq!(released.len(), MAX_PENDING_BYTES + 1);
+    }
+}
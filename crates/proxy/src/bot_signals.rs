@@ -0,0 +1,719 @@
+//! Bot-detection signals for agents.
+//!
+//! On listeners with `bot-signals` enabled, the acceptor in front of Pingora
+//! (see [`crate::client_ip::proxy_protocol`]) reads the start of each
+//! connection before splicing it through:
+//!
+//! - a TLS ClientHello yields JA3 (string and MD5) and JA4 fingerprints
+//! - an HTTP/2 connection preface yields an HTTP/2 fingerprint in the Akamai
+//!   format: SETTINGS, WINDOW_UPDATE and PRIORITY frames and the
+//!   pseudo-header order of the first request
+//!
+//! HTTP/2 inside TLS is read once the acceptor has terminated TLS and the
+//! client negotiated `h2`; cleartext HTTP/2 (h2c with prior knowledge) is
+//! read directly. Each request adds a hash of its header names in the order
+//! sent ([`header_order_hash`]). Agents receive the signals in
+//! `RequestMetadata.bot_signals`.
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use zentinel_agent_protocol::BotSignals;
+
+/// Most bytes read from a connection before the signals are computed
+pub const MAX_INSPECTED: usize = 16 * 1024;
+
+/// Time allowed for a client to send its ClientHello or HTTP/2 preface
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// TLS record content type of handshake messages
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// Handshake message type of a ClientHello
+const CLIENT_HELLO: u8 = 1;
+
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const EXT_ALPN: u16 = 0x0010;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+
+/// Client connection preface of HTTP/2
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const H2_HEADERS: u8 = 0x1;
+const H2_PRIORITY: u8 = 0x2;
+const H2_SETTINGS: u8 = 0x4;
+const H2_WINDOW_UPDATE: u8 = 0x8;
+
+/// Read the start of `stream` into `buf` (after the `start` bytes already
+/// consumed) until the connection's signals are known, then compute them.
+///
+/// Gives up after a short timeout, [`MAX_INSPECTED`] bytes or the end of the
+/// stream; the bytes read stay in `buf` to be forwarded.
+pub(crate) async fn read_connection_signals<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+    start: usize,
+) -> BotSignals {
+    let deadline = tokio::time::Instant::now() + READ_TIMEOUT;
+    let mut chunk = [0u8; 4096];
+    loop {
+        let eof = buf.len() - start >= MAX_INSPECTED;
+        if let Some(signals) = connection_signals(&buf[start..], eof) {
+            return signals;
+        }
+        match tokio::time::timeout_at(deadline, stream.read(&mut chunk)).await {
+            Ok(Ok(n)) if n > 0 => buf.extend_from_slice(&chunk[..n]),
+            _ => return connection_signals(&buf[start..], true).unwrap_or_default(),
+        }
+    }
+}
+
+/// Fingerprints from the first bytes of a connection.
+///
+/// Returns `None` while more data is needed; with `eof` (no more data will
+/// be read) whatever could be computed is returned.
+pub fn connection_signals(data: &[u8], eof: bool) -> Option<BotSignals> {
+    if data.first() == Some(&TLS_HANDSHAKE) {
+        return match client_hello(data) {
+            Parsed::Complete(hello) => {
+                let ja3 = ja3(&hello);
+                Some(BotSignals {
+                    ja3_hash: Some(hex::encode(Md5::digest(ja3.as_bytes()))),
+                    ja3: Some(ja3),
+                    ja4: Some(ja4(&hello)),
+                    ..Default::default()
+                })
+            }
+            Parsed::Incomplete if !eof => None,
+            _ => Some(BotSignals::default()),
+        };
+    }
+
+    if let Some(frames) = data.strip_prefix(H2_PREFACE) {
+        return match http2_fingerprint(frames, eof) {
+            Parsed::Complete(fingerprint) => Some(BotSignals {
+                http2: Some(fingerprint),
+                ..Default::default()
+            }),
+            Parsed::Incomplete => None,
+            Parsed::Invalid => Some(BotSignals::default()),
+        };
+    }
+    if H2_PREFACE.starts_with(data) && !eof {
+        return None;
+    }
+
+    // HTTP/1 or an unknown protocol
+    Some(BotSignals::default())
+}
+
+/// Hash of a request's header names in the order sent: the first 12 hex
+/// digits of a SHA-256 over the comma-joined names.
+///
+/// `cookie` and `referer` are left out; they depend on browsing state rather
+/// than on the client software.
+pub fn header_order_hash(headers: &http::HeaderMap) -> String {
+    let names: Vec<&str> = headers
+        .keys()
+        .filter(|name| *name != http::header::COOKIE && *name != http::header::REFERER)
+        .map(|name| name.as_str())
+        .collect();
+    truncated_sha256(&names.join(","))
+}
+
+/// Result of parsing a prefix of the connection
+#[derive(Debug, PartialEq, Eq)]
+enum Parsed<T> {
+    /// More bytes are needed
+    Incomplete,
+    Complete(T),
+    /// Not what it claimed to be
+    Invalid,
+}
+
+/// ClientHello fields the TLS fingerprints are built from
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ClientHello {
+    /// Legacy version field (0x0303 for TLS 1.2 and 1.3)
+    version: u16,
+    ciphers: Vec<u16>,
+    /// Extension types in the order sent
+    extensions: Vec<u16>,
+    groups: Vec<u16>,
+    point_formats: Vec<u8>,
+    signature_algorithms: Vec<u16>,
+    supported_versions: Vec<u16>,
+    /// First ALPN protocol offered
+    alpn: Option<Vec<u8>>,
+}
+
+/// Reassemble and parse the ClientHello from the TLS records in `data`.
+fn client_hello(data: &[u8]) -> Parsed<ClientHello> {
+    let mut handshake = Vec::new();
+    let mut records = Reader(data);
+    loop {
+        // Record header: type, version, length
+        let Some(header) = records.bytes(5) else {
+            return Parsed::Incomplete;
+        };
+        if header[0] != TLS_HANDSHAKE {
+            return Parsed::Invalid;
+        }
+        let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let Some(fragment) = records.bytes(len) else {
+            return Parsed::Incomplete;
+        };
+        handshake.extend_from_slice(fragment);
+
+        if handshake.len() >= 4 {
+            if handshake[0] != CLIENT_HELLO {
+                return Parsed::Invalid;
+            }
+            let len = u32::from_be_bytes([0, handshake[1], handshake[2], handshake[3]]) as usize;
+            if len > MAX_INSPECTED {
+                return Parsed::Invalid;
+            }
+            if handshake.len() >= 4 + len {
+                return match parse_client_hello(&handshake[4..4 + len]) {
+                    Some(hello) => Parsed::Complete(hello),
+                    None => Parsed::Invalid,
+                };
+            }
+        }
+    }
+}
+
+fn parse_client_hello(body: &[u8]) -> Option<ClientHello> {
+    let mut r = Reader(body);
+    let mut hello = ClientHello {
+        version: r.u16()?,
+        ..Default::default()
+    };
+    r.bytes(32)?; // random
+    r.vec8()?; // session ID
+    hello.ciphers = u16s(r.vec16()?);
+    r.vec8()?; // compression methods
+
+    // Extensions are optional in TLS 1.2
+    let extensions = if r.0.is_empty() { &[][..] } else { r.vec16()? };
+    let mut extensions = Reader(extensions);
+    while !extensions.0.is_empty() {
+        let kind = extensions.u16()?;
+        let mut data = Reader(extensions.vec16()?);
+        hello.extensions.push(kind);
+        match kind {
+            EXT_SUPPORTED_GROUPS => hello.groups = u16s(data.vec16()?),
+            EXT_EC_POINT_FORMATS => hello.point_formats = data.vec8()?.to_vec(),
+            EXT_SIGNATURE_ALGORITHMS => hello.signature_algorithms = u16s(data.vec16()?),
+            EXT_SUPPORTED_VERSIONS => hello.supported_versions = u16s(data.vec8()?),
+            EXT_ALPN => {
+                let mut protocols = Reader(data.vec16()?);
+                hello.alpn = protocols.vec8().map(<[u8]>::to_vec);
+            }
+            _ => {}
+        }
+    }
+    Some(hello)
+}
+
+/// JA3: `version,ciphers,extensions,groups,point-formats` in decimal, list
+/// items joined by `-`, GREASE values removed.
+fn ja3(hello: &ClientHello) -> String {
+    let point_formats: Vec<String> = hello.point_formats.iter().map(u8::to_string).collect();
+    format!(
+        "{},{},{},{},{}",
+        hello.version,
+        decimal_list(&hello.ciphers),
+        decimal_list(&hello.extensions),
+        decimal_list(&hello.groups),
+        point_formats.join("-")
+    )
+}
+
+/// JA4: protocol, version, SNI, counts and first ALPN; then hashes of the
+/// sorted ciphers and of the sorted extensions with the signature algorithms.
+fn ja4(hello: &ClientHello) -> String {
+    let version = hello
+        .supported_versions
+        .iter()
+        .copied()
+        .filter(|v| !is_grease(*v))
+        .max()
+        .unwrap_or(hello.version);
+    let version = match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        _ => "00",
+    };
+    let sni = if hello.extensions.contains(&EXT_SERVER_NAME) {
+        'd'
+    } else {
+        'i'
+    };
+    let mut ciphers = without_grease(&hello.ciphers);
+    let extensions = without_grease(&hello.extensions);
+    let alpn = match hello.alpn.as_deref() {
+        Some(value) if !value.is_empty() => {
+            let (first, last) = (value[0], value[value.len() - 1]);
+            if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+                format!("{}{}", first as char, last as char)
+            } else {
+                let hex = format!("{:02x}{:02x}", first, last);
+                format!("{}{}", &hex[..1], &hex[3..])
+            }
+        }
+        _ => "00".to_string(),
+    };
+    let prefix = format!(
+        "t{}{}{:02}{:02}{}",
+        version,
+        sni,
+        ciphers.len().min(99),
+        extensions.len().min(99),
+        alpn
+    );
+
+    ciphers.sort_unstable();
+    let mut hashed_extensions: Vec<u16> = extensions
+        .into_iter()
+        .filter(|e| *e != EXT_SERVER_NAME && *e != EXT_ALPN)
+        .collect();
+    hashed_extensions.sort_unstable();
+    let mut extension_list = hex_list(&hashed_extensions);
+    let signature_algorithms = without_grease(&hello.signature_algorithms);
+    if !signature_algorithms.is_empty() {
+        extension_list = format!("{}_{}", extension_list, hex_list(&signature_algorithms));
+    }
+
+    format!(
+        "{}_{}_{}",
+        prefix,
+        ja4_hash(&ciphers, &hex_list(&ciphers)),
+        ja4_hash(&hashed_extensions, &extension_list)
+    )
+}
+
+/// HTTP/2 fingerprint of the frames after the connection preface:
+/// `settings|window-update|priorities|pseudo-headers`.
+fn http2_fingerprint(frames: &[u8], eof: bool) -> Parsed<String> {
+    let mut settings = None;
+    let mut window_update = None;
+    let mut priorities = Vec::new();
+    let mut pseudo_headers = None;
+
+    let mut r = Reader(frames);
+    while pseudo_headers.is_none() {
+        let Some(header) = r.bytes(9) else {
+            break;
+        };
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let (kind, flags) = (header[3], header[4]);
+        let stream_id =
+            u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+        if len > MAX_INSPECTED {
+            return Parsed::Invalid;
+        }
+        let Some(payload) = r.bytes(len) else {
+            break;
+        };
+
+        match kind {
+            // Acknowledgements carry no settings
+            H2_SETTINGS if flags & 0x1 == 0 && settings.is_none() => {
+                let entries: Vec<String> = payload
+                    .chunks_exact(6)
+                    .map(|e| {
+                        let id = u16::from_be_bytes([e[0], e[1]]);
+                        let value = u32::from_be_bytes([e[2], e[3], e[4], e[5]]);
+                        format!("{}:{}", id, value)
+                    })
+                    .collect();
+                settings = Some(entries.join(";"));
+            }
+            H2_WINDOW_UPDATE if stream_id == 0 && window_update.is_none() && len == 4 => {
+                let increment =
+                    u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                window_update = Some((increment & 0x7fff_ffff).to_string());
+            }
+            H2_PRIORITY if len == 5 => {
+                let dependency =
+                    u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                priorities.push(format!(
+                    "{}:{}:{}:{}",
+                    stream_id,
+                    dependency >> 31,
+                    dependency & 0x7fff_ffff,
+                    u16::from(payload[4]) + 1
+                ));
+            }
+            H2_HEADERS => {
+                let mut block = payload;
+                if flags & 0x8 != 0 {
+                    // PADDED: pad length, then padding after the block
+                    let pad = usize::from(*block.first().unwrap_or(&0));
+                    block = block
+                        .get(1..block.len().saturating_sub(pad))
+                        .unwrap_or_default();
+                }
+                if flags & 0x20 != 0 {
+                    // PRIORITY: stream dependency and weight
+                    block = block.get(5..).unwrap_or_default();
+                }
+                pseudo_headers = Some(pseudo_header_order(block));
+            }
+            _ => {}
+        }
+    }
+
+    if pseudo_headers.is_none() && !eof {
+        return Parsed::Incomplete;
+    }
+    if settings.is_none() && pseudo_headers.is_none() {
+        return Parsed::Invalid;
+    }
+    Parsed::Complete(format!(
+        "{}|{}|{}|{}",
+        settings.unwrap_or_default(),
+        window_update.as_deref().unwrap_or("00"),
+        if priorities.is_empty() {
+            "0".to_string()
+        } else {
+            priorities.join(",")
+        },
+        pseudo_headers.unwrap_or_default()
+    ))
+}
+
+/// Order of the pseudo-headers at the start of an HPACK header block, as
+/// their first letters after the colon (`m,a,s,p`).
+fn pseudo_header_order(block: &[u8]) -> String {
+    let mut order = Vec::new();
+    let mut r = Reader(block);
+    while let Some(first) = r.peek() {
+        let name = if first & 0x80 != 0 {
+            // Indexed field
+            static_pseudo_header(r.hpack_int(7))
+        } else if first & 0x20 != 0 && first & 0x40 == 0 {
+            // Dynamic table size update
+            r.hpack_int(5);
+            continue;
+        } else {
+            // Literal with incremental indexing (6-bit prefix) or without
+            // indexing / never indexed (4-bit prefix)
+            let prefix = if first & 0x40 != 0 { 6 } else { 4 };
+            let name = match r.hpack_int(prefix) {
+                Some(0) => r.hpack_string().and_then(|name| match name {
+                    [b':', letter, ..] => Some(*letter as char),
+                    _ => None,
+                }),
+                index => static_pseudo_header(index),
+            };
+            if r.hpack_string().is_none() {
+                break;
+            }
+            name
+        };
+        match name {
+            Some(letter) => order.push(letter.to_string()),
+            // Regular headers follow the pseudo-headers
+            None => break,
+        }
+    }
+    order.join(",")
+}
+
+/// First letter of a pseudo-header in the HPACK static table
+fn static_pseudo_header(index: Option<usize>) -> Option<char> {
+    match index? {
+        1 => Some('a'),
+        2 | 3 => Some('m'),
+        4 | 5 => Some('p'),
+        6 | 7 => Some('s'),
+        _ => None,
+    }
+}
+
+/// Cursor over a byte slice
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    /// Bytes with a one-byte length prefix
+    fn vec8(&mut self) -> Option<&'a [u8]> {
+        let len = self.bytes(1)?[0] as usize;
+        self.bytes(len)
+    }
+
+    /// Bytes with a two-byte length prefix
+    fn vec16(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.bytes(len)
+    }
+
+    /// HPACK integer with an N-bit prefix (RFC 7541, section 5.1)
+    fn hpack_int(&mut self, prefix_bits: u32) -> Option<usize> {
+        let max = (1usize << prefix_bits) - 1;
+        let mut value = self.bytes(1)?[0] as usize & max;
+        if value < max {
+            return Some(value);
+        }
+        for shift in (0..28).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value += ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// HPACK string literal; Huffman-coded strings are skipped as empty
+    fn hpack_string(&mut self) -> Option<&'a [u8]> {
+        let huffman = self.peek()? & 0x80 != 0;
+        let len = self.hpack_int(7)?;
+        let data = self.bytes(len)?;
+        Some(if huffman { &[] } else { data })
+    }
+}
+
+/// GREASE values (RFC 8701) are random per connection and left out of
+/// fingerprints
+fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+fn without_grease(values: &[u16]) -> Vec<u16> {
+    values.iter().copied().filter(|v| !is_grease(*v)).collect()
+}
+
+fn u16s(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect()
+}
+
+fn decimal_list(values: &[u16]) -> String {
+    let values: Vec<String> = without_grease(values).iter().map(u16::to_string).collect();
+    values.join("-")
+}
+
+fn hex_list(values: &[u16]) -> String {
+    let values: Vec<String> = values.iter().map(|v| format!("{:04x}", v)).collect();
+    values.join(",")
+}
+
+/// JA4 hash part: zeros when the list is empty
+fn ja4_hash(values: &[u16], list: &str) -> String {
+    if values.is_empty() {
+        "000000000000".to_string()
+    } else {
+        truncated_sha256(list)
+    }
+}
+
+fn truncated_sha256(value: &str) -> String {
+    let mut hash = hex::encode(Sha256::digest(value.as_bytes()));
+    hash.truncate(12);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ClientHello record with GREASE values, SNI and ALPN `h2`
+    fn client_hello_record() -> Vec<u8> {
+        fn extension(kind: u16, data: &[u8]) -> Vec<u8> {
+            let mut out = kind.to_be_bytes().to_vec();
+            out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            out.extend_from_slice(data);
+            out
+        }
+
+        let mut extensions = Vec::new();
+        extensions.extend(extension(0x0a0a, &[]));
+        extensions.extend(extension(
+            EXT_SERVER_NAME,
+            b"\x00\x0e\x00\x00\x0bexample.com",
+        ));
+        extensions.extend(extension(
+            EXT_SUPPORTED_GROUPS,
+            b"\x00\x06\x2a\x2a\x00\x1d\x00\x17",
+        ));
+        extensions.extend(extension(EXT_EC_POINT_FORMATS, b"\x01\x00"));
+        extensions.extend(extension(
+            EXT_SIGNATURE_ALGORITHMS,
+            b"\x00\x04\x04\x03\x08\x04",
+        ));
+        extensions.extend(extension(EXT_ALPN, b"\x00\x0c\x02h2\x08http/1.1"));
+        extensions.extend(extension(EXT_SUPPORTED_VERSIONS, b"\x04\x0a\x0a\x03\x04"));
+
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[7; 32]);
+        body.push(0); // session ID
+        body.extend_from_slice(b"\x00\x08\x1a\x1a\x13\x01\x13\x02\xc0\x2b");
+        body.extend_from_slice(b"\x01\x00");
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend(extensions);
+
+        let mut handshake = vec![CLIENT_HELLO];
+        handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        handshake.extend(body);
+
+        let mut record = vec![TLS_HANDSHAKE, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend(handshake);
+        record
+    }
+
+    #[test]
+    fn test_tls_fingerprints() {
+        let record = client_hello_record();
+        let signals = connection_signals(&record, false).unwrap();
+
+        assert_eq!(
+            signals.ja3.as_deref(),
+            Some("771,4865-4866-49195,0-10-11-13-16-43,29-23,0")
+        );
+        assert_eq!(
+            signals.ja3_hash,
+            Some(hex::encode(Md5::digest(signals.ja3.as_ref().unwrap())))
+        );
+        assert_eq!(
+            signals.ja4.as_deref(),
+            Some(
+                format!(
+                    "t13d0306h2_{}_{}",
+                    truncated_sha256("1301,1302,c02b"),
+                    truncated_sha256("000a,000b,000d,002b_0403,0804")
+                )
+                .as_str()
+            )
+        );
+        assert_eq!(signals.http2, None);
+    }
+
+    #[test]
+    fn test_client_hello_across_records() {
+        let record = client_hello_record();
+        let handshake = &record[5..];
+        let (first, second) = handshake.split_at(40);
+        let mut split = Vec::new();
+        for fragment in [first, second] {
+            split.extend_from_slice(&[TLS_HANDSHAKE, 0x03, 0x01]);
+            split.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            split.extend_from_slice(fragment);
+        }
+
+        // Incomplete until the last byte arrives
+        assert_eq!(connection_signals(&split[..split.len() - 1], false), None);
+        assert_eq!(
+            connection_signals(&split, false).unwrap().ja4,
+            connection_signals(&record, false).unwrap().ja4
+        );
+        // Given up on: no fingerprints
+        assert_eq!(
+            connection_signals(&split[..50], true),
+            Some(BotSignals::default())
+        );
+    }
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut out = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+        out.extend_from_slice(&[kind, flags]);
+        out.extend_from_slice(&stream_id.to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_http2_fingerprint() {
+        let mut data = H2_PREFACE.to_vec();
+        data.extend(frame(
+            H2_SETTINGS,
+            0,
+            0,
+            b"\x00\x01\x00\x01\x00\x00\x00\x04\x00\x60\x00\x00",
+        ));
+        data.extend(frame(H2_WINDOW_UPDATE, 0, 0, &15_663_105u32.to_be_bytes()));
+        data.extend(frame(H2_PRIORITY, 0, 3, b"\x00\x00\x00\x00\xc8"));
+
+        // Waiting for the first request
+        assert_eq!(connection_signals(&data, false), None);
+
+        // :method GET, :authority (literal, indexed name), :scheme https,
+        // :path /, then user-agent
+        let block = b"\x82\x41\x0bexample.com\x87\x84\x7a\x04curl";
+        data.extend(frame(H2_HEADERS, 0x4 | 0x1, 1, block));
+        let signals = connection_signals(&data, false).unwrap();
+        assert_eq!(
+            signals.http2.as_deref(),
+            Some("1:65536;4:6291456|15663105|3:0:0:201|m,a,s,p")
+        );
+        assert_eq!(signals.ja3, None);
+    }
+
+    #[test]
+    fn test_http1_and_partial_preface() {
+        assert_eq!(
+            connection_signals(b"GET / HTTP/1.1\r\n", false),
+            Some(BotSignals::default())
+        );
+        assert_eq!(connection_signals(b"", false), None);
+        assert_eq!(connection_signals(b"PRI * HTTP", false), None);
+        assert_eq!(
+            connection_signals(b"PRI * HTTP", true),
+            Some(BotSignals::default())
+        );
+    }
+
+    #[test]
+    fn test_header_order_hash() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert("host", "a".parse().unwrap());
+        headers.insert("user-agent", "b".parse().unwrap());
+        headers.insert("cookie", "c".parse().unwrap());
+        headers.insert("accept", "d".parse().unwrap());
+        assert_eq!(
+            header_order_hash(&headers),
+            truncated_sha256("host,user-agent,accept")
+        );
+
+        let mut reordered = http::HeaderMap::new();
+        reordered.insert("user-agent", "b".parse().unwrap());
+        reordered.insert("host", "a".parse().unwrap());
+        reordered.insert("accept", "d".parse().unwrap());
+        assert_ne!(header_order_hash(&headers), header_order_hash(&reordered));
+    }
+
+    #[test]
+    fn test_grease() {
+        assert!(is_grease(0x0a0a));
+        assert!(is_grease(0xfafa));
+        assert!(!is_grease(0x0a1a));
+        assert!(!is_grease(0x1301));
+    }
+}
@@ -13,9 +13,22 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, info, trace};
 
+use zentinel_common::{HealthStatus, MetricsRegistry, MetricsSource, MetricsWriter};
 use zentinel_config::{BuiltinHandler, Config};
 
+use crate::agents::{AgentProcessState, AgentProcessStatus};
+use crate::alerts::{AlertState, AlertStatus};
+use crate::api_keys::ApiKeyStatus;
+use crate::audit_store::AuditRecord;
 use crate::cache::{CacheManager, HttpCacheStats};
+use crate::capture::CaptureState;
+use crate::chaos::ChaosState;
+use crate::explain::ExplainTrace;
+use crate::inflight::{ConnectionStatus, InFlightStatus};
+use crate::maintenance::MaintenanceState;
+use crate::probes::HealthReport;
+use crate::quota::QuotaUsage;
+use crate::tenant::TenantStatus;
 
 /// Application state for builtin handlers
 pub struct BuiltinHandlerState {
@@ -116,6 +129,8 @@ pub struct TargetStatus {
     pub weight: u32,
     /// Health status
     pub status: TargetHealthStatus,
+    /// Latest active health check result (None when no active check is configured)
+    pub active_check: Option<bool>,
     /// Failure rate (0.0 - 1.0)
     pub failure_rate: Option<f64>,
     /// Last error message if unhealthy
@@ -143,6 +158,173 @@ pub struct CachePurgeRequest {
     pub wildcard: bool,
 }
 
+/// Tenant admin snapshot for the tenants handler
+#[derive(Debug, Clone, Default)]
+pub struct TenantAdminResult {
+    /// Status of every configured tenant
+    pub tenants: Vec<TenantStatus>,
+    /// Outcome of the requested action, if any
+    pub action: Option<TenantActionResult>,
+}
+
+/// Outcome of a tenant admin action (drain, resume, reload)
+#[derive(Debug, Clone)]
+pub struct TenantActionResult {
+    /// Tenant the action targeted
+    pub tenant: String,
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Maintenance admin snapshot for the maintenance handler
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceAdminResult {
+    /// Flags after the requested action
+    pub state: MaintenanceState,
+    /// Outcome of the requested action, if any
+    pub action: Option<MaintenanceActionResult>,
+}
+
+/// Outcome of a maintenance admin action (enable, disable)
+#[derive(Debug, Clone)]
+pub struct MaintenanceActionResult {
+    /// Route the action targeted, or `None` for global maintenance
+    pub route: Option<String>,
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Chaos admin snapshot for the chaos handler
+#[derive(Debug, Clone, Default)]
+pub struct ChaosAdminResult {
+    /// Faults after the requested action
+    pub state: ChaosState,
+    /// Outcome of the requested action, if any
+    pub action: Option<ChaosActionResult>,
+}
+
+/// Outcome of a chaos admin action (arm, disarm)
+#[derive(Debug, Clone)]
+pub struct ChaosActionResult {
+    /// Fault the action targeted, or `None` for every fault
+    pub fault: Option<String>,
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Capture admin snapshot for the capture handler
+#[derive(Debug, Clone, Default)]
+pub struct CaptureAdminResult {
+    /// Running capture after the requested action
+    pub state: CaptureState,
+    /// Outcome of the requested action, if any
+    pub action: Option<CaptureActionResult>,
+}
+
+/// Outcome of a capture admin action (start, stop)
+#[derive(Debug, Clone)]
+pub struct CaptureActionResult {
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Decision chain lookup for the audit handler
+#[derive(Debug, Clone, Default)]
+pub struct AuditAdminResult {
+    /// Whether an audit store is configured
+    pub enabled: bool,
+    /// Correlation ID that was looked up
+    pub correlation_id: Option<String>,
+    /// Agent responses for the correlation ID, oldest first
+    pub records: Vec<AuditRecord>,
+    /// Response status and message if the lookup failed
+    pub error: Option<(StatusCode, String)>,
+}
+
+/// Dry-run outcome for the explain handler
+#[derive(Debug, Clone, Default)]
+pub struct ExplainAdminResult {
+    /// How the proxy would handle the request
+    pub trace: Option<ExplainTrace>,
+    /// Response status and message if the request could not be explained
+    pub error: Option<(StatusCode, String)>,
+}
+
+/// API key admin snapshot for the api-keys handler
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyAdminResult {
+    /// Key status after the requested action
+    pub keys: Vec<ApiKeyStatus>,
+    /// Outcome of the requested action, if any
+    pub action: Option<ApiKeyActionResult>,
+}
+
+/// Outcome of an API key admin action (revoke, restore)
+#[derive(Debug, Clone)]
+pub struct ApiKeyActionResult {
+    /// Key ID the action targeted
+    pub key: String,
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Quota usage snapshot for the quotas handler
+#[derive(Debug, Clone, Default)]
+pub struct QuotaAdminResult {
+    /// IDs of the quota filters
+    pub filters: Vec<String>,
+    /// Filter and consumer the request inspected
+    pub consumer: Option<(String, String)>,
+    /// Usage of the consumer after the requested action
+    pub usage: Vec<QuotaUsage>,
+    /// Action name as requested
+    pub action: Option<String>,
+    /// Response status and message if the request failed
+    pub error: Option<(StatusCode, String)>,
+}
+
+/// In-flight request snapshot for the requests handler
+#[derive(Debug, Clone, Default)]
+pub struct InFlightAdminResult {
+    /// Requests in flight after the requested action, oldest first
+    pub requests: Vec<InFlightStatus>,
+    /// Outcome of the requested action, if any
+    pub action: Option<InFlightActionResult>,
+}
+
+/// Outcome of an in-flight request admin action (cancel)
+#[derive(Debug, Clone)]
+pub struct InFlightActionResult {
+    /// Correlation ID the action targeted
+    pub correlation_id: Option<String>,
+    /// Action name as requested
+    pub action: String,
+    /// Response status for the action
+    pub status: StatusCode,
+    /// Error message if the action failed
+    pub error: Option<String>,
+}
+
 /// Execute a builtin handler
 pub fn execute_handler(
     handler: BuiltinHandler,
@@ -153,6 +335,19 @@ pub fn execute_handler(
     cache_stats: Option<Arc<HttpCacheStats>>,
     cache_purge: Option<CachePurgeRequest>,
     cache_manager: Option<&Arc<CacheManager>>,
+    agent_processes: Option<Vec<AgentProcessStatus>>,
+    tenants: Option<TenantAdminResult>,
+    maintenance: Option<MaintenanceAdminResult>,
+    api_keys: Option<ApiKeyAdminResult>,
+    chaos: Option<ChaosAdminResult>,
+    capture: Option<CaptureAdminResult>,
+    audit: Option<AuditAdminResult>,
+    explain: Option<ExplainAdminResult>,
+    quotas: Option<QuotaAdminResult>,
+    alerts: Option<Vec<AlertStatus>>,
+    requests: Option<InFlightAdminResult>,
+    connections: Option<Vec<ConnectionStatus>>,
+    health: Option<HealthReport>,
 ) -> Response<Full<Bytes>> {
     trace!(
         handler = ?handler,
@@ -169,6 +364,23 @@ pub fn execute_handler(
         BuiltinHandler::Upstreams => upstreams_handler(upstreams, request_id),
         BuiltinHandler::CachePurge => cache_purge_handler(cache_purge, cache_manager, request_id),
         BuiltinHandler::CacheStats => cache_stats_handler(cache_stats, request_id),
+        BuiltinHandler::Agents => agents_handler(agent_processes, request_id),
+        BuiltinHandler::Tenants => tenants_handler(tenants, request_id),
+        BuiltinHandler::Maintenance => maintenance_handler(maintenance, request_id),
+        BuiltinHandler::ApiKeys => api_keys_handler(api_keys, request_id),
+        BuiltinHandler::Chaos => chaos_handler(chaos, request_id),
+        BuiltinHandler::Capture => capture_handler(capture, request_id),
+        BuiltinHandler::Audit => audit_handler(audit, request_id),
+        BuiltinHandler::Explain => explain_handler(explain, request_id),
+        BuiltinHandler::Quotas => quotas_handler(quotas, request_id),
+        BuiltinHandler::Alerts => alerts_handler(alerts, request_id),
+        // Streamed by the proxy (`serve_request_tap`), never rendered here
+        BuiltinHandler::Tap => not_found_handler(request_id),
+        BuiltinHandler::Requests => requests_handler(requests, request_id),
+        BuiltinHandler::Connections => connections_handler(connections, request_id),
+        BuiltinHandler::Livez => livez_handler(state, request_id),
+        BuiltinHandler::Readyz => readyz_handler(health, request_id),
+        BuiltinHandler::Healthz => healthz_handler(health, request_id),
     };
 
     debug!(
@@ -232,10 +444,28 @@ fn health_handler(request_id: &str) -> Response<Full<Bytes>> {
 /// Content type for the Prometheus text exposition format.
 pub(crate) const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
 
+/// `zentinel_up` and `zentinel_build_info`
+struct ProcessInfoMetrics;
+
+impl MetricsSource for ProcessInfoMetrics {
+    fn collect(&self, writer: &mut MetricsWriter) {
+        writer.gauge("zentinel_up", "Zentinel proxy is up and running", &[], 1.0);
+        writer.gauge(
+            "zentinel_build_info",
+            "Build information",
+            &[("version", env!("CARGO_PKG_VERSION"))],
+            1.0,
+        );
+    }
+}
+
 /// Render the Prometheus exposition body.
 ///
 /// Shared by the builtin `/metrics` route handler and the standalone metrics
-/// server (see [`crate::metrics_server`]) so both expose identical output.
+/// server (see [`crate::metrics_server`]) so both expose identical output:
+/// everything in the global [`MetricsRegistry`] (the `prometheus` collectors
+/// plus registered sources such as agent pools), the process info and the
+/// HTTP cache statistics.
 ///
 /// # Errors
 ///
@@ -244,58 +474,11 @@ pub(crate) const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; cha
 pub(crate) fn render_prometheus_metrics(
     cache_stats: Option<&Arc<HttpCacheStats>>,
 ) -> Result<Vec<u8>, prometheus::Error> {
-    use prometheus::{Encoder, TextEncoder};
-
-    let encoder = TextEncoder::new();
-    let metric_families = prometheus::gather();
-
-    let mut buffer = Vec::new();
-    encoder.encode(&metric_families, &mut buffer)?;
-
-    // Add zentinel_up and build_info metrics
-    let extra_metrics = format!(
-        "# HELP zentinel_up Zentinel proxy is up and running\n\
-         # TYPE zentinel_up gauge\n\
-         zentinel_up 1\n\
-         # HELP zentinel_build_info Build information\n\
-         # TYPE zentinel_build_info gauge\n\
-         zentinel_build_info{{version=\"{}\"}} 1\n",
-        env!("CARGO_PKG_VERSION")
-    );
-    buffer.extend_from_slice(extra_metrics.as_bytes());
-
-    // Add HTTP cache metrics if available
+    let mut extra: Vec<&dyn MetricsSource> = vec![&ProcessInfoMetrics];
     if let Some(stats) = cache_stats {
-        let cache_metrics = format!(
-            "# HELP zentinel_cache_hits_total Total number of cache hits\n\
-             # TYPE zentinel_cache_hits_total counter\n\
-             zentinel_cache_hits_total {}\n\
-             # HELP zentinel_cache_misses_total Total number of cache misses\n\
-             # TYPE zentinel_cache_misses_total counter\n\
-             zentinel_cache_misses_total {}\n\
-             # HELP zentinel_cache_stores_total Total number of cache stores\n\
-             # TYPE zentinel_cache_stores_total counter\n\
-             zentinel_cache_stores_total {}\n\
-             # HELP zentinel_cache_hit_ratio Cache hit ratio (0.0 to 1.0)\n\
-             # TYPE zentinel_cache_hit_ratio gauge\n\
-             zentinel_cache_hit_ratio {:.4}\n\
-             # HELP zentinel_cache_memory_hits_total Cache hits from memory tier\n\
-             # TYPE zentinel_cache_memory_hits_total counter\n\
-             zentinel_cache_memory_hits_total {}\n\
-             # HELP zentinel_cache_disk_hits_total Cache hits from disk tier\n\
-             # TYPE zentinel_cache_disk_hits_total counter\n\
-             zentinel_cache_disk_hits_total {}\n",
-            stats.hits(),
-            stats.misses(),
-            stats.stores(),
-            stats.hit_ratio(),
-            stats.memory_hits(),
-            stats.disk_hits()
-        );
-        buffer.extend_from_slice(cache_metrics.as_bytes());
+        extra.push(&**stats);
     }
-
-    Ok(buffer)
+    MetricsRegistry::global().render_with(&extra)
 }
 
 /// Prometheus metrics handler
@@ -347,14 +530,15 @@ fn not_found_handler(request_id: &str) -> Response<Full<Bytes>> {
 /// Configuration dump handler
 ///
 /// Returns the current running configuration as JSON. Sensitive fields like
-/// TLS private keys are redacted for security.
+/// TLS private keys are redacted for security, as are values resolved from
+/// secret references (`${env:...}`, `${file:...}`).
 fn config_handler(config: Option<Arc<Config>>, request_id: &str) -> Response<Full<Bytes>> {
     let body = match &config {
         Some(cfg) => {
             // Build a response with configuration details
             // The Config struct derives Serialize, so we can serialize directly
             // Note: sensitive fields should be redacted in production
-            let response = serde_json::json!({
+            let mut response = serde_json::json!({
                 "timestamp": chrono::Utc::now().to_rfc3339(),
                 "request_id": request_id,
                 "config": {
@@ -423,6 +607,7 @@ fn config_handler(config: Option<Arc<Config>>, request_id: &str) -> Response<Ful
                     "limits": &cfg.limits,
                 }
             });
+            zentinel_config::secrets::redact_json(&mut response);
 
             serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
                 serde_json::to_vec(&serde_json::json!({
@@ -694,6 +879,601 @@ fn cache_stats_handler(
         .expect("static response builder with valid headers cannot fail")
 }
 
+/// Supervised agent process handler
+///
+/// Returns the state of agents run as child processes (`command` in the
+/// agent configuration).
+fn agents_handler(
+    processes: Option<Vec<AgentProcessStatus>>,
+    request_id: &str,
+) -> Response<Full<Bytes>> {
+    let processes = processes.unwrap_or_default();
+    let running = processes
+        .iter()
+        .filter(|p| p.state == AgentProcessState::Running)
+        .count();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "summary": {
+            "total": processes.len(),
+            "running": running,
+            "not_running": processes.len() - running,
+        },
+        "agents": processes,
+    });
+    if processes.is_empty() {
+        response["message"] = "No supervised agents configured".into();
+    }
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize agent status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Tenant status and admin action handler
+fn tenants_handler(result: Option<TenantAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+    let draining = result.tenants.iter().filter(|t| t.draining).count();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "summary": {
+            "total": result.tenants.len(),
+            "draining": draining,
+        },
+        "tenants": result.tenants,
+    });
+    if result.tenants.is_empty() {
+        response["message"] = "No tenants configured".into();
+    }
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "tenant": action.tenant,
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize tenant status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Maintenance mode status and admin action handler
+fn maintenance_handler(
+    result: Option<MaintenanceAdminResult>,
+    request_id: &str,
+) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "global": result.state.global,
+        "routes": result.state.routes,
+    });
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "route": action.route,
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize maintenance status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// API key status and revocation handler
+fn api_keys_handler(result: Option<ApiKeyAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "keys": result.keys,
+    });
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "key": action.key,
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize API key status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Consumer quota usage and reset handler
+fn quotas_handler(result: Option<QuotaAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "filters": result.filters,
+    });
+    if let Some((filter, consumer)) = &result.consumer {
+        response["filter"] = filter.as_str().into();
+        response["consumer"] = consumer.as_str().into();
+        response["usage"] = serde_json::json!(result.usage);
+    }
+    if let Some(action) = &result.action {
+        response["action"] = serde_json::json!({
+            "action": action,
+            "status": if result.error.is_some() { "error" } else { "ok" },
+        });
+    }
+
+    let status = match &result.error {
+        Some((status, error)) => {
+            response["error"] = error.as_str().into();
+            *status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize quota usage",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Alert rule state handler
+fn alerts_handler(alerts: Option<Vec<AlertStatus>>, request_id: &str) -> Response<Full<Bytes>> {
+    let alerts = alerts.unwrap_or_default();
+    let count = |state| alerts.iter().filter(|a| a.state == state).count();
+
+    let response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "summary": {
+            "total": alerts.len(),
+            "pending": count(AlertState::Pending),
+            "firing": count(AlertState::Firing),
+        },
+        "alerts": alerts,
+    });
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize alert status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// In-flight request listing and cancellation handler
+fn requests_handler(
+    result: Option<InFlightAdminResult>,
+    request_id: &str,
+) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "count": result.requests.len(),
+        "requests": result.requests,
+    });
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "correlation_id": action.correlation_id,
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize in-flight requests",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Open connection listing handler
+fn connections_handler(
+    connections: Option<Vec<ConnectionStatus>>,
+    request_id: &str,
+) -> Response<Full<Bytes>> {
+    let connections = connections.unwrap_or_default();
+    let count = |kind| connections.iter().filter(|c| c.kind == kind).count();
+
+    let response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "summary": {
+            "http": count("http"),
+            "tcp": count("tcp"),
+        },
+        "connections": connections,
+    });
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize connections",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Chaos fault status and arming handler
+fn chaos_handler(result: Option<ChaosAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "enabled": result.state.enabled,
+        "faults": result.state.faults,
+    });
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "fault": action.fault,
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize chaos status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Traffic capture status, start and stop handler
+fn capture_handler(result: Option<CaptureAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "enabled": result.state.enabled,
+        "session": result.state.session,
+    });
+
+    let status = match &result.action {
+        Some(action) => {
+            response["action"] = serde_json::json!({
+                "action": action.action,
+                "status": if action.error.is_some() { "error" } else { "ok" },
+            });
+            if let Some(error) = &action.error {
+                response["action"]["error"] = error.as_str().into();
+            }
+            action.status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize capture status",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Agent decision chain handler
+fn audit_handler(result: Option<AuditAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "enabled": result.enabled,
+        "correlation_id": result.correlation_id,
+        "count": result.records.len(),
+        "records": result.records,
+    });
+
+    let status = match &result.error {
+        Some((status, error)) => {
+            response["error"] = error.as_str().into();
+            *status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize audit records",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Dry-run request explanation handler
+fn explain_handler(result: Option<ExplainAdminResult>, request_id: &str) -> Response<Full<Bytes>> {
+    let result = result.unwrap_or_default();
+
+    let mut response = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id,
+        "dry_run": true,
+    });
+    if let Some(trace) = &result.trace {
+        match serde_json::to_value(trace) {
+            Ok(serde_json::Value::Object(fields)) => {
+                response
+                    .as_object_mut()
+                    .expect("json! object")
+                    .extend(fields);
+            }
+            Ok(_) => {}
+            Err(e) => response["error"] = format!("Failed to serialize trace: {}", e).into(),
+        }
+    }
+
+    let status = match &result.error {
+        Some((status, error)) => {
+            response["error"] = error.as_str().into();
+            *status
+        }
+        None => StatusCode::OK,
+    };
+
+    let body = serde_json::to_vec_pretty(&response).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({
+            "error": "Failed to serialize explain trace",
+            "message": e.to_string(),
+        }))
+        .unwrap_or_default()
+    });
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
+/// Liveness probe handler: answers as long as the process serves requests
+fn livez_handler(state: &BuiltinHandlerState, request_id: &str) -> Response<Full<Bytes>> {
+    let response = serde_json::json!({
+        "status": "alive",
+        "uptime_secs": state.uptime().as_secs(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    probe_response(StatusCode::OK, &response, request_id)
+}
+
+/// Readiness probe handler: 503 until every readiness check holds
+fn readyz_handler(report: Option<HealthReport>, request_id: &str) -> Response<Full<Bytes>> {
+    let Some(report) = report else {
+        return probe_unavailable(request_id);
+    };
+
+    let response = serde_json::json!({
+        "status": if report.ready { "ready" } else { "not_ready" },
+        "checks": report.checks,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    probe_response(status, &response, request_id)
+}
+
+/// Detailed health handler: every subsystem, 503 when one is unhealthy
+fn healthz_handler(report: Option<HealthReport>, request_id: &str) -> Response<Full<Bytes>> {
+    let Some(report) = report else {
+        return probe_unavailable(request_id);
+    };
+
+    let response = serde_json::json!({
+        "status": health_status_str(report.status),
+        "ready": report.ready,
+        "checks": report.checks,
+        "components": report.components,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    let status = if report.status == HealthStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    probe_response(status, &response, request_id)
+}
+
+fn health_status_str(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
+}
+
+fn probe_unavailable(request_id: &str) -> Response<Full<Bytes>> {
+    let response = serde_json::json!({
+        "status": "unknown",
+        "error": "Health report unavailable",
+    });
+    probe_response(StatusCode::SERVICE_UNAVAILABLE, &response, request_id)
+}
+
+fn probe_response(
+    status: StatusCode,
+    response: &serde_json::Value,
+    request_id: &str,
+) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec_pretty(response).unwrap_or_default();
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("X-Request-Id", request_id)
+        .header("Cache-Control", "no-cache, no-store, must-revalidate")
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response builder with valid headers cannot fail")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -733,6 +1513,10 @@ mod tests {
 
         let response = metrics_handler("test-request-id", Some(&stats));
         assert_eq!(response.status(), StatusCode::OK);
+
+        let body = String::from_utf8(render_prometheus_metrics(Some(&stats)).unwrap()).unwrap();
+        assert!(body.contains("zentinel_cache_hits_total 1\n"));
+        assert!(body.contains("zentinel_up 1\n"));
     }
 
     #[test]
@@ -836,6 +1620,7 @@ mod tests {
                         address: "10.0.0.1:8080".to_string(),
                         weight: 1,
                         status: TargetHealthStatus::Healthy,
+                        active_check: None,
                         failure_rate: Some(0.0),
                         last_error: None,
                     },
@@ -843,6 +1628,7 @@ mod tests {
                         address: "10.0.0.2:8080".to_string(),
                         weight: 1,
                         status: TargetHealthStatus::Unhealthy,
+                        active_check: Some(false),
                         failure_rate: Some(0.8),
                         last_error: Some("connection refused".to_string()),
                     },
@@ -864,6 +1650,437 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_agents_handler() {
+        use http_body_util::BodyExt;
+
+        let processes = vec![AgentProcessStatus {
+            id: "waf".to_string(),
+            state: AgentProcessState::Backoff,
+            pid: None,
+            socket: Some(std::path::PathBuf::from("/var/run/zentinel/waf.sock")),
+            restarts: 2,
+            uptime_secs: None,
+            last_exit: Some("exit code 1".to_string()),
+            last_error: None,
+        }];
+
+        let response = agents_handler(Some(processes), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["summary"]["total"], 1);
+        assert_eq!(json["summary"]["running"], 0);
+        assert_eq!(json["agents"][0]["state"], "backoff");
+        assert_eq!(json["agents"][0]["restarts"], 2);
+
+        let response = agents_handler(None, "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tenants_handler() {
+        use http_body_util::BodyExt;
+
+        let result = TenantAdminResult {
+            tenants: vec![TenantStatus {
+                id: "acme".to_string(),
+                draining: true,
+                in_flight: 3,
+                quotas: Default::default(),
+            }],
+            action: Some(TenantActionResult {
+                tenant: "acme".to_string(),
+                action: "drain".to_string(),
+                status: StatusCode::OK,
+                error: None,
+            }),
+        };
+
+        let response = tenants_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["summary"]["draining"], 1);
+        assert_eq!(json["tenants"][0]["in_flight"], 3);
+        assert_eq!(json["action"]["status"], "ok");
+
+        let failed = TenantAdminResult {
+            tenants: vec![],
+            action: Some(TenantActionResult {
+                tenant: "missing".to_string(),
+                action: "drain".to_string(),
+                status: StatusCode::NOT_FOUND,
+                error: Some("Unknown tenant 'missing'".to_string()),
+            }),
+        };
+        let response = tenants_handler(Some(failed), "test-request-id");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_handler() {
+        use http_body_util::BodyExt;
+
+        let result = MaintenanceAdminResult {
+            state: MaintenanceState {
+                global: false,
+                routes: vec!["checkout".to_string()],
+            },
+            action: Some(MaintenanceActionResult {
+                route: Some("checkout".to_string()),
+                action: "enable".to_string(),
+                status: StatusCode::OK,
+                error: None,
+            }),
+        };
+
+        let response = maintenance_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["global"], false);
+        assert_eq!(json["routes"][0], "checkout");
+        assert_eq!(json["action"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_handler() {
+        use crate::api_keys::ApiKey;
+        use http_body_util::BodyExt;
+
+        let result = ApiKeyAdminResult {
+            keys: vec![ApiKeyStatus {
+                filter: "partner-keys".to_string(),
+                key: ApiKey {
+                    id: "acme".to_string(),
+                    owner: Some("acme".to_string()),
+                    routes: vec![],
+                    rate_tier: Some("gold".to_string()),
+                    revoked: false,
+                },
+                revoked_at_runtime: true,
+            }],
+            action: Some(ApiKeyActionResult {
+                key: "acme".to_string(),
+                action: "revoke".to_string(),
+                status: StatusCode::OK,
+                error: None,
+            }),
+        };
+
+        let response = api_keys_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["keys"][0]["id"], "acme");
+        assert_eq!(json["keys"][0]["rate_tier"], "gold");
+        assert_eq!(json["keys"][0]["revoked_at_runtime"], true);
+        assert_eq!(json["action"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_quotas_handler() {
+        use crate::quota::{QuotaMetric, QuotaWindow};
+        use http_body_util::BodyExt;
+
+        let result = QuotaAdminResult {
+            filters: vec!["partner-quota".to_string()],
+            consumer: Some(("partner-quota".to_string(), "acme".to_string())),
+            usage: vec![QuotaUsage {
+                metric: QuotaMetric::Requests,
+                window: QuotaWindow::Day,
+                limit: 1000,
+                used: 0,
+                remaining: 1000,
+                reset_at: 1_792_195_200,
+            }],
+            action: Some("reset".to_string()),
+            error: None,
+        };
+
+        let response = quotas_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["consumer"], "acme");
+        assert_eq!(json["usage"][0]["metric"], "requests");
+        assert_eq!(json["usage"][0]["window"], "day");
+        assert_eq!(json["usage"][0]["remaining"], 1000);
+        assert_eq!(json["action"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_alerts_handler() {
+        use http_body_util::BodyExt;
+
+        let alerts = vec![AlertStatus {
+            name: "agent-timeouts".to_string(),
+            condition: "agent_timeout_rate > 5%".to_string(),
+            state: AlertState::Firing,
+            value: Some(0.12),
+            severity: zentinel_config::NotificationSeverity::Critical,
+            for_secs: 300,
+            window_secs: 300,
+            since: Some("2026-01-01T12:00:00+00:00".to_string()),
+        }];
+
+        let response = alerts_handler(Some(alerts), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["summary"]["total"], 1);
+        assert_eq!(json["summary"]["firing"], 1);
+        assert_eq!(json["alerts"][0]["state"], "firing");
+        assert_eq!(json["alerts"][0]["severity"], "critical");
+    }
+
+    #[tokio::test]
+    async fn test_chaos_handler() {
+        use crate::chaos::FaultStatus;
+        use http_body_util::BodyExt;
+
+        let result = ChaosAdminResult {
+            state: ChaosState {
+                enabled: false,
+                faults: vec![FaultStatus {
+                    id: "slow-api".to_string(),
+                    target: "route:api".to_string(),
+                    kind: "latency",
+                    probability: 0.5,
+                    duration_secs: 300,
+                    armed: false,
+                    remaining_secs: None,
+                }],
+            },
+            action: Some(ChaosActionResult {
+                fault: Some("slow-api".to_string()),
+                action: "arm".to_string(),
+                status: StatusCode::CONFLICT,
+                error: Some("Chaos fault injection is disabled".to_string()),
+            }),
+        };
+
+        let response = chaos_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["enabled"], false);
+        assert_eq!(json["faults"][0]["target"], "route:api");
+        assert!(json["faults"][0].get("remaining_secs").is_none());
+        assert_eq!(json["action"]["status"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_requests_handler() {
+        use crate::inflight::RequestPhase;
+        use http_body_util::BodyExt;
+
+        let result = InFlightAdminResult {
+            requests: vec![InFlightStatus {
+                correlation_id: "req-1".to_string(),
+                method: "POST".to_string(),
+                path: "/api/upload".to_string(),
+                client: "10.0.0.1:40000".to_string(),
+                route: Some("api".to_string()),
+                upstream: None,
+                phase: RequestPhase::AwaitingAgent,
+                started_at: "2026-01-01T00:00:00Z".to_string(),
+                elapsed_ms: 1500,
+                cancelled: true,
+            }],
+            action: Some(InFlightActionResult {
+                correlation_id: Some("req-1".to_string()),
+                action: "cancel".to_string(),
+                status: StatusCode::OK,
+                error: None,
+            }),
+        };
+
+        let response = requests_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["requests"][0]["phase"], "awaiting-agent");
+        assert!(json["requests"][0].get("upstream").is_none());
+        assert_eq!(json["action"]["correlation_id"], "req-1");
+        assert_eq!(json["action"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_capture_handler() {
+        use crate::capture::CaptureSessionStatus;
+        use http_body_util::BodyExt;
+
+        let result = CaptureAdminResult {
+            state: CaptureState {
+                enabled: true,
+                session: Some(CaptureSessionStatus {
+                    route: "api".to_string(),
+                    sample_percent: 1.0,
+                    file: "/var/lib/zentinel/captures/api.jsonl".into(),
+                    max_requests: None,
+                    captured: 12,
+                    started_at: "2026-01-01T00:00:00Z".to_string(),
+                }),
+            },
+            action: Some(CaptureActionResult {
+                action: "start".to_string(),
+                status: StatusCode::CONFLICT,
+                error: Some("A capture of route 'api' is already running".to_string()),
+            }),
+        };
+
+        let response = capture_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["session"]["route"], "api");
+        assert_eq!(json["session"]["captured"], 12);
+        assert!(json["session"].get("max_requests").is_none());
+        assert_eq!(json["action"]["status"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_audit_handler() {
+        use http_body_util::BodyExt;
+        use zentinel_agent_protocol::{AgentResponse, Decision, EventType};
+
+        let mut waf = AgentResponse::default_allow();
+        waf.decision = Decision::Block {
+            status: 403,
+            body: None,
+            headers: None,
+        };
+        waf.audit.rule_ids = vec!["942100".to_string()];
+        let result = AuditAdminResult {
+            enabled: true,
+            correlation_id: Some("req-1".to_string()),
+            records: vec![AuditRecord::new(
+                "req-1",
+                Some("api"),
+                "waf",
+                EventType::RequestHeaders,
+                &waf,
+                Duration::from_millis(4),
+            )],
+            error: None,
+        };
+
+        let response = audit_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["correlation_id"], "req-1");
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["records"][0]["agent_id"], "waf");
+        assert_eq!(json["records"][0]["event_type"], "request_headers");
+        assert_eq!(json["records"][0]["decision"]["block"]["status"], 403);
+        assert_eq!(json["records"][0]["audit"]["rule_ids"][0], "942100");
+
+        let missing = AuditAdminResult {
+            enabled: false,
+            error: Some((
+                StatusCode::NOT_FOUND,
+                "Audit store is not configured".to_string(),
+            )),
+            ..Default::default()
+        };
+        let response = audit_handler(Some(missing), "test-request-id");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_explain_handler() {
+        use crate::explain::{ExplainDecision, ExplainRoute};
+        use http_body_util::BodyExt;
+
+        let trace = ExplainTrace {
+            route: Some(ExplainRoute {
+                id: "api".to_string(),
+                service_type: zentinel_config::ServiceType::Web,
+                upstream: Some("backend".to_string()),
+                builtin_handler: None,
+            }),
+            decision: ExplainDecision {
+                action: "block",
+                status: Some(403),
+                decided_by: Some("waf".to_string()),
+                detail: None,
+            },
+            ..Default::default()
+        };
+        let result = ExplainAdminResult {
+            trace: Some(trace),
+            error: None,
+        };
+
+        let response = explain_handler(Some(result), "test-request-id");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["route"]["id"], "api");
+        assert_eq!(json["route"]["upstream"], "backend");
+        assert_eq!(json["decision"]["action"], "block");
+        assert_eq!(json["decision"]["decided_by"], "waf");
+
+        let invalid = ExplainAdminResult {
+            trace: None,
+            error: Some((StatusCode::BAD_REQUEST, "Invalid method".to_string())),
+        };
+        let response = explain_handler(Some(invalid), "test-request-id");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_probe_handlers() {
+        use crate::probes::ReadinessCheck;
+        use http_body_util::BodyExt;
+
+        let state = BuiltinHandlerState::new("0.1.0".to_string(), "test".to_string());
+        assert_eq!(livez_handler(&state, "req").status(), StatusCode::OK);
+        assert_eq!(
+            readyz_handler(None, "req").status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let report = HealthReport {
+            status: HealthStatus::Degraded,
+            ready: false,
+            checks: vec![ReadinessCheck {
+                name: "upstreams".to_string(),
+                ok: false,
+                message: Some("0 healthy, 1 required".to_string()),
+            }],
+            components: vec![],
+        };
+        assert_eq!(
+            readyz_handler(Some(report.clone()), "req").status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let response = healthz_handler(Some(report), "req");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["ready"], false);
+        assert_eq!(json["checks"][0]["name"], "upstreams");
+    }
+
     #[test]
     fn test_uptime_formatting() {
         let state = BuiltinHandlerState::new("0.1.0".to_string(), "test".to_string());
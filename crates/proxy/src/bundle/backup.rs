@@ -0,0 +1,278 @@
+//! Agent binary backups for rollback
+//!
+//! Before `bundle install` or `bundle upgrade` replaces agent binaries, the
+//! currently installed ones are copied into a timestamped directory under
+//! [`InstallPaths::backup_dir`] together with a manifest of the versions
+//! they replace. `bundle rollback` restores a backup and then discards it,
+//! so repeated rollbacks step further back. Only the newest
+//! [`RETAINED_BACKUPS`] backups are kept.
+
+use crate::bundle::install::{
+    executable_name, install_binary, uninstall_binary, InstallError, InstallPaths,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Number of backups kept after each install or upgrade
+pub const RETAINED_BACKUPS: usize = 5;
+
+/// Manifest file name inside each backup directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// A backup of agent binaries taken before they were replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Backup identifier (directory name, sortable by creation time)
+    pub id: String,
+
+    /// Creation time (RFC 3339)
+    pub created_at: String,
+
+    /// Bundle version that was being installed when the backup was taken
+    pub bundle_version: String,
+
+    /// Agents whose binaries were about to change
+    pub agents: Vec<BackupEntry>,
+}
+
+/// State of a single agent before an install or upgrade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Agent name
+    pub name: String,
+
+    /// Binary file name
+    pub binary_name: String,
+
+    /// Previously installed version, `None` if the agent was not installed
+    pub version: Option<String>,
+}
+
+/// Result of restoring a backup
+#[derive(Debug, Default)]
+pub struct RestoreSummary {
+    /// Agents whose previous binary was put back
+    pub restored: Vec<String>,
+
+    /// Agents removed because they were not installed before
+    pub removed: Vec<String>,
+}
+
+impl BackupManifest {
+    /// Directory holding this backup
+    pub fn dir(&self, backup_dir: &Path) -> PathBuf {
+        backup_dir.join(&self.id)
+    }
+}
+
+/// Back up the installed binaries of agents that are about to be replaced
+///
+/// Entries whose binary is missing from `bin_dir` are recorded as not
+/// installed, so a rollback removes them again.
+pub fn create_backup(
+    paths: &InstallPaths,
+    bundle_version: &str,
+    entries: Vec<BackupEntry>,
+) -> Result<BackupManifest, InstallError> {
+    let now = chrono::Utc::now();
+    let base_id = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    std::fs::create_dir_all(&paths.backup_dir)
+        .map_err(|e| InstallError::CreateDir(format!("{}: {}", paths.backup_dir.display(), e)))?;
+
+    // Two backups within the same second get a numeric suffix
+    let mut id = base_id.clone();
+    let mut suffix = 1;
+    while paths.backup_dir.join(&id).exists() {
+        id = format!("{}-{:02}", base_id, suffix);
+        suffix += 1;
+    }
+
+    let dir = paths.backup_dir.join(&id);
+    std::fs::create_dir(&dir)?;
+
+    let mut agents = Vec::with_capacity(entries.len());
+    for mut entry in entries {
+        let file_name = executable_name(&entry.binary_name);
+        let current = paths.bin_dir.join(&file_name);
+        if entry.version.is_some() && current.exists() {
+            std::fs::copy(&current, dir.join(&file_name))?;
+        } else {
+            entry.version = None;
+        }
+        agents.push(entry);
+    }
+
+    let manifest = BackupManifest {
+        id,
+        created_at: now.to_rfc3339(),
+        bundle_version: bundle_version.to_string(),
+        agents,
+    };
+
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| InstallError::Io(std::io::Error::other(e)))?;
+    std::fs::write(dir.join(MANIFEST_FILE), json)?;
+
+    tracing::info!(
+        backup = %manifest.id,
+        dir = %dir.display(),
+        agents = manifest.agents.len(),
+        "Created bundle backup"
+    );
+
+    Ok(manifest)
+}
+
+/// List available backups, newest first
+///
+/// Directories without a readable manifest are ignored.
+pub fn list_backups(backup_dir: &Path) -> Vec<BackupManifest> {
+    let Ok(entries) = std::fs::read_dir(backup_dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<BackupManifest> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path().join(MANIFEST_FILE)).ok()?;
+            serde_json::from_str(&content).ok()
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    backups
+}
+
+/// Restore the binaries recorded in a backup
+pub fn restore_backup(
+    paths: &InstallPaths,
+    manifest: &BackupManifest,
+) -> Result<RestoreSummary, InstallError> {
+    let dir = manifest.dir(&paths.backup_dir);
+    let mut summary = RestoreSummary::default();
+
+    for entry in &manifest.agents {
+        if entry.version.is_some() {
+            install_binary(
+                &dir.join(executable_name(&entry.binary_name)),
+                &paths.bin_dir,
+                &entry.binary_name,
+            )?;
+            summary.restored.push(entry.name.clone());
+        } else if uninstall_binary(&paths.bin_dir, &entry.binary_name)? {
+            summary.removed.push(entry.name.clone());
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Delete a backup directory
+pub fn remove_backup(backup_dir: &Path, id: &str) -> Result<(), InstallError> {
+    std::fs::remove_dir_all(backup_dir.join(id))?;
+    Ok(())
+}
+
+/// Delete all but the newest `keep` backups
+pub fn prune_backups(backup_dir: &Path, keep: usize) -> Result<usize, InstallError> {
+    let stale: Vec<_> = list_backups(backup_dir).into_iter().skip(keep).collect();
+    for manifest in &stale {
+        remove_backup(backup_dir, &manifest.id)?;
+    }
+    Ok(stale.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_paths(root: &Path) -> InstallPaths {
+        let paths = InstallPaths::with_prefix(root);
+        std::fs::create_dir_all(&paths.bin_dir).unwrap();
+        paths
+    }
+
+    fn entry(name: &str, version: Option<&str>) -> BackupEntry {
+        BackupEntry {
+            name: name.to_string(),
+            binary_name: format!("zentinel-{}-agent", name),
+            version: version.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = test_paths(temp.path());
+        let waf = paths.bin_dir.join(executable_name("zentinel-waf-agent"));
+        std::fs::write(&waf, "waf 0.2.0").unwrap();
+
+        let manifest = create_backup(
+            &paths,
+            "26.03_01",
+            vec![entry("waf", Some("0.2.0")), entry("lua", None)],
+        )
+        .unwrap();
+        assert!(manifest
+            .dir(&paths.backup_dir)
+            .join("manifest.json")
+            .exists());
+
+        // Simulate the upgrade
+        std::fs::write(&waf, "waf 0.3.0").unwrap();
+        let lua = paths.bin_dir.join(executable_name("zentinel-lua-agent"));
+        std::fs::write(&lua, "lua 0.3.0").unwrap();
+
+        let summary = restore_backup(&paths, &manifest).unwrap();
+        assert_eq!(summary.restored, vec!["waf".to_string()]);
+        assert_eq!(summary.removed, vec!["lua".to_string()]);
+        assert_eq!(std::fs::read_to_string(&waf).unwrap(), "waf 0.2.0");
+        assert!(!lua.exists());
+    }
+
+    #[test]
+    fn test_backup_missing_binary_recorded_as_absent() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = test_paths(temp.path());
+
+        let manifest =
+            create_backup(&paths, "26.03_01", vec![entry("waf", Some("0.2.0"))]).unwrap();
+        assert!(manifest.agents[0].version.is_none());
+    }
+
+    #[test]
+    fn test_list_and_prune_backups() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = test_paths(temp.path());
+
+        let ids: Vec<String> = (0..3)
+            .map(|_| {
+                create_backup(&paths, "26.03_01", vec![entry("waf", None)])
+                    .unwrap()
+                    .id
+            })
+            .collect();
+
+        // Garbage directories are ignored
+        std::fs::create_dir_all(paths.backup_dir.join("not-a-backup")).unwrap();
+
+        let listed: Vec<String> = list_backups(&paths.backup_dir)
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        let mut expected = ids.clone();
+        expected.reverse();
+        assert_eq!(listed, expected);
+
+        assert_eq!(prune_backups(&paths.backup_dir, 1).unwrap(), 2);
+        let remaining = list_backups(&paths.backup_dir);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[2]);
+    }
+
+    #[test]
+    fn test_list_backups_missing_dir() {
+        assert!(list_backups(Path::new("/nonexistent/backups")).is_empty());
+    }
+}
@@ -2,15 +2,20 @@
 //!
 //! Implements the `zentinel bundle` subcommand and its subcommands.
 
-use crate::bundle::fetch::{detect_arch, detect_os, download_agent};
+use crate::bundle::backup::{
+    create_backup, list_backups, prune_backups, remove_backup, restore_backup, BackupEntry,
+    BackupManifest, RETAINED_BACKUPS,
+};
+use crate::bundle::fetch::{detect_arch, detect_os, download_agent, FetchError};
 use crate::bundle::install::{
-    generate_default_config, generate_systemd_service, install_binary, install_config,
-    install_systemd_service, uninstall_binary, InstallPaths,
+    executable_name, generate_default_config, generate_systemd_service, install_binary,
+    install_config, install_systemd_service, uninstall_binary, InstallPaths,
 };
-use crate::bundle::lock::BundleLock;
-use crate::bundle::status::BundleStatus;
+use crate::bundle::lock::{AgentInfo, BundleLock};
+use crate::bundle::status::{BundleStatus, Status};
 use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 /// Bundle command arguments
@@ -25,8 +30,8 @@ pub struct BundleArgs {
 pub enum BundleCommand {
     /// Install bundled agents
     Install {
-        /// Specific agent to install (installs all if not specified)
-        agent: Option<String>,
+        /// Agents to install (installs all if none are given)
+        agents: Vec<String>,
 
         /// Preview what would be installed without making changes
         #[arg(long, short = 'n')]
@@ -44,9 +49,45 @@ pub enum BundleCommand {
         #[arg(long)]
         prefix: Option<PathBuf>,
 
-        /// Skip checksum verification
+        /// Install without checksum and signature verification (insecure)
+        #[arg(long, alias = "skip-verify")]
+        insecure_skip_verify: bool,
+    },
+
+    /// Upgrade installed agents that are older than the bundle
+    Upgrade {
+        /// Agents to upgrade (checks all installed agents if none are given)
+        agents: Vec<String>,
+
+        /// Preview what would be upgraded without making changes
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+
+        /// Custom installation prefix
+        #[arg(long)]
+        prefix: Option<PathBuf>,
+
+        /// Upgrade without checksum and signature verification (insecure)
+        #[arg(long, alias = "skip-verify")]
+        insecure_skip_verify: bool,
+    },
+
+    /// Restore the agent versions from before the last install or upgrade
+    Rollback {
+        /// Backup to restore (defaults to the most recent)
+        backup: Option<String>,
+
+        /// List available backups instead of restoring
+        #[arg(long, short = 'l')]
+        list: bool,
+
+        /// Preview what would be restored without making changes
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+
+        /// Custom installation prefix
         #[arg(long)]
-        skip_verify: bool,
+        prefix: Option<PathBuf>,
     },
 
     /// Show status of installed agents
@@ -83,18 +124,46 @@ pub enum BundleCommand {
 
 /// Run the bundle command
 pub fn run_bundle_command(args: BundleArgs) -> Result<()> {
-    // Load the embedded lock file
-    let lock = BundleLock::embedded().context("Failed to load bundle lock file")?;
+    // A self-hosted registry replaces the embedded lock file, so its
+    // download URLs point at the mirror rather than GitHub
+    let lock = match std::env::var("ZENTINEL_API_URL") {
+        Ok(url) if !url.is_empty() => tokio::runtime::Runtime::new()?
+            .block_on(BundleLock::fetch_from_registry(&url))
+            .with_context(|| format!("Failed to load bundle from registry {}", url))?,
+        _ => BundleLock::embedded().context("Failed to load bundle lock file")?,
+    };
 
     match args.command {
         BundleCommand::Install {
-            agent,
+            agents,
             dry_run,
             force,
             systemd,
             prefix,
-            skip_verify,
-        } => cmd_install(&lock, agent, dry_run, force, systemd, prefix, skip_verify),
+            insecure_skip_verify,
+        } => cmd_install(
+            &lock,
+            &agents,
+            dry_run,
+            force,
+            systemd,
+            prefix,
+            insecure_skip_verify,
+        ),
+
+        BundleCommand::Upgrade {
+            agents,
+            dry_run,
+            prefix,
+            insecure_skip_verify,
+        } => cmd_upgrade(&lock, &agents, dry_run, prefix, insecure_skip_verify),
+
+        BundleCommand::Rollback {
+            backup,
+            list,
+            dry_run,
+            prefix,
+        } => cmd_rollback(backup, list, dry_run, prefix),
 
         BundleCommand::Status { verbose } => cmd_status(&lock, verbose),
 
@@ -106,22 +175,40 @@ pub fn run_bundle_command(args: BundleArgs) -> Result<()> {
     }
 }
 
-/// Install command implementation
-fn cmd_install(
-    lock: &BundleLock,
-    agent: Option<String>,
-    dry_run: bool,
-    force: bool,
-    install_systemd: bool,
-    prefix: Option<PathBuf>,
-    skip_verify: bool,
-) -> Result<()> {
-    let paths = match prefix {
+/// Resolve installation paths from an optional prefix
+fn install_paths(prefix: Option<PathBuf>) -> InstallPaths {
+    match prefix {
         Some(p) => InstallPaths::with_prefix(&p),
         None => InstallPaths::detect(),
-    };
+    }
+}
+
+/// Resolve agent names against the lock file (all agents if none are given)
+fn select_agents(lock: &BundleLock, names: &[String]) -> Result<Vec<AgentInfo>> {
+    if names.is_empty() {
+        return Ok(lock.agents());
+    }
 
-    println!("Zentinel Bundle Installer");
+    let unknown: Vec<&str> = names
+        .iter()
+        .filter(|name| lock.agent(name).is_none())
+        .map(|name| name.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!("Unknown agent(s): {}", unknown.join(", "));
+    }
+
+    let mut seen = HashSet::new();
+    Ok(names
+        .iter()
+        .filter(|name| seen.insert(name.as_str()))
+        .filter_map(|name| lock.agent(name))
+        .collect())
+}
+
+/// Print the installer banner
+fn print_header(title: &str, lock: &BundleLock, paths: &InstallPaths, insecure_skip_verify: bool) {
+    println!("{}", title);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Bundle version: {}", lock.bundle.version);
     println!("Platform:       {}-{}", detect_os(), detect_arch());
@@ -131,96 +218,88 @@ fn cmd_install(
     } else {
         println!("Mode:           user-local");
     }
-    println!();
-
-    // Get agents to install
-    let agents: Vec<_> = match &agent {
-        Some(name) => {
-            let agent_info = lock
-                .agent(name)
-                .ok_or_else(|| anyhow::anyhow!("Unknown agent: {}", name))?;
-            vec![agent_info]
-        }
-        None => lock.agents(),
-    };
-
-    if agents.is_empty() {
-        println!("No agents to install.");
-        return Ok(());
+    if insecure_skip_verify {
+        println!("Verification:   DISABLED (--insecure-skip-verify)");
     }
+    println!();
+}
 
-    // Check current status
-    let status = BundleStatus::check(lock, &paths);
-
-    if dry_run {
-        println!("[DRY RUN] Would install the following agents:");
-        println!();
-        for agent in &agents {
-            let agent_status = status.agents.iter().find(|a| a.name == agent.name);
+/// Options for [`install_agents`]
+struct InstallOptions {
+    /// Overwrite existing agent configuration files
+    overwrite_config: bool,
 
-            let action = match agent_status {
-                Some(s) if s.status == crate::bundle::status::Status::UpToDate && !force => {
-                    "skip (already installed)"
-                }
-                Some(s) if s.status == crate::bundle::status::Status::Outdated => "upgrade",
-                _ => "install",
-            };
+    /// Also install systemd service files
+    systemd: bool,
 
-            println!(
-                "  {} {} -> {} ({})",
-                agent.name,
-                agent.version,
-                paths.bin_dir.display(),
-                action
-            );
-        }
-        return Ok(());
-    }
+    /// Skip checksum and signature verification
+    insecure_skip_verify: bool,
+}
 
+/// Download and install agents, backing up the binaries they replace
+///
+/// Returns the number of agents installed and failed. The backup is
+/// discarded again if nothing was installed.
+fn install_agents(
+    lock: &BundleLock,
+    agents: &[AgentInfo],
+    status: &BundleStatus,
+    paths: &InstallPaths,
+    options: &InstallOptions,
+) -> Result<(usize, usize)> {
     // Ensure directories exist
     paths
         .ensure_dirs()
         .context("Failed to create installation directories")?;
 
+    // Back up what is about to be replaced so it can be rolled back
+    let entries = agents
+        .iter()
+        .map(|agent| BackupEntry {
+            name: agent.name.clone(),
+            binary_name: agent.binary_name.clone(),
+            version: status
+                .agents
+                .iter()
+                .find(|a| a.name == agent.name)
+                .and_then(|a| a.installed_version.clone()),
+        })
+        .collect();
+    let backup = create_backup(paths, &lock.bundle.version, entries)
+        .context("Failed to back up installed agents")?;
+
     // Create temporary directory for downloads
     let temp_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
 
     // Create async runtime for downloads
     let rt = tokio::runtime::Runtime::new()?;
 
-    // Install each agent
     let mut installed = 0;
-    let mut skipped = 0;
     let mut failed = 0;
 
-    for agent in &agents {
-        let agent_status = status.agents.iter().find(|a| a.name == agent.name);
-
-        // Skip if already installed (unless forced)
-        if !force {
-            if let Some(s) = agent_status {
-                if s.status == crate::bundle::status::Status::UpToDate {
-                    println!(
-                        "  [skip] {} {} (already installed)",
-                        agent.name, agent.version
-                    );
-                    skipped += 1;
-                    continue;
-                }
-            }
-        }
-
+    for agent in agents {
         print!("  Installing {} {}...", agent.name, agent.version);
 
         // Download
-        let download_result =
-            rt.block_on(async { download_agent(agent, temp_dir.path(), !skip_verify).await });
+        let download_result = rt.block_on(async {
+            download_agent(agent, temp_dir.path(), !options.insecure_skip_verify).await
+        });
 
         let download = match download_result {
             Ok(d) => d,
             Err(e) => {
                 println!(" FAILED");
                 eprintln!("    Error: {}", e);
+                if matches!(
+                    e,
+                    FetchError::ChecksumMismatch { .. }
+                        | FetchError::ChecksumUnavailable { .. }
+                        | FetchError::SignatureInvalid { .. }
+                        | FetchError::SignatureUnavailable { .. }
+                ) {
+                    eprintln!("    Refusing to install an unverified archive.");
+                    eprintln!("    Use --insecure-skip-verify to override (not recommended).");
+                }
                 failed += 1;
                 continue;
             }
@@ -236,11 +315,16 @@ fn cmd_install(
 
         // Install config
         let config_content = generate_default_config(&agent.name);
-        let config_path = install_config(&paths.config_dir, &agent.name, &config_content, force)
-            .context("Failed to install config")?;
+        let config_path = install_config(
+            &paths.config_dir,
+            &agent.name,
+            &config_content,
+            options.overwrite_config,
+        )
+        .context("Failed to install config")?;
 
         // Install systemd service if requested
-        if install_systemd {
+        if options.systemd {
             if let Some(ref systemd_dir) = paths.systemd_dir {
                 let bin_path = paths.bin_dir.join(&agent.binary_name);
                 let service_content =
@@ -250,10 +334,10 @@ fn cmd_install(
             }
         }
 
-        let checksum_status = if download.checksum_verified {
-            "verified"
-        } else {
-            "unverified"
+        let checksum_status = match (download.checksum_verified, download.signature_verified) {
+            (true, true) => "checksum and signature verified",
+            (true, false) => "checksum verified",
+            _ => "unverified",
         };
 
         println!(
@@ -264,6 +348,110 @@ fn cmd_install(
         installed += 1;
     }
 
+    if installed == 0 {
+        remove_backup(&paths.backup_dir, &backup.id).context("Failed to remove unused backup")?;
+    } else {
+        println!();
+        println!(
+            "Backup: {} (restore with `zentinel bundle rollback`)",
+            backup.id
+        );
+        if let Err(e) = prune_backups(&paths.backup_dir, RETAINED_BACKUPS) {
+            tracing::warn!(error = %e, "Failed to prune old bundle backups");
+        }
+    }
+
+    Ok((installed, failed))
+}
+
+/// Install command implementation
+fn cmd_install(
+    lock: &BundleLock,
+    agent_names: &[String],
+    dry_run: bool,
+    force: bool,
+    install_systemd: bool,
+    prefix: Option<PathBuf>,
+    insecure_skip_verify: bool,
+) -> Result<()> {
+    let paths = install_paths(prefix);
+
+    print_header(
+        "Zentinel Bundle Installer",
+        lock,
+        &paths,
+        insecure_skip_verify,
+    );
+
+    // Get agents to install
+    let agents = select_agents(lock, agent_names)?;
+
+    if agents.is_empty() {
+        println!("No agents to install.");
+        return Ok(());
+    }
+
+    // Check current status
+    let status = BundleStatus::check(lock, &paths);
+
+    if dry_run {
+        println!("[DRY RUN] Would install the following agents:");
+        println!();
+        for agent in &agents {
+            let agent_status = status.agents.iter().find(|a| a.name == agent.name);
+
+            let action = match agent_status {
+                Some(s) if s.status == Status::UpToDate && !force => "skip (already installed)",
+                Some(s) if s.status == Status::Outdated => "upgrade",
+                _ => "install",
+            };
+
+            println!(
+                "  {} {} -> {} ({})",
+                agent.name,
+                agent.version,
+                paths.bin_dir.display(),
+                action
+            );
+        }
+        return Ok(());
+    }
+
+    // Skip agents that are already installed (unless forced)
+    let mut pending = Vec::new();
+    let mut skipped = 0;
+    for agent in agents {
+        let up_to_date = status
+            .agents
+            .iter()
+            .any(|a| a.name == agent.name && a.status == Status::UpToDate);
+        if up_to_date && !force {
+            println!(
+                "  [skip] {} {} (already installed)",
+                agent.name, agent.version
+            );
+            skipped += 1;
+        } else {
+            pending.push(agent);
+        }
+    }
+
+    let (installed, failed) = if pending.is_empty() {
+        (0, 0)
+    } else {
+        install_agents(
+            lock,
+            &pending,
+            &status,
+            &paths,
+            &InstallOptions {
+                overwrite_config: force,
+                systemd: install_systemd,
+                insecure_skip_verify,
+            },
+        )?
+    };
+
     println!();
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!(
@@ -290,6 +478,166 @@ fn cmd_install(
     Ok(())
 }
 
+/// Upgrade command implementation
+///
+/// Only agents that are installed at a version different from the lock file
+/// are touched; missing agents are left to `bundle install`.
+fn cmd_upgrade(
+    lock: &BundleLock,
+    agent_names: &[String],
+    dry_run: bool,
+    prefix: Option<PathBuf>,
+    insecure_skip_verify: bool,
+) -> Result<()> {
+    let paths = install_paths(prefix);
+
+    print_header(
+        "Zentinel Bundle Upgrade",
+        lock,
+        &paths,
+        insecure_skip_verify,
+    );
+
+    let agents = select_agents(lock, agent_names)?;
+    let status = BundleStatus::check(lock, &paths);
+
+    let outdated: Vec<AgentInfo> = agents
+        .into_iter()
+        .filter(|agent| {
+            status
+                .agents
+                .iter()
+                .any(|a| a.name == agent.name && a.status == Status::Outdated)
+        })
+        .collect();
+
+    if outdated.is_empty() {
+        println!("All installed agents are up to date.");
+        return Ok(());
+    }
+
+    println!("{:<15} {:<12} {:<12}", "Agent", "Installed", "Bundle");
+    println!("{}", "─".repeat(40));
+    for agent in &outdated {
+        let installed = status
+            .agents
+            .iter()
+            .find(|a| a.name == agent.name)
+            .and_then(|a| a.installed_version.as_deref())
+            .unwrap_or("-");
+        println!("{:<15} {:<12} {:<12}", agent.name, installed, agent.version);
+    }
+    println!();
+
+    if dry_run {
+        println!("[DRY RUN] {} agent(s) would be upgraded", outdated.len());
+        return Ok(());
+    }
+
+    let (upgraded, failed) = install_agents(
+        lock,
+        &outdated,
+        &status,
+        &paths,
+        &InstallOptions {
+            overwrite_config: false,
+            systemd: false,
+            insecure_skip_verify,
+        },
+    )?;
+
+    println!();
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("Upgraded: {} | Failed: {}", upgraded, failed);
+
+    if upgraded > 0 && paths.system_wide {
+        println!();
+        println!("Restart the agents to pick up the new versions:");
+        println!("  sudo systemctl restart zentinel.target");
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} agent(s) failed to upgrade", failed);
+    }
+
+    Ok(())
+}
+
+/// Rollback command implementation
+fn cmd_rollback(
+    backup_id: Option<String>,
+    list: bool,
+    dry_run: bool,
+    prefix: Option<PathBuf>,
+) -> Result<()> {
+    let paths = install_paths(prefix);
+    let backups = list_backups(&paths.backup_dir);
+
+    println!("Zentinel Bundle Rollback");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("Backups:        {}", paths.backup_dir.display());
+    println!();
+
+    if list {
+        if backups.is_empty() {
+            println!("No backups available.");
+            return Ok(());
+        }
+        println!("{:<20} {:<27} {:<10} Agents", "Backup", "Created", "Bundle");
+        println!("{}", "─".repeat(70));
+        for backup in &backups {
+            println!(
+                "{:<20} {:<27} {:<10} {}",
+                backup.id,
+                backup.created_at,
+                backup.bundle_version,
+                backup.agents.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let backup: &BackupManifest = match &backup_id {
+        Some(id) => backups
+            .iter()
+            .find(|b| &b.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Backup not found: {}", id))?,
+        None => backups
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No backups found in {}", paths.backup_dir.display()))?,
+    };
+
+    println!(
+        "Restoring backup {} (taken before installing bundle {})",
+        backup.id, backup.bundle_version
+    );
+    println!();
+    for entry in &backup.agents {
+        match &entry.version {
+            Some(version) => println!("  {} -> {}", entry.name, version),
+            None => println!("  {} -> (remove)", entry.name),
+        }
+    }
+
+    if dry_run {
+        println!();
+        println!("[DRY RUN] No changes made");
+        return Ok(());
+    }
+
+    let summary = restore_backup(&paths, backup).context("Failed to restore backup")?;
+    remove_backup(&paths.backup_dir, &backup.id).context("Failed to remove restored backup")?;
+
+    println!();
+    println!(
+        "Restored: {} | Removed: {}",
+        summary.restored.len(),
+        summary.removed.len()
+    );
+
+    Ok(())
+}
+
 /// Status command implementation
 fn cmd_status(lock: &BundleLock, verbose: bool) -> Result<()> {
     let paths = InstallPaths::detect();
@@ -358,7 +706,7 @@ fn cmd_uninstall(lock: &BundleLock, agent: Option<String>, dry_run: bool) -> Res
     if dry_run {
         println!("[DRY RUN] Would uninstall:");
         for agent in &agents {
-            let bin_path = paths.bin_dir.join(&agent.binary_name);
+            let bin_path = paths.bin_dir.join(executable_name(&agent.binary_name));
             if bin_path.exists() {
                 println!("  {} ({})", agent.name, bin_path.display());
             }
@@ -434,12 +782,11 @@ fn cmd_update(current_lock: &BundleLock, apply: bool) -> Result<()> {
     }
 
     println!();
-    if apply {
-        println!("To update, run: zentinel bundle install --force");
-    } else {
+    if !apply {
         println!("Updates are available. Run with --apply to update.");
         println!("  zentinel bundle update --apply");
+        return Ok(());
     }
 
-    Ok(())
+    cmd_upgrade(&latest_lock, &[], false, None, false)
 }
@@ -1,7 +1,9 @@
 //! Agent download functionality
 //!
-//! Downloads agent binaries from their GitHub releases.
+//! Downloads agent binaries from their GitHub releases. Release assets are
+//! gzipped tarballs, except on Windows where they are zip archives.
 
+use crate::bundle::install::executable_name;
 use crate::bundle::lock::AgentInfo;
 use flate2::read::GzDecoder;
 use std::io::{self, Read, Write};
@@ -24,6 +26,15 @@ pub enum FetchError {
     #[error("Checksum verification failed for {agent}")]
     ChecksumMismatch { agent: String },
 
+    #[error("No checksum available for {agent}: {reason}")]
+    ChecksumUnavailable { agent: String, reason: String },
+
+    #[error("Signature verification failed for {agent}: {reason}")]
+    SignatureInvalid { agent: String, reason: String },
+
+    #[error("No signature available for {agent}: {reason}")]
+    SignatureUnavailable { agent: String, reason: String },
+
     #[error("Failed to extract archive: {0}")]
     Extract(String),
 
@@ -41,11 +52,21 @@ pub struct DownloadResult {
 
     /// Whether checksum was verified
     pub checksum_verified: bool,
+
+    /// Whether the minisign signature was verified
+    pub signature_verified: bool,
 }
 
 /// Detect the current operating system
+///
+/// Static musl builds report "linux-musl" so that Alpine hosts fetch the
+/// musl agent builds.
 pub fn detect_os() -> &'static str {
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", target_env = "musl"))]
+    {
+        "linux-musl"
+    }
+    #[cfg(all(target_os = "linux", not(target_env = "musl")))]
     {
         "linux"
     }
@@ -81,17 +102,31 @@ pub fn detect_arch() -> &'static str {
 
 /// Download an agent binary to a temporary directory
 ///
+/// When `verify` is set, the archive must match its SHA256 checksum (pinned
+/// in the lock file, or the published `.sha256` file) and its detached
+/// minisign signature. A bundle without a public key cannot be verified and
+/// is refused before anything is downloaded. Any failure aborts the download
+/// before the archive is extracted.
+///
 /// Returns the path to the extracted binary.
 pub async fn download_agent(
     agent: &AgentInfo,
     temp_dir: &Path,
-    verify_checksum: bool,
+    verify: bool,
 ) -> Result<DownloadResult, FetchError> {
     let os = detect_os();
     let arch = detect_arch();
 
     let url = agent.download_url(os, arch);
-    let checksum_url = agent.checksum_url(os, arch);
+
+    // A checksum fetched from the same origin as the archive proves nothing
+    // on its own, so the signature is what makes an archive trusted.
+    if verify && agent.public_key.is_none() {
+        return Err(FetchError::SignatureUnavailable {
+            agent: agent.name.clone(),
+            reason: "the bundle has no public_key to verify signatures with".to_string(),
+        });
+    }
 
     tracing::info!(
         agent = %agent.name,
@@ -100,46 +135,43 @@ pub async fn download_agent(
         "Downloading agent"
     );
 
-    let client = reqwest::Client::builder()
+    let client = crate::outbound::client_builder()
         .user_agent("zentinel-bundle")
         .build()?;
 
     // Download the archive
-    let response = client.get(&url).send().await?;
-
-    if !response.status().is_success() {
-        return Err(FetchError::DownloadFailed {
-            url,
-            status: response.status().as_u16(),
-        });
-    }
-
-    let archive_bytes = response.bytes().await?;
+    let archive_bytes = fetch_bytes(&client, &url).await?;
     let archive_size = archive_bytes.len() as u64;
 
-    // Verify checksum if requested
-    let checksum_verified = if verify_checksum {
-        match verify_sha256(&client, &checksum_url, &archive_bytes).await {
-            Ok(true) => {
-                tracing::debug!(agent = %agent.name, "Checksum verified");
-                true
-            }
-            Ok(false) => {
-                return Err(FetchError::ChecksumMismatch {
+    let (checksum_verified, signature_verified) = match agent.public_key.as_deref() {
+        Some(public_key) if verify => {
+            verify_checksum(&client, agent, os, arch, &archive_bytes).await?;
+            tracing::debug!(agent = %agent.name, "Checksum verified");
+
+            let signature_url = agent.signature_url(os, arch);
+            let signature = fetch_text(&client, &signature_url).await.map_err(|e| {
+                FetchError::SignatureUnavailable {
                     agent: agent.name.clone(),
-                });
-            }
-            Err(e) => {
-                tracing::warn!(
-                    agent = %agent.name,
-                    error = %e,
-                    "Checksum verification skipped (file not available)"
-                );
-                false
-            }
+                    reason: e.to_string(),
+                }
+            })?;
+            verify_signature(public_key, &signature, &archive_bytes).map_err(|reason| {
+                FetchError::SignatureInvalid {
+                    agent: agent.name.clone(),
+                    reason,
+                }
+            })?;
+            tracing::debug!(agent = %agent.name, "Signature verified");
+
+            (true, true)
+        }
+        _ => {
+            tracing::warn!(
+                agent = %agent.name,
+                "Checksum and signature verification disabled"
+            );
+            (false, false)
         }
-    } else {
-        false
     };
 
     // Extract the archive
@@ -149,61 +181,121 @@ pub async fn download_agent(
         binary_path,
         archive_size,
         checksum_verified,
+        signature_verified,
     })
 }
 
-/// Verify SHA256 checksum of downloaded data
-async fn verify_sha256(
-    client: &reqwest::Client,
-    checksum_url: &str,
-    data: &[u8],
-) -> Result<bool, FetchError> {
-    use sha2::{Digest, Sha256};
-
-    // Download checksum file
-    let response = client.get(checksum_url).send().await?;
+/// GET a URL and return the body, failing on non-success status
+async fn fetch_bytes(client: &reqwest::Client, url: &str) -> Result<bytes::Bytes, FetchError> {
+    let response = client.get(url).send().await?;
 
     if !response.status().is_success() {
         return Err(FetchError::DownloadFailed {
-            url: checksum_url.to_string(),
+            url: url.to_string(),
             status: response.status().as_u16(),
         });
     }
 
-    let checksum_content = response.text().await?;
+    Ok(response.bytes().await?)
+}
+
+/// GET a URL and return the body as text, failing on non-success status
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, FetchError> {
+    let bytes = fetch_bytes(client, url).await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Verify the SHA256 checksum of a downloaded archive
+///
+/// Prefers the checksum pinned in the lock file; falls back to the
+/// `.sha256` file published next to the archive.
+async fn verify_checksum(
+    client: &reqwest::Client,
+    agent: &AgentInfo,
+    os: &str,
+    arch: &str,
+    data: &[u8],
+) -> Result<(), FetchError> {
+    let expected = match agent.expected_checksum(os, arch) {
+        Some(expected) => expected,
+        None => {
+            let checksum_url = agent.checksum_url(os, arch);
+            let content = fetch_text(client, &checksum_url).await.map_err(|e| {
+                FetchError::ChecksumUnavailable {
+                    agent: agent.name.clone(),
+                    reason: e.to_string(),
+                }
+            })?;
+            parse_checksum_file(&content).ok_or_else(|| FetchError::ChecksumUnavailable {
+                agent: agent.name.clone(),
+                reason: format!("invalid checksum file: {}", checksum_url),
+            })?
+        }
+    };
+
+    if sha256_hex(data) != expected {
+        return Err(FetchError::ChecksumMismatch {
+            agent: agent.name.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Parse the digest from a `sha256sum` style file ("<hex>  <filename>")
+fn parse_checksum_file(content: &str) -> Option<String> {
+    let digest = content.split_whitespace().next()?;
+    let digest = digest.strip_prefix("sha256:").unwrap_or(digest);
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(digest.to_lowercase())
+}
 
-    // Parse expected checksum (format: "sha256hash  filename")
-    let expected = checksum_content
-        .split_whitespace()
-        .next()
-        .ok_or_else(|| FetchError::Extract("Invalid checksum file format".to_string()))?
-        .to_lowercase();
+/// Compute the lowercase hex SHA256 digest of data
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
 
-    // Calculate actual checksum
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let actual = hex::encode(hasher.finalize());
+    hex::encode(Sha256::digest(data))
+}
 
-    Ok(expected == actual)
+/// Verify a detached minisign signature over data
+///
+/// Legacy (non-prehashed) signatures are rejected.
+fn verify_signature(public_key: &str, signature: &str, data: &[u8]) -> Result<(), String> {
+    let public_key = minisign_verify::PublicKey::from_base64(public_key.trim())
+        .map_err(|e| format!("invalid public key: {}", e))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| format!("invalid signature file: {}", e))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| e.to_string())
 }
 
-/// Extract a tarball and find the binary
+/// Zip local file header signature
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Extract a tarball or zip archive and find the binary
 fn extract_archive(
     archive_bytes: &[u8],
     binary_name: &str,
     dest_dir: &Path,
 ) -> Result<PathBuf, FetchError> {
-    let decoder = GzDecoder::new(archive_bytes);
-    let mut archive = Archive::new(decoder);
-
     // Create destination directory
     std::fs::create_dir_all(dest_dir)
         .map_err(|e| FetchError::Extract(format!("Failed to create directory: {}", e)))?;
 
     // Extract all files
-    archive
-        .unpack(dest_dir)
-        .map_err(|e| FetchError::Extract(format!("Failed to extract: {}", e)))?;
+    if archive_bytes.starts_with(ZIP_MAGIC) {
+        zip::ZipArchive::new(io::Cursor::new(archive_bytes))
+            .and_then(|mut archive| archive.extract(dest_dir))
+            .map_err(|e| FetchError::Extract(format!("Failed to extract: {}", e)))?;
+    } else {
+        let decoder = GzDecoder::new(archive_bytes);
+        Archive::new(decoder)
+            .unpack(dest_dir)
+            .map_err(|e| FetchError::Extract(format!("Failed to extract: {}", e)))?;
+    }
 
     // Find the binary (might be at top level or in a subdirectory)
     let binary_path = find_binary(dest_dir, binary_name)?;
@@ -225,6 +317,9 @@ fn extract_archive(
 
 /// Find the binary in the extracted directory
 fn find_binary(dir: &Path, binary_name: &str) -> Result<PathBuf, FetchError> {
+    let binary_name = executable_name(binary_name);
+    let binary_name = binary_name.as_str();
+
     // Check top level
     let direct_path = dir.join(binary_name);
     if direct_path.exists() {
@@ -301,6 +396,7 @@ impl Iterator for WalkDir {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::io::Write;
 
     #[test]
@@ -313,9 +409,12 @@ mod tests {
         assert!(!arch.is_empty());
 
         // On common platforms
-        #[cfg(target_os = "linux")]
+        #[cfg(all(target_os = "linux", not(target_env = "musl")))]
         assert_eq!(os, "linux");
 
+        #[cfg(all(target_os = "linux", target_env = "musl"))]
+        assert_eq!(os, "linux-musl");
+
         #[cfg(target_os = "macos")]
         assert_eq!(os, "darwin");
 
@@ -330,7 +429,7 @@ mod tests {
     fn test_detect_os_is_known() {
         let os = detect_os();
         assert!(
-            ["linux", "darwin", "windows", "unknown"].contains(&os),
+            ["linux", "linux-musl", "darwin", "windows", "unknown"].contains(&os),
             "Unexpected OS: {}",
             os
         );
@@ -431,6 +530,35 @@ mod tests {
         assert!(binary_path.exists());
     }
 
+    #[test]
+    fn test_extract_archive_valid_zip() {
+        use zip::write::SimpleFileOptions;
+
+        let temp = tempfile::tempdir().unwrap();
+        let binary_name = "test-binary";
+
+        // Windows release layout: binary (with .exe on Windows) in a subdirectory
+        let mut archive_data = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(io::Cursor::new(&mut archive_data));
+            writer
+                .start_file(
+                    format!("test-binary-0.1.0/{}", executable_name(binary_name)),
+                    SimpleFileOptions::default(),
+                )
+                .unwrap();
+            writer.write_all(b"MZ fake executable").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let binary_path = extract_archive(&archive_data, binary_name, temp.path()).unwrap();
+        assert!(binary_path.exists());
+        assert_eq!(
+            binary_path.file_name().unwrap().to_string_lossy(),
+            executable_name(binary_name)
+        );
+    }
+
     #[test]
     fn test_extract_archive_invalid_gzip() {
         let temp = tempfile::tempdir().unwrap();
@@ -446,12 +574,85 @@ mod tests {
             binary_path: PathBuf::from("/tmp/test"),
             archive_size: 1024,
             checksum_verified: true,
+            signature_verified: false,
         };
 
         assert_eq!(result.archive_size, 1024);
         assert!(result.checksum_verified);
     }
 
+    #[tokio::test]
+    async fn test_download_refused_without_public_key() {
+        let agent = AgentInfo {
+            name: "waf".to_string(),
+            version: "0.3.0".to_string(),
+            repository: "zentinelproxy/zentinel-agent-waf".to_string(),
+            binary_name: "zentinel-waf-agent".to_string(),
+            precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
+        };
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // Refused before anything is fetched
+        let result = download_agent(&agent, temp_dir.path(), true).await;
+        assert!(matches!(
+            result,
+            Err(FetchError::SignatureUnavailable { .. })
+        ));
+    }
+
+    const TEST_PUBLIC_KEY: &str = "RWRaRU5USU5FTAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const TEST_DATA: &[u8] = b"zentinel bundle test artifact\n";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RURaRU5USU5FTCmE7/51vfqoK7C3MARGa/jv6LWtDOBtfV9bHpNU7aPcRdtv57WYAkCAIdgL/fGPg7ZLjygqAaRdqW+mMwvymQE=
+trusted comment: timestamp:1760000000\tfile:zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz
+u2vUE+ay3UziOudOtBBzES8c8FNWJ6SBWzxgsyj5AntXrW4eZ9sf3hWoE8tXD/fdd7SKE5SvPO73VRgTUZPmDg==
+";
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(TEST_DATA),
+            "84ffff3f5db6b4b4cfde17d8bd912ad6ee38ff7ccf3e7f2cbf6d9d09c6655d24"
+        );
+    }
+
+    #[test]
+    fn test_parse_checksum_file() {
+        let digest = "84ffff3f5db6b4b4cfde17d8bd912ad6ee38ff7ccf3e7f2cbf6d9d09c6655d24";
+        assert_eq!(
+            parse_checksum_file(&format!("{}  agent.tar.gz\n", digest.to_uppercase())).as_deref(),
+            Some(digest)
+        );
+        assert_eq!(
+            parse_checksum_file(&format!("sha256:{}", digest)).as_deref(),
+            Some(digest)
+        );
+        assert!(parse_checksum_file("").is_none());
+        assert!(parse_checksum_file("<html>Not Found</html>").is_none());
+    }
+
+    #[test]
+    fn test_verify_signature_valid() {
+        assert!(verify_signature(TEST_PUBLIC_KEY, TEST_SIGNATURE, TEST_DATA).is_ok());
+    }
+
+    #[test]
+    fn test_verify_signature_tampered_data() {
+        let result = verify_signature(TEST_PUBLIC_KEY, TEST_SIGNATURE, b"tampered archive");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_signature_wrong_key() {
+        // Valid key format, different key id
+        let other_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        assert!(verify_signature(other_key, TEST_SIGNATURE, TEST_DATA).is_err());
+        assert!(verify_signature("not a key", TEST_SIGNATURE, TEST_DATA).is_err());
+        assert!(verify_signature(TEST_PUBLIC_KEY, "garbage", TEST_DATA).is_err());
+    }
+
     #[test]
     fn test_fetch_error_display() {
         let err = FetchError::ChecksumMismatch {
@@ -459,6 +660,12 @@ mod tests {
         };
         assert!(err.to_string().contains("waf"));
 
+        let err = FetchError::SignatureInvalid {
+            agent: "waf".to_string(),
+            reason: "Invalid signature".to_string(),
+        };
+        assert!(err.to_string().contains("Signature verification failed"));
+
         let err = FetchError::BinaryNotFound("test".to_string());
         assert!(err.to_string().contains("test"));
 
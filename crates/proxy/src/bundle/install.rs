@@ -34,6 +34,9 @@ pub struct InstallPaths {
     /// Directory for systemd service files (Linux only)
     pub systemd_dir: Option<PathBuf>,
 
+    /// Directory for binary backups taken before install/upgrade (for rollback)
+    pub backup_dir: PathBuf,
+
     /// Whether this is a system-wide install (requires root)
     pub system_wide: bool,
 }
@@ -45,17 +48,36 @@ impl InstallPaths {
             bin_dir: PathBuf::from("/usr/local/bin"),
             config_dir: PathBuf::from("/etc/zentinel/agents"),
             systemd_dir: Some(PathBuf::from("/etc/systemd/system")),
+            backup_dir: PathBuf::from("/var/lib/zentinel/bundle-backups"),
             system_wide: true,
         }
     }
 
     /// Get user-local installation paths
+    #[cfg(windows)]
+    pub fn user() -> Self {
+        let local = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
+        let roaming = std::env::var("APPDATA").unwrap_or_else(|_| local.clone());
+        Self {
+            bin_dir: PathBuf::from(&local).join("Zentinel").join("bin"),
+            config_dir: PathBuf::from(&roaming).join("Zentinel").join("agents"),
+            systemd_dir: None,
+            backup_dir: PathBuf::from(&local)
+                .join("Zentinel")
+                .join("bundle-backups"),
+            system_wide: false,
+        }
+    }
+
+    /// Get user-local installation paths
+    #[cfg(not(windows))]
     pub fn user() -> Self {
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
         Self {
             bin_dir: PathBuf::from(&home).join(".local/bin"),
             config_dir: PathBuf::from(&home).join(".config/zentinel/agents"),
             systemd_dir: Some(PathBuf::from(&home).join(".config/systemd/user")),
+            backup_dir: PathBuf::from(&home).join(".local/share/zentinel/bundle-backups"),
             system_wide: false,
         }
     }
@@ -66,11 +88,14 @@ impl InstallPaths {
             bin_dir: prefix.join("bin"),
             config_dir: prefix.join("etc/zentinel/agents"),
             systemd_dir: Some(prefix.join("lib/systemd/system")),
+            backup_dir: prefix.join("var/lib/zentinel/bundle-backups"),
             system_wide: false,
         }
     }
 
     /// Determine the best installation paths based on current user
+    ///
+    /// Windows always uses the per-user paths.
     pub fn detect() -> Self {
         // Check if we're root
         #[cfg(unix)]
@@ -81,9 +106,11 @@ impl InstallPaths {
         }
 
         // Check if /usr/local/bin is writable
-        let system_paths = Self::system();
-        if is_writable(&system_paths.bin_dir) {
-            return system_paths;
+        if !cfg!(windows) {
+            let system_paths = Self::system();
+            if is_writable(&system_paths.bin_dir) {
+                return system_paths;
+            }
         }
 
         // Fall back to user paths
@@ -131,9 +158,14 @@ fn create_dir_if_missing(path: &Path) -> Result<(), InstallError> {
     Ok(())
 }
 
+/// File name of an agent executable on this platform (`.exe` on Windows)
+pub fn executable_name(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
 /// Install a binary to the target directory
 pub fn install_binary(source: &Path, dest_dir: &Path, name: &str) -> Result<PathBuf, InstallError> {
-    let dest_path = dest_dir.join(name);
+    let dest_path = dest_dir.join(executable_name(name));
 
     tracing::info!(
         source = %source.display(),
@@ -158,7 +190,7 @@ pub fn install_binary(source: &Path, dest_dir: &Path, name: &str) -> Result<Path
 
 /// Uninstall a binary
 pub fn uninstall_binary(bin_dir: &Path, name: &str) -> Result<bool, InstallError> {
-    let path = bin_dir.join(name);
+    let path = bin_dir.join(executable_name(name));
 
     if path.exists() {
         tracing::info!(path = %path.display(), "Removing binary");
@@ -171,7 +203,7 @@ pub fn uninstall_binary(bin_dir: &Path, name: &str) -> Result<bool, InstallError
 
 /// Check if a binary is installed and get its version
 pub fn get_installed_version(bin_dir: &Path, binary_name: &str) -> Option<String> {
-    let path = bin_dir.join(binary_name);
+    let path = bin_dir.join(executable_name(binary_name));
 
     if !path.exists() {
         return None;
@@ -447,6 +479,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_executable_name() {
+        #[cfg(windows)]
+        assert_eq!(
+            executable_name("zentinel-waf-agent"),
+            "zentinel-waf-agent.exe"
+        );
+
+        #[cfg(not(windows))]
+        assert_eq!(executable_name("zentinel-waf-agent"), "zentinel-waf-agent");
+    }
+
     #[test]
     fn test_install_paths_system() {
         let paths = InstallPaths::system();
@@ -476,6 +520,10 @@ mod tests {
             paths.config_dir,
             PathBuf::from("/opt/zentinel/etc/zentinel/agents")
         );
+        assert_eq!(
+            paths.backup_dir,
+            PathBuf::from("/opt/zentinel/var/lib/zentinel/bundle-backups")
+        );
     }
 
     #[test]
@@ -551,13 +599,16 @@ mod tests {
 
         let installed = result.unwrap();
         assert!(installed.exists());
-        assert_eq!(installed.file_name().unwrap(), "test-binary");
+        assert_eq!(
+            installed.file_name().unwrap().to_string_lossy(),
+            executable_name("test-binary")
+        );
     }
 
     #[test]
     fn test_uninstall_binary_exists() {
         let temp = tempfile::tempdir().unwrap();
-        let binary_path = temp.path().join("test-binary");
+        let binary_path = temp.path().join(executable_name("test-binary"));
         std::fs::write(&binary_path, "content").unwrap();
 
         let result = uninstall_binary(temp.path(), "test-binary");
@@ -638,6 +689,7 @@ mod tests {
             bin_dir: temp.path().join("bin"),
             config_dir: temp.path().join("config"),
             systemd_dir: Some(temp.path().join("systemd")),
+            backup_dir: temp.path().join("backups"),
             system_wide: false,
         };
 
@@ -4,7 +4,7 @@
 //! versions are included in the bundle. Also supports fetching bundle
 //! metadata from the Zentinel API (`api.zentinelproxy.io`).
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use thiserror::Error;
@@ -18,7 +18,7 @@ const LEGACY_LOCK_URL: &str =
     "https://raw.githubusercontent.com/zentinelproxy/zentinel/main/bundle-versions.lock";
 
 /// Maximum schema version this CLI understands
-const MAX_SCHEMA_VERSION: u32 = 1;
+pub(crate) const MAX_SCHEMA_VERSION: u32 = 1;
 
 /// Errors that can occur when parsing the lock file
 #[derive(Debug, Error)]
@@ -46,7 +46,7 @@ pub enum LockError {
 // ---------------------------------------------------------------------------
 
 /// JSON response from `GET /v1/bundle/`
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiBundleResponse {
     pub schema_version: u32,
     pub bundle: ApiBundleMeta,
@@ -54,15 +54,18 @@ pub struct ApiBundleResponse {
 }
 
 /// Bundle-level metadata from the API
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiBundleMeta {
     pub version: String,
     #[allow(dead_code)]
     pub generated_at: String,
+    /// Minisign public key that signs the bundle's release artifacts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
 }
 
 /// Per-agent data from the API bundle endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiBundleAgent {
     pub version: String,
     pub repository: String,
@@ -79,6 +82,7 @@ impl From<ApiBundleResponse> for BundleLock {
         let mut repositories = HashMap::new();
         let mut binary_names = HashMap::new();
         let mut download_urls = HashMap::new();
+        let mut checksums = HashMap::new();
 
         for (name, agent) in &api.agents {
             agents.insert(name.clone(), agent.version.clone());
@@ -89,16 +93,25 @@ impl From<ApiBundleResponse> for BundleLock {
             for (platform, url) in &agent.download_urls {
                 download_urls.insert(format!("{}-{}", name, platform), url.clone());
             }
+
+            // Store checksums keyed as "agent-version-os-arch", matching the lock file
+            for (platform, checksum) in &agent.checksums {
+                checksums.insert(
+                    format!("{}-{}-{}", name, agent.version, platform),
+                    checksum.clone(),
+                );
+            }
         }
 
         BundleLock {
             bundle: BundleInfo {
                 version: api.bundle.version,
+                public_key: api.bundle.public_key,
             },
             agents,
             repositories,
             binary_names,
-            checksums: HashMap::new(),
+            checksums,
             precomputed_urls: download_urls,
         }
     }
@@ -121,7 +134,9 @@ pub struct BundleLock {
     #[serde(default)]
     pub binary_names: HashMap<String, String>,
 
-    /// Optional checksums for verification
+    /// SHA256 checksums of release archives
+    /// Keys are "agent-version-os-arch" (e.g., "waf-0.3.0-linux-x86_64"),
+    /// values are "sha256:<hex>" or bare hex digests.
     #[serde(default)]
     pub checksums: HashMap<String, String>,
 
@@ -136,6 +151,11 @@ pub struct BundleLock {
 pub struct BundleInfo {
     /// Bundle version (CalVer: YY.MM_PATCH)
     pub version: String,
+
+    /// Minisign public key (base64) used to verify `.minisig` signatures
+    /// of agent archives. Signature verification is skipped when unset.
+    #[serde(default)]
+    pub public_key: Option<String>,
 }
 
 /// Information about a bundled agent
@@ -155,6 +175,12 @@ pub struct AgentInfo {
 
     /// Precomputed download URLs from the API, keyed by platform (e.g., "linux-x86_64")
     pub precomputed_urls: HashMap<String, String>,
+
+    /// Expected archive checksums, keyed by platform (e.g., "linux-x86_64")
+    pub checksums: HashMap<String, String>,
+
+    /// Minisign public key for archive signatures
+    pub public_key: Option<String>,
 }
 
 impl BundleLock {
@@ -187,7 +213,7 @@ impl BundleLock {
     /// 2. `api.zentinelproxy.io/v1/bundle/` — primary API
     /// 3. `raw.githubusercontent.com/.../bundle-versions.lock` — legacy fallback
     pub async fn fetch_latest() -> Result<Self, LockError> {
-        let client = reqwest::Client::builder()
+        let client = crate::outbound::client_builder()
             .user_agent("zentinel-bundle")
             .timeout(std::time::Duration::from_secs(15))
             .build()
@@ -213,6 +239,20 @@ impl BundleLock {
         Self::fetch_from_legacy(&client).await
     }
 
+    /// Fetch bundle metadata from a self-hosted registry, without fallback
+    ///
+    /// Used when `ZENTINEL_API_URL` points at an internal mirror
+    /// (`zentinel registry serve`), so installs never reach out to GitHub.
+    pub async fn fetch_from_registry(url: &str) -> Result<Self, LockError> {
+        let client = crate::outbound::client_builder()
+            .user_agent("zentinel-bundle")
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .map_err(|e| LockError::Fetch(e.to_string()))?;
+
+        Self::fetch_from_api(&client, url).await
+    }
+
     /// Fetch bundle metadata from the JSON API
     async fn fetch_from_api(client: &reqwest::Client, url: &str) -> Result<Self, LockError> {
         let response = client
@@ -285,12 +325,15 @@ impl BundleLock {
                     .cloned()
                     .unwrap_or_else(|| format!("zentinel-{}-agent", name));
                 let precomputed_urls = self.precomputed_urls_for(name);
+                let checksums = self.checksums_for(name, version);
                 Some(AgentInfo {
                     name: name.clone(),
                     version: version.clone(),
                     repository: repository.clone(),
                     binary_name,
                     precomputed_urls,
+                    checksums,
+                    public_key: self.bundle.public_key.clone(),
                 })
             })
             .collect()
@@ -306,12 +349,15 @@ impl BundleLock {
             .cloned()
             .unwrap_or_else(|| format!("zentinel-{}-agent", name));
         let precomputed_urls = self.precomputed_urls_for(name);
+        let checksums = self.checksums_for(name, version);
         Some(AgentInfo {
             name: name.to_string(),
             version: version.clone(),
             repository: repository.clone(),
             binary_name,
             precomputed_urls,
+            checksums,
+            public_key: self.bundle.public_key.clone(),
         })
     }
 
@@ -327,6 +373,18 @@ impl BundleLock {
             .collect()
     }
 
+    /// Extract checksums for a specific agent version from the flat map
+    fn checksums_for(&self, agent_name: &str, version: &str) -> HashMap<String, String> {
+        let prefix = format!("{}-{}-", agent_name, version);
+        self.checksums
+            .iter()
+            .filter_map(|(key, checksum)| {
+                key.strip_prefix(&prefix)
+                    .map(|platform| (platform.to_string(), checksum.clone()))
+            })
+            .collect()
+    }
+
     /// Get the list of agent names
     pub fn agent_names(&self) -> Vec<&str> {
         self.agents.keys().map(|s| s.as_str()).collect()
@@ -340,25 +398,24 @@ impl AgentInfo {
     /// the URL from the repository, version, and binary name.
     ///
     /// # Arguments
-    /// * `os` - Operating system (e.g., "linux", "darwin")
+    /// * `os` - Operating system (e.g., "linux", "linux-musl", "darwin", "windows")
     /// * `arch` - Architecture (e.g., "amd64", "arm64")
     pub fn download_url(&self, os: &str, arch: &str) -> String {
-        let release_arch = match arch {
-            "amd64" => "x86_64",
-            "arm64" => "aarch64",
-            _ => arch,
-        };
-
         // Check for precomputed URL from API
-        let platform_key = format!("{}-{}", os, release_arch);
+        let platform_key = platform_key(os, arch);
         if let Some(url) = self.precomputed_urls.get(&platform_key) {
             return url.clone();
         }
 
         // Fall back to constructed URL
         format!(
-            "https://github.com/{}/releases/download/v{}/{}-{}-{}-{}.tar.gz",
-            self.repository, self.version, self.binary_name, self.version, os, release_arch
+            "https://github.com/{}/releases/download/v{}/{}-{}-{}.{}",
+            self.repository,
+            self.version,
+            self.binary_name,
+            self.version,
+            platform_key,
+            archive_extension(os)
         )
     }
 
@@ -366,6 +423,46 @@ impl AgentInfo {
     pub fn checksum_url(&self, os: &str, arch: &str) -> String {
         format!("{}.sha256", self.download_url(os, arch))
     }
+
+    /// Get the detached minisign signature URL for this agent
+    pub fn signature_url(&self, os: &str, arch: &str) -> String {
+        format!("{}.minisig", self.download_url(os, arch))
+    }
+
+    /// Get the pinned SHA256 checksum (lowercase hex) for a platform, if known
+    pub fn expected_checksum(&self, os: &str, arch: &str) -> Option<String> {
+        let checksum = self.checksums.get(&platform_key(os, arch))?;
+        let hex = checksum.strip_prefix("sha256:").unwrap_or(checksum);
+        Some(hex.trim().to_lowercase())
+    }
+}
+
+/// Platform key used in release asset names and API maps
+///
+/// Maps Go-style architecture names to release names and moves the libc
+/// variant to the end: ("linux", "amd64") -> "linux-x86_64",
+/// ("linux-musl", "amd64") -> "linux-x86_64-musl",
+/// ("windows", "amd64") -> "windows-x86_64".
+pub fn platform_key(os: &str, arch: &str) -> String {
+    let release_arch = match arch {
+        "amd64" => "x86_64",
+        "arm64" => "aarch64",
+        _ => arch,
+    };
+
+    match os.split_once('-') {
+        Some((os, libc)) => format!("{}-{}-{}", os, release_arch, libc),
+        None => format!("{}-{}", os, release_arch),
+    }
+}
+
+/// Archive format of release assets: zip on Windows, gzipped tarball elsewhere
+pub fn archive_extension(os: &str) -> &'static str {
+    if os == "windows" {
+        "zip"
+    } else {
+        "tar.gz"
+    }
 }
 
 #[cfg(test)]
@@ -549,6 +646,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-waf".to_string(),
             binary_name: "zentinel-waf-agent".to_string(),
             precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         let url = agent.download_url("linux", "amd64");
@@ -566,6 +665,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-ratelimit".to_string(),
             binary_name: "zentinel-ratelimit-agent".to_string(),
             precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         let url = agent.download_url("linux", "arm64");
@@ -583,6 +684,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-denylist".to_string(),
             binary_name: "zentinel-denylist-agent".to_string(),
             precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         let url = agent.download_url("darwin", "arm64");
@@ -590,6 +693,45 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
         assert!(url.contains("aarch64"));
     }
 
+    #[test]
+    fn test_download_url_windows_and_musl() {
+        let mut precomputed = HashMap::new();
+        precomputed.insert(
+            "linux-x86_64-musl".to_string(),
+            "https://api.example.com/waf-musl.tar.gz".to_string(),
+        );
+        let agent = AgentInfo {
+            name: "waf".to_string(),
+            version: "0.3.0".to_string(),
+            repository: "zentinelproxy/zentinel-agent-waf".to_string(),
+            binary_name: "zentinel-waf-agent".to_string(),
+            precomputed_urls: precomputed,
+            checksums: HashMap::new(),
+            public_key: None,
+        };
+
+        assert_eq!(
+            agent.download_url("windows", "amd64"),
+            "https://github.com/zentinelproxy/zentinel-agent-waf/releases/download/v0.3.0/zentinel-waf-agent-0.3.0-windows-x86_64.zip"
+        );
+        assert_eq!(
+            agent.download_url("linux-musl", "amd64"),
+            "https://api.example.com/waf-musl.tar.gz"
+        );
+        assert_eq!(
+            agent.download_url("linux-musl", "arm64"),
+            "https://github.com/zentinelproxy/zentinel-agent-waf/releases/download/v0.3.0/zentinel-waf-agent-0.3.0-linux-aarch64-musl.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_platform_key() {
+        assert_eq!(platform_key("linux", "amd64"), "linux-x86_64");
+        assert_eq!(platform_key("linux-musl", "amd64"), "linux-x86_64-musl");
+        assert_eq!(platform_key("darwin", "arm64"), "darwin-aarch64");
+        assert_eq!(platform_key("windows", "amd64"), "windows-x86_64");
+    }
+
     #[test]
     fn test_checksum_url() {
         let agent = AgentInfo {
@@ -598,6 +740,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-waf".to_string(),
             binary_name: "zentinel-waf-agent".to_string(),
             precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         let url = agent.checksum_url("linux", "amd64");
@@ -688,6 +832,7 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             bundle: ApiBundleMeta {
                 version: "26.02_13".to_string(),
                 generated_at: "2026-02-23T00:00:00Z".to_string(),
+                public_key: None,
             },
             agents,
         };
@@ -718,6 +863,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-waf".to_string(),
             binary_name: "zentinel-waf-agent".to_string(),
             precomputed_urls: HashMap::new(),
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         let url = agent.download_url("linux", "amd64");
@@ -741,6 +888,8 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
             repository: "zentinelproxy/zentinel-agent-waf".to_string(),
             binary_name: "zentinel-waf-agent".to_string(),
             precomputed_urls: precomputed,
+            checksums: HashMap::new(),
+            public_key: None,
         };
 
         // Should use precomputed URL
@@ -752,6 +901,78 @@ denylist = "zentinelproxy/zentinel-agent-denylist"
         assert!(url.contains("github.com"));
     }
 
+    #[test]
+    fn test_expected_checksum_from_lock_file() {
+        let content = r#"
+[bundle]
+version = "26.03_01"
+public_key = "RWRaRU5USU5FTAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4"
+
+[agents]
+waf = "0.3.0"
+
+[repositories]
+waf = "zentinelproxy/zentinel-agent-waf"
+
+[checksums]
+waf-0.3.0-linux-x86_64 = "sha256:84FFFF3F5DB6B4B4CFDE17D8BD912AD6EE38FF7CCF3E7F2CBF6D9D09C6655D24"
+waf-0.2.0-linux-aarch64 = "sha256:0000000000000000000000000000000000000000000000000000000000000000"
+"#;
+
+        let lock = BundleLock::from_str(content).unwrap();
+        let agent = lock.agent("waf").unwrap();
+
+        assert_eq!(
+            agent.expected_checksum("linux", "amd64").as_deref(),
+            Some("84ffff3f5db6b4b4cfde17d8bd912ad6ee38ff7ccf3e7f2cbf6d9d09c6655d24")
+        );
+        // Checksums for other versions are not picked up
+        assert!(agent.expected_checksum("linux", "arm64").is_none());
+        assert!(agent.public_key.is_some());
+        assert!(agent
+            .signature_url("linux", "amd64")
+            .ends_with(".tar.gz.minisig"));
+    }
+
+    #[test]
+    fn test_api_bundle_response_checksums() {
+        let mut checksums = HashMap::new();
+        checksums.insert("linux-x86_64".to_string(), "sha256:abcdef".to_string());
+
+        let mut agents = HashMap::new();
+        agents.insert(
+            "waf".to_string(),
+            ApiBundleAgent {
+                version: "0.3.0".to_string(),
+                repository: "zentinelproxy/zentinel-agent-waf".to_string(),
+                binary_name: "zentinel-waf-agent".to_string(),
+                download_urls: HashMap::new(),
+                checksums,
+            },
+        );
+
+        let lock = BundleLock::from(ApiBundleResponse {
+            schema_version: 1,
+            bundle: ApiBundleMeta {
+                version: "26.03_01".to_string(),
+                generated_at: "2026-03-01T00:00:00Z".to_string(),
+                public_key: Some("RWQ...".to_string()),
+            },
+            agents,
+        });
+
+        assert_eq!(
+            lock.checksums.get("waf-0.3.0-linux-x86_64"),
+            Some(&"sha256:abcdef".to_string())
+        );
+        let agent = lock.agent("waf").unwrap();
+        assert_eq!(
+            agent.expected_checksum("linux", "amd64").as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(agent.public_key.as_deref(), Some("RWQ..."));
+    }
+
     #[test]
     fn test_unsupported_schema_version_error() {
         let err = LockError::UnsupportedSchema {
@@ -14,6 +14,9 @@
 //! ```bash
 //! zentinel bundle install          # Download and install all bundled agents
 //! zentinel bundle install --dry-run    # Preview what would be installed
+//! zentinel bundle install waf lua  # Install selected agents
+//! zentinel bundle upgrade          # Upgrade outdated installed agents
+//! zentinel bundle rollback         # Restore the previous agent versions
 //! zentinel bundle status           # Show installed vs expected versions
 //! zentinel bundle list             # List available agents in the bundle
 //! zentinel bundle uninstall        # Remove installed agents
@@ -38,11 +41,14 @@
 //! denylist = "zentinelproxy/zentinel-agent-denylist"
 //! ```
 
+mod backup;
 mod commands;
 mod fetch;
 mod install;
 mod lock;
+mod registry;
 mod status;
 
 pub use commands::{run_bundle_command, BundleArgs, BundleCommand};
 pub use lock::BundleLock;
+pub use registry::{run_registry_command, RegistryArgs, RegistryCommand};
@@ -0,0 +1,755 @@
+//! Self-hosted bundle registry
+//!
+//! `zentinel registry serve --dir <dir>` serves the same v1 JSON API as
+//! `api.zentinelproxy.io` plus the agent release archives, so air-gapped
+//! installs can point `ZENTINEL_API_URL` at an internal mirror.
+//!
+//! The registry directory holds a `bundle-versions.lock` and the release
+//! assets exactly as published (`zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz`,
+//! optional `.sha256` and `.minisig` files). Responses are generated from
+//! the directory on every request, so dropping in new assets or editing the
+//! lock file needs no restart. Checksums are computed from the archives and
+//! cached until the file changes.
+//!
+//! Endpoints:
+//!
+//! | Path | Response |
+//! |------|----------|
+//! | `/v1/bundle/` | Bundle metadata, download URLs and checksums |
+//! | `/v1/agents/` | All agents |
+//! | `/v1/agents/<name>/` | A single agent |
+//! | `/v1/health` | Registry status |
+//! | `/artifacts/<file>` | Release archives, checksums and signatures |
+
+use crate::bundle::lock::{
+    archive_extension, ApiBundleAgent, ApiBundleMeta, ApiBundleResponse, BundleLock,
+    MAX_SCHEMA_VERSION,
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use clap::{Args, Subcommand};
+use futures::TryStreamExt;
+use http::{header, Request, StatusCode};
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::net::TcpListener;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, info, warn};
+
+/// Lock file name inside the registry directory
+const LOCK_FILE: &str = "bundle-versions.lock";
+
+/// Path prefix for release assets
+const ARTIFACTS_PREFIX: &str = "/artifacts/";
+
+/// Maximum request head size; registry requests are plain GETs
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Response body: in-memory JSON/text or an archive streamed from disk
+type RegistryBody = BoxBody<Bytes, std::io::Error>;
+
+/// Platforms the registry looks for in the directory
+const PLATFORMS: &[&str] = &[
+    "linux-x86_64",
+    "linux-aarch64",
+    "linux-x86_64-musl",
+    "linux-aarch64-musl",
+    "darwin-x86_64",
+    "darwin-aarch64",
+    "windows-x86_64",
+];
+
+/// Registry command arguments
+#[derive(Args, Debug)]
+pub struct RegistryArgs {
+    #[command(subcommand)]
+    pub command: RegistryCommand,
+}
+
+/// Registry subcommands
+#[derive(Subcommand, Debug)]
+pub enum RegistryCommand {
+    /// Serve the bundle API and agent archives from a local directory
+    Serve {
+        /// Directory with bundle-versions.lock and the agent release assets
+        #[arg(long)]
+        dir: PathBuf,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8480")]
+        listen: String,
+
+        /// Public base URL for download links (defaults to http://<Host header>)
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+}
+
+/// Run the registry command
+pub fn run_registry_command(args: RegistryArgs) -> Result<()> {
+    match args.command {
+        RegistryCommand::Serve {
+            dir,
+            listen,
+            base_url,
+        } => {
+            let registry = Registry::new(dir, base_url);
+            // Fail early on an unusable directory rather than on first request
+            let lock = registry.load_lock()?;
+            info!(
+                dir = %registry.dir.display(),
+                bundle = %lock.bundle.version,
+                agents = lock.agents.len(),
+                "Loaded bundle registry"
+            );
+
+            tokio::runtime::Runtime::new()?.block_on(async {
+                let listener = TcpListener::bind(&listen)
+                    .await
+                    .with_context(|| format!("Failed to bind registry server on {}", listen))?;
+                info!(address = %listen, "Bundle registry listening");
+                println!("Set ZENTINEL_API_URL=http://{}/v1/bundle/", listen);
+                serve(Arc::new(registry), listener).await;
+                Ok(())
+            })
+        }
+    }
+}
+
+/// A registry backed by a directory of release assets
+pub struct Registry {
+    /// Registry directory
+    dir: PathBuf,
+
+    /// Public base URL for download links
+    base_url: Option<String>,
+
+    /// SHA256 digests keyed by file name, with the size and mtime they were computed for
+    checksums: Mutex<HashMap<String, (u64, SystemTime, String)>>,
+}
+
+/// An HTTP response produced by [`Registry::route`]
+#[derive(Debug)]
+enum Response {
+    /// In-memory body
+    Body {
+        status: StatusCode,
+        content_type: &'static str,
+        body: Vec<u8>,
+    },
+
+    /// File streamed from disk
+    File {
+        path: PathBuf,
+        content_type: &'static str,
+        len: u64,
+    },
+}
+
+/// Entry in the `/v1/agents/` listing
+#[derive(Debug, Serialize)]
+struct ApiAgentEntry<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    agent: &'a ApiBundleAgent,
+}
+
+impl Registry {
+    /// Create a registry for a directory
+    pub fn new(dir: PathBuf, base_url: Option<String>) -> Self {
+        Self {
+            dir,
+            base_url: base_url.map(|url| url.trim_end_matches('/').to_string()),
+            checksums: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the registry's lock file
+    fn load_lock(&self) -> Result<BundleLock> {
+        let path = self.dir.join(LOCK_FILE);
+        BundleLock::from_file(&path)
+            .with_context(|| format!("Failed to load registry lock file {}", path.display()))
+    }
+
+    /// Build the `/v1/bundle/` response from the lock file and assets on disk
+    fn bundle_response(&self, lock: &BundleLock, base_url: &str) -> ApiBundleResponse {
+        let agents = lock
+            .agents()
+            .into_iter()
+            .map(|agent| {
+                let mut download_urls = HashMap::new();
+                let mut checksums = HashMap::new();
+
+                for &platform in PLATFORMS {
+                    let os = platform.split('-').next().unwrap_or(platform);
+                    let file = format!(
+                        "{}-{}-{}.{}",
+                        agent.binary_name,
+                        agent.version,
+                        platform,
+                        archive_extension(os)
+                    );
+                    let path = self.dir.join(&file);
+                    if !path.is_file() {
+                        continue;
+                    }
+
+                    match self.checksum(&file) {
+                        Ok(digest) => {
+                            checksums.insert(platform.to_string(), format!("sha256:{}", digest));
+                        }
+                        Err(e) => {
+                            warn!(file = %file, error = %e, "Failed to checksum artifact");
+                            continue;
+                        }
+                    }
+                    download_urls.insert(
+                        platform.to_string(),
+                        format!("{}{}{}", base_url, ARTIFACTS_PREFIX, file),
+                    );
+                }
+
+                let api_agent = ApiBundleAgent {
+                    version: agent.version,
+                    repository: agent.repository,
+                    binary_name: agent.binary_name,
+                    download_urls,
+                    checksums,
+                };
+                (agent.name, api_agent)
+            })
+            .collect();
+
+        ApiBundleResponse {
+            schema_version: MAX_SCHEMA_VERSION,
+            bundle: ApiBundleMeta {
+                version: lock.bundle.version.clone(),
+                generated_at: chrono::Utc::now().to_rfc3339(),
+                public_key: lock.bundle.public_key.clone(),
+            },
+            agents,
+        }
+    }
+
+    /// SHA256 of an artifact, cached until its size or mtime changes
+    fn checksum(&self, file: &str) -> std::io::Result<String> {
+        use sha2::{Digest, Sha256};
+
+        let path = self.dir.join(file);
+        let metadata = std::fs::metadata(&path)?;
+        let len = metadata.len();
+        let modified = metadata.modified()?;
+
+        if let Some((cached_len, cached_modified, digest)) = self.checksums.lock().get(file) {
+            if *cached_len == len && *cached_modified == modified {
+                return Ok(digest.clone());
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(&path)?, &mut hasher)?;
+        let digest = hex::encode(hasher.finalize());
+
+        self.checksums
+            .lock()
+            .insert(file.to_string(), (len, modified, digest.clone()));
+        Ok(digest)
+    }
+
+    /// Resolve a request path to a response
+    ///
+    /// `host` is the request's Host header, used for download links when no
+    /// base URL is configured.
+    fn route(&self, method: &str, path: &str, host: Option<&str>) -> Response {
+        if method != "GET" {
+            return text_response(StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed\n");
+        }
+
+        if let Some(file) = path.strip_prefix(ARTIFACTS_PREFIX) {
+            return self.artifact(file);
+        }
+
+        let lock = match self.load_lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                warn!(error = %format!("{:#}", e), "Registry lock file unavailable");
+                return json_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    &serde_json::json!({ "status": "error", "error": format!("{:#}", e) }),
+                );
+            }
+        };
+
+        let base_url = match (&self.base_url, host) {
+            (Some(url), _) => url.clone(),
+            (None, Some(host)) => format!("http://{}", host),
+            (None, None) => String::new(),
+        };
+
+        match path.trim_end_matches('/') {
+            "/v1/bundle" => json_response(StatusCode::OK, &self.bundle_response(&lock, &base_url)),
+            "/v1/agents" => {
+                let bundle = self.bundle_response(&lock, &base_url);
+                let mut agents: Vec<_> = bundle
+                    .agents
+                    .iter()
+                    .map(|(name, agent)| ApiAgentEntry { name, agent })
+                    .collect();
+                agents.sort_by(|a, b| a.name.cmp(b.name));
+                json_response(
+                    StatusCode::OK,
+                    &serde_json::json!({
+                        "schema_version": MAX_SCHEMA_VERSION,
+                        "agents": agents,
+                    }),
+                )
+            }
+            "/v1/health" => json_response(
+                StatusCode::OK,
+                &serde_json::json!({
+                    "status": "ok",
+                    "bundle_version": lock.bundle.version,
+                    "agents": lock.agents.len(),
+                }),
+            ),
+            other => match other.strip_prefix("/v1/agents/") {
+                Some(name) => {
+                    let bundle = self.bundle_response(&lock, &base_url);
+                    match bundle.agents.get_key_value(name) {
+                        Some((name, agent)) => {
+                            json_response(StatusCode::OK, &ApiAgentEntry { name, agent })
+                        }
+                        None => text_response(StatusCode::NOT_FOUND, "Not Found\n"),
+                    }
+                }
+                None => text_response(StatusCode::NOT_FOUND, "Not Found\n"),
+            },
+        }
+    }
+
+    /// Resolve a file name to a path inside the canonical registry directory
+    fn resolve(&self, file: &str) -> Option<PathBuf> {
+        let root = self.dir.canonicalize().ok()?;
+        let path = root.join(file).canonicalize().ok()?;
+        path.starts_with(&root).then_some(path)
+    }
+
+    /// Serve a release asset, generating `.sha256` files when not present
+    ///
+    /// `file` is the raw request path segment. It is not percent-decoded:
+    /// release asset names never contain `%`, so such requests are refused
+    /// along with anything that is not a plain file name. Files are resolved
+    /// against the canonical registry directory, so symlinks cannot lead out
+    /// of it either.
+    fn artifact(&self, file: &str) -> Response {
+        if file.is_empty() || file.contains(['/', '\\', '%']) || file.starts_with('.') {
+            return text_response(StatusCode::NOT_FOUND, "Not Found\n");
+        }
+
+        if let Some(path) = self.resolve(file) {
+            if let Some(metadata) = std::fs::metadata(&path).ok().filter(|m| m.is_file()) {
+                return Response::File {
+                    path,
+                    content_type: content_type(file),
+                    len: metadata.len(),
+                };
+            }
+        }
+
+        if let Some(archive) = file.strip_suffix(".sha256") {
+            if self.resolve(archive).is_some_and(|path| path.is_file()) {
+                return match self.checksum(archive) {
+                    Ok(digest) => Response::Body {
+                        status: StatusCode::OK,
+                        content_type: "text/plain; charset=utf-8",
+                        body: format!("{}  {}\n", digest, archive).into_bytes(),
+                    },
+                    Err(e) => {
+                        warn!(file = %archive, error = %e, "Failed to checksum artifact");
+                        text_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error\n")
+                    }
+                };
+            }
+        }
+
+        text_response(StatusCode::NOT_FOUND, "Not Found\n")
+    }
+}
+
+/// Accept connections until the process exits
+pub async fn serve(registry: Arc<Registry>, listener: TcpListener) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let registry = Arc::clone(&registry);
+                tokio::spawn(async move {
+                    let service =
+                        service_fn(move |request| handle_request(Arc::clone(&registry), request));
+                    if let Err(e) = http1::Builder::new()
+                        .max_buf_size(MAX_REQUEST_SIZE)
+                        .keep_alive(false)
+                        .title_case_headers(true)
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        debug!(peer = %peer, error = %e, "Registry connection error");
+                    }
+                });
+            }
+            Err(e) => {
+                warn!(error = %e, "Registry accept error");
+            }
+        }
+    }
+}
+
+/// Handle a single HTTP request on the registry server
+async fn handle_request(
+    registry: Arc<Registry>,
+    request: Request<Incoming>,
+) -> Result<http::Response<RegistryBody>, Infallible> {
+    let method = request.method().as_str().to_string();
+    let path = request.uri().path().to_string();
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    debug!(method = %method, path = %path, "Registry request");
+
+    // Routing reads the directory and hashes archives
+    let response =
+        tokio::task::spawn_blocking(move || registry.route(&method, &path, host.as_deref()))
+            .await
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Registry request handler failed");
+                text_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error\n")
+            });
+
+    let (status, content_type, len, body) = match response {
+        Response::Body {
+            status,
+            content_type,
+            body,
+        } => (status, content_type, body.len() as u64, full_body(body)),
+        Response::File {
+            path,
+            content_type,
+            len,
+        } => match tokio::fs::File::open(&path).await {
+            Ok(file) => {
+                let frames = ReaderStream::new(file).map_ok(Frame::data);
+                (
+                    StatusCode::OK,
+                    content_type,
+                    len,
+                    StreamBody::new(frames).boxed(),
+                )
+            }
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to open artifact");
+                let body = b"Internal Server Error\n".to_vec();
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "text/plain; charset=utf-8",
+                    body.len() as u64,
+                    full_body(body),
+                )
+            }
+        },
+    };
+
+    let mut response = http::Response::new(body);
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static(content_type),
+    );
+    headers.insert(header::CONTENT_LENGTH, header::HeaderValue::from(len));
+    Ok(response)
+}
+
+fn full_body(body: Vec<u8>) -> RegistryBody {
+    Full::new(Bytes::from(body))
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+fn text_response(status: StatusCode, body: &str) -> Response {
+    Response::Body {
+        status,
+        content_type: "text/plain; charset=utf-8",
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    match serde_json::to_vec_pretty(value) {
+        Ok(body) => Response::Body {
+            status,
+            content_type: "application/json",
+            body,
+        },
+        Err(e) => {
+            warn!(error = %e, "Failed to encode registry response");
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error\n")
+        }
+    }
+}
+
+/// Content type of a release asset
+fn content_type(file: &str) -> &'static str {
+    if file.ends_with(".tar.gz") || file.ends_with(".tgz") {
+        "application/gzip"
+    } else if file.ends_with(".zip") {
+        "application/zip"
+    } else if file.ends_with(".sha256") || file.ends_with(".minisig") {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    const LOCK: &str = r#"
+[bundle]
+version = "26.03_01"
+
+[agents]
+waf = "0.3.0"
+lua = "0.3.0"
+
+[repositories]
+waf = "zentinelproxy/zentinel-agent-waf"
+lua = "zentinelproxy/zentinel-agent-lua"
+"#;
+
+    fn test_registry() -> (tempfile::TempDir, Registry) {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join(LOCK_FILE), LOCK).unwrap();
+        std::fs::write(
+            temp.path()
+                .join("zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz"),
+            b"zentinel bundle test artifact\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path()
+                .join("zentinel-waf-agent-0.3.0-windows-x86_64.zip"),
+            b"zip",
+        )
+        .unwrap();
+        let registry = Registry::new(temp.path().to_path_buf(), None);
+        (temp, registry)
+    }
+
+    fn body(response: Response) -> (StatusCode, Vec<u8>) {
+        match response {
+            Response::Body { status, body, .. } => (status, body),
+            Response::File { path, .. } => (StatusCode::OK, std::fs::read(path).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_bundle_endpoint_round_trips_into_lock() {
+        let (_temp, registry) = test_registry();
+
+        let (status, json) = body(registry.route("GET", "/v1/bundle/", Some("mirror:8480")));
+        assert_eq!(status, StatusCode::OK);
+
+        let api: ApiBundleResponse = serde_json::from_slice(&json).unwrap();
+        assert_eq!(api.schema_version, MAX_SCHEMA_VERSION);
+        assert_eq!(api.bundle.version, "26.03_01");
+
+        let lock = BundleLock::from(api);
+        let waf = lock.agent("waf").unwrap();
+        assert_eq!(
+            waf.download_url("linux", "amd64"),
+            "http://mirror:8480/artifacts/zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz"
+        );
+        assert_eq!(
+            waf.expected_checksum("linux", "amd64").as_deref(),
+            Some("84ffff3f5db6b4b4cfde17d8bd912ad6ee38ff7ccf3e7f2cbf6d9d09c6655d24")
+        );
+        assert!(waf.download_url("windows", "amd64").ends_with(".zip"));
+
+        // Agents without local assets fall back to GitHub URLs
+        let lua = lock.agent("lua").unwrap();
+        assert!(lua.download_url("linux", "amd64").contains("github.com"));
+    }
+
+    #[test]
+    fn test_base_url_overrides_host() {
+        let (temp, _) = test_registry();
+        let registry = Registry::new(
+            temp.path().to_path_buf(),
+            Some("https://mirror.corp/zentinel/".to_string()),
+        );
+
+        let (_, json) = body(registry.route("GET", "/v1/bundle", Some("ignored")));
+        let api: ApiBundleResponse = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            api.agents["waf"].download_urls["linux-x86_64"],
+            "https://mirror.corp/zentinel/artifacts/zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_agents_and_health_endpoints() {
+        let (_temp, registry) = test_registry();
+
+        let (status, json) = body(registry.route("GET", "/v1/agents/", None));
+        assert_eq!(status, StatusCode::OK);
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["agents"][0]["name"], "lua");
+        assert_eq!(value["agents"][1]["name"], "waf");
+
+        let (status, json) = body(registry.route("GET", "/v1/agents/waf/", None));
+        assert_eq!(status, StatusCode::OK);
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["version"], "0.3.0");
+
+        let (status, _) = body(registry.route("GET", "/v1/agents/missing", None));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, json) = body(registry.route("GET", "/v1/health", None));
+        assert_eq!(status, StatusCode::OK);
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["status"], "ok");
+        assert_eq!(value["agents"], 2);
+    }
+
+    #[test]
+    fn test_artifacts() {
+        let (_temp, registry) = test_registry();
+        let archive = "zentinel-waf-agent-0.3.0-linux-x86_64.tar.gz";
+
+        let (status, bytes) = body(registry.route("GET", &format!("/artifacts/{}", archive), None));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(bytes, b"zentinel bundle test artifact\n");
+
+        // Checksum files are generated when not published
+        let (status, bytes) =
+            body(registry.route("GET", &format!("/artifacts/{}.sha256", archive), None));
+        assert_eq!(status, StatusCode::OK);
+        assert!(String::from_utf8(bytes)
+            .unwrap()
+            .starts_with("84ffff3f5db6b4b4cfde17d8bd912ad6ee38ff7ccf3e7f2cbf6d9d09c6655d24  "));
+
+        // No signature without a published .minisig
+        let (status, _) =
+            body(registry.route("GET", &format!("/artifacts/{}.minisig", archive), None));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_artifact_path_traversal_rejected() {
+        let (_temp, registry) = test_registry();
+
+        for path in [
+            "/artifacts/../bundle-versions.lock",
+            "/artifacts/..%2fbundle-versions.lock",
+            "/artifacts/sub/file.tar.gz",
+            "/artifacts/zentinel-waf-agent-0.3.0-linux-x86_64.tar%2egz",
+            "/artifacts/",
+        ] {
+            let (status, _) = body(registry.route("GET", path, None));
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_missing_lock_file_is_unavailable() {
+        let temp = tempfile::tempdir().unwrap();
+        let registry = Registry::new(temp.path().to_path_buf(), None);
+
+        let (status, _) = body(registry.route("GET", "/v1/health", None));
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (status, _) = body(registry.route("POST", "/v1/bundle/", None));
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn test_serves_over_http() {
+        let (_temp, registry) = test_registry();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Arc::new(registry), listener));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /v1/bundle/ HTTP/1.1\r\nHost: mirror:8480\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        let response = String::from_utf8(buf).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Content-Type: application/json"));
+        let (_, json) = response.split_once("\r\n\r\n").unwrap();
+        let api: ApiBundleResponse = serde_json::from_str(json).unwrap();
+        assert!(api.agents["waf"].download_urls["linux-x86_64"]
+            .starts_with("http://mirror:8480/artifacts/"));
+    }
+
+    #[tokio::test]
+    async fn test_request_split_across_segments() {
+        let (_temp, registry) = test_registry();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Arc::new(registry), listener));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.set_nodelay(true).unwrap();
+        client
+            .write_all(b"GET /artifacts/zentinel-waf-agent-0.3.0-linux")
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        client
+            .write_all(b"-x86_64.tar.gz HTTP/1.1\r\nHost: mirror\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        let response = String::from_utf8(buf).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Content-Type: application/gzip"));
+        assert!(response.ends_with("\r\n\r\nzentinel bundle test artifact\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_registry_rejected() {
+        let (temp, registry) = test_registry();
+        let outside = tempfile::tempdir().unwrap();
+        let secret = outside.path().join("secret.tar.gz");
+        std::fs::write(&secret, b"secret").unwrap();
+        std::os::unix::fs::symlink(&secret, temp.path().join("link.tar.gz")).unwrap();
+
+        let (status, _) = body(registry.route("GET", "/artifacts/link.tar.gz", None));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = body(registry.route("GET", "/artifacts/link.tar.gz.sha256", None));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
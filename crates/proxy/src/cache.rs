@@ -29,9 +29,11 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
 
+use crate::coalesce::RequestCoalescer;
 use crate::disk_cache::DiskCacheStorage;
 use crate::hybrid_cache::HybridCacheStorage;
-use zentinel_config::{CacheBackend, CacheStorageConfig};
+use zentinel_common::{MetricsSource, MetricsWriter};
+use zentinel_config::{CacheBackend, CacheCoalesceConfig, CacheStorageConfig};
 
 // ============================================================================
 // Cache Configuration
@@ -248,6 +250,8 @@ pub struct CacheConfig {
     pub exclude_extensions: Vec<String>,
     /// Path patterns to exclude from caching (pre-compiled from globs)
     pub exclude_paths: Vec<Regex>,
+    /// Coalescing of concurrent misses for the same cache key
+    pub coalesce: CacheCoalesceConfig,
 }
 
 impl Default for CacheConfig {
@@ -263,6 +267,7 @@ impl Default for CacheConfig {
             cacheable_status_codes: vec![200, 203, 204, 206, 300, 301, 308, 404, 410],
             exclude_extensions: Vec::new(),
             exclude_paths: Vec::new(),
+            coalesce: CacheCoalesceConfig::default(),
         }
     }
 }
@@ -276,6 +281,7 @@ pub struct HttpCacheStats {
     evictions: std::sync::atomic::AtomicU64,
     memory_hits: std::sync::atomic::AtomicU64,
     disk_hits: std::sync::atomic::AtomicU64,
+    coalesce_bypasses: std::sync::atomic::AtomicU64,
 }
 
 impl HttpCacheStats {
@@ -354,6 +360,66 @@ impl HttpCacheStats {
     pub fn disk_hits(&self) -> u64 {
         self.disk_hits.load(std::sync::atomic::Ordering::Relaxed)
     }
+
+    /// Record a request that went upstream because too many already
+    /// waited for the same key
+    pub fn record_coalesce_bypass(&self) {
+        self.coalesce_bypasses
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Get current coalescing bypass count
+    pub fn coalesce_bypasses(&self) -> u64 {
+        self.coalesce_bypasses
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl MetricsSource for HttpCacheStats {
+    fn collect(&self, writer: &mut MetricsWriter) {
+        writer.counter(
+            "zentinel_cache_hits_total",
+            "Total number of cache hits",
+            &[],
+            self.hits() as f64,
+        );
+        writer.counter(
+            "zentinel_cache_misses_total",
+            "Total number of cache misses",
+            &[],
+            self.misses() as f64,
+        );
+        writer.counter(
+            "zentinel_cache_stores_total",
+            "Total number of cache stores",
+            &[],
+            self.stores() as f64,
+        );
+        writer.gauge(
+            "zentinel_cache_hit_ratio",
+            "Cache hit ratio (0.0 to 1.0)",
+            &[],
+            self.hit_ratio(),
+        );
+        writer.counter(
+            "zentinel_cache_memory_hits_total",
+            "Cache hits from memory tier",
+            &[],
+            self.memory_hits() as f64,
+        );
+        writer.counter(
+            "zentinel_cache_disk_hits_total",
+            "Cache hits from disk tier",
+            &[],
+            self.disk_hits() as f64,
+        );
+        writer.counter(
+            "zentinel_cache_coalesce_bypasses_total",
+            "Cache misses fetched upstream because the coalescing queue was full",
+            &[],
+            self.coalesce_bypasses() as f64,
+        );
+    }
 }
 
 /// Purge entry with expiration tracking
@@ -383,6 +449,8 @@ pub struct CacheManager {
     purge_patterns: RwLock<Vec<PurgeEntry>>,
     /// Compiled regex patterns for efficient matching
     compiled_patterns: RwLock<Vec<(Regex, Instant)>>,
+    /// Requests waiting on the same upstream fetch
+    coalescer: RequestCoalescer,
 }
 
 impl CacheManager {
@@ -394,6 +462,7 @@ impl CacheManager {
             purged_keys: RwLock::new(HashMap::new()),
             purge_patterns: RwLock::new(Vec::new()),
             compiled_patterns: RwLock::new(Vec::new()),
+            coalescer: RequestCoalescer::new(),
         }
     }
 
@@ -420,6 +489,20 @@ impl CacheManager {
         self.route_configs.read().get(route_id).cloned()
     }
 
+    /// Get the coalescing configuration for a route, if it coalesces
+    pub fn coalesce_config(&self, route_id: &str) -> Option<CacheCoalesceConfig> {
+        self.route_configs
+            .read()
+            .get(route_id)
+            .filter(|c| c.coalesce.enabled)
+            .map(|c| c.coalesce.clone())
+    }
+
+    /// Requests waiting on the same upstream fetch
+    pub fn coalescer(&self) -> &RequestCoalescer {
+        &self.coalescer
+    }
+
     /// Check if caching is enabled for a route
     pub fn is_enabled(&self, route_id: &str) -> bool {
         self.route_configs
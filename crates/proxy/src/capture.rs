@@ -0,0 +1,715 @@
+//! Traffic capture for load tests and agent regression tests.
+//!
+//! A capture is started through the `capture` admin handler (or `zentinel
+//! capture`) for one route and a sample of its requests. Each sampled
+//! request is written as one JSON line to a file in `system.capture
+//! directory` once it completes: method, path, headers, up to
+//! `max-body-bytes` of the body and the response status. Secrets never
+//! reach the file:
+//!
+//! - values of sensitive headers become `[REDACTED]`
+//! - values of sensitive query parameters become `REDACTED`, in the path
+//!   and in form-encoded bodies
+//! - sensitive fields of JSON bodies are replaced at any depth; a JSON body
+//!   that cannot be parsed (e.g. because it was truncated) is dropped
+//!
+//! `zentinel replay` sends a capture file back to a proxy or upstream.
+//! Only one capture runs at a time and it ends when stopped, when
+//! `max-requests` is reached or when a reload removes `system.capture`.
+
+use base64::Engine;
+use chrono::Utc;
+use parking_lot::{Mutex, RwLock};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::LazyLock;
+use tracing::{info, warn};
+
+use zentinel_config::CaptureConfig;
+
+/// Requests written to capture files
+static CAPTURED_REQUESTS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_capture_requests_total",
+        "Requests written to traffic capture files",
+        &["route"]
+    )
+    .ok()
+});
+
+/// Replacement for redacted header values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Replacement for redacted query parameter values (kept URL-safe)
+const REDACTED_PARAM: &str = "REDACTED";
+
+/// One captured request, as written to the capture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedRequest {
+    /// When the request completed (RFC 3339)
+    pub timestamp: String,
+    pub route: String,
+    pub method: String,
+    /// Path and query, with sensitive parameters redacted
+    pub path: String,
+    /// Headers in request order, with sensitive values redacted
+    pub headers: Vec<(String, String)>,
+    /// Base64 request body, absent when empty or dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The body was longer than `max-body-bytes` or could not be redacted
+    #[serde(default)]
+    pub body_truncated: bool,
+    /// Status returned to the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl CapturedRequest {
+    /// Decoded request body
+    pub fn body_bytes(&self) -> Option<Vec<u8>> {
+        self.body
+            .as_deref()
+            .and_then(|body| base64::engine::general_purpose::STANDARD.decode(body).ok())
+    }
+}
+
+/// Errors starting a capture
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    #[error("Traffic capture is not configured (system.capture is not set)")]
+    Disabled,
+
+    #[error("A capture of route '{0}' is already running")]
+    AlreadyRunning(String),
+
+    #[error("Invalid sample '{0}', expected a percentage in (0, 100]")]
+    InvalidSample(String),
+
+    #[error("Invalid capture file name '{0}', expected a plain file name")]
+    InvalidFileName(String),
+
+    #[error("Failed to open capture file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// The running capture, for the `capture` admin handler
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureSessionStatus {
+    pub route: String,
+    pub sample_percent: f64,
+    pub file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<u64>,
+    pub captured: u64,
+    pub started_at: String,
+}
+
+/// Snapshot for the `capture` admin handler
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CaptureState {
+    /// `system.capture` is configured
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<CaptureSessionStatus>,
+}
+
+/// Redaction rules and limits derived from the config
+struct CaptureSettings {
+    directory: PathBuf,
+    max_body_bytes: usize,
+    redact_headers: HashSet<String>,
+    redact_query_params: HashSet<String>,
+    redact_json_fields: HashSet<String>,
+}
+
+impl CaptureSettings {
+    fn from_config(config: &CaptureConfig) -> Self {
+        let merge = |builtin: &[&str], extra: &[String]| -> HashSet<String> {
+            builtin
+                .iter()
+                .map(|s| s.to_string())
+                .chain(extra.iter().cloned())
+                .map(|s| s.to_ascii_lowercase())
+                .collect()
+        };
+
+        Self {
+            directory: config.directory.clone(),
+            max_body_bytes: config.max_body_bytes,
+            redact_headers: merge(CaptureConfig::REDACTED_HEADERS, &config.redact_headers),
+            redact_query_params: merge(
+                CaptureConfig::REDACTED_QUERY_PARAMS,
+                &config.redact_query_params,
+            ),
+            redact_json_fields: merge(
+                CaptureConfig::REDACTED_JSON_FIELDS,
+                &config.redact_json_fields,
+            ),
+        }
+    }
+
+    fn redact_query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_sensitive_param(name) => {
+                    format!("{}={}", name, REDACTED_PARAM)
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    fn is_sensitive_param(&self, name: &str) -> bool {
+        let name = urlencoding::decode(name)
+            .map(|n| n.to_ascii_lowercase())
+            .unwrap_or_else(|_| name.to_ascii_lowercase());
+        self.redact_query_params.contains(&name)
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.redact_json_fields.contains(&key.to_ascii_lowercase()) {
+                        *value = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.redact_json(item))
+            }
+            _ => {}
+        }
+    }
+
+    /// Redacted body, or `None` when it must be dropped
+    fn redact_body(&self, content_type: Option<&str>, body: Vec<u8>) -> Option<Vec<u8>> {
+        let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+        if content_type.contains("json") {
+            let mut value: serde_json::Value = serde_json::from_slice(&body).ok()?;
+            self.redact_json(&mut value);
+            serde_json::to_vec(&value).ok()
+        } else if content_type.starts_with("application/x-www-form-urlencoded") {
+            let form = String::from_utf8(body).ok()?;
+            Some(self.redact_query(&form).into_bytes())
+        } else {
+            Some(body)
+        }
+    }
+}
+
+/// A capture in progress
+struct CaptureSession {
+    route: String,
+    sample_percent: f64,
+    file_path: PathBuf,
+    writer: BufWriter<File>,
+    max_requests: Option<u64>,
+    /// Requests selected so far, including ones still in flight
+    selected: u64,
+    captured: u64,
+    started_at: String,
+    /// Distinguishes this session from later ones with the same route
+    generation: u64,
+}
+
+/// A sampled request being captured
+pub struct PendingCapture {
+    record: CapturedRequest,
+    content_type: Option<String>,
+    body: Vec<u8>,
+    limit: usize,
+    generation: u64,
+}
+
+impl PendingCapture {
+    /// Keep a request body chunk, up to `max-body-bytes`
+    pub fn push_body(&mut self, chunk: &[u8]) {
+        let room = self.limit.saturating_sub(self.body.len());
+        if chunk.len() > room {
+            self.record.body_truncated = true;
+        }
+        self.body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+/// Capture settings and the running capture
+pub struct CaptureManager {
+    settings: RwLock<Option<CaptureSettings>>,
+    session: Mutex<Option<CaptureSession>>,
+    /// Fast path: false while no capture runs
+    active: AtomicBool,
+    generation: AtomicU64,
+}
+
+impl CaptureManager {
+    /// Create the manager; `None` leaves capture disabled
+    pub fn new(config: Option<&CaptureConfig>) -> Self {
+        Self {
+            settings: RwLock::new(config.map(CaptureSettings::from_config)),
+            session: Mutex::new(None),
+            active: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Apply a new config. A running capture keeps going unless capture is
+    /// removed from the config.
+    pub fn reload(&self, config: Option<&CaptureConfig>) {
+        *self.settings.write() = config.map(CaptureSettings::from_config);
+        if config.is_none() && self.stop().is_some() {
+            info!("Traffic capture stopped: system.capture was removed");
+        }
+    }
+
+    /// Start capturing `sample_percent` of the requests to `route` into
+    /// `file_name` in the capture directory. Records are appended if the
+    /// file exists.
+    pub fn start(
+        &self,
+        route: &str,
+        sample_percent: f64,
+        file_name: &str,
+        max_requests: Option<u64>,
+    ) -> Result<PathBuf, CaptureError> {
+        if !(sample_percent > 0.0 && sample_percent <= 100.0) {
+            return Err(CaptureError::InvalidSample(sample_percent.to_string()));
+        }
+        if !is_plain_file_name(file_name) {
+            return Err(CaptureError::InvalidFileName(file_name.to_string()));
+        }
+        let directory = match *self.settings.read() {
+            Some(ref settings) => settings.directory.clone(),
+            None => return Err(CaptureError::Disabled),
+        };
+
+        let mut session = self.session.lock();
+        if let Some(ref running) = *session {
+            return Err(CaptureError::AlreadyRunning(running.route.clone()));
+        }
+
+        let file_path = directory.join(file_name);
+        let file = std::fs::create_dir_all(&directory)
+            .and_then(|_| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&file_path)
+            })
+            .map_err(|source| CaptureError::Io {
+                path: file_path.clone(),
+                source,
+            })?;
+
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        *session = Some(CaptureSession {
+            route: route.to_string(),
+            sample_percent,
+            file_path: file_path.clone(),
+            writer: BufWriter::new(file),
+            max_requests,
+            selected: 0,
+            captured: 0,
+            started_at: Utc::now().to_rfc3339(),
+            generation,
+        });
+        self.active.store(true, Ordering::Release);
+
+        info!(
+            route = route,
+            sample_percent = sample_percent,
+            file = %file_path.display(),
+            max_requests = ?max_requests,
+            "Traffic capture started"
+        );
+        Ok(file_path)
+    }
+
+    /// Stop the running capture, returning its final status
+    pub fn stop(&self) -> Option<CaptureSessionStatus> {
+        let mut session = self.session.lock();
+        let mut stopped = session.take()?;
+        self.active.store(false, Ordering::Release);
+        if let Err(e) = stopped.writer.flush() {
+            warn!(error = %e, "Failed to flush capture file");
+        }
+        info!(
+            route = %stopped.route,
+            captured = stopped.captured,
+            file = %stopped.file_path.display(),
+            "Traffic capture stopped"
+        );
+        Some(session_status(&stopped))
+    }
+
+    /// Whether capture is configured and the running capture, if any
+    pub fn state(&self) -> CaptureState {
+        CaptureState {
+            enabled: self.settings.read().is_some(),
+            session: self.session.lock().as_ref().map(session_status),
+        }
+    }
+
+    /// Start capturing this request if it belongs to the running capture
+    /// and is sampled
+    pub fn begin(
+        &self,
+        route_id: Option<&str>,
+        req: &http::request::Parts,
+    ) -> Option<PendingCapture> {
+        if !self.active.load(Ordering::Acquire) {
+            return None;
+        }
+        let route_id = route_id?;
+
+        let generation = {
+            let mut session = self.session.lock();
+            let session = session.as_mut()?;
+            if session.route != route_id {
+                return None;
+            }
+            if session
+                .max_requests
+                .is_some_and(|max| session.selected >= max)
+            {
+                return None;
+            }
+            if session.sample_percent < 100.0
+                && rand::rng().random_range(0.0..100.0) >= session.sample_percent
+            {
+                return None;
+            }
+            session.selected += 1;
+            session.generation
+        };
+
+        let settings = self.settings.read();
+        let settings = settings.as_ref()?;
+
+        let path = match req.uri.query() {
+            Some(query) => format!("{}?{}", req.uri.path(), settings.redact_query(query)),
+            None => req.uri.path().to_string(),
+        };
+        let headers = req
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if settings.redact_headers.contains(name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+        let content_type = req
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        Some(PendingCapture {
+            record: CapturedRequest {
+                timestamp: String::new(),
+                route: route_id.to_string(),
+                method: req.method.to_string(),
+                path,
+                headers,
+                body: None,
+                body_truncated: false,
+                status: None,
+            },
+            content_type,
+            body: Vec::new(),
+            limit: settings.max_body_bytes,
+            generation,
+        })
+    }
+
+    /// Redact the body and write the record. Dropped if the capture it
+    /// belongs to has ended.
+    pub fn finish(&self, pending: PendingCapture, status: Option<u16>) {
+        let PendingCapture {
+            mut record,
+            content_type,
+            body,
+            generation,
+            ..
+        } = pending;
+
+        if !body.is_empty() {
+            let redacted = match *self.settings.read() {
+                Some(ref settings) if !record.body_truncated => {
+                    settings.redact_body(content_type.as_deref(), body)
+                }
+                // A truncated body can only be kept when it needs no redaction
+                Some(ref settings) => {
+                    let content_type = content_type
+                        .as_deref()
+                        .unwrap_or_default()
+                        .to_ascii_lowercase();
+                    let structured = content_type.contains("json")
+                        || content_type.starts_with("application/x-www-form-urlencoded");
+                    if structured {
+                        None
+                    } else {
+                        settings.redact_body(Some(&content_type), body)
+                    }
+                }
+                None => return,
+            };
+            match redacted {
+                Some(body) => {
+                    record.body = Some(base64::engine::general_purpose::STANDARD.encode(body))
+                }
+                None => record.body_truncated = true,
+            }
+        }
+        record.status = status;
+        record.timestamp = Utc::now().to_rfc3339();
+
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize captured request");
+                return;
+            }
+        };
+
+        let mut guard = self.session.lock();
+        let Some(session) = guard.as_mut().filter(|s| s.generation == generation) else {
+            return;
+        };
+        let written = writeln!(session.writer, "{}", line).and_then(|_| session.writer.flush());
+        if let Err(e) = written {
+            warn!(
+                file = %session.file_path.display(),
+                error = %e,
+                "Failed to write captured request, stopping capture"
+            );
+            *guard = None;
+            self.active.store(false, Ordering::Release);
+            return;
+        }
+
+        session.captured += 1;
+        if let Some(counter) = CAPTURED_REQUESTS.as_ref() {
+            counter.with_label_values(&[record.route.as_str()]).inc();
+        }
+        if session
+            .max_requests
+            .is_some_and(|max| session.captured >= max)
+        {
+            info!(
+                route = %session.route,
+                captured = session.captured,
+                file = %session.file_path.display(),
+                "Traffic capture complete"
+            );
+            *guard = None;
+            self.active.store(false, Ordering::Release);
+        }
+    }
+}
+
+fn session_status(session: &CaptureSession) -> CaptureSessionStatus {
+    CaptureSessionStatus {
+        route: session.route.clone(),
+        sample_percent: session.sample_percent,
+        file: session.file_path.clone(),
+        max_requests: session.max_requests,
+        captured: session.captured,
+        started_at: session.started_at.clone(),
+    }
+}
+
+/// Parse a sample rate: `1%`, `0.5%` or a bare percentage like `25`
+pub fn parse_sample(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let percent: f64 = value
+        .strip_suffix('%')
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .ok()?;
+    (percent > 0.0 && percent <= 100.0).then_some(percent)
+}
+
+/// A file name without directory components
+fn is_plain_file_name(name: &str) -> bool {
+    let path = Path::new(name);
+    !name.is_empty()
+        && path.file_name().is_some_and(|file| file == name)
+        && name != "."
+        && name != ".."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(uri: &str, headers: &[(&str, &str)]) -> http::request::Parts {
+        let mut builder = http::Request::builder().method("POST").uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    fn manager(dir: &Path) -> CaptureManager {
+        CaptureManager::new(Some(&CaptureConfig {
+            directory: dir.to_path_buf(),
+            max_body_bytes: 64,
+            redact_headers: vec!["X-Session".to_string()],
+            redact_query_params: vec![],
+            redact_json_fields: vec!["ssn".to_string()],
+        }))
+    }
+
+    fn read_records(path: &Path) -> Vec<CapturedRequest> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn captures_redacted_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = manager(dir.path());
+        let path = capture.start("api", 100.0, "api.jsonl", None).unwrap();
+
+        let req = parts(
+            "/login?user=alice&token=abc",
+            &[
+                ("authorization", "Bearer secret"),
+                ("x-session", "s1"),
+                ("content-type", "application/json"),
+                ("accept", "*/*"),
+            ],
+        );
+        assert!(capture.begin(Some("other"), &req).is_none());
+        let mut pending = capture.begin(Some("api"), &req).unwrap();
+        pending.push_body(br#"{"user":"alice","#);
+        pending.push_body(br#""profile":{"ssn":"123","password":"pw"}}"#);
+        capture.finish(pending, Some(200));
+
+        let records = read_records(&path);
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.path, "/login?user=alice&token=REDACTED");
+        assert_eq!(record.status, Some(200));
+        let header = |name: &str| {
+            record
+                .headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(header("authorization"), Some(REDACTED));
+        assert_eq!(header("x-session"), Some(REDACTED));
+        assert_eq!(header("accept"), Some("*/*"));
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&record.body_bytes().unwrap()).unwrap();
+        assert_eq!(body["user"], "alice");
+        assert_eq!(body["profile"]["ssn"], REDACTED);
+        assert_eq!(body["profile"]["password"], REDACTED);
+    }
+
+    #[test]
+    fn truncated_json_bodies_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = manager(dir.path());
+        let path = capture.start("api", 100.0, "api.jsonl", None).unwrap();
+
+        let json = parts("/", &[("content-type", "application/json")]);
+        let mut pending = capture.begin(Some("api"), &json).unwrap();
+        pending.push_body(format!(r#"{{"password":"{}"}}"#, "x".repeat(100)).as_bytes());
+        capture.finish(pending, Some(201));
+
+        let binary = parts("/", &[("content-type", "application/octet-stream")]);
+        let mut pending = capture.begin(Some("api"), &binary).unwrap();
+        pending.push_body(&[7u8; 100]);
+        capture.finish(pending, Some(201));
+
+        let records = read_records(&path);
+        assert!(records[0].body.is_none());
+        assert!(records[0].body_truncated);
+        assert_eq!(records[1].body_bytes().unwrap(), vec![7u8; 64]);
+        assert!(records[1].body_truncated);
+    }
+
+    #[test]
+    fn capture_stops_after_max_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = manager(dir.path());
+        capture.start("api", 100.0, "api.jsonl", Some(2)).unwrap();
+        assert!(matches!(
+            capture.start("api", 100.0, "other.jsonl", None),
+            Err(CaptureError::AlreadyRunning(_))
+        ));
+
+        let req = parts("/", &[]);
+        let first = capture.begin(Some("api"), &req).unwrap();
+        let second = capture.begin(Some("api"), &req).unwrap();
+        assert!(capture.begin(Some("api"), &req).is_none());
+        capture.finish(first, Some(200));
+        assert_eq!(capture.state().session.unwrap().captured, 1);
+        capture.finish(second, Some(200));
+        assert!(capture.state().session.is_none());
+
+        // Requests finishing after a restart belong to the old session
+        let path = capture.start("api", 100.0, "api.jsonl", None).unwrap();
+        let pending = capture.begin(Some("api"), &req).unwrap();
+        capture.stop();
+        capture.finish(pending, Some(200));
+        assert_eq!(read_records(&path).len(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_starts() {
+        let dir = tempfile::tempdir().unwrap();
+        let capture = manager(dir.path());
+        for name in ["../escape.jsonl", "a/b.jsonl", "", ".."] {
+            assert!(matches!(
+                capture.start("api", 1.0, name, None),
+                Err(CaptureError::InvalidFileName(_))
+            ));
+        }
+        assert!(matches!(
+            capture.start("api", 0.0, "a.jsonl", None),
+            Err(CaptureError::InvalidSample(_))
+        ));
+        assert!(matches!(
+            CaptureManager::new(None).start("api", 1.0, "a.jsonl", None),
+            Err(CaptureError::Disabled)
+        ));
+    }
+
+    #[test]
+    fn parses_sample_rates() {
+        assert_eq!(parse_sample("1%"), Some(1.0));
+        assert_eq!(parse_sample("0.5 %"), Some(0.5));
+        assert_eq!(parse_sample("100"), Some(100.0));
+        assert_eq!(parse_sample("0%"), None);
+        assert_eq!(parse_sample("150%"), None);
+        assert_eq!(parse_sample("all"), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Signed-cookie challenge.
+//!
+//! Redirects the client back to the requested URL with a signed clearance
+//! cookie. Clients that keep cookies pass on the follow-up request; clients
+//! that do not are challenged again.
+
+use bytes::Bytes;
+use http::header::{CACHE_CONTROL, LOCATION, SET_COOKIE};
+use http::{Response, StatusCode};
+use http_body_util::Full;
+
+use super::{
+    set_cookie, unix_now, ChallengeProvider, ChallengeRequest, ChallengeSigner, Verification,
+};
+
+/// Challenge type name used in signatures.
+const CHALLENGE_TYPE: &str = "cookie";
+
+/// Signed-cookie challenge (`cookie`).
+///
+/// Cookie value: `{expires}.{signature}`, signed over the client IP and
+/// expiry.
+pub struct CookieChallenge;
+
+impl ChallengeProvider for CookieChallenge {
+    fn verify(&self, request: &ChallengeRequest<'_>, signer: &ChallengeSigner) -> Verification {
+        let Some(value) = request.cookie(&request.config.cookie_name) else {
+            return Verification::Missing;
+        };
+        let Some((expires, signature)) = value.split_once('.') else {
+            return Verification::Failed;
+        };
+        let Ok(expires_at) = expires.parse::<u64>() else {
+            return Verification::Failed;
+        };
+
+        if expires_at > unix_now()
+            && signer.verify(&[CHALLENGE_TYPE, request.client_ip, expires], signature)
+        {
+            Verification::Passed
+        } else {
+            Verification::Failed
+        }
+    }
+
+    fn issue(
+        &self,
+        request: &ChallengeRequest<'_>,
+        signer: &ChallengeSigner,
+    ) -> Response<Full<Bytes>> {
+        let ttl = request.config.ttl_secs;
+        let expires = (unix_now() + ttl).to_string();
+        let signature = signer.sign(&[CHALLENGE_TYPE, request.client_ip, &expires]);
+        let cookie = set_cookie(
+            &request.config.cookie_name,
+            &format!("{}.{}", expires, signature),
+            ttl,
+        );
+
+        Response::builder()
+            .status(StatusCode::TEMPORARY_REDIRECT)
+            .header(LOCATION, request.uri)
+            .header(SET_COOKIE, cookie)
+            .header(CACHE_CONTROL, "no-store")
+            .body(Full::new(Bytes::new()))
+            .unwrap_or_else(|_| {
+                // Only reachable with a URI that is not a valid header value
+                let mut response = Response::new(Full::new(Bytes::new()));
+                *response.status_mut() = StatusCode::FORBIDDEN;
+                response
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::tests::request;
+    use std::collections::HashMap;
+    use zentinel_config::ChallengeConfig;
+
+    /// Extract the `name=value` pair from a Set-Cookie header.
+    fn cookie_pair(response: &Response<Full<Bytes>>) -> String {
+        let header = response.headers()[SET_COOKIE].to_str().unwrap();
+        header.split(';').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn issued_cookie_verifies() {
+        let signer = ChallengeSigner::random();
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let response = CookieChallenge.issue(&request(None, &config, &params), &signer);
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[LOCATION], "/login?next=%2F");
+
+        let cookies = cookie_pair(&response);
+        let verification =
+            CookieChallenge.verify(&request(Some(&cookies), &config, &params), &signer);
+        assert_eq!(verification, Verification::Passed);
+    }
+
+    #[test]
+    fn missing_cookie() {
+        let signer = ChallengeSigner::random();
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let verification =
+            CookieChallenge.verify(&request(Some("other=1"), &config, &params), &signer);
+        assert_eq!(verification, Verification::Missing);
+    }
+
+    #[test]
+    fn cookie_is_bound_to_client_ip() {
+        let signer = ChallengeSigner::random();
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let response = CookieChallenge.issue(&request(None, &config, &params), &signer);
+        let cookies = cookie_pair(&response);
+
+        let mut other_client = request(Some(&cookies), &config, &params);
+        other_client.client_ip = "198.51.100.1";
+        assert_eq!(
+            CookieChallenge.verify(&other_client, &signer),
+            Verification::Failed
+        );
+    }
+
+    #[test]
+    fn expired_or_forged_cookie_fails() {
+        let signer = ChallengeSigner::random();
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let expired = format!(
+            "zentinel_clearance=100.{}",
+            signer.sign(&[CHALLENGE_TYPE, "203.0.113.7", "100"])
+        );
+        let verification =
+            CookieChallenge.verify(&request(Some(&expired), &config, &params), &signer);
+        assert_eq!(verification, Verification::Failed);
+
+        let forged = format!("zentinel_clearance={}.abcdef", unix_now() + 60);
+        let verification =
+            CookieChallenge.verify(&request(Some(&forged), &config, &params), &signer);
+        assert_eq!(verification, Verification::Failed);
+    }
+}
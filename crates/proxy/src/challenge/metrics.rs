@@ -0,0 +1,81 @@
+//! Challenge metrics for observability.
+//!
+//! Provides Prometheus metrics for:
+//! - Challenges issued by route and challenge type
+//! - Solution verifications by route, challenge type, and result
+
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::sync::Arc;
+
+use super::Verification;
+
+/// Global challenge metrics instance.
+static CHALLENGE_METRICS: OnceCell<Arc<ChallengeMetrics>> = OnceCell::new();
+
+/// Get the global challenge metrics, if initialized.
+pub fn get_challenge_metrics() -> Option<Arc<ChallengeMetrics>> {
+    CHALLENGE_METRICS.get().cloned()
+}
+
+/// Initialize the global challenge metrics.
+/// Returns Ok if already initialized or initialization succeeds.
+pub fn init_challenge_metrics() -> Result<Arc<ChallengeMetrics>> {
+    if let Some(metrics) = CHALLENGE_METRICS.get() {
+        return Ok(metrics.clone());
+    }
+
+    let metrics = Arc::new(ChallengeMetrics::new()?);
+    let _ = CHALLENGE_METRICS.set(metrics.clone());
+    Ok(metrics)
+}
+
+/// Challenge metrics collector.
+pub struct ChallengeMetrics {
+    /// Challenges served to clients
+    /// Labels: route, challenge_type
+    issued: IntCounterVec,
+
+    /// Presented solutions checked
+    /// Labels: route, challenge_type, result
+    verifications: IntCounterVec,
+}
+
+impl ChallengeMetrics {
+    /// Create new challenge metrics and register with Prometheus.
+    pub fn new() -> Result<Self> {
+        let issued = register_int_counter_vec!(
+            "zentinel_challenges_issued_total",
+            "Total number of challenges served to clients",
+            &["route", "challenge_type"]
+        )
+        .context("Failed to register challenges_issued metric")?;
+
+        let verifications = register_int_counter_vec!(
+            "zentinel_challenge_verifications_total",
+            "Total number of challenge solutions verified",
+            &["route", "challenge_type", "result"]
+        )
+        .context("Failed to register challenge_verifications metric")?;
+
+        Ok(Self {
+            issued,
+            verifications,
+        })
+    }
+
+    /// Record a challenge served to a client.
+    pub fn record_issued(&self, route: &str, challenge_type: &str) {
+        self.issued
+            .with_label_values(&[route, challenge_type])
+            .inc();
+    }
+
+    /// Record the result of verifying a presented solution.
+    pub fn record_verification(&self, route: &str, challenge_type: &str, result: Verification) {
+        self.verifications
+            .with_label_values(&[route, challenge_type, result.as_str()])
+            .inc();
+    }
+}
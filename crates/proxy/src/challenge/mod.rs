@@ -0,0 +1,317 @@
+//! Client challenges for agent `Challenge` decisions.
+//!
+//! When an agent answers with `Decision::Challenge`, the proxy serves the
+//! challenge itself instead of forwarding the request. Solutions come back
+//! as signed cookies bound to the client IP; while a solution is valid the
+//! challenge is considered passed, the route's agents after the challenger
+//! decide, and the request is forwarded upstream unless one of them objects.
+//!
+//! # Built-in challenge types
+//!
+//! - `cookie`: redirects back to the same URL with a signed clearance cookie,
+//!   stopping clients that do not keep cookies
+//! - `js-pow`: serves a page that solves a SHA-256 proof of work in
+//!   JavaScript and stores the solution in a cookie
+//!
+//! Further types implement [`ChallengeProvider`] and are added with
+//! [`ChallengeHandler::register`].
+//!
+//! Solutions are signed with a per-process key, so they do not survive a
+//! restart and are not shared between proxy instances.
+
+mod cookie;
+mod metrics;
+mod pow;
+
+pub use cookie::CookieChallenge;
+pub use metrics::{get_challenge_metrics, init_challenge_metrics, ChallengeMetrics};
+pub use pow::ProofOfWorkChallenge;
+
+use bytes::Bytes;
+use hmac::{Hmac, KeyInit, Mac};
+use http::Response;
+use http_body_util::Full;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::{debug, warn};
+
+use zentinel_config::ChallengeConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Request details a challenge provider needs to issue or verify a challenge.
+pub struct ChallengeRequest<'a> {
+    /// Client IP address (solutions are bound to it)
+    pub client_ip: &'a str,
+    /// Request path and query, used to send the client back after solving
+    pub uri: &'a str,
+    /// Raw `Cookie` header, if any
+    pub cookies: Option<&'a str>,
+    /// Route challenge settings
+    pub config: &'a ChallengeConfig,
+    /// Parameters supplied by the agent with the challenge decision
+    pub params: &'a HashMap<String, String>,
+}
+
+impl ChallengeRequest<'_> {
+    /// Get the value of a request cookie by name.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies?.split(';').find_map(|cookie| {
+            let (key, value) = cookie.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
+    }
+}
+
+/// Result of checking a request for a solved challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// No solution was presented
+    Missing,
+    /// A valid solution was presented
+    Passed,
+    /// A solution was presented but is invalid or expired
+    Failed,
+}
+
+impl Verification {
+    /// Metrics label for this result.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verification::Missing => "missing",
+            Verification::Passed => "passed",
+            Verification::Failed => "failed",
+        }
+    }
+}
+
+/// A challenge type that can be served to clients.
+pub trait ChallengeProvider: Send + Sync {
+    /// Check whether the request carries a solution to this challenge.
+    fn verify(&self, request: &ChallengeRequest<'_>, signer: &ChallengeSigner) -> Verification;
+
+    /// Build the challenge response for a request without a valid solution.
+    fn issue(
+        &self,
+        request: &ChallengeRequest<'_>,
+        signer: &ChallengeSigner,
+    ) -> Response<Full<Bytes>>;
+}
+
+/// Outcome of handling an agent challenge decision.
+pub enum ChallengeOutcome {
+    /// The request carries a valid solution and may be forwarded
+    Passed,
+    /// Send this challenge response to the client
+    Issued(Response<Full<Bytes>>),
+    /// No provider is registered for the requested challenge type
+    Unsupported,
+}
+
+/// HMAC signer for challenge tokens and clearance cookies.
+pub struct ChallengeSigner {
+    key: [u8; 32],
+}
+
+impl ChallengeSigner {
+    /// Create a signer with a random per-process key.
+    pub fn random() -> Self {
+        use rand::Rng;
+
+        let mut key = [0u8; 32];
+        rand::rng().fill_bytes(&mut key);
+        Self { key }
+    }
+
+    /// Sign the given fields with HMAC-SHA256 (hex encoded).
+    pub fn sign(&self, fields: &[&str]) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC key length is valid");
+        for field in fields {
+            mac.update(field.as_bytes());
+            // Separator so ("ab", "c") and ("a", "bc") sign differently
+            mac.update(b"\0");
+        }
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Verify a signature produced by [`ChallengeSigner::sign`].
+    pub fn verify(&self, fields: &[&str], signature: &str) -> bool {
+        self.sign(fields).as_bytes().ct_eq(signature.as_bytes()).into()
+    }
+}
+
+/// Serves and verifies client challenges by type.
+pub struct ChallengeHandler {
+    providers: HashMap<String, Arc<dyn ChallengeProvider>>,
+    signer: ChallengeSigner,
+}
+
+impl ChallengeHandler {
+    /// Create a handler with the built-in `cookie` and `js-pow` challenges.
+    pub fn new() -> Self {
+        let mut handler = Self {
+            providers: HashMap::new(),
+            signer: ChallengeSigner::random(),
+        };
+        handler.register("cookie", Arc::new(CookieChallenge));
+        handler.register("js-pow", Arc::new(ProofOfWorkChallenge));
+        handler
+    }
+
+    /// Register a provider for a challenge type, replacing any existing one.
+    pub fn register(
+        &mut self,
+        challenge_type: impl Into<String>,
+        provider: Arc<dyn ChallengeProvider>,
+    ) {
+        self.providers.insert(challenge_type.into(), provider);
+    }
+
+    /// Handle an agent challenge decision for a request on `route_id`.
+    pub fn handle(
+        &self,
+        challenge_type: &str,
+        request: &ChallengeRequest<'_>,
+        route_id: &str,
+    ) -> ChallengeOutcome {
+        let Some(provider) = self.providers.get(challenge_type) else {
+            warn!(
+                challenge_type = %challenge_type,
+                route_id = %route_id,
+                "Agent requested unsupported challenge type"
+            );
+            return ChallengeOutcome::Unsupported;
+        };
+
+        let metrics = get_challenge_metrics();
+        let verification = provider.verify(request, &self.signer);
+        if verification != Verification::Missing {
+            if let Some(ref metrics) = metrics {
+                metrics.record_verification(route_id, challenge_type, verification);
+            }
+        }
+
+        if verification == Verification::Passed {
+            debug!(
+                challenge_type = %challenge_type,
+                route_id = %route_id,
+                client_ip = %request.client_ip,
+                "Challenge solution verified"
+            );
+            return ChallengeOutcome::Passed;
+        }
+
+        debug!(
+            challenge_type = %challenge_type,
+            route_id = %route_id,
+            client_ip = %request.client_ip,
+            verification = verification.as_str(),
+            "Issuing challenge"
+        );
+        if let Some(ref metrics) = metrics {
+            metrics.record_issued(route_id, challenge_type);
+        }
+        ChallengeOutcome::Issued(provider.issue(request, &self.signer))
+    }
+}
+
+impl Default for ChallengeHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Current Unix time in seconds.
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Build a `Set-Cookie` value for a challenge cookie.
+fn set_cookie(name: &str, value: &str, max_age: u64) -> String {
+    format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        name, value, max_age
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn request<'a>(
+        cookies: Option<&'a str>,
+        config: &'a ChallengeConfig,
+        params: &'a HashMap<String, String>,
+    ) -> ChallengeRequest<'a> {
+        ChallengeRequest {
+            client_ip: "203.0.113.7",
+            uri: "/login?next=%2F",
+            cookies,
+            config,
+            params,
+        }
+    }
+
+    #[test]
+    fn signer_round_trip() {
+        let signer = ChallengeSigner::random();
+        let signature = signer.sign(&["cookie", "203.0.113.7", "1700000000"]);
+
+        assert!(signer.verify(&["cookie", "203.0.113.7", "1700000000"], &signature));
+        assert!(!signer.verify(&["cookie", "203.0.113.8", "1700000000"], &signature));
+        assert!(!signer.verify(&["cookie", "203.0.113.7", "1700000000"], "deadbeef"));
+    }
+
+    #[test]
+    fn cookie_lookup() {
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+        let req = request(
+            Some("a=1; zentinel_clearance=abc.def; b=2"),
+            &config,
+            &params,
+        );
+
+        assert_eq!(req.cookie("zentinel_clearance"), Some("abc.def"));
+        assert_eq!(req.cookie("b"), Some("2"));
+        assert_eq!(req.cookie("missing"), None);
+    }
+
+    #[test]
+    fn unknown_challenge_type_is_unsupported() {
+        let handler = ChallengeHandler::new();
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let outcome = handler.handle("captcha", &request(None, &config, &params), "r");
+        assert!(matches!(outcome, ChallengeOutcome::Unsupported));
+    }
+
+    struct AlwaysPass;
+
+    impl ChallengeProvider for AlwaysPass {
+        fn verify(&self, _: &ChallengeRequest<'_>, _: &ChallengeSigner) -> Verification {
+            Verification::Passed
+        }
+
+        fn issue(&self, _: &ChallengeRequest<'_>, _: &ChallengeSigner) -> Response<Full<Bytes>> {
+            unreachable!("always passes")
+        }
+    }
+
+    #[test]
+    fn custom_providers_can_be_registered() {
+        let mut handler = ChallengeHandler::new();
+        handler.register("captcha", Arc::new(AlwaysPass));
+        let config = ChallengeConfig::default();
+        let params = HashMap::new();
+
+        let outcome = handler.handle("captcha", &request(None, &config, &params), "r");
+        assert!(matches!(outcome, ChallengeOutcome::Passed));
+    }
+}
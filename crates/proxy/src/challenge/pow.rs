@@ -0,0 +1,254 @@
+//! JavaScript proof-of-work challenge.
+//!
+//! Serves a page that searches for a nonce such that
+//! `SHA-256("{token}:{nonce}")` starts with the required number of zero bits,
+//! stores `{token}:{nonce}` in a cookie and reloads. The token is signed and
+//! bound to the client IP, difficulty and expiry, so the proxy verifies
+//! solutions without keeping state.
+//!
+//! The page uses WebCrypto, which browsers only expose on HTTPS (or
+//! localhost) origins.
+
+use bytes::Bytes;
+use http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use http::{Response, StatusCode};
+use http_body_util::Full;
+use sha2::{Digest, Sha256};
+
+use zentinel_config::routes::MAX_POW_DIFFICULTY;
+
+use super::{unix_now, ChallengeProvider, ChallengeRequest, ChallengeSigner, Verification};
+
+/// Challenge type name used in signatures.
+const CHALLENGE_TYPE: &str = "js-pow";
+
+/// JavaScript proof-of-work challenge (`js-pow`).
+///
+/// Agents may raise the route's difficulty with a `difficulty` parameter.
+pub struct ProofOfWorkChallenge;
+
+impl ProofOfWorkChallenge {
+    /// Cookie carrying the solution.
+    fn cookie_name(request: &ChallengeRequest<'_>) -> String {
+        format!("{}_pow", request.config.cookie_name)
+    }
+
+    /// Required difficulty: the agent's `difficulty` parameter, else the route's.
+    fn difficulty(request: &ChallengeRequest<'_>) -> u8 {
+        request
+            .params
+            .get("difficulty")
+            .and_then(|d| d.parse::<u8>().ok())
+            .unwrap_or(request.config.pow_difficulty)
+            .clamp(1, MAX_POW_DIFFICULTY)
+    }
+}
+
+impl ChallengeProvider for ProofOfWorkChallenge {
+    fn verify(&self, request: &ChallengeRequest<'_>, signer: &ChallengeSigner) -> Verification {
+        let Some(value) = request.cookie(&Self::cookie_name(request)) else {
+            return Verification::Missing;
+        };
+        let Some((token, _nonce)) = value.rsplit_once(':') else {
+            return Verification::Failed;
+        };
+        let mut fields = token.splitn(3, '.');
+        let (Some(difficulty), Some(expires), Some(signature)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return Verification::Failed;
+        };
+        let (Ok(token_difficulty), Ok(expires_at)) =
+            (difficulty.parse::<u8>(), expires.parse::<u64>())
+        else {
+            return Verification::Failed;
+        };
+
+        let valid = expires_at > unix_now()
+            && token_difficulty >= Self::difficulty(request)
+            && signer.verify(
+                &[CHALLENGE_TYPE, request.client_ip, difficulty, expires],
+                signature,
+            )
+            && leading_zero_bits(&Sha256::digest(value.as_bytes())) >= u32::from(token_difficulty);
+
+        if valid {
+            Verification::Passed
+        } else {
+            Verification::Failed
+        }
+    }
+
+    fn issue(
+        &self,
+        request: &ChallengeRequest<'_>,
+        signer: &ChallengeSigner,
+    ) -> Response<Full<Bytes>> {
+        let ttl = request.config.ttl_secs;
+        let difficulty = Self::difficulty(request).to_string();
+        let expires = (unix_now() + ttl).to_string();
+        let signature = signer.sign(&[CHALLENGE_TYPE, request.client_ip, &difficulty, &expires]);
+        let token = format!("{}.{}.{}", difficulty, expires, signature);
+
+        let html = CHALLENGE_PAGE
+            .replace("{{token}}", &token)
+            .replace("{{difficulty}}", &difficulty)
+            .replace("{{cookie}}", &Self::cookie_name(request))
+            .replace("{{ttl}}", &ttl.to_string());
+
+        let mut response = Response::new(Full::new(Bytes::from(html)));
+        *response.status_mut() = StatusCode::FORBIDDEN;
+        let headers = response.headers_mut();
+        headers.insert(
+            CONTENT_TYPE,
+            http::HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        headers.insert(CACHE_CONTROL, http::HeaderValue::from_static("no-store"));
+        response
+    }
+}
+
+/// Count the leading zero bits of a digest.
+fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Proof-of-work challenge page.
+const CHALLENGE_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="robots" content="noindex">
+    <title>Checking your browser</title>
+</head>
+<body>
+    <noscript>JavaScript is required to continue.</noscript>
+    <p>Checking your browser&hellip;</p>
+    <script>
+    (async () => {
+        const token = "{{token}}";
+        const difficulty = {{difficulty}};
+        const encoder = new TextEncoder();
+        const zeroBits = (bytes) => {
+            let bits = 0;
+            for (const byte of bytes) {
+                if (byte === 0) { bits += 8; continue; }
+                return bits + Math.clz32(byte) - 24;
+            }
+            return bits;
+        };
+        for (let nonce = 0; ; nonce++) {
+            const solution = token + ":" + nonce;
+            const digest = await crypto.subtle.digest("SHA-256", encoder.encode(solution));
+            if (zeroBits(new Uint8Array(digest)) >= difficulty) {
+                document.cookie = "{{cookie}}=" + solution + "; Path=/; Max-Age={{ttl}}; SameSite=Lax";
+                location.reload();
+                return;
+            }
+        }
+    })();
+    </script>
+</body>
+</html>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::tests::request;
+    use http_body_util::BodyExt;
+    use std::collections::HashMap;
+    use zentinel_config::ChallengeConfig;
+
+    /// Extract the token embedded in an issued challenge page.
+    async fn issued_token(response: Response<Full<Bytes>>) -> String {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let page = String::from_utf8(body.to_vec()).unwrap();
+        let start = page.find("const token = \"").unwrap() + "const token = \"".len();
+        let end = start + page[start..].find('"').unwrap();
+        page[start..end].to_string()
+    }
+
+    /// Brute-force a solution the way the challenge page does.
+    fn solve(token: &str, difficulty: u32) -> String {
+        (0u64..)
+            .map(|nonce| format!("{}:{}", token, nonce))
+            .find(|solution| leading_zero_bits(&Sha256::digest(solution.as_bytes())) >= difficulty)
+            .unwrap()
+    }
+
+    fn easy_config() -> ChallengeConfig {
+        ChallengeConfig {
+            pow_difficulty: 4,
+            ..ChallengeConfig::default()
+        }
+    }
+
+    #[test]
+    fn counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0x00, 0x00, 0xff]), 16);
+        assert_eq!(leading_zero_bits(&[0x00, 0x1f]), 11);
+        assert_eq!(leading_zero_bits(&[0x80]), 0);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00]), 16);
+    }
+
+    #[tokio::test]
+    async fn solved_challenge_verifies() {
+        let signer = ChallengeSigner::random();
+        let config = easy_config();
+        let params = HashMap::new();
+
+        let response = ProofOfWorkChallenge.issue(&request(None, &config, &params), &signer);
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let solution = solve(&issued_token(response).await, 4);
+        let cookies = format!("zentinel_clearance_pow={}", solution);
+        let verification =
+            ProofOfWorkChallenge.verify(&request(Some(&cookies), &config, &params), &signer);
+        assert_eq!(verification, Verification::Passed);
+    }
+
+    #[tokio::test]
+    async fn unsolved_token_fails() {
+        let signer = ChallengeSigner::random();
+        let config = ChallengeConfig {
+            pow_difficulty: 24,
+            ..ChallengeConfig::default()
+        };
+        let params = HashMap::new();
+
+        let response = ProofOfWorkChallenge.issue(&request(None, &config, &params), &signer);
+        // Nonce "x" is overwhelmingly unlikely to meet 24 bits
+        let cookies = format!("zentinel_clearance_pow={}:x", issued_token(response).await);
+        let verification =
+            ProofOfWorkChallenge.verify(&request(Some(&cookies), &config, &params), &signer);
+        assert_eq!(verification, Verification::Failed);
+    }
+
+    #[tokio::test]
+    async fn agent_difficulty_above_token_difficulty_fails() {
+        let signer = ChallengeSigner::random();
+        let config = easy_config();
+        let no_params = HashMap::new();
+
+        let response = ProofOfWorkChallenge.issue(&request(None, &config, &no_params), &signer);
+        let cookies = format!(
+            "zentinel_clearance_pow={}",
+            solve(&issued_token(response).await, 4)
+        );
+
+        let harder = HashMap::from([("difficulty".to_string(), "20".to_string())]);
+        let verification =
+            ProofOfWorkChallenge.verify(&request(Some(&cookies), &config, &harder), &signer);
+        assert_eq!(verification, Verification::Failed);
+    }
+}
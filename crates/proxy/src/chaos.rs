@@ -0,0 +1,419 @@
+//! Fault injection for resilience testing.
+//!
+//! Faults come from `system.chaos` and stay inert until armed through the
+//! `chaos` admin handler; nothing can be armed while `enabled` is off. An
+//! armed fault hits a `probability` share of the requests or agent calls it
+//! targets and disarms itself once its duration is up:
+//!
+//! - `latency` delays the request before limits, agents and upstreams, or
+//!   the agent call before it is sent
+//! - `error` answers the request with the configured status, or fails the
+//!   agent call
+//! - `reset` closes the client connection without a response
+//! - `agent-timeout` holds the agent call until its timeout fires, so the
+//!   agent's circuit breaker and failure mode see a real timeout
+//!
+//! Like the DNS resolver the manager is process-wide, so agent calls reach
+//! it without it being threaded through the agent manager. Armed faults
+//! survive config reloads unless the reload removes them or turns chaos off.
+
+use parking_lot::RwLock;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use rand::RngExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use zentinel_agent_protocol::EventType;
+use zentinel_common::errors::{ZentinelError, ZentinelResult};
+use zentinel_config::{ChaosConfig, ChaosFault, ChaosFaultKind, ChaosTarget};
+
+/// Requests and agent calls a fault was injected into
+static CHAOS_INJECTIONS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_chaos_injections_total",
+        "Faults injected by armed chaos faults",
+        &["fault", "kind"]
+    )
+    .ok()
+});
+
+static CHAOS: LazyLock<ChaosManager> = LazyLock::new(|| ChaosManager::new(&ChaosConfig::default()));
+
+/// Apply the chaos configuration. Called at startup and on every reload.
+pub fn configure(config: &ChaosConfig) {
+    CHAOS.reload(config);
+}
+
+/// The process-wide chaos manager
+pub fn manager() -> &'static ChaosManager {
+    &CHAOS
+}
+
+/// What to do to a request or agent call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Injection {
+    /// Wait before continuing
+    Delay(Duration),
+    /// Answer with this status, or fail the agent call
+    Error(u16),
+    /// Close the client connection without a response
+    Reset,
+    /// Never complete the agent call
+    Timeout,
+}
+
+impl From<ChaosFaultKind> for Injection {
+    fn from(kind: ChaosFaultKind) -> Self {
+        match kind {
+            ChaosFaultKind::Latency { ms } => Self::Delay(Duration::from_millis(ms)),
+            ChaosFaultKind::Error { status } => Self::Error(status),
+            ChaosFaultKind::Reset => Self::Reset,
+            ChaosFaultKind::AgentTimeout => Self::Timeout,
+        }
+    }
+}
+
+/// Errors arming or disarming a fault
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChaosError {
+    #[error("Chaos fault injection is disabled (system.chaos.enabled is off)")]
+    Disabled,
+
+    #[error("Unknown fault '{0}'")]
+    UnknownFault(String),
+}
+
+/// A configured fault and whether it is armed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultStatus {
+    pub id: String,
+    /// `route:<id>` or `agent:<id>`
+    pub target: String,
+    pub kind: &'static str,
+    pub probability: f64,
+    pub duration_secs: u64,
+    pub armed: bool,
+    /// Seconds until an armed fault disarms itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_secs: Option<u64>,
+}
+
+/// Snapshot for the `chaos` admin handler
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChaosState {
+    pub enabled: bool,
+    pub faults: Vec<FaultStatus>,
+}
+
+/// Configured faults and the ones currently armed
+pub struct ChaosManager {
+    config: RwLock<ChaosConfig>,
+    /// Armed fault IDs with the instant they disarm
+    armed: RwLock<HashMap<String, Instant>>,
+    /// Fast path: false while no fault is armed
+    any_armed: AtomicBool,
+}
+
+impl ChaosManager {
+    /// Create the manager with every fault disarmed
+    pub fn new(config: &ChaosConfig) -> Self {
+        Self {
+            config: RwLock::new(config.clone()),
+            armed: RwLock::new(HashMap::new()),
+            any_armed: AtomicBool::new(false),
+        }
+    }
+
+    /// Apply a new config, disarming faults it removes or all of them when
+    /// chaos is turned off.
+    pub fn reload(&self, config: &ChaosConfig) {
+        *self.config.write() = config.clone();
+        let mut armed = self.armed.write();
+        armed.retain(|id, _| config.enabled && config.faults.iter().any(|f| &f.id == id));
+        self.any_armed.store(!armed.is_empty(), Ordering::Release);
+    }
+
+    /// Arm a fault for its configured duration
+    pub fn arm(&self, id: &str) -> Result<Duration, ChaosError> {
+        let duration = {
+            let config = self.config.read();
+            if !config.enabled {
+                return Err(ChaosError::Disabled);
+            }
+            let fault = find_fault(&config, id)?;
+            Duration::from_secs(fault.duration_secs)
+        };
+
+        self.armed
+            .write()
+            .insert(id.to_string(), Instant::now() + duration);
+        self.any_armed.store(true, Ordering::Release);
+        warn!(
+            fault = id,
+            duration_secs = duration.as_secs(),
+            "Chaos fault armed"
+        );
+        Ok(duration)
+    }
+
+    /// Disarm a fault before its duration is up
+    pub fn disarm(&self, id: &str) -> Result<(), ChaosError> {
+        find_fault(&self.config.read(), id)?;
+        let mut armed = self.armed.write();
+        if armed.remove(id).is_some() {
+            info!(fault = id, "Chaos fault disarmed");
+        }
+        self.any_armed.store(!armed.is_empty(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Disarm every fault, returning how many were armed
+    pub fn disarm_all(&self) -> usize {
+        let mut armed = self.armed.write();
+        let count = armed.len();
+        armed.clear();
+        self.any_armed.store(false, Ordering::Release);
+        if count > 0 {
+            info!(count = count, "All chaos faults disarmed");
+        }
+        count
+    }
+
+    /// Configured faults and their armed state
+    pub fn state(&self) -> ChaosState {
+        let now = Instant::now();
+        let config = self.config.read();
+        let armed = self.armed.read();
+        ChaosState {
+            enabled: config.enabled,
+            faults: config
+                .faults
+                .iter()
+                .map(|fault| {
+                    let remaining = armed
+                        .get(&fault.id)
+                        .and_then(|expires| expires.checked_duration_since(now))
+                        .filter(|remaining| !remaining.is_zero());
+                    FaultStatus {
+                        id: fault.id.clone(),
+                        target: match &fault.target {
+                            ChaosTarget::Route(route) => format!("route:{}", route),
+                            ChaosTarget::Agent(agent) => format!("agent:{}", agent),
+                        },
+                        kind: fault.kind.as_str(),
+                        probability: fault.probability,
+                        duration_secs: fault.duration_secs,
+                        armed: remaining.is_some(),
+                        remaining_secs: remaining.map(|r| r.as_secs()),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Fault to inject into a request to `route_id`, if any
+    pub fn route_injection(&self, route_id: Option<&str>) -> Option<Injection> {
+        let route_id = route_id?;
+        self.injection(|target| matches!(target, ChaosTarget::Route(id) if id == route_id))
+    }
+
+    /// Fault to inject into a call to `agent_id`, if any
+    pub fn agent_injection(&self, agent_id: &str) -> Option<Injection> {
+        self.injection(|target| matches!(target, ChaosTarget::Agent(id) if id == agent_id))
+    }
+
+    /// First armed fault for the target whose probability roll hits
+    fn injection(&self, targets: impl Fn(&ChaosTarget) -> bool) -> Option<Injection> {
+        if !self.any_armed.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut expired = false;
+        {
+            let config = self.config.read();
+            let armed = self.armed.read();
+            for fault in &config.faults {
+                let Some(expires) = armed.get(&fault.id) else {
+                    continue;
+                };
+                if *expires <= now {
+                    expired = true;
+                    continue;
+                }
+                if !targets(&fault.target) {
+                    continue;
+                }
+                if fault.probability < 1.0 && rand::rng().random::<f64>() >= fault.probability {
+                    continue;
+                }
+                if let Some(counter) = CHAOS_INJECTIONS.as_ref() {
+                    counter
+                        .with_label_values(&[fault.id.as_str(), fault.kind.as_str()])
+                        .inc();
+                }
+                return Some(fault.kind.into());
+            }
+        }
+
+        if expired {
+            let mut armed = self.armed.write();
+            armed.retain(|id, expires| {
+                let keep = *expires > now;
+                if !keep {
+                    info!(fault = %id, "Chaos fault expired");
+                }
+                keep
+            });
+            self.any_armed.store(!armed.is_empty(), Ordering::Release);
+        }
+        None
+    }
+}
+
+fn find_fault<'a>(config: &'a ChaosConfig, id: &str) -> Result<&'a ChaosFault, ChaosError> {
+    config
+        .faults
+        .iter()
+        .find(|f| f.id == id)
+        .ok_or_else(|| ChaosError::UnknownFault(id.to_string()))
+}
+
+/// Run an agent call with the armed fault for `agent_id`, if any, applied.
+///
+/// An injected timeout never completes, so the caller's timeout fires as it
+/// would for an unresponsive agent.
+pub(crate) async fn agent_call<T>(
+    agent_id: &str,
+    event_type: EventType,
+    call: impl Future<Output = ZentinelResult<T>>,
+) -> ZentinelResult<T> {
+    match CHAOS.agent_injection(agent_id) {
+        None | Some(Injection::Reset) => call.await,
+        Some(Injection::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            call.await
+        }
+        Some(Injection::Error(_)) => Err(ZentinelError::Agent {
+            agent: agent_id.to_string(),
+            message: "Injected chaos fault".to_string(),
+            event: format!("{:?}", event_type),
+            source: None,
+        }),
+        Some(Injection::Timeout) => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fault(id: &str, target: ChaosTarget, kind: ChaosFaultKind) -> ChaosFault {
+        ChaosFault {
+            id: id.to_string(),
+            target,
+            kind,
+            probability: 1.0,
+            duration_secs: 60,
+        }
+    }
+
+    fn config() -> ChaosConfig {
+        ChaosConfig {
+            enabled: true,
+            faults: vec![
+                fault(
+                    "slow-api",
+                    ChaosTarget::Route("api".to_string()),
+                    ChaosFaultKind::Latency { ms: 200 },
+                ),
+                fault(
+                    "waf-timeout",
+                    ChaosTarget::Agent("waf".to_string()),
+                    ChaosFaultKind::AgentTimeout,
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn faults_apply_only_while_armed() {
+        let manager = ChaosManager::new(&config());
+        assert_eq!(manager.route_injection(Some("api")), None);
+
+        manager.arm("slow-api").unwrap();
+        assert_eq!(
+            manager.route_injection(Some("api")),
+            Some(Injection::Delay(Duration::from_millis(200)))
+        );
+        assert_eq!(manager.route_injection(Some("other")), None);
+        assert_eq!(manager.agent_injection("waf"), None);
+
+        let state = manager.state();
+        assert!(state.faults[0].armed);
+        assert_eq!(state.faults[0].target, "route:api");
+        assert!(!state.faults[1].armed);
+
+        manager.disarm("slow-api").unwrap();
+        assert_eq!(manager.route_injection(Some("api")), None);
+        assert_eq!(
+            manager.arm("missing"),
+            Err(ChaosError::UnknownFault("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn arming_requires_chaos_enabled() {
+        let manager = ChaosManager::new(&config());
+        manager.arm("waf-timeout").unwrap();
+        assert_eq!(manager.agent_injection("waf"), Some(Injection::Timeout));
+
+        // Turning chaos off disarms everything and blocks arming
+        manager.reload(&ChaosConfig {
+            enabled: false,
+            ..config()
+        });
+        assert_eq!(manager.agent_injection("waf"), None);
+        assert_eq!(manager.arm("waf-timeout"), Err(ChaosError::Disabled));
+    }
+
+    #[test]
+    fn armed_faults_expire() {
+        let mut config = config();
+        config.faults[0].duration_secs = 0;
+        let manager = ChaosManager::new(&config);
+        manager.arm("slow-api").unwrap();
+        assert_eq!(manager.route_injection(Some("api")), None);
+        assert!(!manager.any_armed.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn injected_agent_errors_fail_the_call() {
+        let mut config = config();
+        config.faults.push(fault(
+            "auth-errors",
+            ChaosTarget::Agent("chaos-test-auth".to_string()),
+            ChaosFaultKind::Error { status: 503 },
+        ));
+        configure(&config);
+        manager().arm("auth-errors").unwrap();
+
+        let result = agent_call("chaos-test-auth", EventType::RequestHeaders, async {
+            Ok(())
+        })
+        .await;
+        assert!(matches!(result, Err(ZentinelError::Agent { .. })));
+        assert!(
+            agent_call("other", EventType::RequestHeaders, async { Ok(()) })
+                .await
+                .is_ok()
+        );
+
+        manager().disarm_all();
+    }
+}
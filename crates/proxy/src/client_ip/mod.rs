@@ -0,0 +1,243 @@
+//! Client IP resolution.
+//!
+//! The client IP used for agent metadata, rate limiting, geo filtering and
+//! logs is resolved once per request:
+//!
+//! 1. The socket peer, or the source address from a PROXY protocol header
+//!    when the listener has `proxy-protocol` enabled
+//! 2. If that address is a trusted proxy, the first configured forwarding
+//!    header present (`X-Forwarded-For`, `Forwarded`, `X-Real-IP`) is walked
+//!    right to left, skipping trusted proxies; the first untrusted address
+//!    is the client
+//!
+//! Headers from untrusted peers are ignored, so clients cannot spoof their
+//! address by sending forwarding headers directly.
+
+pub mod proxy_protocol;
+
+use http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+use zentinel_config::{ClientIpConfig, ClientIpHeader, IpCidr};
+
+/// Resolves client IPs from the peer address and forwarding headers.
+#[derive(Debug, Clone)]
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpCidr>,
+    headers: Vec<ClientIpHeader>,
+}
+
+impl ClientIpResolver {
+    /// Create a resolver from server configuration.
+    pub fn new(config: &ClientIpConfig) -> Self {
+        Self {
+            trusted_proxies: config.trusted_proxies.clone(),
+            headers: config.headers.clone(),
+        }
+    }
+
+    /// Check whether an address belongs to a trusted proxy.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Resolve the client address for a request.
+    ///
+    /// `peer` is the connection's peer (after PROXY protocol). The port is
+    /// kept only when the peer itself is the client.
+    pub fn resolve(&self, peer: SocketAddr, headers: &HeaderMap) -> (IpAddr, Option<u16>) {
+        if !self.is_trusted(peer.ip()) {
+            return (peer.ip(), Some(peer.port()));
+        }
+
+        for header in &self.headers {
+            let chain = forwarded_chain(*header, headers);
+            if chain.is_empty() {
+                continue;
+            }
+
+            // Walk from the nearest hop; stop at the first untrusted address.
+            // An unparseable hop ends the walk at the last address we trust.
+            let mut client = peer.ip();
+            for hop in chain.iter().rev() {
+                match hop {
+                    Some(ip) => {
+                        client = *ip;
+                        if !self.is_trusted(*ip) {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+            return (client, None);
+        }
+
+        (peer.ip(), Some(peer.port()))
+    }
+}
+
+/// Addresses from a forwarding header, leftmost (original client) first.
+///
+/// Entries that are not IP addresses (`unknown`, obfuscated identifiers)
+/// are `None`.
+fn forwarded_chain(header: ClientIpHeader, headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    // Multiple header lines form one list, in order
+    let values = headers
+        .get_all(header.header_name())
+        .iter()
+        .filter_map(|v| v.to_str().ok());
+
+    match header {
+        ClientIpHeader::XForwardedFor => values
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|hop| !hop.is_empty())
+            .map(parse_node)
+            .collect(),
+        ClientIpHeader::Forwarded => values
+            .flat_map(|v| v.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.trim().split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("for")
+                        .then(|| parse_node(value.trim().trim_matches('"')))
+                })
+            })
+            .collect(),
+        ClientIpHeader::XRealIp => values.take(1).map(|v| parse_node(v.trim())).collect(),
+    }
+}
+
+/// Parse a forwarding node: `1.2.3.4`, `1.2.3.4:80`, `2001:db8::1` or
+/// `[2001:db8::1]:80`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    // Bracketed IPv6 without a port
+    node.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn resolver(trusted: &[&str], headers: Vec<ClientIpHeader>) -> ClientIpResolver {
+        ClientIpResolver::new(&ClientIpConfig {
+            trusted_proxies: trusted.iter().map(|c| c.parse().unwrap()).collect(),
+            headers,
+        })
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    const LB: &str = "10.0.0.5:40000";
+
+    #[test]
+    fn untrusted_peer_headers_are_ignored() {
+        let resolver = resolver(&["10.0.0.0/8"], vec![ClientIpHeader::XForwardedFor]);
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4")]);
+
+        let (client, port) = resolver.resolve("198.51.100.9:5555".parse().unwrap(), &spoofed);
+        assert_eq!(client, ip("198.51.100.9"));
+        assert_eq!(port, Some(5555));
+    }
+
+    #[test]
+    fn x_forwarded_for_uses_rightmost_untrusted() {
+        let resolver = resolver(&["10.0.0.0/8"], vec![ClientIpHeader::XForwardedFor]);
+        // Client-supplied "6.6.6.6" must not win over the address our LB saw
+        let h = headers(&[
+            ("x-forwarded-for", "6.6.6.6, 203.0.113.7"),
+            ("x-forwarded-for", "10.1.1.1"),
+        ]);
+
+        let (client, port) = resolver.resolve(LB.parse().unwrap(), &h);
+        assert_eq!(client, ip("203.0.113.7"));
+        assert_eq!(port, None);
+    }
+
+    #[test]
+    fn all_trusted_chain_resolves_to_leftmost() {
+        let resolver = resolver(&["10.0.0.0/8"], vec![ClientIpHeader::XForwardedFor]);
+        let h = headers(&[("x-forwarded-for", "10.9.9.9, 10.1.1.1")]);
+
+        assert_eq!(resolver.resolve(LB.parse().unwrap(), &h).0, ip("10.9.9.9"));
+    }
+
+    #[test]
+    fn invalid_hop_stops_at_last_trusted_address() {
+        let resolver = resolver(&["10.0.0.0/8"], vec![ClientIpHeader::XForwardedFor]);
+        let h = headers(&[("x-forwarded-for", "203.0.113.7, garbage, 10.1.1.1")]);
+
+        assert_eq!(resolver.resolve(LB.parse().unwrap(), &h).0, ip("10.1.1.1"));
+    }
+
+    #[test]
+    fn forwarded_header() {
+        let resolver = resolver(&["10.0.0.0/8"], vec![ClientIpHeader::Forwarded]);
+        let h = headers(&[(
+            "forwarded",
+            r#"for=192.0.2.60;proto=https, For="[2001:db8::1]:4711";by=10.0.0.5"#,
+        )]);
+
+        assert_eq!(
+            resolver.resolve(LB.parse().unwrap(), &h).0,
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn header_order_and_fallback() {
+        let resolver = resolver(
+            &["10.0.0.0/8"],
+            vec![ClientIpHeader::XRealIp, ClientIpHeader::XForwardedFor],
+        );
+
+        let both = headers(&[
+            ("x-real-ip", "203.0.113.1"),
+            ("x-forwarded-for", "203.0.113.2"),
+        ]);
+        assert_eq!(
+            resolver.resolve(LB.parse().unwrap(), &both).0,
+            ip("203.0.113.1")
+        );
+
+        let xff_only = headers(&[("x-forwarded-for", "203.0.113.2:8080")]);
+        assert_eq!(
+            resolver.resolve(LB.parse().unwrap(), &xff_only).0,
+            ip("203.0.113.2")
+        );
+
+        let (client, port) = resolver.resolve(LB.parse().unwrap(), &HeaderMap::new());
+        assert_eq!(client, ip("10.0.0.5"));
+        assert_eq!(port, Some(40000));
+    }
+
+    #[test]
+    fn parses_forwarding_nodes() {
+        assert_eq!(parse_node("203.0.113.7"), Some(ip("203.0.113.7")));
+        assert_eq!(parse_node("203.0.113.7:443"), Some(ip("203.0.113.7")));
+        assert_eq!(parse_node("2001:db8::1"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("[2001:db8::1]"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("[2001:db8::1]:80"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+    }
+}
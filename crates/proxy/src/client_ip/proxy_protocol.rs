@@ -0,0 +1,997 @@
+//! PROXY protocol (v1 and v2) support for listeners.
+//!
+//! Pingora accepts plain TCP/TLS connections only, so a listener with
+//! `proxy-protocol` enabled is fronted by a small acceptor: it binds the
+//! public address, strips the PROXY header from each connection and splices
+//! the rest of the stream to Pingora on an internal Unix socket. The
+//! header's source address is recorded under the path the internal
+//! connection is bound to, where [`lookup`] finds it when the request is
+//! processed.
+//!
+//! Internal sockets live in a directory only the proxy's user can access
+//! (see [`internal_listener_path`]), so no other user can bind or connect
+//! to them. Connections to an internal listener that were not registered
+//! by the acceptor or the HTTP/3 frontend are rejected by the proxy; the
+//! remaining trust assumption is that processes running as the proxy's own
+//! user are not hostile.
+//!
+//! The HTTP/3 frontend ([`crate::http3`]) hands requests to Pingora the same
+//! way and records its QUIC connections in the same registry.
+//!
+//! Listeners with `bot-signals` use the same acceptor, with or without a
+//! PROXY header, to fingerprint the start of each connection (see
+//! [`crate::bot_signals`]) before splicing it through.
+//!
+//! HTTPS listeners are always fronted: the acceptor terminates TLS with the
+//! listener's [`crate::tls::build_server_config`], which serves certificates
+//! from the hot-reloadable resolver, and splices the decrypted stream
+//! through. HTTP/2 reaches Pingora as cleartext HTTP/2 (h2c).
+
+use dashmap::DashMap;
+use once_cell::sync::{Lazy, OnceCell};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream, UnixSocket, UnixStream};
+use tokio::sync::Semaphore;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error, info, warn};
+use zentinel_agent_protocol::BotSignals;
+
+/// v1 headers are at most 107 bytes including CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// v2 signature (12 bytes).
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Upper bound on a v2 header (fixed part plus TLVs) we are willing to buffer.
+const V2_MAX_LEN: usize = 16 + 1024;
+
+/// Time allowed for a client to send the PROXY header.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for a client to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed accept, so errors like EMFILE don't spin the loop.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Decoded PROXY protocol header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyHeader {
+    /// Original client address (`None` for LOCAL/UNKNOWN connections)
+    pub source: Option<SocketAddr>,
+    /// Original destination address
+    pub destination: Option<SocketAddr>,
+}
+
+/// Result of parsing the start of a connection.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseResult {
+    /// More bytes are needed
+    Incomplete,
+    /// A complete header of `len` bytes was decoded
+    Complete { header: ProxyHeader, len: usize },
+}
+
+/// Parse a PROXY protocol v1 or v2 header from the start of `buf`.
+pub fn parse(buf: &[u8]) -> Result<ParseResult, String> {
+    if buf.starts_with(V2_SIGNATURE) {
+        parse_v2(buf)
+    } else if buf.starts_with(b"PROXY ") {
+        parse_v1(buf)
+    } else if V2_SIGNATURE.starts_with(buf) || b"PROXY ".starts_with(buf) {
+        Ok(ParseResult::Incomplete)
+    } else {
+        Err("missing PROXY protocol header".to_string())
+    }
+}
+
+/// Parse a text (v1) header: `PROXY TCP4 <src> <dst> <sport> <dport>\r\n`.
+fn parse_v1(buf: &[u8]) -> Result<ParseResult, String> {
+    let Some(end) = buf.windows(2).position(|w| w == b"\r\n") else {
+        return if buf.len() >= V1_MAX_LEN {
+            Err("PROXY v1 header too long".to_string())
+        } else {
+            Ok(ParseResult::Incomplete)
+        };
+    };
+    let line = std::str::from_utf8(&buf[..end]).map_err(|_| "PROXY v1 header is not ASCII")?;
+    let fields: Vec<&str> = line.split(' ').collect();
+
+    let header = match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => ProxyHeader {
+            source: None,
+            destination: None,
+        },
+        ["PROXY", proto @ ("TCP4" | "TCP6"), src, dst, sport, dport] => {
+            let addr = |ip: &str, port: &str| -> Result<SocketAddr, String> {
+                let ip: IpAddr = ip
+                    .parse()
+                    .map_err(|_| format!("invalid address '{}'", ip))?;
+                if ip.is_ipv4() != (*proto == "TCP4") {
+                    return Err(format!("address '{}' does not match {}", ip, proto));
+                }
+                let port: u16 = port
+                    .parse()
+                    .map_err(|_| format!("invalid port '{}'", port))?;
+                Ok(SocketAddr::new(ip, port))
+            };
+            ProxyHeader {
+                source: Some(addr(src, sport)?),
+                destination: Some(addr(dst, dport)?),
+            }
+        }
+        _ => return Err(format!("malformed PROXY v1 header '{}'", line)),
+    };
+
+    Ok(ParseResult::Complete {
+        header,
+        len: end + 2,
+    })
+}
+
+/// Parse a binary (v2) header.
+fn parse_v2(buf: &[u8]) -> Result<ParseResult, String> {
+    if buf.len() < 16 {
+        return Ok(ParseResult::Incomplete);
+    }
+    let version_command = buf[12];
+    if version_command >> 4 != 2 {
+        return Err(format!(
+            "unsupported PROXY protocol version {}",
+            version_command >> 4
+        ));
+    }
+    let len = 16 + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if len > V2_MAX_LEN {
+        return Err("PROXY v2 header too long".to_string());
+    }
+    if buf.len() < len {
+        return Ok(ParseResult::Incomplete);
+    }
+    let payload = &buf[16..len];
+
+    let header = match version_command & 0x0f {
+        // LOCAL: health checks from the proxy itself, no client address
+        0x0 => ProxyHeader {
+            source: None,
+            destination: None,
+        },
+        // PROXY
+        0x1 => match buf[13] >> 4 {
+            // AF_INET
+            0x1 if payload.len() >= 12 => {
+                let ip = |b: &[u8]| IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3]));
+                let port = |b: &[u8]| u16::from_be_bytes([b[0], b[1]]);
+                ProxyHeader {
+                    source: Some(SocketAddr::new(ip(&payload[0..4]), port(&payload[8..10]))),
+                    destination: Some(SocketAddr::new(ip(&payload[4..8]), port(&payload[10..12]))),
+                }
+            }
+            // AF_INET6
+            0x2 if payload.len() >= 36 => {
+                let ip = |b: &[u8]| {
+                    let octets: [u8; 16] = b.try_into().expect("slice is 16 bytes");
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                };
+                let port = |b: &[u8]| u16::from_be_bytes([b[0], b[1]]);
+                ProxyHeader {
+                    source: Some(SocketAddr::new(ip(&payload[0..16]), port(&payload[32..34]))),
+                    destination: Some(SocketAddr::new(
+                        ip(&payload[16..32]),
+                        port(&payload[34..36]),
+                    )),
+                }
+            }
+            // AF_UNSPEC / AF_UNIX: no usable IP address
+            0x0 | 0x3 => ProxyHeader {
+                source: None,
+                destination: None,
+            },
+            _ => return Err("unsupported or truncated PROXY v2 address block".to_string()),
+        },
+        command => return Err(format!("unsupported PROXY v2 command {}", command)),
+    };
+
+    Ok(ParseResult::Complete { header, len })
+}
+
+/// A connection accepted through a fronted listener or the HTTP/3
+/// frontend.
+#[derive(Debug, Clone)]
+pub struct ProxiedConnection {
+    /// Client address from the PROXY header (`None` for LOCAL/UNKNOWN)
+    pub source: Option<SocketAddr>,
+    /// Public address of the listener the connection arrived on
+    pub listener_address: String,
+    /// Downstream protocol when it differs from the internal hop (`HTTP/3`)
+    pub protocol: Option<&'static str>,
+    /// Whether the acceptor terminated TLS on an HTTPS listener (HTTP/3
+    /// connections are identified by `protocol` instead)
+    pub tls: bool,
+    /// Server name (SNI) from the downstream TLS handshake
+    pub server_name: Option<String>,
+    /// DER client certificate from the downstream handshake (mTLS)
+    pub client_cert: Option<Vec<u8>>,
+    /// Whether the listener's client verifier accepted `client_cert`
+    pub client_cert_verified: bool,
+    /// Connection fingerprints (listeners with `bot-signals`)
+    pub bot_signals: Option<BotSignals>,
+}
+
+/// Proxied connections keyed by the socket path Pingora sees as the peer.
+static CONNECTIONS: Lazy<DashMap<PathBuf, ProxiedConnection>> = Lazy::new(DashMap::new);
+
+/// Private directory holding the internal sockets.
+static SOCKET_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Counter for unique internal socket names.
+static NEXT_SOCKET: AtomicU64 = AtomicU64::new(0);
+
+/// Look up the connection details for a Pingora peer socket path.
+pub fn lookup(peer: &Path) -> Option<ProxiedConnection> {
+    CONNECTIONS.get(peer).map(|c| c.clone())
+}
+
+/// A connection recorded in the registry; dropping it forgets the
+/// connection.
+pub(crate) struct Registration {
+    path: PathBuf,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        CONNECTIONS.remove(&self.path);
+    }
+}
+
+/// Directory for internal sockets, created on first use with mode 0700.
+fn socket_dir() -> io::Result<&'static Path> {
+    SOCKET_DIR
+        .get_or_try_init(|| {
+            use std::os::unix::fs::DirBuilderExt;
+
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            let dir = std::env::temp_dir().join(format!("zentinel-{}", &id[..12]));
+            // Fails if the path exists, so the directory is always ours
+            std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+            Ok(dir)
+        })
+        .map(PathBuf::as_path)
+}
+
+/// Unique path in the internal socket directory.
+fn socket_path(prefix: &str) -> io::Result<PathBuf> {
+    let n = NEXT_SOCKET.fetch_add(1, Ordering::Relaxed);
+    Ok(socket_dir()?.join(format!("{}-{}.sock", prefix, n)))
+}
+
+/// Path of a new internal Unix socket for Pingora to listen on behind a
+/// fronted listener or the HTTP/3 frontend.
+///
+/// The path is unique within a directory private to the proxy's user, so
+/// no other process can claim it before Pingora binds it.
+pub fn internal_listener_path() -> io::Result<PathBuf> {
+    socket_path("l")
+}
+
+/// Connect to Pingora on `internal` and record `connection` under the
+/// connection's own socket path, which Pingora sees as the peer address.
+pub(crate) async fn connect_internal(
+    internal: &Path,
+    connection: ProxiedConnection,
+) -> io::Result<(UnixStream, Registration)> {
+    let path = socket_path("c")?;
+    let socket = UnixSocket::new_stream()?;
+    socket.bind(&path)?;
+    // Register before connecting, so the request never races the registry
+    CONNECTIONS.insert(path.clone(), connection);
+    let registration = Registration { path };
+    let stream = socket.connect(internal).await;
+    // The peer keeps seeing the bound name after the file is removed
+    let _ = std::fs::remove_file(&registration.path);
+    Ok((stream?, registration))
+}
+
+/// Accept connections on `public_address` and splice them to Pingora on
+/// `internal_address`, reading a PROXY header first with `proxy_protocol`,
+/// fingerprinting the connection with `bot_signals` and terminating TLS
+/// with `tls`.
+///
+/// At most `max_connections` connections are handled at once; further
+/// clients wait in the listen backlog.
+pub async fn run_acceptor(
+    listener_id: String,
+    public_address: String,
+    internal_address: PathBuf,
+    proxy_protocol: bool,
+    bot_signals: bool,
+    tls: Option<Arc<rustls::ServerConfig>>,
+    max_connections: usize,
+) {
+    let tls = tls.map(TlsAcceptor::from);
+    let connections = Arc::new(Semaphore::new(max_connections.max(1)));
+    let listener = match TcpListener::bind(&public_address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                listener_id = %listener_id,
+                address = %public_address,
+                error = %e,
+                "Failed to bind PROXY protocol listener"
+            );
+            return;
+        }
+    };
+    info!(
+        listener_id = %listener_id,
+        address = %public_address,
+        internal_address = %internal_address.display(),
+        proxy_protocol,
+        bot_signals,
+        tls = tls.is_some(),
+        "Fronted listener accepting connections"
+    );
+
+    loop {
+        // The semaphore is never closed
+        let Ok(permit) = Arc::clone(&connections).acquire_owned().await else {
+            return;
+        };
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(listener_id = %listener_id, error = %e, "PROXY protocol accept failed");
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+        let public_address = public_address.clone();
+        let internal_address = internal_address.clone();
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            let result = handle_connection(
+                stream,
+                peer,
+                &public_address,
+                &internal_address,
+                proxy_protocol,
+                bot_signals,
+                tls,
+            )
+            .await;
+            if let Err(e) = result {
+                debug!(peer = %peer, error = %e, "Fronted connection rejected");
+            }
+        });
+    }
+}
+
+/// Read the PROXY header and bot signals from `inbound` as configured,
+/// terminate TLS with `tls` and splice it to Pingora.
+async fn handle_connection(
+    mut inbound: TcpStream,
+    peer: SocketAddr,
+    public_address: &str,
+    internal_address: &Path,
+    proxy_protocol: bool,
+    bot_signals: bool,
+    tls: Option<TlsAcceptor>,
+) -> Result<(), String> {
+    let mut buf = Vec::with_capacity(256);
+    let (source, len) = if proxy_protocol {
+        let (header, len) = tokio::time::timeout(HEADER_TIMEOUT, async {
+            loop {
+                let mut chunk = [0u8; 512];
+                let n = inbound.read(&mut chunk).await.map_err(|e| e.to_string())?;
+                if n == 0 {
+                    return Err("connection closed before PROXY header".to_string());
+                }
+                buf.extend_from_slice(&chunk[..n]);
+                if let ParseResult::Complete { header, len } = parse(&buf)? {
+                    return Ok((header, len));
+                }
+            }
+        })
+        .await
+        .map_err(|_| "timed out waiting for PROXY header".to_string())??;
+        (header.source, len)
+    } else {
+        (Some(peer), 0)
+    };
+    let bot_signals = if bot_signals {
+        Some(crate::bot_signals::read_connection_signals(&mut inbound, &mut buf, len).await)
+    } else {
+        None
+    };
+
+    // Bytes read past the PROXY header belong to the client's stream
+    let rest = buf.split_off(len);
+    let inbound = Prefixed::new(inbound, rest);
+    let mut connection = ProxiedConnection {
+        source,
+        listener_address: public_address.to_string(),
+        protocol: None,
+        tls: false,
+        server_name: None,
+        client_cert: None,
+        client_cert_verified: false,
+        bot_signals,
+    };
+
+    match tls {
+        Some(acceptor) => {
+            let mut stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(inbound))
+                .await
+                .map_err(|_| "timed out waiting for TLS handshake".to_string())?
+                .map_err(|e| format!("TLS handshake failed: {}", e))?;
+            connection.tls = true;
+            connection.server_name = stream.get_ref().1.server_name().map(str::to_string);
+            // rustls only keeps peer certificates the listener's client
+            // verifier accepted; without mTLS none are requested
+            connection.client_cert = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|chain| chain.first())
+                .map(|cert| cert.to_vec());
+            connection.client_cert_verified = connection.client_cert.is_some();
+
+            // HTTP/2 frames are only readable once decrypted
+            let h2 = stream.get_ref().1.alpn_protocol() == Some(b"h2".as_slice());
+            if let (true, Some(signals)) = (h2, connection.bot_signals.as_mut()) {
+                let mut buf = Vec::new();
+                let decrypted =
+                    crate::bot_signals::read_connection_signals(&mut stream, &mut buf, 0).await;
+                signals.http2 = decrypted.http2;
+                return splice(Prefixed::new(stream, buf), internal_address, connection).await;
+            }
+            splice(stream, internal_address, connection).await
+        }
+        None => splice(inbound, internal_address, connection).await,
+    }
+}
+
+/// Splice `inbound` to Pingora on `internal_address`, with `connection`
+/// registered for the internal connection.
+async fn splice<S>(
+    mut inbound: S,
+    internal_address: &Path,
+    connection: ProxiedConnection,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut outbound, _registration) = connect_internal(internal_address, connection)
+        .await
+        .map_err(|e| format!("failed to connect to internal listener: {}", e))?;
+
+    tokio::io::copy_bidirectional(&mut inbound, &mut outbound)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// A stream that yields bytes already read from it before the rest.
+struct Prefixed<S> {
+    prefix: Vec<u8>,
+    position: usize,
+    inner: S,
+}
+
+impl<S> Prefixed<S> {
+    fn new(inner: S, prefix: Vec<u8>) -> Self {
+        Self {
+            prefix,
+            position: 0,
+            inner,
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Prefixed<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.position < this.prefix.len() {
+            let remaining = &this.prefix[this.position..];
+            let n = remaining.len().min(buf.remaining());
+            buf.put_slice(&remaining[..n]);
+            this.position += n;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Prefixed<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixListener;
+
+    /// A free local address for the public side of an acceptor.
+    fn free_address() -> SocketAddr {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    fn complete(buf: &[u8]) -> (ProxyHeader, usize) {
+        match parse(buf).unwrap() {
+            ParseResult::Complete { header, len } => (header, len),
+            ParseResult::Incomplete => panic!("expected complete header"),
+        }
+    }
+
+    #[test]
+    fn parses_v1_tcp4() {
+        let buf = b"PROXY TCP4 203.0.113.7 192.0.2.1 51234 443\r\nGET / HTTP/1.1\r\n";
+        let (header, len) = complete(buf);
+
+        assert_eq!(header.source, Some("203.0.113.7:51234".parse().unwrap()));
+        assert_eq!(header.destination, Some("192.0.2.1:443".parse().unwrap()));
+        assert_eq!(&buf[len..], b"GET / HTTP/1.1\r\n");
+    }
+
+    #[test]
+    fn parses_v1_tcp6_and_unknown() {
+        let (header, _) = complete(b"PROXY TCP6 2001:db8::1 2001:db8::2 4000 80\r\n");
+        assert_eq!(header.source, Some("[2001:db8::1]:4000".parse().unwrap()));
+
+        let (header, len) = complete(b"PROXY UNKNOWN\r\n");
+        assert_eq!(header.source, None);
+        assert_eq!(len, 15);
+    }
+
+    #[test]
+    fn v1_rejects_malformed_headers() {
+        assert!(parse(b"PROXY TCP4 2001:db8::1 192.0.2.1 1 2\r\n").is_err());
+        assert!(parse(b"PROXY TCP4 203.0.113.7 192.0.2.1 99999 443\r\n").is_err());
+        assert!(parse(b"PROXY TCP5 a b c d\r\n").is_err());
+        assert!(parse(b"GET / HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse(&[b"PROXY TCP4 ".as_slice(), &[b'1'; 120]].concat()).is_err());
+    }
+
+    #[test]
+    fn partial_headers_are_incomplete() {
+        assert_eq!(parse(b"PRO").unwrap(), ParseResult::Incomplete);
+        assert_eq!(
+            parse(b"PROXY TCP4 203.0.113.7").unwrap(),
+            ParseResult::Incomplete
+        );
+        assert_eq!(parse(&V2_SIGNATURE[..5]).unwrap(), ParseResult::Incomplete);
+    }
+
+    fn v2_header(command: u8, family: u8, payload: &[u8]) -> Vec<u8> {
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.push(0x20 | command);
+        buf.push(family);
+        buf.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn parses_v2_ipv4_with_tlvs() {
+        let mut payload = vec![203, 0, 113, 7, 192, 0, 2, 1];
+        payload.extend_from_slice(&51234u16.to_be_bytes());
+        payload.extend_from_slice(&443u16.to_be_bytes());
+        // PP2_TYPE_AUTHORITY TLV, ignored
+        payload.extend_from_slice(&[0x02, 0x00, 0x03, b'a', b'b', b'c']);
+        let mut buf = v2_header(0x1, 0x11, &payload);
+        buf.extend_from_slice(b"GET /");
+
+        let (header, len) = complete(&buf);
+        assert_eq!(header.source, Some("203.0.113.7:51234".parse().unwrap()));
+        assert_eq!(&buf[len..], b"GET /");
+
+        assert_eq!(parse(&buf[..20]).unwrap(), ParseResult::Incomplete);
+    }
+
+    #[test]
+    fn parses_v2_ipv6_and_local() {
+        let src: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let dst: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        let mut payload = src.octets().to_vec();
+        payload.extend_from_slice(&dst.octets());
+        payload.extend_from_slice(&4000u16.to_be_bytes());
+        payload.extend_from_slice(&80u16.to_be_bytes());
+
+        let (header, _) = complete(&v2_header(0x1, 0x21, &payload));
+        assert_eq!(header.source, Some("[2001:db8::1]:4000".parse().unwrap()));
+
+        let (header, len) = complete(&v2_header(0x0, 0x00, &[]));
+        assert_eq!(header.source, None);
+        assert_eq!(len, 16);
+    }
+
+    #[test]
+    fn v2_rejects_truncated_addresses() {
+        assert!(parse(&v2_header(0x1, 0x11, &[1, 2, 3])).is_err());
+    }
+
+    #[tokio::test]
+    async fn acceptor_strips_header_and_records_source() {
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            true,
+            false,
+            None,
+            16,
+        ));
+
+        let mut client = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        client
+            .write_all(b"PROXY TCP4 203.0.113.7 192.0.2.1 51234 443\r\nping")
+            .await
+            .unwrap();
+
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut data = [0u8; 4];
+        accepted.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"ping");
+
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+        assert_eq!(
+            connection.source,
+            Some("203.0.113.7:51234".parse().unwrap())
+        );
+        assert_eq!(connection.listener_address, public.to_string());
+    }
+
+    #[tokio::test]
+    async fn acceptor_records_bot_signals_without_proxy_header() {
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            false,
+            true,
+            None,
+            16,
+        ));
+
+        let mut client = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut data = [0u8; 16];
+        accepted.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"GET / HTTP/1.1\r\n");
+
+        // The socket peer is the client; plain HTTP/1 has no fingerprints
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+        assert_eq!(connection.source, Some(client.local_addr().unwrap()));
+        assert_eq!(connection.bot_signals, Some(BotSignals::default()));
+    }
+
+    #[tokio::test]
+    async fn acceptor_limits_concurrent_connections() {
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            false,
+            false,
+            None,
+            1,
+        ));
+
+        let first = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let (accepted, _) = internal.accept().await.unwrap();
+
+        // The second client waits in the backlog until the first one closes
+        let _second = TcpStream::connect(public).await.unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(200), internal.accept()).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        drop(accepted);
+        tokio::time::timeout(Duration::from_secs(5), internal.accept())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn acceptor_terminates_tls_and_splices_plaintext() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = certified.cert.der().clone();
+        let key = rustls::pki_types::PrivateKeyDer::try_from(certified.signing_key.serialize_der())
+            .unwrap();
+        let server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.clone()], key)
+            .unwrap();
+
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            false,
+            false,
+            Some(Arc::new(server_config)),
+            16,
+        ));
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let tcp = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut client = connector.connect(server_name, tcp).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        client.flush().await.unwrap();
+
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut data = [0u8; 4];
+        accepted.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"ping");
+
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+        assert!(connection.tls);
+    }
+
+    #[tokio::test]
+    async fn acceptor_fingerprints_http2_over_tls() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = certified.cert.der().clone();
+        let key = rustls::pki_types::PrivateKeyDer::try_from(certified.signing_key.serialize_der())
+            .unwrap();
+        let mut server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.clone()], key)
+            .unwrap();
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            false,
+            true,
+            Some(Arc::new(server_config)),
+            16,
+        ));
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let mut client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_config.alpn_protocols = vec![b"h2".to_vec()];
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let tcp = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut client = connector.connect(server_name, tcp).await.unwrap();
+
+        fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+            let mut out = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+            out.extend_from_slice(&[kind, flags]);
+            out.extend_from_slice(&stream_id.to_be_bytes());
+            out.extend_from_slice(payload);
+            out
+        }
+        // SETTINGS, WINDOW_UPDATE, PRIORITY, then HEADERS for GET https://example.com/
+        let mut data = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+        data.extend(frame(
+            0x4,
+            0,
+            0,
+            b"\x00\x01\x00\x01\x00\x00\x00\x04\x00\x60\x00\x00",
+        ));
+        data.extend(frame(0x8, 0, 0, &15_663_105u32.to_be_bytes()));
+        data.extend(frame(0x2, 0, 3, b"\x00\x00\x00\x00\xc8"));
+        data.extend(frame(
+            0x1,
+            0x4 | 0x1,
+            1,
+            b"\x82\x41\x0bexample.com\x87\x84\x7a\x04curl",
+        ));
+        client.write_all(&data).await.unwrap();
+        client.flush().await.unwrap();
+
+        // The frames read for the fingerprint are still forwarded
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut forwarded = vec![0u8; data.len()];
+        accepted.read_exact(&mut forwarded).await.unwrap();
+        assert_eq!(forwarded, data);
+
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+        let signals = connection.bot_signals.unwrap();
+        assert!(signals.ja4.is_some());
+        assert_eq!(
+            signals.http2.as_deref(),
+            Some("1:65536;4:6291456|15663105|3:0:0:201|m,a,s,p")
+        );
+    }
+
+    #[tokio::test]
+    async fn acceptor_records_verified_client_certificate() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let fixtures =
+            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/tls");
+        let tls_config = zentinel_config::TlsConfig {
+            cert_file: Some(fixtures.join("server-default.crt")),
+            key_file: Some(fixtures.join("server-default.key")),
+            additional_certs: vec![],
+            ca_file: Some(fixtures.join("ca.crt")),
+            min_version: zentinel_common::types::TlsVersion::Tls12,
+            max_version: None,
+            cipher_suites: vec![],
+            client_auth: true,
+            client_cert_header: None,
+            ocsp_stapling: false,
+            session_resumption: true,
+            acme: None,
+        };
+        let resolver = Arc::new(
+            crate::tls::HotReloadableSniResolver::from_config(tls_config.clone(), "test").unwrap(),
+        );
+        let server_config = crate::tls::build_server_config(&tls_config, resolver).unwrap();
+
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let public = free_address();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address.clone(),
+            false,
+            false,
+            Some(Arc::new(server_config)),
+            16,
+        ));
+
+        let read_pem = |name: &str| std::fs::read(fixtures.join(name)).unwrap();
+        let client_cert: Vec<_> = rustls_pemfile::certs(&mut &read_pem("client.crt")[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let client_key = rustls_pemfile::private_key(&mut &read_pem("client.key")[..])
+            .unwrap()
+            .unwrap();
+        let mut roots = rustls::RootCertStore::empty();
+        for ca in rustls_pemfile::certs(&mut &read_pem("ca.crt")[..]) {
+            roots.add(ca.unwrap()).unwrap();
+        }
+        let client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_client_auth_cert(client_cert.clone(), client_key)
+            .unwrap();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let tcp = loop {
+            match TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut client = connector.connect(server_name, tcp).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        client.flush().await.unwrap();
+
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut data = [0u8; 4];
+        accepted.read_exact(&mut data).await.unwrap();
+
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+        assert!(connection.tls);
+        assert!(connection.client_cert_verified);
+        let der = connection.client_cert.unwrap();
+        assert_eq!(der, client_cert[0].to_vec());
+
+        let info =
+            crate::tls::client_certificate_info(&der, connection.client_cert_verified).unwrap();
+        assert!(info.subject.unwrap().contains("CN=test-client"));
+        assert!(info.verified);
+    }
+
+    #[tokio::test]
+    async fn internal_connections_are_registered_while_open() {
+        let internal_address = internal_listener_path().unwrap();
+        let internal = UnixListener::bind(&internal_address).unwrap();
+        let connection = ProxiedConnection {
+            source: Some("203.0.113.7:51234".parse().unwrap()),
+            listener_address: "0.0.0.0:8080".to_string(),
+            protocol: None,
+            tls: false,
+            server_name: None,
+            client_cert: None,
+            client_cert_verified: false,
+            bot_signals: None,
+        };
+
+        let (_stream, registration) = connect_internal(&internal_address, connection)
+            .await
+            .unwrap();
+        let (_accepted, peer) = internal.accept().await.unwrap();
+        let peer = peer.as_pathname().unwrap().to_path_buf();
+        assert!(lookup(&peer).is_some());
+        // The client socket file is removed once connected
+        assert!(!peer.exists());
+
+        drop(registration);
+        assert!(lookup(&peer).is_none());
+
+        // A direct connection to the internal listener is never registered
+        let _direct = UnixStream::connect(&internal_address).await.unwrap();
+        let (_accepted, peer) = internal.accept().await.unwrap();
+        assert!(peer.as_pathname().is_none_or(|path| lookup(path).is_none()));
+    }
+}
@@ -0,0 +1,138 @@
+//! Request coalescing for cache misses.
+//!
+//! When many clients ask for the same uncached resource at once, only the
+//! first request goes upstream. The others wait on the cache lock for the
+//! same cache key and, once the response has been stored, are served from
+//! the cache. A response that turns out to be uncacheable releases the
+//! waiters, which then fetch from upstream themselves.
+//!
+//! Each route bounds the queue with `cache { coalesce { ... } }`:
+//! `max-waiters` requests may wait behind one fetch, and further requests
+//! for the key skip the lock and go upstream directly. Waiters give up after
+//! `timeout-secs`, by default the global cache `lock-timeout-secs`.
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use pingora_cache::lock::CacheLock;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// Cache locks with a route-specific timeout, one per distinct timeout
+static COALESCE_LOCKS: LazyLock<Mutex<HashMap<u64, &'static CacheLock>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Cache lock that makes waiters give up after `timeout_secs`, or after the
+/// global lock timeout when `None`.
+pub fn coalesce_lock(timeout_secs: Option<u64>) -> &'static CacheLock {
+    match timeout_secs {
+        None => crate::cache::get_cache_lock(),
+        Some(secs) => *COALESCE_LOCKS.lock().entry(secs).or_insert_with(|| {
+            // Timeouts come from the configuration, so only a handful leak
+            Box::leak(Box::new(CacheLock::new(Duration::from_secs(secs))))
+        }),
+    }
+}
+
+/// Counts the requests queued behind each cache key.
+#[derive(Debug, Default)]
+pub struct RequestCoalescer {
+    in_flight: Arc<DashMap<String, usize>>,
+}
+
+impl RequestCoalescer {
+    /// Create an empty coalescer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a request for `key` behind the request fetching it.
+    ///
+    /// Returns `None` when `max_waiters` requests already wait; the request
+    /// should then fetch from upstream without taking the cache lock.
+    pub fn join(&self, key: &str, max_waiters: usize) -> Option<CoalesceTicket> {
+        let mut queued = self.in_flight.entry(key.to_string()).or_insert(0);
+        // The first request fetches, the others wait
+        if *queued > max_waiters {
+            return None;
+        }
+        *queued += 1;
+
+        Some(CoalesceTicket {
+            in_flight: Arc::clone(&self.in_flight),
+            key: key.to_string(),
+        })
+    }
+
+    /// Requests currently queued for `key`, including the one fetching it
+    pub fn queued(&self, key: &str) -> usize {
+        self.in_flight.get(key).map(|n| *n).unwrap_or(0)
+    }
+}
+
+/// A request's place in the queue for a cache key, given up on drop.
+#[derive(Debug)]
+pub struct CoalesceTicket {
+    in_flight: Arc<DashMap<String, usize>>,
+    key: String,
+}
+
+impl Drop for CoalesceTicket {
+    fn drop(&mut self) {
+        if let Entry::Occupied(mut entry) = self.in_flight.entry(std::mem::take(&mut self.key)) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_caps_waiters() {
+        let coalescer = RequestCoalescer::new();
+
+        // One fetching request plus two waiters
+        let fetch = coalescer.join("GET:a:/x", 2).unwrap();
+        let waiters: Vec<_> = (0..2)
+            .map(|_| coalescer.join("GET:a:/x", 2).unwrap())
+            .collect();
+        assert!(coalescer.join("GET:a:/x", 2).is_none());
+        assert_eq!(coalescer.queued("GET:a:/x"), 3);
+
+        // Other keys have their own queue
+        assert!(coalescer.join("GET:a:/y", 2).is_some());
+
+        drop(fetch);
+        assert!(coalescer.join("GET:a:/x", 2).is_some());
+
+        drop(waiters);
+        assert_eq!(coalescer.queued("GET:a:/x"), 0);
+        assert!(coalescer.in_flight.is_empty());
+    }
+
+    #[test]
+    fn test_zero_waiters_only_lets_the_fetch_through() {
+        let coalescer = RequestCoalescer::new();
+        let _fetch = coalescer.join("k", 0).unwrap();
+        assert!(coalescer.join("k", 0).is_none());
+    }
+
+    #[test]
+    fn test_coalesce_lock_is_shared_per_timeout() {
+        assert!(std::ptr::eq(coalesce_lock(Some(3)), coalesce_lock(Some(3))));
+        assert!(!std::ptr::eq(
+            coalesce_lock(Some(3)),
+            coalesce_lock(Some(4))
+        ));
+        assert!(std::ptr::eq(
+            coalesce_lock(None),
+            crate::cache::get_cache_lock()
+        ));
+    }
+}
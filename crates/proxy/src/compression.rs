@@ -0,0 +1,330 @@
+//! Streaming response compression for the `compress` filter.
+//!
+//! The encoding is negotiated from the client's `Accept-Encoding` header,
+//! honouring q-values, among the filter's `algorithms`; when the client
+//! rates several equally the filter's order decides. Every response body
+//! chunk is compressed and flushed as it passes through, so bodies are never
+//! buffered whole and streamed responses reach the client without delay.
+//!
+//! Bytes before and after compression are counted in
+//! `zentinel_response_compression_bytes_total{algorithm, stage}`.
+
+use bytes::Bytes;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::io::{self, Write};
+use std::sync::{Arc, LazyLock};
+
+use zentinel_config::CompressionAlgorithm;
+
+/// Response body bytes before (`original`) and after (`compressed`) compression
+static COMPRESSION_BYTES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_response_compression_bytes_total",
+        "Response body bytes before and after compression",
+        &["algorithm", "stage"]
+    )
+    .ok()
+});
+
+/// `Content-Encoding` token for an algorithm
+pub fn encoding_token(algorithm: CompressionAlgorithm) -> &'static str {
+    match algorithm {
+        CompressionAlgorithm::Gzip => "gzip",
+        CompressionAlgorithm::Brotli => "br",
+        CompressionAlgorithm::Deflate => "deflate",
+        CompressionAlgorithm::Zstd => "zstd",
+    }
+}
+
+/// Pick the response encoding for an `Accept-Encoding` header.
+///
+/// Returns the algorithm with the highest q-value, or `None` when the
+/// client accepts none of `algorithms`. `q=0` rules an encoding out and `*`
+/// stands for every encoding not listed.
+pub fn negotiate_encoding(
+    accept_encoding: &str,
+    algorithms: &[CompressionAlgorithm],
+) -> Option<CompressionAlgorithm> {
+    let preferences: Vec<(&str, f32)> = accept_encoding
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let token = parts.next()?.trim();
+            if token.is_empty() {
+                return None;
+            }
+            let q = parts.find_map(|param| {
+                let param = param.trim();
+                param
+                    .strip_prefix("q=")
+                    .or_else(|| param.strip_prefix("Q="))
+            });
+            let q = match q {
+                Some(q) => q.trim().parse::<f32>().ok()?,
+                None => 1.0,
+            };
+            Some((token, q))
+        })
+        .collect();
+
+    let quality = |token: &str| {
+        let listed = |t: &str| {
+            t.eq_ignore_ascii_case(token) || (token == "gzip" && t.eq_ignore_ascii_case("x-gzip"))
+        };
+        preferences
+            .iter()
+            .find(|(t, _)| listed(t))
+            .or_else(|| preferences.iter().find(|(t, _)| *t == "*"))
+            .map_or(0.0, |(_, q)| *q)
+    };
+
+    let mut best: Option<(CompressionAlgorithm, f32)> = None;
+    for &algorithm in algorithms {
+        let q = quality(encoding_token(algorithm));
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((algorithm, q));
+        }
+    }
+    best.map(|(algorithm, _)| algorithm)
+}
+
+/// Encoder output, drained after every chunk
+#[derive(Clone, Default)]
+struct OutputBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl OutputBuffer {
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock()))
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Encoder {
+    Gzip(GzEncoder<OutputBuffer>),
+    Deflate(ZlibEncoder<OutputBuffer>),
+    Brotli(Box<brotli::CompressorWriter<OutputBuffer>>),
+    Zstd(zstd::stream::write::Encoder<'static, OutputBuffer>),
+}
+
+impl Encoder {
+    fn new(algorithm: CompressionAlgorithm, level: u8, output: OutputBuffer) -> io::Result<Self> {
+        Ok(match algorithm {
+            CompressionAlgorithm::Gzip => Self::Gzip(GzEncoder::new(
+                output,
+                Compression::new(u32::from(level.clamp(1, 9))),
+            )),
+            CompressionAlgorithm::Deflate => Self::Deflate(ZlibEncoder::new(
+                output,
+                Compression::new(u32::from(level.clamp(1, 9))),
+            )),
+            CompressionAlgorithm::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                output,
+                4096,
+                u32::from(level.min(11)),
+                22,
+            ))),
+            CompressionAlgorithm::Zstd => Self::Zstd(zstd::stream::write::Encoder::new(
+                output,
+                i32::from(level.clamp(1, 22)),
+            )?),
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Gzip(encoder) => encoder,
+            Self::Deflate(encoder) => encoder,
+            Self::Brotli(encoder) => encoder.as_mut(),
+            Self::Zstd(encoder) => encoder,
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Gzip(encoder) => encoder.finish().map(drop),
+            Self::Deflate(encoder) => encoder.finish().map(drop),
+            // Dropping the writer finishes the brotli stream
+            Self::Brotli(encoder) => {
+                drop(encoder);
+                Ok(())
+            }
+            Self::Zstd(encoder) => encoder.finish().map(drop),
+        }
+    }
+}
+
+/// Compresses one response body as it streams through
+pub struct ResponseCompressor {
+    algorithm: CompressionAlgorithm,
+    /// `None` once the stream has been finished
+    encoder: Option<Encoder>,
+    output: OutputBuffer,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+impl ResponseCompressor {
+    /// Create a compressor. `level` is clamped to the algorithm's range.
+    pub fn new(algorithm: CompressionAlgorithm, level: u8) -> io::Result<Self> {
+        let output = OutputBuffer::default();
+        Ok(Self {
+            algorithm,
+            encoder: Some(Encoder::new(algorithm, level, output.clone())?),
+            output,
+            bytes_in: 0,
+            bytes_out: 0,
+        })
+    }
+
+    /// Negotiated algorithm
+    pub fn algorithm(&self) -> CompressionAlgorithm {
+        self.algorithm
+    }
+
+    /// Compress a body chunk in place.
+    ///
+    /// The compressed output of every chunk is flushed so it can be sent
+    /// right away; at the end of the stream the encoder is finished.
+    pub fn process(&mut self, body: &mut Option<Bytes>, end_of_stream: bool) -> io::Result<()> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(());
+        };
+
+        if let Some(chunk) = body.take() {
+            self.bytes_in += chunk.len() as u64;
+            encoder.writer().write_all(&chunk)?;
+        }
+        if end_of_stream {
+            if let Some(encoder) = self.encoder.take() {
+                encoder.finish()?;
+            }
+        } else {
+            encoder.writer().flush()?;
+        }
+
+        let compressed = self.output.take();
+        self.bytes_out += compressed.len() as u64;
+        if !compressed.is_empty() {
+            *body = Some(compressed);
+        }
+        if end_of_stream {
+            self.record();
+        }
+        Ok(())
+    }
+
+    fn record(&self) {
+        if let Some(counter) = COMPRESSION_BYTES.as_ref() {
+            let algorithm = encoding_token(self.algorithm);
+            counter
+                .with_label_values(&[algorithm, "original"])
+                .inc_by(self.bytes_in);
+            counter
+                .with_label_values(&[algorithm, "compressed"])
+                .inc_by(self.bytes_out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const ALL: [CompressionAlgorithm; 4] = [
+        CompressionAlgorithm::Zstd,
+        CompressionAlgorithm::Brotli,
+        CompressionAlgorithm::Gzip,
+        CompressionAlgorithm::Deflate,
+    ];
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(
+            negotiate_encoding("gzip, deflate, br, zstd", &ALL),
+            Some(CompressionAlgorithm::Zstd)
+        );
+        assert_eq!(
+            negotiate_encoding("gzip;q=1.0, br;q=0.8", &ALL),
+            Some(CompressionAlgorithm::Gzip)
+        );
+        assert_eq!(
+            negotiate_encoding("*;q=0.5, zstd;q=0", &ALL),
+            Some(CompressionAlgorithm::Brotli)
+        );
+        assert_eq!(
+            negotiate_encoding("x-gzip", &ALL),
+            Some(CompressionAlgorithm::Gzip)
+        );
+        assert_eq!(negotiate_encoding("identity", &ALL), None);
+        assert_eq!(negotiate_encoding("br;q=0", &ALL), None);
+        assert_eq!(negotiate_encoding("br;q=high", &ALL), None);
+    }
+
+    fn decompress(algorithm: CompressionAlgorithm, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        match algorithm {
+            CompressionAlgorithm::Gzip => {
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut output)
+                    .unwrap();
+            }
+            CompressionAlgorithm::Deflate => {
+                flate2::read::ZlibDecoder::new(data)
+                    .read_to_end(&mut output)
+                    .unwrap();
+            }
+            CompressionAlgorithm::Brotli => {
+                brotli::Decompressor::new(data, 4096)
+                    .read_to_end(&mut output)
+                    .unwrap();
+            }
+            CompressionAlgorithm::Zstd => output = zstd::decode_all(data).unwrap(),
+        }
+        output
+    }
+
+    #[test]
+    fn test_streaming_round_trip() {
+        let chunks = [
+            "data: first event\n\n".repeat(20),
+            "data: second event\n\n".repeat(20),
+        ];
+
+        for algorithm in ALL {
+            let mut compressor = ResponseCompressor::new(algorithm, 6).unwrap();
+            let mut compressed = Vec::new();
+
+            for chunk in &chunks {
+                let mut body = Some(Bytes::from(chunk.clone()));
+                compressor.process(&mut body, false).unwrap();
+                // Each chunk is flushed rather than held back
+                let body = body.expect("chunk output is flushed");
+                compressed.extend_from_slice(&body);
+            }
+            let mut body = None;
+            compressor.process(&mut body, true).unwrap();
+            compressed.extend_from_slice(&body.unwrap_or_default());
+
+            assert_eq!(
+                decompress(algorithm, &compressed),
+                chunks.concat().into_bytes(),
+                "{algorithm:?} round trip"
+            );
+            assert!(compressed.len() < chunks.concat().len());
+        }
+    }
+}
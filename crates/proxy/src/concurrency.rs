@@ -0,0 +1,520 @@
+//! In-flight request limits and adaptive load shedding.
+//!
+//! [`ConcurrencyManager`] caps the number of proxied requests in flight, for
+//! all routes together (`server { concurrency { ... } }`) and per route
+//! (`policies { concurrency { ... } }`). A request must fit under both
+//! limits; otherwise it is shed with 503 before agents run or an upstream is
+//! selected. Slots are released when the request's [`ConcurrencyPermit`] is
+//! dropped.
+//!
+//! With `adaptive` set, the effective limit moves between `min-limit` and
+//! `max-in-flight` following a gradient algorithm: each latency window is
+//! compared with a long-term baseline, and the limit shrinks as latency
+//! rises above `tolerance` times the baseline. This sheds load while the
+//! upstream is still answering, before queues build up. Only successful
+//! responses (2xx-4xx) are sampled, so fast failures do not pull the limit
+//! up.
+//!
+//! Limiters keep their in-flight counts across config reloads. Route
+//! limiters are created on the first request to the route and pick up
+//! config changes on the next request.
+
+use bytes::Bytes;
+use http::{header, Response, StatusCode};
+use http_body_util::Full;
+use parking_lot::{Mutex, RwLock};
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use zentinel_config::{AdaptiveConcurrencyConfig, ConcurrencyLimitConfig};
+
+/// Prometheus metrics labelled by limiter scope (`global` or `route:<id>`).
+struct ConcurrencyMetrics {
+    /// Requests currently in flight
+    in_flight: IntGaugeVec,
+    /// Current effective limit
+    limit: IntGaugeVec,
+    /// Requests shed, per reason
+    shed: IntCounterVec,
+}
+
+static CONCURRENCY_METRICS: LazyLock<Option<ConcurrencyMetrics>> = LazyLock::new(|| {
+    let in_flight = register_int_gauge_vec!(
+        "zentinel_concurrency_in_flight",
+        "Requests currently in flight per concurrency limit",
+        &["scope"]
+    )
+    .ok()?;
+    let limit = register_int_gauge_vec!(
+        "zentinel_concurrency_limit",
+        "Current in-flight limit, including adaptive adjustments",
+        &["scope"]
+    )
+    .ok()?;
+    let shed = register_int_counter_vec!(
+        "zentinel_concurrency_shed_total",
+        "Requests shed by concurrency limits",
+        &["scope", "reason"]
+    )
+    .ok()?;
+    Some(ConcurrencyMetrics {
+        in_flight,
+        limit,
+        shed,
+    })
+});
+
+const DEFAULT_SHED_BODY: &str = "Service overloaded, please retry later\n";
+
+/// Weight of each window in the long-term latency baseline (roughly the
+/// last 50 windows)
+const BASELINE_WEIGHT: f64 = 0.02;
+
+/// Gradient-based limit estimate.
+struct GradientState {
+    config: AdaptiveConcurrencyConfig,
+    max_limit: u32,
+    estimated: f64,
+    /// Long-term average latency in seconds (0 until the first window)
+    baseline: f64,
+    window_start: Instant,
+    window_sum: f64,
+    window_count: u64,
+    /// Highest in-flight count seen during the window
+    window_peak: u32,
+}
+
+impl GradientState {
+    fn new(config: &AdaptiveConcurrencyConfig, max_limit: u32) -> Self {
+        let min_limit = config.min_limit.clamp(1, max_limit);
+        Self {
+            config: config.clone(),
+            max_limit,
+            estimated: config.initial_limit.clamp(min_limit, max_limit) as f64,
+            baseline: 0.0,
+            window_start: Instant::now(),
+            window_sum: 0.0,
+            window_count: 0,
+            window_peak: 0,
+        }
+    }
+
+    fn limit(&self) -> u32 {
+        self.estimated as u32
+    }
+
+    /// Add a latency sample. Returns the new limit when a window closes.
+    fn sample(&mut self, latency: Duration, in_flight: u32, now: Instant) -> Option<u32> {
+        self.window_sum += latency.as_secs_f64();
+        self.window_count += 1;
+        self.window_peak = self.window_peak.max(in_flight);
+
+        if now.duration_since(self.window_start) < Duration::from_millis(self.config.window_ms) {
+            return None;
+        }
+
+        let current = self.window_sum / self.window_count as f64;
+        let peak = self.window_peak;
+        self.window_start = now;
+        self.window_sum = 0.0;
+        self.window_count = 0;
+        self.window_peak = 0;
+
+        if self.baseline == 0.0 {
+            self.baseline = current;
+        } else {
+            self.baseline = self.baseline * (1.0 - BASELINE_WEIGHT) + current * BASELINE_WEIGHT;
+            // Let the baseline follow latency back down after a slow period
+            if self.baseline / current > 2.0 {
+                self.baseline *= 0.95;
+            }
+        }
+
+        // Too little traffic to tell whether a higher limit would hold
+        if (peak as f64) < self.estimated / 2.0 {
+            return Some(self.limit());
+        }
+
+        let gradient = if current > 0.0 {
+            (self.config.tolerance * self.baseline / current).clamp(0.5, 1.0)
+        } else {
+            1.0
+        };
+        let target = self.estimated * gradient + self.estimated.sqrt();
+        let min_limit = self.config.min_limit.clamp(1, self.max_limit) as f64;
+        self.estimated = (self.estimated * (1.0 - self.config.smoothing)
+            + target * self.config.smoothing)
+            .clamp(min_limit, self.max_limit as f64);
+
+        Some(self.limit())
+    }
+}
+
+/// One global or per-route limit.
+///
+/// Kept across reloads (the config is updated in place) so in-flight counts
+/// are never reset by a config change.
+struct Limiter {
+    scope: String,
+    config: RwLock<ConcurrencyLimitConfig>,
+    in_flight: AtomicU32,
+    /// Effective limit: `max_in_flight`, or the adaptive estimate
+    limit: AtomicU32,
+    gradient: Mutex<Option<GradientState>>,
+}
+
+impl Limiter {
+    fn new(scope: String, config: &ConcurrencyLimitConfig) -> Self {
+        let limiter = Self {
+            scope,
+            config: RwLock::new(config.clone()),
+            in_flight: AtomicU32::new(0),
+            limit: AtomicU32::new(0),
+            gradient: Mutex::new(None),
+        };
+        limiter.reset(config);
+        limiter
+    }
+
+    fn set_config(&self, config: &ConcurrencyLimitConfig) {
+        if *self.config.read() == *config {
+            return;
+        }
+        *self.config.write() = config.clone();
+        self.reset(config);
+    }
+
+    fn reset(&self, config: &ConcurrencyLimitConfig) {
+        let gradient = config
+            .adaptive
+            .as_ref()
+            .map(|adaptive| GradientState::new(adaptive, config.max_in_flight));
+        let limit = gradient
+            .as_ref()
+            .map_or(config.max_in_flight, GradientState::limit);
+        *self.gradient.lock() = gradient;
+        self.set_limit(limit);
+    }
+
+    fn set_limit(&self, limit: u32) {
+        self.limit.store(limit, Ordering::Release);
+        if let Some(metrics) = CONCURRENCY_METRICS.as_ref() {
+            metrics
+                .limit
+                .with_label_values(&[&self.scope])
+                .set(limit as i64);
+        }
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Result<Slot, &'static str> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+        let limit = self.limit.load(Ordering::Acquire);
+        if in_flight > limit {
+            self.in_flight.fetch_sub(1, Ordering::AcqRel);
+            let reason = if in_flight > self.config.read().max_in_flight {
+                "max_in_flight"
+            } else {
+                "adaptive"
+            };
+            if let Some(metrics) = CONCURRENCY_METRICS.as_ref() {
+                metrics.shed.with_label_values(&[&self.scope, reason]).inc();
+            }
+            return Err(reason);
+        }
+        self.report_in_flight(in_flight);
+        Ok(Slot {
+            limiter: Arc::clone(self),
+        })
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        let in_flight = self.in_flight.load(Ordering::Acquire);
+        let mut gradient = self.gradient.lock();
+        let Some(state) = gradient.as_mut() else {
+            return;
+        };
+        if let Some(limit) = state.sample(latency, in_flight, Instant::now()) {
+            if limit != self.limit.load(Ordering::Acquire) {
+                debug!(
+                    scope = %self.scope,
+                    limit = limit,
+                    baseline_ms = state.baseline * 1000.0,
+                    "Adaptive concurrency limit changed"
+                );
+            }
+            self.set_limit(limit);
+        }
+    }
+
+    fn report_in_flight(&self, in_flight: u32) {
+        if let Some(metrics) = CONCURRENCY_METRICS.as_ref() {
+            metrics
+                .in_flight
+                .with_label_values(&[&self.scope])
+                .set(in_flight as i64);
+        }
+    }
+
+    fn shed_response(&self) -> Response<Full<Bytes>> {
+        let config = self.config.read();
+        let body = match config.shed_body {
+            Some(ref body) => Bytes::from(body.clone()),
+            None => Bytes::from_static(DEFAULT_SHED_BODY.as_bytes()),
+        };
+        let mut builder = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(header::CONTENT_TYPE, config.shed_content_type.as_str())
+            .header(header::CONTENT_LENGTH, body.len())
+            .header(header::CACHE_CONTROL, "no-store");
+        if let Some(secs) = config.retry_after_secs {
+            builder = builder.header(header::RETRY_AFTER, secs);
+        }
+        builder.body(Full::new(body)).unwrap_or_else(|_| {
+            // Only reachable with an invalid shed-content-type
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(Full::new(Bytes::from_static(DEFAULT_SHED_BODY.as_bytes())))
+                .expect("static response builder cannot fail")
+        })
+    }
+}
+
+/// In-flight slot of one limiter, released on drop.
+struct Slot {
+    limiter: Arc<Limiter>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let in_flight = self.limiter.in_flight.fetch_sub(1, Ordering::AcqRel) - 1;
+        self.limiter.report_in_flight(in_flight);
+    }
+}
+
+/// Outcome of admitting a request against the concurrency limits.
+pub enum ConcurrencyAdmission {
+    /// The request may proceed; hold the permit until it completes.
+    Admitted(ConcurrencyPermit),
+    /// The request was shed; send `response`.
+    Shed {
+        /// Limiter that shed the request (`global` or `route:<id>`)
+        scope: String,
+        /// Effective limit at the time
+        limit: u32,
+        /// `max_in_flight` or `adaptive`
+        reason: &'static str,
+        /// 503 response with the configured body
+        response: Response<Full<Bytes>>,
+    },
+}
+
+/// In-flight slots of a request, released on drop.
+pub struct ConcurrencyPermit {
+    global: Option<Slot>,
+    route: Option<Slot>,
+    started: Instant,
+}
+
+impl ConcurrencyPermit {
+    /// Release the slots, feeding the request's latency to adaptive limits
+    /// when it completed successfully.
+    pub fn finish(self, success: bool) {
+        if !success {
+            return;
+        }
+        let latency = self.started.elapsed();
+        for slot in [&self.global, &self.route].into_iter().flatten() {
+            slot.limiter.record_latency(latency);
+        }
+    }
+}
+
+/// Global and per-route in-flight limits.
+pub struct ConcurrencyManager {
+    global: RwLock<Option<Arc<Limiter>>>,
+    routes: RwLock<HashMap<String, Arc<Limiter>>>,
+}
+
+impl ConcurrencyManager {
+    /// Create a manager with the server-wide limit, if any.
+    pub fn new(global: Option<&ConcurrencyLimitConfig>) -> Self {
+        let manager = Self {
+            global: RwLock::new(None),
+            routes: RwLock::new(HashMap::new()),
+        };
+        manager.reload(global);
+        manager
+    }
+
+    /// Apply a new server-wide limit. The in-flight count is kept.
+    pub fn reload(&self, global: Option<&ConcurrencyLimitConfig>) {
+        let mut current = self.global.write();
+        match (current.as_ref(), global) {
+            (Some(limiter), Some(config)) => limiter.set_config(config),
+            (None, Some(config)) => {
+                info!(
+                    max_in_flight = config.max_in_flight,
+                    adaptive = config.adaptive.is_some(),
+                    "Global concurrency limit configured"
+                );
+                *current = Some(Arc::new(Limiter::new("global".to_string(), config)));
+            }
+            (_, None) => *current = None,
+        }
+    }
+
+    /// Admit a request against the global limit and its route's limit.
+    pub fn admit(
+        &self,
+        route_id: Option<&str>,
+        route_config: Option<&ConcurrencyLimitConfig>,
+    ) -> ConcurrencyAdmission {
+        let started = Instant::now();
+
+        let global = self.global.read().clone();
+        let global = match global {
+            Some(limiter) => match limiter.try_acquire() {
+                Ok(slot) => Some(slot),
+                Err(reason) => return shed(&limiter, reason),
+            },
+            None => None,
+        };
+
+        let route = match (route_id, route_config) {
+            (Some(route_id), Some(config)) => {
+                let limiter = self.route_limiter(route_id, config);
+                match limiter.try_acquire() {
+                    Ok(slot) => Some(slot),
+                    // Dropping the global slot releases it
+                    Err(reason) => return shed(&limiter, reason),
+                }
+            }
+            _ => None,
+        };
+
+        ConcurrencyAdmission::Admitted(ConcurrencyPermit {
+            global,
+            route,
+            started,
+        })
+    }
+
+    fn route_limiter(&self, route_id: &str, config: &ConcurrencyLimitConfig) -> Arc<Limiter> {
+        if let Some(limiter) = self.routes.read().get(route_id) {
+            limiter.set_config(config);
+            return Arc::clone(limiter);
+        }
+        let mut routes = self.routes.write();
+        let limiter = routes
+            .entry(route_id.to_string())
+            .or_insert_with(|| Arc::new(Limiter::new(format!("route:{}", route_id), config)));
+        limiter.set_config(config);
+        Arc::clone(limiter)
+    }
+}
+
+fn shed(limiter: &Limiter, reason: &'static str) -> ConcurrencyAdmission {
+    ConcurrencyAdmission::Shed {
+        scope: limiter.scope.clone(),
+        limit: limiter.limit.load(Ordering::Acquire),
+        reason,
+        response: limiter.shed_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admitted(admission: ConcurrencyAdmission) -> ConcurrencyPermit {
+        match admission {
+            ConcurrencyAdmission::Admitted(permit) => permit,
+            ConcurrencyAdmission::Shed { scope, .. } => panic!("shed by {}", scope),
+        }
+    }
+
+    #[test]
+    fn test_global_and_route_limits() {
+        let manager = ConcurrencyManager::new(Some(&ConcurrencyLimitConfig::fixed(2)));
+        let route = ConcurrencyLimitConfig {
+            retry_after_secs: Some(1),
+            shed_body: Some("busy".to_string()),
+            ..ConcurrencyLimitConfig::fixed(1)
+        };
+
+        let first = admitted(manager.admit(Some("api"), Some(&route)));
+        match manager.admit(Some("api"), Some(&route)) {
+            ConcurrencyAdmission::Shed {
+                scope,
+                limit,
+                reason,
+                response,
+            } => {
+                assert_eq!(scope, "route:api");
+                assert_eq!((limit, reason), (1, "max_in_flight"));
+                assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+            }
+            ConcurrencyAdmission::Admitted(_) => panic!("route limit not enforced"),
+        }
+
+        // The shed route request released its global slot
+        let _second = admitted(manager.admit(Some("other"), None));
+        assert!(matches!(
+            manager.admit(Some("other"), None),
+            ConcurrencyAdmission::Shed { ref scope, .. } if scope == "global"
+        ));
+
+        first.finish(true);
+        admitted(manager.admit(Some("api"), Some(&route)));
+    }
+
+    #[test]
+    fn test_in_flight_kept_across_reload() {
+        let manager = ConcurrencyManager::new(Some(&ConcurrencyLimitConfig::fixed(1)));
+        let _permit = admitted(manager.admit(None, None));
+
+        manager.reload(Some(&ConcurrencyLimitConfig::fixed(2)));
+        let _second = admitted(manager.admit(None, None));
+        assert!(matches!(
+            manager.admit(None, None),
+            ConcurrencyAdmission::Shed { .. }
+        ));
+
+        manager.reload(None);
+        admitted(manager.admit(None, None));
+    }
+
+    #[test]
+    fn test_gradient_follows_latency() {
+        let config = AdaptiveConcurrencyConfig {
+            min_limit: 10,
+            initial_limit: 100,
+            window_ms: 100,
+            ..Default::default()
+        };
+        let mut state = GradientState::new(&config, 1000);
+        let mut now = Instant::now();
+        let mut window = |state: &mut GradientState, latency_ms: u64| {
+            now += Duration::from_millis(100);
+            state.sample(Duration::from_millis(latency_ms), 100, now)
+        };
+
+        // Steady latency grows the limit
+        window(&mut state, 10);
+        let grown = window(&mut state, 10).unwrap();
+        assert!(grown > 100);
+
+        // Latency far above the baseline shrinks it towards min-limit
+        let mut limit = grown;
+        for _ in 0..20 {
+            limit = window(&mut state, 100).unwrap();
+        }
+        assert!(limit < grown / 2);
+        assert!(limit >= 10);
+    }
+}
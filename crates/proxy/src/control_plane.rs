@@ -0,0 +1,552 @@
+//! Control-plane client for versioned route tables.
+//!
+//! With `--control-plane <url>`, the proxy polls a central control plane for
+//! a versioned table of routes, upstreams and filters, a small subset of what
+//! Envoy's xDS does. Every poll is a `GET <url>?node=<node-id>&version=<applied>`
+//! with `If-None-Match` set to the applied version. The control plane answers
+//! `304 Not Modified` when nothing changed, or `200` with a [`RouteTable`] in
+//! the JSON configuration format:
+//!
+//! ```json
+//! { "version": "42", "routes": [ ... ], "upstreams": { ... }, "filters": { ... } }
+//! ```
+//!
+//! A new table is merged into the configuration from the file and applied
+//! through [`ConfigManager::apply_config`], so validation, reload hooks and
+//! the atomic swap are the same as for a hot reload. Entries of the previous
+//! table are replaced; an id that is also defined in the file rejects the
+//! table. The outcome is reported with a `POST <url>` of a [`TableStatus`]:
+//! `ack` with the version, or `nack` with the version and the error. A
+//! rejected version is not applied again; the proxy keeps the last accepted
+//! table until the control plane publishes another version.
+//!
+//! A file reload (SIGHUP, file watcher) replaces the merged configuration
+//! with the file's contents, after which the last accepted table is applied
+//! again. Agents are connected at startup and stay in the file; routes in
+//! the table refer to them by id.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use zentinel_config::{Config, FilterConfig, RouteConfig, UpstreamConfig};
+
+use crate::reload::{ConfigManager, ReloadEvent, ReloadTrigger};
+
+/// Interval between polls when none is configured
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Time allowed for one request to the control plane
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Route tables received, by result (`ack` or `nack`)
+static UPDATES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_control_plane_updates_total",
+        "Route tables received from the control plane by result",
+        &["result"]
+    )
+    .ok()
+});
+
+/// How the proxy reaches the control plane
+#[derive(Debug, Clone)]
+pub struct ControlPlaneOptions {
+    /// Route table endpoint
+    pub url: String,
+    /// Node id sent with every poll and status report
+    pub node_id: String,
+    /// Bearer token for the control plane
+    pub token: Option<String>,
+    /// Interval between polls
+    pub interval: Duration,
+}
+
+/// A versioned table published by the control plane
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteTable {
+    /// Opaque version; a different version replaces the applied table
+    pub version: String,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+    #[serde(default)]
+    pub upstreams: HashMap<String, UpstreamConfig>,
+    #[serde(default)]
+    pub filters: HashMap<String, FilterConfig>,
+}
+
+/// Outcome of a table, reported to the control plane
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableStatus {
+    pub node: String,
+    pub version: String,
+    pub status: AckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether a table was applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AckStatus {
+    Ack,
+    Nack,
+}
+
+impl AckStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            AckStatus::Ack => "ack",
+            AckStatus::Nack => "nack",
+        }
+    }
+}
+
+/// Error fetching or applying a route table
+#[derive(Debug, Error)]
+pub enum ControlPlaneError {
+    #[error("Control plane request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Control plane returned HTTP {0}")]
+    Status(u16),
+
+    #[error("Invalid route table: {0}")]
+    InvalidTable(String),
+
+    #[error("{kind} '{id}' is already defined in the config file")]
+    Conflict { kind: &'static str, id: String },
+
+    #[error("Route table rejected: {0}")]
+    Rejected(String),
+}
+
+/// Ids of the entries contributed by the applied table
+#[derive(Debug, Default, Clone, PartialEq)]
+struct TableOwned {
+    routes: HashSet<String>,
+    upstreams: HashSet<String>,
+    filters: HashSet<String>,
+}
+
+#[derive(Default)]
+struct ClientState {
+    /// Entries of the applied table
+    owned: TableOwned,
+    /// Last accepted table, applied again after file reloads
+    applied: Option<RouteTable>,
+    /// Last rejected version, not applied again
+    rejected: Option<String>,
+}
+
+/// Polls the control plane and applies its route tables
+pub struct ControlPlaneClient {
+    options: ControlPlaneOptions,
+    http: reqwest::Client,
+    config_manager: Arc<ConfigManager>,
+    /// Held across `apply_config()` so a poll and a re-apply after a file
+    /// reload never merge against a stale set of owned ids
+    state: Mutex<ClientState>,
+}
+
+impl ControlPlaneClient {
+    /// Create a client applying tables to `config_manager`
+    pub fn new(
+        options: ControlPlaneOptions,
+        config_manager: Arc<ConfigManager>,
+    ) -> Result<Self, ControlPlaneError> {
+        let http = crate::outbound::client_builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            options,
+            http,
+            config_manager,
+            state: Mutex::new(ClientState::default()),
+        })
+    }
+
+    /// Version of the applied table
+    pub async fn version(&self) -> Option<String> {
+        let state = self.state.lock().await;
+        state.applied.as_ref().map(|table| table.version.clone())
+    }
+
+    /// Fetch the table once and apply and report it if its version is new
+    pub async fn poll(&self) -> Result<(), ControlPlaneError> {
+        let applied = self.version().await;
+        let Some(body) = self.fetch(applied.as_deref()).await? else {
+            return Ok(());
+        };
+
+        let value: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| ControlPlaneError::InvalidTable(e.to_string()))?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ControlPlaneError::InvalidTable("missing 'version'".to_string()))?
+            .to_string();
+        if applied.as_deref() == Some(version.as_str())
+            || self.state.lock().await.rejected.as_deref() == Some(version.as_str())
+        {
+            return Ok(());
+        }
+
+        let result = match serde_json::from_value::<RouteTable>(value) {
+            Ok(table) => self.apply(table).await,
+            Err(e) => Err(ControlPlaneError::InvalidTable(e.to_string())),
+        };
+        let status = TableStatus {
+            node: self.options.node_id.clone(),
+            version: version.clone(),
+            status: if result.is_ok() {
+                AckStatus::Ack
+            } else {
+                AckStatus::Nack
+            },
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        if result.is_err() {
+            self.state.lock().await.rejected = Some(version);
+        }
+        if let Some(updates) = UPDATES.as_ref() {
+            updates.with_label_values(&[status.status.as_str()]).inc();
+        }
+        self.report(&status).await;
+        result
+    }
+
+    /// Body of a changed table, `None` when the applied version is current
+    async fn fetch(
+        &self,
+        applied: Option<&str>,
+    ) -> Result<Option<bytes::Bytes>, ControlPlaneError> {
+        let mut request = self.http.get(&self.options.url).query(&[
+            ("node", self.options.node_id.as_str()),
+            ("version", applied.unwrap_or("")),
+        ]);
+        if let Some(version) = applied {
+            request = request.header(reqwest::header::IF_NONE_MATCH, format!("\"{}\"", version));
+        }
+        if let Some(token) = &self.options.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_MODIFIED => Ok(None),
+            status if status.is_success() => Ok(Some(response.bytes().await?)),
+            status => Err(ControlPlaneError::Status(status.as_u16())),
+        }
+    }
+
+    /// Send an ack or nack; failures are logged, the next version reports again
+    async fn report(&self, status: &TableStatus) {
+        let mut request = self.http.post(&self.options.url).json(status);
+        if let Some(token) = &self.options.token {
+            request = request.bearer_auth(token);
+        }
+        match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => debug!(
+                version = %status.version,
+                status = status.status.as_str(),
+                "Reported route table status to the control plane"
+            ),
+            Err(e) => warn!(
+                version = %status.version,
+                error = %e,
+                "Failed to report route table status to the control plane"
+            ),
+        }
+    }
+
+    async fn apply(&self, table: RouteTable) -> Result<(), ControlPlaneError> {
+        let mut state = self.state.lock().await;
+        let previous = state.owned.clone();
+        Self::apply_locked(&mut state, &self.config_manager, table, &previous).await
+    }
+
+    /// Apply the accepted table again if a file reload replaced it
+    async fn reapply(&self) -> Result<(), ControlPlaneError> {
+        let mut state = self.state.lock().await;
+        let Some(table) = state.applied.clone() else {
+            return Ok(());
+        };
+        if is_applied(&self.config_manager.current(), &state.owned) {
+            return Ok(());
+        }
+
+        info!(version = %table.version, "Re-applying control plane route table after reload");
+        // The reloaded config holds only the file's entries
+        Self::apply_locked(
+            &mut state,
+            &self.config_manager,
+            table,
+            &TableOwned::default(),
+        )
+        .await
+    }
+
+    async fn apply_locked(
+        state: &mut ClientState,
+        config_manager: &ConfigManager,
+        table: RouteTable,
+        previous: &TableOwned,
+    ) -> Result<(), ControlPlaneError> {
+        let (merged, owned) = merge(&config_manager.current(), &table, previous)?;
+        config_manager
+            .apply_config(merged, ReloadTrigger::ControlPlane(table.version.clone()))
+            .await
+            .map_err(|e| ControlPlaneError::Rejected(e.to_string()))?;
+
+        info!(
+            version = %table.version,
+            routes = owned.routes.len(),
+            upstreams = owned.upstreams.len(),
+            filters = owned.filters.len(),
+            "Applied control plane route table"
+        );
+
+        state.owned = owned;
+        state.applied = Some(table);
+        state.rejected = None;
+        Ok(())
+    }
+}
+
+/// Whether every entry in `owned` is present in `config`
+fn is_applied(config: &Config, owned: &TableOwned) -> bool {
+    owned
+        .routes
+        .iter()
+        .all(|id| config.routes.iter().any(|r| &r.id == id))
+        && owned
+            .upstreams
+            .iter()
+            .all(|id| config.upstreams.contains_key(id))
+        && owned
+            .filters
+            .iter()
+            .all(|id| config.filters.contains_key(id))
+}
+
+/// Replace the table entries of `base` with those of `table`
+///
+/// Entries recorded in `previous` are removed from `base` first, so entries
+/// dropped from the table disappear. An id defined in `base` otherwise
+/// rejects the table.
+fn merge(
+    base: &Config,
+    table: &RouteTable,
+    previous: &TableOwned,
+) -> Result<(Config, TableOwned), ControlPlaneError> {
+    let mut merged = base.clone();
+    let mut owned = TableOwned::default();
+
+    merged.routes.retain(|r| !previous.routes.contains(&r.id));
+    merged
+        .upstreams
+        .retain(|id, _| !previous.upstreams.contains(id));
+    merged
+        .filters
+        .retain(|id, _| !previous.filters.contains(id));
+
+    for route in &table.routes {
+        if owned.routes.contains(&route.id) {
+            return Err(ControlPlaneError::InvalidTable(format!(
+                "route '{}' is defined twice",
+                route.id
+            )));
+        }
+        if merged.routes.iter().any(|r| r.id == route.id) {
+            return Err(ControlPlaneError::Conflict {
+                kind: "route",
+                id: route.id.clone(),
+            });
+        }
+        owned.routes.insert(route.id.clone());
+        merged.routes.push(route.clone());
+    }
+
+    for (id, upstream) in &table.upstreams {
+        if &upstream.id != id {
+            return Err(ControlPlaneError::InvalidTable(format!(
+                "upstream '{}' has id '{}'",
+                id, upstream.id
+            )));
+        }
+        if merged.upstreams.contains_key(id) {
+            return Err(ControlPlaneError::Conflict {
+                kind: "upstream",
+                id: id.clone(),
+            });
+        }
+        owned.upstreams.insert(id.clone());
+        merged.upstreams.insert(id.clone(), upstream.clone());
+    }
+
+    for (id, filter) in &table.filters {
+        if &filter.id != id {
+            return Err(ControlPlaneError::InvalidTable(format!(
+                "filter '{}' has id '{}'",
+                id, filter.id
+            )));
+        }
+        if merged.filters.contains_key(id) {
+            return Err(ControlPlaneError::Conflict {
+                kind: "filter",
+                id: id.clone(),
+            });
+        }
+        owned.filters.insert(id.clone());
+        merged.filters.insert(id.clone(), filter.clone());
+    }
+
+    Ok((merged, owned))
+}
+
+/// Poll the control plane every `options.interval` until the process exits
+pub async fn run_control_plane_client(
+    options: ControlPlaneOptions,
+    config_manager: Arc<ConfigManager>,
+) -> Result<(), ControlPlaneError> {
+    let client = Arc::new(ControlPlaneClient::new(options, config_manager)?);
+
+    // A SIGHUP or file reload replaces the merged config with the file's
+    // contents; put the table entries back.
+    {
+        let client = Arc::clone(&client);
+        let mut events = client.config_manager.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(ReloadEvent::Applied { .. }) | Err(RecvError::Lagged(_)) => {
+                        if let Err(e) = client.reapply().await {
+                            warn!(error = %e, "Failed to re-apply control plane route table");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    info!(
+        url = %client.options.url,
+        node = %client.options.node_id,
+        interval_secs = client.options.interval.as_secs(),
+        "Starting control plane client"
+    );
+
+    let mut interval = tokio::time::interval(client.options.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if let Err(e) = client.poll().await {
+            warn!(error = %e, "Control plane poll failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> RouteTable {
+        let base = Config::default_for_testing();
+
+        let mut route = base.routes[0].clone();
+        route.id = "team-a-api".to_string();
+        route.upstream = Some("team-a".to_string());
+
+        let mut upstream = base.upstreams["default"].clone();
+        upstream.id = "team-a".to_string();
+
+        RouteTable {
+            version: "1".to_string(),
+            routes: vec![route],
+            upstreams: HashMap::from([("team-a".to_string(), upstream)]),
+            filters: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_replaces_previous_table() {
+        let base = Config::default_for_testing();
+        let (merged, owned) = merge(&base, &table(), &TableOwned::default()).unwrap();
+
+        assert_eq!(merged.routes.len(), base.routes.len() + 1);
+        assert!(merged.upstreams.contains_key("team-a"));
+        assert!(owned.routes.contains("team-a-api"));
+        assert!(is_applied(&merged, &owned));
+        assert!(!is_applied(&base, &owned));
+
+        let mut next = table();
+        next.version = "2".to_string();
+        next.routes.clear();
+        next.upstreams.clear();
+        let (merged, owned) = merge(&merged, &next, &owned).unwrap();
+
+        assert_eq!(merged.routes.len(), base.routes.len());
+        assert!(!merged.upstreams.contains_key("team-a"));
+        assert_eq!(owned, TableOwned::default());
+    }
+
+    #[test]
+    fn test_merge_rejects_file_ids_and_mismatched_keys() {
+        let base = Config::default_for_testing();
+
+        let mut conflicting = table();
+        conflicting.routes[0].id = base.routes[0].id.clone();
+        let err = merge(&base, &conflicting, &TableOwned::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            ControlPlaneError::Conflict { kind: "route", .. }
+        ));
+
+        let mut mismatched = table();
+        let upstream = mismatched.upstreams.remove("team-a").unwrap();
+        mismatched.upstreams.insert("team-b".to_string(), upstream);
+        let err = merge(&base, &mismatched, &TableOwned::default()).unwrap_err();
+        assert!(matches!(err, ControlPlaneError::InvalidTable(_)));
+    }
+
+    #[test]
+    fn test_table_and_status_format() {
+        let table: RouteTable =
+            serde_json::from_value(serde_json::json!({ "version": "7" })).unwrap();
+        assert_eq!(table.version, "7");
+        assert!(table.routes.is_empty());
+
+        // Agents are connected at startup and cannot come from the table
+        assert!(serde_json::from_value::<RouteTable>(
+            serde_json::json!({ "version": "7", "agents": [] })
+        )
+        .is_err());
+
+        let status = TableStatus {
+            node: "edge-1".to_string(),
+            version: "7".to_string(),
+            status: AckStatus::Nack,
+            error: Some("route 'api' is already defined in the config file".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({
+                "node": "edge-1",
+                "version": "7",
+                "status": "nack",
+                "error": "route 'api' is already defined in the config file"
+            })
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Agent decision headers for trusted callers
+//!
+//! With `server { debug-headers }` configured, a request carrying a valid
+//! debug token collects the audit metadata of every agent that inspects it,
+//! and its response gets:
+//!
+//! - `X-Zentinel-Decision`: `block`, `redirect`, `challenge` or `allow`, with
+//!   the deciding agent (`block;agent="waf"`)
+//! - `X-Zentinel-Rule-Ids`: rule IDs reported by the agents, in call order
+//! - `X-Zentinel-Agent-Times`: time spent in each agent
+//!   (`waf;dur=7, auth;dur=2`, in milliseconds)
+//!
+//! A token is `<subject>.<expires>.<signature>`, where `expires` is a Unix
+//! timestamp no further away than `max-ttl-secs` and `signature` is the hex
+//! HMAC-SHA256 of `<subject>.<expires>` under the configured secret. The
+//! token header is removed before agents and the upstream see the request.
+//! Checked tokens are counted in `zentinel_debug_tokens_total{result}`.
+
+use std::sync::LazyLock;
+
+use hmac::{Hmac, KeyInit, Mac};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use sha2::Sha256;
+
+use zentinel_agent_protocol::Decision;
+use zentinel_config::DebugHeadersConfig;
+
+use crate::audit_store::AuditRecord;
+use crate::errors::AgentBlock;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Debug tokens checked, by result
+static DEBUG_TOKENS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_debug_tokens_total",
+        "Debug tokens checked for agent decision headers, by result",
+        &["result"]
+    )
+    .ok()
+});
+
+/// Why a debug token was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    /// Not `<subject>.<expires>.<signature>`
+    Malformed,
+    /// Signature does not match
+    BadSignature,
+    /// Expired, or expiring later than `max-ttl-secs` allows
+    Expired,
+}
+
+impl TokenError {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenError::Malformed => "malformed",
+            TokenError::BadSignature => "bad_signature",
+            TokenError::Expired => "expired",
+        }
+    }
+}
+
+/// Sign a debug token for `subject`, valid until `expires` (Unix seconds)
+pub fn sign_token(secret: &str, subject: &str, expires: u64) -> String {
+    let payload = format!("{}.{}", subject, expires);
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(payload.as_bytes());
+    format!("{}.{}", payload, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check a debug token at `now` (Unix seconds), returning its subject
+pub fn verify_token(
+    config: &DebugHeadersConfig,
+    token: &str,
+    now: u64,
+) -> Result<String, TokenError> {
+    let result = verify(config, token, now);
+    if let Some(counter) = DEBUG_TOKENS.as_ref() {
+        let label = result.as_ref().map_or_else(|e| e.as_str(), |_| "accepted");
+        counter.with_label_values(&[label]).inc();
+    }
+    result
+}
+
+fn verify(config: &DebugHeadersConfig, token: &str, now: u64) -> Result<String, TokenError> {
+    let (payload, signature) = token.trim().rsplit_once('.').ok_or(TokenError::Malformed)?;
+    let (subject, expires) = payload.rsplit_once('.').ok_or(TokenError::Malformed)?;
+    let expires: u64 = expires.parse().map_err(|_| TokenError::Malformed)?;
+    let signature = hex::decode(signature).map_err(|_| TokenError::Malformed)?;
+    if subject.is_empty() {
+        return Err(TokenError::Malformed);
+    }
+
+    let mut mac =
+        HmacSha256::new_from_slice(config.secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(payload.as_bytes());
+    // Constant-time comparison
+    mac.verify_slice(&signature)
+        .map_err(|_| TokenError::BadSignature)?;
+
+    if expires <= now || expires - now > config.max_ttl_secs {
+        return Err(TokenError::Expired);
+    }
+    Ok(subject.to_string())
+}
+
+/// Response headers describing the agents' decisions on a request
+pub fn response_headers(
+    records: &[AuditRecord],
+    block: Option<&AgentBlock>,
+) -> Vec<(&'static str, String)> {
+    let decision = match block {
+        Some(block) => decision_value("block", block.agent_id.as_deref()),
+        None => records
+            .iter()
+            .rev()
+            .find_map(|record| {
+                let name = match record.decision {
+                    Decision::Allow => return None,
+                    Decision::Block { .. } => "block",
+                    Decision::Redirect { .. } => "redirect",
+                    Decision::Challenge { .. } => "challenge",
+                };
+                Some(decision_value(name, Some(&record.agent_id)))
+            })
+            .unwrap_or_else(|| "allow".to_string()),
+    };
+    let mut headers = vec![("X-Zentinel-Decision", decision)];
+
+    let mut rule_ids: Vec<&str> = Vec::new();
+    for rule_id in records.iter().flat_map(|r| &r.audit.rule_ids) {
+        if !rule_ids.contains(&rule_id.as_str()) {
+            rule_ids.push(rule_id);
+        }
+    }
+    if !rule_ids.is_empty() {
+        headers.push(("X-Zentinel-Rule-Ids", rule_ids.join(", ")));
+    }
+
+    let mut times: Vec<(&str, u64)> = Vec::new();
+    for record in records {
+        match times
+            .iter_mut()
+            .find(|(agent, _)| *agent == record.agent_id)
+        {
+            Some((_, total)) => *total += record.duration_ms,
+            None => times.push((record.agent_id.as_str(), record.duration_ms)),
+        }
+    }
+    if !times.is_empty() {
+        let value = times
+            .iter()
+            .map(|(agent, ms)| format!("{};dur={}", agent, ms))
+            .collect::<Vec<_>>()
+            .join(", ");
+        headers.push(("X-Zentinel-Agent-Times", value));
+    }
+    headers
+}
+
+fn decision_value(name: &str, agent: Option<&str>) -> String {
+    match agent {
+        Some(agent) => format!("{};agent=\"{}\"", name, agent.replace(['"', '\\'], "")),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use zentinel_agent_protocol::{AgentResponse, EventType};
+
+    fn config() -> DebugHeadersConfig {
+        DebugHeadersConfig {
+            secret: "0123456789abcdef".to_string(),
+            token_header: "X-Zentinel-Debug-Token".to_string(),
+            max_ttl_secs: 3600,
+        }
+    }
+
+    fn record(agent_id: &str, decision: Decision, rule_ids: &[&str], ms: u64) -> AuditRecord {
+        let mut response = AgentResponse::default_allow();
+        response.decision = decision;
+        response.audit.rule_ids = rule_ids.iter().map(|id| id.to_string()).collect();
+        AuditRecord::new(
+            "req-1",
+            Some("api"),
+            agent_id,
+            EventType::RequestHeaders,
+            &response,
+            Duration::from_millis(ms),
+        )
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let config = config();
+        let token = sign_token(&config.secret, "alice", 1_000_600);
+        assert_eq!(verify(&config, &token, 1_000_000), Ok("alice".to_string()));
+
+        // Subjects may contain dots
+        let token = sign_token(&config.secret, "team.api", 1_000_600);
+        assert_eq!(
+            verify(&config, &token, 1_000_000),
+            Ok("team.api".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        let config = config();
+        let token = sign_token(&config.secret, "alice", 1_000_600);
+
+        let tampered = token.replacen("alice", "admin", 1);
+        assert_eq!(
+            verify(&config, &tampered, 1_000_000),
+            Err(TokenError::BadSignature)
+        );
+        let other_key = sign_token("another secret key", "alice", 1_000_600);
+        assert_eq!(
+            verify(&config, &other_key, 1_000_000),
+            Err(TokenError::BadSignature)
+        );
+
+        assert_eq!(verify(&config, &token, 1_000_600), Err(TokenError::Expired));
+        let long_lived = sign_token(&config.secret, "alice", 1_000_000 + 7200);
+        assert_eq!(
+            verify(&config, &long_lived, 1_000_000),
+            Err(TokenError::Expired)
+        );
+
+        for malformed in [
+            "",
+            "alice",
+            "alice.1000600",
+            "alice.soon.abcd",
+            ".1000600.abcd",
+        ] {
+            assert_eq!(
+                verify(&config, malformed, 1_000_000),
+                Err(TokenError::Malformed),
+                "{malformed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_response_headers() {
+        let records = vec![
+            record("auth", Decision::Allow, &[], 2),
+            record(
+                "waf",
+                Decision::Block {
+                    status: 403,
+                    body: None,
+                    headers: None,
+                },
+                &["942100", "941100"],
+                7,
+            ),
+            record("waf", Decision::Allow, &["942100"], 1),
+        ];
+        let block = AgentBlock {
+            status: 403,
+            message: None,
+            agent_id: Some("waf".to_string()),
+            rule_ids: vec![],
+            headers: Default::default(),
+        };
+
+        let headers = response_headers(&records, Some(&block));
+        assert_eq!(
+            headers,
+            vec![
+                ("X-Zentinel-Decision", "block;agent=\"waf\"".to_string()),
+                ("X-Zentinel-Rule-Ids", "942100, 941100".to_string()),
+                (
+                    "X-Zentinel-Agent-Times",
+                    "auth;dur=2, waf;dur=8".to_string()
+                ),
+            ]
+        );
+
+        assert_eq!(
+            response_headers(&[], None),
+            vec![("X-Zentinel-Decision", "allow".to_string())]
+        );
+    }
+}
@@ -7,7 +7,7 @@
 //! - DNS: Resolve backends from DNS A/AAAA records
 //! - DNS SRV: Resolve backends from DNS SRV records
 //! - Consul: Discover backends from Consul service catalog
-//! - Kubernetes: Discover backends from Kubernetes endpoints
+//! - Kubernetes: Discover backends from Kubernetes EndpointSlices
 //! - File: Watch configuration file for backend changes
 //!
 //! # Example KDL Configuration
@@ -27,7 +27,7 @@
 //!         service "backend-api"
 //!         datacenter "dc1"
 //!         refresh-interval 10
-//!         only-passing true
+//!         only-passing #true
 //!     }
 //! }
 //!
@@ -40,8 +40,14 @@
 //!     }
 //! }
 //! ```
+//!
+//! Discovered backends are converted to [`UpstreamTarget`]s with
+//! [`backends_to_targets`] and applied to the upstream pool as an atomic
+//! membership swap (see `UpstreamPool::update_targets`).
 
 use async_trait::async_trait;
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::TokioResolver;
 use parking_lot::RwLock;
 use pingora::prelude::*;
 use pingora_load_balancing::discovery::{ServiceDiscovery, Static as StaticDiscovery};
@@ -51,6 +57,9 @@ use std::net::ToSocketAddrs;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
+use zentinel_config::ServiceDiscoveryConfig;
+
+use crate::upstream::UpstreamTarget;
 
 /// Service discovery configuration
 #[derive(Debug, Clone)]
@@ -121,6 +130,80 @@ impl Default for DiscoveryConfig {
     }
 }
 
+impl From<&ServiceDiscoveryConfig> for DiscoveryConfig {
+    fn from(config: &ServiceDiscoveryConfig) -> Self {
+        match config.clone() {
+            ServiceDiscoveryConfig::Dns {
+                hostname,
+                port,
+                refresh_interval_secs,
+            } => Self::Dns {
+                hostname,
+                port,
+                refresh_interval: Duration::from_secs(refresh_interval_secs),
+            },
+            ServiceDiscoveryConfig::DnsSrv {
+                service,
+                refresh_interval_secs,
+            } => Self::DnsSrv {
+                service,
+                refresh_interval: Duration::from_secs(refresh_interval_secs),
+            },
+            ServiceDiscoveryConfig::Consul {
+                address,
+                service,
+                datacenter,
+                only_passing,
+                tag,
+                refresh_interval_secs,
+            } => Self::Consul {
+                address,
+                service,
+                datacenter,
+                only_passing,
+                refresh_interval: Duration::from_secs(refresh_interval_secs),
+                tag,
+            },
+            ServiceDiscoveryConfig::Kubernetes {
+                namespace,
+                service,
+                port_name,
+                kubeconfig,
+                refresh_interval_secs,
+            } => Self::Kubernetes {
+                namespace,
+                service,
+                port_name,
+                refresh_interval: Duration::from_secs(refresh_interval_secs),
+                kubeconfig,
+            },
+            ServiceDiscoveryConfig::File {
+                path,
+                watch_interval_secs,
+            } => Self::File {
+                path,
+                watch_interval: Duration::from_secs(watch_interval_secs),
+            },
+        }
+    }
+}
+
+/// Convert discovered backends into upstream targets
+///
+/// Non-inet (Unix socket) backends are skipped. Weights are clamped to at
+/// least 1 so weighted balancers never see a zero-weight target.
+pub fn backends_to_targets(backends: &BTreeSet<Backend>) -> Vec<UpstreamTarget> {
+    backends
+        .iter()
+        .filter_map(|backend| {
+            // Same "host:port" form as static targets (IPv6 stays bracketed)
+            let mut target = UpstreamTarget::from_address(&backend.addr.as_inet()?.to_string())?;
+            target.weight = u32::try_from(backend.weight.max(1)).unwrap_or(u32::MAX);
+            Some(target)
+        })
+        .collect()
+}
+
 /// DNS-based service discovery
 ///
 /// Resolves backends from DNS A/AAAA records.
@@ -147,7 +230,10 @@ impl DnsDiscovery {
     }
 
     /// Resolve the hostname to backends
-    fn resolve(&self) -> Result<BTreeSet<Backend>, Box<Error>> {
+    ///
+    /// Uses tokio's resolver so the lookup runs on the blocking pool instead
+    /// of stalling the refresh task's worker thread.
+    async fn resolve(&self) -> Result<BTreeSet<Backend>, Box<Error>> {
         let address = format!("{}:{}", self.hostname, self.port);
 
         trace!(
@@ -156,7 +242,7 @@ impl DnsDiscovery {
             "Resolving DNS for service discovery"
         );
 
-        match address.to_socket_addrs() {
+        match tokio::net::lookup_host(address).await {
             Ok(addrs) => {
                 let backends: BTreeSet<Backend> = addrs
                     .map(|addr| Backend {
@@ -200,7 +286,7 @@ impl ServiceDiscovery for DnsDiscovery {
     async fn discover(&self) -> Result<(BTreeSet<Backend>, HashMap<u64, bool>)> {
         // Check if we need to refresh
         if self.needs_refresh() {
-            match self.resolve() {
+            match self.resolve().await {
                 Ok(backends) => {
                     *self.cached_backends.write() = backends;
                     *self.last_resolution.write() = Instant::now();
@@ -227,6 +313,180 @@ impl ServiceDiscovery for DnsDiscovery {
     }
 }
 
+// ============================================================================
+// DNS SRV Service Discovery
+// ============================================================================
+
+/// DNS SRV-based service discovery
+///
+/// Resolves backends from SRV records. Only the lowest-priority group is
+/// used (higher priorities are fallbacks per RFC 2782); record weights become
+/// backend weights and each record's target is resolved to A/AAAA addresses.
+pub struct DnsSrvDiscovery {
+    service: String,
+    refresh_interval: Duration,
+    resolver: TokioResolver,
+    /// Cached backends
+    cached_backends: RwLock<BTreeSet<Backend>>,
+    /// Last resolution time
+    last_resolution: RwLock<Instant>,
+}
+
+impl DnsSrvDiscovery {
+    /// Create a new DNS SRV discovery instance using the system resolver
+    pub fn new(service: String, refresh_interval: Duration) -> Result<Self, Box<Error>> {
+        let resolver = TokioResolver::builder_tokio()
+            .map_err(|e| {
+                Error::explain(
+                    ErrorType::InternalError,
+                    format!("Failed to read system DNS configuration: {}", e),
+                )
+            })?
+            .build()
+            .map_err(|e| {
+                Error::explain(
+                    ErrorType::InternalError,
+                    format!("Failed to create DNS resolver: {}", e),
+                )
+            })?;
+
+        Ok(Self {
+            service,
+            refresh_interval,
+            resolver,
+            cached_backends: RwLock::new(BTreeSet::new()),
+            last_resolution: RwLock::new(Instant::now() - refresh_interval),
+        })
+    }
+
+    /// Resolve SRV records and their targets to backends
+    async fn resolve(&self) -> Result<BTreeSet<Backend>, Box<Error>> {
+        trace!(service = %self.service, "Resolving DNS SRV for service discovery");
+
+        let lookup = self
+            .resolver
+            .lookup(self.service.as_str(), RecordType::SRV)
+            .await
+            .map_err(|e| {
+                Error::explain(
+                    ErrorType::ConnectNoRoute,
+                    format!("DNS SRV lookup failed for {}: {}", self.service, e),
+                )
+            })?;
+
+        let records: Vec<SrvRecord> = lookup
+            .answers()
+            .iter()
+            .filter_map(|record| match &record.data {
+                RData::SRV(srv) => Some(SrvRecord {
+                    priority: srv.priority,
+                    weight: srv.weight,
+                    port: srv.port,
+                    target: srv.target.to_utf8(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let mut backends = BTreeSet::new();
+        for record in select_srv_records(records) {
+            let ips = match self.resolver.lookup_ip(record.target.as_str()).await {
+                Ok(ips) => ips,
+                Err(e) => {
+                    warn!(
+                        service = %self.service,
+                        target = %record.target,
+                        error = %e,
+                        "Failed to resolve SRV target, skipping"
+                    );
+                    continue;
+                }
+            };
+            for ip in ips.iter() {
+                backends.insert(Backend {
+                    addr: pingora_core::protocols::l4::socket::SocketAddr::Inet(
+                        std::net::SocketAddr::new(ip, record.port),
+                    ),
+                    // A weight of 0 means "rarely selected"; keep it routable
+                    weight: usize::from(record.weight.max(1)),
+                    ext: http::Extensions::new(),
+                });
+            }
+        }
+
+        debug!(
+            service = %self.service,
+            backend_count = backends.len(),
+            "DNS SRV resolution successful"
+        );
+
+        Ok(backends)
+    }
+
+    /// Check if cache needs refresh
+    fn needs_refresh(&self) -> bool {
+        let last = *self.last_resolution.read();
+        last.elapsed() >= self.refresh_interval
+    }
+}
+
+/// SRV record fields relevant to backend selection
+#[derive(Debug, Clone, PartialEq)]
+struct SrvRecord {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+/// Keep only the records in the lowest (most preferred) priority group
+fn select_srv_records(records: Vec<SrvRecord>) -> Vec<SrvRecord> {
+    let Some(min_priority) = records.iter().map(|r| r.priority).min() else {
+        return Vec::new();
+    };
+    records
+        .into_iter()
+        .filter(|r| r.priority == min_priority && r.target != ".")
+        .collect()
+}
+
+#[async_trait]
+impl ServiceDiscovery for DnsSrvDiscovery {
+    async fn discover(&self) -> Result<(BTreeSet<Backend>, HashMap<u64, bool>)> {
+        if self.needs_refresh() {
+            match self.resolve().await {
+                Ok(backends) if !backends.is_empty() => {
+                    *self.cached_backends.write() = backends;
+                    *self.last_resolution.write() = Instant::now();
+                }
+                result => {
+                    let cached = self.cached_backends.read().clone();
+                    let error = match result {
+                        Err(e) => e,
+                        Ok(_) => Error::explain(
+                            ErrorType::ConnectNoRoute,
+                            format!("DNS SRV lookup for {} returned no backends", self.service),
+                        ),
+                    };
+                    if !cached.is_empty() {
+                        warn!(
+                            service = %self.service,
+                            error = %error,
+                            cached_count = cached.len(),
+                            "DNS SRV resolution failed, using cached backends"
+                        );
+                        return Ok((cached, HashMap::new()));
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let backends = self.cached_backends.read().clone();
+        Ok((backends, HashMap::new()))
+    }
+}
+
 // ============================================================================
 // Consul Service Discovery
 // ============================================================================
@@ -513,7 +773,8 @@ use crate::kubeconfig::{KubeAuth, Kubeconfig, ResolvedKubeConfig};
 
 /// Kubernetes endpoint discovery
 ///
-/// Discovers backends from Kubernetes Endpoints resource.
+/// Discovers backends from the `discovery.k8s.io/v1` EndpointSlices of a
+/// Service. Endpoints whose `ready` condition is false are skipped.
 /// Requires either in-cluster configuration or kubeconfig file.
 ///
 /// # Authentication Methods
@@ -643,34 +904,84 @@ impl KubernetesDiscovery {
     }
 }
 
-/// Kubernetes Endpoints API response structures
+/// Kubernetes EndpointSlice API response structures
 #[cfg(feature = "kubernetes")]
 mod k8s_types {
     use serde::Deserialize;
 
     #[derive(Debug, Deserialize)]
-    pub struct Endpoints {
-        pub subsets: Option<Vec<EndpointSubset>>,
+    pub struct EndpointSliceList {
+        #[serde(default)]
+        pub items: Vec<EndpointSlice>,
     }
 
     #[derive(Debug, Deserialize)]
-    pub struct EndpointSubset {
-        pub addresses: Option<Vec<EndpointAddress>>,
+    pub struct EndpointSlice {
+        #[serde(default)]
+        pub endpoints: Vec<Endpoint>,
+        #[serde(default)]
         pub ports: Option<Vec<EndpointPort>>,
     }
 
     #[derive(Debug, Deserialize)]
-    pub struct EndpointAddress {
-        pub ip: String,
-        pub hostname: Option<String>,
+    pub struct Endpoint {
+        #[serde(default)]
+        pub addresses: Vec<String>,
+        #[serde(default)]
+        pub conditions: EndpointConditions,
+    }
+
+    /// A nil `ready` condition must be interpreted as ready
+    #[derive(Debug, Default, Deserialize)]
+    pub struct EndpointConditions {
+        pub ready: Option<bool>,
     }
 
     #[derive(Debug, Deserialize)]
     pub struct EndpointPort {
         pub name: Option<String>,
-        pub port: u16,
-        pub protocol: Option<String>,
+        pub port: Option<u16>,
+    }
+}
+
+/// Extract ready backends from an EndpointSlice list
+#[cfg(feature = "kubernetes")]
+fn endpoint_slices_to_backends(
+    slices: k8s_types::EndpointSliceList,
+    port_name: Option<&str>,
+) -> BTreeSet<Backend> {
+    let mut backends = BTreeSet::new();
+    for slice in slices.items {
+        // Ports are per slice; pick by name or fall back to the first one
+        let target_port = slice.ports.as_ref().and_then(|ports| {
+            match port_name {
+                Some(name) => ports.iter().find(|p| p.name.as_deref() == Some(name)),
+                None => ports.first(),
+            }
+            .and_then(|p| p.port)
+        });
+        let Some(port) = target_port else {
+            continue;
+        };
+
+        for endpoint in slice.endpoints {
+            if endpoint.conditions.ready == Some(false) {
+                continue;
+            }
+            for address in endpoint.addresses {
+                if let Ok(ip) = address.parse::<std::net::IpAddr>() {
+                    backends.insert(Backend {
+                        addr: pingora_core::protocols::l4::socket::SocketAddr::Inet(
+                            std::net::SocketAddr::new(ip, port),
+                        ),
+                        weight: 1,
+                        ext: http::Extensions::new(),
+                    });
+                }
+            }
+        }
     }
+    backends
 }
 
 #[cfg(feature = "kubernetes")]
@@ -723,9 +1034,9 @@ impl ServiceDiscovery for KubernetesDiscovery {
             }
         };
 
-        // Build endpoint URL
+        // Build EndpointSlice URL (slices are labelled with their owning Service)
         let url = format!(
-            "{}/api/v1/namespaces/{}/endpoints/{}",
+            "{}/apis/discovery.k8s.io/v1/namespaces/{}/endpointslices?labelSelector=kubernetes.io%2Fservice-name%3D{}",
             api_server.trim_end_matches('/'),
             self.namespace,
             self.service
@@ -735,11 +1046,11 @@ impl ServiceDiscovery for KubernetesDiscovery {
             url = %url,
             namespace = %self.namespace,
             service = %self.service,
-            "Fetching Kubernetes endpoints"
+            "Fetching Kubernetes EndpointSlices"
         );
 
         // Build HTTP client with proper TLS configuration
-        let client_builder = reqwest::Client::builder()
+        let client_builder = crate::outbound::client_builder()
             .timeout(Duration::from_secs(10))
             .danger_accept_invalid_certs(skip_verify);
 
@@ -804,49 +1115,14 @@ impl ServiceDiscovery for KubernetesDiscovery {
         }
 
         // Parse the response
-        let endpoints: k8s_types::Endpoints = response.json().await.map_err(|e| {
+        let slices: k8s_types::EndpointSliceList = response.json().await.map_err(|e| {
             Error::explain(
                 ErrorType::InternalError,
-                format!("Failed to parse Kubernetes endpoints: {}", e),
+                format!("Failed to parse Kubernetes EndpointSlices: {}", e),
             )
         })?;
 
-        // Extract backends from endpoints
-        let mut backends = BTreeSet::new();
-        if let Some(subsets) = endpoints.subsets {
-            for subset in subsets {
-                // Find the target port
-                let target_port = subset.ports.as_ref().and_then(|ports| {
-                    if let Some(port_name) = &self.port_name {
-                        // Find port by name
-                        ports
-                            .iter()
-                            .find(|p| p.name.as_ref() == Some(port_name))
-                            .map(|p| p.port)
-                    } else {
-                        // Use first port
-                        ports.first().map(|p| p.port)
-                    }
-                });
-
-                if let (Some(addresses), Some(port)) = (subset.addresses, target_port) {
-                    for addr in addresses {
-                        let socket_addr = format!("{}:{}", addr.ip, port);
-                        if let Ok(mut addrs) = socket_addr.to_socket_addrs() {
-                            if let Some(socket_addr) = addrs.next() {
-                                backends.insert(Backend {
-                                    addr: pingora_core::protocols::l4::socket::SocketAddr::Inet(
-                                        socket_addr,
-                                    ),
-                                    weight: 1,
-                                    ext: http::Extensions::new(),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let backends = endpoint_slices_to_backends(slices, self.port_name.as_deref());
 
         info!(
             service = %self.service,
@@ -1200,17 +1476,10 @@ impl DiscoveryManager {
                     upstream_id = %upstream_id,
                     service = %service,
                     refresh_interval_secs = refresh_interval.as_secs(),
-                    "DNS SRV discovery not yet fully implemented, using DNS A record fallback"
+                    "Registered DNS SRV service discovery"
                 );
 
-                // DNS SRV requires async DNS resolver - fall back to regular DNS for now
-                // Extract hostname from service name (e.g., "_http._tcp.example.com" -> "example.com")
-                let hostname = service
-                    .split('.')
-                    .skip_while(|s| s.starts_with('_'))
-                    .collect::<Vec<_>>()
-                    .join(".");
-                Arc::new(DnsDiscovery::new(hostname, 80, refresh_interval))
+                Arc::new(DnsSrvDiscovery::new(service, refresh_interval)?)
             }
             DiscoveryConfig::Consul {
                 address,
@@ -1303,6 +1572,18 @@ impl DiscoveryManager {
         self.discoveries.write().remove(upstream_id);
     }
 
+    /// Drop discoveries for upstreams not in `upstream_ids` (used on reload)
+    pub fn retain(&self, upstream_ids: &std::collections::HashSet<String>) {
+        self.discoveries
+            .write()
+            .retain(|id, _| upstream_ids.contains(id));
+    }
+
+    /// IDs of upstreams with a registered discovery
+    pub fn upstream_ids(&self) -> Vec<String> {
+        self.discoveries.read().keys().cloned().collect()
+    }
+
     /// Number of registered discoveries
     pub fn count(&self) -> usize {
         self.discoveries.read().len()
@@ -1427,6 +1708,109 @@ mod tests {
         assert!(discovery.needs_refresh());
     }
 
+    #[test]
+    fn test_select_srv_records_lowest_priority() {
+        let record = |priority, weight, target: &str| SrvRecord {
+            priority,
+            weight,
+            port: 8080,
+            target: target.to_string(),
+        };
+        let selected = select_srv_records(vec![
+            record(20, 5, "backup.example.com."),
+            record(10, 3, "a.example.com."),
+            record(10, 1, "b.example.com."),
+        ]);
+
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|r| r.priority == 10));
+
+        // "." means the service is explicitly unavailable
+        assert!(select_srv_records(vec![record(0, 0, ".")]).is_empty());
+        assert!(select_srv_records(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_backends_to_targets() {
+        let backends: BTreeSet<Backend> = [("10.0.0.1:8080", 3), ("[::1]:9000", 0)]
+            .into_iter()
+            .map(|(addr, weight)| Backend {
+                addr: pingora_core::protocols::l4::socket::SocketAddr::Inet(addr.parse().unwrap()),
+                weight,
+                ext: http::Extensions::new(),
+            })
+            .collect();
+
+        let mut targets = backends_to_targets(&backends);
+        targets.sort_by(|a, b| a.address.cmp(&b.address));
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].full_address(), "10.0.0.1:8080");
+        assert_eq!(targets[0].weight, 3);
+        assert_eq!(targets[1].full_address(), "[::1]:9000");
+        assert_eq!(targets[1].weight, 1);
+    }
+
+    #[test]
+    fn test_discovery_config_from_service_discovery() {
+        let config = DiscoveryConfig::from(&ServiceDiscoveryConfig::DnsSrv {
+            service: "_http._tcp.api.internal".to_string(),
+            refresh_interval_secs: 15,
+        });
+        match config {
+            DiscoveryConfig::DnsSrv {
+                service,
+                refresh_interval,
+            } => {
+                assert_eq!(service, "_http._tcp.api.internal");
+                assert_eq!(refresh_interval, Duration::from_secs(15));
+            }
+            _ => panic!("Expected DnsSrv config"),
+        }
+    }
+
+    #[test]
+    fn test_discovery_manager_retain() {
+        let manager = DiscoveryManager::new();
+        for id in ["a", "b"] {
+            manager
+                .register(
+                    id,
+                    DiscoveryConfig::Static {
+                        backends: vec!["127.0.0.1:8080".to_string()],
+                    },
+                )
+                .unwrap();
+        }
+
+        manager.retain(&["a".to_string()].into_iter().collect());
+
+        assert_eq!(manager.upstream_ids(), vec!["a".to_string()]);
+    }
+
+    #[cfg(feature = "kubernetes")]
+    #[test]
+    fn test_endpoint_slices_skip_unready() {
+        let body = r#"{
+            "items": [{
+                "ports": [{"name": "metrics", "port": 9090}, {"name": "http", "port": 8080}],
+                "endpoints": [
+                    {"addresses": ["10.1.0.1"], "conditions": {"ready": true}},
+                    {"addresses": ["10.1.0.2"], "conditions": {"ready": false}},
+                    {"addresses": ["10.1.0.3"]}
+                ]
+            }]
+        }"#;
+        let slices: k8s_types::EndpointSliceList = serde_json::from_str(body).unwrap();
+        let backends = endpoint_slices_to_backends(slices, Some("http"));
+
+        let addrs: Vec<String> = backends
+            .iter()
+            .map(|b| b.addr.as_inet().unwrap().to_string())
+            .collect();
+        assert_eq!(addrs, vec!["10.1.0.1:8080", "10.1.0.3:8080"]);
+    }
+
     #[test]
     fn test_parse_consul_response_empty() {
         let body = "[]";
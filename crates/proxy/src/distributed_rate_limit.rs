@@ -99,7 +99,7 @@ impl RedisRateLimiter {
         let connection = ConnectionManager::new(client).await?;
 
         debug!(
-            url = %backend_config.url,
+            url = %zentinel_config::secrets::redact(&backend_config.url),
             prefix = %backend_config.key_prefix,
             max_rps = rate_config.max_rps,
             "Redis rate limiter initialized"
@@ -234,7 +234,7 @@ pub async fn create_redis_rate_limiter(
     match RedisRateLimiter::new(backend_config, rate_config).await {
         Ok(limiter) => {
             debug!(
-                url = %backend_config.url,
+                url = %zentinel_config::secrets::redact(&backend_config.url),
                 "Redis rate limiter created successfully"
             );
             Some(limiter)
@@ -242,7 +242,7 @@ pub async fn create_redis_rate_limiter(
         Err(e) => {
             error!(
                 error = %e,
-                url = %backend_config.url,
+                url = %zentinel_config::secrets::redact(&backend_config.url),
                 "Failed to create Redis rate limiter"
             );
             if backend_config.fallback_local {
@@ -0,0 +1,648 @@
+//! DNS resolution of upstream hostnames.
+//!
+//! Upstream targets given as `host:port` are resolved here before every
+//! connection, according to the top-level `dns` block:
+//!
+//! - answers are cached for their record TTL, clamped to the configured
+//!   minimum and maximum; failed lookups are cached for `negative-ttl-secs`
+//! - with `nameservers`, queries go to those servers directly; otherwise the
+//!   system resolver (`getaddrinfo`, honouring `/etc/hosts`) is used, which
+//!   reports no TTL, so its answers are cached for 30 seconds within the bounds
+//! - `ip-family` restricts and orders the addresses; when a name has both IPv4
+//!   and IPv6 addresses and `happy-eyeballs` is on, connections to them are
+//!   raced as in RFC 8305 and the address that connected first is used until
+//!   the DNS answer expires
+//!
+//! IP literals skip all of this.
+
+use arc_swap::ArcSwap;
+use dashmap::DashMap;
+use hickory_resolver::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use hickory_resolver::net::runtime::TokioRuntimeProvider;
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::TokioResolver;
+use prometheus::{register_histogram_vec, register_int_counter_vec, HistogramVec, IntCounterVec};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tracing::{debug, info, trace, warn};
+use zentinel_config::{DnsConfig, IpFamilyPreference};
+
+/// TTL assumed for answers of the system resolver, which does not report one
+const SYSTEM_RESOLVER_TTL: Duration = Duration::from_secs(30);
+
+/// Resolver used for upstream connections
+static RESOLVER: LazyLock<ArcSwap<DnsResolver>> =
+    LazyLock::new(|| ArcSwap::from_pointee(DnsResolver::new(DnsConfig::default())));
+
+/// Prometheus metrics for upstream name resolution.
+struct DnsMetrics {
+    /// Lookup latency (cache misses only), per result
+    duration: HistogramVec,
+    /// Failed lookups, per reason
+    failures: IntCounterVec,
+    /// Cache lookups: `hit`, `negative_hit` or `miss`
+    cache: IntCounterVec,
+    /// Happy-eyeballs races, per winning address family (`none` when every
+    /// attempt failed)
+    races: IntCounterVec,
+}
+
+static DNS_METRICS: LazyLock<Option<DnsMetrics>> = LazyLock::new(|| {
+    let duration = register_histogram_vec!(
+        "zentinel_dns_resolution_duration_seconds",
+        "Time spent resolving upstream hostnames",
+        &["result"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    )
+    .ok()?;
+    let failures = register_int_counter_vec!(
+        "zentinel_dns_resolution_failures_total",
+        "Failed upstream hostname resolutions",
+        &["reason"]
+    )
+    .ok()?;
+    let cache = register_int_counter_vec!(
+        "zentinel_dns_cache_lookups_total",
+        "Upstream hostname lookups answered from or missing the DNS cache",
+        &["result"]
+    )
+    .ok()?;
+    let races = register_int_counter_vec!(
+        "zentinel_dns_happy_eyeballs_total",
+        "Happy-eyeballs connection races, by winning address family",
+        &["family"]
+    )
+    .ok()?;
+    Some(DnsMetrics {
+        duration,
+        failures,
+        cache,
+        races,
+    })
+});
+
+/// Set the DNS configuration for upstream connections
+///
+/// Called at startup and on every config reload. A changed configuration
+/// replaces the resolver and starts with an empty cache.
+pub fn configure(config: Option<&DnsConfig>) {
+    let config = config.cloned().unwrap_or_default();
+    if RESOLVER.load().config == config {
+        return;
+    }
+
+    info!(
+        nameservers = config.nameservers.len(),
+        min_ttl_secs = config.min_ttl_secs,
+        max_ttl_secs = config.max_ttl_secs,
+        negative_ttl_secs = config.negative_ttl_secs,
+        ip_family = ?config.ip_family,
+        happy_eyeballs = config.happy_eyeballs,
+        "DNS resolution configured"
+    );
+    RESOLVER.store(Arc::new(DnsResolver::new(config)));
+}
+
+/// The resolver for upstream connections
+pub fn resolver() -> Arc<DnsResolver> {
+    RESOLVER.load_full()
+}
+
+/// Errors resolving an upstream address
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DnsError {
+    #[error("Invalid address '{0}': expected HOST:PORT")]
+    InvalidAddress(String),
+
+    #[error("No addresses found for {0}")]
+    NoAddresses(String),
+
+    #[error("DNS lookup for {0} timed out")]
+    Timeout(String),
+
+    #[error("DNS lookup for {host} failed: {message}")]
+    Lookup { host: String, message: String },
+}
+
+impl DnsError {
+    /// Metric label for the failure
+    fn reason(&self) -> &'static str {
+        match self {
+            DnsError::InvalidAddress(_) => "invalid_address",
+            DnsError::NoAddresses(_) => "no_addresses",
+            DnsError::Timeout(_) => "timeout",
+            DnsError::Lookup { .. } => "lookup_error",
+        }
+    }
+}
+
+/// Where lookups go
+enum Backend {
+    /// Configured nameservers, queried directly
+    Nameservers(TokioResolver),
+    /// The system resolver
+    System,
+}
+
+/// Cached answer for a hostname
+#[derive(Debug, Clone)]
+enum Answer {
+    /// Addresses in connection order
+    Addresses(Arc<[IpAddr]>),
+    /// The lookup failed
+    Failed(DnsError),
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    answer: Answer,
+    expires_at: Instant,
+}
+
+/// Caching resolver for upstream hostnames
+pub struct DnsResolver {
+    config: DnsConfig,
+    backend: Backend,
+    cache: DashMap<String, CacheEntry>,
+    /// Happy-eyeballs winner per `host:port`, until the DNS answer expires
+    preferred: DashMap<String, (SocketAddr, Instant)>,
+}
+
+impl DnsResolver {
+    /// Create a resolver for `config`
+    ///
+    /// Falls back to the system resolver if the nameserver client cannot be
+    /// built.
+    pub fn new(config: DnsConfig) -> Self {
+        let backend = if config.nameservers.is_empty() {
+            Backend::System
+        } else {
+            match build_resolver(&config) {
+                Ok(resolver) => Backend::Nameservers(resolver),
+                Err(e) => {
+                    warn!(
+                        error = %e,
+                        "Failed to create DNS resolver for configured nameservers, using the system resolver"
+                    );
+                    Backend::System
+                }
+            }
+        };
+
+        Self {
+            config,
+            backend,
+            cache: DashMap::new(),
+            preferred: DashMap::new(),
+        }
+    }
+
+    /// Resolve an upstream `host:port` to the address to connect to
+    ///
+    /// `connect_timeout` bounds the happy-eyeballs race; if no address
+    /// connects within it, the first address is returned and the connection
+    /// error is left to the caller.
+    pub async fn resolve_target(
+        &self,
+        address: &str,
+        connect_timeout: Duration,
+    ) -> Result<SocketAddr, DnsError> {
+        if let Ok(addr) = address.parse::<SocketAddr>() {
+            return Ok(addr);
+        }
+
+        if let Some(entry) = self.preferred.get(address) {
+            if entry.1 > Instant::now() {
+                return Ok(entry.0);
+            }
+        }
+
+        let (host, port) = split_host_port(address)?;
+        let (ips, expires_at) = self.lookup(host).await?;
+        let addrs: Vec<SocketAddr> = ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect();
+
+        let mixed = addrs.iter().any(SocketAddr::is_ipv4) && addrs.iter().any(SocketAddr::is_ipv6);
+        if !self.config.happy_eyeballs || !mixed {
+            return Ok(addrs[0]);
+        }
+
+        let delay = Duration::from_millis(self.config.happy_eyeballs_delay_ms);
+        let winner = race_connections(&addrs, delay, connect_timeout).await;
+        if let Some(metrics) = DNS_METRICS.as_ref() {
+            let family = match winner {
+                Some(SocketAddr::V4(_)) => "ipv4",
+                Some(SocketAddr::V6(_)) => "ipv6",
+                None => "none",
+            };
+            metrics.races.with_label_values(&[family]).inc();
+        }
+
+        match winner {
+            Some(addr) => {
+                debug!(target = %address, address = %addr, "Happy-eyeballs race won");
+                self.preferred
+                    .insert(address.to_string(), (addr, expires_at));
+                Ok(addr)
+            }
+            None => Ok(addrs[0]),
+        }
+    }
+
+    /// Addresses of `host` in connection order, and when they expire
+    async fn lookup(&self, host: &str) -> Result<(Arc<[IpAddr]>, Instant), DnsError> {
+        if let Some(entry) = self.cache.get(host) {
+            if entry.expires_at > Instant::now() {
+                let (result, answer) = match &entry.answer {
+                    Answer::Addresses(ips) => ("hit", Ok((Arc::clone(ips), entry.expires_at))),
+                    Answer::Failed(e) => ("negative_hit", Err(e.clone())),
+                };
+                record_cache(result);
+                return answer;
+            }
+        }
+        record_cache("miss");
+
+        let start = Instant::now();
+        let result = self.query(host).await;
+        let elapsed = start.elapsed();
+
+        match result {
+            Ok((ips, ttl)) => {
+                if let Some(metrics) = DNS_METRICS.as_ref() {
+                    metrics
+                        .duration
+                        .with_label_values(&["success"])
+                        .observe(elapsed.as_secs_f64());
+                }
+                let ips: Arc<[IpAddr]> = order_addresses(ips, self.config.ip_family).into();
+                if ips.is_empty() {
+                    return self.fail(host, DnsError::NoAddresses(host.to_string()));
+                }
+
+                let ttl = self.clamp_ttl(ttl);
+                let expires_at = Instant::now() + ttl;
+                trace!(
+                    host = %host,
+                    addresses = ips.len(),
+                    ttl_secs = ttl.as_secs(),
+                    duration_ms = elapsed.as_millis() as u64,
+                    "Resolved upstream hostname"
+                );
+                self.cache.insert(
+                    host.to_string(),
+                    CacheEntry {
+                        answer: Answer::Addresses(Arc::clone(&ips)),
+                        expires_at,
+                    },
+                );
+                Ok((ips, expires_at))
+            }
+            Err(e) => {
+                if let Some(metrics) = DNS_METRICS.as_ref() {
+                    metrics
+                        .duration
+                        .with_label_values(&["failure"])
+                        .observe(elapsed.as_secs_f64());
+                }
+                self.fail(host, e)
+            }
+        }
+    }
+
+    /// Record a failed lookup and cache it for the negative TTL
+    fn fail<T>(&self, host: &str, error: DnsError) -> Result<T, DnsError> {
+        warn!(host = %host, error = %error, "Failed to resolve upstream hostname");
+        if let Some(metrics) = DNS_METRICS.as_ref() {
+            metrics.failures.with_label_values(&[error.reason()]).inc();
+        }
+
+        if self.config.negative_ttl_secs > 0 {
+            self.cache.insert(
+                host.to_string(),
+                CacheEntry {
+                    answer: Answer::Failed(error.clone()),
+                    expires_at: Instant::now() + Duration::from_secs(self.config.negative_ttl_secs),
+                },
+            );
+        }
+        Err(error)
+    }
+
+    /// Query the backend for `host`, returning its addresses and TTL
+    async fn query(&self, host: &str) -> Result<(Vec<IpAddr>, Duration), DnsError> {
+        let resolver = match &self.backend {
+            Backend::Nameservers(resolver) => resolver,
+            Backend::System => {
+                let addrs =
+                    tokio::time::timeout(self.lookup_timeout(), tokio::net::lookup_host((host, 0)))
+                        .await
+                        .map_err(|_| DnsError::Timeout(host.to_string()))?
+                        .map_err(|e| DnsError::Lookup {
+                            host: host.to_string(),
+                            message: e.to_string(),
+                        })?;
+                return Ok((addrs.map(|addr| addr.ip()).collect(), SYSTEM_RESOLVER_TTL));
+            }
+        };
+
+        let (v4, v6) = match self.config.ip_family {
+            IpFamilyPreference::Ipv4Only => (
+                self.query_records(resolver, host, RecordType::A).await,
+                Ok((Vec::new(), None)),
+            ),
+            IpFamilyPreference::Ipv6Only => (
+                Ok((Vec::new(), None)),
+                self.query_records(resolver, host, RecordType::AAAA).await,
+            ),
+            IpFamilyPreference::PreferIpv4 | IpFamilyPreference::PreferIpv6 => tokio::join!(
+                self.query_records(resolver, host, RecordType::A),
+                self.query_records(resolver, host, RecordType::AAAA)
+            ),
+        };
+
+        // One family failing is fine as long as the other answered
+        let (mut ips, mut ttl) = (Vec::new(), None::<u32>);
+        let mut error = None;
+        for result in [v4, v6] {
+            match result {
+                Ok((found, found_ttl)) => {
+                    ips.extend(found);
+                    ttl = match (ttl, found_ttl) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+
+        match error {
+            Some(e) if ips.is_empty() => Err(e),
+            _ => Ok((ips, Duration::from_secs(u64::from(ttl.unwrap_or(0))))),
+        }
+    }
+
+    /// Addresses of one record type and their lowest TTL
+    async fn query_records(
+        &self,
+        resolver: &TokioResolver,
+        host: &str,
+        record_type: RecordType,
+    ) -> Result<(Vec<IpAddr>, Option<u32>), DnsError> {
+        let lookup =
+            tokio::time::timeout(self.lookup_timeout(), resolver.lookup(host, record_type))
+                .await
+                .map_err(|_| DnsError::Timeout(host.to_string()))?
+                .map_err(|e| DnsError::Lookup {
+                    host: host.to_string(),
+                    message: e.to_string(),
+                })?;
+
+        let mut ips = Vec::new();
+        let mut ttl = None::<u32>;
+        for record in lookup.answers() {
+            let ip = match &record.data {
+                RData::A(a) => IpAddr::V4(a.0),
+                RData::AAAA(aaaa) => IpAddr::V6(aaaa.0),
+                // CNAMEs and other records in the answer chain
+                _ => continue,
+            };
+            ips.push(ip);
+            ttl = Some(ttl.map_or(record.ttl, |t| t.min(record.ttl)));
+        }
+        Ok((ips, ttl))
+    }
+
+    /// Upper bound for one lookup, including retries
+    fn lookup_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.timeout_ms) * self.config.attempts.max(1) as u32
+    }
+
+    /// Record TTL clamped to the configured bounds
+    fn clamp_ttl(&self, ttl: Duration) -> Duration {
+        ttl.clamp(
+            Duration::from_secs(self.config.min_ttl_secs),
+            Duration::from_secs(self.config.max_ttl_secs.max(self.config.min_ttl_secs)),
+        )
+    }
+}
+
+/// Build a client for the configured nameservers, without its own cache
+fn build_resolver(config: &DnsConfig) -> Result<TokioResolver, String> {
+    let mut resolver_config = ResolverConfig::from_parts(None, vec![], vec![]);
+    for ip in &config.nameservers {
+        resolver_config.add_name_server(NameServerConfig::udp(*ip));
+    }
+
+    let mut opts = ResolverOpts::default();
+    opts.timeout = Duration::from_millis(config.timeout_ms);
+    opts.attempts = config.attempts.max(1);
+    // Answers are cached by `DnsResolver`, with TTL clamping
+    opts.cache_size = 0;
+
+    TokioResolver::builder_with_config(resolver_config, TokioRuntimeProvider::default())
+        .with_options(opts)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn record_cache(result: &str) {
+    if let Some(metrics) = DNS_METRICS.as_ref() {
+        metrics.cache.with_label_values(&[result]).inc();
+    }
+}
+
+/// Split `host:port`, removing the brackets of an IPv6 host
+fn split_host_port(address: &str) -> Result<(&str, u16), DnsError> {
+    let invalid = || DnsError::InvalidAddress(address.to_string());
+    let (host, port) = address.rsplit_once(':').ok_or_else(invalid)?;
+    let port = port.parse().map_err(|_| invalid())?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    Ok((host, port))
+}
+
+/// Filter addresses by family and interleave them, preferred family first
+/// (RFC 8305, section 4)
+fn order_addresses(ips: Vec<IpAddr>, family: IpFamilyPreference) -> Vec<IpAddr> {
+    let (v4, v6): (Vec<IpAddr>, Vec<IpAddr>) = ips.into_iter().partition(IpAddr::is_ipv4);
+    let (first, second) = match family {
+        IpFamilyPreference::Ipv4Only => (v4, Vec::new()),
+        IpFamilyPreference::Ipv6Only => (v6, Vec::new()),
+        IpFamilyPreference::PreferIpv4 => (v4, v6),
+        IpFamilyPreference::PreferIpv6 => (v6, v4),
+    };
+
+    let mut ordered = Vec::with_capacity(first.len() + second.len());
+    let (mut first, mut second) = (first.into_iter(), second.into_iter());
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
+/// Connect to `addrs` in order, starting the next attempt after `delay` or
+/// as soon as one fails, and return the first address that connects
+async fn race_connections(
+    addrs: &[SocketAddr],
+    delay: Duration,
+    timeout: Duration,
+) -> Option<SocketAddr> {
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+
+    let mut pending = addrs.iter().copied().peekable();
+    // Dropping the set aborts the attempts still running
+    let mut attempts = JoinSet::new();
+    loop {
+        if let Some(addr) = pending.next() {
+            attempts.spawn(async move { TcpStream::connect(addr).await.map(|_| addr) });
+        } else if attempts.is_empty() {
+            return None;
+        }
+
+        let more = pending.peek().is_some();
+        tokio::select! {
+            _ = &mut deadline => return None,
+            result = attempts.join_next() => {
+                if let Some(Ok(Ok(addr))) = result {
+                    return Some(addr);
+                }
+            }
+            _ = tokio::time::sleep(delay), if more => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("api.internal:8080").unwrap(),
+            ("api.internal", 8080)
+        );
+        assert_eq!(split_host_port("[::1]:443").unwrap(), ("::1", 443));
+        assert!(split_host_port("api.internal").is_err());
+        assert!(split_host_port("api.internal:http").is_err());
+        assert!(split_host_port(":80").is_err());
+    }
+
+    #[test]
+    fn test_order_addresses() {
+        let ips = vec![ip("10.0.0.1"), ip("10.0.0.2"), ip("fd00::1"), ip("fd00::2")];
+
+        assert_eq!(
+            order_addresses(ips.clone(), IpFamilyPreference::PreferIpv6),
+            vec![ip("fd00::1"), ip("10.0.0.1"), ip("fd00::2"), ip("10.0.0.2")]
+        );
+        assert_eq!(
+            order_addresses(ips.clone(), IpFamilyPreference::PreferIpv4),
+            vec![ip("10.0.0.1"), ip("fd00::1"), ip("10.0.0.2"), ip("fd00::2")]
+        );
+        assert_eq!(
+            order_addresses(ips.clone(), IpFamilyPreference::Ipv4Only),
+            vec![ip("10.0.0.1"), ip("10.0.0.2")]
+        );
+        assert!(order_addresses(vec![ip("10.0.0.1")], IpFamilyPreference::Ipv6Only).is_empty());
+    }
+
+    #[test]
+    fn test_clamp_ttl() {
+        let resolver = DnsResolver::new(DnsConfig {
+            min_ttl_secs: 5,
+            max_ttl_secs: 60,
+            ..Default::default()
+        });
+        assert_eq!(resolver.clamp_ttl(Duration::ZERO), Duration::from_secs(5));
+        assert_eq!(
+            resolver.clamp_ttl(Duration::from_secs(30)),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            resolver.clamp_ttl(Duration::from_secs(86400)),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_answers() {
+        let resolver = DnsResolver::new(DnsConfig::default());
+        let expires_at = Instant::now() + Duration::from_secs(60);
+        resolver.cache.insert(
+            "api.internal".to_string(),
+            CacheEntry {
+                answer: Answer::Addresses(vec![ip("10.0.0.7")].into()),
+                expires_at,
+            },
+        );
+        resolver.cache.insert(
+            "gone.internal".to_string(),
+            CacheEntry {
+                answer: Answer::Failed(DnsError::NoAddresses("gone.internal".to_string())),
+                expires_at,
+            },
+        );
+
+        assert_eq!(
+            resolver
+                .resolve_target("api.internal:8080", Duration::from_secs(1))
+                .await
+                .unwrap(),
+            "10.0.0.7:8080".parse().unwrap()
+        );
+        assert_eq!(
+            resolver
+                .resolve_target("gone.internal:8080", Duration::from_secs(1))
+                .await,
+            Err(DnsError::NoAddresses("gone.internal".to_string()))
+        );
+
+        // IP literals are never looked up
+        assert_eq!(
+            resolver
+                .resolve_target("[::1]:9000", Duration::from_secs(1))
+                .await
+                .unwrap(),
+            "[::1]:9000".parse().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_race_prefers_address_that_connects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap();
+        let closed = {
+            let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            l.local_addr().unwrap()
+        };
+
+        let winner = race_connections(
+            &[closed, open],
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(winner, Some(open));
+
+        assert_eq!(
+            race_connections(&[closed], Duration::from_millis(10), Duration::from_secs(5)).await,
+            None
+        );
+    }
+}
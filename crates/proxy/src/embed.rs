@@ -0,0 +1,311 @@
+//! Embedding the proxy in another application.
+//!
+//! [`ZentinelBuilder`] runs Zentinel inside an existing program, for
+//! integration tests or custom distributions:
+//!
+//! - the configuration can be built in code instead of read from a file
+//! - agents can be [`AgentHandlerV2`] implementations in the same process,
+//!   called directly instead of over a socket
+//! - the proxy is started and stopped from the embedder's tokio runtime
+//! - request lifecycle events can be subscribed to
+//!
+//! ```ignore
+//! use zentinel_proxy::embed::ZentinelBuilder;
+//!
+//! let builder = ZentinelBuilder::new()
+//!     .config(config)
+//!     .agent("waf", Arc::new(MyWaf));
+//! let mut events = builder.subscribe();
+//! let zentinel = builder.start().await?;
+//!
+//! while let Ok(event) = events.recv().await {
+//!     println!("{:?}", event);
+//! }
+//!
+//! zentinel.stop().await;
+//! ```
+//!
+//! An in-process agent still needs an `agent` entry in the configuration (for
+//! its events, timeouts and failure mode, and so routes can reference it),
+//! normally with the `in-process` transport; any other transport is replaced.
+//!
+//! Pingora runs its worker threads on a dedicated thread, while the proxy's
+//! background tasks (health checks, agent pools, service discovery) run on the
+//! runtime `start` is awaited on. The embedder owns process concerns the
+//! `zentinel` binary handles itself: logging setup, signal handling, ACME,
+//! the standalone metrics listener and config file watching. Configuration
+//! updates go through [`ConfigManager::apply_config`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use pingora::prelude::*;
+use pingora::server::{RunArgs, ShutdownSignal, ShutdownSignalWatch};
+use tokio::sync::{broadcast, oneshot};
+use tracing::info;
+use zentinel_agent_protocol::v2::AgentHandlerV2;
+use zentinel_config::Config;
+
+use crate::agents::{AgentManager, AgentSupervisor};
+use crate::lifecycle::{LifecycleEvent, LifecycleEvents, DEFAULT_CAPACITY};
+use crate::listeners::{add_listeners, register_tls_resolvers};
+use crate::proxy::{ZentinelProxy, EMBEDDED_CONFIG_PATH};
+use crate::reload::ConfigManager;
+
+/// Builder for a proxy embedded in another application
+pub struct ZentinelBuilder {
+    config: Option<Config>,
+    config_path: Option<String>,
+    in_process_agents: HashMap<String, Arc<dyn AgentHandlerV2>>,
+    worker_threads: Option<usize>,
+    lifecycle_events: LifecycleEvents,
+}
+
+impl ZentinelBuilder {
+    /// Start from the embedded default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            config_path: None,
+            in_process_agents: HashMap::new(),
+            worker_threads: None,
+            lifecycle_events: LifecycleEvents::new(DEFAULT_CAPACITY),
+        }
+    }
+
+    /// Use a configuration built in code.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self.config_path = None;
+        self
+    }
+
+    /// Load the configuration from a file (KDL, JSON or TOML).
+    pub fn config_file(mut self, path: impl Into<String>) -> Self {
+        self.config_path = Some(path.into());
+        self.config = None;
+        self
+    }
+
+    /// Serve the configured agent `agent_id` from `handler` in this process.
+    pub fn agent(mut self, agent_id: impl Into<String>, handler: Arc<dyn AgentHandlerV2>) -> Self {
+        self.in_process_agents.insert(agent_id.into(), handler);
+        self
+    }
+
+    /// Pingora worker threads; defaults to `server.worker-threads`, or the
+    /// number of CPUs when that is 0.
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = Some(threads);
+        self
+    }
+
+    /// Buffer `capacity` lifecycle events per subscriber.
+    ///
+    /// Replaces the event channel, so call it before [`subscribe`](Self::subscribe).
+    pub fn lifecycle_event_capacity(mut self, capacity: usize) -> Self {
+        self.lifecycle_events = LifecycleEvents::new(capacity);
+        self
+    }
+
+    /// Receive lifecycle events, including those of the first requests.
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle_events.subscribe()
+    }
+
+    /// Build the proxy and start serving its listeners.
+    pub async fn start(self) -> Result<Zentinel> {
+        let (config, config_path) = match (self.config, self.config_path) {
+            (Some(config), _) => (config, EMBEDDED_CONFIG_PATH.to_string()),
+            (None, Some(path)) => (
+                Config::from_file(&path).context("Failed to load configuration file")?,
+                path,
+            ),
+            (None, None) => (
+                Config::default_embedded()
+                    .context("Failed to load embedded default configuration")?,
+                EMBEDDED_CONFIG_PATH.to_string(),
+            ),
+        };
+
+        let mut proxy =
+            ZentinelProxy::from_config(config, &config_path, self.in_process_agents).await?;
+        proxy.lifecycle_events = self.lifecycle_events.clone();
+
+        let config_manager = proxy.config_manager.clone();
+        let agent_manager = proxy.agent_manager();
+        let upstream_pools = proxy.upstream_pools();
+        let agent_supervisor = proxy.agent_supervisor.clone();
+        let config = config_manager.current();
+
+        let cert_reloader = config_manager.cert_reloader();
+        register_tls_resolvers(&config.listeners, &cert_reloader);
+
+        let mut server_conf = pingora::server::configuration::ServerConf::default();
+        server_conf.threads = self
+            .worker_threads
+            .unwrap_or(if config.server.worker_threads > 0 {
+                config.server.worker_threads
+            } else {
+                num_cpus::get()
+            });
+        server_conf.work_stealing = true;
+        server_conf.graceful_shutdown_timeout_seconds =
+            Some(config.server.graceful_shutdown_timeout_secs);
+
+        let mut server = Server::new_with_opt_and_conf(Some(Opt::default()), server_conf);
+        server.bootstrap();
+
+        let keepalive_request_limit = config
+            .listeners
+            .iter()
+            .filter_map(|l| l.keepalive_max_requests)
+            .min();
+        // Only the internal sockets behind HTTPS acceptors accept h2c, since
+        // the acceptors hand decrypted HTTP/2 to Pingora as cleartext
+        let server_options = |h2c| {
+            let mut server_options = pingora_core::apps::HttpServerOptions::default();
+            server_options.keepalive_request_limit = keepalive_request_limit;
+            server_options.h2c = h2c;
+            server_options
+        };
+        let mut proxy_service =
+            pingora_proxy::ProxyServiceBuilder::new(&server.configuration, proxy.clone())
+                .name("Zentinel Proxy")
+                .server_options(server_options(false))
+                .build();
+        let mut internal_service =
+            pingora_proxy::ProxyServiceBuilder::new(&server.configuration, proxy)
+                .name("Zentinel Proxy (internal)")
+                .server_options(server_options(true))
+                .build();
+        add_listeners(
+            &mut proxy_service,
+            &mut internal_service,
+            &config.listeners,
+            &cert_reloader,
+            config.server.max_connections,
+            &tokio::runtime::Handle::current(),
+        );
+        crate::forward_proxy::spawn_listeners(
+            &config.listeners,
+            Arc::clone(&config_manager),
+            Arc::clone(&agent_manager),
+            &tokio::runtime::Handle::current(),
+        );
+        crate::stream_proxy::spawn_listeners(
+            &config.listeners,
+            Arc::clone(&config_manager),
+            Arc::clone(&agent_manager),
+            upstream_pools,
+            &tokio::runtime::Handle::current(),
+        );
+        server.add_service(proxy_service);
+        server.add_service(internal_service);
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let mut run_args = RunArgs::default();
+        run_args.shutdown_signal = Box::new(EmbeddedShutdown(Mutex::new(Some(shutdown_rx))));
+        let server_thread = std::thread::Builder::new()
+            .name("zentinel-server".to_string())
+            .spawn(move || server.run(run_args))
+            .context("Failed to spawn server thread")?;
+
+        info!(
+            listeners = config.listeners.len(),
+            "Embedded Zentinel proxy started"
+        );
+
+        Ok(Zentinel {
+            config_manager,
+            agent_manager,
+            agent_supervisor,
+            lifecycle_events: self.lifecycle_events,
+            shutdown_tx: Some(shutdown_tx),
+            server_thread: Some(server_thread),
+        })
+    }
+}
+
+impl Default for ZentinelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A running embedded proxy
+///
+/// Dropping it stops the server without waiting; [`stop`](Self::stop) also
+/// waits for the server thread and shuts down agents.
+pub struct Zentinel {
+    config_manager: Arc<ConfigManager>,
+    agent_manager: Arc<AgentManager>,
+    agent_supervisor: Option<Arc<AgentSupervisor>>,
+    lifecycle_events: LifecycleEvents,
+    shutdown_tx: Option<oneshot::Sender<ShutdownSignal>>,
+    server_thread: Option<JoinHandle<()>>,
+}
+
+impl Zentinel {
+    /// Configuration manager, to read or replace the running configuration
+    pub fn config_manager(&self) -> &Arc<ConfigManager> {
+        &self.config_manager
+    }
+
+    /// Receive lifecycle events from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle_events.subscribe()
+    }
+
+    /// Stop immediately, dropping in-flight requests.
+    pub async fn stop(self) {
+        self.shutdown(ShutdownSignal::FastShutdown).await;
+    }
+
+    /// Stop accepting connections and let in-flight requests finish, within
+    /// `server.graceful-shutdown-timeout-secs`.
+    pub async fn stop_gracefully(self) {
+        self.shutdown(ShutdownSignal::GracefulTerminate).await;
+    }
+
+    async fn shutdown(mut self, signal: ShutdownSignal) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(signal);
+        }
+        if let Some(thread) = self.server_thread.take() {
+            let _ = tokio::task::spawn_blocking(move || thread.join()).await;
+        }
+
+        self.agent_manager.shutdown().await;
+        if let Some(ref supervisor) = self.agent_supervisor {
+            supervisor.shutdown().await;
+        }
+        info!("Embedded Zentinel proxy stopped");
+    }
+}
+
+impl Drop for Zentinel {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(ShutdownSignal::FastShutdown);
+        }
+    }
+}
+
+/// Shutdown signal sent by [`Zentinel`] instead of an OS signal
+struct EmbeddedShutdown(Mutex<Option<oneshot::Receiver<ShutdownSignal>>>);
+
+#[async_trait]
+impl ShutdownSignalWatch for EmbeddedShutdown {
+    async fn recv(&self) -> ShutdownSignal {
+        let Some(rx) = self.0.lock().take() else {
+            return std::future::pending().await;
+        };
+        // A dropped sender means the handle is gone: stop the server
+        rx.await.unwrap_or(ShutdownSignal::FastShutdown)
+    }
+}
@@ -0,0 +1,406 @@
+//! Block page rendering for agent block decisions.
+//!
+//! Agents only return a status and an optional message; this module turns
+//! that into a full response using per-route HTML/JSON templates (or the
+//! built-in branded page), choosing the format from the request's `Accept`
+//! header.
+
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use http::{Response, StatusCode};
+use http_body_util::Full;
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+use zentinel_config::{BlockPageConfig, BlockPageFormat};
+
+use crate::agents::{AgentAction, AgentDecision};
+
+/// An agent block decision waiting to be rendered.
+#[derive(Debug, Clone)]
+pub struct AgentBlock {
+    /// HTTP status requested by the agent
+    pub status: u16,
+    /// Message (block body) supplied by the agent
+    pub message: Option<String>,
+    /// Agent that produced the block
+    pub agent_id: Option<String>,
+    /// Rule IDs reported in the agents' audit metadata
+    pub rule_ids: Vec<String>,
+    /// Response headers supplied by the agent
+    pub headers: HashMap<String, String>,
+}
+
+impl AgentBlock {
+    /// Extract block details from a decision (`None` unless it is a block).
+    pub fn from_decision(decision: &AgentDecision) -> Option<Self> {
+        let AgentAction::Block {
+            status,
+            body,
+            headers,
+        } = &decision.action
+        else {
+            return None;
+        };
+
+        Some(Self {
+            status: *status,
+            message: body.clone(),
+            agent_id: decision.decided_by.clone(),
+            rule_ids: decision
+                .audit
+                .iter()
+                .flat_map(|a| a.rule_ids.iter().cloned())
+                .collect(),
+            headers: headers.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Renders block responses for a route.
+pub struct BlockPageRenderer {
+    /// Format used when `Accept` expresses no preference
+    default_format: BlockPageFormat,
+    /// Custom HTML template (built-in page when `None`)
+    html_template: Option<String>,
+    /// Custom JSON template (built-in document when `None`)
+    json_template: Option<String>,
+}
+
+impl BlockPageRenderer {
+    /// Create a renderer, loading the configured templates from disk.
+    ///
+    /// Templates that fail to load fall back to the built-in ones.
+    pub fn new(config: &BlockPageConfig) -> Self {
+        let load = |path: &Option<std::path::PathBuf>| {
+            let path = path.as_ref()?;
+            match std::fs::read_to_string(path) {
+                Ok(content) => {
+                    debug!("Loaded block page template: {:?}", path);
+                    Some(content)
+                }
+                Err(e) => {
+                    warn!("Failed to load block page template {:?}: {}", path, e);
+                    None
+                }
+            }
+        };
+
+        Self {
+            default_format: config.default_format,
+            html_template: load(&config.html_template),
+            json_template: load(&config.json_template),
+        }
+    }
+
+    /// Render the block response for a request.
+    pub fn render(
+        &self,
+        block: &AgentBlock,
+        correlation_id: &str,
+        accept: Option<&str>,
+    ) -> Response<Full<Bytes>> {
+        let status = StatusCode::from_u16(block.status).unwrap_or(StatusCode::FORBIDDEN);
+        let title = status.canonical_reason().unwrap_or("Blocked");
+        let message = block
+            .message
+            .as_deref()
+            .unwrap_or("This request was blocked by a security policy.");
+        let vars = [
+            ("status", status.as_u16().to_string()),
+            ("title", title.to_string()),
+            ("message", message.to_string()),
+            ("correlation_id", correlation_id.to_string()),
+            ("agent", block.agent_id.clone().unwrap_or_default()),
+            ("rule_ids", block.rule_ids.join(",")),
+            ("timestamp", chrono::Utc::now().timestamp().to_string()),
+        ];
+
+        let (body, content_type) = match negotiate_format(accept, self.default_format) {
+            BlockPageFormat::Html => {
+                let template = self.html_template.as_deref().unwrap_or(DEFAULT_HTML);
+                (
+                    render_template(template, &vars, escape_html),
+                    "text/html; charset=utf-8",
+                )
+            }
+            BlockPageFormat::Json => {
+                let body = match self.json_template.as_deref() {
+                    Some(template) => render_template(template, &vars, escape_json),
+                    None => default_json(block, title, message, correlation_id),
+                };
+                (body, "application/json; charset=utf-8")
+            }
+        };
+
+        let mut response = Response::new(Full::new(Bytes::from(body)));
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        if let Ok(value) = HeaderValue::from_str(correlation_id) {
+            headers.insert("X-Correlation-Id", value);
+        }
+        for (name, value) in &block.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => warn!(header = %name, "Ignoring invalid agent block header"),
+            }
+        }
+
+        response
+    }
+}
+
+/// Pick HTML or JSON from an `Accept` header by quality value.
+///
+/// Ties (including a missing header or `*/*`) resolve to `default`.
+pub(super) fn negotiate_format(accept: Option<&str>, default: BlockPageFormat) -> BlockPageFormat {
+    let Some(accept) = accept else {
+        return default;
+    };
+
+    let mut html_q = 0.0f32;
+    let mut json_q = 0.0f32;
+    for range in accept.split(',') {
+        let mut parts = range.split(';');
+        let media = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let q = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        match media.as_str() {
+            "text/html" | "application/xhtml+xml" | "text/*" => html_q = html_q.max(q),
+            "application/json" | "application/problem+json" | "application/*" => {
+                json_q = json_q.max(q)
+            }
+            "*/*" => {
+                html_q = html_q.max(q * 0.5);
+                json_q = json_q.max(q * 0.5);
+            }
+            _ => {}
+        }
+    }
+
+    if html_q > json_q {
+        BlockPageFormat::Html
+    } else if json_q > html_q {
+        BlockPageFormat::Json
+    } else {
+        default
+    }
+}
+
+/// Replace `{{name}}` placeholders, escaping values for the output format.
+fn render_template(template: &str, vars: &[(&str, String)], escape: fn(&str) -> String) -> String {
+    vars.iter()
+        .fold(template.to_string(), |rendered, (name, value)| {
+            rendered.replace(&format!("{{{{{}}}}}", name), &escape(value))
+        })
+}
+
+/// Escape a value for HTML text and attribute content.
+pub(super) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape a value for use inside a JSON string literal.
+fn escape_json(s: &str) -> String {
+    let quoted = serde_json::Value::String(s.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Built-in JSON block document.
+fn default_json(block: &AgentBlock, title: &str, message: &str, correlation_id: &str) -> String {
+    serde_json::json!({
+        "error": {
+            "status": block.status,
+            "title": title,
+            "message": message,
+            "correlation_id": correlation_id,
+            "agent": block.agent_id,
+            "rule_ids": block.rule_ids,
+        }
+    })
+    .to_string()
+}
+
+/// Built-in branded HTML block page.
+const DEFAULT_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{status}} {{title}}</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            color: #333;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            min-height: 100vh;
+            margin: 0;
+            padding: 20px;
+        }
+        .block-container {
+            background: white;
+            border-radius: 12px;
+            box-shadow: 0 20px 60px rgba(0,0,0,0.3);
+            padding: 40px;
+            max-width: 600px;
+            width: 100%;
+            text-align: center;
+        }
+        h1 {
+            color: #764ba2;
+            font-size: 72px;
+            margin: 0;
+        }
+        h2 {
+            color: #666;
+            font-size: 24px;
+            margin: 10px 0;
+            font-weight: normal;
+        }
+        p {
+            color: #777;
+            font-size: 16px;
+            line-height: 1.6;
+            margin: 20px 0;
+        }
+        .reference {
+            background: #f5f5f5;
+            border-radius: 4px;
+            padding: 8px 12px;
+            font-family: 'Courier New', monospace;
+            font-size: 12px;
+            color: #999;
+            margin-top: 30px;
+        }
+    </style>
+</head>
+<body>
+    <div class="block-container">
+        <h1>{{status}}</h1>
+        <h2>Request blocked</h2>
+        <p>{{message}}</p>
+        <div class="reference">Reference: {{correlation_id}}</div>
+        <p>Protected by Zentinel</p>
+    </div>
+</body>
+</html>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use zentinel_agent_protocol::AuditMetadata;
+
+    fn waf_block() -> AgentBlock {
+        let mut decision =
+            AgentDecision::block(403, "SQL injection detected <script>").with_decided_by("waf");
+        decision.audit.push(AuditMetadata {
+            rule_ids: vec!["942100".to_string(), "942110".to_string()],
+            ..Default::default()
+        });
+        AgentBlock::from_decision(&decision).unwrap()
+    }
+
+    async fn body_string(response: Response<Full<Bytes>>) -> String {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn from_decision_ignores_non_block_actions() {
+        assert!(AgentBlock::from_decision(&AgentDecision::default_allow()).is_none());
+    }
+
+    #[test]
+    fn negotiates_format_from_accept() {
+        let html = BlockPageFormat::Html;
+        let json = BlockPageFormat::Json;
+
+        assert_eq!(negotiate_format(None, html), html);
+        assert_eq!(negotiate_format(Some("*/*"), json), json);
+        assert_eq!(negotiate_format(Some("application/json"), html), json);
+        assert_eq!(
+            negotiate_format(Some("text/html,application/xhtml+xml,*/*;q=0.8"), json),
+            html
+        );
+        assert_eq!(
+            negotiate_format(Some("text/html;q=0.5, application/json"), html),
+            json
+        );
+    }
+
+    #[tokio::test]
+    async fn renders_default_html_with_escaped_variables() {
+        let renderer = BlockPageRenderer::new(&BlockPageConfig::default());
+        let response = renderer.render(&waf_block(), "req-123", Some("text/html"));
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = body_string(response).await;
+        assert!(body.contains("req-123"));
+        assert!(body.contains("SQL injection detected &lt;script&gt;"));
+    }
+
+    #[tokio::test]
+    async fn renders_default_json() {
+        let renderer = BlockPageRenderer::new(&BlockPageConfig::default());
+        let response = renderer.render(&waf_block(), "req-123", Some("application/json"));
+
+        let body: serde_json::Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(body["error"]["status"], 403);
+        assert_eq!(body["error"]["agent"], "waf");
+        assert_eq!(body["error"]["correlation_id"], "req-123");
+        assert_eq!(body["error"]["rule_ids"][1], "942110");
+    }
+
+    #[tokio::test]
+    async fn renders_custom_json_template() {
+        let renderer = BlockPageRenderer {
+            default_format: BlockPageFormat::Json,
+            html_template: None,
+            json_template: Some(
+                r#"{"blocked":true,"ref":"{{correlation_id}}","why":"{{message}}","rules":"{{rule_ids}}"}"#
+                    .to_string(),
+            ),
+        };
+        let mut block = waf_block();
+        block.message = Some("quote \" inside".to_string());
+        let response = renderer.render(&block, "req-9", None);
+
+        let body: serde_json::Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(body["ref"], "req-9");
+        assert_eq!(body["why"], "quote \" inside");
+        assert_eq!(body["rules"], "942100,942110");
+    }
+
+    #[test]
+    fn agent_headers_are_applied() {
+        let renderer = BlockPageRenderer::new(&BlockPageConfig::default());
+        let mut block = waf_block();
+        block
+            .headers
+            .insert("Retry-After".to_string(), "30".to_string());
+
+        let response = renderer.render(&block, "req-1", None);
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "30");
+    }
+}
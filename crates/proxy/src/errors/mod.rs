@@ -1,7 +1,15 @@
 //! Error handling module for Zentinel proxy
 //!
 //! This module provides customizable error page generation for different
-//! service types (web, API, static) and formats (HTML, JSON, text, XML).
+//! service types (web, API, static) and formats (HTML, JSON, text, XML,
+//! RFC 9457 problem details), and templated block pages for agent block
+//! decisions.
+
+mod block_page;
+mod problem;
+
+pub use block_page::{AgentBlock, BlockPageRenderer};
+pub use problem::{ProblemDetails, ProblemMapper, PROBLEM_JSON};
 
 use anyhow::Result;
 use bytes::Bytes;
@@ -12,7 +20,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, warn};
 
-use zentinel_config::{ErrorFormat, ErrorPage, ErrorPageConfig, ServiceType};
+use zentinel_config::{ErrorFormat, ErrorPage, ErrorPageConfig, ProblemDetailsConfig, ServiceType};
 
 /// Error response generator
 pub struct ErrorHandler {
@@ -22,6 +30,10 @@ pub struct ErrorHandler {
     config: Option<ErrorPageConfig>,
     /// Cached error templates
     templates: Arc<HashMap<u16, String>>,
+    /// Problem details mappings (server settings merged with the route's)
+    problem: ProblemMapper,
+    /// Render errors without a custom page as problem details
+    problem_enabled: bool,
 }
 
 /// Error response data
@@ -54,11 +66,58 @@ impl ErrorHandler {
             Arc::new(HashMap::new())
         };
 
+        let problem = ProblemMapper::new(
+            config
+                .as_ref()
+                .and_then(|c| c.problem.clone())
+                .unwrap_or_default(),
+        );
+        let problem_enabled = config
+            .as_ref()
+            .is_some_and(|c| c.default_format == ErrorFormat::Problem);
+
         Self {
             service_type,
             config,
             templates,
+            problem,
+            problem_enabled,
+        }
+    }
+
+    /// Apply the server's `problem-details` settings.
+    ///
+    /// When set, errors without a custom page for their status are rendered
+    /// as problem details, and the route's mappings are merged over the
+    /// server's.
+    pub fn with_problem_details(mut self, global: Option<&ProblemDetailsConfig>) -> Self {
+        if let Some(global) = global {
+            let route = self.config.as_ref().and_then(|c| c.problem.as_ref());
+            self.problem = ProblemMapper::new(match route {
+                Some(route) => global.overridden_by(route),
+                None => global.clone(),
+            });
+            self.problem_enabled = true;
         }
+        self
+    }
+
+    /// The problem details response for an error generated by the proxy,
+    /// negotiated against `accept`.
+    ///
+    /// Returns `None` when problem details are not enabled for the route.
+    pub fn problem_response(
+        &self,
+        status: StatusCode,
+        detail: Option<&str>,
+        request_id: &str,
+        accept: Option<&str>,
+    ) -> Option<Response<Full<Bytes>>> {
+        if !self.problem_enabled {
+            return None;
+        }
+        let problem = self.problem.problem(status, detail, request_id);
+        Some(self.problem.render(&problem, accept))
     }
 
     /// Generate an error response
@@ -73,7 +132,12 @@ impl ErrorHandler {
         let error_data = ErrorResponse {
             status: status_code,
             title: Self::status_title(status),
-            message: message.unwrap_or_else(|| Self::default_message(status)),
+            message: message
+                .or_else(|| {
+                    self.get_error_page(status_code)
+                        .and_then(|page| page.message.clone())
+                })
+                .unwrap_or_else(|| Self::default_message(status)),
             request_id: request_id.to_string(),
             timestamp: chrono::Utc::now().timestamp(),
             details,
@@ -89,6 +153,12 @@ impl ErrorHandler {
             ErrorFormat::Html => self.generate_html_response(&error_data, status_code)?,
             ErrorFormat::Text => self.generate_text_response(&error_data)?,
             ErrorFormat::Xml => self.generate_xml_response(&error_data)?,
+            ErrorFormat::Problem => {
+                let problem =
+                    self.problem
+                        .problem(status, Some(error_data.message.as_str()), request_id);
+                (problem::render_json(&problem), PROBLEM_JSON)
+            }
         };
 
         // Build the response
@@ -114,6 +184,10 @@ impl ErrorHandler {
             return page.format;
         }
 
+        if self.problem_enabled {
+            return ErrorFormat::Problem;
+        }
+
         // Check if there's a default format configured
         if let Some(ref config) = self.config {
             return config.default_format;
@@ -126,6 +200,11 @@ impl ErrorHandler {
         }
     }
 
+    /// Whether a custom page is configured for `status_code`
+    pub fn has_page(&self, status_code: u16) -> bool {
+        self.get_error_page(status_code).is_some()
+    }
+
     /// Get error page configuration for a specific status code
     fn get_error_page(&self, status_code: u16) -> Option<&ErrorPage> {
         self.config.as_ref().and_then(|c| c.pages.get(&status_code))
@@ -271,26 +350,26 @@ impl ErrorHandler {
     fn load_templates(config: &ErrorPageConfig) -> Arc<HashMap<u16, String>> {
         let mut templates = HashMap::new();
 
-        if let Some(ref template_dir) = config.template_dir {
-            for (status_code, page) in &config.pages {
-                if let Some(ref template_path) = page.template {
-                    let full_path = if template_path.is_absolute() {
-                        template_path.clone()
-                    } else {
+        for (status_code, page) in &config.pages {
+            if let Some(ref template_path) = page.template {
+                // Relative templates are resolved against the template directory
+                let full_path = match config.template_dir {
+                    Some(ref template_dir) if template_path.is_relative() => {
                         template_dir.join(template_path)
-                    };
-
-                    match std::fs::read_to_string(&full_path) {
-                        Ok(content) => {
-                            templates.insert(*status_code, content);
-                            debug!(
-                                "Loaded error template for status {}: {:?}",
-                                status_code, full_path
-                            );
-                        }
-                        Err(e) => {
-                            warn!("Failed to load error template {:?}: {}", full_path, e);
-                        }
+                    }
+                    _ => template_path.clone(),
+                };
+
+                match std::fs::read_to_string(&full_path) {
+                    Ok(content) => {
+                        templates.insert(*status_code, content);
+                        debug!(
+                            "Loaded error template for status {}: {:?}",
+                            status_code, full_path
+                        );
+                    }
+                    Err(e) => {
+                        warn!("Failed to load error template {:?}: {}", full_path, e);
                     }
                 }
             }
@@ -431,6 +510,7 @@ mod tests {
             default_format: ErrorFormat::Xml,
             include_stack_trace: false,
             template_dir: None,
+            problem: None,
         };
 
         config.pages.insert(
@@ -453,4 +533,73 @@ mod tests {
             "text/plain; charset=utf-8"
         );
     }
+
+    #[test]
+    fn test_problem_details_mode() {
+        let global = ProblemDetailsConfig {
+            type_base: Some("https://errors.example.com/".to_string()),
+            ..Default::default()
+        };
+        let handler = ErrorHandler::new(ServiceType::Web, None).with_problem_details(Some(&global));
+
+        let response = handler
+            .problem_response(StatusCode::BAD_GATEWAY, None, "test-1", None)
+            .unwrap();
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            PROBLEM_JSON
+        );
+
+        // Pages without a specific format follow the problem details mode
+        let response = handler
+            .generate_response(StatusCode::NOT_FOUND, None, "test-2", None)
+            .unwrap();
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            PROBLEM_JSON
+        );
+
+        let handler = ErrorHandler::new(ServiceType::Api, None);
+        assert!(handler
+            .problem_response(StatusCode::BAD_GATEWAY, None, "test-3", None)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_page_template_and_message() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let template = temp_dir.path().join("503.html");
+        std::fs::write(&template, "<h1>{{status}}</h1><p>{{message}}</p>").unwrap();
+
+        let mut config = ErrorPageConfig {
+            pages: HashMap::new(),
+            default_format: ErrorFormat::Html,
+            include_stack_trace: false,
+            template_dir: None,
+            problem: None,
+        };
+        config.pages.insert(
+            503,
+            ErrorPage {
+                format: ErrorFormat::Html,
+                template: Some(template),
+                message: Some("Back soon".to_string()),
+                headers: HashMap::from([("Retry-After".to_string(), "120".to_string())]),
+            },
+        );
+
+        let handler = ErrorHandler::new(ServiceType::Web, Some(config));
+        assert!(handler.has_page(503));
+        assert!(!handler.has_page(502));
+
+        let response = handler
+            .generate_response(StatusCode::SERVICE_UNAVAILABLE, None, "test-1", None)
+            .unwrap();
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "120");
+        let body = http_body_util::BodyExt::collect(response.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+        assert_eq!(&body[..], b"<h1>503</h1><p>Back soon</p>");
+    }
 }
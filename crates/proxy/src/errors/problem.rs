@@ -0,0 +1,202 @@
+//! RFC 9457 problem details for errors generated by the proxy.
+//!
+//! With `server { problem-details }` set, or a route's error pages defaulting
+//! to the `problem` format, errors the proxy produces itself (no upstream,
+//! rate limits, timeouts, geo blocks, ...) are answered with an
+//! `application/problem+json` document instead of an ad-hoc body. Clients
+//! that prefer HTML get the same members as a small HTML page. Agent block
+//! decisions keep their own block pages.
+//!
+//! Routes map status codes to their own `type`, `title` and `detail` in
+//! `error-pages { problem { map <status> { ... } } }`; those mappings take
+//! precedence over the server's.
+
+use bytes::Bytes;
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::{Response, StatusCode};
+use http_body_util::Full;
+use serde::Serialize;
+
+use zentinel_config::{BlockPageFormat, ProblemDetailsConfig};
+
+use super::block_page::{escape_html, negotiate_format};
+
+/// Media type of problem details documents
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An RFC 9457 problem details document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProblemDetails {
+    /// URI identifying the problem type
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    /// Short summary of the problem type
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Explanation specific to this occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Request correlation ID (extension member)
+    pub correlation_id: String,
+}
+
+/// Builds problem details from a route's mappings
+#[derive(Debug, Clone, Default)]
+pub struct ProblemMapper {
+    config: ProblemDetailsConfig,
+}
+
+impl ProblemMapper {
+    /// Create a mapper for the merged server and route settings
+    pub fn new(config: ProblemDetailsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Problem details for `status`.
+    ///
+    /// A mapping for the status supplies `type`, `title` and `detail`;
+    /// unmapped members fall back to `type-base` plus the status code (or
+    /// `about:blank`), the reason phrase and the proxy's own `detail`.
+    pub fn problem(
+        &self,
+        status: StatusCode,
+        detail: Option<&str>,
+        correlation_id: &str,
+    ) -> ProblemDetails {
+        let mapping = self.config.mappings.get(&status.as_u16());
+
+        let type_uri = mapping
+            .and_then(|m| m.type_uri.clone())
+            .or_else(|| {
+                self.config
+                    .type_base
+                    .as_ref()
+                    .map(|base| format!("{}{}", base, status.as_u16()))
+            })
+            .unwrap_or_else(|| "about:blank".to_string());
+        let title = mapping
+            .and_then(|m| m.title.clone())
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or("Error").to_string());
+        let detail = mapping
+            .and_then(|m| m.detail.clone())
+            .or_else(|| detail.map(str::to_string));
+
+        ProblemDetails {
+            type_uri,
+            title,
+            status: status.as_u16(),
+            detail,
+            correlation_id: correlation_id.to_string(),
+        }
+    }
+
+    /// Render a problem as `application/problem+json`, or as HTML when the
+    /// `Accept` header prefers it.
+    pub fn render(&self, problem: &ProblemDetails, accept: Option<&str>) -> Response<Full<Bytes>> {
+        let (body, content_type) = match negotiate_format(accept, BlockPageFormat::Json) {
+            BlockPageFormat::Html => (render_html(problem), "text/html; charset=utf-8"),
+            BlockPageFormat::Json => (render_json(problem), PROBLEM_JSON),
+        };
+
+        let mut response = Response::new(Full::new(Bytes::from(body)));
+        *response.status_mut() =
+            StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        response
+    }
+}
+
+/// Serialize a problem as JSON
+pub fn render_json(problem: &ProblemDetails) -> Vec<u8> {
+    serde_json::to_vec(problem).unwrap_or_default()
+}
+
+fn render_html(problem: &ProblemDetails) -> Vec<u8> {
+    let detail = problem
+        .detail
+        .as_deref()
+        .map(|d| format!("\n    <p>{}</p>", escape_html(d)))
+        .unwrap_or_default();
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>{status} {title}</title>
+</head>
+<body>
+    <h1>{status} {title}</h1>{detail}
+    <p>Reference: <code>{correlation_id}</code></p>
+</body>
+</html>
+"#,
+        status = problem.status,
+        title = escape_html(&problem.title),
+        detail = detail,
+        correlation_id = escape_html(&problem.correlation_id),
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use zentinel_config::ProblemMapping;
+
+    fn mapper() -> ProblemMapper {
+        ProblemMapper::new(ProblemDetailsConfig {
+            type_base: Some("https://errors.example.com/".to_string()),
+            mappings: HashMap::from([(
+                503,
+                ProblemMapping {
+                    type_uri: Some("https://errors.example.com/overloaded".to_string()),
+                    title: Some("Overloaded".to_string()),
+                    detail: None,
+                },
+            )]),
+        })
+    }
+
+    #[test]
+    fn test_problem_members() {
+        let problem = mapper().problem(StatusCode::BAD_GATEWAY, Some("upstream refused"), "req-1");
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            serde_json::json!({
+                "type": "https://errors.example.com/502",
+                "title": "Bad Gateway",
+                "status": 502,
+                "detail": "upstream refused",
+                "correlation_id": "req-1"
+            })
+        );
+
+        let problem = mapper().problem(StatusCode::SERVICE_UNAVAILABLE, Some("busy"), "req-2");
+        assert_eq!(problem.type_uri, "https://errors.example.com/overloaded");
+        assert_eq!(problem.title, "Overloaded");
+        assert_eq!(problem.detail.as_deref(), Some("busy"));
+
+        let problem = ProblemMapper::default().problem(StatusCode::NOT_FOUND, None, "req-3");
+        assert_eq!(problem.type_uri, "about:blank");
+        assert!(problem.detail.is_none());
+    }
+
+    #[test]
+    fn test_render_negotiates_html() {
+        let mapper = mapper();
+        let problem = mapper.problem(StatusCode::GATEWAY_TIMEOUT, Some("<slow>"), "req-1");
+
+        let response = mapper.render(&problem, Some("application/json"));
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+
+        let response = mapper.render(&problem, Some("text/html,*/*;q=0.8"));
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+        let html = String::from_utf8(render_html(&problem)).unwrap();
+        assert!(html.contains("&lt;slow&gt;"));
+    }
+}
@@ -0,0 +1,400 @@
+//! Dry-run explanation of how the proxy would handle a request.
+//!
+//! The `explain` admin handler takes a synthetic request description,
+//! matches it against the global route set, evaluates the route's filter
+//! conditions and calls its agents with `dry_run` set. Nothing is sent
+//! upstream; the handler answers with an [`ExplainTrace`]: the matched route,
+//! what each filter did, each agent's decision, the final decision, the
+//! header changes on the way upstream and timings.
+//!
+//! Filters that need live request state (rate limits, geo lookups,
+//! redirects, WASM, body filters) are listed but not run.
+
+use pingora::http::RequestHeader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use zentinel_agent_protocol::{AuditMetadata, Decision};
+use zentinel_config::{BuiltinHandler, Filter, FilterConfig, FilterPhase, ServiceType};
+
+use crate::agents::{AgentAction, AgentDecision};
+use crate::audit_store::AuditRecord;
+
+/// Largest request description accepted
+pub const MAX_EXPLAIN_BODY: usize = 64 * 1024;
+
+/// Synthetic request to explain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplainRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Path and query string
+    #[serde(default = "default_uri")]
+    pub uri: String,
+    /// Host to route on; the `host` header when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_client_ip")]
+    pub client_ip: String,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_uri() -> String {
+    "/".to_string()
+}
+
+fn default_client_ip() -> String {
+    "127.0.0.1".to_string()
+}
+
+impl ExplainRequest {
+    /// Parse and check a JSON request description
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        let request: Self = serde_json::from_slice(body)
+            .map_err(|e| format!("Invalid request description: {e}"))?;
+        if http::Method::from_bytes(request.method.as_bytes()).is_err() {
+            return Err(format!("Invalid method '{}'", request.method));
+        }
+        if !request.uri.starts_with('/') {
+            return Err(format!("URI '{}' must start with '/'", request.uri));
+        }
+        if request.client_ip.parse::<IpAddr>().is_err() {
+            return Err(format!("Invalid client IP '{}'", request.client_ip));
+        }
+        Ok(request)
+    }
+
+    /// Host the request is routed on
+    pub fn host(&self) -> &str {
+        self.host
+            .as_deref()
+            .or_else(|| {
+                self.headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+                    .map(|(_, value)| value.as_str())
+            })
+            .unwrap_or("localhost")
+    }
+
+    /// Request header as the proxy would receive it
+    pub fn to_request_header(&self) -> Result<RequestHeader, String> {
+        let mut header = RequestHeader::build(self.method.as_str(), self.uri.as_bytes(), None)
+            .map_err(|e| format!("Invalid request: {e}"))?;
+        for (name, value) in &self.headers {
+            header
+                .append_header(name.to_ascii_lowercase(), value.as_str())
+                .map_err(|e| format!("Invalid header '{name}': {e}"))?;
+        }
+        if header.headers.get(http::header::HOST).is_none() {
+            header
+                .insert_header(http::header::HOST, self.host())
+                .map_err(|e| format!("Invalid host '{}': {e}", self.host()))?;
+        }
+        Ok(header)
+    }
+}
+
+/// How the proxy would handle a request
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExplainTrace {
+    /// Matched route, `None` when no route matches
+    pub route: Option<ExplainRoute>,
+    /// The route's filters in chain order
+    pub filters: Vec<ExplainFilter>,
+    /// Agent responses in call order
+    pub agents: Vec<ExplainAgent>,
+    /// Outcome of the agent chain
+    pub decision: ExplainDecision,
+    /// Request headers changed on the way upstream
+    pub header_changes: Vec<HeaderChange>,
+    /// Request headers as they would be sent upstream
+    pub upstream_headers: BTreeMap<String, Vec<String>>,
+    pub timings: ExplainTimings,
+}
+
+/// Route a request matched
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainRoute {
+    pub id: String,
+    pub service_type: ServiceType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builtin_handler: Option<BuiltinHandler>,
+}
+
+/// What a filter did to the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterEffect {
+    /// Ran on the request
+    Applied,
+    /// Disabled by its `enable-if` condition
+    Skipped,
+    /// Runs in the response phase or needs live request state
+    NotEvaluated,
+    /// Not defined in the configuration
+    Missing,
+}
+
+/// One filter of the route's chain
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainFilter {
+    pub id: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub filter_type: Option<&'static str>,
+    pub effect: FilterEffect,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ExplainFilter {
+    /// Describe a filter of the chain; `enabled` is false when its
+    /// `enable-if` condition did not hold
+    pub fn new(id: &str, config: Option<&FilterConfig>, enabled: bool) -> Self {
+        let Some(config) = config else {
+            return Self {
+                id: id.to_string(),
+                filter_type: None,
+                effect: FilterEffect::Missing,
+                detail: None,
+            };
+        };
+
+        let (effect, detail) = if !enabled {
+            (
+                FilterEffect::Skipped,
+                "enable-if condition is false".to_string(),
+            )
+        } else if !config.filter.runs_on_request() {
+            (FilterEffect::NotEvaluated, "response phase".to_string())
+        } else {
+            match &config.filter {
+                Filter::Agent(agent) => (FilterEffect::Applied, format!("agent '{}'", agent.agent)),
+                Filter::Headers(headers) => {
+                    let detail = if headers.phase == FilterPhase::Both {
+                        "request headers (response headers not evaluated)"
+                    } else {
+                        "request headers"
+                    };
+                    (FilterEffect::Applied, detail.to_string())
+                }
+                _ => (FilterEffect::NotEvaluated, "not run in dry run".to_string()),
+            }
+        };
+
+        Self {
+            id: id.to_string(),
+            filter_type: Some(config.filter.type_name()),
+            effect,
+            detail: Some(detail),
+        }
+    }
+}
+
+/// One agent's response
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainAgent {
+    pub agent_id: String,
+    pub decision: Decision,
+    pub audit: AuditMetadata,
+    pub duration_ms: u64,
+}
+
+impl From<AuditRecord> for ExplainAgent {
+    fn from(record: AuditRecord) -> Self {
+        Self {
+            agent_id: record.agent_id,
+            decision: record.decision,
+            audit: record.audit,
+            duration_ms: record.duration_ms,
+        }
+    }
+}
+
+/// Final decision for the request
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainDecision {
+    /// `allow`, `block`, `redirect`, `challenge` or `error`
+    pub action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Agent that produced a non-allow decision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    /// Redirect target, challenge type or error message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Default for ExplainDecision {
+    fn default() -> Self {
+        Self {
+            action: "allow",
+            status: None,
+            decided_by: None,
+            detail: None,
+        }
+    }
+}
+
+impl ExplainDecision {
+    /// Merged decision of the agent chain
+    pub fn from_agents(decision: &AgentDecision) -> Self {
+        let (action, status, detail) = match &decision.action {
+            AgentAction::Allow => ("allow", None, None),
+            AgentAction::Block { status, .. } => ("block", Some(*status), None),
+            AgentAction::Redirect { url, status } => ("redirect", Some(*status), Some(url.clone())),
+            AgentAction::Challenge { challenge_type, .. } => {
+                ("challenge", None, Some(challenge_type.clone()))
+            }
+        };
+        Self {
+            action,
+            status,
+            decided_by: decision.decided_by.clone(),
+            detail,
+        }
+    }
+
+    /// Agent chain failed as a whole
+    pub fn error(message: String) -> Self {
+        Self {
+            action: "error",
+            detail: Some(message),
+            ..Default::default()
+        }
+    }
+}
+
+/// A request header changed by agents or filters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeaderChange {
+    pub name: String,
+    /// Values before, empty when the header was added
+    pub before: Vec<String>,
+    /// Values after, empty when the header was removed
+    pub after: Vec<String>,
+}
+
+/// Time spent per step, in microseconds
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExplainTimings {
+    pub routing_us: u64,
+    pub agents_us: u64,
+    pub total_us: u64,
+}
+
+/// Header values by lowercase name
+pub fn header_snapshot(headers: &http::HeaderMap) -> BTreeMap<String, Vec<String>> {
+    let mut snapshot: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers {
+        snapshot
+            .entry(name.as_str().to_string())
+            .or_default()
+            .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+    }
+    snapshot
+}
+
+/// Headers whose values differ between two snapshots, by name
+pub fn header_changes(
+    before: &BTreeMap<String, Vec<String>>,
+    after: &BTreeMap<String, Vec<String>>,
+) -> Vec<HeaderChange> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old = before.get(name).cloned().unwrap_or_default();
+            let new = after.get(name).cloned().unwrap_or_default();
+            (old != new).then(|| HeaderChange {
+                name: name.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_config::{AgentFilter, HeadersFilter};
+
+    #[test]
+    fn parses_request_descriptions() {
+        let request = ExplainRequest::parse(
+            br#"{"method": "POST", "uri": "/login?next=/", "headers": {"Host": "api.example.com"}}"#,
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.host(), "api.example.com");
+        assert_eq!(request.client_ip, "127.0.0.1");
+
+        let header = request.to_request_header().unwrap();
+        assert_eq!(header.uri.path(), "/login");
+        assert_eq!(header.headers["host"], "api.example.com");
+
+        assert_eq!(ExplainRequest::parse(b"{}").unwrap().host(), "localhost");
+        assert!(ExplainRequest::parse(br#"{"uri": "login"}"#).is_err());
+        assert!(ExplainRequest::parse(br#"{"client_ip": "nope"}"#).is_err());
+        assert!(ExplainRequest::parse(br#"{"path": "/"}"#).is_err());
+    }
+
+    #[test]
+    fn describes_filters() {
+        let agent = FilterConfig::new("waf", Filter::Agent(AgentFilter::new("waf-agent")));
+        let step = ExplainFilter::new("waf", Some(&agent), true);
+        assert_eq!(step.effect, FilterEffect::Applied);
+        assert_eq!(step.filter_type, Some("agent"));
+        assert_eq!(step.detail.as_deref(), Some("agent 'waf-agent'"));
+
+        let headers = FilterConfig::new(
+            "hdr",
+            Filter::Headers(HeadersFilter {
+                phase: FilterPhase::Response,
+                ..Default::default()
+            }),
+        );
+        let step = ExplainFilter::new("hdr", Some(&headers), true);
+        assert_eq!(step.effect, FilterEffect::NotEvaluated);
+
+        let step = ExplainFilter::new("waf", Some(&agent), false);
+        assert_eq!(step.effect, FilterEffect::Skipped);
+
+        let step = ExplainFilter::new("gone", None, true);
+        assert_eq!(step.effect, FilterEffect::Missing);
+    }
+
+    #[test]
+    fn lists_changed_headers() {
+        let before = BTreeMap::from([
+            ("x-keep".to_string(), vec!["1".to_string()]),
+            ("x-drop".to_string(), vec!["2".to_string()]),
+            ("x-set".to_string(), vec!["old".to_string()]),
+        ]);
+        let after = BTreeMap::from([
+            ("x-keep".to_string(), vec!["1".to_string()]),
+            ("x-set".to_string(), vec!["new".to_string()]),
+            ("x-user".to_string(), vec!["alice".to_string()]),
+        ]);
+
+        let changes = header_changes(&before, &after);
+        let names: Vec<&str> = changes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["x-drop", "x-set", "x-user"]);
+        assert!(changes[0].after.is_empty());
+        assert_eq!(changes[1].before, vec!["old".to_string()]);
+        assert!(changes[2].before.is_empty());
+    }
+}
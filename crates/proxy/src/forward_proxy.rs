@@ -0,0 +1,1051 @@
+//! Forward-proxy (egress) listeners.
+//!
+//! A listener with `protocol "forward-proxy"` lets clients open TCP tunnels
+//! with HTTP `CONNECT host:port` and, when `socks5` is enabled, the SOCKS5
+//! `CONNECT` command on the same port (told apart by the first byte). Before
+//! a tunnel is opened:
+//!
+//! 1. the client authenticates, when `users` are configured (`Proxy-Authorization:
+//!    Basic` or SOCKS5 username/password)
+//! 2. the destination must match an `allow` rule and no `deny` rule; rules on
+//!    hostnames are checked before any DNS lookup, network rules again on the
+//!    resolved address
+//! 3. the listed agents see the tunnel as a `CONNECT` request headers event
+//!    and may block it
+//!
+//! Rules are read from the running configuration for every connection, so
+//! reloads apply to new tunnels. Only tunnels are supported: plain HTTP
+//! requests in absolute form are answered with 405.
+//!
+//! Exports `zentinel_forward_proxy_tunnels_total{listener, protocol, outcome}`
+//! and `zentinel_forward_proxy_active_tunnels{listener}`.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+use zentinel_agent_protocol::RequestMetadata;
+use zentinel_common::CorrelationId;
+use zentinel_config::{FailureMode, ForwardProxyConfig, ListenerConfig, ListenerProtocol};
+
+use crate::agents::{AgentAction, AgentCallContext, AgentManager};
+use crate::reload::ConfigManager;
+
+/// Time allowed for the CONNECT request or SOCKS5 negotiation
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest CONNECT request head accepted
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Realm sent with `407 Proxy Authentication Required`
+const AUTH_REALM: &str = "zentinel";
+
+/// Header carrying the authenticated user to agents
+const PROXY_USER_HEADER: &str = "x-forwarded-user";
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_AUTH_VERSION: u8 = 0x01;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_USER_PASS: u8 = 0x02;
+const SOCKS_NO_ACCEPTABLE: u8 = 0xff;
+const SOCKS_CMD_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+
+/// Prometheus metrics labelled by listener.
+struct ForwardProxyMetrics {
+    /// Tunnel requests, per protocol and outcome
+    tunnels: IntCounterVec,
+    /// Tunnels currently open
+    active: IntGaugeVec,
+}
+
+static FORWARD_PROXY_METRICS: LazyLock<Option<ForwardProxyMetrics>> = LazyLock::new(|| {
+    let tunnels = register_int_counter_vec!(
+        "zentinel_forward_proxy_tunnels_total",
+        "Forward-proxy tunnel requests by outcome",
+        &["listener", "protocol", "outcome"]
+    )
+    .ok()?;
+    let active = register_int_gauge_vec!(
+        "zentinel_forward_proxy_active_tunnels",
+        "Forward-proxy tunnels currently open",
+        &["listener"]
+    )
+    .ok()?;
+    Some(ForwardProxyMetrics { tunnels, active })
+});
+
+/// Start the accept loops of the `forward-proxy` listeners on `runtime`.
+pub fn spawn_listeners(
+    listeners: &[ListenerConfig],
+    config_manager: Arc<ConfigManager>,
+    agent_manager: Arc<AgentManager>,
+    runtime: &tokio::runtime::Handle,
+) {
+    for listener in listeners {
+        if listener.protocol != ListenerProtocol::ForwardProxy {
+            continue;
+        }
+        let proxy = Arc::new(ForwardProxy {
+            listener_id: listener.id.clone(),
+            config_manager: Arc::clone(&config_manager),
+            agent_manager: Arc::clone(&agent_manager),
+        });
+        runtime.spawn(run_listener(proxy, listener.address.clone()));
+    }
+}
+
+async fn run_listener(proxy: Arc<ForwardProxy>, address: String) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                listener_id = %proxy.listener_id,
+                address = %address,
+                error = %e,
+                "Failed to bind forward-proxy listener"
+            );
+            return;
+        }
+    };
+    info!(
+        listener_id = %proxy.listener_id,
+        address = %address,
+        "Forward proxy listening on: {}", address
+    );
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(listener_id = %proxy.listener_id, error = %e, "Forward-proxy accept failed");
+                continue;
+            }
+        };
+        let proxy = Arc::clone(&proxy);
+        tokio::spawn(async move {
+            if let Err(e) = proxy.handle_connection(stream, peer).await {
+                debug!(
+                    listener_id = %proxy.listener_id,
+                    peer = %peer,
+                    error = %e,
+                    "Forward-proxy connection closed"
+                );
+            }
+        });
+    }
+}
+
+/// Client protocol of a tunnel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TunnelProtocol {
+    Connect,
+    Socks5,
+}
+
+impl TunnelProtocol {
+    fn as_str(self) -> &'static str {
+        match self {
+            TunnelProtocol::Connect => "connect",
+            TunnelProtocol::Socks5 => "socks5",
+        }
+    }
+}
+
+/// Tunnel requested by a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TunnelRequest {
+    host: String,
+    port: u16,
+    /// Authenticated user, if authentication is on
+    user: Option<String>,
+    /// Request headers (CONNECT only), for agents
+    headers: HashMap<String, Vec<String>>,
+}
+
+/// Why a tunnel was not opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Refusal {
+    /// Destination not allowed by the rules
+    Denied,
+    /// Blocked by an agent, with its status
+    Blocked(u16),
+    /// Agent processing failed and the agent fails closed
+    AgentFailed,
+    /// The destination did not resolve
+    Unresolved,
+    /// Connecting to the destination failed or timed out
+    Unreachable,
+}
+
+impl Refusal {
+    fn outcome(self) -> &'static str {
+        match self {
+            Refusal::Denied => "denied",
+            Refusal::Blocked(_) | Refusal::AgentFailed => "blocked",
+            Refusal::Unresolved | Refusal::Unreachable => "failed",
+        }
+    }
+
+    fn http_status(self) -> u16 {
+        match self {
+            Refusal::Denied => 403,
+            Refusal::Blocked(status) => status,
+            Refusal::AgentFailed => 503,
+            Refusal::Unresolved => 502,
+            Refusal::Unreachable => 504,
+        }
+    }
+
+    /// SOCKS5 reply code (RFC 1928, section 6)
+    fn socks_reply(self) -> u8 {
+        match self {
+            Refusal::Denied | Refusal::Blocked(_) => 0x02,
+            Refusal::AgentFailed => 0x01,
+            Refusal::Unresolved => 0x04,
+            Refusal::Unreachable => 0x05,
+        }
+    }
+}
+
+/// A `forward-proxy` listener
+struct ForwardProxy {
+    listener_id: String,
+    config_manager: Arc<ConfigManager>,
+    agent_manager: Arc<AgentManager>,
+}
+
+impl ForwardProxy {
+    /// Egress rules of this listener in the running configuration
+    fn rules(&self) -> Option<ForwardProxyConfig> {
+        let config = self.config_manager.current();
+        let listener = config
+            .listeners
+            .iter()
+            .find(|l| l.id == self.listener_id && l.protocol == ListenerProtocol::ForwardProxy)?;
+        Some(listener.forward_proxy.clone().unwrap_or_default())
+    }
+
+    async fn handle_connection(&self, client: TcpStream, peer: SocketAddr) -> Result<(), String> {
+        // A listener removed by a reload keeps its socket until restart but
+        // no longer opens tunnels
+        let rules = self
+            .rules()
+            .ok_or_else(|| "listener no longer configured".to_string())?;
+
+        let mut first = [0u8; 1];
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, client.peek(&mut first))
+            .await
+            .map_err(|_| "handshake timed out".to_string())?
+            .map_err(|e| e.to_string())?;
+
+        if rules.socks5 && first[0] == SOCKS_VERSION {
+            self.handle_socks5(client, peer, &rules).await
+        } else {
+            self.handle_connect(client, peer, &rules).await
+        }
+    }
+
+    /// Serve an HTTP CONNECT request
+    async fn handle_connect(
+        &self,
+        mut client: TcpStream,
+        peer: SocketAddr,
+        rules: &ForwardProxyConfig,
+    ) -> Result<(), String> {
+        let protocol = TunnelProtocol::Connect;
+        let (head, leftover) =
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, read_request_head(&mut client))
+                .await
+                .map_err(|_| "CONNECT request timed out".to_string())??;
+
+        let request = match parse_connect_request(&head) {
+            Ok(request) => request,
+            Err(ConnectError::NotConnect) => {
+                self.record(protocol, "invalid");
+                return write_response(&mut client, 405, &[("Allow", "CONNECT")]).await;
+            }
+            Err(ConnectError::Malformed(e)) => {
+                self.record(protocol, "invalid");
+                write_response(&mut client, 400, &[]).await?;
+                return Err(e);
+            }
+        };
+
+        let user = match check_basic_auth(rules, &request.headers) {
+            Ok(user) => user,
+            Err(()) => {
+                self.record(protocol, "unauthorized");
+                let challenge = format!("Basic realm=\"{}\"", AUTH_REALM);
+                return write_response(&mut client, 407, &[("Proxy-Authenticate", &challenge)])
+                    .await;
+            }
+        };
+        let request = TunnelRequest { user, ..request };
+
+        let mut upstream = match self.open(rules, &request, protocol, peer).await {
+            Ok(upstream) => upstream,
+            Err(refusal) => {
+                return write_response(&mut client, refusal.http_status(), &[]).await;
+            }
+        };
+
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await
+            .map_err(|e| e.to_string())?;
+        // Bytes the client sent right after the request head
+        if !leftover.is_empty() {
+            upstream
+                .write_all(&leftover)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        self.relay(client, upstream, rules, &request).await
+    }
+
+    /// Serve a SOCKS5 client (RFC 1928, with RFC 1929 authentication)
+    async fn handle_socks5(
+        &self,
+        mut client: TcpStream,
+        peer: SocketAddr,
+        rules: &ForwardProxyConfig,
+    ) -> Result<(), String> {
+        let protocol = TunnelProtocol::Socks5;
+        let negotiated =
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, negotiate_socks5(&mut client, rules))
+                .await
+                .map_err(|_| "SOCKS5 negotiation timed out".to_string())?;
+
+        let request = match negotiated {
+            Ok(request) => request,
+            Err(SocksError::Unauthorized) => {
+                self.record(protocol, "unauthorized");
+                return Ok(());
+            }
+            Err(SocksError::Reply(code, e)) => {
+                self.record(protocol, "invalid");
+                write_socks_reply(&mut client, code, None).await?;
+                return Err(e);
+            }
+            Err(SocksError::Io(e)) => {
+                self.record(protocol, "invalid");
+                return Err(e);
+            }
+        };
+
+        let upstream = match self.open(rules, &request, protocol, peer).await {
+            Ok(upstream) => upstream,
+            Err(refusal) => {
+                return write_socks_reply(&mut client, refusal.socks_reply(), None).await;
+            }
+        };
+
+        write_socks_reply(&mut client, 0x00, upstream.local_addr().ok()).await?;
+        self.relay(client, upstream, rules, &request).await
+    }
+
+    /// Check the destination and connect to it
+    async fn open(
+        &self,
+        rules: &ForwardProxyConfig,
+        request: &TunnelRequest,
+        protocol: TunnelProtocol,
+        peer: SocketAddr,
+    ) -> Result<TcpStream, Refusal> {
+        let result = self.try_open(rules, request, protocol, peer).await;
+        match &result {
+            Ok(_) => self.record(protocol, "opened"),
+            Err(refusal) => {
+                info!(
+                    listener_id = %self.listener_id,
+                    client = %peer,
+                    destination = %format_args!("{}:{}", request.host, request.port),
+                    user = request.user.as_deref().unwrap_or("-"),
+                    refusal = ?refusal,
+                    "Forward-proxy tunnel refused"
+                );
+                self.record(protocol, refusal.outcome());
+            }
+        }
+        result
+    }
+
+    async fn try_open(
+        &self,
+        rules: &ForwardProxyConfig,
+        request: &TunnelRequest,
+        protocol: TunnelProtocol,
+        peer: SocketAddr,
+    ) -> Result<TcpStream, Refusal> {
+        let (host, port) = (request.host.as_str(), request.port);
+
+        // Hostname rules first, so refused names are never looked up
+        let allowed_by_name = rules.allow.iter().any(|r| r.matches(host, None, port));
+        if !allowed_by_name && !rules.allow.iter().any(|r| r.matches_addresses()) {
+            return Err(Refusal::Denied);
+        }
+        if rules.deny.iter().any(|r| r.matches(host, None, port)) {
+            return Err(Refusal::Denied);
+        }
+
+        if !rules.agents.is_empty() {
+            self.inspect(rules, request, protocol, peer).await?;
+        }
+
+        let connect_timeout = Duration::from_secs(rules.connect_timeout_secs);
+        let address = crate::dns::resolver()
+            .resolve_target(&format_host_port(host, port), connect_timeout)
+            .await
+            .map_err(|_| Refusal::Unresolved)?;
+
+        let ip = Some(address.ip());
+        let allowed = allowed_by_name || rules.allow.iter().any(|r| r.matches(host, ip, port));
+        if !allowed || rules.deny.iter().any(|r| r.matches(host, ip, port)) {
+            return Err(Refusal::Denied);
+        }
+
+        let upstream = tokio::time::timeout(connect_timeout, TcpStream::connect(address))
+            .await
+            .map_err(|_| Refusal::Unreachable)?
+            .map_err(|_| Refusal::Unreachable)?;
+
+        debug!(
+            listener_id = %self.listener_id,
+            client = %peer,
+            destination = %format_args!("{}:{}", host, port),
+            address = %address,
+            "Forward-proxy tunnel opened"
+        );
+        Ok(upstream)
+    }
+
+    /// Ask the listener's agents about the tunnel
+    async fn inspect(
+        &self,
+        rules: &ForwardProxyConfig,
+        request: &TunnelRequest,
+        protocol: TunnelProtocol,
+        peer: SocketAddr,
+    ) -> Result<(), Refusal> {
+        let config = self.config_manager.current();
+        let agents: Vec<(String, FailureMode)> = rules
+            .agents
+            .iter()
+            .map(|id| {
+                let failure_mode = config
+                    .agents
+                    .iter()
+                    .find(|a| &a.id == id)
+                    .map(|a| a.failure_mode)
+                    .unwrap_or(FailureMode::Closed);
+                (id.clone(), failure_mode)
+            })
+            .collect();
+
+        let target = format_host_port(&request.host, request.port);
+        let mut headers = request.headers.clone();
+        headers.remove("proxy-authorization");
+        headers.insert(":method".to_string(), vec!["CONNECT".to_string()]);
+        headers.insert(":path".to_string(), vec![target.clone()]);
+        headers
+            .entry("host".to_string())
+            .or_insert_with(|| vec![target]);
+        if let Some(ref user) = request.user {
+            headers.insert(PROXY_USER_HEADER.to_string(), vec![user.clone()]);
+        }
+
+        let correlation_id = CorrelationId::new();
+        let ctx = AgentCallContext::new(
+            correlation_id.clone(),
+            RequestMetadata {
+                correlation_id: correlation_id.to_string(),
+                request_id: correlation_id.to_string(),
+                client_ip: peer.ip().to_string(),
+                client_port: peer.port(),
+                server_name: Some(request.host.clone()),
+                protocol: match protocol {
+                    TunnelProtocol::Connect => "HTTP/1.1".to_string(),
+                    TunnelProtocol::Socks5 => "SOCKS5".to_string(),
+                },
+                tls_version: None,
+                tls_cipher: None,
+                route_id: None,
+                upstream_id: None,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                traceparent: None,
+                client_cert: None,
+                tags: Vec::new(),
+                bot_signals: None,
+                dry_run: false,
+            },
+        );
+
+        let result = self
+            .agent_manager
+            .process_request_headers(&ctx, headers, &agents)
+            .await;
+        self.agent_manager
+            .end_request(correlation_id.as_str())
+            .await;
+
+        match result {
+            Ok(decision) => match decision.action {
+                AgentAction::Allow => Ok(()),
+                AgentAction::Block { status, .. } => {
+                    warn!(
+                        listener_id = %self.listener_id,
+                        client = %peer,
+                        destination = %format_args!("{}:{}", request.host, request.port),
+                        agent_id = decision.decided_by.as_deref().unwrap_or("unknown"),
+                        status = status,
+                        "Forward-proxy tunnel blocked by agent"
+                    );
+                    Err(Refusal::Blocked(status))
+                }
+                // Tunnels cannot be redirected or challenged
+                AgentAction::Redirect { .. } | AgentAction::Challenge { .. } => {
+                    Err(Refusal::Blocked(403))
+                }
+            },
+            Err(e) => {
+                warn!(
+                    listener_id = %self.listener_id,
+                    error = %e,
+                    "Agent processing failed for forward-proxy tunnel"
+                );
+                Err(Refusal::AgentFailed)
+            }
+        }
+    }
+
+    /// Copy bytes both ways until either side closes or the tunnel is idle
+    async fn relay(
+        &self,
+        client: TcpStream,
+        upstream: TcpStream,
+        rules: &ForwardProxyConfig,
+        request: &TunnelRequest,
+    ) -> Result<(), String> {
+        let active = FORWARD_PROXY_METRICS
+            .as_ref()
+            .map(|m| m.active.with_label_values(&[&self.listener_id]));
+        if let Some(ref gauge) = active {
+            gauge.inc();
+        }
+
+        let idle_timeout = Duration::from_secs(rules.idle_timeout_secs);
+        let result = relay(client, upstream, idle_timeout).await;
+
+        if let Some(ref gauge) = active {
+            gauge.dec();
+        }
+        let (sent, received) = result.map_err(|e| e.to_string())?;
+        debug!(
+            listener_id = %self.listener_id,
+            destination = %format_args!("{}:{}", request.host, request.port),
+            bytes_sent = sent,
+            bytes_received = received,
+            "Forward-proxy tunnel closed"
+        );
+        Ok(())
+    }
+
+    fn record(&self, protocol: TunnelProtocol, outcome: &str) {
+        if let Some(metrics) = FORWARD_PROXY_METRICS.as_ref() {
+            metrics
+                .tunnels
+                .with_label_values(&[&self.listener_id, protocol.as_str(), outcome])
+                .inc();
+        }
+    }
+}
+
+/// `host:port`, with brackets around IPv6 addresses
+fn format_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Read up to the end of the request head; returns the head and any bytes
+/// received after it
+async fn read_request_head<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed before request head".to_string());
+        }
+        // Only the new bytes (and 3 before them) can complete the terminator
+        let search_from = buf.len().saturating_sub(3);
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf[search_from..].windows(4).position(|w| w == b"\r\n\r\n") {
+            let end = search_from + pos + 4;
+            let leftover = buf.split_off(end);
+            return Ok((buf, leftover));
+        }
+        if buf.len() > MAX_REQUEST_HEAD {
+            return Err("request head too large".to_string());
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ConnectError {
+    /// A well-formed request with another method
+    NotConnect,
+    Malformed(String),
+}
+
+/// Parse a `CONNECT host:port HTTP/1.x` request head
+fn parse_connect_request(head: &[u8]) -> Result<TunnelRequest, ConnectError> {
+    let head = std::str::from_utf8(head)
+        .map_err(|_| ConnectError::Malformed("request head is not UTF-8".to_string()))?;
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ConnectError::Malformed(format!(
+            "invalid request line '{}'",
+            request_line
+        )));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(ConnectError::Malformed(format!(
+            "unsupported version '{}'",
+            version
+        )));
+    }
+    if !method.eq_ignore_ascii_case("CONNECT") {
+        return Err(ConnectError::NotConnect);
+    }
+
+    let (host, port) = target
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+        .filter(|(host, port)| !host.is_empty() && *port != 0)
+        .ok_or_else(|| ConnectError::Malformed(format!("invalid CONNECT target '{}'", target)))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+    for line in lines.filter(|l| !l.is_empty()) {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| ConnectError::Malformed(format!("invalid header line '{}'", line)))?;
+        headers
+            .entry(name.trim().to_ascii_lowercase())
+            .or_default()
+            .push(value.trim().to_string());
+    }
+
+    Ok(TunnelRequest {
+        host: host.to_string(),
+        port,
+        user: None,
+        headers,
+    })
+}
+
+/// Check `Proxy-Authorization`; returns the user, or `None` without
+/// authentication
+fn check_basic_auth(
+    rules: &ForwardProxyConfig,
+    headers: &HashMap<String, Vec<String>>,
+) -> Result<Option<String>, ()> {
+    if rules.users.is_empty() {
+        return Ok(None);
+    }
+    let credentials = headers
+        .get("proxy-authorization")
+        .and_then(|values| values.first())
+        .and_then(|value| {
+            let (scheme, encoded) = value.split_once(' ')?;
+            if !scheme.eq_ignore_ascii_case("basic") {
+                return None;
+            }
+            BASE64.decode(encoded.trim()).ok()
+        })
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(())?;
+    let (username, password) = credentials.split_once(':').ok_or(())?;
+    authenticate(rules, username, password)
+        .then(|| Some(username.to_string()))
+        .ok_or(())
+}
+
+fn authenticate(rules: &ForwardProxyConfig, username: &str, password: &str) -> bool {
+    rules.users.iter().any(|user| {
+        user.username == username && bool::from(user.password.as_bytes().ct_eq(password.as_bytes()))
+    })
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    status: u16,
+    headers: &[(&str, &str)],
+) -> Result<(), String> {
+    let reason = http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("Unknown");
+    let mut response = format!("HTTP/1.1 {} {}\r\n", status, reason);
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("Content-Length: 0\r\nConnection: close\r\n\r\n");
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug)]
+enum SocksError {
+    /// Authentication failed; the client has been told
+    Unauthorized,
+    /// Refuse the request with this reply code
+    Reply(u8, String),
+    Io(String),
+}
+
+impl From<std::io::Error> for SocksError {
+    fn from(e: std::io::Error) -> Self {
+        SocksError::Io(e.to_string())
+    }
+}
+
+/// Method selection, authentication and the CONNECT request of SOCKS5
+async fn negotiate_socks5<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    rules: &ForwardProxyConfig,
+) -> Result<TunnelRequest, SocksError> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+
+    let method = if rules.users.is_empty() {
+        SOCKS_NO_AUTH
+    } else {
+        SOCKS_USER_PASS
+    };
+    if !methods.contains(&method) {
+        stream
+            .write_all(&[SOCKS_VERSION, SOCKS_NO_ACCEPTABLE])
+            .await?;
+        return Err(SocksError::Unauthorized);
+    }
+    stream.write_all(&[SOCKS_VERSION, method]).await?;
+
+    let mut user = None;
+    if method == SOCKS_USER_PASS {
+        let mut version = [0u8; 2];
+        stream.read_exact(&mut version).await?;
+        let mut username = vec![0u8; version[1] as usize];
+        stream.read_exact(&mut username).await?;
+        let mut len = [0u8; 1];
+        stream.read_exact(&mut len).await?;
+        let mut password = vec![0u8; len[0] as usize];
+        stream.read_exact(&mut password).await?;
+
+        let username = String::from_utf8_lossy(&username).into_owned();
+        let password = String::from_utf8_lossy(&password);
+        if version[0] != SOCKS_AUTH_VERSION || !authenticate(rules, &username, &password) {
+            stream.write_all(&[SOCKS_AUTH_VERSION, 0x01]).await?;
+            return Err(SocksError::Unauthorized);
+        }
+        stream.write_all(&[SOCKS_AUTH_VERSION, 0x00]).await?;
+        user = Some(username);
+    }
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    let [version, command, _, address_type] = request;
+    if version != SOCKS_VERSION {
+        return Err(SocksError::Io(format!("invalid SOCKS version {}", version)));
+    }
+    if command != SOCKS_CMD_CONNECT {
+        return Err(SocksError::Reply(
+            0x07,
+            format!("unsupported SOCKS command {}", command),
+        ));
+    }
+
+    let host = match address_type {
+        SOCKS_ATYP_IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            Ipv4Addr::from(octets).to_string()
+        }
+        SOCKS_ATYP_IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        SOCKS_ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name)
+                .map_err(|_| SocksError::Reply(0x08, "invalid domain name".to_string()))?
+        }
+        other => {
+            return Err(SocksError::Reply(
+                0x08,
+                format!("unsupported address type {}", other),
+            ));
+        }
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port).await?;
+
+    Ok(TunnelRequest {
+        host,
+        port: u16::from_be_bytes(port),
+        user,
+        headers: HashMap::new(),
+    })
+}
+
+async fn write_socks_reply<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    code: u8,
+    bound: Option<SocketAddr>,
+) -> Result<(), String> {
+    let bound = bound.unwrap_or_else(|| SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0));
+    let mut reply = vec![SOCKS_VERSION, code, 0x00];
+    match bound.ip() {
+        IpAddr::V4(ip) => {
+            reply.push(SOCKS_ATYP_IPV4);
+            reply.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            reply.push(SOCKS_ATYP_IPV6);
+            reply.extend_from_slice(&ip.octets());
+        }
+    }
+    reply.extend_from_slice(&bound.port().to_be_bytes());
+    stream.write_all(&reply).await.map_err(|e| e.to_string())
+}
+
+/// Copy both ways until both sides have closed or nothing moved for
+/// `idle_timeout`; returns the bytes sent to and received from upstream
+async fn relay(
+    mut client: TcpStream,
+    mut upstream: TcpStream,
+    idle_timeout: Duration,
+) -> std::io::Result<(u64, u64)> {
+    let (mut client_read, mut client_write) = client.split();
+    let (mut upstream_read, mut upstream_write) = upstream.split();
+    let mut client_buf = vec![0u8; 16 * 1024];
+    let mut upstream_buf = vec![0u8; 16 * 1024];
+    let (mut client_open, mut upstream_open) = (true, true);
+    let (mut sent, mut received) = (0u64, 0u64);
+
+    while client_open || upstream_open {
+        tokio::select! {
+            n = client_read.read(&mut client_buf), if client_open => {
+                let n = n?;
+                if n == 0 {
+                    client_open = false;
+                    upstream_write.shutdown().await?;
+                } else {
+                    upstream_write.write_all(&client_buf[..n]).await?;
+                    sent += n as u64;
+                }
+            }
+            n = upstream_read.read(&mut upstream_buf), if upstream_open => {
+                let n = n?;
+                if n == 0 {
+                    upstream_open = false;
+                    client_write.shutdown().await?;
+                } else {
+                    client_write.write_all(&upstream_buf[..n]).await?;
+                    received += n as u64;
+                }
+            }
+            _ = tokio::time::sleep(idle_timeout) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "forward-proxy tunnel idle",
+                ));
+            }
+        }
+    }
+    Ok((sent, received))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_config::ForwardProxyUser;
+
+    fn rules_with_user() -> ForwardProxyConfig {
+        ForwardProxyConfig {
+            users: vec![ForwardProxyUser {
+                username: "ci".to_string(),
+                password: "s3cret".to_string(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_connect_request() {
+        let request = parse_connect_request(
+            b"CONNECT api.github.com:443 HTTP/1.1\r\nHost: api.github.com:443\r\nUser-Agent: curl\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(request.host, "api.github.com");
+        assert_eq!(request.port, 443);
+        assert_eq!(request.headers["user-agent"], vec!["curl"]);
+
+        let request =
+            parse_connect_request(b"CONNECT [2001:db8::1]:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((request.host.as_str(), request.port), ("2001:db8::1", 8443));
+
+        assert_eq!(
+            parse_connect_request(b"GET http://example.com/ HTTP/1.1\r\n\r\n"),
+            Err(ConnectError::NotConnect)
+        );
+        for malformed in [
+            &b"CONNECT example.com HTTP/1.1\r\n\r\n"[..],
+            b"CONNECT example.com:0 HTTP/1.1\r\n\r\n",
+            b"CONNECT example.com:443 HTTP/2\r\n\r\n",
+            b"CONNECT example.com:443 HTTP/1.1\r\nbroken header\r\n\r\n",
+        ] {
+            assert!(matches!(
+                parse_connect_request(malformed),
+                Err(ConnectError::Malformed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let no_auth = ForwardProxyConfig::default();
+        assert_eq!(check_basic_auth(&no_auth, &HashMap::new()), Ok(None));
+
+        let rules = rules_with_user();
+        let header = |value: &str| {
+            HashMap::from([("proxy-authorization".to_string(), vec![value.to_string()])])
+        };
+        let valid = format!("Basic {}", BASE64.encode("ci:s3cret"));
+        assert_eq!(
+            check_basic_auth(&rules, &header(&valid)),
+            Ok(Some("ci".to_string()))
+        );
+        let wrong = format!("Basic {}", BASE64.encode("ci:guess"));
+        assert!(check_basic_auth(&rules, &header(&wrong)).is_err());
+        assert!(check_basic_auth(&rules, &header("Bearer abc")).is_err());
+        assert!(check_basic_auth(&rules, &HashMap::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_request_head_keeps_leftover() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(b"CONNECT a:1 HTTP/1.1\r\n\r\n\x16\x03\x01")
+            .await
+            .unwrap();
+        let (head, leftover) = read_request_head(&mut server).await.unwrap();
+        assert_eq!(head, b"CONNECT a:1 HTTP/1.1\r\n\r\n");
+        assert_eq!(leftover, b"\x16\x03\x01");
+    }
+
+    #[tokio::test]
+    async fn test_socks5_negotiation() {
+        let rules = rules_with_user();
+        let (mut client, mut server) = tokio::io::duplex(256);
+        let negotiation = tokio::spawn(async move { negotiate_socks5(&mut server, &rules).await });
+
+        // Greeting offering no-auth and username/password
+        client.write_all(&[0x05, 0x02, 0x00, 0x02]).await.unwrap();
+        let mut reply = [0u8; 2];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, [0x05, SOCKS_USER_PASS]);
+
+        client.write_all(&[0x01, 2, b'c', b'i']).await.unwrap();
+        client.write_all(&[6]).await.unwrap();
+        client.write_all(b"s3cret").await.unwrap();
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, [0x01, 0x00]);
+
+        // CONNECT example.com:443
+        client
+            .write_all(&[0x05, 0x01, 0x00, SOCKS_ATYP_DOMAIN, 11])
+            .await
+            .unwrap();
+        client.write_all(b"example.com").await.unwrap();
+        client.write_all(&443u16.to_be_bytes()).await.unwrap();
+
+        let request = negotiation.await.unwrap().unwrap();
+        assert_eq!(request.host, "example.com");
+        assert_eq!(request.port, 443);
+        assert_eq!(request.user.as_deref(), Some("ci"));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_rejects_missing_auth() {
+        let rules = rules_with_user();
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let negotiation = tokio::spawn(async move { negotiate_socks5(&mut server, &rules).await });
+
+        client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        let mut reply = [0u8; 2];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, [0x05, SOCKS_NO_ACCEPTABLE]);
+        assert!(matches!(
+            negotiation.await.unwrap(),
+            Err(SocksError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_relay_copies_both_ways() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        let echo = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let front = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let front_addr = front.local_addr().unwrap();
+        let tunnel = tokio::spawn(async move {
+            let (client, _) = front.accept().await.unwrap();
+            let upstream = TcpStream::connect(upstream_addr).await.unwrap();
+            relay(client, upstream, Duration::from_secs(5)).await
+        });
+
+        let mut client = TcpStream::connect(front_addr).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        client.shutdown().await.unwrap();
+        echo.await.unwrap();
+
+        assert_eq!(tunnel.await.unwrap().unwrap(), (5, 5));
+    }
+}
@@ -0,0 +1,299 @@
+//! Embedded Kubernetes Gateway API controller.
+//!
+//! With the `gateway-api` feature and `--gateway-controller`, the proxy runs
+//! the `zentinel-gateway` controller in-process. It watches GatewayClass,
+//! Gateway, HTTPRoute (and the other supported route kinds) and applies the
+//! translated routes, upstreams and filters through
+//! `ConfigManager::apply_config()`, so the usual validation, hooks and atomic
+//! swap apply to every reconciliation.
+//!
+//! Listeners, server settings, agents and everything else still come from the
+//! proxy's own configuration file: Pingora listeners are bound before the
+//! server starts and cannot follow Gateway listener changes at runtime.
+//! Entries defined in the file take precedence over translated entries with
+//! the same id.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use zentinel_config::Config;
+use zentinel_gateway::embedded::EmbeddedSink;
+use zentinel_gateway::{GatewayController, GatewayError};
+
+use crate::reload::{ConfigManager, ReloadEvent, ReloadTrigger};
+
+/// Ids of the entries contributed by the last applied Gateway API config.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct GatewayOwned {
+    routes: HashSet<String>,
+    upstreams: HashSet<String>,
+    filters: HashSet<String>,
+}
+
+#[derive(Default)]
+struct GatewayState {
+    /// Entries of the last successfully applied config.
+    owned: GatewayOwned,
+    /// Last translated config, re-applied after file reloads.
+    last: Option<Config>,
+}
+
+/// Merges translated Gateway API configs into the running proxy config.
+struct GatewayConfigMerger {
+    /// Held across `apply_config()` so pushes from concurrent rebuilds
+    /// never merge against a stale set of owned ids.
+    state: Mutex<GatewayState>,
+}
+
+impl GatewayConfigMerger {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(GatewayState::default()),
+        }
+    }
+
+    /// Merge and apply a freshly translated config.
+    async fn apply(
+        &self,
+        config_manager: &ConfigManager,
+        translated: Config,
+    ) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        state.last = Some(translated.clone());
+        Self::apply_locked(&mut state, config_manager, &translated).await
+    }
+
+    /// Re-apply the last translated config if a file reload replaced it.
+    async fn reapply(&self, config_manager: &ConfigManager) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        let Some(translated) = state.last.clone() else {
+            return Ok(());
+        };
+        if is_applied(&config_manager.current(), &state.owned) {
+            return Ok(());
+        }
+
+        info!("Re-applying Gateway API configuration after reload");
+        Self::apply_locked(&mut state, config_manager, &translated).await
+    }
+
+    async fn apply_locked(
+        state: &mut GatewayState,
+        config_manager: &ConfigManager,
+        translated: &Config,
+    ) -> Result<(), String> {
+        let current = config_manager.current();
+        let (merged, owned) = merge(&current, translated, &state.owned);
+
+        if !translated.listeners.is_empty() {
+            debug!(
+                listeners = translated.listeners.len(),
+                "Gateway listeners are served by the proxy's configured listeners"
+            );
+        }
+
+        config_manager
+            .apply_config(merged, ReloadTrigger::GatewayApi)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        info!(
+            routes = owned.routes.len(),
+            upstreams = owned.upstreams.len(),
+            filters = owned.filters.len(),
+            "Applied Gateway API configuration"
+        );
+
+        state.owned = owned;
+        Ok(())
+    }
+}
+
+/// Whether every entry in `owned` is present in `config`.
+fn is_applied(config: &Config, owned: &GatewayOwned) -> bool {
+    owned
+        .routes
+        .iter()
+        .all(|id| config.routes.iter().any(|r| &r.id == id))
+        && owned
+            .upstreams
+            .iter()
+            .all(|id| config.upstreams.contains_key(id))
+        && owned
+            .filters
+            .iter()
+            .all(|id| config.filters.contains_key(id))
+}
+
+/// Replace the Gateway API entries of `base` with those of `translated`.
+///
+/// Entries recorded in `previous` are removed from `base` first, so routes
+/// deleted from the cluster disappear. Translated entries whose id clashes
+/// with a file-defined entry are skipped.
+fn merge(base: &Config, translated: &Config, previous: &GatewayOwned) -> (Config, GatewayOwned) {
+    let mut merged = base.clone();
+    let mut owned = GatewayOwned::default();
+
+    merged.routes.retain(|r| !previous.routes.contains(&r.id));
+    merged
+        .upstreams
+        .retain(|id, _| !previous.upstreams.contains(id));
+    merged
+        .filters
+        .retain(|id, _| !previous.filters.contains(id));
+
+    for route in &translated.routes {
+        if merged.routes.iter().any(|r| r.id == route.id) {
+            warn!(
+                route = %route.id,
+                "Skipping Gateway API route, id is defined in the config file"
+            );
+            continue;
+        }
+        owned.routes.insert(route.id.clone());
+        merged.routes.push(route.clone());
+    }
+
+    for (id, upstream) in &translated.upstreams {
+        if merged.upstreams.contains_key(id) {
+            warn!(
+                upstream = %id,
+                "Skipping Gateway API upstream, id is defined in the config file"
+            );
+            continue;
+        }
+        owned.upstreams.insert(id.clone());
+        merged.upstreams.insert(id.clone(), upstream.clone());
+    }
+
+    for (id, filter) in &translated.filters {
+        if merged.filters.contains_key(id) {
+            warn!(
+                filter = %id,
+                "Skipping Gateway API filter, id is defined in the config file"
+            );
+            continue;
+        }
+        owned.filters.insert(id.clone());
+        merged.filters.insert(id.clone(), filter.clone());
+    }
+
+    (merged, owned)
+}
+
+/// Run the Gateway API controller against this proxy until it exits.
+///
+/// Connects to the cluster with the default kube client (in-cluster service
+/// account or `KUBECONFIG`) and applies every rebuild to `config_manager`.
+pub async fn run_gateway_controller(
+    config_manager: Arc<ConfigManager>,
+) -> Result<(), GatewayError> {
+    let merger = Arc::new(GatewayConfigMerger::new());
+
+    // A SIGHUP or file reload replaces the merged config with the file's
+    // contents; put the Gateway API entries back.
+    {
+        let config_manager = Arc::clone(&config_manager);
+        let merger = Arc::clone(&merger);
+        let mut events = config_manager.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(ReloadEvent::Applied { .. }) | Err(RecvError::Lagged(_)) => {
+                        if let Err(e) = merger.reapply(&config_manager).await {
+                            warn!(error = %e, "Failed to re-apply Gateway API configuration");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    let sink = EmbeddedSink::new(move |translated| {
+        let config_manager = Arc::clone(&config_manager);
+        let merger = Arc::clone(&merger);
+        async move { merger.apply(&config_manager, translated).await }
+    });
+
+    let controller = GatewayController::new()
+        .await?
+        .with_config_sink(Arc::new(sink));
+
+    info!("Starting embedded Gateway API controller");
+    controller.run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translated() -> Config {
+        let base = Config::default_for_testing();
+        let mut config = base.clone();
+        config.routes.clear();
+        config.upstreams.clear();
+
+        let mut route = base.routes[0].clone();
+        route.id = "default-web-rule0".to_string();
+        route.upstream = Some("default-web-rule0-upstream".to_string());
+        config.routes.push(route);
+
+        let mut upstream = base.upstreams["default"].clone();
+        upstream.id = "default-web-rule0-upstream".to_string();
+        config
+            .upstreams
+            .insert("default-web-rule0-upstream".to_string(), upstream);
+
+        config
+    }
+
+    #[test]
+    fn test_merge_adds_gateway_entries() {
+        let base = Config::default_for_testing();
+        let (merged, owned) = merge(&base, &translated(), &GatewayOwned::default());
+
+        assert_eq!(merged.routes.len(), base.routes.len() + 1);
+        assert!(merged.upstreams.contains_key("default"));
+        assert!(merged.upstreams.contains_key("default-web-rule0-upstream"));
+        assert!(owned.routes.contains("default-web-rule0"));
+        assert!(owned.upstreams.contains("default-web-rule0-upstream"));
+        assert_eq!(merged.listeners.len(), base.listeners.len());
+        assert!(is_applied(&merged, &owned));
+        assert!(!is_applied(&base, &owned));
+    }
+
+    #[test]
+    fn test_merge_removes_deleted_gateway_entries() {
+        let base = Config::default_for_testing();
+        let (merged, owned) = merge(&base, &translated(), &GatewayOwned::default());
+
+        let mut empty = translated();
+        empty.routes.clear();
+        empty.upstreams.clear();
+        let (merged, owned) = merge(&merged, &empty, &owned);
+
+        assert_eq!(merged.routes.len(), base.routes.len());
+        assert!(!merged.upstreams.contains_key("default-web-rule0-upstream"));
+        assert_eq!(owned, GatewayOwned::default());
+    }
+
+    #[test]
+    fn test_merge_keeps_file_entries_on_conflict() {
+        let base = Config::default_for_testing();
+        let mut conflicting = translated();
+        conflicting.routes[0].id = base.routes[0].id.clone();
+        conflicting.routes[0].upstream = Some("default-web-rule0-upstream".to_string());
+
+        let (merged, owned) = merge(&base, &conflicting, &GatewayOwned::default());
+
+        assert_eq!(merged.routes.len(), base.routes.len());
+        assert_eq!(merged.routes[0].upstream, base.routes[0].upstream);
+        assert!(owned.routes.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! Per-listener request hardening
+//!
+//! Checks the parsed request head against the listener's `hardening` block
+//! at the start of `request_filter`, before maintenance, limits and agents.
+//! A failed check answers with the check's status code (431, 414, 405 or
+//! 400 by default) and closes the connection:
+//!
+//! - `max-header-count` / `max-header-bytes`: number and total size of the
+//!   headers
+//! - `max-uri-length`: length of the path and query
+//! - `allowed-methods`: the request method; the answer lists the allowed
+//!   methods in `Allow`
+//! - `reject-ambiguous-framing`: both `Content-Length` and
+//!   `Transfer-Encoding`, or `Content-Length` values that disagree
+//! - `obs-fold`: header values continued on another line are forwarded
+//!   (`allow`), joined with a single space (`normalize`) or rejected
+//! - `reject-control-chars`: NUL and other control characters (except tab)
+//!   in header names or values; unless normalized, an obs-fold counts as one
+//!
+//! The HTTP/1 parser already refuses many malformed heads; these checks also
+//! cover requests from the HTTP/2 and HTTP/3 frontends and decide the status
+//! code. Rejected and normalized requests are counted in
+//! `zentinel_request_hardening_total{listener, check, action}`.
+
+use std::sync::LazyLock;
+
+use http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use http::{HeaderMap, HeaderValue};
+use pingora::http::RequestHeader;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+
+use zentinel_config::{ObsFoldPolicy, RequestHardeningConfig};
+
+/// Requests rejected or normalized, by listener, check and action
+static HARDENING: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_request_hardening_total",
+        "Requests rejected or normalized by listener request hardening",
+        &["listener", "check", "action"]
+    )
+    .ok()
+});
+
+fn count(listener: &str, check: Check, action: &str) {
+    if let Some(counter) = HARDENING.as_ref() {
+        counter
+            .with_label_values(&[listener, check.as_str(), action])
+            .inc();
+    }
+}
+
+/// A hardening check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    HeaderCount,
+    HeaderBytes,
+    UriLength,
+    Method,
+    Framing,
+    ObsFold,
+    ControlChars,
+    /// Raised by [`crate::normalization`] before route matching
+    Normalization,
+}
+
+impl Check {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::HeaderCount => "header_count",
+            Check::HeaderBytes => "header_bytes",
+            Check::UriLength => "uri_length",
+            Check::Method => "method",
+            Check::Framing => "framing",
+            Check::ObsFold => "obs_fold",
+            Check::ControlChars => "control_chars",
+            Check::Normalization => "normalization",
+        }
+    }
+
+    /// Response body of a rejected request
+    pub fn message(&self) -> &'static str {
+        match self {
+            Check::HeaderCount => "Too many request headers",
+            Check::HeaderBytes => "Request headers too large",
+            Check::UriLength => "Request URI too long",
+            Check::Method => "Method not allowed",
+            Check::Framing => "Ambiguous request framing",
+            Check::ObsFold => "Folded header values are not accepted",
+            Check::ControlChars => "Control characters in request headers",
+            Check::Normalization => "Request has no canonical form",
+        }
+    }
+}
+
+/// A request that failed a check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub check: Check,
+    pub status: u16,
+    /// `Allow` header value for rejected methods
+    pub allow: Option<String>,
+}
+
+/// Check a request against a listener's hardening settings, joining folded
+/// header values when `obs-fold "normalize"` is set
+pub fn inspect(
+    listener: &str,
+    config: &RequestHardeningConfig,
+    req: &mut RequestHeader,
+) -> Result<(), Rejection> {
+    let result = check(config, req);
+    match &result {
+        Ok(true) => count(listener, Check::ObsFold, "normalized"),
+        Ok(false) => {}
+        Err(rejection) => count(listener, rejection.check, "rejected"),
+    }
+    result.map(|_| ())
+}
+
+/// Run every configured check; `Ok(true)` when folded values were joined
+fn check(config: &RequestHardeningConfig, req: &mut RequestHeader) -> Result<bool, Rejection> {
+    let status = &config.status;
+    let reject = |check: Check, status: u16| Rejection {
+        check,
+        status,
+        allow: None,
+    };
+
+    if !config.allowed_methods.is_empty()
+        && !config
+            .allowed_methods
+            .iter()
+            .any(|m| m == req.method.as_str())
+    {
+        return Err(Rejection {
+            allow: Some(config.allowed_methods.join(", ")),
+            ..reject(Check::Method, status.method)
+        });
+    }
+
+    if let Some(limit) = config.max_uri_length {
+        let length = req.uri.path_and_query().map_or(0, |pq| pq.as_str().len());
+        if length > limit {
+            return Err(reject(Check::UriLength, status.uri_length));
+        }
+    }
+
+    if config
+        .max_header_count
+        .is_some_and(|limit| req.headers.len() > limit)
+    {
+        return Err(reject(Check::HeaderCount, status.header_count));
+    }
+
+    if let Some(limit) = config.max_header_bytes {
+        let size: usize = req
+            .headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum();
+        if size > limit {
+            return Err(reject(Check::HeaderBytes, status.header_bytes));
+        }
+    }
+
+    let mut normalized = false;
+    match config.obs_fold {
+        ObsFoldPolicy::Allow => {}
+        ObsFoldPolicy::Reject => {
+            if req.headers.values().any(|v| unfold(v.as_bytes()).is_some()) {
+                return Err(reject(Check::ObsFold, status.obs_fold));
+            }
+        }
+        ObsFoldPolicy::Normalize => {
+            normalized =
+                normalize_folds(req).map_err(|_| reject(Check::ObsFold, status.obs_fold))?;
+        }
+    }
+
+    if config.reject_control_chars
+        && req.headers.iter().any(|(name, value)| {
+            name.as_str().bytes().any(is_control)
+                || value.as_bytes().iter().copied().any(is_control)
+        })
+    {
+        return Err(reject(Check::ControlChars, status.control_chars));
+    }
+
+    if config.reject_ambiguous_framing && ambiguous_framing(&req.headers) {
+        return Err(reject(Check::Framing, status.framing));
+    }
+
+    Ok(normalized)
+}
+
+/// Replace the values of every header with a folded value by their joined
+/// form; fails when a joined value is still not a valid header value
+fn normalize_folds(req: &mut RequestHeader) -> Result<bool, http::header::InvalidHeaderValue> {
+    let mut folded: Vec<String> = req
+        .headers
+        .iter()
+        .filter(|(_, value)| unfold(value.as_bytes()).is_some())
+        .map(|(name, _)| name.as_str().to_string())
+        .collect();
+    folded.dedup();
+
+    for name in &folded {
+        let values = req
+            .headers
+            .get_all(name.as_str())
+            .iter()
+            .map(|value| {
+                let bytes = value.as_bytes();
+                HeaderValue::from_bytes(&unfold(bytes).unwrap_or_else(|| bytes.to_vec()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        req.remove_header(name.as_str());
+        for value in values {
+            req.append_header(name.clone(), value).ok();
+        }
+    }
+    Ok(!folded.is_empty())
+}
+
+/// A header value with every obs-fold (line break followed by whitespace)
+/// replaced by a single space, or `None` when it has no fold
+fn unfold(value: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut folded = false;
+    let mut i = 0;
+    while i < value.len() {
+        let rest = &value[i..];
+        let newline = if rest.starts_with(b"\r\n") {
+            2
+        } else if rest[0] == b'\n' {
+            1
+        } else {
+            0
+        };
+        if newline > 0 && matches!(rest.get(newline), Some(b' ' | b'\t')) {
+            while out.last().is_some_and(|b| matches!(b, b' ' | b'\t')) {
+                out.pop();
+            }
+            i += newline;
+            while matches!(value.get(i), Some(b' ' | b'\t')) {
+                i += 1;
+            }
+            out.push(b' ');
+            folded = true;
+        } else {
+            out.push(value[i]);
+            i += 1;
+        }
+    }
+    folded.then_some(out)
+}
+
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && b != b'\t') || b == 0x7f
+}
+
+/// Whether the body length is given both ways, or by disagreeing
+/// `Content-Length` values
+fn ambiguous_framing(headers: &HeaderMap) -> bool {
+    let mut lengths = headers
+        .get_all(CONTENT_LENGTH)
+        .iter()
+        .flat_map(|value| value.as_bytes().split(|b| *b == b','))
+        .map(|length| length.trim_ascii());
+    let Some(first) = lengths.next() else {
+        return false;
+    };
+    headers.contains_key(TRANSFER_ENCODING) || lengths.any(|length| length != first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> RequestHeader {
+        let mut req = RequestHeader::build(method, path.as_bytes(), None).unwrap();
+        for (name, value) in headers {
+            req.append_header(name.to_string(), *value).unwrap();
+        }
+        req
+    }
+
+    fn rejected(config: &RequestHardeningConfig, mut req: RequestHeader) -> Option<Rejection> {
+        check(config, &mut req).err()
+    }
+
+    #[test]
+    fn test_limits_and_methods() {
+        let config = RequestHardeningConfig {
+            max_header_count: Some(2),
+            max_header_bytes: Some(32),
+            max_uri_length: Some(16),
+            allowed_methods: vec!["GET".to_string(), "HEAD".to_string()],
+            ..Default::default()
+        };
+
+        let req = request("GET", "/ok", &[("host", "example.com")]);
+        assert_eq!(rejected(&config, req), None);
+
+        let rejection = rejected(&config, request("DELETE", "/", &[])).unwrap();
+        assert_eq!(rejection.check, Check::Method);
+        assert_eq!(rejection.status, 405);
+        assert_eq!(rejection.allow.as_deref(), Some("GET, HEAD"));
+
+        let req = request("GET", "/a/very/long/path?q=1", &[]);
+        assert_eq!(rejected(&config, req).unwrap().status, 414);
+
+        let req = request("GET", "/", &[("a", "1"), ("b", "2"), ("c", "3")]);
+        assert_eq!(rejected(&config, req).unwrap().check, Check::HeaderCount);
+
+        let req = request("GET", "/", &[("cookie", &"x".repeat(40))]);
+        let rejection = rejected(&config, req).unwrap();
+        assert_eq!(rejection.check, Check::HeaderBytes);
+        assert_eq!(rejection.status, 431);
+    }
+
+    #[test]
+    fn test_ambiguous_framing() {
+        let config = RequestHardeningConfig {
+            reject_ambiguous_framing: true,
+            ..Default::default()
+        };
+
+        let req = request("POST", "/", &[("content-length", "5")]);
+        assert_eq!(rejected(&config, req), None);
+        let req = request("POST", "/", &[("content-length", "5, 5")]);
+        assert_eq!(rejected(&config, req), None);
+
+        for headers in [
+            &[("content-length", "5"), ("transfer-encoding", "chunked")][..],
+            &[("content-length", "5"), ("content-length", "6")][..],
+            &[("content-length", "5, 7")][..],
+        ] {
+            let rejection = rejected(&config, request("POST", "/", headers)).unwrap();
+            assert_eq!(rejection.check, Check::Framing, "{headers:?}");
+            assert_eq!(rejection.status, 400);
+        }
+
+        let req = request("POST", "/", &[("transfer-encoding", "chunked")]);
+        assert_eq!(rejected(&config, req), None);
+    }
+
+    #[test]
+    fn test_unfold_and_control_chars() {
+        assert_eq!(unfold(b"plain value"), None);
+        assert_eq!(unfold(b"a,\r\n  b").as_deref(), Some(&b"a, b"[..]));
+        assert_eq!(unfold(b"a \n\tb\r\n c").as_deref(), Some(&b"a b c"[..]));
+        // A line break without whitespace is not a fold
+        assert_eq!(unfold(b"a\r\nb"), None);
+
+        assert!(is_control(0));
+        assert!(is_control(b'\r'));
+        assert!(is_control(0x7f));
+        assert!(!is_control(b'\t'));
+        assert!(!is_control(0x80));
+    }
+
+    #[test]
+    fn test_status_codes_are_configurable() {
+        let mut config = RequestHardeningConfig {
+            max_uri_length: Some(1),
+            ..Default::default()
+        };
+        config.status.uri_length = 400;
+        let rejection = rejected(&config, request("GET", "/long", &[])).unwrap();
+        assert_eq!(rejection.status, 400);
+        assert!(inspect(
+            "public",
+            &RequestHardeningConfig::default(),
+            &mut request("GET", "/", &[])
+        )
+        .is_ok());
+    }
+}
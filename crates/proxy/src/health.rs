@@ -166,6 +166,7 @@ impl ActiveHealthChecker {
                 path,
                 expected_status,
                 host,
+                ..
             } => {
                 trace!(
                     path = %path,
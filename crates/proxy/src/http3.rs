@@ -0,0 +1,454 @@
+//! HTTP/3 (QUIC) listener support.
+//!
+//! Pingora serves TCP only, so an `h3` listener is fronted by a QUIC endpoint:
+//! it terminates QUIC and TLS 1.3 on the listener's UDP address and replays
+//! each request stream as HTTP/1.1 to Pingora on an internal Unix socket,
+//! streaming bodies in both directions. The internal connection is
+//! recorded in the [`proxy_protocol`] registry with the client address, the
+//! public listener address, the protocol and any mTLS client certificate, so
+//! routing, client IP resolution, rate limiting and the agent event path
+//! (headers and body chunks) behave exactly as on TCP listeners.
+//!
+//! HTTP/1.1 has no multiplexing, so every request stream uses its own
+//! internal connection.
+//!
+//! HTTPS listeners advertise `h3` listeners with an `Alt-Svc` header (see
+//! [`alt_svc_value`]).
+
+use anyhow::{anyhow, Context, Result};
+use bytes::{Buf, Bytes};
+use http::{header, HeaderMap, Request, Response};
+use http_body_util::{BodyExt, Either, Empty, StreamBody};
+use hyper::body::Frame;
+use hyper_util::rt::TokioIo;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use zentinel_config::{ListenerConfig, ListenerProtocol, QuicConfig};
+
+use crate::client_ip::proxy_protocol::{self, ProxiedConnection};
+
+/// Protocol name recorded for HTTP/3 requests
+pub const PROTOCOL: &str = "HTTP/3";
+
+/// Request body chunks buffered between the QUIC stream and the internal hop
+const BODY_CHANNEL_CAPACITY: usize = 16;
+
+/// Hop-by-hop headers that must not cross between HTTP/1.1 and HTTP/3
+const HOP_BY_HOP_HEADERS: &[header::HeaderName] = &[
+    header::CONNECTION,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+];
+
+/// Build the `Alt-Svc` header value advertising the configured HTTP/3
+/// listeners, e.g. `h3=":443"; ma=86400`.
+///
+/// Returns `None` when no `h3` listener advertises itself.
+pub fn alt_svc_value(listeners: &[ListenerConfig]) -> Option<String> {
+    let entries: Vec<String> = listeners
+        .iter()
+        .filter(|l| l.protocol == ListenerProtocol::Http3 && l.quic.alt_svc_max_age_secs > 0)
+        .filter_map(|l| {
+            let port = l.address.parse::<SocketAddr>().ok()?.port();
+            Some(format!(
+                "h3=\":{}\"; ma={}",
+                port, l.quic.alt_svc_max_age_secs
+            ))
+        })
+        .collect();
+    (!entries.is_empty()).then(|| entries.join(", "))
+}
+
+/// Build the QUIC endpoint configuration for a listener.
+pub fn build_server_config(
+    tls: rustls::ServerConfig,
+    quic: &QuicConfig,
+    max_concurrent_streams: u32,
+) -> Result<quinn::ServerConfig> {
+    let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls)
+        .map_err(|e| anyhow!("TLS configuration is not usable for QUIC: {}", e))?;
+    let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+
+    let mut transport = quinn::TransportConfig::default();
+    transport
+        .max_idle_timeout(Some(
+            Duration::from_secs(quic.max_idle_timeout_secs)
+                .try_into()
+                .context("quic max-idle-timeout-secs out of range")?,
+        ))
+        .keep_alive_interval(quic.keep_alive_interval_secs.map(Duration::from_secs))
+        .receive_window(
+            quinn::VarInt::from_u64(quic.initial_max_data)
+                .context("quic initial-max-data out of range")?,
+        )
+        .stream_receive_window(
+            quinn::VarInt::from_u64(quic.initial_max_stream_data)
+                .context("quic initial-max-stream-data out of range")?,
+        )
+        .max_concurrent_bidi_streams(quinn::VarInt::from_u32(max_concurrent_streams))
+        .max_concurrent_uni_streams(quinn::VarInt::from_u32(quic.max_concurrent_uni_streams))
+        .initial_mtu(quic.initial_mtu);
+    server_config.transport_config(Arc::new(transport));
+
+    Ok(server_config)
+}
+
+/// Accept HTTP/3 connections on `public_address` and hand their requests to
+/// Pingora on `internal_address`.
+pub async fn run_listener(
+    listener_id: String,
+    public_address: String,
+    server_config: quinn::ServerConfig,
+    internal_address: PathBuf,
+) {
+    let bind_address = match public_address.parse::<SocketAddr>() {
+        Ok(address) => address,
+        Err(e) => {
+            error!(
+                listener_id = %listener_id,
+                address = %public_address,
+                error = %e,
+                "Invalid HTTP/3 listener address"
+            );
+            return;
+        }
+    };
+    let endpoint = match quinn::Endpoint::server(server_config, bind_address) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            error!(
+                listener_id = %listener_id,
+                address = %public_address,
+                error = %e,
+                "Failed to bind HTTP/3 listener"
+            );
+            return;
+        }
+    };
+    info!(
+        listener_id = %listener_id,
+        address = %public_address,
+        internal_address = %internal_address.display(),
+        "HTTP/3 (QUIC) listener accepting connections"
+    );
+
+    let internal_address: Arc<Path> = Arc::from(internal_address);
+    while let Some(incoming) = endpoint.accept().await {
+        let public_address = public_address.clone();
+        let internal_address = Arc::clone(&internal_address);
+        tokio::spawn(async move {
+            let peer = incoming.remote_address();
+            let connection = match incoming.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    debug!(peer = %peer, error = %e, "QUIC handshake failed");
+                    return;
+                }
+            };
+            if let Err(e) = handle_connection(connection, public_address, internal_address).await {
+                debug!(peer = %peer, error = %e, "HTTP/3 connection closed with error");
+            }
+        });
+    }
+    warn!(listener_id = %listener_id, "HTTP/3 listener endpoint closed");
+}
+
+/// Serve the request streams of one QUIC connection.
+async fn handle_connection(
+    connection: quinn::Connection,
+    public_address: String,
+    internal_address: Arc<Path>,
+) -> Result<()> {
+    // The QUIC handshake only completes once the client verifier accepted
+    // the certificate
+    let client_cert = peer_certificate(&connection);
+    let downstream = ProxiedConnection {
+        source: Some(connection.remote_address()),
+        listener_address: public_address,
+        protocol: Some(PROTOCOL),
+        tls: false,
+        server_name: server_name(&connection),
+        client_cert_verified: client_cert.is_some(),
+        client_cert,
+        bot_signals: None,
+    };
+
+    let mut h3_conn =
+        h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(connection))
+            .await
+            .map_err(|e| anyhow!("HTTP/3 connection setup failed: {}", e))?;
+
+    loop {
+        let resolver = match h3_conn.accept().await {
+            Ok(Some(resolver)) => resolver,
+            // Client closed the connection gracefully
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(anyhow!("{}", e)),
+        };
+        let downstream = downstream.clone();
+        let internal_address = Arc::clone(&internal_address);
+        tokio::spawn(async move {
+            let (request, stream) = match resolver.resolve_request().await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    debug!(error = %e, "Failed to read HTTP/3 request headers");
+                    return;
+                }
+            };
+            if let Err(e) = handle_request(request, stream, downstream, &internal_address).await {
+                debug!(error = %e, "HTTP/3 request failed");
+            }
+        });
+    }
+}
+
+/// Server name (SNI) the client sent in the QUIC handshake.
+fn server_name(connection: &quinn::Connection) -> Option<String> {
+    connection
+        .handshake_data()?
+        .downcast::<quinn::crypto::rustls::HandshakeData>()
+        .ok()?
+        .server_name
+}
+
+/// Leaf certificate presented by the client during the QUIC handshake.
+fn peer_certificate(connection: &quinn::Connection) -> Option<Vec<u8>> {
+    let identity = connection.peer_identity()?;
+    let chain = identity
+        .downcast::<Vec<rustls::pki_types::CertificateDer<'static>>>()
+        .ok()?;
+    chain.first().map(|cert| cert.to_vec())
+}
+
+/// Replay one HTTP/3 request to Pingora over an internal HTTP/1.1
+/// connection and stream the response back.
+async fn handle_request(
+    request: Request<()>,
+    stream: h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    downstream: ProxiedConnection,
+    internal_address: &Path,
+) -> Result<()> {
+    let (mut send, mut recv) = stream.split();
+
+    // The registration is dropped with this function, after the response
+    let (internal, _registration) = proxy_protocol::connect_internal(internal_address, downstream)
+        .await
+        .context("failed to connect to internal listener")?;
+
+    async move {
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(TokioIo::new(internal))
+                .await
+                .context("internal handshake failed")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!(error = %e, "Loopback connection to internal listener failed");
+            }
+        });
+
+        let (parts, ()) = request.into_parts();
+        let body = if expects_body(&parts.method, &parts.headers) {
+            // Stream the request body as it arrives on the QUIC stream
+            let (tx, rx) =
+                mpsc::channel::<Result<Frame<Bytes>, std::io::Error>>(BODY_CHANNEL_CAPACITY);
+            tokio::spawn(async move {
+                loop {
+                    match recv.recv_data().await {
+                        Ok(Some(mut chunk)) => {
+                            let data = chunk.copy_to_bytes(chunk.remaining());
+                            if tx.send(Ok(Frame::data(data))).await.is_err() {
+                                return;
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+                            return;
+                        }
+                    }
+                }
+                if let Ok(Some(trailers)) = recv.recv_trailers().await {
+                    let _ = tx.send(Ok(Frame::trailers(trailers))).await;
+                }
+            });
+            let frames = futures::stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|frame| (frame, rx))
+            });
+            Either::Right(StreamBody::new(Box::pin(frames)))
+        } else {
+            Either::Left(Empty::<Bytes>::new())
+        };
+
+        let upstream_request = to_http1_request(parts, body)?;
+        let response = sender
+            .send_request(upstream_request)
+            .await
+            .context("internal listener request failed")?;
+
+        let (parts, mut body) = response.into_parts();
+        let mut head = Response::from_parts(parts, ());
+        strip_hop_by_hop(head.headers_mut());
+        *head.version_mut() = http::Version::HTTP_3;
+        send.send_response(head)
+            .await
+            .map_err(|e| anyhow!("failed to send response headers: {}", e))?;
+
+        while let Some(frame) = body.frame().await {
+            let frame = frame.context("internal listener response body failed")?;
+            match frame.into_data() {
+                Ok(data) => send
+                    .send_data(data)
+                    .await
+                    .map_err(|e| anyhow!("failed to send response body: {}", e))?,
+                Err(frame) => {
+                    if let Ok(trailers) = frame.into_trailers() {
+                        send.send_trailers(trailers)
+                            .await
+                            .map_err(|e| anyhow!("failed to send trailers: {}", e))?;
+                    }
+                }
+            }
+        }
+        send.finish()
+            .await
+            .map_err(|e| anyhow!("failed to finish response stream: {}", e))?;
+        Ok::<_, anyhow::Error>(())
+    }
+    .await
+}
+
+/// Whether a request may carry a body worth streaming.
+///
+/// Body-less requests are sent with an empty body so the internal hop does
+/// not switch to chunked encoding for them.
+fn expects_body(method: &http::Method, headers: &HeaderMap) -> bool {
+    match headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        Some(length) => length > 0,
+        None => !matches!(
+            *method,
+            http::Method::GET
+                | http::Method::HEAD
+                | http::Method::DELETE
+                | http::Method::OPTIONS
+                | http::Method::TRACE
+        ),
+    }
+}
+
+/// Convert an HTTP/3 request head to its HTTP/1.1 form: origin-form target
+/// and a `Host` header from the `:authority` pseudo-header.
+fn to_http1_request<B>(mut parts: http::request::Parts, body: B) -> Result<Request<B>> {
+    if !parts.headers.contains_key(header::HOST) {
+        let authority = parts
+            .uri
+            .authority()
+            .ok_or_else(|| anyhow!("HTTP/3 request without :authority or Host"))?;
+        parts
+            .headers
+            .insert(header::HOST, authority.as_str().parse()?);
+    }
+    strip_hop_by_hop(&mut parts.headers);
+
+    parts.uri = parts
+        .uri
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/")
+        .parse()?;
+    parts.version = http::Version::HTTP_11;
+
+    Ok(Request::from_parts(parts, body))
+}
+
+/// Remove headers that are meaningful only for a single HTTP/1.1 hop.
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+    headers.remove("keep-alive");
+    headers.remove("proxy-connection");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener(id: &str, address: &str, protocol: ListenerProtocol) -> ListenerConfig {
+        ListenerConfig {
+            id: id.to_string(),
+            address: address.to_string(),
+            protocol,
+            tls: None,
+            default_route: None,
+            namespace: None,
+            request_timeout_secs: 60,
+            keepalive_timeout_secs: 75,
+            max_concurrent_streams: 100,
+            keepalive_max_requests: None,
+            proxy_protocol: false,
+            bot_signals: false,
+            quic: QuicConfig::default(),
+            slow_client: Default::default(),
+            hardening: Default::default(),
+            forward_proxy: None,
+            stream: None,
+        }
+    }
+
+    #[test]
+    fn alt_svc_advertises_http3_listeners() {
+        let mut quic = listener("quic", "0.0.0.0:443", ListenerProtocol::Http3);
+        let web = listener("web", "0.0.0.0:8443", ListenerProtocol::Https);
+        assert_eq!(
+            alt_svc_value(&[web.clone(), quic.clone()]).as_deref(),
+            Some("h3=\":443\"; ma=86400")
+        );
+
+        quic.quic.alt_svc_max_age_secs = 0;
+        assert_eq!(alt_svc_value(&[web.clone(), quic]), None);
+        assert_eq!(alt_svc_value(&[web]), None);
+    }
+
+    #[test]
+    fn converts_request_head_to_http1() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("https://api.example.com/v1/items?page=2")
+            .version(http::Version::HTTP_3)
+            .header("content-type", "application/json")
+            .header("connection", "close")
+            .body(())
+            .unwrap();
+        let (parts, ()) = request.into_parts();
+        let request = to_http1_request(parts, ()).unwrap();
+
+        assert_eq!(request.uri(), "/v1/items?page=2");
+        assert_eq!(request.version(), http::Version::HTTP_11);
+        assert_eq!(request.headers()["host"], "api.example.com");
+        assert_eq!(request.headers()["content-type"], "application/json");
+        assert!(!request.headers().contains_key("connection"));
+    }
+
+    #[test]
+    fn body_less_requests_skip_streaming() {
+        let mut headers = HeaderMap::new();
+        assert!(!expects_body(&http::Method::GET, &headers));
+        assert!(expects_body(&http::Method::POST, &headers));
+
+        headers.insert(header::CONTENT_LENGTH, "0".parse().unwrap());
+        assert!(!expects_body(&http::Method::POST, &headers));
+        headers.insert(header::CONTENT_LENGTH, "12".parse().unwrap());
+        assert!(expects_body(&http::Method::DELETE, &headers));
+    }
+}
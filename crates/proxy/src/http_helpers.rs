@@ -229,6 +229,34 @@ pub async fn write_error(
     Ok(())
 }
 
+/// Write an error response with additional headers
+///
+/// Like `write_error`, followed by `headers` (e.g. `Retry-After`).
+pub async fn write_error_with_headers(
+    session: &mut Session,
+    status: u16,
+    body: &str,
+    content_type: &str,
+    headers: &[(&str, String)],
+) -> Result<(), Box<Error>> {
+    let mut resp_header = ResponseHeader::build(status, None)?;
+    resp_header.insert_header("Content-Type", content_type)?;
+    resp_header.insert_header("Content-Length", body.len().to_string())?;
+    for (name, value) in headers {
+        resp_header.insert_header(name.to_string(), value)?;
+    }
+
+    session.set_keepalive(None);
+    session
+        .write_response_header(Box::new(resp_header), false)
+        .await?;
+    session
+        .write_response_body(Some(Bytes::copy_from_slice(body.as_bytes())), true)
+        .await?;
+
+    Ok(())
+}
+
 /// Write a plain text error response
 ///
 /// Shorthand for `write_error` with `text/plain; charset=utf-8` content type.
@@ -293,14 +321,8 @@ pub async fn write_rate_limit_error(
     resp_header.insert_header("Content-Type", "text/plain; charset=utf-8")?;
     resp_header.insert_header("Content-Length", body.len().to_string())?;
 
-    // Add standard rate limit headers
-    resp_header.insert_header("X-RateLimit-Limit", limit.to_string())?;
-    resp_header.insert_header("X-RateLimit-Remaining", remaining.to_string())?;
-    resp_header.insert_header("X-RateLimit-Reset", reset_at.to_string())?;
-
-    // Add Retry-After header (seconds until reset)
-    if retry_after > 0 {
-        resp_header.insert_header("Retry-After", retry_after.to_string())?;
+    for (name, value) in rate_limit_headers(limit, remaining, reset_at, retry_after) {
+        resp_header.insert_header(name, value)?;
     }
 
     session.set_keepalive(None);
@@ -314,6 +336,27 @@ pub async fn write_rate_limit_error(
     Ok(())
 }
 
+/// Standard rate limit headers for a rejected request
+///
+/// `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`,
+/// plus `Retry-After` when `retry_after` is non-zero.
+pub fn rate_limit_headers(
+    limit: u32,
+    remaining: u32,
+    reset_at: u64,
+    retry_after: u64,
+) -> Vec<(&'static str, String)> {
+    let mut headers = vec![
+        ("X-RateLimit-Limit", limit.to_string()),
+        ("X-RateLimit-Remaining", remaining.to_string()),
+        ("X-RateLimit-Reset", reset_at.to_string()),
+    ];
+    if retry_after > 0 {
+        headers.push(("Retry-After", retry_after.to_string()));
+    }
+    headers
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
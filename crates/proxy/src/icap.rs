@@ -0,0 +1,831 @@
+//! ICAP client for the `icap` filter
+//!
+//! Streams request bodies to an ICAP server's REQMOD service and response
+//! bodies to its RESPMOD service (RFC 3507), typically c-icap or another
+//! antivirus gateway in front of ClamAV. Bodies are held back while they
+//! are scanned:
+//!
+//! - The first `preview-bytes` of a body (by default the `Preview` size the
+//!   service advertises in its OPTIONS response, cached for `Options-TTL`)
+//!   are sent as a preview. The server either answers right away or asks
+//!   for the rest with `100 Continue`; the rest is then streamed as it
+//!   arrives.
+//! - Requests carry `Allow: 204`, so clean bodies are answered with
+//!   `204 No Content` and released unchanged, as soon as the answer comes,
+//!   even when it comes after the preview.
+//! - Any `200` answer means the server replaced the message (e.g. with a
+//!   virus notice): requests are blocked with `status-code`, responses are
+//!   cut off, since their headers have already been sent.
+//! - Connection errors, error answers, timeouts and bodies larger than
+//!   `max-body-bytes` block or pass depending on `failure-mode`.
+//!
+//! Every scan uses its own connection. `icaps` (ICAP over TLS) is not
+//! supported.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "antivirus" {
+//!         type "icap"
+//!         reqmod "icap://clamav.internal:1344/avscan"
+//!         respmod "icap://clamav.internal:1344/avscan"
+//!         timeout-ms 5000
+//!         failure-mode "closed"
+//!     }
+//! }
+//! ```
+
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use http::HeaderMap;
+use prometheus::{register_histogram_vec, register_int_counter_vec, HistogramVec, IntCounterVec};
+use serde_json::json;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+use zentinel_config::{parse_icap_url, FailureMode, IcapFilter};
+
+/// Scan latency per filter, mode and result
+static ICAP_SCAN_DURATION: LazyLock<Option<HistogramVec>> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "zentinel_icap_scan_duration_seconds",
+        "Time from the first body byte sent to the ICAP server to its verdict",
+        &["filter", "mode", "result"],
+        vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .ok()
+});
+
+/// Scanned bodies per filter, mode and result (clean, blocked, error,
+/// too_large)
+static ICAP_SCANS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_icap_scans_total",
+        "Bodies scanned by icap filters",
+        &["filter", "mode", "result"]
+    )
+    .ok()
+});
+
+/// OPTIONS answers per service URL with their expiry
+static SERVICE_OPTIONS: LazyLock<DashMap<String, (Option<usize>, Instant)>> =
+    LazyLock::new(DashMap::new);
+
+/// How long OPTIONS answers without `Options-TTL` are cached
+const DEFAULT_OPTIONS_TTL: Duration = Duration::from_secs(3600);
+
+/// Largest ICAP response header block read
+const MAX_RESPONSE_HEAD_BYTES: usize = 64 * 1024;
+
+/// ICAP method of a scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcapMode {
+    /// Request body scan
+    Reqmod,
+    /// Response body scan
+    Respmod,
+}
+
+impl IcapMode {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IcapMode::Reqmod => "reqmod",
+            IcapMode::Respmod => "respmod",
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        match self {
+            IcapMode::Reqmod => "REQMOD",
+            IcapMode::Respmod => "RESPMOD",
+        }
+    }
+}
+
+/// Errors talking to an ICAP server
+#[derive(Debug, Error)]
+pub enum IcapError {
+    /// Connection or transfer failed
+    #[error("ICAP I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The server didn't answer in time
+    #[error("ICAP server did not answer within {0:?}")]
+    Timeout(Duration),
+    /// The server's answer isn't valid ICAP
+    #[error("invalid ICAP response: {0}")]
+    Protocol(&'static str),
+    /// The server answered with an error status
+    #[error("ICAP server answered {0}")]
+    Status(u16),
+}
+
+/// Outcome of a completed scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcapVerdict {
+    /// `204 No Content`: the body is unchanged
+    Clean,
+    /// `200 OK`: the server replaced the message
+    Blocked {
+        /// Threat reported in `X-Infection-Found` or `X-Virus-ID`
+        threat: Option<String>,
+    },
+}
+
+/// A parsed ICAP response head
+#[derive(Debug)]
+struct IcapResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl IcapResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Threat name from the de-facto antivirus headers
+    fn threat(&self) -> Option<String> {
+        if let Some(found) = self.header("X-Infection-Found") {
+            // `Type=0; Resolution=2; Threat=Eicar-Test-Signature;`
+            let threat = found
+                .split(';')
+                .filter_map(|field| field.trim().strip_prefix("Threat="))
+                .next();
+            if let Some(threat) = threat {
+                return Some(threat.to_string());
+            }
+        }
+        self.header("X-Virus-ID")
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+    }
+}
+
+enum Phase {
+    /// Nothing sent yet
+    Idle,
+    /// Collecting the preview before the ICAP request is sent
+    Preview(usize),
+    /// ICAP request sent, streaming the rest of the body
+    Body(TcpStream),
+    Done(IcapVerdict),
+}
+
+/// Streams one body to an ICAP service
+pub struct IcapScan {
+    mode: IcapMode,
+    url: String,
+    host: String,
+    port: u16,
+    preview_bytes: Option<usize>,
+    timeout: Duration,
+    /// Encapsulated HTTP heads: (`req-hdr` or `res-hdr`, head)
+    http_heads: Vec<(&'static str, Bytes)>,
+    phase: Phase,
+    preview: BytesMut,
+    started: Option<Instant>,
+}
+
+impl IcapScan {
+    /// Scan on the service at `url`, encapsulating the HTTP heads
+    pub fn new(
+        mode: IcapMode,
+        url: &str,
+        filter: &IcapFilter,
+        http_heads: Vec<(&'static str, Bytes)>,
+    ) -> Result<Self, String> {
+        let (host, port, _) = parse_icap_url(url)?;
+        Ok(Self {
+            mode,
+            url: url.to_string(),
+            host,
+            port,
+            preview_bytes: filter.preview_bytes,
+            timeout: Duration::from_millis(filter.timeout_ms),
+            http_heads,
+            phase: Phase::Idle,
+            preview: BytesMut::new(),
+            started: None,
+        })
+    }
+
+    /// Send the next body chunk; returns the verdict once the server has
+    /// given it, which may be before the end of the body
+    pub async fn send(
+        &mut self,
+        chunk: &[u8],
+        end_of_stream: bool,
+    ) -> Result<Option<IcapVerdict>, IcapError> {
+        if let Phase::Done(verdict) = &self.phase {
+            return Ok(Some(verdict.clone()));
+        }
+        // Each step waits for the server at most once, so the timeout
+        // covers the server and not a slow client
+        let timeout = self.timeout;
+        let result = tokio::time::timeout(timeout, self.advance(chunk, end_of_stream))
+            .await
+            .unwrap_or(Err(IcapError::Timeout(timeout)));
+        if let Ok(Some(verdict)) = &result {
+            self.phase = Phase::Done(verdict.clone());
+        }
+        result
+    }
+
+    /// Time since the first body byte was sent
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.started.map(|started| started.elapsed())
+    }
+
+    async fn advance(
+        &mut self,
+        chunk: &[u8],
+        end_of_stream: bool,
+    ) -> Result<Option<IcapVerdict>, IcapError> {
+        if let Phase::Idle = self.phase {
+            if chunk.is_empty() {
+                // Nothing to scan in an empty body
+                return Ok(end_of_stream.then_some(IcapVerdict::Clean));
+            }
+            self.started = Some(Instant::now());
+            let preview = match self.preview_bytes {
+                Some(size) => Some(size),
+                None => self.service_preview().await?,
+            };
+            self.phase = match preview {
+                Some(size) => Phase::Preview(size),
+                None => Phase::Body(self.connect(None).await?),
+            };
+        }
+
+        match &mut self.phase {
+            Phase::Preview(size) => {
+                let size = *size;
+                self.preview.extend_from_slice(chunk);
+                if self.preview.len() < size && !end_of_stream {
+                    return Ok(None);
+                }
+                let preview = self.preview.split_to(size.min(self.preview.len()));
+                let rest = self.preview.split();
+                let complete = end_of_stream && rest.is_empty();
+
+                let mut stream = self.connect(Some(preview.len())).await?;
+                write_chunk(&mut stream, &preview).await?;
+                let terminator: &[u8] = if complete {
+                    b"0; ieof\r\n\r\n"
+                } else {
+                    b"0\r\n\r\n"
+                };
+                stream.write_all(terminator).await?;
+
+                let response = read_response(&mut stream).await?;
+                if response.status != 100 {
+                    return verdict(response).map(Some);
+                }
+                if complete {
+                    return Err(IcapError::Protocol("100 Continue after the whole body"));
+                }
+                write_chunk(&mut stream, &rest).await?;
+                if end_of_stream {
+                    return finish(&mut stream).await.map(Some);
+                }
+                self.phase = Phase::Body(stream);
+                Ok(None)
+            }
+            Phase::Body(stream) => {
+                write_chunk(stream, chunk).await?;
+                if end_of_stream {
+                    return finish(stream).await.map(Some);
+                }
+                Ok(None)
+            }
+            Phase::Idle | Phase::Done(_) => Ok(None),
+        }
+    }
+
+    /// Connect and send the ICAP head and the encapsulated HTTP heads
+    async fn connect(&self, preview: Option<usize>) -> Result<TcpStream, IcapError> {
+        let mut stream = self.open().await?;
+
+        let mut encapsulated = Vec::new();
+        let mut offset = 0;
+        for (name, head) in &self.http_heads {
+            encapsulated.push(format!("{}={}", name, offset));
+            offset += head.len();
+        }
+        let body = match self.mode {
+            IcapMode::Reqmod => "req-body",
+            IcapMode::Respmod => "res-body",
+        };
+        encapsulated.push(format!("{}={}", body, offset));
+
+        let mut head = format!(
+            "{} {} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\n",
+            self.mode.method(),
+            self.url,
+            self.authority()
+        );
+        if let Some(preview) = preview {
+            head.push_str(&format!("Preview: {}\r\n", preview));
+        }
+        head.push_str(&format!(
+            "Encapsulated: {}\r\n\r\n",
+            encapsulated.join(", ")
+        ));
+
+        stream.write_all(head.as_bytes()).await?;
+        for (_, http_head) in &self.http_heads {
+            stream.write_all(http_head).await?;
+        }
+        Ok(stream)
+    }
+
+    /// Preview size advertised by the service, from the cache or a fresh
+    /// OPTIONS request
+    async fn service_preview(&self) -> Result<Option<usize>, IcapError> {
+        if let Some(cached) = SERVICE_OPTIONS.get(&self.url) {
+            if cached.1 > Instant::now() {
+                return Ok(cached.0);
+            }
+        }
+
+        let mut stream = self.open().await?;
+        let request = format!(
+            "OPTIONS {} ICAP/1.0\r\nHost: {}\r\nEncapsulated: null-body=0\r\n\r\n",
+            self.url,
+            self.authority()
+        );
+        stream.write_all(request.as_bytes()).await?;
+        let response = read_response(&mut stream).await?;
+        if response.status != 200 {
+            return Err(IcapError::Status(response.status));
+        }
+
+        let preview = response
+            .header("Preview")
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|size| *size > 0);
+        let ttl = response
+            .header("Options-TTL")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map_or(DEFAULT_OPTIONS_TTL, Duration::from_secs);
+        debug!(
+            service = %self.url,
+            preview = ?preview,
+            ttl_secs = ttl.as_secs(),
+            "Fetched ICAP service options"
+        );
+        SERVICE_OPTIONS.insert(self.url.clone(), (preview, Instant::now() + ttl));
+        Ok(preview)
+    }
+
+    async fn open(&self) -> Result<TcpStream, IcapError> {
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let stream = TcpStream::connect((host, self.port)).await?;
+        stream.set_nodelay(true)?;
+        Ok(stream)
+    }
+
+    fn authority(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Write a body chunk in chunked encoding (nothing for an empty chunk,
+/// which would end the body)
+async fn write_chunk(stream: &mut TcpStream, data: &[u8]) -> Result<(), IcapError> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    stream
+        .write_all(format!("{:x}\r\n", data.len()).as_bytes())
+        .await?;
+    stream.write_all(data).await?;
+    stream.write_all(b"\r\n").await?;
+    Ok(())
+}
+
+/// End the body and wait for the verdict
+async fn finish(stream: &mut TcpStream) -> Result<IcapVerdict, IcapError> {
+    stream.write_all(b"0\r\n\r\n").await?;
+    let response = read_response(stream).await?;
+    if response.status == 100 {
+        return Err(IcapError::Protocol("100 Continue after the whole body"));
+    }
+    verdict(response)
+}
+
+fn verdict(response: IcapResponse) -> Result<IcapVerdict, IcapError> {
+    match response.status {
+        204 => Ok(IcapVerdict::Clean),
+        200 => Ok(IcapVerdict::Blocked {
+            threat: response.threat(),
+        }),
+        status => Err(IcapError::Status(status)),
+    }
+}
+
+/// Read an ICAP response head; an encapsulated message after it is ignored
+async fn read_response(stream: &mut TcpStream) -> Result<IcapResponse, IcapError> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    let end = loop {
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        if head.len() > MAX_RESPONSE_HEAD_BYTES {
+            return Err(IcapError::Protocol("response head too large"));
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Err(IcapError::Protocol("connection closed before the response"));
+        }
+        head.extend_from_slice(&buf[..read]);
+    };
+    parse_response_head(&head[..end])
+}
+
+fn parse_response_head(head: &[u8]) -> Result<IcapResponse, IcapError> {
+    let head = std::str::from_utf8(head).map_err(|_| IcapError::Protocol("head is not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.strip_prefix("ICAP/1.0 "))
+        .and_then(|rest| rest.get(..3))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or(IcapError::Protocol("invalid status line"))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok(IcapResponse { status, headers })
+}
+
+/// HTTP request head encapsulated in ICAP requests
+pub fn request_head(method: &str, uri: &str, headers: &HeaderMap) -> Bytes {
+    encode_head(format!("{} {} HTTP/1.1\r\n", method, uri), headers)
+}
+
+/// HTTP response head encapsulated in RESPMOD requests
+pub fn response_head(status: u16, headers: &HeaderMap) -> Bytes {
+    let reason = http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    encode_head(format!("HTTP/1.1 {} {}\r\n", status, reason), headers)
+}
+
+fn encode_head(start_line: String, headers: &HeaderMap) -> Bytes {
+    let mut head = BytesMut::from(start_line.as_bytes());
+    for (name, value) in headers {
+        head.extend_from_slice(name.as_str().as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+    head.freeze()
+}
+
+/// Body scanned by an `icap` filter, rejected with this
+#[derive(Debug, Clone)]
+pub struct IcapRejection {
+    pub filter_id: String,
+    pub status: u16,
+    /// `infected`, `too_large` or `scan_failed`
+    pub reason: &'static str,
+    pub threat: Option<String>,
+}
+
+impl IcapRejection {
+    /// JSON body of the error response
+    pub fn to_json(&self, request_id: &str) -> String {
+        let message = match self.reason {
+            "infected" => "Request body rejected by antivirus scan",
+            "too_large" => "Request body too large to scan",
+            _ => "Request body could not be scanned",
+        };
+        json!({
+            "error": message,
+            "status": self.status,
+            "threat": self.threat,
+            "request_id": request_id,
+        })
+        .to_string()
+    }
+}
+
+/// A body held back while an `icap` filter scans it
+pub struct IcapBodyScan {
+    filter_id: String,
+    mode: IcapMode,
+    failure_mode: FailureMode,
+    max_body_bytes: usize,
+    status_code: u16,
+    /// `None` once the verdict is in (or scanning was given up)
+    scan: Option<IcapScan>,
+    held: BytesMut,
+    correlation_id: String,
+}
+
+impl IcapBodyScan {
+    /// Scan for the filter's service of `mode`; `None` if the filter has
+    /// none
+    pub fn new(
+        filter_id: &str,
+        filter: &IcapFilter,
+        mode: IcapMode,
+        http_heads: Vec<(&'static str, Bytes)>,
+        correlation_id: String,
+    ) -> Option<Self> {
+        let url = match mode {
+            IcapMode::Reqmod => filter.reqmod.as_deref()?,
+            IcapMode::Respmod => filter.respmod.as_deref()?,
+        };
+        let scan = IcapScan::new(mode, url, filter, http_heads)
+            .inspect_err(|e| warn!(filter_id = %filter_id, error = %e, "Invalid ICAP service"))
+            .ok()?;
+        Some(Self {
+            filter_id: filter_id.to_string(),
+            mode,
+            failure_mode: filter.failure_mode,
+            max_body_bytes: filter.max_body_bytes,
+            status_code: filter.status_code,
+            scan: Some(scan),
+            held: BytesMut::new(),
+            correlation_id,
+        })
+    }
+
+    /// Hold back the chunk and scan it. Held data is released when the
+    /// server finds the body clean, or when it can't be scanned and the
+    /// filter fails open; afterwards chunks pass unchanged.
+    pub async fn process(
+        &mut self,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+    ) -> Result<(), IcapRejection> {
+        let Some(scan) = self.scan.as_mut() else {
+            return Ok(());
+        };
+        let chunk = body.take().unwrap_or_default();
+
+        if self.held.len() + chunk.len() > self.max_body_bytes {
+            self.record("too_large", None);
+            self.held.extend_from_slice(&chunk);
+            return self.fail("too_large", 413, body);
+        }
+        self.held.extend_from_slice(&chunk);
+
+        match scan.send(&chunk, end_of_stream).await {
+            Ok(None) => Ok(()),
+            Ok(Some(IcapVerdict::Clean)) => {
+                let elapsed = scan.elapsed();
+                self.record("clean", elapsed);
+                self.release(body);
+                Ok(())
+            }
+            Ok(Some(IcapVerdict::Blocked { threat })) => {
+                let elapsed = scan.elapsed();
+                self.record("blocked", elapsed);
+                self.scan = None;
+                warn!(
+                    correlation_id = %self.correlation_id,
+                    filter_id = %self.filter_id,
+                    mode = self.mode.as_str(),
+                    threat = threat.as_deref().unwrap_or("unknown"),
+                    "ICAP server blocked body"
+                );
+                Err(IcapRejection {
+                    filter_id: self.filter_id.clone(),
+                    status: self.status_code,
+                    reason: "infected",
+                    threat,
+                })
+            }
+            Err(e) => {
+                let elapsed = scan.elapsed();
+                self.record("error", elapsed);
+                warn!(
+                    correlation_id = %self.correlation_id,
+                    filter_id = %self.filter_id,
+                    mode = self.mode.as_str(),
+                    error = %e,
+                    failure_mode = ?self.failure_mode,
+                    "ICAP scan failed"
+                );
+                self.fail("scan_failed", 503, body)
+            }
+        }
+    }
+
+    /// Give up scanning: release the body when failing open
+    fn fail(
+        &mut self,
+        reason: &'static str,
+        status: u16,
+        body: &mut Option<Bytes>,
+    ) -> Result<(), IcapRejection> {
+        match self.failure_mode {
+            FailureMode::Open => {
+                self.release(body);
+                Ok(())
+            }
+            FailureMode::Closed => {
+                self.scan = None;
+                Err(IcapRejection {
+                    filter_id: self.filter_id.clone(),
+                    status,
+                    reason,
+                    threat: None,
+                })
+            }
+        }
+    }
+
+    fn release(&mut self, body: &mut Option<Bytes>) {
+        self.scan = None;
+        if !self.held.is_empty() {
+            *body = Some(self.held.split().freeze());
+        }
+    }
+
+    fn record(&self, result: &str, elapsed: Option<Duration>) {
+        let labels = [self.filter_id.as_str(), self.mode.as_str(), result];
+        if let Some(counter) = ICAP_SCANS.as_ref() {
+            counter.with_label_values(&labels).inc();
+        }
+        if let (Some(histogram), Some(elapsed)) = (ICAP_SCAN_DURATION.as_ref(), elapsed) {
+            histogram
+                .with_label_values(&labels)
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// ICAP server answering one connection: with `preview_answer` after
+    /// the preview, else with `final_answer` after the whole body. Returns
+    /// the bytes it received.
+    async fn serve_once(
+        preview_answer: Option<&'static str>,
+        final_answer: &'static str,
+    ) -> (String, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("icap://{}/avscan", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            let mut answered_preview = false;
+            loop {
+                let read = stream.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buf[..read]);
+                if !received.ends_with(b"0\r\n\r\n") && !received.ends_with(b"0; ieof\r\n\r\n") {
+                    continue;
+                }
+                match preview_answer {
+                    Some(answer) if !answered_preview => {
+                        answered_preview = true;
+                        stream.write_all(answer.as_bytes()).await.unwrap();
+                        if !answer.contains(" 100 ") {
+                            break;
+                        }
+                    }
+                    _ => {
+                        stream.write_all(final_answer.as_bytes()).await.unwrap();
+                        break;
+                    }
+                }
+            }
+            received
+        });
+        (url, handle)
+    }
+
+    fn filter(preview_bytes: Option<usize>) -> IcapFilter {
+        IcapFilter {
+            preview_bytes,
+            ..Default::default()
+        }
+    }
+
+    fn heads() -> Vec<(&'static str, Bytes)> {
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "app.example.com".parse().unwrap());
+        vec![("req-hdr", request_head("POST", "/upload", &headers))]
+    }
+
+    #[tokio::test]
+    async fn test_clean_after_continue() {
+        let (url, server) = serve_once(
+            Some("ICAP/1.0 100 Continue\r\n\r\n"),
+            "ICAP/1.0 204 No Content\r\n\r\n",
+        )
+        .await;
+        let mut scan = IcapScan::new(IcapMode::Reqmod, &url, &filter(Some(4)), heads()).unwrap();
+        assert_eq!(scan.send(b"abcdef", false).await.unwrap(), None);
+        assert_eq!(
+            scan.send(b"gh", true).await.unwrap(),
+            Some(IcapVerdict::Clean)
+        );
+
+        let received = String::from_utf8(server.await.unwrap()).unwrap();
+        assert!(received.starts_with(&format!("REQMOD {} ICAP/1.0\r\n", url)));
+        assert!(received.contains("Allow: 204\r\nPreview: 4\r\n"));
+        let req_hdr_len = "POST /upload HTTP/1.1\r\nhost: app.example.com\r\n\r\n".len();
+        assert!(received.contains(&format!(
+            "Encapsulated: req-hdr=0, req-body={}\r\n",
+            req_hdr_len
+        )));
+        assert!(received.ends_with("4\r\nabcd\r\n0\r\n\r\n2\r\nef\r\n2\r\ngh\r\n0\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_verdict_after_preview() {
+        let (url, server) = serve_once(
+            Some(
+                "ICAP/1.0 200 OK\r\n\
+                 X-Infection-Found: Type=0; Resolution=2; Threat=Eicar-Test-Signature;\r\n\
+                 Encapsulated: res-hdr=0, res-body=40\r\n\r\n",
+            ),
+            "",
+        )
+        .await;
+        let mut scan = IcapScan::new(IcapMode::Reqmod, &url, &filter(Some(4)), heads()).unwrap();
+        assert_eq!(
+            scan.send(b"X5O!P%@AP", false).await.unwrap(),
+            Some(IcapVerdict::Blocked {
+                threat: Some("Eicar-Test-Signature".to_string())
+            })
+        );
+        // The rest of the body is no longer sent
+        assert!(scan.send(b"more", true).await.unwrap().is_some());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_small_body_ends_in_preview() {
+        let (url, server) = serve_once(None, "ICAP/1.0 204 No Content\r\n\r\n").await;
+        let mut scan = IcapScan::new(IcapMode::Reqmod, &url, &filter(Some(1024)), heads()).unwrap();
+        assert_eq!(
+            scan.send(b"hello", true).await.unwrap(),
+            Some(IcapVerdict::Clean)
+        );
+        let received = server.await.unwrap();
+        assert!(received.ends_with(b"5\r\nhello\r\n0; ieof\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_failure_modes() {
+        // Nothing listens on a port that was just released
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("icap://{}/avscan", listener.local_addr().unwrap());
+        drop(listener);
+
+        let mut closed = IcapFilter {
+            reqmod: Some(url.clone()),
+            preview_bytes: Some(4),
+            ..Default::default()
+        };
+        let mut scan =
+            IcapBodyScan::new("av", &closed, IcapMode::Reqmod, heads(), "req-1".into()).unwrap();
+        let mut body = Some(Bytes::from_static(b"payload"));
+        let rejection = scan.process(&mut body, true).await.unwrap_err();
+        assert_eq!((rejection.status, rejection.reason), (503, "scan_failed"));
+
+        closed.failure_mode = FailureMode::Open;
+        let mut scan =
+            IcapBodyScan::new("av", &closed, IcapMode::Reqmod, heads(), "req-2".into()).unwrap();
+        let mut body = Some(Bytes::from_static(b"payload"));
+        scan.process(&mut body, true).await.unwrap();
+        assert_eq!(body.as_deref(), Some(&b"payload"[..]));
+    }
+
+    #[test]
+    fn test_parse_response_head() {
+        let response = parse_response_head(
+            b"ICAP/1.0 200 OK\r\nX-Virus-ID: Win.Test.EICAR_HDB-1\r\nEncapsulated: res-hdr=0",
+        )
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.threat().as_deref(), Some("Win.Test.EICAR_HDB-1"));
+        assert!(parse_response_head(b"HTTP/1.1 200 OK").is_err());
+    }
+}
@@ -148,6 +148,7 @@ mod tests {
             default_input_cost: 1.0,
             default_output_cost: 2.0,
             currency: "USD".to_string(),
+            budget: None,
         }
     }
 
@@ -0,0 +1,343 @@
+//! Monthly cost accounting for inference routes.
+//!
+//! Every priced request is counted in `zentinel_inference_cost_dollars_total`
+//! by route, model, tenant and API key. Routes with a cost budget also keep
+//! the spend of the current calendar month (UTC) per consumer, the whole
+//! route, a tenant or an API key depending on the budget's scope, fire alerts
+//! as thresholds are crossed, and block or downgrade requests once the limit
+//! is spent.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use dashmap::DashMap;
+use prometheus::{register_counter_vec, register_gauge_vec, CounterVec, GaugeVec};
+use std::sync::LazyLock;
+
+use zentinel_common::budget::{CostBudgetAction, CostBudgetConfig, CostBudgetScope, CostResult};
+
+/// Consumer name for requests without a tenant or API key
+const ANONYMOUS: &str = "anonymous";
+
+/// Spend by route, model, tenant and API key
+static COST_TOTAL: LazyLock<Option<CounterVec>> = LazyLock::new(|| {
+    register_counter_vec!(
+        "zentinel_inference_cost_dollars_total",
+        "Cost of inference requests in the attribution currency",
+        &["route", "model", "tenant", "api_key", "currency"]
+    )
+    .ok()
+});
+
+/// Month-to-date spend per budget consumer
+static BUDGET_SPENT: LazyLock<Option<GaugeVec>> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "zentinel_inference_cost_budget_spent_dollars",
+        "Month-to-date spend counted against inference cost budgets",
+        &["route", "consumer"]
+    )
+    .ok()
+});
+
+/// Alerts per route and threshold
+static BUDGET_ALERTS: LazyLock<Option<CounterVec>> = LazyLock::new(|| {
+    register_counter_vec!(
+        "zentinel_inference_cost_budget_alerts_total",
+        "Inference cost budget alert thresholds crossed",
+        &["route", "threshold"]
+    )
+    .ok()
+});
+
+/// Requests blocked or downgraded per route and action
+static BUDGET_ENFORCED: LazyLock<Option<CounterVec>> = LazyLock::new(|| {
+    register_counter_vec!(
+        "zentinel_inference_cost_budget_enforced_total",
+        "Requests blocked or downgraded by an exhausted inference cost budget",
+        &["route", "action"]
+    )
+    .ok()
+});
+
+/// Count a priced request in the cost metrics
+pub fn record_cost_metrics(
+    route_id: &str,
+    cost: &CostResult,
+    tenant: Option<&str>,
+    api_key: Option<&str>,
+) {
+    if let Some(counter) = COST_TOTAL.as_ref() {
+        counter
+            .with_label_values(&[
+                route_id,
+                cost.model.as_str(),
+                tenant.unwrap_or("-"),
+                api_key.unwrap_or("-"),
+                cost.currency.as_str(),
+            ])
+            .inc_by(cost.total_cost);
+    }
+}
+
+/// What to do with a request, given its consumer's budget
+#[derive(Debug, Clone, PartialEq)]
+pub enum CostBudgetDecision {
+    /// Budget left, send the request as is
+    Allow,
+    /// Budget spent, reject the request
+    Block {
+        /// Seconds until the month ends
+        retry_after_secs: u64,
+    },
+    /// Budget spent, send the request to a cheaper model
+    Downgrade {
+        /// Model to use instead
+        model: String,
+    },
+}
+
+/// Alert fired when a consumer's spend crosses a threshold
+#[derive(Debug, Clone)]
+pub struct CostBudgetAlert {
+    /// Consumer whose spend crossed the threshold
+    pub consumer: String,
+    /// Threshold that was crossed (e.g., 0.80)
+    pub threshold: f64,
+    /// Month-to-date spend
+    pub spent: f64,
+    /// Monthly limit
+    pub limit: f64,
+}
+
+/// Month-to-date spend of one consumer
+#[derive(Debug, Clone, Copy)]
+struct ConsumerSpend {
+    /// Months since year 0, identifies the period
+    month: i32,
+    amount: f64,
+    /// Number of (sorted) thresholds already alerted this month
+    alerted: usize,
+}
+
+/// Monthly spend per consumer of a route with a cost budget
+pub struct CostLedger {
+    route_id: String,
+    budget: CostBudgetConfig,
+    /// Alert thresholds, ascending
+    thresholds: Vec<f64>,
+    spend: DashMap<String, ConsumerSpend>,
+}
+
+impl CostLedger {
+    /// Create a ledger enforcing `budget` on a route
+    pub fn new(route_id: impl Into<String>, budget: CostBudgetConfig) -> Self {
+        let mut thresholds = budget.alert_thresholds.clone();
+        thresholds.sort_by(f64::total_cmp);
+        Self {
+            route_id: route_id.into(),
+            budget,
+            thresholds,
+            spend: DashMap::new(),
+        }
+    }
+
+    /// The consumer a request is counted under, by the budget's scope
+    pub fn consumer(&self, tenant: Option<&str>, api_key: Option<&str>) -> String {
+        match self.budget.scope {
+            CostBudgetScope::Route => self.route_id.clone(),
+            CostBudgetScope::Tenant => tenant.unwrap_or(ANONYMOUS).to_string(),
+            CostBudgetScope::ApiKey => api_key.unwrap_or(ANONYMOUS).to_string(),
+        }
+    }
+
+    /// Decide what to do with a consumer's next request
+    pub fn check(&self, consumer: &str) -> CostBudgetDecision {
+        self.check_at(consumer, Utc::now())
+    }
+
+    fn check_at(&self, consumer: &str, now: DateTime<Utc>) -> CostBudgetDecision {
+        if self.spent_at(consumer, now) < self.budget.monthly_limit {
+            return CostBudgetDecision::Allow;
+        }
+
+        let decision = match &self.budget.action {
+            CostBudgetAction::Block => CostBudgetDecision::Block {
+                retry_after_secs: secs_until_next_month(now),
+            },
+            CostBudgetAction::Downgrade { model } => CostBudgetDecision::Downgrade {
+                model: model.clone(),
+            },
+        };
+        if let Some(counter) = BUDGET_ENFORCED.as_ref() {
+            let action = match decision {
+                CostBudgetDecision::Downgrade { .. } => "downgrade",
+                _ => "block",
+            };
+            counter
+                .with_label_values(&[self.route_id.as_str(), action])
+                .inc();
+        }
+        decision
+    }
+
+    /// Add a request's cost to the consumer's spend, returning the alerts
+    /// whose thresholds it crossed
+    pub fn record(&self, consumer: &str, cost: f64) -> Vec<CostBudgetAlert> {
+        self.record_at(consumer, cost, Utc::now())
+    }
+
+    fn record_at(&self, consumer: &str, cost: f64, now: DateTime<Utc>) -> Vec<CostBudgetAlert> {
+        let month = month_index(now);
+        let mut entry = self
+            .spend
+            .entry(consumer.to_string())
+            .or_insert(ConsumerSpend {
+                month,
+                amount: 0.0,
+                alerted: 0,
+            });
+        if entry.month != month {
+            *entry = ConsumerSpend {
+                month,
+                amount: 0.0,
+                alerted: 0,
+            };
+        }
+        entry.amount += cost;
+
+        let limit = self.budget.monthly_limit;
+        let mut alerts = Vec::new();
+        while let Some(&threshold) = self.thresholds.get(entry.alerted) {
+            if entry.amount < threshold * limit {
+                break;
+            }
+            entry.alerted += 1;
+            alerts.push(CostBudgetAlert {
+                consumer: consumer.to_string(),
+                threshold,
+                spent: entry.amount,
+                limit,
+            });
+            if let Some(counter) = BUDGET_ALERTS.as_ref() {
+                counter
+                    .with_label_values(&[self.route_id.as_str(), threshold.to_string().as_str()])
+                    .inc();
+            }
+        }
+
+        if let Some(gauge) = BUDGET_SPENT.as_ref() {
+            gauge
+                .with_label_values(&[self.route_id.as_str(), consumer])
+                .set(entry.amount);
+        }
+        alerts
+    }
+
+    /// Month-to-date spend of a consumer
+    pub fn spent(&self, consumer: &str) -> f64 {
+        self.spent_at(consumer, Utc::now())
+    }
+
+    fn spent_at(&self, consumer: &str, now: DateTime<Utc>) -> f64 {
+        self.spend
+            .get(consumer)
+            .filter(|s| s.month == month_index(now))
+            .map_or(0.0, |s| s.amount)
+    }
+}
+
+/// Months since year 0 of a timestamp
+fn month_index(now: DateTime<Utc>) -> i32 {
+    now.year() * 12 + now.month0() as i32
+}
+
+/// Seconds from `now` to the first of next month (UTC)
+fn secs_until_next_month(now: DateTime<Utc>) -> u64 {
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|start| (start.and_utc() - now).num_seconds().max(1) as u64)
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn budget(scope: CostBudgetScope, action: CostBudgetAction) -> CostBudgetConfig {
+        CostBudgetConfig {
+            monthly_limit: 10.0,
+            scope,
+            action,
+            alert_thresholds: vec![0.9, 0.5],
+        }
+    }
+
+    #[test]
+    fn test_spend_alerts_and_block_until_next_month() {
+        let ledger = CostLedger::new(
+            "chat",
+            budget(CostBudgetScope::Tenant, CostBudgetAction::Block),
+        );
+        let now = Utc.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap();
+        let consumer = ledger.consumer(Some("acme"), Some("key-1"));
+        assert_eq!(consumer, "acme");
+        assert_eq!(ledger.consumer(None, Some("key-1")), "anonymous");
+
+        assert!(ledger.record_at(&consumer, 4.0, now).is_empty());
+        let alerts = ledger.record_at(&consumer, 6.0, now);
+        let thresholds: Vec<f64> = alerts.iter().map(|a| a.threshold).collect();
+        assert_eq!(thresholds, vec![0.5, 0.9]);
+        assert_eq!(alerts[1].spent, 10.0);
+
+        assert_eq!(
+            ledger.check_at(&consumer, now),
+            CostBudgetDecision::Block {
+                retry_after_secs: 3600
+            }
+        );
+        assert_eq!(ledger.check_at("other", now), CostBudgetDecision::Allow);
+
+        // A new month starts from zero
+        let next_month = Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 1).unwrap();
+        assert_eq!(
+            ledger.check_at(&consumer, next_month),
+            CostBudgetDecision::Allow
+        );
+        assert_eq!(ledger.record_at(&consumer, 1.0, next_month).len(), 0);
+        assert_eq!(ledger.spent_at(&consumer, next_month), 1.0);
+    }
+
+    #[test]
+    fn test_downgrade_and_route_scope() {
+        let ledger = CostLedger::new(
+            "chat",
+            budget(
+                CostBudgetScope::Route,
+                CostBudgetAction::Downgrade {
+                    model: "gpt-4o-mini".to_string(),
+                },
+            ),
+        );
+        let consumer = ledger.consumer(Some("acme"), None);
+        assert_eq!(consumer, "chat");
+
+        ledger.record(&consumer, 12.5);
+        assert_eq!(ledger.spent(&consumer), 12.5);
+        assert_eq!(
+            ledger.check(&consumer),
+            CostBudgetDecision::Downgrade {
+                model: "gpt-4o-mini".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_secs_until_next_month() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 28, 0, 0, 0).unwrap();
+        assert_eq!(secs_until_next_month(now), 86_400);
+    }
+}
@@ -0,0 +1,453 @@
+//! Inference key pools
+//!
+//! A key pool spreads an inference route's traffic across several provider
+//! endpoints and API keys. Each attempt goes through one member, picked by
+//! weight among the members that are not cooling down and still have
+//! per-minute budget left. A response with a failover status (429 and 5xx by
+//! default) puts the member into cooldown, for the response's Retry-After or
+//! the pool's cooldown, and the request is retried on another member.
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use rand::RngExt;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use zentinel_config::{InferenceConfig, InferenceKeyPoolMember, InferenceProvider};
+
+/// Length of the requests-per-minute and tokens-per-minute windows
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// Attempts per route and member
+static KEY_POOL_REQUESTS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_inference_key_pool_requests_total",
+        "Upstream attempts sent through inference key pool members",
+        &["route", "member"]
+    )
+    .ok()
+});
+
+/// Failover responses per route, member and status
+static KEY_POOL_FAILOVERS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_inference_key_pool_failovers_total",
+        "Responses that put an inference key pool member into cooldown",
+        &["route", "member", "status"]
+    )
+    .ok()
+});
+
+/// Requests rejected because no member was available
+static KEY_POOL_EXHAUSTED: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_inference_key_pool_exhausted_total",
+        "Requests rejected because every inference key pool member was unavailable",
+        &["route"]
+    )
+    .ok()
+});
+
+/// Usage in the current minute and cooldown of a member
+#[derive(Debug, Clone)]
+struct MemberState {
+    window_start: Instant,
+    requests: u64,
+    tokens: u64,
+    cooldown_until: Option<Instant>,
+}
+
+impl MemberState {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            requests: 0,
+            tokens: 0,
+            cooldown_until: None,
+        }
+    }
+
+    /// Start a new window once the current one has passed
+    fn roll(&mut self, now: Instant) {
+        if now.duration_since(self.window_start) >= BUDGET_WINDOW {
+            self.window_start = now;
+            self.requests = 0;
+            self.tokens = 0;
+        }
+    }
+}
+
+/// A provider endpoint and API key in a key pool
+#[derive(Debug)]
+pub struct KeyPoolMember {
+    config: InferenceKeyPoolMember,
+    provider: InferenceProvider,
+    state: Mutex<MemberState>,
+}
+
+impl KeyPoolMember {
+    fn new(config: InferenceKeyPoolMember, default_provider: InferenceProvider) -> Self {
+        let provider = config.provider.unwrap_or(default_provider);
+        Self {
+            config,
+            provider,
+            state: Mutex::new(MemberState::new(Instant::now())),
+        }
+    }
+
+    /// Member ID
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    /// Upstream the member's requests are sent to
+    pub fn upstream(&self) -> &str {
+        &self.config.upstream
+    }
+
+    /// Header carrying the member's key, in the form its provider expects
+    pub fn credential_header(&self) -> (&'static str, String) {
+        match self.provider {
+            InferenceProvider::Anthropic => ("x-api-key", self.config.key.clone()),
+            InferenceProvider::OpenAi | InferenceProvider::Generic => {
+                ("Authorization", format!("Bearer {}", self.config.key))
+            }
+        }
+    }
+
+    /// Count tokens consumed by a request against the member's budget
+    pub fn record_tokens(&self, tokens: u64) {
+        let mut state = self.state.lock();
+        state.roll(Instant::now());
+        state.tokens += tokens;
+    }
+
+    /// Time until the member can take a request, zero if it can now
+    fn wait_time(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock();
+        state.roll(now);
+
+        let cooldown = state
+            .cooldown_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+        let over_budget = self
+            .config
+            .requests_per_minute
+            .is_some_and(|limit| state.requests >= limit)
+            || self
+                .config
+                .tokens_per_minute
+                .is_some_and(|limit| state.tokens >= limit);
+        let window_left = if over_budget {
+            (state.window_start + BUDGET_WINDOW).saturating_duration_since(now)
+        } else {
+            Duration::ZERO
+        };
+
+        cooldown.max(window_left)
+    }
+}
+
+/// A route's key pool
+#[derive(Debug)]
+pub struct KeyPool {
+    route_id: String,
+    members: Vec<Arc<KeyPoolMember>>,
+    cooldown: Duration,
+    failover_status: Vec<u16>,
+    max_attempts: usize,
+}
+
+impl KeyPool {
+    /// Build the pool of an inference block, keeping the usage and cooldowns
+    /// of members that were in `previous`
+    fn new(route_id: &str, config: &InferenceConfig, previous: Option<&KeyPool>) -> Option<Self> {
+        let pool = config.key_pool.as_ref()?;
+        let members = pool
+            .members
+            .iter()
+            .map(|m| {
+                let member = KeyPoolMember::new(m.clone(), config.provider);
+                if let Some(old) = previous.and_then(|p| p.member(&m.id)) {
+                    *member.state.lock() = old.state.lock().clone();
+                }
+                Arc::new(member)
+            })
+            .collect();
+
+        Some(Self {
+            route_id: route_id.to_string(),
+            members,
+            cooldown: Duration::from_secs(pool.cooldown_secs),
+            failover_status: pool.failover_status.clone(),
+            max_attempts: pool.max_attempts as usize,
+        })
+    }
+
+    /// Look up a member by ID
+    pub fn member(&self, id: &str) -> Option<&Arc<KeyPoolMember>> {
+        self.members.iter().find(|m| m.id() == id)
+    }
+
+    /// Pick a member by weight, skipping the `tried` members and those
+    /// cooling down or over budget, and count the attempt against it
+    pub fn select(&self, tried: &[String]) -> Option<Arc<KeyPoolMember>> {
+        let now = Instant::now();
+        let candidates: Vec<&Arc<KeyPoolMember>> = self
+            .members
+            .iter()
+            .filter(|m| !tried.iter().any(|t| t == m.id()))
+            .filter(|m| m.wait_time(now).is_zero())
+            .collect();
+
+        let total_weight: u64 = candidates.iter().map(|m| m.config.weight as u64).sum();
+        if total_weight == 0 {
+            if let Some(counter) = KEY_POOL_EXHAUSTED.as_ref() {
+                counter.with_label_values(&[self.route_id.as_str()]).inc();
+            }
+            return None;
+        }
+
+        let mut threshold = rand::rng().random_range(0..total_weight);
+        let member = candidates
+            .into_iter()
+            .find(|m| {
+                let weight = m.config.weight as u64;
+                if threshold < weight {
+                    true
+                } else {
+                    threshold -= weight;
+                    false
+                }
+            })?
+            .clone();
+
+        member.state.lock().requests += 1;
+        if let Some(counter) = KEY_POOL_REQUESTS.as_ref() {
+            counter
+                .with_label_values(&[self.route_id.as_str(), member.id()])
+                .inc();
+        }
+        Some(member)
+    }
+
+    /// Whether an upstream status puts the member into cooldown
+    pub fn is_failover_status(&self, status: u16) -> bool {
+        self.failover_status.contains(&status)
+    }
+
+    /// Put a member into cooldown after a failover status, for `retry_after`
+    /// when the provider sent one and the pool's cooldown otherwise
+    pub fn cool_down(&self, member: &KeyPoolMember, status: u16, retry_after: Option<Duration>) {
+        let cooldown = retry_after.unwrap_or(self.cooldown);
+        member.state.lock().cooldown_until = Some(Instant::now() + cooldown);
+
+        warn!(
+            route_id = %self.route_id,
+            member = %member.id(),
+            status = status,
+            cooldown_secs = cooldown.as_secs(),
+            "Inference key pool member cooling down"
+        );
+        if let Some(counter) = KEY_POOL_FAILOVERS.as_ref() {
+            counter
+                .with_label_values(&[
+                    self.route_id.as_str(),
+                    member.id(),
+                    status.to_string().as_str(),
+                ])
+                .inc();
+        }
+    }
+
+    /// Whether a request that already tried `tried` may fail over to
+    /// another member that is available now
+    pub fn can_fail_over(&self, tried: &[String]) -> bool {
+        let now = Instant::now();
+        tried.len() < self.max_attempts
+            && self
+                .members
+                .iter()
+                .any(|m| !tried.iter().any(|t| t == m.id()) && m.wait_time(now).is_zero())
+    }
+
+    /// Seconds until the first member can take a request again (at least 1)
+    pub fn retry_after_secs(&self) -> u64 {
+        let now = Instant::now();
+        self.members
+            .iter()
+            .map(|m| m.wait_time(now))
+            .min()
+            .unwrap_or(self.cooldown)
+            .as_secs()
+            .max(1)
+    }
+}
+
+/// Key pools of inference routes, by route ID
+pub struct KeyPoolManager {
+    pools: DashMap<String, Arc<KeyPool>>,
+}
+
+impl KeyPoolManager {
+    /// Create a manager without pools
+    pub fn new() -> Self {
+        Self {
+            pools: DashMap::new(),
+        }
+    }
+
+    /// Replace the pools with those of `routes` (inference blocks by route
+    /// ID); members that are kept keep their usage and cooldowns
+    pub fn reload(&self, routes: &HashMap<String, InferenceConfig>) {
+        self.pools
+            .retain(|route_id, _| routes.contains_key(route_id));
+
+        for (route_id, config) in routes {
+            let previous = self.pools.get(route_id).map(|p| Arc::clone(&p));
+            match KeyPool::new(route_id, config, previous.as_deref()) {
+                Some(pool) => {
+                    info!(
+                        route_id = %route_id,
+                        members = pool.members.len(),
+                        "Inference key pool configured"
+                    );
+                    self.pools.insert(route_id.clone(), Arc::new(pool));
+                }
+                None => {
+                    self.pools.remove(route_id);
+                }
+            }
+        }
+    }
+
+    /// Key pool of a route
+    pub fn get(&self, route_id: &str) -> Option<Arc<KeyPool>> {
+        self.pools.get(route_id).map(|p| Arc::clone(&p))
+    }
+}
+
+impl Default for KeyPoolManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_config::InferenceKeyPoolConfig;
+
+    fn member(id: &str, weight: u32, rpm: Option<u64>) -> InferenceKeyPoolMember {
+        InferenceKeyPoolMember {
+            id: id.to_string(),
+            upstream: format!("{id}-upstream"),
+            key: format!("sk-{id}"),
+            provider: None,
+            weight,
+            requests_per_minute: rpm,
+            tokens_per_minute: None,
+        }
+    }
+
+    fn inference(members: Vec<InferenceKeyPoolMember>) -> InferenceConfig {
+        InferenceConfig {
+            provider: InferenceProvider::OpenAi,
+            key_pool: Some(InferenceKeyPoolConfig {
+                members,
+                cooldown_secs: 60,
+                failover_status: vec![429, 503],
+                max_attempts: 2,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn pool_manager(members: Vec<InferenceKeyPoolMember>) -> KeyPoolManager {
+        let manager = KeyPoolManager::new();
+        manager.reload(&HashMap::from([("chat".to_string(), inference(members))]));
+        manager
+    }
+
+    #[test]
+    fn test_selection_skips_tried_and_exhausted_members() {
+        let manager = pool_manager(vec![member("a", 1, Some(1)), member("b", 1, None)]);
+        let pool = manager.get("chat").unwrap();
+
+        // "b" is excluded, so the first request uses "a" and spends its budget
+        let first = pool.select(&["b".to_string()]).unwrap();
+        assert_eq!(first.id(), "a");
+        assert_eq!(first.upstream(), "a-upstream");
+        assert!(pool.select(&["b".to_string()]).is_none());
+
+        for _ in 0..10 {
+            assert_eq!(pool.select(&[]).unwrap().id(), "b");
+        }
+    }
+
+    #[test]
+    fn test_cooldown_and_failover() {
+        let manager = pool_manager(vec![member("a", 1, None), member("b", 1, None)]);
+        let pool = manager.get("chat").unwrap();
+        assert!(pool.is_failover_status(429));
+        assert!(!pool.is_failover_status(500));
+
+        let a = Arc::clone(pool.member("a").unwrap());
+        pool.cool_down(&a, 429, Some(Duration::from_secs(30)));
+        assert!(pool.can_fail_over(&["a".to_string()]));
+        for _ in 0..10 {
+            assert_eq!(pool.select(&[]).unwrap().id(), "b");
+        }
+
+        let b = Arc::clone(pool.member("b").unwrap());
+        pool.cool_down(&b, 503, None);
+        assert!(pool.select(&[]).is_none());
+        assert!(!pool.can_fail_over(&["a".to_string()]));
+        let retry_after = pool.retry_after_secs();
+        assert!((29..=30).contains(&retry_after), "{retry_after}");
+
+        // max-attempts 2 stops failover even with members left
+        let manager = pool_manager(vec![
+            member("a", 1, None),
+            member("b", 1, None),
+            member("c", 1, None),
+        ]);
+        let pool = manager.get("chat").unwrap();
+        assert!(!pool.can_fail_over(&["a".to_string(), "b".to_string()]));
+    }
+
+    #[test]
+    fn test_credentials_and_reload_keep_state() {
+        let mut claude = member("claude", 1, None);
+        claude.provider = Some(InferenceProvider::Anthropic);
+        let manager = pool_manager(vec![member("a", 1, None), claude.clone()]);
+        let pool = manager.get("chat").unwrap();
+
+        assert_eq!(
+            pool.member("a").unwrap().credential_header(),
+            ("Authorization", "Bearer sk-a".to_string())
+        );
+        assert_eq!(
+            pool.member("claude").unwrap().credential_header(),
+            ("x-api-key", "sk-claude".to_string())
+        );
+
+        let a = Arc::clone(pool.member("a").unwrap());
+        pool.cool_down(&a, 429, None);
+
+        // "a" keeps its cooldown across a reload
+        manager.reload(&HashMap::from([(
+            "chat".to_string(),
+            inference(vec![member("a", 1, None), claude]),
+        )]));
+        let pool = manager.get("chat").unwrap();
+        assert_eq!(pool.select(&[]).unwrap().id(), "claude");
+
+        manager.reload(&HashMap::new());
+        assert!(manager.get("chat").is_none());
+    }
+}
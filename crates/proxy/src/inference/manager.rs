@@ -13,6 +13,7 @@ use zentinel_config::{InferenceConfig, TokenEstimation};
 
 use super::budget::TokenBudgetTracker;
 use super::cost::CostCalculator;
+use super::cost_ledger::{CostBudgetAlert, CostBudgetDecision, CostLedger};
 use super::providers::create_provider;
 use super::rate_limit::{TokenRateLimitResult, TokenRateLimiter};
 use super::tokens::{TokenCounter, TokenEstimate, TokenSource};
@@ -25,6 +26,8 @@ struct RouteInferenceState {
     budget_tracker: Option<TokenBudgetTracker>,
     /// Cost calculator
     cost_calculator: Option<CostCalculator>,
+    /// Monthly cost budget ledger
+    cost_ledger: Option<CostLedger>,
     /// Token counter (for estimation and actual counting)
     token_counter: TokenCounter,
     /// Route ID for logging
@@ -99,12 +102,29 @@ impl InferenceRateLimitManager {
             CostCalculator::new(cost.clone(), route_id)
         });
 
+        // Create cost ledger if a monthly cost budget is configured
+        let cost_ledger = config
+            .cost_attribution
+            .as_ref()
+            .filter(|cost| cost.enabled)
+            .and_then(|cost| cost.budget.clone())
+            .map(|budget| {
+                info!(
+                    route_id = route_id,
+                    monthly_limit = budget.monthly_limit,
+                    scope = budget.scope.as_str(),
+                    "Registered cost budget"
+                );
+                CostLedger::new(route_id, budget)
+            });
+
         // Only register if at least one feature is enabled
         if rate_limiter.is_some() || budget_tracker.is_some() || cost_calculator.is_some() {
             let state = RouteInferenceState {
                 rate_limiter,
                 budget_tracker,
                 cost_calculator,
+                cost_ledger,
                 token_counter,
                 route_id: route_id.to_string(),
             };
@@ -248,6 +268,46 @@ impl InferenceRateLimitManager {
         Some(cost_calculator.calculate(model, input_tokens, output_tokens))
     }
 
+    /// Consumer a request is counted under by the route's cost budget.
+    ///
+    /// Returns None if the route has no cost budget.
+    pub fn cost_budget_consumer(
+        &self,
+        route_id: &str,
+        tenant: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Option<String> {
+        let state = self.routes.get(route_id)?;
+        let ledger = state.cost_ledger.as_ref()?;
+        Some(ledger.consumer(tenant, api_key))
+    }
+
+    /// Check a consumer's monthly cost budget.
+    ///
+    /// Returns None if the route has no cost budget.
+    pub fn check_cost_budget(&self, route_id: &str, consumer: &str) -> Option<CostBudgetDecision> {
+        let state = self.routes.get(route_id)?;
+        let ledger = state.cost_ledger.as_ref()?;
+        Some(ledger.check(consumer))
+    }
+
+    /// Record a request's cost against a consumer's monthly budget.
+    ///
+    /// Returns any budget alerts that were triggered.
+    pub fn record_cost(
+        &self,
+        route_id: &str,
+        consumer: &str,
+        cost: &CostResult,
+    ) -> Vec<CostBudgetAlert> {
+        if let Some(state) = self.routes.get(route_id) {
+            if let Some(ref ledger) = state.cost_ledger {
+                return ledger.record(consumer, cost.total_cost);
+            }
+        }
+        Vec::new()
+    }
+
     /// Record actual token usage from response.
     ///
     /// This adjusts the rate limiter based on actual vs estimated usage.
@@ -394,6 +454,7 @@ mod tests {
             routing: None,
             model_routing: None,
             guardrails: None,
+            key_pool: None,
         }
     }
 
@@ -436,6 +497,7 @@ mod tests {
             routing: None,
             model_routing: None,
             guardrails: None,
+            key_pool: None,
         };
         manager.register_route("no-limit-route", &config);
 
@@ -465,6 +527,7 @@ mod tests {
             routing: None,
             model_routing: None,
             guardrails: None,
+            key_pool: None,
         };
         manager.register_route("budget-route", &config);
 
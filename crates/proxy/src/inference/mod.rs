@@ -3,9 +3,12 @@
 //! This module provides:
 //! - Token-based rate limiting (tokens/minute instead of requests/second)
 //! - Token budget tracking (cumulative usage per period)
-//! - Cost attribution (per-model pricing)
+//! - Cost attribution (per-model pricing) with monthly cost budgets
 //! - Multi-provider token counting (OpenAI, Anthropic, generic)
 //! - Model-aware load balancing (LeastTokensQueued strategy)
+//! - Key pools spreading traffic across provider API keys with failover
+//! - Prompt embeddings attached to guardrail events for similarity checks
+//! - Server-side system prompts and allowed prompt templates
 //!
 //! # Example Usage
 //!
@@ -40,9 +43,13 @@
 
 mod budget;
 mod cost;
+mod cost_ledger;
+mod embeddings;
 mod guardrails;
+mod key_pool;
 mod manager;
 mod metrics;
+mod prompt_template;
 mod providers;
 mod rate_limit;
 mod streaming;
@@ -51,11 +58,18 @@ mod tokens;
 
 pub use budget::TokenBudgetTracker;
 pub use cost::CostCalculator;
+pub use cost_ledger::{record_cost_metrics, CostBudgetAlert, CostBudgetDecision, CostLedger};
+pub use embeddings::{EmbeddingClient, EmbeddingError};
 pub use guardrails::{
     extract_inference_content, GuardrailProcessor, PiiCheckResult, PromptInjectionResult,
 };
+pub use key_pool::{KeyPool, KeyPoolManager, KeyPoolMember};
 pub use manager::{InferenceCheckResult, InferenceRateLimitManager, InferenceRouteStats};
 pub use metrics::InferenceMetrics;
+pub use prompt_template::{
+    PromptRejection, PromptTemplateCheck, PromptViolation, PromptViolationKind,
+    RequestPromptEnforcement,
+};
 pub use providers::{create_provider, InferenceProviderAdapter};
 pub use rate_limit::{TokenRateLimitResult, TokenRateLimiter};
 pub use streaming::{
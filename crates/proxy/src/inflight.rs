@@ -0,0 +1,433 @@
+//! Introspection of in-flight requests and open connections.
+//!
+//! Every proxied request is registered from `request_filter` until it is
+//! logged; TCP stream proxy connections are registered while they relay.
+//! The `requests` admin handler lists in-flight requests with the phase
+//! they are in, and cancels one by correlation ID; the `connections` admin
+//! handler lists client connections with a request in flight and stream
+//! connections.
+//!
+//! A request moves through these phases:
+//!
+//! - `processing`: limits, filters and routing
+//! - `awaiting-agent`: waiting for agent decisions on the request headers
+//! - `upstream`: connecting to the upstream or waiting for its response
+//! - `downstream`: sending the response to the client
+//!
+//! Cancelling is cooperative: a request waiting for agents is answered
+//! right away, otherwise it fails at its next phase change or body chunk.
+//! A request waiting for upstream response headers therefore ends when the
+//! first response bytes or the upstream read timeout arrive.
+//!
+//! Like the chaos manager the registry is process-wide.
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use pingora_core::{Error, ErrorType};
+use prometheus::{register_int_counter, IntCounter};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+use thiserror::Error;
+use tokio::sync::Notify;
+
+static REGISTRY: LazyLock<InFlightRegistry> = LazyLock::new(InFlightRegistry::default);
+
+/// Requests cancelled through the admin API
+static CANCELLATIONS: LazyLock<Option<IntCounter>> = LazyLock::new(|| {
+    register_int_counter!(
+        "zentinel_inflight_cancellations_total",
+        "In-flight requests cancelled through the admin API"
+    )
+    .ok()
+});
+
+/// The process-wide registry
+pub fn registry() -> &'static InFlightRegistry {
+    &REGISTRY
+}
+
+/// Phase of an in-flight request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequestPhase {
+    Processing,
+    AwaitingAgent,
+    Upstream,
+    Downstream,
+}
+
+impl RequestPhase {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => RequestPhase::AwaitingAgent,
+            2 => RequestPhase::Upstream,
+            3 => RequestPhase::Downstream,
+            _ => RequestPhase::Processing,
+        }
+    }
+}
+
+/// Error of an admin cancel action
+#[derive(Debug, Error, PartialEq)]
+pub enum InFlightError {
+    #[error("No in-flight request with correlation ID '{0}'")]
+    UnknownRequest(String),
+}
+
+/// Error ending a cancelled request; it is answered with a 503
+pub fn cancelled_error() -> Box<Error> {
+    Error::explain(
+        ErrorType::HTTPStatus(503),
+        "Request cancelled by an administrator",
+    )
+}
+
+/// An in-flight request
+pub struct InFlightRequest {
+    correlation_id: String,
+    method: String,
+    path: String,
+    /// Client address including the port (identifies the connection)
+    client: String,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+    route: Mutex<Option<String>>,
+    upstream: Mutex<Option<String>>,
+    phase: AtomicU8,
+    cancelled: AtomicBool,
+    cancel: Notify,
+}
+
+impl InFlightRequest {
+    /// Enter a phase
+    pub fn set_phase(&self, phase: RequestPhase) {
+        self.phase.store(phase as u8, Ordering::Relaxed);
+    }
+
+    pub fn phase(&self) -> RequestPhase {
+        RequestPhase::from_u8(self.phase.load(Ordering::Relaxed))
+    }
+
+    /// Record the matched route and selected upstream
+    pub fn set_target(&self, route: Option<&str>, upstream: Option<&str>) {
+        *self.route.lock() = route.map(str::to_string);
+        *self.upstream.lock() = upstream.map(str::to_string);
+    }
+
+    /// Whether an admin cancelled the request
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Completes once the request is cancelled
+    pub async fn cancelled(&self) {
+        let notified = self.cancel.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    fn status(&self) -> InFlightStatus {
+        InFlightStatus {
+            correlation_id: self.correlation_id.clone(),
+            method: self.method.clone(),
+            path: self.path.clone(),
+            client: self.client.clone(),
+            route: self.route.lock().clone(),
+            upstream: self.upstream.lock().clone(),
+            phase: self.phase(),
+            started_at: self.started_at.to_rfc3339(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            cancelled: self.is_cancelled(),
+        }
+    }
+}
+
+/// Admin view of an in-flight request
+#[derive(Debug, Clone, Serialize)]
+pub struct InFlightStatus {
+    pub correlation_id: String,
+    pub method: String,
+    pub path: String,
+    pub client: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub phase: RequestPhase,
+    pub started_at: String,
+    pub elapsed_ms: u64,
+    pub cancelled: bool,
+}
+
+/// Admin view of a client connection
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    /// `http` (a connection with requests in flight) or `tcp` (stream proxy)
+    pub kind: &'static str,
+    pub client: String,
+    /// Listener of a stream connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listener: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// Requests in flight on an HTTP connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<usize>,
+    /// Time since the stream connection opened or the oldest request started
+    pub elapsed_ms: u64,
+}
+
+/// A relayed stream proxy connection
+struct StreamConnection {
+    listener: String,
+    client: SocketAddr,
+    route: String,
+    upstream: String,
+    started: Instant,
+}
+
+/// In-flight requests and stream connections
+#[derive(Default)]
+pub struct InFlightRegistry {
+    requests: DashMap<String, Arc<InFlightRequest>>,
+    connections: DashMap<u64, StreamConnection>,
+    next_connection: AtomicU64,
+}
+
+impl InFlightRegistry {
+    /// Register a request; it is removed when the guard is dropped
+    pub fn register_request(
+        &self,
+        correlation_id: &str,
+        method: &str,
+        path: &str,
+        client: Option<SocketAddr>,
+    ) -> InFlightGuard {
+        let request = Arc::new(InFlightRequest {
+            correlation_id: correlation_id.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            client: client.map(|a| a.to_string()).unwrap_or_default(),
+            started: Instant::now(),
+            started_at: chrono::Utc::now(),
+            route: Mutex::new(None),
+            upstream: Mutex::new(None),
+            phase: AtomicU8::new(RequestPhase::Processing as u8),
+            cancelled: AtomicBool::new(false),
+            cancel: Notify::new(),
+        });
+        self.requests
+            .insert(correlation_id.to_string(), Arc::clone(&request));
+        InFlightGuard { request }
+    }
+
+    /// Register a stream proxy connection; it is removed when the guard is
+    /// dropped
+    pub fn register_connection(
+        &self,
+        listener: &str,
+        client: SocketAddr,
+        route: &str,
+        upstream: &str,
+    ) -> ConnectionGuard {
+        let id = self.next_connection.fetch_add(1, Ordering::Relaxed);
+        self.connections.insert(
+            id,
+            StreamConnection {
+                listener: listener.to_string(),
+                client,
+                route: route.to_string(),
+                upstream: upstream.to_string(),
+                started: Instant::now(),
+            },
+        );
+        ConnectionGuard { id }
+    }
+
+    /// Cancel an in-flight request
+    pub fn cancel(&self, correlation_id: &str) -> Result<(), InFlightError> {
+        let request = self
+            .requests
+            .get(correlation_id)
+            .map(|r| Arc::clone(r.value()))
+            .ok_or_else(|| InFlightError::UnknownRequest(correlation_id.to_string()))?;
+        if !request.cancelled.swap(true, Ordering::AcqRel) {
+            if let Some(counter) = CANCELLATIONS.as_ref() {
+                counter.inc();
+            }
+        }
+        request.cancel.notify_waiters();
+        Ok(())
+    }
+
+    /// In-flight requests, oldest first
+    pub fn requests(&self) -> Vec<InFlightStatus> {
+        let mut requests: Vec<InFlightStatus> =
+            self.requests.iter().map(|r| r.value().status()).collect();
+        requests.sort_by_key(|r| std::cmp::Reverse(r.elapsed_ms));
+        requests
+    }
+
+    /// Client connections with requests in flight and stream connections,
+    /// oldest first
+    pub fn connections(&self) -> Vec<ConnectionStatus> {
+        let mut http: std::collections::HashMap<String, (usize, u64)> =
+            std::collections::HashMap::new();
+        for request in self.requests.iter() {
+            let entry = http.entry(request.client.clone()).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(request.started.elapsed().as_millis() as u64);
+        }
+
+        let mut connections: Vec<ConnectionStatus> = http
+            .into_iter()
+            .map(|(client, (requests, elapsed_ms))| ConnectionStatus {
+                kind: "http",
+                client,
+                listener: None,
+                route: None,
+                upstream: None,
+                requests: Some(requests),
+                elapsed_ms,
+            })
+            .chain(self.connections.iter().map(|c| ConnectionStatus {
+                kind: "tcp",
+                client: c.client.to_string(),
+                listener: Some(c.listener.clone()),
+                route: Some(c.route.clone()),
+                upstream: Some(c.upstream.clone()),
+                requests: None,
+                elapsed_ms: c.started.elapsed().as_millis() as u64,
+            }))
+            .collect();
+        connections.sort_by_key(|c| std::cmp::Reverse(c.elapsed_ms));
+        connections
+    }
+}
+
+/// Keeps a request registered; dropping it unregisters the request
+pub struct InFlightGuard {
+    request: Arc<InFlightRequest>,
+}
+
+impl InFlightGuard {
+    /// Shared handle, e.g. to wait for a cancel while the context is borrowed
+    pub fn handle(&self) -> Arc<InFlightRequest> {
+        Arc::clone(&self.request)
+    }
+}
+
+impl std::ops::Deref for InFlightGuard {
+    type Target = InFlightRequest;
+
+    fn deref(&self) -> &InFlightRequest {
+        &self.request
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        REGISTRY
+            .requests
+            .remove_if(&self.request.correlation_id, |_, r| {
+                Arc::ptr_eq(r, &self.request)
+            });
+    }
+}
+
+/// Keeps a stream connection registered
+pub struct ConnectionGuard {
+    id: u64,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        REGISTRY.connections.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_cancel_and_unregister() {
+        let client: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        let guard = registry().register_request("inflight-test-1", "GET", "/slow", Some(client));
+        guard.set_target(Some("api"), Some("backend"));
+        guard.set_phase(RequestPhase::AwaitingAgent);
+
+        let status = registry()
+            .requests()
+            .into_iter()
+            .find(|r| r.correlation_id == "inflight-test-1")
+            .unwrap();
+        assert_eq!(status.phase, RequestPhase::AwaitingAgent);
+        assert_eq!(status.route.as_deref(), Some("api"));
+        assert_eq!(status.client, "10.0.0.1:40000");
+        assert!(!status.cancelled);
+
+        // A waiter is woken by the cancel
+        let waiter = {
+            let request = Arc::clone(&guard.request);
+            tokio::spawn(async move { request.cancelled().await })
+        };
+        tokio::task::yield_now().await;
+        registry().cancel("inflight-test-1").unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(guard.is_cancelled());
+        // Already cancelled: completes immediately
+        guard.cancelled().await;
+
+        drop(guard);
+        assert!(registry()
+            .requests()
+            .iter()
+            .all(|r| r.correlation_id != "inflight-test-1"));
+        assert_eq!(
+            registry().cancel("inflight-test-1"),
+            Err(InFlightError::UnknownRequest("inflight-test-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_connections() {
+        let client: SocketAddr = "10.0.0.2:40001".parse().unwrap();
+        let first = registry().register_request("inflight-test-2", "GET", "/a", Some(client));
+        let second = registry().register_request("inflight-test-3", "GET", "/b", Some(client));
+        let stream_client: SocketAddr = "10.0.0.3:5000".parse().unwrap();
+        let stream = registry().register_connection("tcp-in", stream_client, "db", "postgres");
+
+        let connections = registry().connections();
+        let http = connections
+            .iter()
+            .find(|c| c.client == "10.0.0.2:40001")
+            .unwrap();
+        assert_eq!(http.kind, "http");
+        assert_eq!(http.requests, Some(2));
+        let tcp = connections
+            .iter()
+            .find(|c| c.client == "10.0.0.3:5000")
+            .unwrap();
+        assert_eq!(tcp.kind, "tcp");
+        assert_eq!(tcp.listener.as_deref(), Some("tcp-in"));
+        assert_eq!(tcp.route.as_deref(), Some("db"));
+        assert_eq!(tcp.upstream.as_deref(), Some("postgres"));
+
+        drop((first, second, stream));
+        assert!(registry()
+            .connections()
+            .iter()
+            .all(|c| c.client != "10.0.0.2:40001" && c.client != "10.0.0.3:5000"));
+    }
+}
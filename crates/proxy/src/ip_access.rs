@@ -0,0 +1,838 @@
+//! Client IP allow/deny lists
+//!
+//! The `ip-access` filter blocks requests by client address without a
+//! denylist agent round-trip. Addresses come from inline CIDRs and from list
+//! sources (local files or HTTP(S) URLs with one IP or CIDR per line).
+//!
+//! Entries are stored in a binary radix trie per address family, so a
+//! lookup walks at most 32 (IPv4) or 128 (IPv6) nodes no matter how many
+//! prefixes are loaded. A prefix covered by a broader one is not stored.
+//!
+//! Sources are refreshed by a background task:
+//!
+//! - Files are re-read when their modification time changes.
+//! - URLs are fetched with `If-None-Match` / `If-Modified-Since`, so an
+//!   unchanged list costs a `304`. A URL source matches nothing until its
+//!   first successful fetch.
+//! - A failed refresh keeps the previous entries.
+//!
+//! # Example
+//!
+//! ```kdl
+//! filter "drop-list" {
+//!     type "ip-access"
+//!     sources "https://www.spamhaus.org/drop/drop.txt"
+//!     refresh-secs 3600
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use arc_swap::ArcSwap;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use zentinel_config::{IpAccessAction, IpAccessFilter};
+
+/// Decisions of ip-access filters per filter and result
+static IP_ACCESS_MATCHES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_ip_access_matches_total",
+        "Requests matched by ip-access lists, plus requests blocked by allow lists",
+        &["filter", "result"]
+    )
+    .ok()
+});
+
+/// Loaded prefixes per filter
+static IP_ACCESS_ENTRIES: LazyLock<Option<IntGaugeVec>> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "zentinel_ip_access_entries",
+        "Prefixes loaded by ip-access filters",
+        &["filter"]
+    )
+    .ok()
+});
+
+/// Source refreshes per filter and outcome
+static IP_ACCESS_REFRESHES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_ip_access_refresh_total",
+        "Refreshes of ip-access list sources",
+        &["filter", "outcome"]
+    )
+    .ok()
+});
+
+/// How often the refresh task looks for due sources
+pub const REFRESH_TICK: Duration = Duration::from_secs(15);
+
+/// Errors from loading an IP list
+#[derive(Debug, Error)]
+pub enum IpAccessError {
+    #[error("invalid CIDR '{0}'")]
+    InvalidCidr(String),
+
+    #[error("failed to read IP list {path:?}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to fetch IP list {url}: {source}")]
+    Fetch {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("IP list {0} has no valid entries")]
+    NoEntries(String),
+}
+
+// =============================================================================
+// CIDR Set
+// =============================================================================
+
+/// Trie node; child index 0 means "no child" (the root is never a child)
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    children: [u32; 2],
+    terminal: bool,
+}
+
+/// Binary radix trie over the leading bits of one address family
+#[derive(Debug, Clone)]
+struct Trie {
+    nodes: Vec<Node>,
+    width: u32,
+}
+
+impl Trie {
+    fn new(width: u32) -> Self {
+        Self {
+            nodes: vec![Node::default()],
+            width,
+        }
+    }
+
+    fn bit(&self, bits: u128, depth: u32) -> usize {
+        ((bits >> (self.width - 1 - depth)) & 1) as usize
+    }
+
+    /// Insert a prefix, returning false if it was already covered
+    fn insert(&mut self, bits: u128, prefix: u32) -> bool {
+        let mut node = 0;
+        for depth in 0..prefix {
+            if self.nodes[node].terminal {
+                return false;
+            }
+            let bit = self.bit(bits, depth);
+            node = match self.nodes[node].children[bit] {
+                0 => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children[bit] = child as u32;
+                    child
+                }
+                child => child as usize,
+            };
+        }
+        if self.nodes[node].terminal {
+            return false;
+        }
+        // Narrower prefixes below are now redundant
+        self.nodes[node] = Node {
+            children: [0, 0],
+            terminal: true,
+        };
+        true
+    }
+
+    fn contains(&self, bits: u128) -> bool {
+        let mut node = 0;
+        for depth in 0..self.width {
+            let current = &self.nodes[node];
+            if current.terminal {
+                return true;
+            }
+            match current.children[self.bit(bits, depth)] {
+                0 => return false,
+                child => node = child as usize,
+            }
+        }
+        self.nodes[node].terminal
+    }
+}
+
+/// Set of IPv4 and IPv6 prefixes
+#[derive(Debug, Clone)]
+pub struct CidrSet {
+    v4: Trie,
+    v6: Trie,
+    len: usize,
+}
+
+impl CidrSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self {
+            v4: Trie::new(32),
+            v6: Trie::new(128),
+            len: 0,
+        }
+    }
+
+    /// Insert a prefix; a bare address is a /32 or /128
+    pub fn insert(&mut self, addr: IpAddr, prefix: u8) {
+        let inserted = match addr {
+            IpAddr::V4(v4) => self.v4.insert(u32::from(v4) as u128, prefix.min(32) as u32),
+            IpAddr::V6(v6) => self.v6.insert(u128::from(v6), prefix.min(128) as u32),
+        };
+        if inserted {
+            self.len += 1;
+        }
+    }
+
+    /// Parse and insert an IP or CIDR
+    pub fn insert_str(&mut self, entry: &str) -> Result<(), IpAccessError> {
+        let (addr, prefix) =
+            parse_cidr(entry).ok_or_else(|| IpAccessError::InvalidCidr(entry.to_string()))?;
+        self.insert(addr, prefix);
+        Ok(())
+    }
+
+    /// Whether an address falls in any prefix of the set
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against the IPv4 prefixes.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match addr.to_canonical() {
+            IpAddr::V4(v4) => self.v4.contains(u32::from(v4) as u128),
+            IpAddr::V6(v6) => self.v6.contains(u128::from(v6)),
+        }
+    }
+
+    /// Number of prefixes inserted that were not covered by an earlier one
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Parse a list with one IP or CIDR per line
+    ///
+    /// Text after `#` or `;` is a comment and only the first word of a line
+    /// is read, so Spamhaus DROP style lists load as-is. Returns the set and
+    /// the number of invalid lines.
+    pub fn parse_list(text: &str) -> (Self, usize) {
+        let mut set = Self::new();
+        let mut invalid = 0;
+        for line in text.lines() {
+            let entry = line.split(['#', ';']).next().unwrap_or_default();
+            let Some(entry) = entry.split_whitespace().next() else {
+                continue;
+            };
+            if set.insert_str(entry).is_err() {
+                invalid += 1;
+            }
+        }
+        (set, invalid)
+    }
+}
+
+impl Default for CidrSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse `addr` or `addr/prefix`
+fn parse_cidr(entry: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = match entry.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (entry, None),
+    };
+    let addr: IpAddr = addr.parse().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(p) => p.parse::<u8>().ok().filter(|p| *p <= max)?,
+        None => max,
+    };
+    Some((addr, prefix))
+}
+
+// =============================================================================
+// List Sources
+// =============================================================================
+
+/// Outcome of a source refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RefreshOutcome {
+    Updated,
+    Unchanged,
+    Error,
+}
+
+impl RefreshOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            RefreshOutcome::Updated => "updated",
+            RefreshOutcome::Unchanged => "unchanged",
+            RefreshOutcome::Error => "error",
+        }
+    }
+}
+
+/// Cache validators of a source
+#[derive(Debug, Default)]
+struct RefreshState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    modified: Option<SystemTime>,
+    refreshed_at: Option<Instant>,
+}
+
+/// A list file or URL and its current entries
+#[derive(Debug)]
+struct ListSource {
+    location: String,
+    is_url: bool,
+    entries: ArcSwap<CidrSet>,
+    state: Mutex<RefreshState>,
+}
+
+impl ListSource {
+    /// Read a list file
+    fn file(location: &str) -> Result<Self, IpAccessError> {
+        let path = Path::new(location);
+        let read_err = |source| IpAccessError::Read {
+            path: path.to_path_buf(),
+            source,
+        };
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(read_err)?;
+        let text = std::fs::read_to_string(path).map_err(read_err)?;
+        let (entries, invalid) = CidrSet::parse_list(&text);
+        if invalid > 0 {
+            warn!(source = %location, invalid, "Skipped invalid lines in IP list");
+        }
+
+        Ok(Self {
+            location: location.to_string(),
+            is_url: false,
+            entries: ArcSwap::from_pointee(entries),
+            state: Mutex::new(RefreshState {
+                modified: Some(modified),
+                refreshed_at: Some(Instant::now()),
+                ..Default::default()
+            }),
+        })
+    }
+
+    /// A URL source, empty until the first fetch
+    fn url(location: &str) -> Self {
+        Self {
+            location: location.to_string(),
+            is_url: true,
+            entries: ArcSwap::from_pointee(CidrSet::new()),
+            state: Mutex::new(RefreshState::default()),
+        }
+    }
+
+    fn is_due(&self, interval: Duration) -> bool {
+        self.state
+            .lock()
+            .refreshed_at
+            .is_none_or(|at| at.elapsed() >= interval)
+    }
+
+    async fn refresh(&self, client: &reqwest::Client) -> Result<RefreshOutcome, IpAccessError> {
+        self.state.lock().refreshed_at = Some(Instant::now());
+        let text = if self.is_url {
+            self.fetch_url(client).await?
+        } else {
+            self.read_file().await?
+        };
+        let Some(text) = text else {
+            return Ok(RefreshOutcome::Unchanged);
+        };
+
+        let (entries, invalid) = CidrSet::parse_list(&text);
+        // An error page served with 200 must not wipe the list
+        if entries.is_empty() && invalid > 0 {
+            return Err(IpAccessError::NoEntries(self.location.clone()));
+        }
+        if invalid > 0 {
+            warn!(source = %self.location, invalid, "Skipped invalid lines in IP list");
+        }
+        self.entries.store(Arc::new(entries));
+        Ok(RefreshOutcome::Updated)
+    }
+
+    /// Re-read the file if its modification time changed
+    async fn read_file(&self) -> Result<Option<String>, IpAccessError> {
+        let path = Path::new(&self.location);
+        let read_err = |source| IpAccessError::Read {
+            path: path.to_path_buf(),
+            source,
+        };
+        let modified = tokio::fs::metadata(path)
+            .await
+            .and_then(|m| m.modified())
+            .map_err(read_err)?;
+        if self.state.lock().modified == Some(modified) {
+            return Ok(None);
+        }
+        let text = tokio::fs::read_to_string(path).await.map_err(read_err)?;
+        self.state.lock().modified = Some(modified);
+        Ok(Some(text))
+    }
+
+    /// Fetch the URL unless the server reports it unchanged
+    async fn fetch_url(&self, client: &reqwest::Client) -> Result<Option<String>, IpAccessError> {
+        let fetch_err = |source| IpAccessError::Fetch {
+            url: self.location.clone(),
+            source,
+        };
+        let mut request = client.get(&self.location);
+        {
+            let state = self.state.lock();
+            if let Some(etag) = &state.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &state.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await.map_err(fetch_err)?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status().map_err(fetch_err)?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let text = response.text().await.map_err(fetch_err)?;
+
+        let mut state = self.state.lock();
+        state.etag = etag;
+        state.last_modified = last_modified;
+        Ok(Some(text))
+    }
+}
+
+// =============================================================================
+// Filter Lists
+// =============================================================================
+
+/// Result of an ip-access check
+#[derive(Debug, Clone)]
+pub struct IpAccessResult {
+    /// Whether the request may continue
+    pub allowed: bool,
+    /// Whether the client address is on the list
+    pub listed: bool,
+    /// HTTP status code to return if blocked
+    pub status_code: u16,
+    /// Block message to return if blocked
+    pub block_message: Option<String>,
+}
+
+/// Entries and settings of one ip-access filter
+#[derive(Debug)]
+pub struct IpAccessList {
+    filter_id: String,
+    action: IpAccessAction,
+    status_code: u16,
+    block_message: Option<String>,
+    refresh_interval: Duration,
+    inline: CidrSet,
+    sources: Vec<Arc<ListSource>>,
+}
+
+impl IpAccessList {
+    /// Build a filter's list, reading its files
+    ///
+    /// URL sources of `previous` are reused so a config reload keeps their
+    /// entries and cache validators.
+    fn build(
+        filter_id: &str,
+        config: &IpAccessFilter,
+        previous: Option<&IpAccessList>,
+    ) -> Result<Self, IpAccessError> {
+        let mut inline = CidrSet::new();
+        for cidr in &config.cidrs {
+            inline.insert_str(cidr)?;
+        }
+
+        let mut sources = Vec::with_capacity(config.sources.len());
+        for location in &config.sources {
+            let source = if IpAccessFilter::is_url_source(location) {
+                previous
+                    .and_then(|p| {
+                        p.sources
+                            .iter()
+                            .find(|s| s.is_url && s.location == *location)
+                    })
+                    .cloned()
+                    .unwrap_or_else(|| Arc::new(ListSource::url(location)))
+            } else {
+                Arc::new(ListSource::file(location)?)
+            };
+            sources.push(source);
+        }
+
+        Ok(Self {
+            filter_id: filter_id.to_string(),
+            action: config.action,
+            status_code: config.status_code,
+            block_message: config.block_message.clone(),
+            refresh_interval: Duration::from_secs(config.refresh_secs),
+            inline,
+            sources,
+        })
+    }
+
+    /// Whether an address is on the list
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        self.inline.contains(addr) || self.sources.iter().any(|s| s.entries.load().contains(addr))
+    }
+
+    /// Prefixes loaded across inline CIDRs and sources
+    pub fn entry_count(&self) -> usize {
+        self.inline.len()
+            + self
+                .sources
+                .iter()
+                .map(|s| s.entries.load().len())
+                .sum::<usize>()
+    }
+
+    /// Decide on a client address
+    ///
+    /// An unparseable address is treated as unlisted: it passes a deny list
+    /// and is blocked by an allow list.
+    pub fn check(&self, client_ip: &str) -> IpAccessResult {
+        let listed = match client_ip.parse() {
+            Ok(addr) => self.contains(addr),
+            Err(_) => {
+                warn!(
+                    filter_id = %self.filter_id,
+                    client_ip = %client_ip,
+                    "Failed to parse client IP for ip-access filter"
+                );
+                false
+            }
+        };
+        let allowed = listed == (self.action == IpAccessAction::Allow);
+
+        if listed || !allowed {
+            if let Some(counter) = IP_ACCESS_MATCHES.as_ref() {
+                let result = if allowed { "allowed" } else { "denied" };
+                counter
+                    .with_label_values(&[self.filter_id.as_str(), result])
+                    .inc();
+            }
+        }
+
+        IpAccessResult {
+            allowed,
+            listed,
+            status_code: self.status_code,
+            block_message: self.block_message.clone(),
+        }
+    }
+
+    fn update_entries_gauge(&self) {
+        if let Some(gauge) = IP_ACCESS_ENTRIES.as_ref() {
+            gauge
+                .with_label_values(&[self.filter_id.as_str()])
+                .set(self.entry_count() as i64);
+        }
+    }
+}
+
+// =============================================================================
+// IP Access Manager
+// =============================================================================
+
+/// Lists of all ip-access filters
+pub struct IpAccessManager {
+    /// Filter ID → list
+    lists: DashMap<String, Arc<IpAccessList>>,
+}
+
+impl IpAccessManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            lists: DashMap::new(),
+        }
+    }
+
+    /// Build a filter's list, reading its files
+    pub fn register_filter(
+        &self,
+        filter_id: &str,
+        config: &IpAccessFilter,
+    ) -> Result<(), IpAccessError> {
+        let previous = self.get(filter_id);
+        let list = IpAccessList::build(filter_id, config, previous.as_deref())?;
+        list.update_entries_gauge();
+        debug!(
+            filter_id = %filter_id,
+            entries = list.entry_count(),
+            sources = list.sources.len(),
+            "Registered ip-access filter"
+        );
+        self.lists.insert(filter_id.to_string(), Arc::new(list));
+        Ok(())
+    }
+
+    /// List of a filter
+    pub fn get(&self, filter_id: &str) -> Option<Arc<IpAccessList>> {
+        self.lists.get(filter_id).map(|l| Arc::clone(&l))
+    }
+
+    /// Check a client address against a filter
+    pub fn check(&self, filter_id: &str, client_ip: &str) -> Option<IpAccessResult> {
+        self.lists.get(filter_id).map(|list| list.check(client_ip))
+    }
+
+    /// Rebuild lists from a new configuration
+    ///
+    /// A list that fails to load keeps its previous entries, if any.
+    pub fn reload(&self, filters: &HashMap<String, IpAccessFilter>) {
+        self.lists.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            if let Err(e) = self.register_filter(filter_id, config) {
+                warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload ip-access filter"
+                );
+            }
+        }
+    }
+
+    /// Refresh sources whose refresh interval has elapsed
+    pub async fn refresh_due(&self, client: &reqwest::Client) {
+        let lists: Vec<_> = self.lists.iter().map(|l| Arc::clone(l.value())).collect();
+
+        for list in lists {
+            let mut updated = false;
+            for source in &list.sources {
+                if !source.is_due(list.refresh_interval) {
+                    continue;
+                }
+                let outcome = match source.refresh(client).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        warn!(
+                            filter_id = %list.filter_id,
+                            source = %source.location,
+                            error = %e,
+                            "Failed to refresh IP list, keeping previous entries"
+                        );
+                        RefreshOutcome::Error
+                    }
+                };
+                if outcome == RefreshOutcome::Updated {
+                    info!(
+                        filter_id = %list.filter_id,
+                        source = %source.location,
+                        entries = source.entries.load().len(),
+                        "Refreshed IP list"
+                    );
+                    updated = true;
+                }
+                if let Some(counter) = IP_ACCESS_REFRESHES.as_ref() {
+                    counter
+                        .with_label_values(&[list.filter_id.as_str(), outcome.as_str()])
+                        .inc();
+                }
+            }
+            if updated {
+                list.update_entries_gauge();
+            }
+        }
+    }
+}
+
+impl Default for IpAccessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_set_matches_prefixes() {
+        let mut set = CidrSet::new();
+        set.insert_str("10.0.0.0/8").unwrap();
+        set.insert_str("192.0.2.7").unwrap();
+        set.insert_str("2001:db8::/32").unwrap();
+
+        assert!(set.contains(ip("10.255.1.2")));
+        assert!(set.contains(ip("192.0.2.7")));
+        assert!(!set.contains(ip("192.0.2.8")));
+        assert!(!set.contains(ip("11.0.0.1")));
+        assert!(set.contains(ip("2001:db8:ffff::1")));
+        assert!(!set.contains(ip("2001:db9::1")));
+        // IPv4-mapped addresses hit the IPv4 trie
+        assert!(set.contains(ip("::ffff:10.1.2.3")));
+
+        // Covered prefixes are not stored again
+        set.insert_str("10.1.0.0/16").unwrap();
+        assert_eq!(set.len(), 3);
+
+        assert!(set.insert_str("10.0.0.0/33").is_err());
+        assert!(set.insert_str("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn test_zero_prefix_matches_family() {
+        let mut set = CidrSet::new();
+        set.insert_str("0.0.0.0/0").unwrap();
+        assert!(set.contains(ip("203.0.113.9")));
+        assert!(!set.contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn test_parse_list_skips_comments_and_invalid_lines() {
+        let text = "\
+; Spamhaus DROP List
+1.10.16.0/20 ; SBL256894
+# local additions
+198.51.100.4   # scanner
+garbage
+
+2001:db8::/48
+";
+        let (set, invalid) = CidrSet::parse_list(text);
+        assert_eq!(set.len(), 3);
+        assert_eq!(invalid, 1);
+        assert!(set.contains(ip("1.10.31.255")));
+        assert!(set.contains(ip("198.51.100.4")));
+    }
+
+    #[test]
+    fn test_manager_allow_and_deny_lists() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "203.0.113.0/24").unwrap();
+
+        let manager = IpAccessManager::new();
+        let deny = IpAccessFilter {
+            sources: vec![file.path().display().to_string()],
+            ..Default::default()
+        };
+        manager.register_filter("deny", &deny).unwrap();
+        let allow = IpAccessFilter {
+            action: IpAccessAction::Allow,
+            cidrs: vec!["10.0.0.0/8".into()],
+            status_code: 451,
+            ..Default::default()
+        };
+        manager.register_filter("allow", &allow).unwrap();
+
+        assert!(!manager.check("deny", "203.0.113.50").unwrap().allowed);
+        assert!(manager.check("deny", "198.51.100.1").unwrap().allowed);
+        assert!(manager.check("deny", "unknown").unwrap().allowed);
+
+        assert!(manager.check("allow", "10.2.3.4").unwrap().allowed);
+        let blocked = manager.check("allow", "198.51.100.1").unwrap();
+        assert!(!blocked.allowed);
+        assert_eq!(blocked.status_code, 451);
+        assert!(!manager.check("allow", "unknown").unwrap().allowed);
+
+        assert!(manager.check("missing", "10.2.3.4").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_source_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deny.txt");
+        std::fs::write(&path, "192.0.2.1\n").unwrap();
+
+        let manager = IpAccessManager::new();
+        let filter = IpAccessFilter {
+            sources: vec![path.display().to_string()],
+            refresh_secs: 1,
+            ..Default::default()
+        };
+        manager.register_filter("deny", &filter).unwrap();
+        let list = manager.get("deny").unwrap();
+        let client = reqwest::Client::new();
+
+        // Unchanged file
+        list.sources[0].state.lock().refreshed_at = None;
+        manager.refresh_due(&client).await;
+        assert!(list.contains(ip("192.0.2.1")));
+
+        std::fs::write(&path, "192.0.2.2\n").unwrap();
+        {
+            let mut state = list.sources[0].state.lock();
+            state.refreshed_at = None;
+            state.modified = None;
+        }
+        manager.refresh_due(&client).await;
+        assert!(!list.contains(ip("192.0.2.1")));
+        assert!(list.contains(ip("192.0.2.2")));
+
+        // A list with no valid entries keeps the previous one
+        std::fs::write(&path, "<html>error</html>\n").unwrap();
+        {
+            let mut state = list.sources[0].state.lock();
+            state.refreshed_at = None;
+            state.modified = None;
+        }
+        manager.refresh_due(&client).await;
+        assert!(list.contains(ip("192.0.2.2")));
+    }
+
+    #[test]
+    fn test_missing_file_fails_registration() {
+        let manager = IpAccessManager::new();
+        let filter = IpAccessFilter {
+            sources: vec!["/nonexistent/zentinel/deny.txt".into()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            manager.register_filter("deny", &filter),
+            Err(IpAccessError::Read { .. })
+        ));
+    }
+}
@@ -0,0 +1,370 @@
+//! JSON body transformation for the `json-transform` filter
+//!
+//! Adds, removes, renames and redacts fields of JSON request or response
+//! bodies, and injects per-request values such as the correlation ID,
+//! without an external agent.
+//!
+//! Bodies are buffered until the end of the stream, transformed once and
+//! sent as a single chunk. Buffering stops at `max-body-bytes`: the bytes
+//! held so far are released and the rest of the body streams through
+//! unchanged, so large bodies never accumulate in memory. Bodies that are
+//! not valid JSON are also forwarded unchanged.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "scrub-users" {
+//!         type "json-transform"
+//!         phase "response"
+//!         max-body-bytes 1048576
+//!         rename { "$.user_id" "userId"; }
+//!         remove "$.internal" "$.debug"
+//!         redact "$..password" "$.cards[*].number"
+//!         set { "$.meta.source" "zentinel"; }
+//!         inject { "$.meta.request_id" "correlation-id"; }
+//!     }
+//! }
+//! ```
+
+use std::sync::LazyLock;
+
+use bytes::{Bytes, BytesMut};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde_json::Value;
+use tracing::{debug, trace};
+
+use zentinel_config::{JsonComputedValue, JsonPath, JsonPathSegment, JsonTransformFilter};
+
+/// Transformed, oversized and unparseable bodies per phase
+static JSON_TRANSFORMS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_json_transform_total",
+        "Bodies processed by json-transform filters",
+        &["phase", "outcome"]
+    )
+    .ok()
+});
+
+/// Per-request values available to `inject`
+#[derive(Debug, Clone, Copy)]
+pub struct ComputedValues<'a> {
+    /// Request correlation ID
+    pub correlation_id: &'a str,
+    /// Matched route ID
+    pub route_id: Option<&'a str>,
+    /// Client IP address
+    pub client_ip: &'a str,
+}
+
+impl ComputedValues<'_> {
+    fn get(&self, value: JsonComputedValue) -> Value {
+        match value {
+            JsonComputedValue::Timestamp => Value::from(chrono::Utc::now().to_rfc3339()),
+            JsonComputedValue::TimestampMs => Value::from(chrono::Utc::now().timestamp_millis()),
+            JsonComputedValue::CorrelationId => Value::from(self.correlation_id),
+            JsonComputedValue::RouteId => self.route_id.map_or(Value::Null, Value::from),
+            JsonComputedValue::ClientIp => Value::from(self.client_ip),
+        }
+    }
+}
+
+/// Apply one filter's operations: rename, remove, redact, set, inject
+pub fn apply_transform(filter: &JsonTransformFilter, body: &mut Value, values: &ComputedValues) {
+    for rename in &filter.rename {
+        rename
+            .path
+            .for_each_parent_mut(body, false, |parent, last| {
+                if let (Value::Object(map), JsonPathSegment::Field(name)) = (parent, last) {
+                    if let Some(value) = map.remove(name) {
+                        map.insert(rename.to.clone(), value);
+                    }
+                }
+            });
+    }
+
+    for path in &filter.remove {
+        path.for_each_parent_mut(body, false, |parent, last| match (parent, last) {
+            (Value::Object(map), JsonPathSegment::Field(name)) => {
+                map.remove(name);
+            }
+            (Value::Array(items), JsonPathSegment::Index(index)) if *index < items.len() => {
+                items.remove(*index);
+            }
+            (Value::Object(map), JsonPathSegment::Wildcard) => map.clear(),
+            (Value::Array(items), JsonPathSegment::Wildcard) => items.clear(),
+            _ => {}
+        });
+    }
+
+    for path in &filter.redact {
+        path.for_each_parent_mut(body, false, |parent, last| {
+            let redacted = || Value::from(filter.redaction.as_str());
+            match (parent, last) {
+                (parent, JsonPathSegment::Wildcard) => match parent {
+                    Value::Object(map) => map.values_mut().for_each(|v| *v = redacted()),
+                    Value::Array(items) => items.iter_mut().for_each(|v| *v = redacted()),
+                    _ => {}
+                },
+                (Value::Object(map), JsonPathSegment::Field(name)) => {
+                    if let Some(value) = map.get_mut(name) {
+                        *value = redacted();
+                    }
+                }
+                (Value::Array(items), JsonPathSegment::Index(index)) => {
+                    if let Some(value) = items.get_mut(*index) {
+                        *value = redacted();
+                    }
+                }
+                _ => {}
+            }
+        });
+    }
+
+    for set in &filter.set {
+        set_field(body, &set.path, &set.value);
+    }
+
+    for inject in &filter.inject {
+        set_field(body, &inject.path, &values.get(inject.value));
+    }
+}
+
+/// Set the field selected by `path`, creating missing parent objects
+fn set_field(body: &mut Value, path: &JsonPath, value: &Value) {
+    path.for_each_parent_mut(body, true, |parent, last| {
+        if let (Value::Object(map), JsonPathSegment::Field(name)) = (parent, last) {
+            map.insert(name.clone(), value.clone());
+        }
+    });
+}
+
+/// Whether a content type carries JSON (`application/json` or `*/*+json`)
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || mime.ends_with("+json")
+}
+
+/// Buffers one body for the `json-transform` filters of a route
+#[derive(Debug)]
+pub struct JsonBodyTransform {
+    filters: Vec<JsonTransformFilter>,
+    /// `request` or `response`, for metrics and logs
+    phase: &'static str,
+    /// Smallest `max-body-bytes` of the filters
+    max_body_bytes: usize,
+    buffer: BytesMut,
+    /// The body exceeded the limit and streams through unchanged
+    passthrough: bool,
+}
+
+impl JsonBodyTransform {
+    /// Create a transform applying `filters` in order
+    pub fn new(filters: Vec<JsonTransformFilter>, phase: &'static str) -> Self {
+        let max_body_bytes = filters
+            .iter()
+            .map(|f| f.max_body_bytes)
+            .min()
+            .unwrap_or_default();
+        Self {
+            filters,
+            phase,
+            max_body_bytes,
+            buffer: BytesMut::new(),
+            passthrough: false,
+        }
+    }
+
+    /// Process a body chunk in place.
+    ///
+    /// While buffering, `body` is replaced by `None`. At the end of the
+    /// stream it holds the transformed body.
+    pub fn process(
+        &mut self,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+        values: &ComputedValues,
+    ) {
+        if self.passthrough {
+            return;
+        }
+
+        if let Some(chunk) = body.take() {
+            self.buffer.extend_from_slice(&chunk);
+        }
+
+        if self.buffer.len() > self.max_body_bytes {
+            debug!(
+                correlation_id = %values.correlation_id,
+                phase = self.phase,
+                max_body_bytes = self.max_body_bytes,
+                "Body exceeds json-transform limit, passing through unchanged"
+            );
+            self.record("too_large");
+            self.passthrough = true;
+            *body = Some(self.buffer.split().freeze());
+            return;
+        }
+
+        if !end_of_stream {
+            return;
+        }
+
+        let original = self.buffer.split().freeze();
+        if original.is_empty() {
+            return;
+        }
+
+        let mut value = match serde_json::from_slice::<Value>(&original) {
+            Ok(value) => value,
+            Err(e) => {
+                debug!(
+                    correlation_id = %values.correlation_id,
+                    phase = self.phase,
+                    error = %e,
+                    "Body is not valid JSON, passing through unchanged"
+                );
+                self.record("invalid_json");
+                *body = Some(original);
+                return;
+            }
+        };
+
+        for filter in &self.filters {
+            apply_transform(filter, &mut value, values);
+        }
+
+        match serde_json::to_vec(&value) {
+            Ok(transformed) => {
+                trace!(
+                    correlation_id = %values.correlation_id,
+                    phase = self.phase,
+                    original_size = original.len(),
+                    new_size = transformed.len(),
+                    "Applied json-transform filters"
+                );
+                self.record("transformed");
+                *body = Some(Bytes::from(transformed));
+            }
+            Err(_) => *body = Some(original),
+        }
+    }
+
+    fn record(&self, outcome: &str) {
+        if let Some(counter) = JSON_TRANSFORMS.as_ref() {
+            counter.with_label_values(&[self.phase, outcome]).inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use zentinel_config::{JsonInjectField, JsonRename, JsonSetField};
+
+    const VALUES: ComputedValues<'static> = ComputedValues {
+        correlation_id: "req-1",
+        route_id: Some("users"),
+        client_ip: "10.0.0.1",
+    };
+
+    fn path(p: &str) -> JsonPath {
+        JsonPath::parse(p).unwrap()
+    }
+
+    fn filter() -> JsonTransformFilter {
+        JsonTransformFilter {
+            rename: vec![JsonRename {
+                path: path("$.user_id"),
+                to: "userId".to_string(),
+            }],
+            remove: vec![path("$.internal"), path("$.items[0]")],
+            redact: vec![path("$..password")],
+            set: vec![JsonSetField {
+                path: path("$.meta.source"),
+                value: json!("zentinel"),
+            }],
+            inject: vec![JsonInjectField {
+                path: path("$.meta.request_id"),
+                value: JsonComputedValue::CorrelationId,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_transform() {
+        let mut body = json!({
+            "user_id": 7,
+            "internal": true,
+            "items": ["a", "b"],
+            "auth": {"password": "secret"}
+        });
+
+        apply_transform(&filter(), &mut body, &VALUES);
+
+        assert_eq!(
+            body,
+            json!({
+                "userId": 7,
+                "items": ["b"],
+                "auth": {"password": "[REDACTED]"},
+                "meta": {"source": "zentinel", "request_id": "req-1"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_body_buffered_until_end_of_stream() {
+        let mut transform = JsonBodyTransform::new(vec![filter()], "response");
+
+        let mut chunk = Some(Bytes::from_static(br#"{"user_id": 7, "#));
+        transform.process(&mut chunk, false, &VALUES);
+        assert!(chunk.is_none());
+
+        let mut chunk = Some(Bytes::from_static(br#""internal": 1}"#));
+        transform.process(&mut chunk, true, &VALUES);
+        let body: Value = serde_json::from_slice(&chunk.unwrap()).unwrap();
+        assert_eq!(body["userId"], 7);
+        assert!(body.get("internal").is_none());
+    }
+
+    #[test]
+    fn test_oversized_and_invalid_bodies_pass_through() {
+        let mut small = filter();
+        small.max_body_bytes = 8;
+        let mut transform = JsonBodyTransform::new(vec![small], "request");
+
+        let mut chunk = Some(Bytes::from_static(b"{\"a\":"));
+        transform.process(&mut chunk, false, &VALUES);
+        assert!(chunk.is_none());
+        let mut chunk = Some(Bytes::from_static(b" \"long value\""));
+        transform.process(&mut chunk, false, &VALUES);
+        assert_eq!(
+            chunk.unwrap(),
+            Bytes::from_static(b"{\"a\": \"long value\"")
+        );
+        let mut chunk = Some(Bytes::from_static(b"}"));
+        transform.process(&mut chunk, true, &VALUES);
+        assert_eq!(chunk.unwrap(), Bytes::from_static(b"}"));
+
+        let mut transform = JsonBodyTransform::new(vec![filter()], "request");
+        let mut chunk = Some(Bytes::from_static(b"not json"));
+        transform.process(&mut chunk, true, &VALUES);
+        assert_eq!(chunk.unwrap(), Bytes::from_static(b"not json"));
+    }
+
+    #[test]
+    fn test_is_json_content_type() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("application/json; charset=utf-8"));
+        assert!(is_json_content_type("application/problem+json"));
+        assert!(!is_json_content_type("text/html"));
+    }
+}
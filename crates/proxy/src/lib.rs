@@ -23,6 +23,8 @@
 //! - **Validation**: JSON Schema validation for API requests/responses
 //! - **Error Handling**: Customizable error pages per service type
 //! - **Hot Reload**: Configuration changes without restarts
+//! - **Embedding**: Run the proxy inside another application with
+//!   [`ZentinelBuilder`], including in-process agents
 //!
 //! # Example
 //!
@@ -43,45 +45,95 @@
 // ============================================================================
 
 pub mod acme;
+pub mod adaptive_protection;
 pub mod agents;
+pub mod alerts;
+pub mod api_keys;
 pub mod app;
+pub mod audit_store;
+pub mod body_framing;
+pub mod bot_signals;
 pub mod builtin_handlers;
 pub mod cache;
+pub mod capture;
+pub mod challenge;
+pub mod chaos;
+pub mod client_ip;
+pub mod coalesce;
+pub mod compression;
+pub mod concurrency;
+pub mod control_plane;
+pub mod debug_headers;
 pub mod decompression;
 pub mod discovery;
 pub mod disk_cache;
 pub mod distributed_rate_limit;
+pub mod dns;
+pub mod embed;
 pub mod errors;
+pub mod explain;
+pub mod forward_proxy;
+#[cfg(feature = "gateway-api")]
+pub mod gateway_controller;
 pub mod hybrid_cache;
 pub mod memcached_rate_limit;
 
 // Kubernetes kubeconfig parsing (requires kubernetes feature)
 pub mod geo_filter;
 pub mod grpc_health;
+pub mod hardening;
 pub mod health;
+pub mod http3;
 pub mod http_helpers;
+pub mod icap;
 pub mod inference;
+pub mod inflight;
+pub mod ip_access;
+pub mod json_transform;
 #[cfg(feature = "kubernetes")]
 pub mod kubeconfig;
+pub mod lifecycle;
+pub mod listeners;
+pub mod log_journal;
 pub mod logging;
+pub mod maintenance;
+pub mod managed_rules;
 pub mod memory_cache;
 pub mod metrics;
 pub mod metrics_server;
+pub mod multipart;
+pub mod normalization;
+pub mod notifications;
+pub mod openapi_import;
 pub mod otel;
+pub mod outbound;
+pub mod preflight;
+pub mod probes;
 pub mod proxy;
+pub mod quota;
 pub mod rate_limit;
 pub mod reload;
+pub mod replay;
+pub mod request_tags;
+pub mod rollout;
 pub mod routing;
+pub mod schema_validate;
 pub mod scoped_circuit_breaker;
 pub mod scoped_rate_limit;
 pub mod scoped_routing;
+pub mod server_timing;
 pub mod shadow;
+pub mod slow_client;
 pub mod static_files;
+pub mod stream_proxy;
+pub mod tap;
+pub mod tenant;
 pub mod tls;
 pub mod tls_metrics;
 pub mod trace_id;
 pub mod upstream;
 pub mod validation;
+pub mod wasm_filter;
 pub mod websocket;
 
 // Bundle management (agent installation)
@@ -114,7 +166,7 @@ pub use upstream::{
 pub use health::{ActiveHealthChecker, PassiveHealthChecker, TargetHealthInfo};
 
 // Agents
-pub use agents::{AgentAction, AgentCallContext, AgentDecision, AgentManager};
+pub use agents::{AgentAction, AgentCallContext, AgentDecision, AgentManager, AgentSupervisor};
 
 // Hot reload
 pub use reload::{ConfigManager, ReloadEvent, ReloadTrigger, SignalManager, SignalType};
@@ -125,9 +177,14 @@ pub use app::AppState;
 // Proxy core
 pub use proxy::ZentinelProxy;
 
+// Embedding
+pub use embed::{Zentinel, ZentinelBuilder};
+pub use lifecycle::{LifecycleEvent, LifecycleEvents};
+
 // Built-in handlers
 pub use builtin_handlers::{
-    execute_handler, BuiltinHandlerState, CachePurgeRequest, TargetHealthStatus, TargetStatus,
+    execute_handler, ApiKeyActionResult, ApiKeyAdminResult, BuiltinHandlerState, CachePurgeRequest,
+    TargetHealthStatus, TargetStatus, TenantActionResult, TenantAdminResult,
     UpstreamHealthSnapshot, UpstreamStatus,
 };
 
@@ -139,7 +196,9 @@ pub use http_helpers::{
 
 // Trace ID generation (TinyFlake)
 pub use trace_id::{
-    generate_for_format, generate_tinyflake, generate_uuid, TraceIdFormat, TINYFLAKE_LENGTH,
+    generate_for_format, generate_hex, generate_tinyflake, generate_ulid, generate_uuid,
+    generate_uuid_v7, resolve_request_id, RequestIdSource, TraceIdFormat, TINYFLAKE_LENGTH,
+    ULID_LENGTH,
 };
 
 // OpenTelemetry tracing
@@ -173,6 +232,18 @@ pub use scoped_rate_limit::{ScopedRateLimitManager, ScopedRateLimitResult};
 // Scoped circuit breakers
 pub use scoped_circuit_breaker::{ScopedBreakerStatus, ScopedCircuitBreakerManager};
 
+// Multi-tenancy
+pub use tenant::{TenantAdmission, TenantManager, TenantPermit, TenantStatus};
+
+// Maintenance mode
+pub use maintenance::{MaintenanceManager, MaintenanceState};
+
+// Concurrency limits and load shedding
+pub use concurrency::{ConcurrencyAdmission, ConcurrencyManager, ConcurrencyPermit};
+
+// Slow-client protection
+pub use slow_client::{SlowClientGuard, SlowClientViolation};
+
 // Traffic mirroring / shadowing
 pub use shadow::{buffer_request_body, clone_body_for_shadow, should_buffer_method, ShadowManager};
 
@@ -181,6 +252,50 @@ pub use geo_filter::{
     GeoDatabaseWatcher, GeoFilterManager, GeoFilterPool, GeoFilterResult, GeoLookupError,
 };
 
+// Embedded WASM filters
+pub use wasm_filter::{WasmFilterError, WasmFilterManager, WasmFilterPool};
+
+// JSON body transformation
+pub use json_transform::{ComputedValues, JsonBodyTransform};
+
+// Request validation against OpenAPI / JSON Schema documents
+pub use schema_validate::{
+    SchemaDocument, SchemaLoadError, SchemaRejection, SchemaValidateManager, Violation,
+    ViolationLocation,
+};
+
+// In-proxy WAF-style rule bundles
+pub use managed_rules::{
+    ManagedRulesManager, RuleLoadError, RuleMatch, RuleRejection, RuleSet, Severity,
+};
+
+// Per-client anomaly scoring
+pub use adaptive_protection::{
+    AdaptiveDecision, AdaptiveFilter, AdaptiveProtectionManager, AdaptiveVerdict, Observation,
+    Reason, Signals,
+};
+
+// Client IP allow/deny lists
+pub use ip_access::{CidrSet, IpAccessError, IpAccessList, IpAccessManager, IpAccessResult};
+
+// Per-consumer daily and monthly quotas
+pub use quota::{QuotaCheck, QuotaManager, QuotaMetric, QuotaStore, QuotaUsage, QuotaWindow};
+
+// API key authentication
+pub use api_keys::{ApiKey, ApiKeyError, ApiKeyManager, ApiKeyOutcome, ApiKeyStatus, ApiKeyStore};
+
+// Startup preflight checks
+pub use preflight::{PreflightFailure, PreflightOptions, PreflightReport};
+
+// Liveness, readiness and health reports
+pub use probes::{HealthProbes, HealthReport, ProbeInputs, ReadinessCheck};
+
+// Typed request tags set by agents
+pub use request_tags::RequestTags;
+
+// Streaming response compression
+pub use compression::{encoding_token, negotiate_encoding, ResponseCompressor};
+
 // Body decompression with ratio limits
 pub use decompression::{
     decompress_body, decompress_body_with_stats, is_supported_encoding, parse_content_encoding,
@@ -215,7 +330,8 @@ pub use metrics::{MetricsManager, MetricsResponse};
 
 // Service discovery
 pub use discovery::{
-    ConsulDiscovery, DiscoveryConfig, DiscoveryManager, DnsDiscovery, KubernetesDiscovery,
+    backends_to_targets, ConsulDiscovery, DiscoveryConfig, DiscoveryManager, DnsDiscovery,
+    DnsSrvDiscovery, KubernetesDiscovery,
 };
 
 // Kubernetes kubeconfig parsing
@@ -0,0 +1,110 @@
+//! Request lifecycle events for embedding applications.
+//!
+//! The proxy announces every request when it arrives and again when it has
+//! been logged. Subscribers receive the events from a broadcast channel; a
+//! subscriber that falls behind skips events (`RecvError::Lagged`) instead of
+//! slowing requests down. Nothing is built or sent while nobody subscribes.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before the oldest are dropped
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Something that happened to a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleEvent {
+    /// A request arrived, before routing, rate limiting and agents
+    RequestStarted {
+        correlation_id: String,
+        method: String,
+        path: String,
+        client_ip: String,
+    },
+    /// A request finished, after the access log entry was written
+    RequestCompleted {
+        correlation_id: String,
+        method: String,
+        path: String,
+        route_id: Option<String>,
+        upstream: Option<String>,
+        /// Response status; 0 if no response was written
+        status: u16,
+        duration: Duration,
+        /// Tags agents set on the request
+        tags: BTreeMap<String, String>,
+    },
+}
+
+impl LifecycleEvent {
+    /// Correlation ID of the request the event belongs to
+    pub fn correlation_id(&self) -> &str {
+        match self {
+            LifecycleEvent::RequestStarted { correlation_id, .. }
+            | LifecycleEvent::RequestCompleted { correlation_id, .. } => correlation_id,
+        }
+    }
+}
+
+/// Broadcast channel for [`LifecycleEvent`]s
+#[derive(Debug, Clone)]
+pub struct LifecycleEvents {
+    sender: broadcast::Sender<LifecycleEvent>,
+}
+
+impl LifecycleEvents {
+    /// Create a channel buffering up to `capacity` events per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Receive events from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send the event built by `event`, if anyone is listening.
+    pub fn emit(&self, event: impl FnOnce() -> LifecycleEvent) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(event());
+        }
+    }
+}
+
+impl Default for LifecycleEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(id: &str) -> LifecycleEvent {
+        LifecycleEvent::RequestStarted {
+            correlation_id: id.to_string(),
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            client_ip: "127.0.0.1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_reach_subscribers_only() {
+        let events = LifecycleEvents::new(4);
+        let mut built = false;
+        events.emit(|| {
+            built = true;
+            started("unseen")
+        });
+        assert!(!built);
+
+        let mut rx = events.subscribe();
+        events.emit(|| started("r1"));
+        assert_eq!(rx.recv().await.unwrap().correlation_id(), "r1");
+    }
+}
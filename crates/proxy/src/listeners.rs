@@ -0,0 +1,237 @@
+//! Binding configured listeners to the Pingora proxy services.
+//!
+//! Shared by the `zentinel` binary and [`crate::embed`], so an embedded
+//! proxy listens exactly like a standalone one: plain HTTP, HTTPS with
+//! certificate files or ACME-managed certificates, HTTP/3 through the QUIC
+//! frontend, and PROXY protocol acceptors.
+//!
+//! HTTPS is terminated by the fronting acceptor in
+//! [`crate::client_ip::proxy_protocol`] rather than by Pingora, so every
+//! TLS listener serves certificates from its [`HotReloadableSniResolver`]:
+//! file reloads, ACME renewals and OCSP staples apply without a restart.
+
+use std::sync::Arc;
+
+use pingora_core::services::listening::Service;
+use tracing::{error, info, warn};
+use zentinel_config::{ListenerConfig, ListenerProtocol};
+
+use crate::tls::{CertificateReloader, HotReloadableSniResolver, OcspStapler};
+
+/// Build the hot-reloadable SNI resolver for a TLS listener
+///
+/// OCSP responses (refreshed daily by the ACME renewal scheduler) are kept
+/// for up to three days so a failed refresh does not drop the staple.
+pub fn build_tls_resolver(listener: &ListenerConfig) -> Option<Arc<HotReloadableSniResolver>> {
+    let tls = listener.tls.as_ref()?;
+    let resolver = match HotReloadableSniResolver::from_config(tls.clone(), &listener.id) {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            warn!(
+                listener_id = %listener.id,
+                error = %e,
+                "Failed to build TLS resolver; listener will not be started"
+            );
+            return None;
+        }
+    };
+    let stapler = Arc::new(OcspStapler::with_refresh_interval(
+        std::time::Duration::from_secs(3 * 24 * 3600),
+    ));
+    Some(Arc::new(resolver.with_ocsp_stapler(stapler)))
+}
+
+/// Register a hot-reloadable SNI resolver for every TLS listener that does
+/// not have one yet (ACME listeners register theirs during ACME setup).
+pub fn register_tls_resolvers(listeners: &[ListenerConfig], cert_reloader: &CertificateReloader) {
+    for listener in listeners {
+        let tls_listener = matches!(
+            listener.protocol,
+            ListenerProtocol::Https | ListenerProtocol::Http3
+        );
+        if !tls_listener || cert_reloader.get(&listener.id).is_some() {
+            continue;
+        }
+        if let Some(resolver) = build_tls_resolver(listener) {
+            cert_reloader.register(&listener.id, resolver);
+        }
+    }
+}
+
+/// Build the rustls configuration of an HTTPS listener around its registered
+/// resolver, see [`crate::tls::build_server_config`].
+fn https_server_config(
+    listener: &ListenerConfig,
+    cert_reloader: &CertificateReloader,
+) -> Option<Arc<rustls::ServerConfig>> {
+    let (Some(tls_config), Some(resolver)) = (&listener.tls, cert_reloader.get(&listener.id))
+    else {
+        error!(
+            listener_id = %listener.id,
+            address = %listener.address,
+            "HTTPS listener requires a loadable TLS configuration"
+        );
+        return None;
+    };
+    match crate::tls::build_server_config(tls_config, resolver) {
+        Ok(server_config) => Some(Arc::new(server_config)),
+        Err(e) => {
+            error!(
+                listener_id = %listener.id,
+                error = %e,
+                "Failed to create TLS settings"
+            );
+            None
+        }
+    }
+}
+
+/// Add `listeners` to the proxy services.
+///
+/// Plain HTTP listeners bind on `public_service`; fronted and HTTP/3
+/// listeners bind their internal Unix sockets on `internal_service`, the
+/// only one that should accept h2c (HTTPS acceptors hand decrypted HTTP/2
+/// to Pingora as h2c). Fronting acceptors and HTTP/3 frontends are spawned
+/// on `runtime`, each acceptor handling at most `max_connections`
+/// connections at once. A listener that cannot be set up is logged and
+/// skipped.
+pub fn add_listeners<A>(
+    public_service: &mut Service<A>,
+    internal_service: &mut Service<A>,
+    listeners: &[ListenerConfig],
+    cert_reloader: &CertificateReloader,
+    max_connections: usize,
+    runtime: &tokio::runtime::Handle,
+) {
+    for listener in listeners {
+        // Forward-proxy and TCP listeners bypass Pingora, see
+        // `forward_proxy::spawn_listeners` and `stream_proxy::spawn_listeners`
+        if matches!(
+            listener.protocol,
+            ListenerProtocol::ForwardProxy | ListenerProtocol::Tcp
+        ) {
+            continue;
+        }
+
+        match listener.protocol {
+            ListenerProtocol::Http | ListenerProtocol::Https => {
+                // HTTPS listeners terminate TLS in the acceptor, which serves
+                // certificates from the listener's hot-reloadable resolver
+                let tls = if listener.protocol == ListenerProtocol::Https {
+                    match https_server_config(listener, cert_reloader) {
+                        Some(tls) => Some(tls),
+                        None => continue,
+                    }
+                } else {
+                    None
+                };
+
+                if tls.is_none() && !listener.proxy_protocol && !listener.bot_signals {
+                    public_service.add_tcp(&listener.address);
+                    info!("HTTP listening on: {}", listener.address);
+                    continue;
+                }
+
+                // Fronted listeners: an acceptor owns the public address and
+                // hands connections to Pingora on an internal Unix socket
+                let internal_address =
+                    match crate::client_ip::proxy_protocol::internal_listener_path() {
+                        Ok(internal_address) => internal_address,
+                        Err(e) => {
+                            error!(
+                                listener_id = %listener.id,
+                                error = %e,
+                                "Failed to create internal socket for fronted listener"
+                            );
+                            continue;
+                        }
+                    };
+                internal_service.add_uds(&internal_address.to_string_lossy(), None);
+                let https = tls.is_some();
+                runtime.spawn(crate::client_ip::proxy_protocol::run_acceptor(
+                    listener.id.clone(),
+                    listener.address.clone(),
+                    internal_address,
+                    listener.proxy_protocol,
+                    listener.bot_signals,
+                    tls,
+                    max_connections,
+                ));
+                match (https, &listener.tls) {
+                    (true, Some(tls_config)) => info!(
+                        listener_id = %listener.id,
+                        address = %listener.address,
+                        min_tls_version = ?tls_config.min_version,
+                        client_auth = tls_config.client_auth,
+                        acme_enabled = tls_config.acme.is_some(),
+                        "HTTPS (h2+http/1.1) listening on: {}", listener.address
+                    ),
+                    _ => info!("HTTP listening on: {}", listener.address),
+                }
+            }
+            ListenerProtocol::Http3 => {
+                // QUIC terminates in the HTTP/3 frontend, which serves
+                // certificates from the hot-reloadable resolver and hands
+                // requests to Pingora on an internal Unix socket
+                let (Some(tls_config), Some(resolver)) =
+                    (&listener.tls, cert_reloader.get(&listener.id))
+                else {
+                    error!(
+                        listener_id = %listener.id,
+                        address = %listener.address,
+                        "HTTP/3 listener requires a loadable TLS configuration"
+                    );
+                    continue;
+                };
+                let quic_config = crate::tls::build_quic_server_config(tls_config, resolver)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|tls| {
+                        crate::http3::build_server_config(
+                            tls,
+                            &listener.quic,
+                            listener.max_concurrent_streams,
+                        )
+                    });
+                let quic_config = match quic_config {
+                    Ok(quic_config) => quic_config,
+                    Err(e) => {
+                        error!(
+                            listener_id = %listener.id,
+                            error = %e,
+                            "Failed to create QUIC settings"
+                        );
+                        continue;
+                    }
+                };
+                let internal_address =
+                    match crate::client_ip::proxy_protocol::internal_listener_path() {
+                        Ok(internal_address) => internal_address,
+                        Err(e) => {
+                            error!(
+                                listener_id = %listener.id,
+                                error = %e,
+                                "Failed to create internal socket for HTTP/3 listener"
+                            );
+                            continue;
+                        }
+                    };
+                internal_service.add_uds(&internal_address.to_string_lossy(), None);
+                runtime.spawn(crate::http3::run_listener(
+                    listener.id.clone(),
+                    listener.address.clone(),
+                    quic_config,
+                    internal_address,
+                ));
+                info!(
+                    listener_id = %listener.id,
+                    address = %listener.address,
+                    alt_svc_max_age_secs = listener.quic.alt_svc_max_age_secs,
+                    "HTTP/3 (QUIC) listening on: {}", listener.address
+                );
+            }
+            _ => {
+                warn!("Unsupported protocol: {:?}", listener.protocol);
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Write-ahead journal for access and audit log records
+//!
+//! Log files are written through a `BufWriter`, so records of completed
+//! requests can sit in memory for a while before they reach the file. When
+//! billing or compliance depends on those records, losing them on a crash
+//! is not acceptable.
+//!
+//! With a journal configured, every access and audit line is appended to
+//! the journal file (synced according to `JournalFsync`) before it enters
+//! the buffered writer. Once the writers are flushed and synced the journal
+//! is truncated. Lines still in the journal at startup are replayed into
+//! the log files.
+//!
+//! Each journal line is a one-character sink tag, a space and the log line.
+//! Log lines never contain a newline (JSON escapes them, combined format
+//! has none), so a final line without a trailing newline is a torn write
+//! and is dropped on replay.
+//!
+//! Replay is at-least-once: a record that reached the log file just before
+//! the crash is written again. Consumers can dedupe on `trace_id`.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use zentinel_config::{JournalFsync, LogJournalConfig};
+
+/// Log file a journaled record belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalSink {
+    Access,
+    Audit,
+}
+
+impl JournalSink {
+    fn tag(self) -> char {
+        match self {
+            JournalSink::Access => 'a',
+            JournalSink::Audit => 'u',
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "a" => Some(JournalSink::Access),
+            "u" => Some(JournalSink::Audit),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for JournalSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JournalSink::Access => write!(f, "access"),
+            JournalSink::Audit => write!(f, "audit"),
+        }
+    }
+}
+
+/// Append-only journal file
+pub struct LogJournal {
+    file: File,
+    path: PathBuf,
+    fsync: JournalFsync,
+    fsync_interval: Duration,
+    last_sync: Instant,
+    /// Bytes appended since the last checkpoint
+    bytes: u64,
+    max_bytes: u64,
+}
+
+impl LogJournal {
+    /// Open the journal, returning the records left over from the previous
+    /// run. The caller replays them and then calls `checkpoint`.
+    pub fn open(config: &LogJournalConfig) -> Result<(Self, Vec<(JournalSink, String)>)> {
+        let path = &config.path;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create journal directory: {:?}", parent))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log journal: {:?}", path))?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .with_context(|| format!("Failed to read log journal: {:?}", path))?;
+        let pending = parse_records(&data);
+
+        Ok((
+            Self {
+                file,
+                path: path.clone(),
+                fsync: config.fsync,
+                fsync_interval: Duration::from_millis(config.fsync_interval_ms),
+                last_sync: Instant::now(),
+                bytes: data.len() as u64,
+                max_bytes: config.max_bytes,
+            },
+            pending,
+        ))
+    }
+
+    /// Journal file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one record, syncing according to the fsync policy
+    pub fn append(&mut self, sink: JournalSink, line: &str) -> Result<()> {
+        let record = format!("{} {}\n", sink.tag(), line);
+        self.file.write_all(record.as_bytes())?;
+        self.bytes += record.len() as u64;
+
+        match self.fsync {
+            JournalFsync::Always => self.sync()?,
+            JournalFsync::Interval if self.last_sync.elapsed() >= self.fsync_interval => {
+                self.sync()?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the journal has grown past `max_bytes` and the log writers
+    /// should be flushed so it can be truncated
+    pub fn needs_checkpoint(&self) -> bool {
+        self.bytes >= self.max_bytes
+    }
+
+    /// Drop all records. Only call once every journaled line is durable in
+    /// its log file.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.sync()?;
+        self.bytes = 0;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        self.file.sync_data()?;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+/// Parse journal contents, skipping a torn final line and unknown tags
+fn parse_records(data: &[u8]) -> Vec<(JournalSink, String)> {
+    let complete = match data.iter().rposition(|&b| b == b'\n') {
+        Some(end) => &data[..end],
+        None => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(complete)
+        .lines()
+        .filter_map(|line| {
+            let (tag, record) = line.split_once(' ')?;
+            Some((JournalSink::from_tag(tag)?, record.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config(path: PathBuf) -> LogJournalConfig {
+        LogJournalConfig {
+            path,
+            fsync: JournalFsync::Always,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_records_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let config = config(dir.path().join("logs.journal"));
+
+        let (mut journal, pending) = LogJournal::open(&config).unwrap();
+        assert!(pending.is_empty());
+        journal
+            .append(JournalSink::Access, r#"{"status":200}"#)
+            .unwrap();
+        journal
+            .append(JournalSink::Audit, r#"{"event_type":"blocked"}"#)
+            .unwrap();
+        drop(journal);
+
+        let (_, pending) = LogJournal::open(&config).unwrap();
+        assert_eq!(
+            pending,
+            vec![
+                (JournalSink::Access, r#"{"status":200}"#.to_string()),
+                (
+                    JournalSink::Audit,
+                    r#"{"event_type":"blocked"}"#.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_truncates() {
+        let dir = tempdir().unwrap();
+        let config = LogJournalConfig {
+            max_bytes: 16,
+            ..config(dir.path().join("logs.journal"))
+        };
+
+        let (mut journal, _) = LogJournal::open(&config).unwrap();
+        journal.append(JournalSink::Access, "first line").unwrap();
+        assert!(!journal.needs_checkpoint());
+        journal.append(JournalSink::Access, "second line").unwrap();
+        assert!(journal.needs_checkpoint());
+
+        journal.checkpoint().unwrap();
+        assert!(!journal.needs_checkpoint());
+        journal.append(JournalSink::Audit, "third line").unwrap();
+        drop(journal);
+
+        let (_, pending) = LogJournal::open(&config).unwrap();
+        assert_eq!(
+            pending,
+            vec![(JournalSink::Audit, "third line".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_torn_write_is_dropped() {
+        let records = parse_records(b"a complete\nu also complete\nx unknown\na torn");
+        assert_eq!(
+            records,
+            vec![
+                (JournalSink::Access, "complete".to_string()),
+                (JournalSink::Audit, "also complete".to_string()),
+            ]
+        );
+        assert!(parse_records(b"a torn").is_empty());
+    }
+}
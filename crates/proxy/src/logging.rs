@@ -8,18 +8,24 @@
 //! Access log formats supported:
 //! - `json` (default): Structured JSON with all fields
 //! - `combined`: Apache/nginx Combined Log Format with trace_id extension
+//!
+//! With `logging.journal` configured, access and audit lines are also
+//! written ahead to a journal (see `log_journal`) and replayed on restart.
 
 use anyhow::{Context, Result};
 use parking_lot::Mutex;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::sync::Arc;
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 
 use zentinel_config::{AuditLogConfig, LoggingConfig};
 
+use crate::log_journal::{JournalSink, LogJournal};
+
 /// Access log format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AccessLogFormat {
@@ -90,6 +96,12 @@ pub struct AccessLogEntry {
     /// GeoIP country code (ISO 3166-1 alpha-2)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub geo_country: Option<String>,
+    /// Time spent per request phase, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<zentinel_agent_protocol::LatencyBreakdown>,
+    /// Tags set by agents, keyed by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub request_tags: BTreeMap<String, String>,
 }
 
 impl AccessLogEntry {
@@ -153,6 +165,9 @@ impl AccessLogEntry {
                 "duration_ms".to_string(),
                 serde_json::json!(self.duration_ms),
             );
+            if let Some(ref latency) = self.latency {
+                map.insert("latency".to_string(), serde_json::json!(latency));
+            }
         }
         if fields.client_ip {
             map.insert(
@@ -207,6 +222,12 @@ impl AccessLogEntry {
                 );
             }
         }
+        if fields.request_tags && !self.request_tags.is_empty() {
+            map.insert(
+                "request_tags".to_string(),
+                serde_json::json!(self.request_tags),
+            );
+        }
         // Always include these core fields (not configurable)
         if let Some(ref route) = self.route_id {
             map.insert(
@@ -387,6 +408,9 @@ pub struct AuditLogEntry {
     /// Additional metadata as key-value pairs
     #[serde(skip_serializing_if = "std::collections::HashMap::is_empty")]
     pub metadata: std::collections::HashMap<String, String>,
+    /// Request tags set by agents, keyed by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub request_tags: BTreeMap<String, String>,
     /// Namespace (for scoped requests)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub namespace: Option<String>,
@@ -421,6 +445,7 @@ impl AuditLogEntry {
             user_id: None,
             session_id: None,
             metadata: std::collections::HashMap::new(),
+            request_tags: BTreeMap::new(),
             namespace: None,
             service: None,
         }
@@ -475,6 +500,12 @@ impl AuditLogEntry {
         self
     }
 
+    /// Builder: set request tags
+    pub fn with_request_tags(mut self, request_tags: BTreeMap<String, String>) -> Self {
+        self.request_tags = request_tags;
+        self
+    }
+
     /// Builder: set action
     pub fn with_action(mut self, action: impl Into<String>) -> Self {
         self.action = Some(action.into());
@@ -656,6 +687,13 @@ impl LogFileWriter {
         self.writer.flush()?;
         Ok(())
     }
+
+    /// Flush and sync to disk, so journaled lines can be dropped
+    fn sync(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        Ok(())
+    }
 }
 
 /// Log manager handling all log file writers
@@ -667,6 +705,8 @@ pub struct LogManager {
     error_log_level: String,
     audit_log: Option<Mutex<LogFileWriter>>,
     audit_config: Option<AuditLogConfig>,
+    /// Write-ahead journal; locked before any writer it covers
+    journal: Option<Mutex<LogJournal>>,
 }
 
 impl LogManager {
@@ -716,7 +756,7 @@ impl LogManager {
             None
         };
 
-        Ok(Self {
+        let mut manager = Self {
             access_log,
             access_log_format,
             access_log_config: config.access_log.clone(),
@@ -724,7 +764,26 @@ impl LogManager {
             error_log_level,
             audit_log,
             audit_config: config.audit_log.clone(),
-        })
+            journal: None,
+        };
+
+        if let Some(ref journal_config) = config.journal {
+            let (mut journal, pending) = LogJournal::open(journal_config)?;
+            if !pending.is_empty() {
+                manager.replay(&pending);
+                info!(
+                    records = pending.len(),
+                    path = ?journal.path(),
+                    "Replayed log journal"
+                );
+            }
+            manager
+                .checkpoint(&mut journal)
+                .context("Failed to checkpoint log journal after replay")?;
+            manager.journal = Some(Mutex::new(journal));
+        }
+
+        Ok(manager)
     }
 
     /// Create a disabled log manager (no file logging)
@@ -737,6 +796,7 @@ impl LogManager {
             error_log_level: "warn".to_string(),
             audit_log: None,
             audit_config: None,
+            journal: None,
         }
     }
 
@@ -772,10 +832,7 @@ impl LogManager {
         if let Some(ref writer) = self.access_log {
             let fields = self.access_log_config.as_ref().map(|c| &c.fields);
             let formatted = entry.format(self.access_log_format, fields);
-            let mut guard = writer.lock();
-            if let Err(e) = guard.write_line(&formatted) {
-                error!("Failed to write access log: {}", e);
-            }
+            self.write_journaled(JournalSink::Access, writer, &formatted);
         }
     }
 
@@ -815,10 +872,7 @@ impl LogManager {
 
             match serde_json::to_string(entry) {
                 Ok(json) => {
-                    let mut guard = writer.lock();
-                    if let Err(e) = guard.write_line(&json) {
-                        error!("Failed to write audit log: {}", e);
-                    }
+                    self.write_journaled(JournalSink::Audit, writer, &json);
                 }
                 Err(e) => {
                     error!("Failed to serialize audit log entry: {}", e);
@@ -827,8 +881,65 @@ impl LogManager {
         }
     }
 
+    /// Write a line to the journal (if any) and then to its log file
+    fn write_journaled(&self, sink: JournalSink, writer: &Mutex<LogFileWriter>, line: &str) {
+        let mut journal = self.journal.as_ref().map(|j| j.lock());
+        if let Some(ref mut journal) = journal {
+            if let Err(e) = journal.append(sink, line) {
+                error!("Failed to write {} record to log journal: {}", sink, e);
+            }
+        }
+
+        if let Err(e) = writer.lock().write_line(line) {
+            error!("Failed to write {} log: {}", sink, e);
+        }
+
+        if let Some(ref mut journal) = journal {
+            if journal.needs_checkpoint() {
+                if let Err(e) = self.checkpoint(journal) {
+                    warn!("Failed to checkpoint log journal: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Write records left in the journal by the previous run
+    fn replay(&self, records: &[(JournalSink, String)]) {
+        for (sink, line) in records {
+            let writer = match sink {
+                JournalSink::Access => self.access_log.as_ref(),
+                JournalSink::Audit => self.audit_log.as_ref(),
+            };
+            match writer {
+                Some(writer) => {
+                    if let Err(e) = writer.lock().write_line(line) {
+                        error!("Failed to replay {} log record: {}", sink, e);
+                    }
+                }
+                None => warn!(
+                    "Dropping journaled {} record, {} log is disabled",
+                    sink, sink
+                ),
+            }
+        }
+    }
+
+    /// Sync the journaled log files and truncate the journal. The journal
+    /// is kept when a sync fails so its records are replayed next start.
+    fn checkpoint(&self, journal: &mut LogJournal) -> Result<()> {
+        for writer in [&self.access_log, &self.audit_log].into_iter().flatten() {
+            writer.lock().sync()?;
+        }
+        journal.checkpoint()
+    }
+
     /// Flush all log buffers
     pub fn flush(&self) {
+        if let Some(ref journal) = self.journal {
+            if let Err(e) = self.checkpoint(&mut journal.lock()) {
+                warn!("Failed to checkpoint log journal: {}", e);
+            }
+        }
         if let Some(ref writer) = self.access_log {
             if let Err(e) = writer.lock().flush() {
                 warn!("Failed to flush access log: {}", e);
@@ -939,6 +1050,8 @@ mod tests {
             connection_reused: true,
             rate_limit_hit: false,
             geo_country: None,
+            latency: None,
+            request_tags: BTreeMap::new(),
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -973,6 +1086,8 @@ mod tests {
             connection_reused: false,
             rate_limit_hit: false,
             geo_country: Some("US".to_string()),
+            latency: None,
+            request_tags: BTreeMap::new(),
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -1015,7 +1130,9 @@ mod tests {
                 log_blocked: true,
                 log_agent_decisions: true,
                 log_waf_events: true,
+                store: None,
             }),
+            journal: None,
         };
 
         let manager = LogManager::new(&config).unwrap();
@@ -1024,6 +1141,58 @@ mod tests {
         assert!(manager.audit_log_enabled());
     }
 
+    #[test]
+    fn test_journal_replays_unflushed_records() {
+        let dir = tempdir().unwrap();
+        let access_log_path = dir.path().join("access.log");
+        let audit_log_path = dir.path().join("audit.log");
+        let journal_path = dir.path().join("logs.journal");
+
+        let config = LoggingConfig {
+            access_log: Some(AccessLogConfig {
+                file: access_log_path.clone(),
+                ..Default::default()
+            }),
+            error_log: None,
+            audit_log: Some(AuditLogConfig {
+                file: audit_log_path.clone(),
+                ..Default::default()
+            }),
+            journal: Some(zentinel_config::LogJournalConfig {
+                path: journal_path.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Simulate a crash: records stay in the write buffers, Drop never runs
+        let manager = LogManager::new(&config).unwrap();
+        manager.log_access(&test_entry());
+        manager.log_audit(&AuditLogEntry::blocked(
+            "trace-1", "GET", "/admin", "10.0.0.1", "denied",
+        ));
+        std::mem::forget(manager);
+        assert!(std::fs::read_to_string(&access_log_path)
+            .unwrap()
+            .is_empty());
+
+        let manager = LogManager::new(&config).unwrap();
+        let access = std::fs::read_to_string(&access_log_path).unwrap();
+        let audit = std::fs::read_to_string(&audit_log_path).unwrap();
+        assert_eq!(access.lines().count(), 1);
+        assert!(access.contains(&test_entry().trace_id));
+        assert_eq!(audit.lines().count(), 1);
+        assert!(audit.contains("trace-1"));
+        assert!(std::fs::read_to_string(&journal_path).unwrap().is_empty());
+
+        // A clean shutdown leaves nothing to replay
+        manager.log_access(&test_entry());
+        drop(manager);
+        let _manager = LogManager::new(&config).unwrap();
+        let access = std::fs::read_to_string(&access_log_path).unwrap();
+        assert_eq!(access.lines().count(), 2);
+    }
+
     #[test]
     fn test_access_log_combined_format() {
         let entry = AccessLogEntry {
@@ -1051,6 +1220,8 @@ mod tests {
             connection_reused: true,
             rate_limit_hit: false,
             geo_country: Some("US".to_string()),
+            latency: None,
+            request_tags: BTreeMap::new(),
         };
 
         let combined = entry.format(AccessLogFormat::Combined, None);
@@ -1123,6 +1294,8 @@ mod tests {
             connection_reused: false,
             rate_limit_hit: true,
             geo_country: Some("DE".to_string()),
+            latency: None,
+            request_tags: BTreeMap::from([("tier".to_string(), "gold".to_string())]),
         }
     }
 
@@ -1140,6 +1313,7 @@ mod tests {
         assert_eq!(parsed["duration_ms"], 25);
         assert_eq!(parsed["upstream_addr"], "10.1.0.5:9090");
         assert_eq!(parsed["rate_limit_hit"], true);
+        assert_eq!(parsed["request_tags"]["tier"], "gold");
     }
 
     #[test]
@@ -1162,6 +1336,7 @@ mod tests {
             user_agent: false,
             referer: false,
             client_ip: false,
+            request_tags: false,
         };
 
         let json_str = entry.format(AccessLogFormat::Json, Some(&fields));
@@ -1190,6 +1365,10 @@ mod tests {
             parsed.get("client_ip").is_none(),
             "client_ip should be filtered out"
         );
+        assert!(
+            parsed.get("request_tags").is_none(),
+            "request_tags should be filtered out"
+        );
         assert!(
             parsed.get("user_agent").is_none(),
             "user_agent should be filtered out"
@@ -1251,6 +1430,8 @@ mod tests {
             connection_reused: false,
             rate_limit_hit: false,
             geo_country: None,
+            latency: None,
+            request_tags: BTreeMap::new(),
         };
 
         let combined = entry.format(AccessLogFormat::Combined, None);
@@ -1289,6 +1470,8 @@ mod tests {
             connection_reused: false,
             rate_limit_hit: false,
             geo_country: None,
+            latency: None,
+            request_tags: BTreeMap::new(),
         };
 
         // Full serialization (no field filter) uses skip_serializing_if
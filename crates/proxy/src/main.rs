@@ -25,9 +25,13 @@ use zentinel_config::Config;
 use zentinel_proxy::acme::{
     AcmeClient, AcmeError, CertificateStorage, ChallengeManager, RenewalScheduler,
 };
-use zentinel_proxy::bundle::{run_bundle_command, BundleArgs};
-use zentinel_proxy::tls::HotReloadableSniResolver;
-use zentinel_proxy::{ReloadTrigger, SignalManager, SignalType, ZentinelProxy};
+use zentinel_proxy::bundle::{run_bundle_command, run_registry_command, BundleArgs, RegistryArgs};
+use zentinel_proxy::control_plane::ControlPlaneOptions;
+use zentinel_proxy::listeners::build_tls_resolver;
+use zentinel_proxy::preflight::{run_preflight, PreflightOptions};
+use zentinel_proxy::reload::CertificateWatcher;
+use zentinel_proxy::tls::CertificateReloader;
+use zentinel_proxy::{AgentSupervisor, ReloadTrigger, SignalManager, SignalType, ZentinelProxy};
 
 /// Version string combining Cargo semver and CalVer release tag
 const VERSION: &str = concat!(
@@ -65,6 +69,48 @@ struct Cli {
     #[arg(short = 'u', long = "upgrade")]
     upgrade: bool,
 
+    /// Build routes from Kubernetes Gateway API resources (requires the
+    /// `gateway-api` feature)
+    #[arg(long = "gateway-controller")]
+    gateway_controller: bool,
+
+    /// Poll this control-plane URL for versioned routes, upstreams and
+    /// filters
+    #[arg(long = "control-plane", env = "ZENTINEL_CONTROL_PLANE")]
+    control_plane: Option<String>,
+
+    /// Node id reported to the control plane (defaults to the hostname)
+    #[arg(long = "control-plane-node-id", env = "ZENTINEL_CONTROL_PLANE_NODE_ID")]
+    control_plane_node_id: Option<String>,
+
+    /// Bearer token for the control plane
+    #[arg(
+        long = "control-plane-token",
+        env = "ZENTINEL_CONTROL_PLANE_TOKEN",
+        hide_env_values = true
+    )]
+    control_plane_token: Option<String>,
+
+    /// Seconds between control-plane polls
+    #[arg(
+        long = "control-plane-interval",
+        default_value_t = zentinel_proxy::control_plane::DEFAULT_POLL_INTERVAL.as_secs()
+    )]
+    control_plane_interval: u64,
+
+    /// Check agents, upstreams and TLS material before binding listeners
+    #[arg(long = "preflight")]
+    preflight: bool,
+
+    /// Fail startup when a preflight check fails (implies --preflight)
+    #[arg(long = "strict")]
+    strict: bool,
+
+    /// Also connect to upstream targets during preflight, not just resolve
+    /// them (implies --preflight)
+    #[arg(long = "probe-upstreams")]
+    probe_upstreams: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -110,6 +156,117 @@ enum Commands {
 
     /// Manage bundled agents (install, status, update)
     Bundle(BundleArgs),
+
+    /// Run a self-hosted bundle registry for air-gapped installs
+    Registry(RegistryArgs),
+
+    /// Generate or convert configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Manage agent processes
+    Agents {
+        #[command(subcommand)]
+        command: AgentsCommand,
+    },
+
+    /// Start or stop a traffic capture on a running proxy
+    Capture {
+        /// Admin listener of the running proxy
+        #[arg(long = "admin", default_value = "http://127.0.0.1:9090")]
+        admin: String,
+
+        /// Route whose requests are captured
+        #[arg(long = "route", required_unless_present = "stop")]
+        route: Option<String>,
+
+        /// Share of the route's requests to capture, e.g. `1%`
+        #[arg(long = "sample", default_value = "100%")]
+        sample: String,
+
+        /// File name in the configured capture directory
+        #[arg(long = "out", required_unless_present = "stop")]
+        out: Option<String>,
+
+        /// Stop after this many requests
+        #[arg(long = "max-requests")]
+        max_requests: Option<u64>,
+
+        /// Stop the running capture instead of starting one
+        #[arg(long = "stop", conflicts_with_all = ["route", "out", "max_requests"])]
+        stop: bool,
+    },
+
+    /// Replay a capture file against a target
+    Replay {
+        /// Capture file written by `zentinel capture`
+        file: std::path::PathBuf,
+
+        /// Base URL requests are sent to
+        #[arg(long = "target")]
+        target: String,
+
+        /// Requests per second, e.g. `100rps` (default: as fast as possible)
+        #[arg(long = "rate")]
+        rate: Option<String>,
+
+        /// Requests in flight at once
+        #[arg(long = "concurrency", default_value_t = 32)]
+        concurrency: usize,
+
+        /// Timeout per request in seconds
+        #[arg(long = "timeout", default_value_t = 30)]
+        timeout_secs: u64,
+    },
+}
+
+/// Configuration subcommands
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Generate routes from an OpenAPI 3.x document (JSON or YAML)
+    FromOpenapi {
+        /// OpenAPI document
+        spec: std::path::PathBuf,
+
+        /// Prefix of the generated route and filter IDs (default: file name)
+        #[arg(long = "name")]
+        name: Option<String>,
+
+        /// Upstream the routes forward to (default: `<name>-backend`)
+        #[arg(long = "upstream")]
+        upstream: Option<String>,
+
+        /// Don't attach a schema-validate filter to the routes
+        #[arg(long = "no-validate")]
+        no_validate: bool,
+
+        /// Serve the document at this path, e.g. `/openapi.json`
+        #[arg(long = "serve-spec", conflicts_with = "no_validate")]
+        serve_spec: Option<String>,
+
+        /// Write the configuration to a file instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Print the JSON Schema of the configuration
+    Schema {
+        /// Write the schema to a file instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+/// Agent process subcommands
+#[derive(Subcommand, Debug)]
+enum AgentsCommand {
+    /// Run agents configured with a `command` without the proxy
+    Run {
+        /// Configuration file path
+        #[arg(short = 'c', long = "config")]
+        config: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -126,12 +283,35 @@ fn main() -> Result<()> {
         return test_config(cli.config.as_deref());
     }
 
+    let preflight =
+        (cli.preflight || cli.strict || cli.probe_upstreams).then(|| PreflightOptions {
+            strict: cli.strict,
+            probe_upstreams: cli.probe_upstreams,
+            ..Default::default()
+        });
+
+    let control_plane = cli.control_plane.map(|url| ControlPlaneOptions {
+        url,
+        node_id: cli
+            .control_plane_node_id
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .unwrap_or_else(|| "zentinel".to_string()),
+        token: cli.control_plane_token,
+        interval: std::time::Duration::from_secs(cli.control_plane_interval.max(1)),
+    });
+
     // Handle subcommands
     match cli.command {
         Some(Commands::Test { config }) => test_config(config.as_deref().or(cli.config.as_deref())),
-        Some(Commands::Run { config }) => {
-            run_server(config.or(cli.config), cli.verbose, cli.daemon, cli.upgrade)
-        }
+        Some(Commands::Run { config }) => run_server(
+            config.or(cli.config),
+            cli.verbose,
+            cli.daemon,
+            cli.upgrade,
+            cli.gateway_controller,
+            control_plane,
+            preflight,
+        ),
         Some(Commands::Validate {
             config,
             skip_network,
@@ -150,11 +330,78 @@ fn main() -> Result<()> {
                 .with_target(false)
                 .with_level(true)
                 .init();
+            // Downloads honour the config file's outbound proxy, if one is given
+            if let Some(path) = cli.config.as_deref() {
+                match Config::from_file(path) {
+                    Ok(config) => {
+                        zentinel_proxy::outbound::configure(&config.server.outbound_proxy)
+                    }
+                    Err(e) => tracing::warn!(
+                        config = %path,
+                        error = %e,
+                        "Failed to load configuration, using environment proxy settings"
+                    ),
+                }
+            }
             run_bundle_command(args)
         }
+        Some(Commands::Registry(args)) => {
+            tracing_subscriber::fmt()
+                .with_target(false)
+                .with_level(true)
+                .init();
+            run_registry_command(args)
+        }
+        Some(Commands::Config {
+            command:
+                ConfigCommand::FromOpenapi {
+                    spec,
+                    name,
+                    upstream,
+                    no_validate,
+                    serve_spec,
+                    output,
+                },
+        }) => config_from_openapi(
+            &spec,
+            name,
+            upstream,
+            !no_validate,
+            serve_spec,
+            output.as_deref(),
+        ),
+        Some(Commands::Config {
+            command: ConfigCommand::Schema { output },
+        }) => config_schema(output.as_deref()),
+        Some(Commands::Agents {
+            command: AgentsCommand::Run { config },
+        }) => run_agents(config.as_deref().or(cli.config.as_deref())),
+        Some(Commands::Capture {
+            admin,
+            route,
+            sample,
+            out,
+            max_requests,
+            stop,
+        }) => run_capture(&admin, route, &sample, out, max_requests, stop),
+        Some(Commands::Replay {
+            file,
+            target,
+            rate,
+            concurrency,
+            timeout_secs,
+        }) => run_replay(&file, target, rate.as_deref(), concurrency, timeout_secs),
         None => {
             // Default: run the server
-            run_server(cli.config, cli.verbose, cli.daemon, cli.upgrade)
+            run_server(
+                cli.config,
+                cli.verbose,
+                cli.daemon,
+                cli.upgrade,
+                cli.gateway_controller,
+                control_plane,
+                preflight,
+            )
         }
     }
 }
@@ -355,6 +602,211 @@ fn lint_config(config_path: Option<&str>) -> Result<()> {
     }
 }
 
+/// Start or stop a capture through the admin `capture` endpoint
+fn run_capture(
+    admin: &str,
+    route: Option<String>,
+    sample: &str,
+    out: Option<String>,
+    max_requests: Option<u64>,
+    stop: bool,
+) -> Result<()> {
+    let mut query = Vec::new();
+    if stop {
+        query.push(("action", "stop".to_string()));
+    } else {
+        let sample = zentinel_proxy::capture::parse_sample(sample).ok_or_else(|| {
+            anyhow::anyhow!("Invalid sample '{}', expected a percentage like 1%", sample)
+        })?;
+        query.push(("action", "start".to_string()));
+        query.push(("route", route.unwrap_or_default()));
+        query.push(("sample", sample.to_string()));
+        query.push(("out", out.unwrap_or_default()));
+        if let Some(max) = max_requests {
+            query.push(("max-requests", max.to_string()));
+        }
+    }
+
+    let url = format!("{}/admin/capture", admin.trim_end_matches('/'));
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create runtime")?;
+    let (status, body) = runtime.block_on(async {
+        let response = reqwest::Client::new()
+            .post(&url)
+            .query(&query)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach admin endpoint {}", url))?;
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Admin endpoint returned an invalid response")?;
+        anyhow::Ok((status, body))
+    })?;
+
+    if !status.is_success() {
+        let error = body["action"]["error"].as_str().unwrap_or("unknown error");
+        anyhow::bail!("Capture request failed ({}): {}", status, error);
+    }
+
+    match body.get("session").filter(|s| !s.is_null()) {
+        Some(session) => println!(
+            "Capturing {}% of route '{}' into {}",
+            session["sample_percent"],
+            session["route"].as_str().unwrap_or_default(),
+            session["file"].as_str().unwrap_or_default()
+        ),
+        None => println!("Capture stopped"),
+    }
+    Ok(())
+}
+
+/// Replay a capture file and print a summary
+/// Generate routes from an OpenAPI document (`zentinel config from-openapi`)
+fn config_from_openapi(
+    spec: &std::path::Path,
+    name: Option<String>,
+    upstream: Option<String>,
+    validate: bool,
+    serve_spec: Option<String>,
+    output: Option<&std::path::Path>,
+) -> Result<()> {
+    use zentinel_proxy::openapi_import::{generate, load_document, OpenApiImportOptions};
+
+    if serve_spec.as_ref().is_some_and(|p| !p.starts_with('/')) {
+        anyhow::bail!("--serve-spec must be a path starting with '/'");
+    }
+
+    let document = load_document(spec)?;
+    let name = name.unwrap_or_else(|| {
+        spec.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "api".to_string())
+    });
+    // The filter loads the document at runtime, so reference it absolutely
+    let schema = validate.then(|| {
+        std::fs::canonicalize(spec)
+            .unwrap_or_else(|_| spec.to_path_buf())
+            .display()
+            .to_string()
+    });
+    let options = OpenApiImportOptions {
+        upstream: upstream.unwrap_or_else(|| format!("{}-backend", name)),
+        name,
+        schema,
+        serve_spec,
+    };
+
+    let kdl = generate(&document, &options)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, kdl)
+                .with_context(|| format!("Failed to write configuration to {:?}", path))?;
+            eprintln!("Wrote routes for {:?} to {:?}", spec, path);
+        }
+        None => print!("{}", kdl),
+    }
+    Ok(())
+}
+
+/// Print the configuration JSON Schema (`zentinel config schema`)
+fn config_schema(output: Option<&std::path::Path>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&zentinel_config::config_schema())?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, schema + "\n")
+                .with_context(|| format!("Failed to write schema to {:?}", path))?;
+            eprintln!(
+                "Wrote configuration schema {} to {:?}",
+                zentinel_config::CURRENT_SCHEMA_VERSION,
+                path
+            );
+        }
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
+
+fn run_replay(
+    file: &std::path::Path,
+    target: String,
+    rate: Option<&str>,
+    concurrency: usize,
+    timeout_secs: u64,
+) -> Result<()> {
+    use zentinel_proxy::replay::{parse_rate, replay_file, ReplayOptions};
+
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_level(true)
+        .init();
+
+    let rate = rate
+        .map(|rate| {
+            parse_rate(rate)
+                .ok_or_else(|| anyhow::anyhow!("Invalid rate '{}', expected e.g. 100rps", rate))
+        })
+        .transpose()?;
+    let options = ReplayOptions {
+        target,
+        rate,
+        concurrency,
+        timeout: std::time::Duration::from_secs(timeout_secs),
+    };
+
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create runtime")?;
+    let summary = runtime.block_on(replay_file(file, &options))?;
+
+    println!(
+        "Replayed {} request(s) against {}",
+        summary.sent, options.target
+    );
+    println!("  status matched:    {}", summary.status_matched);
+    println!("  status mismatched: {}", summary.status_mismatched);
+    println!("  errors:            {}", summary.errors);
+    if summary.skipped > 0 {
+        println!("  skipped lines:     {}", summary.skipped);
+    }
+    Ok(())
+}
+
+/// Run supervised agents in the foreground until interrupted
+fn run_agents(config_path: Option<&str>) -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_level(true)
+        .init();
+
+    let path = config_path.context("A configuration file is required (--config)")?;
+    let config = Config::from_file(path).context("Failed to load configuration file")?;
+
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let Some(supervisor) = AgentSupervisor::start(&config.agents) else {
+            anyhow::bail!("No agent in {} has a command to run", path);
+        };
+
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
+        tokio::select! {
+            _ = supervisor.wait() => {
+                info!("All supervised agents have exited");
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Received SIGINT, stopping agents");
+                supervisor.shutdown().await;
+            }
+            _ = sigterm.recv() => {
+                info!("Received SIGTERM, stopping agents");
+                supervisor.shutdown().await;
+            }
+        }
+
+        Ok(())
+    })
+}
+
 /// State produced by ACME initialization, used to wire components into the proxy
 struct AcmeState {
     /// Challenge manager for HTTP-01 challenge handling
@@ -369,23 +821,32 @@ struct AcmeState {
 /// 1. Creates storage, client, and challenge manager for each ACME configuration
 /// 2. Initializes (or loads) the ACME account with Let's Encrypt
 /// 3. Obtains initial certificates if they don't exist yet
-/// 4. Returns the ACME state for wiring into the proxy and background schedulers
+/// 4. Registers a hot-reloadable SNI resolver (with OCSP stapling) per listener
+///    so renewed certificates are swapped in without a restart
+/// 5. Returns the ACME state for wiring into the proxy and background schedulers
 ///
 /// For HTTP-01 challenges during initial issuance, a temporary HTTP server is
 /// spawned to serve challenge responses (since Pingora isn't running yet).
 async fn initialize_acme(
     config: &Config,
-    sni_resolver: Option<Arc<HotReloadableSniResolver>>,
+    cert_reloader: &CertificateReloader,
 ) -> Result<Option<AcmeState>, AcmeError> {
     // Collect all ACME configurations from listeners and SNI blocks
-    let mut acme_configs: Vec<(String, AcmeConfig)> = Vec::new();
+    let mut acme_configs: Vec<(String, &zentinel_config::ListenerConfig, AcmeConfig)> = Vec::new();
 
     for listener in &config.listeners {
-        if listener.protocol == zentinel_config::ListenerProtocol::Https {
+        if matches!(
+            listener.protocol,
+            zentinel_config::ListenerProtocol::Https | zentinel_config::ListenerProtocol::Http3
+        ) {
             if let Some(ref tls) = listener.tls {
                 // Root-level ACME
                 if let Some(ref acme) = tls.acme {
-                    acme_configs.push((format!("listener '{}' (root)", listener.id), acme.clone()));
+                    acme_configs.push((
+                        format!("listener '{}' (root)", listener.id),
+                        listener,
+                        acme.clone(),
+                    ));
                 }
 
                 // SNI-level ACME
@@ -393,6 +854,7 @@ async fn initialize_acme(
                     if let Some(ref acme) = sni.acme {
                         acme_configs.push((
                             format!("listener '{}' (sni cert #{})", listener.id, i),
+                            listener,
                             acme.clone(),
                         ));
                     }
@@ -414,7 +876,7 @@ async fn initialize_acme(
     let challenge_manager = Arc::new(ChallengeManager::new());
     let mut schedulers = Vec::new();
 
-    for (description, acme_config) in acme_configs {
+    for (description, listener, acme_config) in acme_configs {
         info!(
             source = %description,
             domains = ?acme_config.domains,
@@ -434,7 +896,7 @@ async fn initialize_acme(
         let mut scheduler = RenewalScheduler::new(
             Arc::clone(&acme_client),
             Arc::clone(&challenge_manager),
-            sni_resolver.clone(),
+            None,
         );
 
         // If DNS-01, set up DNS challenge manager
@@ -524,9 +986,26 @@ async fn initialize_acme(
             }
         }
 
-        schedulers.push(scheduler);
+        schedulers.push((listener, scheduler));
     }
 
+    // Register one hot-reloadable resolver per listener once all initial
+    // certificates exist, and share it with that listener's schedulers
+    let schedulers = schedulers
+        .into_iter()
+        .map(|(listener, scheduler)| {
+            let resolver = cert_reloader.get(&listener.id).or_else(|| {
+                let resolver = build_tls_resolver(listener)?;
+                cert_reloader.register(&listener.id, Arc::clone(&resolver));
+                Some(resolver)
+            });
+            match resolver {
+                Some(resolver) => scheduler.with_sni_resolver(resolver),
+                None => scheduler,
+            }
+        })
+        .collect();
+
     Ok(Some(AcmeState {
         challenge_manager,
         schedulers,
@@ -539,7 +1018,17 @@ fn run_server(
     verbose: bool,
     daemon: bool,
     upgrade: bool,
+    gateway_controller: bool,
+    control_plane: Option<ControlPlaneOptions>,
+    preflight: Option<PreflightOptions>,
 ) -> Result<()> {
+    #[cfg(not(feature = "gateway-api"))]
+    if gateway_controller {
+        anyhow::bail!(
+            "--gateway-controller requires zentinel to be built with the `gateway-api` feature"
+        );
+    }
+
     // Initialize logging based on verbose flag
     let log_level = if verbose { "debug" } else { "info" };
     tracing_subscriber::fmt()
@@ -594,9 +1083,41 @@ fn run_server(
     let mut proxy =
         runtime.block_on(async { ZentinelProxy::new(effective_config_path.as_deref()).await })?;
 
+    // Preflight runs before anything binds a socket
+    if let Some(options) = preflight {
+        let config = proxy.config_manager.current();
+        let report = runtime.block_on(async {
+            let agents = proxy.agent_manager().agent_health().await;
+            run_preflight(&config, &agents, &options).await
+        });
+        report.log();
+        if !report.passed() {
+            if options.strict {
+                let failed: Vec<_> = report
+                    .failures
+                    .iter()
+                    .map(|f| f.component.as_str())
+                    .collect();
+                anyhow::bail!("Preflight failed for: {}", failed.join(", "));
+            }
+            warn!(
+                failures = report.failures.len(),
+                "Starting with components that failed preflight"
+            );
+            proxy.health_probes().record_preflight(&report.failures);
+        }
+    }
+
     // Get config manager for reload operations
     let config_manager = proxy.config_manager.clone();
 
+    // Supervised agents are stopped by the signal handler on shutdown
+    let agent_supervisor = proxy.agent_supervisor.clone();
+
+    // Agents and upstreams of forward-proxy and TCP listeners
+    let agent_manager = proxy.agent_manager();
+    let upstream_pools = proxy.upstream_pools();
+
     // Get initial config for server setup
     let config = proxy.config_manager.current();
 
@@ -629,7 +1150,7 @@ fn run_server(
 
     // Initialize ACME if any listener has it configured
     let acme_state = runtime
-        .block_on(async { initialize_acme(&config, None).await })
+        .block_on(async { initialize_acme(&config, &config_manager.cert_reloader()).await })
         .context("ACME initialization failed")?;
 
     // Wire ACME components into the proxy
@@ -642,6 +1163,11 @@ fn run_server(
             .collect();
     }
 
+    // Register remaining TLS listeners for certificate hot-reload (file
+    // changes, SIGHUP and config reloads)
+    let cert_reloader = config_manager.cert_reloader();
+    zentinel_proxy::listeners::register_tls_resolvers(&config.listeners, &cert_reloader);
+
     // Initialize OpenTelemetry tracer if configured
     if let Some(ref tracing_config) = config.observability.tracing {
         match zentinel_proxy::otel::init_tracer(tracing_config) {
@@ -718,145 +1244,52 @@ fn run_server(
         .filter_map(|l| l.keepalive_max_requests)
         .min();
 
-    // Create proxy service with server options (Pingora 0.8.0 builder pattern)
-    let mut server_options = pingora_core::apps::HttpServerOptions::default();
-    server_options.keepalive_request_limit = keepalive_request_limit;
-    let mut proxy_service = pingora_proxy::ProxyServiceBuilder::new(&server.configuration, proxy)
-        .name("Zentinel Proxy")
-        .server_options(server_options)
-        .build();
+    // Create proxy services with server options (Pingora 0.8.0 builder pattern)
+    // Only the internal sockets behind HTTPS acceptors accept h2c, since
+    // the acceptors hand decrypted HTTP/2 to Pingora as cleartext
+    let server_options = |h2c| {
+        let mut server_options = pingora_core::apps::HttpServerOptions::default();
+        server_options.keepalive_request_limit = keepalive_request_limit;
+        server_options.h2c = h2c;
+        server_options
+    };
+    let mut proxy_service =
+        pingora_proxy::ProxyServiceBuilder::new(&server.configuration, proxy.clone())
+            .name("Zentinel Proxy")
+            .server_options(server_options(false))
+            .build();
+    let mut internal_service =
+        pingora_proxy::ProxyServiceBuilder::new(&server.configuration, proxy)
+            .name("Zentinel Proxy (internal)")
+            .server_options(server_options(true))
+            .build();
 
     // Configure listening addresses from config
-    for listener in &config.listeners {
-        match listener.protocol {
-            zentinel_config::ListenerProtocol::Http => {
-                proxy_service.add_tcp(&listener.address);
-                info!("HTTP listening on: {}", listener.address);
-            }
-            zentinel_config::ListenerProtocol::Https => {
-                match &listener.tls {
-                    Some(tls_config) => {
-                        // Determine certificate paths: manual or ACME-managed
-                        let (cert_path, key_path) = if let (Some(ref cert), Some(ref key)) =
-                            (&tls_config.cert_file, &tls_config.key_file)
-                        {
-                            // Manual certificates specified
-                            (cert.clone(), key.clone())
-                        } else if let Some(ref acme_config) = tls_config.acme {
-                            // ACME-managed certificates
-                            let acme_storage = &acme_config.storage;
-                            let primary_domain = acme_config
-                                .domains
-                                .first()
-                                .ok_or_else(|| {
-                                    error!(
-                                        listener_id = %listener.id,
-                                        "ACME configuration has no domains"
-                                    );
-                                })
-                                .unwrap_or(&"default".to_string())
-                                .clone();
-
-                            let cert_path = acme_storage
-                                .join("domains")
-                                .join(&primary_domain)
-                                .join("cert.pem");
-                            let key_path = acme_storage
-                                .join("domains")
-                                .join(&primary_domain)
-                                .join("key.pem");
-
-                            // If certs still don't exist after ACME init, something went wrong
-                            if !cert_path.exists() || !key_path.exists() {
-                                error!(
-                                    listener_id = %listener.id,
-                                    address = %listener.address,
-                                    domains = ?acme_config.domains,
-                                    cert_path = %cert_path.display(),
-                                    "ACME certificate files not found after initialization"
-                                );
-                                continue;
-                            }
-
-                            (cert_path, key_path)
-                        } else {
-                            error!(
-                                listener_id = %listener.id,
-                                "TLS configuration requires either cert-file/key-file or acme block"
-                            );
-                            continue;
-                        };
-
-                        let cert_path_str = cert_path.to_string_lossy();
-                        let key_path_str = key_path.to_string_lossy();
-
-                        // Validate certificate files exist
-                        if !cert_path.exists() {
-                            error!(
-                                listener_id = %listener.id,
-                                cert_file = %cert_path_str,
-                                "TLS certificate file not found"
-                            );
-                            continue;
-                        }
-                        if !key_path.exists() {
-                            error!(
-                                listener_id = %listener.id,
-                                key_file = %key_path_str,
-                                "TLS key file not found"
-                            );
-                            continue;
-                        }
-
-                        // TODO: Once the Pingora fork's TlsSettings supports accepting
-                        // a pre-built rustls::ServerConfig, use tls::build_server_config()
-                        // here to apply cipher_suites, min/max_version, and session_resumption.
-                        // Currently Pingora's TlsSettings::build() creates its own ServerConfig
-                        // with hardcoded defaults, ignoring our TLS hardening settings.
-                        let mut tls_settings =
-                            match pingora::listeners::tls::TlsSettings::intermediate(
-                                &cert_path_str,
-                                &key_path_str,
-                            ) {
-                                Ok(s) => s,
-                                Err(e) => {
-                                    error!(
-                                        listener_id = %listener.id,
-                                        error = %e,
-                                        "Failed to create TLS settings"
-                                    );
-                                    continue;
-                                }
-                            };
-                        tls_settings.enable_h2();
-                        proxy_service.add_tls_with_settings(&listener.address, None, tls_settings);
-                        info!(
-                            listener_id = %listener.id,
-                            address = %listener.address,
-                            cert_file = %cert_path_str,
-                            min_tls_version = ?tls_config.min_version,
-                            client_auth = tls_config.client_auth,
-                            acme_enabled = tls_config.acme.is_some(),
-                            "HTTPS (h2+http/1.1) listening on: {}", listener.address
-                        );
-                    }
-                    None => {
-                        error!(
-                            listener_id = %listener.id,
-                            address = %listener.address,
-                            "HTTPS listener requires TLS configuration"
-                        );
-                    }
-                }
-            }
-            _ => {
-                warn!("Unsupported protocol: {:?}", listener.protocol);
-            }
-        }
-    }
+    zentinel_proxy::listeners::add_listeners(
+        &mut proxy_service,
+        &mut internal_service,
+        &config.listeners,
+        &cert_reloader,
+        config.server.max_connections,
+        runtime.handle(),
+    );
+    zentinel_proxy::forward_proxy::spawn_listeners(
+        &config.listeners,
+        config_manager.clone(),
+        agent_manager.clone(),
+        runtime.handle(),
+    );
+    zentinel_proxy::stream_proxy::spawn_listeners(
+        &config.listeners,
+        config_manager.clone(),
+        agent_manager,
+        upstream_pools,
+        runtime.handle(),
+    );
 
-    // Add proxy service to server
+    // Add proxy services to server
     server.add_service(proxy_service);
+    server.add_service(internal_service);
 
     // Enable auto-reload file watching if configured
     let auto_reload_enabled = config.server.auto_reload;
@@ -875,6 +1308,9 @@ fn run_server(
         warn!("Auto-reload requires a config file path");
     }
 
+    // Watch certificate files (e.g. cert-manager secrets) and hot-reload changes
+    runtime.spawn(CertificateWatcher::new(cert_reloader).run());
+
     // Spawn ACME renewal schedulers as background tasks
     if let Some(state) = acme_state {
         let scheduler_count = state.schedulers.len();
@@ -889,10 +1325,39 @@ fn run_server(
         );
     }
 
+    // Translate Kubernetes Gateway API resources into routes and upstreams
+    #[cfg(feature = "gateway-api")]
+    if gateway_controller {
+        let config_manager_gateway = config_manager.clone();
+        runtime.spawn(async move {
+            if let Err(e) =
+                zentinel_proxy::gateway_controller::run_gateway_controller(config_manager_gateway)
+                    .await
+            {
+                error!(error = %e, "Gateway API controller exited");
+            }
+        });
+    }
+
+    // Routes, upstreams and filters from a central control plane
+    if let Some(options) = control_plane {
+        let config_manager_control_plane = config_manager.clone();
+        runtime.spawn(async move {
+            if let Err(e) = zentinel_proxy::control_plane::run_control_plane_client(
+                options,
+                config_manager_control_plane,
+            )
+            .await
+            {
+                error!(error = %e, "Control plane client exited");
+            }
+        });
+    }
+
     // Spawn signal handler task in the runtime
     let signal_manager_clone = signal_manager.clone();
     runtime.spawn(async move {
-        run_signal_handler(signal_manager_clone, config_manager).await;
+        run_signal_handler(signal_manager_clone, config_manager, agent_supervisor).await;
     });
 
     info!("Zentinel proxy started successfully");
@@ -987,6 +1452,7 @@ fn create_default_config_file(path: &std::path::Path) -> Result<()> {
 async fn run_signal_handler(
     signal_manager: Arc<SignalManager>,
     config_manager: Arc<zentinel_proxy::ConfigManager>,
+    agent_supervisor: Option<Arc<AgentSupervisor>>,
 ) {
     loop {
         // Use spawn_blocking to wait for signals without blocking the async runtime
@@ -1011,6 +1477,10 @@ async fn run_signal_handler(
                 info!("Processing graceful shutdown request");
                 // Shutdown OpenTelemetry tracer to flush pending spans
                 zentinel_proxy::otel::shutdown_tracer();
+                // Stop supervised agents before exiting
+                if let Some(ref supervisor) = agent_supervisor {
+                    supervisor.shutdown().await;
+                }
                 // Note: Connection draining is handled by Pingora's internal mechanisms
                 // We give it a moment to start draining, then the signal thread will force exit
                 info!("Shutdown initiated, draining connections...");
@@ -0,0 +1,386 @@
+//! Runtime maintenance mode for routes and the whole proxy.
+//!
+//! [`MaintenanceManager`] holds the global and per-route maintenance flags
+//! that admins toggle through the `maintenance` builtin handler, so a route
+//! can be switched off without a config reload. Requests to a route in
+//! maintenance are answered with 503, `Retry-After` and the maintenance page
+//! before rate limiting, agents or upstream selection. Clients in
+//! `allow-ips`, and requests carrying `bypass-header` with the secret from
+//! `bypass-secret-env`, reach the route as usual. The bypass header is
+//! removed from every request so the secret is not forwarded.
+//!
+//! The flags survive config reloads; only the page and bypass rules are
+//! updated. With `state-file` set every change is written to disk and the
+//! flags are restored from it on startup, taking precedence over the
+//! `enabled` and `routes` settings of the config.
+
+use bytes::Bytes;
+use http::{header, HeaderMap, HeaderName, Response, StatusCode};
+use http_body_util::Full;
+use parking_lot::RwLock;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+use zentinel_config::{IpCidr, MaintenanceConfig};
+
+/// Requests answered with the maintenance page or let through by a bypass
+static MAINTENANCE_REQUESTS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_maintenance_requests_total",
+        "Requests to routes in maintenance, by outcome",
+        &["route", "outcome"]
+    )
+    .ok()
+});
+
+const DEFAULT_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>503 Service Unavailable</title>
+</head>
+<body>
+    <h1>Down for maintenance</h1>
+    <p>This service is temporarily unavailable for scheduled maintenance. Please try again later.</p>
+</body>
+</html>
+"#;
+
+/// Maintenance flags, as persisted to the state file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    /// Every proxied route is in maintenance
+    pub global: bool,
+    /// Routes in maintenance, sorted by ID
+    pub routes: Vec<String>,
+}
+
+/// Page and bypass rules derived from the config
+struct MaintenanceSettings {
+    retry_after_secs: u64,
+    page: Bytes,
+    allow_ips: Vec<IpCidr>,
+    bypass: Option<(HeaderName, String)>,
+    state_file: Option<PathBuf>,
+}
+
+impl MaintenanceSettings {
+    fn from_config(config: &MaintenanceConfig) -> Self {
+        let secret = config
+            .bypass_secret_env
+            .as_deref()
+            .and_then(|env| std::env::var(env).ok());
+        Self::with_bypass_secret(config, secret)
+    }
+
+    fn with_bypass_secret(config: &MaintenanceConfig, secret: Option<String>) -> Self {
+        let page = match config.page {
+            Some(ref path) => match std::fs::read(path) {
+                Ok(page) => Bytes::from(page),
+                Err(e) => {
+                    warn!(
+                        path = %path.display(),
+                        error = %e,
+                        "Failed to read maintenance page, using the built-in page"
+                    );
+                    Bytes::from_static(DEFAULT_PAGE.as_bytes())
+                }
+            },
+            None => Bytes::from_static(DEFAULT_PAGE.as_bytes()),
+        };
+
+        let bypass = match (&config.bypass_header, &config.bypass_secret_env) {
+            (Some(name), Some(env)) => {
+                let name = HeaderName::from_bytes(name.as_bytes()).ok();
+                match (name, secret) {
+                    (Some(name), Some(secret)) if !secret.is_empty() => Some((name, secret)),
+                    _ => {
+                        warn!(
+                            env = %env,
+                            "Maintenance bypass secret is not set, header bypass disabled"
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        Self {
+            retry_after_secs: config.retry_after_secs,
+            page,
+            allow_ips: config.allow_ips.clone(),
+            bypass,
+            state_file: config.state_file.clone(),
+        }
+    }
+}
+
+/// Global and per-route maintenance flags
+pub struct MaintenanceManager {
+    settings: RwLock<MaintenanceSettings>,
+    global: AtomicBool,
+    routes: RwLock<BTreeSet<String>>,
+    /// Fast path: false while no route is in maintenance
+    any_route: AtomicBool,
+}
+
+impl MaintenanceManager {
+    /// Create the manager, restoring flags from the state file if present.
+    pub fn new(config: &MaintenanceConfig) -> Self {
+        Self::with_settings(config, MaintenanceSettings::from_config(config))
+    }
+
+    fn with_settings(config: &MaintenanceConfig, settings: MaintenanceSettings) -> Self {
+        let state = settings
+            .state_file
+            .as_deref()
+            .and_then(load_state)
+            .unwrap_or_else(|| MaintenanceState {
+                global: config.enabled,
+                routes: config.routes.clone(),
+            });
+
+        if state.global || !state.routes.is_empty() {
+            info!(
+                global = state.global,
+                routes = ?state.routes,
+                "Starting with maintenance mode enabled"
+            );
+        }
+
+        let routes: BTreeSet<String> = state.routes.into_iter().collect();
+        Self {
+            settings: RwLock::new(settings),
+            global: AtomicBool::new(state.global),
+            any_route: AtomicBool::new(!routes.is_empty()),
+            routes: RwLock::new(routes),
+        }
+    }
+
+    /// Apply a new config. Flags set at runtime are kept.
+    pub fn reload(&self, config: &MaintenanceConfig) {
+        *self.settings.write() = MaintenanceSettings::from_config(config);
+    }
+
+    /// Whether requests to `route_id` get the maintenance page
+    pub fn is_active(&self, route_id: Option<&str>) -> bool {
+        if self.global.load(Ordering::Acquire) {
+            return true;
+        }
+        if !self.any_route.load(Ordering::Acquire) {
+            return false;
+        }
+        route_id.is_some_and(|id| self.routes.read().contains(id))
+    }
+
+    /// Whether the client may reach a route in maintenance
+    pub fn can_bypass(&self, client_ip: &str, headers: &HeaderMap) -> bool {
+        let settings = self.settings.read();
+
+        if let Ok(ip) = client_ip.parse::<IpAddr>() {
+            if settings.allow_ips.iter().any(|cidr| cidr.contains(ip)) {
+                return true;
+            }
+        }
+
+        match settings.bypass {
+            Some((ref name, ref secret)) => headers
+                .get(name)
+                .is_some_and(|value| value.as_bytes().ct_eq(secret.as_bytes()).into()),
+            None => false,
+        }
+    }
+
+    /// The bypass header, removed from requests so the secret never reaches
+    /// agents or upstreams
+    pub fn bypass_header(&self) -> Option<HeaderName> {
+        self.settings
+            .read()
+            .bypass
+            .as_ref()
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Record a request to a route in maintenance (`blocked` or `bypassed`)
+    pub fn record(&self, route_id: Option<&str>, outcome: &str) {
+        if let Some(counter) = MAINTENANCE_REQUESTS.as_ref() {
+            counter
+                .with_label_values(&[route_id.unwrap_or("unknown"), outcome])
+                .inc();
+        }
+    }
+
+    /// The 503 maintenance response
+    pub fn response(&self) -> Response<Full<Bytes>> {
+        let settings = self.settings.read();
+        Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .header(header::CONTENT_LENGTH, settings.page.len())
+            .header(header::RETRY_AFTER, settings.retry_after_secs)
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(Full::new(settings.page.clone()))
+            .expect("static response builder with valid headers cannot fail")
+    }
+
+    /// Turn global maintenance on or off.
+    ///
+    /// Returns an error if the new state could not be persisted; the flag
+    /// is changed in memory either way.
+    pub fn set_global(&self, enabled: bool) -> std::io::Result<()> {
+        self.global.store(enabled, Ordering::Release);
+        info!(enabled = enabled, "Global maintenance mode changed");
+        self.persist()
+    }
+
+    /// Turn maintenance on or off for one route.
+    ///
+    /// Returns an error if the new state could not be persisted; the flag
+    /// is changed in memory either way.
+    pub fn set_route(&self, route_id: &str, enabled: bool) -> std::io::Result<()> {
+        {
+            let mut routes = self.routes.write();
+            if enabled {
+                routes.insert(route_id.to_string());
+            } else {
+                routes.remove(route_id);
+            }
+            self.any_route.store(!routes.is_empty(), Ordering::Release);
+        }
+        info!(
+            route_id = %route_id,
+            enabled = enabled,
+            "Route maintenance mode changed"
+        );
+        self.persist()
+    }
+
+    /// Current flags
+    pub fn state(&self) -> MaintenanceState {
+        MaintenanceState {
+            global: self.global.load(Ordering::Acquire),
+            routes: self.routes.read().iter().cloned().collect(),
+        }
+    }
+
+    fn persist(&self) -> std::io::Result<()> {
+        let Some(path) = self.settings.read().state_file.clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(&self.state())?;
+        // Write then rename so a crash never leaves a truncated file
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &path)
+    }
+}
+
+fn load_state(path: &Path) -> Option<MaintenanceState> {
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Failed to read maintenance state");
+            return None;
+        }
+    };
+    match serde_json::from_slice(&content) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Ignoring invalid maintenance state");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_and_global_flags() {
+        let config = MaintenanceConfig {
+            routes: vec!["checkout".to_string()],
+            ..Default::default()
+        };
+        let manager = MaintenanceManager::new(&config);
+
+        assert!(manager.is_active(Some("checkout")));
+        assert!(!manager.is_active(Some("search")));
+        assert!(!manager.is_active(None));
+
+        manager.set_route("checkout", false).unwrap();
+        assert!(!manager.is_active(Some("checkout")));
+
+        manager.set_global(true).unwrap();
+        assert!(manager.is_active(Some("search")));
+        assert_eq!(
+            manager.state(),
+            MaintenanceState {
+                global: true,
+                routes: vec![]
+            }
+        );
+
+        let response = manager.response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "300");
+    }
+
+    #[test]
+    fn test_bypass() {
+        let config = MaintenanceConfig {
+            allow_ips: vec!["10.0.0.0/8".parse().unwrap()],
+            bypass_header: Some("X-Maintenance-Bypass".to_string()),
+            bypass_secret_env: Some("ZENTINEL_TEST_MAINTENANCE_SECRET".to_string()),
+            ..Default::default()
+        };
+        let settings = MaintenanceSettings::with_bypass_secret(&config, Some("s3cret".to_string()));
+        let manager = MaintenanceManager::with_settings(&config, settings);
+        let mut headers = HeaderMap::new();
+
+        assert!(manager.can_bypass("10.1.2.3", &headers));
+        assert!(!manager.can_bypass("192.0.2.1", &headers));
+
+        headers.insert("x-maintenance-bypass", "wrong".parse().unwrap());
+        assert!(!manager.can_bypass("192.0.2.1", &headers));
+        headers.insert("x-maintenance-bypass", "s3cret".parse().unwrap());
+        assert!(manager.can_bypass("192.0.2.1", &headers));
+        assert_eq!(
+            manager.bypass_header().as_ref().map(HeaderName::as_str),
+            Some("x-maintenance-bypass")
+        );
+
+        let settings = MaintenanceSettings::with_bypass_secret(&config, None);
+        let manager = MaintenanceManager::with_settings(&config, settings);
+        assert!(!manager.can_bypass("192.0.2.1", &headers));
+        assert!(manager.bypass_header().is_none());
+    }
+
+    #[test]
+    fn test_state_persisted_across_restarts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = MaintenanceConfig {
+            routes: vec!["from-config".to_string()],
+            state_file: Some(temp_dir.path().join("maintenance.json")),
+            ..Default::default()
+        };
+
+        let manager = MaintenanceManager::new(&config);
+        manager.set_route("from-config", false).unwrap();
+        manager.set_route("checkout", true).unwrap();
+
+        // The state file wins over the routes listed in the config
+        let restarted = MaintenanceManager::new(&config);
+        assert!(restarted.is_active(Some("checkout")));
+        assert!(!restarted.is_active(Some("from-config")));
+    }
+}
@@ -0,0 +1,1294 @@
+//! In-proxy rule engine for the `managed-rules` filter
+//!
+//! Loads a rule file written in a subset of the ModSecurity `SecRule`
+//! language, as used by the OWASP Core Rule Set, and evaluates it against
+//! requests without an external WAF agent:
+//!
+//! - Phase 1 rules run on the request line and headers. Phase 2 rules also
+//!   see the body: up to `max-body-bytes` of it is held back and inspected,
+//!   the rest is passed through. Requests without a body run phase 2 right
+//!   after phase 1.
+//! - Every matching rule adds its severity's score to the request's anomaly
+//!   score (CRITICAL 5, ERROR 4, WARNING 3, NOTICE 2; rules without a
+//!   severity count as CRITICAL). A request reaching the filter's threshold,
+//!   or matching a rule with the `deny` action, is blocked with 403.
+//!
+//! Supported variables are `REQUEST_METHOD`, `REQUEST_LINE`, `REQUEST_URI`,
+//! `REQUEST_FILENAME`, `QUERY_STRING`, `ARGS`, `ARGS_GET`, `ARGS_POST`,
+//! their `_NAMES` forms, `REQUEST_HEADERS`, `REQUEST_HEADERS_NAMES`,
+//! `REQUEST_COOKIES`, `REQUEST_COOKIES_NAMES` and `REQUEST_BODY`, with
+//! `:key` selectors and `!` exclusions. Operators are `@rx` (Rust regex
+//! syntax, no look-around or back-references), `@pm`, `@contains`,
+//! `@streq`, `@beginsWith`, `@endsWith`, `@within`, `@eq`, `@gt`, `@lt`,
+//! `@ge`, `@le` and `@unconditionalMatch`. `ARGS_POST` is read from
+//! URL-encoded form bodies only.
+//!
+//! Rules using anything else (chains, other variables, operators or
+//! transformations, regex selectors, phases 3-5) are skipped with a warning
+//! when the file is loaded. Directives other than `SecRule` are ignored.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "crs-lite" {
+//!         type "managed-rules"
+//!         rules "/etc/zentinel/rules/crs-subset.conf"
+//!         anomaly-threshold 5
+//!         max-body-bytes 131072
+//!     }
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use http::{HeaderMap, Method, Uri};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use regex::Regex;
+use serde_json::json;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use zentinel_config::{ManagedRulesFilter, ManagedRulesMode};
+
+/// Rule matches per filter and rule ID
+static MANAGED_RULE_MATCHES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_managed_rules_matches_total",
+        "Rule matches of managed-rules filters",
+        &["filter", "rule_id"]
+    )
+    .ok()
+});
+
+/// Requests over the anomaly threshold per filter and mode
+static MANAGED_RULE_ANOMALIES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_managed_rules_anomalies_total",
+        "Requests reaching the anomaly threshold of managed-rules filters",
+        &["filter", "mode"]
+    )
+    .ok()
+});
+
+/// Errors from loading a rule file
+#[derive(Debug, Error)]
+pub enum RuleLoadError {
+    /// The rule file could not be read
+    #[error("failed to read rule file '{path}': {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A directive is malformed
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+// =============================================================================
+// Rules
+// =============================================================================
+
+/// Rule severity, scored as in the OWASP Core Rule Set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    /// INFO, DEBUG and the syslog levels above CRITICAL: no score
+    Other,
+}
+
+impl Severity {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "CRITICAL" | "2" => Some(Self::Critical),
+            "ERROR" | "3" => Some(Self::Error),
+            "WARNING" | "4" => Some(Self::Warning),
+            "NOTICE" | "5" => Some(Self::Notice),
+            "EMERGENCY" | "ALERT" | "INFO" | "DEBUG" | "0" | "1" | "6" | "7" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// Anomaly score added by a match
+    pub fn score(&self) -> u32 {
+        match self {
+            Self::Critical => 5,
+            Self::Error => 4,
+            Self::Warning => 3,
+            Self::Notice => 2,
+            Self::Other => 0,
+        }
+    }
+}
+
+/// Which arguments an `ARGS` variable covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgSource {
+    All,
+    Get,
+    Post,
+}
+
+/// Request data a variable reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collection {
+    RequestMethod,
+    RequestLine,
+    RequestUri,
+    RequestFilename,
+    QueryString,
+    Args(ArgSource),
+    ArgsNames(ArgSource),
+    RequestHeaders,
+    RequestHeadersNames,
+    RequestCookies,
+    RequestCookiesNames,
+    RequestBody,
+}
+
+impl Collection {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "REQUEST_METHOD" => Self::RequestMethod,
+            "REQUEST_LINE" => Self::RequestLine,
+            "REQUEST_URI" | "REQUEST_URI_RAW" => Self::RequestUri,
+            "REQUEST_FILENAME" => Self::RequestFilename,
+            "QUERY_STRING" => Self::QueryString,
+            "ARGS" => Self::Args(ArgSource::All),
+            "ARGS_GET" => Self::Args(ArgSource::Get),
+            "ARGS_POST" => Self::Args(ArgSource::Post),
+            "ARGS_NAMES" => Self::ArgsNames(ArgSource::All),
+            "ARGS_GET_NAMES" => Self::ArgsNames(ArgSource::Get),
+            "ARGS_POST_NAMES" => Self::ArgsNames(ArgSource::Post),
+            "REQUEST_HEADERS" => Self::RequestHeaders,
+            "REQUEST_HEADERS_NAMES" => Self::RequestHeadersNames,
+            "REQUEST_COOKIES" => Self::RequestCookies,
+            "REQUEST_COOKIES_NAMES" => Self::RequestCookiesNames,
+            "REQUEST_BODY" => Self::RequestBody,
+            _ => return None,
+        })
+    }
+}
+
+/// A variable of a rule, optionally narrowed to one key
+#[derive(Debug, Clone)]
+struct Target {
+    collection: Collection,
+    /// Variable name as written, for match reports
+    name: String,
+    /// Key selector, matched case-insensitively
+    key: Option<String>,
+}
+
+impl Target {
+    fn selects(&self, key: &str) -> bool {
+        self.key
+            .as_ref()
+            .is_none_or(|k| k.eq_ignore_ascii_case(key))
+    }
+}
+
+/// Rule operator
+#[derive(Debug, Clone)]
+enum Operator {
+    Rx(Regex),
+    /// Lowercased phrases, matched case-insensitively
+    Pm(Vec<String>),
+    Contains(String),
+    StrEq(String),
+    BeginsWith(String),
+    EndsWith(String),
+    Within(String),
+    Eq(i64),
+    Gt(i64),
+    Lt(i64),
+    Ge(i64),
+    Le(i64),
+    UnconditionalMatch,
+}
+
+impl Operator {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let Some(spec) = spec.strip_prefix('@') else {
+            return Self::regex(spec);
+        };
+        let (name, arg) = spec.split_once(' ').unwrap_or((spec, ""));
+        let number = || {
+            arg.trim()
+                .parse::<i64>()
+                .map_err(|_| format!("@{name} needs a number, got '{arg}'"))
+        };
+        Ok(match name {
+            "rx" => Self::regex(arg)?,
+            "pm" => Self::Pm(arg.split_whitespace().map(str::to_lowercase).collect()),
+            "contains" => Self::Contains(arg.to_string()),
+            "streq" => Self::StrEq(arg.to_string()),
+            "beginsWith" => Self::BeginsWith(arg.to_string()),
+            "endsWith" => Self::EndsWith(arg.to_string()),
+            "within" => Self::Within(arg.to_string()),
+            "eq" => Self::Eq(number()?),
+            "gt" => Self::Gt(number()?),
+            "lt" => Self::Lt(number()?),
+            "ge" => Self::Ge(number()?),
+            "le" => Self::Le(number()?),
+            "unconditionalMatch" => Self::UnconditionalMatch,
+            other => return Err(format!("unsupported operator @{other}")),
+        })
+    }
+
+    fn regex(pattern: &str) -> Result<Self, String> {
+        Regex::new(pattern)
+            .map(Self::Rx)
+            .map_err(|e| format!("unsupported regex: {e}"))
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let number = || value.trim().parse::<i64>().ok();
+        match self {
+            Self::Rx(re) => re.is_match(value),
+            Self::Pm(phrases) => {
+                let value = value.to_lowercase();
+                phrases.iter().any(|p| value.contains(p.as_str()))
+            }
+            Self::Contains(s) => value.contains(s.as_str()),
+            Self::StrEq(s) => value == s,
+            Self::BeginsWith(s) => value.starts_with(s.as_str()),
+            Self::EndsWith(s) => value.ends_with(s.as_str()),
+            Self::Within(s) => s.contains(value),
+            Self::Eq(n) => number() == Some(*n),
+            Self::Gt(n) => number().is_some_and(|v| v > *n),
+            Self::Lt(n) => number().is_some_and(|v| v < *n),
+            Self::Ge(n) => number().is_some_and(|v| v >= *n),
+            Self::Le(n) => number().is_some_and(|v| v <= *n),
+            Self::UnconditionalMatch => true,
+        }
+    }
+}
+
+/// Transformation applied to values before the operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transform {
+    Lowercase,
+    Uppercase,
+    UrlDecode,
+    HtmlEntityDecode,
+    CompressWhitespace,
+    RemoveWhitespace,
+    RemoveNulls,
+    Trim,
+}
+
+impl Transform {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "lowercase" => Self::Lowercase,
+            "uppercase" => Self::Uppercase,
+            "urlDecode" | "urlDecodeUni" => Self::UrlDecode,
+            "htmlEntityDecode" => Self::HtmlEntityDecode,
+            "compressWhitespace" => Self::CompressWhitespace,
+            "removeWhitespace" => Self::RemoveWhitespace,
+            "removeNulls" => Self::RemoveNulls,
+            "trim" => Self::Trim,
+            _ => return None,
+        })
+    }
+
+    fn apply(&self, value: &str) -> String {
+        match self {
+            Self::Lowercase => value.to_lowercase(),
+            Self::Uppercase => value.to_uppercase(),
+            Self::UrlDecode => {
+                let plus_decoded = value.replace('+', " ");
+                String::from_utf8_lossy(&urlencoding::decode_binary(plus_decoded.as_bytes()))
+                    .into_owned()
+            }
+            Self::HtmlEntityDecode => html_escape::decode_html_entities(value).into_owned(),
+            Self::CompressWhitespace => value.split_whitespace().collect::<Vec<_>>().join(" "),
+            Self::RemoveWhitespace => value.chars().filter(|c| !c.is_whitespace()).collect(),
+            Self::RemoveNulls => value.replace('\0', ""),
+            Self::Trim => value.trim().to_string(),
+        }
+    }
+}
+
+/// A compiled `SecRule`
+#[derive(Debug, Clone)]
+struct Rule {
+    id: u64,
+    phase: u8,
+    targets: Vec<Target>,
+    exclusions: Vec<Target>,
+    operator: Operator,
+    negated: bool,
+    transforms: Vec<Transform>,
+    severity: Severity,
+    message: String,
+    /// Block on match regardless of the anomaly score
+    deny: bool,
+}
+
+impl Rule {
+    /// Name of the first variable value the rule matches
+    fn first_match(&self, data: &RequestData) -> Option<String> {
+        for target in &self.targets {
+            for (name, value) in data.values(target) {
+                if self.excluded(target.collection, &name) {
+                    continue;
+                }
+                let value = self
+                    .transforms
+                    .iter()
+                    .fold(Cow::Borrowed(value.as_ref()), |v, t| {
+                        Cow::Owned(t.apply(&v))
+                    });
+                if self.operator.matches(&value) != self.negated {
+                    return Some(if name.is_empty() {
+                        target.name.clone()
+                    } else {
+                        format!("{}:{}", target.name, name)
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn excluded(&self, collection: Collection, key: &str) -> bool {
+        !key.is_empty()
+            && self
+                .exclusions
+                .iter()
+                .any(|e| e.collection == collection && e.selects(key))
+    }
+}
+
+/// Outcome of parsing one `SecRule`
+enum ParsedRule {
+    Rule(Rule),
+    /// A valid rule this engine can't run
+    Unsupported {
+        id: u64,
+        reason: String,
+    },
+}
+
+// =============================================================================
+// Rule File Parsing
+// =============================================================================
+
+/// Join `\`-continued lines, dropping comments and blank lines
+fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (index, raw) in source.lines().enumerate() {
+        let trimmed = raw.trim();
+        if current.is_none() && (trimmed.is_empty() || trimmed.starts_with('#')) {
+            continue;
+        }
+        let (text, continued) = match trimmed.strip_suffix('\\') {
+            Some(text) => (text, true),
+            None => (trimmed, false),
+        };
+        let (_, line) = current.get_or_insert_with(|| (index + 1, String::new()));
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(text.trim());
+        if !continued {
+            lines.extend(current.take());
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+/// Split a directive into words and double-quoted strings (`\"` escapes a
+/// quote; other backslashes are kept for the regex)
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') if chars.peek() == Some(&'"') => {
+                        token.push('"');
+                        chars.next();
+                    }
+                    Some('"') => break,
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated quoted string".into()),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Split an action list on commas outside single quotes
+fn split_actions(actions: &str) -> Vec<(&str, &str)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in actions.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(&actions[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&actions[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|action| {
+            let (name, value) = action.split_once(':').unwrap_or((action, ""));
+            (name.trim(), value.trim().trim_matches('\''))
+        })
+        .collect()
+}
+
+/// Split variables into targets and exclusions; errors name what is
+/// unsupported
+fn parse_targets(spec: &str) -> Result<(Vec<Target>, Vec<Target>), String> {
+    let mut targets = Vec::new();
+    let mut exclusions = Vec::new();
+
+    for part in spec.split('|') {
+        let (excluded, part) = match part.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, part),
+        };
+        if part.starts_with('&') {
+            return Err(format!("variable count '{part}'"));
+        }
+        let (name, key) = match part.split_once(':') {
+            Some((name, key)) => (name, Some(key.trim_matches('\''))),
+            None => (part, None),
+        };
+        if key.is_some_and(|k| k.starts_with('/')) {
+            return Err(format!("regex selector in '{part}'"));
+        }
+        let collection = Collection::parse(name).ok_or_else(|| format!("variable '{name}'"))?;
+        let target = Target {
+            collection,
+            name: name.to_ascii_uppercase(),
+            key: key.map(str::to_string),
+        };
+        if excluded {
+            exclusions.push(target);
+        } else {
+            targets.push(target);
+        }
+    }
+
+    if targets.is_empty() {
+        return Err("no variables".into());
+    }
+    Ok((targets, exclusions))
+}
+
+/// Parse `SecRule VARIABLES "OPERATOR" "ACTIONS"`; errors are for
+/// malformed rules
+fn parse_rule(tokens: &[String]) -> Result<ParsedRule, String> {
+    let [_, variables, operator, rest @ ..] = tokens else {
+        return Err("SecRule needs variables and an operator".into());
+    };
+    let actions = rest.first().map(String::as_str).unwrap_or_default();
+
+    let mut id = None;
+    let mut phase = 2;
+    let mut severity = Severity::Critical;
+    let mut message = String::new();
+    let mut transforms = Vec::new();
+    let mut deny = false;
+    let mut unsupported = None;
+
+    for (name, value) in split_actions(actions) {
+        match name {
+            "id" => {
+                id = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("invalid rule id '{value}'"))?,
+                )
+            }
+            "phase" => {
+                phase = match value {
+                    "1" => 1,
+                    "2" | "request" => 2,
+                    other => {
+                        unsupported.get_or_insert(format!("phase {other}"));
+                        2
+                    }
+                }
+            }
+            "severity" => {
+                severity =
+                    Severity::parse(value).ok_or_else(|| format!("invalid severity '{value}'"))?;
+            }
+            "msg" => message = value.to_string(),
+            "t" if value == "none" => transforms.clear(),
+            "t" => match Transform::parse(value) {
+                Some(t) => transforms.push(t),
+                None => {
+                    unsupported.get_or_insert(format!("transformation '{value}'"));
+                }
+            },
+            "deny" | "drop" => deny = true,
+            "chain" => {
+                unsupported.get_or_insert("chained rules".to_string());
+            }
+            // Logging, flow and variable actions have no effect here
+            _ => {}
+        }
+    }
+
+    let id = id.ok_or("SecRule without an 'id' action")?;
+    if let Some(reason) = unsupported {
+        return Ok(ParsedRule::Unsupported { id, reason });
+    }
+    let (targets, exclusions) = match parse_targets(variables) {
+        Ok(parsed) => parsed,
+        Err(reason) => return Ok(ParsedRule::Unsupported { id, reason }),
+    };
+    let (negated, operator) = match operator.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, operator.as_str()),
+    };
+    let operator = match Operator::parse(operator) {
+        Ok(operator) => operator,
+        Err(reason) => return Ok(ParsedRule::Unsupported { id, reason }),
+    };
+
+    Ok(ParsedRule::Rule(Rule {
+        id,
+        phase,
+        targets,
+        exclusions,
+        operator,
+        negated,
+        transforms,
+        severity,
+        message,
+        deny,
+    }))
+}
+
+// =============================================================================
+// Request Data
+// =============================================================================
+
+/// Request fields visible to rules
+#[derive(Debug, Clone, Default)]
+struct RequestData {
+    method: String,
+    uri: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    cookies: Vec<(String, String)>,
+    args_get: Vec<(String, String)>,
+    args_post: Vec<(String, String)>,
+    body: String,
+    form_body: bool,
+}
+
+impl RequestData {
+    fn new(method: &Method, uri: &Uri, headers: &HeaderMap) -> Self {
+        let query = uri.query().unwrap_or_default().to_string();
+        let headers: Vec<(String, String)> = headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let cookies = headers
+            .iter()
+            .filter(|(name, _)| name == "cookie")
+            .flat_map(|(_, value)| value.split(';'))
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let name = name.trim();
+                (!name.is_empty()).then(|| (name.to_string(), value.trim().to_string()))
+            })
+            .collect();
+        let form_body = headers.iter().any(|(name, value)| {
+            name == "content-type"
+                && value
+                    .to_ascii_lowercase()
+                    .starts_with("application/x-www-form-urlencoded")
+        });
+
+        Self {
+            method: method.as_str().to_string(),
+            uri: uri
+                .path_and_query()
+                .map_or_else(|| uri.path().to_string(), |pq| pq.as_str().to_string()),
+            path: uri.path().to_string(),
+            args_get: url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect(),
+            query,
+            headers,
+            cookies,
+            args_post: Vec::new(),
+            body: String::new(),
+            form_body,
+        }
+    }
+
+    fn set_body(&mut self, body: &[u8]) {
+        self.body = String::from_utf8_lossy(body).into_owned();
+        if self.form_body {
+            self.args_post = url::form_urlencoded::parse(body).into_owned().collect();
+        }
+    }
+
+    fn args(&self, source: ArgSource) -> impl Iterator<Item = &(String, String)> {
+        let get = matches!(source, ArgSource::All | ArgSource::Get);
+        let post = matches!(source, ArgSource::All | ArgSource::Post);
+        self.args_get
+            .iter()
+            .filter(move |_| get)
+            .chain(self.args_post.iter().filter(move |_| post))
+    }
+
+    /// Values of a target as `(key, value)`; the key is empty for scalar
+    /// variables
+    fn values(&self, target: &Target) -> Vec<(String, Cow<'_, str>)> {
+        let scalar = match target.collection {
+            Collection::RequestMethod => &self.method,
+            Collection::RequestUri => &self.uri,
+            Collection::RequestFilename => &self.path,
+            Collection::QueryString => &self.query,
+            Collection::RequestBody => &self.body,
+            Collection::RequestLine => {
+                let line = format!("{} {}", self.method, self.uri);
+                return vec![(String::new(), Cow::Owned(line))];
+            }
+            Collection::Args(source) => return select(target, self.args(source), false),
+            Collection::ArgsNames(source) => return select(target, self.args(source), true),
+            Collection::RequestHeaders => return select(target, self.headers.iter(), false),
+            Collection::RequestHeadersNames => return select(target, self.headers.iter(), true),
+            Collection::RequestCookies => return select(target, self.cookies.iter(), false),
+            Collection::RequestCookiesNames => return select(target, self.cookies.iter(), true),
+        };
+        vec![(String::new(), Cow::Borrowed(scalar.as_str()))]
+    }
+}
+
+/// Key-value pairs selected by a target, as keys or values
+fn select<'a>(
+    target: &Target,
+    pairs: impl Iterator<Item = &'a (String, String)>,
+    names: bool,
+) -> Vec<(String, Cow<'a, str>)> {
+    pairs
+        .filter(|(key, _)| target.selects(key))
+        .map(|(key, value)| {
+            let value = if names { key } else { value };
+            (key.clone(), Cow::Borrowed(value.as_str()))
+        })
+        .collect()
+}
+
+// =============================================================================
+// Rule Set
+// =============================================================================
+
+/// A matched rule
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    pub rule_id: u64,
+    pub message: String,
+    pub severity: Severity,
+    /// Variable (and key) that matched, e.g. `REQUEST_HEADERS:user-agent`
+    pub target: String,
+    pub deny: bool,
+}
+
+/// Rules of one rule file
+#[derive(Debug)]
+pub struct RuleSet {
+    /// Filter the rules belong to
+    filter_id: String,
+    rules: Vec<Rule>,
+    /// Rules the engine can't run
+    skipped: usize,
+}
+
+impl RuleSet {
+    /// Load a filter's rule file
+    pub fn load(filter_id: &str, path: &Path) -> Result<Self, RuleLoadError> {
+        let source = std::fs::read_to_string(path).map_err(|source| RuleLoadError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::parse(filter_id, &source)
+    }
+
+    /// Parse rule file contents
+    pub fn parse(filter_id: &str, source: &str) -> Result<Self, RuleLoadError> {
+        let mut rules = Vec::new();
+        let mut skipped = 0;
+        let mut in_chain = false;
+
+        for (line, text) in logical_lines(source) {
+            let tokens =
+                tokenize(&text).map_err(|message| RuleLoadError::Parse { line, message })?;
+            if !tokens
+                .first()
+                .is_some_and(|d| d.eq_ignore_ascii_case("SecRule"))
+            {
+                continue;
+            }
+
+            // Later links of a chain belong to the skipped first rule
+            let chained = tokens
+                .get(3)
+                .is_some_and(|a| split_actions(a).iter().any(|(n, _)| *n == "chain"));
+            if std::mem::replace(&mut in_chain, chained) {
+                continue;
+            }
+
+            match parse_rule(&tokens).map_err(|message| RuleLoadError::Parse { line, message })? {
+                ParsedRule::Rule(rule) => rules.push(rule),
+                ParsedRule::Unsupported { id, reason } => {
+                    warn!(
+                        filter_id = %filter_id,
+                        rule_id = id,
+                        line,
+                        reason = %reason,
+                        "Skipping unsupported managed rule"
+                    );
+                    skipped += 1;
+                }
+            }
+        }
+
+        Ok(Self {
+            filter_id: filter_id.to_string(),
+            rules,
+            skipped,
+        })
+    }
+
+    /// Rules that will be evaluated
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Rules skipped as unsupported
+    pub fn skipped_count(&self) -> usize {
+        self.skipped
+    }
+
+    fn has_phase(&self, phase: u8) -> bool {
+        self.rules.iter().any(|r| r.phase == phase)
+    }
+
+    fn evaluate(&self, phase: u8, data: &RequestData, disabled: &[u64]) -> Vec<RuleMatch> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.phase == phase && !disabled.contains(&rule.id))
+            .filter_map(|rule| {
+                rule.first_match(data).map(|target| RuleMatch {
+                    rule_id: rule.id,
+                    message: rule.message.clone(),
+                    severity: rule.severity,
+                    target,
+                    deny: rule.deny,
+                })
+            })
+            .collect()
+    }
+
+    /// Run the rules that need only the request line and headers.
+    ///
+    /// Returns the check to finish in the body phase when phase 2 rules
+    /// should see the body, or rejects a request that is already over the
+    /// threshold.
+    pub fn check_request(
+        self: &Arc<Self>,
+        filter: &ManagedRulesFilter,
+        correlation_id: &str,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        has_body: Option<bool>,
+    ) -> Result<Option<RulesCheck>, RuleRejection> {
+        let mut check = RulesCheck {
+            filter_id: self.filter_id.clone(),
+            correlation_id: correlation_id.to_string(),
+            rules: Arc::clone(self),
+            mode: filter.mode,
+            anomaly_threshold: filter.anomaly_threshold,
+            disabled_rules: filter.disabled_rules.clone(),
+            max_body_bytes: filter.max_body_bytes,
+            data: RequestData::new(method, uri, headers),
+            matches: Vec::new(),
+        };
+        check.run_phase(1);
+
+        if filter.inspect_body && has_body != Some(false) && self.has_phase(2) {
+            if check.mode == ManagedRulesMode::Block {
+                check.verdict()?;
+            }
+            return Ok(Some(check));
+        }
+
+        check.run_phase(2);
+        check.verdict()?;
+        Ok(None)
+    }
+}
+
+/// A managed-rules filter's evaluation of one request
+#[derive(Debug)]
+pub struct RulesCheck {
+    filter_id: String,
+    correlation_id: String,
+    rules: Arc<RuleSet>,
+    mode: ManagedRulesMode,
+    anomaly_threshold: u32,
+    disabled_rules: Vec<u64>,
+    max_body_bytes: usize,
+    data: RequestData,
+    matches: Vec<RuleMatch>,
+}
+
+impl RulesCheck {
+    fn run_phase(&mut self, phase: u8) {
+        let matches = self.rules.evaluate(phase, &self.data, &self.disabled_rules);
+        if let Some(metric) = MANAGED_RULE_MATCHES.as_ref() {
+            for m in &matches {
+                metric
+                    .with_label_values(&[&self.filter_id, &m.rule_id.to_string()])
+                    .inc();
+            }
+        }
+        self.matches.extend(matches);
+    }
+
+    /// Current anomaly score
+    pub fn anomaly_score(&self) -> u32 {
+        self.matches.iter().map(|m| m.severity.score()).sum()
+    }
+
+    /// Reject a request over the threshold; in detect mode only report it
+    fn verdict(&self) -> Result<(), RuleRejection> {
+        let anomaly_score = self.anomaly_score();
+        if anomaly_score < self.anomaly_threshold && !self.matches.iter().any(|m| m.deny) {
+            return Ok(());
+        }
+
+        let mode = match self.mode {
+            ManagedRulesMode::Block => "block",
+            ManagedRulesMode::Detect => "detect",
+        };
+        if let Some(metric) = MANAGED_RULE_ANOMALIES.as_ref() {
+            metric.with_label_values(&[&self.filter_id, mode]).inc();
+        }
+        let rejection = RuleRejection {
+            filter_id: self.filter_id.clone(),
+            anomaly_score,
+            matches: self.matches.clone(),
+        };
+        match self.mode {
+            ManagedRulesMode::Block => Err(rejection),
+            ManagedRulesMode::Detect => {
+                warn!(
+                    correlation_id = %self.correlation_id,
+                    filter_id = %self.filter_id,
+                    anomaly_score,
+                    rule_ids = ?rejection.rule_ids(),
+                    "Request reached managed rules anomaly threshold (detect mode)"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn inspect_body(&mut self, body: &[u8]) -> Result<(), RuleRejection> {
+        let inspected = &body[..body.len().min(self.max_body_bytes)];
+        self.data.set_body(inspected);
+        self.run_phase(2);
+        self.verdict()
+    }
+}
+
+/// A request blocked by a `managed-rules` filter
+#[derive(Debug, Clone)]
+pub struct RuleRejection {
+    /// Filter that blocked the request
+    pub filter_id: String,
+    /// Sum of the matched rules' scores
+    pub anomaly_score: u32,
+    /// Rules that matched
+    pub matches: Vec<RuleMatch>,
+}
+
+impl RuleRejection {
+    /// IDs of the matched rules, as strings for logs
+    pub fn rule_ids(&self) -> Vec<String> {
+        self.matches.iter().map(|m| m.rule_id.to_string()).collect()
+    }
+
+    /// JSON body of the 403 response; rule messages stay in the logs
+    pub fn to_json(&self, request_id: &str) -> String {
+        json!({
+            "error": "Request blocked by managed rules",
+            "status": 403,
+            "anomaly_score": self.anomaly_score,
+            "rule_ids": self.matches.iter().map(|m| m.rule_id).collect::<Vec<_>>(),
+            "request_id": request_id,
+        })
+        .to_string()
+    }
+}
+
+// =============================================================================
+// Body Inspection
+// =============================================================================
+
+/// Holds back the start of the request body for phase 2 rules
+#[derive(Debug)]
+pub struct RequestBodyInspection {
+    checks: Vec<RulesCheck>,
+    buffer: BytesMut,
+    max_body_bytes: usize,
+    released: bool,
+}
+
+impl RequestBodyInspection {
+    /// Buffer for the given checks, sized for the largest limit
+    pub fn new(checks: Vec<RulesCheck>) -> Self {
+        let max_body_bytes = checks
+            .iter()
+            .map(|c| c.max_body_bytes)
+            .max()
+            .unwrap_or_default();
+        Self {
+            checks,
+            buffer: BytesMut::new(),
+            max_body_bytes,
+            released: false,
+        }
+    }
+
+    /// Hold back body chunks until the limit or the end of the body, then
+    /// run the checks and release the held bytes as one chunk. Later
+    /// chunks pass through uninspected.
+    pub fn process(
+        &mut self,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+    ) -> Result<(), RuleRejection> {
+        if self.released {
+            return Ok(());
+        }
+        if let Some(chunk) = body.take() {
+            self.buffer.extend_from_slice(&chunk);
+        }
+        if self.buffer.len() < self.max_body_bytes && !end_of_stream {
+            return Ok(());
+        }
+
+        for check in &mut self.checks {
+            check.inspect_body(&self.buffer)?;
+        }
+        self.released = true;
+        let data = std::mem::take(&mut self.buffer).freeze();
+        if !data.is_empty() {
+            *body = Some(data);
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// ManagedRulesManager
+// =============================================================================
+
+/// Loaded rule sets of all `managed-rules` filters
+pub struct ManagedRulesManager {
+    /// Filter ID → rule set
+    rule_sets: DashMap<String, Arc<RuleSet>>,
+}
+
+impl ManagedRulesManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            rule_sets: DashMap::new(),
+        }
+    }
+
+    /// Load a filter's rule file
+    pub fn register_filter(
+        &self,
+        filter_id: &str,
+        config: &ManagedRulesFilter,
+    ) -> Result<(), RuleLoadError> {
+        let rule_set = RuleSet::load(filter_id, Path::new(&config.rules))?;
+        debug!(
+            filter_id = %filter_id,
+            rules = rule_set.rule_count(),
+            skipped = rule_set.skipped_count(),
+            "Registered managed-rules filter"
+        );
+        self.rule_sets
+            .insert(filter_id.to_string(), Arc::new(rule_set));
+        Ok(())
+    }
+
+    /// Rule set of a filter
+    pub fn get(&self, filter_id: &str) -> Option<Arc<RuleSet>> {
+        self.rule_sets.get(filter_id).map(|r| Arc::clone(&r))
+    }
+
+    /// Reload rule files from a new configuration
+    ///
+    /// A rule file that fails to load keeps its previous rules, if any.
+    pub fn reload(&self, filters: &HashMap<String, ManagedRulesFilter>) {
+        self.rule_sets.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            if let Err(e) = self.register_filter(filter_id, config) {
+                warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload managed rules"
+                );
+            }
+        }
+    }
+}
+
+impl Default for ManagedRulesManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+# Scanner detection
+SecRule REQUEST_HEADERS:User-Agent "@pm nikto sqlmap" \
+    "id:913100,phase:1,block,t:none,t:lowercase,\
+    msg:'Found User-Agent associated with security scanner',severity:'CRITICAL'"
+
+SecRule ARGS|!ARGS:comment "@rx (?i)\bunion\b.+\bselect\b" \
+    "id:942100,phase:2,block,t:urlDecodeUni,msg:'SQL injection',severity:'CRITICAL'"
+SecRule REQUEST_FILENAME "@endsWith .bak" "id:920440,phase:1,msg:'Backup file',severity:'NOTICE'"
+SecRule REQUEST_BODY "@contains <script>" "id:941100,phase:2,msg:'XSS',severity:'WARNING'"
+SecRule REQUEST_METHOD "@streq TRACE" "id:911100,phase:1,deny,severity:'NOTICE'"
+
+SecAction "id:900000,phase:1,pass,nolog,setvar:tx.paranoia_level=1"
+SecRule ARGS "@detectSQLi" "id:942101,phase:2,block"
+SecRule REQUEST_HEADERS:Host "@rx ^$" "id:920280,phase:1,chain"
+    SecRule REQUEST_METHOD "@rx ^GET$" "t:none"
+"#;
+
+    fn rules() -> Arc<RuleSet> {
+        Arc::new(RuleSet::parse("crs", RULES).unwrap())
+    }
+
+    fn filter() -> ManagedRulesFilter {
+        ManagedRulesFilter::new("rules.conf")
+    }
+
+    fn check(
+        rules: &Arc<RuleSet>,
+        filter: &ManagedRulesFilter,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+        has_body: Option<bool>,
+    ) -> Result<Option<RulesCheck>, RuleRejection> {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        rules.check_request(
+            filter,
+            "req-1",
+            &method.parse().unwrap(),
+            &uri.parse().unwrap(),
+            &map,
+            has_body,
+        )
+    }
+
+    #[test]
+    fn parses_supported_rules_and_skips_the_rest() {
+        let rules = rules();
+        assert_eq!(rules.rule_count(), 5);
+        // @detectSQLi and the chain (whose second link is not counted)
+        assert_eq!(rules.skipped_count(), 2);
+
+        let err = RuleSet::parse("crs", r#"SecRule ARGS "@rx a" "id:1"#).unwrap_err();
+        assert!(err.to_string().contains("unterminated"));
+        let err = RuleSet::parse("crs", r#"SecRule ARGS "@rx a" "phase:1""#).unwrap_err();
+        assert!(err.to_string().contains("without an 'id'"));
+    }
+
+    #[test]
+    fn scores_header_rules_against_the_threshold() {
+        let rules = rules();
+        let filter = filter();
+
+        let rejection = check(
+            &rules,
+            &filter,
+            "GET",
+            "/",
+            &[("user-agent", "Nikto/2.1")],
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(rejection.anomaly_score, 5);
+        assert_eq!(rejection.matches[0].target, "REQUEST_HEADERS:user-agent");
+        let body: serde_json::Value = serde_json::from_str(&rejection.to_json("req-1")).unwrap();
+        assert_eq!(body["status"], 403);
+        assert_eq!(body["rule_ids"][0], 913100);
+
+        // A NOTICE match stays under the threshold
+        let clean = check(&rules, &filter, "GET", "/site.bak", &[], Some(false));
+        assert!(clean.unwrap().is_none());
+
+        // deny blocks regardless of the score
+        let rejection = check(&rules, &filter, "TRACE", "/", &[], Some(false)).unwrap_err();
+        assert_eq!(rejection.anomaly_score, 2);
+
+        let mut detect = filter.clone();
+        detect.mode = ManagedRulesMode::Detect;
+        assert!(check(
+            &rules,
+            &detect,
+            "GET",
+            "/",
+            &[("user-agent", "sqlmap")],
+            None
+        )
+        .is_ok());
+
+        let mut disabled = filter.clone();
+        disabled.disabled_rules = vec![913100];
+        assert!(check(
+            &rules,
+            &disabled,
+            "GET",
+            "/",
+            &[("user-agent", "nikto")],
+            None
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn runs_body_rules_without_a_body_right_away() {
+        let rules = rules();
+        let filter = filter();
+
+        let rejection = check(
+            &rules,
+            &filter,
+            "GET",
+            "/search?q=1%20UNION%20ALL%20SELECT%20password",
+            &[],
+            Some(false),
+        )
+        .unwrap_err();
+        assert_eq!(rejection.matches[0].rule_id, 942100);
+        assert_eq!(rejection.matches[0].target, "ARGS:q");
+
+        // Excluded argument
+        let clean = check(
+            &rules,
+            &filter,
+            "GET",
+            "/post?comment=union%20select",
+            &[],
+            Some(false),
+        );
+        assert!(clean.unwrap().is_none());
+    }
+
+    #[test]
+    fn inspects_form_bodies_up_to_the_limit() {
+        let rules = rules();
+        let mut filter = filter();
+        filter.max_body_bytes = 64;
+        let form = [("content-type", "application/x-www-form-urlencoded")];
+
+        let pending = check(&rules, &filter, "POST", "/login", &form, Some(true))
+            .unwrap()
+            .unwrap();
+        let mut inspection = RequestBodyInspection::new(vec![pending]);
+        let mut chunk = Some(Bytes::from_static(b"user=a&pass=x'+union+"));
+        inspection.process(&mut chunk, false).unwrap();
+        assert!(chunk.is_none());
+        let mut chunk = Some(Bytes::from_static(b"select+1"));
+        let rejection = inspection.process(&mut chunk, true).unwrap_err();
+        assert_eq!(rejection.matches[0].target, "ARGS:pass");
+
+        // Beyond the limit the body is released and not inspected
+        let pending = check(&rules, &filter, "POST", "/upload", &[], Some(true))
+            .unwrap()
+            .unwrap();
+        let mut inspection = RequestBodyInspection::new(vec![pending]);
+        let mut chunk = Some(Bytes::from(vec![b'a'; 64]));
+        inspection.process(&mut chunk, false).unwrap();
+        assert_eq!(chunk.as_ref().map(Bytes::len), Some(64));
+        let mut chunk = Some(Bytes::from_static(b"<script>"));
+        inspection.process(&mut chunk, true).unwrap();
+        assert_eq!(chunk.as_deref(), Some(&b"<script>"[..]));
+    }
+}
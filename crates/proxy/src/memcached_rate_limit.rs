@@ -103,7 +103,7 @@ impl MemcachedRateLimiter {
         let client = async_memcached::Client::new(addr).await?;
 
         debug!(
-            url = %backend_config.url,
+            url = %zentinel_config::secrets::redact(&backend_config.url),
             prefix = %backend_config.key_prefix,
             max_rps = rate_config.max_rps,
             "Memcached rate limiter initialized"
@@ -244,7 +244,7 @@ pub async fn create_memcached_rate_limiter(
     match MemcachedRateLimiter::new(backend_config, rate_config).await {
         Ok(limiter) => {
             debug!(
-                url = %backend_config.url,
+                url = %zentinel_config::secrets::redact(&backend_config.url),
                 "Memcached rate limiter created successfully"
             );
             Some(limiter)
@@ -252,7 +252,7 @@ pub async fn create_memcached_rate_limiter(
         Err(e) => {
             error!(
                 error = %e,
-                url = %backend_config.url,
+                url = %zentinel_config::secrets::redact(&backend_config.url),
                 "Failed to create Memcached rate limiter"
             );
             if backend_config.fallback_local {
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use zentinel_agent_protocol::v2::{MetricsCollector, UnifiedMetricsAggregator};
+use zentinel_common::observability::{metric_labels, MetricsSource, MetricsWriter};
 
 /// Metrics manager for the proxy.
 ///
@@ -143,21 +144,17 @@ impl MetricsManager {
             return MetricsResponse::not_found();
         }
 
-        // Export proxy metrics
-        let mut body = self.aggregator.export_prometheus();
+        let mut writer = MetricsWriter::new();
+        self.aggregator.collect(&mut writer);
 
-        // Append pool metrics from all registered v2 agents
+        // Pool series carry agent_id, so families shared by several agents merge.
         // Use try_read to avoid blocking - if lock is held, skip pool metrics this scrape
         if let Ok(pool_metrics) = self.pool_metrics.try_read() {
-            for (agent_id, collector) in pool_metrics.iter() {
-                let pool_output = collector.export_prometheus();
-                if !pool_output.is_empty() {
-                    // Add a comment separator for clarity
-                    body.push_str(&format!("\n# Agent pool metrics: {}\n", agent_id));
-                    body.push_str(&pool_output);
-                }
+            for collector in pool_metrics.values() {
+                collector.collect(&mut writer);
             }
         }
+        let body = writer.render();
 
         MetricsResponse::ok(body)
     }
@@ -171,7 +168,7 @@ impl MetricsManager {
         let mut labels = HashMap::new();
         labels.insert("method".to_string(), method.to_string());
         labels.insert("status".to_string(), status.to_string());
-        labels.insert("route".to_string(), route.to_string());
+        labels.insert(metric_labels::ROUTE_ID.to_string(), route.to_string());
 
         self.aggregator.increment_counter(
             "zentinel_requests_total",
@@ -185,7 +182,7 @@ impl MetricsManager {
     pub fn observe_request_duration(&self, method: &str, route: &str, duration_secs: f64) {
         let mut labels = HashMap::new();
         labels.insert("method".to_string(), method.to_string());
-        labels.insert("route".to_string(), route.to_string());
+        labels.insert(metric_labels::ROUTE_ID.to_string(), route.to_string());
 
         // Standard latency buckets
         let buckets = vec![
@@ -275,7 +272,7 @@ impl MetricsManager {
     /// Increment agent requests.
     pub fn inc_agent_requests(&self, agent: &str, decision: &str) {
         let mut labels = HashMap::new();
-        labels.insert("agent".to_string(), agent.to_string());
+        labels.insert(metric_labels::AGENT_ID.to_string(), agent.to_string());
         labels.insert("decision".to_string(), decision.to_string());
 
         self.aggregator.increment_counter(
@@ -289,7 +286,7 @@ impl MetricsManager {
     /// Record agent processing time.
     pub fn observe_agent_duration(&self, agent: &str, duration_secs: f64) {
         let mut labels = HashMap::new();
-        labels.insert("agent".to_string(), agent.to_string());
+        labels.insert(metric_labels::AGENT_ID.to_string(), agent.to_string());
 
         let buckets = vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
 
@@ -331,7 +328,7 @@ impl MetricsManager {
     /// Increment rate limited requests.
     pub fn inc_rate_limited(&self, route: &str) {
         let mut labels = HashMap::new();
-        labels.insert("route".to_string(), route.to_string());
+        labels.insert(metric_labels::ROUTE_ID.to_string(), route.to_string());
 
         self.aggregator.increment_counter(
             "zentinel_rate_limited_total",
@@ -556,5 +553,8 @@ mod tests {
         let response = manager.handle_metrics_request();
         assert!(response.body.contains("zentinel_agent_requests_total"));
         assert!(response.body.contains("zentinel_agent_duration_seconds"));
+        assert!(response
+            .body
+            .contains("zentinel_agent_requests_total{agent_id=\"waf\",decision=\"allow\"} 1"));
     }
 }
@@ -0,0 +1,671 @@
+//! multipart/form-data inspection for the `multipart` filter
+//!
+//! Raw body chunks don't line up with the fields and files of a form
+//! upload, so agents can't tell an uploaded file from the fields around it.
+//! A route with a `multipart` filter parses multipart/form-data bodies
+//! incrementally: every part's field name, filename and content type are
+//! sent to the route's body-inspecting agents together with the part's
+//! body, streamed as request body chunk events carrying a
+//! [`MultipartPart`].
+//!
+//! Parts are held back until they end, so agents can drop a part (a drop
+//! mutation on any of its chunks) or replace a chunk's data; the body is
+//! re-encoded with the same boundary before it goes upstream. Parts larger
+//! than `max-part-bytes`, bodies larger than `max-total-bytes` and bodies
+//! with more than `max-parts` parts are rejected with 413, bodies that
+//! aren't valid multipart with 400.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "uploads" {
+//!         type "multipart"
+//!         max-part-bytes 10485760
+//!         max-total-bytes 52428800
+//!         max-parts 100
+//!     }
+//! }
+//! ```
+
+use std::sync::LazyLock;
+
+use bytes::{Buf, Bytes, BytesMut};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use thiserror::Error;
+
+use zentinel_agent_protocol::MultipartPart;
+use zentinel_config::MultipartFilter;
+
+/// Parts per filter and outcome (forwarded, replaced, dropped)
+static MULTIPART_PARTS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_multipart_parts_total",
+        "Parts of multipart/form-data bodies inspected by multipart filters",
+        &["filter", "action"]
+    )
+    .ok()
+});
+
+/// Rejected bodies per filter and reason
+static MULTIPART_REJECTIONS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_multipart_rejections_total",
+        "Request bodies rejected by multipart filters",
+        &["filter", "reason"]
+    )
+    .ok()
+});
+
+/// Largest header block of a single part
+pub const MAX_HEADER_BYTES: usize = 8 * 1024;
+
+/// Longest boundary allowed by RFC 2046
+const MAX_BOUNDARY_LEN: usize = 70;
+
+/// Errors that reject a multipart body
+#[derive(Debug, Error)]
+pub enum MultipartError {
+    /// The body doesn't follow the multipart/form-data format
+    #[error("malformed multipart body: {0}")]
+    Malformed(&'static str),
+    /// A part's header block is too large
+    #[error("multipart part headers exceed {} bytes", MAX_HEADER_BYTES)]
+    HeadersTooLarge,
+    /// A part's body is too large
+    #[error("multipart part {index} exceeds {limit} bytes")]
+    PartTooLarge { index: u32, limit: usize },
+    /// The body is too large
+    #[error("multipart body exceeds {limit} bytes")]
+    TooLarge { limit: usize },
+    /// The body has too many parts
+    #[error("multipart body has more than {limit} parts")]
+    TooManyParts { limit: usize },
+}
+
+impl MultipartError {
+    /// HTTP status code the request is answered with
+    pub fn status(&self) -> u16 {
+        match self {
+            MultipartError::Malformed(_) | MultipartError::HeadersTooLarge => 400,
+            MultipartError::PartTooLarge { .. }
+            | MultipartError::TooLarge { .. }
+            | MultipartError::TooManyParts { .. } => 413,
+        }
+    }
+
+    /// Metric label
+    pub fn reason(&self) -> &'static str {
+        match self {
+            MultipartError::Malformed(_) => "malformed",
+            MultipartError::HeadersTooLarge => "headers_too_large",
+            MultipartError::PartTooLarge { .. } => "part_too_large",
+            MultipartError::TooLarge { .. } => "too_large",
+            MultipartError::TooManyParts { .. } => "too_many_parts",
+        }
+    }
+}
+
+/// What the parser found in the body so far
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultipartEvent {
+    /// A part begins; `headers` is its raw header block
+    PartStart { part: MultipartPart, headers: Bytes },
+    /// Body data of the current part; `is_last` ends the part
+    PartData { data: Bytes, is_last: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the first delimiter
+    Preamble,
+    /// After a delimiter: CRLF before a part or `--` closing the body
+    Delimiter,
+    Headers,
+    Body,
+    /// After the closing delimiter; the epilogue is ignored
+    Done,
+}
+
+/// Incremental multipart/form-data parser
+#[derive(Debug)]
+pub struct MultipartParser {
+    /// `CRLF--boundary`
+    delimiter: Vec<u8>,
+    buf: BytesMut,
+    state: State,
+    max_part_bytes: usize,
+    max_total_bytes: usize,
+    max_parts: usize,
+    parts: usize,
+    part_bytes: usize,
+    total_bytes: usize,
+}
+
+impl MultipartParser {
+    /// Parser for bodies delimited by `boundary`, within the filter's limits
+    pub fn new(boundary: &str, limits: &MultipartFilter) -> Self {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        // The first delimiter has no CRLF of its own; supply one so every
+        // delimiter looks the same
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"\r\n");
+        Self {
+            delimiter,
+            buf,
+            state: State::Preamble,
+            max_part_bytes: limits.max_part_bytes,
+            max_total_bytes: limits.max_total_bytes,
+            max_parts: limits.max_parts,
+            parts: 0,
+            part_bytes: 0,
+            total_bytes: 0,
+        }
+    }
+
+    /// Whether the closing delimiter has been seen
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Add a chunk and return the parts and part data it completes. Part
+    /// data is released as soon as it can't be the start of a delimiter.
+    pub fn push(
+        &mut self,
+        chunk: &[u8],
+        end_of_stream: bool,
+    ) -> Result<Vec<MultipartEvent>, MultipartError> {
+        self.total_bytes += chunk.len();
+        if self.total_bytes > self.max_total_bytes {
+            return Err(MultipartError::TooLarge {
+                limit: self.max_total_bytes,
+            });
+        }
+        if self.state != State::Done {
+            self.buf.extend_from_slice(chunk);
+        }
+
+        let mut events = Vec::new();
+        loop {
+            match self.state {
+                State::Preamble => match find(&self.buf, &self.delimiter) {
+                    Some(at) => {
+                        self.buf.advance(at + self.delimiter.len());
+                        self.state = State::Delimiter;
+                    }
+                    None => {
+                        let keep = self.delimiter.len() - 1;
+                        if self.buf.len() > keep {
+                            self.buf.advance(self.buf.len() - keep);
+                        }
+                        break;
+                    }
+                },
+                State::Delimiter => {
+                    if self.buf.len() < 2 {
+                        break;
+                    }
+                    if self.buf.starts_with(b"--") {
+                        self.buf.clear();
+                        self.state = State::Done;
+                    } else if self.buf.starts_with(b"\r\n") {
+                        self.buf.advance(2);
+                        self.state = State::Headers;
+                    } else {
+                        return Err(MultipartError::Malformed("unexpected data after boundary"));
+                    }
+                }
+                State::Headers => {
+                    if self.buf.starts_with(b"\r\n") {
+                        return Err(MultipartError::Malformed("part has no headers"));
+                    }
+                    let Some(end) = find(&self.buf, b"\r\n\r\n") else {
+                        if self.buf.len() > MAX_HEADER_BYTES {
+                            return Err(MultipartError::HeadersTooLarge);
+                        }
+                        break;
+                    };
+                    if end > MAX_HEADER_BYTES {
+                        return Err(MultipartError::HeadersTooLarge);
+                    }
+                    if self.parts == self.max_parts {
+                        return Err(MultipartError::TooManyParts {
+                            limit: self.max_parts,
+                        });
+                    }
+                    let headers = self.buf.split_to(end).freeze();
+                    self.buf.advance(4);
+                    let part = parse_part_headers(&headers, self.parts as u32)?;
+                    self.parts += 1;
+                    self.part_bytes = 0;
+                    self.state = State::Body;
+                    events.push(MultipartEvent::PartStart { part, headers });
+                }
+                State::Body => {
+                    if let Some(at) = find(&self.buf, &self.delimiter) {
+                        let data = self.buf.split_to(at).freeze();
+                        self.buf.advance(self.delimiter.len());
+                        self.add_part_bytes(data.len())?;
+                        self.state = State::Delimiter;
+                        events.push(MultipartEvent::PartData {
+                            data,
+                            is_last: true,
+                        });
+                    } else {
+                        // Keep what may be the start of the next delimiter
+                        let keep = self.delimiter.len() - 1;
+                        if self.buf.len() > keep {
+                            let data = self.buf.split_to(self.buf.len() - keep).freeze();
+                            self.add_part_bytes(data.len())?;
+                            events.push(MultipartEvent::PartData {
+                                data,
+                                is_last: false,
+                            });
+                        }
+                        break;
+                    }
+                }
+                State::Done => break,
+            }
+        }
+
+        if end_of_stream && self.state != State::Done {
+            return Err(MultipartError::Malformed(
+                "body ends before the closing boundary",
+            ));
+        }
+        Ok(events)
+    }
+
+    fn add_part_bytes(&mut self, len: usize) -> Result<(), MultipartError> {
+        self.part_bytes += len;
+        if self.part_bytes > self.max_part_bytes {
+            return Err(MultipartError::PartTooLarge {
+                index: self.parts as u32 - 1,
+                limit: self.max_part_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Part collected for re-encoding
+#[derive(Debug)]
+struct PendingPart {
+    part: MultipartPart,
+    encoded: BytesMut,
+    bytes_received: usize,
+    dropped: bool,
+    replaced: bool,
+}
+
+/// A multipart body on its way upstream: parsed, inspected part by part
+/// and re-encoded without the parts agents dropped
+#[derive(Debug)]
+pub struct MultipartInspection {
+    filter_id: String,
+    boundary: String,
+    parser: MultipartParser,
+    current: Option<PendingPart>,
+    output: BytesMut,
+    closed: bool,
+}
+
+impl MultipartInspection {
+    /// Inspection for a request with `content_type`; `None` unless it is
+    /// multipart/form-data with a valid boundary
+    pub fn new(filter_id: &str, filter: &MultipartFilter, content_type: &str) -> Option<Self> {
+        let boundary = form_data_boundary(content_type)?;
+        Some(Self {
+            filter_id: filter_id.to_string(),
+            parser: MultipartParser::new(&boundary, filter),
+            boundary,
+            current: None,
+            output: BytesMut::new(),
+            closed: false,
+        })
+    }
+
+    /// ID of the filter that set up the inspection
+    pub fn filter_id(&self) -> &str {
+        &self.filter_id
+    }
+
+    /// Parse the next body chunk
+    pub fn parse(
+        &mut self,
+        chunk: Option<&Bytes>,
+        end_of_stream: bool,
+    ) -> Result<Vec<MultipartEvent>, MultipartError> {
+        let chunk = chunk.map(|c| &c[..]).unwrap_or_default();
+        self.parser.push(chunk, end_of_stream).inspect_err(|e| {
+            if let Some(counter) = MULTIPART_REJECTIONS.as_ref() {
+                counter
+                    .with_label_values(&[&self.filter_id, e.reason()])
+                    .inc();
+            }
+        })
+    }
+
+    /// Begin collecting a part announced by [`MultipartEvent::PartStart`]
+    pub fn start_part(&mut self, part: MultipartPart, headers: &[u8]) {
+        let mut encoded = BytesMut::new();
+        encoded.extend_from_slice(b"--");
+        encoded.extend_from_slice(self.boundary.as_bytes());
+        encoded.extend_from_slice(b"\r\n");
+        encoded.extend_from_slice(headers);
+        encoded.extend_from_slice(b"\r\n\r\n");
+        self.current = Some(PendingPart {
+            part,
+            encoded,
+            bytes_received: 0,
+            dropped: false,
+            replaced: false,
+        });
+    }
+
+    /// Count `len` received bytes of the current part and return the part
+    /// with its bytes received so far; `None` once the part is dropped
+    pub fn receive(&mut self, len: usize) -> Option<(MultipartPart, usize)> {
+        let pending = self.current.as_mut().filter(|p| !p.dropped)?;
+        pending.bytes_received += len;
+        Some((pending.part.clone(), pending.bytes_received))
+    }
+
+    /// Add data to the current part, `replaced` when an agent changed it
+    pub fn push_data(&mut self, data: &[u8], replaced: bool) {
+        if let Some(pending) = self.current.as_mut().filter(|p| !p.dropped) {
+            pending.encoded.extend_from_slice(data);
+            pending.replaced |= replaced;
+        }
+    }
+
+    /// Leave the current part out of the body sent upstream
+    pub fn drop_part(&mut self) {
+        if let Some(pending) = self.current.as_mut() {
+            pending.dropped = true;
+            pending.encoded.clear();
+        }
+    }
+
+    /// End the current part, queueing it for upstream unless dropped
+    pub fn finish_part(&mut self) {
+        let Some(pending) = self.current.take() else {
+            return;
+        };
+        let action = if pending.dropped {
+            "dropped"
+        } else {
+            self.output.extend_from_slice(&pending.encoded);
+            self.output.extend_from_slice(b"\r\n");
+            if pending.replaced {
+                "replaced"
+            } else {
+                "forwarded"
+            }
+        };
+        if let Some(counter) = MULTIPART_PARTS.as_ref() {
+            counter.with_label_values(&[&self.filter_id, action]).inc();
+        }
+    }
+
+    /// Re-encoded parts ready for upstream, closed with the final delimiter
+    /// once the body has ended
+    pub fn take_output(&mut self) -> Option<Bytes> {
+        if self.parser.is_done() && !self.closed {
+            self.closed = true;
+            self.output.extend_from_slice(b"--");
+            self.output.extend_from_slice(self.boundary.as_bytes());
+            self.output.extend_from_slice(b"--\r\n");
+        }
+        (!self.output.is_empty()).then(|| self.output.split().freeze())
+    }
+}
+
+/// Boundary of a multipart/form-data content type
+pub fn form_data_boundary(content_type: &str) -> Option<String> {
+    let (mime, _) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    parse_params(content_type)
+        .into_iter()
+        .find(|(name, _)| name == "boundary")
+        .map(|(_, boundary)| boundary)
+        .filter(|b| !b.is_empty() && b.len() <= MAX_BOUNDARY_LEN)
+}
+
+/// Field name, filename and content type from a part's header block
+fn parse_part_headers(headers: &[u8], index: u32) -> Result<MultipartPart, MultipartError> {
+    let headers = std::str::from_utf8(headers)
+        .map_err(|_| MultipartError::Malformed("part headers are not UTF-8"))?;
+    let mut part = MultipartPart {
+        index,
+        name: String::new(),
+        filename: None,
+        content_type: None,
+    };
+    let mut has_disposition = false;
+    for line in headers.split("\r\n") {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(MultipartError::Malformed("invalid part header"));
+        };
+        let value = value.trim();
+        if name.trim().eq_ignore_ascii_case("content-disposition") {
+            let kind = value.split(';').next().unwrap_or_default().trim();
+            if !kind.eq_ignore_ascii_case("form-data") {
+                return Err(MultipartError::Malformed("part is not form-data"));
+            }
+            has_disposition = true;
+            for (param, value) in parse_params(value) {
+                match param.as_str() {
+                    "name" => part.name = value,
+                    "filename" => part.filename = Some(value),
+                    _ => {}
+                }
+            }
+        } else if name.trim().eq_ignore_ascii_case("content-type") {
+            part.content_type = Some(value.to_string());
+        }
+    }
+    if !has_disposition || part.name.is_empty() {
+        return Err(MultipartError::Malformed(
+            "part has no Content-Disposition field name",
+        ));
+    }
+    Ok(part)
+}
+
+/// `; name=value` parameters of a header value, names lowercased and
+/// quoted values unquoted
+fn parse_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = value;
+    while let Some(semi) = rest.find(';') {
+        rest = rest[semi + 1..].trim_start();
+        let Some(eq) = rest.find('=') else {
+            break;
+        };
+        let name = rest[..eq].trim().to_ascii_lowercase();
+        rest = rest[eq + 1..].trim_start();
+        let value = match rest.strip_prefix('"') {
+            // Browsers don't escape within quotes (quotes in filenames are
+            // percent-encoded), so the value ends at the next quote
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                rest = &quoted[(end + 1).min(quoted.len())..];
+                &quoted[..end]
+            }
+            None => {
+                let end = rest.find(';').unwrap_or(rest.len());
+                let value = rest[..end].trim();
+                rest = &rest[end..];
+                value
+            }
+        };
+        params.push((name, value.to_string()));
+    }
+    params
+}
+
+/// Position of the first occurrence of `needle` in `haystack`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+        Quarterly report\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"report;v2.pdf\"\r\n\
+        Content-Type: application/pdf\r\n\r\n\
+        %PDF-1.7 data\r\n--XyZ--\r\nepilogue";
+
+    fn parser(limits: MultipartFilter) -> MultipartParser {
+        MultipartParser::new("XyZ", &limits)
+    }
+
+    /// Parts with their concatenated data
+    fn collect(events: Vec<MultipartEvent>) -> Vec<(MultipartPart, Vec<u8>)> {
+        let mut parts: Vec<(MultipartPart, Vec<u8>)> = Vec::new();
+        for event in events {
+            match event {
+                MultipartEvent::PartStart { part, .. } => parts.push((part, Vec::new())),
+                MultipartEvent::PartData { data, .. } => {
+                    parts.last_mut().unwrap().1.extend_from_slice(&data)
+                }
+            }
+        }
+        parts
+    }
+
+    #[test]
+    fn test_boundary_from_content_type() {
+        assert_eq!(
+            form_data_boundary("multipart/form-data; boundary=----abc123").as_deref(),
+            Some("----abc123")
+        );
+        assert_eq!(
+            form_data_boundary("Multipart/Form-Data; charset=utf-8; boundary=\"a b\"").as_deref(),
+            Some("a b")
+        );
+        assert_eq!(form_data_boundary("multipart/mixed; boundary=abc"), None);
+        assert_eq!(form_data_boundary("multipart/form-data"), None);
+    }
+
+    #[test]
+    fn test_parses_parts_in_any_chunking() {
+        for chunk_size in [1, 3, 7, BODY.len()] {
+            let mut parser = parser(MultipartFilter::default());
+            let mut events = Vec::new();
+            let chunks: Vec<&[u8]> = BODY.chunks(chunk_size).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                events.extend(parser.push(chunk, i == chunks.len() - 1).unwrap());
+            }
+            assert!(parser.is_done());
+
+            let last_flags: Vec<bool> = events
+                .iter()
+                .filter_map(|e| match e {
+                    MultipartEvent::PartData { is_last, .. } => Some(*is_last),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(last_flags.iter().filter(|last| **last).count(), 2);
+
+            let parts = collect(events);
+            assert_eq!(parts.len(), 2, "chunk size {chunk_size}");
+            assert_eq!(parts[0].0.name, "title");
+            assert_eq!(parts[0].0.filename, None);
+            assert_eq!(parts[0].1, b"Quarterly report");
+            assert_eq!(parts[1].0.index, 1);
+            assert_eq!(parts[1].0.filename.as_deref(), Some("report;v2.pdf"));
+            assert_eq!(parts[1].0.content_type.as_deref(), Some("application/pdf"));
+            assert_eq!(parts[1].1, b"%PDF-1.7 data");
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let result = parser(MultipartFilter {
+            max_part_bytes: 8,
+            ..Default::default()
+        })
+        .push(BODY, true);
+        assert!(matches!(
+            result,
+            Err(MultipartError::PartTooLarge { index: 0, limit: 8 })
+        ));
+
+        let result = parser(MultipartFilter {
+            max_parts: 1,
+            ..Default::default()
+        })
+        .push(BODY, true);
+        assert!(matches!(result, Err(MultipartError::TooManyParts { .. })));
+
+        let result = parser(MultipartFilter {
+            max_total_bytes: 64,
+            max_part_bytes: 64,
+            ..Default::default()
+        })
+        .push(BODY, true);
+        assert_eq!(result.unwrap_err().status(), 413);
+    }
+
+    #[test]
+    fn test_malformed_bodies() {
+        let mut truncated = parser(MultipartFilter::default());
+        let error = truncated.push(&BODY[..60], true).unwrap_err();
+        assert_eq!(error.status(), 400);
+
+        let nameless = b"--XyZ\r\nContent-Type: text/plain\r\n\r\nx\r\n--XyZ--";
+        let error = parser(MultipartFilter::default())
+            .push(nameless, true)
+            .unwrap_err();
+        assert!(matches!(error, MultipartError::Malformed(_)));
+    }
+
+    #[test]
+    fn test_reencodes_without_dropped_parts() {
+        let filter = MultipartFilter::default();
+        let mut inspection =
+            MultipartInspection::new("uploads", &filter, "multipart/form-data; boundary=XyZ")
+                .unwrap();
+        for event in inspection
+            .parse(Some(&Bytes::from_static(BODY)), true)
+            .unwrap()
+        {
+            match event {
+                MultipartEvent::PartStart { part, headers } => {
+                    inspection.start_part(part, &headers)
+                }
+                MultipartEvent::PartData { data, is_last } => {
+                    let (part, _) = inspection.receive(data.len()).unwrap();
+                    if part.filename.is_some() {
+                        inspection.drop_part();
+                    } else {
+                        inspection.push_data(b"Q3 report", true);
+                    }
+                    if is_last {
+                        inspection.finish_part();
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            inspection.take_output().unwrap(),
+            Bytes::from_static(
+                b"--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\n\
+                  Q3 report\r\n--XyZ--\r\n"
+            )
+        );
+        assert_eq!(inspection.take_output(), None);
+    }
+}
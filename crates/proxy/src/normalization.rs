@@ -0,0 +1,495 @@
+//! Request normalization before route matching
+//!
+//! Rewrites a request to one canonical form in `early_request_filter`, so
+//! route matching, agents and the upstream all interpret it the same way and
+//! leave no room for request smuggling or desync between them:
+//!
+//! - framing: `Content-Length` values are collapsed into one header,
+//!   `Transfer-Encoding` codings are lower-cased and joined into one header,
+//!   and `Content-Length` is dropped when `Transfer-Encoding` is present
+//!   (unless `reject-ambiguous-framing` is set, which then rejects it)
+//! - path: percent-encoded unreserved characters are decoded, other
+//!   percent-encodings upper-cased, dot segments removed and, with
+//!   `merge-slashes`, repeated slashes collapsed; the query is kept as is
+//!
+//! A request without a canonical form (a non-numeric or conflicting
+//! `Content-Length`, a `Transfer-Encoding` not ending in `chunked`, an
+//! invalid percent-encoding or an encoded NUL) is rejected with the
+//! `normalization` status in `strict` mode. In `log-only` mode requests are
+//! forwarded unchanged and every change or rejection that would have
+//! happened is logged. Both are counted in
+//! `zentinel_request_normalization_total{listener, step, action}`.
+
+use std::sync::LazyLock;
+
+use http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use http::uri::PathAndQuery;
+use http::{HeaderMap, Uri};
+use pingora::http::RequestHeader;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use tracing::warn;
+
+use zentinel_config::{NormalizationMode, RequestHardeningConfig};
+
+use crate::hardening::{Check, Rejection};
+
+/// Requests normalized or rejected, by listener, step and action
+static NORMALIZATION: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_request_normalization_total",
+        "Requests normalized or rejected before route matching",
+        &["listener", "step", "action"]
+    )
+    .ok()
+});
+
+fn count(listener: &str, step: Step, action: &str) {
+    if let Some(counter) = NORMALIZATION.as_ref() {
+        counter
+            .with_label_values(&[listener, step.as_str(), action])
+            .inc();
+    }
+}
+
+/// Part of the request being normalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Framing,
+    Path,
+}
+
+impl Step {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Step::Framing => "framing",
+            Step::Path => "path",
+        }
+    }
+}
+
+/// Why a request has no canonical form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Failure {
+    step: Step,
+    reason: &'static str,
+}
+
+/// Changes that bring a request to its canonical form
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Canonical {
+    /// New `Content-Length` header; `Some(None)` removes it
+    content_length: Option<Option<u64>>,
+    /// New `Transfer-Encoding` header
+    transfer_encoding: Option<String>,
+    /// New path and query
+    path_and_query: Option<PathAndQuery>,
+}
+
+impl Canonical {
+    fn framing_changed(&self) -> bool {
+        self.content_length.is_some() || self.transfer_encoding.is_some()
+    }
+
+    fn path_changed(&self) -> bool {
+        self.path_and_query.is_some()
+    }
+}
+
+/// Normalize a request for a listener's `hardening { normalization }`
+/// settings; a no-op when normalization is not configured
+pub fn normalize(
+    listener: &str,
+    hardening: &RequestHardeningConfig,
+    req: &mut RequestHeader,
+) -> Result<(), Rejection> {
+    let Some(config) = &hardening.normalization else {
+        return Ok(());
+    };
+    let log_only = config.mode == NormalizationMode::LogOnly;
+
+    let canonical = match canonicalize(
+        req,
+        config.merge_slashes,
+        hardening.reject_ambiguous_framing,
+    ) {
+        Ok(canonical) => canonical,
+        Err(failure) if log_only => {
+            warn!(
+                listener = %listener,
+                step = failure.step.as_str(),
+                reason = failure.reason,
+                path = %req.uri,
+                "Request would be rejected by normalization"
+            );
+            count(listener, failure.step, "would_reject");
+            return Ok(());
+        }
+        Err(failure) => {
+            count(listener, failure.step, "rejected");
+            return Err(Rejection {
+                check: Check::Normalization,
+                status: hardening.status.normalization,
+                allow: None,
+            });
+        }
+    };
+
+    let action = if log_only {
+        "would_normalize"
+    } else {
+        "normalized"
+    };
+    if canonical.framing_changed() {
+        count(listener, Step::Framing, action);
+    }
+    if canonical.path_changed() {
+        count(listener, Step::Path, action);
+    }
+
+    if log_only {
+        if canonical != Canonical::default() {
+            warn!(
+                listener = %listener,
+                path = %req.uri,
+                canonical_path = ?canonical.path_and_query.as_ref().map(PathAndQuery::as_str),
+                content_length = ?canonical.content_length,
+                transfer_encoding = ?canonical.transfer_encoding,
+                "Request would be normalized"
+            );
+        }
+    } else {
+        apply(req, canonical);
+    }
+    Ok(())
+}
+
+/// Work out the canonical form of a request without changing it
+fn canonicalize(
+    req: &RequestHeader,
+    merge_slashes: bool,
+    keep_ambiguous_framing: bool,
+) -> Result<Canonical, Failure> {
+    let (content_length, transfer_encoding) =
+        canonical_framing(&req.headers, keep_ambiguous_framing).map_err(|reason| Failure {
+            step: Step::Framing,
+            reason,
+        })?;
+    let path_and_query =
+        canonical_path_and_query(&req.uri, merge_slashes).map_err(|reason| Failure {
+            step: Step::Path,
+            reason,
+        })?;
+    Ok(Canonical {
+        content_length,
+        transfer_encoding,
+        path_and_query,
+    })
+}
+
+fn apply(req: &mut RequestHeader, canonical: Canonical) {
+    if let Some(content_length) = canonical.content_length {
+        req.remove_header(&CONTENT_LENGTH);
+        if let Some(length) = content_length {
+            req.insert_header(CONTENT_LENGTH, length.to_string()).ok();
+        }
+    }
+    if let Some(transfer_encoding) = canonical.transfer_encoding {
+        req.remove_header(&TRANSFER_ENCODING);
+        req.insert_header(TRANSFER_ENCODING, transfer_encoding).ok();
+    }
+    if let Some(path_and_query) = canonical.path_and_query {
+        let mut parts = req.uri.clone().into_parts();
+        parts.path_and_query = Some(path_and_query);
+        if let Ok(uri) = Uri::from_parts(parts) {
+            req.set_uri(uri);
+        }
+    }
+}
+
+/// Canonical `Content-Length` and `Transfer-Encoding` headers, where they
+/// differ from the received ones
+#[allow(clippy::type_complexity)]
+fn canonical_framing(
+    headers: &HeaderMap,
+    keep_ambiguous: bool,
+) -> Result<(Option<Option<u64>>, Option<String>), &'static str> {
+    let mut length = None;
+    for value in headers
+        .get_all(CONTENT_LENGTH)
+        .iter()
+        .flat_map(|value| value.as_bytes().split(|b| *b == b','))
+    {
+        let parsed = std::str::from_utf8(value.trim_ascii())
+            .ok()
+            .filter(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or("invalid Content-Length")?;
+        if length.is_some_and(|length| length != parsed) {
+            return Err("conflicting Content-Length values");
+        }
+        length = Some(parsed);
+    }
+
+    let mut transfer_encoding = None;
+    if headers.contains_key(TRANSFER_ENCODING) {
+        let codings: Vec<String> = headers
+            .get_all(TRANSFER_ENCODING)
+            .iter()
+            .flat_map(|value| value.as_bytes().split(|b| *b == b','))
+            .map(|coding| String::from_utf8_lossy(coding.trim_ascii()).to_ascii_lowercase())
+            .filter(|coding| !coding.is_empty())
+            .collect();
+        let chunked = codings.iter().filter(|c| *c == "chunked").count();
+        if codings.last().map(String::as_str) != Some("chunked") || chunked > 1 {
+            return Err("Transfer-Encoding does not end in chunked");
+        }
+        let canonical = codings.join(", ");
+        let mut received = headers.get_all(TRANSFER_ENCODING).iter();
+        if received.next().map(|v| v.as_bytes()) != Some(canonical.as_bytes())
+            || received.next().is_some()
+        {
+            transfer_encoding = Some(canonical);
+        }
+    }
+
+    let content_length = match length {
+        None => None,
+        // The body is read as chunked; drop the length so no hop reads it
+        // the other way
+        Some(_) if headers.contains_key(TRANSFER_ENCODING) => (!keep_ambiguous).then_some(None),
+        Some(length) => {
+            let mut received = headers.get_all(CONTENT_LENGTH).iter();
+            let canonical = length.to_string();
+            (received.next().map(|v| v.as_bytes()) != Some(canonical.as_bytes())
+                || received.next().is_some())
+            .then_some(Some(length))
+        }
+    };
+
+    Ok((content_length, transfer_encoding))
+}
+
+/// Canonical path and query, where it differs from the received one.
+/// Only origin-form targets are normalized; `*` and authority-form targets
+/// are kept.
+fn canonical_path_and_query(
+    uri: &Uri,
+    merge_slashes: bool,
+) -> Result<Option<PathAndQuery>, &'static str> {
+    let path = uri.path();
+    if !path.starts_with('/') {
+        return Ok(None);
+    }
+
+    let mut canonical = canonical_path(path, merge_slashes)?;
+    if let Some(query) = uri.query() {
+        canonical.push('?');
+        canonical.push_str(query);
+    }
+    if uri.path_and_query().map(PathAndQuery::as_str) == Some(canonical.as_str()) {
+        return Ok(None);
+    }
+    PathAndQuery::try_from(canonical)
+        .map(Some)
+        .map_err(|_| "invalid path")
+}
+
+fn canonical_path(path: &str, merge_slashes: bool) -> Result<String, &'static str> {
+    let bytes = path.as_bytes();
+    let mut decoded = String::with_capacity(path.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            if !(merge_slashes && bytes[i] == b'/' && decoded.ends_with('/')) {
+                decoded.push(bytes[i] as char);
+            }
+            i += 1;
+            continue;
+        }
+        let value = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or("invalid percent-encoding")?;
+        if value == 0 {
+            return Err("encoded NUL");
+        }
+        if value.is_ascii_alphanumeric() || matches!(value, b'-' | b'.' | b'_' | b'~') {
+            decoded.push(value as char);
+        } else {
+            decoded.push_str(&format!("%{:02X}", value));
+        }
+        i += 3;
+    }
+    Ok(remove_dot_segments(&decoded))
+}
+
+/// Resolve `.` and `..` segments as RFC 3986 section 5.2.4 does
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path[1..].split('/').collect();
+    let last = segments.len() - 1;
+    let mut out: Vec<&str> = Vec::with_capacity(segments.len());
+    for (i, segment) in segments.into_iter().enumerate() {
+        match segment {
+            "." | ".." => {
+                if segment == ".." {
+                    out.pop();
+                }
+                if i == last {
+                    out.push("");
+                }
+            }
+            segment => out.push(segment),
+        }
+    }
+    format!("/{}", out.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_config::RequestNormalizationConfig;
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> RequestHeader {
+        let mut req = RequestHeader::build("POST", path.as_bytes(), None).unwrap();
+        for (name, value) in headers {
+            req.append_header(name.to_string(), *value).unwrap();
+        }
+        req
+    }
+
+    fn hardening(mode: NormalizationMode) -> RequestHardeningConfig {
+        RequestHardeningConfig {
+            normalization: Some(RequestNormalizationConfig {
+                mode,
+                merge_slashes: true,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_canonical_path() {
+        for (path, canonical) in [
+            ("/", "/"),
+            ("/api/users", "/api/users"),
+            ("/a/./b/../c", "/a/c"),
+            ("/a/b/..", "/a/"),
+            ("/../../etc/passwd", "/etc/passwd"),
+            ("/%61dmin/%2e%2e/secret", "/secret"),
+            ("/files/a%2fb%3f", "/files/a%2Fb%3F"),
+            ("/%7Euser", "/~user"),
+        ] {
+            assert_eq!(canonical_path(path, false).unwrap(), canonical, "{path}");
+        }
+
+        assert_eq!(canonical_path("//a///b/", true).unwrap(), "/a/b/");
+        assert_eq!(canonical_path("//a///b/", false).unwrap(), "//a///b/");
+
+        assert_eq!(
+            canonical_path("/a%zz", false),
+            Err("invalid percent-encoding")
+        );
+        assert_eq!(
+            canonical_path("/a%2", false),
+            Err("invalid percent-encoding")
+        );
+        assert_eq!(canonical_path("/a%00b", false), Err("encoded NUL"));
+    }
+
+    #[test]
+    fn test_canonical_framing() {
+        let headers = |pairs: &[(&str, &str)]| request("/", pairs).headers;
+
+        assert_eq!(canonical_framing(&headers(&[]), false), Ok((None, None)));
+        assert_eq!(
+            canonical_framing(&headers(&[("content-length", "5")]), false),
+            Ok((None, None))
+        );
+        assert_eq!(
+            canonical_framing(&headers(&[("content-length", "5, 005")]), false),
+            Ok((Some(Some(5)), None))
+        );
+        assert_eq!(
+            canonical_framing(
+                &headers(&[("content-length", "5"), ("transfer-encoding", "Chunked")]),
+                false
+            ),
+            Ok((Some(None), Some("chunked".to_string())))
+        );
+        // Left in place for reject-ambiguous-framing
+        assert_eq!(
+            canonical_framing(
+                &headers(&[("content-length", "5"), ("transfer-encoding", "chunked")]),
+                true
+            ),
+            Ok((None, None))
+        );
+        assert_eq!(
+            canonical_framing(
+                &headers(&[
+                    ("transfer-encoding", "gzip"),
+                    ("transfer-encoding", "chunked")
+                ]),
+                false
+            ),
+            Ok((None, Some("gzip, chunked".to_string())))
+        );
+
+        for invalid in [
+            &[("content-length", "5"), ("content-length", "6")][..],
+            &[("content-length", "+5")][..],
+            &[("content-length", "")][..],
+            &[("transfer-encoding", "chunked, gzip")][..],
+            &[("transfer-encoding", "chunked, chunked")][..],
+            &[("transfer-encoding", "identity")][..],
+        ] {
+            assert!(
+                canonical_framing(&headers(invalid), false).is_err(),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_rewrites_and_rejects() {
+        let config = hardening(NormalizationMode::Strict);
+
+        let mut req = request(
+            "/api//v1/./users/%2e%2e/items?q=%2e",
+            &[("content-length", "3"), ("transfer-encoding", "chunked")],
+        );
+        normalize("public", &config, &mut req).unwrap();
+        assert_eq!(req.uri.path_and_query().unwrap(), "/api/v1/items?q=%2e");
+        assert!(req.headers.get(CONTENT_LENGTH).is_none());
+        assert_eq!(req.headers.get(TRANSFER_ENCODING).unwrap(), "chunked");
+
+        let mut req = request("/a%00", &[]);
+        let rejection = normalize("public", &config, &mut req).unwrap_err();
+        assert_eq!(rejection.check, Check::Normalization);
+        assert_eq!(rejection.status, 400);
+
+        let mut unconfigured = request("/a/../b", &[]);
+        normalize(
+            "public",
+            &RequestHardeningConfig::default(),
+            &mut unconfigured,
+        )
+        .unwrap();
+        assert_eq!(unconfigured.uri.path(), "/a/../b");
+    }
+
+    #[test]
+    fn test_log_only_mode_forwards_unchanged() {
+        let config = hardening(NormalizationMode::LogOnly);
+
+        let mut req = request("/a/../b", &[("content-length", "1, 1")]);
+        normalize("public", &config, &mut req).unwrap();
+        assert_eq!(req.uri.path(), "/a/../b");
+        assert_eq!(req.headers.get(CONTENT_LENGTH).unwrap(), "1, 1");
+
+        let mut req = request("/a", &[("content-length", "1"), ("content-length", "2")]);
+        assert!(normalize("public", &config, &mut req).is_ok());
+    }
+}
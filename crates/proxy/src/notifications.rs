@@ -0,0 +1,707 @@
+//! Webhook notifications for security and availability events
+//!
+//! Configured by the top-level `notifications` block. Events are posted as
+//! JSON to every webhook subscribed to them:
+//!
+//! - `circuit-breaker-open`: an upstream target's or agent's circuit
+//!   breaker opened
+//! - `agent-unhealthy`: an agent lost its healthy connections or its
+//!   breaker opened
+//! - `block-rate-spike`: the share of blocked requests in a window rose
+//!   above `block-rate { threshold }`
+//! - `certificate-expiry`: a listener or ACME certificate expires within
+//!   `certificate-expiry-days` (repeated daily)
+//! - `guardrail-detection`: a guardrail agent reported a detection at or
+//!   above `guardrail-min-severity`
+//! - `alert-firing` / `alert-resolved`: an `alert` rule started firing or
+//!   resolved (see [`crate::alerts`])
+//! - `config-rolled-back`: a staged configuration rollout regressed and
+//!   was rolled back (see [`crate::rollout`])
+//!
+//! The same event for the same subject is sent at most once per
+//! `cooldown-secs`. Deliveries run in the background: each webhook gets
+//! `max-retries` more attempts after network errors, 429 and 5xx answers,
+//! with exponential backoff.
+//!
+//! # Payload
+//!
+//! ```json
+//! {
+//!   "event": "circuit-breaker-open",
+//!   "severity": "high",
+//!   "subject": "upstream:api/10.0.0.1:8080",
+//!   "message": "Circuit breaker opened for upstream target api/10.0.0.1:8080",
+//!   "timestamp": "2026-01-01T12:00:00+00:00",
+//!   "details": { "kind": "upstream", "name": "api/10.0.0.1:8080" }
+//! }
+//! ```
+//!
+//! With a `secret`, requests carry `X-Zentinel-Timestamp` (Unix seconds)
+//! and `X-Zentinel-Signature: sha256=<hex>`, the HMAC-SHA256 of
+//! `<timestamp>.<body>` keyed with the secret.
+
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use dashmap::DashMap;
+use hmac::{Hmac, KeyInit, Mac};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{debug, info, warn};
+
+use zentinel_agent_protocol::{DetectionSeverity, GuardrailDetection};
+use zentinel_common::observability::RequestMetrics;
+use zentinel_config::{
+    BlockRateAlertConfig, NotificationEvent, NotificationSeverity, NotificationsConfig,
+    WebhookConfig,
+};
+
+use crate::agents::AgentManager;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Webhook deliveries per webhook, event and outcome
+static NOTIFICATIONS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_notifications_total",
+        "Webhook notification deliveries",
+        &["webhook", "event", "outcome"]
+    )
+    .ok()
+});
+
+/// The active notifier, replaced on reload
+static NOTIFIER: LazyLock<ArcSwap<Notifier>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Notifier::new(None)));
+
+/// How often agent health, block rates, certificates and alert rules are
+/// checked
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Certificate expiry reminders are repeated this often
+const CERTIFICATE_REMINDER_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Set the notification configuration
+///
+/// Called at startup and on every config reload. Cooldowns and tracked
+/// certificates carry over to the new configuration.
+pub fn configure(config: Option<&NotificationsConfig>) {
+    if NOTIFIER.load().config.as_ref() == config {
+        return;
+    }
+    if let Some(config) = config {
+        info!(
+            webhooks = config.webhooks.len(),
+            cooldown_secs = config.cooldown_secs,
+            "Webhook notifications configured"
+        );
+    }
+    let notifier = Notifier::new(config.cloned());
+    // Certificates are only reported when loaded; keep the ones seen so far
+    let previous = NOTIFIER.load();
+    for entry in previous.last_sent.iter() {
+        notifier
+            .last_sent
+            .insert(entry.key().clone(), *entry.value());
+    }
+    for entry in previous.certificates.iter() {
+        notifier
+            .certificates
+            .insert(entry.key().clone(), *entry.value());
+    }
+    NOTIFIER.store(Arc::new(notifier));
+}
+
+/// The active notifier
+pub fn notifier() -> Arc<Notifier> {
+    NOTIFIER.load_full()
+}
+
+/// An event to notify
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub severity: NotificationSeverity,
+    /// What the event is about, e.g. `agent:waf`; cooldowns are per subject
+    pub subject: String,
+    pub message: String,
+    pub timestamp: String,
+    pub details: serde_json::Value,
+}
+
+impl Notification {
+    pub fn new(
+        event: NotificationEvent,
+        severity: NotificationSeverity,
+        subject: impl Into<String>,
+        message: impl Into<String>,
+        details: serde_json::Value,
+    ) -> Self {
+        Self {
+            event,
+            severity,
+            subject: subject.into(),
+            message: message.into(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            details,
+        }
+    }
+}
+
+/// Sends notifications to the configured webhooks
+pub struct Notifier {
+    config: Option<NotificationsConfig>,
+    client: Option<reqwest::Client>,
+    /// Last notification per event and subject
+    last_sent: DashMap<(NotificationEvent, String), Instant>,
+    /// `not_after` (Unix seconds) per certificate subject
+    certificates: DashMap<String, i64>,
+}
+
+impl Notifier {
+    fn new(config: Option<NotificationsConfig>) -> Self {
+        let client = config
+            .as_ref()
+            .filter(|config| !config.webhooks.is_empty())
+            .and_then(|_| match crate::outbound::client_builder().build() {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    warn!(error = %e, "Failed to create webhook client, notifications disabled");
+                    None
+                }
+            });
+        Self {
+            config,
+            client,
+            last_sent: DashMap::new(),
+            certificates: DashMap::new(),
+        }
+    }
+
+    /// Whether any webhook receives `event`
+    pub fn enabled(&self, event: NotificationEvent) -> bool {
+        self.client.is_some()
+            && self
+                .config
+                .as_ref()
+                .is_some_and(|config| config.webhooks.iter().any(|w| w.wants(event)))
+    }
+
+    /// Send a notification to the webhooks subscribed to its event, unless
+    /// one was sent for the same subject within the cooldown
+    pub fn notify(&self, notification: Notification) {
+        let cooldown = match notification.event {
+            NotificationEvent::CertificateExpiry => CERTIFICATE_REMINDER_INTERVAL,
+            _ => Duration::from_secs(self.config.as_ref().map_or(0, |c| c.cooldown_secs)),
+        };
+        let (Some(config), Some(client)) = (self.config.as_ref(), self.client.as_ref()) else {
+            return;
+        };
+        if !self.enabled(notification.event) {
+            return;
+        }
+
+        let key = (notification.event, notification.subject.clone());
+        let now = Instant::now();
+        let mut suppressed = false;
+        self.last_sent
+            .entry(key)
+            .and_modify(|last| {
+                if now.duration_since(*last) < cooldown {
+                    suppressed = true;
+                } else {
+                    *last = now;
+                }
+            })
+            .or_insert(now);
+        if suppressed {
+            debug!(
+                event = notification.event.as_str(),
+                subject = %notification.subject,
+                "Notification suppressed by cooldown"
+            );
+            return;
+        }
+
+        let body = match serde_json::to_vec(&notification) {
+            Ok(body) => Bytes::from(body),
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize notification");
+                return;
+            }
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!(
+                event = notification.event.as_str(),
+                "No runtime for notification delivery, dropping it"
+            );
+            return;
+        };
+
+        for webhook in config
+            .webhooks
+            .iter()
+            .filter(|w| w.wants(notification.event))
+        {
+            runtime.spawn(deliver(
+                client.clone(),
+                webhook.clone(),
+                notification.event,
+                body.clone(),
+            ));
+        }
+    }
+
+    /// Remember a certificate's expiry; it is checked by the monitor
+    pub fn track_certificate(&self, subject: &str, not_after: i64) {
+        self.certificates.insert(subject.to_string(), not_after);
+    }
+
+    /// Notify certificates expiring within `certificate-expiry-days`
+    fn check_certificates(&self) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        let horizon = i64::from(config.certificate_expiry_days) * 86_400;
+        for entry in self.certificates.iter() {
+            let remaining = *entry.value() - now;
+            if remaining > horizon {
+                continue;
+            }
+            let days_left = remaining.div_euclid(86_400);
+            let severity = if remaining <= 0 {
+                NotificationSeverity::Critical
+            } else if days_left < 3 {
+                NotificationSeverity::High
+            } else {
+                NotificationSeverity::Medium
+            };
+            let message = if remaining <= 0 {
+                format!("Certificate {} has expired", entry.key())
+            } else {
+                format!("Certificate {} expires in {} days", entry.key(), days_left)
+            };
+            self.notify(Notification::new(
+                NotificationEvent::CertificateExpiry,
+                severity,
+                format!("certificate:{}", entry.key()),
+                message,
+                json!({
+                    "certificate": entry.key(),
+                    "not_after": chrono::DateTime::from_timestamp(*entry.value(), 0)
+                        .map(|t| t.to_rfc3339()),
+                    "days_left": days_left,
+                }),
+            ));
+        }
+    }
+}
+
+/// Post a notification, retrying failed deliveries with backoff
+async fn deliver(
+    client: reqwest::Client,
+    webhook: WebhookConfig,
+    event: NotificationEvent,
+    body: Bytes,
+) {
+    let mut backoff = Duration::from_millis(webhook.retry_backoff_ms);
+    let mut attempt = 0;
+    let outcome = loop {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let mut request = client
+            .post(&webhook.url)
+            .timeout(Duration::from_millis(webhook.timeout_ms))
+            .header("Content-Type", "application/json")
+            .header("X-Zentinel-Event", event.as_str())
+            .header("X-Zentinel-Timestamp", &timestamp)
+            .body(body.clone());
+        if let Some(secret) = webhook.secret.as_deref() {
+            request = request.header("X-Zentinel-Signature", sign(secret, &timestamp, &body));
+        }
+
+        let retryable = match request.send().await {
+            Ok(response) if response.status().is_success() => break "delivered",
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    webhook = %webhook.id,
+                    event = event.as_str(),
+                    status = status.as_u16(),
+                    attempt = attempt + 1,
+                    "Webhook rejected notification"
+                );
+                status.is_server_error() || status.as_u16() == 429
+            }
+            Err(e) => {
+                warn!(
+                    webhook = %webhook.id,
+                    event = event.as_str(),
+                    error = %e,
+                    attempt = attempt + 1,
+                    "Webhook notification delivery failed"
+                );
+                true
+            }
+        };
+        if !retryable {
+            break "rejected";
+        }
+        if attempt >= webhook.max_retries {
+            break "failed";
+        }
+        attempt += 1;
+        tokio::time::sleep(backoff).await;
+        backoff = backoff.saturating_mul(2);
+    };
+
+    if let Some(metric) = NOTIFICATIONS.as_ref() {
+        metric
+            .with_label_values(&[webhook.id.as_str(), event.as_str(), outcome])
+            .inc();
+    }
+}
+
+/// `X-Zentinel-Signature` value for a payload
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC key length is valid");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// =============================================================================
+// Event sources
+// =============================================================================
+
+/// A circuit breaker opened; `kind` is `upstream` or `agent`
+pub fn circuit_breaker_opened(kind: &str, name: &str) {
+    let notifier = notifier();
+    if !notifier.enabled(NotificationEvent::CircuitBreakerOpen) {
+        return;
+    }
+    let what = match kind {
+        "upstream" => "upstream target",
+        other => other,
+    };
+    notifier.notify(Notification::new(
+        NotificationEvent::CircuitBreakerOpen,
+        NotificationSeverity::High,
+        format!("{}:{}", kind, name),
+        format!("Circuit breaker opened for {} {}", what, name),
+        json!({ "kind": kind, "name": name }),
+    ));
+}
+
+/// A staged configuration rollout regressed and the previous configuration
+/// was re-applied; `generation` numbers the rollout
+pub fn config_rolled_back(generation: u64, reason: &str, details: serde_json::Value) {
+    let notifier = notifier();
+    if !notifier.enabled(NotificationEvent::ConfigRolledBack) {
+        return;
+    }
+    notifier.notify(Notification::new(
+        NotificationEvent::ConfigRolledBack,
+        NotificationSeverity::High,
+        format!("rollout:{}", generation),
+        format!("Staged configuration rolled back: {}", reason),
+        details,
+    ));
+}
+
+/// A guardrail agent answered with detections; those at or above the
+/// configured severity are notified
+pub fn guardrail_detections(
+    check: &str,
+    route_id: Option<&str>,
+    detections: &[GuardrailDetection],
+) {
+    let notifier = notifier();
+    let Some(min_severity) = notifier
+        .config
+        .as_ref()
+        .map(|config| config.guardrail_min_severity)
+    else {
+        return;
+    };
+    if !notifier.enabled(NotificationEvent::GuardrailDetection) {
+        return;
+    }
+
+    let reported: Vec<&GuardrailDetection> = detections
+        .iter()
+        .filter(|d| severity_of(d.severity) >= min_severity)
+        .collect();
+    let Some(severity) = reported.iter().map(|d| severity_of(d.severity)).max() else {
+        return;
+    };
+    let route = route_id.unwrap_or("unknown");
+    let categories: Vec<&str> = reported.iter().map(|d| d.category.as_str()).collect();
+    notifier.notify(Notification::new(
+        NotificationEvent::GuardrailDetection,
+        severity,
+        format!("guardrail:{}:{}", check, route),
+        format!(
+            "Guardrail {} detections on route {}: {}",
+            check,
+            route,
+            categories.join(", ")
+        ),
+        json!({
+            "check": check,
+            "route": route,
+            "detections": reported
+                .iter()
+                .map(|d| json!({
+                    "category": d.category,
+                    "description": d.description,
+                    "severity": severity_of(d.severity).as_str(),
+                    "confidence": d.confidence,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    ));
+}
+
+fn severity_of(severity: DetectionSeverity) -> NotificationSeverity {
+    match severity {
+        DetectionSeverity::Low => NotificationSeverity::Low,
+        DetectionSeverity::Medium => NotificationSeverity::Medium,
+        DetectionSeverity::High => NotificationSeverity::High,
+        DetectionSeverity::Critical => NotificationSeverity::Critical,
+    }
+}
+
+/// A certificate was loaded; `not_after` is a Unix timestamp
+pub fn certificate_loaded(subject: &str, not_after: i64) {
+    notifier().track_certificate(subject, not_after);
+}
+
+/// Share of requests blocked between two samples of the request and block
+/// totals, if it is a spike
+pub fn block_rate_spike(
+    config: &BlockRateAlertConfig,
+    previous: (u64, u64),
+    current: (u64, u64),
+) -> Option<f64> {
+    let requests = current.0.saturating_sub(previous.0);
+    let blocked = current.1.saturating_sub(previous.1);
+    if requests == 0 || requests < config.min_requests {
+        return None;
+    }
+    let rate = blocked as f64 / requests as f64;
+    (rate >= config.threshold).then_some(rate)
+}
+
+/// Spawn the task that watches agent health, block rates and certificate
+/// expiry, and evaluates alert rules
+pub fn spawn_monitor(agent_manager: Arc<AgentManager>, metrics: Arc<RequestMetrics>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MONITOR_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut unhealthy_agents: HashSet<String> = HashSet::new();
+        let mut window_start = (
+            Instant::now(),
+            metrics.requests_total(),
+            metrics.blocked_requests_total(),
+        );
+
+        loop {
+            interval.tick().await;
+            let notifier = notifier();
+
+            // Agents that became unhealthy since the last check
+            if notifier.enabled(NotificationEvent::AgentUnhealthy) {
+                let mut now_unhealthy = HashSet::new();
+                for (agent_id, problem) in agent_manager.agent_health().await {
+                    let Some(problem) = problem else {
+                        continue;
+                    };
+                    if !unhealthy_agents.contains(&agent_id) {
+                        notifier.notify(Notification::new(
+                            NotificationEvent::AgentUnhealthy,
+                            NotificationSeverity::High,
+                            format!("agent:{}", agent_id),
+                            format!("Agent {} is unhealthy: {}", agent_id, problem),
+                            json!({ "agent": agent_id, "reason": problem }),
+                        ));
+                    }
+                    now_unhealthy.insert(agent_id);
+                }
+                unhealthy_agents = now_unhealthy;
+            }
+
+            // Block rate over the last window
+            if let Some(config) = notifier.config.as_ref() {
+                let window = Duration::from_secs(config.block_rate.window_secs.max(1));
+                if window_start.0.elapsed() >= window {
+                    let current = (metrics.requests_total(), metrics.blocked_requests_total());
+                    let previous = (window_start.1, window_start.2);
+                    if let Some(rate) = block_rate_spike(&config.block_rate, previous, current) {
+                        notifier.notify(Notification::new(
+                            NotificationEvent::BlockRateSpike,
+                            NotificationSeverity::High,
+                            "block-rate",
+                            format!(
+                                "{:.1}% of requests blocked in the last {}s",
+                                rate * 100.0,
+                                window.as_secs()
+                            ),
+                            json!({
+                                "block_rate": rate,
+                                "threshold": config.block_rate.threshold,
+                                "requests": current.0.saturating_sub(previous.0),
+                                "blocked": current.1.saturating_sub(previous.1),
+                                "window_secs": window.as_secs(),
+                            }),
+                        ));
+                    }
+                    window_start = (Instant::now(), current.0, current.1);
+                }
+            }
+
+            if notifier.enabled(NotificationEvent::CertificateExpiry) {
+                notifier.check_certificates();
+            }
+
+            // Alert rules are evaluated even without webhooks for the admin API
+            if crate::alerts::has_rules() {
+                let sample = crate::alerts::MetricsSample::collect(&metrics, &agent_manager).await;
+                for notification in crate::alerts::evaluate(sample) {
+                    notifier.notify(notification);
+                }
+            }
+        }
+    });
+
+    info!("Started notification monitor task");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256("secret", "1700000000.{}")
+        let signature = sign("secret", "1700000000", b"{}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_eq!(signature, sign("secret", "1700000000", b"{}"));
+        assert_ne!(signature, sign("secret", "1700000001", b"{}"));
+        assert_ne!(signature, sign("other", "1700000000", b"{}"));
+    }
+
+    #[test]
+    fn test_block_rate_spike() {
+        let config = BlockRateAlertConfig {
+            threshold: 0.25,
+            min_requests: 100,
+            window_secs: 60,
+        };
+        // 30 of 100 requests blocked
+        assert_eq!(block_rate_spike(&config, (1000, 10), (1100, 40)), Some(0.3));
+        // Below the threshold
+        assert_eq!(block_rate_spike(&config, (1000, 10), (1100, 20)), None);
+        // Too few requests to tell
+        assert_eq!(block_rate_spike(&config, (1000, 10), (1050, 50)), None);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_and_signs() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in ["503 Service Unavailable", "204 No Content"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut received = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Headers and the two-byte body
+                while !received.ends_with(b"\r\n\r\n{}") {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    received.extend_from_slice(&buf[..read]);
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8(received).unwrap());
+            }
+            requests
+        });
+
+        let mut webhook = WebhookConfig::new("hook", url);
+        webhook.secret = Some("secret".to_string());
+        webhook.retry_backoff_ms = 10;
+        deliver(
+            reqwest::Client::new(),
+            webhook,
+            NotificationEvent::BlockRateSpike,
+            Bytes::from_static(b"{}"),
+        )
+        .await;
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let request = requests[1].to_ascii_lowercase();
+        assert!(request.contains("x-zentinel-event: block-rate-spike\r\n"));
+        let header = |name: &str| {
+            request
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        let timestamp = header("x-zentinel-timestamp:");
+        assert_eq!(
+            header("x-zentinel-signature:"),
+            sign("secret", &timestamp, b"{}")
+        );
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut config = NotificationsConfig::default();
+        config.webhooks.push(WebhookConfig::new(
+            "hook",
+            "http://127.0.0.1:9/notifications",
+        ));
+        let notifier = Notifier::new(Some(config));
+        assert!(notifier.enabled(NotificationEvent::AgentUnhealthy));
+
+        let notification = || {
+            Notification::new(
+                NotificationEvent::AgentUnhealthy,
+                NotificationSeverity::High,
+                "agent:waf",
+                "Agent waf is unhealthy",
+                json!({}),
+            )
+        };
+        // No runtime: the delivery is dropped, but the cooldown starts
+        notifier.notify(notification());
+        let first = *notifier
+            .last_sent
+            .get(&(NotificationEvent::AgentUnhealthy, "agent:waf".to_string()))
+            .unwrap();
+        notifier.notify(notification());
+        let second = *notifier
+            .last_sent
+            .get(&(NotificationEvent::AgentUnhealthy, "agent:waf".to_string()))
+            .unwrap();
+        assert_eq!(first, second);
+
+        assert!(!Notifier::new(None).enabled(NotificationEvent::AgentUnhealthy));
+    }
+}
@@ -0,0 +1,386 @@
+//! Route generation from OpenAPI documents (`zentinel config from-openapi`).
+//!
+//! Turns the `paths` of an OpenAPI 3.x document into KDL route definitions,
+//! so large APIs don't have to be transcribed route by route:
+//!
+//! - one route per path template, matching its methods; concrete paths get
+//!   `priority "high"` so they win over overlapping templates
+//! - templated paths (`/users/{id}`) become `path-regex` matches, others
+//!   exact `path` matches, both under the path of the first `servers` URL
+//! - a `schema-validate` filter on every route, optionally serving the
+//!   document itself (`serve-spec`)
+//! - a placeholder upstream, pointed at the `servers` URL when it names a
+//!   host
+//!
+//! The output is a starting point meant to be reviewed and merged into the
+//! configuration.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::schema_validate::{server_path, OPERATION_METHODS};
+
+/// Target of the generated upstream when the document names no host
+const PLACEHOLDER_TARGET: &str = "127.0.0.1:8080";
+
+/// How routes are generated
+#[derive(Debug, Clone)]
+pub struct OpenApiImportOptions {
+    /// Prefix of the generated route and filter IDs
+    pub name: String,
+    /// Upstream the routes forward to
+    pub upstream: String,
+    /// Document path referenced by the schema-validate filter; `None`
+    /// generates routes without validation
+    pub schema: Option<String>,
+    /// Path at which the proxy serves the document
+    pub serve_spec: Option<String>,
+}
+
+/// A route generated for one path template
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedRoute {
+    pub id: String,
+    /// Path template as written in the document
+    pub template: String,
+    pub matcher: PathMatcher,
+    /// Upper-case methods of the template's operations
+    pub methods: Vec<String>,
+    /// Operation IDs, for a comment above the route
+    pub operations: Vec<String>,
+}
+
+/// Path match of a generated route
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathMatcher {
+    Exact(String),
+    Regex(String),
+}
+
+/// Read an OpenAPI document from a JSON or YAML file
+pub fn load_document(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read OpenAPI document {:?}", path))?;
+    let document: Value = if path.extension().is_some_and(|e| e == "yaml" || e == "yml") {
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse OpenAPI document {:?}", path))?
+    } else {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse OpenAPI document {:?}", path))?
+    };
+    Ok(document)
+}
+
+/// Routes for the paths of a document
+pub fn plan_routes(document: &Value, name: &str) -> Result<Vec<GeneratedRoute>> {
+    let version = document
+        .get("openapi")
+        .and_then(Value::as_str)
+        .context("Document has no 'openapi' version field")?;
+    if !version.starts_with("3.") {
+        bail!("Unsupported OpenAPI version '{}' (expected 3.x)", version);
+    }
+    let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+        bail!("Document has no 'paths'");
+    };
+
+    let base_path = document
+        .pointer("/servers/0/url")
+        .and_then(Value::as_str)
+        .map(server_path)
+        .unwrap_or_default();
+
+    let mut routes: Vec<GeneratedRoute> = Vec::new();
+    for (template, item) in paths {
+        let methods: Vec<String> = OPERATION_METHODS
+            .iter()
+            .filter(|m| item.get(**m).is_some())
+            .map(|m| m.to_ascii_uppercase())
+            .collect();
+        if methods.is_empty() {
+            continue;
+        }
+        let operations = OPERATION_METHODS
+            .iter()
+            .filter_map(|m| item.get(*m)?.get("operationId")?.as_str())
+            .map(str::to_string)
+            .collect();
+
+        let mut id = format!("{}-{}", name, slug(template));
+        let base = id.clone();
+        let mut n = 2;
+        while routes.iter().any(|r| r.id == id) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        routes.push(GeneratedRoute {
+            id,
+            template: template.clone(),
+            matcher: path_matcher(&base_path, template),
+            methods,
+            operations,
+        });
+    }
+
+    if routes.is_empty() {
+        bail!("Document defines no operations");
+    }
+    Ok(routes)
+}
+
+/// KDL configuration for a document
+pub fn generate(document: &Value, options: &OpenApiImportOptions) -> Result<String> {
+    let routes = plan_routes(document, &options.name)?;
+    let filter_id = format!("{}-schema", options.name);
+
+    let mut out = String::new();
+    let title = document.pointer("/info/title").and_then(Value::as_str);
+    let version = document.pointer("/info/version").and_then(Value::as_str);
+    let _ = writeln!(out, "// Generated by `zentinel config from-openapi`");
+    if let Some(title) = title {
+        let _ = writeln!(out, "// {} {}", title, version.unwrap_or_default());
+    }
+    out.push('\n');
+
+    if let Some(schema) = &options.schema {
+        let _ = writeln!(out, "filters {{");
+        let _ = writeln!(out, "    filter {} {{", kdl_string(&filter_id));
+        let _ = writeln!(out, "        type \"schema-validate\"");
+        let _ = writeln!(out, "        schema {}", kdl_string(schema));
+        if let Some(path) = &options.serve_spec {
+            let _ = writeln!(out, "        serve-spec {}", kdl_string(path));
+        }
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    let filters = options
+        .schema
+        .as_ref()
+        .map(|_| format!("        filters {}\n", kdl_string(&filter_id)))
+        .unwrap_or_default();
+
+    let _ = writeln!(out, "routes {{");
+    for (i, route) in routes.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "    // {}", route.template);
+        if !route.operations.is_empty() {
+            let _ = writeln!(out, "    // Operations: {}", route.operations.join(", "));
+        }
+        let _ = writeln!(out, "    route {} {{", kdl_string(&route.id));
+        let matcher = match &route.matcher {
+            PathMatcher::Exact(path) => {
+                let _ = writeln!(out, "        priority \"high\"");
+                format!("path {}", kdl_string(path))
+            }
+            PathMatcher::Regex(pattern) => format!("path-regex {}", kdl_string(pattern)),
+        };
+        let methods: Vec<String> = route.methods.iter().map(|m| kdl_string(m)).collect();
+        let _ = writeln!(out, "        matches {{");
+        let _ = writeln!(out, "            {}", matcher);
+        let _ = writeln!(out, "            method {}", methods.join(" "));
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "        upstream {}", kdl_string(&options.upstream));
+        out.push_str(&filters);
+        let _ = writeln!(out, "    }}");
+    }
+    if let (Some(path), Some(_)) = (&options.serve_spec, &options.schema) {
+        let _ = writeln!(out, "\n    // Serves the OpenAPI document");
+        let _ = writeln!(
+            out,
+            "    route {} {{",
+            kdl_string(&format!("{}-spec", options.name))
+        );
+        let _ = writeln!(out, "        priority \"high\"");
+        let _ = writeln!(out, "        matches {{");
+        let _ = writeln!(out, "            path {}", kdl_string(path));
+        let _ = writeln!(out, "            method \"GET\" \"HEAD\"");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "        upstream {}", kdl_string(&options.upstream));
+        out.push_str(&filters);
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "}}\n");
+
+    let target = document
+        .pointer("/servers/0/url")
+        .and_then(Value::as_str)
+        .and_then(server_authority)
+        .unwrap_or_else(|| PLACEHOLDER_TARGET.to_string());
+    let _ = writeln!(out, "upstreams {{");
+    let _ = writeln!(out, "    // Placeholder: replace with the API's backends");
+    let _ = writeln!(out, "    upstream {} {{", kdl_string(&options.upstream));
+    let _ = writeln!(out, "        target {}", kdl_string(&target));
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+
+    Ok(out)
+}
+
+/// Match for a path template below `base_path`
+fn path_matcher(base_path: &str, template: &str) -> PathMatcher {
+    let path = format!("{}{}", base_path, template);
+    if !template.contains('{') {
+        return PathMatcher::Exact(path);
+    }
+
+    let mut pattern = String::from("^");
+    let mut rest = path.as_str();
+    while let Some(start) = rest.find('{') {
+        pattern.push_str(&regex::escape(&rest[..start]));
+        match rest[start..].find('}') {
+            Some(end) => {
+                pattern.push_str("[^/]+");
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+    pattern.push_str(&regex::escape(rest));
+    pattern.push('$');
+    PathMatcher::Regex(pattern)
+}
+
+/// Route ID fragment of a path template: `/users/{id}/orders` → `users-id-orders`
+fn slug(template: &str) -> String {
+    let mut slug = String::new();
+    for c in template.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "root".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// `host:port` of an absolute `servers` URL
+fn server_authority(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next().filter(|a| !a.is_empty())?;
+    if authority.contains('{') {
+        // Server variables are not expanded
+        return None;
+    }
+    if authority
+        .rsplit_once(':')
+        .is_some_and(|(_, p)| p.parse::<u16>().is_ok())
+    {
+        return Some(authority.to_string());
+    }
+    let port = if scheme.eq_ignore_ascii_case("https") {
+        443
+    } else {
+        80
+    };
+    Some(format!("{}:{}", authority, port))
+}
+
+/// Quoted KDL string
+fn kdl_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Orders API", "version": "1.2.0" },
+            "servers": [{ "url": "https://orders.internal/v1" }],
+            "paths": {
+                "/orders": {
+                    "get": { "operationId": "listOrders" },
+                    "post": { "operationId": "createOrder" }
+                },
+                "/orders/{id}": {
+                    "parameters": [],
+                    "get": { "operationId": "getOrder" },
+                    "delete": {}
+                },
+                "/files/{name}.{ext}": { "get": {} }
+            }
+        })
+    }
+
+    #[test]
+    fn test_plan_routes() {
+        let routes = plan_routes(&document(), "orders").unwrap();
+        assert_eq!(routes.len(), 3);
+
+        let list = routes.iter().find(|r| r.template == "/orders").unwrap();
+        assert_eq!(list.id, "orders-orders");
+        assert_eq!(list.matcher, PathMatcher::Exact("/v1/orders".to_string()));
+        assert_eq!(list.methods, vec!["GET", "POST"]);
+        assert_eq!(list.operations, vec!["listOrders", "createOrder"]);
+
+        let get = routes
+            .iter()
+            .find(|r| r.template == "/orders/{id}")
+            .unwrap();
+        assert_eq!(get.id, "orders-orders-id");
+        assert_eq!(
+            get.matcher,
+            PathMatcher::Regex("^/v1/orders/[^/]+$".to_string())
+        );
+        assert_eq!(get.methods, vec!["GET", "DELETE"]);
+
+        let file = routes
+            .iter()
+            .find(|r| r.template.starts_with("/files"))
+            .unwrap();
+        assert_eq!(
+            file.matcher,
+            PathMatcher::Regex(r"^/v1/files/[^/]+\.[^/]+$".to_string())
+        );
+    }
+
+    #[test]
+    fn test_generated_config_parses() {
+        let options = OpenApiImportOptions {
+            name: "orders".to_string(),
+            upstream: "orders-backend".to_string(),
+            schema: Some("/etc/zentinel/orders.yaml".to_string()),
+            serve_spec: Some("/v1/openapi.json".to_string()),
+        };
+        let kdl = generate(&document(), &options).unwrap();
+        assert!(kdl.contains(r#"target "orders.internal:443""#));
+        assert!(kdl.contains(r#"path-regex "^/v1/files/[^/]+\\.[^/]+$""#));
+
+        let listeners = r#"listeners {
+    listener "http" {
+        address "0.0.0.0:8080"
+        protocol "http"
+    }
+}
+"#;
+        let config = zentinel_config::Config::from_kdl(&format!("{listeners}{kdl}")).unwrap();
+        assert!(config.filters.contains_key("orders-schema"));
+        let ids: Vec<&str> = config.routes.iter().map(|r| r.id.as_str()).collect();
+        assert!(ids.contains(&"orders-orders-id"));
+        assert!(ids.contains(&"orders-spec"));
+    }
+
+    #[test]
+    fn test_rejects_non_openapi_documents() {
+        assert!(plan_routes(&json!({ "type": "object" }), "api").is_err());
+        assert!(plan_routes(&json!({ "openapi": "2.0", "paths": {} }), "api").is_err());
+        assert!(plan_routes(&json!({ "openapi": "3.0.3", "paths": {} }), "api").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Startup preflight checks (`--preflight`).
+//!
+//! Run after the proxy is built and before any listener is bound: every
+//! agent must have completed its handshake, every upstream target must
+//! resolve (and accept a TCP connection with `--probe-upstreams`) and the
+//! TLS material of every listener must load. With `--strict` a failed check
+//! aborts startup; otherwise it is logged and the component starts out
+//! unhealthy in the `healthz` report.
+
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use zentinel_common::HealthStatus;
+use zentinel_config::validate::certs::validate_listener_certificates;
+use zentinel_config::Config;
+
+use crate::dns;
+
+/// Time allowed for resolving or connecting to one upstream target
+const DEFAULT_TARGET_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How preflight runs
+#[derive(Debug, Clone)]
+pub struct PreflightOptions {
+    /// Fail startup on any failed check
+    pub strict: bool,
+    /// Connect to upstream targets, not just resolve them
+    pub probe_upstreams: bool,
+    /// Time allowed per upstream target
+    pub target_timeout: Duration,
+}
+
+impl Default for PreflightOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            probe_upstreams: false,
+            target_timeout: DEFAULT_TARGET_TIMEOUT,
+        }
+    }
+}
+
+/// A component that failed preflight
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightFailure {
+    /// Component name as used in health reports (`agent:<id>`,
+    /// `upstream:<id>`, `tls:<listener>`)
+    pub component: String,
+    /// `Degraded` when only part of the component failed
+    pub status: HealthStatus,
+    /// What failed
+    pub message: String,
+}
+
+/// Outcome of all preflight checks
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    /// Components that failed
+    pub failures: Vec<PreflightFailure>,
+    /// Problems that do not fail preflight (e.g. certificates close to expiry)
+    pub warnings: Vec<String>,
+}
+
+impl PreflightReport {
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Log every failure and warning
+    pub fn log(&self) {
+        for failure in &self.failures {
+            warn!(
+                component = %failure.component,
+                status = ?failure.status,
+                "Preflight check failed: {}",
+                failure.message
+            );
+        }
+        for warning in &self.warnings {
+            warn!("Preflight warning: {}", warning);
+        }
+        if self.passed() {
+            info!(warnings = self.warnings.len(), "Preflight checks passed");
+        }
+    }
+}
+
+/// Run the preflight checks.
+///
+/// `agents` is the agent health after the handshake, as reported by
+/// [`crate::agents::AgentManager::agent_health`].
+pub async fn run_preflight(
+    config: &Config,
+    agents: &[(String, Option<String>)],
+    options: &PreflightOptions,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+
+    for (id, problem) in agents {
+        if let Some(problem) = problem {
+            report.failures.push(PreflightFailure {
+                component: format!("agent:{}", id),
+                status: HealthStatus::Unhealthy,
+                message: format!("agent '{}' handshake failed: {}", id, problem),
+            });
+        }
+    }
+
+    let mut upstream_ids: Vec<_> = config.upstreams.keys().collect();
+    upstream_ids.sort();
+    for id in upstream_ids {
+        let upstream = &config.upstreams[id];
+        let mut problems = Vec::new();
+        for target in &upstream.targets {
+            if let Err(problem) = check_target(&target.address, options).await {
+                problems.push(format!("{}: {}", target.address, problem));
+            }
+        }
+        if problems.is_empty() {
+            continue;
+        }
+        let status = if problems.len() == upstream.targets.len() {
+            HealthStatus::Unhealthy
+        } else {
+            HealthStatus::Degraded
+        };
+        report.failures.push(PreflightFailure {
+            component: format!("upstream:{}", id),
+            status,
+            message: format!("upstream '{}' targets failed: {}", id, problems.join("; ")),
+        });
+    }
+
+    for listener in &config.listeners {
+        let result = validate_listener_certificates(listener);
+        report
+            .warnings
+            .extend(result.warnings.iter().map(|w| w.message.clone()));
+        if !result.errors.is_empty() {
+            report.failures.push(PreflightFailure {
+                component: format!("tls:{}", listener.id),
+                status: HealthStatus::Unhealthy,
+                message: result
+                    .errors
+                    .iter()
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            });
+        }
+    }
+
+    report
+}
+
+/// Resolve a target with the upstream resolver and, when probing, connect
+/// to the resolved address
+async fn check_target(address: &str, options: &PreflightOptions) -> Result<(), String> {
+    let resolved = tokio::time::timeout(
+        options.target_timeout,
+        dns::resolver().resolve_target(address, options.target_timeout),
+    )
+    .await
+    .map_err(|_| "resolution timed out".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    if options.probe_upstreams {
+        tokio::time::timeout(options.target_timeout, TcpStream::connect(resolved))
+            .await
+            .map_err(|_| format!("connect to {} timed out", resolved))?
+            .map_err(|e| format!("connect to {} failed: {}", resolved, e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_components_are_reported() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap().to_string();
+        let closed = {
+            let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            l.local_addr().unwrap().to_string()
+        };
+
+        let mut config = Config::default_for_testing();
+        let upstream = config.upstreams.values_mut().next().unwrap();
+        upstream.targets[0].address = reachable.clone();
+        let mut second = upstream.targets[0].clone();
+        second.address = closed;
+        upstream.targets.push(second);
+        let upstream_id = upstream.id.clone();
+
+        let agents = vec![
+            ("waf".to_string(), None),
+            (
+                "auth".to_string(),
+                Some("no healthy connections".to_string()),
+            ),
+        ];
+
+        // Resolving only: both targets pass
+        let report = run_preflight(&config, &agents, &PreflightOptions::default()).await;
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].component, "agent:auth");
+
+        // Probing: the closed target degrades the upstream
+        let options = PreflightOptions {
+            probe_upstreams: true,
+            ..Default::default()
+        };
+        let report = run_preflight(&config, &[], &options).await;
+        assert!(!report.passed());
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].component,
+            format!("upstream:{}", upstream_id)
+        );
+        assert_eq!(report.failures[0].status, HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_targets_fail_the_upstream() {
+        let mut config = Config::default_for_testing();
+        let upstream = config.upstreams.values_mut().next().unwrap();
+        upstream.targets[0].address = "upstream.invalid:80".to_string();
+        let upstream_id = upstream.id.clone();
+
+        let options = PreflightOptions {
+            target_timeout: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let report = run_preflight(&config, &[], &options).await;
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].component,
+            format!("upstream:{}", upstream_id)
+        );
+        assert_eq!(report.failures[0].status, HealthStatus::Unhealthy);
+    }
+}
@@ -0,0 +1,526 @@
+//! Liveness, readiness and health reports for the admin listener.
+//!
+//! `livez` only tells that the process serves requests. `readyz` checks the
+//! `system.readiness` criteria: configuration loaded, listeners accepting
+//! connections and enough healthy upstreams and agents. `healthz` reports
+//! every subsystem as a [`ComponentHealth`]; the [`ComponentHealthTracker`]
+//! behind it keeps consecutive failure counts from one probe to the next.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use zentinel_common::{ComponentHealth, ComponentHealthTracker, HealthStatus};
+use zentinel_config::{Config, ListenerConfig, ListenerProtocol};
+
+use crate::builtin_handlers::{TargetHealthStatus, UpstreamHealthSnapshot, UpstreamStatus};
+use crate::preflight::PreflightFailure;
+
+/// How long a listener gets to accept the probe connection
+const LISTENER_PROBE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Everything a health report is built from
+pub struct ProbeInputs<'a> {
+    /// Active configuration
+    pub config: &'a Config,
+    /// Set when the last reload failed and an older configuration is served
+    pub config_error: Option<String>,
+    /// Target health of every upstream
+    pub upstreams: Option<&'a UpstreamHealthSnapshot>,
+    /// Agent IDs with the reason for unhealthy ones
+    pub agents: &'a [(String, Option<String>)],
+    /// Whether every listener accepts connections
+    pub listeners: Result<(), String>,
+}
+
+/// One readiness criterion and whether it holds
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessCheck {
+    /// Criterion name (`config`, `listeners`, `upstreams`, `upstream:<id>`, ...)
+    pub name: String,
+    /// Whether the criterion holds
+    pub ok: bool,
+    /// Details on the outcome
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Readiness and per-subsystem health
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    /// Worst status of all components
+    pub status: HealthStatus,
+    /// Whether every readiness check holds
+    pub ready: bool,
+    /// Readiness criteria
+    pub checks: Vec<ReadinessCheck>,
+    /// Health of every subsystem
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Health probe state kept across requests
+pub struct HealthProbes {
+    tracker: ComponentHealthTracker,
+    /// Set once every listener accepted a connection; listeners stay bound
+    /// for the life of the process
+    listeners_bound: AtomicBool,
+}
+
+impl Default for HealthProbes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthProbes {
+    /// Create probe state with no components recorded yet
+    pub fn new() -> Self {
+        Self {
+            tracker: ComponentHealthTracker::new(),
+            listeners_bound: AtomicBool::new(false),
+        }
+    }
+
+    /// Check that every listener accepts TCP connections.
+    ///
+    /// HTTP/3 listeners are skipped since QUIC runs over UDP.
+    pub async fn check_listeners(&self, listeners: &[ListenerConfig]) -> Result<(), String> {
+        if self.listeners_bound.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        for listener in listeners {
+            if listener.protocol == ListenerProtocol::Http3 {
+                continue;
+            }
+            let Some(address) = probe_address(&listener.address) else {
+                continue;
+            };
+            match tokio::time::timeout(LISTENER_PROBE_TIMEOUT, TcpStream::connect(address)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    return Err(format!("listener '{}' not accepting: {}", listener.id, e));
+                }
+                Err(_) => {
+                    return Err(format!("listener '{}' did not accept in time", listener.id));
+                }
+            }
+        }
+
+        debug!("All listeners accept connections");
+        self.listeners_bound.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Record components that failed startup preflight.
+    ///
+    /// Upstream entries stay until health checks produce data for their
+    /// targets; agent entries until the next probe; `tls:<listener>` entries
+    /// for the life of the process.
+    pub fn record_preflight(&self, failures: &[PreflightFailure]) {
+        for failure in failures {
+            self.tracker.update_component(
+                failure.component.clone(),
+                failure.status,
+                Some(failure.message.clone()),
+            );
+        }
+    }
+
+    /// Record the health of every subsystem and evaluate readiness.
+    pub fn evaluate(&self, inputs: ProbeInputs<'_>) -> HealthReport {
+        let readiness = &inputs.config.server.readiness;
+        let mut checks = Vec::new();
+        let mut names = Vec::new();
+
+        let recorded = self.tracker.get_report();
+        let mut kept = Vec::new();
+        let mut update = |name: String, status: HealthStatus, error: Option<String>| {
+            self.tracker.update_component(name.clone(), status, error);
+            names.push(name);
+        };
+
+        // Configuration: an older config after a failed reload still serves
+        match &inputs.config_error {
+            Some(error) => update(
+                "config".to_string(),
+                HealthStatus::Degraded,
+                Some(error.clone()),
+            ),
+            None => update("config".to_string(), HealthStatus::Healthy, None),
+        }
+        checks.push(ReadinessCheck {
+            name: "config".to_string(),
+            ok: true,
+            message: inputs.config_error.clone(),
+        });
+
+        let listeners_error = inputs.listeners.as_ref().err().cloned();
+        update(
+            "listeners".to_string(),
+            if listeners_error.is_some() {
+                HealthStatus::Unhealthy
+            } else {
+                HealthStatus::Healthy
+            },
+            listeners_error.clone(),
+        );
+        if readiness.require_listeners {
+            checks.push(ReadinessCheck {
+                name: "listeners".to_string(),
+                ok: listeners_error.is_none(),
+                message: listeners_error,
+            });
+        }
+
+        // Upstreams
+        let mut healthy_upstreams = Vec::new();
+        let mut upstreams: Vec<_> = inputs
+            .upstreams
+            .map(|snapshot| snapshot.upstreams.values().collect())
+            .unwrap_or_default();
+        upstreams.sort_by(|a, b| a.id.cmp(&b.id));
+        for upstream in upstreams {
+            let name = format!("upstream:{}", upstream.id);
+            let no_data = !upstream.targets.is_empty()
+                && upstream
+                    .targets
+                    .iter()
+                    .all(|t| t.status == TargetHealthStatus::Unknown);
+            if let Some(previous) = recorded.iter().find(|c| no_data && c.name == name) {
+                // Nothing newer than what preflight found
+                if previous.status != HealthStatus::Unhealthy {
+                    healthy_upstreams.push(upstream.id.as_str());
+                }
+                kept.push(name);
+                continue;
+            }
+            let (status, error) = upstream_health(upstream);
+            if status != HealthStatus::Unhealthy {
+                healthy_upstreams.push(upstream.id.as_str());
+            }
+            update(name, status, error);
+        }
+        if readiness.min_healthy_upstreams > 0 {
+            checks.push(count_check(
+                "upstreams",
+                healthy_upstreams.len(),
+                readiness.min_healthy_upstreams,
+            ));
+        }
+        for id in &readiness.required_upstreams {
+            checks.push(ReadinessCheck {
+                name: format!("upstream:{}", id),
+                ok: healthy_upstreams.contains(&id.as_str()),
+                message: None,
+            });
+        }
+
+        // Agents
+        let mut healthy_agents = Vec::new();
+        for (id, problem) in inputs.agents {
+            match problem {
+                Some(problem) => update(
+                    format!("agent:{}", id),
+                    HealthStatus::Unhealthy,
+                    Some(problem.clone()),
+                ),
+                None => {
+                    healthy_agents.push(id.as_str());
+                    update(format!("agent:{}", id), HealthStatus::Healthy, None);
+                }
+            }
+        }
+        if readiness.min_healthy_agents > 0 {
+            checks.push(count_check(
+                "agents",
+                healthy_agents.len(),
+                readiness.min_healthy_agents,
+            ));
+        }
+        for id in &readiness.required_agents {
+            checks.push(ReadinessCheck {
+                name: format!("agent:{}", id),
+                ok: healthy_agents.contains(&id.as_str()),
+                message: None,
+            });
+        }
+
+        // Forget upstreams and agents a reload removed; components recorded
+        // elsewhere (e.g. preflight `tls:<listener>`) stay
+        self.tracker.retain(|c| {
+            names.contains(&c.name) || kept.contains(&c.name) || !is_probed_component(&c.name)
+        });
+
+        let mut components = self.tracker.get_report();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        HealthReport {
+            status: self.tracker.get_status(),
+            ready: checks.iter().all(|check| check.ok),
+            checks,
+            components,
+        }
+    }
+}
+
+/// Healthy while every target is, unhealthy once none is.
+///
+/// Targets without health data yet count as healthy, as they do for load
+/// balancing.
+fn upstream_health(upstream: &UpstreamStatus) -> (HealthStatus, Option<String>) {
+    let total = upstream.targets.len();
+    let unhealthy = upstream
+        .targets
+        .iter()
+        .filter(|t| t.status == TargetHealthStatus::Unhealthy)
+        .count();
+
+    if total == 0 {
+        (
+            HealthStatus::Degraded,
+            Some("no targets configured".to_string()),
+        )
+    } else if unhealthy == 0 {
+        (HealthStatus::Healthy, None)
+    } else if unhealthy < total {
+        (
+            HealthStatus::Degraded,
+            Some(format!("{} of {} targets unhealthy", unhealthy, total)),
+        )
+    } else {
+        (
+            HealthStatus::Unhealthy,
+            Some("all targets unhealthy".to_string()),
+        )
+    }
+}
+
+/// Whether `evaluate` records the component on every probe
+fn is_probed_component(name: &str) -> bool {
+    name == "config"
+        || name == "listeners"
+        || name.starts_with("upstream:")
+        || name.starts_with("agent:")
+}
+
+fn count_check(name: &str, healthy: usize, required: usize) -> ReadinessCheck {
+    ReadinessCheck {
+        name: name.to_string(),
+        ok: healthy >= required,
+        message: Some(format!("{} healthy, {} required", healthy, required)),
+    }
+}
+
+/// Address to connect to for a listener bound to `address`; unspecified
+/// addresses are probed on loopback.
+fn probe_address(address: &str) -> Option<SocketAddr> {
+    let mut addr: SocketAddr = address.parse().ok()?;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(match addr.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        });
+    }
+    Some(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_handlers::TargetStatus;
+    use std::collections::HashMap;
+
+    fn upstream(id: &str, statuses: &[TargetHealthStatus]) -> UpstreamStatus {
+        UpstreamStatus {
+            id: id.to_string(),
+            load_balancing: "RoundRobin".to_string(),
+            targets: statuses
+                .iter()
+                .enumerate()
+                .map(|(i, status)| TargetStatus {
+                    address: format!("10.0.0.{}:80", i + 1),
+                    weight: 1,
+                    status: *status,
+                    active_check: None,
+                    failure_rate: None,
+                    last_error: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn snapshot(upstreams: Vec<UpstreamStatus>) -> UpstreamHealthSnapshot {
+        UpstreamHealthSnapshot {
+            upstreams: upstreams
+                .into_iter()
+                .map(|u| (u.id.clone(), u))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn readiness_follows_configured_criteria() {
+        let mut config = Config::default_for_testing();
+        config.server.readiness.min_healthy_upstreams = 2;
+        config.server.readiness.required_agents = vec!["waf".to_string()];
+
+        let probes = HealthProbes::new();
+        let upstreams = snapshot(vec![
+            upstream(
+                "api",
+                &[TargetHealthStatus::Healthy, TargetHealthStatus::Unhealthy],
+            ),
+            upstream("legacy", &[TargetHealthStatus::Unhealthy]),
+        ]);
+        let agents = vec![("waf".to_string(), None)];
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: None,
+            upstreams: Some(&upstreams),
+            agents: &agents,
+            listeners: Ok(()),
+        });
+
+        assert!(!report.ready);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        let failed: Vec<_> = report.checks.iter().filter(|c| !c.ok).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "upstreams");
+        let api = report
+            .components
+            .iter()
+            .find(|c| c.name == "upstream:api")
+            .unwrap();
+        assert_eq!(api.status, HealthStatus::Degraded);
+
+        // The legacy upstream recovers and the agent's breaker opens
+        let upstreams = snapshot(vec![
+            upstream("api", &[TargetHealthStatus::Healthy]),
+            upstream("legacy", &[TargetHealthStatus::Unknown]),
+        ]);
+        let agents = vec![("waf".to_string(), Some("circuit breaker open".to_string()))];
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: None,
+            upstreams: Some(&upstreams),
+            agents: &agents,
+            listeners: Ok(()),
+        });
+        assert!(!report.ready);
+        assert!(report.checks.iter().all(|c| c.ok || c.name == "agent:waf"));
+    }
+
+    #[test]
+    fn removed_components_are_forgotten() {
+        let config = Config::default_for_testing();
+        let probes = HealthProbes::new();
+        let upstreams = snapshot(vec![upstream("old", &[TargetHealthStatus::Unhealthy])]);
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: None,
+            upstreams: Some(&upstreams),
+            agents: &[],
+            listeners: Ok(()),
+        });
+        assert!(report.ready);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: Some("last reload failed".to_string()),
+            upstreams: None,
+            agents: &[],
+            listeners: Err("listener 'public' not accepting".to_string()),
+        });
+        assert!(!report.ready);
+        assert!(report.components.iter().all(|c| c.name != "upstream:old"));
+        let config_health = report
+            .components
+            .iter()
+            .find(|c| c.name == "config")
+            .unwrap();
+        assert_eq!(config_health.status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn preflight_failures_persist_until_live_data() {
+        let config = Config::default_for_testing();
+        let probes = HealthProbes::new();
+        probes.record_preflight(&[
+            PreflightFailure {
+                component: "upstream:api".to_string(),
+                status: HealthStatus::Unhealthy,
+                message: "upstream 'api' targets failed".to_string(),
+            },
+            PreflightFailure {
+                component: "tls:public".to_string(),
+                status: HealthStatus::Unhealthy,
+                message: "Certificate not found".to_string(),
+            },
+        ]);
+
+        let component = |report: &HealthReport, name: &str| {
+            report
+                .components
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.status)
+        };
+
+        let unknown = snapshot(vec![upstream("api", &[TargetHealthStatus::Unknown])]);
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: None,
+            upstreams: Some(&unknown),
+            agents: &[],
+            listeners: Ok(()),
+        });
+        assert_eq!(
+            component(&report, "upstream:api"),
+            Some(HealthStatus::Unhealthy)
+        );
+        assert_eq!(
+            component(&report, "tls:public"),
+            Some(HealthStatus::Unhealthy)
+        );
+
+        let healthy = snapshot(vec![upstream("api", &[TargetHealthStatus::Healthy])]);
+        let report = probes.evaluate(ProbeInputs {
+            config: &config,
+            config_error: None,
+            upstreams: Some(&healthy),
+            agents: &[],
+            listeners: Ok(()),
+        });
+        assert_eq!(
+            component(&report, "upstream:api"),
+            Some(HealthStatus::Healthy)
+        );
+        assert_eq!(
+            component(&report, "tls:public"),
+            Some(HealthStatus::Unhealthy)
+        );
+    }
+
+    #[tokio::test]
+    async fn listener_probe_connects_to_bound_listeners() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut config = Config::default_for_testing();
+        config.listeners[0].address = format!("0.0.0.0:{}", port);
+
+        let probes = HealthProbes::new();
+        assert!(probes.check_listeners(&config.listeners).await.is_ok());
+
+        let unbound = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let unbound_port = unbound.local_addr().unwrap().port();
+        drop(unbound);
+        config.listeners[0].address = format!("127.0.0.1:{}", unbound_port);
+        let fresh = HealthProbes::new();
+        assert!(fresh.check_listeners(&config.listeners).await.is_err());
+    }
+}
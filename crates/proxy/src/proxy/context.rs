@@ -3,12 +3,16 @@
 //! The `RequestContext` struct maintains state throughout a single request,
 //! including timing, routing decisions, and metadata for logging.
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
+use zentinel_agent_protocol::LatencyBreakdown;
 use zentinel_config::{BodyStreamingMode, Config, RouteConfig, ServiceType};
 
+use crate::agents::AgentTimings;
 use crate::inference::StreamingTokenCounter;
+use crate::upstream::TargetSelection;
 use crate::websocket::WebSocketHandler;
 
 /// Reason why fallback routing was triggered
@@ -99,6 +103,8 @@ pub struct RequestContext {
     pub(crate) upstream: Option<String>,
     /// Selected upstream peer address (IP:port) for feedback reporting
     pub(crate) selected_upstream_address: Option<String>,
+    /// Upstream ID and load balancer selection, released when the request ends
+    pub(crate) upstream_selection: Option<(String, TargetSelection)>,
     /// Number of upstream attempts
     pub(crate) upstream_attempts: u32,
 
@@ -107,6 +113,14 @@ pub struct RequestContext {
     pub(crate) namespace: Option<String>,
     /// Service for this request (if routed to a service scope)
     pub(crate) service: Option<String>,
+    /// Tenant this request is attributed to
+    pub(crate) tenant: Option<String>,
+    /// In-flight slot held against the tenant's quotas until the request ends
+    pub(crate) tenant_permit: Option<crate::tenant::TenantPermit>,
+    /// In-flight slots held against the global and route concurrency limits
+    pub(crate) concurrency_permit: Option<crate::concurrency::ConcurrencyPermit>,
+    /// Slot held in the selected upstream's in-flight limit
+    pub(crate) upstream_queue_permit: Option<crate::upstream::QueuePermit>,
 
     // === Request metadata (cached for logging) ===
     /// HTTP method
@@ -117,8 +131,20 @@ pub struct RequestContext {
     pub(crate) query: Option<String>,
 
     // === Client info ===
-    /// Client IP address
+    /// Client IP address (resolved through trusted proxies)
     pub(crate) client_ip: String,
+    /// Client port (0 when the client was resolved from forwarding headers)
+    pub(crate) client_port: u16,
+    /// Client certificate presented on an mTLS listener
+    pub(crate) client_cert: Option<zentinel_agent_protocol::ClientCertificate>,
+    /// Client certificate as PEM, when available from the handshake
+    pub(crate) client_cert_pem: Option<String>,
+    /// Client fingerprints on a `bot-signals` listener
+    pub(crate) bot_signals: Option<zentinel_agent_protocol::BotSignals>,
+    /// Header forwarding the client certificate upstream (listener `client-cert-header`)
+    pub(crate) client_cert_header: Option<String>,
+    /// Downstream HTTP protocol (`HTTP/1.1`, `HTTP/2`, `HTTP/3`)
+    pub(crate) protocol: &'static str,
     /// User-Agent header
     pub(crate) user_agent: Option<String>,
     /// Referer header
@@ -135,6 +161,10 @@ pub struct RequestContext {
     // === Connection tracking ===
     /// Whether the upstream connection was reused
     pub(crate) connection_reused: bool,
+    /// Upstream phase timings for the latency breakdown
+    pub(crate) timings: PhaseTimings,
+    /// Time spent matching the request to a route
+    pub(crate) route_match_time: Option<Duration>,
     /// Whether this request is a WebSocket upgrade
     pub(crate) is_websocket_upgrade: bool,
 
@@ -153,6 +183,9 @@ pub struct RequestContext {
     pub(crate) cache_eligible: bool,
     /// Cache status for Cache-Status response header (RFC 9211)
     pub(crate) cache_status: Option<CacheStatus>,
+    /// Place in the coalescing queue for the cache key, until the response
+    /// is known
+    pub(crate) cache_coalesce: Option<crate::coalesce::CoalesceTicket>,
 
     // === Body Inspection ===
     /// Whether body inspection is enabled for this request
@@ -186,6 +219,18 @@ pub struct RequestContext {
     /// Whether a geo lookup was performed for this request
     pub(crate) geo_lookup_performed: bool,
 
+    // === Adaptive Protection ===
+    /// Adaptive-protection filters that let the request through; they are
+    /// told the response status when the request ends
+    pub(crate) adaptive_filters: Vec<String>,
+
+    // === Quotas ===
+    /// Quota filters and consumer IDs the request was counted for; body
+    /// bytes are added to their byte quotas when the request ends
+    pub(crate) quota_consumers: Vec<(String, String)>,
+    /// Quota announced in the `X-Quota-*` response headers
+    pub(crate) quota_usage: Option<crate::quota::QuotaUsage>,
+
     // === Body Streaming ===
     /// Body streaming mode for request body inspection
     pub(crate) request_body_streaming_mode: BodyStreamingMode,
@@ -193,6 +238,8 @@ pub struct RequestContext {
     pub(crate) request_body_chunk_index: u32,
     /// Whether agent needs more data (streaming mode)
     pub(crate) agent_needs_more: bool,
+    /// Cuts streamed request body chunks on the route's record boundaries
+    pub(crate) request_body_framer: Option<crate::body_framing::RecordFramer>,
     /// Body streaming mode for response body inspection
     pub(crate) response_body_streaming_mode: BodyStreamingMode,
     /// Current chunk index for response body streaming
@@ -245,6 +292,10 @@ pub struct RequestContext {
     pub(crate) inference_input_tokens: u64,
     /// Output tokens for cost calculation
     pub(crate) inference_output_tokens: u64,
+    /// Consumer the request's cost is counted under by the cost budget
+    pub(crate) inference_cost_consumer: Option<String>,
+    /// Model the request was downgraded to by an exhausted cost budget
+    pub(crate) inference_model_downgrade: Option<String>,
 
     // === Streaming Token Counting ===
     /// Whether this is a streaming (SSE) response
@@ -266,6 +317,15 @@ pub struct RequestContext {
     /// Whether fallback should be retried after response
     pub(crate) should_retry_with_fallback: bool,
 
+    // === Inference Key Pool ===
+    /// Key pool member serving the current attempt
+    pub(crate) inference_key_member: Option<Arc<crate::inference::KeyPoolMember>>,
+    /// Key pool members tried for this request, in order
+    pub(crate) inference_key_attempts: Vec<String>,
+    /// Set when the response put the member into cooldown and the request
+    /// is retried on another member
+    pub(crate) inference_key_failover: bool,
+
     // === Semantic Guardrails ===
     /// Whether guardrails are enabled for this route
     pub(crate) guardrails_enabled: bool,
@@ -282,6 +342,29 @@ pub struct RequestContext {
     /// Whether shadow request was sent for this request
     pub(crate) shadow_sent: bool,
 
+    // === Traffic Capture ===
+    /// Request being recorded by the running traffic capture
+    pub(crate) capture: Option<crate::capture::PendingCapture>,
+
+    // === Request Hardening ===
+    /// Rejection raised by request normalization before route matching,
+    /// answered at the start of `request_filter`
+    pub(crate) normalization_rejection: Option<crate::hardening::Rejection>,
+
+    // === Debug Headers ===
+    /// Audit records of the agents that inspected a request with a valid
+    /// debug token, reported in its response headers
+    pub(crate) debug_trace: Option<Arc<parking_lot::Mutex<Vec<crate::audit_store::AuditRecord>>>>,
+
+    // === Staged Rollout ===
+    /// Route table version the request is matched against while a staged
+    /// rollout runs
+    pub(crate) rollout_cohort: Option<crate::rollout::Cohort>,
+
+    // === Introspection ===
+    /// Registration in the in-flight request registry (removed on drop)
+    pub(crate) inflight: Option<crate::inflight::InFlightGuard>,
+
     // === Sticky Sessions ===
     /// Whether a new sticky session assignment was made (needs Set-Cookie header)
     pub(crate) sticky_session_new_assignment: bool,
@@ -293,6 +376,8 @@ pub struct RequestContext {
     // === Listener Overrides ===
     /// Keepalive timeout from listener config (seconds, for response phase)
     pub(crate) listener_keepalive_timeout_secs: Option<u64>,
+    /// Slow-client limits of the listener and route, when any are set
+    pub(crate) slow_client: Option<crate::slow_client::SlowClientGuard>,
 
     // === Filter Overrides ===
     /// Upstream connect timeout override from Timeout filter (seconds)
@@ -301,12 +386,52 @@ pub struct RequestContext {
     pub(crate) filter_upstream_timeout_secs: Option<u64>,
     /// CORS origin matched by a CORS filter (for response headers)
     pub(crate) cors_origin: Option<String>,
-    /// Whether response compression is enabled by a Compress filter
-    pub(crate) compress_enabled: bool,
+    /// Streaming response compression set up by a Compress filter
+    pub(crate) response_compressor: Option<crate::compression::ResponseCompressor>,
+    /// Response body size limit set up by a response-policy filter
+    pub(crate) response_size_limit: Option<super::filters::ResponseSizeLimit>,
+    /// Filters whose `enable-if` condition did not hold for this request
+    pub(crate) disabled_filters: Vec<String>,
+    /// Request body chunks delivered to WASM filters so far
+    pub(crate) wasm_body_chunk_index: u32,
+    /// Buffered request body for json-transform filters
+    pub(crate) request_json_transform: Option<crate::json_transform::JsonBodyTransform>,
+    /// Buffered response body for json-transform filters
+    pub(crate) response_json_transform: Option<crate::json_transform::JsonBodyTransform>,
+    /// Buffered request body for schema-validate filters
+    pub(crate) request_schema_validation: Option<crate::schema_validate::RequestBodyValidation>,
+    /// Body rejected by a schema-validate filter, answered in `fail_to_proxy`
+    pub(crate) schema_rejection: Option<crate::schema_validate::SchemaRejection>,
+    /// Held-back request body for phase 2 rules of managed-rules filters
+    pub(crate) request_rule_inspection: Option<crate::managed_rules::RequestBodyInspection>,
+    /// Body blocked by a managed-rules filter, answered in `fail_to_proxy`
+    pub(crate) rule_rejection: Option<crate::managed_rules::RuleRejection>,
+    /// Held-back request body for prompt-template filters
+    pub(crate) request_prompt_enforcement: Option<crate::inference::RequestPromptEnforcement>,
+    /// Request blocked by a prompt-template filter, answered in `fail_to_proxy`
+    pub(crate) prompt_rejection: Option<crate::inference::PromptRejection>,
+    /// multipart/form-data body parsed into per-part agent events
+    pub(crate) request_multipart_inspection: Option<crate::multipart::MultipartInspection>,
+    /// Held-back request body scanned by an icap filter
+    pub(crate) request_icap_scan: Option<crate::icap::IcapBodyScan>,
+    /// Held-back response body scanned by an icap filter
+    pub(crate) response_icap_scan: Option<crate::icap::IcapBodyScan>,
+    /// Request body blocked by an icap filter, answered in `fail_to_proxy`
+    pub(crate) icap_rejection: Option<crate::icap::IcapRejection>,
 
     // === Response-Phase Agent Processing ===
     /// Agent IDs resolved from route filters (saved in request phase for response phase)
     pub(crate) route_agent_ids: Vec<String>,
+    /// Routing metadata set by agent decisions (`metadata` in policy expressions)
+    pub(crate) routing_metadata: HashMap<String, String>,
+    /// Typed tags set by agent decisions (`tags` in policy expressions)
+    pub(crate) tags: crate::request_tags::RequestTags,
+    /// How decisions of the route's agents are combined (saved in request phase)
+    pub(crate) decision_merge: Arc<crate::agents::DecisionMergePolicy>,
+    /// Agent block awaiting a templated block page (route `block-page`)
+    pub(crate) agent_block: Option<crate::errors::AgentBlock>,
+    /// Challenge response to serve instead of forwarding (agent challenge decision)
+    pub(crate) challenge_response: Option<http::Response<http_body_util::Full<bytes::Bytes>>>,
     /// Whether response-phase agent processing is enabled (agent subscribes to response events)
     pub(crate) response_agent_processing_enabled: bool,
     /// Accumulated response body buffer for agent processing (when agent needs full body)
@@ -315,6 +440,42 @@ pub struct RequestContext {
     pub(crate) response_agent_body_complete: bool,
 }
 
+/// Upstream phase timings of a request, for its latency breakdown.
+///
+/// Reset on every upstream attempt, so they describe the last one.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimings {
+    /// Resolving the upstream address
+    pub dns: Option<Duration>,
+    /// TCP connect to the upstream (new connections only)
+    pub connect: Option<Duration>,
+    /// TLS handshake with the upstream (new TLS connections only)
+    pub tls: Option<Duration>,
+    /// Peer handed to Pingora for connecting
+    pub upstream_started: Option<Instant>,
+    /// Wall-clock twin of `upstream_started`, to compare with the
+    /// connection's establishment timestamps
+    pub upstream_started_wall: Option<SystemTime>,
+    /// Upstream connection established or reused
+    pub upstream_ready: Option<Instant>,
+    /// Upstream response headers received
+    pub upstream_headers: Option<Instant>,
+    /// Last upstream response body chunk received
+    pub upstream_body_done: Option<Instant>,
+}
+
+impl PhaseTimings {
+    /// Start a new upstream attempt
+    pub fn start_upstream(&mut self, dns: Option<Duration>) {
+        *self = Self {
+            dns,
+            upstream_started: Some(Instant::now()),
+            upstream_started_wall: Some(SystemTime::now()),
+            ..Default::default()
+        };
+    }
+}
+
 /// Pending shadow request information stored in context for deferred execution
 #[derive(Clone)]
 pub struct ShadowPendingRequest {
@@ -339,19 +500,32 @@ impl RequestContext {
             route_config: None,
             upstream: None,
             selected_upstream_address: None,
+            upstream_selection: None,
             upstream_attempts: 0,
             namespace: None,
             service: None,
+            tenant: None,
+            tenant_permit: None,
+            concurrency_permit: None,
+            upstream_queue_permit: None,
             method: String::new(),
             path: String::new(),
             query: None,
             client_ip: String::new(),
+            client_port: 0,
+            client_cert: None,
+            client_cert_pem: None,
+            bot_signals: None,
+            client_cert_header: None,
+            protocol: "HTTP/1.1",
             user_agent: None,
             referer: None,
             host: None,
             request_body_bytes: 0,
             response_bytes: 0,
             connection_reused: false,
+            timings: PhaseTimings::default(),
+            route_match_time: None,
             is_websocket_upgrade: false,
             websocket_inspection_enabled: false,
             websocket_skip_inspection: false,
@@ -359,6 +533,7 @@ impl RequestContext {
             websocket_handler: None,
             cache_eligible: false,
             cache_status: None,
+            cache_coalesce: None,
             body_inspection_enabled: false,
             body_bytes_inspected: 0,
             body_buffer: Vec::new(),
@@ -371,8 +546,12 @@ impl RequestContext {
             rate_limit_info: None,
             geo_country_code: None,
             geo_lookup_performed: false,
+            adaptive_filters: Vec::new(),
+            quota_consumers: Vec::new(),
+            quota_usage: None,
             request_body_streaming_mode: BodyStreamingMode::Buffer,
             request_body_chunk_index: 0,
+            request_body_framer: None,
             agent_needs_more: false,
             response_body_streaming_mode: BodyStreamingMode::Buffer,
             response_body_chunk_index: 0,
@@ -396,6 +575,8 @@ impl RequestContext {
             inference_request_cost: None,
             inference_input_tokens: 0,
             inference_output_tokens: 0,
+            inference_cost_consumer: None,
+            inference_model_downgrade: None,
             inference_streaming_response: false,
             inference_streaming_counter: None,
             fallback_attempt: 0,
@@ -404,21 +585,50 @@ impl RequestContext {
             original_upstream: None,
             model_mapping_applied: None,
             should_retry_with_fallback: false,
+            inference_key_member: None,
+            inference_key_attempts: Vec::new(),
+            inference_key_failover: false,
             guardrails_enabled: false,
             guardrail_warning: false,
             guardrail_detection_categories: Vec::new(),
             pii_detection_categories: Vec::new(),
             shadow_pending: None,
             shadow_sent: false,
+            capture: None,
+            normalization_rejection: None,
+            debug_trace: None,
+            rollout_cohort: None,
+            inflight: None,
             sticky_session_new_assignment: false,
             sticky_session_set_cookie: None,
             sticky_target_index: None,
             listener_keepalive_timeout_secs: None,
+            slow_client: None,
             filter_connect_timeout_secs: None,
             filter_upstream_timeout_secs: None,
             cors_origin: None,
-            compress_enabled: false,
+            response_compressor: None,
+            response_size_limit: None,
+            disabled_filters: Vec::new(),
+            wasm_body_chunk_index: 0,
+            request_json_transform: None,
+            response_json_transform: None,
+            request_schema_validation: None,
+            schema_rejection: None,
+            request_rule_inspection: None,
+            rule_rejection: None,
+            request_prompt_enforcement: None,
+            prompt_rejection: None,
+            request_multipart_inspection: None,
+            request_icap_scan: None,
+            response_icap_scan: None,
+            icap_rejection: None,
             route_agent_ids: Vec::new(),
+            routing_metadata: HashMap::new(),
+            tags: Default::default(),
+            decision_merge: Arc::default(),
+            agent_block: None,
+            challenge_response: None,
             response_agent_processing_enabled: false,
             response_agent_body_buffer: Vec::new(),
             response_agent_body_complete: false,
@@ -439,6 +649,46 @@ impl RequestContext {
         self.start_time.elapsed()
     }
 
+    /// Per-phase breakdown of the request so far, given its agent time.
+    pub fn latency_breakdown(&self, agents: &AgentTimings) -> LatencyBreakdown {
+        self.latency_breakdown_at(agents, Instant::now())
+    }
+
+    fn latency_breakdown_at(&self, agents: &AgentTimings, now: Instant) -> LatencyBreakdown {
+        let t = &self.timings;
+        let ms = |d: Duration| d.as_millis() as u64;
+        let between = |from: Option<Instant>, to: Option<Instant>| match (from, to) {
+            (Some(from), Some(to)) => Some(ms(to.saturating_duration_since(from))),
+            _ => None,
+        };
+
+        LatencyBreakdown {
+            dns_ms: t.dns.map(ms),
+            connect_ms: t.connect.map(ms),
+            tls_ms: t.tls.map(ms),
+            agent_total_ms: ms(agents.total),
+            agents_ms: agents
+                .by_agent
+                .iter()
+                .map(|(id, d)| (id.clone(), ms(*d)))
+                .collect(),
+            upstream_ttfb_ms: between(t.upstream_ready.or(t.upstream_started), t.upstream_headers),
+            upstream_body_ms: between(t.upstream_headers, t.upstream_body_done),
+            downstream_write_ms: between(t.upstream_body_done, Some(now)),
+        }
+    }
+
+    /// Agent decision headers for a request with a valid debug token;
+    /// empty for every other request.
+    pub(crate) fn debug_headers(&self) -> Vec<(&'static str, String)> {
+        self.debug_trace
+            .as_ref()
+            .map(|trace| {
+                crate::debug_headers::response_headers(&trace.lock(), self.agent_block.as_ref())
+            })
+            .unwrap_or_default()
+    }
+
     // === Read-only accessors ===
 
     /// Get trace_id (alias for backwards compatibility with correlation_id usage).
@@ -483,12 +733,29 @@ impl RequestContext {
         self.config.as_ref()
     }
 
+    /// Whether the route forwards response bodies without buffering.
+    #[inline]
+    pub fn streaming_passthrough(&self) -> bool {
+        self.route_config
+            .as_ref()
+            .is_some_and(|c| c.policies.streaming_passthrough)
+    }
+
     /// Get the service type from cached route config.
     #[inline]
     pub fn service_type(&self) -> Option<ServiceType> {
         self.route_config.as_ref().map(|c| c.service_type.clone())
     }
 
+    /// Get the route's total agent time budget.
+    #[inline]
+    pub fn agent_budget(&self) -> Option<std::time::Duration> {
+        self.route_config
+            .as_ref()
+            .and_then(|c| c.policies.agent_budget_ms)
+            .map(std::time::Duration::from_millis)
+    }
+
     /// Get the number of upstream attempts.
     #[inline]
     pub fn upstream_attempts(&self) -> u32 {
@@ -549,12 +816,24 @@ impl RequestContext {
         self.geo_country_code.as_deref()
     }
 
+    /// Get the tags agents attached to this request.
+    #[inline]
+    pub fn tags(&self) -> &crate::request_tags::RequestTags {
+        &self.tags
+    }
+
     /// Check if a geo lookup was performed for this request.
     #[inline]
     pub fn geo_lookup_performed(&self) -> bool {
         self.geo_lookup_performed
     }
 
+    /// Check if a filter applies to this request (its `enable-if` held).
+    #[inline]
+    pub fn filter_enabled(&self, filter_id: &str) -> bool {
+        !self.disabled_filters.iter().any(|id| id == filter_id)
+    }
+
     /// Get traceparent header value for distributed tracing.
     ///
     /// Returns the W3C Trace Context traceparent header value if tracing is enabled.
@@ -571,8 +850,50 @@ impl RequestContext {
         })
     }
 
+    /// Per-request values injected by json-transform filters.
+    pub fn json_computed_values(&self) -> crate::json_transform::ComputedValues<'_> {
+        crate::json_transform::ComputedValues {
+            correlation_id: &self.trace_id,
+            route_id: self.route_id.as_deref(),
+            client_ip: &self.client_ip,
+        }
+    }
+
+    /// Fail the request when an admin cancelled it
+    pub(crate) fn check_cancelled(&self) -> pingora_core::Result<()> {
+        match &self.inflight {
+            Some(inflight) if inflight.is_cancelled() => Err(crate::inflight::cancelled_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Move the request to `phase` in the in-flight registry, failing it
+    /// when an admin cancelled it
+    pub(crate) fn enter_phase(
+        &self,
+        phase: crate::inflight::RequestPhase,
+    ) -> pingora_core::Result<()> {
+        if let Some(inflight) = &self.inflight {
+            inflight.set_phase(phase);
+        }
+        self.check_cancelled()
+    }
+
     // === Mutation helpers ===
 
+    /// Register the request in the in-flight registry once its correlation
+    /// ID is known
+    pub(crate) fn track_inflight(&mut self, client: Option<std::net::SocketAddr>) {
+        if self.inflight.is_none() && !self.trace_id.is_empty() {
+            self.inflight = Some(crate::inflight::registry().register_request(
+                &self.trace_id,
+                &self.method,
+                &self.path,
+                client,
+            ));
+        }
+    }
+
     /// Set the trace ID.
     #[inline]
     pub fn set_trace_id(&mut self, trace_id: impl Into<String>) {
@@ -874,4 +1195,34 @@ mod tests {
             "connection_error_timeout"
         );
     }
+
+    #[test]
+    fn test_latency_breakdown() {
+        let mut ctx = RequestContext::new();
+        let agents = AgentTimings {
+            total: Duration::from_millis(7),
+            by_agent: HashMap::from([("waf".to_string(), Duration::from_millis(7))]),
+        };
+
+        // Served without reaching an upstream
+        let breakdown = ctx.latency_breakdown(&agents);
+        assert_eq!(breakdown.agent_total_ms, 7);
+        assert_eq!(breakdown.agents_ms["waf"], 7);
+        assert_eq!(breakdown.upstream_ttfb_ms, None);
+
+        ctx.timings.start_upstream(Some(Duration::from_millis(2)));
+        let started = ctx.timings.upstream_started.unwrap();
+        ctx.timings.connect = Some(Duration::from_millis(4));
+        ctx.timings.upstream_ready = Some(started + Duration::from_millis(5));
+        ctx.timings.upstream_headers = Some(started + Duration::from_millis(25));
+        ctx.timings.upstream_body_done = Some(started + Duration::from_millis(40));
+
+        let breakdown = ctx.latency_breakdown_at(&agents, started + Duration::from_millis(41));
+        assert_eq!(breakdown.dns_ms, Some(2));
+        assert_eq!(breakdown.connect_ms, Some(4));
+        assert_eq!(breakdown.tls_ms, None);
+        assert_eq!(breakdown.upstream_ttfb_ms, Some(20));
+        assert_eq!(breakdown.upstream_body_ms, Some(15));
+        assert_eq!(breakdown.downstream_write_ms, Some(1));
+    }
 }
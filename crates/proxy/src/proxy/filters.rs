@@ -1,19 +1,169 @@
-//! Filter dispatch for route-level filters (Headers, Compress, CORS, Timeout, Log).
+//! Filter dispatch for route-level filters (Headers, Compress, CORS, Timeout, Log,
+//! ResponsePolicy).
 //!
 //! These filters are applied per-request based on the route configuration.
 //! Each filter type hooks into the appropriate phase of the request lifecycle.
+//! Filters with an `enable-if` condition are skipped when
+//! [`evaluate_filter_conditions`] found the condition false.
 
-use std::sync::Arc;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, LazyLock};
 
-use pingora::http::ResponseHeader;
+use pingora::http::{RequestHeader, ResponseHeader};
+use pingora_core::{Error, ErrorType};
 use pingora_proxy::Session;
-use tracing::{debug, trace};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use tracing::{debug, trace, warn};
 use zentinel_config::{
-    CompressFilter, Config, CorsFilter, Filter, FilterPhase, HeadersFilter, LogFilter,
-    PathModifier, RedirectFilter, TimeoutFilter, UrlRewriteFilter,
+    CompressFilter, Config, CorsFilter, ExprContext, Expression, Filter, FilterPhase,
+    HeadersFilter, IcapFilter, JsonSetField, JsonTransformFilter, LogFilter, PathModifier,
+    RedirectFilter, ResponsePolicyFilter, TimeoutFilter, UrlRewriteFilter,
 };
 
 use super::context::RequestContext;
+use crate::icap::{IcapBodyScan, IcapMode};
+use crate::inference::{PromptTemplateCheck, RequestPromptEnforcement};
+use crate::json_transform::{is_json_content_type, JsonBodyTransform};
+use crate::multipart::MultipartInspection;
+use crate::routing::RequestInfo;
+
+// =============================================================================
+// Filter Conditions
+// =============================================================================
+
+/// Point in request processing at which `enable-if` conditions are evaluated.
+///
+/// Each condition is evaluated once, at the first stage where everything it
+/// reads is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionStage {
+    /// After routing: request attributes and client IP
+    Request,
+    /// After geo filtering: adds `client.country`
+    Geo,
+    /// After agent processing: adds agent `metadata` and `tags`
+    Agents,
+}
+
+impl ConditionStage {
+    fn of(condition: &Expression) -> Self {
+        if condition.needs_metadata() {
+            Self::Agents
+        } else if condition.needs_country() {
+            Self::Geo
+        } else {
+            Self::Request
+        }
+    }
+}
+
+/// Evaluate the `enable-if` conditions of the route's filters that belong to
+/// `stage`, recording filters whose condition is false in the context.
+pub fn evaluate_filter_conditions(
+    req_header: &RequestHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+    stage: ConditionStage,
+) {
+    let route_config = match ctx.route_config.as_ref() {
+        Some(rc) => Arc::clone(rc),
+        None => return,
+    };
+
+    let expr_ctx = FilterExprContext {
+        req_header,
+        ctx,
+        query_params: OnceCell::new(),
+    };
+    let disabled: Vec<String> = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| {
+            let Some(condition) = config
+                .filters
+                .get(filter_id.as_str())
+                .and_then(|fc| fc.enable_if.as_ref())
+            else {
+                return false;
+            };
+            if ConditionStage::of(condition) != stage || condition.evaluate(&expr_ctx) {
+                return false;
+            }
+            trace!(
+                correlation_id = %expr_ctx.ctx.trace_id,
+                filter_id = %filter_id,
+                condition = %condition,
+                "Filter disabled by enable-if condition"
+            );
+            true
+        })
+        .cloned()
+        .collect();
+
+    ctx.disabled_filters.extend(disabled);
+}
+
+/// Expression view of the request being proxied.
+struct FilterExprContext<'a> {
+    req_header: &'a RequestHeader,
+    ctx: &'a RequestContext,
+    /// Parsed on first use
+    query_params: OnceCell<HashMap<String, String>>,
+}
+
+impl ExprContext for FilterExprContext<'_> {
+    fn method(&self) -> &str {
+        &self.ctx.method
+    }
+
+    fn path(&self) -> &str {
+        &self.ctx.path
+    }
+
+    fn host(&self) -> &str {
+        self.ctx.host.as_deref().unwrap_or("")
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.req_header.headers.get(name)?.to_str().ok()
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params
+            .get_or_init(|| {
+                RequestInfo::parse_query_params(
+                    self.req_header
+                        .uri
+                        .path_and_query()
+                        .map_or("", |pq| pq.as_str()),
+                )
+            })
+            .get(name)
+            .map(|v| v.as_str())
+    }
+
+    fn client_ip(&self) -> Option<IpAddr> {
+        self.ctx.client_ip.parse().ok()
+    }
+
+    fn country(&self) -> Option<&str> {
+        self.ctx.geo_country_code.as_deref()
+    }
+
+    fn metadata(&self, key: &str) -> Option<&str> {
+        self.ctx.routing_metadata.get(key).map(|v| v.as_str())
+    }
+
+    fn tag(&self, name: &str) -> Option<&str> {
+        self.ctx.tags.get(name)
+    }
+}
+
+// =============================================================================
+// Filter Dispatch
+// =============================================================================
 
 /// Apply request-phase filters (CORS preflight, Timeout, Log, Headers).
 ///
@@ -30,6 +180,9 @@ pub async fn apply_request_filters(
     };
 
     for filter_id in &route_config.filters {
+        if !ctx.filter_enabled(filter_id) {
+            continue;
+        }
         let filter_config = match config.filters.get(filter_id) {
             Some(fc) => fc,
             None => continue,
@@ -70,6 +223,9 @@ pub fn apply_request_headers_filters(
     };
 
     for filter_id in &route_config.filters {
+        if !ctx.filter_enabled(filter_id) {
+            continue;
+        }
         let filter_config = match config.filters.get(filter_id) {
             Some(fc) => fc,
             None => continue,
@@ -83,7 +239,10 @@ pub fn apply_request_headers_filters(
     }
 }
 
-/// Apply response-phase filters (Headers, CORS, Compress setup, Log).
+/// Apply response-phase filters (Headers, CORS, Log).
+///
+/// Compress filters are set up separately by [`setup_response_compression`],
+/// after the body transforms that need the unencoded body.
 pub fn apply_response_filters(
     upstream_response: &mut ResponseHeader,
     ctx: &mut RequestContext,
@@ -95,6 +254,9 @@ pub fn apply_response_filters(
     };
 
     for filter_id in &route_config.filters {
+        if !ctx.filter_enabled(filter_id) {
+            continue;
+        }
         let filter_config = match config.filters.get(filter_id) {
             Some(fc) => fc,
             None => continue,
@@ -109,9 +271,6 @@ pub fn apply_response_filters(
             Filter::Cors(cors) => {
                 apply_cors_response_headers(upstream_response, ctx, cors);
             }
-            Filter::Compress(compress) => {
-                apply_compress_setup(upstream_response, ctx, compress);
-            }
             Filter::Log(log) if log.log_response => {
                 emit_response_log(ctx, log, upstream_response.status.as_u16());
             }
@@ -469,21 +628,62 @@ fn is_origin_allowed(origin: &str, allowed: &[String]) -> bool {
 // Compress Filter
 // =============================================================================
 
-/// Set up compression by modifying response headers.
+/// Set up streaming compression of the response body for the route's first
+/// enabled Compress filter.
+///
+/// Runs after [`setup_response_json_transform`], which only transforms
+/// bodies that are not content-encoded.
+pub fn setup_response_compression(
+    resp: &mut ResponseHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+    accept_encoding: Option<&str>,
+) {
+    let Some(route_config) = ctx.route_config.as_ref().map(Arc::clone) else {
+        return;
+    };
+    let compress = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .find_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::Compress(c) => Some(c),
+            _ => None,
+        });
+
+    if let Some(compress) = compress {
+        apply_compress_setup(resp, ctx, compress, accept_encoding);
+    }
+}
+
+/// Negotiate an encoding and rewrite the response headers for it.
 ///
-/// We remove Content-Length (since compressed size differs) and add
-/// Content-Encoding if the client supports it and the response is compressible.
+/// Content-Length is removed since the compressed size is unknown until the
+/// body has streamed through, and a strong ETag is weakened because the
+/// bytes no longer match the upstream representation.
 fn apply_compress_setup(
     resp: &mut ResponseHeader,
     ctx: &mut RequestContext,
     compress: &CompressFilter,
+    accept_encoding: Option<&str>,
 ) {
+    let status = resp.status.as_u16();
+    if ctx.method.eq_ignore_ascii_case("HEAD")
+        || status < 200
+        || status == 204
+        || status == 206
+        || status == 304
+    {
+        return;
+    }
+
     // Check if response content type is compressible
     let content_type = resp
         .headers
         .get("content-type")
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+        .unwrap_or("")
+        .to_string();
 
     let is_compressible = compress.content_types.iter().any(|ct| {
         // Match on the MIME type prefix (ignore charset/params)
@@ -495,30 +695,78 @@ fn apply_compress_setup(
     }
 
     // Check Content-Length against min_size (if present)
-    if let Some(cl) = resp
-        .headers
-        .get("content-length")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.parse::<usize>().ok())
-    {
-        if cl < compress.min_size {
+    if let Some(cl) = content_length(&resp.headers) {
+        if cl < compress.min_size as u64 {
             return;
         }
     }
 
-    // Check if response is already encoded
-    if resp.headers.get("content-encoding").is_some() {
+    // Check if response is already encoded or must not be transformed
+    if resp.headers.get("content-encoding").is_some()
+        || resp
+            .headers
+            .get("cache-control")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.to_ascii_lowercase().contains("no-transform"))
+    {
         return;
     }
 
-    // Mark that compression should be applied (Pingora handles actual compression
-    // via its built-in compression module when downstream_compression is enabled)
-    ctx.compress_enabled = true;
+    // The representation now depends on Accept-Encoding, compressed or not
+    let varies = resp
+        .headers
+        .get_all("vary")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| v.trim() == "*" || v.trim().eq_ignore_ascii_case("accept-encoding"));
+    if !varies {
+        resp.append_header("Vary", "Accept-Encoding").ok();
+    }
+
+    let Some(algorithm) = accept_encoding
+        .and_then(|accept| crate::compression::negotiate_encoding(accept, &compress.algorithms))
+    else {
+        return;
+    };
+    let level = compress.level_for(&content_type);
+    let compressor = match crate::compression::ResponseCompressor::new(algorithm, level) {
+        Ok(compressor) => compressor,
+        Err(e) => {
+            debug!(
+                correlation_id = %ctx.trace_id,
+                error = %e,
+                "Failed to create response compressor, sending uncompressed"
+            );
+            return;
+        }
+    };
+
+    resp.remove_header("Content-Length");
+    resp.remove_header("Accept-Ranges");
+    resp.insert_header("Transfer-Encoding", "chunked").ok();
+    resp.insert_header(
+        "Content-Encoding",
+        crate::compression::encoding_token(algorithm),
+    )
+    .ok();
+    if let Some(etag) = resp
+        .headers
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .map(|etag| format!("W/{}", etag))
+    {
+        resp.insert_header("ETag", etag).ok();
+    }
+    ctx.response_compressor = Some(compressor);
 
     trace!(
         correlation_id = %ctx.trace_id,
         content_type = %content_type,
-        "Compression eligible, delegating to Pingora compression module"
+        encoding = crate::compression::encoding_token(algorithm),
+        level = level,
+        "Compressing response"
     );
 }
 
@@ -615,6 +863,501 @@ fn emit_response_log(ctx: &RequestContext, log: &LogFilter, status: u16) {
     }
 }
 
+// =============================================================================
+// Prompt Template Filter
+// =============================================================================
+
+/// Prepare the route's `prompt-template` filters.
+///
+/// The body is held back and enforced in `request_body_filter`. Filters
+/// that inject or strip system prompts change its length, so Content-Length
+/// is then replaced by chunked encoding.
+pub fn setup_prompt_templates(req: &mut RequestHeader, ctx: &mut RequestContext, config: &Config) {
+    let has_body = req.headers.contains_key("transfer-encoding")
+        || content_length(&req.headers).is_some_and(|len| len > 0);
+    let Some(route_config) = ctx.route_config.as_ref().filter(|_| has_body) else {
+        return;
+    };
+
+    let provider = route_config
+        .inference
+        .as_ref()
+        .map(|inference| inference.provider)
+        .unwrap_or_default();
+    let checks: Vec<PromptTemplateCheck> = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .filter_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::PromptTemplate(p) => {
+                Some(PromptTemplateCheck::new(filter_id, p.clone(), provider))
+            }
+            _ => None,
+        })
+        .collect();
+    if checks.is_empty() {
+        return;
+    }
+
+    let enforcement = RequestPromptEnforcement::new(checks, ctx.trace_id.clone());
+    if enforcement.rewrites_body() {
+        req.remove_header("Content-Length");
+        req.insert_header("Transfer-Encoding", "chunked").ok();
+    }
+    ctx.request_prompt_enforcement = Some(enforcement);
+}
+
+// =============================================================================
+// Multipart Filter
+// =============================================================================
+
+/// Prepare the route's `multipart` filter for multipart/form-data bodies.
+///
+/// Parts are sent to the route's agents and re-encoded in
+/// `request_body_filter`; dropped or replaced parts change the body's
+/// length, so Content-Length is replaced by chunked encoding. The first
+/// enabled filter on the route sets the limits.
+pub fn setup_multipart_inspection(
+    req: &mut RequestHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+) {
+    let has_body = req.headers.contains_key("transfer-encoding")
+        || content_length(&req.headers).is_some_and(|len| len > 0);
+    let Some(route_config) = ctx.route_config.as_ref().filter(|_| has_body) else {
+        return;
+    };
+    let Some(content_type) = req
+        .headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+    else {
+        return;
+    };
+
+    let inspection = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .find_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::Multipart(m) => Some(MultipartInspection::new(filter_id, m, content_type)),
+            _ => None,
+        })
+        .flatten();
+    if let Some(inspection) = inspection {
+        trace!(
+            correlation_id = %ctx.trace_id,
+            filter_id = inspection.filter_id(),
+            "Inspecting multipart body part by part"
+        );
+        req.remove_header("Content-Length");
+        req.insert_header("Transfer-Encoding", "chunked").ok();
+        ctx.request_multipart_inspection = Some(inspection);
+    }
+}
+
+// =============================================================================
+// ICAP Filter
+// =============================================================================
+
+/// Prepare the route's `icap` filter for the request body.
+///
+/// The body is held back in `request_body_filter` until the REQMOD service
+/// finds it clean. Clean bodies are forwarded unchanged, so Content-Length
+/// is kept. The first enabled filter with a `reqmod` service is used.
+pub fn setup_icap_request_scan(req: &RequestHeader, ctx: &mut RequestContext, config: &Config) {
+    let has_body = req.headers.contains_key("transfer-encoding")
+        || content_length(&req.headers).is_some_and(|len| len > 0);
+    if !has_body {
+        return;
+    }
+    let Some((filter_id, filter)) = icap_filter(ctx, config, |f| f.reqmod.is_some()) else {
+        return;
+    };
+
+    let uri = req.uri.to_string();
+    let heads = vec![(
+        "req-hdr",
+        crate::icap::request_head(req.method.as_str(), &uri, &req.headers),
+    )];
+    ctx.request_icap_scan = IcapBodyScan::new(
+        &filter_id,
+        filter,
+        IcapMode::Reqmod,
+        heads,
+        ctx.trace_id.clone(),
+    );
+}
+
+/// Prepare the route's `icap` filter for the response body.
+///
+/// The response headers are sent before the body is scanned; bodies the
+/// RESPMOD service blocks are cut off in `response_body_filter`.
+pub fn setup_icap_response_scan(
+    req: &RequestHeader,
+    resp: &ResponseHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+) {
+    let status = resp.status.as_u16();
+    // Validation rejects RESPMOD filters on passthrough routes; never hold
+    // the body back there regardless
+    if ctx.streaming_passthrough()
+        || ctx.method.eq_ignore_ascii_case("HEAD")
+        || status == 204
+        || status == 304
+        || content_length(&resp.headers) == Some(0)
+    {
+        return;
+    }
+    let Some((filter_id, filter)) = icap_filter(ctx, config, |f| f.respmod.is_some()) else {
+        return;
+    };
+
+    let uri = req.uri.to_string();
+    let heads = vec![
+        (
+            "req-hdr",
+            crate::icap::request_head(req.method.as_str(), &uri, &req.headers),
+        ),
+        ("res-hdr", crate::icap::response_head(status, &resp.headers)),
+    ];
+    ctx.response_icap_scan = IcapBodyScan::new(
+        &filter_id,
+        filter,
+        IcapMode::Respmod,
+        heads,
+        ctx.trace_id.clone(),
+    );
+}
+
+/// First enabled `icap` filter on the route matching `wanted`
+fn icap_filter<'a>(
+    ctx: &RequestContext,
+    config: &'a Config,
+    wanted: impl Fn(&IcapFilter) -> bool,
+) -> Option<(String, &'a IcapFilter)> {
+    let route_config = ctx.route_config.as_ref()?;
+    route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .find_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::Icap(i) if wanted(i) => Some((filter_id.clone(), i)),
+            _ => None,
+        })
+}
+
+// =============================================================================
+// JSON Transform Filter
+// =============================================================================
+
+/// Prepare the route's request-phase `json-transform` filters.
+///
+/// The transformed body has a different length, so Content-Length is
+/// replaced by chunked encoding.
+pub fn setup_request_json_transform(
+    req: &mut RequestHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+) {
+    let has_body = req.headers.contains_key("transfer-encoding")
+        || content_length(&req.headers).is_some_and(|len| len > 0);
+    if !has_body {
+        return;
+    }
+
+    if let Some(transform) = json_body_transform(&req.headers, ctx, config, FilterPhase::Request) {
+        req.remove_header("Content-Length");
+        req.insert_header("Transfer-Encoding", "chunked").ok();
+        ctx.request_json_transform = Some(transform);
+    }
+}
+
+/// Prepare the route's response-phase `json-transform` filters.
+pub fn setup_response_json_transform(
+    resp: &mut ResponseHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+) {
+    let status = resp.status.as_u16();
+    // Validation rejects these filters on passthrough routes; never buffer
+    // there regardless
+    if ctx.streaming_passthrough()
+        || ctx.method.eq_ignore_ascii_case("HEAD")
+        || status == 204
+        || status == 304
+        || content_length(&resp.headers) == Some(0)
+    {
+        return;
+    }
+
+    if let Some(transform) = json_body_transform(&resp.headers, ctx, config, FilterPhase::Response)
+    {
+        resp.remove_header("Content-Length");
+        resp.insert_header("Transfer-Encoding", "chunked").ok();
+        ctx.response_json_transform = Some(transform);
+    }
+}
+
+/// Collect the enabled `json-transform` filters for `phase`, if the body
+/// is plain (not content-encoded) JSON.
+fn json_body_transform(
+    headers: &http::HeaderMap,
+    ctx: &RequestContext,
+    config: &Config,
+    phase: FilterPhase,
+) -> Option<JsonBodyTransform> {
+    let route_config = ctx.route_config.as_ref()?;
+    let mut filters: Vec<JsonTransformFilter> = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .filter_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::JsonTransform(j) if j.phase == phase || j.phase == FilterPhase::Both => {
+                Some(j.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    // An exhausted cost budget sends the request to a cheaper model
+    if phase == FilterPhase::Request {
+        if let Some(model) = ctx.inference_model_downgrade.as_deref() {
+            filters.push(JsonTransformFilter {
+                set: vec![JsonSetField {
+                    path: "$.model".parse().ok()?,
+                    value: serde_json::Value::String(model.to_string()),
+                }],
+                ..Default::default()
+            });
+        }
+    }
+    if filters.is_empty() {
+        return None;
+    }
+
+    let content_type = headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let encoded = headers
+        .get("content-encoding")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|e| !e.eq_ignore_ascii_case("identity"));
+    if !is_json_content_type(content_type) || encoded {
+        trace!(
+            correlation_id = %ctx.trace_id,
+            content_type = %content_type,
+            encoded = encoded,
+            "Skipping json-transform filters for non-JSON or encoded body"
+        );
+        return None;
+    }
+
+    let phase = if phase == FilterPhase::Response {
+        "response"
+    } else {
+        "request"
+    };
+    Some(JsonBodyTransform::new(filters, phase))
+}
+
+fn content_length(headers: &http::HeaderMap) -> Option<u64> {
+    headers
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+// =============================================================================
+// Response Policy Filter
+// =============================================================================
+
+/// Responses refused by a response-policy filter, by filter and reason
+/// (`content_type`, `content_length`, `body_size`).
+static RESPONSE_POLICY_VIOLATIONS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_response_policy_violations_total",
+        "Upstream responses refused by response-policy filters",
+        &["filter", "reason"]
+    )
+    .ok()
+});
+
+/// Body size limit set up by a response-policy filter, checked as the
+/// upstream body streams through.
+#[derive(Debug)]
+pub struct ResponseSizeLimit {
+    filter_id: String,
+    max_bytes: u64,
+    received: u64,
+}
+
+/// Enforce the route's response-policy filters on the upstream response.
+///
+/// Listed headers are stripped; a disallowed content type or a declared
+/// Content-Length over the limit fails the request with 502 before
+/// anything reaches the client. Bodies without a Content-Length are
+/// counted in [`check_response_size`].
+pub fn apply_response_policies(
+    resp: &mut ResponseHeader,
+    ctx: &mut RequestContext,
+    config: &Config,
+) -> pingora::Result<()> {
+    let Some(route_config) = ctx.route_config.as_ref().map(Arc::clone) else {
+        return Ok(());
+    };
+
+    for filter_id in &route_config.filters {
+        if !ctx.filter_enabled(filter_id) {
+            continue;
+        }
+        let Some(Filter::ResponsePolicy(policy)) =
+            config.filters.get(filter_id).map(|fc| &fc.filter)
+        else {
+            continue;
+        };
+        if !policy.applies_to(ctx.upstream.as_deref()) {
+            continue;
+        }
+        apply_response_policy(resp, ctx, filter_id, policy)?;
+    }
+
+    Ok(())
+}
+
+fn apply_response_policy(
+    resp: &mut ResponseHeader,
+    ctx: &mut RequestContext,
+    filter_id: &str,
+    policy: &ResponsePolicyFilter,
+) -> pingora::Result<()> {
+    for name in &policy.strip_headers {
+        resp.remove_header(name.as_str());
+    }
+
+    let status = resp.status.as_u16();
+    if ctx.method.eq_ignore_ascii_case("HEAD") || status < 200 || status == 204 || status == 304 {
+        return Ok(());
+    }
+
+    if !policy.allowed_content_types.is_empty() {
+        let content_type = resp
+            .headers
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        if !content_type_allowed(content_type, &policy.allowed_content_types) {
+            return Err(response_policy_violation(
+                ctx,
+                filter_id,
+                "content_type",
+                format!("content type '{}' is not allowed", content_type),
+            ));
+        }
+    }
+
+    if let Some(max_bytes) = policy.max_body_bytes {
+        if let Some(length) = content_length(&resp.headers).filter(|&cl| cl > max_bytes) {
+            return Err(response_policy_violation(
+                ctx,
+                filter_id,
+                "content_length",
+                format!("content length {} exceeds {} bytes", length, max_bytes),
+            ));
+        }
+        // The tightest limit wins when several policies apply
+        if ctx
+            .response_size_limit
+            .as_ref()
+            .map_or(true, |limit| max_bytes < limit.max_bytes)
+        {
+            ctx.response_size_limit = Some(ResponseSizeLimit {
+                filter_id: filter_id.to_string(),
+                max_bytes,
+                received: 0,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Count an upstream body chunk against the response size limit.
+///
+/// Headers have already been sent at this point, so exceeding the limit
+/// aborts the response instead of answering with a 502.
+pub fn check_response_size(
+    ctx: &mut RequestContext,
+    body: &Option<bytes::Bytes>,
+) -> pingora::Result<()> {
+    let (Some(limit), Some(chunk)) = (ctx.response_size_limit.as_mut(), body.as_ref()) else {
+        return Ok(());
+    };
+    limit.received += chunk.len() as u64;
+    if limit.received <= limit.max_bytes {
+        return Ok(());
+    }
+
+    let filter_id = limit.filter_id.clone();
+    let message = format!(
+        "response body exceeds {} bytes, terminating",
+        limit.max_bytes
+    );
+    ctx.response_size_limit = None;
+    Err(response_policy_violation(
+        ctx,
+        &filter_id,
+        "body_size",
+        message,
+    ))
+}
+
+/// Match a response content type against an allowlist of `type/subtype`
+/// or `type/*` entries, ignoring parameters such as charset.
+fn content_type_allowed(content_type: &str, allowed: &[String]) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    if mime.is_empty() {
+        return false;
+    }
+    allowed
+        .iter()
+        .any(|pattern| match pattern.strip_suffix("/*") {
+            Some(main_type) => mime
+                .split_once('/')
+                .is_some_and(|(t, _)| t.eq_ignore_ascii_case(main_type)),
+            None => mime.eq_ignore_ascii_case(pattern),
+        })
+}
+
+fn response_policy_violation(
+    ctx: &RequestContext,
+    filter_id: &str,
+    reason: &'static str,
+    message: String,
+) -> Box<Error> {
+    if let Some(counter) = RESPONSE_POLICY_VIOLATIONS.as_ref() {
+        counter.with_label_values(&[filter_id, reason]).inc();
+    }
+    warn!(
+        correlation_id = %ctx.trace_id,
+        filter = %filter_id,
+        upstream = ctx.upstream.as_deref().unwrap_or("-"),
+        reason = reason,
+        "Response policy violation: {}",
+        message
+    );
+    Error::explain(
+        ErrorType::HTTPStatus(502),
+        format!("response-policy '{}': {}", filter_id, message),
+    )
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -626,8 +1369,8 @@ mod tests {
 
     use pingora::http::RequestHeader as PingoraRequestHeader;
     use zentinel_config::{
-        filters::FilterConfig, CompressFilter, CorsFilter, FilterPhase, HeadersFilter, LogFilter,
-        TimeoutFilter,
+        filters::FilterConfig, CompressFilter, CompressionAlgorithm, CorsFilter, FilterPhase,
+        HeadersFilter, LogFilter, ResponsePolicyFilter, TimeoutFilter,
     };
 
     // =========================================================================
@@ -888,51 +1631,72 @@ mod tests {
     // Compress filter tests
     // =========================================================================
 
-    #[test]
-    fn compress_enables_for_compressible_content() {
-        let compress = CompressFilter {
-            algorithms: vec![],
+    fn gzip_filter() -> CompressFilter {
+        CompressFilter {
+            algorithms: vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Zstd],
             min_size: 1024,
             content_types: vec!["text/".to_string()],
             level: 6,
-        };
+            levels: HashMap::new(),
+        }
+    }
 
-        let (config, route) = test_config_with_filter("gz", Filter::Compress(compress));
+    #[test]
+    fn compress_enables_for_compressible_content() {
+        let (config, route) = test_config_with_filter("gz", Filter::Compress(gzip_filter()));
         let mut ctx = new_ctx_with_route(&route);
 
         let mut resp = ResponseHeader::build(200, None).unwrap();
         resp.insert_header("Content-Type", "text/html; charset=utf-8")
             .unwrap();
         resp.insert_header("Content-Length", "5000").unwrap();
+        resp.insert_header("ETag", "\"v1\"").unwrap();
+
+        setup_response_compression(&mut resp, &mut ctx, &config, Some("gzip, zstd;q=0.5"));
+
+        let compressor = ctx
+            .response_compressor
+            .as_ref()
+            .expect("Should enable compression for text/html > 1024 bytes");
+        assert_eq!(compressor.algorithm(), CompressionAlgorithm::Gzip);
+        assert_eq!(resp.headers.get("content-encoding").unwrap(), "gzip");
+        assert_eq!(resp.headers.get("vary").unwrap(), "Accept-Encoding");
+        assert_eq!(resp.headers.get("etag").unwrap(), "W/\"v1\"");
+        assert!(resp.headers.get("content-length").is_none());
+    }
 
-        apply_response_filters(&mut resp, &mut ctx, &config);
+    #[test]
+    fn compress_requires_accepted_encoding() {
+        let (config, route) = test_config_with_filter("gz", Filter::Compress(gzip_filter()));
+        let mut ctx = new_ctx_with_route(&route);
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "text/html").unwrap();
+        resp.insert_header("Content-Length", "5000").unwrap();
+
+        setup_response_compression(&mut resp, &mut ctx, &config, Some("br, gzip;q=0"));
 
         assert!(
-            ctx.compress_enabled,
-            "Should enable compression for text/html > 1024 bytes"
+            ctx.response_compressor.is_none(),
+            "Should not compress with an encoding the client does not accept"
         );
+        assert!(resp.headers.get("content-encoding").is_none());
+        assert_eq!(resp.headers.get("vary").unwrap(), "Accept-Encoding");
     }
 
     #[test]
     fn compress_skips_small_responses() {
-        let compress = CompressFilter {
-            algorithms: vec![],
-            min_size: 1024,
-            content_types: vec!["text/".to_string()],
-            level: 6,
-        };
-
-        let (config, route) = test_config_with_filter("gz", Filter::Compress(compress));
+        let (config, route) = test_config_with_filter("gz", Filter::Compress(gzip_filter()));
         let mut ctx = new_ctx_with_route(&route);
 
         let mut resp = ResponseHeader::build(200, None).unwrap();
         resp.insert_header("Content-Type", "text/html").unwrap();
         resp.insert_header("Content-Length", "100").unwrap();
 
-        apply_response_filters(&mut resp, &mut ctx, &config);
+        setup_response_compression(&mut resp, &mut ctx, &config, Some("gzip"));
 
         assert!(
-            !ctx.compress_enabled,
+            ctx.response_compressor.is_none(),
             "Should skip compression for responses smaller than min_size"
         );
     }
@@ -940,10 +1704,8 @@ mod tests {
     #[test]
     fn compress_skips_non_compressible_types() {
         let compress = CompressFilter {
-            algorithms: vec![],
-            min_size: 1024,
             content_types: vec!["text/".to_string(), "application/json".to_string()],
-            level: 6,
+            ..gzip_filter()
         };
 
         let (config, route) = test_config_with_filter("gz", Filter::Compress(compress));
@@ -953,24 +1715,17 @@ mod tests {
         resp.insert_header("Content-Type", "image/png").unwrap();
         resp.insert_header("Content-Length", "50000").unwrap();
 
-        apply_response_filters(&mut resp, &mut ctx, &config);
+        setup_response_compression(&mut resp, &mut ctx, &config, Some("gzip"));
 
         assert!(
-            !ctx.compress_enabled,
+            ctx.response_compressor.is_none(),
             "Should skip compression for non-compressible content types"
         );
     }
 
     #[test]
     fn compress_skips_already_encoded() {
-        let compress = CompressFilter {
-            algorithms: vec![],
-            min_size: 1024,
-            content_types: vec!["text/".to_string()],
-            level: 6,
-        };
-
-        let (config, route) = test_config_with_filter("gz", Filter::Compress(compress));
+        let (config, route) = test_config_with_filter("gz", Filter::Compress(gzip_filter()));
         let mut ctx = new_ctx_with_route(&route);
 
         let mut resp = ResponseHeader::build(200, None).unwrap();
@@ -978,10 +1733,10 @@ mod tests {
         resp.insert_header("Content-Length", "5000").unwrap();
         resp.insert_header("Content-Encoding", "gzip").unwrap();
 
-        apply_response_filters(&mut resp, &mut ctx, &config);
+        setup_response_compression(&mut resp, &mut ctx, &config, Some("gzip"));
 
         assert!(
-            !ctx.compress_enabled,
+            ctx.response_compressor.is_none(),
             "Should skip compression for already-encoded responses"
         );
     }
@@ -1177,4 +1932,181 @@ mod tests {
             "/v2/v1/users"
         );
     }
+
+    // =========================================================================
+    // Filter condition tests
+    // =========================================================================
+
+    fn headers_filter_with_condition(condition: &str) -> (Arc<Config>, RequestContext) {
+        let mut set = HashMap::new();
+        set.insert("X-Beta".to_string(), "1".to_string());
+        let headers_filter = HeadersFilter {
+            phase: FilterPhase::Request,
+            set,
+            ..Default::default()
+        };
+
+        let (config, route) = test_config_with_filter("beta", Filter::Headers(headers_filter));
+        let mut config = Arc::unwrap_or_clone(config);
+        config.filters.get_mut("beta").unwrap().enable_if = Some(condition.parse().unwrap());
+        (Arc::new(config), new_ctx_with_route(&route))
+    }
+
+    #[test]
+    fn enable_if_false_skips_filter() {
+        let (config, mut ctx) = headers_filter_with_condition(r#"request.query["beta"] == "1""#);
+        let mut req = PingoraRequestHeader::build("GET", b"/test?beta=0", None).unwrap();
+
+        evaluate_filter_conditions(&req, &mut ctx, &config, ConditionStage::Request);
+        apply_request_headers_filters(&mut req, &ctx, &config);
+
+        assert!(!ctx.filter_enabled("beta"));
+        assert!(req.headers.get("X-Beta").is_none());
+    }
+
+    #[test]
+    fn enable_if_true_applies_filter() {
+        let (config, mut ctx) = headers_filter_with_condition(r#"request.query["beta"] == "1""#);
+        let mut req = PingoraRequestHeader::build("GET", b"/test?beta=1", None).unwrap();
+
+        evaluate_filter_conditions(&req, &mut ctx, &config, ConditionStage::Request);
+        apply_request_headers_filters(&mut req, &ctx, &config);
+
+        assert!(ctx.filter_enabled("beta"));
+        assert!(req.headers.get("X-Beta").is_some());
+    }
+
+    #[test]
+    fn enable_if_metadata_waits_for_agents_stage() {
+        let (config, mut ctx) = headers_filter_with_condition(r#"metadata["tier"] == "gold""#);
+        let req = PingoraRequestHeader::build("GET", b"/test", None).unwrap();
+
+        // Not evaluated before agents have run
+        evaluate_filter_conditions(&req, &mut ctx, &config, ConditionStage::Request);
+        evaluate_filter_conditions(&req, &mut ctx, &config, ConditionStage::Geo);
+        assert!(ctx.filter_enabled("beta"));
+
+        ctx.routing_metadata
+            .insert("tier".to_string(), "silver".to_string());
+        evaluate_filter_conditions(&req, &mut ctx, &config, ConditionStage::Agents);
+        assert!(!ctx.filter_enabled("beta"));
+    }
+
+    // =========================================================================
+    // JSON transform filter tests
+    // =========================================================================
+
+    #[test]
+    fn json_transform_filter_prepares_json_responses_only() {
+        let json_filter = JsonTransformFilter {
+            phase: FilterPhase::Response,
+            remove: vec![zentinel_config::JsonPath::parse("$.internal").unwrap()],
+            ..Default::default()
+        };
+        let (config, route) = test_config_with_filter("json", Filter::JsonTransform(json_filter));
+        let mut ctx = new_ctx_with_route(&route);
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "text/html").unwrap();
+        resp.insert_header("Content-Length", "42").unwrap();
+        setup_response_json_transform(&mut resp, &mut ctx, &config);
+        assert!(ctx.response_json_transform.is_none());
+        assert!(resp.headers.get("Content-Length").is_some());
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "application/json")
+            .unwrap();
+        resp.insert_header("Content-Length", "42").unwrap();
+        setup_response_json_transform(&mut resp, &mut ctx, &config);
+        assert!(ctx.response_json_transform.is_some());
+        assert!(resp.headers.get("Content-Length").is_none());
+
+        // Request-phase setup ignores response-phase filters
+        let mut req = PingoraRequestHeader::build("POST", b"/test", None).unwrap();
+        req.insert_header("Content-Type", "application/json")
+            .unwrap();
+        req.insert_header("Content-Length", "42").unwrap();
+        setup_request_json_transform(&mut req, &mut ctx, &config);
+        assert!(ctx.request_json_transform.is_none());
+    }
+
+    // =========================================================================
+    // Response policy filter tests
+    // =========================================================================
+
+    fn response_policy(upstreams: Vec<String>) -> Filter {
+        Filter::ResponsePolicy(ResponsePolicyFilter {
+            max_body_bytes: Some(10),
+            allowed_content_types: vec!["application/json".to_string(), "text/*".to_string()],
+            strip_headers: vec!["Set-Cookie".to_string()],
+            upstreams,
+        })
+    }
+
+    #[test]
+    fn response_policy_strips_headers_and_enforces_content_type() {
+        let (config, route) = test_config_with_filter("policy", response_policy(vec![]));
+        let mut ctx = new_ctx_with_route(&route);
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "application/json; charset=utf-8")
+            .unwrap();
+        resp.insert_header("Set-Cookie", "tracking=1").unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_ok());
+        assert!(resp.headers.get("Set-Cookie").is_none());
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "text/plain").unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_ok());
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "application/octet-stream")
+            .unwrap();
+        let err = apply_response_policies(&mut resp, &mut ctx, &config).unwrap_err();
+        assert_eq!(err.etype, ErrorType::HTTPStatus(502));
+
+        // No body, nothing to check
+        let mut resp = ResponseHeader::build(304, None).unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_ok());
+    }
+
+    #[test]
+    fn response_policy_limits_body_size() {
+        let (config, route) = test_config_with_filter("policy", response_policy(vec![]));
+        let mut ctx = new_ctx_with_route(&route);
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "application/json")
+            .unwrap();
+        resp.insert_header("Content-Length", "11").unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_err());
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "application/json")
+            .unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_ok());
+        assert!(
+            check_response_size(&mut ctx, &Some(bytes::Bytes::from_static(b"{\"a\":1}"))).is_ok()
+        );
+        assert!(check_response_size(&mut ctx, &Some(bytes::Bytes::from_static(b"{}{}"))).is_err());
+        assert!(ctx.response_size_limit.is_none());
+    }
+
+    #[test]
+    fn response_policy_only_applies_to_listed_upstreams() {
+        let (config, route) =
+            test_config_with_filter("policy", response_policy(vec!["partner".to_string()]));
+        let mut ctx = new_ctx_with_route(&route);
+        ctx.upstream = Some("internal".to_string());
+
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        resp.insert_header("Content-Type", "image/png").unwrap();
+        resp.insert_header("Set-Cookie", "session=1").unwrap();
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_ok());
+        assert!(resp.headers.get("Set-Cookie").is_some());
+
+        ctx.upstream = Some("partner".to_string());
+        assert!(apply_response_policies(&mut resp, &mut ctx, &config).is_err());
+        assert!(resp.headers.get("Set-Cookie").is_none());
+    }
 }
@@ -9,22 +9,38 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
-use pingora::http::ResponseHeader;
+use pingora::http::{RequestHeader, ResponseHeader};
 use pingora::prelude::*;
 use pingora::proxy::Session;
 use tracing::{debug, error, info, warn};
-use uuid::Uuid;
 
+use crate::adaptive_protection::AdaptiveVerdict;
+use crate::api_keys::ApiKeyOutcome;
 use crate::builtin_handlers;
+use crate::capture::CaptureError;
+use crate::chaos::ChaosError;
+use crate::explain::{
+    header_changes, header_snapshot, ExplainAgent, ExplainDecision, ExplainFilter, ExplainRequest,
+    ExplainRoute, ExplainTrace, MAX_EXPLAIN_BODY,
+};
+use crate::icap::IcapRejection;
+use crate::inference::{CostBudgetDecision, PromptRejection};
+use crate::inflight::InFlightError;
 use crate::logging::{AuditEventType, AuditLogEntry};
-use crate::routing::RouteMatch;
+use crate::managed_rules::{RequestBodyInspection, RuleRejection};
+use crate::routing::{RequestInfo, RouteMatch};
+use crate::schema_validate::{RequestBodyValidation, SchemaRejection};
 use crate::validation::SchemaValidator;
 
 use super::context::RequestContext;
+use super::filters::{self, ConditionStage};
 use super::ZentinelProxy;
 
+use zentinel_agent_protocol::{Decision, HeaderOp};
 use zentinel_common::CorrelationId;
+use zentinel_config::{AgentEvent, BodyStreamingMode, Filter};
 
 impl ZentinelProxy {
     /// Handle static file route
@@ -35,7 +51,9 @@ impl ZentinelProxy {
         route_match: &RouteMatch,
     ) -> Result<bool, Box<Error>> {
         ctx.route_id = Some(route_match.route_id.to_string());
-        ctx.trace_id = self.get_trace_id(session);
+        if ctx.trace_id.is_empty() {
+            ctx.trace_id = self.get_trace_id(session);
+        }
         let route_id = route_match.route_id.as_str();
 
         if let Some(static_server) = self.static_servers.get(route_id).await {
@@ -53,7 +71,10 @@ impl ZentinelProxy {
 
             match static_server.serve(&static_req, &path).await {
                 Ok(response) => {
-                    self.write_http_response(session, response).await?;
+                    let status = response.status().as_u16();
+                    if status < 400 || !self.write_route_error_page(session, ctx, status).await? {
+                        self.write_http_response(session, response).await?;
+                    }
 
                     info!(
                         correlation_id = %ctx.trace_id,
@@ -101,7 +122,7 @@ impl ZentinelProxy {
         Ok(false)
     }
 
-    /// Handle builtin route (status, health, metrics, config, upstreams)
+    /// Handle builtin route (status, health, metrics, config, upstreams, agents)
     pub(super) async fn handle_builtin_route(
         &self,
         session: &mut Session,
@@ -112,8 +133,16 @@ impl ZentinelProxy {
         let route_id = route_match.route_id.as_str();
 
         if let Some(handler) = route_match.config.builtin_handler {
-            let request_id = self.get_trace_id(session);
-            ctx.trace_id = request_id.clone();
+            if ctx.trace_id.is_empty() {
+                ctx.trace_id = self.get_trace_id(session);
+            }
+            let request_id = ctx.trace_id.clone();
+
+            // The request tap streams until the client goes away
+            if matches!(handler, zentinel_config::BuiltinHandler::Tap) {
+                self.serve_request_tap(session, &request_id).await?;
+                return Ok(true);
+            }
 
             // Get current config for config dump handler
             let config = Some(self.config_manager.current());
@@ -141,6 +170,71 @@ impl ZentinelProxy {
                 None
             };
 
+            // Run tenant admin actions before taking the status snapshot
+            let tenants = if matches!(handler, zentinel_config::BuiltinHandler::Tenants) {
+                Some(self.run_tenant_admin_action(session).await)
+            } else {
+                None
+            };
+            let maintenance = if matches!(handler, zentinel_config::BuiltinHandler::Maintenance) {
+                Some(self.run_maintenance_admin_action(session))
+            } else {
+                None
+            };
+            let api_keys = if matches!(handler, zentinel_config::BuiltinHandler::ApiKeys) {
+                Some(self.run_api_key_admin_action(session))
+            } else {
+                None
+            };
+            let chaos = if matches!(handler, zentinel_config::BuiltinHandler::Chaos) {
+                Some(self.run_chaos_admin_action(session))
+            } else {
+                None
+            };
+            let capture = if matches!(handler, zentinel_config::BuiltinHandler::Capture) {
+                Some(self.run_capture_admin_action(session))
+            } else {
+                None
+            };
+            let audit = if matches!(handler, zentinel_config::BuiltinHandler::Audit) {
+                Some(self.run_audit_query(session))
+            } else {
+                None
+            };
+            let explain = if matches!(handler, zentinel_config::BuiltinHandler::Explain) {
+                Some(self.run_explain(session).await)
+            } else {
+                None
+            };
+            let quotas = if matches!(handler, zentinel_config::BuiltinHandler::Quotas) {
+                Some(self.run_quota_admin_action(session).await)
+            } else {
+                None
+            };
+            let alerts = if matches!(handler, zentinel_config::BuiltinHandler::Alerts) {
+                Some(crate::alerts::status())
+            } else {
+                None
+            };
+            let requests = if matches!(handler, zentinel_config::BuiltinHandler::Requests) {
+                Some(self.run_requests_admin_action(session))
+            } else {
+                None
+            };
+            let connections = if matches!(handler, zentinel_config::BuiltinHandler::Connections) {
+                Some(crate::inflight::registry().connections())
+            } else {
+                None
+            };
+            let health = if matches!(
+                handler,
+                zentinel_config::BuiltinHandler::Readyz | zentinel_config::BuiltinHandler::Healthz
+            ) {
+                Some(self.build_health_report(upstreams.as_ref()).await)
+            } else {
+                None
+            };
+
             let response = builtin_handlers::execute_handler(
                 handler,
                 &self.builtin_state,
@@ -150,6 +244,19 @@ impl ZentinelProxy {
                 cache_stats,
                 cache_purge,
                 Some(&self.cache_manager),
+                self.agent_supervisor.as_ref().map(|s| s.status()),
+                tenants,
+                maintenance,
+                api_keys,
+                chaos,
+                capture,
+                audit,
+                explain,
+                quotas,
+                alerts,
+                requests,
+                connections,
+                health,
             );
 
             self.write_http_response(session, response).await?;
@@ -172,6 +279,842 @@ impl ZentinelProxy {
         Ok(false)
     }
 
+    /// Apply a tenant admin action from the query string
+    ///
+    /// `?tenant=<id>&action=drain|resume|reload` (POST) acts on a single
+    /// tenant; other tenants' config, quotas and in-flight requests are not
+    /// touched. Without an action only the status snapshot is returned.
+    async fn run_tenant_admin_action(
+        &self,
+        session: &Session,
+    ) -> builtin_handlers::TenantAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let tenant = params.remove("tenant");
+        let action = params.remove("action");
+
+        let action = match (tenant, action) {
+            (Some(tenant), Some(action)) => {
+                let (status, error) = if req_header.method != http::Method::POST {
+                    (
+                        http::StatusCode::METHOD_NOT_ALLOWED,
+                        Some("Tenant actions require POST".to_string()),
+                    )
+                } else {
+                    self.apply_tenant_action(&tenant, &action).await
+                };
+                Some(builtin_handlers::TenantActionResult {
+                    tenant,
+                    action,
+                    status,
+                    error,
+                })
+            }
+            (None, Some(action)) => Some(builtin_handlers::TenantActionResult {
+                tenant: String::new(),
+                action,
+                status: http::StatusCode::BAD_REQUEST,
+                error: Some("Missing 'tenant' query parameter".to_string()),
+            }),
+            _ => None,
+        };
+
+        builtin_handlers::TenantAdminResult {
+            tenants: self.tenant_manager.status(),
+            action,
+        }
+    }
+
+    async fn apply_tenant_action(
+        &self,
+        tenant: &str,
+        action: &str,
+    ) -> (http::StatusCode, Option<String>) {
+        let unknown = || {
+            (
+                http::StatusCode::NOT_FOUND,
+                Some(format!("Unknown tenant '{}'", tenant)),
+            )
+        };
+
+        match action {
+            "drain" if self.tenant_manager.drain(tenant) => {
+                info!(tenant = tenant, "Tenant draining");
+                (http::StatusCode::OK, None)
+            }
+            "resume" if self.tenant_manager.resume(tenant) => {
+                info!(tenant = tenant, "Tenant resumed");
+                (http::StatusCode::OK, None)
+            }
+            "drain" | "resume" => unknown(),
+            "reload" => {
+                if self.config_manager.current().tenant(tenant).is_none() {
+                    return unknown();
+                }
+                match self.config_manager.reload_tenant(tenant).await {
+                    Ok(()) => {
+                        info!(tenant = tenant, "Tenant configuration reloaded");
+                        (http::StatusCode::OK, None)
+                    }
+                    Err(e) => {
+                        warn!(tenant = tenant, error = %e, "Tenant reload failed");
+                        (http::StatusCode::INTERNAL_SERVER_ERROR, Some(e.to_string()))
+                    }
+                }
+            }
+            other => (
+                http::StatusCode::BAD_REQUEST,
+                Some(format!(
+                    "Unknown action '{}'. Valid actions are: drain, resume, reload",
+                    other
+                )),
+            ),
+        }
+    }
+
+    /// Apply a maintenance admin action from the query string
+    ///
+    /// `?action=enable|disable` (POST) toggles global maintenance;
+    /// `?route=<id>&action=enable|disable` toggles a single route. Without
+    /// an action only the current flags are returned.
+    fn run_maintenance_admin_action(
+        &self,
+        session: &Session,
+    ) -> builtin_handlers::MaintenanceAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let route = params.remove("route");
+        let action = params.remove("action");
+
+        let action = action.map(|action| {
+            let (status, error) = if req_header.method != http::Method::POST {
+                (
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    Some("Maintenance actions require POST".to_string()),
+                )
+            } else {
+                self.apply_maintenance_action(route.as_deref(), &action)
+            };
+            builtin_handlers::MaintenanceActionResult {
+                route,
+                action,
+                status,
+                error,
+            }
+        });
+
+        builtin_handlers::MaintenanceAdminResult {
+            state: self.maintenance_manager.state(),
+            action,
+        }
+    }
+
+    fn apply_maintenance_action(
+        &self,
+        route: Option<&str>,
+        action: &str,
+    ) -> (http::StatusCode, Option<String>) {
+        let enabled = match action {
+            "enable" => true,
+            "disable" => false,
+            other => {
+                return (
+                    http::StatusCode::BAD_REQUEST,
+                    Some(format!(
+                        "Unknown action '{}'. Valid actions are: enable, disable",
+                        other
+                    )),
+                )
+            }
+        };
+
+        let result = match route {
+            Some(route) => {
+                let config = self.config_manager.current();
+                let known = config.routes.iter().any(|r| r.id == route)
+                    || config
+                        .namespaces
+                        .iter()
+                        .any(|ns| ns.routes.iter().any(|r| r.id == route));
+                // Unknown routes can still be disabled to clear stale state
+                if enabled && !known {
+                    return (
+                        http::StatusCode::NOT_FOUND,
+                        Some(format!("Unknown route '{}'", route)),
+                    );
+                }
+                self.maintenance_manager.set_route(route, enabled)
+            }
+            None => self.maintenance_manager.set_global(enabled),
+        };
+
+        match result {
+            Ok(()) => (http::StatusCode::OK, None),
+            Err(e) => {
+                warn!(error = %e, "Failed to persist maintenance state");
+                (
+                    http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Some(format!(
+                        "Maintenance state changed but could not be persisted: {}",
+                        e
+                    )),
+                )
+            }
+        }
+    }
+
+    /// Stream request summaries as Server-Sent Events
+    ///
+    /// Filters come from the query string (see [`crate::tap`]). The stream
+    /// ends when the client disconnects, which is noticed on the next write
+    /// (at the latest the keep-alive comment), or once `limit` is reached.
+    async fn serve_request_tap(
+        &self,
+        session: &mut Session,
+        request_id: &str,
+    ) -> Result<(), Box<Error>> {
+        let query = session.req_header().uri.query().unwrap_or_default();
+        let filter = match crate::tap::TapFilter::from_query(query) {
+            Ok(filter) => filter,
+            Err(e) => {
+                let body = serde_json::json!({
+                    "error": e.to_string(),
+                    "request_id": request_id,
+                });
+                let response = http::Response::builder()
+                    .status(http::StatusCode::BAD_REQUEST)
+                    .header("Content-Type", "application/json; charset=utf-8")
+                    .header("X-Request-Id", request_id)
+                    .body(http_body_util::Full::new(bytes::Bytes::from(
+                        body.to_string(),
+                    )))
+                    .expect("static response builder with valid headers cannot fail");
+                return self.write_http_response(session, response).await;
+            }
+        };
+        info!(
+            request_id = %request_id,
+            filter = ?filter,
+            "Request tap stream opened"
+        );
+        let mut stream = crate::tap::TapStream::open(filter);
+
+        let mut resp_header = ResponseHeader::build(200, None)?;
+        resp_header.insert_header("Content-Type", "text/event-stream")?;
+        resp_header.insert_header("Cache-Control", "no-cache")?;
+        resp_header.insert_header("X-Request-Id", request_id)?;
+        session.set_keepalive(None);
+        session
+            .write_response_header(Box::new(resp_header), false)
+            .await?;
+
+        let mut keepalive = tokio::time::interval(crate::tap::TAP_KEEPALIVE);
+        keepalive.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        keepalive.tick().await;
+        let mut sent = 0u64;
+        let result = loop {
+            let frame = tokio::select! {
+                frame = stream.next() => match frame {
+                    Some(frame) => frame,
+                    None => break session.write_response_body(None, true).await,
+                },
+                _ = keepalive.tick() => bytes::Bytes::from_static(b": keep-alive\n\n"),
+            };
+            if let Err(e) = session.write_response_body(Some(frame), false).await {
+                break Err(e);
+            }
+            sent += 1;
+        };
+
+        debug!(
+            request_id = %request_id,
+            frames = sent,
+            closed_by_client = result.is_err(),
+            "Request tap stream closed"
+        );
+        // A disconnected client is the normal end of a tap
+        Ok(())
+    }
+
+    /// Apply a chaos admin action from the query string
+    ///
+    /// `?fault=<id>&action=arm|disarm` (POST) arms a fault for its configured
+    /// duration or disarms it early; `?action=disarm` disarms every fault.
+    /// Without an action only the faults are returned.
+    fn run_chaos_admin_action(&self, session: &Session) -> builtin_handlers::ChaosAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let fault = params.remove("fault");
+        let action = params.remove("action");
+
+        let chaos = crate::chaos::manager();
+        let action = action.map(|action| {
+            let result = if req_header.method != http::Method::POST {
+                Err((
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Chaos actions require POST".to_string(),
+                ))
+            } else {
+                match (action.as_str(), fault.as_deref()) {
+                    ("arm", Some(fault)) => chaos.arm(fault).map(|_| ()).map_err(chaos_error),
+                    ("arm", None) => Err((
+                        http::StatusCode::BAD_REQUEST,
+                        "Missing 'fault' parameter".to_string(),
+                    )),
+                    ("disarm", Some(fault)) => chaos.disarm(fault).map_err(chaos_error),
+                    ("disarm", None) => {
+                        chaos.disarm_all();
+                        Ok(())
+                    }
+                    (other, _) => Err((
+                        http::StatusCode::BAD_REQUEST,
+                        format!("Unknown action '{}'. Valid actions are: arm, disarm", other),
+                    )),
+                }
+            };
+            let (status, error) = match result {
+                Ok(()) => (http::StatusCode::OK, None),
+                Err((status, error)) => (status, Some(error)),
+            };
+            builtin_handlers::ChaosActionResult {
+                fault,
+                action,
+                status,
+                error,
+            }
+        });
+
+        builtin_handlers::ChaosAdminResult {
+            state: chaos.state(),
+            action,
+        }
+    }
+
+    /// Apply an in-flight request admin action from the query string
+    ///
+    /// `?action=cancel&id=<correlation-id>` (POST) cancels a request that is
+    /// still in flight. Without an action only the requests are returned.
+    fn run_requests_admin_action(
+        &self,
+        session: &Session,
+    ) -> builtin_handlers::InFlightAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let id = params.remove("id");
+        let action = params.remove("action");
+
+        let registry = crate::inflight::registry();
+        let action = action.map(|action| {
+            let result = if req_header.method != http::Method::POST {
+                Err((
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Request actions require POST".to_string(),
+                ))
+            } else {
+                match (action.as_str(), id.as_deref()) {
+                    ("cancel", Some(id)) => registry.cancel(id).map_err(inflight_error),
+                    ("cancel", None) => Err((
+                        http::StatusCode::BAD_REQUEST,
+                        "Missing 'id' parameter".to_string(),
+                    )),
+                    (other, _) => Err((
+                        http::StatusCode::BAD_REQUEST,
+                        format!("Unknown action '{}'. Valid actions are: cancel", other),
+                    )),
+                }
+            };
+            let (status, error) = match result {
+                Ok(()) => {
+                    info!(
+                        correlation_id = id.as_deref().unwrap_or_default(),
+                        "In-flight request cancelled by admin"
+                    );
+                    (http::StatusCode::OK, None)
+                }
+                Err((status, error)) => (status, Some(error)),
+            };
+            builtin_handlers::InFlightActionResult {
+                correlation_id: id,
+                action,
+                status,
+                error,
+            }
+        });
+
+        builtin_handlers::InFlightAdminResult {
+            requests: registry.requests(),
+            action,
+        }
+    }
+
+    /// Apply a capture admin action from the query string
+    ///
+    /// `?action=start&route=<id>&sample=1%&out=<file>[&max-requests=<n>]`
+    /// (POST) starts capturing a sample of a route's requests into a file in
+    /// the capture directory; `?action=stop` ends the running capture.
+    /// Without an action only the capture status is returned.
+    fn run_capture_admin_action(&self, session: &Session) -> builtin_handlers::CaptureAdminResult {
+        let req_header = session.req_header();
+        let params = parse_admin_query(req_header);
+
+        let action = params.get("action").cloned().map(|action| {
+            let result = if req_header.method != http::Method::POST {
+                Err((
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Capture actions require POST".to_string(),
+                ))
+            } else {
+                match action.as_str() {
+                    "start" => self.start_capture(&params),
+                    "stop" => match self.capture_manager.stop() {
+                        Some(_) => Ok(()),
+                        None => Err((
+                            http::StatusCode::CONFLICT,
+                            "No capture is running".to_string(),
+                        )),
+                    },
+                    other => Err((
+                        http::StatusCode::BAD_REQUEST,
+                        format!("Unknown action '{}'. Valid actions are: start, stop", other),
+                    )),
+                }
+            };
+            let (status, error) = match result {
+                Ok(()) => (http::StatusCode::OK, None),
+                Err((status, error)) => (status, Some(error)),
+            };
+            builtin_handlers::CaptureActionResult {
+                action,
+                status,
+                error,
+            }
+        });
+
+        builtin_handlers::CaptureAdminResult {
+            state: self.capture_manager.state(),
+            action,
+        }
+    }
+
+    /// Look up the agent decisions of a request
+    ///
+    /// `?correlation_id=<id>` returns every agent response recorded for the
+    /// request, oldest first.
+    fn run_audit_query(&self, session: &Session) -> builtin_handlers::AuditAdminResult {
+        let correlation_id = parse_admin_query(session.req_header()).remove("correlation_id");
+
+        let mut result = builtin_handlers::AuditAdminResult {
+            enabled: self.audit_store.is_some(),
+            correlation_id: correlation_id.clone(),
+            ..Default::default()
+        };
+        let Some(store) = &self.audit_store else {
+            result.error = Some((
+                http::StatusCode::NOT_FOUND,
+                "Audit store is not configured".to_string(),
+            ));
+            return result;
+        };
+        let Some(correlation_id) = correlation_id.filter(|id| !id.is_empty()) else {
+            result.error = Some((
+                http::StatusCode::BAD_REQUEST,
+                "Missing 'correlation_id' parameter".to_string(),
+            ));
+            return result;
+        };
+
+        match store.query(&correlation_id) {
+            Ok(records) => result.records = records,
+            Err(e) => {
+                warn!(correlation_id = %correlation_id, error = %e, "Audit store query failed");
+                result.error = Some((http::StatusCode::INTERNAL_SERVER_ERROR, e));
+            }
+        }
+        result
+    }
+
+    /// Dry-run a synthetic request through routing, filters and agents
+    ///
+    /// `POST` with an [`ExplainRequest`] JSON body. Agents are called with
+    /// `dry_run` set and nothing is sent upstream.
+    async fn run_explain(&self, session: &mut Session) -> builtin_handlers::ExplainAdminResult {
+        let failed = |status, message: String| builtin_handlers::ExplainAdminResult {
+            trace: None,
+            error: Some((status, message)),
+        };
+        if session.req_header().method != http::Method::POST {
+            return failed(
+                http::StatusCode::METHOD_NOT_ALLOWED,
+                "Explain requires POST with a request description".to_string(),
+            );
+        }
+
+        let mut body = Vec::new();
+        loop {
+            match session.read_request_body().await {
+                Ok(Some(chunk)) if body.len() + chunk.len() > MAX_EXPLAIN_BODY => {
+                    return failed(
+                        http::StatusCode::PAYLOAD_TOO_LARGE,
+                        format!("Request description exceeds {} bytes", MAX_EXPLAIN_BODY),
+                    );
+                }
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(e) => {
+                    return failed(
+                        http::StatusCode::BAD_REQUEST,
+                        format!("Failed to read body: {}", e),
+                    );
+                }
+            }
+        }
+
+        let parsed = ExplainRequest::parse(&body)
+            .and_then(|request| Ok((request.to_request_header()?, request)));
+        match parsed {
+            Ok((req_header, request)) => builtin_handlers::ExplainAdminResult {
+                trace: Some(self.explain_request(&request, req_header).await),
+                error: None,
+            },
+            Err(e) => failed(http::StatusCode::BAD_REQUEST, e),
+        }
+    }
+
+    /// Trace a synthetic request through routing, filter conditions and the
+    /// route's agents, in the order the proxy runs them
+    async fn explain_request(
+        &self,
+        request: &ExplainRequest,
+        mut req_header: RequestHeader,
+    ) -> ExplainTrace {
+        let started = Instant::now();
+        let config = self.config_manager.current();
+        let original_headers = header_snapshot(&req_header.headers);
+        let mut trace = ExplainTrace::default();
+
+        // Listener and SNI route sets are not considered
+        let host = request.host().to_string();
+        let route_match = {
+            let request_info = RequestInfo::new(&request.method, req_header.uri.path(), &host)
+                .with_headers(RequestInfo::build_headers(req_header.headers.iter()))
+                .with_query_params(RequestInfo::parse_query_params(&request.uri))
+                .with_client_ip(request.client_ip.parse().ok());
+            self.route_matcher.read().match_request(&request_info)
+        };
+        trace.timings.routing_us = started.elapsed().as_micros() as u64;
+
+        let Some(route_match) = route_match else {
+            trace.upstream_headers = original_headers;
+            trace.timings.total_us = started.elapsed().as_micros() as u64;
+            return trace;
+        };
+        let route_config = Arc::clone(&route_match.config);
+        let route_id = route_match.route_id.to_string();
+        trace.route = Some(ExplainRoute {
+            id: route_id.clone(),
+            service_type: route_config.service_type.clone(),
+            upstream: route_config.upstream.clone(),
+            builtin_handler: route_config.builtin_handler,
+        });
+
+        let mut ctx = RequestContext::new();
+        ctx.trace_id = format!("explain-{}", uuid::Uuid::new_v4());
+        ctx.method = request.method.clone();
+        ctx.path = req_header.uri.path().to_string();
+        ctx.host = Some(host.clone());
+        ctx.client_ip = request.client_ip.clone();
+        ctx.route_id = Some(route_id.clone());
+        ctx.route_config = Some(Arc::clone(&route_config));
+        // No geo lookup: `client.country` conditions see no country
+        for stage in [ConditionStage::Request, ConditionStage::Geo] {
+            filters::evaluate_filter_conditions(&req_header, &mut ctx, &config, stage);
+        }
+
+        let (agent_filters, decision_merge) =
+            route_agent_filters(&route_config, &config, &ctx.disabled_filters);
+        if !agent_filters.is_empty() {
+            let responses = Arc::new(parking_lot::Mutex::new(Vec::new()));
+            let agent_ctx = crate::agents::AgentCallContext {
+                correlation_id: CorrelationId::from_string(&ctx.trace_id),
+                metadata: zentinel_agent_protocol::RequestMetadata {
+                    correlation_id: ctx.trace_id.clone(),
+                    request_id: ctx.trace_id.clone(),
+                    client_ip: request.client_ip.clone(),
+                    client_port: 0,
+                    server_name: Some(host),
+                    protocol: "HTTP/1.1".to_string(),
+                    tls_version: None,
+                    tls_cipher: None,
+                    route_id: Some(route_id.clone()),
+                    upstream_id: route_config.upstream.clone(),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    traceparent: None,
+                    client_cert: None,
+                    tags: Vec::new(),
+                    bot_signals: None,
+                    dry_run: true,
+                },
+                route_id: Some(route_id),
+                upstream_id: route_config.upstream.clone(),
+                request_body: None,
+                response_body: None,
+                agent_budget: ctx.agent_budget(),
+                decision_merge: Arc::new(decision_merge),
+                trace: Some(Arc::clone(&responses)),
+            };
+
+            let agents_started = Instant::now();
+            let result = self
+                .agent_manager
+                .process_request_headers(&agent_ctx, agent_headers_map(&req_header), &agent_filters)
+                .await;
+            trace.timings.agents_us = agents_started.elapsed().as_micros() as u64;
+            self.agent_manager.end_request(&ctx.trace_id).await;
+            trace.agents = responses.lock().drain(..).map(ExplainAgent::from).collect();
+
+            match result {
+                Ok(decision) => {
+                    trace.decision = ExplainDecision::from_agents(&decision);
+                    ctx.tags.apply(&decision.tags);
+                    ctx.routing_metadata
+                        .extend(decision.routing_metadata.clone());
+                    if decision.is_allow() {
+                        apply_header_ops_to_request(&mut req_header, &decision.request_headers);
+                    }
+                }
+                Err(e) => trace.decision = ExplainDecision::error(e.to_string()),
+            }
+        }
+        filters::evaluate_filter_conditions(&req_header, &mut ctx, &config, ConditionStage::Agents);
+
+        trace.filters = route_config
+            .filters
+            .iter()
+            .map(|id| ExplainFilter::new(id, config.filters.get(id), ctx.filter_enabled(id)))
+            .collect();
+
+        // Headers filters run on the upstream request, after the agents
+        filters::apply_request_headers_filters(&mut req_header, &ctx, &config);
+        trace.upstream_headers = header_snapshot(&req_header.headers);
+        trace.header_changes = header_changes(&original_headers, &trace.upstream_headers);
+        trace.timings.total_us = started.elapsed().as_micros() as u64;
+        trace
+    }
+
+    fn start_capture(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<(), (http::StatusCode, String)> {
+        let bad_request = |message: String| (http::StatusCode::BAD_REQUEST, message);
+
+        let route = params
+            .get("route")
+            .ok_or_else(|| bad_request("Missing 'route' parameter".to_string()))?;
+        let config = self.config_manager.current();
+        let known = config.routes.iter().any(|r| &r.id == route)
+            || config
+                .namespaces
+                .iter()
+                .any(|ns| ns.routes.iter().any(|r| &r.id == route));
+        if !known {
+            return Err((
+                http::StatusCode::NOT_FOUND,
+                format!("Unknown route '{}'", route),
+            ));
+        }
+
+        let sample = match params.get("sample") {
+            Some(sample) => crate::capture::parse_sample(sample).ok_or_else(|| {
+                bad_request(format!(
+                    "Invalid sample '{}', expected a percentage like 1%",
+                    sample
+                ))
+            })?,
+            None => 100.0,
+        };
+        let out = params
+            .get("out")
+            .ok_or_else(|| bad_request("Missing 'out' parameter".to_string()))?;
+        let max_requests = match params.get("max-requests") {
+            Some(max) => Some(
+                max.parse::<u64>()
+                    .ok()
+                    .filter(|max| *max > 0)
+                    .ok_or_else(|| bad_request(format!("Invalid max-requests '{}'", max)))?,
+            ),
+            None => None,
+        };
+
+        self.capture_manager
+            .start(route, sample, out, max_requests)
+            .map(|_| ())
+            .map_err(capture_error)
+    }
+
+    /// Apply an API key admin action from the query string
+    ///
+    /// `?key=<id>&action=revoke|restore` (POST) revokes a key or lifts its
+    /// runtime revocation. Without an action only the key status is returned.
+    fn run_api_key_admin_action(&self, session: &Session) -> builtin_handlers::ApiKeyAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let key = params.remove("key");
+        let action = params.remove("action");
+
+        let action = action.map(|action| {
+            let key = key.unwrap_or_default();
+            let (status, error) = if req_header.method != http::Method::POST {
+                (
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    Some("API key actions require POST".to_string()),
+                )
+            } else if key.is_empty() {
+                (
+                    http::StatusCode::BAD_REQUEST,
+                    Some("Missing 'key' parameter".to_string()),
+                )
+            } else {
+                match action.as_str() {
+                    "revoke" if self.api_key_manager.revoke(&key) => (http::StatusCode::OK, None),
+                    "revoke" => (
+                        http::StatusCode::NOT_FOUND,
+                        Some(format!("Unknown API key '{}'", key)),
+                    ),
+                    "restore" if self.api_key_manager.restore(&key) => (http::StatusCode::OK, None),
+                    "restore" => (
+                        http::StatusCode::NOT_FOUND,
+                        Some(format!("API key '{}' is not revoked at runtime", key)),
+                    ),
+                    other => (
+                        http::StatusCode::BAD_REQUEST,
+                        Some(format!(
+                            "Unknown action '{}'. Valid actions are: revoke, restore",
+                            other
+                        )),
+                    ),
+                }
+            };
+            builtin_handlers::ApiKeyActionResult {
+                key,
+                action,
+                status,
+                error,
+            }
+        });
+
+        builtin_handlers::ApiKeyAdminResult {
+            keys: self.api_key_manager.status(),
+            action,
+        }
+    }
+
+    /// Apply a quota admin action from the query string
+    ///
+    /// `?filter=<id>&consumer=<id>` returns the consumer's usage in the
+    /// current windows; `&action=reset` (POST) resets them first. Without
+    /// a consumer only the quota filter IDs are returned.
+    async fn run_quota_admin_action(
+        &self,
+        session: &Session,
+    ) -> builtin_handlers::QuotaAdminResult {
+        let req_header = session.req_header();
+        let mut params = parse_admin_query(req_header);
+        let filter = params.remove("filter");
+        let consumer = params.remove("consumer");
+        let action = params.remove("action");
+
+        let mut result = builtin_handlers::QuotaAdminResult {
+            filters: self.quota_manager.filter_ids(),
+            action: action.clone(),
+            ..Default::default()
+        };
+        let (filter, consumer) = match (filter, consumer) {
+            (Some(filter), Some(consumer)) => (filter, consumer),
+            (None, None) if action.is_none() => return result,
+            _ => {
+                result.error = Some((
+                    http::StatusCode::BAD_REQUEST,
+                    "Both 'filter' and 'consumer' parameters are required".to_string(),
+                ));
+                return result;
+            }
+        };
+        if self.quota_manager.get(&filter).is_none() {
+            result.error = Some((
+                http::StatusCode::NOT_FOUND,
+                format!("Unknown quota filter '{}'", filter),
+            ));
+            return result;
+        }
+
+        // Remaining errors come from the counter store
+        if let Some(action) = action {
+            let error = if req_header.method != http::Method::POST {
+                Some((
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Quota actions require POST".to_string(),
+                ))
+            } else if action != "reset" {
+                Some((
+                    http::StatusCode::BAD_REQUEST,
+                    format!("Unknown action '{}'. Valid actions are: reset", action),
+                ))
+            } else {
+                self.quota_manager
+                    .reset(&filter, &consumer)
+                    .await
+                    .err()
+                    .map(|e| (http::StatusCode::SERVICE_UNAVAILABLE, e))
+            };
+            if error.is_some() {
+                result.error = error;
+                return result;
+            }
+        }
+
+        match self.quota_manager.usage(&filter, &consumer).await {
+            Ok(usage) => {
+                result.usage = usage;
+                result.consumer = Some((filter, consumer));
+            }
+            Err(e) => result.error = Some((http::StatusCode::SERVICE_UNAVAILABLE, e)),
+        }
+        result
+    }
+
+    /// Evaluate readiness and subsystem health for the probe endpoints
+    async fn build_health_report(
+        &self,
+        upstreams: Option<&builtin_handlers::UpstreamHealthSnapshot>,
+    ) -> crate::probes::HealthReport {
+        let config = self.config_manager.current();
+
+        // A failed reload leaves the previous configuration in place
+        let stats = self.config_manager.stats();
+        let last_success = *stats.last_success.read().await;
+        let last_failure = *stats.last_failure.read().await;
+        let config_error = match (last_failure, last_success) {
+            (Some(failure), Some(success)) if failure > success => {
+                Some("last reload failed, serving the previous configuration".to_string())
+            }
+            (Some(_), None) => {
+                Some("last reload failed, serving the initial configuration".to_string())
+            }
+            _ => None,
+        };
+
+        let listeners = self.health_probes.check_listeners(&config.listeners).await;
+        let agents = self.agent_manager.agent_health().await;
+
+        self.health_probes.evaluate(crate::probes::ProbeInputs {
+            config: &config,
+            config_error,
+            upstreams,
+            agents: &agents,
+            listeners,
+        })
+    }
+
     /// Build upstream health snapshot for the upstreams admin endpoint
     pub(super) async fn build_upstream_health_snapshot(
         &self,
@@ -191,11 +1134,19 @@ impl ZentinelProxy {
                 // Get failure rate from passive health checker
                 let failure_rate = self.passive_health.get_failure_rate(&target.address).await;
 
-                // Determine health status based on failure rate
-                let status = match failure_rate {
-                    Some(rate) if rate > 0.5 => builtin_handlers::TargetHealthStatus::Unhealthy,
-                    Some(_) => builtin_handlers::TargetHealthStatus::Healthy,
-                    None => builtin_handlers::TargetHealthStatus::Unknown,
+                // Active health checks are authoritative when configured;
+                // otherwise fall back to the passive failure rate
+                let active = self
+                    .health_check_runner
+                    .get_health(upstream_id, &target.address);
+                let status = match (active, failure_rate) {
+                    (Some(true), _) => builtin_handlers::TargetHealthStatus::Healthy,
+                    (Some(false), _) => builtin_handlers::TargetHealthStatus::Unhealthy,
+                    (None, Some(rate)) if rate > 0.5 => {
+                        builtin_handlers::TargetHealthStatus::Unhealthy
+                    }
+                    (None, Some(_)) => builtin_handlers::TargetHealthStatus::Healthy,
+                    (None, None) => builtin_handlers::TargetHealthStatus::Unknown,
                 };
 
                 let last_error = self.passive_health.get_last_error(&target.address).await;
@@ -204,6 +1155,7 @@ impl ZentinelProxy {
                     address: target.address.clone(),
                     weight: target.weight,
                     status,
+                    active_check: active,
                     failure_rate,
                     last_error,
                 });
@@ -286,27 +1238,844 @@ impl ZentinelProxy {
                     http::StatusCode::BAD_REQUEST,
                     Some("Request validation failed".to_string()),
                     &ctx.trace_id,
-                    Some(error_details),
-                ) {
-                    self.write_http_response(session, error_response).await?;
-                    self.metrics.record_blocked_request("validation_failed");
-                    return Ok(Some(true)); // Skip upstream on validation failure
+                    Some(error_details),
+                ) {
+                    self.write_http_response(session, error_response).await?;
+                    self.metrics.record_blocked_request("validation_failed");
+                    return Ok(Some(true)); // Skip upstream on validation failure
+                }
+            }
+
+            return Err(Error::explain(
+                ErrorType::HTTPStatus(400),
+                "Request validation failed",
+            ));
+        }
+
+        info!(
+            correlation_id = %ctx.trace_id,
+            route_id = route_id,
+            "Request validation passed"
+        );
+
+        Ok(None)
+    }
+
+    /// Run the route's api-key filters.
+    ///
+    /// Valid keys tag the request with the key's identity and are stripped
+    /// before the request is forwarded if the filter says so. Returns
+    /// `Ok(true)` if the request was rejected.
+    pub(super) async fn authenticate_api_key(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+        config: &zentinel_config::Config,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(Filter::ApiKey(filter)) = config.filters.get(filter_id).map(|fc| &fc.filter)
+            else {
+                continue;
+            };
+
+            let secret = presented_api_key(session.req_header(), filter);
+            let outcome = self.api_key_manager.authenticate(
+                filter_id,
+                secret.as_deref(),
+                ctx.route_id.as_deref(),
+            );
+
+            let key = match outcome {
+                ApiKeyOutcome::Valid(key) => key,
+                rejected => {
+                    let detail = match &rejected {
+                        ApiKeyOutcome::Missing => "API key required",
+                        ApiKeyOutcome::Forbidden(_) => "API key is not allowed on this route",
+                        ApiKeyOutcome::Revoked(_) => "API key has been revoked",
+                        _ => "Invalid API key",
+                    };
+                    let key_id = match &rejected {
+                        ApiKeyOutcome::Revoked(key) | ApiKeyOutcome::Forbidden(key) => {
+                            Some(key.id.as_str())
+                        }
+                        _ => None,
+                    };
+                    debug!(
+                        correlation_id = %ctx.trace_id,
+                        route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                        filter_id = %filter_id,
+                        key_id = key_id.unwrap_or("unknown"),
+                        outcome = rejected.as_str(),
+                        "Request rejected by api-key filter"
+                    );
+                    self.metrics
+                        .record_blocked_request(&format!("api_key_{}", rejected.as_str()));
+
+                    let status = rejected.status();
+                    if !self
+                        .write_problem(session, ctx, status, Some(detail), &[])
+                        .await?
+                    {
+                        let error = if status == 403 {
+                            "Forbidden"
+                        } else {
+                            "Unauthorized"
+                        };
+                        crate::http_helpers::write_json_error(session, status, error, Some(detail))
+                            .await?;
+                    }
+                    return Ok(true);
+                }
+            };
+
+            ctx.tags.apply(&key.tags());
+            if filter.strip_key {
+                strip_api_key(session, filter);
+            }
+            debug!(
+                correlation_id = %ctx.trace_id,
+                filter_id = %filter_id,
+                key_id = %key.id,
+                "API key accepted"
+            );
+        }
+
+        Ok(false)
+    }
+
+    /// Run the route's schema-validate filters on the request line and
+    /// headers.
+    ///
+    /// Body checks are left in the context for `request_body_filter`.
+    /// Returns `Ok(true)` if the request was rejected.
+    pub(super) async fn validate_request_schema(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+        config: &zentinel_config::Config,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+
+        let mut body_checks = Vec::new();
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(Filter::SchemaValidate(filter)) =
+                config.filters.get(filter_id).map(|fc| &fc.filter)
+            else {
+                continue;
+            };
+            let Some(document) = self.schema_validate_manager.get(filter_id) else {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    filter_id = %filter_id,
+                    "Schema-validate document is not loaded, skipping filter"
+                );
+                continue;
+            };
+
+            let req_header = session.req_header();
+            if filter.serve_spec.as_deref() == Some(req_header.uri.path())
+                && matches!(req_header.method, http::Method::GET | http::Method::HEAD)
+            {
+                let head = req_header.method == http::Method::HEAD;
+                let spec = document.spec();
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    filter_id = %filter_id,
+                    "Serving schema-validate document"
+                );
+                let mut resp_header = ResponseHeader::build(200, None)?;
+                resp_header.insert_header("Content-Type", "application/json")?;
+                resp_header.insert_header("Content-Length", spec.len().to_string())?;
+                resp_header.insert_header("Cache-Control", "no-cache")?;
+                session
+                    .write_response_header(Box::new(resp_header), head)
+                    .await?;
+                if !head {
+                    session.write_response_body(Some(spec), true).await?;
+                }
+                return Ok(true);
+            }
+
+            let has_body = request_has_body(req_header);
+            match document.check_request(
+                filter_id,
+                filter,
+                &req_header.method,
+                &req_header.uri,
+                &req_header.headers,
+                has_body,
+            ) {
+                Ok(Some(check)) => body_checks.push(check),
+                Ok(None) => {}
+                Err(rejection) => {
+                    self.write_schema_rejection(session, ctx, rejection).await?;
+                    return Ok(true);
+                }
+            }
+        }
+
+        if !body_checks.is_empty() {
+            ctx.request_schema_validation = Some(RequestBodyValidation::new(body_checks));
+        }
+        Ok(false)
+    }
+
+    /// Answer a request rejected by a schema-validate filter with 400 and
+    /// its violations
+    pub(super) async fn write_schema_rejection(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        rejection: SchemaRejection,
+    ) -> Result<(), Box<Error>> {
+        warn!(
+            correlation_id = %ctx.trace_id,
+            route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+            filter_id = %rejection.filter_id,
+            operation_id = rejection.operation_id.as_deref().unwrap_or("unknown"),
+            violations = rejection.violations.len(),
+            "Request failed schema validation"
+        );
+        rejection.record();
+        self.metrics
+            .record_blocked_request("schema_validation_failed");
+
+        crate::http_helpers::write_error(
+            session,
+            400,
+            &rejection.to_json(&ctx.trace_id),
+            "application/json",
+        )
+        .await
+    }
+
+    /// Run the route's managed-rules filters on the request line and
+    /// headers.
+    ///
+    /// Checks waiting for the body are left in the context for
+    /// `request_body_filter`. Returns `Ok(true)` if the request was blocked.
+    pub(super) async fn inspect_managed_rules(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+        config: &zentinel_config::Config,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+
+        let mut body_checks = Vec::new();
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(Filter::ManagedRules(filter)) =
+                config.filters.get(filter_id).map(|fc| &fc.filter)
+            else {
+                continue;
+            };
+            let Some(rule_set) = self.managed_rules_manager.get(filter_id) else {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    filter_id = %filter_id,
+                    "Managed rules are not loaded, skipping filter"
+                );
+                continue;
+            };
+
+            let req_header = session.req_header();
+            let has_body = request_has_body(req_header);
+            match rule_set.check_request(
+                filter,
+                &ctx.trace_id,
+                &req_header.method,
+                &req_header.uri,
+                &req_header.headers,
+                has_body,
+            ) {
+                Ok(Some(check)) => body_checks.push(check),
+                Ok(None) => {}
+                Err(rejection) => {
+                    self.write_rule_rejection(session, ctx, rejection).await?;
+                    return Ok(true);
+                }
+            }
+        }
+
+        if !body_checks.is_empty() {
+            ctx.request_rule_inspection = Some(RequestBodyInspection::new(body_checks));
+        }
+        Ok(false)
+    }
+
+    /// Answer a request blocked by a prompt-template filter with its
+    /// configured status and violations
+    pub(super) async fn write_prompt_rejection(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        rejection: PromptRejection,
+    ) -> Result<(), Box<Error>> {
+        warn!(
+            correlation_id = %ctx.trace_id,
+            route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+            filter_id = %rejection.filter_id,
+            violations = rejection.violations.len(),
+            "Request blocked by prompt policy"
+        );
+        self.metrics.record_blocked_request("prompt_template");
+
+        let kinds: Vec<&str> = rejection
+            .violations
+            .iter()
+            .map(|v| v.kind.as_str())
+            .collect();
+        let audit_entry = AuditLogEntry::new(
+            &ctx.trace_id,
+            AuditEventType::Blocked,
+            &ctx.method,
+            &ctx.path,
+            &ctx.client_ip,
+        )
+        .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
+        .with_status_code(rejection.status)
+        .with_reason(format!(
+            "Prompt policy violated: filter={}, violations={}",
+            rejection.filter_id,
+            kinds.join(",")
+        ))
+        .with_request_tags(ctx.tags.fields());
+        self.log_manager.log_audit(&audit_entry);
+
+        crate::http_helpers::write_error(
+            session,
+            rejection.status,
+            &rejection.to_json(&ctx.trace_id),
+            "application/json",
+        )
+        .await
+    }
+
+    /// Answer a request whose body an icap filter blocked or couldn't scan
+    pub(super) async fn write_icap_rejection(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        rejection: IcapRejection,
+    ) -> Result<(), Box<Error>> {
+        warn!(
+            correlation_id = %ctx.trace_id,
+            route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+            filter_id = %rejection.filter_id,
+            reason = rejection.reason,
+            threat = rejection.threat.as_deref().unwrap_or("none"),
+            "Request blocked by ICAP scan"
+        );
+        self.metrics.record_blocked_request("icap");
+
+        let audit_entry = AuditLogEntry::new(
+            &ctx.trace_id,
+            AuditEventType::Blocked,
+            &ctx.method,
+            &ctx.path,
+            &ctx.client_ip,
+        )
+        .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
+        .with_status_code(rejection.status)
+        .with_reason(format!(
+            "ICAP scan: filter={}, reason={}, threat={}",
+            rejection.filter_id,
+            rejection.reason,
+            rejection.threat.as_deref().unwrap_or("none")
+        ))
+        .with_request_tags(ctx.tags.fields());
+        self.log_manager.log_audit(&audit_entry);
+
+        crate::http_helpers::write_error(
+            session,
+            rejection.status,
+            &rejection.to_json(&ctx.trace_id),
+            "application/json",
+        )
+        .await
+    }
+
+    /// Answer a request blocked by a managed-rules filter with 403
+    pub(super) async fn write_rule_rejection(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        rejection: RuleRejection,
+    ) -> Result<(), Box<Error>> {
+        let rule_ids = rejection.rule_ids();
+        warn!(
+            correlation_id = %ctx.trace_id,
+            route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+            filter_id = %rejection.filter_id,
+            anomaly_score = rejection.anomaly_score,
+            rule_ids = ?rule_ids,
+            "Request blocked by managed rules"
+        );
+        self.metrics.record_blocked_request("managed_rules");
+
+        let audit_entry = AuditLogEntry::waf_blocked(
+            &ctx.trace_id,
+            &ctx.method,
+            &ctx.path,
+            &ctx.client_ip,
+            rule_ids,
+        )
+        .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
+        .with_status_code(403)
+        .with_reason(format!(
+            "Managed rules anomaly score {}: filter={}",
+            rejection.anomaly_score, rejection.filter_id
+        ))
+        .with_request_tags(ctx.tags.fields());
+        self.log_manager.log_audit(&audit_entry);
+
+        crate::http_helpers::write_error(
+            session,
+            403,
+            &rejection.to_json(&ctx.trace_id),
+            "application/json",
+        )
+        .await
+    }
+
+    /// Check the client address against the route's ip-access filters
+    ///
+    /// Returns true if the request was blocked and answered.
+    pub(super) async fn check_ip_access(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(result) = self.ip_access_manager.check(filter_id, &ctx.client_ip) else {
+                continue;
+            };
+            if result.allowed {
+                continue;
+            }
+
+            let route_id = ctx.route_id.as_deref().unwrap_or("unknown");
+            warn!(
+                correlation_id = %ctx.trace_id,
+                route_id = route_id,
+                client_ip = %ctx.client_ip,
+                filter_id = %filter_id,
+                listed = result.listed,
+                "Request blocked by ip-access filter"
+            );
+            self.metrics.record_blocked_request("ip_denied");
+
+            let audit_entry = AuditLogEntry::new(
+                &ctx.trace_id,
+                AuditEventType::Blocked,
+                &ctx.method,
+                &ctx.path,
+                &ctx.client_ip,
+            )
+            .with_route_id(route_id)
+            .with_status_code(result.status_code)
+            .with_reason(if result.listed {
+                format!("IP denied: filter={}", filter_id)
+            } else {
+                format!("IP not allowed: filter={}", filter_id)
+            });
+            self.log_manager.log_audit(&audit_entry);
+
+            let body = result
+                .block_message
+                .unwrap_or_else(|| "Access denied".to_string());
+            if !self
+                .write_problem(session, ctx, result.status_code, Some(&body), &[])
+                .await?
+            {
+                crate::http_helpers::write_error(session, result.status_code, &body, "text/plain")
+                    .await?;
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Score the client with the route's adaptive-protection filters
+    ///
+    /// Throttles or challenges clients under a penalty. Returns true if the
+    /// request was answered.
+    pub(super) async fn apply_adaptive_protection(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+        let route_id = ctx
+            .route_id
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(observation) =
+                self.adaptive_protection_manager
+                    .observe(filter_id, &ctx.client_ip, &ctx.path)
+            else {
+                continue;
+            };
+
+            if let Some(decision) = &observation.decision {
+                let reasons = decision.reason_codes();
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = %route_id,
+                    client_ip = %ctx.client_ip,
+                    filter_id = %filter_id,
+                    action = ?decision.action,
+                    score = decision.score,
+                    reasons = %reasons,
+                    requests = decision.signals.requests,
+                    error_ratio = decision.signals.error_ratio,
+                    path_entropy = decision.signals.path_entropy,
+                    penalty_secs = decision.penalty.as_secs(),
+                    "Client penalized by adaptive protection"
+                );
+                let audit_entry = AuditLogEntry::new(
+                    &ctx.trace_id,
+                    AuditEventType::Blocked,
+                    &ctx.method,
+                    &ctx.path,
+                    &ctx.client_ip,
+                )
+                .with_route_id(&route_id)
+                .with_reason(format!(
+                    "Adaptive protection: action={:?}, score={:.2}, reasons={}, filter={}",
+                    decision.action, decision.score, reasons, filter_id
+                ));
+                self.log_manager.log_audit(&audit_entry);
+            }
+
+            match observation.verdict {
+                AdaptiveVerdict::Allow => ctx.adaptive_filters.push(filter_id.clone()),
+                AdaptiveVerdict::Throttle {
+                    status_code,
+                    retry_after_secs,
+                } => {
+                    debug!(
+                        correlation_id = %ctx.trace_id,
+                        client_ip = %ctx.client_ip,
+                        filter_id = %filter_id,
+                        "Request throttled by adaptive protection"
+                    );
+                    self.metrics.record_blocked_request("adaptive_throttle");
+
+                    let body = "Too many requests";
+                    let reset_at = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs()
+                        + retry_after_secs;
+                    if !self
+                        .write_problem(
+                            session,
+                            ctx,
+                            status_code,
+                            Some(body),
+                            &crate::http_helpers::rate_limit_headers(
+                                0,
+                                0,
+                                reset_at,
+                                retry_after_secs,
+                            ),
+                        )
+                        .await?
+                    {
+                        crate::http_helpers::write_rate_limit_error(
+                            session,
+                            status_code,
+                            body,
+                            0,
+                            0,
+                            reset_at,
+                            retry_after_secs,
+                        )
+                        .await?;
+                    }
+                    return Ok(true);
+                }
+                AdaptiveVerdict::Challenge { challenge_type } => {
+                    let req_header = session.req_header();
+                    // HTTP/2 may split cookies across several headers
+                    let cookies = req_header
+                        .headers
+                        .get_all(http::header::COOKIE)
+                        .iter()
+                        .filter_map(|v| v.to_str().ok())
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    let uri = req_header
+                        .uri
+                        .path_and_query()
+                        .map(|pq| pq.as_str())
+                        .unwrap_or("/");
+                    let params = HashMap::new();
+                    let challenge_request = crate::challenge::ChallengeRequest {
+                        client_ip: &ctx.client_ip,
+                        uri,
+                        cookies: (!cookies.is_empty()).then_some(cookies.as_str()),
+                        config: &route_config.policies.challenge,
+                        params: &params,
+                    };
+
+                    let response = match self.challenge_handler.handle(
+                        &challenge_type,
+                        &challenge_request,
+                        &route_id,
+                    ) {
+                        crate::challenge::ChallengeOutcome::Passed => {
+                            ctx.adaptive_filters.push(filter_id.clone());
+                            continue;
+                        }
+                        crate::challenge::ChallengeOutcome::Issued(response) => Some(response),
+                        // Fail closed rather than forwarding unchallenged
+                        crate::challenge::ChallengeOutcome::Unsupported => None,
+                    };
+
+                    self.metrics.record_blocked_request("adaptive_challenge");
+                    match response {
+                        Some(response) => {
+                            crate::http_helpers::write_response(session, response, None).await?
+                        }
+                        None => {
+                            crate::http_helpers::write_error(
+                                session,
+                                403,
+                                "Access denied",
+                                "text/plain",
+                            )
+                            .await?
+                        }
+                    }
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Count the request against the route's quota filters.
+    ///
+    /// Remembers the consumers for byte accounting and the tightest quota
+    /// for the `X-Quota-*` response headers. Returns true if the consumer
+    /// is over quota and the request was answered.
+    pub(super) async fn enforce_quotas(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+
+        for filter_id in &route_config.filters {
+            if !ctx.filter_enabled(filter_id) {
+                continue;
+            }
+            let Some(filter) = self.quota_manager.get(filter_id) else {
+                continue;
+            };
+            let Some(consumer) = crate::quota::consumer(
+                filter.key,
+                ctx.tags.get("api_key_id"),
+                &session.req_header().headers,
+            ) else {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    filter_id = %filter_id,
+                    "No quota consumer identified, request not counted"
+                );
+                continue;
+            };
+            let Some(check) = self.quota_manager.check(filter_id, &consumer).await else {
+                continue;
+            };
+
+            if let Some(usage) = check.exhausted {
+                let route_id = ctx.route_id.as_deref().unwrap_or("unknown");
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = %route_id,
+                    filter_id = %filter_id,
+                    consumer = %consumer,
+                    metric = usage.metric.as_str(),
+                    window = usage.window.as_str(),
+                    limit = usage.limit,
+                    "Request rejected, consumer over quota"
+                );
+                self.metrics.record_blocked_request("quota_exceeded");
+                let audit_entry = AuditLogEntry::new(
+                    &ctx.trace_id,
+                    AuditEventType::RateLimitExceeded,
+                    &ctx.method,
+                    &ctx.path,
+                    &ctx.client_ip,
+                )
+                .with_route_id(route_id)
+                .with_status_code(filter.status_code)
+                .with_reason(format!(
+                    "Quota exceeded: filter={}, consumer={}, quota={}-{}, limit={}",
+                    filter_id,
+                    consumer,
+                    usage.metric.as_str(),
+                    usage.window.as_str(),
+                    usage.limit
+                ));
+                self.log_manager.log_audit(&audit_entry);
+
+                let body = "Quota exceeded";
+                let mut headers = usage.headers();
+                headers.push(("Retry-After", usage.retry_after_secs().to_string()));
+                if !self
+                    .write_problem(session, ctx, filter.status_code, Some(body), &headers)
+                    .await?
+                {
+                    crate::http_helpers::write_error_with_headers(
+                        session,
+                        filter.status_code,
+                        body,
+                        "text/plain; charset=utf-8",
+                        &headers,
+                    )
+                    .await?;
+                }
+                return Ok(true);
+            }
+
+            if filter.headers {
+                let tighter = match (&ctx.quota_usage, &check.tightest) {
+                    (Some(current), Some(new)) => new.remaining < current.remaining,
+                    (None, Some(_)) => true,
+                    _ => false,
+                };
+                if tighter {
+                    ctx.quota_usage = check.tightest;
+                }
+            }
+            ctx.quota_consumers.push((filter_id.clone(), consumer));
+        }
+
+        Ok(false)
+    }
+
+    /// Check the inference route's monthly cost budget.
+    ///
+    /// Remembers the budget consumer so the request's cost is counted
+    /// against it, and switches to the downgrade model once the budget is
+    /// spent. Returns true if the budget blocks the request and it was
+    /// answered.
+    pub(super) async fn enforce_cost_budget(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_id) = ctx.route_id.clone() else {
+            return Ok(false);
+        };
+        let Some(consumer) = self.inference_rate_limit_manager.cost_budget_consumer(
+            &route_id,
+            ctx.tenant.as_deref(),
+            ctx.tags.get("api_key_id"),
+        ) else {
+            return Ok(false);
+        };
+        let Some(decision) = self
+            .inference_rate_limit_manager
+            .check_cost_budget(&route_id, &consumer)
+        else {
+            return Ok(false);
+        };
+        ctx.inference_cost_consumer = Some(consumer.clone());
+
+        match decision {
+            CostBudgetDecision::Allow => Ok(false),
+            CostBudgetDecision::Downgrade { model } => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = %route_id,
+                    consumer = %consumer,
+                    requested_model = ?ctx.inference_model,
+                    model = %model,
+                    "Cost budget spent, downgrading model"
+                );
+                ctx.inference_model = Some(model.clone());
+                ctx.inference_model_downgrade = Some(model);
+                Ok(false)
+            }
+            CostBudgetDecision::Block { retry_after_secs } => {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = %route_id,
+                    consumer = %consumer,
+                    retry_after_secs = retry_after_secs,
+                    "Request rejected, monthly cost budget spent"
+                );
+                self.metrics.record_blocked_request("cost_budget_exceeded");
+                let audit_entry = AuditLogEntry::new(
+                    &ctx.trace_id,
+                    AuditEventType::RateLimitExceeded,
+                    &ctx.method,
+                    &ctx.path,
+                    &ctx.client_ip,
+                )
+                .with_route_id(&route_id)
+                .with_status_code(429)
+                .with_reason(format!("Cost budget exceeded: consumer={}", consumer));
+                self.log_manager.log_audit(&audit_entry);
+
+                let body = "Cost budget exceeded";
+                let headers = [("Retry-After", retry_after_secs.to_string())];
+                if !self
+                    .write_problem(session, ctx, 429, Some(body), &headers)
+                    .await?
+                {
+                    crate::http_helpers::write_error_with_headers(
+                        session,
+                        429,
+                        body,
+                        "text/plain; charset=utf-8",
+                        &headers,
+                    )
+                    .await?;
                 }
+                Ok(true)
             }
-
-            return Err(Error::explain(
-                ErrorType::HTTPStatus(400),
-                "Request validation failed",
-            ));
         }
-
-        info!(
-            correlation_id = %ctx.trace_id,
-            route_id = route_id,
-            "Request validation passed"
-        );
-
-        Ok(None)
     }
 
     /// Process request through external agents
@@ -339,23 +2108,8 @@ impl ZentinelProxy {
             .get_or_insert_with(|| self.config_manager.current());
 
         // Extract agent IDs and their failure modes from filter chain by looking up filter definitions
-        let agent_filters: Vec<(String, zentinel_config::FailureMode)> = route_config
-            .filters
-            .iter()
-            .filter_map(|filter_id| {
-                config.filters.get(filter_id).and_then(|filter_config| {
-                    if let zentinel_config::Filter::Agent(agent_filter) = &filter_config.filter {
-                        // Use filter's failure mode if specified, otherwise fall back to route's policy
-                        let failure_mode = agent_filter
-                            .failure_mode
-                            .unwrap_or(route_config.policies.failure_mode);
-                        Some((agent_filter.agent.clone(), failure_mode))
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
+        let (agent_filters, decision_merge) =
+            route_agent_filters(route_config, config, &ctx.disabled_filters);
 
         if agent_filters.is_empty() {
             return Ok(());
@@ -366,6 +2120,7 @@ impl ZentinelProxy {
 
         // Save agent IDs in context for response-phase processing
         ctx.route_agent_ids = agent_ids.clone();
+        ctx.decision_merge = Arc::new(decision_merge);
 
         debug!(
             correlation_id = %ctx.trace_id,
@@ -405,6 +2160,26 @@ impl ZentinelProxy {
                 ctx.body_inspection_enabled = true;
                 ctx.body_inspection_agents = agent_ids.clone();
 
+                // Chunks are streamed only if every inspecting agent asks
+                // for it in the same way
+                let mut modes = agent_ids.iter().map(|id| {
+                    config
+                        .agents
+                        .iter()
+                        .find(|a| &a.id == id)
+                        .map_or(BodyStreamingMode::Buffer, |a| a.request_body_mode)
+                });
+                let first = modes.next().unwrap_or_default();
+                if modes.all(|mode| mode == first) {
+                    ctx.request_body_streaming_mode = first;
+                }
+                if ctx.request_body_streaming_mode == BodyStreamingMode::Stream {
+                    ctx.request_body_framer = route_config
+                        .policies
+                        .body_framing
+                        .map(crate::body_framing::RecordFramer::new);
+                }
+
                 // Set up decompression if enabled in WAF config
                 let decompress_enabled = config
                     .waf
@@ -467,166 +2242,451 @@ impl ZentinelProxy {
 
         let req_header = session.req_header_mut();
 
-        // Build headers map for agent processing
-        // HTTP header names are already lowercase (Pingora normalizes HTTP/1.1; HTTP/2 is lowercase by spec)
-        let mut headers_map: HashMap<String, Vec<String>> =
-            HashMap::with_capacity(req_header.headers.len() + 2);
-        for (name, value) in req_header.headers.iter() {
-            headers_map
-                .entry(name.as_str().to_string())
-                .or_default()
-                .push(value.to_str().unwrap_or("").to_string());
-        }
-
-        // Add HTTP/2 pseudo-headers for agent processing
-        headers_map.insert(
-            ":method".to_string(),
-            vec![req_header.method.as_str().to_string()],
-        );
-        // Include full path with query string for WAF inspection
-        let full_path = req_header
-            .uri
-            .path_and_query()
-            .map(|pq| pq.as_str().to_string())
-            .unwrap_or_else(|| req_header.uri.path().to_string());
-        headers_map.insert(":path".to_string(), vec![full_path]);
-
         // Create agent call context
         let agent_ctx = crate::agents::AgentCallContext {
             correlation_id: CorrelationId::from_string(&ctx.trace_id),
             metadata: zentinel_agent_protocol::RequestMetadata {
                 correlation_id: ctx.trace_id.clone(),
-                request_id: Uuid::new_v4().to_string(),
+                request_id: ctx.trace_id.clone(),
                 client_ip: client_addr.to_string(),
                 client_port,
                 server_name: req_header.uri.host().map(|h| h.to_string()),
-                protocol: "HTTP/1.1".to_string(),
+                protocol: ctx.protocol.to_string(),
                 tls_version: None,
                 tls_cipher: None,
                 route_id: Some(route_id.clone()),
                 upstream_id: ctx.upstream.clone(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
                 traceparent: ctx.traceparent(),
+                client_cert: ctx.client_cert.clone(),
+                tags: ctx.tags.to_vec(),
+                bot_signals: ctx.bot_signals.clone(),
+                dry_run: false,
             },
             route_id: Some(route_id.clone()),
             upstream_id: ctx.upstream.clone(),
             request_body: None,
             response_body: None,
+            agent_budget: ctx.agent_budget(),
+            decision_merge: Arc::clone(&ctx.decision_merge),
+            trace: ctx.debug_trace.clone(),
         };
 
+        // Set when a passed challenge resumes with the agents after it
+        let mut challenger: Option<String> = None;
         // Process through agents (passing filter-specific failure modes)
-        match self
-            .agent_manager
-            .process_request_headers(&agent_ctx, headers_map, &agent_filters)
-            .await
-        {
-            Ok(decision) => {
-                // Apply agent decision
-                if !decision.is_allow() {
-                    match decision.action {
-                        AgentAction::Block { status, body, .. } => {
-                            warn!(
-                                correlation_id = %ctx.trace_id,
-                                agent_id = decision.decided_by.as_deref().unwrap_or("unknown"),
-                                status = status,
-                                "Request blocked by agent"
-                            );
-                            self.metrics.record_blocked_request("agent_blocked");
-
-                            // Audit log the block decision
-                            // Collect tags and rule_ids from all audit metadata
-                            let mut all_tags: Vec<String> = decision
-                                .audit
-                                .iter()
-                                .flat_map(|a| a.tags.iter().cloned())
-                                .collect();
-                            if let Some(ref agent_id) = decision.decided_by {
-                                all_tags.push(format!("agent:{agent_id}"));
+        loop {
+            let headers_map = agent_headers_map(req_header);
+            let result = match challenger.take() {
+                Some(agent_id) => {
+                    self.agent_manager
+                        .process_request_headers_after(
+                            &agent_ctx,
+                            headers_map,
+                            &agent_filters,
+                            &agent_id,
+                        )
+                        .await
+                }
+                None => {
+                    self.agent_manager
+                        .process_request_headers(&agent_ctx, headers_map, &agent_filters)
+                        .await
+                }
+            };
+            match result {
+                Ok(decision) => {
+                    // Record tags first so blocked requests are logged with them
+                    ctx.tags.apply(&decision.tags);
+
+                    // Apply agent decision
+                    if !decision.is_allow() {
+                        ctx.agent_block = crate::errors::AgentBlock::from_decision(&decision);
+                        match decision.action {
+                            AgentAction::Block { status, body, .. } => {
+                                warn!(
+                                    correlation_id = %ctx.trace_id,
+                                    agent_id = decision.decided_by.as_deref().unwrap_or("unknown"),
+                                    status = status,
+                                    "Request blocked by agent"
+                                );
+                                self.metrics.record_blocked_request("agent_blocked");
+
+                                // Audit log the block decision
+                                // Collect tags and rule_ids from all audit metadata
+                                let mut all_tags: Vec<String> = decision
+                                    .audit
+                                    .iter()
+                                    .flat_map(|a| a.tags.iter().cloned())
+                                    .collect();
+                                if let Some(ref agent_id) = decision.decided_by {
+                                    all_tags.push(format!("agent:{agent_id}"));
+                                }
+                                let all_rule_ids: Vec<String> = decision
+                                    .audit
+                                    .iter()
+                                    .flat_map(|a| a.rule_ids.iter().cloned())
+                                    .collect();
+
+                                let audit_entry = AuditLogEntry::new(
+                                    &ctx.trace_id,
+                                    AuditEventType::AgentDecision,
+                                    &ctx.method,
+                                    &ctx.path,
+                                    &ctx.client_ip,
+                                )
+                                .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
+                                .with_action("block")
+                                .with_status_code(status)
+                                .with_reason(
+                                    body.clone()
+                                        .unwrap_or_else(|| "Blocked by agent".to_string()),
+                                )
+                                .with_tags(all_tags)
+                                .with_rule_ids(all_rule_ids)
+                                .with_request_tags(ctx.tags.fields());
+                                self.log_manager.log_audit(&audit_entry);
+
+                                // Use HTTPStatus error type to send proper HTTP response
+                                return Err(Error::explain(
+                                    ErrorType::HTTPStatus(status),
+                                    body.unwrap_or_else(|| "Blocked by agent".to_string()),
+                                ));
                             }
-                            let all_rule_ids: Vec<String> = decision
-                                .audit
-                                .iter()
-                                .flat_map(|a| a.rule_ids.iter().cloned())
-                                .collect();
-
-                            let audit_entry = AuditLogEntry::new(
-                                &ctx.trace_id,
-                                AuditEventType::AgentDecision,
-                                &ctx.method,
-                                &ctx.path,
-                                &ctx.client_ip,
-                            )
-                            .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
-                            .with_action("block")
-                            .with_status_code(status)
-                            .with_reason(
-                                body.clone()
-                                    .unwrap_or_else(|| "Blocked by agent".to_string()),
-                            )
-                            .with_tags(all_tags)
-                            .with_rule_ids(all_rule_ids);
-                            self.log_manager.log_audit(&audit_entry);
-
-                            // Use HTTPStatus error type to send proper HTTP response
-                            return Err(Error::explain(
-                                ErrorType::HTTPStatus(status),
-                                body.unwrap_or_else(|| "Blocked by agent".to_string()),
-                            ));
-                        }
-                        AgentAction::Redirect { url, status } => {
-                            info!(
-                                correlation_id = %ctx.trace_id,
-                                url = %url,
-                                status = status,
-                                "Request redirected by agent"
-                            );
-                            return Err(Error::explain(
-                                ErrorType::InternalError,
-                                format!("Redirect to {}", url),
-                            ));
+                            AgentAction::Redirect { url, status } => {
+                                info!(
+                                    correlation_id = %ctx.trace_id,
+                                    url = %url,
+                                    status = status,
+                                    "Request redirected by agent"
+                                );
+                                return Err(Error::explain(
+                                    ErrorType::InternalError,
+                                    format!("Redirect to {}", url),
+                                ));
+                            }
+                            AgentAction::Challenge {
+                                challenge_type,
+                                params,
+                            } => {
+                                // HTTP/2 may split cookies across several headers
+                                let cookies = req_header
+                                    .headers
+                                    .get_all(http::header::COOKIE)
+                                    .iter()
+                                    .filter_map(|v| v.to_str().ok())
+                                    .collect::<Vec<_>>()
+                                    .join("; ");
+                                let uri = req_header
+                                    .uri
+                                    .path_and_query()
+                                    .map(|pq| pq.as_str())
+                                    .unwrap_or("/");
+                                let challenge_request = crate::challenge::ChallengeRequest {
+                                    client_ip: client_addr,
+                                    uri,
+                                    cookies: (!cookies.is_empty()).then_some(cookies.as_str()),
+                                    config: &route_config.policies.challenge,
+                                    params: &params,
+                                };
+
+                                match self.challenge_handler.handle(
+                                    &challenge_type,
+                                    &challenge_request,
+                                    route_id,
+                                ) {
+                                    crate::challenge::ChallengeOutcome::Passed => {
+                                        // Solved earlier. The merge stopped at the
+                                        // challenger, so the agents after it still
+                                        // decide before the request is forwarded
+                                        challenger = decision.decided_by.clone();
+                                    }
+                                    crate::challenge::ChallengeOutcome::Issued(response) => {
+                                        info!(
+                                            correlation_id = %ctx.trace_id,
+                                            agent_id = decision.decided_by.as_deref().unwrap_or("unknown"),
+                                            challenge_type = %challenge_type,
+                                            "Request challenged by agent"
+                                        );
+                                        self.metrics.record_blocked_request("agent_challenge");
+                                        let status = response.status().as_u16();
+                                        ctx.challenge_response = Some(response);
+                                        return Err(Error::explain(
+                                            ErrorType::HTTPStatus(status),
+                                            "Challenged by agent",
+                                        ));
+                                    }
+                                    crate::challenge::ChallengeOutcome::Unsupported => {
+                                        // Fail closed rather than forwarding unchallenged
+                                        self.metrics.record_blocked_request("agent_challenge");
+                                        return Err(Error::explain(
+                                            ErrorType::HTTPStatus(403),
+                                            format!(
+                                                "Unsupported challenge type: {}",
+                                                challenge_type
+                                            ),
+                                        ));
+                                    }
+                                }
+                            }
+                            _ => {}
                         }
-                        _ => {}
                     }
+
+                    // Expose routing metadata to filter conditions
+                    ctx.routing_metadata.extend(decision.routing_metadata);
+
+                    // Apply header modifications
+                    apply_header_ops_to_request(req_header, &decision.request_headers);
+
+                    if challenger.is_some() {
+                        continue;
+                    }
+                    debug!(
+                        correlation_id = %ctx.trace_id,
+                        "Agent processing completed, request allowed"
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        correlation_id = %ctx.trace_id,
+                        error = %e,
+                        "Agent processing failed"
+                    );
+                    // Check failure mode from cached route config
+                    if route_config.policies.failure_mode == zentinel_config::FailureMode::Closed {
+                        return Err(Error::explain(
+                            ErrorType::InternalError,
+                            "Agent processing failed",
+                        ));
+                    }
+                    // Otherwise fail-open and continue
                 }
+            }
+            return Ok(());
+        }
+    }
 
-                // Apply header modifications
-                for op in decision.request_headers {
-                    match op {
-                        zentinel_agent_protocol::HeaderOp::Set { name, value } => {
-                            req_header.insert_header(name, &value).ok();
-                        }
-                        zentinel_agent_protocol::HeaderOp::Add { name, value } => {
-                            req_header.append_header(name, &value).ok();
-                        }
-                        zentinel_agent_protocol::HeaderOp::Remove { name } => {
-                            req_header.remove_header(&name);
+    /// Run the route's WASM filters on the request headers.
+    ///
+    /// Header operations are applied to the request before the next filter
+    /// runs. Returns `Ok(true)` if a filter answered the request itself.
+    pub(super) async fn process_wasm_request_headers(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+        config: &zentinel_config::Config,
+    ) -> Result<bool, Box<Error>> {
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(false);
+        };
+        let filters = route_wasm_filters(&route_config, ctx, config, AgentEvent::RequestHeaders);
+        if filters.is_empty() {
+            return Ok(false);
+        }
+
+        let metadata = zentinel_agent_protocol::RequestMetadata {
+            correlation_id: ctx.trace_id.clone(),
+            request_id: ctx.trace_id.clone(),
+            client_ip: ctx.client_ip.clone(),
+            client_port: 0,
+            server_name: ctx.host.clone(),
+            protocol: ctx.protocol.to_string(),
+            tls_version: None,
+            tls_cipher: None,
+            route_id: ctx.route_id.clone(),
+            upstream_id: ctx.upstream.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            traceparent: ctx.traceparent(),
+            client_cert: ctx.client_cert.clone(),
+            tags: ctx.tags.to_vec(),
+            bot_signals: ctx.bot_signals.clone(),
+            dry_run: false,
+        };
+
+        for (filter_id, wasm) in filters {
+            let req_header = session.req_header_mut();
+            let uri = req_header
+                .uri
+                .path_and_query()
+                .map(|pq| pq.as_str().to_string())
+                .unwrap_or_else(|| req_header.uri.path().to_string());
+            let headers = headers_to_map(&req_header.headers);
+
+            let response = self.wasm_filter_manager.call(filter_id, wasm, |pool| {
+                pool.on_request_headers(&metadata, req_header.method.as_str(), &uri, &headers)
+            });
+
+            match response.decision {
+                Decision::Block { status, body, .. } => {
+                    warn!(
+                        correlation_id = %ctx.trace_id,
+                        filter_id = %filter_id,
+                        status = status,
+                        "Request blocked by WASM filter"
+                    );
+                    self.metrics.record_blocked_request("wasm_blocked");
+                    self.log_wasm_block(ctx, filter_id, status, &response.audit);
+
+                    let body = body.unwrap_or_else(|| "Request blocked".to_string());
+                    let mut resp_header = ResponseHeader::build(status, None)?;
+                    resp_header.insert_header("Content-Type", "text/plain")?;
+                    resp_header.insert_header("Content-Length", body.len().to_string())?;
+                    apply_header_ops_to_response(&mut resp_header, &response.response_headers);
+
+                    session.set_keepalive(None);
+                    session
+                        .write_response_header(Box::new(resp_header), false)
+                        .await?;
+                    session
+                        .write_response_body(Some(bytes::Bytes::from(body)), true)
+                        .await?;
+                    return Ok(true);
+                }
+                Decision::Redirect { url, status } => {
+                    debug!(
+                        correlation_id = %ctx.trace_id,
+                        filter_id = %filter_id,
+                        location = %url,
+                        status = status,
+                        "Request redirected by WASM filter"
+                    );
+                    let mut resp_header = ResponseHeader::build(status, None)?;
+                    resp_header.insert_header("Location", &url)?;
+                    resp_header.insert_header("Content-Length", "0")?;
+                    session
+                        .write_response_header(Box::new(resp_header), true)
+                        .await?;
+                    return Ok(true);
+                }
+                _ => {
+                    for op in response.request_headers {
+                        match op {
+                            HeaderOp::Set { name, value } => {
+                                req_header.insert_header(name, &value).ok();
+                            }
+                            HeaderOp::Add { name, value } => {
+                                req_header.append_header(name, &value).ok();
+                            }
+                            HeaderOp::Remove { name } => {
+                                req_header.remove_header(&name);
+                            }
                         }
                     }
                 }
+            }
+        }
 
-                debug!(
+        Ok(false)
+    }
+
+    /// Run the route's WASM filters on a request body chunk.
+    ///
+    /// A block decision fails the request with the filter's status.
+    pub(super) fn process_wasm_request_body(
+        &self,
+        body: &Option<bytes::Bytes>,
+        end_of_stream: bool,
+        ctx: &mut RequestContext,
+        config: &zentinel_config::Config,
+    ) -> Result<(), Box<Error>> {
+        if body.is_none() && !end_of_stream {
+            return Ok(());
+        }
+        let Some(route_config) = ctx.route_config.clone() else {
+            return Ok(());
+        };
+        let filters = route_wasm_filters(&route_config, ctx, config, AgentEvent::RequestBody);
+        if filters.is_empty() {
+            return Ok(());
+        }
+
+        let data = body.as_deref().unwrap_or_default();
+        let chunk_index = ctx.wasm_body_chunk_index;
+        ctx.wasm_body_chunk_index += 1;
+
+        for (filter_id, wasm) in filters {
+            let response = self.wasm_filter_manager.call(filter_id, wasm, |pool| {
+                pool.on_request_body(&ctx.trace_id, data, chunk_index, end_of_stream)
+            });
+
+            if let Decision::Block { status, body, .. } = response.decision {
+                warn!(
                     correlation_id = %ctx.trace_id,
-                    "Agent processing completed, request allowed"
+                    filter_id = %filter_id,
+                    status = status,
+                    chunk_index = chunk_index,
+                    "Request body blocked by WASM filter"
                 );
+                self.metrics.record_blocked_request("wasm_blocked");
+                self.log_wasm_block(ctx, filter_id, status, &response.audit);
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(status),
+                    body.unwrap_or_else(|| "Request blocked".to_string()),
+                ));
             }
-            Err(e) => {
-                error!(
+        }
+
+        Ok(())
+    }
+
+    /// Run the route's WASM filters on the upstream response headers.
+    ///
+    /// Only header operations apply: the upstream has already answered, so
+    /// block and redirect decisions are ignored.
+    pub(super) fn process_wasm_response_headers(
+        &self,
+        upstream_response: &mut ResponseHeader,
+        ctx: &RequestContext,
+        config: &zentinel_config::Config,
+    ) {
+        let Some(route_config) = ctx.route_config.as_ref() else {
+            return;
+        };
+        let filters = route_wasm_filters(route_config, ctx, config, AgentEvent::ResponseHeaders);
+
+        for (filter_id, wasm) in filters {
+            let status = upstream_response.status.as_u16();
+            let headers = headers_to_map(&upstream_response.headers);
+            let response = self.wasm_filter_manager.call(filter_id, wasm, |pool| {
+                pool.on_response_headers(&ctx.trace_id, status, &headers)
+            });
+
+            if !matches!(response.decision, Decision::Allow) {
+                debug!(
                     correlation_id = %ctx.trace_id,
-                    error = %e,
-                    "Agent processing failed"
+                    filter_id = %filter_id,
+                    "Ignoring WASM filter decision on response headers"
                 );
-                // Check failure mode from cached route config
-                if route_config.policies.failure_mode == zentinel_config::FailureMode::Closed {
-                    return Err(Error::explain(
-                        ErrorType::InternalError,
-                        "Agent processing failed",
-                    ));
-                }
-                // Otherwise fail-open and continue
+                continue;
             }
+            apply_header_ops_to_response(upstream_response, &response.response_headers);
         }
+    }
 
-        Ok(())
+    fn log_wasm_block(
+        &self,
+        ctx: &RequestContext,
+        filter_id: &str,
+        status: u16,
+        audit: &zentinel_agent_protocol::AuditMetadata,
+    ) {
+        let mut tags = audit.tags.clone();
+        tags.push(format!("wasm-filter:{filter_id}"));
+        let audit_entry = AuditLogEntry::new(
+            &ctx.trace_id,
+            AuditEventType::Blocked,
+            &ctx.method,
+            &ctx.path,
+            &ctx.client_ip,
+        )
+        .with_route_id(ctx.route_id.as_deref().unwrap_or("unknown"))
+        .with_action("block")
+        .with_status_code(status)
+        .with_reason(format!("WASM filter blocked: filter={filter_id}"))
+        .with_tags(tags)
+        .with_rule_ids(audit.rule_ids.clone());
+        self.log_manager.log_audit(&audit_entry);
     }
 
     /// Handle error responses with custom error pages
@@ -691,6 +2751,167 @@ impl ZentinelProxy {
         Ok(())
     }
 
+    /// Write the route's custom error page for a response generated by the
+    /// proxy itself (upstream connect failures, timeouts, static 404s), or
+    /// problem details when enabled.
+    ///
+    /// Returns `false` without writing anything when the route has no page
+    /// configured for `status` and problem details are off.
+    pub(super) async fn write_route_error_page(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        status: u16,
+    ) -> Result<bool, Box<Error>> {
+        let Some(ref route_id) = ctx.route_id else {
+            return self.write_problem(session, ctx, status, None, &[]).await;
+        };
+        let Some(error_handler) = self.error_handlers.get(route_id).await else {
+            return self.write_problem(session, ctx, status, None, &[]).await;
+        };
+        if !error_handler.has_page(status) {
+            return self.write_problem(session, ctx, status, None, &[]).await;
+        }
+
+        let status_code =
+            http::StatusCode::from_u16(status).unwrap_or(http::StatusCode::BAD_GATEWAY);
+        let response = match error_handler.generate_response(status_code, None, &ctx.trace_id, None)
+        {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = route_id,
+                    error = %e,
+                    "Failed to generate custom error page"
+                );
+                return Ok(false);
+            }
+        };
+
+        self.write_http_response(session, response).await?;
+        debug!(
+            correlation_id = %ctx.trace_id,
+            route_id = route_id,
+            status = status,
+            "Wrote custom error page"
+        );
+        Ok(true)
+    }
+
+    /// Write an RFC 9457 problem details response for an error generated by
+    /// the proxy, with `headers` added (e.g. `Retry-After`).
+    ///
+    /// Returns `false` without writing anything when problem details are not
+    /// enabled for the request's route, so the caller writes its own body.
+    pub(super) async fn write_problem(
+        &self,
+        session: &mut Session,
+        ctx: &RequestContext,
+        status: u16,
+        detail: Option<&str>,
+        headers: &[(&str, String)],
+    ) -> Result<bool, Box<Error>> {
+        let error_handler = match ctx.route_id {
+            Some(ref route_id) => self.error_handlers.get(route_id).await,
+            None => None,
+        }
+        .unwrap_or_else(|| self.default_error_handler.clone());
+
+        let status_code =
+            http::StatusCode::from_u16(status).unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        let accept = session
+            .req_header()
+            .headers
+            .get(http::header::ACCEPT)
+            .and_then(|v| v.to_str().ok());
+        let Some(mut response) =
+            error_handler.problem_response(status_code, detail, &ctx.trace_id, accept)
+        else {
+            return Ok(false);
+        };
+
+        let response_headers = response.headers_mut();
+        for (name, value) in headers {
+            if let (Ok(name), Ok(value)) = (
+                http::HeaderName::from_bytes(name.as_bytes()),
+                http::HeaderValue::from_str(value),
+            ) {
+                response_headers.insert(name, value);
+            }
+        }
+        if self.request_id.echo {
+            if let (Ok(name), Ok(value)) = (
+                http::HeaderName::from_bytes(self.request_id.response_header.as_bytes()),
+                http::HeaderValue::from_str(&ctx.trace_id),
+            ) {
+                response_headers.insert(name, value);
+            }
+        }
+
+        self.write_http_response(session, response).await?;
+        debug!(
+            correlation_id = %ctx.trace_id,
+            route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+            status = status,
+            "Wrote problem details response"
+        );
+        Ok(true)
+    }
+
+    /// Write the route's templated block page for a pending agent block.
+    ///
+    /// Returns `false` without writing anything when the request has no
+    /// pending agent block or the route has no block page configured.
+    pub(super) async fn write_block_page(
+        &self,
+        session: &mut Session,
+        ctx: &mut RequestContext,
+    ) -> Result<bool, Box<Error>> {
+        // Kept in the context for the request tap
+        let Some(block) = ctx.agent_block.as_ref() else {
+            return Ok(false);
+        };
+        let Some(ref route_id) = ctx.route_id else {
+            return Ok(false);
+        };
+        let Some(renderer) = self.block_pages.get(route_id).await else {
+            return Ok(false);
+        };
+
+        let accept = session
+            .req_header()
+            .headers
+            .get(http::header::ACCEPT)
+            .and_then(|v| v.to_str().ok());
+        let mut response = renderer.render(block, &ctx.trace_id, accept);
+        // The renderer always sets X-Correlation-Id; apply the echo policy
+        let headers = response.headers_mut();
+        for (name, value) in ctx.debug_headers() {
+            if let Ok(value) = http::HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+        if let Some(id) = headers.remove("x-correlation-id") {
+            if self.request_id.echo {
+                if let Ok(name) =
+                    http::HeaderName::from_bytes(self.request_id.response_header.as_bytes())
+                {
+                    headers.insert(name, id);
+                }
+            }
+        }
+
+        debug!(
+            correlation_id = %ctx.trace_id,
+            route_id = route_id,
+            status = block.status,
+            "Sending templated block page"
+        );
+        crate::http_helpers::write_response(session, response, None).await?;
+        Ok(true)
+    }
+
     /// Write HTTP response to session
     pub(super) async fn write_http_response(
         &self,
@@ -727,3 +2948,270 @@ impl ZentinelProxy {
         Ok(())
     }
 }
+
+/// The route's enabled WASM filters that receive `event`, in route order
+/// Admin response status for a failed chaos action
+fn chaos_error(e: ChaosError) -> (http::StatusCode, String) {
+    let status = match e {
+        ChaosError::Disabled => http::StatusCode::CONFLICT,
+        ChaosError::UnknownFault(_) => http::StatusCode::NOT_FOUND,
+    };
+    (status, e.to_string())
+}
+
+fn inflight_error(e: InFlightError) -> (http::StatusCode, String) {
+    let status = match e {
+        InFlightError::UnknownRequest(_) => http::StatusCode::NOT_FOUND,
+    };
+    (status, e.to_string())
+}
+
+fn capture_error(e: CaptureError) -> (http::StatusCode, String) {
+    let status = match e {
+        CaptureError::Disabled | CaptureError::AlreadyRunning(_) => http::StatusCode::CONFLICT,
+        CaptureError::InvalidSample(_) | CaptureError::InvalidFileName(_) => {
+            http::StatusCode::BAD_REQUEST
+        }
+        CaptureError::Io { .. } => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+fn route_wasm_filters<'a>(
+    route_config: &'a zentinel_config::RouteConfig,
+    ctx: &RequestContext,
+    config: &'a zentinel_config::Config,
+    event: AgentEvent,
+) -> Vec<(&'a str, &'a zentinel_config::WasmFilter)> {
+    route_config
+        .filters
+        .iter()
+        .filter(|filter_id| ctx.filter_enabled(filter_id))
+        .filter_map(|filter_id| match config.filters.get(filter_id) {
+            Some(fc) => match &fc.filter {
+                Filter::Wasm(wasm) if wasm.handles(event) => Some((filter_id.as_str(), wasm)),
+                _ => None,
+            },
+            None => None,
+        })
+        .collect()
+}
+
+/// Agents of the route's enabled agent filters with their failure modes, and
+/// how their decisions are merged
+fn route_agent_filters(
+    route_config: &zentinel_config::RouteConfig,
+    config: &zentinel_config::Config,
+    disabled_filters: &[String],
+) -> (
+    Vec<(String, zentinel_config::FailureMode)>,
+    crate::agents::DecisionMergePolicy,
+) {
+    let mut priorities = HashMap::new();
+    let agent_filters = route_config
+        .filters
+        .iter()
+        .filter(|filter_id| !disabled_filters.contains(filter_id))
+        .filter_map(|filter_id| match &config.filters.get(filter_id)?.filter {
+            Filter::Agent(agent_filter) => {
+                // Use filter's failure mode if specified, otherwise fall back to route's policy
+                let failure_mode = agent_filter
+                    .failure_mode
+                    .unwrap_or(route_config.policies.failure_mode);
+                priorities.insert(agent_filter.agent.clone(), agent_filter.priority);
+                Some((agent_filter.agent.clone(), failure_mode))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let decision_merge = crate::agents::DecisionMergePolicy {
+        strategy: route_config.policies.decision_merge,
+        priorities,
+    };
+    (agent_filters, decision_merge)
+}
+
+/// Request headers for agents, with the `:method` and `:path` pseudo-headers
+fn agent_headers_map(req_header: &RequestHeader) -> HashMap<String, Vec<String>> {
+    // HTTP header names are already lowercase (Pingora normalizes HTTP/1.1; HTTP/2 is lowercase by spec)
+    let mut headers_map = headers_to_map(&req_header.headers);
+    headers_map.insert(
+        ":method".to_string(),
+        vec![req_header.method.as_str().to_string()],
+    );
+    // Include full path with query string for WAF inspection
+    let full_path = req_header
+        .uri
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| req_header.uri.path().to_string());
+    headers_map.insert(":path".to_string(), vec![full_path]);
+    headers_map
+}
+
+/// Query parameters of an admin endpoint request, percent-decoded
+///
+/// Pairs without `=` are ignored; a repeated parameter keeps its last value.
+fn parse_admin_query(req_header: &RequestHeader) -> HashMap<String, String> {
+    let decode = |s: &str| {
+        urlencoding::decode(s)
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|_| s.to_string())
+    };
+    req_header
+        .uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (decode(name), decode(value)))
+        .collect()
+}
+
+/// Key presented to an api-key filter: the header wins over the query
+/// parameter
+fn presented_api_key(
+    req_header: &pingora::http::RequestHeader,
+    filter: &zentinel_config::ApiKeyFilter,
+) -> Option<String> {
+    let from_header = filter.header.as_deref().and_then(|name| {
+        req_header
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+    });
+    from_header.or_else(|| {
+        let param = filter.query_param.as_deref()?;
+        req_header
+            .uri
+            .query()?
+            .split('&')
+            .find_map(|pair| match pair.split_once('=') {
+                Some((name, value)) if name == param => {
+                    urlencoding::decode(value).ok().map(|v| v.into_owned())
+                }
+                _ => None,
+            })
+    })
+}
+
+/// Remove the key's header and query parameter so the upstream never sees it
+fn strip_api_key(session: &mut Session, filter: &zentinel_config::ApiKeyFilter) {
+    if let Some(ref header) = filter.header {
+        session.req_header_mut().remove_header(header.as_str());
+    }
+
+    let Some(ref param) = filter.query_param else {
+        return;
+    };
+    let uri = &session.req_header().uri;
+    let Some(query) = uri.query() else {
+        return;
+    };
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| pair.split_once('=').map_or(*pair, |(name, _)| name) != param)
+        .collect();
+    if kept.len() == query.split('&').count() {
+        return;
+    }
+
+    let path = uri.path();
+    let new_uri = if kept.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, kept.join("&"))
+    };
+    if let Ok(uri) = new_uri.parse::<http::Uri>() {
+        session.req_header_mut().set_uri(uri);
+    }
+}
+
+/// Whether the request carries a body: `None` if only the end of the body
+/// will tell (HTTP/2 without Content-Length)
+fn request_has_body(req_header: &pingora::http::RequestHeader) -> Option<bool> {
+    if req_header
+        .headers
+        .contains_key(http::header::TRANSFER_ENCODING)
+    {
+        return Some(true);
+    }
+    match req_header
+        .headers
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        Some(len) => Some(len > 0),
+        None if req_header.version < http::Version::HTTP_2 => Some(false),
+        None => None,
+    }
+}
+
+/// Collect headers into the multi-value map the agent interface expects
+fn headers_to_map(headers: &http::HeaderMap) -> HashMap<String, Vec<String>> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::with_capacity(headers.len());
+    for (name, value) in headers.iter() {
+        map.entry(name.as_str().to_string())
+            .or_default()
+            .push(value.to_str().unwrap_or("").to_string());
+    }
+    map
+}
+
+fn apply_header_ops_to_request(req: &mut RequestHeader, ops: &[HeaderOp]) {
+    for op in ops {
+        match op {
+            HeaderOp::Set { name, value } => {
+                req.insert_header(name.clone(), value.as_str()).ok();
+            }
+            HeaderOp::Add { name, value } => {
+                req.append_header(name.clone(), value.as_str()).ok();
+            }
+            HeaderOp::Remove { name } => {
+                req.remove_header(name);
+            }
+        }
+    }
+}
+
+fn apply_header_ops_to_response(resp: &mut ResponseHeader, ops: &[HeaderOp]) {
+    for op in ops {
+        match op {
+            HeaderOp::Set { name, value } => {
+                resp.insert_header(name.clone(), value.as_str()).ok();
+            }
+            HeaderOp::Add { name, value } => {
+                resp.append_header(name.clone(), value.as_str()).ok();
+            }
+            HeaderOp::Remove { name } => {
+                resp.remove_header(name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_query_is_percent_decoded() {
+        let req = RequestHeader::build(
+            "POST",
+            b"/admin/requests?id=req%2F42&action=cancel&flag&route%5Fid=a%20b&action=drain",
+            None,
+        )
+        .unwrap();
+        let params = parse_admin_query(&req);
+        assert_eq!(params.get("id").map(String::as_str), Some("req/42"));
+        assert_eq!(params.get("route_id").map(String::as_str), Some("a b"));
+        assert_eq!(params.get("action").map(String::as_str), Some("drain"));
+        assert!(!params.contains_key("flag"));
+
+        let req = RequestHeader::build("GET", b"/admin/requests", None).unwrap();
+        assert!(parse_admin_query(&req).is_empty());
+    }
+}
@@ -13,24 +13,34 @@ use pingora::upstreams::peer::Peer;
 use pingora_cache::{
     CacheKey, CacheMeta, ForcedFreshness, HitHandler, NoCacheReason, RespCacheable,
 };
+use pingora_core::ErrorSource;
 use pingora_timeout::sleep;
 use std::os::unix::io::RawFd;
 use std::time::Duration;
 use tracing::{debug, error, info, trace, warn};
 
-use crate::cache::{get_cache_eviction, get_cache_lock, get_cache_storage};
+use crate::agents::AgentTimings;
+use crate::cache::{get_cache_eviction, get_cache_storage};
+use crate::chaos::Injection;
+use crate::client_ip::proxy_protocol::ProxiedConnection;
+use crate::coalesce::coalesce_lock;
+use crate::concurrency::ConcurrencyAdmission;
 use crate::disk_cache::DiskHitHandler;
 use crate::hybrid_cache::HybridHitHandler;
 use crate::inference::{
     extract_inference_content, is_sse_response, PromptInjectionResult, StreamingTokenCounter,
 };
+use crate::lifecycle::LifecycleEvent;
 use crate::logging::{AccessLogEntry, AuditEventType, AuditLogEntry};
 use crate::rate_limit::HeaderAccessor;
 use crate::routing::RequestInfo;
+use crate::slow_client::SlowClientGuard;
+use crate::tenant::TenantAdmission;
 
 use super::context::{FallbackReason, RequestContext};
 use super::fallback::FallbackEvaluator;
 use super::fallback_metrics::get_fallback_metrics;
+use super::filters::ConditionStage;
 use super::model_routing;
 use super::model_routing_metrics::get_model_routing_metrics;
 use super::ZentinelProxy;
@@ -44,11 +54,12 @@ impl HeaderAccessor for NoHeaderAccessor {
 }
 
 impl ZentinelProxy {
-    /// Route matcher for the listener a request arrived on.
+    /// Route matcher for the listener and server name a request arrived on.
     ///
-    /// Returns `Some` only when the arrival listener is bound to a namespace
-    /// route set; that matcher serves the namespace's routes in isolation.
-    /// Returns `None` for ordinary listeners, which use the global matcher.
+    /// Returns `Some` only when the arrival listener, or the SNI certificate
+    /// matching the handshake's server name, is bound to a namespace route
+    /// set; that matcher serves the namespace's routes in isolation. Returns
+    /// `None` otherwise, and the global matcher is used.
     fn listener_matcher_for(
         &self,
         session: &Session,
@@ -57,8 +68,247 @@ impl ZentinelProxy {
         if matchers.is_empty() {
             return None;
         }
-        let addr = session.downstream_session.server_addr()?.to_string();
-        matchers.get(&addr).cloned()
+        matchers
+            .get(&Self::listener_address(session)?)?
+            .matcher_for_connection(Self::proxied_connection(session).as_ref())
+    }
+
+    /// Global route matcher for a request: the previous route table for the
+    /// stable cohort of a staged rollout, the current one otherwise.
+    fn global_route_matcher(
+        &self,
+        cohort: Option<crate::rollout::Cohort>,
+    ) -> parking_lot::MappedRwLockReadGuard<'_, crate::routing::RouteMatcher> {
+        self.rollout_manager
+            .stable_matcher(cohort)
+            .unwrap_or_else(|| parking_lot::RwLockReadGuard::map(self.route_matcher.read(), |m| m))
+    }
+
+    /// Connection details recorded by the fronting acceptor or the HTTP/3
+    /// frontend for a request's internal socket peer.
+    fn proxied_connection(session: &Session) -> Option<ProxiedConnection> {
+        session
+            .client_addr()
+            .and_then(|a| a.as_unix())
+            .and_then(|peer| peer.as_pathname())
+            .and_then(crate::client_ip::proxy_protocol::lookup)
+    }
+
+    /// Configured address of the listener a request arrived on.
+    fn listener_address(session: &Session) -> Option<String> {
+        // Fronted and HTTP/3 connections arrive on an internal socket;
+        // match them by the public listener address instead
+        match Self::proxied_connection(session) {
+            Some(connection) => Some(connection.listener_address),
+            None => Some(session.downstream_session.server_addr()?.to_string()),
+        }
+    }
+
+    /// Resolve the downstream HTTP protocol for logging and agent metadata.
+    fn resolve_protocol(session: &Session, ctx: &mut RequestContext) {
+        ctx.protocol = match Self::proxied_connection(session).and_then(|c| c.protocol) {
+            Some(protocol) => protocol,
+            None => match session.req_header().version {
+                http::Version::HTTP_10 => "HTTP/1.0",
+                http::Version::HTTP_2 => "HTTP/2",
+                _ => "HTTP/1.1",
+            },
+        };
+    }
+
+    /// Resolve the listener's `client-cert-header`.
+    ///
+    /// Resolved even without a certificate, so a client-supplied value is
+    /// always stripped before proxying.
+    fn resolve_client_cert_header(&self, session: &Session, ctx: &mut RequestContext) {
+        if let Some(address) = Self::listener_address(session) {
+            let config = ctx
+                .config
+                .get_or_insert_with(|| self.config_manager.current());
+            ctx.client_cert_header = config
+                .listeners
+                .iter()
+                .find(|l| l.address == address)
+                .and_then(|l| l.tls.as_ref())
+                .and_then(|tls| tls.client_cert_header.clone());
+        }
+    }
+
+    /// Bring the request to its canonical form for the listener's
+    /// `hardening { normalization }` settings before route matching, so
+    /// routes, agents and the upstream all see the same request.
+    fn normalize_request(&self, session: &mut Session, ctx: &mut RequestContext) {
+        let Some(address) = Self::listener_address(session) else {
+            return;
+        };
+        let config = std::sync::Arc::clone(
+            ctx.config
+                .get_or_insert_with(|| self.config_manager.current()),
+        );
+        if let Some(listener) = config.listeners.iter().find(|l| l.address == address) {
+            ctx.normalization_rejection = crate::normalization::normalize(
+                &listener.id,
+                &listener.hardening,
+                session.req_header_mut(),
+            )
+            .err();
+        }
+    }
+
+    /// Collect agent audit records for the response's debug headers when the
+    /// request carries a valid debug token. The token is removed, so agents
+    /// and the upstream never see it.
+    fn resolve_debug_token(&self, session: &mut Session, ctx: &mut RequestContext) {
+        let config = std::sync::Arc::clone(
+            ctx.config
+                .get_or_insert_with(|| self.config_manager.current()),
+        );
+        let Some(debug_headers) = &config.server.debug_headers else {
+            return;
+        };
+        let header = debug_headers.token_header.as_str();
+        let Some(token) = session
+            .req_header()
+            .headers
+            .get(header)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+        else {
+            return;
+        };
+        session.req_header_mut().remove_header(header);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        match crate::debug_headers::verify_token(debug_headers, &token, now) {
+            Ok(subject) => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    subject = %subject,
+                    "Debug token accepted, adding agent decision headers"
+                );
+                ctx.debug_trace = Some(std::sync::Arc::default());
+            }
+            Err(e) => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    reason = e.as_str(),
+                    "Debug token refused"
+                );
+            }
+        }
+    }
+
+    /// Capture the client certificate from an mTLS handshake.
+    ///
+    /// Populates the agent metadata and, when the listener sets
+    /// `client-cert-header`, the header used to forward it upstream.
+    fn resolve_client_cert(&self, session: &Session, ctx: &mut RequestContext) {
+        // TLS terminates in the HTTPS acceptor or the HTTP/3 frontend, which
+        // record the peer certificate from the handshake
+        let Some(connection) =
+            Self::proxied_connection(session).filter(|c| c.tls || c.protocol.is_some())
+        else {
+            return;
+        };
+        self.resolve_client_cert_header(session, ctx);
+        if let Some(der) = connection.client_cert {
+            ctx.client_cert =
+                crate::tls::client_certificate_info(&der, connection.client_cert_verified);
+            ctx.client_cert_pem = Some(crate::tls::certificate_pem(&der));
+        }
+    }
+
+    /// Collect bot-detection signals on a `bot-signals` listener: the
+    /// connection's fingerprints plus the request's header order.
+    fn resolve_bot_signals(session: &Session, ctx: &mut RequestContext) {
+        let Some(mut signals) = Self::proxied_connection(session).and_then(|c| c.bot_signals)
+        else {
+            return;
+        };
+        signals.header_order_hash = Some(crate::bot_signals::header_order_hash(
+            &session.req_header().headers,
+        ));
+        ctx.bot_signals = Some(signals);
+    }
+
+    /// Address of the client's connection: the source recorded for fronted
+    /// connections, the socket peer otherwise.
+    fn client_socket_address(session: &Session) -> Option<std::net::SocketAddr> {
+        match Self::proxied_connection(session) {
+            Some(connection) => connection.source,
+            None => session.client_addr().and_then(|a| a.as_inet()).copied(),
+        }
+    }
+
+    /// Resolve the client IP and port for a request.
+    ///
+    /// Uses the source recorded for fronted connections (the PROXY header
+    /// or the accepted socket), then forwarding headers from trusted proxies
+    /// (see [`crate::client_ip`]).
+    fn resolve_client_ip(&self, session: &Session, ctx: &mut RequestContext) {
+        let Some(peer) = Self::client_socket_address(session) else {
+            // PROXY LOCAL/UNKNOWN connection or unknown peer
+            ctx.client_ip = "unknown".to_string();
+            return;
+        };
+
+        let (ip, port) = self
+            .client_ip_resolver
+            .read()
+            .resolve(peer, &session.req_header().headers);
+        ctx.client_ip = ip.to_string();
+        ctx.client_port = port.unwrap_or(0);
+    }
+
+    /// Hand the request's load balancer selection back to its upstream pool.
+    async fn release_upstream_selection(&self, ctx: &mut RequestContext) {
+        if let Some((upstream, selection)) = ctx.upstream_selection.take() {
+            if let Some(pool) = self.upstream_pools.get(&upstream).await {
+                pool.release_selection(&selection).await;
+            }
+        }
+    }
+
+    /// Request attributes for hash-based and sticky load balancing.
+    ///
+    /// Header names are lowercase; repeated `cookie` headers (HTTP/2) are
+    /// joined so cookie keys see every pair.
+    fn build_lb_context(
+        req_header: &pingora::http::RequestHeader,
+        ctx: &RequestContext,
+    ) -> crate::upstream::RequestContext {
+        let mut headers: std::collections::HashMap<String, String> =
+            std::collections::HashMap::with_capacity(req_header.headers.len());
+        for (name, value) in req_header.headers.iter() {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            let separator = if name == http::header::COOKIE {
+                "; "
+            } else {
+                ", "
+            };
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|existing| {
+                    existing.push_str(separator);
+                    existing.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+
+        crate::upstream::RequestContext {
+            client_ip: ctx
+                .client_ip
+                .parse::<std::net::IpAddr>()
+                .ok()
+                .map(|ip| std::net::SocketAddr::new(ip, 0)),
+            headers,
+            path: req_header.uri.path().to_string(),
+            method: req_header.method.to_string(),
+        }
     }
 }
 
@@ -108,12 +358,39 @@ impl ProxyHttp for ZentinelProxy {
         // (proxied, builtin, static, rejected). Paired with dec_requests() in logging().
         self.reload_coordinator.inc_requests();
 
+        // Internal sockets only carry connections registered by the fronting
+        // acceptor or the HTTP/3 frontend; anything else connected directly
+        if session.client_addr().is_some_and(|a| a.as_unix().is_some())
+            && Self::proxied_connection(session).is_none()
+        {
+            warn!("Rejecting unregistered connection on internal listener");
+            return Err(Error::explain(
+                ErrorType::HTTPStatus(403),
+                "Unregistered connection on internal listener",
+            ));
+        }
+
+        // Resolve the client IP once, before rate limiting, agents and logging
+        self.resolve_client_ip(session, ctx);
+        self.resolve_client_cert(session, ctx);
+        Self::resolve_bot_signals(session, ctx);
+        Self::resolve_protocol(session, ctx);
+        self.normalize_request(session, ctx);
+
         // Extract request info for routing
         let req_header = session.req_header();
         let method = req_header.method.as_str();
         let path = req_header.uri.path();
         let host = crate::http_helpers::extract_request_host(req_header);
 
+        self.lifecycle_events
+            .emit(|| LifecycleEvent::RequestStarted {
+                correlation_id: ctx.trace_id.clone(),
+                method: method.to_string(),
+                path: path.to_string(),
+                client_ip: ctx.client_ip.clone(),
+            });
+
         // Handle ACME HTTP-01 challenges before any other processing
         if let Some(ref challenge_manager) = self.acme_challenges {
             if let Some(token) = crate::acme::ChallengeManager::extract_token(path) {
@@ -154,26 +431,48 @@ impl ProxyHttp for ZentinelProxy {
         ctx.host = Some(host.to_string());
 
         // Select the matcher for the listener this request arrived on. A
-        // namespace-bound listener matches only its own route set (isolated);
-        // every other listener uses the global matcher.
+        // namespace-bound listener or SNI hostname matches only its own route
+        // set (isolated); everything else uses the global matcher.
         let listener_matcher = self.listener_matcher_for(session);
 
         // Match route to determine service type
+        let route_start = std::time::Instant::now();
         let route_match = {
             let mut request_info = RequestInfo::new(method, path, host);
+            let path_and_query = req_header
+                .uri
+                .path_and_query()
+                .map_or(path, |pq| pq.as_str());
             let matched = if let Some(ref matcher) = listener_matcher {
                 // Include headers for header-based route matching (Gateway API)
                 if matcher.needs_headers() {
                     request_info = request_info
                         .with_headers(RequestInfo::build_headers(req_header.headers.iter()));
                 }
+                if matcher.needs_query_params() {
+                    request_info = request_info
+                        .with_query_params(RequestInfo::parse_query_params(path_and_query));
+                }
+                if matcher.needs_client_ip() {
+                    request_info = request_info.with_client_ip(ctx.client_ip.parse().ok());
+                }
                 matcher.match_request(&request_info)
             } else {
-                let route_matcher = self.route_matcher.read();
+                ctx.rollout_cohort = self
+                    .rollout_manager
+                    .cohort(&req_header.headers, &ctx.client_ip);
+                let route_matcher = self.global_route_matcher(ctx.rollout_cohort);
                 if route_matcher.needs_headers() {
                     request_info = request_info
                         .with_headers(RequestInfo::build_headers(req_header.headers.iter()));
                 }
+                if route_matcher.needs_query_params() {
+                    request_info = request_info
+                        .with_query_params(RequestInfo::parse_query_params(path_and_query));
+                }
+                if route_matcher.needs_client_ip() {
+                    request_info = request_info.with_client_ip(ctx.client_ip.parse().ok());
+                }
                 route_matcher.match_request(&request_info)
             };
 
@@ -182,6 +481,7 @@ impl ProxyHttp for ZentinelProxy {
                 None => return Ok(()), // No matching route, let upstream_peer handle it
             }
         };
+        ctx.route_match_time = Some(route_start.elapsed());
 
         ctx.trace_id = self.get_trace_id(session);
         ctx.route_id = Some(route_match.route_id.to_string());
@@ -235,12 +535,10 @@ impl ProxyHttp for ZentinelProxy {
             ctx.config = Some(self.config_manager.current());
         }
 
-        // Cache client address for logging if not already set
+        // Resolve client address if early_request_filter did not
         if ctx.client_ip.is_empty() {
-            ctx.client_ip = session
-                .client_addr()
-                .map(|a| a.to_string())
-                .unwrap_or_else(|| "unknown".to_string());
+            self.resolve_client_ip(session, ctx);
+            Self::resolve_protocol(session, ctx);
         }
 
         let req_header = session.req_header();
@@ -286,6 +584,10 @@ impl ProxyHttp for ZentinelProxy {
 
                 // Build request info (zero-copy for common case)
                 let mut request_info = RequestInfo::new(&ctx.method, &ctx.path, host);
+                let path_and_query = req_header
+                    .uri
+                    .path_and_query()
+                    .map_or(ctx.path.as_str(), |pq| pq.as_str());
 
                 let route_start = std::time::Instant::now();
                 let matched = if let Some(ref matcher) = listener_matcher {
@@ -295,11 +597,14 @@ impl ProxyHttp for ZentinelProxy {
                     }
                     if matcher.needs_query_params() {
                         request_info = request_info
-                            .with_query_params(RequestInfo::parse_query_params(&ctx.path));
+                            .with_query_params(RequestInfo::parse_query_params(path_and_query));
+                    }
+                    if matcher.needs_client_ip() {
+                        request_info = request_info.with_client_ip(ctx.client_ip.parse().ok());
                     }
                     matcher.match_request(&request_info)
                 } else {
-                    let route_matcher = self.route_matcher.read();
+                    let route_matcher = self.global_route_matcher(ctx.rollout_cohort);
                     // Only build headers HashMap if any route needs header matching
                     if route_matcher.needs_headers() {
                         request_info = request_info
@@ -308,7 +613,10 @@ impl ProxyHttp for ZentinelProxy {
                     // Only parse query params if any route needs query param matching
                     if route_matcher.needs_query_params() {
                         request_info = request_info
-                            .with_query_params(RequestInfo::parse_query_params(&ctx.path));
+                            .with_query_params(RequestInfo::parse_query_params(path_and_query));
+                    }
+                    if route_matcher.needs_client_ip() {
+                        request_info = request_info.with_client_ip(ctx.client_ip.parse().ok());
                     }
                     route_matcher.match_request(&request_info)
                 };
@@ -341,6 +649,7 @@ impl ProxyHttp for ZentinelProxy {
 
             ctx.route_id = Some(match_result.route_id.to_string());
             ctx.route_config = Some(match_result.config.clone());
+            ctx.route_match_time = Some(route_duration);
 
             // Set trace_id if not already set by early_request_filter
             if ctx.trace_id.is_empty() {
@@ -387,6 +696,10 @@ impl ProxyHttp for ZentinelProxy {
             ));
         }
 
+        // Requests routed here (not in early_request_filter) are tracked now
+        ctx.track_inflight(Self::client_socket_address(session));
+        ctx.enter_phase(crate::inflight::RequestPhase::Upstream)?;
+
         // Check if this is a static file route
         if route_match.config.service_type == zentinel_config::ServiceType::Static {
             trace!(
@@ -504,13 +817,18 @@ impl ProxyHttp for ZentinelProxy {
                     route_id = %route_match.route_id,
                     "Route has no upstream configured, returning 500"
                 );
-                crate::http_helpers::write_error(
-                    session,
-                    500,
-                    "Internal Server Error",
-                    "text/plain",
-                )
-                .await?;
+                if !self
+                    .write_problem(session, ctx, 500, Some("Route has no valid upstream"), &[])
+                    .await?
+                {
+                    crate::http_helpers::write_error(
+                        session,
+                        500,
+                        "Internal Server Error",
+                        "text/plain",
+                    )
+                    .await?;
+                }
                 return Err(Error::explain(
                     ErrorType::HTTPStatus(500),
                     "Route has no valid upstream",
@@ -595,6 +913,60 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // === Inference key pool ===
+        // Pick the provider key (and its upstream) for this attempt; Pingora
+        // calls upstream_peer again after a failover, with the failed member
+        // recorded as tried
+        if !ctx.used_fallback() {
+            if let Some(pool) = self.inference_key_pools.get(route_match.route_id.as_str()) {
+                let Some(member) = pool.select(&ctx.inference_key_attempts) else {
+                    warn!(
+                        correlation_id = %ctx.trace_id,
+                        route_id = %route_match.route_id,
+                        tried = ?ctx.inference_key_attempts,
+                        "No inference key pool member available"
+                    );
+                    let headers = [("Retry-After", pool.retry_after_secs().to_string())];
+                    if !self
+                        .write_problem(
+                            session,
+                            ctx,
+                            429,
+                            Some("All provider keys are rate limited"),
+                            &headers,
+                        )
+                        .await?
+                    {
+                        crate::http_helpers::write_error_with_headers(
+                            session,
+                            429,
+                            "Too Many Requests",
+                            "text/plain",
+                            &headers,
+                        )
+                        .await?;
+                    }
+                    return Err(Error::explain(
+                        ErrorType::HTTPStatus(429),
+                        "No inference key pool member available",
+                    ));
+                };
+
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = %route_match.route_id,
+                    member = %member.id(),
+                    upstream = %member.upstream(),
+                    attempt = ctx.inference_key_attempts.len() + 1,
+                    "Selected inference key pool member"
+                );
+                ctx.upstream = Some(member.upstream().to_string());
+                ctx.inference_key_attempts.push(member.id().to_string());
+                ctx.inference_key_member = Some(member);
+            }
+        }
+
+        let req_header = session.req_header();
         debug!(
             correlation_id = %ctx.trace_id,
             route_id = %route_match.route_id,
@@ -619,6 +991,10 @@ impl ProxyHttp for ZentinelProxy {
             ));
         }
 
+        // Pingora calls upstream_peer again when it retries a failed connection;
+        // hand the previous selection back before picking a new target
+        self.release_upstream_selection(ctx).await;
+
         let upstream_name = ctx
             .upstream
             .as_ref()
@@ -654,6 +1030,75 @@ impl ProxyHttp for ZentinelProxy {
                 )
             })?;
 
+        // Wait for a slot when the upstream is at its in-flight limit;
+        // retries against the same upstream keep the slot they hold
+        match pool.queue_config() {
+            Some(queue)
+                if ctx
+                    .upstream_queue_permit
+                    .as_ref()
+                    .is_none_or(|permit| permit.upstream() != upstream_name) =>
+            {
+                ctx.upstream_queue_permit = None;
+                let class = route_match.config.policies.priority_class;
+                let client = queue.fair.as_ref().map(|fair| {
+                    crate::upstream::QueueClient::from_request(
+                        fair.client_key,
+                        &ctx.client_ip,
+                        &ctx.tags,
+                    )
+                });
+                match self
+                    .upstream_queues
+                    .admit(upstream_name, queue, class, client.as_ref())
+                    .await
+                {
+                    Ok(permit) => ctx.upstream_queue_permit = Some(permit),
+                    Err(reason) => {
+                        // Not logged at warn: shedding happens under overload
+                        debug!(
+                            correlation_id = %ctx.trace_id,
+                            upstream = %upstream_name,
+                            priority_class = class.as_str(),
+                            reason = reason.as_str(),
+                            "Request shed by upstream queue"
+                        );
+                        self.metrics.record_blocked_request("upstream_queue_shed");
+                        let headers: Vec<_> = queue
+                            .retry_after_secs
+                            .map(|secs| ("Retry-After", secs.to_string()))
+                            .into_iter()
+                            .collect();
+                        if !self
+                            .write_problem(
+                                session,
+                                ctx,
+                                503,
+                                Some("Upstream is overloaded"),
+                                &headers,
+                            )
+                            .await?
+                        {
+                            crate::http_helpers::write_error_with_headers(
+                                session,
+                                503,
+                                "Service Unavailable",
+                                "text/plain",
+                                &headers,
+                            )
+                            .await?;
+                        }
+                        return Err(Error::explain(
+                            ErrorType::HTTPStatus(503),
+                            "Request shed by upstream queue",
+                        ));
+                    }
+                }
+            }
+            Some(_) => {}
+            None => ctx.upstream_queue_permit = None,
+        }
+
         // Select peer from pool with retries
         let max_retries = route_match
             .config
@@ -672,6 +1117,11 @@ impl ProxyHttp for ZentinelProxy {
         let mut last_error = None;
         let selection_start = std::time::Instant::now();
 
+        // Hash-based and sticky balancers key on request attributes
+        let lb_context = pool
+            .uses_request_context()
+            .then(|| Self::build_lb_context(session.req_header(), ctx));
+
         for attempt in 1..=max_retries {
             ctx.upstream_attempts = attempt;
 
@@ -683,16 +1133,26 @@ impl ProxyHttp for ZentinelProxy {
                 "Attempting to select upstream peer"
             );
 
-            match pool.select_peer_with_metadata(None).await {
-                Ok((mut peer, metadata)) => {
+            match pool.select_peer_with_metadata(lb_context.as_ref()).await {
+                Ok((mut peer, selection)) => {
                     let selection_duration = selection_start.elapsed();
                     // Track active request for drain lifecycle
                     pool.increment_active();
+                    self.metrics.record_connection_acquired(upstream_name);
+                    self.metrics.update_connection_pool_utilization(
+                        upstream_name,
+                        pool.active_request_count(),
+                        pool.connection_capacity(),
+                    );
                     // Store selected peer address for feedback reporting in logging()
                     let peer_addr = peer.address().to_string();
                     ctx.selected_upstream_address = Some(peer_addr.clone());
+                    if let Some(inflight) = &ctx.inflight {
+                        inflight.set_target(ctx.route_id.as_deref(), Some(upstream_name));
+                    }
 
                     // Copy sticky session metadata to context for response_filter
+                    let metadata = &selection.metadata;
                     if metadata.contains_key("sticky_session_new") {
                         ctx.sticky_session_new_assignment = true;
                         ctx.sticky_session_set_cookie =
@@ -718,6 +1178,13 @@ impl ProxyHttp for ZentinelProxy {
                         sticky_session_new = ctx.sticky_session_new_assignment,
                         "Selected upstream peer"
                     );
+                    let dns = metadata
+                        .get(crate::upstream::DNS_RESOLVE_MICROS_KEY)
+                        .and_then(|us| us.parse().ok())
+                        .map(Duration::from_micros);
+                    ctx.timings.start_upstream(dns);
+                    ctx.upstream_selection = Some((upstream_name.clone(), selection));
+
                     // Apply per-route policy timeout (lowest priority)
                     if let Some(ref rc) = ctx.route_config {
                         if let Some(timeout_secs) = rc.policies.timeout_secs {
@@ -802,25 +1269,298 @@ impl ProxyHttp for ZentinelProxy {
             "Starting request filter phase"
         );
 
-        // Apply per-listener timeouts from config
-        if let Some(server_addr) = session.downstream_session.server_addr() {
-            let server_addr_str = server_addr.to_string();
+        // Track the request for the requests and connections admin handlers
+        ctx.track_inflight(Self::client_socket_address(session));
+        if let Some(inflight) = &ctx.inflight {
+            inflight.set_target(ctx.route_id.as_deref(), None);
+        }
+
+        // Apply per-listener timeouts and request hardening from config
+        let mut slow_client = None;
+        let mut hardening = ctx.normalization_rejection.take();
+        // Fronted listeners are matched by their public address, not the
+        // internal socket the connection arrived on
+        if let Some(address) = Self::listener_address(session) {
             let config = ctx
                 .config
                 .get_or_insert_with(|| self.config_manager.current());
             for listener in &config.listeners {
-                if listener.address == server_addr_str {
+                if listener.address == address {
                     // Apply downstream read timeout
                     session.downstream_session.set_read_timeout(Some(
                         std::time::Duration::from_secs(listener.request_timeout_secs),
                     ));
                     // Store keepalive for response phase
                     ctx.listener_keepalive_timeout_secs = Some(listener.keepalive_timeout_secs);
+                    slow_client = Some(listener.slow_client.clone());
+                    // Folded header values may be rewritten in place
+                    if hardening.is_none() {
+                        hardening = crate::hardening::inspect(
+                            &listener.id,
+                            &listener.hardening,
+                            session.req_header_mut(),
+                        )
+                        .err();
+                    }
                     break;
                 }
             }
         }
 
+        if let Some(rejection) = hardening {
+            debug!(
+                correlation_id = %ctx.trace_id,
+                check = rejection.check.as_str(),
+                status = rejection.status,
+                "Request rejected by listener hardening"
+            );
+            self.metrics.record_blocked_request("request_hardening");
+            let headers: Vec<(&str, String)> = rejection
+                .allow
+                .map(|allow| ("Allow", allow))
+                .into_iter()
+                .collect();
+            crate::http_helpers::write_error_with_headers(
+                session,
+                rejection.status,
+                rejection.check.message(),
+                "text/plain; charset=utf-8",
+                &headers,
+            )
+            .await?;
+            return Ok(true);
+        }
+
+        self.resolve_debug_token(session, ctx);
+
+        // Slow-client limits: the route's settings override the listener's
+        let route_slow_client = ctx
+            .route_config
+            .as_ref()
+            .and_then(|route| route.policies.slow_client.as_ref());
+        let slow_client = match (slow_client, route_slow_client) {
+            (Some(listener), Some(route)) => Some(listener.overridden_by(route)),
+            (None, Some(route)) => Some(route.clone()),
+            (listener, None) => listener,
+        };
+        if let Some(slow_client) = slow_client.filter(|c| c.is_enabled()) {
+            if let Some(min_send_rate) = slow_client.min_send_rate {
+                session
+                    .downstream_session
+                    .set_min_send_rate(Some(min_send_rate as usize));
+            }
+            ctx.slow_client = Some(SlowClientGuard::new(slow_client));
+        }
+
+        // Sampled requests are captured as the client sent them
+        ctx.capture = self
+            .capture_manager
+            .begin(ctx.route_id.as_deref(), session.req_header());
+
+        // Maintenance mode answers before any limits, agents or upstreams
+        if self.maintenance_manager.is_active(ctx.route_id.as_deref()) {
+            if self
+                .maintenance_manager
+                .can_bypass(&ctx.client_ip, &session.req_header().headers)
+            {
+                self.maintenance_manager
+                    .record(ctx.route_id.as_deref(), "bypassed");
+            } else {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                    "Route in maintenance, serving maintenance page"
+                );
+                self.maintenance_manager
+                    .record(ctx.route_id.as_deref(), "blocked");
+                self.metrics.record_blocked_request("maintenance");
+                crate::http_helpers::write_response(
+                    session,
+                    self.maintenance_manager.response(),
+                    None,
+                )
+                .await?;
+                return Ok(true);
+            }
+        }
+        // The bypass secret is never forwarded, whether or not it was needed
+        if let Some(name) = self.maintenance_manager.bypass_header() {
+            session.req_header_mut().remove_header(&name);
+        }
+
+        // Armed chaos faults for the route
+        match crate::chaos::manager().route_injection(ctx.route_id.as_deref()) {
+            None | Some(Injection::Timeout) => {}
+            Some(Injection::Delay(delay)) => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    delay_ms = delay.as_millis() as u64,
+                    "Injecting chaos latency"
+                );
+                sleep(delay).await;
+            }
+            Some(Injection::Error(status)) => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    status = status,
+                    "Injecting chaos error response"
+                );
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(status),
+                    "Injected chaos fault",
+                ));
+            }
+            Some(Injection::Reset) => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    "Injecting chaos connection reset"
+                );
+                // Finishing the request without a response drops the
+                // connection
+                session.set_keepalive(None);
+                return Ok(true);
+            }
+        }
+
+        // Global and per-route in-flight limits; shed before any work is done
+        let route_concurrency = ctx
+            .route_config
+            .as_ref()
+            .and_then(|route| route.policies.concurrency.as_ref());
+        match self
+            .concurrency_manager
+            .admit(ctx.route_id.as_deref(), route_concurrency)
+        {
+            ConcurrencyAdmission::Admitted(permit) => {
+                ctx.concurrency_permit = Some(permit);
+            }
+            ConcurrencyAdmission::Shed {
+                scope,
+                limit,
+                reason,
+                response,
+            } => {
+                // Not logged at warn: shedding happens under overload
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                    scope = %scope,
+                    limit = limit,
+                    reason = reason,
+                    "Request shed by concurrency limit"
+                );
+                self.metrics.record_blocked_request("load_shed");
+                crate::http_helpers::write_response(session, response, None).await?;
+                return Ok(true);
+            }
+        }
+
+        // Tenant quotas and drain state, enforced across all of a tenant's
+        // routes before the per-route limits
+        if self.tenant_manager.is_enabled() {
+            ctx.tenant = self.tenant_manager.resolve(
+                ctx.route_id.as_deref(),
+                Self::listener_address(session).as_deref(),
+            );
+            let admission = ctx
+                .tenant
+                .as_deref()
+                .and_then(|tenant| self.tenant_manager.admit(tenant));
+
+            match admission {
+                None => {}
+                Some(TenantAdmission::Admitted(permit)) => {
+                    ctx.tenant_permit = Some(permit);
+                }
+                Some(TenantAdmission::RateLimited { limit, reset_at }) => {
+                    warn!(
+                        correlation_id = %ctx.trace_id,
+                        tenant = ctx.tenant.as_deref().unwrap_or_default(),
+                        limit = limit,
+                        "Tenant rate limit exceeded"
+                    );
+                    self.metrics.record_blocked_request("tenant_rate_limited");
+                    let retry_after = reset_at.saturating_sub(
+                        std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs(),
+                    );
+                    if !self
+                        .write_problem(
+                            session,
+                            ctx,
+                            429,
+                            Some("Tenant rate limit exceeded"),
+                            &crate::http_helpers::rate_limit_headers(
+                                limit,
+                                0,
+                                reset_at,
+                                retry_after,
+                            ),
+                        )
+                        .await?
+                    {
+                        crate::http_helpers::write_rate_limit_error(
+                            session,
+                            429,
+                            "Tenant rate limit exceeded",
+                            limit,
+                            0,
+                            reset_at,
+                            retry_after,
+                        )
+                        .await?;
+                    }
+                    return Ok(true);
+                }
+                Some(TenantAdmission::ConcurrencyLimited { limit }) => {
+                    warn!(
+                        correlation_id = %ctx.trace_id,
+                        tenant = ctx.tenant.as_deref().unwrap_or_default(),
+                        limit = limit,
+                        "Tenant concurrency limit exceeded"
+                    );
+                    self.metrics
+                        .record_blocked_request("tenant_concurrency_limited");
+                    if !self
+                        .write_problem(
+                            session,
+                            ctx,
+                            429,
+                            Some("Tenant concurrency limit exceeded"),
+                            &[],
+                        )
+                        .await?
+                    {
+                        crate::http_helpers::write_text_error(
+                            session,
+                            429,
+                            "Tenant concurrency limit exceeded",
+                        )
+                        .await?;
+                    }
+                    return Ok(true);
+                }
+                Some(TenantAdmission::Draining) => {
+                    debug!(
+                        correlation_id = %ctx.trace_id,
+                        tenant = ctx.tenant.as_deref().unwrap_or_default(),
+                        "Rejecting request for draining tenant"
+                    );
+                    self.metrics.record_blocked_request("tenant_draining");
+                    if !self
+                        .write_problem(session, ctx, 503, Some("Tenant is draining"), &[])
+                        .await?
+                    {
+                        crate::http_helpers::write_text_error(session, 503, "Tenant is draining")
+                            .await?;
+                    }
+                    return Ok(true);
+                }
+            }
+        }
+
         // Check rate limiting early (before other processing)
         // Fast path: skip if no rate limiting is configured for this route
         if let Some(route_id) = ctx.route_id.as_deref() {
@@ -881,16 +1621,32 @@ impl ProxyHttp for ZentinelProxy {
                                     .unwrap_or_default()
                                     .as_secs(),
                             );
-                            crate::http_helpers::write_rate_limit_error(
-                                session,
-                                rate_result.status_code,
-                                &body,
-                                rate_result.limit,
-                                rate_result.remaining,
-                                rate_result.reset_at,
-                                retry_after,
-                            )
-                            .await?;
+                            if !self
+                                .write_problem(
+                                    session,
+                                    ctx,
+                                    rate_result.status_code,
+                                    Some(&body),
+                                    &crate::http_helpers::rate_limit_headers(
+                                        rate_result.limit,
+                                        rate_result.remaining,
+                                        rate_result.reset_at,
+                                        retry_after,
+                                    ),
+                                )
+                                .await?
+                            {
+                                crate::http_helpers::write_rate_limit_error(
+                                    session,
+                                    rate_result.status_code,
+                                    &body,
+                                    rate_result.limit,
+                                    rate_result.remaining,
+                                    rate_result.reset_at,
+                                    retry_after,
+                                )
+                                .await?;
+                            }
                             return Ok(true); // Request complete, don't continue
                         }
                         RateLimitAction::LogOnly => {
@@ -1000,16 +1756,32 @@ impl ProxyHttp for ZentinelProxy {
                                 + retry_after_secs;
 
                             // Use simplified error write for inference rate limit
-                            crate::http_helpers::write_rate_limit_error(
-                                session,
-                                429,
-                                body,
-                                0, // No request limit
-                                0, // No remaining
-                                reset_at,
-                                retry_after_secs,
-                            )
-                            .await?;
+                            if !self
+                                .write_problem(
+                                    session,
+                                    ctx,
+                                    429,
+                                    Some(body),
+                                    &crate::http_helpers::rate_limit_headers(
+                                        0,
+                                        0,
+                                        reset_at,
+                                        retry_after_secs,
+                                    ),
+                                )
+                                .await?
+                            {
+                                crate::http_helpers::write_rate_limit_error(
+                                    session,
+                                    429,
+                                    body,
+                                    0, // No request limit
+                                    0, // No remaining
+                                    reset_at,
+                                    retry_after_secs,
+                                )
+                                .await?;
+                            }
                             return Ok(true); // Request complete, don't continue
                         }
 
@@ -1068,16 +1840,32 @@ impl ProxyHttp for ZentinelProxy {
                                         .as_secs()
                                         + retry_after_secs;
 
-                                    crate::http_helpers::write_rate_limit_error(
-                                        session,
-                                        429,
-                                        body,
-                                        0,
-                                        0,
-                                        reset_at,
-                                        retry_after_secs,
-                                    )
-                                    .await?;
+                                    if !self
+                                        .write_problem(
+                                            session,
+                                            ctx,
+                                            429,
+                                            Some(body),
+                                            &crate::http_helpers::rate_limit_headers(
+                                                0,
+                                                0,
+                                                reset_at,
+                                                retry_after_secs,
+                                            ),
+                                        )
+                                        .await?
+                                    {
+                                        crate::http_helpers::write_rate_limit_error(
+                                            session,
+                                            429,
+                                            body,
+                                            0,
+                                            0,
+                                            reset_at,
+                                            retry_after_secs,
+                                        )
+                                        .await?;
+                                    }
                                     return Ok(true);
                                 }
 
@@ -1137,6 +1925,7 @@ impl ProxyHttp for ZentinelProxy {
                                     .guardrail_processor
                                     .check_prompt_injection(
                                         pi_config,
+                                        guardrails.embedding.as_ref(),
                                         &content,
                                         ctx.inference_model.as_deref(),
                                         ctx.route_id.as_deref(),
@@ -1177,13 +1966,24 @@ impl ProxyHttp for ZentinelProxy {
                                         self.log_manager.log_audit(&audit_entry);
 
                                         // Send error response
-                                        crate::http_helpers::write_json_error(
-                                            session,
-                                            status,
-                                            "prompt_injection_blocked",
-                                            Some(&message),
-                                        )
-                                        .await?;
+                                        if !self
+                                            .write_problem(
+                                                session,
+                                                ctx,
+                                                status,
+                                                Some(&message),
+                                                &[],
+                                            )
+                                            .await?
+                                        {
+                                            crate::http_helpers::write_json_error(
+                                                session,
+                                                status,
+                                                "prompt_injection_blocked",
+                                                Some(&message),
+                                            )
+                                            .await?;
+                                        }
                                         return Ok(true);
                                     }
                                     PromptInjectionResult::Detected { detections } => {
@@ -1229,10 +2029,38 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Route-level filters: clone the Arc to avoid borrow conflict
+        // between &Config and &mut ctx
+        let config_for_filters = std::sync::Arc::clone(
+            ctx.config
+                .get_or_insert_with(|| self.config_manager.current()),
+        );
+
+        // Filter `enable-if` conditions over request attributes and client IP
+        super::filters::evaluate_filter_conditions(
+            session.req_header(),
+            ctx,
+            &config_for_filters,
+            ConditionStage::Request,
+        );
+
+        // Client IP allow/deny lists
+        if self.check_ip_access(session, ctx).await? {
+            return Ok(true);
+        }
+
+        // Per-client anomaly scoring (throttles and challenges outliers)
+        if self.apply_adaptive_protection(session, ctx).await? {
+            return Ok(true);
+        }
+
         // Geo filtering
         if let Some(route_id) = ctx.route_id.as_deref() {
             if let Some(ref route_config) = ctx.route_config {
                 for filter_id in &route_config.filters {
+                    if !ctx.filter_enabled(filter_id) {
+                        continue;
+                    }
                     if let Some(result) = self.geo_filter_manager.check(filter_id, &ctx.client_ip) {
                         // Store country code for response header
                         ctx.geo_country_code = result.country_code.clone();
@@ -1271,13 +2099,18 @@ impl ProxyHttp for ZentinelProxy {
                                 .block_message
                                 .unwrap_or_else(|| "Access denied".to_string());
 
-                            crate::http_helpers::write_error(
-                                session,
-                                result.status_code,
-                                &body,
-                                "text/plain",
-                            )
-                            .await?;
+                            if !self
+                                .write_problem(session, ctx, result.status_code, Some(&body), &[])
+                                .await?
+                            {
+                                crate::http_helpers::write_error(
+                                    session,
+                                    result.status_code,
+                                    &body,
+                                    "text/plain",
+                                )
+                                .await?;
+                            }
                             return Ok(true); // Request complete, don't continue
                         }
 
@@ -1288,16 +2121,44 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
-        // Route-level filters (CORS preflight, Timeout, Log)
-        // Clone the Arc to avoid borrow conflict between &Config and &mut ctx
-        let config_for_filters = std::sync::Arc::clone(
-            ctx.config
-                .get_or_insert_with(|| self.config_manager.current()),
+        // Conditions that read the geo lookup's country
+        super::filters::evaluate_filter_conditions(
+            session.req_header(),
+            ctx,
+            &config_for_filters,
+            ConditionStage::Geo,
         );
+
+        // Route-level filters (CORS preflight, Timeout, Log)
         if super::filters::apply_request_filters(session, ctx, &config_for_filters).await? {
             return Ok(true); // Filter handled request (e.g. CORS preflight)
         }
 
+        // API key authentication (after CORS preflight, which carries no key)
+        if self
+            .authenticate_api_key(session, ctx, &config_for_filters)
+            .await?
+        {
+            return Ok(true); // Missing, invalid or revoked key
+        }
+
+        // Schema validation of parameters; body checks run in request_body_filter
+        if self
+            .validate_request_schema(session, ctx, &config_for_filters)
+            .await?
+        {
+            return Ok(true); // Request failed schema validation
+        }
+
+        // Managed rules on the request line and headers; body rules run in
+        // request_body_filter
+        if self
+            .inspect_managed_rules(session, ctx, &config_for_filters)
+            .await?
+        {
+            return Ok(true); // Anomaly threshold reached
+        }
+
         // Check for WebSocket upgrade requests
         let is_websocket_upgrade = session
             .req_header()
@@ -1335,13 +2196,24 @@ impl ProxyHttp for ZentinelProxy {
                     self.log_manager.log_audit(&audit_entry);
 
                     // Send 403 Forbidden response
-                    crate::http_helpers::write_error(
-                        session,
-                        403,
-                        "WebSocket not enabled for this route",
-                        "text/plain",
-                    )
-                    .await?;
+                    if !self
+                        .write_problem(
+                            session,
+                            ctx,
+                            403,
+                            Some("WebSocket not enabled for this route"),
+                            &[],
+                        )
+                        .await?
+                    {
+                        crate::http_helpers::write_error(
+                            session,
+                            403,
+                            "WebSocket not enabled for this route",
+                            "text/plain",
+                        )
+                        .await?;
+                    }
                     return Ok(true); // Request complete, don't continue
                 }
 
@@ -1442,18 +2314,28 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
-        // Get client address before mutable borrow
-        let client_addr = session
-            .client_addr()
-            .map(|a| format!("{}", a))
-            .unwrap_or_else(|| "unknown".to_string());
-        let client_port = session.client_addr().map(|_| 0).unwrap_or(0);
+        // Resolved client address (copied before the mutable session borrow)
+        let client_addr = ctx.client_ip.clone();
+        let client_port = ctx.client_port;
 
         let req_header = session.req_header_mut();
 
-        // Add correlation ID header
+        // Pass the request ID upstream. Untrusted incoming IDs are dropped
+        // so upstreams never see a client-chosen value.
+        if !self.request_id.trust_incoming {
+            match self.request_id.header {
+                Some(ref name) => {
+                    req_header.remove_header(name.as_str());
+                }
+                None => {
+                    for name in crate::trace_id::DEFAULT_INCOMING_HEADERS {
+                        req_header.remove_header(name);
+                    }
+                }
+            }
+        }
         req_header
-            .insert_header("X-Correlation-Id", &ctx.trace_id)
+            .insert_header(self.request_id.propagate_header.clone(), &ctx.trace_id)
             .ok();
         req_header.insert_header("X-Forwarded-By", "Zentinel").ok();
 
@@ -1510,10 +2392,20 @@ impl ProxyHttp for ZentinelProxy {
             correlation_id = %ctx.trace_id,
             "Processing request through agents"
         );
-        if let Err(e) = self
-            .process_agents(session, ctx, &client_addr, client_port)
-            .await
-        {
+        // An admin cancel interrupts the wait for agent decisions
+        let inflight = ctx.inflight.as_ref().map(|i| i.handle());
+        ctx.enter_phase(crate::inflight::RequestPhase::AwaitingAgent)?;
+        let agents_result = match &inflight {
+            Some(inflight) => tokio::select! {
+                result = self.process_agents(session, ctx, &client_addr, client_port) => result,
+                _ = inflight.cancelled() => Err(crate::inflight::cancelled_error()),
+            },
+            None => {
+                self.process_agents(session, ctx, &client_addr, client_port)
+                    .await
+            }
+        };
+        if let Err(e) = agents_result {
             // Check if this is an HTTPStatus error (e.g., agent block or fail-closed)
             // In that case, we need to send a proper HTTP response instead of just closing the connection
             if let ErrorType::HTTPStatus(status) = e.etype() {
@@ -1524,6 +2416,13 @@ impl ProxyHttp for ZentinelProxy {
                     .nth(1)
                     .map(|s| s.trim())
                     .unwrap_or("Request blocked");
+                if let Some(response) = ctx.challenge_response.take() {
+                    crate::http_helpers::write_response(session, response, None).await?;
+                    return Ok(true);
+                }
+                if self.write_block_page(session, ctx).await? {
+                    return Ok(true);
+                }
                 debug!(
                     correlation_id = %ctx.trace_id,
                     status = status,
@@ -1536,6 +2435,33 @@ impl ProxyHttp for ZentinelProxy {
             // For other errors, propagate them
             return Err(e);
         }
+        ctx.enter_phase(crate::inflight::RequestPhase::Processing)?;
+
+        // Conditions that read routing metadata set by agents
+        super::filters::evaluate_filter_conditions(
+            session.req_header(),
+            ctx,
+            &config_for_filters,
+            ConditionStage::Agents,
+        );
+
+        // Quotas run after agents so auth agents can reject bad tokens first
+        if self.enforce_quotas(session, ctx).await? {
+            return Ok(true); // Consumer is over quota
+        }
+
+        // Cost budgets need the API key and tenant, so they run with quotas
+        if self.enforce_cost_budget(session, ctx).await? {
+            return Ok(true); // Monthly cost budget spent
+        }
+
+        // Embedded WASM filters run last, so their conditions may read metadata
+        if self
+            .process_wasm_request_headers(session, ctx, &config_for_filters)
+            .await?
+        {
+            return Ok(true); // WASM filter answered the request
+        }
 
         trace!(
             correlation_id = %ctx.trace_id,
@@ -1560,6 +2486,8 @@ impl ProxyHttp for ZentinelProxy {
     ) -> Result<(), Box<Error>> {
         use zentinel_config::BodyStreamingMode;
 
+        ctx.check_cancelled()?;
+
         // Handle WebSocket frame inspection (client -> server)
         if ctx.is_websocket_upgrade {
             if let Some(ref handler) = ctx.websocket_handler {
@@ -1589,6 +2517,29 @@ impl ProxyHttp for ZentinelProxy {
 
         // Track request body size
         let chunk_len = body.as_ref().map(|b| b.len()).unwrap_or(0);
+
+        if let (Some(capture), Some(chunk)) = (ctx.capture.as_mut(), body.as_ref()) {
+            capture.push_body(chunk);
+        }
+
+        // Close uploads that are too slow or run past the request deadline
+        if let Some(ref mut guard) = ctx.slow_client {
+            if let Err(violation) = guard.on_request_body(chunk_len) {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                    client_ip = %ctx.client_ip,
+                    violation = %violation,
+                    "Closing slow client"
+                );
+                crate::slow_client::record_closed(violation.reason());
+                self.metrics.record_blocked_request("slow_client");
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(408),
+                    violation.to_string(),
+                ));
+            }
+        }
         if chunk_len > 0 {
             ctx.request_body_bytes += chunk_len as u64;
 
@@ -1620,8 +2571,81 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Schema validation holds the body back until it validates, so
+        // agents and upstream only see valid bodies
+        if let Some(mut validation) = ctx.request_schema_validation.take() {
+            if let Err(rejection) = validation.process(body, end_of_stream) {
+                ctx.schema_rejection = Some(rejection);
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(400),
+                    "Request failed schema validation",
+                ));
+            }
+            ctx.request_schema_validation = Some(validation);
+        }
+
+        // Managed rules hold back the start of the body until it is inspected
+        if let Some(mut inspection) = ctx.request_rule_inspection.take() {
+            if let Err(rejection) = inspection.process(body, end_of_stream) {
+                ctx.rule_rejection = Some(rejection);
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(403),
+                    "Request blocked by managed rules",
+                ));
+            }
+            ctx.request_rule_inspection = Some(inspection);
+        }
+
+        // Prompt-template filters hold back the body, enforce the system
+        // prompt and check user messages against the allowed templates
+        if let Some(mut enforcement) = ctx.request_prompt_enforcement.take() {
+            if let Err(rejection) = enforcement.process(body, end_of_stream) {
+                let status = rejection.status;
+                ctx.prompt_rejection = Some(rejection);
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(status),
+                    "Request violates prompt policy",
+                ));
+            }
+            ctx.request_prompt_enforcement = Some(enforcement);
+        }
+
+        // ICAP filters hold back the body until the REQMOD service finds
+        // it clean
+        if let Some(mut scan) = ctx.request_icap_scan.take() {
+            if let Err(rejection) = scan.process(body, end_of_stream).await {
+                let status = rejection.status;
+                ctx.icap_rejection = Some(rejection);
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(status),
+                    "Request body blocked by ICAP scan",
+                ));
+            }
+            ctx.request_icap_scan = Some(scan);
+        }
+
+        // Request body chunks for embedded WASM filters
+        let config = std::sync::Arc::clone(
+            ctx.config
+                .get_or_insert_with(|| self.config_manager.current()),
+        );
+        self.process_wasm_request_body(body, end_of_stream, ctx, &config)?;
+
+        // Multipart filters send agents one run of chunks per form part in
+        // place of raw body chunks, and forward the re-encoded parts
+        if let Some(mut inspection) = ctx.request_multipart_inspection.take() {
+            let result = self
+                .process_multipart_body(&mut inspection, body, end_of_stream, ctx)
+                .await;
+            ctx.request_multipart_inspection = Some(inspection);
+            result?;
+        }
+
         // Body inspection for agents (WAF, etc.)
-        if ctx.body_inspection_enabled && !ctx.body_inspection_agents.is_empty() {
+        if ctx.body_inspection_enabled
+            && !ctx.body_inspection_agents.is_empty()
+            && ctx.request_multipart_inspection.is_none()
+        {
             let config = ctx
                 .config
                 .get_or_insert_with(|| self.config_manager.current());
@@ -1633,6 +2657,12 @@ impl ProxyHttp for ZentinelProxy {
 
             match ctx.request_body_streaming_mode {
                 BodyStreamingMode::Stream => {
+                    // Hold back incomplete records of framed bodies, so
+                    // chunk events (and upstream chunks) end on a boundary
+                    if let Some(ref mut framer) = ctx.request_body_framer {
+                        *body = framer.push(body.take(), end_of_stream);
+                    }
+
                     // Stream mode: send each chunk immediately
                     if body.is_some() {
                         self.process_body_chunk_streaming(body, end_of_stream, ctx)
@@ -1720,6 +2750,13 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // JSON field manipulation (json-transform filters) on what is
+        // forwarded upstream, after agents have inspected the original body
+        if let Some(mut transform) = ctx.request_json_transform.take() {
+            transform.process(body, end_of_stream, &ctx.json_computed_values());
+            ctx.request_json_transform = Some(transform);
+        }
+
         if end_of_stream {
             trace!(
                 correlation_id = %ctx.trace_id,
@@ -1747,6 +2784,51 @@ impl ProxyHttp for ZentinelProxy {
             "Starting response filter phase"
         );
 
+        if ctx.timings.upstream_started.is_some() && ctx.timings.upstream_headers.is_none() {
+            ctx.timings.upstream_headers = Some(std::time::Instant::now());
+        }
+
+        ctx.enter_phase(crate::inflight::RequestPhase::Downstream)?;
+
+        // Cool down a key pool member that answered with a failover status
+        // and retry the request on another member (see error_while_proxy)
+        if let Some(member) = ctx.inference_key_member.clone() {
+            let pool = ctx
+                .route_id
+                .as_deref()
+                .and_then(|id| self.inference_key_pools.get(id));
+            if let Some(pool) = pool.filter(|p| p.is_failover_status(status)) {
+                let retry_after = upstream_response
+                    .headers
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                pool.cool_down(&member, status, retry_after);
+
+                if pool.can_fail_over(&ctx.inference_key_attempts) {
+                    info!(
+                        correlation_id = %ctx.trace_id,
+                        route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                        member = %member.id(),
+                        status = status,
+                        "Failing over to another inference key pool member"
+                    );
+                    ctx.inference_key_failover = true;
+                    return Err(Error::explain(
+                        ErrorType::HTTPStatus(status),
+                        "inference key pool failover",
+                    ));
+                }
+            }
+        }
+
+        // Refuse responses the route's response policies do not allow,
+        // before anything is sent to the client
+        if let Some(config) = ctx.config.as_ref().map(std::sync::Arc::clone) {
+            super::filters::apply_response_policies(upstream_response, ctx, &config)?;
+        }
+
         // Handle WebSocket 101 Switching Protocols
         if status == 101 && ctx.is_websocket_upgrade {
             if ctx.websocket_inspection_enabled && !ctx.websocket_skip_inspection {
@@ -1790,8 +2872,16 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
-        // Add correlation ID to response
-        upstream_response.insert_header("X-Correlation-Id", &ctx.trace_id)?;
+        // Return the request ID to the client
+        self.echo_request_id(upstream_response, &ctx.trace_id);
+
+        // Advertise HTTP/3 on TLS connections, unless the upstream set its own
+        if let Some(ref alt_svc) = self.alt_svc {
+            let over_tls = Self::proxied_connection(session).is_some_and(|c| c.tls);
+            if over_tls && !upstream_response.headers.contains_key("alt-svc") {
+                upstream_response.insert_header("Alt-Svc", alt_svc.as_str())?;
+            }
+        }
 
         // Add rate limit headers if rate limiting was applied
         if let Some(ref rate_info) = ctx.rate_limit_info {
@@ -1801,6 +2891,13 @@ impl ProxyHttp for ZentinelProxy {
             upstream_response.insert_header("X-RateLimit-Reset", rate_info.reset_at.to_string())?;
         }
 
+        // Announce the tightest quota of the consumer
+        if let Some(ref usage) = ctx.quota_usage {
+            for (name, value) in usage.headers() {
+                upstream_response.insert_header(name, value)?;
+            }
+        }
+
         // Add token budget headers if budget tracking was enabled
         if ctx.inference_budget_enabled {
             if let Some(remaining) = ctx.inference_budget_remaining {
@@ -1890,18 +2987,47 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
-        // Apply response-phase route filters (Headers, CORS, Compress, Log)
-        if let Some(config) = ctx.config.as_ref().map(std::sync::Arc::clone) {
-            super::filters::apply_response_filters(upstream_response, ctx, &config);
+        // Streaming passthrough: chunks are forwarded as they arrive, and
+        // proxies in front of us are asked not to buffer them either
+        if ctx.streaming_passthrough() {
+            upstream_response
+                .insert_header("X-Accel-Buffering", "no")
+                .ok();
         }
 
-        // Enable Pingora response compression if Compress filter marked it eligible
-        if ctx.compress_enabled {
-            session.upstream_compression.adjust_level(6);
+        // Apply response-phase route filters (Headers, CORS, Compress, Log, WASM)
+        if let Some(config) = ctx.config.as_ref().map(std::sync::Arc::clone) {
+            super::filters::apply_response_filters(upstream_response, ctx, &config);
+            self.process_wasm_response_headers(upstream_response, ctx, &config);
+            super::filters::setup_response_json_transform(upstream_response, ctx, &config);
+            super::filters::setup_icap_response_scan(
+                session.req_header(),
+                upstream_response,
+                ctx,
+                &config,
+            );
+            let accept_encoding = session
+                .req_header()
+                .headers
+                .get(http::header::ACCEPT_ENCODING)
+                .and_then(|v| v.to_str().ok());
+            super::filters::setup_response_compression(
+                upstream_response,
+                ctx,
+                &config,
+                accept_encoding,
+            );
         }
 
-        // Apply per-listener keepalive timeout
+        // Apply per-listener keepalive timeout. Pingora also applies it to
+        // reads of the next request's headers, so it is capped by the
+        // slow-client header timeout.
         if let Some(keepalive_secs) = ctx.listener_keepalive_timeout_secs {
+            let header_timeout = ctx
+                .slow_client
+                .as_ref()
+                .and_then(|guard| guard.config().header_timeout_secs);
+            let keepalive_secs = header_timeout.map_or(keepalive_secs, |t| t.min(keepalive_secs));
             session
                 .downstream_session
                 .set_keepalive(Some(keepalive_secs));
@@ -1964,6 +3090,29 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Report the request's phase durations to routes that ask for them
+        if let Some(server_timing) = ctx
+            .route_config
+            .as_ref()
+            .and_then(|route| route.policies.server_timing.as_ref())
+        {
+            let agents = self.agent_manager.request_timings(&ctx.trace_id);
+            let t = &ctx.timings;
+            let phases = crate::server_timing::Phases {
+                route: ctx.route_match_time,
+                agents: &agents,
+                upstream: t
+                    .upstream_started
+                    .zip(t.upstream_headers)
+                    .map(|(started, headers)| headers.saturating_duration_since(started)),
+                total: ctx.elapsed(),
+            };
+            upstream_response.append_header(
+                "Server-Timing",
+                crate::server_timing::header_value(server_timing, &phases),
+            )?;
+        }
+
         // Initialize streaming token counter for SSE responses on inference routes
         if ctx.inference_rate_limit_enabled {
             // Check if this is an SSE response
@@ -2012,22 +3161,29 @@ impl ProxyHttp for ZentinelProxy {
                 correlation_id: zentinel_common::CorrelationId::from_string(&ctx.trace_id),
                 metadata: zentinel_agent_protocol::RequestMetadata {
                     correlation_id: ctx.trace_id.clone(),
-                    request_id: uuid::Uuid::new_v4().to_string(),
+                    request_id: ctx.trace_id.clone(),
                     client_ip: ctx.client_ip.clone(),
                     client_port: 0,
                     server_name: ctx.host.clone(),
-                    protocol: "HTTP/1.1".to_string(),
+                    protocol: ctx.protocol.to_string(),
                     tls_version: None,
                     tls_cipher: None,
                     route_id: ctx.route_id.clone(),
                     upstream_id: ctx.upstream.clone(),
                     timestamp: chrono::Utc::now().to_rfc3339(),
                     traceparent: ctx.traceparent(),
+                    client_cert: ctx.client_cert.clone(),
+                    tags: ctx.tags.to_vec(),
+                    bot_signals: ctx.bot_signals.clone(),
+                    dry_run: false,
                 },
                 route_id: ctx.route_id.clone(),
                 upstream_id: ctx.upstream.clone(),
                 request_body: None,
                 response_body: None,
+                agent_budget: ctx.agent_budget(),
+                decision_merge: std::sync::Arc::clone(&ctx.decision_merge),
+                trace: ctx.debug_trace.clone(),
             };
 
             match self
@@ -2036,6 +3192,8 @@ impl ProxyHttp for ZentinelProxy {
                 .await
             {
                 Ok(decision) => {
+                    ctx.tags.apply(&decision.tags);
+
                     // Apply response header modifications from agent
                     for op in &decision.response_headers {
                         match op {
@@ -2055,14 +3213,16 @@ impl ProxyHttp for ZentinelProxy {
                         }
                     }
 
-                    // Check if any agent subscribes to response body events
-                    let has_body_agents = self
-                        .agent_manager
-                        .any_agent_handles_event(
-                            &agent_ids,
-                            zentinel_agent_protocol::EventType::ResponseBodyChunk,
-                        )
-                        .await;
+                    // Check if any agent subscribes to response body events;
+                    // streaming-passthrough routes never send them
+                    let has_body_agents = !ctx.streaming_passthrough()
+                        && self
+                            .agent_manager
+                            .any_agent_handles_event(
+                                &agent_ids,
+                                zentinel_agent_protocol::EventType::ResponseBodyChunk,
+                            )
+                            .await;
                     if has_body_agents {
                         ctx.response_agent_processing_enabled = true;
                         // Since agent may replace the body, Content-Length is invalid.
@@ -2092,6 +3252,11 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Agent decisions for callers with a debug token
+        for (name, value) in ctx.debug_headers() {
+            upstream_response.insert_header(name, value)?;
+        }
+
         // Generate custom error pages for error responses
         if status >= 400 {
             trace!(
@@ -2264,6 +3429,17 @@ impl ProxyHttp for ZentinelProxy {
             .insert_header("X-Forwarded-By", "Zentinel")
             .ok();
 
+        // Forward the mTLS client certificate; a client-supplied value is
+        // always removed so upstreams can trust the header
+        if let Some(ref header) = ctx.client_cert_header {
+            upstream_request.remove_header(header);
+            if let Some(ref pem) = ctx.client_cert_pem {
+                upstream_request
+                    .insert_header(header.clone(), urlencoding::encode(pem).as_ref())
+                    .ok();
+            }
+        }
+
         // Apply route-specific request header modifications
         // Note: Pingora's IntoCaseHeaderName requires owned String for header names,
         // so we clone names but pass values by reference to avoid cloning both.
@@ -2310,9 +3486,52 @@ impl ProxyHttp for ZentinelProxy {
             );
         }
 
-        // Apply request-phase Headers filters
-        if let Some(ref config) = ctx.config {
-            super::filters::apply_request_headers_filters(upstream_request, ctx, config);
+        // Apply request-phase Headers filters and prepare ICAP scans, prompt
+        // enforcement, multipart inspection and JSON body transforms
+        if let Some(config) = ctx.config.as_ref().map(std::sync::Arc::clone) {
+            super::filters::apply_request_headers_filters(upstream_request, ctx, &config);
+            super::filters::setup_icap_request_scan(upstream_request, ctx, &config);
+            super::filters::setup_prompt_templates(upstream_request, ctx, &config);
+            super::filters::setup_multipart_inspection(upstream_request, ctx, &config);
+            super::filters::setup_request_json_transform(upstream_request, ctx, &config);
+        }
+
+        // Attach the upstream's own credentials, replacing any the client sent
+        let mut attached_credentials = false;
+        if let Some(credential) = ctx
+            .upstream
+            .as_deref()
+            .and_then(|id| self.upstream_auth_manager.get(id))
+        {
+            match credential.authorization().await {
+                Ok(value) => {
+                    upstream_request.insert_header("Authorization", value).ok();
+                    attached_credentials = true;
+                }
+                Err(e) => {
+                    error!(
+                        correlation_id = %ctx.trace_id,
+                        upstream = ctx.upstream.as_deref().unwrap_or("unknown"),
+                        error = %e,
+                        "No credentials available for upstream"
+                    );
+                    return Err(Error::explain(
+                        ErrorType::HTTPStatus(502),
+                        "upstream credentials unavailable",
+                    ));
+                }
+            }
+        }
+
+        // Key pool members carry their own provider key, replacing any other
+        let pool_credential = ctx
+            .inference_key_member
+            .as_ref()
+            .map(|member| member.credential_header());
+        if let Some((name, value)) = &pool_credential {
+            upstream_request.remove_header("Authorization");
+            upstream_request.remove_header("x-api-key");
+            upstream_request.insert_header(*name, value.as_str()).ok();
         }
 
         // Remove sensitive headers that shouldn't go to upstream
@@ -2350,8 +3569,15 @@ impl ProxyHttp for ZentinelProxy {
                         "Shadowing request"
                     );
 
-                    // Clone headers for shadow request
-                    let shadow_headers = upstream_request.clone();
+                    // Clone headers for shadow request; the primary
+                    // upstream's credentials are not for the shadow target
+                    let mut shadow_headers = upstream_request.clone();
+                    if attached_credentials {
+                        shadow_headers.remove_header("Authorization");
+                    }
+                    if let Some((name, _)) = &pool_credential {
+                        shadow_headers.remove_header(*name);
+                    }
 
                     // Create request context for shadow (simplified from proxy context)
                     let shadow_ctx = crate::upstream::RequestContext {
@@ -2410,6 +3636,16 @@ impl ProxyHttp for ZentinelProxy {
         end_of_stream: bool,
         ctx: &mut Self::CTX,
     ) -> Result<Option<Duration>, Box<Error>> {
+        // A cancel after the response headers were sent aborts the response
+        ctx.check_cancelled()?;
+
+        if end_of_stream && ctx.timings.upstream_headers.is_some() {
+            ctx.timings.upstream_body_done = Some(std::time::Instant::now());
+        }
+
+        // Count raw upstream bytes against a response-policy size limit
+        super::filters::check_response_size(ctx, body)?;
+
         // Handle WebSocket frame inspection (server -> client)
         // Note: This filter is synchronous, so we use block_in_place for async agent calls
         if ctx.is_websocket_upgrade {
@@ -2446,8 +3682,50 @@ impl ProxyHttp for ZentinelProxy {
                     }
                 }
             }
-            // Skip normal body processing for WebSocket
-            return Ok(None);
+            // Skip normal body processing for WebSocket
+            return Ok(None);
+        }
+
+        // Abort responses still streaming past the request deadline
+        if let Some(Err(violation)) = ctx.slow_client.as_ref().map(|g| g.check_duration()) {
+            warn!(
+                correlation_id = %ctx.trace_id,
+                route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                client_ip = %ctx.client_ip,
+                violation = %violation,
+                "Closing slow client"
+            );
+            crate::slow_client::record_closed(violation.reason());
+            return Err(Error::explain(
+                ErrorType::WriteTimedout,
+                violation.to_string(),
+            ));
+        }
+
+        // ICAP filters hold back the body until the RESPMOD service finds
+        // it clean. The headers are already sent, so blocked bodies are
+        // cut off.
+        if let Some(mut scan) = ctx.response_icap_scan.take() {
+            let result = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current()
+                    .block_on(async { scan.process(body, end_of_stream).await })
+            });
+            if let Err(rejection) = result {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                    filter_id = %rejection.filter_id,
+                    reason = rejection.reason,
+                    threat = rejection.threat.as_deref().unwrap_or("none"),
+                    "Response body blocked by ICAP scan, closing connection"
+                );
+                *body = None;
+                return Err(Error::explain(
+                    ErrorType::InternalError,
+                    "Response body blocked by ICAP scan",
+                ));
+            }
+            ctx.response_icap_scan = Some(scan);
         }
 
         // Process response body through agents (for agents that subscribe to ResponseBody events)
@@ -2467,6 +3745,10 @@ impl ProxyHttp for ZentinelProxy {
                 let route_id = ctx.route_id.clone();
                 let upstream_id = ctx.upstream.clone();
                 let traceparent = ctx.traceparent();
+                let client_cert = ctx.client_cert.clone();
+                let tags = ctx.tags.to_vec();
+                let bot_signals = ctx.bot_signals.clone();
+                let protocol = ctx.protocol;
                 let agent_mgr = self.agent_manager.clone();
 
                 // Use block_in_place to run async agent call from sync context
@@ -2477,22 +3759,29 @@ impl ProxyHttp for ZentinelProxy {
                             correlation_id: zentinel_common::CorrelationId::from_string(&trace_id),
                             metadata: zentinel_agent_protocol::RequestMetadata {
                                 correlation_id: trace_id.clone(),
-                                request_id: uuid::Uuid::new_v4().to_string(),
+                                request_id: trace_id.clone(),
                                 client_ip,
                                 client_port: 0,
                                 server_name: host,
-                                protocol: "HTTP/1.1".to_string(),
+                                protocol: protocol.to_string(),
                                 tls_version: None,
                                 tls_cipher: None,
                                 route_id: route_id.clone(),
                                 upstream_id: upstream_id.clone(),
                                 timestamp: chrono::Utc::now().to_rfc3339(),
                                 traceparent,
+                                client_cert,
+                                tags,
+                                bot_signals,
+                                dry_run: false,
                             },
                             route_id,
                             upstream_id,
                             request_body: None,
                             response_body: None,
+                            agent_budget: ctx.agent_budget(),
+                            decision_merge: std::sync::Arc::clone(&ctx.decision_merge),
+                            trace: None,
                         };
 
                         agent_mgr
@@ -2511,6 +3800,8 @@ impl ProxyHttp for ZentinelProxy {
 
                 match result {
                     Ok(decision) => {
+                        ctx.tags.apply(&decision.tags);
+
                         // Apply response body mutation if present
                         if let Some(mutation) = decision.response_body_mutation {
                             if let Some(ref data) = mutation.data {
@@ -2559,6 +3850,13 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // JSON field manipulation (json-transform filters); buffered chunks
+        // are counted once the transformed body is released
+        if let Some(mut transform) = ctx.response_json_transform.take() {
+            transform.process(body, end_of_stream, &ctx.json_computed_values());
+            ctx.response_json_transform = Some(transform);
+        }
+
         // Track response body size
         if let Some(ref chunk) = body {
             ctx.response_bytes += chunk.len() as u64;
@@ -2625,6 +3923,21 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Compress last, after everything that reads the plain body
+        if let Some(ref mut compressor) = ctx.response_compressor {
+            if let Err(e) = compressor.process(body, end_of_stream) {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %e,
+                    "Response compression failed"
+                );
+                return Err(Error::explain(
+                    ErrorType::InternalError,
+                    format!("response compression failed: {}", e),
+                ));
+            }
+        }
+
         if end_of_stream {
             trace!(
                 correlation_id = %ctx.trace_id,
@@ -2652,6 +3965,25 @@ impl ProxyHttp for ZentinelProxy {
     ) -> Result<(), Box<Error>> {
         // Track connection reuse for metrics
         ctx.connection_reused = reused;
+        ctx.timings.upstream_ready = Some(std::time::Instant::now());
+
+        // Connect and TLS time of new connections, from the establishment
+        // timestamps of the transport layers (TCP first, then TLS)
+        if !reused {
+            if let (Some(started), Some(digest)) = (ctx.timings.upstream_started_wall, digest) {
+                let established: Vec<_> = digest
+                    .timing_digest
+                    .iter()
+                    .map(|t| t.as_ref().map(|t| t.established_ts))
+                    .collect();
+                if let Some(Some(tcp)) = established.first() {
+                    ctx.timings.connect = tcp.duration_since(started).ok();
+                    if let Some(Some(tls)) = established.get(1) {
+                        ctx.timings.tls = tls.duration_since(*tcp).ok();
+                    }
+                }
+            }
+        }
 
         // Log connection establishment/reuse
         if reused {
@@ -2746,16 +4078,56 @@ impl ProxyHttp for ZentinelProxy {
         // Get static references to cache infrastructure
         let storage = get_cache_storage();
         let eviction = get_cache_eviction();
-        let cache_lock = get_cache_lock();
+
+        // Concurrent misses for the same key wait on the cache lock for one
+        // upstream fetch, unless too many are waiting already
+        let cache_lock = self
+            .cache_manager
+            .coalesce_config(route_id)
+            .and_then(|coalesce| {
+                let uri = &session.req_header().uri;
+                let key = crate::cache::CacheManager::generate_cache_key(
+                    &ctx.method,
+                    ctx.host.as_deref().unwrap_or("unknown"),
+                    uri.path(),
+                    uri.query(),
+                );
+                match self
+                    .cache_manager
+                    .coalescer()
+                    .join(&key, coalesce.max_waiters)
+                {
+                    Some(ticket) => {
+                        ctx.cache_coalesce = Some(ticket);
+                        Some(coalesce_lock(coalesce.timeout_secs))
+                    }
+                    None => {
+                        self.cache_manager.stats().record_coalesce_bypass();
+                        debug!(
+                            correlation_id = %ctx.trace_id,
+                            route_id = %route_id,
+                            cache_key = %key,
+                            max_waiters = coalesce.max_waiters,
+                            "Coalescing queue full, fetching from upstream"
+                        );
+                        None
+                    }
+                }
+            });
 
         // Enable the cache with storage, eviction, and lock
-        session.cache.enable(
-            storage,
-            Some(eviction),
-            None, // predictor - optional
-            Some(cache_lock),
-            None, // option overrides
-        );
+        match cache_lock {
+            Some(cache_lock) => session.cache.enable(
+                storage,
+                Some(eviction),
+                None, // predictor - optional
+                Some(cache_lock),
+                None, // option overrides
+            ),
+            None => session
+                .cache
+                .enable(storage, Some(eviction), None, None, None),
+        }
 
         // Mark request as cache-eligible in context
         ctx.cache_eligible = true;
@@ -2834,6 +4206,9 @@ impl ProxyHttp for ZentinelProxy {
     where
         Self::CTX: Send + Sync,
     {
+        // Waiting is over, whether or not the entry is served
+        ctx.cache_coalesce = None;
+
         // Check if this cache entry should be invalidated due to a purge request
         let req_header = session.req_header();
         let method = req_header.method.as_str();
@@ -2910,6 +4285,10 @@ impl ProxyHttp for ZentinelProxy {
         resp: &ResponseHeader,
         ctx: &mut Self::CTX,
     ) -> Result<RespCacheable> {
+        // The fetch is done; waiters are woken once the response is stored
+        // or found uncacheable
+        ctx.cache_coalesce = None;
+
         let route_id = match ctx.route_id.as_deref() {
             Some(id) => id,
             None => {
@@ -3144,6 +4523,93 @@ impl ProxyHttp for ZentinelProxy {
     where
         Self::CTX: Send + Sync,
     {
+        // Bodies rejected by schema-validate filters get their violations
+        if let Some(rejection) = ctx.schema_rejection.take() {
+            if let Err(write_err) = self.write_schema_rejection(session, ctx, rejection).await {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %write_err,
+                    "Failed to write schema validation response"
+                );
+            }
+            return pingora_proxy::FailToProxy {
+                error_code: 400,
+                can_reuse_downstream: false,
+            };
+        }
+
+        // Bodies blocked by managed-rules filters
+        if let Some(rejection) = ctx.rule_rejection.take() {
+            if let Err(write_err) = self.write_rule_rejection(session, ctx, rejection).await {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %write_err,
+                    "Failed to write managed rules response"
+                );
+            }
+            return pingora_proxy::FailToProxy {
+                error_code: 403,
+                can_reuse_downstream: false,
+            };
+        }
+
+        // Requests blocked by prompt-template filters
+        if let Some(rejection) = ctx.prompt_rejection.take() {
+            let status = rejection.status;
+            if let Err(write_err) = self.write_prompt_rejection(session, ctx, rejection).await {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %write_err,
+                    "Failed to write prompt policy response"
+                );
+            }
+            return pingora_proxy::FailToProxy {
+                error_code: status,
+                can_reuse_downstream: false,
+            };
+        }
+
+        // Request bodies blocked by icap filters
+        if let Some(rejection) = ctx.icap_rejection.take() {
+            let status = rejection.status;
+            if let Err(write_err) = self.write_icap_rejection(session, ctx, rejection).await {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %write_err,
+                    "Failed to write ICAP scan response"
+                );
+            }
+            return pingora_proxy::FailToProxy {
+                error_code: status,
+                can_reuse_downstream: false,
+            };
+        }
+
+        // Agent blocks raised outside request_filter (e.g. body inspection)
+        // get the route's templated block page
+        if let ErrorType::HTTPStatus(status) = e.etype() {
+            match self.write_block_page(session, ctx).await {
+                Ok(false) => {}
+                Ok(true) => {
+                    return pingora_proxy::FailToProxy {
+                        error_code: *status,
+                        can_reuse_downstream: false,
+                    };
+                }
+                Err(write_err) => {
+                    warn!(
+                        correlation_id = %ctx.trace_id,
+                        error = %write_err,
+                        "Failed to write block page"
+                    );
+                    return pingora_proxy::FailToProxy {
+                        error_code: *status,
+                        can_reuse_downstream: false,
+                    };
+                }
+            }
+        }
+
         let error_code = match e.etype() {
             // Connection errors
             ErrorType::ConnectRefused => 503,
@@ -3191,11 +4657,34 @@ impl ProxyHttp for ZentinelProxy {
         self.metrics
             .record_blocked_request(&format!("proxy_error_{}", error_code));
 
+        // Prefer the route's custom error page when one is configured
+        match self.write_route_error_page(session, ctx, error_code).await {
+            Ok(true) => {
+                return pingora_proxy::FailToProxy {
+                    error_code,
+                    can_reuse_downstream: false,
+                };
+            }
+            Ok(false) => {}
+            Err(write_err) => {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %write_err,
+                    "Failed to write custom error page"
+                );
+                return pingora_proxy::FailToProxy {
+                    error_code,
+                    can_reuse_downstream: false,
+                };
+            }
+        }
+
         // Write error response to ensure client receives a proper HTTP response
         // This is necessary because some errors occur before the upstream connection
         // is established, and Pingora may not send a response automatically
         let error_message = match error_code {
             400 => "Bad Request",
+            408 => "Request Timeout",
             502 => "Bad Gateway",
             503 => "Service Unavailable",
             504 => "Gateway Timeout",
@@ -3216,9 +4705,10 @@ impl ProxyHttp for ZentinelProxy {
         header
             .insert_header("Content-Length", body.len().to_string())
             .ok();
-        header
-            .insert_header("X-Correlation-Id", ctx.trace_id.as_str())
-            .ok();
+        self.echo_request_id(&mut header, &ctx.trace_id);
+        for (name, value) in ctx.debug_headers() {
+            header.insert_header(name, value).ok();
+        }
         header.insert_header("Connection", "close").ok();
 
         // Write headers and body
@@ -3276,6 +4766,9 @@ impl ProxyHttp for ZentinelProxy {
                 | ErrorType::ConnectRefused
         );
 
+        // Raised by response_filter to retry on another key pool member
+        let key_pool_failover = std::mem::take(&mut ctx.inference_key_failover);
+
         // Log the error with context
         warn!(
             correlation_id = %ctx.trace_id,
@@ -3290,15 +4783,18 @@ impl ProxyHttp for ZentinelProxy {
         );
 
         // Record failure with circuit breaker via upstream pool
-        // This is done asynchronously since we can't await in a sync fn
-        let peer_address = peer.address().to_string();
-        let upstream_pools = self.upstream_pools.clone();
-        let upstream_id_owned = upstream_id.to_string();
-        tokio::spawn(async move {
-            if let Some(pool) = upstream_pools.get(&upstream_id_owned).await {
-                pool.report_result(&peer_address, false).await;
-            }
-        });
+        // This is done asynchronously since we can't await in a sync fn.
+        // A key pool failover is about the key, not the target's health.
+        if !key_pool_failover {
+            let peer_address = peer.address().to_string();
+            let upstream_pools = self.upstream_pools.clone();
+            let upstream_id_owned = upstream_id.to_string();
+            tokio::spawn(async move {
+                if let Some(pool) = upstream_pools.get(&upstream_id_owned).await {
+                    pool.report_result(&peer_address, false).await;
+                }
+            });
+        }
 
         // Metrics tracking
         self.metrics
@@ -3313,10 +4809,21 @@ impl ProxyHttp for ZentinelProxy {
         ));
 
         // Determine if retry should be attempted:
+        // - Key pool failover retries while the request body can be replayed
         // - Only retry if error is retryable type
         // - Only retry reused connections if buffer isn't truncated
         // - Track retry metrics
-        if is_retryable {
+        if key_pool_failover {
+            let can_retry = !session.as_ref().retry_buffer_truncated();
+            enhanced_error.set_retry(can_retry);
+            if !can_retry {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    upstream = %upstream_id,
+                    "Request body exceeded the retry buffer, cannot fail over to another key"
+                );
+            }
+        } else if is_retryable {
             let can_retry = if client_reused {
                 // For reused connections, check if retry buffer is intact
                 !session.as_ref().retry_buffer_truncated()
@@ -3343,18 +4850,21 @@ impl ProxyHttp for ZentinelProxy {
         enhanced_error
     }
 
-    async fn logging(&self, session: &mut Session, _error: Option<&Error>, ctx: &mut Self::CTX) {
+    async fn logging(&self, session: &mut Session, error: Option<&Error>, ctx: &mut Self::CTX) {
         // Decrement active requests
         self.reload_coordinator.dec_requests();
+        ctx.inflight = None;
 
         // Release per-request agent state (correlation affinity) now that the
         // request is complete; the pool TTL sweep is only the backstop.
+        let mut agent_timings = AgentTimings::default();
         if !ctx.route_agent_ids.is_empty()
             || !ctx.body_inspection_agents.is_empty()
             || !ctx.websocket_inspection_agents.is_empty()
         {
-            self.agent_manager.end_request(&ctx.trace_id).await;
+            agent_timings = self.agent_manager.end_request(&ctx.trace_id).await;
         }
+        let latency = ctx.latency_breakdown(&agent_timings);
 
         // === Fire pending shadow request (if body buffering was enabled) ===
         if !ctx.shadow_sent {
@@ -3389,6 +4899,85 @@ impl ProxyHttp for ZentinelProxy {
             .map(|r| r.status.as_u16())
             .unwrap_or(0);
 
+        if let Some(capture) = ctx.capture.take() {
+            self.capture_manager
+                .finish(capture, (status != 0).then_some(status));
+        }
+
+        // Compared between route table versions by the rollout monitor
+        self.rollout_manager
+            .record(ctx.rollout_cohort, status, duration);
+
+        // Live request tap (only built while an admin stream is open)
+        crate::tap::publish(|| crate::tap::TapEvent {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            trace_id: ctx.trace_id.clone(),
+            method: ctx.method.clone(),
+            path: ctx.path.clone(),
+            status,
+            route: ctx.route_id.clone(),
+            upstream: ctx.upstream.clone(),
+            client_ip: ctx.client_ip.clone(),
+            duration_ms: duration.as_millis() as u64,
+            agents: latency
+                .agents_ms
+                .iter()
+                .map(|(agent, ms)| (agent.clone(), *ms))
+                .collect(),
+            blocked_by: ctx.agent_block.as_ref().and_then(|b| b.agent_id.clone()),
+            rule_ids: ctx
+                .agent_block
+                .as_ref()
+                .map(|b| b.rule_ids.clone())
+                .unwrap_or_default(),
+        });
+
+        // Per-tenant request metrics; dropping the permit frees the
+        // tenant's concurrency slot
+        if let Some(tenant) = ctx.tenant.as_deref() {
+            self.tenant_manager.record_response(tenant, status);
+        }
+        ctx.tenant_permit = None;
+
+        // Feed response statuses into the error ratio of adaptive protection
+        for filter_id in &ctx.adaptive_filters {
+            self.adaptive_protection_manager
+                .record_response(filter_id, &ctx.client_ip, status);
+        }
+
+        // Count body bytes against byte quotas
+        let body_bytes = ctx.request_body_bytes + ctx.response_bytes;
+        if body_bytes > 0 {
+            for (filter_id, consumer) in std::mem::take(&mut ctx.quota_consumers) {
+                let manager = std::sync::Arc::clone(&self.quota_manager);
+                tokio::spawn(async move {
+                    manager
+                        .record_bytes(&filter_id, &consumer, body_bytes)
+                        .await;
+                });
+            }
+        }
+
+        // Downstream timeouts are slow clients: stalled uploads hit the
+        // listener read timeout, slow readers the send-rate write timeout
+        if let Some(e) = error.filter(|e| e.esource() == &ErrorSource::Downstream) {
+            match e.etype() {
+                ErrorType::ReadTimedout => crate::slow_client::record_closed("read_timeout"),
+                ErrorType::WriteTimedout => crate::slow_client::record_closed("send_timeout"),
+                _ => {}
+            }
+        }
+
+        // Free the concurrency slots; successful requests feed the adaptive
+        // limits (upgrades and errors would skew the latency baseline)
+        if let Some(permit) = ctx.concurrency_permit.take() {
+            permit.finish((200..500).contains(&status));
+        }
+        ctx.upstream_queue_permit = None;
+
+        // Return the load balancer selection (connection tracking)
+        self.release_upstream_selection(ctx).await;
+
         // Report result to load balancer for adaptive LB feedback
         // This enables latency-aware weight adjustment
         if let (Some(ref peer_addr), Some(ref upstream_id)) =
@@ -3401,6 +4990,11 @@ impl ProxyHttp for ZentinelProxy {
                 pool.report_result_with_latency(peer_addr, success, Some(duration))
                     .await;
                 pool.decrement_active();
+                self.metrics.update_connection_pool_utilization(
+                    upstream_id,
+                    pool.active_request_count(),
+                    pool.connection_capacity(),
+                );
                 trace!(
                     correlation_id = %ctx.trace_id,
                     upstream = %upstream_id,
@@ -3461,8 +5055,9 @@ impl ProxyHttp for ZentinelProxy {
                     );
                 }
 
-                // PII detection guardrail (for streaming inference responses)
-                if ctx.inference_streaming_response {
+                // PII detection guardrail (for streaming inference responses),
+                // skipped on streaming-passthrough routes
+                if ctx.inference_streaming_response && !ctx.streaming_passthrough() {
                     if let Some(ref route_config) = ctx.route_config {
                         if let Some(ref inference) = route_config.inference {
                             if let Some(ref guardrails) = inference.guardrails {
@@ -3641,6 +5236,33 @@ impl ProxyHttp for ZentinelProxy {
                                     currency = %cost_result.currency,
                                     "Calculated inference request cost"
                                 );
+
+                                crate::inference::record_cost_metrics(
+                                    route_id,
+                                    &cost_result,
+                                    ctx.tenant.as_deref(),
+                                    ctx.tags.get("api_key_id"),
+                                );
+
+                                // Count the cost against the monthly cost budget
+                                if let Some(consumer) = ctx.inference_cost_consumer.as_deref() {
+                                    let alerts = self.inference_rate_limit_manager.record_cost(
+                                        route_id,
+                                        consumer,
+                                        &cost_result,
+                                    );
+                                    for alert in alerts {
+                                        warn!(
+                                            correlation_id = %ctx.trace_id,
+                                            route_id = route_id,
+                                            consumer = %alert.consumer,
+                                            threshold = alert.threshold,
+                                            spent = alert.spent,
+                                            limit = alert.limit,
+                                            "Cost budget alert threshold crossed"
+                                        );
+                                    }
+                                }
                             }
                         }
                     }
@@ -3648,6 +5270,24 @@ impl ProxyHttp for ZentinelProxy {
             }
         }
 
+        // Count the tokens against the key pool member that served the request
+        if let Some(member) = ctx.inference_key_member.as_ref() {
+            member.record_tokens(
+                ctx.inference_actual_tokens
+                    .unwrap_or(ctx.inference_estimated_tokens),
+            );
+        }
+
+        // Count the request under its exported low-cardinality tags
+        if !ctx.tags.is_empty() {
+            if let Some(config) = ctx.config.as_deref() {
+                ctx.tags.record(
+                    ctx.route_id.as_deref().unwrap_or("unknown"),
+                    &config.observability.metrics.tag_labels,
+                );
+            }
+        }
+
         // Write to access log file if configured (check sampling before allocating entry)
         if self.log_manager.should_log_access(status) {
             let access_entry = AccessLogEntry {
@@ -3656,7 +5296,7 @@ impl ProxyHttp for ZentinelProxy {
                 method: ctx.method.clone(),
                 path: ctx.path.clone(),
                 query: ctx.query.clone(),
-                protocol: "HTTP/1.1".to_string(),
+                protocol: ctx.protocol.to_string(),
                 status,
                 body_bytes: ctx.response_bytes,
                 duration_ms: duration.as_millis() as u64,
@@ -3676,6 +5316,8 @@ impl ProxyHttp for ZentinelProxy {
                 connection_reused: ctx.connection_reused,
                 rate_limit_hit: status == 429,
                 geo_country: ctx.geo_country_code.clone(),
+                latency: Some(latency.clone()),
+                request_tags: ctx.tags.fields(),
             };
             self.log_manager.log_access(&access_entry);
         }
@@ -3694,7 +5336,8 @@ impl ProxyHttp for ZentinelProxy {
                 duration_ms = duration.as_millis() as u64,
                 upstream_write_pending_ms = write_pending_ms,
                 upstream_attempts = ctx.upstream_attempts,
-                error = ?_error.map(|e| e.to_string()),
+                latency = ?latency,
+                error = ?error.map(|e| e.to_string()),
                 "Request completed"
             );
         }
@@ -3714,6 +5357,18 @@ impl ProxyHttp for ZentinelProxy {
         if let Some(span) = ctx.otel_span.take() {
             span.end();
         }
+
+        self.lifecycle_events
+            .emit(|| LifecycleEvent::RequestCompleted {
+                correlation_id: ctx.trace_id.clone(),
+                method: ctx.method.clone(),
+                path: ctx.path.clone(),
+                route_id: ctx.route_id.clone(),
+                upstream: ctx.upstream.clone(),
+                status,
+                duration,
+                tags: ctx.tags.fields(),
+            });
     }
 }
 
@@ -3752,18 +5407,25 @@ impl ZentinelProxy {
                 client_ip: ctx.client_ip.clone(),
                 client_port: 0,
                 server_name: ctx.host.clone(),
-                protocol: "HTTP/1.1".to_string(),
+                protocol: ctx.protocol.to_string(),
                 tls_version: None,
                 tls_cipher: None,
                 route_id: ctx.route_id.clone(),
                 upstream_id: ctx.upstream.clone(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
                 traceparent: ctx.traceparent(),
+                client_cert: ctx.client_cert.clone(),
+                tags: ctx.tags.to_vec(),
+                bot_signals: ctx.bot_signals.clone(),
+                dry_run: false,
             },
             route_id: ctx.route_id.clone(),
             upstream_id: ctx.upstream.clone(),
             request_body: None, // Not used in streaming mode
             response_body: None,
+            agent_budget: ctx.agent_budget(),
+            decision_merge: std::sync::Arc::clone(&ctx.decision_merge),
+            trace: ctx.debug_trace.clone(),
         };
 
         let agent_ids = ctx.body_inspection_agents.clone();
@@ -3783,6 +5445,8 @@ impl ZentinelProxy {
             .await
         {
             Ok(decision) => {
+                ctx.tags.apply(&decision.tags);
+
                 // Track if agent needs more data
                 ctx.agent_needs_more = decision.needs_more;
 
@@ -3820,6 +5484,7 @@ impl ZentinelProxy {
                         "Agent blocked request body"
                     );
                     self.metrics.record_blocked_request("agent_body_inspection");
+                    ctx.agent_block = crate::errors::AgentBlock::from_decision(&decision);
 
                     let (status, message) = match &decision.action {
                         crate::agents::AgentAction::Block { status, body, .. } => (
@@ -3884,6 +5549,189 @@ impl ZentinelProxy {
         Ok(())
     }
 
+    /// Parse a chunk of a multipart/form-data body, send its parts to the
+    /// route's agents and replace the chunk with the re-encoded parts that
+    /// are complete.
+    async fn process_multipart_body(
+        &self,
+        inspection: &mut crate::multipart::MultipartInspection,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+        ctx: &mut RequestContext,
+    ) -> Result<(), Box<Error>> {
+        use crate::multipart::MultipartEvent;
+
+        let events = match inspection.parse(body.as_ref(), end_of_stream) {
+            Ok(events) => events,
+            Err(e) => {
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    route_id = ctx.route_id.as_deref().unwrap_or("unknown"),
+                    filter_id = inspection.filter_id(),
+                    error = %e,
+                    "Multipart body rejected"
+                );
+                self.metrics.record_blocked_request("multipart");
+                return Err(Error::explain(
+                    ErrorType::HTTPStatus(e.status()),
+                    e.to_string(),
+                ));
+            }
+        };
+
+        for event in events {
+            let (data, is_last) = match event {
+                MultipartEvent::PartStart { part, headers } => {
+                    inspection.start_part(part, &headers);
+                    continue;
+                }
+                MultipartEvent::PartData { data, is_last } => (data, is_last),
+            };
+            if let Some((part, bytes_received)) = inspection.receive(data.len()) {
+                self.inspect_multipart_chunk(inspection, &part, data, is_last, bytes_received, ctx)
+                    .await?;
+            }
+            if is_last {
+                inspection.finish_part();
+            }
+        }
+
+        *body = inspection.take_output();
+        Ok(())
+    }
+
+    /// Send one chunk of a multipart part to the route's agents and add it
+    /// to the part as the agents decide: unchanged, replaced or dropped
+    /// with the whole part
+    async fn inspect_multipart_chunk(
+        &self,
+        inspection: &mut crate::multipart::MultipartInspection,
+        part: &zentinel_agent_protocol::MultipartPart,
+        data: Bytes,
+        is_last: bool,
+        bytes_received: usize,
+        ctx: &mut RequestContext,
+    ) -> Result<(), Box<Error>> {
+        if ctx.route_agent_ids.is_empty() {
+            inspection.push_data(&data, false);
+            return Ok(());
+        }
+        let chunk_index = ctx.request_body_chunk_index;
+        ctx.request_body_chunk_index += 1;
+
+        let agent_ctx = crate::agents::AgentCallContext {
+            correlation_id: zentinel_common::CorrelationId::from_string(&ctx.trace_id),
+            metadata: zentinel_agent_protocol::RequestMetadata {
+                correlation_id: ctx.trace_id.clone(),
+                request_id: ctx.trace_id.clone(),
+                client_ip: ctx.client_ip.clone(),
+                client_port: 0,
+                server_name: ctx.host.clone(),
+                protocol: ctx.protocol.to_string(),
+                tls_version: None,
+                tls_cipher: None,
+                route_id: ctx.route_id.clone(),
+                upstream_id: ctx.upstream.clone(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                traceparent: ctx.traceparent(),
+                client_cert: ctx.client_cert.clone(),
+                tags: ctx.tags.to_vec(),
+                bot_signals: ctx.bot_signals.clone(),
+                dry_run: false,
+            },
+            route_id: ctx.route_id.clone(),
+            upstream_id: ctx.upstream.clone(),
+            request_body: None,
+            response_body: None,
+            agent_budget: ctx.agent_budget(),
+            decision_merge: std::sync::Arc::clone(&ctx.decision_merge),
+            trace: ctx.debug_trace.clone(),
+        };
+
+        let agent_ids = ctx.route_agent_ids.clone();
+        let decision = match self
+            .agent_manager
+            .process_request_body_part(
+                &agent_ctx,
+                part,
+                &data,
+                is_last,
+                chunk_index,
+                bytes_received,
+                &agent_ids,
+            )
+            .await
+        {
+            Ok(decision) => decision,
+            Err(e) => {
+                let fail_closed = ctx
+                    .route_config
+                    .as_ref()
+                    .map(|r| r.policies.failure_mode == zentinel_config::FailureMode::Closed)
+                    .unwrap_or(false);
+                if fail_closed {
+                    error!(
+                        correlation_id = %ctx.trace_id,
+                        error = %e,
+                        "Agent multipart inspection failed, blocking (fail-closed)"
+                    );
+                    return Err(Error::explain(
+                        ErrorType::HTTPStatus(503),
+                        "Service unavailable",
+                    ));
+                }
+                warn!(
+                    correlation_id = %ctx.trace_id,
+                    error = %e,
+                    "Agent multipart inspection failed, allowing (fail-open)"
+                );
+                inspection.push_data(&data, false);
+                return Ok(());
+            }
+        };
+        ctx.tags.apply(&decision.tags);
+
+        if !decision.needs_more && !decision.is_allow() {
+            warn!(
+                correlation_id = %ctx.trace_id,
+                agent_id = decision.decided_by.as_deref().unwrap_or("unknown"),
+                part_name = %part.name,
+                filename = part.filename.as_deref().unwrap_or(""),
+                action = ?decision.action,
+                "Agent blocked multipart part"
+            );
+            self.metrics.record_blocked_request("agent_body_inspection");
+            ctx.agent_block = crate::errors::AgentBlock::from_decision(&decision);
+
+            let (status, message) = match &decision.action {
+                crate::agents::AgentAction::Block { status, body, .. } => (
+                    *status,
+                    body.clone().unwrap_or_else(|| "Blocked".to_string()),
+                ),
+                _ => (403, "Forbidden".to_string()),
+            };
+            return Err(Error::explain(ErrorType::HTTPStatus(status), message));
+        }
+
+        match decision.request_body_mutation {
+            Some(mutation) if mutation.is_drop() => {
+                debug!(
+                    correlation_id = %ctx.trace_id,
+                    part_index = part.index,
+                    part_name = %part.name,
+                    "Agent dropped multipart part"
+                );
+                inspection.drop_part();
+            }
+            Some(mutation) if !mutation.is_pass_through() => {
+                let replaced = mutation.data.map(Bytes::from).unwrap_or_default();
+                inspection.push_data(&replaced, true);
+            }
+            _ => inspection.push_data(&data, false),
+        }
+        Ok(())
+    }
+
     /// Send buffered body to agents (buffer mode).
     async fn send_buffered_body_to_agents(
         &self,
@@ -3988,18 +5836,25 @@ impl ZentinelProxy {
                 client_ip: ctx.client_ip.clone(),
                 client_port: 0,
                 server_name: ctx.host.clone(),
-                protocol: "HTTP/1.1".to_string(),
+                protocol: ctx.protocol.to_string(),
                 tls_version: None,
                 tls_cipher: None,
                 route_id: ctx.route_id.clone(),
                 upstream_id: ctx.upstream.clone(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
                 traceparent: ctx.traceparent(),
+                client_cert: ctx.client_cert.clone(),
+                tags: ctx.tags.to_vec(),
+                bot_signals: ctx.bot_signals.clone(),
+                dry_run: false,
             },
             route_id: ctx.route_id.clone(),
             upstream_id: ctx.upstream.clone(),
             request_body: Some(body_for_inspection.clone()),
             response_body: None,
+            agent_budget: ctx.agent_budget(),
+            decision_merge: std::sync::Arc::clone(&ctx.decision_merge),
+            trace: ctx.debug_trace.clone(),
         };
 
         let agent_ids = ctx.body_inspection_agents.clone();
@@ -4009,6 +5864,8 @@ impl ZentinelProxy {
             .await
         {
             Ok(decision) => {
+                ctx.tags.apply(&decision.tags);
+
                 if !decision.is_allow() {
                     warn!(
                         correlation_id = %ctx.trace_id,
@@ -4017,6 +5874,7 @@ impl ZentinelProxy {
                         "Agent blocked request body"
                     );
                     self.metrics.record_blocked_request("agent_body_inspection");
+                    ctx.agent_block = crate::errors::AgentBlock::from_decision(&decision);
 
                     let (status, message) = match &decision.action {
                         crate::agents::AgentAction::Block { status, body, .. } => (
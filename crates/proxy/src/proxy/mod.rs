@@ -6,6 +6,7 @@
 //! - `context`: Request context maintained throughout the request lifecycle
 //! - `handlers`: Helper methods for handling different route types
 //! - `http_trait`: ProxyHttp trait implementation for Pingora
+//! - `sni_routing`: Per-SNI route set selection for TLS listeners
 
 mod context;
 mod fallback;
@@ -15,6 +16,7 @@ mod handlers;
 mod http_trait;
 mod model_routing;
 mod model_routing_metrics;
+mod sni_routing;
 
 pub use context::{FallbackReason, RequestContext};
 pub use fallback::{FallbackDecision, FallbackEvaluator};
@@ -24,11 +26,13 @@ pub use model_routing_metrics::{
     get_model_routing_metrics, init_model_routing_metrics, ModelRoutingMetrics,
 };
 
+use sni_routing::ListenerRoutes;
+
 use anyhow::{Context, Result};
 use parking_lot::RwLock;
 use pingora::http::ResponseHeader;
 use pingora::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
@@ -38,40 +42,76 @@ use uuid::Uuid;
 use zentinel_common::ids::{QualifiedId, Scope};
 use zentinel_common::{Registry, ScopedMetrics, ScopedRegistry};
 
-use crate::agents::AgentManager;
+use crate::adaptive_protection::AdaptiveProtectionManager;
+use crate::agents::{AgentManager, AgentStateStore, AgentSupervisor};
+use crate::api_keys::ApiKeyManager;
 use crate::app::AppState;
+use crate::audit_store::AuditStore;
 use crate::builtin_handlers::BuiltinHandlerState;
 use crate::cache::{CacheConfig, CacheManager};
-use crate::errors::ErrorHandler;
+use crate::capture::CaptureManager;
+use crate::client_ip::ClientIpResolver;
+use crate::concurrency::ConcurrencyManager;
+use crate::discovery::{backends_to_targets, DiscoveryConfig, DiscoveryManager};
+use crate::errors::{BlockPageRenderer, ErrorHandler};
 use crate::geo_filter::{GeoDatabaseWatcher, GeoFilterManager};
 use crate::health::PassiveHealthChecker;
-use crate::http_helpers;
-use crate::inference::InferenceRateLimitManager;
+use crate::inference::{InferenceRateLimitManager, KeyPoolManager};
+use crate::ip_access::IpAccessManager;
+use crate::lifecycle::LifecycleEvents;
 use crate::logging::{LogManager, SharedLogManager};
+use crate::maintenance::MaintenanceManager;
+use crate::managed_rules::ManagedRulesManager;
+use crate::probes::HealthProbes;
+use crate::quota::{QuotaManager, QuotaStore};
 use crate::rate_limit::{RateLimitConfig, RateLimitManager};
 use crate::reload::{
     ConfigManager, GracefulReloadCoordinator, ReloadEvent, RouteValidator, UpstreamValidator,
 };
+use crate::rollout::RolloutManager;
 use crate::routing::RouteMatcher;
+use crate::schema_validate::SchemaValidateManager;
 use crate::scoped_routing::ScopedRouteMatcher;
 use crate::static_files::StaticFileServer;
-use crate::upstream::{ActiveHealthChecker, HealthCheckRunner, UpstreamPool};
+use crate::tenant::TenantManager;
+use crate::trace_id::{self, RequestIdSource};
+use crate::upstream::{
+    ActiveHealthChecker, HealthCheckRunner, UpstreamAuthManager, UpstreamPool, UpstreamQueueManager,
+};
 use crate::validation::SchemaValidator;
+use crate::wasm_filter::WasmFilterManager;
 
+use zentinel_agent_protocol::v2::AgentHandlerV2;
 use zentinel_common::TraceIdFormat;
-use zentinel_config::{Config, FlattenedConfig};
+use zentinel_config::{Config, FlattenedConfig, RequestIdConfig, UpstreamConfig};
+
+/// Time to wait for supervised agents to create their sockets at startup
+const AGENT_STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration path of a proxy without a configuration file
+pub const EMBEDDED_CONFIG_PATH: &str = "_embedded_";
 
 /// Main proxy service implementing Pingora's ProxyHttp trait
+///
+/// Clones share all state, so the public and internal listener services
+/// serve the same proxy.
+#[derive(Clone)]
 pub struct ZentinelProxy {
     /// Configuration manager with hot reload
     pub config_manager: Arc<ConfigManager>,
     /// Route matcher (global routes only, for backward compatibility)
     pub(super) route_matcher: Arc<RwLock<RouteMatcher>>,
-    /// Per-listener route matchers for listeners bound to a namespace route set,
-    /// keyed by the listener's bound address. A request arriving on one of these
-    /// listeners is matched only against its namespace's routes (isolated from
-    /// the global set). Listeners absent from this map use [`Self::route_matcher`].
-    pub(super) listener_matchers: Arc<RwLock<HashMap<String, Arc<RouteMatcher>>>>,
+    /// Per-listener route sets for listeners (or their SNI certificates) bound
+    /// to a namespace route set, keyed by the listener's bound address. A
+    /// request selecting one of these is matched only against its namespace's
+    /// routes (isolated from the global set). Otherwise [`Self::route_matcher`]
+    /// is used.
+    pub(super) listener_matchers: Arc<RwLock<HashMap<String, ListenerRoutes>>>,
+    /// `Alt-Svc` value advertising HTTP/3 listeners on HTTPS responses.
+    /// Listener bindings are not hot-reloaded, so this is fixed at startup.
+    pub(super) alt_svc: Option<String>,
+    /// Client IP resolver (trusted proxies and forwarding headers)
+    pub(super) client_ip_resolver: Arc<RwLock<ClientIpResolver>>,
     /// Scoped route matcher (namespace/service aware)
     pub(super) scoped_route_matcher: Arc<tokio::sync::RwLock<ScopedRouteMatcher>>,
     /// Upstream pools (keyed by upstream ID, global only)
@@ -80,6 +120,9 @@ pub struct ZentinelProxy {
     pub(super) scoped_upstream_pools: ScopedRegistry<UpstreamPool>,
     /// Agent manager for external processing
     pub(super) agent_manager: Arc<AgentManager>,
+    /// Supervisor for agents run as child processes
+    /// Present only when at least one agent has a `command`
+    pub agent_supervisor: Option<Arc<AgentSupervisor>>,
     /// Passive health checker
     pub(super) passive_health: Arc<PassiveHealthChecker>,
     /// Metrics collector
@@ -92,16 +135,26 @@ pub struct ZentinelProxy {
     pub(super) reload_coordinator: Arc<GracefulReloadCoordinator>,
     /// Error handlers per route (keyed by route ID)
     pub(super) error_handlers: Registry<ErrorHandler>,
+    /// Error handler for requests without a matched route
+    pub(super) default_error_handler: Arc<ErrorHandler>,
+    /// Agent block page renderers per route (keyed by route ID)
+    pub(super) block_pages: Registry<BlockPageRenderer>,
+    /// Client challenge handler for agent challenge decisions
+    pub(super) challenge_handler: Arc<crate::challenge::ChallengeHandler>,
     /// API schema validators per route (keyed by route ID)
     pub(super) validators: Registry<SchemaValidator>,
     /// Static file servers per route (keyed by route ID)
     pub(super) static_servers: Registry<StaticFileServer>,
     /// Builtin handler state
     pub(super) builtin_state: Arc<BuiltinHandlerState>,
+    /// Component health and listener state for the readiness probes
+    pub(super) health_probes: Arc<HealthProbes>,
     /// Log manager for file-based logging
     pub(super) log_manager: SharedLogManager,
     /// Trace ID format for request tracing
     pub(super) trace_id_format: TraceIdFormat,
+    /// Request ID trust and propagation policy
+    pub(super) request_id: RequestIdConfig,
     /// Active health check runner
     pub(super) health_check_runner: Arc<HealthCheckRunner>,
     /// Rate limit manager
@@ -110,8 +163,40 @@ pub struct ZentinelProxy {
     pub(super) cache_manager: Arc<CacheManager>,
     /// GeoIP filter manager
     pub(super) geo_filter_manager: Arc<GeoFilterManager>,
+    /// Embedded WASM filter manager
+    pub(super) wasm_filter_manager: Arc<WasmFilterManager>,
+    /// Compiled documents of schema-validate filters
+    pub(super) schema_validate_manager: Arc<SchemaValidateManager>,
+    /// Rule sets of managed-rules filters
+    pub(super) managed_rules_manager: Arc<ManagedRulesManager>,
+    /// Client IP allow/deny lists
+    pub(super) ip_access_manager: Arc<IpAccessManager>,
+    /// Per-client anomaly profiles of adaptive-protection filters
+    pub(super) adaptive_protection_manager: Arc<AdaptiveProtectionManager>,
+    /// Daily and monthly counters of quota filters
+    pub(super) quota_manager: Arc<QuotaManager>,
+    /// Key stores of api-key filters and runtime revocations
+    pub(super) api_key_manager: Arc<ApiKeyManager>,
+    /// Credentials attached to upstream requests
+    pub(super) upstream_auth_manager: Arc<UpstreamAuthManager>,
+    /// Tenant attribution, quotas and drain state
+    pub(super) tenant_manager: Arc<TenantManager>,
+    /// Runtime maintenance flags, page and bypass rules
+    pub(super) maintenance_manager: Arc<MaintenanceManager>,
+    /// Traffic capture settings and the running capture
+    pub(super) capture_manager: Arc<CaptureManager>,
+    /// Staged rollout of reloaded route tables
+    pub(super) rollout_manager: Arc<RolloutManager>,
+    /// Agent decisions by correlation ID, for the audit handler
+    pub(super) audit_store: Option<Arc<AuditStore>>,
+    /// Global and per-route in-flight limits with load shedding
+    pub(super) concurrency_manager: Arc<ConcurrencyManager>,
+    /// Upstream in-flight limits with priority queuing
+    pub(super) upstream_queues: Arc<UpstreamQueueManager>,
     /// Inference rate limit manager (token-based rate limiting for LLM/AI routes)
     pub(super) inference_rate_limit_manager: Arc<InferenceRateLimitManager>,
+    /// Provider key pools of inference routes
+    pub(super) inference_key_pools: Arc<KeyPoolManager>,
     /// Warmth tracker for cold model detection on inference routes
     pub(super) warmth_tracker: Arc<crate::health::WarmthTracker>,
     /// Guardrail processor for semantic inspection (prompt injection, PII detection)
@@ -122,6 +207,8 @@ pub struct ZentinelProxy {
     /// ACME clients for certificate management
     /// Present only when ACME is configured
     pub acme_clients: Vec<Arc<crate::acme::AcmeClient>>,
+    /// Request lifecycle events for embedding applications
+    pub lifecycle_events: LifecycleEvents,
 }
 
 impl ZentinelProxy {
@@ -142,14 +229,42 @@ impl ZentinelProxy {
                 let cfg = Config::default_embedded()
                     .context("Failed to load embedded default configuration")?;
                 // Use a zentinel path to indicate embedded config
-                (cfg, "_embedded_".to_string())
+                (cfg, EMBEDDED_CONFIG_PATH.to_string())
             }
         };
 
+        Self::from_config(config, &effective_config_path, HashMap::new()).await
+    }
+
+    /// Create a proxy instance from a configuration built in code
+    ///
+    /// `config_path` is the file reloads read; pass [`EMBEDDED_CONFIG_PATH`]
+    /// when there is none and update the configuration through
+    /// [`ConfigManager::apply_config`] instead. Agents with an entry in
+    /// `in_process_agents` are served by that handler rather than their
+    /// configured transport.
+    pub async fn from_config(
+        config: Config,
+        config_path: &str,
+        in_process_agents: HashMap<String, Arc<dyn AgentHandlerV2>>,
+    ) -> Result<Self> {
         config
             .validate()
             .context("Initial configuration validation failed")?;
 
+        // Outbound proxy for control-plane HTTP (ACME, OCSP, discovery)
+        crate::outbound::configure(&config.server.outbound_proxy);
+
+        // Resolver for upstream hostnames
+        crate::dns::configure(config.dns.as_ref());
+
+        // Webhook notifications for security events
+        crate::notifications::configure(config.notifications.as_ref());
+        crate::alerts::configure(config.notifications.as_ref());
+
+        // Chaos faults that can be armed through the admin API
+        crate::chaos::configure(&config.server.chaos);
+
         // Configure global cache storage (must be done before cache is accessed)
         if let Some(ref cache_config) = config.cache {
             info!(
@@ -162,8 +277,7 @@ impl ZentinelProxy {
         }
 
         // Create configuration manager
-        let config_manager =
-            Arc::new(ConfigManager::new(&effective_config_path, config.clone()).await?);
+        let config_manager = Arc::new(ConfigManager::new(config_path, config.clone()).await?);
 
         // Add validators
         config_manager.add_validator(Box::new(RouteValidator)).await;
@@ -181,6 +295,11 @@ impl ZentinelProxy {
         // Build per-listener route matchers for listeners bound to a namespace
         // route set (empty unless any listener references a namespace).
         let listener_matchers = Arc::new(RwLock::new(Self::build_listener_matchers(&config)));
+        let alt_svc = crate::http3::alt_svc_value(&config.listeners);
+
+        // Client IP resolution from trusted proxies' forwarding headers
+        let client_ip_resolver =
+            Arc::new(RwLock::new(ClientIpResolver::new(&config.server.client_ip)));
 
         // Flatten config for namespace/service resources
         let flattened = config.flatten();
@@ -213,6 +332,55 @@ impl ZentinelProxy {
         let scoped_upstream_pools =
             Self::create_scoped_upstream_pools(&flattened, &mut health_check_runner).await?;
 
+        // Service discovery: resolve dynamic membership before serving
+        let discovery_manager = Arc::new(DiscoveryManager::new());
+        Self::register_discoveries(&discovery_manager, &flattened);
+        if discovery_manager.count() > 0 {
+            let initial_refresh = Self::refresh_discovered_upstreams(
+                &discovery_manager,
+                &upstream_pools,
+                &scoped_upstream_pools,
+            );
+            if tokio::time::timeout(Duration::from_secs(10), initial_refresh)
+                .await
+                .is_err()
+            {
+                warn!("Initial service discovery refresh timed out after 10s");
+            }
+        }
+
+        // Create metrics collectors
+        let metrics = Arc::new(zentinel_common::observability::RequestMetrics::new()?);
+        let scoped_metrics =
+            Arc::new(ScopedMetrics::new().context("Failed to create scoped metrics collector")?);
+
+        // Active health check transitions take targets out of (or back into)
+        // load balancing and are exported as per-target gauges
+        {
+            let pools = upstream_pools.clone();
+            let scoped_pools = scoped_upstream_pools.clone();
+            let metrics = metrics.clone();
+            health_check_runner
+                .on_health_change(move |upstream_id, target, healthy| {
+                    metrics.set_upstream_target_health(upstream_id, target, healthy);
+
+                    let pools = pools.clone();
+                    let scoped_pools = scoped_pools.clone();
+                    let upstream_id = upstream_id.to_string();
+                    let target = target.to_string();
+                    tokio::spawn(async move {
+                        let pool = match pools.get(&upstream_id).await {
+                            Some(pool) => Some(pool),
+                            None => scoped_pools.get_by_canonical(&upstream_id).await,
+                        };
+                        if let Some(pool) = pool {
+                            pool.report_target_health(&target, healthy).await;
+                        }
+                    });
+                })
+                .await;
+        }
+
         let health_check_runner = Arc::new(health_check_runner);
 
         // Create passive health checker
@@ -222,15 +390,32 @@ impl ZentinelProxy {
             None, // Will be linked to active health checkers
         ));
 
+        // Spawn supervised agents first so their sockets exist when connecting
+        let agent_supervisor = AgentSupervisor::start(&config.agents);
+        if let Some(ref supervisor) = agent_supervisor {
+            supervisor.wait_ready(AGENT_STARTUP_TIMEOUT).await;
+        }
+
         // Create agent manager (per-agent queue isolation)
-        let agent_manager = Arc::new(AgentManager::new(config.agents.clone()).await?);
+        let agent_manager = Arc::new(
+            AgentManager::with_in_process_agents(config.agents.clone(), in_process_agents).await?,
+        );
+        if let Some(session_store) = &config.session_store {
+            let store = AgentStateStore::new(session_store, &config.agents).await;
+            agent_manager.set_state_store(Arc::new(store)).await;
+        }
+        let audit_store = config
+            .observability
+            .logging
+            .audit_log
+            .as_ref()
+            .and_then(|audit_log| audit_log.store.as_ref())
+            .map(|store_config| Arc::new(AuditStore::new(store_config)));
+        if let Some(store) = &audit_store {
+            agent_manager.set_audit_store(Arc::clone(store));
+        }
         agent_manager.initialize().await?;
 
-        // Create metrics collectors
-        let metrics = Arc::new(zentinel_common::observability::RequestMetrics::new()?);
-        let scoped_metrics =
-            Arc::new(ScopedMetrics::new().context("Failed to create scoped metrics collector")?);
-
         // Create application state
         let app_state = Arc::new(AppState::new(Uuid::new_v4().to_string()));
 
@@ -239,19 +424,88 @@ impl ZentinelProxy {
             Duration::from_secs(30), // Max drain time
         ));
 
+        // Load embedded WASM filters
+        let wasm_filter_manager = Arc::new(Self::initialize_wasm_filters(&config)?);
+
+        // Load schema-validate documents
+        let schema_validate_manager = Arc::new(Self::initialize_schema_validators(&config)?);
+
+        // Load managed rule files
+        let managed_rules_manager = Arc::new(Self::initialize_managed_rules(&config)?);
+
+        // Load ip-access lists (URL sources are fetched by the refresh task)
+        let ip_access_manager = Arc::new(Self::initialize_ip_access(&config)?);
+
+        // Adaptive protection (client profiles are built from traffic)
+        let adaptive_protection_manager = Arc::new(AdaptiveProtectionManager::new());
+        adaptive_protection_manager.reload(&Self::adaptive_protection_configs(&config));
+
+        // Quota counters (shared through the session store when it uses Redis)
+        let quota_manager = Arc::new(QuotaManager::new(
+            QuotaStore::new(config.session_store.as_ref()).await,
+        ));
+        quota_manager.reload(&Self::quota_configs(&config));
+
+        // Provider key pools of inference routes
+        let inference_key_pools = Arc::new(KeyPoolManager::new());
+        inference_key_pools.reload(&Self::inference_key_pool_configs(&config));
+
+        // Load api-key stores
+        let api_key_manager = Arc::new(Self::initialize_api_keys(&config)?);
+
+        // Upstream credentials (OAuth2 tokens are fetched on first use)
+        let upstream_auth_manager = Arc::new(Self::initialize_upstream_auth(&flattened)?);
+
+        // Tenant quotas and drain state
+        let tenant_manager = Arc::new(TenantManager::new(&config));
+
+        // Maintenance flags, restored from the state file if configured
+        let maintenance_manager = Arc::new(MaintenanceManager::new(&config.server.maintenance));
+
+        // Traffic capture (captures are started through the admin handler)
+        let capture_manager = Arc::new(CaptureManager::new(config.server.capture.as_ref()));
+
+        // Global in-flight limit (route limits are created on first use)
+        let concurrency_manager =
+            Arc::new(ConcurrencyManager::new(config.server.concurrency.as_ref()));
+
+        // Staged rollouts start on reload; the monitor compares their cohorts
+        let rollout_manager = Arc::new(RolloutManager::new());
+        tokio::spawn(crate::rollout::run_rollout_monitor(
+            rollout_manager.clone(),
+            config_manager.clone(),
+        ));
+
         // Setup configuration reload subscription
         Self::setup_reload_handler(
             config_manager.clone(),
             route_matcher.clone(),
             listener_matchers.clone(),
+            client_ip_resolver.clone(),
             upstream_pools.clone(),
             scoped_route_matcher.clone(),
             scoped_upstream_pools.clone(),
+            discovery_manager.clone(),
+            wasm_filter_manager.clone(),
+            schema_validate_manager.clone(),
+            managed_rules_manager.clone(),
+            ip_access_manager.clone(),
+            adaptive_protection_manager.clone(),
+            quota_manager.clone(),
+            api_key_manager.clone(),
+            upstream_auth_manager.clone(),
+            agent_manager.clone(),
+            tenant_manager.clone(),
+            maintenance_manager.clone(),
+            capture_manager.clone(),
+            concurrency_manager.clone(),
+            inference_key_pools.clone(),
+            rollout_manager.clone(),
         )
         .await;
 
         // Initialize service type components
-        let (error_handlers, validators, static_servers) =
+        let (error_handlers, block_pages, validators, static_servers) =
             Self::initialize_route_components(&config).await?;
 
         // Create builtin handler state
@@ -303,6 +557,14 @@ impl ZentinelProxy {
             );
         }
 
+        // Keep discovered upstream membership current (discovery may also be
+        // added by a later reload, so the task always runs)
+        Self::spawn_discovery_refresh(
+            discovery_manager,
+            upstream_pools.clone(),
+            scoped_upstream_pools.clone(),
+        );
+
         // Initialize rate limit manager
         let rate_limit_manager = Arc::new(Self::initialize_rate_limiters(&config));
 
@@ -322,16 +584,36 @@ impl ZentinelProxy {
         let geo_filter_manager = Arc::new(Self::initialize_geo_filters(&config));
 
         // Start periodic cleanup task for rate limiters and geo caches
-        Self::spawn_cleanup_task(rate_limit_manager.clone(), geo_filter_manager.clone());
+        Self::spawn_cleanup_task(
+            rate_limit_manager.clone(),
+            geo_filter_manager.clone(),
+            adaptive_protection_manager.clone(),
+            quota_manager.clone(),
+        );
+
+        // Watch agent health, block rates and certificate expiry for
+        // notifications (they may also be enabled by a later reload)
+        crate::notifications::spawn_monitor(agent_manager.clone(), metrics.clone());
 
         // Start geo database file watcher for hot reload
         Self::spawn_geo_database_watcher(geo_filter_manager.clone());
 
+        // Start API key file watcher for hot reload
+        Self::spawn_api_key_watcher(api_key_manager.clone());
+
+        // Start ip-access list refresh
+        Self::spawn_ip_access_refresh_task(ip_access_manager.clone());
+
         // Mark as ready
         app_state.set_ready(true);
 
         // Get trace ID format from config
         let trace_id_format = config.server.trace_id_format;
+        let request_id = config.server.request_id.clone();
+        let default_error_handler = Arc::new(
+            ErrorHandler::new(zentinel_config::ServiceType::Api, None)
+                .with_problem_details(config.server.problem_details.as_ref()),
+        );
 
         // Initialize cache manager
         let cache_manager = Arc::new(Self::initialize_cache_manager(&config));
@@ -351,38 +633,83 @@ impl ZentinelProxy {
             warn!("Failed to initialize TLS metrics: {}", e);
         }
 
+        // Initialize challenge metrics (best-effort, log warning if fails)
+        if let Err(e) = crate::challenge::init_challenge_metrics() {
+            warn!("Failed to initialize challenge metrics: {}", e);
+        }
+
         Ok(Self {
             config_manager,
             route_matcher,
             listener_matchers,
+            alt_svc,
+            client_ip_resolver,
             scoped_route_matcher,
             upstream_pools,
             scoped_upstream_pools,
             agent_manager,
+            agent_supervisor,
             passive_health,
             metrics,
             scoped_metrics,
             app_state,
             reload_coordinator,
             error_handlers,
+            default_error_handler,
+            block_pages,
+            challenge_handler: Arc::new(crate::challenge::ChallengeHandler::new()),
             validators,
             static_servers,
             builtin_state,
+            health_probes: Arc::new(HealthProbes::new()),
             log_manager,
             trace_id_format,
+            request_id,
             health_check_runner,
             rate_limit_manager,
             cache_manager,
             geo_filter_manager,
+            wasm_filter_manager,
+            schema_validate_manager,
+            managed_rules_manager,
+            ip_access_manager,
+            adaptive_protection_manager,
+            quota_manager,
+            api_key_manager,
+            upstream_auth_manager,
+            tenant_manager,
+            maintenance_manager,
+            capture_manager,
+            rollout_manager,
+            audit_store,
+            concurrency_manager,
+            upstream_queues: Arc::new(UpstreamQueueManager::new()),
             inference_rate_limit_manager,
+            inference_key_pools,
             warmth_tracker,
             guardrail_processor,
             // ACME challenge manager - initialized later if ACME is configured
             acme_challenges: None,
             acme_clients: Vec::new(),
+            lifecycle_events: LifecycleEvents::default(),
         })
     }
 
+    /// Agent manager, for shutting agents down after the server stops
+    pub fn agent_manager(&self) -> Arc<AgentManager> {
+        self.agent_manager.clone()
+    }
+
+    /// Health probe state, shared with the `readyz` and `healthz` handlers
+    pub fn health_probes(&self) -> Arc<HealthProbes> {
+        self.health_probes.clone()
+    }
+
+    /// Upstream pools, replaced in place on reload
+    pub fn upstream_pools(&self) -> Registry<UpstreamPool> {
+        self.upstream_pools.clone()
+    }
+
     /// Shared HTTP cache statistics.
     ///
     /// Exposed so the standalone metrics server can include cache counters in
@@ -391,8 +718,8 @@ impl ZentinelProxy {
         self.cache_manager.stats()
     }
 
-    /// Build per-listener route matchers for listeners that reference a
-    /// namespace route set.
+    /// Build per-listener route sets for listeners that reference a namespace
+    /// route set, directly or through their SNI certificates.
     ///
     /// Listeners without a `namespace` reference are omitted (they fall back to
     /// the global matcher at request time). Unknown namespace references are
@@ -400,43 +727,95 @@ impl ZentinelProxy {
     /// so this only guards against a reload racing a bad config.
     fn build_listener_matchers(
         config: &zentinel_config::Config,
-    ) -> HashMap<String, Arc<RouteMatcher>> {
-        let mut matchers = HashMap::new();
-        for listener in &config.listeners {
-            let Some(ns_id) = listener.namespace.as_ref() else {
-                continue;
-            };
-            let Some(ns) = config.namespaces.iter().find(|n| &n.id == ns_id) else {
+    ) -> HashMap<String, ListenerRoutes> {
+        // Listeners and SNI certificates sharing a namespace share its matcher
+        let mut compiled: HashMap<&str, Option<Arc<RouteMatcher>>> = HashMap::new();
+        let mut namespace_matcher = |listener_id: &str, ns_id: &str| -> Option<Arc<RouteMatcher>> {
+            if let Some(matcher) = compiled.get(ns_id) {
+                return matcher.clone();
+            }
+            let Some(ns) = config.namespaces.iter().find(|n| n.id == ns_id) else {
                 warn!(
-                    listener_id = %listener.id,
+                    listener_id = %listener_id,
                     namespace = %ns_id,
                     "Listener references unknown namespace; no routes will match on this listener"
                 );
-                continue;
+                return None;
             };
-            match RouteMatcher::with_cache_size(
+            let matcher = match RouteMatcher::with_cache_size(
                 ns.routes.clone(),
                 None,
                 config.server.route_cache_size,
             ) {
                 Ok(matcher) => {
                     info!(
-                        listener_id = %listener.id,
-                        address = %listener.address,
                         namespace = %ns_id,
                         routes = ns.routes.len(),
-                        "Listener bound to namespace route set"
+                        "Compiled namespace route set for listener"
                     );
-                    matchers.insert(listener.address.clone(), Arc::new(matcher));
+                    Some(Arc::new(matcher))
                 }
                 Err(e) => {
                     error!(
-                        listener_id = %listener.id,
+                        listener_id = %listener_id,
                         namespace = %ns_id,
                         error = %e,
                         "Failed to compile route matcher for listener namespace"
                     );
+                    None
+                }
+            };
+            compiled.insert(ns.id.as_str(), matcher.clone());
+            matcher
+        };
+
+        let mut matchers = HashMap::new();
+        for listener in &config.listeners {
+            let default = listener
+                .namespace
+                .as_deref()
+                .and_then(|ns_id| namespace_matcher(&listener.id, ns_id));
+            if let (Some(ns_id), Some(_)) = (&listener.namespace, &default) {
+                info!(
+                    listener_id = %listener.id,
+                    address = %listener.address,
+                    namespace = %ns_id,
+                    "Listener bound to namespace route set"
+                );
+            }
+
+            let mut routes = ListenerRoutes::new(default);
+            let sni_certs = listener
+                .tls
+                .iter()
+                .flat_map(|tls| tls.additional_certs.iter());
+            for sni in sni_certs {
+                let Some(ns_id) = sni.namespace.as_deref() else {
+                    continue;
+                };
+                let Some(matcher) = namespace_matcher(&listener.id, ns_id) else {
+                    continue;
+                };
+                let hostnames: Vec<&String> = sni
+                    .hostnames
+                    .iter()
+                    .chain(&sni.priority_hostnames)
+                    .chain(sni.acme.iter().flat_map(|acme| acme.domains.iter()))
+                    .collect();
+                for hostname in &hostnames {
+                    routes.bind(hostname, Arc::clone(&matcher));
                 }
+                info!(
+                    listener_id = %listener.id,
+                    address = %listener.address,
+                    namespace = %ns_id,
+                    hostnames = ?hostnames,
+                    "SNI hostnames bound to namespace route set"
+                );
+            }
+
+            if routes.default.is_some() || routes.has_sni_routes() {
+                matchers.insert(listener.address.clone(), routes);
             }
         }
         matchers
@@ -446,13 +825,32 @@ impl ZentinelProxy {
     async fn setup_reload_handler(
         config_manager: Arc<ConfigManager>,
         route_matcher: Arc<RwLock<RouteMatcher>>,
-        listener_matchers: Arc<RwLock<HashMap<String, Arc<RouteMatcher>>>>,
+        listener_matchers: Arc<RwLock<HashMap<String, ListenerRoutes>>>,
+        client_ip_resolver: Arc<RwLock<ClientIpResolver>>,
         upstream_pools: Registry<UpstreamPool>,
         scoped_route_matcher: Arc<tokio::sync::RwLock<ScopedRouteMatcher>>,
         scoped_upstream_pools: ScopedRegistry<UpstreamPool>,
+        discovery_manager: Arc<DiscoveryManager>,
+        wasm_filter_manager: Arc<WasmFilterManager>,
+        schema_validate_manager: Arc<SchemaValidateManager>,
+        managed_rules_manager: Arc<ManagedRulesManager>,
+        ip_access_manager: Arc<IpAccessManager>,
+        adaptive_protection_manager: Arc<AdaptiveProtectionManager>,
+        quota_manager: Arc<QuotaManager>,
+        api_key_manager: Arc<ApiKeyManager>,
+        upstream_auth_manager: Arc<UpstreamAuthManager>,
+        agent_manager: Arc<AgentManager>,
+        tenant_manager: Arc<TenantManager>,
+        maintenance_manager: Arc<MaintenanceManager>,
+        capture_manager: Arc<CaptureManager>,
+        concurrency_manager: Arc<ConcurrencyManager>,
+        inference_key_pools: Arc<KeyPoolManager>,
+        rollout_manager: Arc<RolloutManager>,
     ) {
         let mut reload_rx = config_manager.subscribe();
         let config_manager_clone = config_manager.clone();
+        // Config the running upstream pools were built from
+        let mut previous_config = config_manager.current();
 
         tokio::spawn(async move {
             loop {
@@ -473,11 +871,19 @@ impl ZentinelProxy {
                     // Update route matcher FIRST (most critical for traffic)
                     match RouteMatcher::new(new_config.routes.clone(), None) {
                         Ok(new_matcher) => {
-                            *route_matcher.write() = new_matcher;
+                            let replaced =
+                                std::mem::replace(&mut *route_matcher.write(), new_matcher);
                             info!(
                                 routes = new_config.routes.len(),
                                 "Global routes reloaded successfully"
                             );
+                            // With a rollout configured, the replaced table
+                            // keeps serving the stable cohort
+                            rollout_manager.stage(
+                                replaced,
+                                Arc::clone(&previous_config),
+                                &new_config,
+                            );
                         }
                         Err(e) => {
                             error!(error = %e, "Failed to compile route matcher");
@@ -487,6 +893,99 @@ impl ZentinelProxy {
                     // Rebuild per-listener (namespace-bound) route matchers
                     *listener_matchers.write() = Self::build_listener_matchers(&new_config);
 
+                    // Trusted proxies and forwarding headers
+                    *client_ip_resolver.write() =
+                        ClientIpResolver::new(&new_config.server.client_ip);
+
+                    // Outbound proxy for clients created from now on
+                    crate::outbound::configure(&new_config.server.outbound_proxy);
+
+                    // Upstream name resolution (a changed config drops the DNS cache)
+                    crate::dns::configure(new_config.dns.as_ref());
+
+                    // Webhooks and thresholds for notifications
+                    crate::notifications::configure(new_config.notifications.as_ref());
+
+                    // Alert rules (unchanged rules keep their state)
+                    crate::alerts::configure(new_config.notifications.as_ref());
+
+                    // Chaos faults (armed faults are kept unless removed)
+                    crate::chaos::configure(&new_config.server.chaos);
+
+                    // Tenant quotas (in-flight counts and drain state are kept)
+                    tenant_manager.reload(&new_config);
+
+                    // Maintenance page and bypass rules (flags are kept)
+                    maintenance_manager.reload(&new_config.server.maintenance);
+
+                    // Capture directory and redaction rules (a running capture is kept)
+                    capture_manager.reload(new_config.server.capture.as_ref());
+
+                    // Global in-flight limit (in-flight count is kept)
+                    concurrency_manager.reload(new_config.server.concurrency.as_ref());
+
+                    // Reload WASM filters off the runtime: compiling is CPU-bound
+                    let wasm_filters = Self::wasm_filter_configs(&new_config);
+                    let manager = Arc::clone(&wasm_filter_manager);
+                    if let Err(e) =
+                        tokio::task::spawn_blocking(move || manager.reload(&wasm_filters)).await
+                    {
+                        error!(error = %e, "WASM filter reload task failed");
+                    }
+
+                    // Reload schema-validate documents (edited files are re-read)
+                    let schema_filters = Self::schema_validate_configs(&new_config);
+                    let manager = Arc::clone(&schema_validate_manager);
+                    if let Err(e) =
+                        tokio::task::spawn_blocking(move || manager.reload(&schema_filters)).await
+                    {
+                        error!(error = %e, "Schema validation reload task failed");
+                    }
+
+                    // Reload managed rule files (edited files are re-read)
+                    let rules_filters = Self::managed_rules_configs(&new_config);
+                    let manager = Arc::clone(&managed_rules_manager);
+                    if let Err(e) =
+                        tokio::task::spawn_blocking(move || manager.reload(&rules_filters)).await
+                    {
+                        error!(error = %e, "Managed rules reload task failed");
+                    }
+
+                    // Reload ip-access lists (URL sources keep their entries)
+                    let ip_access_filters = Self::ip_access_configs(&new_config);
+                    let manager = Arc::clone(&ip_access_manager);
+                    if let Err(e) =
+                        tokio::task::spawn_blocking(move || manager.reload(&ip_access_filters))
+                            .await
+                    {
+                        error!(error = %e, "IP access reload task failed");
+                    }
+
+                    // Adaptive protection (unchanged filters keep their client profiles)
+                    adaptive_protection_manager
+                        .reload(&Self::adaptive_protection_configs(&new_config));
+
+                    // Quota filters (counters are kept)
+                    quota_manager.reload(&Self::quota_configs(&new_config));
+
+                    // Inference key pools (kept members keep usage and cooldowns)
+                    inference_key_pools.reload(&Self::inference_key_pool_configs(&new_config));
+
+                    // Reload API key files (runtime revocations are kept)
+                    let api_key_filters = Self::api_key_configs(&new_config);
+                    let manager = Arc::clone(&api_key_manager);
+                    if let Err(e) =
+                        tokio::task::spawn_blocking(move || manager.reload(&api_key_filters)).await
+                    {
+                        error!(error = %e, "API key reload task failed");
+                    }
+
+                    // Upstream credentials (unchanged ones keep their cached tokens)
+                    upstream_auth_manager.reload(&Self::upstream_auth_configs(&flattened));
+
+                    // Agents (unchanged agents keep their connections)
+                    agent_manager.reload(new_config.agents.clone()).await;
+
                     // Update scoped route matcher
                     if let Err(e) = scoped_route_matcher
                         .write()
@@ -498,10 +997,23 @@ impl ZentinelProxy {
                     }
 
                     // Update upstream pools with timeout to avoid blocking
-                    // the reload handler on DNS resolution / connection attempts
+                    // the reload handler on DNS resolution / connection attempts.
+                    // Pools whose config is unchanged are kept, so reloading
+                    // one tenant leaves other tenants' connections and load
+                    // balancer state alone.
+                    let previous_flattened = previous_config.flatten();
                     let pool_update = async {
                         let mut new_pools = HashMap::new();
                         for (upstream_id, upstream_config) in &new_config.upstreams {
+                            if same_upstream(
+                                previous_config.upstreams.get(upstream_id),
+                                upstream_config,
+                            ) {
+                                if let Some(pool) = upstream_pools.get(upstream_id).await {
+                                    new_pools.insert(upstream_id.clone(), pool);
+                                    continue;
+                                }
+                            }
                             let mut config_with_id = upstream_config.clone();
                             config_with_id.id = upstream_id.clone();
                             match UpstreamPool::new(config_with_id).await {
@@ -513,17 +1025,48 @@ impl ZentinelProxy {
                                 }
                             }
                         }
+                        // Upstreams removed by a staged configuration are
+                        // still used by the stable cohort
+                        for upstream_id in rollout_manager.stable_upstreams() {
+                            if !new_pools.contains_key(&upstream_id) {
+                                if let Some(pool) = upstream_pools.get(&upstream_id).await {
+                                    new_pools.insert(upstream_id, pool);
+                                }
+                            }
+                        }
                         new_pools
                     };
 
                     match tokio::time::timeout(Duration::from_secs(10), pool_update).await {
                         Ok(new_pools) => {
-                            let old_pools = upstream_pools.replace(new_pools).await;
+                            let mut old_pools = upstream_pools.replace(new_pools.clone()).await;
+                            retain_replaced(&mut old_pools, &new_pools);
 
                             // Update scoped upstream pools
-                            let new_scoped_pools = Self::build_scoped_pools_list(&flattened).await;
-                            let old_scoped_pools =
+                            let new_scoped_pools = Self::build_scoped_pools_list(
+                                &flattened,
+                                &previous_flattened,
+                                &scoped_upstream_pools,
+                            )
+                            .await;
+                            let kept: HashMap<_, _> = new_scoped_pools
+                                .iter()
+                                .map(|(qid, pool, _)| (qid.canonical(), Arc::clone(pool)))
+                                .collect();
+                            let mut old_scoped_pools =
                                 scoped_upstream_pools.replace_all(new_scoped_pools).await;
+                            retain_replaced(&mut old_scoped_pools, &kept);
+                            previous_config = Arc::clone(&new_config);
+
+                            // New pools start from static targets; re-apply
+                            // discovered membership right away
+                            Self::register_discoveries(&discovery_manager, &flattened);
+                            Self::refresh_discovered_upstreams(
+                                &discovery_manager,
+                                &upstream_pools,
+                                &scoped_upstream_pools,
+                            )
+                            .await;
 
                             // Track drain lifecycle for old pools
                             tokio::spawn(async move {
@@ -541,6 +1084,111 @@ impl ZentinelProxy {
         });
     }
 
+    /// Register service discovery for every upstream that configures it,
+    /// dropping registrations for upstreams that no longer do
+    fn register_discoveries(manager: &DiscoveryManager, flattened: &FlattenedConfig) {
+        let mut upstream_ids = HashSet::new();
+        for (qid, upstream_config) in &flattened.upstreams {
+            let Some(discovery) = &upstream_config.discovery else {
+                continue;
+            };
+            let upstream_id = qid.canonical();
+            match manager.register(&upstream_id, DiscoveryConfig::from(discovery)) {
+                Ok(()) => {
+                    upstream_ids.insert(upstream_id);
+                }
+                Err(e) => {
+                    error!(
+                        upstream_id = %upstream_id,
+                        error = %e,
+                        "Failed to register service discovery"
+                    );
+                }
+            }
+        }
+        manager.retain(&upstream_ids);
+    }
+
+    /// Poll registered discoveries and apply membership changes to the pools
+    ///
+    /// Global upstreams live in both registries (the scoped one under their
+    /// canonical ID), so both pools are updated. An empty or failed result
+    /// keeps the current membership.
+    async fn refresh_discovered_upstreams(
+        manager: &DiscoveryManager,
+        upstream_pools: &Registry<UpstreamPool>,
+        scoped_upstream_pools: &ScopedRegistry<UpstreamPool>,
+    ) {
+        for upstream_id in manager.upstream_ids() {
+            let backends = match manager.discover(&upstream_id).await {
+                Some(Ok((backends, _))) => backends,
+                Some(Err(e)) => {
+                    debug!(
+                        upstream_id = %upstream_id,
+                        error = %e,
+                        "Service discovery failed, keeping current targets"
+                    );
+                    continue;
+                }
+                None => continue,
+            };
+
+            let targets = backends_to_targets(&backends);
+            if targets.is_empty() {
+                debug!(
+                    upstream_id = %upstream_id,
+                    "Service discovery returned no targets, keeping current targets"
+                );
+                continue;
+            }
+
+            let pools = [
+                upstream_pools.get(&upstream_id).await,
+                scoped_upstream_pools.get_by_canonical(&upstream_id).await,
+            ];
+            for pool in pools.into_iter().flatten() {
+                if let Err(e) = pool.update_targets(targets.clone()).await {
+                    error!(
+                        upstream_id = %upstream_id,
+                        error = %e,
+                        "Failed to apply discovered targets"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Spawn the background task that keeps discovered membership current
+    ///
+    /// Each discovery caches its result for its own refresh interval, so the
+    /// short tick only bounds how quickly a due refresh is picked up.
+    fn spawn_discovery_refresh(
+        manager: Arc<DiscoveryManager>,
+        upstream_pools: Registry<UpstreamPool>,
+        scoped_upstream_pools: ScopedRegistry<UpstreamPool>,
+    ) {
+        const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // First tick completes immediately; the initial refresh already ran
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                Self::refresh_discovered_upstreams(
+                    &manager,
+                    &upstream_pools,
+                    &scoped_upstream_pools,
+                )
+                .await;
+            }
+        });
+
+        info!("Started service discovery refresh task");
+    }
+
     /// Create scoped upstream pools from flattened config
     async fn create_scoped_upstream_pools(
         flattened: &FlattenedConfig,
@@ -592,18 +1240,28 @@ impl ZentinelProxy {
     /// Build list of scoped pools for atomic replacement
     async fn build_scoped_pools_list(
         flattened: &FlattenedConfig,
+        previous: &FlattenedConfig,
+        current: &ScopedRegistry<UpstreamPool>,
     ) -> Vec<(QualifiedId, Arc<UpstreamPool>, bool)> {
         let mut result = Vec::new();
 
         for (qid, upstream_config) in &flattened.upstreams {
+            let is_exported = flattened
+                .exported_upstreams
+                .contains_key(&upstream_config.id);
+
+            if same_upstream(previous.upstreams.get(qid), upstream_config) {
+                if let Some(pool) = current.get(qid).await {
+                    result.push((qid.clone(), pool, is_exported));
+                    continue;
+                }
+            }
+
             let mut config_with_id = upstream_config.clone();
             config_with_id.id = qid.canonical();
 
             match UpstreamPool::new(config_with_id).await {
                 Ok(pool) => {
-                    let is_exported = flattened
-                        .exported_upstreams
-                        .contains_key(&upstream_config.id);
                     result.push((qid.clone(), Arc::new(pool), is_exported));
                 }
                 Err(e) => {
@@ -619,15 +1277,18 @@ impl ZentinelProxy {
         result
     }
 
-    /// Initialize route-specific components (error handlers, validators, static servers)
+    /// Initialize route-specific components (error handlers, block pages, validators,
+    /// static servers)
     async fn initialize_route_components(
         config: &Config,
     ) -> Result<(
         Registry<ErrorHandler>,
+        Registry<BlockPageRenderer>,
         Registry<SchemaValidator>,
         Registry<StaticFileServer>,
     )> {
         let mut error_handlers_map = HashMap::new();
+        let mut block_pages_map = HashMap::new();
         let mut validators_map = HashMap::new();
         let mut static_servers_map = HashMap::new();
 
@@ -640,15 +1301,24 @@ impl ZentinelProxy {
             // Initialize error handler for each route
             if let Some(ref error_config) = route.error_pages {
                 let handler =
-                    ErrorHandler::new(route.service_type.clone(), Some(error_config.clone()));
+                    ErrorHandler::new(route.service_type.clone(), Some(error_config.clone()))
+                        .with_problem_details(config.server.problem_details.as_ref());
                 error_handlers_map.insert(route.id.clone(), Arc::new(handler));
                 debug!("Initialized error handler for route: {}", route.id);
             } else {
                 // Use default error handler for the service type
-                let handler = ErrorHandler::new(route.service_type.clone(), None);
+                let handler = ErrorHandler::new(route.service_type.clone(), None)
+                    .with_problem_details(config.server.problem_details.as_ref());
                 error_handlers_map.insert(route.id.clone(), Arc::new(handler));
             }
 
+            // Initialize agent block page renderer if configured
+            if let Some(ref block_page) = route.policies.block_page {
+                let renderer = BlockPageRenderer::new(block_page);
+                block_pages_map.insert(route.id.clone(), Arc::new(renderer));
+                debug!("Initialized block page renderer for route: {}", route.id);
+            }
+
             // Initialize schema validator for API routes
             if route.service_type == zentinel_config::ServiceType::Api {
                 if let Some(ref api_schema) = route.api_schema {
@@ -684,14 +1354,36 @@ impl ZentinelProxy {
 
         Ok((
             Registry::from_map(error_handlers_map),
+            Registry::from_map(block_pages_map),
             Registry::from_map(validators_map),
             Registry::from_map(static_servers_map),
         ))
     }
 
-    /// Get or generate trace ID from session
+    /// Get or generate trace ID from session, following the request ID policy
     pub(super) fn get_trace_id(&self, session: &pingora::proxy::Session) -> String {
-        http_helpers::get_or_create_trace_id(session, self.trace_id_format)
+        let (id, source) = trace_id::resolve_request_id(
+            &session.req_header().headers,
+            self.trace_id_format,
+            &self.request_id,
+        );
+        trace_id::record_request_id(source);
+        if source == RequestIdSource::Rejected {
+            debug!(
+                correlation_id = %id,
+                "Replaced malformed incoming request ID"
+            );
+        }
+        id
+    }
+
+    /// Add the request ID to a response, unless echoing it is disabled
+    pub(super) fn echo_request_id(&self, response: &mut ResponseHeader, request_id: &str) {
+        if self.request_id.echo {
+            response
+                .insert_header(self.request_id.response_header.clone(), request_id)
+                .ok();
+        }
     }
 
     /// Initialize rate limiters from configuration
@@ -800,6 +1492,21 @@ impl ZentinelProxy {
         manager
     }
 
+    /// Inference blocks with a key pool, by route ID
+    fn inference_key_pool_configs(
+        config: &Config,
+    ) -> HashMap<String, zentinel_config::InferenceConfig> {
+        config
+            .routes
+            .iter()
+            .filter_map(|route| {
+                let inference = route.inference.as_ref()?;
+                inference.key_pool.as_ref()?;
+                Some((route.id.clone(), inference.clone()))
+            })
+            .collect()
+    }
+
     /// Initialize cache manager from configuration
     fn initialize_cache_manager(config: &Config) -> CacheManager {
         let manager = CacheManager::new();
@@ -841,6 +1548,7 @@ impl ZentinelProxy {
                     cacheable_status_codes: rc.cacheable_status_codes.clone(),
                     exclude_extensions: rc.exclude_extensions.clone(),
                     exclude_paths,
+                    coalesce: rc.coalesce.clone(),
                 }
             } else {
                 match route.service_type {
@@ -926,10 +1634,247 @@ impl ZentinelProxy {
         manager
     }
 
-    /// Spawn background task to periodically clean up idle rate limiters and expired geo caches
+    /// Load the WASM filters defined in the configuration
+    ///
+    /// Unlike geo filters, a WASM filter that fails to load aborts startup:
+    /// running without it would silently skip the filter's checks.
+    fn initialize_wasm_filters(config: &Config) -> Result<WasmFilterManager> {
+        let manager = WasmFilterManager::new();
+
+        for (filter_id, wasm_filter) in Self::wasm_filter_configs(config) {
+            let module = wasm_filter.module.clone();
+            manager
+                .register_filter(&filter_id, wasm_filter)
+                .with_context(|| format!("Failed to load WASM filter '{filter_id}'"))?;
+            info!(
+                filter_id = %filter_id,
+                module = %module,
+                "Loaded WASM filter"
+            );
+        }
+
+        let filter_ids = manager.filter_ids();
+        if !filter_ids.is_empty() {
+            info!(
+                filter_count = filter_ids.len(),
+                filter_ids = ?filter_ids,
+                "Embedded WASM filters initialized"
+            );
+        }
+
+        Ok(manager)
+    }
+
+    /// Load the documents of the schema-validate filters in the configuration
+    ///
+    /// A document that fails to load aborts startup, like a WASM filter.
+    fn initialize_schema_validators(config: &Config) -> Result<SchemaValidateManager> {
+        let manager = SchemaValidateManager::new();
+
+        for (filter_id, filter) in Self::schema_validate_configs(config) {
+            manager
+                .register_filter(&filter_id, &filter)
+                .with_context(|| format!("Failed to load schema for filter '{filter_id}'"))?;
+            info!(
+                filter_id = %filter_id,
+                schema = %filter.schema,
+                "Loaded schema-validate document"
+            );
+        }
+
+        Ok(manager)
+    }
+
+    /// Schema-validate filter definitions by filter ID
+    fn schema_validate_configs(
+        config: &Config,
+    ) -> HashMap<String, zentinel_config::SchemaValidateFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::SchemaValidate(s) => Some((id.clone(), s.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Load the rule files of the managed-rules filters in the configuration
+    ///
+    /// A rule file that fails to parse aborts startup.
+    fn initialize_managed_rules(config: &Config) -> Result<ManagedRulesManager> {
+        let manager = ManagedRulesManager::new();
+
+        for (filter_id, filter) in Self::managed_rules_configs(config) {
+            manager
+                .register_filter(&filter_id, &filter)
+                .with_context(|| format!("Failed to load rules for filter '{filter_id}'"))?;
+            if let Some(rule_set) = manager.get(&filter_id) {
+                info!(
+                    filter_id = %filter_id,
+                    rules = %filter.rules,
+                    loaded = rule_set.rule_count(),
+                    skipped = rule_set.skipped_count(),
+                    "Loaded managed rules"
+                );
+            }
+        }
+
+        Ok(manager)
+    }
+
+    /// Managed-rules filter definitions by filter ID
+    fn managed_rules_configs(
+        config: &Config,
+    ) -> HashMap<String, zentinel_config::ManagedRulesFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::ManagedRules(m) => Some((id.clone(), m.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Load the lists of the ip-access filters in the configuration
+    ///
+    /// A list file that fails to load aborts startup.
+    fn initialize_ip_access(config: &Config) -> Result<IpAccessManager> {
+        let manager = IpAccessManager::new();
+
+        for (filter_id, filter) in Self::ip_access_configs(config) {
+            manager
+                .register_filter(&filter_id, &filter)
+                .with_context(|| format!("Failed to load IP lists for filter '{filter_id}'"))?;
+            if let Some(list) = manager.get(&filter_id) {
+                info!(
+                    filter_id = %filter_id,
+                    action = ?filter.action,
+                    entries = list.entry_count(),
+                    sources = filter.sources.len(),
+                    "Loaded ip-access filter"
+                );
+            }
+        }
+
+        Ok(manager)
+    }
+
+    /// IP access filter definitions by filter ID
+    fn ip_access_configs(config: &Config) -> HashMap<String, zentinel_config::IpAccessFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::IpAccess(f) => Some((id.clone(), f.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Adaptive-protection filter definitions by filter ID
+    fn adaptive_protection_configs(
+        config: &Config,
+    ) -> HashMap<String, zentinel_config::AdaptiveProtectionFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::AdaptiveProtection(f) => Some((id.clone(), f.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Quota filter definitions by filter ID
+    fn quota_configs(config: &Config) -> HashMap<String, zentinel_config::QuotaFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::Quota(f) => Some((id.clone(), f.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Load the key files of the api-key filters in the configuration
+    ///
+    /// A key file that fails to load aborts startup.
+    fn initialize_api_keys(config: &Config) -> Result<ApiKeyManager> {
+        let manager = ApiKeyManager::new();
+
+        for (filter_id, filter) in Self::api_key_configs(config) {
+            manager
+                .register_filter(&filter_id, &filter)
+                .with_context(|| format!("Failed to load API keys for filter '{filter_id}'"))?;
+            info!(
+                filter_id = %filter_id,
+                key_file = %filter.key_file,
+                "Loaded API key file"
+            );
+        }
+
+        Ok(manager)
+    }
+
+    /// Create the credentials of upstreams with an `auth` block
+    fn initialize_upstream_auth(flattened: &FlattenedConfig) -> Result<UpstreamAuthManager> {
+        let manager = UpstreamAuthManager::new();
+
+        for (upstream_id, auth) in Self::upstream_auth_configs(flattened) {
+            manager.register(&upstream_id, &auth).with_context(|| {
+                format!("Failed to set up credentials for upstream '{upstream_id}'")
+            })?;
+            info!(upstream_id = %upstream_id, "Upstream credentials configured");
+        }
+
+        Ok(manager)
+    }
+
+    /// Upstream `auth` blocks by canonical upstream ID
+    fn upstream_auth_configs(
+        flattened: &FlattenedConfig,
+    ) -> HashMap<String, zentinel_config::UpstreamAuthConfig> {
+        flattened
+            .upstreams
+            .iter()
+            .filter_map(|(qid, upstream)| Some((qid.canonical(), upstream.auth.clone()?)))
+            .collect()
+    }
+
+    /// API key filter definitions by filter ID
+    fn api_key_configs(config: &Config) -> HashMap<String, zentinel_config::ApiKeyFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::ApiKey(f) => Some((id.clone(), f.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// WASM filter definitions by filter ID
+    fn wasm_filter_configs(config: &Config) -> HashMap<String, zentinel_config::WasmFilter> {
+        config
+            .filters
+            .iter()
+            .filter_map(|(id, fc)| match &fc.filter {
+                zentinel_config::Filter::Wasm(wasm) => Some((id.clone(), wasm.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Spawn background task to periodically clean up idle rate limiters, expired geo caches,
+    /// idle adaptive-protection clients and expired quota counters
     fn spawn_cleanup_task(
         rate_limit_manager: Arc<RateLimitManager>,
         geo_filter_manager: Arc<GeoFilterManager>,
+        adaptive_protection_manager: Arc<AdaptiveProtectionManager>,
+        quota_manager: Arc<QuotaManager>,
     ) {
         // Cleanup interval: 5 minutes
         const CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
@@ -948,6 +1893,12 @@ impl ZentinelProxy {
                 // Clean up expired geo filter caches
                 geo_filter_manager.clear_expired_caches();
 
+                // Forget clients idle for a whole adaptive-protection window
+                adaptive_protection_manager.cleanup();
+
+                // Drop in-memory quota counters of past periods
+                quota_manager.cleanup();
+
                 debug!("Periodic cleanup completed");
             }
         });
@@ -992,6 +1943,93 @@ impl ZentinelProxy {
             }
         }
     }
+
+    /// Spawn background task to refresh ip-access list sources
+    fn spawn_ip_access_refresh_task(ip_access_manager: Arc<IpAccessManager>) {
+        let client = match crate::outbound::client_builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    "Failed to create HTTP client, ip-access list refresh disabled"
+                );
+                return;
+            }
+        };
+
+        tokio::spawn(async move {
+            // First tick completes immediately, fetching URL sources at startup
+            let mut interval = tokio::time::interval(crate::ip_access::REFRESH_TICK);
+            loop {
+                interval.tick().await;
+                ip_access_manager.refresh_due(&client).await;
+            }
+        });
+
+        debug!("Started ip-access list refresh task");
+    }
+
+    /// Spawn background task to watch API key files for changes
+    fn spawn_api_key_watcher(api_key_manager: Arc<ApiKeyManager>) {
+        match api_key_manager.start_watching() {
+            Ok(mut rx) => {
+                tokio::spawn(async move {
+                    const DEBOUNCE_MS: u64 = 500;
+
+                    while let Some(path) = rx.recv().await {
+                        // Debounce rapid changes (e.g., temp file then rename)
+                        tokio::time::sleep(Duration::from_millis(DEBOUNCE_MS)).await;
+
+                        // Several key files can change during the debounce
+                        let mut changed = std::collections::HashSet::from([path]);
+                        while let Ok(path) = rx.try_recv() {
+                            changed.insert(path);
+                        }
+
+                        let manager = Arc::clone(&api_key_manager);
+                        let _ = tokio::task::spawn_blocking(move || {
+                            for path in &changed {
+                                manager.handle_change(path);
+                            }
+                        })
+                        .await;
+                    }
+                });
+
+                info!("Started API key file watcher");
+            }
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    "Failed to start API key file watcher, auto-reload disabled"
+                );
+            }
+        }
+    }
+}
+
+/// Whether an upstream's config is unchanged since the previous reload
+///
+/// `UpstreamConfig` has no `PartialEq`, so the serialized forms are compared.
+fn same_upstream(previous: Option<&UpstreamConfig>, current: &UpstreamConfig) -> bool {
+    previous.is_some_and(|previous| {
+        matches!(
+            (serde_json::to_value(previous), serde_json::to_value(current)),
+            (Ok(a), Ok(b)) if a == b
+        )
+    })
+}
+
+/// Keep only the old pools that were actually replaced, so pools carried
+/// over to the new registry are not drained
+fn retain_replaced(
+    old: &mut HashMap<String, Arc<UpstreamPool>>,
+    new: &HashMap<String, Arc<UpstreamPool>>,
+) {
+    old.retain(|id, pool| !new.get(id).is_some_and(|kept| Arc::ptr_eq(kept, pool)));
 }
 
 #[cfg(test)]
@@ -1039,6 +2077,7 @@ mod listener_matcher_tests {
         assert!(!matchers.contains_key("0.0.0.0:8080"));
         let admin = matchers
             .get("127.0.0.1:9000")
+            .and_then(|routes| routes.matcher_for(None))
             .expect("admin listener bound to namespace");
 
         // Isolated: the admin listener serves the namespace route...
@@ -1077,4 +2116,62 @@ mod listener_matcher_tests {
         let matchers = ZentinelProxy::build_listener_matchers(&config);
         assert!(matchers.is_empty());
     }
+
+    #[test]
+    fn sni_certificates_select_namespace_route_sets() {
+        let kdl = r#"
+            schema-version "1.0"
+            system { worker-threads 0 }
+            listeners {
+                listener "edge" {
+                    address "0.0.0.0:8443"
+                    protocol "https"
+                    tls {
+                        cert-file "/tmp/default.crt"
+                        key-file "/tmp/default.key"
+                        sni {
+                            hostnames "*.tenant-a.example.com"
+                            cert-file "/tmp/tenant-a.crt"
+                            key-file "/tmp/tenant-a.key"
+                            namespace "ops"
+                        }
+                    }
+                }
+            }
+            routes {
+                route "api" {
+                    matches { path-prefix "/api" }
+                    upstream "backend"
+                }
+            }
+            upstreams {
+                upstream "backend" { target "127.0.0.1:3000" }
+            }
+            namespace "ops" {
+                routes {
+                    route "metrics" {
+                        matches { path "/metrics" }
+                        service-type "builtin"
+                        builtin-handler "metrics"
+                    }
+                }
+            }
+        "#;
+        let config = zentinel_config::Config::from_kdl(kdl).expect("config parses");
+        let matchers = ZentinelProxy::build_listener_matchers(&config);
+        let edge = matchers
+            .get("0.0.0.0:8443")
+            .expect("listener with SNI route set");
+
+        let tenant = edge
+            .matcher_for(Some("app.tenant-a.example.com"))
+            .expect("tenant hostname bound to namespace");
+        assert!(tenant
+            .match_request(&RequestInfo::new("GET", "/metrics", "x"))
+            .is_some());
+
+        // Other server names fall back to the global routes
+        assert!(edge.matcher_for(Some("other.example.com")).is_none());
+        assert!(edge.matcher_for(None).is_none());
+    }
 }
@@ -0,0 +1,235 @@
+//! Per-SNI route set selection for TLS listeners.
+//!
+//! A listener may bind its SNI certificates to namespace route sets, so a
+//! single TLS endpoint can serve several tenants with isolated route tables.
+//! Hostname patterns follow the certificate resolver: exact names first, then
+//! `*.domain` wildcards, then the listener's own route set.
+//!
+//! The route set is selected by the server name from the TLS handshake, as
+//! recorded by the HTTPS acceptor or the HTTP/3 frontend. The `Host` header
+//! is client-controlled and never selects a tenant's route set.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::client_ip::proxy_protocol::ProxiedConnection;
+use crate::routing::RouteMatcher;
+
+/// Route sets selectable on a single listener.
+#[derive(Default)]
+pub(crate) struct ListenerRoutes {
+    /// Route set for the listener itself (`None` = global routes)
+    pub(crate) default: Option<Arc<RouteMatcher>>,
+    /// Route sets for exact server names (lowercase)
+    pub(crate) exact: HashMap<String, Arc<RouteMatcher>>,
+    /// Route sets for wildcard patterns, keyed by the domain after `*.`
+    pub(crate) wildcard: HashMap<String, Arc<RouteMatcher>>,
+}
+
+impl ListenerRoutes {
+    /// Route set bound to the listener itself.
+    pub(crate) fn new(default: Option<Arc<RouteMatcher>>) -> Self {
+        Self {
+            default,
+            ..Self::default()
+        }
+    }
+
+    /// Bind a hostname pattern (`api.example.com` or `*.example.com`) to a
+    /// route set. The first binding for a pattern wins.
+    pub(crate) fn bind(&mut self, pattern: &str, matcher: Arc<RouteMatcher>) {
+        let pattern = pattern.to_lowercase();
+        let entry = match pattern.strip_prefix("*.") {
+            Some(domain) => self.wildcard.entry(domain.to_string()),
+            None => self.exact.entry(pattern),
+        };
+        entry.or_insert(matcher);
+    }
+
+    /// Whether any hostname is bound to a route set.
+    pub(crate) fn has_sni_routes(&self) -> bool {
+        !self.exact.is_empty() || !self.wildcard.is_empty()
+    }
+
+    /// Route set for a request's server name.
+    ///
+    /// Returns `None` when neither the name nor the listener is bound to a
+    /// route set; the caller then uses the global matcher.
+    pub(crate) fn matcher_for(&self, server_name: Option<&str>) -> Option<Arc<RouteMatcher>> {
+        if let Some(name) = server_name.filter(|_| self.has_sni_routes()) {
+            let name = name.to_lowercase();
+            if let Some(matcher) = self.exact.get(&name) {
+                return Some(Arc::clone(matcher));
+            }
+
+            // For "foo.bar.example.com", try "bar.example.com", then "example.com"
+            let parts: Vec<&str> = name.split('.').collect();
+            for i in 1..parts.len() {
+                if let Some(matcher) = self.wildcard.get(&parts[i..].join(".")) {
+                    return Some(Arc::clone(matcher));
+                }
+            }
+        }
+        self.default.clone()
+    }
+
+    /// Route set for a connection, by the server name of its TLS handshake.
+    pub(crate) fn matcher_for_connection(
+        &self,
+        connection: Option<&ProxiedConnection>,
+    ) -> Option<Arc<RouteMatcher>> {
+        self.matcher_for(connection.and_then(|c| c.server_name.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::RequestInfo;
+    use zentinel_common::types::Priority;
+    use zentinel_config::{MatchCondition, RouteConfig, RoutePolicies, ServiceType};
+
+    fn matcher(path: &str) -> Arc<RouteMatcher> {
+        let route = RouteConfig {
+            id: path.trim_start_matches('/').to_string(),
+            priority: Priority::NORMAL,
+            matches: vec![MatchCondition::Path(path.to_string())],
+            upstream: Some("test-upstream".to_string()),
+            service_type: ServiceType::Web,
+            policies: RoutePolicies::default(),
+            filters: vec![],
+            builtin_handler: None,
+            waf_enabled: false,
+            retry_policy: None,
+            static_files: None,
+            api_schema: None,
+            error_pages: None,
+            websocket: false,
+            websocket_inspection: false,
+            inference: None,
+            shadow: None,
+            fallback: None,
+        };
+        Arc::new(RouteMatcher::new(vec![route], None).unwrap())
+    }
+
+    fn serves(routes: &ListenerRoutes, server_name: Option<&str>, path: &str) -> bool {
+        routes
+            .matcher_for(server_name)
+            .and_then(|m| m.match_request(&RequestInfo::new("GET", path, "x")))
+            .is_some()
+    }
+
+    #[test]
+    fn selects_route_set_by_server_name() {
+        let mut routes = ListenerRoutes::new(Some(matcher("/default")));
+        routes.bind("api.tenant-a.com", matcher("/exact"));
+        routes.bind("*.tenant-b.com", matcher("/wildcard"));
+
+        assert!(serves(&routes, Some("api.tenant-a.com"), "/exact"));
+        assert!(serves(&routes, Some("API.Tenant-A.com"), "/exact"));
+        assert!(serves(&routes, Some("www.tenant-b.com"), "/wildcard"));
+        assert!(serves(&routes, Some("a.b.tenant-b.com"), "/wildcard"));
+        assert!(serves(&routes, Some("other.com"), "/default"));
+        assert!(serves(&routes, None, "/default"));
+
+        // Route sets are isolated from each other
+        assert!(!serves(&routes, Some("api.tenant-a.com"), "/default"));
+    }
+
+    #[test]
+    fn unmatched_name_without_listener_route_set_uses_global_routes() {
+        let mut routes = ListenerRoutes::new(None);
+        routes.bind("*.tenant-a.com", matcher("/tenant"));
+
+        assert!(routes.matcher_for(Some("tenant-a.com")).is_none());
+        assert!(routes.matcher_for(Some("www.tenant-a.com")).is_some());
+    }
+
+    #[tokio::test]
+    async fn selects_route_set_by_handshake_server_name_not_host() {
+        use crate::client_ip::proxy_protocol::{internal_listener_path, lookup, run_acceptor};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let fixtures =
+            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/tls");
+        let tls_config = zentinel_config::TlsConfig {
+            cert_file: Some(fixtures.join("server-default.crt")),
+            key_file: Some(fixtures.join("server-default.key")),
+            additional_certs: vec![],
+            ca_file: None,
+            min_version: zentinel_common::types::TlsVersion::Tls12,
+            max_version: None,
+            cipher_suites: vec![],
+            client_auth: false,
+            client_cert_header: None,
+            ocsp_stapling: false,
+            session_resumption: true,
+            acme: None,
+        };
+        let resolver = Arc::new(
+            crate::tls::HotReloadableSniResolver::from_config(tls_config.clone(), "test").unwrap(),
+        );
+        let server_config = crate::tls::build_server_config(&tls_config, resolver).unwrap();
+
+        let internal_address = internal_listener_path().unwrap();
+        let internal = tokio::net::UnixListener::bind(&internal_address).unwrap();
+        let public = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(run_acceptor(
+            "test".to_string(),
+            public.to_string(),
+            internal_address,
+            false,
+            false,
+            Some(Arc::new(server_config)),
+            16,
+        ));
+
+        let mut roots = rustls::RootCertStore::empty();
+        let ca = std::fs::read(fixtures.join("ca.crt")).unwrap();
+        for cert in rustls_pemfile::certs(&mut &ca[..]) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+        let client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let tcp = loop {
+            match tokio::net::TcpStream::connect(public).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        // Handshake for one tenant, Host header naming another
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut client = connector.connect(server_name, tcp).await.unwrap();
+        let request = b"GET /other HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        client.write_all(request).await.unwrap();
+        client.flush().await.unwrap();
+
+        let (mut accepted, peer) = internal.accept().await.unwrap();
+        let mut data = vec![0u8; request.len()];
+        accepted.read_exact(&mut data).await.unwrap();
+        let connection = lookup(peer.as_pathname().unwrap()).unwrap();
+
+        let mut routes = ListenerRoutes::new(None);
+        routes.bind("localhost", matcher("/tenant"));
+        routes.bind("example.com", matcher("/other"));
+
+        let selected = routes.matcher_for_connection(Some(&connection)).unwrap();
+        let serves = |path| {
+            selected
+                .match_request(&RequestInfo::new("GET", path, "example.com"))
+                .is_some()
+        };
+        assert!(serves("/tenant"));
+        assert!(!serves("/other"));
+
+        // Connections without a handshake fall back to the listener's set
+        assert!(routes.matcher_for_connection(None).is_none());
+    }
+}
@@ -0,0 +1,787 @@
+//! Per-consumer request and byte quotas
+//!
+//! The `quota` filter counts what each API consumer uses over UTC calendar
+//! days and months, beyond the instantaneous limits of `rate-limit`.
+//!
+//! # Consumers
+//!
+//! A consumer is the key ID set by the route's `api-key` filter
+//! (`key "api-key"`), or the `sub` claim of the bearer JWT
+//! (`key "jwt-sub"`). The JWT is only decoded, not verified: pair the
+//! filter with an auth agent that validates the token, since quotas are
+//! checked after agents ran. Requests without a consumer are not counted.
+//!
+//! # Counters
+//!
+//! One counter per consumer, metric and window, keyed
+//! `quota:{filter}:{consumer}:{metric}:{period}`, expiring at the end of
+//! the period. With a Redis `session-store` the counters are shared between
+//! proxy instances and survive restarts; otherwise they live in memory.
+//!
+//! Request counters are incremented when a request is checked; a request
+//! over a request quota is rejected. Body bytes are added once the request
+//! completes, so a consumer is rejected from the request after the one
+//! that crossed a byte quota. Store errors fail open.
+//!
+//! # Webhook
+//!
+//! When a consumer exhausts a quota, the filter's `webhook` receives a POST
+//! with a JSON body describing the quota, once per window.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, Utc};
+use dashmap::DashMap;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+#[cfg(feature = "session-store-redis")]
+use redis::aio::ConnectionManager;
+
+use zentinel_config::{QuotaFilter, QuotaKey, SessionStoreBackend, SessionStoreConfig};
+
+/// Requests rejected per filter, metric and window
+static QUOTA_REJECTED: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_quota_rejected_total",
+        "Requests rejected by quota filters",
+        &["filter", "metric", "window"]
+    )
+    .ok()
+});
+
+/// Exhaustion webhooks per filter and outcome
+static QUOTA_WEBHOOKS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_quota_webhooks_total",
+        "Quota exhaustion webhook deliveries",
+        &["filter", "outcome"]
+    )
+    .ok()
+});
+
+/// Counters outlive their period by this much, so usage of a period that
+/// just ended can still be inspected
+const EXPIRY_GRACE: Duration = Duration::from_secs(3600);
+
+/// Timeout of webhook deliveries
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a quota counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaMetric {
+    Requests,
+    Bytes,
+}
+
+impl QuotaMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuotaMetric::Requests => "requests",
+            QuotaMetric::Bytes => "bytes",
+        }
+    }
+}
+
+/// Calendar window of a quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaWindow {
+    Day,
+    Month,
+}
+
+impl QuotaWindow {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuotaWindow::Day => "day",
+            QuotaWindow::Month => "month",
+        }
+    }
+
+    /// Period containing `now` and the time it ends
+    fn period(self, now: DateTime<Utc>) -> (String, DateTime<Utc>) {
+        let today = now.date_naive();
+        match self {
+            QuotaWindow::Day => (
+                today.format("%Y%m%d").to_string(),
+                midnight(today.succ_opt().unwrap_or(NaiveDate::MAX)),
+            ),
+            QuotaWindow::Month => {
+                let first = today.with_day(1).unwrap_or(today);
+                let next = first
+                    .checked_add_months(Months::new(1))
+                    .unwrap_or(NaiveDate::MAX);
+                (first.format("%Y%m").to_string(), midnight(next))
+            }
+        }
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// One configured limit of a filter
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    metric: QuotaMetric,
+    window: QuotaWindow,
+    limit: u64,
+}
+
+fn limits(filter: &QuotaFilter) -> Vec<Limit> {
+    let windows = [
+        (QuotaMetric::Requests, QuotaWindow::Day),
+        (QuotaMetric::Requests, QuotaWindow::Month),
+        (QuotaMetric::Bytes, QuotaWindow::Day),
+        (QuotaMetric::Bytes, QuotaWindow::Month),
+    ];
+    windows
+        .into_iter()
+        .zip(filter.limits())
+        .filter_map(|((metric, window), limit)| {
+            Some(Limit {
+                metric,
+                window,
+                limit: limit?,
+            })
+        })
+        .collect()
+}
+
+/// Usage of one quota of a consumer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaUsage {
+    pub metric: QuotaMetric,
+    pub window: QuotaWindow,
+    pub limit: u64,
+    pub used: u64,
+    pub remaining: u64,
+    /// Unix time the window resets
+    pub reset_at: i64,
+}
+
+impl QuotaUsage {
+    fn new(limit: &Limit, used: u64, reset: DateTime<Utc>) -> Self {
+        Self {
+            metric: limit.metric,
+            window: limit.window,
+            limit: limit.limit,
+            used,
+            remaining: limit.limit.saturating_sub(used),
+            reset_at: reset.timestamp(),
+        }
+    }
+
+    /// Whether the request being checked is over this quota.
+    ///
+    /// Request counters include the request itself; byte counters only
+    /// hold completed requests, so reaching the limit is enough.
+    pub fn exhausted(&self) -> bool {
+        match self.metric {
+            QuotaMetric::Requests => self.used > self.limit,
+            QuotaMetric::Bytes => self.used >= self.limit,
+        }
+    }
+
+    /// Seconds until the window resets
+    pub fn retry_after_secs(&self) -> u64 {
+        (self.reset_at - Utc::now().timestamp()).max(1) as u64
+    }
+
+    /// `X-Quota-*` headers announcing this quota
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("X-Quota-Limit", self.limit.to_string()),
+            ("X-Quota-Remaining", self.remaining.to_string()),
+            ("X-Quota-Reset", self.reset_at.to_string()),
+            (
+                "X-Quota-Window",
+                format!("{}-{}", self.metric.as_str(), self.window.as_str()),
+            ),
+        ]
+    }
+}
+
+/// Result of checking a request against a filter's quotas
+#[derive(Debug, Clone)]
+pub struct QuotaCheck {
+    /// First quota the request is over
+    pub exhausted: Option<QuotaUsage>,
+    /// Quota with the smallest share left, announced in response headers
+    pub tightest: Option<QuotaUsage>,
+}
+
+/// Consumer identity of a request, if the filter can identify one
+pub fn consumer(
+    key: QuotaKey,
+    api_key_id: Option<&str>,
+    headers: &http::HeaderMap,
+) -> Option<String> {
+    match key {
+        QuotaKey::ApiKey => api_key_id.map(str::to_string),
+        QuotaKey::JwtSubject => jwt_subject(headers),
+    }
+}
+
+/// `sub` claim of the bearer token, decoded without verification
+fn jwt_subject(headers: &http::HeaderMap) -> Option<String> {
+    let value = headers.get(http::header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    let payload = token.trim().split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims
+        .get("sub")?
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn counter_key(filter_id: &str, consumer: &str, metric: QuotaMetric, period: &str) -> String {
+    format!(
+        "quota:{}:{}:{}:{}",
+        filter_id,
+        consumer,
+        metric.as_str(),
+        period
+    )
+}
+
+/// Time-to-live of a counter whose period ends at `reset`
+fn counter_ttl(reset: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (reset - now).to_std().unwrap_or_default() + EXPIRY_GRACE
+}
+
+/// Quota counters of all quota filters
+pub struct QuotaManager {
+    store: QuotaStore,
+    filters: DashMap<String, Arc<QuotaFilter>>,
+    webhook_client: Option<reqwest::Client>,
+}
+
+impl QuotaManager {
+    pub fn new(store: QuotaStore) -> Self {
+        let webhook_client = match crate::outbound::client_builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+        {
+            Ok(client) => Some(client),
+            Err(e) => {
+                warn!(error = %e, "Failed to create quota webhook client, webhooks disabled");
+                None
+            }
+        };
+        Self {
+            store,
+            filters: DashMap::new(),
+            webhook_client,
+        }
+    }
+
+    /// Replace the filter definitions. Counters are kept.
+    pub fn reload(&self, filters: &HashMap<String, QuotaFilter>) {
+        self.filters.retain(|id, _| filters.contains_key(id));
+        for (id, filter) in filters {
+            self.filters.insert(id.clone(), Arc::new(filter.clone()));
+        }
+    }
+
+    pub fn get(&self, filter_id: &str) -> Option<Arc<QuotaFilter>> {
+        self.filters.get(filter_id).map(|f| Arc::clone(f.value()))
+    }
+
+    /// IDs of the registered filters
+    pub fn filter_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.filters.iter().map(|f| f.key().clone()).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Count a request of `consumer` and check it against the filter's
+    /// quotas. Returns `None` for unknown filters.
+    pub async fn check(&self, filter_id: &str, consumer: &str) -> Option<QuotaCheck> {
+        let filter = self.get(filter_id)?;
+        let now = Utc::now();
+
+        let mut usages = Vec::new();
+        for limit in limits(&filter) {
+            let (period, reset) = limit.window.period(now);
+            let key = counter_key(filter_id, consumer, limit.metric, &period);
+            let used = match limit.metric {
+                QuotaMetric::Requests => self.store.incr(&key, 1, counter_ttl(reset, now)).await,
+                QuotaMetric::Bytes => self.store.get(&key).await,
+            };
+            let used = match used {
+                Ok(used) => used,
+                Err(e) => {
+                    warn!(
+                        filter_id = %filter_id,
+                        consumer = %consumer,
+                        error = %e,
+                        "Quota counter unavailable, allowing request"
+                    );
+                    continue;
+                }
+            };
+
+            let usage = QuotaUsage::new(&limit, used, reset);
+            // Only the first rejected request of a window notifies
+            if limit.metric == QuotaMetric::Requests && used == limit.limit + 1 {
+                self.notify_exhausted(filter_id, &filter, consumer, &usage);
+            }
+            usages.push(usage);
+        }
+
+        let exhausted = usages.iter().find(|u| u.exhausted()).cloned();
+        if let (Some(usage), Some(metric)) = (&exhausted, QUOTA_REJECTED.as_ref()) {
+            metric
+                .with_label_values(&[filter_id, usage.metric.as_str(), usage.window.as_str()])
+                .inc();
+        }
+        let tightest = usages
+            .into_iter()
+            .min_by(|a, b| share_left(a).total_cmp(&share_left(b)));
+
+        Some(QuotaCheck {
+            exhausted,
+            tightest,
+        })
+    }
+
+    /// Add the body bytes of a completed request to the byte quotas
+    pub async fn record_bytes(&self, filter_id: &str, consumer: &str, bytes: u64) {
+        let Some(filter) = self.get(filter_id) else {
+            return;
+        };
+        if bytes == 0 {
+            return;
+        }
+        let now = Utc::now();
+
+        for limit in limits(&filter) {
+            if limit.metric != QuotaMetric::Bytes {
+                continue;
+            }
+            let (period, reset) = limit.window.period(now);
+            let key = counter_key(filter_id, consumer, limit.metric, &period);
+            match self.store.incr(&key, bytes, counter_ttl(reset, now)).await {
+                Ok(used) => {
+                    if used >= limit.limit && used - bytes < limit.limit {
+                        let usage = QuotaUsage::new(&limit, used, reset);
+                        self.notify_exhausted(filter_id, &filter, consumer, &usage);
+                    }
+                }
+                Err(e) => warn!(
+                    filter_id = %filter_id,
+                    consumer = %consumer,
+                    error = %e,
+                    "Failed to record quota bytes"
+                ),
+            }
+        }
+    }
+
+    /// Current usage of a consumer, without counting a request
+    pub async fn usage(&self, filter_id: &str, consumer: &str) -> Result<Vec<QuotaUsage>, String> {
+        let filter = self
+            .get(filter_id)
+            .ok_or_else(|| format!("unknown quota filter '{}'", filter_id))?;
+        let now = Utc::now();
+
+        let mut usages = Vec::new();
+        for limit in limits(&filter) {
+            let (period, reset) = limit.window.period(now);
+            let used = self
+                .store
+                .get(&counter_key(filter_id, consumer, limit.metric, &period))
+                .await?;
+            usages.push(QuotaUsage::new(&limit, used, reset));
+        }
+        Ok(usages)
+    }
+
+    /// Reset the current windows of a consumer
+    pub async fn reset(&self, filter_id: &str, consumer: &str) -> Result<(), String> {
+        let filter = self
+            .get(filter_id)
+            .ok_or_else(|| format!("unknown quota filter '{}'", filter_id))?;
+        let now = Utc::now();
+
+        for limit in limits(&filter) {
+            let (period, _) = limit.window.period(now);
+            self.store
+                .delete(&counter_key(filter_id, consumer, limit.metric, &period))
+                .await?;
+        }
+        debug!(filter_id = %filter_id, consumer = %consumer, "Quota counters reset");
+        Ok(())
+    }
+
+    /// Drop expired in-memory counters
+    pub fn cleanup(&self) {
+        self.store.purge_expired();
+    }
+
+    fn notify_exhausted(
+        &self,
+        filter_id: &str,
+        filter: &QuotaFilter,
+        consumer: &str,
+        usage: &QuotaUsage,
+    ) {
+        warn!(
+            filter_id = %filter_id,
+            consumer = %consumer,
+            metric = usage.metric.as_str(),
+            window = usage.window.as_str(),
+            limit = usage.limit,
+            "Consumer exhausted quota"
+        );
+        let (Some(url), Some(client)) = (filter.webhook.clone(), self.webhook_client.clone())
+        else {
+            return;
+        };
+
+        let payload = serde_json::json!({
+            "event": "quota_exhausted",
+            "filter": filter_id,
+            "consumer": consumer,
+            "metric": usage.metric,
+            "window": usage.window,
+            "limit": usage.limit,
+            "used": usage.used,
+            "reset_at": usage.reset_at,
+        });
+        let filter_id = filter_id.to_string();
+        tokio::spawn(async move {
+            let outcome = match client.post(&url).json(&payload).send().await {
+                Ok(response) if response.status().is_success() => "delivered",
+                Ok(response) => {
+                    warn!(
+                        filter_id = %filter_id,
+                        status = response.status().as_u16(),
+                        "Quota webhook rejected notification"
+                    );
+                    "rejected"
+                }
+                Err(e) => {
+                    warn!(filter_id = %filter_id, error = %e, "Quota webhook delivery failed");
+                    "failed"
+                }
+            };
+            if let Some(metric) = QUOTA_WEBHOOKS.as_ref() {
+                metric
+                    .with_label_values(&[filter_id.as_str(), outcome])
+                    .inc();
+            }
+        });
+    }
+}
+
+/// Share of a quota still available
+fn share_left(usage: &QuotaUsage) -> f64 {
+    usage.remaining as f64 / usage.limit.max(1) as f64
+}
+
+/// Counter store for quotas
+pub struct QuotaStore {
+    backend: Backend,
+}
+
+enum Backend {
+    Memory(DashMap<String, (u64, Instant)>),
+    #[cfg(feature = "session-store-redis")]
+    Redis(RedisCounters),
+}
+
+impl QuotaStore {
+    /// In-process counters, local to this proxy instance
+    pub fn memory() -> Self {
+        Self {
+            backend: Backend::Memory(DashMap::new()),
+        }
+    }
+
+    /// Create the store configured in `session-store`.
+    ///
+    /// Falls back to memory when no store is configured or Redis is
+    /// unavailable.
+    pub async fn new(config: Option<&SessionStoreConfig>) -> Self {
+        match config.map(|c| &c.backend) {
+            None | Some(SessionStoreBackend::Memory) => Self::memory(),
+            #[cfg(feature = "session-store-redis")]
+            Some(SessionStoreBackend::Redis(redis)) => match RedisCounters::new(redis).await {
+                Ok(counters) => Self {
+                    backend: Backend::Redis(counters),
+                },
+                Err(e) => {
+                    warn!(
+                        url = %zentinel_config::secrets::redact(&redis.url),
+                        error = %e,
+                        "Failed to connect quota store to Redis, keeping quotas in memory"
+                    );
+                    Self::memory()
+                }
+            },
+            #[cfg(not(feature = "session-store-redis"))]
+            Some(SessionStoreBackend::Redis(_)) => {
+                warn!(
+                    "Redis session store requested but the 'session-store-redis' feature is \
+                     disabled. Keeping quotas in memory."
+                );
+                Self::memory()
+            }
+        }
+    }
+
+    async fn incr(&self, key: &str, by: u64, ttl: Duration) -> Result<u64, String> {
+        match &self.backend {
+            Backend::Memory(counters) => {
+                let now = Instant::now();
+                let mut entry = counters.entry(key.to_string()).or_insert((0, now + ttl));
+                if entry.1 <= now {
+                    *entry = (0, now + ttl);
+                }
+                entry.0 += by;
+                Ok(entry.0)
+            }
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.incr(key, by, ttl).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<u64, String> {
+        match &self.backend {
+            Backend::Memory(counters) => Ok(counters
+                .get(key)
+                .filter(|entry| entry.1 > Instant::now())
+                .map(|entry| entry.0)
+                .unwrap_or(0)),
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.get(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match &self.backend {
+            Backend::Memory(counters) => {
+                counters.remove(key);
+                Ok(())
+            }
+            #[cfg(feature = "session-store-redis")]
+            Backend::Redis(redis) => redis.delete(key).await,
+        }
+    }
+
+    fn purge_expired(&self) {
+        if let Backend::Memory(counters) = &self.backend {
+            let now = Instant::now();
+            counters.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+    }
+}
+
+/// Redis counters, shared between proxy instances
+#[cfg(feature = "session-store-redis")]
+struct RedisCounters {
+    connection: ConnectionManager,
+    key_prefix: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "session-store-redis")]
+impl RedisCounters {
+    async fn new(config: &zentinel_config::RedisBackendConfig) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(config.url.as_str())?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            key_prefix: config.key_prefix.clone(),
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+
+    async fn timed<T>(
+        &self,
+        query: impl std::future::Future<Output = redis::RedisResult<T>>,
+    ) -> Result<T, String> {
+        match tokio::time::timeout(self.timeout, query).await {
+            Ok(result) => result.map_err(|e| format!("quota store error: {}", e)),
+            Err(_) => Err("quota store timed out".to_string()),
+        }
+    }
+
+    async fn incr(&self, key: &str, by: u64, ttl: Duration) -> Result<u64, String> {
+        let key = format!("{}{}", self.key_prefix, key);
+        let mut conn = self.connection.clone();
+        let pipe = redis::pipe()
+            .atomic()
+            .cmd("INCRBY")
+            .arg(&key)
+            .arg(by)
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(ttl.as_secs().max(1))
+            .ignore()
+            .clone();
+        let (used,): (u64,) = self.timed(pipe.query_async(&mut conn)).await?;
+        Ok(used)
+    }
+
+    async fn get(&self, key: &str) -> Result<u64, String> {
+        let mut conn = self.connection.clone();
+        let cmd = redis::cmd("GET")
+            .arg(format!("{}{}", self.key_prefix, key))
+            .clone();
+        let used: Option<u64> = self.timed(cmd.query_async(&mut conn)).await?;
+        Ok(used.unwrap_or(0))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let mut conn = self.connection.clone();
+        let cmd = redis::cmd("DEL")
+            .arg(format!("{}{}", self.key_prefix, key))
+            .clone();
+        self.timed(cmd.query_async(&mut conn)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn manager(filter: QuotaFilter) -> QuotaManager {
+        let manager = QuotaManager::new(QuotaStore::memory());
+        manager.reload(&HashMap::from([("quota".to_string(), filter)]));
+        manager
+    }
+
+    #[test]
+    fn test_periods() {
+        let now = Utc.with_ymd_and_hms(2026, 12, 31, 15, 30, 0).unwrap();
+
+        let (day, reset) = QuotaWindow::Day.period(now);
+        assert_eq!(day, "20261231");
+        assert_eq!(reset, Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+
+        let (month, reset) = QuotaWindow::Month.period(now);
+        assert_eq!(month, "202612");
+        assert_eq!(reset, Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_quota_exhaustion() {
+        let manager = manager(QuotaFilter {
+            requests_per_day: Some(2),
+            requests_per_month: Some(100),
+            ..Default::default()
+        });
+
+        let first = manager.check("quota", "alice").await.unwrap();
+        assert!(first.exhausted.is_none());
+        let tightest = first.tightest.unwrap();
+        assert_eq!(tightest.window, QuotaWindow::Day);
+        assert_eq!(tightest.remaining, 1);
+
+        assert!(manager
+            .check("quota", "alice")
+            .await
+            .unwrap()
+            .exhausted
+            .is_none());
+        let third = manager.check("quota", "alice").await.unwrap();
+        let exhausted = third.exhausted.unwrap();
+        assert_eq!(exhausted.window, QuotaWindow::Day);
+        assert_eq!(exhausted.remaining, 0);
+
+        // Other consumers have their own counters
+        assert!(manager
+            .check("quota", "bob")
+            .await
+            .unwrap()
+            .exhausted
+            .is_none());
+        assert!(manager.check("unknown", "alice").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_byte_quota_applies_to_next_request() {
+        let manager = manager(QuotaFilter {
+            bytes_per_month: Some(1000),
+            ..Default::default()
+        });
+
+        assert!(manager
+            .check("quota", "alice")
+            .await
+            .unwrap()
+            .exhausted
+            .is_none());
+        manager.record_bytes("quota", "alice", 1200).await;
+
+        let check = manager.check("quota", "alice").await.unwrap();
+        let exhausted = check.exhausted.unwrap();
+        assert_eq!(exhausted.metric, QuotaMetric::Bytes);
+        assert_eq!(exhausted.used, 1200);
+    }
+
+    #[tokio::test]
+    async fn test_usage_and_reset() {
+        let manager = manager(QuotaFilter {
+            requests_per_day: Some(1),
+            bytes_per_day: Some(100),
+            ..Default::default()
+        });
+
+        manager.check("quota", "alice").await;
+        manager.check("quota", "alice").await;
+        manager.record_bytes("quota", "alice", 40).await;
+
+        let usage = manager.usage("quota", "alice").await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].used, 2);
+        assert_eq!(usage[1].used, 40);
+        assert_eq!(usage[1].remaining, 60);
+
+        manager.reset("quota", "alice").await.unwrap();
+        let usage = manager.usage("quota", "alice").await.unwrap();
+        assert!(usage.iter().all(|u| u.used == 0));
+        assert!(manager.usage("missing", "alice").await.is_err());
+    }
+
+    #[test]
+    fn test_jwt_subject() {
+        let payload =
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(r#"{"sub":"user-42","exp":1}"#);
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer eyJhbGciOiJIUzI1NiJ9.{}.sig", payload)
+                .parse()
+                .unwrap(),
+        );
+
+        assert_eq!(
+            consumer(QuotaKey::JwtSubject, None, &headers).as_deref(),
+            Some("user-42")
+        );
+        assert_eq!(
+            consumer(QuotaKey::ApiKey, Some("key-1"), &headers).as_deref(),
+            Some("key-1")
+        );
+        assert!(consumer(QuotaKey::JwtSubject, None, &http::HeaderMap::new()).is_none());
+    }
+}
@@ -0,0 +1,125 @@
+//! Certificate file watcher for TLS hot-reload.
+//!
+//! Watches the certificate and key files of TLS listeners (e.g. Kubernetes
+//! secrets maintained by cert-manager) and swaps changed pairs into the
+//! listeners' resolvers. Files are compared by content, so the atomic
+//! symlink swaps used for mounted secrets are detected, and a periodic poll
+//! covers filesystems where change events are unreliable.
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, trace, warn};
+
+use crate::tls::CertificateReloader;
+
+/// Default poll interval for the fallback content check
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Watches certificate files and reloads changed listeners.
+pub struct CertificateWatcher {
+    reloader: Arc<CertificateReloader>,
+    poll_interval: Duration,
+}
+
+impl CertificateWatcher {
+    /// Create a watcher for the listeners registered with `reloader`.
+    pub fn new(reloader: Arc<CertificateReloader>) -> Self {
+        Self {
+            reloader,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Set the fallback poll interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Watch certificate files until the task is dropped.
+    pub async fn run(self) {
+        let files = self.reloader.watched_files();
+        if files.is_empty() {
+            debug!("No certificate files to watch");
+            return;
+        }
+
+        // Watch the parent directories: secret volumes and most tools
+        // replace files by rename, which a file watch would miss
+        let directories: BTreeSet<_> = files
+            .iter()
+            .filter_map(|file| file.parent().map(|p| p.to_path_buf()))
+            .collect();
+
+        let notify = Arc::new(tokio::sync::Notify::new());
+        let notify_sender = Arc::clone(&notify);
+        let watcher =
+            notify::recommended_watcher(move |event: Result<Event, notify::Error>| match event {
+                Ok(event) => {
+                    if matches!(
+                        event.kind,
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                    ) {
+                        notify_sender.notify_one();
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Certificate file watcher error");
+                }
+            });
+
+        // Keep the watcher alive for the lifetime of this task
+        let _watcher = match watcher {
+            Ok(mut watcher) => {
+                for directory in &directories {
+                    if let Err(e) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+                        warn!(
+                            path = %directory.display(),
+                            error = %e,
+                            "Could not watch certificate directory, relying on polling"
+                        );
+                    }
+                }
+                Some(watcher)
+            }
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    "Failed to create certificate file watcher, relying on polling"
+                );
+                None
+            }
+        };
+
+        info!(
+            files = files.len(),
+            poll_interval_secs = self.poll_interval.as_secs(),
+            "Watching TLS certificate files for changes"
+        );
+
+        loop {
+            tokio::select! {
+                _ = notify.notified() => {
+                    // Debounce: a rotation usually writes the certificate and
+                    // key separately; wait until changes settle
+                    while let Ok(()) =
+                        tokio::time::timeout(Duration::from_millis(500), notify.notified()).await
+                    {
+                        trace!("Debounce: additional certificate file change");
+                    }
+                }
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+
+            let reloaded = self.reloader.reload_changed();
+            if reloaded > 0 {
+                info!(
+                    listeners = reloaded,
+                    "Hot-reloaded TLS certificates from disk"
+                );
+            }
+        }
+    }
+}
@@ -5,14 +5,17 @@
 //!
 //! ## Submodules
 //!
+//! - `cert_watcher`: TLS certificate file watching and hot-reload
 //! - `coordinator`: Graceful reload coordination and request draining
 //! - `signals`: OS signal handling (SIGHUP, SIGTERM)
 //! - `validators`: Runtime configuration validators
 
+mod cert_watcher;
 mod coordinator;
 mod signals;
 mod validators;
 
+pub use cert_watcher::CertificateWatcher;
 pub use coordinator::GracefulReloadCoordinator;
 pub use signals::{SignalManager, SignalType};
 pub use validators::{RouteValidator, UpstreamValidator};
@@ -68,6 +71,12 @@ pub enum ReloadTrigger {
     Scheduled,
     /// Gateway API controller reconciliation
     GatewayApi,
+    /// Admin API reload of a single tenant
+    Tenant(String),
+    /// Route table from the control plane, with its version
+    ControlPlane(String),
+    /// Rollback of a staged rollout that regressed
+    Rollout,
 }
 
 // ============================================================================
@@ -690,6 +699,34 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Reload one tenant's resources from the configuration file.
+    ///
+    /// Only the tenant's namespaces, routes and agents (and its quotas) are
+    /// taken from the file; everything else, including other tenants, stays
+    /// as currently running. See [`Config::with_tenant_from`].
+    pub async fn reload_tenant(&self, tenant_id: &str) -> ZentinelResult<()> {
+        info!(
+            tenant = %tenant_id,
+            config_path = %self.config_path.display(),
+            "Starting tenant configuration reload"
+        );
+
+        let source = Config::from_file(&self.config_path).map_err(|e| ZentinelError::Config {
+            message: format!("Failed to load configuration: {}", e),
+            source: None,
+        })?;
+        let merged = self
+            .current()
+            .with_tenant_from(&source, tenant_id)
+            .map_err(|message| ZentinelError::Config {
+                message,
+                source: None,
+            })?;
+
+        self.apply_config(merged, ReloadTrigger::Tenant(tenant_id.to_string()))
+            .await
+    }
+
     /// Get a handle to the underlying ArcSwap for direct reads.
     ///
     /// Used by the Gateway API controller to share the same config store.
@@ -0,0 +1,264 @@
+//! Replay of traffic capture files (`zentinel replay`).
+//!
+//! Reads the JSON lines written by [`crate::capture`] and sends each request
+//! to a target base URL at a fixed rate, for load tests and for checking an
+//! agent change against real traffic. Redacted headers are not sent, so
+//! replayed requests arrive without credentials; the summary compares each
+//! response status with the status recorded at capture time.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::capture::{CapturedRequest, REDACTED};
+
+/// Headers that describe the original connection rather than the request
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+    "proxy-connection",
+];
+
+/// How a capture file is replayed
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Base URL requests are sent to, e.g. `http://127.0.0.1:8080`
+    pub target: String,
+    /// Requests per second; `None` sends as fast as `concurrency` allows
+    pub rate: Option<f64>,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Timeout per request
+    pub timeout: Duration,
+}
+
+/// Outcome of a replay
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// Requests sent
+    pub sent: u64,
+    /// Requests that got no response
+    pub errors: u64,
+    /// Responses with the status recorded at capture time
+    pub status_matched: u64,
+    /// Responses with a different status
+    pub status_mismatched: u64,
+    /// Lines of the capture file that could not be parsed
+    pub skipped: u64,
+}
+
+/// Parse a replay rate: `100rps`, `100/s` or a bare number
+pub fn parse_rate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number = value
+        .strip_suffix("rps")
+        .or_else(|| value.strip_suffix("/s"))
+        .unwrap_or(value)
+        .trim();
+    let rate: f64 = number.parse().ok()?;
+    (rate > 0.0 && rate.is_finite()).then_some(rate)
+}
+
+/// Replay every request of a capture file
+pub async fn replay_file(path: &Path, options: &ReplayOptions) -> Result<ReplaySummary> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read capture file {}", path.display()))?;
+
+    let mut summary = ReplaySummary::default();
+    let mut requests = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<CapturedRequest>(line) {
+            Ok(request) => requests.push(request),
+            Err(e) => {
+                tracing::warn!(line = number + 1, error = %e, "Skipping unreadable capture line");
+                summary.skipped += 1;
+            }
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(options.timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .context("Failed to build HTTP client")?;
+    let target = options.target.trim_end_matches('/').to_string();
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let interval = options.rate.map(|rate| Duration::from_secs_f64(1.0 / rate));
+    let started = Instant::now();
+
+    let mut tasks = JoinSet::new();
+    for (index, request) in requests.into_iter().enumerate() {
+        if let Some(interval) = interval {
+            let due = started + interval.mul_f64(index as f64);
+            tokio::time::sleep_until(due.into()).await;
+        }
+        let permit = permits
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Replay concurrency limiter closed")?;
+        let builder = build_request(&client, &target, &request)?;
+        let expected = request.status;
+        tasks.spawn(async move {
+            let result = builder
+                .send()
+                .await
+                .map(|response| response.status().as_u16());
+            drop(permit);
+            (expected, result)
+        });
+        summary.sent += 1;
+
+        // Collect finished requests so results do not pile up
+        while let Some(done) = tasks.try_join_next() {
+            record(&mut summary, done);
+        }
+    }
+    while let Some(done) = tasks.join_next().await {
+        record(&mut summary, done);
+    }
+
+    Ok(summary)
+}
+
+type ReplayResult = (Option<u16>, reqwest::Result<u16>);
+
+fn record(summary: &mut ReplaySummary, done: Result<ReplayResult, tokio::task::JoinError>) {
+    match done {
+        Ok((expected, Ok(status))) => match expected {
+            Some(expected) if expected != status => summary.status_mismatched += 1,
+            _ => summary.status_matched += 1,
+        },
+        Ok((_, Err(e))) => {
+            tracing::debug!(error = %e, "Replayed request failed");
+            summary.errors += 1;
+        }
+        Err(_) => summary.errors += 1,
+    }
+}
+
+/// Build the request for a captured record against `target`
+fn build_request(
+    client: &reqwest::Client,
+    target: &str,
+    request: &CapturedRequest,
+) -> Result<reqwest::RequestBuilder> {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())
+        .with_context(|| format!("Invalid method '{}' in capture file", request.method))?;
+    let mut builder = client.request(method, format!("{}{}", target, request.path));
+
+    for (name, value) in &request.headers {
+        if value == REDACTED || SKIPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body_bytes() {
+        builder = builder.body(body);
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured(path: &str, status: u16) -> CapturedRequest {
+        CapturedRequest {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            route: "api".to_string(),
+            method: "POST".to_string(),
+            path: path.to_string(),
+            headers: vec![
+                ("host".to_string(), "api.example.com".to_string()),
+                ("authorization".to_string(), REDACTED.to_string()),
+                ("x-request-source".to_string(), "capture".to_string()),
+            ],
+            body: Some("aGVsbG8=".to_string()),
+            body_truncated: false,
+            status: Some(status),
+        }
+    }
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!(parse_rate("100rps"), Some(100.0));
+        assert_eq!(parse_rate("2.5/s"), Some(2.5));
+        assert_eq!(parse_rate("50"), Some(50.0));
+        assert_eq!(parse_rate("0rps"), None);
+        assert_eq!(parse_rate("fast"), None);
+    }
+
+    #[test]
+    fn builds_requests_without_redacted_headers() {
+        let client = reqwest::Client::new();
+        let request = build_request(&client, "http://127.0.0.1:8080", &captured("/a?b=1", 200))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(request.method(), reqwest::Method::POST);
+        assert_eq!(request.url().as_str(), "http://127.0.0.1:8080/a?b=1");
+        assert!(request.headers().get("authorization").is_none());
+        assert!(request.headers().get("host").is_none());
+        assert_eq!(request.headers()["x-request-source"], "capture");
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()),
+            Some(&b"hello"[..])
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_capture_files() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("capture.jsonl");
+        let lines = [
+            serde_json::to_string(&captured("/ok", 200)).unwrap(),
+            serde_json::to_string(&captured("/ok", 201)).unwrap(),
+            "not json".to_string(),
+        ];
+        std::fs::write(&file, lines.join("\n")).unwrap();
+
+        let options = ReplayOptions {
+            target: server.uri(),
+            rate: Some(1000.0),
+            concurrency: 2,
+            timeout: Duration::from_secs(5),
+        };
+        let summary = replay_file(&file, &options).await.unwrap();
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                sent: 2,
+                errors: 0,
+                status_matched: 1,
+                status_mismatched: 1,
+                skipped: 1,
+            }
+        );
+    }
+}
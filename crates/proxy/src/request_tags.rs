@@ -0,0 +1,168 @@
+//! Typed request tags set by agents.
+//!
+//! Agents attach [`RequestTag`]s to their responses. The proxy keeps them on
+//! the request context, where `enable-if` conditions (`tags["name"]`) and
+//! agents running in later phases can read them. When the request completes
+//! they are written to the access log as structured fields, and
+//! low-cardinality tags named in the metrics `tag-labels` list are counted in
+//! `zentinel_request_tags_total{route, tag, value}`.
+//!
+//! Tags are bounded so a misbehaving agent cannot bloat log lines: at most
+//! [`MAX_TAGS`] per request, names up to [`MAX_NAME_LEN`] bytes, and values
+//! truncated to [`MAX_VALUE_LEN`] bytes.
+
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use zentinel_agent_protocol::{RequestTag, TagCardinality};
+
+/// Maximum number of tags kept per request
+pub const MAX_TAGS: usize = 32;
+
+/// Maximum tag name length in bytes; longer names are rejected
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Maximum tag value length in bytes; longer values are truncated
+pub const MAX_VALUE_LEN: usize = 256;
+
+/// Completed requests per low-cardinality tag value
+static REQUEST_TAGS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_request_tags_total",
+        "Completed requests by low-cardinality request tag",
+        &["route", "tag", "value"]
+    )
+    .ok()
+});
+
+/// Tags attached to one request
+#[derive(Debug, Clone, Default)]
+pub struct RequestTags {
+    /// In the order they were first set
+    tags: Vec<RequestTag>,
+}
+
+impl RequestTags {
+    /// Apply tags from an agent decision; a tag set again replaces its
+    /// earlier value.
+    pub fn apply(&mut self, tags: &[RequestTag]) {
+        for tag in tags {
+            if tag.name.is_empty() || tag.name.len() > MAX_NAME_LEN {
+                continue;
+            }
+            let value = truncate(&tag.value, MAX_VALUE_LEN).to_string();
+            match self.tags.iter_mut().find(|t| t.name == tag.name) {
+                Some(existing) => {
+                    existing.value = value;
+                    existing.cardinality = tag.cardinality;
+                }
+                None if self.tags.len() < MAX_TAGS => self.tags.push(RequestTag {
+                    name: tag.name.clone(),
+                    value,
+                    cardinality: tag.cardinality,
+                }),
+                None => {}
+            }
+        }
+    }
+
+    /// Value of a tag
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| t.value.as_str())
+    }
+
+    /// Whether no tag has been set
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Tags to pass on to agents
+    pub fn to_vec(&self) -> Vec<RequestTag> {
+        self.tags.clone()
+    }
+
+    /// Tags as log fields, keyed by name
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        self.tags
+            .iter()
+            .map(|t| (t.name.clone(), t.value.clone()))
+            .collect()
+    }
+
+    /// Count the request under its low-cardinality tags listed in `labels`.
+    pub fn record(&self, route: &str, labels: &[String]) {
+        let Some(counter) = REQUEST_TAGS.as_ref() else {
+            return;
+        };
+        for tag in self.exported(labels) {
+            counter
+                .with_label_values(&[route, &tag.name, &tag.value])
+                .inc();
+        }
+    }
+
+    /// Tags eligible as metric labels
+    fn exported<'a>(&'a self, labels: &'a [String]) -> impl Iterator<Item = &'a RequestTag> {
+        self.tags
+            .iter()
+            .filter(|t| t.cardinality == TagCardinality::Low && labels.iter().any(|l| *l == t.name))
+    }
+}
+
+/// Cut `value` to at most `max` bytes on a character boundary
+fn truncate(value: &str, max: usize) -> &str {
+    if value.len() <= max {
+        return value;
+    }
+    let mut end = max;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_replaces_and_bounds_tags() {
+        let mut tags = RequestTags::default();
+        tags.apply(&[
+            RequestTag::low_cardinality("tier", "free"),
+            RequestTag::new("user_id", "u-1"),
+            RequestTag::new("x".repeat(MAX_NAME_LEN + 1), "dropped"),
+            RequestTag::new("note", "é".repeat(MAX_VALUE_LEN)),
+        ]);
+        tags.apply(&[RequestTag::low_cardinality("tier", "gold")]);
+
+        assert_eq!(tags.get("tier"), Some("gold"));
+        assert_eq!(tags.to_vec().len(), 3);
+        assert_eq!(tags.get("note").unwrap().len(), MAX_VALUE_LEN);
+
+        let many: Vec<RequestTag> = (0..MAX_TAGS)
+            .map(|i| RequestTag::new(format!("t{i}"), "v"))
+            .collect();
+        tags.apply(&many);
+        assert_eq!(tags.to_vec().len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn test_only_listed_low_cardinality_tags_are_exported() {
+        let mut tags = RequestTags::default();
+        tags.apply(&[
+            RequestTag::low_cardinality("tier", "gold"),
+            RequestTag::low_cardinality("region", "eu"),
+            RequestTag::new("bot_class", "verified"),
+        ]);
+        let labels = ["tier".to_string(), "bot_class".to_string()];
+
+        let exported: Vec<&str> = tags.exported(&labels).map(|t| t.name.as_str()).collect();
+        assert_eq!(exported, vec!["tier"]);
+        assert_eq!(tags.fields().len(), 3);
+    }
+}
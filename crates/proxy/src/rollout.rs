@@ -0,0 +1,547 @@
+//! Staged rollout of reloaded configurations
+//!
+//! With `system { rollout { ... } }`, a reload does not switch all traffic
+//! to the new route table at once. The route table it replaces is kept as
+//! the stable version, and requests are split into two cohorts by a hash of
+//! the `hash-header` value, or of the client IP:
+//!
+//! - the candidate cohort (`percent` of the requests) is matched against the
+//!   new route table;
+//! - the stable cohort keeps the previous route table.
+//!
+//! The status and latency of every request are recorded for its cohort.
+//! Every 5 seconds, once both cohorts have `min-requests` requests, they are
+//! compared: a 5xx rate above the stable one by more than
+//! `max-error-rate-increase` percentage points, or a mean latency above it by
+//! more than `max-latency-increase-percent`, re-applies the previous
+//! configuration and sends a `config-rolled-back` notification. Without a
+//! regression, the new version serves all traffic once `bake-window-secs`
+//! has passed.
+//!
+//! A reload during the bake window stages the newer configuration against
+//! the same stable version and restarts the window. Only route matching is
+//! staged: upstreams, filters and agents of the new configuration apply to
+//! all traffic at once, and listener-bound route sets are not split.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use http::HeaderMap;
+use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use zentinel_config::{Config, RolloutConfig};
+
+use crate::reload::{ConfigManager, ReloadTrigger};
+use crate::routing::RouteMatcher;
+
+/// Interval between comparisons of the cohorts
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Finished rollouts, by outcome (`promoted` or `rolled_back`)
+static OUTCOMES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_rollout_outcomes_total",
+        "Staged configuration rollouts by outcome",
+        &["outcome"]
+    )
+    .ok()
+});
+
+fn count_outcome(outcome: &str) {
+    if let Some(outcomes) = OUTCOMES.as_ref() {
+        outcomes.with_label_values(&[outcome]).inc();
+    }
+}
+
+/// Which version of the route table a request is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cohort {
+    /// The route table that served all traffic before the reload
+    Stable,
+    /// The reloaded route table
+    Candidate,
+}
+
+/// Requests recorded for one cohort
+#[derive(Debug, Default)]
+struct CohortStats {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_us: AtomicU64,
+}
+
+impl CohortStats {
+    fn record(&self, status: u16, duration: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        // Requests that never got a response count as errors
+        if status == 0 || status >= 500 {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_us
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CohortSnapshot {
+        CohortSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            latency_us: self.latency_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counters of one cohort at a point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CohortSnapshot {
+    pub requests: u64,
+    /// Requests answered with a 5xx or not answered at all
+    pub errors: u64,
+    /// Sum of the request durations
+    pub latency_us: u64,
+}
+
+impl CohortSnapshot {
+    /// Share of errors, in percent
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.errors as f64 * 100.0 / self.requests as f64
+    }
+
+    /// Mean request duration in milliseconds
+    pub fn mean_latency_ms(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.latency_us as f64 / self.requests as f64 / 1000.0
+    }
+}
+
+/// Outcome of comparing the cohorts
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// Keep comparing
+    Pending,
+    /// The bake window passed without a regression
+    Promote,
+    /// The candidate regressed, for the given reason
+    RollBack(String),
+}
+
+/// Compare the cohorts of a rollout that has been running for `elapsed`
+pub fn compare(
+    settings: &RolloutConfig,
+    stable: &CohortSnapshot,
+    candidate: &CohortSnapshot,
+    elapsed: Duration,
+) -> Verdict {
+    if stable.requests >= settings.min_requests && candidate.requests >= settings.min_requests {
+        if candidate.error_rate() - stable.error_rate() > settings.max_error_rate_increase {
+            return Verdict::RollBack(format!(
+                "error rate {:.2}% against {:.2}% on the previous version",
+                candidate.error_rate(),
+                stable.error_rate()
+            ));
+        }
+        let limit =
+            stable.mean_latency_ms() * (1.0 + settings.max_latency_increase_percent / 100.0);
+        if stable.mean_latency_ms() > 0.0 && candidate.mean_latency_ms() > limit {
+            return Verdict::RollBack(format!(
+                "mean latency {:.1}ms against {:.1}ms on the previous version",
+                candidate.mean_latency_ms(),
+                stable.mean_latency_ms()
+            ));
+        }
+    }
+
+    if elapsed >= Duration::from_secs(settings.bake_window_secs) {
+        Verdict::Promote
+    } else {
+        Verdict::Pending
+    }
+}
+
+/// Position of a cohort key in `[0, 100)`
+fn bucket(key: &str) -> f64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % 10_000) as f64 / 100.0
+}
+
+/// A running rollout
+struct Rollout {
+    settings: RolloutConfig,
+    /// Sequence number, distinguishing notifications of successive rollouts
+    generation: u64,
+    /// Route table of the previous version
+    stable: RouteMatcher,
+    /// Configuration the stable route table was built from
+    stable_config: Arc<Config>,
+    started: Instant,
+    stable_stats: CohortStats,
+    candidate_stats: CohortStats,
+}
+
+/// A regressed rollout whose stable configuration is to be re-applied
+struct Rollback {
+    config: Arc<Config>,
+    reason: String,
+    generation: u64,
+    stable: CohortSnapshot,
+    candidate: CohortSnapshot,
+}
+
+/// Staged rollout state, shared by the reload handler, the request path and
+/// the rollout monitor
+#[derive(Default)]
+pub struct RolloutManager {
+    rollout: RwLock<Option<Rollout>>,
+    /// Set from the moment a rollback is applied until the reload handler
+    /// installs the stable route table again
+    rolling_back: AtomicBool,
+    generations: AtomicU64,
+}
+
+impl RolloutManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start or restart a rollout after the reload handler installed the
+    /// route table of `config`
+    ///
+    /// `previous` is the route table it replaced, built from
+    /// `previous_config`. It becomes the stable version unless a rollout is
+    /// already running, in which case that rollout's stable version is kept.
+    /// Nothing is staged when `config` has no `rollout` block or is the
+    /// stable configuration re-applied by a rollback.
+    pub fn stage(&self, previous: RouteMatcher, previous_config: Arc<Config>, config: &Config) {
+        let mut state = self.rollout.write();
+        if self.rolling_back.swap(false, Ordering::AcqRel) {
+            *state = None;
+            return;
+        }
+        let Some(settings) = config.server.rollout.clone() else {
+            if state.take().is_some() {
+                info!("Staged rollout ended: rollout is no longer configured");
+            }
+            return;
+        };
+
+        let (stable, stable_config) = match state.take() {
+            // The superseded candidate is dropped; the stable version stays
+            Some(current) => (current.stable, current.stable_config),
+            None => (previous, previous_config),
+        };
+        info!(
+            percent = settings.percent,
+            bake_window_secs = settings.bake_window_secs,
+            "Staged configuration rollout started"
+        );
+        *state = Some(Rollout {
+            settings,
+            generation: self.generations.fetch_add(1, Ordering::Relaxed) + 1,
+            stable,
+            stable_config,
+            started: Instant::now(),
+            stable_stats: CohortStats::default(),
+            candidate_stats: CohortStats::default(),
+        });
+    }
+
+    /// Whether a rollout is running
+    pub fn is_active(&self) -> bool {
+        self.rollout.read().is_some()
+    }
+
+    /// Cohort of a request, `None` when no rollout is running
+    pub fn cohort(&self, headers: &HeaderMap, client_ip: &str) -> Option<Cohort> {
+        let state = self.rollout.read();
+        let rollout = state.as_ref()?;
+        if self.rolling_back.load(Ordering::Acquire) {
+            return Some(Cohort::Stable);
+        }
+        let key = rollout
+            .settings
+            .hash_header
+            .as_deref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(client_ip);
+        if bucket(key) < rollout.settings.percent {
+            Some(Cohort::Candidate)
+        } else {
+            Some(Cohort::Stable)
+        }
+    }
+
+    /// Route table for requests of the stable cohort; `None` for the
+    /// candidate cohort and outside a rollout
+    pub fn stable_matcher(
+        &self,
+        cohort: Option<Cohort>,
+    ) -> Option<MappedRwLockReadGuard<'_, RouteMatcher>> {
+        if cohort != Some(Cohort::Stable) {
+            return None;
+        }
+        RwLockReadGuard::try_map(self.rollout.read(), |state| {
+            state.as_ref().map(|rollout| &rollout.stable)
+        })
+        .ok()
+    }
+
+    /// Record a finished request of a cohort
+    pub fn record(&self, cohort: Option<Cohort>, status: u16, duration: Duration) {
+        let Some(cohort) = cohort else {
+            return;
+        };
+        if let Some(rollout) = self.rollout.read().as_ref() {
+            match cohort {
+                Cohort::Stable => rollout.stable_stats.record(status, duration),
+                Cohort::Candidate => rollout.candidate_stats.record(status, duration),
+            }
+        }
+    }
+
+    /// Upstreams of the stable configuration, whose pools are kept while it
+    /// serves traffic
+    pub fn stable_upstreams(&self) -> Vec<String> {
+        self.rollout
+            .read()
+            .as_ref()
+            .map(|rollout| rollout.stable_config.upstreams.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Compare the cohorts, promoting the candidate when its bake window
+    /// passed and returning the rollback to apply when it regressed
+    fn check(&self) -> Option<Rollback> {
+        let mut state = self.rollout.write();
+        let rollout = state.as_ref()?;
+        if self.rolling_back.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let stable = rollout.stable_stats.snapshot();
+        let candidate = rollout.candidate_stats.snapshot();
+        match compare(
+            &rollout.settings,
+            &stable,
+            &candidate,
+            rollout.started.elapsed(),
+        ) {
+            Verdict::Pending => None,
+            Verdict::Promote => {
+                info!(
+                    candidate_requests = candidate.requests,
+                    candidate_error_rate = candidate.error_rate(),
+                    stable_error_rate = stable.error_rate(),
+                    "Staged configuration promoted to all traffic"
+                );
+                *state = None;
+                count_outcome("promoted");
+                None
+            }
+            Verdict::RollBack(reason) => {
+                self.rolling_back.store(true, Ordering::Release);
+                Some(Rollback {
+                    config: Arc::clone(&rollout.stable_config),
+                    reason,
+                    generation: rollout.generation,
+                    stable,
+                    candidate,
+                })
+            }
+        }
+    }
+}
+
+/// Compare the cohorts of the running rollout every few seconds, re-applying
+/// the stable configuration when the candidate regresses
+pub async fn run_rollout_monitor(manager: Arc<RolloutManager>, config_manager: Arc<ConfigManager>) {
+    let mut interval = tokio::time::interval(EVALUATION_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let Some(rollback) = manager.check() else {
+            continue;
+        };
+
+        warn!(reason = %rollback.reason, "Staged configuration regressed, rolling back");
+        match config_manager
+            .apply_config((*rollback.config).clone(), ReloadTrigger::Rollout)
+            .await
+        {
+            Ok(()) => {
+                count_outcome("rolled_back");
+                crate::notifications::config_rolled_back(
+                    rollback.generation,
+                    &rollback.reason,
+                    json!({
+                        "reason": rollback.reason,
+                        "stable": cohort_json(&rollback.stable),
+                        "candidate": cohort_json(&rollback.candidate),
+                    }),
+                );
+            }
+            Err(e) => {
+                // The stable cohort keeps the previous route table; retried
+                // on the next comparison
+                error!(error = %e, "Failed to roll back staged configuration");
+                manager.rolling_back.store(false, Ordering::Release);
+            }
+        }
+    }
+}
+
+fn cohort_json(snapshot: &CohortSnapshot) -> serde_json::Value {
+    json!({
+        "requests": snapshot.requests,
+        "error_rate": snapshot.error_rate(),
+        "mean_latency_ms": snapshot.mean_latency_ms(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(requests: u64, errors: u64, mean_ms: u64) -> CohortSnapshot {
+        CohortSnapshot {
+            requests,
+            errors,
+            latency_us: requests * mean_ms * 1000,
+        }
+    }
+
+    fn rollout_config() -> Config {
+        let mut config = Config::default_for_testing();
+        config.server.rollout = Some(RolloutConfig::default());
+        config
+    }
+
+    fn matcher(config: &Config) -> RouteMatcher {
+        RouteMatcher::new(config.routes.clone(), None).unwrap()
+    }
+
+    #[test]
+    fn test_compare_verdicts() {
+        let settings = RolloutConfig::default();
+        let window = Duration::from_secs(settings.bake_window_secs);
+        let stable = snapshot(1000, 5, 20);
+
+        // Within thresholds
+        let candidate = snapshot(200, 2, 25);
+        assert_eq!(
+            compare(&settings, &stable, &candidate, Duration::ZERO),
+            Verdict::Pending
+        );
+        assert_eq!(
+            compare(&settings, &stable, &candidate, window),
+            Verdict::Promote
+        );
+
+        // 5% errors against 0.5%
+        let candidate = snapshot(200, 10, 20);
+        assert!(matches!(
+            compare(&settings, &stable, &candidate, Duration::ZERO),
+            Verdict::RollBack(reason) if reason.starts_with("error rate 5.00%")
+        ));
+
+        // 40ms against 20ms
+        let candidate = snapshot(200, 1, 40);
+        assert!(matches!(
+            compare(&settings, &stable, &candidate, window),
+            Verdict::RollBack(reason) if reason.starts_with("mean latency 40.0ms")
+        ));
+
+        // Too few requests to compare
+        let candidate = snapshot(50, 50, 40);
+        assert_eq!(
+            compare(&settings, &stable, &candidate, Duration::ZERO),
+            Verdict::Pending
+        );
+    }
+
+    #[test]
+    fn test_cohorts_split_by_hash() {
+        let manager = RolloutManager::new();
+        let headers = HeaderMap::new();
+        assert_eq!(manager.cohort(&headers, "10.0.0.1"), None);
+
+        let previous = Arc::new(Config::default_for_testing());
+        let mut config = rollout_config();
+        config.server.rollout.as_mut().unwrap().hash_header = Some("x-user-id".to_string());
+        manager.stage(matcher(&previous), previous, &config);
+
+        let candidates = (0..10_000)
+            .filter(|i| {
+                manager.cohort(&headers, &format!("10.0.{}.{}", i / 256, i % 256))
+                    == Some(Cohort::Candidate)
+            })
+            .count();
+        assert!((800..1200).contains(&candidates), "{candidates}");
+
+        // The header decides over the client IP, consistently
+        let mut headers = HeaderMap::new();
+        headers.insert("x-user-id", "user-42".parse().unwrap());
+        let cohort = manager.cohort(&headers, "10.0.0.1");
+        assert!(cohort.is_some());
+        assert_eq!(manager.cohort(&headers, "10.0.0.2"), cohort);
+
+        assert!(manager.stable_matcher(Some(Cohort::Stable)).is_some());
+        assert!(manager.stable_matcher(Some(Cohort::Candidate)).is_none());
+    }
+
+    #[test]
+    fn test_reload_during_rollout_keeps_stable_version() {
+        let manager = RolloutManager::new();
+        let mut previous = Config::default_for_testing();
+        previous
+            .upstreams
+            .insert("legacy".to_string(), previous.upstreams["default"].clone());
+        let previous = Arc::new(previous);
+        let config = rollout_config();
+        manager.stage(matcher(&previous), Arc::clone(&previous), &config);
+        manager.record(Some(Cohort::Stable), 200, Duration::from_millis(5));
+
+        // A second reload restarts the window against the same stable version
+        manager.stage(matcher(&config), Arc::new(config.clone()), &config);
+        assert!(manager.stable_upstreams().contains(&"legacy".to_string()));
+        let state = manager.rollout.read();
+        let rollout = state.as_ref().unwrap();
+        assert_eq!(rollout.generation, 2);
+        assert_eq!(rollout.stable_stats.snapshot().requests, 0);
+        drop(state);
+
+        // Regression: the stable configuration is handed out for re-applying
+        let mut state = manager.rollout.write();
+        let rollout = state.as_mut().unwrap();
+        rollout.settings.min_requests = 1;
+        rollout.stable_stats.record(200, Duration::from_millis(5));
+        rollout
+            .candidate_stats
+            .record(502, Duration::from_millis(5));
+        drop(state);
+        let rollback = manager.check().unwrap();
+        assert!(Arc::ptr_eq(&rollback.config, &previous));
+        assert_eq!(
+            manager.cohort(&HeaderMap::new(), "10.0.0.1"),
+            Some(Cohort::Stable)
+        );
+        assert!(manager.check().is_none());
+
+        // Re-applying it ends the rollout instead of staging it
+        manager.stage(matcher(&config), Arc::new(config.clone()), &previous);
+        assert!(!manager.is_active());
+        manager.stage(matcher(&previous), Arc::clone(&previous), &config);
+        assert!(manager.is_active());
+    }
+}
@@ -8,6 +8,7 @@ use dashmap::DashMap;
 use prometheus::{register_int_counter, IntCounter};
 use regex::Regex;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, LazyLock};
 use tracing::{debug, info, trace, warn};
@@ -23,7 +24,7 @@ static ROUTE_CACHE_EVICTIONS: LazyLock<Option<IntCounter>> = LazyLock::new(|| {
 
 use zentinel_common::types::Priority;
 use zentinel_common::RouteId;
-use zentinel_config::{MatchCondition, RouteConfig, RoutePolicies};
+use zentinel_config::{ExprContext, Expression, MatchCondition, RouteConfig, RoutePolicies};
 
 /// Route matcher for efficient route selection
 pub struct RouteMatcher {
@@ -37,6 +38,8 @@ pub struct RouteMatcher {
     needs_headers: bool,
     /// Whether any route requires query param matching (optimization flag)
     needs_query_params: bool,
+    /// Whether any route expression reads the client IP (disables the cache)
+    needs_client_ip: bool,
 }
 
 /// Compiled route with pre-processed match conditions
@@ -67,6 +70,8 @@ enum CompiledMatcher {
     Method(Vec<String>),
     /// Query parameter match
     QueryParam { name: String, value: Option<String> },
+    /// Policy expression match
+    Expr(Expression),
 }
 
 /// Host matching logic
@@ -149,19 +154,28 @@ impl RouteMatcher {
 
         // Determine if any routes need headers or query params (optimization)
         let needs_headers = compiled_routes.iter().any(|r| {
-            r.matchers
-                .iter()
-                .any(|m| matches!(m, CompiledMatcher::Header { .. }))
+            r.matchers.iter().any(|m| match m {
+                CompiledMatcher::Header { .. } => true,
+                CompiledMatcher::Expr(expr) => expr.needs_headers(),
+                _ => false,
+            })
         });
         let needs_query_params = compiled_routes.iter().any(|r| {
+            r.matchers.iter().any(|m| match m {
+                CompiledMatcher::QueryParam { .. } => true,
+                CompiledMatcher::Expr(expr) => expr.needs_query_params(),
+                _ => false,
+            })
+        });
+        let needs_client_ip = compiled_routes.iter().any(|r| {
             r.matchers
                 .iter()
-                .any(|m| matches!(m, CompiledMatcher::QueryParam { .. }))
+                .any(|m| matches!(m, CompiledMatcher::Expr(expr) if expr.needs_client_ip()))
         });
 
         info!(
             compiled_routes = compiled_routes.len(),
-            needs_headers, needs_query_params, "Route matcher initialized"
+            needs_headers, needs_query_params, needs_client_ip, "Route matcher initialized"
         );
 
         Ok(Self {
@@ -170,6 +184,7 @@ impl RouteMatcher {
             cache: Arc::new(RouteCache::new(cache_size)),
             needs_headers,
             needs_query_params,
+            needs_client_ip,
         })
     }
 
@@ -185,6 +200,12 @@ impl RouteMatcher {
         self.needs_query_params
     }
 
+    /// Check if any route expression requires the client IP
+    #[inline]
+    pub fn needs_client_ip(&self) -> bool {
+        self.needs_client_ip
+    }
+
     /// Match a request to a route
     pub fn match_request(&self, req: &RequestInfo<'_>) -> Option<RouteMatch> {
         trace!(
@@ -194,14 +215,20 @@ impl RouteMatcher {
             "Starting route matching"
         );
 
-        // Check cache first (lock-free read, zero-allocation on hit)
-        let cached = req.with_cache_key(|key| {
-            self.cache.get(key).map(|r| {
-                let route_id = r.clone();
-                drop(r);
-                route_id
+        // Check cache first (lock-free read, zero-allocation on hit).
+        // Client IPs are not part of the cache key, so routes that match on
+        // them are always evaluated.
+        let cached = if self.needs_client_ip {
+            None
+        } else {
+            req.with_cache_key(|key| {
+                self.cache.get(key).map(|r| {
+                    let route_id = r.clone();
+                    drop(r);
+                    route_id
+                })
             })
-        });
+        };
         if let Some(route_id) = cached {
             trace!(
                 route_id = %route_id,
@@ -252,9 +279,11 @@ impl RouteMatcher {
                 );
 
                 // Update cache — allocate key only on miss (rare after warmup)
-                req.with_cache_key(|key| {
-                    self.cache.insert(key.to_string(), route.id.clone());
-                });
+                if !self.needs_client_ip {
+                    req.with_cache_key(|key| {
+                        self.cache.insert(key.to_string(), route.id.clone());
+                    });
+                }
 
                 trace!(
                     route_id = %route.id,
@@ -342,6 +371,7 @@ impl CompiledRoute {
                     name: name.clone(),
                     value: value.clone(),
                 },
+                MatchCondition::Expr(expr) => CompiledMatcher::Expr(expr.clone()),
             };
             matchers.push(compiled);
         }
@@ -435,6 +465,7 @@ impl CompiledRoute {
                 CompiledMatcher::QueryParam { value, .. } => {
                     condition_score += if value.is_some() { 25 } else { 15 };
                 }
+                CompiledMatcher::Expr(_) => condition_score += 20,
             }
         }
 
@@ -476,6 +507,7 @@ impl CompiledMatcher {
                     false
                 }
             }
+            Self::Expr(expr) => expr.evaluate(req),
         }
     }
 }
@@ -631,6 +663,8 @@ pub struct RequestInfo<'a> {
     headers: Option<HashMap<String, String>>,
     /// Query parameters (lazy-initialized, only if needed)
     query_params: Option<HashMap<String, String>>,
+    /// Client IP (only set if a route expression reads it)
+    client_ip: Option<IpAddr>,
 }
 
 impl<'a> RequestInfo<'a> {
@@ -643,6 +677,7 @@ impl<'a> RequestInfo<'a> {
             host,
             headers: None,
             query_params: None,
+            client_ip: None,
         }
     }
 
@@ -660,6 +695,13 @@ impl<'a> RequestInfo<'a> {
         self
     }
 
+    /// Set the client IP for expression matching (only call if RouteMatcher.needs_client_ip())
+    #[inline]
+    pub fn with_client_ip(mut self, client_ip: Option<IpAddr>) -> Self {
+        self.client_ip = client_ip;
+        self
+    }
+
     /// Get headers (returns empty map if not set)
     #[inline]
     pub fn headers(&self) -> &HashMap<String, String> {
@@ -701,6 +743,14 @@ impl<'a> RequestInfo<'a> {
                     let _ = write!(buf, "\n{k}={v}");
                 }
             }
+            // Same for query params, which are not part of `path`
+            if let Some(ref params) = self.query_params {
+                let mut pairs: Vec<_> = params.iter().collect();
+                pairs.sort_by_key(|(k, _)| k.as_str());
+                for (k, v) in pairs {
+                    let _ = write!(buf, "\n?{k}={v}");
+                }
+            }
             f(&buf)
         })
     }
@@ -750,6 +800,32 @@ impl<'a> RequestInfo<'a> {
     }
 }
 
+impl ExprContext for RequestInfo<'_> {
+    fn method(&self) -> &str {
+        self.method
+    }
+
+    fn path(&self) -> &str {
+        self.path.split('?').next().unwrap_or(self.path)
+    }
+
+    fn host(&self) -> &str {
+        self.host
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers().get(name).map(|v| v.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params().get(name).map(|v| v.as_str())
+    }
+
+    fn client_ip(&self) -> Option<IpAddr> {
+        self.client_ip
+    }
+}
+
 /// Route match result
 #[derive(Debug, Clone)]
 pub struct RouteMatch {
@@ -796,6 +872,7 @@ impl std::fmt::Debug for CompiledMatcher {
             Self::Header { name, .. } => write!(f, "Header({})", name),
             Self::Method(m) => write!(f, "Method({:?})", m),
             Self::QueryParam { name, .. } => write!(f, "QueryParam({})", name),
+            Self::Expr(expr) => write!(f, "Expr({})", expr),
         }
     }
 }
@@ -996,4 +1073,40 @@ mod tests {
             "header-v2"
         );
     }
+
+    #[test]
+    fn test_expr_matching_client_ip() {
+        let expr = r#"request.path.startsWith("/admin") && client.ip.inCidr("10.0.0.0/8")"#;
+        let routes = vec![
+            create_test_route(
+                "catch-all",
+                vec![MatchCondition::PathPrefix("/".to_string())],
+            ),
+            create_test_route(
+                "internal-admin",
+                vec![
+                    MatchCondition::PathPrefix("/".to_string()),
+                    MatchCondition::Expr(expr.parse().unwrap()),
+                ],
+            ),
+        ];
+
+        let matcher = RouteMatcher::new(routes, None).unwrap();
+        assert!(matcher.needs_client_ip());
+
+        let req = RequestInfo::new("GET", "/admin/users", "example.com")
+            .with_client_ip("10.1.2.3".parse().ok());
+        assert_eq!(
+            matcher.match_request(&req).unwrap().route_id.as_str(),
+            "internal-admin"
+        );
+
+        // Same method, host and path from another client must not hit the cache
+        let req = RequestInfo::new("GET", "/admin/users", "example.com")
+            .with_client_ip("192.0.2.1".parse().ok());
+        assert_eq!(
+            matcher.match_request(&req).unwrap().route_id.as_str(),
+            "catch-all"
+        );
+    }
 }
@@ -0,0 +1,1250 @@
+//! Request validation for the `schema-validate` filter
+//!
+//! Loads an OpenAPI 3.1 (JSON or YAML) or plain JSON Schema document when
+//! the configuration is applied and checks requests against it:
+//!
+//! - For OpenAPI documents the operation is matched by method and path
+//!   template (concrete paths before templated ones, after stripping the
+//!   path of the first `servers` URL). Its path, query and header parameters
+//!   are checked from the request headers; its JSON request body once the
+//!   body is complete.
+//! - A plain JSON Schema document validates the body of every request.
+//!
+//! Schemas are compiled as JSON Schema 2020-12, with the document's
+//! `components` reachable through local `$ref`s. Cookie parameters,
+//! parameters described by `content` and OpenAPI 3.0's `nullable` are not
+//! supported.
+//!
+//! Bodies are buffered up to `max-body-bytes` and released upstream only
+//! after they validate; larger bodies are rejected. A body that fails after
+//! the request headers were sent upstream aborts the upstream request.
+//! Rejected requests are answered with 400 and a JSON list of violations.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "orders-api" {
+//!         type "schema-validate"
+//!         schema "/etc/zentinel/schemas/orders.yaml"
+//!         reject-unknown-operations #true
+//!         max-body-bytes 262144
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use bytes::{Bytes, BytesMut};
+use dashmap::DashMap;
+use http::{HeaderMap, Method, Uri};
+use jsonschema::Validator;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::Serialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use zentinel_config::SchemaValidateFilter;
+
+use crate::json_transform::is_json_content_type;
+
+/// Rejected requests per filter, operation and first violation location
+static SCHEMA_VALIDATION_FAILURES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_schema_validation_failures_total",
+        "Requests rejected by schema-validate filters",
+        &["filter", "operation_id", "location"]
+    )
+    .ok()
+});
+
+/// Violations reported per rejected request
+const MAX_VIOLATIONS: usize = 20;
+
+/// Depth limit when following `$ref` chains outside a schema
+const MAX_REF_DEPTH: usize = 16;
+
+/// HTTP methods of an OpenAPI path item
+pub(crate) const OPERATION_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Errors from loading a schema document
+#[derive(Debug, Error)]
+pub enum SchemaLoadError {
+    /// The document could not be read
+    #[error("failed to read schema document '{path}': {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The document is not valid JSON or YAML
+    #[error("failed to parse schema document: {0}")]
+    Parse(String),
+
+    /// A schema in the document does not compile
+    #[error("invalid schema for {context}: {message}")]
+    Schema { context: String, message: String },
+}
+
+// =============================================================================
+// Violations
+// =============================================================================
+
+/// Part of the request a violation refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViolationLocation {
+    Path,
+    Query,
+    Header,
+    Body,
+    /// No operation matched the request
+    Operation,
+}
+
+impl ViolationLocation {
+    /// Label used in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Query => "query",
+            Self::Header => "header",
+            Self::Body => "body",
+            Self::Operation => "operation",
+        }
+    }
+}
+
+/// A single schema violation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// Where the violating value came from
+    pub location: ViolationLocation,
+    /// Parameter name, or JSON pointer into the body (empty for the body
+    /// itself)
+    pub field: String,
+    /// Validation error message
+    pub message: String,
+}
+
+impl Violation {
+    fn new(
+        location: ViolationLocation,
+        field: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            location,
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A request rejected by a `schema-validate` filter
+#[derive(Debug, Clone)]
+pub struct SchemaRejection {
+    /// Filter that rejected the request
+    pub filter_id: String,
+    /// Matched operation, if any
+    pub operation_id: Option<String>,
+    /// Violations found, at most [`MAX_VIOLATIONS`]
+    pub violations: Vec<Violation>,
+}
+
+impl SchemaRejection {
+    /// Count the rejection in the failure metric
+    pub fn record(&self) {
+        if let Some(metric) = SCHEMA_VALIDATION_FAILURES.as_ref() {
+            let location = self
+                .violations
+                .first()
+                .map_or("body", |v| v.location.as_str());
+            metric
+                .with_label_values(&[
+                    &self.filter_id,
+                    self.operation_id.as_deref().unwrap_or("unknown"),
+                    location,
+                ])
+                .inc();
+        }
+    }
+
+    /// JSON body of the 400 response
+    pub fn to_json(&self, request_id: &str) -> String {
+        json!({
+            "error": "Request failed schema validation",
+            "status": 400,
+            "operation_id": self.operation_id,
+            "violations": self.violations,
+            "request_id": request_id,
+        })
+        .to_string()
+    }
+}
+
+// =============================================================================
+// Schema Document
+// =============================================================================
+
+/// Segment of an OpenAPI path template
+#[derive(Debug)]
+enum PathSegment {
+    Literal(String),
+    /// `prefix{name}suffix`
+    Param {
+        prefix: String,
+        name: String,
+        suffix: String,
+    },
+}
+
+impl PathSegment {
+    fn parse(segment: &str) -> Self {
+        if let (Some(open), Some(close)) = (segment.find('{'), segment.rfind('}')) {
+            if open < close {
+                return Self::Param {
+                    prefix: segment[..open].to_string(),
+                    name: segment[open + 1..close].to_string(),
+                    suffix: segment[close + 1..].to_string(),
+                };
+            }
+        }
+        Self::Literal(segment.to_string())
+    }
+}
+
+/// Compiled parameter of an operation
+struct Parameter {
+    name: String,
+    location: ViolationLocation,
+    required: bool,
+    /// Resolved schema, used to convert the raw string values
+    schema: Value,
+    validator: Option<Validator>,
+}
+
+/// Compiled request body of an operation
+struct RequestBody {
+    required: bool,
+    /// Declared media types (empty for plain JSON Schema documents)
+    media_types: Vec<String>,
+    /// Schema of the JSON media type, if one is declared
+    validator: Option<Validator>,
+}
+
+/// Compiled operation
+struct Operation {
+    id: String,
+    /// `None` matches any method
+    method: Option<Method>,
+    /// `None` matches any path
+    path: Option<Vec<PathSegment>>,
+    parameters: Vec<Parameter>,
+    body: Option<RequestBody>,
+}
+
+impl Operation {
+    /// Path parameters if the operation matches the request
+    fn matches(&self, method: &Method, segments: &[&str]) -> Option<HashMap<&str, String>> {
+        if self.method.as_ref().is_some_and(|m| m != method) {
+            return None;
+        }
+        let mut params = HashMap::new();
+        let Some(template) = &self.path else {
+            return Some(params);
+        };
+        if template.len() != segments.len() {
+            return None;
+        }
+        for (expected, actual) in template.iter().zip(segments) {
+            match expected {
+                PathSegment::Literal(literal) if literal.as_str() == *actual => {}
+                PathSegment::Literal(_) => return None,
+                PathSegment::Param {
+                    prefix,
+                    name,
+                    suffix,
+                } => {
+                    let value = actual
+                        .strip_prefix(prefix.as_str())?
+                        .strip_suffix(suffix.as_str())?;
+                    if value.is_empty() {
+                        return None;
+                    }
+                    let value = urlencoding::decode(value)
+                        .map(|v| v.into_owned())
+                        .unwrap_or_else(|_| value.to_string());
+                    params.insert(name.as_str(), value);
+                }
+            }
+        }
+        Some(params)
+    }
+
+    fn template_params(&self) -> usize {
+        self.path.as_ref().map_or(0, |segments| {
+            segments
+                .iter()
+                .filter(|s| matches!(s, PathSegment::Param { .. }))
+                .count()
+        })
+    }
+}
+
+/// A compiled OpenAPI or JSON Schema document
+pub struct SchemaDocument {
+    /// Path prefix of the first `servers` URL
+    base_path: String,
+    /// Operations, concrete paths first
+    operations: Vec<Operation>,
+    /// The document as JSON, for `serve-spec`
+    spec: Bytes,
+}
+
+impl std::fmt::Debug for SchemaDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaDocument")
+            .field("base_path", &self.base_path)
+            .field("operations", &self.operations.len())
+            .finish()
+    }
+}
+
+impl SchemaDocument {
+    /// Load and compile a document from a JSON or YAML file
+    pub fn load(filter_id: &str, path: &Path) -> Result<Self, SchemaLoadError> {
+        let content = std::fs::read_to_string(path).map_err(|source| SchemaLoadError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let document: Value = if path.extension().is_some_and(|e| e == "yaml" || e == "yml") {
+            serde_yaml::from_str(&content).map_err(|e| SchemaLoadError::Parse(e.to_string()))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| SchemaLoadError::Parse(e.to_string()))?
+        };
+        Self::from_value(filter_id, &document)
+    }
+
+    /// Compile a parsed document
+    ///
+    /// Documents with an `openapi` field are read as OpenAPI; anything else
+    /// is a JSON Schema for request bodies, identified by `filter_id`.
+    pub fn from_value(filter_id: &str, document: &Value) -> Result<Self, SchemaLoadError> {
+        let spec = Bytes::from(document.to_string());
+        if document.get("openapi").is_some() {
+            return OpenApiCompiler::new(document).compile(spec);
+        }
+
+        let validator =
+            jsonschema::validator_for(document).map_err(|e| SchemaLoadError::Schema {
+                context: "request body".to_string(),
+                message: e.to_string(),
+            })?;
+        Ok(Self {
+            base_path: String::new(),
+            operations: vec![Operation {
+                id: filter_id.to_string(),
+                method: None,
+                path: None,
+                parameters: Vec::new(),
+                body: Some(RequestBody {
+                    required: false,
+                    media_types: Vec::new(),
+                    validator: Some(validator),
+                }),
+            }],
+            spec,
+        })
+    }
+
+    /// The document serialized as JSON
+    pub fn spec(&self) -> Bytes {
+        self.spec.clone()
+    }
+
+    /// Number of compiled operations
+    pub fn operation_count(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Check the request line and headers
+    ///
+    /// Returns the body check to run once the body is complete, if the
+    /// matched operation has a JSON body to validate.
+    pub fn check_request(
+        self: &Arc<Self>,
+        filter_id: &str,
+        filter: &SchemaValidateFilter,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        has_body: Option<bool>,
+    ) -> Result<Option<BodyCheck>, SchemaRejection> {
+        let path = uri.path();
+        let path = path
+            .strip_prefix(self.base_path.as_str())
+            .filter(|p| p.is_empty() || p.starts_with('/'))
+            .unwrap_or(path);
+        let path = if path.is_empty() { "/" } else { path };
+        let segments: Vec<&str> = path.split('/').collect();
+
+        let matched = self
+            .operations
+            .iter()
+            .enumerate()
+            .find_map(|(i, op)| op.matches(method, &segments).map(|params| (i, op, params)));
+        let Some((index, operation, path_params)) = matched else {
+            if filter.reject_unknown_operations {
+                return Err(SchemaRejection {
+                    filter_id: filter_id.to_string(),
+                    operation_id: None,
+                    violations: vec![Violation::new(
+                        ViolationLocation::Operation,
+                        "",
+                        format!("no operation matches {} {}", method, uri.path()),
+                    )],
+                });
+            }
+            return Ok(None);
+        };
+
+        let mut violations = Vec::new();
+        if filter.validate_parameters {
+            check_parameters(
+                operation,
+                &path_params,
+                uri.query(),
+                headers,
+                &mut violations,
+            );
+        }
+
+        let mut body_check = None;
+        if let (true, Some(body)) = (filter.validate_body, &operation.body) {
+            let content_type = headers
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok());
+            if has_body == Some(false) {
+                if body.required {
+                    violations.push(Violation::new(
+                        ViolationLocation::Body,
+                        "",
+                        "request body is required",
+                    ));
+                }
+            } else if body.media_types.is_empty() {
+                body_check = body.validator.is_some().then_some(index);
+            } else if let Some(content_type) = content_type {
+                if !body
+                    .media_types
+                    .iter()
+                    .any(|declared| media_type_matches(declared, content_type))
+                {
+                    violations.push(Violation::new(
+                        ViolationLocation::Body,
+                        "",
+                        format!("unsupported content type '{}'", content_type),
+                    ));
+                } else if body.validator.is_some() && is_json_content_type(content_type) {
+                    body_check = Some(index);
+                }
+            } else if body.required || body.validator.is_some() {
+                body_check = Some(index);
+            }
+        }
+
+        if !violations.is_empty() {
+            violations.truncate(MAX_VIOLATIONS);
+            return Err(SchemaRejection {
+                filter_id: filter_id.to_string(),
+                operation_id: Some(operation.id.clone()),
+                violations,
+            });
+        }
+
+        debug!(
+            filter_id = %filter_id,
+            operation_id = %operation.id,
+            "Request matched schema operation"
+        );
+        Ok(body_check.map(|operation| BodyCheck {
+            filter_id: filter_id.to_string(),
+            document: Arc::clone(self),
+            operation,
+            max_body_bytes: filter.max_body_bytes,
+        }))
+    }
+}
+
+/// Check an operation's parameters against the request
+fn check_parameters(
+    operation: &Operation,
+    path_params: &HashMap<&str, String>,
+    query: Option<&str>,
+    headers: &HeaderMap,
+    violations: &mut Vec<Violation>,
+) {
+    let query: Vec<(String, String)> = query
+        .map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for param in &operation.parameters {
+        let array = schema_types(&param.schema).contains(&"array");
+        let raw: Vec<String> = match param.location {
+            ViolationLocation::Path => path_params
+                .get(param.name.as_str())
+                .cloned()
+                .into_iter()
+                .collect(),
+            ViolationLocation::Query => query
+                .iter()
+                .filter(|(name, _)| *name == param.name)
+                .map(|(_, value)| value.clone())
+                .collect(),
+            ViolationLocation::Header => headers
+                .get_all(param.name.as_str())
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .map(str::to_string)
+                .collect(),
+            _ => continue,
+        };
+        if raw.is_empty() {
+            if param.required {
+                violations.push(Violation::new(
+                    param.location,
+                    &param.name,
+                    "required parameter is missing",
+                ));
+            }
+            continue;
+        }
+        let Some(validator) = &param.validator else {
+            continue;
+        };
+
+        let value = if array {
+            // Path and header arrays use the comma-separated simple style;
+            // query arrays repeat the parameter
+            let items = param.schema.get("items").unwrap_or(&Value::Null);
+            let values: Vec<Value> = if param.location == ViolationLocation::Query {
+                raw.iter().map(|v| coerce(v, items)).collect()
+            } else {
+                raw.iter()
+                    .flat_map(|v| v.split(','))
+                    .map(|v| coerce(v.trim(), items))
+                    .collect()
+            };
+            Value::Array(values)
+        } else {
+            coerce(&raw[0], &param.schema)
+        };
+
+        for error in validator.iter_errors(&value) {
+            let pointer = error.instance_path().to_string();
+            violations.push(Violation::new(
+                param.location,
+                format!("{}{}", param.name, pointer),
+                error.to_string(),
+            ));
+        }
+    }
+}
+
+/// Convert a raw parameter value to the first JSON type the schema allows
+fn coerce(raw: &str, schema: &Value) -> Value {
+    for ty in schema_types(schema) {
+        match ty {
+            "integer" => {
+                if let Ok(i) = raw.parse::<i64>() {
+                    return Value::from(i);
+                }
+            }
+            "number" => {
+                if let Some(n) = raw
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                {
+                    return Value::Number(n);
+                }
+            }
+            "boolean" => match raw {
+                "true" => return Value::Bool(true),
+                "false" => return Value::Bool(false),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Value::String(raw.to_string())
+}
+
+/// Types listed in a schema's `type` keyword
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Match a request content type against a declared media type range
+fn media_type_matches(declared: &str, content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let declared = declared
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if declared == "*/*" {
+        return true;
+    }
+    match declared.strip_suffix("/*") {
+        Some(kind) => essence
+            .split_once('/')
+            .is_some_and(|(actual, _)| actual == kind),
+        None => declared == essence,
+    }
+}
+
+// =============================================================================
+// OpenAPI Compilation
+// =============================================================================
+
+/// Compiles the operations of an OpenAPI document
+struct OpenApiCompiler<'a> {
+    document: &'a Value,
+}
+
+impl<'a> OpenApiCompiler<'a> {
+    fn new(document: &'a Value) -> Self {
+        Self { document }
+    }
+
+    fn compile(&self, spec: Bytes) -> Result<SchemaDocument, SchemaLoadError> {
+        let document = self.document;
+        let version = document
+            .get("openapi")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !version.starts_with("3.") {
+            return Err(SchemaLoadError::Parse(format!(
+                "unsupported OpenAPI version '{}' (expected 3.x)",
+                version
+            )));
+        }
+
+        let base_path = document
+            .pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .map(server_path)
+            .unwrap_or_default();
+
+        let mut operations = Vec::new();
+        let paths = document.get("paths").and_then(Value::as_object);
+        for (template, item) in paths.into_iter().flatten() {
+            let item = self.resolve(item);
+            let shared = item.get("parameters").and_then(Value::as_array);
+            for method in OPERATION_METHODS {
+                let Some(op) = item.get(method) else {
+                    continue;
+                };
+                operations.push(self.compile_operation(template, method, op, shared)?);
+            }
+        }
+
+        // Concrete paths match before templated ones
+        operations.sort_by_key(Operation::template_params);
+
+        Ok(SchemaDocument {
+            base_path,
+            operations,
+            spec,
+        })
+    }
+
+    fn compile_operation(
+        &self,
+        template: &str,
+        method: &str,
+        op: &'a Value,
+        shared: Option<&'a Vec<Value>>,
+    ) -> Result<Operation, SchemaLoadError> {
+        let id = op
+            .get("operationId")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", method.to_ascii_uppercase(), template));
+
+        // Operation-level parameters override path-level ones
+        let mut declared: Vec<&Value> = Vec::new();
+        let own = op.get("parameters").and_then(Value::as_array);
+        for param in own.into_iter().chain(shared).flatten() {
+            let param = self.resolve(param);
+            let key = (param.get("name"), param.get("in"));
+            if !declared.iter().any(|p| (p.get("name"), p.get("in")) == key) {
+                declared.push(param);
+            }
+        }
+
+        let mut parameters = Vec::new();
+        for param in declared {
+            let Some(name) = param.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let location = match param.get("in").and_then(Value::as_str) {
+                Some("path") => ViolationLocation::Path,
+                Some("query") => ViolationLocation::Query,
+                Some("header") => ViolationLocation::Header,
+                _ => continue,
+            };
+            let schema = param.get("schema");
+            let validator = schema
+                .map(|s| self.compile_schema(s, &format!("{} parameter '{}'", id, name)))
+                .transpose()?;
+            parameters.push(Parameter {
+                name: if location == ViolationLocation::Header {
+                    name.to_ascii_lowercase()
+                } else {
+                    name.to_string()
+                },
+                location,
+                required: location == ViolationLocation::Path
+                    || param.get("required").and_then(Value::as_bool) == Some(true),
+                schema: schema.map(|s| self.resolve(s).clone()).unwrap_or_default(),
+                validator,
+            });
+        }
+
+        let body = match op.get("requestBody") {
+            Some(body) => Some(self.compile_body(&id, self.resolve(body))?),
+            None => None,
+        };
+
+        Ok(Operation {
+            id,
+            method: Method::from_bytes(method.to_ascii_uppercase().as_bytes()).ok(),
+            path: Some(template.split('/').map(PathSegment::parse).collect()),
+            parameters,
+            body,
+        })
+    }
+
+    fn compile_body(&self, id: &str, body: &Value) -> Result<RequestBody, SchemaLoadError> {
+        let content = body.get("content").and_then(Value::as_object);
+        let media_types: Vec<String> = content
+            .map(|c| c.keys().cloned().collect())
+            .unwrap_or_default();
+        let json_schema = content.into_iter().flatten().find_map(|(media, spec)| {
+            is_json_content_type(media)
+                .then(|| spec.get("schema"))
+                .flatten()
+        });
+        let validator = json_schema
+            .map(|s| self.compile_schema(s, &format!("{} request body", id)))
+            .transpose()?;
+
+        Ok(RequestBody {
+            required: body.get("required").and_then(Value::as_bool) == Some(true),
+            media_types,
+            validator,
+        })
+    }
+
+    /// Compile a schema with the document's components in scope
+    fn compile_schema(&self, schema: &Value, context: &str) -> Result<Validator, SchemaLoadError> {
+        let mut wrapper = json!({ "allOf": [schema] });
+        if let Some(components) = self.document.get("components") {
+            wrapper["components"] = components.clone();
+        }
+        jsonschema::draft202012::new(&wrapper).map_err(|e| SchemaLoadError::Schema {
+            context: context.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Follow local `$ref`s to the referenced object
+    fn resolve(&self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_REF_DEPTH {
+            let Some(pointer) = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.strip_prefix('#'))
+            else {
+                break;
+            };
+            match self.document.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+}
+
+/// Path component of a `servers` URL, without a trailing slash
+pub(crate) fn server_path(url: &str) -> String {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |i| &rest[i..]),
+        None => url,
+    };
+    path.trim_end_matches('/').to_string()
+}
+
+// =============================================================================
+// Body Validation
+// =============================================================================
+
+/// Pending body validation for one filter
+#[derive(Debug)]
+pub struct BodyCheck {
+    filter_id: String,
+    document: Arc<SchemaDocument>,
+    operation: usize,
+    max_body_bytes: usize,
+}
+
+impl BodyCheck {
+    fn operation(&self) -> &Operation {
+        &self.document.operations[self.operation]
+    }
+
+    fn rejection(&self, violations: Vec<Violation>) -> SchemaRejection {
+        SchemaRejection {
+            filter_id: self.filter_id.clone(),
+            operation_id: Some(self.operation().id.clone()),
+            violations,
+        }
+    }
+
+    fn validate(&self, data: &[u8]) -> Result<(), SchemaRejection> {
+        let Some(body) = &self.operation().body else {
+            return Ok(());
+        };
+        if data.is_empty() {
+            if body.required {
+                return Err(self.rejection(vec![Violation::new(
+                    ViolationLocation::Body,
+                    "",
+                    "request body is required",
+                )]));
+            }
+            return Ok(());
+        }
+        let Some(validator) = &body.validator else {
+            return Ok(());
+        };
+
+        let instance: Value = serde_json::from_slice(data).map_err(|e| {
+            self.rejection(vec![Violation::new(
+                ViolationLocation::Body,
+                "",
+                format!("request body is not valid JSON: {}", e),
+            )])
+        })?;
+        let violations: Vec<Violation> = validator
+            .iter_errors(&instance)
+            .take(MAX_VIOLATIONS)
+            .map(|error| {
+                Violation::new(
+                    ViolationLocation::Body,
+                    error.instance_path().to_string(),
+                    error.to_string(),
+                )
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(self.rejection(violations))
+        }
+    }
+}
+
+/// Buffers one request body for the `schema-validate` filters of a route
+#[derive(Debug)]
+pub struct RequestBodyValidation {
+    checks: Vec<BodyCheck>,
+    buffer: BytesMut,
+    max_body_bytes: usize,
+}
+
+impl RequestBodyValidation {
+    /// Buffer for the given checks, bounded by their smallest limit
+    pub fn new(checks: Vec<BodyCheck>) -> Self {
+        let max_body_bytes = checks
+            .iter()
+            .map(|c| c.max_body_bytes)
+            .min()
+            .unwrap_or_default();
+        Self {
+            checks,
+            buffer: BytesMut::new(),
+            max_body_bytes,
+        }
+    }
+
+    /// Hold back a body chunk; at end of stream validate the whole body and
+    /// release it as a single chunk
+    pub fn process(
+        &mut self,
+        body: &mut Option<Bytes>,
+        end_of_stream: bool,
+    ) -> Result<(), SchemaRejection> {
+        if let Some(chunk) = body.take() {
+            if self.buffer.len() + chunk.len() > self.max_body_bytes {
+                let check = self
+                    .checks
+                    .iter()
+                    .min_by_key(|c| c.max_body_bytes)
+                    .expect("body validation has at least one check");
+                return Err(check.rejection(vec![Violation::new(
+                    ViolationLocation::Body,
+                    "",
+                    format!("request body exceeds {} bytes", self.max_body_bytes),
+                )]));
+            }
+            self.buffer.extend_from_slice(&chunk);
+        }
+        if !end_of_stream {
+            return Ok(());
+        }
+
+        let data = std::mem::take(&mut self.buffer).freeze();
+        for check in &self.checks {
+            check.validate(&data)?;
+        }
+        if !data.is_empty() {
+            *body = Some(data);
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// SchemaValidateManager
+// =============================================================================
+
+/// Compiled documents of all `schema-validate` filters
+pub struct SchemaValidateManager {
+    /// Filter ID → compiled document
+    documents: DashMap<String, Arc<SchemaDocument>>,
+}
+
+impl SchemaValidateManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            documents: DashMap::new(),
+        }
+    }
+
+    /// Load and compile a filter's document
+    pub fn register_filter(
+        &self,
+        filter_id: &str,
+        config: &SchemaValidateFilter,
+    ) -> Result<(), SchemaLoadError> {
+        let document = SchemaDocument::load(filter_id, Path::new(&config.schema))?;
+        debug!(
+            filter_id = %filter_id,
+            operations = document.operation_count(),
+            "Registered schema-validate filter"
+        );
+        self.documents
+            .insert(filter_id.to_string(), Arc::new(document));
+        Ok(())
+    }
+
+    /// Compiled document of a filter
+    pub fn get(&self, filter_id: &str) -> Option<Arc<SchemaDocument>> {
+        self.documents.get(filter_id).map(|r| Arc::clone(&r))
+    }
+
+    /// Get all filter IDs
+    pub fn filter_ids(&self) -> Vec<String> {
+        self.documents.iter().map(|r| r.key().clone()).collect()
+    }
+
+    /// Reload documents from a new configuration
+    ///
+    /// Every document is read again, so edits are picked up on reload. A
+    /// document that fails to load keeps its previous version, if any.
+    pub fn reload(&self, filters: &HashMap<String, SchemaValidateFilter>) {
+        self.documents.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            if let Err(e) = self.register_filter(filter_id, config) {
+                warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload schema-validate document"
+                );
+            }
+        }
+    }
+}
+
+impl Default for SchemaValidateManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orders_api() -> Arc<SchemaDocument> {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "servers": [{ "url": "https://api.example.com/v1" }],
+            "paths": {
+                "/orders": {
+                    "post": {
+                        "operationId": "createOrder",
+                        "parameters": [
+                            { "$ref": "#/components/parameters/Tenant" }
+                        ],
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Order" }
+                                }
+                            }
+                        }
+                    }
+                },
+                "/orders/{id}": {
+                    "parameters": [
+                        { "name": "id", "in": "path", "schema": { "type": "integer" } }
+                    ],
+                    "get": {
+                        "operationId": "getOrder",
+                        "parameters": [
+                            {
+                                "name": "fields",
+                                "in": "query",
+                                "schema": { "type": "array", "items": { "enum": ["id", "items"] } }
+                            }
+                        ]
+                    }
+                },
+                "/orders/latest": {
+                    "get": { "operationId": "latestOrder" }
+                }
+            },
+            "components": {
+                "parameters": {
+                    "Tenant": {
+                        "name": "X-Tenant",
+                        "in": "header",
+                        "required": true,
+                        "schema": { "type": "string", "minLength": 3 }
+                    }
+                },
+                "schemas": {
+                    "Order": {
+                        "type": "object",
+                        "required": ["items"],
+                        "properties": {
+                            "items": {
+                                "type": "array",
+                                "minItems": 1,
+                                "items": { "$ref": "#/components/schemas/Item" }
+                            }
+                        }
+                    },
+                    "Item": {
+                        "type": "object",
+                        "required": ["sku"],
+                        "properties": { "sku": { "type": "string" } }
+                    }
+                }
+            }
+        });
+        Arc::new(SchemaDocument::from_value("orders", &spec).unwrap())
+    }
+
+    fn check(
+        document: &Arc<SchemaDocument>,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<Option<BodyCheck>, SchemaRejection> {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        document.check_request(
+            "orders",
+            &SchemaValidateFilter::new("orders.yaml"),
+            &method.parse().unwrap(),
+            &uri.parse().unwrap(),
+            &map,
+            None,
+        )
+    }
+
+    #[test]
+    fn matches_operations_by_method_and_template() {
+        let api = orders_api();
+        assert!(check(&api, "GET", "/v1/orders/42?fields=items", &[])
+            .unwrap()
+            .is_none());
+        // Concrete paths win over templates
+        assert!(check(&api, "GET", "/v1/orders/latest", &[]).is_ok());
+
+        let rejection = check(&api, "GET", "/v1/orders/abc?fields=secret", &[]).unwrap_err();
+        assert_eq!(rejection.operation_id.as_deref(), Some("getOrder"));
+        let fields: Vec<_> = rejection
+            .violations
+            .iter()
+            .map(|v| v.field.as_str())
+            .collect();
+        assert_eq!(fields, ["id", "fields/0"]);
+        assert_eq!(rejection.violations[0].location, ViolationLocation::Path);
+
+        // Unknown operations pass unless rejected by configuration
+        assert!(check(&api, "DELETE", "/v1/orders/1", &[])
+            .unwrap()
+            .is_none());
+        let mut filter = SchemaValidateFilter::new("orders.yaml");
+        filter.reject_unknown_operations = true;
+        let rejection = api
+            .check_request(
+                "orders",
+                &filter,
+                &Method::DELETE,
+                &"/v1/orders/1".parse().unwrap(),
+                &HeaderMap::new(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            rejection.violations[0].location,
+            ViolationLocation::Operation
+        );
+    }
+
+    #[test]
+    fn validates_headers_and_json_bodies() {
+        let api = orders_api();
+        let rejection = check(
+            &api,
+            "POST",
+            "/v1/orders",
+            &[("content-type", "application/json")],
+        )
+        .unwrap_err();
+        assert_eq!(rejection.violations[0].field, "x-tenant");
+        assert_eq!(
+            rejection.violations[0].message,
+            "required parameter is missing"
+        );
+
+        let rejection = check(
+            &api,
+            "POST",
+            "/v1/orders",
+            &[("x-tenant", "acme"), ("content-type", "text/plain")],
+        )
+        .unwrap_err();
+        assert!(rejection.violations[0]
+            .message
+            .contains("unsupported content type"));
+
+        let headers = [("x-tenant", "acme"), ("content-type", "application/json")];
+        let body_check = check(&api, "POST", "/v1/orders", &headers)
+            .unwrap()
+            .unwrap();
+        let mut validation = RequestBodyValidation::new(vec![body_check]);
+        let mut chunk = Some(Bytes::from_static(br#"{"items": [{"sku": 1}"#));
+        validation.process(&mut chunk, false).unwrap();
+        assert!(chunk.is_none());
+        let mut chunk = Some(Bytes::from_static(b"]}"));
+        let rejection = validation.process(&mut chunk, true).unwrap_err();
+        assert_eq!(rejection.operation_id.as_deref(), Some("createOrder"));
+        assert_eq!(rejection.violations[0].field, "/items/0/sku");
+
+        let body_check = check(&api, "POST", "/v1/orders", &headers)
+            .unwrap()
+            .unwrap();
+        let mut validation = RequestBodyValidation::new(vec![body_check]);
+        let mut chunk = Some(Bytes::from_static(br#"{"items": [{"sku": "a-1"}]}"#));
+        validation.process(&mut chunk, true).unwrap();
+        assert_eq!(
+            chunk.as_deref(),
+            Some(&br#"{"items": [{"sku": "a-1"}]}"#[..])
+        );
+    }
+
+    #[test]
+    fn plain_json_schema_limits_body_size() {
+        let schema = json!({ "type": "object", "required": ["name"] });
+        let document = Arc::new(SchemaDocument::from_value("users", &schema).unwrap());
+        let mut filter = SchemaValidateFilter::new("user.json");
+        filter.max_body_bytes = 8;
+
+        let body_check = document
+            .check_request(
+                "users",
+                &filter,
+                &Method::PUT,
+                &"/anything".parse().unwrap(),
+                &HeaderMap::new(),
+                Some(true),
+            )
+            .unwrap()
+            .unwrap();
+        let mut validation = RequestBodyValidation::new(vec![body_check]);
+        let mut chunk = Some(Bytes::from_static(br#"{"name": "x"}"#));
+        let rejection = validation.process(&mut chunk, true).unwrap_err();
+        assert_eq!(rejection.operation_id.as_deref(), Some("users"));
+        assert!(rejection.violations[0].message.contains("exceeds 8 bytes"));
+
+        let body: Value = serde_json::from_str(&rejection.to_json("req-1")).unwrap();
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["violations"][0]["location"], "body");
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        let spec = json!({ "openapi": "2.0", "paths": {} });
+        assert!(matches!(
+            SchemaDocument::from_value("old", &spec),
+            Err(SchemaLoadError::Parse(_))
+        ));
+        let schema = json!({ "type": 12 });
+        assert!(matches!(
+            SchemaDocument::from_value("bad", &schema),
+            Err(SchemaLoadError::Schema { .. })
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! `Server-Timing` response header
+//!
+//! Routes with `policies { server-timing }` report where a response's
+//! latency came from, as seen when its headers are sent:
+//!
+//! - `route`: matching the request to a route
+//! - `agents`: waiting for agents; agents called in parallel count once
+//! - `agent-<id>`: each agent, with `per-agent`
+//! - `upstream`: from handing the request to the upstream to its response
+//!   headers, including connection setup
+//! - `total`: from the start of the request to the response headers
+//!
+//! With `privacy`, durations are rounded up to one of [`COARSE_BUCKETS_MS`]
+//! and per-agent entries are left out, so agent IDs and precise timings are
+//! not exposed. Entries set by the upstream are kept.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use zentinel_config::ServerTimingConfig;
+
+use crate::agents::AgentTimings;
+
+/// Bucket bounds, in milliseconds, that durations are rounded up to in
+/// privacy mode; longer durations report the last bound
+pub const COARSE_BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Phase durations of a request
+#[derive(Debug, Clone)]
+pub struct Phases<'a> {
+    pub route: Option<Duration>,
+    pub agents: &'a AgentTimings,
+    pub upstream: Option<Duration>,
+    pub total: Duration,
+}
+
+/// `Server-Timing` header value for a request's phases
+pub fn header_value(config: &ServerTimingConfig, phases: &Phases<'_>) -> String {
+    let mut entries = Vec::new();
+    if let Some(route) = phases.route {
+        entries.push(("route".to_string(), route));
+    }
+    if !phases.agents.by_agent.is_empty() {
+        entries.push(("agents".to_string(), phases.agents.total));
+        if config.per_agent && !config.privacy {
+            let mut agents: Vec<_> = phases.agents.by_agent.iter().collect();
+            agents.sort();
+            for (id, duration) in agents {
+                entries.push((format!("agent-{}", metric_name(id)), *duration));
+            }
+        }
+    }
+    if let Some(upstream) = phases.upstream {
+        entries.push(("upstream".to_string(), upstream));
+    }
+    entries.push(("total".to_string(), phases.total));
+
+    let mut value = String::new();
+    for (name, duration) in entries {
+        if !value.is_empty() {
+            value.push_str(", ");
+        }
+        if config.privacy {
+            let _ = write!(value, "{};dur={}", name, coarse_ms(duration));
+        } else {
+            let _ = write!(
+                value,
+                "{};dur={:.1}",
+                name,
+                duration.as_micros() as f64 / 1000.0
+            );
+        }
+    }
+    value
+}
+
+/// Smallest bucket bound the duration fits in
+fn coarse_ms(duration: Duration) -> u64 {
+    let micros = duration.as_micros();
+    COARSE_BUCKETS_MS
+        .iter()
+        .copied()
+        .find(|bound| micros <= u128::from(*bound) * 1000)
+        .unwrap_or(COARSE_BUCKETS_MS[COARSE_BUCKETS_MS.len() - 1])
+}
+
+/// Agent ID as a header token, with other characters replaced by `_`
+fn metric_name(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn agents() -> AgentTimings {
+        AgentTimings {
+            total: Duration::from_micros(7_240),
+            by_agent: HashMap::from([
+                ("waf".to_string(), Duration::from_millis(7)),
+                ("auth v2".to_string(), Duration::from_millis(3)),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_full_header() {
+        let agents = agents();
+        let phases = Phases {
+            route: Some(Duration::from_micros(120)),
+            agents: &agents,
+            upstream: Some(Duration::from_millis(42)),
+            total: Duration::from_micros(50_020),
+        };
+
+        let config = ServerTimingConfig::default();
+        assert_eq!(
+            header_value(&config, &phases),
+            "route;dur=0.1, agents;dur=7.2, upstream;dur=42.0, total;dur=50.0"
+        );
+
+        let config = ServerTimingConfig {
+            per_agent: true,
+            privacy: false,
+        };
+        assert_eq!(
+            header_value(&config, &phases),
+            "route;dur=0.1, agents;dur=7.2, agent-auth_v2;dur=3.0, agent-waf;dur=7.0, \
+             upstream;dur=42.0, total;dur=50.0"
+        );
+    }
+
+    #[test]
+    fn test_privacy_uses_coarse_buckets() {
+        let agents = agents();
+        let phases = Phases {
+            route: None,
+            agents: &agents,
+            upstream: None,
+            total: Duration::from_secs(30),
+        };
+        let config = ServerTimingConfig {
+            per_agent: true,
+            privacy: true,
+        };
+        assert_eq!(
+            header_value(&config, &phases),
+            "agents;dur=10, total;dur=10000"
+        );
+
+        assert_eq!(coarse_ms(Duration::ZERO), 1);
+        assert_eq!(coarse_ms(Duration::from_millis(25)), 25);
+        assert_eq!(coarse_ms(Duration::from_micros(25_001)), 50);
+    }
+
+    #[test]
+    fn test_requests_without_agents() {
+        let agents = AgentTimings::default();
+        let phases = Phases {
+            route: Some(Duration::from_millis(1)),
+            agents: &agents,
+            upstream: Some(Duration::from_millis(2)),
+            total: Duration::from_millis(4),
+        };
+        assert_eq!(
+            header_value(&ServerTimingConfig::default(), &phases),
+            "route;dur=1.0, upstream;dur=2.0, total;dur=4.0"
+        );
+    }
+}
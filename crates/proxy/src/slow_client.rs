@@ -0,0 +1,207 @@
+//! Slow-client (slowloris) protection.
+//!
+//! Listeners carry a `slow-client` block that routes may override setting
+//! by setting. The limits map onto the request lifecycle as follows:
+//!
+//! - `header-timeout-secs`: caps the keep-alive timeout, which Pingora
+//!   applies to every read of the next request's headers. The headers of a
+//!   connection's first request are read before Zentinel gets control.
+//! - `min-body-rate`: average upload rate from the first body chunk on,
+//!   checked by [`SlowClientGuard::on_request_body`] once `rate-grace-secs`
+//!   have passed. A client that stops sending entirely is caught by the
+//!   listener's `request-timeout-secs` read timeout.
+//! - `min-send-rate`: handed to Pingora, which derives write timeouts from
+//!   it for each response chunk.
+//! - `max-request-duration-secs`: checked on every request and response body
+//!   chunk.
+//!
+//! Offending connections are closed and counted in
+//! `zentinel_slow_client_closed_total{reason}`.
+
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::fmt;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use zentinel_config::SlowClientConfig;
+
+/// Connections closed for being too slow, by reason
+static SLOW_CLIENT_CLOSED: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_slow_client_closed_total",
+        "Connections closed by slow-client protection",
+        &["reason"]
+    )
+    .ok()
+});
+
+/// Count a connection closed for being too slow.
+///
+/// Reasons: `body_rate`, `max_duration`, `read_timeout`, `send_timeout`.
+pub fn record_closed(reason: &str) {
+    if let Some(counter) = SLOW_CLIENT_CLOSED.as_ref() {
+        counter.with_label_values(&[reason]).inc();
+    }
+}
+
+/// A slow-client limit was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowClientViolation {
+    /// The request body arrives slower than `min-body-rate`
+    BodyRate { rate: u64, min_rate: u64 },
+    /// The request exceeded `max-request-duration-secs`
+    MaxDuration { limit_secs: u64 },
+}
+
+impl SlowClientViolation {
+    /// Metric label for this violation
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::BodyRate { .. } => "body_rate",
+            Self::MaxDuration { .. } => "max_duration",
+        }
+    }
+}
+
+impl fmt::Display for SlowClientViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BodyRate { rate, min_rate } => write!(
+                f,
+                "request body rate {} B/s below minimum {} B/s",
+                rate, min_rate
+            ),
+            Self::MaxDuration { limit_secs } => {
+                write!(f, "request exceeded maximum duration of {}s", limit_secs)
+            }
+        }
+    }
+}
+
+/// Per-request slow-client state
+#[derive(Debug)]
+pub struct SlowClientGuard {
+    config: SlowClientConfig,
+    started: Instant,
+    body_started: Option<Instant>,
+    body_bytes: u64,
+}
+
+impl SlowClientGuard {
+    /// Start tracking a request whose headers just arrived
+    pub fn new(config: SlowClientConfig) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+            body_started: None,
+            body_bytes: 0,
+        }
+    }
+
+    /// Effective settings for this request
+    pub fn config(&self) -> &SlowClientConfig {
+        &self.config
+    }
+
+    /// Account for a request body chunk and check the body rate and the
+    /// request duration.
+    pub fn on_request_body(&mut self, chunk_len: usize) -> Result<(), SlowClientViolation> {
+        self.on_request_body_at(chunk_len, Instant::now())
+    }
+
+    /// Check the request duration.
+    pub fn check_duration(&self) -> Result<(), SlowClientViolation> {
+        self.check_duration_at(Instant::now())
+    }
+
+    fn on_request_body_at(
+        &mut self,
+        chunk_len: usize,
+        now: Instant,
+    ) -> Result<(), SlowClientViolation> {
+        self.check_duration_at(now)?;
+
+        let body_started = *self.body_started.get_or_insert(now);
+        self.body_bytes += chunk_len as u64;
+
+        let Some(min_rate) = self.config.min_body_rate else {
+            return Ok(());
+        };
+        let grace = Duration::from_secs(
+            self.config
+                .rate_grace_secs
+                .unwrap_or(SlowClientConfig::DEFAULT_RATE_GRACE_SECS),
+        );
+        let elapsed = now.duration_since(body_started);
+        if elapsed <= grace {
+            return Ok(());
+        }
+        let rate = (self.body_bytes as f64 / elapsed.as_secs_f64()) as u64;
+        if rate < min_rate {
+            return Err(SlowClientViolation::BodyRate { rate, min_rate });
+        }
+        Ok(())
+    }
+
+    fn check_duration_at(&self, now: Instant) -> Result<(), SlowClientViolation> {
+        match self.config.max_request_duration_secs {
+            Some(limit_secs)
+                if now.duration_since(self.started) > Duration::from_secs(limit_secs) =>
+            {
+                Err(SlowClientViolation::MaxDuration { limit_secs })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_rate_enforced_after_grace() {
+        let mut guard = SlowClientGuard::new(SlowClientConfig {
+            min_body_rate: Some(1000),
+            rate_grace_secs: Some(2),
+            ..Default::default()
+        });
+        let start = guard.started;
+
+        assert!(guard.on_request_body_at(10, start).is_ok());
+        // Within the grace period a slow start is tolerated
+        assert!(guard
+            .on_request_body_at(10, start + Duration::from_secs(1))
+            .is_ok());
+        assert_eq!(
+            guard.on_request_body_at(10, start + Duration::from_secs(3)),
+            Err(SlowClientViolation::BodyRate {
+                rate: 10,
+                min_rate: 1000
+            })
+        );
+
+        let mut fast = SlowClientGuard::new(guard.config().clone());
+        let start = fast.started;
+        assert!(fast.on_request_body_at(5000, start).is_ok());
+        assert!(fast
+            .on_request_body_at(5000, start + Duration::from_secs(3))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_max_duration() {
+        let guard = SlowClientGuard::new(SlowClientConfig {
+            max_request_duration_secs: Some(30),
+            ..Default::default()
+        });
+
+        assert!(guard
+            .check_duration_at(guard.started + Duration::from_secs(29))
+            .is_ok());
+        let violation = guard
+            .check_duration_at(guard.started + Duration::from_secs(31))
+            .unwrap_err();
+        assert_eq!(violation.reason(), "max_duration");
+    }
+}
@@ -57,6 +57,8 @@ const MMAP_THRESHOLD: u64 = 10 * 1024 * 1024;
 pub struct StaticFileServer {
     /// Configuration for static file serving
     config: Arc<StaticFileConfig>,
+    /// Root directory with symlinks resolved
+    canonical_root: PathBuf,
     /// Cached file metadata
     cache: Arc<FileCache>,
 }
@@ -74,6 +76,8 @@ impl StaticFileServer {
         );
 
         let cache = Arc::new(FileCache::with_defaults());
+        let canonical_root =
+            std::fs::canonicalize(&config.root).unwrap_or_else(|_| config.root.clone());
 
         debug!(
             root = %config.root.display(),
@@ -82,6 +86,7 @@ impl StaticFileServer {
 
         Self {
             config: Arc::new(config),
+            canonical_root,
             cache,
         }
     }
@@ -177,16 +182,14 @@ impl StaticFileServer {
                 "Path is directory, looking for index"
             );
             // Try to serve index file
-            for index_file in &["index.html", "index.htm"] {
-                let index_path = file_path.join(index_file);
-                if let Ok(index_meta) = fs::metadata(&index_path).await {
-                    if index_meta.is_file() {
-                        trace!(
-                            index_file = %index_file,
-                            "Found index file"
-                        );
-                        return self.serve_file(req, &index_path, index_meta).await;
-                    }
+            let index_path = file_path.join(&self.config.index);
+            if let Ok(index_meta) = fs::metadata(&index_path).await {
+                if index_meta.is_file() {
+                    trace!(
+                        index_file = %self.config.index,
+                        "Found index file"
+                    );
+                    return self.serve_file(req, &index_path, index_meta).await;
                 }
             }
 
@@ -247,6 +250,14 @@ impl StaticFileServer {
         Some(resolved)
     }
 
+    /// Whether `path` stays inside the root once symlinks are resolved
+    async fn is_within_root(&self, path: &Path) -> bool {
+        match fs::canonicalize(path).await {
+            Ok(canonical) => canonical.starts_with(&self.canonical_root),
+            Err(_) => false,
+        }
+    }
+
     /// Find SPA fallback index file
     fn find_spa_fallback(&self) -> Option<PathBuf> {
         if let Some(ref fallback) = self.config.fallback {
@@ -265,6 +276,15 @@ impl StaticFileServer {
         file_path: &Path,
         metadata: std::fs::Metadata,
     ) -> Result<Response<Full<Bytes>>> {
+        // Reject symlinks pointing outside the root
+        if !self.is_within_root(file_path).await {
+            warn!(
+                "File resolves outside root directory: {:?} (root: {:?})",
+                file_path, self.config.root
+            );
+            return self.not_found_response();
+        }
+
         let modified = metadata.modified()?;
         let file_size = metadata.len();
 
@@ -401,24 +421,27 @@ impl StaticFileServer {
         etag: &str,
         modified: std::time::SystemTime,
     ) -> Result<Option<Response<Full<Bytes>>>> {
-        // Check If-None-Match (ETag)
+        // Check If-None-Match (ETag). When present, If-Modified-Since is
+        // ignored (RFC 9110 section 13.1.3).
         if let Some(if_none_match) = req.headers().get(header::IF_NONE_MATCH) {
-            if let Ok(if_none_match_str) = if_none_match.to_str() {
-                // Handle multiple ETags separated by commas
-                let matches = if_none_match_str == "*"
-                    || if_none_match_str
-                        .split(',')
-                        .any(|tag| tag.trim().trim_matches('"') == etag.trim_matches('"'));
-
-                if matches {
-                    return Ok(Some(
-                        Response::builder()
-                            .status(StatusCode::NOT_MODIFIED)
-                            .header(header::ETAG, etag)
-                            .body(Full::new(Bytes::new()))?,
-                    ));
-                }
+            let if_none_match_str = if_none_match.to_str().unwrap_or_default();
+            // Handle multiple ETags separated by commas, compared weakly
+            let matches = if_none_match_str.trim() == "*"
+                || if_none_match_str.split(',').any(|tag| {
+                    let tag = tag.trim();
+                    tag.strip_prefix("W/").unwrap_or(tag).trim_matches('"')
+                        == etag.trim_matches('"')
+                });
+
+            if !matches {
+                return Ok(None);
             }
+            return Ok(Some(
+                Response::builder()
+                    .status(StatusCode::NOT_MODIFIED)
+                    .header(header::ETAG, etag)
+                    .body(Full::new(Bytes::new()))?,
+            ));
         }
 
         // Check If-Modified-Since
@@ -768,6 +791,88 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    fn test_server(root: &Path) -> StaticFileServer {
+        StaticFileServer::new(StaticFileConfig {
+            root: root.to_path_buf(),
+            index: "index.html".to_string(),
+            directory_listing: false,
+            cache_control: "public, max-age=3600".to_string(),
+            compress: false,
+            mime_types: std::collections::HashMap::new(),
+            fallback: None,
+        })
+    }
+
+    fn get(path: &str) -> Request<()> {
+        Request::builder().uri(path).body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("public");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "secret").unwrap();
+        let server = test_server(&root);
+
+        for path in [
+            "/../secret.txt",
+            "/%2e%2e/secret.txt",
+            "/a/../../secret.txt",
+        ] {
+            let response = server.serve(&get(path), path).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp_dir.path().join("secret.txt"), root.join("link"))
+                .unwrap();
+            let response = server.serve(&get("/link"), "/link").await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_requests() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "hello").unwrap();
+        let server = test_server(temp_dir.path());
+
+        let response = server.serve(&get("/a.txt"), "/a.txt").await.unwrap();
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+
+        let req = Request::builder()
+            .uri("/a.txt")
+            .header(header::IF_NONE_MATCH, format!("W/{etag}"))
+            .body(())
+            .unwrap();
+        let response = server.serve(&req, "/a.txt").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let req = Request::builder()
+            .uri("/a.txt")
+            .header(header::IF_MODIFIED_SINCE, last_modified.clone())
+            .body(())
+            .unwrap();
+        let response = server.serve(&req, "/a.txt").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // A stale ETag wins over a matching If-Modified-Since
+        let req = Request::builder()
+            .uri("/a.txt")
+            .header(header::IF_NONE_MATCH, "\"stale\"")
+            .header(header::IF_MODIFIED_SINCE, last_modified)
+            .body(())
+            .unwrap();
+        let response = server.serve(&req, "/a.txt").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(0), "0 B");
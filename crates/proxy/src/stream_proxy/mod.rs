@@ -0,0 +1,922 @@
+//! Layer 4 (TCP/TLS) stream proxying.
+//!
+//! A listener with `protocol "tcp"` forwards connections byte for byte to an
+//! upstream pool, served by its own accept loop rather than Pingora. TLS is
+//! passed through: when the listener has SNI routes, the ClientHello is read
+//! (and replayed to the upstream) to pick the route; other connections go to
+//! the listener's default upstream. Server-first protocols, where the client
+//! sends nothing for a few seconds, also take the default upstream.
+//!
+//! Targets are picked by the upstream's load balancer and connect results are
+//! reported to its circuit breakers, as for HTTP routes.
+//!
+//! Agents listed on the listener see each connection as:
+//!
+//! - a `CONNECT` request headers event when it opens (`host` is the server
+//!   name, if any); a block refuses the connection
+//! - request/response body chunks with the first `inspect-bytes` of each
+//!   direction, for a `sample-rate` fraction of connections; a block closes
+//!   the connection
+//! - a final, empty request body chunk (`is_last`) when it closes, with the
+//!   bytes sent by the client as `total_size`
+//!
+//! With `redis` settings, plaintext connections to the default upstream are
+//! parsed as RESP instead of relayed blindly (see the `redis` submodule).
+//!
+//! Exports `zentinel_stream_connections_total{listener, route, outcome}`,
+//! `zentinel_stream_active_connections{listener}` and
+//! `zentinel_stream_bytes_total{listener, direction}`. Routed connections
+//! are listed by the `connections` admin handler while they relay.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+use zentinel_agent_protocol::RequestMetadata;
+use zentinel_common::{CorrelationId, Registry};
+use zentinel_config::{
+    Config, FailureMode, ListenerConfig, ListenerProtocol, RedisStreamConfig, StreamProxyConfig,
+};
+
+use crate::agents::{AgentCallContext, AgentManager};
+use crate::reload::ConfigManager;
+use crate::upstream::UpstreamPool;
+
+mod redis;
+
+/// Time the client has to send its first bytes before a connection is
+/// treated as server-first and sent to the default upstream
+const FIRST_BYTES_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Time allowed to receive the whole ClientHello
+const CLIENT_HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest ClientHello read while looking for the server name
+const MAX_CLIENT_HELLO: usize = 16 * 1024;
+
+/// TLS record content type of a handshake
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// Route label of connections sent to the default upstream
+const DEFAULT_ROUTE: &str = "default";
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Prometheus metrics labelled by listener.
+struct StreamMetrics {
+    /// Connections, per route and outcome
+    connections: IntCounterVec,
+    /// Connections currently forwarded
+    active: IntGaugeVec,
+    /// Bytes forwarded, per direction
+    bytes: IntCounterVec,
+}
+
+static STREAM_METRICS: LazyLock<Option<StreamMetrics>> = LazyLock::new(|| {
+    let connections = register_int_counter_vec!(
+        "zentinel_stream_connections_total",
+        "TCP stream proxy connections by outcome",
+        &["listener", "route", "outcome"]
+    )
+    .ok()?;
+    let active = register_int_gauge_vec!(
+        "zentinel_stream_active_connections",
+        "TCP stream proxy connections currently open",
+        &["listener"]
+    )
+    .ok()?;
+    let bytes = register_int_counter_vec!(
+        "zentinel_stream_bytes_total",
+        "Bytes forwarded by the TCP stream proxy (upstream = client to upstream)",
+        &["listener", "direction"]
+    )
+    .ok()?;
+    Some(StreamMetrics {
+        connections,
+        active,
+        bytes,
+    })
+});
+
+/// Start the accept loops of the `tcp` listeners on `runtime`.
+pub fn spawn_listeners(
+    listeners: &[ListenerConfig],
+    config_manager: Arc<ConfigManager>,
+    agent_manager: Arc<AgentManager>,
+    upstream_pools: Registry<UpstreamPool>,
+    runtime: &tokio::runtime::Handle,
+) {
+    for listener in listeners {
+        if listener.protocol != ListenerProtocol::Tcp {
+            continue;
+        }
+        let proxy = Arc::new(StreamProxy {
+            listener_id: listener.id.clone(),
+            config_manager: Arc::clone(&config_manager),
+            agent_manager: Arc::clone(&agent_manager),
+            upstream_pools: upstream_pools.clone(),
+        });
+        runtime.spawn(run_listener(proxy, listener.address.clone()));
+    }
+}
+
+async fn run_listener(proxy: Arc<StreamProxy>, address: String) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                listener_id = %proxy.listener_id,
+                address = %address,
+                error = %e,
+                "Failed to bind TCP stream listener"
+            );
+            return;
+        }
+    };
+    info!(
+        listener_id = %proxy.listener_id,
+        address = %address,
+        "TCP stream proxy listening on: {}", address
+    );
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(listener_id = %proxy.listener_id, error = %e, "TCP stream accept failed");
+                continue;
+            }
+        };
+        let proxy = Arc::clone(&proxy);
+        tokio::spawn(async move { proxy.handle_connection(stream, peer).await });
+    }
+}
+
+/// How a relayed connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelayEnd {
+    /// Both sides closed
+    Closed,
+    /// No traffic for the idle timeout
+    Idle,
+    /// An agent blocked the payload
+    Blocked,
+}
+
+/// Bytes forwarded in each direction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Transfer {
+    /// Client to upstream
+    sent: u64,
+    /// Upstream to client
+    received: u64,
+}
+
+/// A `tcp` listener
+struct StreamProxy {
+    listener_id: String,
+    config_manager: Arc<ConfigManager>,
+    agent_manager: Arc<AgentManager>,
+    upstream_pools: Registry<UpstreamPool>,
+}
+
+impl StreamProxy {
+    /// Stream routing of this listener in the running configuration
+    fn rules(&self) -> Option<StreamProxyConfig> {
+        let config = self.config_manager.current();
+        let listener = config
+            .listeners
+            .iter()
+            .find(|l| l.id == self.listener_id && l.protocol == ListenerProtocol::Tcp)?;
+        listener.stream.clone()
+    }
+
+    async fn handle_connection(&self, mut client: TcpStream, peer: SocketAddr) {
+        // A listener removed by a reload keeps its socket until restart but
+        // no longer forwards connections
+        let Some(rules) = self.rules() else {
+            return;
+        };
+
+        let (server_name, prefix) = if rules.routes.is_empty() {
+            (None, Vec::new())
+        } else {
+            match read_server_name(&mut client).await {
+                Ok(hello) => hello,
+                Err(e) => {
+                    debug!(
+                        listener_id = %self.listener_id,
+                        client = %peer,
+                        error = %e,
+                        "Failed to read ClientHello"
+                    );
+                    return;
+                }
+            }
+        };
+
+        let route = server_name.as_deref().and_then(|n| rules.route_for(n));
+        let (route_id, upstream_id) = match route {
+            Some(route) => (route.id.clone(), route.upstream.clone()),
+            None => match rules.upstream {
+                Some(ref upstream) => (DEFAULT_ROUTE.to_string(), upstream.clone()),
+                None => {
+                    debug!(
+                        listener_id = %self.listener_id,
+                        client = %peer,
+                        server_name = server_name.as_deref().unwrap_or("-"),
+                        "No stream route for connection"
+                    );
+                    self.record(DEFAULT_ROUTE, "unrouted");
+                    return;
+                }
+            },
+        };
+        let _connection = crate::inflight::registry().register_connection(
+            &self.listener_id,
+            peer,
+            &route_id,
+            &upstream_id,
+        );
+
+        // Only plaintext connections to the default upstream are parsed
+        let redis = rules.redis.as_ref().filter(|_| route.is_none());
+
+        let config = self.config_manager.current();
+        let session = (!rules.agents.is_empty()).then(|| {
+            AgentSession::new(
+                &self.agent_manager,
+                &config,
+                &rules,
+                self.agent_metadata(peer, server_name.as_deref(), &route_id, &upstream_id),
+                rand::random::<f64>() < rules.sample_rate,
+            )
+        });
+
+        if let Some(ref session) = session {
+            let target = match server_name {
+                Some(ref name) => name.clone(),
+                None => client
+                    .local_addr()
+                    .map(|a| a.to_string())
+                    .unwrap_or_default(),
+            };
+            if !session.open(&target).await {
+                info!(
+                    listener_id = %self.listener_id,
+                    client = %peer,
+                    route = %route_id,
+                    "Stream connection refused by agent"
+                );
+                self.record(&route_id, "blocked");
+                session.finish(Transfer::default()).await;
+                return;
+            }
+        }
+
+        let result = self
+            .forward(
+                client,
+                peer,
+                &rules,
+                &route_id,
+                &upstream_id,
+                redis,
+                prefix,
+                session.as_ref(),
+            )
+            .await;
+        let transfer = match result {
+            Ok((end, transfer)) => {
+                let outcome = match end {
+                    RelayEnd::Closed => "closed",
+                    RelayEnd::Idle => "idle",
+                    RelayEnd::Blocked => "blocked",
+                };
+                self.record(&route_id, outcome);
+                transfer
+            }
+            Err((outcome, transfer)) => {
+                self.record(&route_id, outcome);
+                transfer
+            }
+        };
+        if let Some(session) = session {
+            session.finish(transfer).await;
+        }
+    }
+
+    /// Connect to the route's upstream and relay the connection
+    async fn forward(
+        &self,
+        client: TcpStream,
+        peer: SocketAddr,
+        rules: &StreamProxyConfig,
+        route_id: &str,
+        upstream_id: &str,
+        redis: Option<&RedisStreamConfig>,
+        prefix: Vec<u8>,
+        session: Option<&AgentSession<'_>>,
+    ) -> Result<(RelayEnd, Transfer), (&'static str, Transfer)> {
+        let Some(pool) = self.upstream_pools.get(upstream_id).await else {
+            warn!(
+                listener_id = %self.listener_id,
+                upstream = %upstream_id,
+                "Stream upstream not found"
+            );
+            return Err(("failed", Transfer::default()));
+        };
+
+        let (http_peer, selection) = pool.select_peer_with_metadata(None).await.map_err(|e| {
+            warn!(
+                listener_id = %self.listener_id,
+                upstream = %upstream_id,
+                error = %e,
+                "No stream upstream target available"
+            );
+            ("failed", Transfer::default())
+        })?;
+        let Some(address) = http_peer._address.as_inet().copied() else {
+            pool.release_selection(&selection).await;
+            return Err(("failed", Transfer::default()));
+        };
+
+        let connect_timeout = Duration::from_secs(rules.connect_timeout_secs);
+        let upstream =
+            match tokio::time::timeout(connect_timeout, TcpStream::connect(address)).await {
+                Ok(Ok(upstream)) => {
+                    pool.report_result(&selection.address, true).await;
+                    upstream
+                }
+                Ok(Err(_)) | Err(_) => {
+                    pool.report_result(&selection.address, false).await;
+                    pool.release_selection(&selection).await;
+                    warn!(
+                        listener_id = %self.listener_id,
+                        upstream = %upstream_id,
+                        target = %selection.address,
+                        "Failed to connect to stream upstream"
+                    );
+                    return Err(("failed", Transfer::default()));
+                }
+            };
+
+        debug!(
+            listener_id = %self.listener_id,
+            client = %peer,
+            route = %route_id,
+            target = %selection.address,
+            "Stream connection opened"
+        );
+
+        let active = STREAM_METRICS
+            .as_ref()
+            .map(|m| m.active.with_label_values(&[&self.listener_id]));
+        if let Some(ref gauge) = active {
+            gauge.inc();
+        }
+        pool.increment_active();
+
+        let mut transfer = Transfer::default();
+        let idle_timeout = Duration::from_secs(rules.idle_timeout_secs);
+        let result = match redis {
+            Some(policy) => {
+                let relay = redis::RedisRelay::new(upstream_id, policy, session);
+                relay
+                    .run(client, upstream, prefix, idle_timeout, &mut transfer)
+                    .await
+            }
+            None => {
+                relay(
+                    client,
+                    upstream,
+                    prefix,
+                    idle_timeout,
+                    session,
+                    &mut transfer,
+                )
+                .await
+            }
+        };
+
+        pool.decrement_active();
+        pool.release_selection(&selection).await;
+        if let Some(ref gauge) = active {
+            gauge.dec();
+        }
+        if let Some(metrics) = STREAM_METRICS.as_ref() {
+            metrics
+                .bytes
+                .with_label_values(&[&self.listener_id, "upstream"])
+                .inc_by(transfer.sent);
+            metrics
+                .bytes
+                .with_label_values(&[&self.listener_id, "downstream"])
+                .inc_by(transfer.received);
+        }
+
+        match result {
+            Ok(end) => Ok((end, transfer)),
+            Err(e) => {
+                debug!(
+                    listener_id = %self.listener_id,
+                    client = %peer,
+                    route = %route_id,
+                    error = %e,
+                    "Stream connection failed"
+                );
+                Err(("error", transfer))
+            }
+        }
+    }
+
+    fn agent_metadata(
+        &self,
+        peer: SocketAddr,
+        server_name: Option<&str>,
+        route_id: &str,
+        upstream_id: &str,
+    ) -> RequestMetadata {
+        let correlation_id = CorrelationId::new().into_string();
+        RequestMetadata {
+            request_id: correlation_id.clone(),
+            correlation_id,
+            client_ip: peer.ip().to_string(),
+            client_port: peer.port(),
+            server_name: server_name.map(str::to_string),
+            protocol: if server_name.is_some() { "TLS" } else { "TCP" }.to_string(),
+            tls_version: None,
+            tls_cipher: None,
+            route_id: Some(route_id.to_string()),
+            upstream_id: Some(upstream_id.to_string()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
+        }
+    }
+
+    fn record(&self, route_id: &str, outcome: &str) {
+        if let Some(metrics) = STREAM_METRICS.as_ref() {
+            metrics
+                .connections
+                .with_label_values(&[&self.listener_id, route_id, outcome])
+                .inc();
+        }
+    }
+}
+
+/// Agent events of one connection
+struct AgentSession<'a> {
+    agent_manager: &'a AgentManager,
+    ctx: AgentCallContext,
+    agents: Vec<String>,
+    /// Agents with their failure modes, for headers events
+    filters: Vec<(String, FailureMode)>,
+    /// Payload bytes inspected per direction (0 when not sampled)
+    inspect_bytes: usize,
+    inspected: parking_lot::Mutex<Inspected>,
+}
+
+/// Payload inspected so far
+#[derive(Debug, Default)]
+struct Inspected {
+    client_bytes: usize,
+    client_chunks: u32,
+    upstream_bytes: usize,
+    upstream_chunks: u32,
+}
+
+impl<'a> AgentSession<'a> {
+    fn new(
+        agent_manager: &'a AgentManager,
+        config: &Config,
+        rules: &StreamProxyConfig,
+        metadata: RequestMetadata,
+        sampled: bool,
+    ) -> Self {
+        let correlation_id = CorrelationId::from_string(&metadata.correlation_id);
+        let filters = rules
+            .agents
+            .iter()
+            .map(|id| {
+                let failure_mode = config
+                    .agents
+                    .iter()
+                    .find(|a| &a.id == id)
+                    .map(|a| a.failure_mode)
+                    .unwrap_or(FailureMode::Closed);
+                (id.clone(), failure_mode)
+            })
+            .collect();
+        Self {
+            agent_manager,
+            ctx: AgentCallContext::new(correlation_id, metadata),
+            agents: rules.agents.clone(),
+            filters,
+            inspect_bytes: if sampled { rules.inspect_bytes } else { 0 },
+            inspected: parking_lot::Mutex::new(Inspected::default()),
+        }
+    }
+
+    /// Connection-open event; false when an agent refuses the connection
+    async fn open(&self, target: &str) -> bool {
+        let mut headers = HashMap::new();
+        headers.insert(":method".to_string(), vec!["CONNECT".to_string()]);
+        headers.insert(":path".to_string(), vec![target.to_string()]);
+        if let Some(ref server_name) = self.ctx.metadata.server_name {
+            headers.insert("host".to_string(), vec![server_name.clone()]);
+        }
+        self.headers_event(headers).await
+    }
+
+    /// Redis command event; false when an agent refuses the command
+    async fn command(&self, command: &redis::Command) -> bool {
+        let mut headers = HashMap::new();
+        headers.insert(":method".to_string(), vec![command.name.clone()]);
+        if let Some(ref pattern) = command.key_pattern {
+            headers.insert(":path".to_string(), vec![pattern.clone()]);
+        }
+        headers.insert("content-length".to_string(), vec![command.size.to_string()]);
+        headers.insert(
+            redis::ARGS_HEADER.to_string(),
+            vec![command.args.to_string()],
+        );
+        self.headers_event(headers).await
+    }
+
+    async fn headers_event(&self, headers: HashMap<String, Vec<String>>) -> bool {
+        match self
+            .agent_manager
+            .process_request_headers(&self.ctx, headers, &self.filters)
+            .await
+        {
+            Ok(decision) => decision.is_allow(),
+            Err(e) => {
+                warn!(
+                    correlation_id = %self.ctx.correlation_id,
+                    error = %e,
+                    "Agent processing failed for stream connection"
+                );
+                false
+            }
+        }
+    }
+
+    /// Inspect payload sent by the client (`from_client`) or the upstream;
+    /// false when an agent blocks it
+    async fn inspect(&self, data: &[u8], from_client: bool) -> bool {
+        let (chunk, chunk_index, seen) = {
+            let mut inspected = self.inspected.lock();
+            let (bytes, chunks) = if from_client {
+                (&mut inspected.client_bytes, &mut inspected.client_chunks)
+            } else {
+                (
+                    &mut inspected.upstream_bytes,
+                    &mut inspected.upstream_chunks,
+                )
+            };
+            let remaining = self.inspect_bytes.saturating_sub(*bytes);
+            if remaining == 0 || data.is_empty() {
+                return true;
+            }
+            let len = remaining.min(data.len());
+            *bytes += len;
+            *chunks += 1;
+            (&data[..len], *chunks - 1, *bytes)
+        };
+
+        let result = if from_client {
+            self.agent_manager
+                .process_request_body_streaming(
+                    &self.ctx,
+                    chunk,
+                    false,
+                    chunk_index,
+                    seen,
+                    None,
+                    &self.agents,
+                )
+                .await
+        } else {
+            self.agent_manager
+                .process_response_body_streaming(
+                    &self.ctx,
+                    chunk,
+                    false,
+                    chunk_index,
+                    seen,
+                    None,
+                    &self.agents,
+                )
+                .await
+        };
+        match result {
+            Ok(decision) => decision.is_allow(),
+            Err(e) => {
+                warn!(
+                    correlation_id = %self.ctx.correlation_id,
+                    error = %e,
+                    "Agent payload inspection failed for stream connection"
+                );
+                false
+            }
+        }
+    }
+
+    /// Connection-close event
+    async fn finish(&self, transfer: Transfer) {
+        let (chunk_index, inspected) = {
+            let inspected = self.inspected.lock();
+            (inspected.client_chunks, inspected.client_bytes)
+        };
+        // The connection is gone, so the decision no longer matters
+        let _ = self
+            .agent_manager
+            .process_request_body_streaming(
+                &self.ctx,
+                &[],
+                true,
+                chunk_index,
+                inspected,
+                Some(transfer.sent as usize),
+                &self.agents,
+            )
+            .await;
+        self.agent_manager
+            .end_request(self.ctx.correlation_id.as_str())
+            .await;
+    }
+}
+
+/// Read the server name of a TLS ClientHello.
+///
+/// Returns the name, if any, and the bytes read, which must be sent to the
+/// upstream before anything else. Connections that send nothing within
+/// [`FIRST_BYTES_TIMEOUT`] or do not start with a TLS handshake have no name.
+async fn read_server_name(stream: &mut TcpStream) -> std::io::Result<(Option<String>, Vec<u8>)> {
+    let mut first = [0u8; 1];
+    match tokio::time::timeout(FIRST_BYTES_TIMEOUT, stream.peek(&mut first)).await {
+        Ok(Ok(n)) if n > 0 && first[0] == TLS_HANDSHAKE => {}
+        Ok(Ok(_)) | Err(_) => return Ok((None, Vec::new())),
+        Ok(Err(e)) => return Err(e),
+    }
+
+    tokio::time::timeout(CLIENT_HELLO_TIMEOUT, read_client_hello(stream))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "ClientHello timed out"))?
+}
+
+async fn read_client_hello<S: tokio::io::AsyncRead + Unpin>(
+    stream: &mut S,
+) -> std::io::Result<(Option<String>, Vec<u8>)> {
+    let mut acceptor = rustls::server::Acceptor::default();
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok((None, buf));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut data = &chunk[..n];
+        while !data.is_empty() {
+            if acceptor.read_tls(&mut data).is_err() {
+                return Ok((None, buf));
+            }
+        }
+        match acceptor.accept() {
+            Ok(Some(accepted)) => {
+                let server_name = accepted.client_hello().server_name().map(str::to_string);
+                return Ok((server_name, buf));
+            }
+            Ok(None) if buf.len() < MAX_CLIENT_HELLO => {}
+            // Not TLS after all, or a ClientHello too large to bother with
+            Ok(None) | Err(_) => return Ok((None, buf)),
+        }
+    }
+}
+
+/// Copy both ways until both sides have closed, nothing moved for
+/// `idle_timeout` or an agent blocked the payload. `prefix` was already read
+/// from the client.
+async fn relay(
+    mut client: TcpStream,
+    mut upstream: TcpStream,
+    prefix: Vec<u8>,
+    idle_timeout: Duration,
+    session: Option<&AgentSession<'_>>,
+    transfer: &mut Transfer,
+) -> std::io::Result<RelayEnd> {
+    if !prefix.is_empty() {
+        if !allowed(session, &prefix, true).await {
+            return Ok(RelayEnd::Blocked);
+        }
+        upstream.write_all(&prefix).await?;
+        transfer.sent += prefix.len() as u64;
+    }
+
+    let (mut client_read, mut client_write) = client.split();
+    let (mut upstream_read, mut upstream_write) = upstream.split();
+    let mut client_buf = vec![0u8; BUFFER_SIZE];
+    let mut upstream_buf = vec![0u8; BUFFER_SIZE];
+    let (mut client_open, mut upstream_open) = (true, true);
+    let mut last_activity = Instant::now();
+
+    while client_open || upstream_open {
+        tokio::select! {
+            n = client_read.read(&mut client_buf), if client_open => {
+                let n = n?;
+                if n == 0 {
+                    client_open = false;
+                    upstream_write.shutdown().await?;
+                } else {
+                    if !allowed(session, &client_buf[..n], true).await {
+                        return Ok(RelayEnd::Blocked);
+                    }
+                    upstream_write.write_all(&client_buf[..n]).await?;
+                    transfer.sent += n as u64;
+                }
+                last_activity = Instant::now();
+            }
+            n = upstream_read.read(&mut upstream_buf), if upstream_open => {
+                let n = n?;
+                if n == 0 {
+                    upstream_open = false;
+                    client_write.shutdown().await?;
+                } else {
+                    if !allowed(session, &upstream_buf[..n], false).await {
+                        return Ok(RelayEnd::Blocked);
+                    }
+                    client_write.write_all(&upstream_buf[..n]).await?;
+                    transfer.received += n as u64;
+                }
+                last_activity = Instant::now();
+            }
+            _ = tokio::time::sleep_until((last_activity + idle_timeout).into()) => {
+                return Ok(RelayEnd::Idle);
+            }
+        }
+    }
+    Ok(RelayEnd::Closed)
+}
+
+async fn allowed(session: Option<&AgentSession<'_>>, data: &[u8], from_client: bool) -> bool {
+    match session {
+        Some(session) => session.inspect(data, from_client).await,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal TLS 1.3 ClientHello naming `server_name`
+    fn client_hello(server_name: &str) -> Vec<u8> {
+        let name = server_name.as_bytes();
+        let mut sni = Vec::new();
+        sni.extend_from_slice(&((name.len() + 3) as u16).to_be_bytes());
+        sni.push(0); // host_name
+        sni.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        sni.extend_from_slice(name);
+
+        let mut extensions = Vec::new();
+        extensions.extend_from_slice(&0u16.to_be_bytes()); // server_name
+        extensions.extend_from_slice(&(sni.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&sni);
+        // supported_versions: TLS 1.3
+        extensions.extend_from_slice(&[0x00, 0x2b, 0x00, 0x03, 0x02, 0x03, 0x04]);
+
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0u8; 32]); // random
+        body.push(0); // session id
+        body.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]); // TLS_AES_128_GCM_SHA256
+        body.extend_from_slice(&[0x01, 0x00]); // null compression
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(&extensions);
+
+        let mut handshake = vec![0x01];
+        handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        handshake.extend_from_slice(&body);
+
+        let mut record = vec![TLS_HANDSHAKE, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[tokio::test]
+    async fn test_read_client_hello_server_name() {
+        let hello = client_hello("db.example.com");
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let writer = {
+            let hello = hello.clone();
+            tokio::spawn(async move {
+                // Split across reads
+                for part in hello.chunks(20) {
+                    client.write_all(part).await.unwrap();
+                }
+                client
+            })
+        };
+
+        let (server_name, read) = read_client_hello(&mut server).await.unwrap();
+        assert_eq!(server_name.as_deref(), Some("db.example.com"));
+        assert_eq!(read, hello);
+        drop(writer.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_read_client_hello_not_tls() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await.unwrap();
+        drop(client);
+        let (server_name, read) = read_client_hello(&mut server).await.unwrap();
+        assert_eq!(server_name, None);
+        assert_eq!(read, b"SSH-2.0-OpenSSH_9.6\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_relay_forwards_prefix_and_counts_bytes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        let echo = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let front = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let front_addr = front.local_addr().unwrap();
+        let proxy = tokio::spawn(async move {
+            let (client, _) = front.accept().await.unwrap();
+            let upstream = TcpStream::connect(upstream_addr).await.unwrap();
+            let mut transfer = Transfer::default();
+            let end = relay(
+                client,
+                upstream,
+                b"hello ".to_vec(),
+                Duration::from_secs(5),
+                None,
+                &mut transfer,
+            )
+            .await
+            .unwrap();
+            (end, transfer)
+        });
+
+        let mut client = TcpStream::connect(front_addr).await.unwrap();
+        client.write_all(b"world").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut echoed = Vec::new();
+        client.read_to_end(&mut echoed).await.unwrap();
+        assert_eq!(echoed, b"hello world");
+        echo.await.unwrap();
+
+        let (end, transfer) = proxy.await.unwrap();
+        assert_eq!(end, RelayEnd::Closed);
+        assert_eq!(
+            transfer,
+            Transfer {
+                sent: 11,
+                received: 11
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relay_idle_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        let front = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let front_addr = front.local_addr().unwrap();
+
+        let _client = TcpStream::connect(front_addr).await.unwrap();
+        let (client, _) = front.accept().await.unwrap();
+        let upstream = TcpStream::connect(upstream_addr).await.unwrap();
+        let _accepted = listener.accept().await.unwrap();
+
+        let mut transfer = Transfer::default();
+        let end = relay(
+            client,
+            upstream,
+            Vec::new(),
+            Duration::from_millis(50),
+            None,
+            &mut transfer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(end, RelayEnd::Idle);
+    }
+}
@@ -0,0 +1,914 @@
+//! Redis (RESP) awareness for stream connections.
+//!
+//! Client data is split into commands (RESP arrays or inline commands) and
+//! each command is checked before it is forwarded: against the listener's
+//! `deny-commands` and `allow-commands`, then its agents, which see a request
+//! headers event per command:
+//!
+//! - method: the command name, upper case
+//! - uri: the key pattern, the first key with segments (split on `:`) that
+//!   contain digits or are longer than 32 bytes replaced by `*`
+//!   (`user:1234:profile` becomes `user:*:profile`); `/` for commands
+//!   without a key. Key arguments only: values, passwords and script bodies
+//!   are never sent
+//! - `content-length`: size of the command in bytes
+//! - `x-redis-args`: number of arguments after the name
+//!
+//! A refused command is answered with `-ERR` in its place among the
+//! pipelined replies and never reaches Redis; the connection stays open.
+//!
+//! Replies are matched to commands in order to time them. Connections that
+//! enter pub/sub or `MONITOR` mode are relayed unparsed from then on.
+//!
+//! Exports `zentinel_redis_commands_total{upstream, command, outcome}`
+//! (`forwarded`, `denied`, `blocked`) and
+//! `zentinel_redis_command_duration_seconds{upstream, command}`. Commands
+//! missing from the built-in command table are labelled `other`.
+
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use prometheus::{register_histogram_vec, register_int_counter_vec, HistogramVec, IntCounterVec};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::debug;
+use zentinel_config::RedisStreamConfig;
+
+use super::{AgentSession, RelayEnd, Transfer, BUFFER_SIZE};
+
+/// Header carrying the argument count to agents
+pub(super) const ARGS_HEADER: &str = "x-redis-args";
+
+/// Largest bulk string accepted, Redis' own `proto-max-bulk-len` default
+const MAX_BULK_LEN: i64 = 512 * 1024 * 1024;
+
+/// Largest element count of an aggregate
+const MAX_ELEMENTS: i64 = 1024 * 1024;
+
+/// Longest line (simple string, length prefix or inline command)
+const MAX_LINE: usize = 64 * 1024;
+
+/// Deepest nesting of aggregates in a reply
+const MAX_DEPTH: usize = 32;
+
+/// Key segments longer than this are replaced in key patterns
+const MAX_KEY_SEGMENT: usize = 32;
+
+/// Metric label of commands not in [`COMMANDS`]
+const OTHER_COMMAND: &str = "other";
+
+/// Known commands and whether their first argument is a key
+const COMMANDS: &[(&str, bool)] = &[
+    // Strings
+    ("APPEND", true),
+    ("DECR", true),
+    ("DECRBY", true),
+    ("GET", true),
+    ("GETDEL", true),
+    ("GETEX", true),
+    ("GETRANGE", true),
+    ("GETSET", true),
+    ("INCR", true),
+    ("INCRBY", true),
+    ("INCRBYFLOAT", true),
+    ("MGET", true),
+    ("MSET", true),
+    ("MSETNX", true),
+    ("PSETEX", true),
+    ("SET", true),
+    ("SETEX", true),
+    ("SETNX", true),
+    ("SETRANGE", true),
+    ("STRLEN", true),
+    ("GETBIT", true),
+    ("SETBIT", true),
+    ("BITCOUNT", true),
+    ("BITPOS", true),
+    // Keys
+    ("COPY", true),
+    ("DEL", true),
+    ("DUMP", true),
+    ("EXISTS", true),
+    ("EXPIRE", true),
+    ("EXPIREAT", true),
+    ("EXPIRETIME", true),
+    ("PERSIST", true),
+    ("PEXPIRE", true),
+    ("PEXPIREAT", true),
+    ("PTTL", true),
+    ("RENAME", true),
+    ("RENAMENX", true),
+    ("RESTORE", true),
+    ("SORT", true),
+    ("TOUCH", true),
+    ("TTL", true),
+    ("TYPE", true),
+    ("UNLINK", true),
+    ("WATCH", true),
+    ("KEYS", false),
+    ("SCAN", false),
+    ("RANDOMKEY", false),
+    // Hashes
+    ("HDEL", true),
+    ("HEXISTS", true),
+    ("HGET", true),
+    ("HGETALL", true),
+    ("HINCRBY", true),
+    ("HINCRBYFLOAT", true),
+    ("HKEYS", true),
+    ("HLEN", true),
+    ("HMGET", true),
+    ("HMSET", true),
+    ("HRANDFIELD", true),
+    ("HSCAN", true),
+    ("HSET", true),
+    ("HSETNX", true),
+    ("HSTRLEN", true),
+    ("HVALS", true),
+    // Lists
+    ("BLMOVE", true),
+    ("BLPOP", true),
+    ("BRPOP", true),
+    ("LINDEX", true),
+    ("LINSERT", true),
+    ("LLEN", true),
+    ("LMOVE", true),
+    ("LPOP", true),
+    ("LPOS", true),
+    ("LPUSH", true),
+    ("LPUSHX", true),
+    ("LRANGE", true),
+    ("LREM", true),
+    ("LSET", true),
+    ("LTRIM", true),
+    ("RPOP", true),
+    ("RPOPLPUSH", true),
+    ("RPUSH", true),
+    ("RPUSHX", true),
+    // Sets
+    ("SADD", true),
+    ("SCARD", true),
+    ("SDIFF", true),
+    ("SDIFFSTORE", true),
+    ("SINTER", true),
+    ("SINTERSTORE", true),
+    ("SISMEMBER", true),
+    ("SMEMBERS", true),
+    ("SMISMEMBER", true),
+    ("SMOVE", true),
+    ("SPOP", true),
+    ("SRANDMEMBER", true),
+    ("SREM", true),
+    ("SSCAN", true),
+    ("SUNION", true),
+    ("SUNIONSTORE", true),
+    // Sorted sets
+    ("BZPOPMAX", true),
+    ("BZPOPMIN", true),
+    ("ZADD", true),
+    ("ZCARD", true),
+    ("ZCOUNT", true),
+    ("ZINCRBY", true),
+    ("ZLEXCOUNT", true),
+    ("ZMSCORE", true),
+    ("ZPOPMAX", true),
+    ("ZPOPMIN", true),
+    ("ZRANGE", true),
+    ("ZRANGEBYLEX", true),
+    ("ZRANGEBYSCORE", true),
+    ("ZRANGESTORE", true),
+    ("ZRANK", true),
+    ("ZREM", true),
+    ("ZREMRANGEBYRANK", true),
+    ("ZREMRANGEBYSCORE", true),
+    ("ZREVRANGE", true),
+    ("ZREVRANGEBYSCORE", true),
+    ("ZREVRANK", true),
+    ("ZSCAN", true),
+    ("ZSCORE", true),
+    // HyperLogLog, geo, streams
+    ("PFADD", true),
+    ("PFCOUNT", true),
+    ("PFMERGE", true),
+    ("GEOADD", true),
+    ("GEODIST", true),
+    ("GEOPOS", true),
+    ("GEORADIUS", true),
+    ("GEOSEARCH", true),
+    ("XACK", true),
+    ("XADD", true),
+    ("XAUTOCLAIM", true),
+    ("XCLAIM", true),
+    ("XDEL", true),
+    ("XLEN", true),
+    ("XPENDING", true),
+    ("XRANGE", true),
+    ("XREVRANGE", true),
+    ("XTRIM", true),
+    ("XGROUP", false),
+    ("XINFO", false),
+    ("XREAD", false),
+    ("XREADGROUP", false),
+    // Transactions and scripting
+    ("DISCARD", false),
+    ("EXEC", false),
+    ("MULTI", false),
+    ("UNWATCH", false),
+    ("EVAL", false),
+    ("EVALSHA", false),
+    ("FCALL", false),
+    ("FUNCTION", false),
+    ("SCRIPT", false),
+    // Pub/sub
+    ("PSUBSCRIBE", false),
+    ("PUBLISH", false),
+    ("PUBSUB", false),
+    ("PUNSUBSCRIBE", false),
+    ("SSUBSCRIBE", false),
+    ("SUBSCRIBE", false),
+    ("SUNSUBSCRIBE", false),
+    ("UNSUBSCRIBE", false),
+    // Connection and server
+    ("ACL", false),
+    ("AUTH", false),
+    ("BGREWRITEAOF", false),
+    ("BGSAVE", false),
+    ("CLIENT", false),
+    ("CLUSTER", false),
+    ("COMMAND", false),
+    ("CONFIG", false),
+    ("DBSIZE", false),
+    ("DEBUG", false),
+    ("ECHO", false),
+    ("FLUSHALL", false),
+    ("FLUSHDB", false),
+    ("HELLO", false),
+    ("INFO", false),
+    ("LASTSAVE", false),
+    ("LATENCY", false),
+    ("MEMORY", false),
+    ("MONITOR", false),
+    ("OBJECT", false),
+    ("PING", false),
+    ("QUIT", false),
+    ("READONLY", false),
+    ("READWRITE", false),
+    ("REPLICAOF", false),
+    ("RESET", false),
+    ("ROLE", false),
+    ("SAVE", false),
+    ("SELECT", false),
+    ("SHUTDOWN", false),
+    ("SLAVEOF", false),
+    ("SLOWLOG", false),
+    ("SWAPDB", false),
+    ("TIME", false),
+    ("WAIT", false),
+];
+
+/// Commands after which the server pushes data that answers no command
+const PASSTHROUGH_COMMANDS: &[&str] = &["SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "MONITOR"];
+
+/// Prometheus metrics labelled by upstream.
+struct RedisMetrics {
+    /// Commands, per outcome
+    commands: IntCounterVec,
+    /// Time from forwarding a command to its reply
+    duration: HistogramVec,
+}
+
+static REDIS_METRICS: LazyLock<Option<RedisMetrics>> = LazyLock::new(|| {
+    let commands = register_int_counter_vec!(
+        "zentinel_redis_commands_total",
+        "Redis commands seen by TCP stream listeners by outcome",
+        &["upstream", "command", "outcome"]
+    )
+    .ok()?;
+    let duration = register_histogram_vec!(
+        "zentinel_redis_command_duration_seconds",
+        "Time from forwarding a Redis command to its reply",
+        &["upstream", "command"],
+        vec![0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]
+    )
+    .ok()?;
+    Some(RedisMetrics { commands, duration })
+});
+
+/// Malformed RESP data
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum RespError {
+    #[error("invalid RESP frame: {0}")]
+    Invalid(&'static str),
+
+    #[error("RESP frame exceeds protocol limits")]
+    TooLarge,
+}
+
+impl From<RespError> for std::io::Error {
+    fn from(e: RespError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// A command parsed from the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Command {
+    /// Name, upper case
+    pub name: String,
+    /// Pattern of the first key, for commands that take one
+    pub key_pattern: Option<String>,
+    /// Arguments after the name
+    pub args: usize,
+    /// Size of the command in bytes
+    pub size: usize,
+}
+
+impl Command {
+    /// Metric label: the name if it is a known command
+    fn label(&self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(name, _)| *name == self.name)
+            .map(|(name, _)| *name)
+            .unwrap_or(OTHER_COMMAND)
+    }
+}
+
+/// Reply owed to the client, in command order
+#[derive(Debug)]
+enum Pending {
+    /// Reply expected from Redis
+    Upstream {
+        command: &'static str,
+        forwarded: Instant,
+        /// The command switches the connection to unparsed relaying
+        passthrough: bool,
+    },
+    /// Error for a refused command, sent once the replies before it are
+    Refused(Vec<u8>),
+}
+
+/// Relays one connection command by command
+pub(super) struct RedisRelay<'a> {
+    upstream_id: &'a str,
+    policy: &'a RedisStreamConfig,
+    session: Option<&'a AgentSession<'a>>,
+    pending: VecDeque<Pending>,
+    /// Pub/sub or `MONITOR`: replies are no longer matched to commands
+    passthrough: bool,
+}
+
+impl<'a> RedisRelay<'a> {
+    pub(super) fn new(
+        upstream_id: &'a str,
+        policy: &'a RedisStreamConfig,
+        session: Option<&'a AgentSession<'a>>,
+    ) -> Self {
+        Self {
+            upstream_id,
+            policy,
+            session,
+            pending: VecDeque::new(),
+            passthrough: false,
+        }
+    }
+
+    /// Relay until both sides have closed or nothing moved for
+    /// `idle_timeout`. `prefix` was already read from the client.
+    pub(super) async fn run(
+        mut self,
+        mut client: TcpStream,
+        mut upstream: TcpStream,
+        prefix: Vec<u8>,
+        idle_timeout: Duration,
+        transfer: &mut Transfer,
+    ) -> std::io::Result<RelayEnd> {
+        let (mut client_read, mut client_write) = client.split();
+        let (mut upstream_read, mut upstream_write) = upstream.split();
+        let mut chunk = vec![0u8; BUFFER_SIZE];
+        let mut from_client = prefix;
+        let mut from_upstream = Vec::new();
+        let (mut client_open, mut upstream_open) = (true, true);
+        let mut last_activity = Instant::now();
+
+        self.client_data(
+            &mut from_client,
+            &mut upstream_write,
+            &mut client_write,
+            transfer,
+        )
+        .await?;
+
+        while client_open || upstream_open {
+            tokio::select! {
+                n = client_read.read(&mut chunk), if client_open => {
+                    let n = n?;
+                    if n == 0 {
+                        client_open = false;
+                        upstream_write.shutdown().await?;
+                    } else {
+                        from_client.extend_from_slice(&chunk[..n]);
+                        self.client_data(
+                            &mut from_client,
+                            &mut upstream_write,
+                            &mut client_write,
+                            transfer,
+                        )
+                        .await?;
+                    }
+                    last_activity = Instant::now();
+                }
+                n = upstream_read.read(&mut chunk), if upstream_open => {
+                    let n = n?;
+                    if n == 0 {
+                        upstream_open = false;
+                        // Whatever is left can no longer be matched
+                        client_write.write_all(&from_upstream).await?;
+                        client_write.shutdown().await?;
+                    } else {
+                        from_upstream.extend_from_slice(&chunk[..n]);
+                        self.upstream_data(&mut from_upstream, &mut client_write, transfer)
+                            .await?;
+                    }
+                    last_activity = Instant::now();
+                }
+                _ = tokio::time::sleep_until((last_activity + idle_timeout).into()) => {
+                    return Ok(RelayEnd::Idle);
+                }
+            }
+        }
+        Ok(RelayEnd::Closed)
+    }
+
+    /// Forward or refuse the complete commands in `buf`
+    async fn client_data<U, C>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        upstream: &mut U,
+        client: &mut C,
+        transfer: &mut Transfer,
+    ) -> std::io::Result<()>
+    where
+        U: tokio::io::AsyncWrite + Unpin,
+        C: tokio::io::AsyncWrite + Unpin,
+    {
+        let mut consumed = 0;
+        while let Some((command, len)) = parse_command(&buf[consumed..])? {
+            let frame = &buf[consumed..consumed + len];
+            consumed += len;
+            let Some(command) = command else {
+                // Blank inline line
+                continue;
+            };
+
+            let outcome = if !self.policy.permits(&command.name) {
+                "denied"
+            } else if let Some(session) = self.session {
+                if session.command(&command).await {
+                    "forwarded"
+                } else {
+                    "blocked"
+                }
+            } else {
+                "forwarded"
+            };
+            if let Some(metrics) = REDIS_METRICS.as_ref() {
+                metrics
+                    .commands
+                    .with_label_values(&[self.upstream_id, command.label(), outcome])
+                    .inc();
+            }
+
+            if outcome == "forwarded" {
+                upstream.write_all(frame).await?;
+                transfer.sent += len as u64;
+                if !self.passthrough {
+                    self.pending.push_back(Pending::Upstream {
+                        command: command.label(),
+                        forwarded: Instant::now(),
+                        passthrough: PASSTHROUGH_COMMANDS.contains(&command.name.as_str()),
+                    });
+                }
+            } else {
+                debug!(
+                    upstream = %self.upstream_id,
+                    command = %command.name,
+                    outcome = outcome,
+                    "Redis command refused"
+                );
+                let error = refusal(&command.name);
+                if self.pending.is_empty() {
+                    client.write_all(&error).await?;
+                } else {
+                    self.pending.push_back(Pending::Refused(error));
+                }
+            }
+        }
+        buf.drain(..consumed);
+        Ok(())
+    }
+
+    /// Send the complete replies in `buf` to the client
+    async fn upstream_data<C>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        client: &mut C,
+        transfer: &mut Transfer,
+    ) -> std::io::Result<()>
+    where
+        C: tokio::io::AsyncWrite + Unpin,
+    {
+        if self.passthrough {
+            client.write_all(buf).await?;
+            transfer.received += buf.len() as u64;
+            buf.clear();
+            return Ok(());
+        }
+
+        let mut consumed = 0;
+        while let Some(len) = frame_len(&buf[consumed..])? {
+            let frame = &buf[consumed..consumed + len];
+            consumed += len;
+            client.write_all(frame).await?;
+            transfer.received += len as u64;
+
+            // RESP3 push data answers no command
+            if frame[0] == b'>' {
+                continue;
+            }
+            if let Some(Pending::Upstream {
+                command,
+                forwarded,
+                passthrough,
+            }) = self.pending.pop_front()
+            {
+                if let Some(metrics) = REDIS_METRICS.as_ref() {
+                    metrics
+                        .duration
+                        .with_label_values(&[self.upstream_id, command])
+                        .observe(forwarded.elapsed().as_secs_f64());
+                }
+                self.passthrough |= passthrough;
+            }
+            while let Some(Pending::Refused(_)) = self.pending.front() {
+                if let Some(Pending::Refused(error)) = self.pending.pop_front() {
+                    client.write_all(&error).await?;
+                }
+            }
+            if self.passthrough {
+                // Later commands' replies can't be told apart from pushes
+                for pending in self.pending.drain(..) {
+                    if let Pending::Refused(error) = pending {
+                        client.write_all(&error).await?;
+                    }
+                }
+                let rest = &buf[consumed..];
+                client.write_all(rest).await?;
+                transfer.received += rest.len() as u64;
+                buf.clear();
+                return Ok(());
+            }
+        }
+        buf.drain(..consumed);
+        Ok(())
+    }
+}
+
+/// Error reply for a refused command
+fn refusal(name: &str) -> Vec<u8> {
+    // The name comes from the client and must not break the reply framing
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(64)
+        .collect();
+    format!("-ERR command '{}' not allowed by proxy\r\n", name).into_bytes()
+}
+
+/// Parse the command at the start of `buf`.
+///
+/// Returns `None` while the command is incomplete, otherwise the command
+/// (`None` for a blank inline line) and its length.
+pub(super) fn parse_command(buf: &[u8]) -> Result<Option<(Option<Command>, usize)>, RespError> {
+    match buf.first() {
+        None => Ok(None),
+        Some(b'*') => parse_array_command(buf),
+        Some(_) => parse_inline_command(buf),
+    }
+}
+
+fn parse_array_command(buf: &[u8]) -> Result<Option<(Option<Command>, usize)>, RespError> {
+    let Some(len) = frame_len(buf)? else {
+        return Ok(None);
+    };
+    let (count, mut pos) = read_line(buf, 1)?.ok_or(RespError::Invalid("truncated array"))?;
+    let count = parse_int(count)?;
+    if count <= 0 {
+        return Ok(Some((None, len)));
+    }
+
+    let mut name = None;
+    let mut first_arg = None;
+    for index in 0..count {
+        if buf.get(pos) != Some(&b'$') {
+            return Err(RespError::Invalid("command arguments must be bulk strings"));
+        }
+        let (size, start) = read_line(buf, pos + 1)?.ok_or(RespError::Invalid("truncated"))?;
+        let size = usize::try_from(parse_int(size)?)
+            .map_err(|_| RespError::Invalid("null command argument"))?;
+        let arg = &buf[start..start + size];
+        match index {
+            0 => name = Some(arg),
+            1 => first_arg = Some(arg),
+            _ => {}
+        }
+        pos = start + size + 2;
+    }
+
+    let name = name.unwrap_or_default();
+    Ok(Some((
+        Some(command(name, first_arg, count as usize - 1, len)),
+        len,
+    )))
+}
+
+fn parse_inline_command(buf: &[u8]) -> Result<Option<(Option<Command>, usize)>, RespError> {
+    let Some(end) = buf.iter().position(|&b| b == b'\n') else {
+        return if buf.len() > MAX_LINE {
+            Err(RespError::TooLarge)
+        } else {
+            Ok(None)
+        };
+    };
+    let len = end + 1;
+    let line = buf[..end].strip_suffix(b"\r").unwrap_or(&buf[..end]);
+    let mut args = line
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|arg| !arg.is_empty());
+    let Some(name) = args.next() else {
+        return Ok(Some((None, len)));
+    };
+    let first_arg = args.next();
+    let count = first_arg.map_or(0, |_| 1 + args.count());
+    Ok(Some((Some(command(name, first_arg, count, len)), len)))
+}
+
+fn command(name: &[u8], first_arg: Option<&[u8]>, args: usize, size: usize) -> Command {
+    let name = String::from_utf8_lossy(name).to_ascii_uppercase();
+    let keyed = COMMANDS
+        .iter()
+        .any(|(known, keyed)| *keyed && *known == name);
+    Command {
+        key_pattern: first_arg.filter(|_| keyed).map(key_pattern),
+        name,
+        args,
+        size,
+    }
+}
+
+/// Generalize a key for agents: `user:1234:profile` becomes `user:*:profile`
+pub(super) fn key_pattern(key: &[u8]) -> String {
+    String::from_utf8_lossy(key)
+        .split(':')
+        .map(|segment| {
+            if segment.len() > MAX_KEY_SEGMENT || segment.bytes().any(|b| b.is_ascii_digit()) {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Length of the complete frame at the start of `buf`, `None` while it is
+/// incomplete. Understands RESP2 and RESP3.
+pub(super) fn frame_len(buf: &[u8]) -> Result<Option<usize>, RespError> {
+    frame_end(buf, 0, 0)
+}
+
+fn frame_end(buf: &[u8], pos: usize, depth: usize) -> Result<Option<usize>, RespError> {
+    if depth > MAX_DEPTH {
+        return Err(RespError::TooLarge);
+    }
+    let Some(&kind) = buf.get(pos) else {
+        return Ok(None);
+    };
+    let Some((line, next)) = read_line(buf, pos + 1)? else {
+        return Ok(None);
+    };
+
+    match kind {
+        // Simple string, error, integer, null, double, boolean, big number
+        b'+' | b'-' | b':' | b'_' | b',' | b'#' | b'(' => Ok(Some(next)),
+        // Bulk string, bulk error, verbatim string
+        b'$' | b'!' | b'=' => {
+            let size = parse_int(line)?;
+            if size < 0 {
+                return Ok(Some(next));
+            }
+            if size > MAX_BULK_LEN {
+                return Err(RespError::TooLarge);
+            }
+            let end = next + size as usize + 2;
+            match buf.get(end - 2..end) {
+                None => Ok(None),
+                Some(b"\r\n") => Ok(Some(end)),
+                Some(_) => Err(RespError::Invalid("bulk string not terminated by CRLF")),
+            }
+        }
+        // Array, set, push, map, attribute
+        b'*' | b'~' | b'>' | b'%' | b'|' => {
+            let count = parse_int(line)?;
+            if count < 0 {
+                return Ok(Some(next));
+            }
+            if count > MAX_ELEMENTS {
+                return Err(RespError::TooLarge);
+            }
+            let elements = if matches!(kind, b'%' | b'|') {
+                count * 2
+            } else {
+                count
+            };
+            let mut end = next;
+            for _ in 0..elements {
+                match frame_end(buf, end, depth + 1)? {
+                    Some(element_end) => end = element_end,
+                    None => return Ok(None),
+                }
+            }
+            if kind == b'|' {
+                // Attributes precede the reply they describe
+                return frame_end(buf, end, depth + 1);
+            }
+            Ok(Some(end))
+        }
+        _ => Err(RespError::Invalid("unknown type byte")),
+    }
+}
+
+/// The line starting at `start` and the position after its CRLF
+fn read_line(buf: &[u8], start: usize) -> Result<Option<(&[u8], usize)>, RespError> {
+    let rest = buf.get(start..).unwrap_or_default();
+    match rest.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => Ok(Some((&rest[..end], start + end + 2))),
+        None if rest.len() > MAX_LINE => Err(RespError::TooLarge),
+        None => Ok(None),
+    }
+}
+
+fn parse_int(line: &[u8]) -> Result<i64, RespError> {
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(RespError::Invalid("invalid length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_array_command() {
+        let buf =
+            b"*3\r\n$3\r\nSET\r\n$14\r\nuser:1234:name\r\n$5\r\nalice\r\n*1\r\n$4\r\nPING\r\n";
+        let (command, len) = parse_command(buf).unwrap().unwrap();
+        let command = command.unwrap();
+        assert_eq!(command.name, "SET");
+        assert_eq!(command.key_pattern.as_deref(), Some("user:*:name"));
+        assert_eq!(command.args, 2);
+        assert_eq!(command.size, len);
+        assert_eq!(command.label(), "SET");
+
+        let (ping, _) = parse_command(&buf[len..]).unwrap().unwrap();
+        let ping = ping.unwrap();
+        assert_eq!(
+            (ping.name.as_str(), ping.key_pattern, ping.args),
+            ("PING", None, 0)
+        );
+
+        // Incomplete until the last byte
+        for cut in 1..len {
+            assert_eq!(parse_command(&buf[..cut]).unwrap(), None, "{}", cut);
+        }
+    }
+
+    #[test]
+    fn test_secrets_never_become_key_patterns() {
+        let (auth, _) = parse_command(b"*3\r\n$4\r\nauth\r\n$7\r\ndefault\r\n$6\r\nsecret\r\n")
+            .unwrap()
+            .unwrap();
+        let auth = auth.unwrap();
+        assert_eq!(auth.name, "AUTH");
+        assert_eq!(auth.key_pattern, None);
+
+        let (unknown, _) = parse_command(b"*2\r\n$6\r\nMYCMD1\r\n$3\r\nkey\r\n")
+            .unwrap()
+            .unwrap();
+        let unknown = unknown.unwrap();
+        assert_eq!(unknown.key_pattern, None);
+        assert_eq!(unknown.label(), OTHER_COMMAND);
+    }
+
+    #[test]
+    fn test_parse_inline_command() {
+        let (command, len) = parse_command(b"get session:abc123\r\n").unwrap().unwrap();
+        let command = command.unwrap();
+        assert_eq!(command.name, "GET");
+        assert_eq!(command.key_pattern.as_deref(), Some("session:*"));
+        assert_eq!(len, 20);
+
+        assert_eq!(parse_command(b"\r\n").unwrap(), Some((None, 2)));
+        assert_eq!(parse_command(b"PING").unwrap(), None);
+    }
+
+    #[test]
+    fn test_frame_len() {
+        assert_eq!(frame_len(b"+OK\r\n").unwrap(), Some(5));
+        assert_eq!(frame_len(b"$-1\r\n").unwrap(), Some(5));
+        assert_eq!(frame_len(b"$5\r\nhello\r\n+OK\r\n").unwrap(), Some(11));
+        assert_eq!(frame_len(b"$5\r\nhel").unwrap(), None);
+        assert_eq!(frame_len(b"*2\r\n:1\r\n*1\r\n_\r\n").unwrap(), Some(15));
+        assert_eq!(frame_len(b"%1\r\n+a\r\n:1\r\n").unwrap(), Some(12));
+        // Attribute followed by the reply it annotates
+        assert_eq!(frame_len(b"|1\r\n+k\r\n+v\r\n:7\r\n").unwrap(), Some(16));
+        assert_eq!(frame_len(b"*2\r\n:1\r\n").unwrap(), None);
+
+        assert!(frame_len(b"?\r\n").is_err());
+        assert!(frame_len(b"$3\r\nabcde\r\n").is_err());
+        assert_eq!(frame_len(b"$1000000000\r\n"), Err(RespError::TooLarge));
+    }
+
+    #[test]
+    fn test_key_pattern() {
+        assert_eq!(key_pattern(b"user:1234:profile"), "user:*:profile");
+        assert_eq!(key_pattern(b"config"), "config");
+        assert_eq!(
+            key_pattern(b"cache:aGVsbG8gd29ybGQgdGhpcyBpcyBsb25nZXI"),
+            "cache:*"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refused_commands_keep_reply_order() {
+        let policy = RedisStreamConfig {
+            deny_commands: vec!["FLUSHALL".to_string()],
+            ..Default::default()
+        };
+        let mut relay = RedisRelay::new("cache", &policy, None);
+        let mut transfer = Transfer::default();
+        let (mut upstream, mut client) = (Vec::new(), Vec::new());
+
+        let mut from_client =
+            b"*1\r\n$4\r\nPING\r\n*1\r\n$8\r\nFLUSHALL\r\n*2\r\n$3\r\nGET\r\n$1\r\nk\r\n".to_vec();
+        relay
+            .client_data(&mut from_client, &mut upstream, &mut client, &mut transfer)
+            .await
+            .unwrap();
+        assert!(from_client.is_empty());
+        assert_eq!(
+            upstream,
+            b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$1\r\nk\r\n"
+        );
+        // The error waits for the PING reply
+        assert!(client.is_empty());
+
+        let mut from_upstream = b"+PONG\r\n$1\r\nv\r\n".to_vec();
+        relay
+            .upstream_data(&mut from_upstream, &mut client, &mut transfer)
+            .await
+            .unwrap();
+        assert_eq!(
+            client,
+            b"+PONG\r\n-ERR command 'FLUSHALL' not allowed by proxy\r\n$1\r\nv\r\n"
+        );
+        assert!(relay.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_switches_to_passthrough() {
+        let policy = RedisStreamConfig::default();
+        let mut relay = RedisRelay::new("cache", &policy, None);
+        let mut transfer = Transfer::default();
+        let (mut upstream, mut client) = (Vec::new(), Vec::new());
+
+        let mut from_client = b"SUBSCRIBE news\r\n".to_vec();
+        relay
+            .client_data(&mut from_client, &mut upstream, &mut client, &mut transfer)
+            .await
+            .unwrap();
+        let mut from_upstream =
+            b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$4\r\nne"
+                .to_vec();
+        relay
+            .upstream_data(&mut from_upstream, &mut client, &mut transfer)
+            .await
+            .unwrap();
+        assert!(relay.passthrough);
+        assert!(from_upstream.is_empty());
+        assert!(client.ends_with(b"$4\r\nne"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Live request tap for production debugging.
+//!
+//! The `tap` builtin handler streams a summary of every completed request as
+//! Server-Sent Events (`event: request`, JSON `data`). Filters are given in
+//! the query string and applied per stream:
+//!
+//! - `route=<id>`: only requests matched to this route
+//! - `status=<class or code>`: `2xx`..`5xx` or an exact status like `404`
+//! - `min-duration-ms=<n>`: only requests that took at least `n` ms
+//! - `sample=<percent>`: share of matching requests that is sent (`10%`)
+//! - `limit=<n>`: end the stream after `n` events
+//!
+//! Summaries are only built while a stream is open, so an idle tap costs
+//! one atomic load per request. Summaries carry the path without the query
+//! string. A stream that falls behind skips the missed requests and gets a
+//! `lagged` event with their count; comments keep idle streams alive.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+use bytes::Bytes;
+use prometheus::{register_int_gauge, IntGauge};
+use rand::RngExt;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Summaries buffered per stream before it lags
+const TAP_CAPACITY: usize = 1024;
+
+/// Interval between keep-alive comments on an idle stream
+pub const TAP_KEEPALIVE: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Open tap streams
+static TAP_STREAMS: LazyLock<Option<IntGauge>> = LazyLock::new(|| {
+    register_int_gauge!("zentinel_tap_streams", "Open live request tap streams").ok()
+});
+
+static TAP: LazyLock<broadcast::Sender<Arc<TapEvent>>> =
+    LazyLock::new(|| broadcast::channel(TAP_CAPACITY).0);
+
+/// Summary of a completed request
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TapEvent {
+    pub timestamp: String,
+    pub trace_id: String,
+    pub method: String,
+    /// Request path without the query string
+    pub path: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub client_ip: String,
+    pub duration_ms: u64,
+    /// Time spent in each agent, in milliseconds
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub agents: BTreeMap<String, u64>,
+    /// Agent that blocked the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<String>,
+    /// Rule IDs reported by the blocking agent
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rule_ids: Vec<String>,
+}
+
+/// Publish a request summary to the open streams
+///
+/// `event` is only called while at least one stream is open.
+pub fn publish(event: impl FnOnce() -> TapEvent) {
+    if TAP.receiver_count() == 0 {
+        return;
+    }
+    let _ = TAP.send(Arc::new(event()));
+}
+
+/// An open tap stream; counted in `zentinel_tap_streams` until dropped
+pub struct TapStream {
+    receiver: broadcast::Receiver<Arc<TapEvent>>,
+    filter: TapFilter,
+    sent: u64,
+}
+
+impl TapStream {
+    /// Open a stream receiving the requests that match `filter`
+    pub fn open(filter: TapFilter) -> Self {
+        if let Some(gauge) = TAP_STREAMS.as_ref() {
+            gauge.inc();
+        }
+        Self {
+            receiver: TAP.subscribe(),
+            filter,
+            sent: 0,
+        }
+    }
+
+    /// Next SSE frame for the client, `None` once the limit is reached
+    pub async fn next(&mut self) -> Option<Bytes> {
+        if self.filter.limit.is_some_and(|limit| self.sent >= limit) {
+            return None;
+        }
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    if !self.filter.matches(&event) || !self.filter.sampled() {
+                        continue;
+                    }
+                    self.sent += 1;
+                    return Some(sse_frame("request", &*event));
+                }
+                Err(broadcast::error::RecvError::Lagged(dropped)) => {
+                    return Some(sse_frame(
+                        "lagged",
+                        &serde_json::json!({ "dropped": dropped }),
+                    ));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for TapStream {
+    fn drop(&mut self) {
+        if let Some(gauge) = TAP_STREAMS.as_ref() {
+            gauge.dec();
+        }
+    }
+}
+
+/// Invalid tap query parameter
+#[derive(Debug, Error, PartialEq)]
+pub enum TapError {
+    #[error("Invalid '{name}' parameter '{value}'")]
+    InvalidParameter { name: &'static str, value: String },
+}
+
+/// Which requests a stream receives
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TapFilter {
+    pub route: Option<String>,
+    pub status: Option<StatusFilter>,
+    pub min_duration_ms: Option<u64>,
+    /// Percentage of matching requests sent (all when unset)
+    pub sample_percent: Option<f64>,
+    pub limit: Option<u64>,
+}
+
+/// Status filter: a class (`5xx`) or an exact code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    Class(u16),
+    Exact(u16),
+}
+
+impl TapFilter {
+    /// Filter from a query string, e.g. `route=api&status=5xx&sample=10%`
+    ///
+    /// Unknown parameters are ignored.
+    pub fn from_query(query: &str) -> Result<Self, TapError> {
+        let mut filter = Self::default();
+        for pair in query.split('&') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = urlencoding::decode(value)
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| value.to_string());
+            let invalid = |name| TapError::InvalidParameter {
+                name,
+                value: value.clone(),
+            };
+            match name {
+                "route" => filter.route = Some(value.clone()),
+                "status" => {
+                    let status = match value.strip_suffix("xx") {
+                        Some(class) => class
+                            .parse()
+                            .ok()
+                            .filter(|c| (1..=5).contains(c))
+                            .map(StatusFilter::Class),
+                        None => value
+                            .parse()
+                            .ok()
+                            .filter(|c| (100..=599).contains(c))
+                            .map(StatusFilter::Exact),
+                    };
+                    filter.status = Some(status.ok_or_else(|| invalid("status"))?);
+                }
+                "min-duration-ms" => {
+                    filter.min_duration_ms =
+                        Some(value.parse().map_err(|_| invalid("min-duration-ms"))?);
+                }
+                "sample" => {
+                    filter.sample_percent = Some(
+                        crate::capture::parse_sample(&value).ok_or_else(|| invalid("sample"))?,
+                    );
+                }
+                "limit" => {
+                    filter.limit = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&limit| limit > 0)
+                            .ok_or_else(|| invalid("limit"))?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Whether `event` passes the route, status and duration filters
+    pub fn matches(&self, event: &TapEvent) -> bool {
+        if self
+            .route
+            .as_deref()
+            .is_some_and(|route| event.route.as_deref() != Some(route))
+        {
+            return false;
+        }
+        let status_matches = match self.status {
+            Some(StatusFilter::Class(class)) => event.status / 100 == class,
+            Some(StatusFilter::Exact(status)) => event.status == status,
+            None => true,
+        };
+        status_matches
+            && self
+                .min_duration_ms
+                .is_none_or(|min| event.duration_ms >= min)
+    }
+
+    fn sampled(&self) -> bool {
+        self.sample_percent
+            .is_none_or(|percent| rand::rng().random_range(0.0..100.0) < percent)
+    }
+}
+
+/// An SSE frame with a JSON `data` line
+fn sse_frame(event: &str, data: &impl Serialize) -> Bytes {
+    let data = serde_json::to_string(data).unwrap_or_else(|_| "{}".to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(route: &str, status: u16, duration_ms: u64) -> TapEvent {
+        TapEvent {
+            route: Some(route.to_string()),
+            status,
+            duration_ms,
+            path: "/api/users".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_from_query() {
+        let filter =
+            TapFilter::from_query("route=api&status=5xx&min-duration-ms=250&sample=10%25&x=1")
+                .unwrap();
+        assert_eq!(filter.route.as_deref(), Some("api"));
+        assert_eq!(filter.status, Some(StatusFilter::Class(5)));
+        assert_eq!(filter.min_duration_ms, Some(250));
+        assert_eq!(filter.sample_percent, Some(10.0));
+        assert_eq!(filter.limit, None);
+
+        assert_eq!(
+            TapFilter::from_query("status=404").unwrap().status,
+            Some(StatusFilter::Exact(404))
+        );
+        assert_eq!(TapFilter::from_query("").unwrap(), TapFilter::default());
+        for invalid in [
+            "status=7xx",
+            "status=abc",
+            "min-duration-ms=-1",
+            "sample=0",
+            "limit=0",
+        ] {
+            assert!(TapFilter::from_query(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = TapFilter::from_query("route=api&status=5xx&min-duration-ms=100").unwrap();
+        assert!(filter.matches(&event("api", 503, 150)));
+        assert!(!filter.matches(&event("web", 503, 150)));
+        assert!(!filter.matches(&event("api", 404, 150)));
+        assert!(!filter.matches(&event("api", 503, 50)));
+        assert!(TapFilter::default().matches(&event("web", 200, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_stream_receives_matching_requests() {
+        let mut built = false;
+        publish(|| {
+            built = true;
+            TapEvent::default()
+        });
+        assert!(!built, "summaries are not built without streams");
+
+        let mut stream = TapStream::open(TapFilter::from_query("status=5xx&limit=1").unwrap());
+        publish(|| event("api", 200, 10));
+        publish(|| event("api", 502, 10));
+
+        let frame = stream.next().await.unwrap();
+        let frame = std::str::from_utf8(&frame).unwrap();
+        assert!(frame.starts_with("event: request\ndata: {"));
+        assert!(frame.ends_with("}\n\n"));
+        assert!(frame.contains("\"status\":502"));
+        assert!(!frame.contains("\"agents\""));
+
+        // Limit reached
+        assert!(stream.next().await.is_none());
+    }
+}
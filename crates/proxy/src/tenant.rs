@@ -0,0 +1,457 @@
+//! Per-tenant quotas, drain state and metrics.
+//!
+//! [`TenantManager`] attributes each request to a tenant (by route, then by
+//! arrival listener) and enforces the tenant's quotas centrally, across all of
+//! its routes:
+//!
+//! - `requests-per-second`: one rate window shared by the whole tenant
+//! - `max-concurrent-requests`: in-flight requests, released when the
+//!   request's [`TenantPermit`] is dropped
+//!
+//! A tenant can also be drained through the admin API: new requests for it
+//! are rejected with 503 while its in-flight requests finish, and requests of
+//! other tenants are unaffected. The drain flag survives config reloads.
+//!
+//! Requests that belong to no tenant are never limited here.
+
+use parking_lot::RwLock;
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use tracing::{debug, info};
+
+use zentinel_config::{Config, RateLimitAction, RateLimitBackend, RateLimitKey, TenantQuotas};
+
+use crate::rate_limit::{
+    RateLimitConfig, RateLimitOutcome, RateLimiterPool, DEFAULT_MAX_RATE_LIMIT_KEYS,
+};
+
+/// Prometheus metrics labelled by tenant.
+struct TenantMetrics {
+    /// Completed requests per tenant and status code
+    requests: IntCounterVec,
+    /// In-flight requests per tenant
+    active: IntGaugeVec,
+    /// Requests rejected by tenant quotas or drain, per reason
+    rejections: IntCounterVec,
+}
+
+static TENANT_METRICS: LazyLock<Option<TenantMetrics>> = LazyLock::new(|| {
+    let requests = register_int_counter_vec!(
+        "zentinel_tenant_requests_total",
+        "Requests served per tenant",
+        &["tenant", "status"]
+    )
+    .ok()?;
+    let active = register_int_gauge_vec!(
+        "zentinel_tenant_active_requests",
+        "Requests currently in flight per tenant",
+        &["tenant"]
+    )
+    .ok()?;
+    let rejections = register_int_counter_vec!(
+        "zentinel_tenant_rejections_total",
+        "Requests rejected by tenant quotas or drain",
+        &["tenant", "reason"]
+    )
+    .ok()?;
+    Some(TenantMetrics {
+        requests,
+        active,
+        rejections,
+    })
+});
+
+/// Runtime state of one tenant.
+///
+/// Kept across reloads (quotas are updated in place) so in-flight counts and
+/// the drain flag are never reset by a config change.
+struct TenantState {
+    id: String,
+    quotas: RwLock<TenantQuotas>,
+    limiter: RwLock<Option<Arc<RateLimiterPool>>>,
+    in_flight: AtomicU64,
+    draining: AtomicBool,
+}
+
+impl TenantState {
+    fn new(id: &str, quotas: &TenantQuotas) -> Self {
+        let state = Self {
+            id: id.to_string(),
+            quotas: RwLock::new(TenantQuotas::default()),
+            limiter: RwLock::new(None),
+            in_flight: AtomicU64::new(0),
+            draining: AtomicBool::new(false),
+        };
+        state.set_quotas(quotas);
+        state
+    }
+
+    fn set_quotas(&self, quotas: &TenantQuotas) {
+        if *self.quotas.read() == *quotas {
+            return;
+        }
+        *self.limiter.write() = quotas.requests_per_second.map(|max_rps| {
+            let config = RateLimitConfig {
+                max_rps,
+                burst: max_rps,
+                key: RateLimitKey::Route,
+                action: RateLimitAction::Reject,
+                status_code: 429,
+                message: None,
+                backend: RateLimitBackend::Local,
+                max_delay_ms: 0,
+                max_keys: DEFAULT_MAX_RATE_LIMIT_KEYS,
+            };
+            Arc::new(RateLimiterPool::with_scope(
+                config,
+                format!("tenant:{}", self.id),
+            ))
+        });
+        *self.quotas.write() = quotas.clone();
+    }
+}
+
+/// Outcome of admitting a request to its tenant.
+pub enum TenantAdmission {
+    /// The request may proceed; hold the permit until it completes.
+    Admitted(TenantPermit),
+    /// The tenant's request rate is exhausted.
+    RateLimited { limit: u32, reset_at: u64 },
+    /// The tenant already has `limit` requests in flight.
+    ConcurrencyLimited { limit: u64 },
+    /// The tenant is being drained.
+    Draining,
+}
+
+/// In-flight slot of a tenant, released on drop.
+pub struct TenantPermit {
+    state: Arc<TenantState>,
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        self.state.in_flight.fetch_sub(1, Ordering::AcqRel);
+        if let Some(metrics) = TENANT_METRICS.as_ref() {
+            metrics.active.with_label_values(&[&self.state.id]).dec();
+        }
+    }
+}
+
+/// Tenant status reported by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantStatus {
+    /// Tenant ID
+    pub id: String,
+    /// Whether new requests are being rejected
+    pub draining: bool,
+    /// Requests currently in flight
+    pub in_flight: u64,
+    /// Configured quotas
+    pub quotas: TenantQuotas,
+}
+
+/// Maps requests to tenants.
+#[derive(Default)]
+struct TenantResolver {
+    /// Route ID -> tenant ID (global routes and namespace routes)
+    routes: HashMap<String, String>,
+    /// Listener address -> tenant ID
+    listeners: HashMap<String, String>,
+}
+
+impl TenantResolver {
+    fn from_config(config: &Config) -> Self {
+        let mut resolver = Self::default();
+        for tenant in &config.tenants {
+            for route_id in tenant.route_ids(config) {
+                resolver
+                    .routes
+                    .insert(route_id.to_string(), tenant.id.clone());
+            }
+            for listener in &config.listeners {
+                let owned = tenant.listeners.contains(&listener.id)
+                    || listener
+                        .namespace
+                        .as_deref()
+                        .is_some_and(|ns| tenant.owns_namespace(ns));
+                if owned {
+                    resolver
+                        .listeners
+                        .insert(listener.address.clone(), tenant.id.clone());
+                }
+            }
+        }
+        resolver
+    }
+
+    fn resolve(&self, route_id: Option<&str>, listener_address: Option<&str>) -> Option<&str> {
+        route_id
+            .and_then(|id| self.routes.get(id))
+            .or_else(|| listener_address.and_then(|addr| self.listeners.get(addr)))
+            .map(String::as_str)
+    }
+}
+
+/// Tenant attribution, quota enforcement and drain control.
+pub struct TenantManager {
+    tenants: RwLock<HashMap<String, Arc<TenantState>>>,
+    resolver: RwLock<TenantResolver>,
+}
+
+impl TenantManager {
+    /// Create a manager for the tenants defined in `config`.
+    pub fn new(config: &Config) -> Self {
+        let manager = Self {
+            tenants: RwLock::new(HashMap::new()),
+            resolver: RwLock::new(TenantResolver::default()),
+        };
+        manager.reload(config);
+        manager
+    }
+
+    /// Apply the tenants of a new configuration.
+    ///
+    /// Tenants that still exist keep their in-flight count and drain flag;
+    /// only tenants whose quotas changed get a new rate window.
+    pub fn reload(&self, config: &Config) {
+        let mut tenants = self.tenants.write();
+        tenants.retain(|id, _| config.tenant(id).is_some());
+        for tenant in &config.tenants {
+            match tenants.get(&tenant.id) {
+                Some(state) => state.set_quotas(&tenant.quotas),
+                None => {
+                    tenants.insert(
+                        tenant.id.clone(),
+                        Arc::new(TenantState::new(&tenant.id, &tenant.quotas)),
+                    );
+                }
+            }
+        }
+        *self.resolver.write() = TenantResolver::from_config(config);
+
+        if !tenants.is_empty() {
+            info!(tenants = tenants.len(), "Tenants configured");
+        }
+    }
+
+    /// Returns true if any tenant is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.tenants.read().is_empty()
+    }
+
+    /// Tenant owning a request, by matched route first, then by the
+    /// listener it arrived on.
+    pub fn resolve(
+        &self,
+        route_id: Option<&str>,
+        listener_address: Option<&str>,
+    ) -> Option<String> {
+        self.resolver
+            .read()
+            .resolve(route_id, listener_address)
+            .map(str::to_string)
+    }
+
+    /// Admit a request to its tenant, enforcing drain and quotas.
+    ///
+    /// Returns `None` when the tenant is unknown (removed by a reload).
+    pub fn admit(&self, tenant_id: &str) -> Option<TenantAdmission> {
+        let state = self.tenants.read().get(tenant_id).cloned()?;
+
+        if state.draining.load(Ordering::Acquire) {
+            record_rejection(tenant_id, "draining");
+            return Some(TenantAdmission::Draining);
+        }
+
+        let max_concurrent = state.quotas.read().max_concurrent_requests;
+        let in_flight = state.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+        if max_concurrent.is_some_and(|limit| in_flight > limit) {
+            state.in_flight.fetch_sub(1, Ordering::AcqRel);
+            record_rejection(tenant_id, "concurrency");
+            return Some(TenantAdmission::ConcurrencyLimited {
+                limit: max_concurrent.unwrap_or_default(),
+            });
+        }
+        // From here on the permit releases the slot, including on rejection
+        let permit = TenantPermit {
+            state: Arc::clone(&state),
+        };
+        if let Some(metrics) = TENANT_METRICS.as_ref() {
+            metrics.active.with_label_values(&[tenant_id]).inc();
+        }
+
+        let limiter = state.limiter.read().clone();
+        if let Some(limiter) = limiter {
+            let check = limiter.check(tenant_id);
+            if check.outcome == RateLimitOutcome::Limited {
+                record_rejection(tenant_id, "rate");
+                return Some(TenantAdmission::RateLimited {
+                    limit: check.limit,
+                    reset_at: check.reset_at,
+                });
+            }
+        }
+
+        Some(TenantAdmission::Admitted(permit))
+    }
+
+    /// Record a completed request for the tenant's metrics.
+    pub fn record_response(&self, tenant_id: &str, status: u16) {
+        if let Some(metrics) = TENANT_METRICS.as_ref() {
+            metrics
+                .requests
+                .with_label_values(&[tenant_id, &status.to_string()])
+                .inc();
+        }
+    }
+
+    /// Start draining a tenant. Returns false if the tenant is unknown.
+    pub fn drain(&self, tenant_id: &str) -> bool {
+        self.set_draining(tenant_id, true)
+    }
+
+    /// Stop draining a tenant. Returns false if the tenant is unknown.
+    pub fn resume(&self, tenant_id: &str) -> bool {
+        self.set_draining(tenant_id, false)
+    }
+
+    fn set_draining(&self, tenant_id: &str, draining: bool) -> bool {
+        let Some(state) = self.tenants.read().get(tenant_id).cloned() else {
+            return false;
+        };
+        state.draining.store(draining, Ordering::Release);
+        info!(
+            tenant = %tenant_id,
+            draining = draining,
+            in_flight = state.in_flight.load(Ordering::Acquire),
+            "Tenant drain state changed"
+        );
+        true
+    }
+
+    /// Status of every tenant, sorted by ID.
+    pub fn status(&self) -> Vec<TenantStatus> {
+        let mut status: Vec<_> = self
+            .tenants
+            .read()
+            .values()
+            .map(|state| TenantStatus {
+                id: state.id.clone(),
+                draining: state.draining.load(Ordering::Acquire),
+                in_flight: state.in_flight.load(Ordering::Acquire),
+                quotas: state.quotas.read().clone(),
+            })
+            .collect();
+        status.sort_by(|a, b| a.id.cmp(&b.id));
+        status
+    }
+}
+
+fn record_rejection(tenant_id: &str, reason: &str) {
+    debug!(tenant = %tenant_id, reason = reason, "Request rejected by tenant");
+    if let Some(metrics) = TENANT_METRICS.as_ref() {
+        metrics
+            .rejections
+            .with_label_values(&[tenant_id, reason])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_config::TenantConfig;
+
+    fn config(quotas: TenantQuotas) -> Config {
+        let mut config = Config::default_for_testing();
+        let mut tenant = TenantConfig::new("acme");
+        tenant.routes = vec![config.routes[0].id.clone()];
+        tenant.listeners = vec![config.listeners[0].id.clone()];
+        tenant.quotas = quotas;
+        config.tenants.push(tenant);
+        config
+    }
+
+    #[test]
+    fn test_resolve_by_route_then_listener() {
+        let config = config(TenantQuotas::default());
+        let manager = TenantManager::new(&config);
+        let route_id = config.routes[0].id.as_str();
+        let address = config.listeners[0].address.as_str();
+
+        assert_eq!(
+            manager.resolve(Some(route_id), None).as_deref(),
+            Some("acme")
+        );
+        assert_eq!(
+            manager.resolve(Some("other"), Some(address)).as_deref(),
+            Some("acme")
+        );
+        assert_eq!(manager.resolve(Some("other"), Some("127.0.0.1:1")), None);
+    }
+
+    #[test]
+    fn test_concurrency_quota_released_on_drop() {
+        let manager = TenantManager::new(&config(TenantQuotas {
+            max_concurrent_requests: Some(1),
+            ..Default::default()
+        }));
+
+        let Some(TenantAdmission::Admitted(permit)) = manager.admit("acme") else {
+            panic!("first request must be admitted");
+        };
+        assert!(matches!(
+            manager.admit("acme"),
+            Some(TenantAdmission::ConcurrencyLimited { limit: 1 })
+        ));
+        drop(permit);
+        assert!(matches!(
+            manager.admit("acme"),
+            Some(TenantAdmission::Admitted(_))
+        ));
+    }
+
+    #[test]
+    fn test_rate_quota() {
+        let manager = TenantManager::new(&config(TenantQuotas {
+            requests_per_second: Some(2),
+            ..Default::default()
+        }));
+
+        let admitted = (0..5)
+            .filter(|_| matches!(manager.admit("acme"), Some(TenantAdmission::Admitted(_))))
+            .count();
+        assert_eq!(admitted, 2);
+    }
+
+    #[test]
+    fn test_drain_survives_reload_and_is_per_tenant() {
+        let mut config = config(TenantQuotas::default());
+        config.tenants.push(TenantConfig::new("globex"));
+        let manager = TenantManager::new(&config);
+
+        assert!(manager.drain("acme"));
+        assert!(!manager.drain("unknown"));
+
+        config.tenants[0].quotas.max_concurrent_requests = Some(10);
+        manager.reload(&config);
+
+        assert!(matches!(
+            manager.admit("acme"),
+            Some(TenantAdmission::Draining)
+        ));
+        assert!(matches!(
+            manager.admit("globex"),
+            Some(TenantAdmission::Admitted(_))
+        ));
+
+        assert!(manager.resume("acme"));
+        assert!(matches!(
+            manager.admit("acme"),
+            Some(TenantAdmission::Admitted(_))
+        ));
+    }
+}
@@ -75,7 +75,7 @@ use rustls::sign::CertifiedKey;
 use rustls::{RootCertStore, ServerConfig};
 use tracing::{debug, error, info, trace, warn};
 
-use zentinel_config::{TlsConfig, UpstreamTlsConfig};
+use zentinel_config::{AlpnProtocol, TlsConfig, UpstreamTlsConfig};
 
 /// Error type for TLS operations
 #[derive(Debug)]
@@ -125,6 +125,8 @@ pub struct SniResolver {
     sni_certs: HashMap<String, Arc<CertifiedKey>>,
     /// Wildcard certificates (e.g., "*.example.com" -> cert)
     wildcard_certs: HashMap<String, Arc<CertifiedKey>>,
+    /// `not_after` (Unix seconds) of each loaded leaf certificate, by file
+    expiry: Vec<(String, i64)>,
 }
 
 impl SniResolver {
@@ -156,6 +158,10 @@ impl SniResolver {
 
         // Load default certificate
         let default_cert = load_certified_key(cert_file, key_file)?;
+        let mut expiry = Vec::new();
+        if let Some(not_after) = certificate_not_after(&default_cert) {
+            expiry.push((cert_file.display().to_string(), not_after));
+        }
 
         info!(
             listener_id = %listener_id_str,
@@ -222,6 +228,9 @@ impl SniResolver {
                     }
                 }
             };
+            if let Some(not_after) = certificate_not_after(&cert) {
+                expiry.push((sni_cert_path.display().to_string(), not_after));
+            }
 
             // Build priority set for this cert (lowercased for consistent matching)
             let priority_set: HashSet<String> = sni_config
@@ -390,9 +399,16 @@ impl SniResolver {
             default_cert: Arc::new(default_cert),
             sni_certs,
             wildcard_certs,
+            expiry,
         })
     }
 
+    /// `not_after` (Unix seconds) of each certificate this resolver serves,
+    /// labelled by the file it was loaded from
+    pub fn certificate_expiry(&self) -> &[(String, i64)] {
+        &self.expiry
+    }
+
     /// Resolve certificate for a given server name
     ///
     /// This is the core resolution logic. For the rustls trait implementation,
@@ -432,6 +448,31 @@ impl SniResolver {
         );
         self.default_cert.clone()
     }
+
+    /// Attach cached OCSP responses to the resolver's certificates.
+    ///
+    /// Certificates without a cached response are served without a staple.
+    /// Returns the number of certificates stapled.
+    pub fn staple_ocsp(&mut self, stapler: &OcspStapler) -> usize {
+        let mut stapled = 0;
+        let certs = std::iter::once(&mut self.default_cert)
+            .chain(self.sni_certs.values_mut())
+            .chain(self.wildcard_certs.values_mut());
+
+        for cert in certs {
+            let Some(leaf) = cert.cert.first() else {
+                continue;
+            };
+            if let Some(response) = stapler.get_response(&calculate_cert_fingerprint(leaf)) {
+                let mut stapled_cert = CertifiedKey::clone(cert);
+                stapled_cert.ocsp = Some(response);
+                *cert = Arc::new(stapled_cert);
+                stapled += 1;
+            }
+        }
+
+        stapled
+    }
 }
 
 impl ResolvesServerCert for SniResolver {
@@ -458,6 +499,10 @@ pub struct HotReloadableSniResolver {
     listener_id: String,
     /// Last reload time
     last_reload: RwLock<Instant>,
+    /// OCSP responses stapled on (re)load when `ocsp_stapling` is enabled
+    ocsp_stapler: Option<Arc<OcspStapler>>,
+    /// Content hash of the watched certificate/key files at the last check
+    files_signature: RwLock<String>,
 }
 
 impl std::fmt::Debug for HotReloadableSniResolver {
@@ -465,6 +510,7 @@ impl std::fmt::Debug for HotReloadableSniResolver {
         f.debug_struct("HotReloadableSniResolver")
             .field("last_reload", &*self.last_reload.read())
             .field("listener_id", &self.listener_id)
+            .field("has_ocsp_stapler", &self.ocsp_stapler.is_some())
             .finish()
     }
 }
@@ -477,15 +523,91 @@ impl HotReloadableSniResolver {
     ) -> Result<Self, TlsError> {
         let listener_id = listener_id.into();
         let resolver = SniResolver::from_config(&config, Some(&listener_id))?;
+        record_certificate_expiry(&listener_id, &resolver);
+        let files_signature = files_signature(&manual_certificate_files(&config));
 
         Ok(Self {
             inner: RwLock::new(Arc::new(resolver)),
             config: RwLock::new(config),
             listener_id,
             last_reload: RwLock::new(Instant::now()),
+            ocsp_stapler: None,
+            files_signature: RwLock::new(files_signature),
         })
     }
 
+    /// Staple OCSP responses from this stapler on every (re)load
+    ///
+    /// Responses already cached are stapled immediately; call
+    /// [`refresh_ocsp`](Self::refresh_ocsp) to fetch fresh ones.
+    pub fn with_ocsp_stapler(mut self, stapler: Arc<OcspStapler>) -> Self {
+        if self.config.get_mut().ocsp_stapling {
+            // Not yet shared with a listener, so the resolver is unique
+            if let Some(resolver) = Arc::get_mut(self.inner.get_mut()) {
+                resolver.staple_ocsp(&stapler);
+            }
+        }
+        self.ocsp_stapler = Some(stapler);
+        self
+    }
+
+    /// Listener this resolver serves
+    pub fn listener_id(&self) -> &str {
+        &self.listener_id
+    }
+
+    /// Certificate and key files to watch for changes
+    ///
+    /// Only manually configured files are included; ACME-managed
+    /// certificates are reloaded by the renewal scheduler.
+    pub fn watched_files(&self) -> Vec<std::path::PathBuf> {
+        manual_certificate_files(&self.config.read())
+    }
+
+    /// Reload if any watched certificate or key file changed since the last check
+    ///
+    /// Returns `Ok(true)` when new certificates were swapped in. A failed
+    /// reload (e.g. a certificate written before its key) keeps serving the
+    /// old certificates and is retried on the next change.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let signature = files_signature(&self.watched_files());
+        {
+            let mut current = self.files_signature.write();
+            if *current == signature {
+                return Ok(false);
+            }
+            *current = signature;
+        }
+
+        info!(
+            listener_id = %self.listener_id,
+            "TLS certificate files changed on disk"
+        );
+        self.reload().map(|()| true)
+    }
+
+    /// Build a resolver for `config`, stapling cached OCSP responses
+    fn build_resolver(&self, config: &TlsConfig) -> Result<SniResolver, TlsError> {
+        let mut resolver = SniResolver::from_config(config, Some(&self.listener_id))?;
+        if let Some(ref stapler) = self.ocsp_stapler {
+            if config.ocsp_stapling {
+                let stapled = resolver.staple_ocsp(stapler);
+                debug!(
+                    listener_id = %self.listener_id,
+                    stapled = stapled,
+                    "Stapled cached OCSP responses"
+                );
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// Serve `resolver` from now on, reporting the expiry of its certificates
+    fn install(&self, resolver: SniResolver) {
+        record_certificate_expiry(&self.listener_id, &resolver);
+        *self.inner.write() = Arc::new(resolver);
+    }
+
     /// Reload certificates from disk
     ///
     /// This is called on SIGHUP to pick up new certificates without restart.
@@ -507,10 +629,14 @@ impl HotReloadableSniResolver {
         );
 
         // Try to load new certificates
-        let new_resolver = SniResolver::from_config(&config, Some(&self.listener_id))?;
+        let result = self.build_resolver(&config);
+        if let Some(metrics) = crate::tls_metrics::get_tls_metrics() {
+            metrics.record_cert_reload(&self.listener_id, result.is_ok());
+        }
+        let new_resolver = result?;
 
         // Swap in the new resolver atomically
-        *self.inner.write() = Arc::new(new_resolver);
+        self.install(new_resolver);
         *self.last_reload.write() = Instant::now();
 
         info!(
@@ -523,11 +649,11 @@ impl HotReloadableSniResolver {
     /// Update configuration and reload
     pub fn update_config(&self, new_config: TlsConfig) -> Result<(), TlsError> {
         // Load with new config first
-        let new_resolver = SniResolver::from_config(&new_config, Some(&self.listener_id))?;
+        let new_resolver = self.build_resolver(&new_config)?;
 
         // Update both config and resolver
         *self.config.write() = new_config;
-        *self.inner.write() = Arc::new(new_resolver);
+        self.install(new_resolver);
         *self.last_reload.write() = Instant::now();
 
         info!(
@@ -537,6 +663,32 @@ impl HotReloadableSniResolver {
         Ok(())
     }
 
+    /// Fetch fresh OCSP responses and staple them into the served certificates
+    ///
+    /// Returns warnings for certificates whose responses could not be
+    /// fetched; those keep any previously cached response until it expires.
+    pub async fn refresh_ocsp(&self) -> Result<Vec<String>, TlsError> {
+        let Some(ref stapler) = self.ocsp_stapler else {
+            return Ok(Vec::new());
+        };
+        let config = self.config.read().clone();
+        if !config.ocsp_stapling {
+            return Ok(Vec::new());
+        }
+
+        let warnings = stapler.prefetch_for_config(&config).await;
+
+        let new_resolver = self.build_resolver(&config)?;
+        self.install(new_resolver);
+
+        debug!(
+            listener_id = %self.listener_id,
+            warnings = warnings.len(),
+            "OCSP responses refreshed"
+        );
+        Ok(warnings)
+    }
+
     /// Get time since last reload
     pub fn last_reload_age(&self) -> Duration {
         self.last_reload.read().elapsed()
@@ -580,6 +732,48 @@ impl CertificateReloader {
             .insert(listener_id.to_string(), resolver);
     }
 
+    /// Get the resolver registered for a listener
+    pub fn get(&self, listener_id: &str) -> Option<Arc<HotReloadableSniResolver>> {
+        self.resolvers.read().get(listener_id).cloned()
+    }
+
+    /// Certificate and key files watched across all listeners
+    pub fn watched_files(&self) -> Vec<std::path::PathBuf> {
+        let mut files: Vec<_> = self
+            .resolvers
+            .read()
+            .values()
+            .flat_map(|resolver| resolver.watched_files())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Reload listeners whose certificate or key files changed on disk
+    ///
+    /// Returns the number of listeners that swapped in new certificates.
+    pub fn reload_changed(&self) -> usize {
+        let resolvers: Vec<_> = self.resolvers.read().values().cloned().collect();
+        let mut reloaded = 0;
+
+        for resolver in resolvers {
+            match resolver.reload_if_changed() {
+                Ok(true) => reloaded += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!(
+                        listener_id = %resolver.listener_id(),
+                        error = %e,
+                        "Changed certificate files failed validation, keeping current certificates"
+                    );
+                }
+            }
+        }
+
+        reloaded
+    }
+
     /// Reload all registered certificates
     ///
     /// Returns the number of successfully reloaded listeners and any errors.
@@ -783,7 +977,13 @@ impl OcspStapler {
     }
 
     /// Prefetch OCSP responses for all certificates in a config
-    pub fn prefetch_for_config(&self, config: &TlsConfig) -> Vec<String> {
+    ///
+    /// Fetches a response for each certificate whose chain includes its
+    /// issuer and whose AIA extension names an OCSP responder. Returns a
+    /// warning for each certificate that could not be refreshed.
+    pub async fn prefetch_for_config(&self, config: &TlsConfig) -> Vec<String> {
+        use x509_parser::prelude::*;
+
         let mut warnings = Vec::new();
 
         if !config.ocsp_stapling {
@@ -793,9 +993,43 @@ impl OcspStapler {
 
         info!("Prefetching OCSP responses for certificates");
 
-        // For now, just log that we would prefetch
-        // Full implementation would iterate certificates and fetch OCSP responses
-        warnings.push("OCSP stapling prefetch not yet fully implemented".to_string());
+        for cert_path in certificate_files(config) {
+            let chain = match load_certificate_chain(&cert_path) {
+                Ok(chain) => chain,
+                Err(e) => {
+                    warnings.push(e.to_string());
+                    continue;
+                }
+            };
+
+            let has_responder = X509Certificate::from_der(&chain[0])
+                .ok()
+                .is_some_and(|(_, cert)| extract_ocsp_responder_url(&cert).is_ok());
+            if !has_responder {
+                debug!(
+                    cert_file = %cert_path.display(),
+                    "Certificate has no OCSP responder, skipping stapling"
+                );
+                continue;
+            }
+
+            let Some(issuer) = chain.get(1) else {
+                warnings.push(format!(
+                    "{}: certificate chain has no issuer certificate for OCSP",
+                    cert_path.display()
+                ));
+                continue;
+            };
+
+            if let Err(e) = self.fetch_ocsp_response_async(&chain[0], issuer).await {
+                warn!(
+                    cert_file = %cert_path.display(),
+                    error = %e,
+                    "Failed to refresh OCSP response"
+                );
+                warnings.push(format!("{}: {}", cert_path.display(), e));
+            }
+        }
 
         warnings
     }
@@ -1064,7 +1298,7 @@ fn send_ocsp_request_sync(url: &str, request: &[u8]) -> Result<Vec<u8>, TlsError
 
 /// Send OCSP request asynchronously
 async fn send_ocsp_request_async(url: &str, request: &[u8]) -> Result<Vec<u8>, TlsError> {
-    let client = reqwest::Client::builder()
+    let client = crate::outbound::client_builder()
         .timeout(Duration::from_secs(10))
         .build()
         .map_err(|e| TlsError::OcspFetch(format!("Failed to create HTTP client: {}", e)))?;
@@ -1102,9 +1336,80 @@ fn calculate_cert_fingerprint(cert_der: &[u8]) -> String {
 }
 
 // ============================================================================
-// Upstream mTLS Support (Client Certificates)
+// Downstream mTLS Client Certificate Metadata
+// ============================================================================
+
+/// Extract agent-facing metadata from a downstream client certificate.
+///
+/// `verified` is the handshake's verification result; with rustls the
+/// handshake only completes once the client verifier has accepted the chain.
+/// Returns `None` if the certificate cannot be parsed.
+pub fn client_certificate_info(
+    cert_der: &[u8],
+    verified: bool,
+) -> Option<zentinel_agent_protocol::ClientCertificate> {
+    use x509_parser::prelude::*;
+
+    let (_, cert) = X509Certificate::from_der(cert_der).ok()?;
+
+    let mut sans = Vec::new();
+    if let Ok(Some(san_ext)) = cert.subject_alternative_name() {
+        for name in &san_ext.value.general_names {
+            match name {
+                GeneralName::DNSName(dns) => sans.push(format!("DNS:{}", dns)),
+                GeneralName::RFC822Name(email) => sans.push(format!("email:{}", email)),
+                GeneralName::URI(uri) => sans.push(format!("URI:{}", uri)),
+                GeneralName::IPAddress(bytes) => {
+                    let ip = match bytes.len() {
+                        4 => <[u8; 4]>::try_from(*bytes)
+                            .map(|b| std::net::IpAddr::from(b).to_string())
+                            .ok(),
+                        16 => <[u8; 16]>::try_from(*bytes)
+                            .map(|b| std::net::IpAddr::from(b).to_string())
+                            .ok(),
+                        _ => None,
+                    };
+                    if let Some(ip) = ip {
+                        sans.push(format!("IP:{}", ip));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Some(zentinel_agent_protocol::ClientCertificate {
+        subject: Some(cert.subject().to_string()),
+        sans,
+        fingerprint_sha256: calculate_cert_fingerprint(cert_der),
+        serial_number: Some(cert.raw_serial_as_string()),
+        verified,
+    })
+}
+
+/// Encode a DER certificate as PEM for forwarding to upstreams.
+pub fn certificate_pem(cert_der: &[u8]) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let encoded = STANDARD.encode(cert_der);
+    let mut pem = String::with_capacity(encoded.len() + encoded.len() / 64 + 64);
+    pem.push_str("-----BEGIN CERTIFICATE-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        // base64 output is ASCII
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
+// ============================================================================
+// Upstream TLS Support (mTLS, CA bundles, SPIFFE)
 // ============================================================================
 
+/// How often [`UpstreamTlsSecrets`] checks its files for changes
+const UPSTREAM_SECRETS_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Load client certificate and key for mTLS to upstreams
 ///
 /// This function loads PEM-encoded certificates and private key and converts
@@ -1122,72 +1427,374 @@ pub fn load_client_cert_key(
     cert_path: &Path,
     key_path: &Path,
 ) -> Result<Arc<pingora_core::utils::tls::CertKey>, TlsError> {
-    // Read certificate chain (PEM format, may contain intermediates)
-    let cert_file = File::open(cert_path)
+    let cert_pem = std::fs::read(cert_path)
         .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
-    let mut cert_reader = BufReader::new(cert_file);
+    let key_pem = std::fs::read(key_path)
+        .map_err(|e| TlsError::KeyLoad(format!("{}: {}", key_path.display(), e)))?;
 
-    // Parse certificates from PEM to DER
-    let cert_ders: Vec<Vec<u8>> = rustls_pemfile::certs(&mut cert_reader)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?
+    let cert_key = client_cert_key_from_pem(
+        &cert_pem,
+        &cert_path.display().to_string(),
+        &key_pem,
+        &key_path.display().to_string(),
+    )?;
+
+    debug!(
+        cert_path = %cert_path.display(),
+        key_path = %key_path.display(),
+        "Loaded mTLS client certificate for upstream connections"
+    );
+
+    Ok(cert_key)
+}
+
+/// Build Pingora's CertKey from PEM certificate chain and key
+fn client_cert_key_from_pem(
+    cert_pem: &[u8],
+    cert_source: &str,
+    key_pem: &[u8],
+    key_source: &str,
+) -> Result<Arc<pingora_core::utils::tls::CertKey>, TlsError> {
+    let cert_ders: Vec<Vec<u8>> = parse_pem_certificates(cert_pem, cert_source)?
         .into_iter()
         .map(|c| c.to_vec())
         .collect();
+    let key_der = parse_pem_private_key(key_pem, key_source)?
+        .secret_der()
+        .to_vec();
 
-    if cert_ders.is_empty() {
+    Ok(Arc::new(pingora_core::utils::tls::CertKey::new(
+        cert_ders, key_der,
+    )))
+}
+
+/// Parse a non-empty PEM certificate list
+fn parse_pem_certificates(
+    pem: &[u8],
+    source: &str,
+) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut BufReader::new(pem))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", source, e)))?;
+
+    if certs.is_empty() {
         return Err(TlsError::CertificateLoad(format!(
             "{}: No certificates found in PEM file",
-            cert_path.display()
+            source
         )));
     }
+    Ok(certs)
+}
 
-    // Read private key (PEM format)
-    let key_file = File::open(key_path)
-        .map_err(|e| TlsError::KeyLoad(format!("{}: {}", key_path.display(), e)))?;
-    let mut key_reader = BufReader::new(key_file);
+/// Parse the first PEM private key
+fn parse_pem_private_key(
+    pem: &[u8],
+    source: &str,
+) -> Result<rustls::pki_types::PrivateKeyDer<'static>, TlsError> {
+    rustls_pemfile::private_key(&mut BufReader::new(pem))
+        .map_err(|e| TlsError::KeyLoad(format!("{}: {}", source, e)))?
+        .ok_or_else(|| TlsError::KeyLoad(format!("{}: No private key found in PEM file", source)))
+}
 
-    // Parse private key from PEM to DER
-    let key_der = rustls_pemfile::private_key(&mut key_reader)
-        .map_err(|e| TlsError::KeyLoad(format!("{}: {}", key_path.display(), e)))?
-        .ok_or_else(|| {
-            TlsError::KeyLoad(format!(
-                "{}: No private key found in PEM file",
-                key_path.display()
-            ))
-        })?
-        .secret_der()
-        .to_vec();
+/// Read an upstream TLS secret from its file or environment variable
+///
+/// Returns the PEM and a description of its source for error messages.
+fn read_upstream_secret(
+    path: Option<&Path>,
+    env: Option<&str>,
+    error: fn(String) -> TlsError,
+) -> Result<Option<(Vec<u8>, String)>, TlsError> {
+    if let Some(path) = path {
+        let pem = std::fs::read(path).map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+        return Ok(Some((pem, path.display().to_string())));
+    }
+    if let Some(var) = env {
+        let pem = std::env::var(var).map_err(|e| error(format!("${}: {}", var, e)))?;
+        return Ok(Some((pem.into_bytes(), format!("${}", var))));
+    }
+    Ok(None)
+}
 
-    // Create Pingora's CertKey (certificates: Vec<Vec<u8>>, key: Vec<u8>)
-    let cert_key = pingora_core::utils::tls::CertKey::new(cert_ders, key_der);
+/// Load the CA bundle of an upstream, if configured
+pub fn load_upstream_ca(
+    config: &UpstreamTlsConfig,
+) -> Result<Option<Vec<CertificateDer<'static>>>, TlsError> {
+    read_upstream_secret(
+        config.ca_cert.as_deref(),
+        config.ca_cert_env.as_deref(),
+        TlsError::CertificateLoad,
+    )?
+    .map(|(pem, source)| parse_pem_certificates(&pem, &source))
+    .transpose()
+}
 
-    debug!(
-        cert_path = %cert_path.display(),
-        key_path = %key_path.display(),
-        "Loaded mTLS client certificate for upstream connections"
-    );
+/// Load the mTLS client certificate of an upstream, if configured
+pub fn load_upstream_client_cert_key(
+    config: &UpstreamTlsConfig,
+) -> Result<Option<Arc<pingora_core::utils::tls::CertKey>>, TlsError> {
+    let cert = read_upstream_secret(
+        config.client_cert.as_deref(),
+        config.client_cert_env.as_deref(),
+        TlsError::CertificateLoad,
+    )?;
+    let key = read_upstream_secret(
+        config.client_key.as_deref(),
+        config.client_key_env.as_deref(),
+        TlsError::KeyLoad,
+    )?;
+
+    match (cert, key) {
+        (Some((cert_pem, cert_source)), Some((key_pem, key_source))) => {
+            client_cert_key_from_pem(&cert_pem, &cert_source, &key_pem, &key_source).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// SPIFFE IDs in a certificate's URI SANs
+pub fn certificate_spiffe_ids(cert_der: &[u8]) -> Vec<String> {
+    use x509_parser::prelude::*;
+
+    let Ok((_, cert)) = X509Certificate::from_der(cert_der) else {
+        return Vec::new();
+    };
+    let Ok(Some(san)) = cert.subject_alternative_name() else {
+        return Vec::new();
+    };
+    san.value
+        .general_names
+        .iter()
+        .filter_map(|name| match name {
+            GeneralName::URI(uri) if uri.starts_with("spiffe://") => Some(uri.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `id` is accepted by one of `allowed`
+///
+/// An entry without a path accepts every ID in its trust domain.
+pub fn spiffe_id_allowed(id: &str, allowed: &[String]) -> bool {
+    allowed.iter().any(|entry| {
+        if entry == id {
+            return true;
+        }
+        let trust_domain_only = entry
+            .strip_prefix("spiffe://")
+            .is_some_and(|domain| !domain.contains('/'));
+        trust_domain_only
+            && id
+                .strip_prefix(entry.as_str())
+                .is_some_and(|path| path.starts_with('/'))
+    })
+}
+
+/// Verifies upstream certificates by SPIFFE ID instead of hostname
+///
+/// The chain is verified against the configured roots like any server
+/// certificate; the DNS name check is replaced by requiring a URI SAN
+/// accepted by [`spiffe_id_allowed`].
+#[derive(Debug)]
+pub struct SpiffeServerVerifier {
+    roots: Arc<RootCertStore>,
+    allowed: Vec<String>,
+    provider: Arc<rustls::crypto::CryptoProvider>,
+}
+
+impl SpiffeServerVerifier {
+    /// Create a verifier accepting `allowed` SPIFFE IDs signed by `roots`
+    pub fn new(roots: Arc<RootCertStore>, allowed: Vec<String>) -> Result<Self, TlsError> {
+        let provider = rustls::crypto::CryptoProvider::get_default()
+            .cloned()
+            .ok_or_else(|| TlsError::ConfigBuild("No default crypto provider installed".into()))?;
+        Ok(Self {
+            roots,
+            allowed,
+            provider,
+        })
+    }
+}
+
+impl rustls::client::danger::ServerCertVerifier for SpiffeServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let cert = rustls::server::ParsedCertificate::try_from(end_entity)?;
+        rustls::client::verify_server_cert_signed_by_trust_anchor(
+            &cert,
+            &self.roots,
+            intermediates,
+            now,
+            self.provider.signature_verification_algorithms.all,
+        )?;
+
+        let ids = certificate_spiffe_ids(end_entity);
+        if !ids.iter().any(|id| spiffe_id_allowed(id, &self.allowed)) {
+            warn!(
+                presented = ?ids,
+                allowed = ?self.allowed,
+                "Upstream certificate has no accepted SPIFFE ID"
+            );
+            return Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
 
-    Ok(Arc::new(cert_key))
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// CA bundle and client certificate of an upstream
+///
+/// Secrets are loaded when the upstream pool is created; files are checked
+/// for changes at most every [`UPSTREAM_SECRETS_CHECK_INTERVAL`] and
+/// reloaded, so rotated certificates apply to new connections. A rotation
+/// that fails to load keeps the previous secrets. Secrets from environment
+/// variables change only with a configuration reload.
+pub struct UpstreamTlsSecrets {
+    upstream_id: String,
+    config: UpstreamTlsConfig,
+    loaded: RwLock<LoadedUpstreamSecrets>,
+    files_signature: RwLock<String>,
+    last_check: parking_lot::Mutex<Instant>,
+}
+
+/// Secrets as handed to Pingora peers
+#[derive(Clone, Default)]
+pub struct LoadedUpstreamSecrets {
+    /// Roots to verify the upstream against; webpki roots when `None`
+    pub ca: Option<Arc<pingora_core::protocols::tls::CaType>>,
+    /// mTLS client certificate
+    pub client_cert_key: Option<Arc<pingora_core::utils::tls::CertKey>>,
+}
+
+impl UpstreamTlsSecrets {
+    /// Load the secrets of `config`
+    pub fn load(upstream_id: &str, config: &UpstreamTlsConfig) -> Result<Self, TlsError> {
+        let loaded = Self::read(config)?;
+        if loaded.client_cert_key.is_some() {
+            info!(
+                upstream_id = %upstream_id,
+                "Configured mTLS client certificate for upstream connections"
+            );
+        }
+        Ok(Self {
+            upstream_id: upstream_id.to_string(),
+            config: config.clone(),
+            loaded: RwLock::new(loaded),
+            files_signature: RwLock::new(files_signature(&Self::files(config))),
+            last_check: parking_lot::Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Current secrets, reloading changed files first
+    pub fn current(&self) -> LoadedUpstreamSecrets {
+        self.reload_if_due(Instant::now());
+        self.loaded.read().clone()
+    }
+
+    fn read(config: &UpstreamTlsConfig) -> Result<LoadedUpstreamSecrets, TlsError> {
+        Ok(LoadedUpstreamSecrets {
+            ca: load_upstream_ca(config)?.map(|certs| Arc::from(certs.into_boxed_slice())),
+            client_cert_key: load_upstream_client_cert_key(config)?,
+        })
+    }
+
+    /// Secret files of `config`
+    fn files(config: &UpstreamTlsConfig) -> Vec<std::path::PathBuf> {
+        [&config.ca_cert, &config.client_cert, &config.client_key]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    fn reload_if_due(&self, now: Instant) {
+        {
+            let mut last_check = self.last_check.lock();
+            if now.duration_since(*last_check) < UPSTREAM_SECRETS_CHECK_INTERVAL {
+                return;
+            }
+            *last_check = now;
+        }
+
+        let files = Self::files(&self.config);
+        if files.is_empty() {
+            return;
+        }
+        let signature = files_signature(&files);
+        if *self.files_signature.read() == signature {
+            return;
+        }
+
+        match Self::read(&self.config) {
+            Ok(loaded) => {
+                *self.loaded.write() = loaded;
+                *self.files_signature.write() = signature;
+                info!(
+                    upstream_id = %self.upstream_id,
+                    "Hot-reloaded upstream TLS certificates from disk"
+                );
+            }
+            Err(e) => {
+                error!(
+                    upstream_id = %self.upstream_id,
+                    error = %e,
+                    "Changed upstream TLS files failed to load, keeping current certificates"
+                );
+            }
+        }
+    }
 }
 
 /// Build a TLS client configuration for upstream connections with mTLS
 ///
 /// This creates a rustls ClientConfig that can be used when Zentinel
 /// connects to backends that require client certificate authentication.
+/// Upstreams with SPIFFE IDs are verified with [`SpiffeServerVerifier`].
 pub fn build_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<ClientConfig, TlsError> {
     let mut root_store = RootCertStore::empty();
 
     // Load CA certificates for server verification
-    if let Some(ca_path) = &config.ca_cert {
-        let ca_file = File::open(ca_path)
-            .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", ca_path.display(), e)))?;
-        let mut ca_reader = BufReader::new(ca_file);
-
-        let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut ca_reader)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", ca_path.display(), e)))?;
-
+    if let Some(certs) = load_upstream_ca(config)? {
         for cert in certs {
             root_store.add(cert).map_err(|e| {
                 TlsError::InvalidCertificate(format!("Failed to add CA certificate: {}", e))
@@ -1195,7 +1802,6 @@ pub fn build_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<ClientCon
         }
 
         debug!(
-            ca_file = %ca_path.display(),
             cert_count = root_store.len(),
             "Loaded upstream CA certificates"
         );
@@ -1208,50 +1814,53 @@ pub fn build_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<ClientCon
     }
 
     // Build the client config
-    let builder = ClientConfig::builder().with_root_certificates(root_store);
-
-    let client_config = if let (Some(cert_path), Some(key_path)) =
-        (&config.client_cert, &config.client_key)
-    {
-        // Load client certificate for mTLS
-        let cert_file = File::open(cert_path)
-            .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
-        let mut cert_reader = BufReader::new(cert_file);
-
-        let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut cert_reader)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
-
-        if certs.is_empty() {
-            return Err(TlsError::CertificateLoad(format!(
-                "{}: No certificates found",
-                cert_path.display()
-            )));
-        }
+    let builder = if config.spiffe_ids.is_empty() {
+        ClientConfig::builder().with_root_certificates(root_store)
+    } else {
+        let verifier = SpiffeServerVerifier::new(Arc::new(root_store), config.spiffe_ids.clone())?;
+        ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+    };
 
-        // Load client private key
-        let key_file = File::open(key_path)
-            .map_err(|e| TlsError::KeyLoad(format!("{}: {}", key_path.display(), e)))?;
-        let mut key_reader = BufReader::new(key_file);
+    let cert = read_upstream_secret(
+        config.client_cert.as_deref(),
+        config.client_cert_env.as_deref(),
+        TlsError::CertificateLoad,
+    )?;
+    let key = read_upstream_secret(
+        config.client_key.as_deref(),
+        config.client_key_env.as_deref(),
+        TlsError::KeyLoad,
+    )?;
+
+    let mut client_config =
+        if let (Some((cert_pem, cert_source)), Some((key_pem, key_source))) = (cert, key) {
+            // Load client certificate for mTLS
+            let certs = parse_pem_certificates(&cert_pem, &cert_source)?;
+            let key = parse_pem_private_key(&key_pem, &key_source)?;
 
-        let key = rustls_pemfile::private_key(&mut key_reader)
-            .map_err(|e| TlsError::KeyLoad(format!("{}: {}", key_path.display(), e)))?
-            .ok_or_else(|| {
-                TlsError::KeyLoad(format!("{}: No private key found", key_path.display()))
-            })?;
+            info!(
+                cert_source = %cert_source,
+                "Configured mTLS client certificate for upstream connections"
+            );
 
-        info!(
-            cert_file = %cert_path.display(),
-            "Configured mTLS client certificate for upstream connections"
-        );
+            builder.with_client_auth_cert(certs, key).map_err(|e| {
+                TlsError::CertKeyMismatch(format!("Failed to set client auth: {}", e))
+            })?
+        } else {
+            // No client certificate
+            builder.with_no_client_auth()
+        };
 
-        builder
-            .with_client_auth_cert(certs, key)
-            .map_err(|e| TlsError::CertKeyMismatch(format!("Failed to set client auth: {}", e)))?
-    } else {
-        // No client certificate
-        builder.with_no_client_auth()
-    };
+    client_config.alpn_protocols = config
+        .alpn
+        .iter()
+        .map(|protocol| match protocol {
+            AlpnProtocol::H2 => b"h2".to_vec(),
+            AlpnProtocol::Http11 => b"http/1.1".to_vec(),
+        })
+        .collect();
 
     debug!("Upstream TLS configuration built successfully");
     Ok(client_config)
@@ -1277,25 +1886,45 @@ pub fn validate_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<(), Tl
                 cert_path.display()
             )));
         }
+    }
+    if let Some(key_path) = &config.client_key {
+        if !key_path.exists() {
+            return Err(TlsError::KeyLoad(format!(
+                "Upstream client key not found: {}",
+                key_path.display()
+            )));
+        }
+    }
 
-        // If cert is specified, key must also be specified
-        match &config.client_key {
-            Some(key_path) if !key_path.exists() => {
-                return Err(TlsError::KeyLoad(format!(
-                    "Upstream client key not found: {}",
-                    key_path.display()
-                )));
-            }
-            None => {
-                return Err(TlsError::ConfigBuild(
-                    "client_cert specified without client_key".to_string(),
-                ));
-            }
-            _ => {}
+    // Secrets from the environment must be set
+    let env_vars = [
+        (
+            &config.ca_cert_env,
+            TlsError::CertificateLoad as fn(String) -> TlsError,
+        ),
+        (&config.client_cert_env, TlsError::CertificateLoad),
+        (&config.client_key_env, TlsError::KeyLoad),
+    ];
+    for (var, error) in env_vars {
+        let Some(var) = var else {
+            continue;
+        };
+        if std::env::var_os(var).is_none() {
+            return Err(error(format!(
+                "Upstream TLS environment variable not set: {}",
+                var
+            )));
         }
     }
 
-    if config.client_key.is_some() && config.client_cert.is_none() {
+    // If a cert is specified, a key must also be specified
+    if config.has_client_cert() && !config.has_client_key() {
+        return Err(TlsError::ConfigBuild(
+            "client_cert specified without client_key".to_string(),
+        ));
+    }
+
+    if config.has_client_key() && !config.has_client_cert() {
         return Err(TlsError::ConfigBuild(
             "client_key specified without client_cert".to_string(),
         ));
@@ -1311,20 +1940,7 @@ pub fn validate_upstream_tls_config(config: &UpstreamTlsConfig) -> Result<(), Tl
 /// Load a certificate chain and private key from files
 fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, TlsError> {
     // Load certificate chain
-    let cert_file = File::open(cert_path)
-        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
-    let mut cert_reader = BufReader::new(cert_file);
-
-    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut cert_reader)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
-
-    if certs.is_empty() {
-        return Err(TlsError::CertificateLoad(format!(
-            "{}: No certificates found in file",
-            cert_path.display()
-        )));
-    }
+    let certs = load_certificate_chain(cert_path)?;
 
     // Load private key
     let key_file = File::open(key_path)
@@ -1350,7 +1966,112 @@ fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey,
         .load_private_key(key)
         .map_err(|e| TlsError::CertKeyMismatch(format!("Failed to load private key: {:?}", e)))?;
 
-    Ok(CertifiedKey::new(certs, signing_key))
+    let certified_key = CertifiedKey::new(certs, signing_key);
+
+    // Reject a certificate paired with the wrong key (e.g. a half-written
+    // rotation) instead of failing every handshake
+    if let Err(rustls::Error::InconsistentKeys(rustls::InconsistentKeys::KeyMismatch)) =
+        certified_key.keys_match()
+    {
+        return Err(TlsError::CertKeyMismatch(format!(
+            "{} does not match the public key in {}",
+            key_path.display(),
+            cert_path.display()
+        )));
+    }
+
+    Ok(certified_key)
+}
+
+/// Load the PEM certificate chain from a file (leaf first)
+fn load_certificate_chain(cert_path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let cert_file = File::open(cert_path)
+        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
+    let mut cert_reader = BufReader::new(cert_file);
+
+    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut cert_reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::CertificateLoad(format!("{}: {}", cert_path.display(), e)))?;
+
+    if certs.is_empty() {
+        return Err(TlsError::CertificateLoad(format!(
+            "{}: No certificates found in file",
+            cert_path.display()
+        )));
+    }
+
+    Ok(certs)
+}
+
+/// Certificate files served by a TLS config (default and SNI)
+///
+/// ACME-managed certificates resolve to their storage paths.
+fn certificate_files(config: &TlsConfig) -> Vec<std::path::PathBuf> {
+    let acme_cert_path = |acme: &zentinel_config::server::AcmeConfig| {
+        acme.domains
+            .first()
+            .map(|primary| acme.storage.join("domains").join(primary).join("cert.pem"))
+    };
+
+    let default = config
+        .cert_file
+        .clone()
+        .or_else(|| config.acme.as_ref().and_then(acme_cert_path));
+    let sni = config.additional_certs.iter().filter_map(|sni| {
+        sni.cert_file
+            .clone()
+            .or_else(|| sni.acme.as_ref().and_then(acme_cert_path))
+    });
+
+    default.into_iter().chain(sni).collect()
+}
+
+/// Manually configured certificate and key files (default and SNI)
+fn manual_certificate_files(config: &TlsConfig) -> Vec<std::path::PathBuf> {
+    let default = [&config.cert_file, &config.key_file];
+    let sni = config
+        .additional_certs
+        .iter()
+        .flat_map(|sni| [&sni.cert_file, &sni.key_file]);
+
+    default.into_iter().chain(sni).flatten().cloned().collect()
+}
+
+/// Content hash over a set of files; missing files hash as empty
+fn files_signature(files: &[std::path::PathBuf]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let contents = std::fs::read(file).unwrap_or_default();
+        hasher.update((contents.len() as u64).to_be_bytes());
+        hasher.update(&contents);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// `not_after` (Unix seconds) of a certified key's leaf certificate
+fn certificate_not_after(key: &CertifiedKey) -> Option<i64> {
+    use x509_parser::prelude::*;
+
+    let (_, cert) = X509Certificate::from_der(key.cert.first()?).ok()?;
+    Some(cert.validity().not_after.timestamp())
+}
+
+/// Export the `not_after` of each certificate a listener serves, and track
+/// it for expiry notifications
+fn record_certificate_expiry(listener_id: &str, resolver: &SniResolver) {
+    let metrics = crate::tls_metrics::get_tls_metrics();
+
+    for (certificate, not_after) in resolver.certificate_expiry() {
+        if let Some(metrics) = metrics.as_ref() {
+            metrics.set_cert_not_after(listener_id, certificate, *not_after);
+        }
+        crate::notifications::certificate_loaded(
+            &format!("{}/{}", listener_id, certificate),
+            *not_after,
+        );
+    }
 }
 
 /// Extract DNS hostnames from a certificate's CN and Subject Alternative Names.
@@ -1526,25 +2247,16 @@ fn resolve_cipher_suites(names: &[String]) -> Result<Vec<rustls::SupportedCipher
     Ok(suites)
 }
 
-/// Build a TLS ServerConfig from our configuration.
-///
-/// Applies protocol versions, cipher suites, session resumption, mTLS,
-/// and SNI certificate resolution from the Zentinel TLS config.
-///
-/// # Note
+/// Build the TLS ServerConfig of an HTTPS listener.
 ///
-/// This ServerConfig is fully configured but currently not used by
-/// Pingora's listener infrastructure. Pingora's rustls `TlsSettings`
-/// builds its own `ServerConfig` internally with hardcoded defaults.
-/// A future update to the Pingora fork should accept a pre-built
-/// `ServerConfig` via `TlsSettings`, at which point this function's
-/// output will be wired into the listener setup.
+/// Applies protocol versions, cipher suites, session resumption and mTLS
+/// from the Zentinel TLS config. Certificates are served from `resolver`,
+/// so hot reloads, ACME renewals and OCSP staples reach new connections
+/// immediately.
 pub fn build_server_config(
     config: &TlsConfig,
-    listener_id: &str,
+    resolver: Arc<HotReloadableSniResolver>,
 ) -> Result<ServerConfig, TlsError> {
-    let resolver = SniResolver::from_config(config, Some(listener_id))?;
-
     // Resolve protocol versions from config
     let versions = resolve_protocol_versions(config);
     info!(
@@ -1574,34 +2286,13 @@ pub fn build_server_config(
     };
 
     // Configure client authentication (mTLS)
-    let server_config = if config.client_auth {
-        if let Some(ca_path) = &config.ca_file {
-            let root_store = load_client_ca(ca_path)?;
-            let verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(root_store))
-                .build()
-                .map_err(|e| {
-                    TlsError::ConfigBuild(format!("Failed to build client verifier: {}", e))
-                })?;
-
-            info!("mTLS enabled: client certificates required");
-
-            builder
-                .with_client_cert_verifier(verifier)
-                .with_cert_resolver(Arc::new(resolver))
-        } else {
-            warn!("client_auth enabled but no ca_file specified, disabling client auth");
-            builder
-                .with_no_client_auth()
-                .with_cert_resolver(Arc::new(resolver))
-        }
-    } else {
-        builder
-            .with_no_client_auth()
-            .with_cert_resolver(Arc::new(resolver))
+    let builder = match client_cert_verifier(config)? {
+        Some(verifier) => builder.with_client_cert_verifier(verifier),
+        None => builder.with_no_client_auth(),
     };
+    let mut server_config = builder.with_cert_resolver(resolver);
 
     // Configure ALPN for HTTP/2 support
-    let mut server_config = server_config;
     server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
 
     // Disable session resumption if configured
@@ -1615,6 +2306,66 @@ pub fn build_server_config(
     Ok(server_config)
 }
 
+/// Build a TLS 1.3 ServerConfig for an HTTP/3 (QUIC) listener.
+///
+/// Certificates are served from `resolver`, so hot reloads, ACME renewals
+/// and OCSP staples reach HTTP/3 clients immediately. Configured cipher
+/// suites are applied where they are TLS 1.3 suites; QUIC has no TLS 1.2.
+pub fn build_quic_server_config(
+    config: &TlsConfig,
+    resolver: Arc<HotReloadableSniResolver>,
+) -> Result<ServerConfig, TlsError> {
+    let mut provider = rustls::crypto::aws_lc_rs::default_provider();
+    if !config.cipher_suites.is_empty() {
+        provider.cipher_suites = resolve_cipher_suites(&config.cipher_suites)?
+            .into_iter()
+            .filter(|suite| suite.version() == &rustls::version::TLS13)
+            .collect();
+        if provider.cipher_suites.is_empty() {
+            return Err(TlsError::ConfigBuild(
+                "No TLS 1.3 cipher suites configured for HTTP/3 listener".to_string(),
+            ));
+        }
+    }
+
+    let builder = ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| TlsError::ConfigBuild(format!("Invalid QUIC TLS configuration: {}", e)))?;
+    let builder = match client_cert_verifier(config)? {
+        Some(verifier) => builder.with_client_cert_verifier(verifier),
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder.with_cert_resolver(resolver);
+    server_config.alpn_protocols = vec![b"h3".to_vec()];
+
+    if !config.session_resumption {
+        server_config.session_storage = Arc::new(rustls::server::NoServerSessionStorage {});
+    }
+
+    Ok(server_config)
+}
+
+/// Client certificate verifier for an mTLS listener (`None` = no client auth).
+fn client_cert_verifier(
+    config: &TlsConfig,
+) -> Result<Option<Arc<dyn rustls::server::danger::ClientCertVerifier>>, TlsError> {
+    if !config.client_auth {
+        return Ok(None);
+    }
+    let Some(ca_path) = &config.ca_file else {
+        warn!("client_auth enabled but no ca_file specified, disabling client auth");
+        return Ok(None);
+    };
+
+    let root_store = load_client_ca(ca_path)?;
+    let verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(root_store))
+        .build()
+        .map_err(|e| TlsError::ConfigBuild(format!("Failed to build client verifier: {}", e)))?;
+
+    info!("mTLS enabled: client certificates required");
+    Ok(Some(verifier))
+}
+
 /// Validate TLS configuration files exist and are readable
 pub fn validate_tls_config(config: &TlsConfig) -> Result<(), TlsError> {
     // If ACME is configured, skip manual cert file validation
@@ -1690,11 +2441,28 @@ pub fn validate_tls_config(config: &TlsConfig) -> Result<(), TlsError> {
         }
     }
 
+    // Check client certificate forwarding header
+    if let Some(header) = &config.client_cert_header {
+        if http::header::HeaderName::from_bytes(header.as_bytes()).is_err() {
+            return Err(TlsError::ConfigBuild(format!(
+                "Invalid client-cert-header name: '{}'",
+                header
+            )));
+        }
+        if !config.client_auth {
+            warn!(
+                header = %header,
+                "client-cert-header is set but client-auth is disabled; no certificate will be forwarded"
+            );
+        }
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_wildcard_matching() {
@@ -1719,4 +2487,265 @@ mod tests {
         let normalized = hostname.to_lowercase();
         assert_eq!(normalized, "example.com");
     }
+
+    #[test]
+    fn test_client_certificate_info() {
+        let mut params =
+            rcgen::CertificateParams::new(vec!["client.example.com".to_string()]).unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "client-1");
+        params
+            .subject_alt_names
+            .push(rcgen::SanType::IpAddress("10.0.0.7".parse().unwrap()));
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        let der = cert.der().to_vec();
+
+        let info = client_certificate_info(&der, true).unwrap();
+        assert_eq!(info.subject.as_deref(), Some("CN=client-1"));
+        assert_eq!(info.sans, vec!["DNS:client.example.com", "IP:10.0.0.7"]);
+        assert_eq!(info.fingerprint_sha256, calculate_cert_fingerprint(&der));
+        assert_eq!(info.fingerprint_sha256.len(), 64);
+        assert!(info.serial_number.is_some());
+        assert!(info.verified);
+
+        assert!(client_certificate_info(b"not a certificate", true).is_none());
+    }
+
+    #[test]
+    fn test_certificate_pem_roundtrip() {
+        let cert = rcgen::generate_simple_self_signed(vec!["client.example.com".to_string()])
+            .unwrap()
+            .cert;
+        let pem = certificate_pem(cert.der());
+
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+        assert!(pem.ends_with("-----END CERTIFICATE-----\n"));
+        assert!(pem.lines().all(|line| line.len() <= 64));
+
+        let parsed: Vec<_> = rustls_pemfile::certs(&mut pem.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].as_ref(), cert.der().as_ref());
+    }
+
+    fn fixture_tls_config() -> TlsConfig {
+        let fixtures =
+            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/tls");
+        TlsConfig {
+            cert_file: Some(fixtures.join("server-default.crt")),
+            key_file: Some(fixtures.join("server-default.key")),
+            additional_certs: vec![],
+            ca_file: None,
+            min_version: zentinel_common::types::TlsVersion::Tls12,
+            max_version: None,
+            cipher_suites: vec![],
+            client_auth: false,
+            client_cert_header: None,
+            ocsp_stapling: true,
+            session_resumption: true,
+            acme: None,
+        }
+    }
+
+    #[test]
+    fn test_staple_ocsp_from_cache() {
+        let config = fixture_tls_config();
+        let mut resolver = SniResolver::from_config(&config, Some("test")).unwrap();
+        let stapler = OcspStapler::new();
+
+        // Nothing cached: certificates are served without a staple
+        assert_eq!(resolver.staple_ocsp(&stapler), 0);
+        assert!(resolver.resolve(None).ocsp.is_none());
+
+        let leaf = resolver.resolve(None).cert[0].clone();
+        stapler.cache.write().insert(
+            calculate_cert_fingerprint(&leaf),
+            OcspCacheEntry {
+                response: vec![0x30, 0x03, 0x0a, 0x01, 0x00],
+                fetched_at: Instant::now(),
+                expires_at: None,
+            },
+        );
+
+        assert_eq!(resolver.staple_ocsp(&stapler), 1);
+        assert_eq!(
+            resolver
+                .resolve(Some("unknown.example.com"))
+                .ocsp
+                .as_deref(),
+            Some(&[0x30, 0x03, 0x0a, 0x01, 0x00][..])
+        );
+    }
+
+    #[test]
+    fn test_hot_reload_keeps_ocsp_staple() {
+        let stapler = Arc::new(OcspStapler::new());
+        let resolver = HotReloadableSniResolver::from_config(fixture_tls_config(), "test")
+            .unwrap()
+            .with_ocsp_stapler(Arc::clone(&stapler));
+        assert!(resolver.resolve(None).ocsp.is_none());
+
+        let leaf = resolver.resolve(None).cert[0].clone();
+        stapler.cache.write().insert(
+            calculate_cert_fingerprint(&leaf),
+            OcspCacheEntry {
+                response: vec![0x30, 0x00],
+                fetched_at: Instant::now(),
+                expires_at: None,
+            },
+        );
+
+        // Reloading (e.g. after renewal) staples the cached response
+        resolver.reload().unwrap();
+        assert_eq!(
+            resolver.resolve(None).ocsp.as_deref(),
+            Some(&[0x30, 0x00][..])
+        );
+    }
+
+    #[test]
+    fn test_certificate_files_resolves_acme_paths() {
+        let mut config = fixture_tls_config();
+        config.cert_file = None;
+        config.acme = Some(zentinel_config::server::AcmeConfig {
+            email: "admin@example.com".to_string(),
+            domains: vec!["example.com".to_string()],
+            server_url: None,
+            staging: false,
+            eab: None,
+            storage: std::path::PathBuf::from("/var/lib/zentinel/acme"),
+            renew_before_days: 30,
+            challenge_type: Default::default(),
+            key_type: Default::default(),
+            dns_provider: None,
+        });
+
+        assert_eq!(
+            certificate_files(&config),
+            vec![std::path::PathBuf::from(
+                "/var/lib/zentinel/acme/domains/example.com/cert.pem"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_reload_if_changed_validates_pair() {
+        let fixtures = fixture_tls_config();
+        let default_cert = fixtures.cert_file.unwrap();
+        let fixtures_dir = default_cert.parent().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("tls.crt");
+        let key_path = dir.path().join("tls.key");
+        std::fs::copy(&default_cert, &cert_path).unwrap();
+        std::fs::copy(fixtures_dir.join("server-default.key"), &key_path).unwrap();
+
+        let mut config = fixture_tls_config();
+        config.cert_file = Some(cert_path.clone());
+        config.key_file = Some(key_path.clone());
+        let resolver = HotReloadableSniResolver::from_config(config, "test").unwrap();
+        let original = resolver.resolve(None).cert[0].clone();
+        let original_expiry = resolver.inner.read().certificate_expiry().to_vec();
+        assert_eq!(original_expiry.len(), 1);
+        assert_eq!(original_expiry[0].0, cert_path.display().to_string());
+
+        assert_eq!(
+            resolver.watched_files(),
+            vec![cert_path.clone(), key_path.clone()]
+        );
+        assert!(!resolver.reload_if_changed().unwrap());
+
+        // Certificate rotated before its key: rejected, old pair kept
+        std::fs::copy(fixtures_dir.join("server-api.crt"), &cert_path).unwrap();
+        assert!(matches!(
+            resolver.reload_if_changed(),
+            Err(TlsError::CertKeyMismatch(_))
+        ));
+        assert_eq!(resolver.resolve(None).cert[0], original);
+        // Expiry is reported for the certificate still served, not the file
+        assert_eq!(resolver.inner.read().certificate_expiry(), original_expiry);
+
+        // Key arrives: the new pair is swapped in
+        std::fs::copy(fixtures_dir.join("server-api.key"), &key_path).unwrap();
+        assert!(resolver.reload_if_changed().unwrap());
+        assert_ne!(resolver.resolve(None).cert[0], original);
+        assert_eq!(
+            resolver.inner.read().certificate_expiry()[0].1,
+            certificate_not_after(&resolver.resolve(None)).unwrap()
+        );
+        assert!(!resolver.reload_if_changed().unwrap());
+    }
+
+    #[test]
+    fn test_spiffe_id_matching() {
+        let mut params = rcgen::CertificateParams::new(vec![]).unwrap();
+        params.subject_alt_names.push(rcgen::SanType::URI(
+            "spiffe://example.org/ns/prod/sa/api".try_into().unwrap(),
+        ));
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+
+        let ids = certificate_spiffe_ids(cert.der());
+        assert_eq!(ids, vec!["spiffe://example.org/ns/prod/sa/api"]);
+
+        let id = &ids[0];
+        assert!(spiffe_id_allowed(
+            id,
+            &["spiffe://example.org/ns/prod/sa/api".to_string()]
+        ));
+        assert!(spiffe_id_allowed(id, &["spiffe://example.org".to_string()]));
+        assert!(!spiffe_id_allowed(id, &["spiffe://example.or".to_string()]));
+        assert!(!spiffe_id_allowed(
+            id,
+            &["spiffe://example.org/ns/prod".to_string()]
+        ));
+        assert!(certificate_spiffe_ids(b"not a certificate").is_empty());
+    }
+
+    #[test]
+    fn test_upstream_secrets_reload_changed_files() {
+        let fixtures =
+            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/tls");
+        let dir = tempfile::tempdir().unwrap();
+        let ca_path = dir.path().join("ca.crt");
+        std::fs::copy(fixtures.join("ca.crt"), &ca_path).unwrap();
+
+        let config = UpstreamTlsConfig {
+            ca_cert: Some(ca_path.clone()),
+            client_cert: Some(fixtures.join("client.crt")),
+            client_key: Some(fixtures.join("client.key")),
+            ..Default::default()
+        };
+        let secrets = UpstreamTlsSecrets::load("backend", &config).unwrap();
+        let original = secrets.current();
+        assert_eq!(original.ca.as_ref().unwrap().len(), 1);
+        assert!(original.client_cert_key.is_some());
+
+        // A broken rotation keeps the previous bundle
+        std::fs::write(&ca_path, "not a certificate").unwrap();
+        let later = Instant::now() + UPSTREAM_SECRETS_CHECK_INTERVAL;
+        secrets.reload_if_due(later);
+        assert_eq!(secrets.loaded.read().ca.as_ref().unwrap().len(), 1);
+
+        // A valid rotation is picked up on the next check
+        let bundle = [
+            std::fs::read(fixtures.join("ca.crt")).unwrap(),
+            std::fs::read(fixtures.join("untrusted.crt")).unwrap(),
+        ]
+        .concat();
+        std::fs::write(&ca_path, bundle).unwrap();
+        secrets.reload_if_due(later + UPSTREAM_SECRETS_CHECK_INTERVAL);
+        assert_eq!(secrets.loaded.read().ca.as_ref().unwrap().len(), 2);
+
+        assert!(UpstreamTlsSecrets::load(
+            "backend",
+            &UpstreamTlsConfig {
+                ca_cert_env: Some("ZENTINEL_TEST_UNSET_UPSTREAM_CA".to_string()),
+                ..Default::default()
+            }
+        )
+        .is_err());
+    }
 }
@@ -1,11 +1,12 @@
 //! TLS-related Prometheus metrics.
 //!
 //! Provides metrics for tracking certificate status, resolution,
-//! and SNI cold-start events.
+//! SNI cold-start events, certificate reloads, ACME renewals and OCSP
+//! stapling.
 
 use anyhow::{Context, Result};
 use once_cell::sync::OnceCell;
-use prometheus::{register_int_counter_vec, IntCounterVec};
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
 use std::sync::Arc;
 
 /// Global TLS metrics instance.
@@ -32,6 +33,24 @@ pub struct TlsMetrics {
     /// Number of SNI certificates skipped at startup due to missing files (ACME)
     /// Labels: listener, primary_domain
     sni_certs_skipped_total: IntCounterVec,
+    /// Certificate reloads (file change, SIGHUP, config reload, renewal)
+    /// Labels: listener, result (success, failure)
+    cert_reloads_total: IntCounterVec,
+    /// `not_after` of each active certificate as a Unix timestamp
+    /// Labels: listener, certificate (file path)
+    cert_not_after_timestamp_seconds: IntGaugeVec,
+    /// ACME certificate renewal attempts
+    /// Labels: domain, result (success, failure)
+    cert_renewals_total: IntCounterVec,
+    /// Consecutive failed renewal attempts (0 after a success); alert on > 0
+    /// Labels: domain
+    cert_renewal_consecutive_failures: IntGaugeVec,
+    /// Certificate expiry as a Unix timestamp
+    /// Labels: domain
+    cert_expiry_timestamp_seconds: IntGaugeVec,
+    /// OCSP response refreshes
+    /// Labels: listener, result (success, failure)
+    ocsp_refreshes_total: IntCounterVec,
 }
 
 impl TlsMetrics {
@@ -44,8 +63,56 @@ impl TlsMetrics {
         )
         .context("Failed to register zentinel_tls_sni_certs_skipped_total metric")?;
 
+        let cert_reloads_total = register_int_counter_vec!(
+            "zentinel_tls_cert_reloads_total",
+            "Total number of TLS certificate reloads",
+            &["listener", "result"]
+        )
+        .context("Failed to register zentinel_tls_cert_reloads_total metric")?;
+
+        let cert_not_after_timestamp_seconds = register_int_gauge_vec!(
+            "zentinel_tls_cert_not_after_timestamp_seconds",
+            "Expiry (not_after) of active TLS certificates as a Unix timestamp",
+            &["listener", "certificate"]
+        )
+        .context("Failed to register zentinel_tls_cert_not_after_timestamp_seconds metric")?;
+
+        let cert_renewals_total = register_int_counter_vec!(
+            "zentinel_tls_cert_renewals_total",
+            "Total number of ACME certificate renewal attempts",
+            &["domain", "result"]
+        )
+        .context("Failed to register zentinel_tls_cert_renewals_total metric")?;
+
+        let cert_renewal_consecutive_failures = register_int_gauge_vec!(
+            "zentinel_tls_cert_renewal_consecutive_failures",
+            "Number of consecutive failed ACME renewal attempts (0 after a successful renewal)",
+            &["domain"]
+        )
+        .context("Failed to register zentinel_tls_cert_renewal_consecutive_failures metric")?;
+
+        let cert_expiry_timestamp_seconds = register_int_gauge_vec!(
+            "zentinel_tls_cert_expiry_timestamp_seconds",
+            "Expiry time of managed TLS certificates as a Unix timestamp",
+            &["domain"]
+        )
+        .context("Failed to register zentinel_tls_cert_expiry_timestamp_seconds metric")?;
+
+        let ocsp_refreshes_total = register_int_counter_vec!(
+            "zentinel_tls_ocsp_refreshes_total",
+            "Total number of OCSP response refreshes for stapling",
+            &["listener", "result"]
+        )
+        .context("Failed to register zentinel_tls_ocsp_refreshes_total metric")?;
+
         Ok(Self {
             sni_certs_skipped_total,
+            cert_reloads_total,
+            cert_not_after_timestamp_seconds,
+            cert_renewals_total,
+            cert_renewal_consecutive_failures,
+            cert_expiry_timestamp_seconds,
+            ocsp_refreshes_total,
         })
     }
 
@@ -55,4 +122,51 @@ impl TlsMetrics {
             .with_label_values(&[listener_id, primary_domain])
             .inc();
     }
+
+    /// Record a certificate reload for a listener.
+    pub fn record_cert_reload(&self, listener_id: &str, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.cert_reloads_total
+            .with_label_values(&[listener_id, result])
+            .inc();
+    }
+
+    /// Set the `not_after` of a certificate served by a listener.
+    pub fn set_cert_not_after(&self, listener_id: &str, certificate: &str, not_after: i64) {
+        self.cert_not_after_timestamp_seconds
+            .with_label_values(&[listener_id, certificate])
+            .set(not_after);
+    }
+
+    /// Record an ACME renewal attempt.
+    pub fn record_cert_renewal(&self, domain: &str, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.cert_renewals_total
+            .with_label_values(&[domain, result])
+            .inc();
+
+        let failures = self
+            .cert_renewal_consecutive_failures
+            .with_label_values(&[domain]);
+        if success {
+            failures.set(0);
+        } else {
+            failures.inc();
+        }
+    }
+
+    /// Set a managed certificate's expiry time.
+    pub fn set_cert_expiry(&self, domain: &str, expires_unix_secs: i64) {
+        self.cert_expiry_timestamp_seconds
+            .with_label_values(&[domain])
+            .set(expires_unix_secs);
+    }
+
+    /// Record an OCSP refresh for a listener.
+    pub fn record_ocsp_refresh(&self, listener_id: &str, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.ocsp_refreshes_total
+            .with_label_values(&[listener_id, result])
+            .inc();
+    }
 }
@@ -49,7 +49,7 @@
 //!
 //! ```kdl
 //! server {
-//!     trace-id-format "tinyflake"  // default, or "uuid"
+//!     trace-id-format "tinyflake"  // default, or "uuid", "uuidv7", "ulid", "hex"
 //! }
 //! ```
 //!
@@ -72,28 +72,122 @@
 //!
 //! # Header Propagation
 //!
-//! TinyFlake respects incoming trace headers in this order:
+//! Incoming IDs are trusted by default, checked in this order:
 //! 1. `X-Trace-Id`
 //! 2. `X-Correlation-Id`
 //! 3. `X-Request-Id`
 //!
 //! If an incoming request has any of these headers, that value is used instead of
-//! generating a new ID. This allows distributed tracing across services.
+//! generating a new ID. This allows distributed tracing across services. The
+//! `server { request-id }` block selects a single header instead, turns trust
+//! off, and names the headers that carry the ID upstream and back to the
+//! client; see [`resolve_request_id`].
 
+use std::sync::LazyLock;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use http::HeaderMap;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use zentinel_config::RequestIdConfig;
+
 // Re-export TraceIdFormat from zentinel_common for convenience
 pub use zentinel_common::TraceIdFormat;
 
+/// Request IDs by source
+static REQUEST_IDS: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_request_ids_total",
+        "Request IDs taken from the client or generated, by source",
+        &["source"]
+    )
+    .ok()
+});
+
+/// Headers checked for an incoming ID when no header is configured
+pub const DEFAULT_INCOMING_HEADERS: [&str; 3] = ["x-trace-id", "x-correlation-id", "x-request-id"];
+
 /// Generate a trace ID using the specified format
 #[inline]
 pub fn generate_for_format(format: TraceIdFormat) -> String {
     match format {
         TraceIdFormat::TinyFlake => generate_tinyflake(),
         TraceIdFormat::Uuid => generate_uuid(),
+        TraceIdFormat::UuidV7 => generate_uuid_v7(),
+        TraceIdFormat::Ulid => generate_ulid(),
+        TraceIdFormat::Hex => generate_hex(),
     }
 }
 
+// ============================================================================
+// Request ID Policy
+// ============================================================================
+
+/// Where a request's ID came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestIdSource {
+    /// Taken from the incoming header
+    Incoming,
+    /// Generated; no usable incoming ID, or incoming IDs are not trusted
+    Generated,
+    /// Generated because the incoming ID was malformed or too long
+    Rejected,
+}
+
+impl RequestIdSource {
+    /// Metric label for this source
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incoming => "incoming",
+            Self::Generated => "generated",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Pick the ID for a request according to the request ID policy.
+///
+/// A trusted incoming ID is used when it is at most `max-length` visible
+/// ASCII characters; anything else is replaced by a new ID in `format`, so
+/// clients cannot inject whitespace or control characters into logs and
+/// upstream headers.
+pub fn resolve_request_id(
+    headers: &HeaderMap,
+    format: TraceIdFormat,
+    config: &RequestIdConfig,
+) -> (String, RequestIdSource) {
+    let incoming = if !config.trust_incoming {
+        None
+    } else if let Some(ref name) = config.header {
+        headers.get(name.as_str())
+    } else {
+        DEFAULT_INCOMING_HEADERS
+            .iter()
+            .find_map(|name| headers.get(*name).filter(|v| !v.is_empty()))
+    };
+
+    let source = match incoming {
+        Some(value) if is_valid_request_id(value.as_bytes(), config.max_length) => {
+            // Visible ASCII is always valid UTF-8
+            let id = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            return (id, RequestIdSource::Incoming);
+        }
+        Some(value) if !value.is_empty() => RequestIdSource::Rejected,
+        _ => RequestIdSource::Generated,
+    };
+    (generate_for_format(format), source)
+}
+
+/// Count a resolved request ID.
+pub fn record_request_id(source: RequestIdSource) {
+    if let Some(counter) = REQUEST_IDS.as_ref() {
+        counter.with_label_values(&[source.as_str()]).inc();
+    }
+}
+
+fn is_valid_request_id(id: &[u8], max_length: usize) -> bool {
+    !id.is_empty() && id.len() <= max_length && id.iter().all(|b| (0x21..=0x7e).contains(b))
+}
+
 // ============================================================================
 // TinyFlake Generation
 // ============================================================================
@@ -199,6 +293,52 @@ pub fn generate_uuid() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
+/// Generate a UUID v7 trace ID
+///
+/// Format: 36 characters with dashes; the first 48 bits are a Unix
+/// millisecond timestamp, so IDs sort by creation time.
+pub fn generate_uuid_v7() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+// ============================================================================
+// ULID and Hex Generation
+// ============================================================================
+
+/// Crockford Base32 alphabet used by ULIDs (no `I`, `L`, `O`, `U`)
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// ULID length in characters
+pub const ULID_LENGTH: usize = 26;
+
+/// Generate a ULID trace ID
+///
+/// Format: 26 characters, Crockford Base32 encoding of a 48-bit Unix
+/// millisecond timestamp followed by 80 random bits.
+pub fn generate_ulid() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u128;
+    let random = u128::from_le_bytes(rand::random::<[u8; 16]>()) & ((1 << 80) - 1);
+    encode_ulid(((millis & ((1 << 48) - 1)) << 80) | random)
+}
+
+fn encode_ulid(value: u128) -> String {
+    // 26 chars x 5 bits = 130 bits; the top two bits are always zero
+    (0..ULID_LENGTH)
+        .rev()
+        .map(|i| CROCKFORD_ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// Generate a random hex trace ID
+///
+/// Format: 32 lowercase hex characters (128 random bits).
+pub fn generate_hex() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -298,6 +438,60 @@ mod tests {
 
         let uuid = generate_for_format(TraceIdFormat::Uuid);
         assert_eq!(uuid.len(), 36);
+
+        let uuid_v7 = generate_for_format(TraceIdFormat::UuidV7);
+        assert_eq!(
+            uuid::Uuid::parse_str(&uuid_v7).unwrap().get_version_num(),
+            7
+        );
+
+        let ulid = generate_for_format(TraceIdFormat::Ulid);
+        assert_eq!(ulid.len(), ULID_LENGTH);
+        assert!(ulid.bytes().all(|b| CROCKFORD_ALPHABET.contains(&b)));
+
+        let hex = generate_for_format(TraceIdFormat::Hex);
+        assert_eq!(hex.len(), 32);
+        assert!(hex.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_encode_ulid() {
+        assert_eq!(encode_ulid(0), "00000000000000000000000000");
+        // Largest valid ULID
+        assert_eq!(encode_ulid(u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_resolve_request_id() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-correlation-id", "abc-123".parse().unwrap());
+        let config = RequestIdConfig::default();
+
+        assert_eq!(
+            resolve_request_id(&headers, TraceIdFormat::Hex, &config),
+            ("abc-123".to_string(), RequestIdSource::Incoming)
+        );
+
+        // A configured header replaces the default list
+        let config = RequestIdConfig {
+            header: Some("X-Request-Id".to_string()),
+            ..Default::default()
+        };
+        let (id, source) = resolve_request_id(&headers, TraceIdFormat::Hex, &config);
+        assert_eq!(source, RequestIdSource::Generated);
+        assert_eq!(id.len(), 32);
+
+        headers.insert("x-request-id", "has space".parse().unwrap());
+        let (_, source) = resolve_request_id(&headers, TraceIdFormat::Hex, &config);
+        assert_eq!(source, RequestIdSource::Rejected);
+
+        let config = RequestIdConfig {
+            trust_incoming: false,
+            ..Default::default()
+        };
+        let (id, source) = resolve_request_id(&headers, TraceIdFormat::Hex, &config);
+        assert_eq!(source, RequestIdSource::Generated);
+        assert_ne!(id, "abc-123");
     }
 
     #[test]
@@ -324,6 +518,7 @@ mod tests {
     fn test_trace_id_format_display() {
         assert_eq!(TraceIdFormat::TinyFlake.to_string(), "tinyflake");
         assert_eq!(TraceIdFormat::Uuid.to_string(), "uuid");
+        assert_eq!(TraceIdFormat::UuidV7.to_string(), "uuidv7");
     }
 
     #[test]
@@ -0,0 +1,572 @@
+//! Credentials attached to upstream requests
+//!
+//! Upstreams with an `auth` block get an `Authorization` header on every
+//! request, replacing any the client sent. Static bearer tokens and basic
+//! credentials are encoded once; OAuth2 client-credentials tokens are fetched
+//! on first use, cached, and refreshed shortly before they expire.
+//!
+//! A failed refresh keeps using the cached token while it is still valid and
+//! retries after a short backoff. Requests fail with 502 only when there is
+//! no valid token at all.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use dashmap::DashMap;
+use http::HeaderValue;
+use parking_lot::RwLock;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use zentinel_config::{OAuth2ClientAuth, UpstreamAuthConfig};
+
+/// Lifetime assumed for tokens issued without `expires_in`
+const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(300);
+
+/// Wait before retrying a failed token request
+const REFRESH_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// OAuth2 token requests per upstream and outcome
+static TOKEN_REFRESHES: LazyLock<Option<IntCounterVec>> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "zentinel_upstream_auth_token_refreshes_total",
+        "OAuth2 token requests for upstream credentials, by outcome",
+        &["upstream", "outcome"]
+    )
+    .ok()
+});
+
+fn record_refresh(upstream_id: &str, outcome: &str) {
+    if let Some(metric) = TOKEN_REFRESHES.as_ref() {
+        metric.with_label_values(&[upstream_id, outcome]).inc();
+    }
+}
+
+/// Errors creating or obtaining upstream credentials
+#[derive(Debug, Error)]
+pub enum UpstreamAuthError {
+    /// The credential cannot be sent as a header value
+    #[error("credentials for upstream '{0}' contain characters not allowed in a header")]
+    InvalidHeader(String),
+
+    /// The HTTP client for the token endpoint could not be built
+    #[error("failed to create OAuth2 client: {0}")]
+    Client(#[source] reqwest::Error),
+
+    /// The token endpoint could not be reached
+    #[error("token request to '{url}' failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The token endpoint answered with an error status
+    #[error("token endpoint '{url}' returned HTTP {status}")]
+    Status { url: String, status: u16 },
+
+    /// The token endpoint's answer is not a usable token
+    #[error("invalid token response from '{url}': {message}")]
+    Response { url: String, message: String },
+
+    /// An earlier token request failed and the retry backoff has not passed
+    #[error("no valid token for upstream '{0}' (waiting to retry the token request)")]
+    Backoff(String),
+}
+
+/// Credential of one upstream
+pub enum UpstreamCredential {
+    /// Precomputed `Authorization` value (bearer token or basic credentials)
+    Static(HeaderValue),
+    /// OAuth2 client-credentials token
+    OAuth2(OAuth2TokenSource),
+}
+
+impl UpstreamCredential {
+    /// Create the credential for an upstream's `auth` configuration
+    pub fn new(upstream_id: &str, config: &UpstreamAuthConfig) -> Result<Self, UpstreamAuthError> {
+        let value = match config {
+            UpstreamAuthConfig::Bearer { token } => format!("Bearer {}", token),
+            UpstreamAuthConfig::Basic { username, password } => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, password))
+            ),
+            UpstreamAuthConfig::OAuth2 { .. } => {
+                return OAuth2TokenSource::new(upstream_id, config).map(Self::OAuth2)
+            }
+        };
+        Ok(Self::Static(sensitive_header(upstream_id, &value)?))
+    }
+
+    /// `Authorization` value for the next request
+    pub async fn authorization(&self) -> Result<HeaderValue, UpstreamAuthError> {
+        match self {
+            Self::Static(value) => Ok(value.clone()),
+            Self::OAuth2(source) => source.authorization().await,
+        }
+    }
+}
+
+fn sensitive_header(upstream_id: &str, value: &str) -> Result<HeaderValue, UpstreamAuthError> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| UpstreamAuthError::InvalidHeader(upstream_id.to_string()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+// =============================================================================
+// OAuth2 client credentials
+// =============================================================================
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Cached token and refresh bookkeeping
+#[derive(Default)]
+struct TokenState {
+    token: Option<CachedToken>,
+    /// No token requests before this instant (after a failure)
+    retry_at: Option<Instant>,
+}
+
+struct CachedToken {
+    value: HeaderValue,
+    /// Refreshed on first use after this instant
+    refresh_at: Instant,
+    /// Not used after this instant
+    expires_at: Instant,
+}
+
+/// OAuth2 client-credentials token source with caching
+pub struct OAuth2TokenSource {
+    upstream_id: String,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scopes: Vec<String>,
+    audience: Option<String>,
+    client_auth: OAuth2ClientAuth,
+    refresh_before: Duration,
+    client: reqwest::Client,
+    state: RwLock<TokenState>,
+    /// Serializes token requests so concurrent requests share one refresh
+    refresh: tokio::sync::Mutex<()>,
+}
+
+impl OAuth2TokenSource {
+    fn new(upstream_id: &str, config: &UpstreamAuthConfig) -> Result<Self, UpstreamAuthError> {
+        let UpstreamAuthConfig::OAuth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scopes,
+            audience,
+            client_auth,
+            refresh_before_secs,
+            timeout_secs,
+        } = config
+        else {
+            unreachable!("OAuth2TokenSource is only created for oauth2 auth");
+        };
+
+        let client = crate::outbound::client_builder()
+            .timeout(Duration::from_secs(*timeout_secs))
+            .build()
+            .map_err(UpstreamAuthError::Client)?;
+
+        Ok(Self {
+            upstream_id: upstream_id.to_string(),
+            token_url: token_url.clone(),
+            client_id: client_id.clone(),
+            client_secret: client_secret.clone(),
+            scopes: scopes.clone(),
+            audience: audience.clone(),
+            client_auth: *client_auth,
+            refresh_before: Duration::from_secs(*refresh_before_secs),
+            client,
+            state: RwLock::new(TokenState::default()),
+            refresh: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Cached token, refreshing it first if it is due
+    pub async fn authorization(&self) -> Result<HeaderValue, UpstreamAuthError> {
+        if let Some(value) = self.cached(Instant::now()) {
+            return Ok(value);
+        }
+
+        let _refresh = self.refresh.lock().await;
+        // Another request may have refreshed the token while we waited
+        let now = Instant::now();
+        if let Some(value) = self.cached(now) {
+            return Ok(value);
+        }
+        if self.state.read().retry_at.is_some_and(|at| now < at) {
+            return Err(UpstreamAuthError::Backoff(self.upstream_id.clone()));
+        }
+
+        match self.fetch().await {
+            Ok(response) => {
+                record_refresh(&self.upstream_id, "success");
+                let value = sensitive_header(
+                    &self.upstream_id,
+                    &format!("Bearer {}", response.access_token),
+                )?;
+                let lifetime = response
+                    .expires_in
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_TOKEN_LIFETIME);
+                // Short-lived tokens are refreshed halfway through instead
+                let margin = self.refresh_before.min(lifetime / 2);
+                let now = Instant::now();
+                *self.state.write() = TokenState {
+                    token: Some(CachedToken {
+                        value: value.clone(),
+                        refresh_at: now + lifetime - margin,
+                        expires_at: now + lifetime,
+                    }),
+                    retry_at: None,
+                };
+                debug!(
+                    upstream_id = %self.upstream_id,
+                    expires_in_secs = lifetime.as_secs(),
+                    "Fetched upstream OAuth2 token"
+                );
+                Ok(value)
+            }
+            Err(e) => {
+                record_refresh(&self.upstream_id, "failure");
+                let mut state = self.state.write();
+                state.retry_at = Some(now + REFRESH_RETRY_BACKOFF);
+                let fallback = state
+                    .token
+                    .as_ref()
+                    .filter(|token| now < token.expires_at)
+                    .map(|token| token.value.clone());
+                warn!(
+                    upstream_id = %self.upstream_id,
+                    error = %e,
+                    using_cached_token = fallback.is_some(),
+                    "Failed to refresh upstream OAuth2 token"
+                );
+                fallback.ok_or(e)
+            }
+        }
+    }
+
+    /// Cached token unless a refresh is due and allowed
+    ///
+    /// During the retry backoff a still-valid token is used as is; without
+    /// one, the request fails without contacting the token endpoint.
+    fn cached(&self, now: Instant) -> Option<HeaderValue> {
+        let state = self.state.read();
+        let token = state.token.as_ref().filter(|t| now < t.expires_at);
+        let backing_off = state.retry_at.is_some_and(|at| now < at);
+        match token {
+            Some(token) if now < token.refresh_at || backing_off => Some(token.value.clone()),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self) -> Result<TokenResponse, UpstreamAuthError> {
+        let mut form = vec![("grant_type", "client_credentials".to_string())];
+        if !self.scopes.is_empty() {
+            form.push(("scope", self.scopes.join(" ")));
+        }
+        if let Some(ref audience) = self.audience {
+            form.push(("audience", audience.clone()));
+        }
+        if self.client_auth == OAuth2ClientAuth::Body {
+            form.push(("client_id", self.client_id.clone()));
+            form.push(("client_secret", self.client_secret.clone()));
+        }
+        let body = form
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut request = self
+            .client
+            .post(&self.token_url)
+            .header(
+                http::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .header(http::header::ACCEPT, "application/json")
+            .body(body);
+        if self.client_auth == OAuth2ClientAuth::Basic {
+            // RFC 6749 section 2.3.1: form-encode before basic auth
+            request = request.basic_auth(
+                urlencoding::encode(&self.client_id),
+                Some(urlencoding::encode(&self.client_secret)),
+            );
+        }
+
+        let request_error = |source| UpstreamAuthError::Request {
+            url: self.token_url.clone(),
+            source,
+        };
+        let response = request.send().await.map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(UpstreamAuthError::Status {
+                url: self.token_url.clone(),
+                status: response.status().as_u16(),
+            });
+        }
+        let token: TokenResponse =
+            response
+                .json()
+                .await
+                .map_err(|e| UpstreamAuthError::Response {
+                    url: self.token_url.clone(),
+                    message: e.to_string(),
+                })?;
+        if token.access_token.is_empty() {
+            return Err(UpstreamAuthError::Response {
+                url: self.token_url.clone(),
+                message: "empty access_token".to_string(),
+            });
+        }
+        Ok(token)
+    }
+}
+
+// =============================================================================
+// UpstreamAuthManager
+// =============================================================================
+
+/// Credentials of all upstreams with an `auth` block
+pub struct UpstreamAuthManager {
+    /// Upstream ID → configuration and credential
+    credentials: DashMap<String, (UpstreamAuthConfig, Arc<UpstreamCredential>)>,
+}
+
+impl UpstreamAuthManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            credentials: DashMap::new(),
+        }
+    }
+
+    /// Create the credential for an upstream
+    pub fn register(
+        &self,
+        upstream_id: &str,
+        config: &UpstreamAuthConfig,
+    ) -> Result<(), UpstreamAuthError> {
+        let credential = UpstreamCredential::new(upstream_id, config)?;
+        self.credentials.insert(
+            upstream_id.to_string(),
+            (config.clone(), Arc::new(credential)),
+        );
+        Ok(())
+    }
+
+    /// Credential for an upstream, if it has one
+    pub fn get(&self, upstream_id: &str) -> Option<Arc<UpstreamCredential>> {
+        self.credentials
+            .get(upstream_id)
+            .map(|entry| Arc::clone(&entry.1))
+    }
+
+    /// Apply the `auth` blocks of a new configuration
+    ///
+    /// Unchanged credentials keep their cached tokens. A credential that
+    /// fails to build is dropped, so its upstream gets no credentials.
+    pub fn reload(&self, configs: &HashMap<String, UpstreamAuthConfig>) {
+        self.credentials.retain(|id, _| configs.contains_key(id));
+
+        for (upstream_id, config) in configs {
+            if self
+                .credentials
+                .get(upstream_id)
+                .is_some_and(|entry| entry.0 == *config)
+            {
+                continue;
+            }
+            match self.register(upstream_id, config) {
+                Ok(()) => info!(upstream_id = %upstream_id, "Upstream credentials updated"),
+                Err(e) => {
+                    self.credentials.remove(upstream_id);
+                    warn!(
+                        upstream_id = %upstream_id,
+                        error = %e,
+                        "Failed to reload upstream credentials"
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Default for UpstreamAuthManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Token endpoint answering every request with a new token
+    async fn token_server(expires_in: u64) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                let body = format!(
+                    r#"{{"access_token":"token-{}","token_type":"Bearer","expires_in":{}}}"#,
+                    n, expires_in
+                );
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn oauth2_config(token_url: String) -> UpstreamAuthConfig {
+        UpstreamAuthConfig::OAuth2 {
+            token_url,
+            client_id: "zentinel".to_string(),
+            client_secret: "shh".to_string(),
+            scopes: vec!["read".to_string()],
+            audience: None,
+            client_auth: OAuth2ClientAuth::Basic,
+            refresh_before_secs: 60,
+            timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn static_credentials() {
+        let bearer = UpstreamCredential::new(
+            "api",
+            &UpstreamAuthConfig::Bearer {
+                token: "s3cret".to_string(),
+            },
+        )
+        .unwrap();
+        let value = bearer.authorization().await.unwrap();
+        assert_eq!(value, "Bearer s3cret");
+        assert!(value.is_sensitive());
+
+        let basic = UpstreamCredential::new(
+            "api",
+            &UpstreamAuthConfig::Basic {
+                username: "Aladdin".to_string(),
+                password: "open sesame".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            basic.authorization().await.unwrap(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+
+        let invalid = UpstreamCredential::new(
+            "api",
+            &UpstreamAuthConfig::Bearer {
+                token: "line\nbreak".to_string(),
+            },
+        );
+        assert!(matches!(invalid, Err(UpstreamAuthError::InvalidHeader(_))));
+    }
+
+    #[tokio::test]
+    async fn oauth2_tokens_are_cached() {
+        let (url, requests) = token_server(3600).await;
+        let credential = UpstreamCredential::new("billing", &oauth2_config(url)).unwrap();
+
+        assert_eq!(credential.authorization().await.unwrap(), "Bearer token-1");
+        assert_eq!(credential.authorization().await.unwrap(), "Bearer token-1");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn oauth2_tokens_refresh_before_expiry() {
+        // A 1-second token is refreshed after half its lifetime
+        let (url, requests) = token_server(1).await;
+        let credential = UpstreamCredential::new("billing", &oauth2_config(url)).unwrap();
+
+        assert_eq!(credential.authorization().await.unwrap(), "Bearer token-1");
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(credential.authorization().await.unwrap(), "Bearer token-2");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn oauth2_failure_without_token_is_an_error() {
+        // Nothing listens on this port
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        drop(listener);
+
+        let credential = UpstreamCredential::new("billing", &oauth2_config(url)).unwrap();
+        assert!(matches!(
+            credential.authorization().await,
+            Err(UpstreamAuthError::Request { .. })
+        ));
+        // Retries wait for the backoff
+        assert!(matches!(
+            credential.authorization().await,
+            Err(UpstreamAuthError::Backoff(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reload_keeps_unchanged_credentials() {
+        let manager = UpstreamAuthManager::new();
+        let bearer = UpstreamAuthConfig::Bearer {
+            token: "one".to_string(),
+        };
+        manager.register("api", &bearer).unwrap();
+        let before = manager.get("api").unwrap();
+
+        let mut configs = HashMap::from([("api".to_string(), bearer)]);
+        manager.reload(&configs);
+        assert!(Arc::ptr_eq(&before, &manager.get("api").unwrap()));
+
+        configs.insert(
+            "api".to_string(),
+            UpstreamAuthConfig::Bearer {
+                token: "two".to_string(),
+            },
+        );
+        manager.reload(&configs);
+        assert_eq!(
+            manager.get("api").unwrap().authorization().await.unwrap(),
+            "Bearer two"
+        );
+
+        manager.reload(&HashMap::new());
+        assert!(manager.get("api").is_none());
+    }
+}
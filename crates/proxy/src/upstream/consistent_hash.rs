@@ -1,5 +1,5 @@
 use murmur3::murmur3_32;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::io::Cursor;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
@@ -210,7 +210,7 @@ impl ConsistentHashBalancer {
         let healthy_count = new_ring
             .values()
             .map(|n| n.target_index)
-            .collect::<std::collections::HashSet<_>>()
+            .collect::<HashSet<_>>()
             .len();
 
         if new_ring.is_empty() {
@@ -269,8 +269,9 @@ impl ConsistentHashBalancer {
             "Finding target for hash key"
         );
 
-        // Check cache first
-        {
+        // Check cache first. Cached owners bypass the load check, so the cache
+        // is only consulted when bounded loads are disabled.
+        if !self.config.bounded_loads {
             let cache = self.lookup_cache.read().await;
             if let Some(&target_index) = cache.get(&key_hash) {
                 // Verify target is still healthy
@@ -300,26 +301,15 @@ impl ConsistentHashBalancer {
             return None;
         }
 
-        // Find the first virtual node with hash >= key_hash
-        let candidates = if let Some((&_node_hash, vnode)) = ring.range(key_hash..).next() {
-            vec![vnode.clone()]
-        } else {
-            // Wrap around to the first node
-            ring.iter()
-                .next()
-                .map(|(_, vnode)| vec![vnode.clone()])
-                .unwrap_or_default()
-        };
-
-        trace!(
-            hash_key = %hash_key,
-            candidate_count = candidates.len(),
-            "Found candidates on hash ring"
-        );
+        // Walk the ring clockwise from the key, wrapping around once
+        let mut walk = ring
+            .range(key_hash..)
+            .chain(ring.range(..key_hash))
+            .map(|(_, vnode)| vnode);
 
-        // If bounded loads is disabled, return the first candidate
+        // If bounded loads is disabled, return the owning node
         if !self.config.bounded_loads {
-            let target_index = candidates.first().map(|n| n.target_index);
+            let target_index = walk.next().map(|n| n.target_index);
 
             // Update cache
             if let Some(idx) = target_index {
@@ -334,39 +324,45 @@ impl ConsistentHashBalancer {
             return target_index;
         }
 
-        // Bounded loads: check if target is overloaded
-        let avg_load = self.calculate_average_load().await;
-        let max_load = (avg_load * self.config.max_load_factor) as u64;
+        // Bounded loads: skip targets at capacity, keeping the ring order so a
+        // hot key spills to the same neighbour every time
+        let capacity = self.load_capacity().await;
 
         trace!(
-            avg_load = avg_load,
-            max_load = max_load,
+            capacity = capacity,
             max_load_factor = self.config.max_load_factor,
             "Checking bounded loads"
         );
 
-        // Try candidates in order until we find one that's not overloaded
-        for vnode in candidates {
+        let mut visited = HashSet::new();
+        for vnode in walk {
+            if !visited.insert(vnode.target_index) {
+                continue;
+            }
+
             let current_load = self.connection_counts[vnode.target_index].load(Ordering::Relaxed);
 
             trace!(
                 target_index = vnode.target_index,
                 current_load = current_load,
-                max_load = max_load,
+                capacity = capacity,
                 "Evaluating candidate load"
             );
 
-            if current_load <= max_load {
-                // Update cache
-                bounded_cache_insert(&self.lookup_cache, key_hash, vnode.target_index).await;
+            if current_load < capacity {
                 debug!(
                     hash_key = %hash_key,
                     target_index = vnode.target_index,
                     current_load = current_load,
+                    spilled = visited.len() > 1,
                     "Selected target within load bounds"
                 );
                 return Some(vnode.target_index);
             }
+
+            if visited.len() == self.targets.len() {
+                break;
+            }
         }
 
         trace!(
@@ -374,12 +370,17 @@ impl ConsistentHashBalancer {
             "All candidates overloaded, falling back to least loaded"
         );
 
+        drop(ring);
         // If all candidates are overloaded, find least loaded target
         self.find_least_loaded_target().await
     }
 
-    /// Calculate average load across all healthy targets
-    async fn calculate_average_load(&self) -> f64 {
+    /// Per-target in-flight cap for bounded loads:
+    /// `ceil(max_load_factor * (total + 1) / healthy_targets)`.
+    ///
+    /// Counting the request being placed keeps the cap at least 1, so an idle
+    /// ring never rejects its owner.
+    async fn load_capacity(&self) -> u64 {
         let health = self.health_status.read().await;
         let healthy_count = self
             .targets
@@ -388,14 +389,11 @@ impl ConsistentHashBalancer {
                 let target_id = format!("{}:{}", t.address, t.port);
                 health.get(&target_id).copied().unwrap_or(true)
             })
-            .count();
+            .count()
+            .max(1);
 
-        if healthy_count == 0 {
-            return 0.0;
-        }
-
-        let total = self.total_connections.load(Ordering::Relaxed);
-        total as f64 / healthy_count as f64
+        let total = self.total_connections.load(Ordering::Relaxed) + 1;
+        (self.config.max_load_factor * total as f64 / healthy_count as f64).ceil() as u64
     }
 
     /// Find the least loaded target when all consistent hash candidates are overloaded
@@ -448,7 +446,7 @@ impl ConsistentHashBalancer {
     /// Extract hash key from request context
     pub fn extract_hash_key(&self, context: &RequestContext) -> Option<String> {
         let key = match &self.config.hash_key_extractor {
-            HashKeyExtractor::ClientIp => context.client_ip.map(|ip| ip.to_string()),
+            HashKeyExtractor::ClientIp => context.client_ip.map(|ip| ip.ip().to_string()),
             HashKeyExtractor::Header(name) => context.headers.get(name).cloned(),
             HashKeyExtractor::Cookie(name) => {
                 // Parse cookie header and extract specific cookie
@@ -621,6 +619,10 @@ impl LoadBalancer for ConsistentHashBalancer {
         targets
     }
 
+    fn uses_request_context(&self) -> bool {
+        true
+    }
+
     /// Release connection when request completes
     async fn release(&self, selection: &TargetSelection) {
         if self.config.bounded_loads {
@@ -739,6 +741,36 @@ mod tests {
         assert_ne!(index, 0);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_bounded_loads_spill_and_return() {
+        let targets = create_test_targets(2);
+        let config = ConsistentHashConfig {
+            max_load_factor: 1.0,
+            hash_key_extractor: HashKeyExtractor::Header("x-user-id".to_string()),
+            ..Default::default()
+        };
+        let balancer = ConsistentHashBalancer::new(targets, config);
+
+        let mut headers = HashMap::new();
+        headers.insert("x-user-id".to_string(), "user-42".to_string());
+        let context = RequestContext {
+            client_ip: None,
+            headers,
+            path: "/".to_string(),
+            method: "GET".to_string(),
+        };
+
+        // Owner takes the first request, the neighbour absorbs the overflow
+        let first = balancer.select(Some(&context)).await.unwrap();
+        let second = balancer.select(Some(&context)).await.unwrap();
+        assert_ne!(first.address, second.address);
+
+        // Once the owner drains, the key returns to it
+        balancer.release(&first).await;
+        let third = balancer.select(Some(&context)).await.unwrap();
+        assert_eq!(first.address, third.address);
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_ring_rebuild_on_health_change() {
         let targets = create_test_targets(3);
@@ -6,16 +6,17 @@
 
 use pingora_load_balancing::{
     discovery::Static,
-    health_check::{HealthCheck as PingoraHealthCheck, HttpHealthCheck, TcpHealthCheck},
+    health_check::{HealthCheck as PingoraHealthCheck, TcpHealthCheck},
     Backend, Backends,
 };
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, info, trace, warn};
 
 use crate::grpc_health::GrpcHealthCheck;
+use crate::upstream::http_health::HttpHealthCheck;
 use crate::upstream::inference_health::InferenceHealthCheck;
 
 use zentinel_common::types::HealthCheckType;
@@ -30,6 +31,10 @@ pub struct ActiveHealthChecker {
     upstream_id: String,
     /// Pingora backends with health checking
     backends: Arc<Backends>,
+    /// Resolved backend address -> configured target address
+    target_addresses: HashMap<String, String>,
+    /// Last observed health per configured target address
+    last_health: Arc<RwLock<HashMap<String, bool>>>,
     /// Health check interval
     interval: Duration,
     /// Whether to run checks in parallel
@@ -55,9 +60,11 @@ impl ActiveHealthChecker {
 
         // Create backends from targets
         let mut backend_set = BTreeSet::new();
+        let mut target_addresses = HashMap::new();
         for target in &config.targets {
             match Backend::new_with_weight(&target.address, target.weight as usize) {
                 Ok(backend) => {
+                    target_addresses.insert(backend.addr.to_string(), target.address.clone());
                     debug!(
                         upstream_id = %config.id,
                         target = %target.address,
@@ -98,6 +105,8 @@ impl ActiveHealthChecker {
         Some(Self {
             upstream_id: config.id.clone(),
             backends: Arc::new(backends),
+            target_addresses,
+            last_health: Arc::new(RwLock::new(HashMap::new())),
             interval: Duration::from_secs(health_config.interval_secs),
             parallel: true,
             health_callback: Arc::new(RwLock::new(None)),
@@ -114,34 +123,29 @@ impl ActiveHealthChecker {
                 path,
                 expected_status,
                 host,
+                method,
+                expected_body,
             } => {
-                let hostname = host.as_deref().unwrap_or("localhost");
-                let mut hc = HttpHealthCheck::new(hostname, false);
-
-                // Configure thresholds
+                let timeout = Duration::from_secs(config.timeout_secs);
+                let mut hc = HttpHealthCheck::new(
+                    path.clone(),
+                    *method,
+                    *expected_status,
+                    expected_body.clone(),
+                    host.clone(),
+                    timeout,
+                );
                 hc.consecutive_success = config.healthy_threshold as usize;
                 hc.consecutive_failure = config.unhealthy_threshold as usize;
 
-                // Configure request path
-                // Note: HttpHealthCheck sends GET to / by default
-                // We customize by modifying hc.req for non-root paths
-                if path != "/" {
-                    // Create custom request header for the health check path
-                    if let Ok(req) =
-                        pingora_http::RequestHeader::build("GET", path.as_bytes(), None)
-                    {
-                        hc.req = req;
-                    }
-                }
-
-                // Note: health_changed_callback requires implementing HealthObserve trait
-                // We use polling via run_health_check() and get_health_statuses() instead
-
                 debug!(
                     upstream_id = %upstream_id,
                     path = %path,
+                    method = method.as_str(),
                     expected_status = expected_status,
-                    host = hostname,
+                    expected_body = expected_body.as_deref().unwrap_or("(any)"),
+                    host = host.as_deref().unwrap_or("(target address)"),
+                    timeout_secs = config.timeout_secs,
                     consecutive_success = hc.consecutive_success,
                     consecutive_failure = hc.consecutive_failure,
                     "Created HTTP health check"
@@ -154,9 +158,12 @@ impl ActiveHealthChecker {
                 let mut hc = TcpHealthCheck::new();
                 hc.consecutive_success = config.healthy_threshold as usize;
                 hc.consecutive_failure = config.unhealthy_threshold as usize;
+                hc.peer_template.options.connection_timeout =
+                    Some(Duration::from_secs(config.timeout_secs));
 
                 debug!(
                     upstream_id = %upstream_id,
+                    timeout_secs = config.timeout_secs,
                     consecutive_success = hc.consecutive_success,
                     consecutive_failure = hc.consecutive_failure,
                     "Created TCP health check"
@@ -221,14 +228,69 @@ impl ActiveHealthChecker {
             "Running health check cycle"
         );
 
+        self.discover_backends().await;
         self.backends.run_health_check(self.parallel).await;
+        self.notify_health_changes().await;
+    }
+
+    /// Load the static backend set into Pingora's `Backends` on first use.
+    ///
+    /// `Backends` starts empty until `update()` runs discovery; without it
+    /// there is nothing to probe.
+    async fn discover_backends(&self) {
+        if !self.backends.get_backend().is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.backends.update(|_| {}).await {
+            warn!(
+                upstream_id = %self.upstream_id,
+                error = %e,
+                "Failed to load backends for health checking"
+            );
+        }
+    }
+
+    /// Invoke the health callback for every target whose status changed
+    /// since the previous cycle (and for every target on the first cycle).
+    async fn notify_health_changes(&self) {
+        let statuses = self.get_health_statuses();
+        let callback = self.health_callback.read().await;
+        let mut last_health = self.last_health.write().await;
+
+        for (address, healthy) in statuses {
+            let previous = last_health.insert(address.clone(), healthy);
+            if previous == Some(healthy) {
+                continue;
+            }
+
+            if previous.is_some() {
+                if healthy {
+                    info!(
+                        upstream_id = %self.upstream_id,
+                        target = %address,
+                        "Active health check restored target"
+                    );
+                } else {
+                    warn!(
+                        upstream_id = %self.upstream_id,
+                        target = %address,
+                        "Active health check failed target"
+                    );
+                }
+            }
+
+            if let Some(ref callback) = *callback {
+                callback(&address, healthy);
+            }
+        }
     }
 
     /// Check if a specific backend is healthy
     pub fn is_backend_healthy(&self, address: &str) -> bool {
         let backends = self.backends.get_backend();
         for backend in backends.iter() {
-            if backend.addr.to_string() == address {
+            if self.target_address(backend) == address {
                 return self.backends.ready(backend);
             }
         }
@@ -236,19 +298,28 @@ impl ActiveHealthChecker {
         true
     }
 
-    /// Get all backend health statuses
+    /// Get all backend health statuses, keyed by configured target address
     pub fn get_health_statuses(&self) -> Vec<(String, bool)> {
         let backends = self.backends.get_backend();
         backends
             .iter()
             .map(|b| {
-                let addr = b.addr.to_string();
+                let addr = self.target_address(b);
                 let healthy = self.backends.ready(b);
                 (addr, healthy)
             })
             .collect()
     }
 
+    /// Map a backend back to the target address it was configured with
+    fn target_address(&self, backend: &Backend) -> String {
+        let resolved = backend.addr.to_string();
+        self.target_addresses
+            .get(&resolved)
+            .cloned()
+            .unwrap_or(resolved)
+    }
+
     /// Get the health check interval
     pub fn interval(&self) -> Duration {
         self.interval
@@ -287,6 +358,24 @@ impl HealthCheckRunner {
         self.checkers.push(checker);
     }
 
+    /// Install a callback invoked with `(upstream_id, target, healthy)`
+    /// whenever an active check changes a target's health.
+    pub async fn on_health_change<F>(&self, callback: F)
+    where
+        F: Fn(&str, &str, bool) + Send + Sync + 'static,
+    {
+        let callback = Arc::new(callback);
+        for checker in &self.checkers {
+            let callback = Arc::clone(&callback);
+            let upstream_id = checker.upstream_id.clone();
+            checker
+                .set_health_callback(Box::new(move |target, healthy| {
+                    callback(&upstream_id, target, healthy)
+                }))
+                .await;
+        }
+    }
+
     /// Get the number of health checkers
     pub fn checker_count(&self) -> usize {
         self.checkers.len()
@@ -377,7 +466,7 @@ mod tests {
     use super::*;
     use std::collections::HashMap;
     use std::sync::Once;
-    use zentinel_common::types::LoadBalancingAlgorithm;
+    use zentinel_common::types::{HealthCheckMethod, LoadBalancingAlgorithm};
     use zentinel_config::{
         ConnectionPoolConfig, HttpVersionConfig, UpstreamTarget, UpstreamTimeouts,
     };
@@ -401,11 +490,15 @@ mod tests {
             }],
             load_balancing: LoadBalancingAlgorithm::RoundRobin,
             sticky_session: None,
+            consistent_hash: None,
+            discovery: None,
             health_check: Some(HealthCheckConfig {
                 check_type: HealthCheckType::Http {
                     path: "/health".to_string(),
                     expected_status: 200,
                     host: None,
+                    method: HealthCheckMethod::Get,
+                    expected_body: None,
                 },
                 interval_secs: 5,
                 timeout_secs: 2,
@@ -416,6 +509,8 @@ mod tests {
             circuit_breaker: None,
             timeouts: UpstreamTimeouts::default(),
             tls: None,
+            auth: None,
+            queue: None,
             http_version: HttpVersionConfig::default(),
         }
     }
@@ -432,6 +527,39 @@ mod tests {
         assert_eq!(checker.interval, Duration::from_secs(5));
     }
 
+    #[tokio::test]
+    async fn test_health_change_callback_fires_on_transitions_only() {
+        init_crypto_provider();
+        let mut runner = HealthCheckRunner::new();
+        runner.add_checker(ActiveHealthChecker::new(&create_test_config()).unwrap());
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        runner
+            .on_health_change(move |upstream, target, healthy| {
+                sink.lock()
+                    .unwrap()
+                    .push((upstream.to_string(), target.to_string(), healthy));
+            })
+            .await;
+
+        // First observation reports the initial state, keyed by configured address
+        runner.checkers[0].discover_backends().await;
+        runner.checkers[0].notify_health_changes().await;
+        // Unchanged state is not reported again
+        runner.checkers[0].notify_health_changes().await;
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            *seen,
+            vec![(
+                "test-upstream".to_string(),
+                "127.0.0.1:8081".to_string(),
+                true
+            )]
+        );
+    }
+
     #[test]
     fn test_no_health_check_config() {
         let mut config = create_test_config();
@@ -0,0 +1,289 @@
+//! HTTP probe health check for upstream backends.
+//!
+//! Sends a GET or HEAD request to each backend and verifies the response
+//! status and, optionally, that the body contains an expected substring.
+//!
+//! # Example Configuration
+//!
+//! ```kdl
+//! upstream "api" {
+//!     target "10.0.0.1:8080"
+//!     health-check {
+//!         type "http" {
+//!             path "/ready"
+//!             method "GET"
+//!             expected-status 200
+//!             expected-body "\"status\":\"ok\""
+//!         }
+//!         interval-secs 5
+//!         timeout-secs 2
+//!     }
+//! }
+//! ```
+
+use async_trait::async_trait;
+use pingora_core::{Error, ErrorType::CustomCode, Result};
+use pingora_load_balancing::health_check::HealthCheck as PingoraHealthCheck;
+use pingora_load_balancing::Backend;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, trace};
+
+use zentinel_common::types::HealthCheckMethod;
+
+/// Upper bound on how much of a probe response is read
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// HTTP health check implementing Pingora's HealthCheck trait.
+pub struct HttpHealthCheck {
+    /// Request path
+    path: String,
+    /// Probe method
+    method: HealthCheckMethod,
+    /// Expected response status
+    expected_status: u16,
+    /// Substring the body must contain
+    expected_body: Option<String>,
+    /// Host header (defaults to the backend address)
+    host: Option<String>,
+    /// Bound on connect + request + response
+    timeout: Duration,
+    /// Consecutive successes needed to mark healthy
+    pub consecutive_success: usize,
+    /// Consecutive failures needed to mark unhealthy
+    pub consecutive_failure: usize,
+}
+
+impl HttpHealthCheck {
+    /// Create a new HTTP health check.
+    pub fn new(
+        path: String,
+        method: HealthCheckMethod,
+        expected_status: u16,
+        expected_body: Option<String>,
+        host: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            path,
+            method,
+            expected_status,
+            expected_body,
+            host,
+            timeout,
+            consecutive_success: 1,
+            consecutive_failure: 1,
+        }
+    }
+
+    /// Perform the probe against a backend address.
+    async fn check_backend(&self, addr: &str) -> Result<(), String> {
+        let socket_addr: std::net::SocketAddr = addr
+            .parse()
+            .map_err(|e| format!("Invalid address '{}': {}", addr, e))?;
+
+        tokio::time::timeout(self.timeout, self.probe(socket_addr, addr))
+            .await
+            .map_err(|_| format!("Health check timed out after {:?}", self.timeout))?
+    }
+
+    /// Send the request and validate the response.
+    async fn probe(&self, socket_addr: std::net::SocketAddr, addr: &str) -> Result<(), String> {
+        let mut stream = TcpStream::connect(socket_addr)
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?;
+
+        let request = format!(
+            "{} {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             User-Agent: Zentinel-HealthCheck/1.0\r\n\
+             Connection: close\r\n\r\n",
+            self.method.as_str(),
+            self.path,
+            self.host.as_deref().unwrap_or(addr)
+        );
+
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        let response = self.read_response(&mut stream).await?;
+        if response.is_empty() {
+            return Err("Empty response".to_string());
+        }
+
+        self.validate(&String::from_utf8_lossy(&response))
+    }
+
+    /// Read the response: headers only for HEAD (or when no body match is
+    /// configured), otherwise until EOF or `MAX_RESPONSE_BYTES`.
+    async fn read_response(&self, stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+        let need_body = self.method == HealthCheckMethod::Get && self.expected_body.is_some();
+        let mut response = Vec::with_capacity(4096);
+        let mut buf = [0u8; 4096];
+
+        loop {
+            let n = stream
+                .read(&mut buf)
+                .await
+                .map_err(|e| format!("Failed to read response: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            response.extend_from_slice(&buf[..n]);
+
+            if response.len() >= MAX_RESPONSE_BYTES {
+                break;
+            }
+            if !need_body && response.windows(4).any(|w| w == b"\r\n\r\n") {
+                break;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Check the status line and body of a raw HTTP response.
+    fn validate(&self, response: &str) -> Result<(), String> {
+        let status = response
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| "Failed to parse HTTP status".to_string())?;
+
+        if status != self.expected_status {
+            return Err(format!(
+                "Unexpected status code: {} (expected {})",
+                status, self.expected_status
+            ));
+        }
+
+        if self.method == HealthCheckMethod::Head {
+            return Ok(());
+        }
+
+        if let Some(ref expected) = self.expected_body {
+            let body = response
+                .find("\r\n\r\n")
+                .map(|pos| &response[pos + 4..])
+                .unwrap_or("");
+            if !body.contains(expected.as_str()) {
+                return Err(format!("Response body does not contain '{}'", expected));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PingoraHealthCheck for HttpHealthCheck {
+    async fn check(&self, backend: &Backend) -> Result<()> {
+        let addr = backend.addr.to_string();
+
+        match self.check_backend(&addr).await {
+            Ok(()) => {
+                trace!(
+                    addr = %addr,
+                    path = %self.path,
+                    method = self.method.as_str(),
+                    "HTTP health check passed"
+                );
+                Ok(())
+            }
+            Err(error) => {
+                debug!(
+                    addr = %addr,
+                    path = %self.path,
+                    method = self.method.as_str(),
+                    error = %error,
+                    "HTTP health check failed"
+                );
+                Err(Error::explain(CustomCode("http health check", 1), error))
+            }
+        }
+    }
+
+    fn health_threshold(&self, success: bool) -> usize {
+        if success {
+            self.consecutive_success
+        } else {
+            self.consecutive_failure
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(method: HealthCheckMethod, expected_body: Option<&str>) -> HttpHealthCheck {
+        HttpHealthCheck::new(
+            "/health".to_string(),
+            method,
+            200,
+            expected_body.map(String::from),
+            None,
+            Duration::from_secs(1),
+        )
+    }
+
+    #[test]
+    fn test_validate_status() {
+        let hc = check(HealthCheckMethod::Get, None);
+        assert!(hc.validate("HTTP/1.1 200 OK\r\n\r\n").is_ok());
+
+        let err = hc
+            .validate("HTTP/1.1 503 Service Unavailable\r\n\r\n")
+            .unwrap_err();
+        assert!(err.contains("503"));
+
+        assert!(hc.validate("garbage").is_err());
+    }
+
+    #[test]
+    fn test_validate_expected_body() {
+        let hc = check(HealthCheckMethod::Get, Some("\"status\":\"ok\""));
+        assert!(hc
+            .validate(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"status\":\"ok\"}"
+            )
+            .is_ok());
+
+        let err = hc
+            .validate("HTTP/1.1 200 OK\r\n\r\n{\"status\":\"degraded\"}")
+            .unwrap_err();
+        assert!(err.contains("does not contain"));
+    }
+
+    #[test]
+    fn test_validate_head_ignores_body() {
+        let hc = check(HealthCheckMethod::Head, Some("never-sent"));
+        assert!(hc
+            .validate("HTTP/1.1 200 OK\r\nContent-Length: 42\r\n\r\n")
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_probe_against_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(buf[..n].starts_with(b"GET /health HTTP/1.1\r\n"));
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nready")
+                .await
+                .unwrap();
+        });
+
+        let hc = check(HealthCheckMethod::Get, Some("ready"));
+        assert!(hc.check_backend(&addr).await.is_ok());
+    }
+}
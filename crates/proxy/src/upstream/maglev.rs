@@ -356,6 +356,10 @@ impl LoadBalancer for MaglevBalancer {
             .filter_map(|(addr, &healthy)| if healthy { Some(addr.clone()) } else { None })
             .collect()
     }
+
+    fn uses_request_context(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
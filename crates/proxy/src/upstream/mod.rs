@@ -3,14 +3,15 @@
 //! This module handles upstream server pools, load balancing, health checking,
 //! connection pooling, and retry logic with circuit breakers.
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use pingora::upstreams::peer::HttpPeer;
 use rand::seq::IndexedRandom;
-use std::collections::HashMap;
-use std::net::ToSocketAddrs;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, trace, warn};
 
@@ -19,7 +20,7 @@ use zentinel_common::{
     types::{CircuitBreakerConfig, LoadBalancingAlgorithm},
     CircuitBreaker, UpstreamId,
 };
-use zentinel_config::UpstreamConfig;
+use zentinel_config::{AlpnProtocol, HashKeySource, UpstreamConfig};
 
 // ============================================================================
 // Internal Upstream Target Type
@@ -85,31 +86,37 @@ impl UpstreamTarget {
 
 // Load balancing algorithm implementations
 pub mod adaptive;
+pub mod auth;
 pub mod consistent_hash;
 pub mod drain;
 pub mod health;
+pub mod http_health;
 pub mod inference_health;
 pub mod least_tokens;
 pub mod locality;
 pub mod maglev;
 pub mod p2c;
 pub mod peak_ewma;
+pub mod queue;
 pub mod sticky_session;
 pub mod subset;
 pub mod weighted_least_conn;
 
 // Re-export commonly used types from sub-modules
 pub use adaptive::{AdaptiveBalancer, AdaptiveConfig};
-pub use consistent_hash::{ConsistentHashBalancer, ConsistentHashConfig};
+pub use auth::{UpstreamAuthError, UpstreamAuthManager, UpstreamCredential};
+pub use consistent_hash::{ConsistentHashBalancer, ConsistentHashConfig, HashKeyExtractor};
 pub use health::{ActiveHealthChecker, HealthCheckRunner};
+pub use http_health::HttpHealthCheck;
 pub use inference_health::InferenceHealthCheck;
 pub use least_tokens::{
     LeastTokensQueuedBalancer, LeastTokensQueuedConfig, LeastTokensQueuedTargetStats,
 };
 pub use locality::{LocalityAwareBalancer, LocalityAwareConfig};
-pub use maglev::{MaglevBalancer, MaglevConfig};
+pub use maglev::{MaglevBalancer, MaglevConfig, MaglevKeySource};
 pub use p2c::{P2cBalancer, P2cConfig};
 pub use peak_ewma::{PeakEwmaBalancer, PeakEwmaConfig};
+pub use queue::{QueueClient, QueuePermit, ShedReason, UpstreamQueueManager};
 pub use sticky_session::{StickySessionBalancer, StickySessionRuntimeConfig};
 pub use subset::{SubsetBalancer, SubsetConfig};
 pub use weighted_least_conn::{WeightedLeastConnBalancer, WeightedLeastConnConfig};
@@ -140,6 +147,13 @@ pub trait LoadBalancer: Send + Sync {
         // Default implementation - no-op
     }
 
+    /// Whether `select` reads the request context (client IP, headers).
+    ///
+    /// The proxy only builds a [`RequestContext`] for balancers that need one.
+    fn uses_request_context(&self) -> bool {
+        false
+    }
+
     /// Report request result (for adaptive algorithms)
     async fn report_result(
         &self,
@@ -178,14 +192,35 @@ pub struct TargetSelection {
     pub metadata: HashMap<String, String>,
 }
 
+/// Metadata key tagging a selection with the membership it came from
+const MEMBERSHIP_GENERATION_KEY: &str = "membership_generation";
+
+/// Metadata key carrying how long resolving the target address took, in
+/// microseconds
+pub const DNS_RESOLVE_MICROS_KEY: &str = "dns_resolve_us";
+
+/// Targets and the load balancer built over them.
+///
+/// Swapped as one unit when service discovery changes the target set, so a
+/// selection never sees a balancer from one generation and targets from
+/// another.
+struct Membership {
+    /// Current targets
+    targets: Vec<UpstreamTarget>,
+    /// Load balancer over `targets`
+    load_balancer: Arc<dyn LoadBalancer>,
+    /// Incremented on every swap
+    generation: u64,
+}
+
 /// Upstream pool managing multiple backend servers
 pub struct UpstreamPool {
     /// Pool identifier
     id: UpstreamId,
-    /// Configured targets
-    targets: Vec<UpstreamTarget>,
-    /// Load balancer implementation
-    load_balancer: Arc<dyn LoadBalancer>,
+    /// Upstream configuration (load balancer settings for membership rebuilds)
+    config: UpstreamConfig,
+    /// Targets and load balancer
+    membership: ArcSwap<Membership>,
     /// Connection pool configuration (Pingora handles actual pooling)
     pool_config: ConnectionPoolConfig,
     /// HTTP version configuration
@@ -196,10 +231,26 @@ pub struct UpstreamPool {
     tls_sni: Option<String>,
     /// TLS configuration for upstream mTLS (client certificates)
     tls_config: Option<zentinel_config::UpstreamTlsConfig>,
+    /// CA bundle and client certificate, reloaded when their files change
+    tls_secrets: Option<Arc<crate::tls::UpstreamTlsSecrets>>,
     /// Circuit breakers per target
     circuit_breakers: Arc<RwLock<HashMap<String, CircuitBreaker>>>,
     /// Pool statistics
     stats: Arc<PoolStats>,
+    /// Targets currently failed by active health checks
+    actively_unhealthy: Arc<RwLock<HashSet<String>>>,
+}
+
+/// Pingora ALPN setting for the protocols listed in an upstream TLS config
+fn upstream_alpn(protocols: &[AlpnProtocol]) -> Option<pingora::upstreams::peer::ALPN> {
+    let h2 = protocols.contains(&AlpnProtocol::H2);
+    let h1 = protocols.contains(&AlpnProtocol::Http11);
+    match (h2, h1) {
+        (true, true) => Some(pingora::upstreams::peer::ALPN::H2H1),
+        (true, false) => Some(pingora::upstreams::peer::ALPN::H2),
+        (false, true) => Some(pingora::upstreams::peer::ALPN::H1),
+        (false, false) => None,
+    }
 }
 
 // Note: Active health checking is handled by the PassiveHealthChecker in health.rs
@@ -225,6 +276,33 @@ pub struct ConnectionPoolConfig {
     pub read_timeout: Duration,
     /// Write timeout
     pub write_timeout: Duration,
+    /// TCP keepalive probes (None = disabled)
+    pub tcp_keepalive: Option<TcpKeepaliveOptions>,
+}
+
+/// TCP keepalive probe timings for upstream connections
+#[derive(Debug, Clone, Copy)]
+pub struct TcpKeepaliveOptions {
+    /// Idle time before the first probe
+    pub idle: Duration,
+    /// Interval between probes
+    pub interval: Duration,
+    /// Unanswered probes before the connection is dropped
+    pub count: usize,
+}
+
+impl TcpKeepaliveOptions {
+    /// Pingora keepalive settings for these timings
+    fn to_pingora(self) -> pingora::protocols::TcpKeepalive {
+        pingora::protocols::TcpKeepalive {
+            idle: self.idle,
+            interval: self.interval,
+            count: self.count,
+            // user_timeout is Linux-only: give up once all probes went unanswered
+            #[cfg(target_os = "linux")]
+            user_timeout: self.idle + self.interval * self.count as u32,
+        }
+    }
 }
 
 /// HTTP version configuration for upstream connections
@@ -253,8 +331,21 @@ impl ConnectionPoolConfig {
             connection_timeout: Duration::from_secs(timeouts.connect_secs),
             read_timeout: Duration::from_secs(timeouts.read_secs),
             write_timeout: Duration::from_secs(timeouts.write_secs),
+            tcp_keepalive: pool_config
+                .tcp_keepalive
+                .enabled
+                .then(|| TcpKeepaliveOptions {
+                    idle: Duration::from_secs(pool_config.tcp_keepalive.idle_secs),
+                    interval: Duration::from_secs(pool_config.tcp_keepalive.interval_secs),
+                    count: pool_config.tcp_keepalive.count,
+                }),
         }
     }
+
+    /// Total connection budget across `targets` targets
+    pub fn capacity(&self, targets: usize) -> usize {
+        self.max_connections * targets
+    }
 }
 
 // CircuitBreaker is imported from zentinel_common
@@ -317,6 +408,10 @@ pub struct PoolConfigSnapshot {
     pub read_timeout_secs: u64,
     /// Write timeout in seconds
     pub write_timeout_secs: u64,
+    /// TCP keepalive idle time in seconds (None = keepalive disabled)
+    pub tcp_keepalive_idle_secs: Option<u64>,
+    /// Maximum concurrent H2 streams per connection
+    pub max_h2_streams: usize,
 }
 
 /// Round-robin load balancer
@@ -532,7 +627,7 @@ impl LoadBalancer for LeastConnectionsBalancer {
         );
 
         let health = self.health_status.read().await;
-        let conns = self.connections.read().await;
+        let mut conns = self.connections.write().await;
 
         let mut best_target = None;
         let mut min_connections = usize::MAX;
@@ -562,14 +657,16 @@ impl LoadBalancer for LeastConnectionsBalancer {
 
         match best_target {
             Some(target) => {
+                let address = target.full_address();
+                *conns.entry(address.clone()).or_insert(0) += 1;
                 trace!(
-                    selected_target = %target.full_address(),
+                    selected_target = %address,
                     connections = min_connections,
                     algorithm = "least_connections",
                     "Selected target with fewest connections"
                 );
                 Ok(TargetSelection {
-                    address: target.full_address(),
+                    address,
                     weight: target.weight,
                     metadata: HashMap::new(),
                 })
@@ -606,6 +703,19 @@ impl LoadBalancer for LeastConnectionsBalancer {
             .filter_map(|(addr, &healthy)| if healthy { Some(addr.clone()) } else { None })
             .collect()
     }
+
+    async fn release(&self, selection: &TargetSelection) {
+        let mut conns = self.connections.write().await;
+        if let Some(count) = conns.get_mut(&selection.address) {
+            *count = count.saturating_sub(1);
+            trace!(
+                target = %selection.address,
+                connections = *count,
+                algorithm = "least_connections",
+                "Released connection"
+            );
+        }
+    }
 }
 
 /// Weighted load balancer
@@ -793,6 +903,10 @@ impl LoadBalancer for IpHashBalancer {
             .filter_map(|(addr, &healthy)| if healthy { Some(addr.clone()) } else { None })
             .collect()
     }
+
+    fn uses_request_context(&self) -> bool {
+        true
+    }
 }
 
 impl UpstreamPool {
@@ -814,7 +928,8 @@ impl UpstreamPool {
             .filter_map(UpstreamTarget::from_config)
             .collect();
 
-        if targets.is_empty() {
+        // Discovered upstreams may start empty and fill in on the first refresh
+        if targets.is_empty() && config.discovery.is_none() {
             error!(
                 upstream_id = %config.id,
                 "No valid upstream targets configured"
@@ -873,9 +988,23 @@ impl UpstreamPool {
         let tls_sni = config.tls.as_ref().and_then(|t| t.sni.clone());
         let tls_config = config.tls.clone();
 
+        // Load CA bundle and client certificate; a secret that fails to load
+        // fails the pool, so a bad reload keeps the previous configuration
+        let tls_secrets = match tls_config {
+            Some(ref tls) => Some(Arc::new(
+                crate::tls::UpstreamTlsSecrets::load(&config.id, tls).map_err(|e| {
+                    ZentinelError::Tls {
+                        message: format!("Upstream '{}' TLS secrets: {}", config.id, e),
+                        source: None,
+                    }
+                })?,
+            )),
+            None => None,
+        };
+
         // Log mTLS configuration if present
         if let Some(ref tls) = tls_config {
-            if tls.client_cert.is_some() {
+            if tls.has_client_cert() {
                 info!(
                     upstream_id = %config.id,
                     "mTLS enabled for upstream (client certificate configured)"
@@ -910,20 +1039,26 @@ impl UpstreamPool {
 
         let pool = Self {
             id: id.clone(),
-            targets,
-            load_balancer,
+            membership: ArcSwap::from_pointee(Membership {
+                targets,
+                load_balancer,
+                generation: 0,
+            }),
+            config,
             pool_config,
             http_version,
             tls_enabled,
             tls_sni,
             tls_config,
+            tls_secrets,
             circuit_breakers: Arc::new(RwLock::new(circuit_breakers)),
             stats: Arc::new(PoolStats::default()),
+            actively_unhealthy: Arc::new(RwLock::new(HashSet::new())),
         };
 
         info!(
             upstream_id = %id,
-            target_count = pool.targets.len(),
+            target_count = pool.target_count(),
             "Upstream pool created successfully"
         );
 
@@ -959,7 +1094,7 @@ impl UpstreamPool {
             LoadBalancingAlgorithm::Random => Arc::new(RandomBalancer::new(targets.to_vec())),
             LoadBalancingAlgorithm::ConsistentHash => Arc::new(ConsistentHashBalancer::new(
                 targets.to_vec(),
-                ConsistentHashConfig::default(),
+                Self::consistent_hash_config(config),
             )),
             LoadBalancingAlgorithm::PowerOfTwoChoices => {
                 Arc::new(P2cBalancer::new(targets.to_vec(), P2cConfig::default()))
@@ -974,7 +1109,7 @@ impl UpstreamPool {
             )),
             LoadBalancingAlgorithm::Maglev => Arc::new(MaglevBalancer::new(
                 targets.to_vec(),
-                MaglevConfig::default(),
+                Self::maglev_config(config),
             )),
             LoadBalancingAlgorithm::LocalityAware => Arc::new(LocalityAwareBalancer::new(
                 targets.to_vec(),
@@ -1030,6 +1165,39 @@ impl UpstreamPool {
         Ok(balancer)
     }
 
+    /// Build the consistent-hash balancer config from the upstream's hash settings
+    fn consistent_hash_config(config: &UpstreamConfig) -> ConsistentHashConfig {
+        let Some(hash) = config.consistent_hash.as_ref() else {
+            return ConsistentHashConfig::default();
+        };
+
+        ConsistentHashConfig {
+            virtual_nodes: hash.virtual_nodes.max(1),
+            bounded_loads: hash.bounded_load,
+            max_load_factor: hash.max_load_factor.max(1.0),
+            hash_key_extractor: match &hash.key {
+                HashKeySource::ClientIp => HashKeyExtractor::ClientIp,
+                HashKeySource::Header(name) => HashKeyExtractor::Header(name.to_lowercase()),
+                HashKeySource::Cookie(name) => HashKeyExtractor::Cookie(name.clone()),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Build the Maglev balancer config from the upstream's hash settings
+    fn maglev_config(config: &UpstreamConfig) -> MaglevConfig {
+        let key_source = match config.consistent_hash.as_ref().map(|h| &h.key) {
+            None | Some(HashKeySource::ClientIp) => MaglevKeySource::ClientIp,
+            Some(HashKeySource::Header(name)) => MaglevKeySource::Header(name.to_lowercase()),
+            Some(HashKeySource::Cookie(name)) => MaglevKeySource::Cookie(name.clone()),
+        };
+
+        MaglevConfig {
+            key_source,
+            ..Default::default()
+        }
+    }
+
     /// Create load balancer without sticky session support (for fallback balancers)
     fn create_load_balancer_inner(
         algorithm: &LoadBalancingAlgorithm,
@@ -1107,24 +1275,26 @@ impl UpstreamPool {
 
     /// Select next upstream peer with selection metadata
     ///
-    /// Returns the selected peer along with optional metadata from the load balancer.
-    /// The metadata can contain sticky session information that should be passed to
-    /// the response filter.
+    /// Returns the selected peer along with the load balancer's selection. The
+    /// selection metadata can contain sticky session information that should be
+    /// passed to the response filter, and the selection must be handed back to
+    /// [`release_selection`](Self::release_selection) when the request completes.
     pub async fn select_peer_with_metadata(
         &self,
         context: Option<&RequestContext>,
-    ) -> ZentinelResult<(HttpPeer, HashMap<String, String>)> {
+    ) -> ZentinelResult<(HttpPeer, TargetSelection)> {
         let request_num = self.stats.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        let membership = self.membership.load_full();
 
         trace!(
             upstream_id = %self.id,
             request_num = request_num,
-            target_count = self.targets.len(),
+            target_count = membership.targets.len(),
             "Starting peer selection with metadata"
         );
 
         let mut attempts = 0;
-        let max_attempts = self.targets.len() * 2;
+        let max_attempts = membership.targets.len() * 2;
 
         while attempts < max_attempts {
             attempts += 1;
@@ -1136,7 +1306,7 @@ impl UpstreamPool {
                 "Attempting to select peer"
             );
 
-            let selection = match self.load_balancer.select(context).await {
+            let mut selection = match membership.load_balancer.select(context).await {
                 Ok(s) => s,
                 Err(e) => {
                     warn!(
@@ -1169,9 +1339,12 @@ impl UpstreamPool {
                     self.stats
                         .circuit_breaker_trips
                         .fetch_add(1, Ordering::Relaxed);
+                    drop(breakers);
+                    membership.load_balancer.release(&selection).await;
                     continue;
                 }
             }
+            drop(breakers);
 
             // Create peer with pooling options
             trace!(
@@ -1179,7 +1352,37 @@ impl UpstreamPool {
                 target = %selection.address,
                 "Creating peer for upstream (Pingora handles connection reuse)"
             );
-            let peer = self.create_peer(&selection)?;
+            let resolve_start = Instant::now();
+            let resolved = match crate::dns::resolver()
+                .resolve_target(&selection.address, self.pool_config.connection_timeout)
+                .await
+            {
+                Ok(addr) => addr,
+                Err(e) => {
+                    error!(
+                        upstream = %self.id,
+                        address = %selection.address,
+                        error = %e,
+                        "Failed to resolve upstream address"
+                    );
+                    membership.load_balancer.release(&selection).await;
+                    return Err(ZentinelError::Upstream {
+                        upstream: self.id.to_string(),
+                        message: e.to_string(),
+                        retryable: true,
+                        source: None,
+                    });
+                }
+            };
+            selection.metadata.insert(
+                DNS_RESOLVE_MICROS_KEY.to_string(),
+                resolve_start.elapsed().as_micros().to_string(),
+            );
+            let peer = self.create_peer(&selection, resolved);
+            selection.metadata.insert(
+                MEMBERSHIP_GENERATION_KEY.to_string(),
+                membership.generation.to_string(),
+            );
 
             debug!(
                 upstream_id = %self.id,
@@ -1190,7 +1393,7 @@ impl UpstreamPool {
             );
 
             self.stats.successes.fetch_add(1, Ordering::Relaxed);
-            return Ok((peer, selection.metadata));
+            return Ok((peer, selection));
         }
 
         self.stats.failures.fetch_add(1, Ordering::Relaxed);
@@ -1214,12 +1417,112 @@ impl UpstreamPool {
             .map(|(peer, _)| peer)
     }
 
-    /// Create new peer connection with connection pooling options
+    /// Whether peer selection needs a [`RequestContext`] (hash-based and sticky balancers)
+    pub fn uses_request_context(&self) -> bool {
+        self.membership.load().load_balancer.uses_request_context()
+    }
+
+    /// Release a selection returned by [`select_peer_with_metadata`](Self::select_peer_with_metadata)
+    ///
+    /// Connection-tracking balancers (least connections, bounded-load consistent
+    /// hashing, P2C, ...) decrement the target's in-flight count here. Selections
+    /// made before a membership change are dropped: the balancer that counted
+    /// them has been replaced.
+    pub async fn release_selection(&self, selection: &TargetSelection) {
+        let membership = self.membership.load_full();
+        let generation = selection
+            .metadata
+            .get(MEMBERSHIP_GENERATION_KEY)
+            .and_then(|g| g.parse::<u64>().ok());
+        if generation != Some(membership.generation) {
+            trace!(
+                upstream_id = %self.id,
+                target = %selection.address,
+                "Skipping release of selection from a previous membership"
+            );
+            return;
+        }
+
+        trace!(
+            upstream_id = %self.id,
+            target = %selection.address,
+            "Releasing load balancer selection"
+        );
+        membership.load_balancer.release(selection).await;
+    }
+
+    /// Replace the pool's targets, e.g. after a service discovery refresh.
+    ///
+    /// The target set and a load balancer rebuilt over it are swapped in
+    /// atomically; requests already holding a peer are unaffected. Targets that
+    /// remain keep their circuit breaker and active health state. Returns
+    /// `false` when the target set is unchanged.
+    pub async fn update_targets(&self, mut targets: Vec<UpstreamTarget>) -> ZentinelResult<bool> {
+        targets.sort_by(|a, b| (&a.address, a.port).cmp(&(&b.address, b.port)));
+        targets.dedup_by(|a, b| a.address == b.address && a.port == b.port);
+
+        let current = self.membership.load_full();
+        let unchanged = current.targets.len() == targets.len()
+            && current.targets.iter().all(|t| {
+                targets
+                    .iter()
+                    .any(|n| n.address == t.address && n.port == t.port && n.weight == t.weight)
+            });
+        if unchanged {
+            return Ok(false);
+        }
+
+        let load_balancer =
+            Self::create_load_balancer(&self.config.load_balancing, &targets, &self.config)?;
+
+        let addresses: HashSet<String> = targets.iter().map(|t| t.full_address()).collect();
+        let previous: HashSet<String> = current.targets.iter().map(|t| t.full_address()).collect();
+
+        // Carry active health state over to the new balancer
+        {
+            let mut actively_unhealthy = self.actively_unhealthy.write().await;
+            actively_unhealthy.retain(|address| addresses.contains(address));
+            for address in actively_unhealthy.iter() {
+                load_balancer.report_health(address, false).await;
+            }
+        }
+
+        {
+            let cb_config = self.config.circuit_breaker.unwrap_or_default();
+            let mut breakers = self.circuit_breakers.write().await;
+            breakers.retain(|address, _| addresses.contains(address));
+            for address in &addresses {
+                breakers
+                    .entry(address.clone())
+                    .or_insert_with(|| CircuitBreaker::new(cb_config));
+            }
+        }
+
+        let generation = current.generation + 1;
+        self.membership.store(Arc::new(Membership {
+            targets,
+            load_balancer,
+            generation,
+        }));
+
+        info!(
+            upstream_id = %self.id,
+            target_count = addresses.len(),
+            added = addresses.difference(&previous).count(),
+            removed = previous.difference(&addresses).count(),
+            generation = generation,
+            "Updated upstream membership"
+        );
+
+        Ok(true)
+    }
+
+    /// Create new peer connection to `resolved_address` with connection pooling options
     ///
     /// Pingora handles actual connection pooling internally. When idle_timeout
     /// is set on the peer options, Pingora will keep the connection alive and
     /// reuse it for subsequent requests to the same upstream.
-    fn create_peer(&self, selection: &TargetSelection) -> ZentinelResult<HttpPeer> {
+    fn create_peer(&self, selection: &TargetSelection, resolved_address: SocketAddr) -> HttpPeer {
         // Determine SNI hostname for TLS connections
         let sni_hostname = self.tls_sni.clone().unwrap_or_else(|| {
             // Extract hostname from address (strip port)
@@ -1231,40 +1534,6 @@ impl UpstreamPool {
                 .to_string()
         });
 
-        // Pre-resolve the address to avoid panics in Pingora's HttpPeer::new
-        // when DNS resolution fails (e.g., when a container is killed)
-        let resolved_address = selection
-            .address
-            .to_socket_addrs()
-            .map_err(|e| {
-                error!(
-                    upstream = %self.id,
-                    address = %selection.address,
-                    error = %e,
-                    "Failed to resolve upstream address"
-                );
-                ZentinelError::Upstream {
-                    upstream: self.id.to_string(),
-                    message: format!("DNS resolution failed for {}: {}", selection.address, e),
-                    retryable: true,
-                    source: None,
-                }
-            })?
-            .next()
-            .ok_or_else(|| {
-                error!(
-                    upstream = %self.id,
-                    address = %selection.address,
-                    "No addresses returned from DNS resolution"
-                );
-                ZentinelError::Upstream {
-                    upstream: self.id.to_string(),
-                    message: format!("No addresses for {}", selection.address),
-                    retryable: true,
-                    source: None,
-                }
-            })?;
-
         // Use the resolved IP address to create the peer
         let mut peer = HttpPeer::new(resolved_address, self.tls_enabled, sni_hostname.clone());
 
@@ -1273,23 +1542,24 @@ impl UpstreamPool {
         // kept alive and reused for this duration
         peer.options.idle_timeout = Some(self.pool_config.idle_timeout);
 
-        // Connection timeouts
+        // Connection timeouts; the total budget (including the TLS handshake)
+        // never undercuts the configured TCP connect timeout
         peer.options.connection_timeout = Some(self.pool_config.connection_timeout);
-        peer.options.total_connection_timeout = Some(Duration::from_secs(10));
+        peer.options.total_connection_timeout = Some(
+            self.pool_config
+                .connection_timeout
+                .max(Duration::from_secs(10)),
+        );
 
         // Read/write timeouts
         peer.options.read_timeout = Some(self.pool_config.read_timeout);
         peer.options.write_timeout = Some(self.pool_config.write_timeout);
 
-        // Enable TCP keepalive for long-lived connections
-        peer.options.tcp_keepalive = Some(pingora::protocols::TcpKeepalive {
-            idle: Duration::from_secs(60),
-            interval: Duration::from_secs(10),
-            count: 3,
-            // user_timeout is Linux-only
-            #[cfg(target_os = "linux")]
-            user_timeout: Duration::from_secs(60),
-        });
+        // TCP keepalive for long-lived connections
+        peer.options.tcp_keepalive = self
+            .pool_config
+            .tcp_keepalive
+            .map(TcpKeepaliveOptions::to_pingora);
 
         // Configure HTTP version and ALPN for TLS connections
         if self.tls_enabled {
@@ -1308,7 +1578,12 @@ impl UpstreamPool {
                     pingora::upstreams::peer::ALPN::H1
                 }
             };
-            peer.options.alpn = alpn;
+            // An explicit `alpn` in the TLS config takes precedence
+            peer.options.alpn = self
+                .tls_config
+                .as_ref()
+                .and_then(|tls| upstream_alpn(&tls.alpn))
+                .unwrap_or(alpn);
 
             // Configure TLS verification options based on upstream config
             if let Some(ref tls_config) = self.tls_config {
@@ -1333,35 +1608,15 @@ impl UpstreamPool {
                         "Set alternative CN for TLS verification"
                     );
                 }
+            }
 
-                // Configure mTLS client certificate if provided
-                if let (Some(cert_path), Some(key_path)) =
-                    (&tls_config.client_cert, &tls_config.client_key)
-                {
-                    match crate::tls::load_client_cert_key(cert_path, key_path) {
-                        Ok(cert_key) => {
-                            peer.client_cert_key = Some(cert_key);
-                            info!(
-                                upstream_id = %self.id,
-                                target = %selection.address,
-                                cert_path = ?cert_path,
-                                "mTLS client certificate configured"
-                            );
-                        }
-                        Err(e) => {
-                            error!(
-                                upstream_id = %self.id,
-                                target = %selection.address,
-                                error = %e,
-                                "Failed to load mTLS client certificate"
-                            );
-                            return Err(ZentinelError::Tls {
-                                message: format!("Failed to load client certificate: {}", e),
-                                source: None,
-                            });
-                        }
-                    }
+            // Custom CA bundle and mTLS client certificate
+            if let Some(ref secrets) = self.tls_secrets {
+                let secrets = secrets.current();
+                if secrets.ca.is_some() {
+                    peer.options.ca = secrets.ca;
                 }
+                peer.client_cert_key = secrets.client_cert_key;
             }
 
             trace!(
@@ -1388,6 +1643,9 @@ impl UpstreamPool {
                     "Configured H2 ping interval"
                 );
             }
+
+            // Cap concurrent streams multiplexed on one H2 connection
+            peer.options.max_h2_streams = self.http_version.max_h2_streams;
         }
 
         trace!(
@@ -1400,7 +1658,7 @@ impl UpstreamPool {
             "Created peer with Pingora connection pooling enabled"
         );
 
-        Ok(peer)
+        peer
     }
 
     /// Report connection result for a target
@@ -1426,7 +1684,11 @@ impl UpstreamPool {
                     "Recorded success in circuit breaker"
                 );
             }
-            self.load_balancer.report_health(target, true).await;
+            // A passive success must not re-add a target that active
+            // health checks have taken out of rotation
+            if !self.is_actively_unhealthy(target).await {
+                self.load_balancer().report_health(target, true).await;
+            }
         } else {
             let breaker_opened =
                 if let Some(breaker) = self.circuit_breakers.read().await.get(target) {
@@ -1457,6 +1719,12 @@ impl UpstreamPool {
                 circuit_breaker_opened = breaker_opened,
                 "Connection failure reported for target"
             );
+            if breaker_opened {
+                crate::notifications::circuit_breaker_opened(
+                    "upstream",
+                    &format!("{}/{}", self.id, target),
+                );
+            }
         }
     }
 
@@ -1487,22 +1755,68 @@ impl UpstreamPool {
                 breaker.record_success();
             }
             // Always report success to the load balancer (restores health + records latency)
-            self.load_balancer
+            self.load_balancer()
                 .report_result_with_latency(target, true, latency)
                 .await;
+            // In-flight requests finishing after an active check failed the
+            // target must not put it back into rotation
+            if self.is_actively_unhealthy(target).await {
+                self.load_balancer().report_health(target, false).await;
+            }
         } else {
             // Record the failure in the circuit breaker (this may open it). We
             // do NOT propagate a health-down to the load balancer on open: the
             // selection loop's `is_closed()` check is the sole availability gate
             // and runs the timed half-open recovery probe. Marking the target
             // down here would remove it from selection and block recovery (#261).
-            if let Some(breaker) = self.circuit_breakers.read().await.get(target) {
-                breaker.record_failure();
+            let opened = self
+                .circuit_breakers
+                .read()
+                .await
+                .get(target)
+                .is_some_and(|breaker| breaker.record_failure());
+            if opened {
+                crate::notifications::circuit_breaker_opened(
+                    "upstream",
+                    &format!("{}/{}", self.id, target),
+                );
             }
             self.stats.failures.fetch_add(1, Ordering::Relaxed);
         }
     }
 
+    /// Apply an active health check result for a target.
+    ///
+    /// Unhealthy targets are removed from load balancing until a later
+    /// check marks them healthy again; passive successes in the meantime
+    /// do not re-add them.
+    pub async fn report_target_health(&self, target: &str, healthy: bool) {
+        let changed = if healthy {
+            self.actively_unhealthy.write().await.remove(target)
+        } else {
+            self.actively_unhealthy
+                .write()
+                .await
+                .insert(target.to_string())
+        };
+
+        self.load_balancer().report_health(target, healthy).await;
+
+        if changed {
+            info!(
+                upstream_id = %self.id,
+                target = %target,
+                healthy = healthy,
+                "Active health check updated target availability"
+            );
+        }
+    }
+
+    /// Check whether active health checks currently fail a target
+    async fn is_actively_unhealthy(&self, target: &str) -> bool {
+        self.actively_unhealthy.read().await.contains(target)
+    }
+
     /// Get pool statistics
     pub fn stats(&self) -> &PoolStats {
         &self.stats
@@ -1515,7 +1829,12 @@ impl UpstreamPool {
 
     /// Get target count
     pub fn target_count(&self) -> usize {
-        self.targets.len()
+        self.membership.load().targets.len()
+    }
+
+    /// Load balancer for the current membership
+    fn load_balancer(&self) -> Arc<dyn LoadBalancer> {
+        self.membership.load().load_balancer.clone()
     }
 
     /// Get pool configuration (for metrics/debugging)
@@ -1528,14 +1847,27 @@ impl UpstreamPool {
             connection_timeout_secs: self.pool_config.connection_timeout.as_secs(),
             read_timeout_secs: self.pool_config.read_timeout.as_secs(),
             write_timeout_secs: self.pool_config.write_timeout.as_secs(),
+            tcp_keepalive_idle_secs: self.pool_config.tcp_keepalive.map(|k| k.idle.as_secs()),
+            max_h2_streams: self.http_version.max_h2_streams,
         }
     }
 
+    /// In-flight limit and request queue, if configured
+    pub fn queue_config(&self) -> Option<&zentinel_config::UpstreamQueueConfig> {
+        self.config.queue.as_ref()
+    }
+
+    /// Total connection budget across all targets (`max-connections` per
+    /// target), used as the denominator for pool utilization.
+    pub fn connection_capacity(&self) -> usize {
+        self.pool_config.capacity(self.target_count())
+    }
+
     /// Check if the pool has any healthy targets.
     ///
     /// Returns true if at least one target is healthy, false if all targets are unhealthy.
     pub async fn has_healthy_targets(&self) -> bool {
-        let healthy = self.load_balancer.healthy_targets().await;
+        let healthy = self.load_balancer().healthy_targets().await;
         !healthy.is_empty()
     }
 
@@ -1547,8 +1879,11 @@ impl UpstreamPool {
         &self,
         context: Option<&RequestContext>,
     ) -> ZentinelResult<ShadowTarget> {
-        // Use load balancer to select target
-        let selection = self.load_balancer.select(context).await?;
+        // Use load balancer to select target. Shadow requests are fire-and-forget,
+        // so the selection is not held against connection-tracking balancers.
+        let load_balancer = self.load_balancer();
+        let selection = load_balancer.select(context).await?;
+        load_balancer.release(&selection).await;
 
         // Check circuit breaker
         let breakers = self.circuit_breakers.read().await;
@@ -1627,7 +1962,7 @@ impl UpstreamPool {
     pub async fn shutdown(&self) {
         info!(
             upstream_id = %self.id,
-            target_count = self.targets.len(),
+            target_count = self.target_count(),
             total_requests = self.stats.requests.load(Ordering::Relaxed),
             total_successes = self.stats.successes.load(Ordering::Relaxed),
             total_failures = self.stats.failures.load(Ordering::Relaxed),
@@ -1637,3 +1972,80 @@ impl UpstreamPool {
         debug!(upstream_id = %self.id, "Upstream pool shutdown complete");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_least_connections_tracks_in_flight() {
+        let balancer = LeastConnectionsBalancer::new(vec![
+            UpstreamTarget::new("10.0.0.1", 8080, 1),
+            UpstreamTarget::new("10.0.0.2", 8080, 1),
+        ]);
+
+        let first = balancer.select(None).await.unwrap();
+        let second = balancer.select(None).await.unwrap();
+        assert_ne!(first.address, second.address);
+
+        // Releasing the first target makes it the least loaded again
+        balancer.release(&first).await;
+        let third = balancer.select(None).await.unwrap();
+        assert_eq!(third.address, first.address);
+    }
+
+    #[tokio::test]
+    async fn test_update_targets_swaps_membership() {
+        use zentinel_common::types::LoadBalancingAlgorithm;
+        use zentinel_config::{
+            ConnectionPoolConfig, HttpVersionConfig, ServiceDiscoveryConfig, UpstreamTimeouts,
+        };
+
+        let pool = UpstreamPool::new(UpstreamConfig {
+            id: "discovered".to_string(),
+            targets: vec![],
+            load_balancing: LoadBalancingAlgorithm::LeastConnections,
+            sticky_session: None,
+            consistent_hash: None,
+            discovery: Some(ServiceDiscoveryConfig::Dns {
+                hostname: "api.internal".to_string(),
+                port: 8080,
+                refresh_interval_secs: 30,
+            }),
+            health_check: None,
+            connection_pool: ConnectionPoolConfig::default(),
+            circuit_breaker: None,
+            timeouts: UpstreamTimeouts::default(),
+            tls: None,
+            auth: None,
+            queue: None,
+            http_version: HttpVersionConfig::default(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(pool.target_count(), 0);
+
+        let targets = vec![
+            UpstreamTarget::new("127.0.0.1", 8081, 1),
+            UpstreamTarget::new("127.0.0.1", 8082, 1),
+        ];
+        assert!(pool.update_targets(targets.clone()).await.unwrap());
+        assert_eq!(pool.target_count(), 2);
+
+        // Same set in a different order is not a change
+        let reversed: Vec<_> = targets.iter().rev().cloned().collect();
+        assert!(!pool.update_targets(reversed).await.unwrap());
+
+        // A selection from the old membership is released without touching
+        // the new balancer's counts
+        let (_, stale) = pool.select_peer_with_metadata(None).await.unwrap();
+        pool.update_targets(vec![UpstreamTarget::new("127.0.0.1", 8083, 1)])
+            .await
+            .unwrap();
+        pool.release_selection(&stale).await;
+        assert_eq!(pool.target_count(), 1);
+
+        let (_, selection) = pool.select_peer_with_metadata(None).await.unwrap();
+        assert_eq!(selection.address, "127.0.0.1:8083");
+    }
+}
@@ -0,0 +1,760 @@
+//! Upstream in-flight limits with priority queuing.
+//!
+//! Upstreams with a `queue` block send at most `max-in-flight` requests at a
+//! time. Requests over the limit wait in a bounded queue and are admitted by
+//! the [`PriorityClass`] of their route: highest class first, and in arrival
+//! order within a class. When the queue is full, an arriving request
+//! displaces the newest waiting request of a lower class, or is shed itself
+//! if there is none. Requests also leave the queue after waiting
+//! `timeout-ms`. Shed requests are answered with 503.
+//!
+//! With `fair`, requests of a class are also scheduled between clients (API
+//! key ID or client IP): the waiting request whose client has the fewest
+//! requests in flight for its tier weight goes first, so a busy client
+//! cannot hold every slot while others wait. Requests passed over for a less
+//! busy client are counted as throttled.
+//!
+//! Queues are kept per upstream across config reloads, so in-flight counts
+//! survive a reload. A raised limit admits waiting requests at once; a
+//! lowered one takes effect as slots are released.
+
+use parking_lot::{Mutex, RwLock};
+use prometheus::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use zentinel_config::{FairQueueConfig, FairQueueKey, PriorityClass, UpstreamQueueConfig};
+
+use crate::request_tags::RequestTags;
+
+/// Prometheus metrics labelled by upstream and priority class.
+struct QueueMetrics {
+    /// Requests sent to the upstream
+    in_flight: IntGaugeVec,
+    /// Requests waiting, per class
+    depth: IntGaugeVec,
+    /// Requests shed, per class and reason
+    shed: IntCounterVec,
+    /// Requests passed over for another client's, per tier
+    throttled: IntCounterVec,
+    /// Clients with requests in flight (fair queuing)
+    clients: IntGaugeVec,
+}
+
+static QUEUE_METRICS: LazyLock<Option<QueueMetrics>> = LazyLock::new(|| {
+    let in_flight = register_int_gauge_vec!(
+        "zentinel_upstream_queue_in_flight",
+        "Requests in flight to upstreams with a request queue",
+        &["upstream"]
+    )
+    .ok()?;
+    let depth = register_int_gauge_vec!(
+        "zentinel_upstream_queue_depth",
+        "Requests waiting in upstream request queues, per priority class",
+        &["upstream", "class"]
+    )
+    .ok()?;
+    let shed = register_int_counter_vec!(
+        "zentinel_upstream_queue_shed_total",
+        "Requests shed by upstream request queues",
+        &["upstream", "class", "reason"]
+    )
+    .ok()?;
+    let throttled = register_int_counter_vec!(
+        "zentinel_upstream_fair_queue_throttled_total",
+        "Requests passed over by fair queuing for a less busy client, per tier",
+        &["upstream", "tier"]
+    )
+    .ok()?;
+    let clients = register_int_gauge_vec!(
+        "zentinel_upstream_fair_queue_clients",
+        "Clients with requests in flight to upstreams with fair queuing",
+        &["upstream"]
+    )
+    .ok()?;
+    Some(QueueMetrics {
+        in_flight,
+        depth,
+        shed,
+        throttled,
+        clients,
+    })
+});
+
+/// Why an upstream queue shed a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShedReason {
+    /// The queue was full of requests of the same or a higher class
+    QueueFull,
+    /// A request of a higher class took its place in the queue
+    Displaced,
+    /// No slot became free within `timeout-ms`
+    Timeout,
+}
+
+impl ShedReason {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShedReason::QueueFull => "queue_full",
+            ShedReason::Displaced => "displaced",
+            ShedReason::Timeout => "timeout",
+        }
+    }
+}
+
+/// Position of a class in [`PriorityClass::ALL`], highest first
+fn rank(class: PriorityClass) -> usize {
+    match class {
+        PriorityClass::Critical => 0,
+        PriorityClass::High => 1,
+        PriorityClass::Normal => 2,
+        PriorityClass::Low => 3,
+    }
+}
+
+/// Client of a request, for fair queuing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueClient {
+    /// API key ID or client IP
+    pub id: String,
+    /// Rate tier of the client's API key
+    pub tier: Option<String>,
+}
+
+impl QueueClient {
+    /// Identify the client of a request by `key`
+    pub fn from_request(key: FairQueueKey, client_ip: &str, tags: &RequestTags) -> Self {
+        let id = match key {
+            FairQueueKey::ApiKey => tags.get("api_key_id").unwrap_or(client_ip),
+            FairQueueKey::ClientIp => client_ip,
+        };
+        Self {
+            id: id.to_string(),
+            tier: tags.get("api_key_tier").map(str::to_string),
+        }
+    }
+}
+
+/// Client and weight a request is scheduled by under fair queuing.
+#[derive(Debug, Clone)]
+struct Share {
+    client: Arc<str>,
+    weight: u32,
+    /// Configured tier, or `default` (metric label)
+    tier: String,
+}
+
+impl Share {
+    fn new(config: &FairQueueConfig, client: &QueueClient) -> Self {
+        let tier = client
+            .tier
+            .as_ref()
+            .and_then(|tier| config.tiers.get_key_value(tier));
+        Self {
+            client: Arc::from(client.id.as_str()),
+            weight: tier.map_or(config.default_weight, |(_, w)| *w).max(1),
+            tier: tier.map_or_else(|| "default".to_string(), |(name, _)| name.clone()),
+        }
+    }
+}
+
+/// A request waiting for a slot.
+struct Waiter {
+    id: u64,
+    share: Option<Share>,
+    /// Passed over for another client's request at least once
+    deferred: bool,
+    /// Receives the slot, or the reason the request was shed
+    grant: oneshot::Sender<Result<(), ShedReason>>,
+}
+
+struct State {
+    config: UpstreamQueueConfig,
+    in_flight: u32,
+    /// Waiting requests per class, highest class first
+    waiting: [VecDeque<Waiter>; 4],
+    /// Requests in flight per client (fair queuing)
+    clients: HashMap<Arc<str>, u32>,
+    next_id: u64,
+}
+
+impl State {
+    fn queued(&self) -> usize {
+        self.waiting.iter().map(VecDeque::len).sum()
+    }
+
+    /// Index of the request of `class` to admit next: the oldest, or with
+    /// fair queuing the oldest of the client with the fewest requests in
+    /// flight per unit of weight
+    fn next_waiter(&self, class: PriorityClass) -> Option<usize> {
+        let waiting = &self.waiting[rank(class)];
+        if waiting.is_empty() {
+            return None;
+        }
+        if self.config.fair.is_none() {
+            return Some(0);
+        }
+        waiting
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                let (a_load, a_weight) = self.load(a.share.as_ref());
+                let (b_load, b_weight) = self.load(b.share.as_ref());
+                (a_load * b_weight).cmp(&(b_load * a_weight))
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Requests in flight and weight of a request's client
+    fn load(&self, share: Option<&Share>) -> (u64, u64) {
+        share.map_or((0, 1), |share| {
+            let in_flight = self.clients.get(&share.client).copied().unwrap_or(0);
+            (u64::from(in_flight), u64::from(share.weight))
+        })
+    }
+
+    fn take(&mut self, share: Option<&Share>) {
+        if let Some(share) = share {
+            *self.clients.entry(Arc::clone(&share.client)).or_default() += 1;
+        }
+    }
+
+    fn give_back(&mut self, share: Option<&Share>) {
+        let Some(share) = share else {
+            return;
+        };
+        if let Some(in_flight) = self.clients.get_mut(&share.client) {
+            *in_flight -= 1;
+            if *in_flight == 0 {
+                self.clients.remove(&share.client);
+            }
+        }
+    }
+}
+
+/// Limit and queue of one upstream.
+struct UpstreamQueue {
+    upstream: String,
+    state: Mutex<State>,
+}
+
+impl UpstreamQueue {
+    fn new(upstream: &str, config: &UpstreamQueueConfig) -> Self {
+        Self {
+            upstream: upstream.to_string(),
+            state: Mutex::new(State {
+                config: config.clone(),
+                in_flight: 0,
+                waiting: Default::default(),
+                clients: HashMap::new(),
+                next_id: 0,
+            }),
+        }
+    }
+
+    fn set_config(&self, config: &UpstreamQueueConfig) {
+        let mut state = self.state.lock();
+        if state.config == *config {
+            return;
+        }
+        state.config = config.clone();
+        // Admit waiting requests up to a raised limit
+        while state.in_flight < state.config.max_in_flight {
+            if !self.hand_over(&mut state) {
+                break;
+            }
+            state.in_flight += 1;
+        }
+        self.report_in_flight(state.in_flight);
+        self.report_clients(&state);
+    }
+
+    async fn acquire(
+        self: &Arc<Self>,
+        class: PriorityClass,
+        client: Option<&QueueClient>,
+    ) -> Result<QueuePermit, ShedReason> {
+        let (id, receiver, timeout, share) = {
+            let mut state = self.state.lock();
+            let share = state
+                .config
+                .fair
+                .as_ref()
+                .zip(client)
+                .map(|(fair, client)| Share::new(fair, client));
+            if state.in_flight < state.config.max_in_flight && state.queued() == 0 {
+                state.in_flight += 1;
+                state.take(share.as_ref());
+                self.report_in_flight(state.in_flight);
+                self.report_clients(&state);
+                return Ok(self.permit(share));
+            }
+
+            if state.queued() >= state.config.max_depth as usize {
+                // Make room by displacing the newest request of the lowest
+                // class below this one
+                let displaced = PriorityClass::ALL
+                    .into_iter()
+                    .rev()
+                    .take_while(|lower| *lower < class)
+                    .find_map(|lower| {
+                        let waiter = state.waiting[rank(lower)].pop_back()?;
+                        Some((lower, waiter))
+                    });
+                let Some((lower, waiter)) = displaced else {
+                    self.record_shed(class, ShedReason::QueueFull);
+                    return Err(ShedReason::QueueFull);
+                };
+                self.report_depth(&state, lower);
+                self.record_shed(lower, ShedReason::Displaced);
+                let _ = waiter.grant.send(Err(ShedReason::Displaced));
+            }
+
+            let id = state.next_id;
+            state.next_id += 1;
+            let (grant, receiver) = oneshot::channel();
+            state.waiting[rank(class)].push_back(Waiter {
+                id,
+                share: share.clone(),
+                deferred: false,
+                grant,
+            });
+            self.report_depth(&state, class);
+            let timeout = Duration::from_millis(state.config.timeout_ms);
+            (id, receiver, timeout, share)
+        };
+
+        let mut waiting = Waiting {
+            queue: Arc::clone(self),
+            class,
+            id,
+            share,
+            receiver,
+            done: false,
+        };
+        let granted = match tokio::time::timeout(timeout, &mut waiting.receiver).await {
+            Ok(result) => result.unwrap_or(Err(ShedReason::Displaced)),
+            Err(_) => {
+                if waiting.leave() {
+                    self.record_shed(class, ShedReason::Timeout);
+                    Err(ShedReason::Timeout)
+                } else {
+                    // Granted or displaced just as the wait timed out
+                    waiting
+                        .receiver
+                        .try_recv()
+                        .unwrap_or(Err(ShedReason::Timeout))
+                }
+            }
+        };
+        waiting.done = true;
+        granted.map(|()| self.permit(waiting.share.take()))
+    }
+
+    fn permit(self: &Arc<Self>, share: Option<Share>) -> QueuePermit {
+        QueuePermit {
+            queue: Arc::clone(self),
+            share,
+        }
+    }
+
+    /// Free a slot, handing it to the next waiting request if there is one.
+    fn release(&self, share: Option<&Share>) {
+        let mut state = self.state.lock();
+        state.give_back(share);
+        if state.in_flight > state.config.max_in_flight || !self.hand_over(&mut state) {
+            state.in_flight -= 1;
+            self.report_in_flight(state.in_flight);
+        }
+        self.report_clients(&state);
+    }
+
+    /// Grant a slot to the next waiting request of the highest class.
+    fn hand_over(&self, state: &mut State) -> bool {
+        for class in PriorityClass::ALL {
+            while let Some(next) = state.next_waiter(class) {
+                let waiting = &mut state.waiting[rank(class)];
+                // Older requests passed over for a less busy client
+                for passed in waiting.iter_mut().take(next).filter(|w| !w.deferred) {
+                    passed.deferred = true;
+                    self.record_throttled(passed.share.as_ref());
+                }
+                let Some(waiter) = waiting.remove(next) else {
+                    break;
+                };
+                self.report_depth(state, class);
+                if waiter.grant.send(Ok(())).is_ok() {
+                    state.take(waiter.share.as_ref());
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn report_in_flight(&self, in_flight: u32) {
+        if let Some(metrics) = QUEUE_METRICS.as_ref() {
+            metrics
+                .in_flight
+                .with_label_values(&[&self.upstream])
+                .set(in_flight as i64);
+        }
+    }
+
+    fn report_depth(&self, state: &State, class: PriorityClass) {
+        if let Some(metrics) = QUEUE_METRICS.as_ref() {
+            metrics
+                .depth
+                .with_label_values(&[&self.upstream, class.as_str()])
+                .set(state.waiting[rank(class)].len() as i64);
+        }
+    }
+
+    fn record_shed(&self, class: PriorityClass, reason: ShedReason) {
+        if let Some(metrics) = QUEUE_METRICS.as_ref() {
+            metrics
+                .shed
+                .with_label_values(&[&self.upstream, class.as_str(), reason.as_str()])
+                .inc();
+        }
+    }
+
+    fn record_throttled(&self, share: Option<&Share>) {
+        if let Some(metrics) = QUEUE_METRICS.as_ref() {
+            let tier = share.map_or("default", |share| share.tier.as_str());
+            metrics
+                .throttled
+                .with_label_values(&[&self.upstream, tier])
+                .inc();
+        }
+    }
+
+    fn report_clients(&self, state: &State) {
+        if state.config.fair.is_none() {
+            return;
+        }
+        if let Some(metrics) = QUEUE_METRICS.as_ref() {
+            metrics
+                .clients
+                .with_label_values(&[&self.upstream])
+                .set(state.clients.len() as i64);
+        }
+    }
+}
+
+/// A queued request; leaves the queue when dropped before it is answered.
+struct Waiting {
+    queue: Arc<UpstreamQueue>,
+    class: PriorityClass,
+    id: u64,
+    share: Option<Share>,
+    receiver: oneshot::Receiver<Result<(), ShedReason>>,
+    done: bool,
+}
+
+impl Waiting {
+    /// Remove the request from the queue; false if it was already answered.
+    fn leave(&self) -> bool {
+        let mut state = self.queue.state.lock();
+        let waiting = &mut state.waiting[rank(self.class)];
+        let Some(position) = waiting.iter().position(|w| w.id == self.id) else {
+            return false;
+        };
+        waiting.remove(position);
+        self.queue.report_depth(&state, self.class);
+        true
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if self.done || self.leave() {
+            return;
+        }
+        // The request went away after being granted a slot; pass it on
+        if let Ok(Ok(())) = self.receiver.try_recv() {
+            self.queue.release(self.share.as_ref());
+        }
+    }
+}
+
+/// Slot of a request in an upstream's in-flight limit, released on drop.
+pub struct QueuePermit {
+    queue: Arc<UpstreamQueue>,
+    share: Option<Share>,
+}
+
+impl QueuePermit {
+    /// Upstream the slot belongs to
+    pub fn upstream(&self) -> &str {
+        &self.queue.upstream
+    }
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        self.queue.release(self.share.as_ref());
+    }
+}
+
+/// In-flight limits and request queues of all upstreams with a `queue`.
+#[derive(Default)]
+pub struct UpstreamQueueManager {
+    queues: RwLock<HashMap<String, Arc<UpstreamQueue>>>,
+}
+
+impl UpstreamQueueManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for a slot of `upstream`, queuing the request with its route's
+    /// priority class when the upstream is at its limit. `client` is only
+    /// used with fair queuing.
+    pub async fn admit(
+        &self,
+        upstream: &str,
+        config: &UpstreamQueueConfig,
+        class: PriorityClass,
+        client: Option<&QueueClient>,
+    ) -> Result<QueuePermit, ShedReason> {
+        self.queue(upstream, config).acquire(class, client).await
+    }
+
+    fn queue(&self, upstream: &str, config: &UpstreamQueueConfig) -> Arc<UpstreamQueue> {
+        if let Some(queue) = self.queues.read().get(upstream) {
+            queue.set_config(config);
+            return Arc::clone(queue);
+        }
+        let mut queues = self.queues.write();
+        let queue = queues.entry(upstream.to_string()).or_insert_with(|| {
+            info!(
+                upstream = %upstream,
+                max_in_flight = config.max_in_flight,
+                max_depth = config.max_depth,
+                "Upstream request queue configured"
+            );
+            Arc::new(UpstreamQueue::new(upstream, config))
+        });
+        queue.set_config(config);
+        Arc::clone(queue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::task::JoinHandle;
+
+    fn queue_config(max_in_flight: u32, max_depth: u32, timeout_ms: u64) -> UpstreamQueueConfig {
+        UpstreamQueueConfig {
+            max_in_flight,
+            max_depth,
+            timeout_ms,
+            retry_after_secs: None,
+            fair: None,
+        }
+    }
+
+    fn fair_config(max_in_flight: u32) -> UpstreamQueueConfig {
+        UpstreamQueueConfig {
+            fair: Some(FairQueueConfig {
+                client_key: FairQueueKey::ApiKey,
+                default_weight: 1,
+                tiers: [("gold".to_string(), 2)].into(),
+            }),
+            ..queue_config(max_in_flight, 10, 5000)
+        }
+    }
+
+    fn client(id: &str, tier: Option<&str>) -> Option<QueueClient> {
+        Some(QueueClient {
+            id: id.to_string(),
+            tier: tier.map(str::to_string),
+        })
+    }
+
+    fn spawn_admit(
+        manager: &Arc<UpstreamQueueManager>,
+        config: &UpstreamQueueConfig,
+        class: PriorityClass,
+        client: Option<QueueClient>,
+    ) -> JoinHandle<Result<QueuePermit, ShedReason>> {
+        let manager = Arc::clone(manager);
+        let config = config.clone();
+        tokio::spawn(async move { manager.admit("api", &config, class, client.as_ref()).await })
+    }
+
+    async fn wait_queued(manager: &UpstreamQueueManager, queued: usize) {
+        let queue = manager.queues.read().get("api").cloned().unwrap();
+        while queue.state.lock().queued() != queued {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admits_highest_class_first() {
+        let manager = Arc::new(UpstreamQueueManager::new());
+        let config = queue_config(1, 10, 5000);
+        let first = manager
+            .admit("api", &config, PriorityClass::Normal, None)
+            .await
+            .unwrap();
+        assert_eq!(first.upstream(), "api");
+
+        let low = spawn_admit(&manager, &config, PriorityClass::Low, None);
+        wait_queued(&manager, 1).await;
+        let normal = spawn_admit(&manager, &config, PriorityClass::Normal, None);
+        wait_queued(&manager, 2).await;
+        let critical = spawn_admit(&manager, &config, PriorityClass::Critical, None);
+        wait_queued(&manager, 3).await;
+
+        drop(first);
+        let critical = critical.await.unwrap().unwrap();
+        assert!(!normal.is_finished() && !low.is_finished());
+
+        drop(critical);
+        let normal = normal.await.unwrap().unwrap();
+        drop(normal);
+        let low = low.await.unwrap().unwrap();
+
+        drop(low);
+        let queue = manager.queues.read().get("api").cloned().unwrap();
+        assert_eq!(queue.state.lock().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_sheds_lowest_class() {
+        let manager = Arc::new(UpstreamQueueManager::new());
+        let config = queue_config(1, 1, 5000);
+        let first = manager
+            .admit("api", &config, PriorityClass::Normal, None)
+            .await
+            .unwrap();
+
+        let low = spawn_admit(&manager, &config, PriorityClass::Low, None);
+        wait_queued(&manager, 1).await;
+        let high = spawn_admit(&manager, &config, PriorityClass::High, None);
+        assert!(matches!(low.await.unwrap(), Err(ShedReason::Displaced)));
+        wait_queued(&manager, 1).await;
+
+        // Nothing below normal left to displace
+        assert!(matches!(
+            manager
+                .admit("api", &config, PriorityClass::Normal, None)
+                .await,
+            Err(ShedReason::QueueFull)
+        ));
+
+        drop(first);
+        high.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_cancellation_leave_queue() {
+        let manager = Arc::new(UpstreamQueueManager::new());
+        let config = queue_config(1, 10, 20);
+        let first = manager
+            .admit("api", &config, PriorityClass::Normal, None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            manager
+                .admit("api", &config, PriorityClass::High, None)
+                .await,
+            Err(ShedReason::Timeout)
+        ));
+        wait_queued(&manager, 0).await;
+
+        let patient = queue_config(1, 10, 5000);
+        let cancelled = spawn_admit(&manager, &patient, PriorityClass::High, None);
+        wait_queued(&manager, 1).await;
+        cancelled.abort();
+        let _ = cancelled.await;
+        wait_queued(&manager, 0).await;
+
+        // Raising the limit admits without waiting
+        drop(first);
+        let _a = manager
+            .admit("api", &config, PriorityClass::Low, None)
+            .await
+            .unwrap();
+        let raised = queue_config(2, 10, 20);
+        let _b = manager
+            .admit("api", &raised, PriorityClass::Low, None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fair_queuing_prefers_less_busy_clients() {
+        let manager = Arc::new(UpstreamQueueManager::new());
+        let config = fair_config(2);
+        let normal = PriorityClass::Normal;
+        let heavy = client("heavy", None);
+        let light = client("light", None);
+
+        let first = manager
+            .admit("api", &config, normal, heavy.as_ref())
+            .await
+            .unwrap();
+        let second = manager
+            .admit("api", &config, normal, heavy.as_ref())
+            .await
+            .unwrap();
+
+        let heavy_waiting = spawn_admit(&manager, &config, normal, heavy.clone());
+        wait_queued(&manager, 1).await;
+        let light_waiting = spawn_admit(&manager, &config, normal, light.clone());
+        wait_queued(&manager, 2).await;
+
+        // The light client goes first although it queued last
+        drop(first);
+        let _light = light_waiting.await.unwrap().unwrap();
+        assert!(!heavy_waiting.is_finished());
+
+        let queue = manager.queues.read().get("api").cloned().unwrap();
+        {
+            let state = queue.state.lock();
+            assert_eq!(state.clients.len(), 2);
+            assert!(state.waiting[rank(normal)][0].deferred);
+        }
+        drop(second);
+        heavy_waiting.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fair_queuing_weights_tiers() {
+        let manager = Arc::new(UpstreamQueueManager::new());
+        let config = fair_config(3);
+        let normal = PriorityClass::Normal;
+        let gold = client("gold-key", Some("gold"));
+        let free = client("free-key", Some("free"));
+
+        let mut gold_permits = Vec::new();
+        for _ in 0..2 {
+            let permit = manager.admit("api", &config, normal, gold.as_ref()).await;
+            gold_permits.push(permit.unwrap());
+        }
+        let _free_permit = manager
+            .admit("api", &config, normal, free.as_ref())
+            .await
+            .unwrap();
+
+        let free_waiting = spawn_admit(&manager, &config, normal, free.clone());
+        wait_queued(&manager, 1).await;
+        let gold_waiting = spawn_admit(&manager, &config, normal, gold.clone());
+        wait_queued(&manager, 2).await;
+
+        // One gold request in flight at weight 2 is less busy than one free
+        // request at weight 1
+        drop(gold_permits.pop());
+        gold_waiting.await.unwrap().unwrap();
+        assert!(!free_waiting.is_finished());
+    }
+}
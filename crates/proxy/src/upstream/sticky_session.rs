@@ -340,10 +340,18 @@ impl LoadBalancer for StickySessionBalancer {
     }
 
     async fn release(&self, selection: &TargetSelection) {
+        // Cookie hits never went through the fallback, so there is nothing to release
+        if selection.metadata.contains_key("sticky_session_hit") {
+            return;
+        }
         // Delegate to fallback balancer
         self.fallback.release(selection).await;
     }
 
+    fn uses_request_context(&self) -> bool {
+        true
+    }
+
     async fn report_result(
         &self,
         selection: &TargetSelection,
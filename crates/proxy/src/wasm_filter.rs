@@ -0,0 +1,455 @@
+//! Embedded WASM filters for Zentinel proxy
+//!
+//! Runs small WebAssembly components in-process for transformations that do
+//! not justify an external agent. Components implement the same
+//! `zentinel:agent` interface as WASM agents and receive the same events:
+//! request headers, request body chunks and response headers.
+//!
+//! # Limits
+//! - Every call runs with a fresh `max-fuel` budget; a component that runs
+//!   out of fuel traps and the filter's failure mode applies
+//! - Linear memory growth beyond `max-memory-mb` fails inside the component
+//! - Each filter keeps a fixed pool of instances; calls are spread across
+//!   them round-robin and serialize on an instance's store
+//!
+//! Requires the `wasm-filters` feature. Without it, registering a WASM filter
+//! fails so that startup rejects configurations that use them.
+//!
+//! # Configuration
+//!
+//! ```kdl
+//! filters {
+//!     filter "tag-tenant" {
+//!         type "wasm"
+//!         module "/etc/zentinel/filters/tag-tenant.wasm"
+//!         config "{\"header\": \"x-tenant\"}"
+//!         events "request-headers" "response-headers"
+//!         max-memory-mb 16
+//!         max-fuel 1000000
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use zentinel_agent_protocol::{AgentResponse, RequestMetadata};
+use zentinel_config::{FailureMode, WasmFilter};
+
+/// Errors from loading or calling a WASM filter
+#[derive(Debug, Error)]
+pub enum WasmFilterError {
+    /// The proxy was built without the `wasm-filters` feature
+    #[error("WASM filters require the 'wasm-filters' feature")]
+    Disabled,
+
+    /// The component could not be compiled, instantiated or configured
+    #[error("failed to load WASM filter: {0}")]
+    Load(String),
+
+    /// The component trapped, ran out of fuel or exceeded its memory limit
+    #[error("WASM filter call failed: {0}")]
+    Call(String),
+}
+
+// =============================================================================
+// Component Instances
+// =============================================================================
+
+#[cfg(feature = "wasm-filters")]
+mod wasm_impl {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use zentinel_wasm_runtime::{
+        WasmAgentConfig, WasmAgentInstance, WasmAgentRuntime, WasmResourceLimits,
+    };
+
+    /// Compiled component with its instance pool
+    pub struct WasmModule {
+        /// Owns the engine and compiled component; dropping it shuts the
+        /// instances down
+        _runtime: WasmAgentRuntime,
+        instances: Vec<Arc<WasmAgentInstance>>,
+        next: AtomicUsize,
+    }
+
+    impl WasmModule {
+        pub fn load(filter_id: &str, config: &WasmFilter) -> Result<Self, WasmFilterError> {
+            let runtime_config = WasmAgentConfig {
+                limits: WasmResourceLimits {
+                    max_memory: config.max_memory_mb * 1024 * 1024,
+                    max_fuel: config.max_fuel,
+                    ..Default::default()
+                },
+                fuel_enabled: true,
+                epoch_enabled: false,
+                cache_enabled: false,
+                max_instances: config.instances as u32,
+                ..Default::default()
+            };
+            let runtime = WasmAgentRuntime::new(runtime_config)
+                .map_err(|e| WasmFilterError::Load(e.to_string()))?;
+            runtime
+                .compile_component_file(filter_id, &config.module)
+                .map_err(|e| WasmFilterError::Load(e.to_string()))?;
+
+            let config_json = config.config.as_deref().unwrap_or("{}");
+            let instances = (0..config.instances)
+                .map(|i| runtime.load_agent(&format!("{filter_id}#{i}"), filter_id, config_json))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| WasmFilterError::Load(e.to_string()))?;
+
+            Ok(Self {
+                _runtime: runtime,
+                instances,
+                next: AtomicUsize::new(0),
+            })
+        }
+
+        fn instance(&self) -> &WasmAgentInstance {
+            let i = self.next.fetch_add(1, Ordering::Relaxed) % self.instances.len();
+            &self.instances[i]
+        }
+
+        pub fn on_request_headers(
+            &self,
+            metadata: &RequestMetadata,
+            method: &str,
+            uri: &str,
+            headers: &HashMap<String, Vec<String>>,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            self.instance()
+                .on_request_headers(metadata, method, uri, headers)
+                .map_err(|e| WasmFilterError::Call(e.to_string()))
+        }
+
+        pub fn on_request_body(
+            &self,
+            correlation_id: &str,
+            data: &[u8],
+            chunk_index: u32,
+            is_last: bool,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            self.instance()
+                .on_request_body(correlation_id, data, chunk_index, is_last)
+                .map_err(|e| WasmFilterError::Call(e.to_string()))
+        }
+
+        pub fn on_response_headers(
+            &self,
+            correlation_id: &str,
+            status: u16,
+            headers: &HashMap<String, Vec<String>>,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            self.instance()
+                .on_response_headers(correlation_id, status, headers)
+                .map_err(|e| WasmFilterError::Call(e.to_string()))
+        }
+    }
+}
+
+#[cfg(not(feature = "wasm-filters"))]
+mod wasm_impl {
+    use super::*;
+
+    pub struct WasmModule;
+
+    impl WasmModule {
+        pub fn load(_filter_id: &str, _config: &WasmFilter) -> Result<Self, WasmFilterError> {
+            Err(WasmFilterError::Disabled)
+        }
+
+        pub fn on_request_headers(
+            &self,
+            _metadata: &RequestMetadata,
+            _method: &str,
+            _uri: &str,
+            _headers: &HashMap<String, Vec<String>>,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            Err(WasmFilterError::Disabled)
+        }
+
+        pub fn on_request_body(
+            &self,
+            _correlation_id: &str,
+            _data: &[u8],
+            _chunk_index: u32,
+            _is_last: bool,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            Err(WasmFilterError::Disabled)
+        }
+
+        pub fn on_response_headers(
+            &self,
+            _correlation_id: &str,
+            _status: u16,
+            _headers: &HashMap<String, Vec<String>>,
+        ) -> Result<AgentResponse, WasmFilterError> {
+            Err(WasmFilterError::Disabled)
+        }
+    }
+}
+
+use wasm_impl::WasmModule;
+
+// =============================================================================
+// WasmFilterPool
+// =============================================================================
+
+/// A loaded WASM filter
+pub struct WasmFilterPool {
+    module: WasmModule,
+    config: WasmFilter,
+}
+
+impl WasmFilterPool {
+    /// Compile and instantiate the filter's component
+    pub fn new(filter_id: &str, config: WasmFilter) -> Result<Self, WasmFilterError> {
+        let module = WasmModule::load(filter_id, &config)?;
+        Ok(Self { module, config })
+    }
+
+    /// Filter configuration
+    pub fn config(&self) -> &WasmFilter {
+        &self.config
+    }
+
+    /// Run the component on request headers
+    pub fn on_request_headers(
+        &self,
+        metadata: &RequestMetadata,
+        method: &str,
+        uri: &str,
+        headers: &HashMap<String, Vec<String>>,
+    ) -> AgentResponse {
+        let result = self
+            .module
+            .on_request_headers(metadata, method, uri, headers);
+        self.settle(&metadata.correlation_id, result)
+    }
+
+    /// Run the component on a request body chunk
+    pub fn on_request_body(
+        &self,
+        correlation_id: &str,
+        data: &[u8],
+        chunk_index: u32,
+        is_last: bool,
+    ) -> AgentResponse {
+        let result = self
+            .module
+            .on_request_body(correlation_id, data, chunk_index, is_last);
+        self.settle(correlation_id, result)
+    }
+
+    /// Run the component on response headers
+    pub fn on_response_headers(
+        &self,
+        correlation_id: &str,
+        status: u16,
+        headers: &HashMap<String, Vec<String>>,
+    ) -> AgentResponse {
+        let result = self
+            .module
+            .on_response_headers(correlation_id, status, headers);
+        self.settle(correlation_id, result)
+    }
+
+    /// Apply the failure mode to a failed call
+    fn settle(
+        &self,
+        correlation_id: &str,
+        result: Result<AgentResponse, WasmFilterError>,
+    ) -> AgentResponse {
+        match result {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    correlation_id = %correlation_id,
+                    module = %self.config.module,
+                    failure_mode = ?self.config.failure_mode,
+                    error = %e,
+                    "WASM filter failed"
+                );
+                failure_response(self.config.failure_mode)
+            }
+        }
+    }
+}
+
+// =============================================================================
+// WasmFilterManager
+// =============================================================================
+
+/// Manages all WASM filter instances
+pub struct WasmFilterManager {
+    /// Filter ID → WasmFilterPool mapping
+    filter_pools: DashMap<String, Arc<WasmFilterPool>>,
+}
+
+impl WasmFilterManager {
+    /// Create a new empty WASM filter manager
+    pub fn new() -> Self {
+        Self {
+            filter_pools: DashMap::new(),
+        }
+    }
+
+    /// Load a WASM filter from configuration
+    pub fn register_filter(
+        &self,
+        filter_id: &str,
+        config: WasmFilter,
+    ) -> Result<(), WasmFilterError> {
+        let pool = WasmFilterPool::new(filter_id, config)?;
+        self.filter_pools
+            .insert(filter_id.to_string(), Arc::new(pool));
+        debug!(filter_id = %filter_id, "Registered WASM filter");
+        Ok(())
+    }
+
+    /// Get a reference to a filter pool
+    pub fn get_pool(&self, filter_id: &str) -> Option<Arc<WasmFilterPool>> {
+        self.filter_pools.get(filter_id).map(|r| r.clone())
+    }
+
+    /// Run a configured filter's component
+    ///
+    /// A filter whose component failed to load on reload has no pool; its
+    /// failure mode decides the outcome.
+    pub fn call(
+        &self,
+        filter_id: &str,
+        config: &WasmFilter,
+        f: impl FnOnce(&WasmFilterPool) -> AgentResponse,
+    ) -> AgentResponse {
+        match self.get_pool(filter_id) {
+            Some(pool) => f(&pool),
+            None => {
+                warn!(
+                    filter_id = %filter_id,
+                    failure_mode = ?config.failure_mode,
+                    "WASM filter is not loaded"
+                );
+                failure_response(config.failure_mode)
+            }
+        }
+    }
+
+    /// Check if a filter exists
+    pub fn has_filter(&self, filter_id: &str) -> bool {
+        self.filter_pools.contains_key(filter_id)
+    }
+
+    /// Get all filter IDs
+    pub fn filter_ids(&self) -> Vec<String> {
+        self.filter_pools.iter().map(|r| r.key().clone()).collect()
+    }
+
+    /// Reload filters from a new configuration
+    ///
+    /// Filters whose configuration is unchanged keep their instances.
+    /// A filter that fails to load keeps its previous instances, if any.
+    pub fn reload(&self, filters: &HashMap<String, WasmFilter>) {
+        self.filter_pools.retain(|id, _| filters.contains_key(id));
+
+        for (filter_id, config) in filters {
+            let unchanged = self
+                .filter_pools
+                .get(filter_id)
+                .is_some_and(|pool| same_filter(pool.config(), config));
+            if unchanged {
+                continue;
+            }
+            if let Err(e) = self.register_filter(filter_id, config.clone()) {
+                warn!(
+                    filter_id = %filter_id,
+                    error = %e,
+                    "Failed to reload WASM filter"
+                );
+            }
+        }
+    }
+}
+
+impl Default for WasmFilterManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Response standing in for a failed component call
+fn failure_response(failure_mode: FailureMode) -> AgentResponse {
+    match failure_mode {
+        FailureMode::Open => AgentResponse::default_allow(),
+        FailureMode::Closed => AgentResponse::block(503, Some("Service unavailable".to_string())),
+    }
+}
+
+/// Whether two filter configurations load the same component the same way
+fn same_filter(a: &WasmFilter, b: &WasmFilter) -> bool {
+    a.module == b.module
+        && a.config == b.config
+        && a.events == b.events
+        && a.max_memory_mb == b.max_memory_mb
+        && a.max_fuel == b.max_fuel
+        && a.instances == b.instances
+        && a.failure_mode == b.failure_mode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zentinel_agent_protocol::Decision;
+
+    #[cfg(not(feature = "wasm-filters"))]
+    #[test]
+    fn test_register_requires_feature() {
+        let manager = WasmFilterManager::new();
+        let err = manager
+            .register_filter("tag", WasmFilter::new("/tmp/tag.wasm"))
+            .unwrap_err();
+        assert!(matches!(err, WasmFilterError::Disabled));
+        assert!(!manager.has_filter("tag"));
+    }
+
+    #[cfg(feature = "wasm-filters")]
+    #[test]
+    fn test_register_missing_module_fails() {
+        let manager = WasmFilterManager::new();
+        let err = manager
+            .register_filter("tag", WasmFilter::new("/nonexistent/tag.wasm"))
+            .unwrap_err();
+        assert!(matches!(err, WasmFilterError::Load(_)));
+    }
+
+    #[test]
+    fn test_same_filter() {
+        let a = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+        let mut b = a.clone();
+        assert!(same_filter(&a, &b));
+
+        b.max_fuel += 1;
+        assert!(!same_filter(&a, &b));
+    }
+
+    #[test]
+    fn test_call_without_pool_applies_failure_mode() {
+        let manager = WasmFilterManager::new();
+        let mut config = WasmFilter::new("/etc/zentinel/filters/tag.wasm");
+
+        let response = manager.call("tag", &config, |_| unreachable!());
+        assert!(matches!(
+            response.decision,
+            Decision::Block { status: 503, .. }
+        ));
+
+        config.failure_mode = FailureMode::Open;
+        let response = manager.call("tag", &config, |_| unreachable!());
+        assert!(matches!(response.decision, Decision::Allow));
+    }
+}
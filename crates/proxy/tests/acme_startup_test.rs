@@ -79,6 +79,7 @@ mod storage_resolver_integration {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: Some(acme_config(temp_dir.path().to_path_buf())),
@@ -315,6 +316,7 @@ mod validate_acme_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: Some(acme_config(temp_dir.path().to_path_buf())),
@@ -340,6 +342,7 @@ mod validate_acme_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
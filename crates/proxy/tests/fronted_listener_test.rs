@@ -0,0 +1,185 @@
+//! Integration tests for fronted listeners.
+//!
+//! HTTPS listeners reach Pingora through the fronting acceptor on an
+//! internal Unix socket; these tests check that per-listener settings still
+//! apply to requests arriving that way.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+use zentinel_config::Config;
+use zentinel_proxy::embed::ZentinelBuilder;
+
+/// Get the path to the test fixtures directory
+fn fixtures_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/tls")
+}
+
+/// A free local port for the proxy listener
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Start an upstream answering every request with `200 ok`, keeping the
+/// connection open.
+async fn start_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                loop {
+                    let Ok(n) = stream.read(&mut chunk).await else {
+                        return;
+                    };
+                    if n == 0 {
+                        return;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    while let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        buf.drain(..end + 4);
+                        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                        if stream.write_all(response).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    address
+}
+
+/// Configuration with one HTTPS listener on `port` proxying to `upstream`
+fn https_config(port: u16, upstream: &str) -> Config {
+    let fixtures = fixtures_path();
+    let kdl = format!(
+        r#"
+        schema-version "1.0"
+        listeners {{
+            listener "edge" {{
+                address "127.0.0.1:{port}"
+                protocol "https"
+                keepalive-timeout-secs 1
+                hardening {{
+                    allowed-methods "GET"
+                }}
+                tls {{
+                    cert-file "{cert}"
+                    key-file "{key}"
+                }}
+            }}
+        }}
+        routes {{
+            route "default" {{
+                matches {{ path-prefix "/" }}
+                upstream "backend"
+            }}
+        }}
+        upstreams {{
+            upstream "backend" {{ target "{upstream}" }}
+        }}
+        "#,
+        cert = fixtures.join("server-default.crt").display(),
+        key = fixtures.join("server-default.key").display(),
+    );
+    Config::from_kdl(&kdl).unwrap()
+}
+
+/// Open a TLS connection to the proxy, retrying until it listens
+async fn connect(port: u16) -> TlsStream<TcpStream> {
+    let ca = std::fs::read(fixtures_path().join("ca.crt")).unwrap();
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut &ca[..]) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let client_config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = TlsConnector::from(Arc::new(client_config));
+
+    let tcp = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match TcpStream::connect(("127.0.0.1", port)).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+    })
+    .await
+    .expect("proxy listening");
+    let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+    connector.connect(server_name, tcp).await.unwrap()
+}
+
+/// Read one response head and its `Content-Length` body
+async fn read_response<S: AsyncRead + Unpin>(stream: &mut S) -> String {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).to_string();
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if buf.len() >= end + 4 + length {
+                return head;
+            }
+        }
+        let n = stream.read(&mut chunk).await.unwrap();
+        assert!(n > 0, "connection closed before the response");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fronted_https_listener_applies_listener_settings() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    let upstream = start_upstream().await;
+    let port = free_port();
+    let zentinel = ZentinelBuilder::new()
+        .config(https_config(port, &upstream))
+        .worker_threads(1)
+        .start()
+        .await
+        .unwrap();
+
+    // Hardening: methods outside `allowed-methods` are rejected
+    let mut client = connect(port).await;
+    client
+        .write_all(b"DELETE / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let head = read_response(&mut client).await;
+    assert!(head.starts_with("HTTP/1.1 405"), "{head}");
+
+    // Keepalive timeout: the idle connection is closed after one second
+    let mut client = connect(port).await;
+    client
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let head = read_response(&mut client).await;
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    let mut rest = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), client.read_to_end(&mut rest))
+        .await
+        .expect("idle connection closed by the listener keepalive timeout")
+        .ok();
+
+    zentinel.stop().await;
+}
@@ -16,8 +16,9 @@ use zentinel_agent_protocol::{
     AgentResponse, AuditMetadata, Decision, HeaderOp, RequestHeadersEvent, RequestMetadata,
 };
 use zentinel_common::CorrelationId;
-use zentinel_config::Config;
-use zentinel_proxy::agents::AgentDecision;
+use zentinel_config::{AgentTransport, Config, FailureMode};
+use zentinel_proxy::agents::{AgentAction, AgentDecision};
+use zentinel_proxy::{AgentCallContext, AgentManager};
 
 // ============================================================================
 // Test Agent Implementation
@@ -85,6 +86,26 @@ impl AgentHandlerV2 for BlockingAgent {
     }
 }
 
+/// Agent that challenges every request.
+struct ChallengingAgent;
+
+#[async_trait::async_trait]
+impl AgentHandlerV2 for ChallengingAgent {
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("challenging-agent", "Challenging Agent", "0.1.0")
+    }
+
+    async fn on_request_headers(&self, _event: RequestHeadersEvent) -> AgentResponse {
+        AgentResponse {
+            decision: Decision::Challenge {
+                challenge_type: "cookie".to_string(),
+                params: HashMap::new(),
+            },
+            ..AgentResponse::default_allow()
+        }
+    }
+}
+
 // ============================================================================
 // Configuration Integration Tests
 // ============================================================================
@@ -249,6 +270,10 @@ async fn test_agent_server_client_roundtrip() {
             upstream_id: Some("backend".to_string()),
             timestamp: chrono::Utc::now().to_rfc3339(),
             traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
         },
         method: "GET".to_string(),
         uri: "/api/users".to_string(),
@@ -312,6 +337,10 @@ async fn test_blocking_agent_rejects_request() {
             upstream_id: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
             traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
         },
         method: "GET".to_string(),
         uri: "/admin/secret".to_string(),
@@ -345,6 +374,10 @@ async fn test_blocking_agent_rejects_request() {
             upstream_id: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
             traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
         },
         method: "GET".to_string(),
         uri: "/api/users".to_string(),
@@ -362,6 +395,214 @@ async fn test_blocking_agent_rejects_request() {
     server_handle.abort();
 }
 
+#[tokio::test]
+async fn test_in_process_agent_bypasses_transport() {
+    let kdl_config = r#"
+        listeners {
+            listener "http" {
+                address "127.0.0.1:8080"
+                protocol "http"
+            }
+        }
+
+        upstreams {
+            upstream "backend" {
+                target "127.0.0.1:3000"
+            }
+        }
+
+        routes {
+            route "api" {
+                matches {
+                    path-prefix "/"
+                }
+                upstream "backend"
+            }
+        }
+
+        agents {
+            agent "blocker" type="custom" {
+                grpc address="http://127.0.0.1:1"
+                events "request_headers"
+                timeout-ms 100
+                failure-mode "closed"
+            }
+        }
+    "#;
+    let config = Config::from_kdl(kdl_config).expect("Config should parse");
+
+    // The gRPC address is never dialed: the handler runs in this process
+    let mut handlers: HashMap<String, Arc<dyn AgentHandlerV2>> = HashMap::new();
+    handlers.insert(
+        "blocker".to_string(),
+        Arc::new(BlockingAgent::new(vec!["/admin".to_string()])),
+    );
+    let manager = AgentManager::with_in_process_agents(config.agents.clone(), handlers)
+        .await
+        .expect("Manager should be created");
+    manager.initialize().await.unwrap();
+
+    let route_agents = vec![("blocker".to_string(), FailureMode::Closed)];
+    let mut decisions = Vec::new();
+    for (id, path) in [("in-process-1", "/admin/users"), ("in-process-2", "/api")] {
+        let metadata = RequestMetadata {
+            correlation_id: id.to_string(),
+            request_id: id.to_string(),
+            client_ip: "127.0.0.1".to_string(),
+            client_port: 12345,
+            server_name: None,
+            protocol: "HTTP/1.1".to_string(),
+            tls_version: None,
+            tls_cipher: None,
+            route_id: Some("api".to_string()),
+            upstream_id: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            traceparent: None,
+            client_cert: None,
+            tags: Vec::new(),
+            bot_signals: None,
+            dry_run: false,
+        };
+        let ctx = AgentCallContext::new(CorrelationId::from_string(id), metadata);
+        let headers = HashMap::from([(":path".to_string(), vec![path.to_string()])]);
+        let decision = manager
+            .process_request_headers(&ctx, headers, &route_agents)
+            .await
+            .expect("Agent should respond");
+        manager.end_request(id).await;
+        decisions.push(decision);
+    }
+
+    assert!(!decisions[0].is_allow());
+    assert!(decisions[1].is_allow());
+
+    // Handlers must belong to a configured agent
+    let mut unknown: HashMap<String, Arc<dyn AgentHandlerV2>> = HashMap::new();
+    unknown.insert("missing".to_string(), Arc::new(TestAgent::new("missing")));
+    assert!(
+        AgentManager::with_in_process_agents(config.agents.clone(), unknown)
+            .await
+            .is_err()
+    );
+
+    // Agents declared `in-process` must have a handler
+    let mut declared = config.agents.clone();
+    declared[0].transport = AgentTransport::InProcess;
+    assert!(
+        AgentManager::with_in_process_agents(declared, HashMap::new())
+            .await
+            .is_err()
+    );
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_agents_after_passed_challenge_still_decide() {
+    let kdl_config = r#"
+        listeners {
+            listener "http" {
+                address "127.0.0.1:8080"
+                protocol "http"
+            }
+        }
+
+        upstreams {
+            upstream "backend" {
+                target "127.0.0.1:3000"
+            }
+        }
+
+        routes {
+            route "api" {
+                matches {
+                    path-prefix "/"
+                }
+                upstream "backend"
+            }
+        }
+
+        agents {
+            agent "challenger" type="custom" {
+                grpc address="http://127.0.0.1:1"
+                events "request_headers"
+                timeout-ms 100
+            }
+            agent "blocker" type="custom" {
+                grpc address="http://127.0.0.1:1"
+                events "request_headers"
+                timeout-ms 100
+            }
+        }
+    "#;
+    let config = Config::from_kdl(kdl_config).expect("Config should parse");
+
+    let mut handlers: HashMap<String, Arc<dyn AgentHandlerV2>> = HashMap::new();
+    handlers.insert("challenger".to_string(), Arc::new(ChallengingAgent));
+    handlers.insert(
+        "blocker".to_string(),
+        Arc::new(BlockingAgent::new(vec!["/admin".to_string()])),
+    );
+    let manager = AgentManager::with_in_process_agents(config.agents.clone(), handlers)
+        .await
+        .expect("Manager should be created");
+    manager.initialize().await.unwrap();
+
+    let route_agents = vec![
+        ("challenger".to_string(), FailureMode::Open),
+        ("blocker".to_string(), FailureMode::Open),
+    ];
+    let metadata = RequestMetadata {
+        correlation_id: "challenge-1".to_string(),
+        request_id: "challenge-1".to_string(),
+        client_ip: "127.0.0.1".to_string(),
+        client_port: 12345,
+        server_name: None,
+        protocol: "HTTP/1.1".to_string(),
+        tls_version: None,
+        tls_cipher: None,
+        route_id: Some("api".to_string()),
+        upstream_id: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        traceparent: None,
+        client_cert: None,
+        tags: Vec::new(),
+        bot_signals: None,
+        dry_run: false,
+    };
+    let ctx = AgentCallContext::new(CorrelationId::from_string("challenge-1"), metadata);
+    let headers = || HashMap::from([(":path".to_string(), vec!["/admin".to_string()])]);
+
+    // The challenge is final: the blocker's decision is not merged
+    let decision = manager
+        .process_request_headers(&ctx, headers(), &route_agents)
+        .await
+        .unwrap();
+    assert!(matches!(decision.action, AgentAction::Challenge { .. }));
+    assert_eq!(decision.decided_by.as_deref(), Some("challenger"));
+
+    // Once the challenge is passed, the blocker after it still blocks
+    let decision = manager
+        .process_request_headers_after(&ctx, headers(), &route_agents, "challenger")
+        .await
+        .unwrap();
+    assert!(matches!(
+        decision.action,
+        AgentAction::Block { status: 403, .. }
+    ));
+    assert_eq!(decision.decided_by.as_deref(), Some("blocker"));
+
+    // Nothing after the last agent
+    let decision = manager
+        .process_request_headers_after(&ctx, headers(), &route_agents, "blocker")
+        .await
+        .unwrap();
+    assert!(decision.is_allow());
+
+    manager.end_request("challenge-1").await;
+    manager.shutdown().await;
+}
+
 // ============================================================================
 // Decision Merging Tests
 // ============================================================================
@@ -238,6 +238,7 @@ mod tests {
             default_format: ErrorFormat::Json,
             include_stack_trace: false,
             template_dir: None,
+            problem: None,
         };
 
         let api_handler = ErrorHandler::new(ServiceType::Api, Some(api_error_config));
@@ -280,6 +281,7 @@ mod tests {
             default_format: ErrorFormat::Html,
             include_stack_trace: false,
             template_dir: None,
+            problem: None,
         };
 
         let web_handler = ErrorHandler::new(ServiceType::Web, Some(web_error_config));
@@ -317,6 +319,7 @@ mod tests {
             default_format: ErrorFormat::Text,
             include_stack_trace: false,
             template_dir: None,
+            problem: None,
         };
 
         let text_handler = ErrorHandler::new(ServiceType::Api, Some(text_config));
@@ -338,6 +341,7 @@ mod tests {
             default_format: ErrorFormat::Xml,
             include_stack_trace: false,
             template_dir: None,
+            problem: None,
         };
 
         let xml_handler = ErrorHandler::new(ServiceType::Api, Some(xml_config));
@@ -7,7 +7,7 @@
 use std::path::PathBuf;
 use std::sync::Once;
 
-use zentinel_config::UpstreamTlsConfig;
+use zentinel_config::{AlpnProtocol, UpstreamTlsConfig};
 use zentinel_proxy::tls::{build_upstream_tls_config, validate_upstream_tls_config, TlsError};
 
 static CRYPTO_PROVIDER_INIT: Once = Once::new();
@@ -37,6 +37,7 @@ fn minimal_upstream_config() -> UpstreamTlsConfig {
         client_cert: None,
         client_key: None,
         insecure_skip_verify: false,
+        ..Default::default()
     }
 }
 
@@ -49,6 +50,7 @@ fn custom_ca_upstream_config() -> UpstreamTlsConfig {
         client_cert: None,
         client_key: None,
         insecure_skip_verify: false,
+        ..Default::default()
     }
 }
 
@@ -61,6 +63,7 @@ fn mtls_upstream_config() -> UpstreamTlsConfig {
         client_cert: Some(fixtures.join("client.crt")),
         client_key: Some(fixtures.join("client.key")),
         insecure_skip_verify: false,
+        ..Default::default()
     }
 }
 
@@ -72,6 +75,7 @@ fn insecure_upstream_config() -> UpstreamTlsConfig {
         client_cert: None,
         client_key: None,
         insecure_skip_verify: true,
+        ..Default::default()
     }
 }
 
@@ -140,6 +144,7 @@ mod build_config {
             client_cert: None,
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = build_upstream_tls_config(&config);
@@ -162,6 +167,7 @@ mod build_config {
             client_cert: Some(fixtures.join("nonexistent-client.crt")),
             client_key: Some(fixtures.join("client.key")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = build_upstream_tls_config(&config);
@@ -178,11 +184,28 @@ mod build_config {
             client_cert: Some(fixtures.join("client.crt")),
             client_key: Some(fixtures.join("nonexistent-client.key")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = build_upstream_tls_config(&config);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_build_with_spiffe_ids_and_alpn() {
+        ensure_crypto_provider();
+        let config = UpstreamTlsConfig {
+            alpn: vec![AlpnProtocol::H2, AlpnProtocol::Http11],
+            spiffe_ids: vec!["spiffe://example.org/ns/prod/sa/api".to_string()],
+            ..mtls_upstream_config()
+        };
+
+        let client_config = build_upstream_tls_config(&config).unwrap();
+        assert_eq!(
+            client_config.alpn_protocols,
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        );
+    }
 }
 
 // ============================================================================
@@ -229,6 +252,7 @@ mod validation {
             client_cert: None,
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = validate_upstream_tls_config(&config);
@@ -250,6 +274,7 @@ mod validation {
             client_cert: Some(fixtures.join("nonexistent-client.crt")),
             client_key: Some(fixtures.join("client.key")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = validate_upstream_tls_config(&config);
@@ -271,6 +296,7 @@ mod validation {
             client_cert: Some(fixtures.join("client.crt")),
             client_key: Some(fixtures.join("nonexistent-client.key")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = validate_upstream_tls_config(&config);
@@ -292,6 +318,7 @@ mod validation {
             client_cert: Some(fixtures.join("client.crt")),
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = validate_upstream_tls_config(&config);
@@ -313,6 +340,7 @@ mod validation {
             client_cert: None,
             client_key: Some(fixtures.join("client.key")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = validate_upstream_tls_config(&config);
@@ -347,6 +375,7 @@ mod edge_cases {
             client_cert: None,
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         // Empty CA file should either fail to parse or produce empty root store
@@ -369,6 +398,7 @@ mod edge_cases {
             client_cert: None,
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         // Invalid content may or may not cause an error depending on parsing
@@ -388,6 +418,7 @@ mod edge_cases {
             client_cert: None,
             client_key: None,
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         let result = build_upstream_tls_config(&config);
@@ -408,6 +439,7 @@ mod edge_cases {
             client_cert: Some(fixtures.join("client.pem")),
             client_key: Some(fixtures.join("client.pem")),
             insecure_skip_verify: false,
+            ..Default::default()
         };
 
         // Combined PEM file should work for both cert and key
@@ -35,6 +35,7 @@ fn minimal_tls_config() -> TlsConfig {
         max_version: None,
         cipher_suites: vec![],
         client_auth: false,
+        client_cert_header: None,
         ocsp_stapling: false,
         session_resumption: true,
         acme: None,
@@ -54,6 +55,7 @@ fn multi_sni_tls_config() -> TlsConfig {
                 cert_file: Some(fixtures.join("server-api.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             },
             SniCertificate {
                 hostnames: vec!["secure.example.com".to_string()],
@@ -61,6 +63,7 @@ fn multi_sni_tls_config() -> TlsConfig {
                 cert_file: Some(fixtures.join("server-secure.crt")),
                 key_file: Some(fixtures.join("server-secure.key")),
                 acme: None,
+                namespace: None,
             },
         ],
         ca_file: None,
@@ -68,6 +71,7 @@ fn multi_sni_tls_config() -> TlsConfig {
         max_version: None,
         cipher_suites: vec![],
         client_auth: false,
+        client_cert_header: None,
         ocsp_stapling: false,
         session_resumption: true,
         acme: None,
@@ -86,12 +90,14 @@ fn wildcard_tls_config() -> TlsConfig {
             cert_file: Some(fixtures.join("server-wildcard.crt")),
             key_file: Some(fixtures.join("server-wildcard.key")),
             acme: None,
+            namespace: None,
         }],
         ca_file: None,
         min_version: zentinel_common::types::TlsVersion::Tls12,
         max_version: None,
         cipher_suites: vec![],
         client_auth: false,
+        client_cert_header: None,
         ocsp_stapling: false,
         session_resumption: true,
         acme: None,
@@ -110,6 +116,7 @@ fn mtls_tls_config() -> TlsConfig {
         max_version: None,
         cipher_suites: vec![],
         client_auth: true,
+        client_cert_header: None,
         ocsp_stapling: false,
         session_resumption: true,
         acme: None,
@@ -275,6 +282,7 @@ mod sni_resolver {
                     cert_file: Some(fixtures.join("server-wildcard.crt")),
                     key_file: Some(fixtures.join("server-wildcard.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec!["api.example.com".to_string()],
@@ -282,6 +290,7 @@ mod sni_resolver {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -289,6 +298,7 @@ mod sni_resolver {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -320,6 +330,7 @@ mod sni_resolver {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -345,6 +356,7 @@ mod sni_resolver {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -370,12 +382,14 @@ mod sni_resolver {
                 cert_file: Some(fixtures.join("nonexistent.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -406,12 +420,14 @@ mod sni_auto_extraction {
                 cert_file: Some(fixtures.join("server-api.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -463,12 +479,14 @@ mod sni_auto_extraction {
                 cert_file: Some(fixtures.join("server-wildcard.crt")),
                 key_file: Some(fixtures.join("server-wildcard.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -502,6 +520,7 @@ mod sni_auto_extraction {
                     cert_file: Some(fixtures.join("server-secure.crt")),
                     key_file: Some(fixtures.join("server-secure.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![], // Auto-extract from server-api.crt
@@ -509,6 +528,7 @@ mod sni_auto_extraction {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -516,6 +536,7 @@ mod sni_auto_extraction {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -547,6 +568,7 @@ mod sni_auto_extraction {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![], // Auto-extract: SAN also includes "localhost"
@@ -554,6 +576,7 @@ mod sni_auto_extraction {
                     cert_file: Some(fixtures.join("server-secure.crt")),
                     key_file: Some(fixtures.join("server-secure.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -561,6 +584,7 @@ mod sni_auto_extraction {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -593,12 +617,14 @@ mod sni_auto_extraction {
                 cert_file: Some(fixtures.join("server-api.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -659,6 +685,7 @@ mod acme_resolver {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: Some(acme_config(storage)),
@@ -729,6 +756,7 @@ mod acme_resolver {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -834,12 +862,14 @@ mod acme_resolver {
                     key_type: AcmeKeyType::EcdsaP256,
                     dns_provider: None,
                 }),
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -893,12 +923,14 @@ mod acme_resolver {
                     key_type: AcmeKeyType::EcdsaP256,
                     dns_provider: None,
                 }),
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -939,12 +971,14 @@ mod acme_resolver {
                     key_type: AcmeKeyType::EcdsaP256,
                     dns_provider: None,
                 }),
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1130,6 +1164,7 @@ mod hot_reload {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1183,6 +1218,7 @@ mod hot_reload {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1229,6 +1265,7 @@ mod hot_reload {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1355,6 +1392,7 @@ mod certificate_reloader {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1458,6 +1496,7 @@ mod validation {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1485,6 +1524,7 @@ mod validation {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1512,12 +1552,14 @@ mod validation {
                 cert_file: Some(fixtures.join("nonexistent.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1539,6 +1581,7 @@ mod validation {
             max_version: None,
             cipher_suites: vec![],
             client_auth: true,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1565,11 +1608,15 @@ mod server_config {
         });
     }
 
+    fn resolver(config: &TlsConfig) -> Arc<HotReloadableSniResolver> {
+        Arc::new(HotReloadableSniResolver::from_config(config.clone(), "test-listener").unwrap())
+    }
+
     #[test]
     fn test_build_server_config_minimal() {
         ensure_crypto_provider();
         let config = minimal_tls_config();
-        let result = build_server_config(&config, "test-listener");
+        let result = build_server_config(&config, resolver(&config));
         assert!(
             result.is_ok(),
             "Failed to build server config: {:?}",
@@ -1586,7 +1633,7 @@ mod server_config {
     fn test_build_server_config_with_sni() {
         ensure_crypto_provider();
         let config = multi_sni_tls_config();
-        let result = build_server_config(&config, "test-listener");
+        let result = build_server_config(&config, resolver(&config));
         assert!(
             result.is_ok(),
             "Failed to build server config with SNI: {:?}",
@@ -1598,7 +1645,7 @@ mod server_config {
     fn test_build_server_config_with_mtls() {
         ensure_crypto_provider();
         let config = mtls_tls_config();
-        let result = build_server_config(&config, "test-listener");
+        let result = build_server_config(&config, resolver(&config));
         assert!(
             result.is_ok(),
             "Failed to build mTLS server config: {:?}",
@@ -1610,7 +1657,7 @@ mod server_config {
     fn test_build_server_config_with_wildcard() {
         ensure_crypto_provider();
         let config = wildcard_tls_config();
-        let result = build_server_config(&config, "test-listener");
+        let result = build_server_config(&config, resolver(&config));
         assert!(
             result.is_ok(),
             "Failed to build wildcard server config: {:?}",
@@ -1639,6 +1686,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![],
@@ -1646,6 +1694,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-secure.crt")),
                     key_file: Some(fixtures.join("server-secure.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -1653,6 +1702,7 @@ mod server_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1690,12 +1740,14 @@ mod server_config {
                 cert_file: Some(fixtures.join("server-api.crt")),
                 key_file: Some(fixtures.join("server-api.key")),
                 acme: None,
+                namespace: None,
             }],
             ca_file: None,
             min_version: zentinel_common::types::TlsVersion::Tls12,
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1731,6 +1783,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![],
@@ -1738,6 +1791,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-secure.crt")),
                     key_file: Some(fixtures.join("server-secure.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -1745,6 +1799,7 @@ mod server_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1779,6 +1834,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-wildcard.crt")),
                     key_file: Some(fixtures.join("server-wildcard.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![],
@@ -1786,6 +1842,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -1793,6 +1850,7 @@ mod server_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
@@ -1832,6 +1890,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-secure.crt")),
                     key_file: Some(fixtures.join("server-secure.key")),
                     acme: None,
+                    namespace: None,
                 },
                 SniCertificate {
                     hostnames: vec![],
@@ -1839,6 +1898,7 @@ mod server_config {
                     cert_file: Some(fixtures.join("server-api.crt")),
                     key_file: Some(fixtures.join("server-api.key")),
                     acme: None,
+                    namespace: None,
                 },
             ],
             ca_file: None,
@@ -1846,6 +1906,7 @@ mod server_config {
             max_version: None,
             cipher_suites: vec![],
             client_auth: false,
+            client_cert_header: None,
             ocsp_stapling: false,
             session_resumption: true,
             acme: None,
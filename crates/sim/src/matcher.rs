@@ -7,7 +7,7 @@
 use regex::Regex;
 
 use zentinel_common::types::Priority;
-use zentinel_config::{MatchCondition, RouteConfig};
+use zentinel_config::{ExprContext, Expression, MatchCondition, RouteConfig};
 
 use crate::trace::{ConditionDetail, MatchStep};
 use crate::types::{MatchedRoute, SimulatedRequest};
@@ -46,6 +46,8 @@ enum CompiledMatcher {
     Method(Vec<String>),
     /// Query parameter match
     QueryParam { name: String, value: Option<String> },
+    /// Policy expression match
+    Expr(Expression),
 }
 
 /// Host matching variants
@@ -217,6 +219,7 @@ impl CompiledRoute {
                 CompiledMatcher::QueryParam { value, .. } => {
                     if value.is_some() { 25 } else { 15 }
                 }
+                CompiledMatcher::Expr(_) => 20,
             };
         }
         score
@@ -276,6 +279,7 @@ impl CompiledMatcher {
                 name: name.clone(),
                 value: value.clone(),
             },
+            MatchCondition::Expr(expr) => Self::Expr(expr.clone()),
         })
     }
 
@@ -301,6 +305,7 @@ impl CompiledMatcher {
                     false
                 }
             }
+            Self::Expr(expr) => expr.evaluate(&ExprRequest { request, path }),
         }
     }
 
@@ -358,10 +363,45 @@ impl CompiledMatcher {
                     ConditionDetail::query_param(name, value.as_deref(), actual, matched),
                 )
             }
+            Self::Expr(expr) => {
+                let matched = expr.evaluate(&ExprRequest { request, path });
+                (matched, ConditionDetail::expr(expr.as_str(), matched))
+            }
         }
     }
 }
 
+/// Expression view of a simulated request.
+///
+/// The simulator has no client address, geo lookup or agents, so
+/// `client.*` and `metadata` evaluate to null.
+struct ExprRequest<'a> {
+    request: &'a SimulatedRequest,
+    path: &'a str,
+}
+
+impl ExprContext for ExprRequest<'_> {
+    fn method(&self) -> &str {
+        &self.request.method
+    }
+
+    fn path(&self) -> &str {
+        self.path
+    }
+
+    fn host(&self) -> &str {
+        &self.request.host
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.request.headers.get(name).map(|s| s.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.request.query_params.get(name).map(|s| s.as_str())
+    }
+}
+
 impl HostMatcher {
     /// Parse a host pattern into a matcher
     fn parse(pattern: &str) -> Self {
@@ -582,6 +622,23 @@ mod tests {
         assert!(matcher.match_request(&with_correct_version).is_some());
     }
 
+    #[test]
+    fn test_expr_matching() {
+        let expr = "request.method == \"POST\" && request.query[\"version\"] == \"2\"";
+        let routes = vec![create_route(
+            "expr",
+            vec![MatchCondition::Expr(expr.parse().unwrap())],
+        )];
+
+        let matcher = RouteMatcher::new(&routes, None).unwrap();
+
+        let get = SimulatedRequest::new("GET", "example.com", "/api?version=2");
+        assert!(matcher.match_request(&get).is_none());
+
+        let post = SimulatedRequest::new("POST", "example.com", "/api?version=2");
+        assert!(matcher.match_request(&post).is_some());
+    }
+
     #[test]
     fn test_priority_ordering() {
         let mut low_priority = create_route("low", vec![MatchCondition::PathPrefix("/".to_string())]);
@@ -272,6 +272,21 @@ impl ConditionDetail {
             },
         }
     }
+
+    /// Create a policy expression condition detail
+    pub fn expr(source: &str, matched: bool) -> Self {
+        Self {
+            condition_type: "Expr".to_string(),
+            pattern: source.to_string(),
+            matched,
+            actual_value: None,
+            explanation: if matched {
+                Some("Expression evaluated to true".to_string())
+            } else {
+                Some("Expression did not evaluate to true".to_string())
+            },
+        }
+    }
 }
 
 #[cfg(test)]
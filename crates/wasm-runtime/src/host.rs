@@ -13,7 +13,7 @@ use parking_lot::Mutex;
 use std::collections::HashMap;
 use tracing::{debug, instrument, warn};
 use wasmtime::component::{Component, Linker, ResourceTable};
-use wasmtime::{Config, Engine, Store};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
 use zentinel_agent_protocol::{AgentResponse, RequestMetadata};
 
@@ -48,6 +48,8 @@ pub struct AgentState {
     wasi_ctx: WasiCtx,
     /// Resource table for WASI
     resource_table: ResourceTable,
+    /// Memory and table growth limits
+    store_limits: StoreLimits,
 }
 
 impl WasiView for AgentState {
@@ -92,9 +94,16 @@ impl WasmAgentInstance {
             configured: false,
             wasi_ctx,
             resource_table: ResourceTable::new(),
+            store_limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory)
+                .table_elements(limits.max_table_elements as usize)
+                .build(),
         };
         let mut store = Store::new(engine, state);
 
+        // Enforce memory limits: growth beyond `max_memory` fails
+        store.limiter(|state| &mut state.store_limits);
+
         // Configure fuel metering
         store.set_fuel(limits.max_fuel)?;
 
@@ -0,0 +1,253 @@
+#!/usr/bin/env bash
+#
+# Bundle Checksum Generator
+#
+# Downloads every agent archive pinned in bundle-versions.lock, computes its
+# SHA256 and prints `[checksums]` entries for the lock file. When a release
+# publishes a `.sha256` file, it must agree with the computed digest.
+#
+# The bundle API serves the same digests per agent as
+# `checksums = { "linux-x86_64" = "sha256:..." }`.
+#
+# With --verify-releases, each agent's GitHub release is queried through the
+# releases API first (authenticated with GITHUB_TOKEN when set). Every
+# required platform asset must be present; digests come from the published
+# `.sha256` assets, falling back to downloading the archive. Missing assets
+# are listed and the script exits non-zero, so it can gate release CI.
+#
+# Prerequisites:
+#   - curl
+#   - sha256sum (or shasum on macOS)
+#   - jq (for --verify-releases and --bundle-json)
+#
+# Usage:
+#   ./scripts/bundle-checksums.sh                       # use repo lock file
+#   ./scripts/bundle-checksums.sh path/to/bundle.lock   # custom lock file
+#   ./scripts/bundle-checksums.sh >> bundle-versions.lock
+#   ./scripts/bundle-checksums.sh --verify-releases --write
+#   ./scripts/bundle-checksums.sh --verify-releases --bundle-json dist/v1/bundle/index.json
+#
+# Options:
+#   --verify-releases   Validate release assets via the GitHub releases API
+#   --write             Replace the [checksums] section of the lock file
+#   --bundle-json FILE  Update the per-agent checksums maps in an API bundle JSON
+
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+ROOT_DIR="$(cd "$SCRIPT_DIR/.." && pwd)"
+LOCK_FILE="$ROOT_DIR/bundle-versions.lock"
+VERIFY_RELEASES=false
+WRITE_LOCK=false
+BUNDLE_JSON=""
+
+# Platforms every agent release must provide
+REQUIRED_PLATFORMS=("linux-x86_64" "linux-aarch64" "darwin-x86_64" "darwin-aarch64")
+
+# Platforms included when published
+OPTIONAL_PLATFORMS=("linux-x86_64-musl" "windows-x86_64")
+
+while [[ $# -gt 0 ]]; do
+    case "$1" in
+        --verify-releases) VERIFY_RELEASES=true ;;
+        --write) WRITE_LOCK=true ;;
+        --bundle-json)
+            BUNDLE_JSON="${2:?--bundle-json requires a file}"
+            shift
+            ;;
+        -h|--help)
+            sed -n '3,/^$/p' "${BASH_SOURCE[0]}" | sed 's/^# \{0,1\}//'
+            exit 0
+            ;;
+        -*)
+            echo "error: unknown option $1" >&2
+            exit 2
+            ;;
+        *) LOCK_FILE="$1" ;;
+    esac
+    shift
+done
+
+if command -v sha256sum >/dev/null 2>&1; then
+    SHA256=(sha256sum)
+else
+    SHA256=(shasum -a 256)
+fi
+
+if { $VERIFY_RELEASES || [[ -n "$BUNDLE_JSON" ]]; } && ! command -v jq >/dev/null 2>&1; then
+    echo "error: jq is required for --verify-releases and --bundle-json" >&2
+    exit 2
+fi
+
+GITHUB_API=(curl -fsSL -H "Accept: application/vnd.github+json")
+if [[ -n "${GITHUB_TOKEN:-}" ]]; then
+    GITHUB_API+=(-H "Authorization: Bearer $GITHUB_TOKEN")
+fi
+
+# Print `key value` pairs from a TOML section of the lock file
+section() {
+    awk -v section="[$1]" '
+        /^[[:space:]]*#/ { next }
+        /^\[/ { in_section = ($0 == section); next }
+        in_section && /=/ {
+            gsub(/[" ]/, "")
+            split($0, kv, "=")
+            print kv[1], kv[2]
+        }
+    ' "$LOCK_FILE"
+}
+
+lookup() {
+    section "$1" | awk -v key="$2" '$1 == key { print $2 }'
+}
+
+# Release asset file name for a platform
+asset_name() {
+    local binary="$1" version="$2" platform="$3" ext="tar.gz"
+    # Windows releases are zip archives, everything else is a gzipped tarball
+    [[ "$platform" == windows-* ]] && ext="zip"
+    echo "$binary-$version-$platform.$ext"
+}
+
+# First field of `sha256sum` output or a `.sha256` file, lowercased
+parse_digest() {
+    awk 'NR == 1 { sub(/^sha256:/, "", $1); print tolower($1) }'
+}
+
+is_required() {
+    [[ " ${REQUIRED_PLATFORMS[*]} " == *" $1 "* ]]
+}
+
+TMP_DIR="$(mktemp -d)"
+trap 'rm -rf "$TMP_DIR"' EXIT
+
+ENTRIES="$TMP_DIR/entries"      # lock file lines
+DIGESTS="$TMP_DIR/digests.tsv"  # agent, platform, digest
+: > "$ENTRIES"
+: > "$DIGESTS"
+
+failed=0
+missing=()
+
+record() {
+    local agent="$1" version="$2" platform="$3" digest="$4"
+    echo "$agent-$version-$platform = \"sha256:$digest\"" >> "$ENTRIES"
+    printf '%s\t%s\t%s\n' "$agent" "$platform" "$digest" >> "$DIGESTS"
+}
+
+while read -r agent version; do
+    repo="$(lookup repositories "$agent")"
+    binary="$(lookup binary_names "$agent")"
+    binary="${binary:-zentinel-$agent-agent}"
+
+    if [[ -z "$repo" ]]; then
+        echo "warning: no repository for $agent, skipping" >&2
+        continue
+    fi
+
+    base_url="https://github.com/$repo/releases/download/v$version"
+
+    if $VERIFY_RELEASES; then
+        # Asset names of the release, one per line
+        if ! assets="$("${GITHUB_API[@]}" "https://api.github.com/repos/$repo/releases/tags/v$version" \
+                | jq -r '.assets[].name')"; then
+            missing+=("$agent $version: release v$version not found in $repo")
+            continue
+        fi
+
+        for platform in "${REQUIRED_PLATFORMS[@]}" "${OPTIONAL_PLATFORMS[@]}"; do
+            asset="$(asset_name "$binary" "$version" "$platform")"
+
+            if ! grep -qxF "$asset" <<< "$assets"; then
+                if is_required "$platform"; then
+                    missing+=("$agent $version: $asset")
+                fi
+                continue
+            fi
+
+            if grep -qxF "$asset.sha256" <<< "$assets"; then
+                digest="$(curl -fsSL "$base_url/$asset.sha256" | parse_digest)"
+            else
+                curl -fsSL -o "$TMP_DIR/archive" "$base_url/$asset"
+                digest="$("${SHA256[@]}" "$TMP_DIR/archive" | parse_digest)"
+            fi
+
+            if [[ ! "$digest" =~ ^[0-9a-f]{64}$ ]]; then
+                echo "error: $agent $version $platform: invalid checksum '$digest'" >&2
+                failed=1
+                continue
+            fi
+
+            record "$agent" "$version" "$platform" "$digest"
+        done
+        continue
+    fi
+
+    for platform in "${REQUIRED_PLATFORMS[@]}" "${OPTIONAL_PLATFORMS[@]}"; do
+        url="$base_url/$(asset_name "$binary" "$version" "$platform")"
+
+        if ! curl -fsSL -o "$TMP_DIR/archive" "$url"; then
+            echo "warning: $agent $version has no $platform archive" >&2
+            continue
+        fi
+
+        digest="$("${SHA256[@]}" "$TMP_DIR/archive" | parse_digest)"
+
+        if published="$(curl -fsSL "$url.sha256" 2>/dev/null)"; then
+            published="$(parse_digest <<< "$published")"
+            if [[ "$published" != "$digest" ]]; then
+                echo "error: $agent $version $platform: published checksum $published != computed $digest" >&2
+                failed=1
+                continue
+            fi
+        fi
+
+        record "$agent" "$version" "$platform" "$digest"
+    done
+done < <(section agents)
+
+if [[ ${#missing[@]} -gt 0 ]]; then
+    echo "error: ${#missing[@]} missing release asset(s):" >&2
+    printf '  %s\n' "${missing[@]}" >&2
+    failed=1
+fi
+
+if $WRITE_LOCK; then
+    # Replace the entries of [checksums], keeping its comments; append the
+    # section if the lock file has none
+    awk -v entries="$ENTRIES" '
+        function flush(line) {
+            while ((getline line < entries) > 0) print line
+            close(entries)
+            in_checksums = 0
+        }
+        /^\[/ {
+            if (in_checksums) flush()
+            in_checksums = ($0 == "[checksums]")
+            if (in_checksums) seen = 1
+            print
+            next
+        }
+        in_checksums && /^[^#[:space:]].*=/ { next }
+        { print }
+        END {
+            if (in_checksums) flush()
+            else if (!seen) { print ""; print "[checksums]"; flush() }
+        }
+    ' "$LOCK_FILE" > "$TMP_DIR/lock"
+    cp "$TMP_DIR/lock" "$LOCK_FILE"
+    echo "Updated $(wc -l < "$ENTRIES" | tr -d ' ') checksum(s) in $LOCK_FILE" >&2
+else
+    cat "$ENTRIES"
+fi
+
+if [[ -n "$BUNDLE_JSON" ]]; then
+    jq --rawfile digests "$DIGESTS" '
+        reduce ($digests | split("\n")[] | select(length > 0) | split("\t")) as [$agent, $platform, $digest]
+            (.; if .agents[$agent] then .agents[$agent].checksums[$platform] = "sha256:" + $digest else . end)
+    ' "$BUNDLE_JSON" > "$TMP_DIR/bundle.json"
+    cp "$TMP_DIR/bundle.json" "$BUNDLE_JSON"
+    echo "Updated checksums in $BUNDLE_JSON" >&2
+fi
+
+exit "$failed"